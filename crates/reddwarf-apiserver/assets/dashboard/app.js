@@ -0,0 +1,203 @@
+// reddwarf dashboard: lists nodes and pods, follows them over the SSE watch
+// API and redraws the version DAG whenever something changes.
+"use strict";
+
+const MAX_EVENTS = 200;
+const COMMIT_LIMIT = 40;
+
+const nodes = new Map();
+const pods = new Map();
+let watches = 0;
+
+function key(object) {
+  const meta = object.metadata || {};
+  return meta.namespace ? `${meta.namespace}/${meta.name}` : meta.name;
+}
+
+function cell(text, className) {
+  const td = document.createElement("td");
+  td.textContent = text ?? "";
+  if (className) td.className = className;
+  return td;
+}
+
+function age(timestamp) {
+  if (!timestamp) return "";
+  const seconds = Math.max(0, (Date.now() - Date.parse(timestamp)) / 1000);
+  if (seconds < 120) return `${Math.floor(seconds)}s`;
+  if (seconds < 7200) return `${Math.floor(seconds / 60)}m`;
+  if (seconds < 172800) return `${Math.floor(seconds / 3600)}h`;
+  return `${Math.floor(seconds / 86400)}d`;
+}
+
+function renderRows(tbody, objects, columns) {
+  const rows = [...objects.values()]
+    .sort((a, b) => key(a).localeCompare(key(b)))
+    .map((object) => {
+      const tr = document.createElement("tr");
+      for (const [text, className] of columns(object)) tr.append(cell(text, className));
+      return tr;
+    });
+  tbody.replaceChildren(...rows);
+}
+
+function renderNodes() {
+  renderRows(document.getElementById("nodes"), nodes, (node) => {
+    const status = node.status || {};
+    const ready = (status.conditions || []).find((c) => c.type === "Ready");
+    const address = (status.addresses || []).find((a) => a.type === "InternalIP")
+      || (status.addresses || [])[0];
+    const allocatable = status.allocatable || {};
+    const isReady = ready && ready.status === "True";
+    return [
+      [node.metadata.name],
+      [isReady ? "Ready" : "NotReady", isReady ? "ok" : "bad"],
+      [address && address.address],
+      [allocatable.cpu],
+      [allocatable.memory],
+      [allocatable.pods],
+    ];
+  });
+  document.getElementById("node-count").textContent = nodes.size;
+}
+
+const PHASE_CLASS = { Running: "ok", Succeeded: "ok", Pending: "warn", Failed: "bad" };
+
+function renderPods() {
+  renderRows(document.getElementById("pods"), pods, (pod) => {
+    const status = pod.status || {};
+    return [
+      [pod.metadata.namespace],
+      [pod.metadata.name],
+      [status.phase, PHASE_CLASS[status.phase]],
+      [(pod.spec || {}).nodeName],
+      [status.podIP],
+      [age(pod.metadata.creationTimestamp)],
+    ];
+  });
+  document.getElementById("pod-count").textContent = pods.size;
+}
+
+function recordEvent(type, kind, object) {
+  const feed = document.getElementById("events");
+  const li = document.createElement("li");
+  const time = document.createElement("time");
+  time.textContent = new Date().toLocaleTimeString();
+  li.append(time, ` ${type} ${kind} ${key(object)}`);
+  feed.prepend(li);
+  while (feed.children.length > MAX_EVENTS) feed.lastChild.remove();
+}
+
+function setConnection(live) {
+  const badge = document.getElementById("connection");
+  badge.textContent = live ? "live" : "reconnecting";
+  badge.classList.toggle("live", live);
+}
+
+// List a kind, then watch it from the list's resourceVersion so no change
+// in between is missed. The browser reconnects dropped streams by itself.
+async function follow(path, kind, objects, render) {
+  const response = await fetch(path);
+  const list = await response.json();
+  objects.clear();
+  for (const item of list.items || []) objects.set(key(item), item);
+  render();
+
+  const version = (list.metadata || {}).resourceVersion || "";
+  const source = new EventSource(`${path}?watch=true&resourceVersion=${encodeURIComponent(version)}`);
+  source.onopen = () => setConnection(++watches > 0);
+  source.onerror = () => {
+    watches = Math.max(0, watches - 1);
+    setConnection(false);
+  };
+  source.onmessage = (message) => {
+    const event = JSON.parse(message.data);
+    if (event.type === "DELETED") {
+      objects.delete(key(event.object));
+    } else {
+      objects.set(key(event.object), event.object);
+    }
+    render();
+    recordEvent(event.type, kind, event.object);
+    scheduleDag();
+  };
+}
+
+// Lay commits out newest first, one row each, giving every line of history
+// its own lane so forks and merges show up as diverging and joining edges.
+function renderDag(list) {
+  const svg = document.getElementById("dag");
+  const rowHeight = 22;
+  const laneWidth = 14;
+  const lanes = [];
+  const positions = new Map();
+  const nodesSvg = [];
+
+  list.items.forEach((commit, row) => {
+    let lane = lanes.indexOf(commit.id);
+    if (lane < 0) {
+      lane = lanes.indexOf(null);
+      if (lane < 0) lane = lanes.length;
+    }
+    lanes[lane] = null;
+    commit.parents.forEach((parent, i) => {
+      if (lanes.includes(parent)) return;
+      const free = i === 0 ? lane : lanes.indexOf(null);
+      lanes[free < 0 ? lanes.length : free] = parent;
+    });
+    positions.set(commit.id, { x: 10 + lane * laneWidth, y: 12 + row * rowHeight });
+    nodesSvg.push(commit);
+  });
+
+  const ns = "http://www.w3.org/2000/svg";
+  const element = (name, attributes, text) => {
+    const el = document.createElementNS(ns, name);
+    for (const [k, v] of Object.entries(attributes)) el.setAttribute(k, v);
+    if (text !== undefined) el.textContent = text;
+    return el;
+  };
+
+  const edges = [];
+  const circles = [];
+  const labels = [];
+  const textX = 20 + Math.max(1, lanes.length) * laneWidth;
+  for (const commit of nodesSvg) {
+    const from = positions.get(commit.id);
+    for (const parent of commit.parents) {
+      const to = positions.get(parent) || { x: from.x, y: from.y + rowHeight / 2 };
+      edges.push(element("line", { x1: from.x, y1: from.y, x2: to.x, y2: to.y }));
+    }
+    circles.push(element("circle", {
+      cx: from.x,
+      cy: from.y,
+      r: 4,
+      class: commit.parents.length > 1 ? "merge" : "",
+    }));
+    const label = element("text", { x: textX, y: from.y + 4 });
+    const head = commit.id === list.head ? " (HEAD)" : "";
+    label.append(
+      element("tspan", {}, `${commit.id.slice(0, 8)}${head} `),
+      element("tspan", { class: "meta" }, commit.changes.join(", ") || commit.message),
+    );
+    labels.push(label);
+  }
+
+  svg.setAttribute("height", 12 + nodesSvg.length * rowHeight);
+  svg.replaceChildren(...edges, ...circles, ...labels);
+}
+
+let dagTimer = null;
+
+function scheduleDag() {
+  if (dagTimer) return;
+  dagTimer = setTimeout(async () => {
+    dagTimer = null;
+    const response = await fetch(`/dashboard/api/commits?limit=${COMMIT_LIMIT}`);
+    if (response.ok) renderDag(await response.json());
+  }, 500);
+}
+
+follow("/api/v1/nodes", "Node", nodes, renderNodes);
+follow("/api/v1/pods", "Pod", pods, renderPods);
+scheduleDag();
+setInterval(renderPods, 30000);
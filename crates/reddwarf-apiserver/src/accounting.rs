@@ -0,0 +1,407 @@
+//! Usage accounting for chargeback: periodic per-namespace records of the
+//! CPU, memory and storage requested by running pods, exported in the
+//! OpenMetrics text format
+
+use crate::handlers::common::list_resources;
+use crate::handlers::usage::namespace_pods;
+use crate::{AppState, Result};
+use chrono::{DateTime, Utc};
+use reddwarf_core::{Namespace, Pod, ResourceQuantities};
+use reddwarf_storage::{KVStore, KeyEncoder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+/// Storage key prefix of the usage records
+/// (`usage/records/{end in unix milliseconds}/{namespace}`)
+pub const USAGE_RECORD_KEY_PREFIX: &str = "usage/records/";
+
+/// Storage key of the end of the last recorded period
+const USAGE_LAST_KEY: &[u8] = b"usage/last";
+
+/// Content type of the usage report
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+const BYTES_PER_GIGABYTE: f64 = 1e9;
+
+/// Resources requested by the running pods of one namespace over one
+/// recording period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    pub namespace: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Pods running at the end of the period
+    pub pods: i64,
+    pub cpu_seconds: f64,
+    pub memory_byte_hours: f64,
+    pub storage_gigabyte_hours: f64,
+}
+
+/// Configuration for the usage recorder
+#[derive(Debug, Clone)]
+pub struct UsageRecorderConfig {
+    /// Length of a recording period
+    pub interval: Duration,
+    /// How long records are kept
+    pub retention: Duration,
+}
+
+impl Default for UsageRecorderConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            retention: Duration::from_secs(400 * 24 * 3600),
+        }
+    }
+}
+
+/// Records what each namespace's running pods request, once per interval
+pub struct UsageRecorder {
+    state: Arc<AppState>,
+    config: UsageRecorderConfig,
+}
+
+impl UsageRecorder {
+    pub fn new(state: Arc<AppState>, config: UsageRecorderConfig) -> Self {
+        Self { state, config }
+    }
+
+    /// Run the recording loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!("Starting usage recorder");
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Usage recorder shutting down");
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(self.config.interval) => {
+                    if let Err(e) = self.record(Utc::now()).await {
+                        error!("Failed to record usage: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record the period since the last recording, charging what the pods
+    /// running now request for all of it. A period is at most twice the
+    /// interval long, so time the server was down is not charged.
+    pub async fn record(&self, now: DateTime<Utc>) -> Result<Vec<UsageRecord>> {
+        let interval = chrono::Duration::from_std(self.config.interval).unwrap_or_default();
+        let start = match self.state.storage.get(USAGE_LAST_KEY)? {
+            Some(data) => String::from_utf8_lossy(&data)
+                .parse::<DateTime<Utc>>()
+                .ok()
+                .filter(|last| *last < now && now - *last <= interval * 2)
+                .unwrap_or(now - interval),
+            None => now - interval,
+        };
+        let hours = (now - start).num_milliseconds() as f64 / 3_600_000.0;
+
+        let namespaces: Vec<Namespace> = list_resources(
+            &self.state,
+            &KeyEncoder::encode_prefix("v1", "Namespace", None),
+        )
+        .await?;
+        let mut records = Vec::new();
+        for namespace in namespaces {
+            let Some(name) = namespace.metadata.name else {
+                continue;
+            };
+            let mut record = UsageRecord {
+                namespace: name.clone(),
+                start,
+                end: now,
+                pods: 0,
+                cpu_seconds: 0.0,
+                memory_byte_hours: 0.0,
+                storage_gigabyte_hours: 0.0,
+            };
+            for pod in namespace_pods(&self.state, &name)? {
+                if !is_running(&pod) {
+                    continue;
+                }
+                let Some(spec) = pod.spec.as_ref() else {
+                    continue;
+                };
+                let requests = ResourceQuantities::pod_requests(spec);
+                record.pods += 1;
+                record.cpu_seconds += requests.cpu_millicores as f64 / 1000.0 * hours * 3600.0;
+                record.memory_byte_hours += requests.memory_bytes as f64 * hours;
+                record.storage_gigabyte_hours +=
+                    storage_request_bytes(&pod) as f64 / BYTES_PER_GIGABYTE * hours;
+            }
+            if record.pods == 0 {
+                continue;
+            }
+            let data = serde_json::to_vec(&record)?;
+            self.state
+                .storage
+                .put(record_key(now, &name).as_bytes(), &data)?;
+            records.push(record);
+        }
+
+        self.state
+            .storage
+            .put(USAGE_LAST_KEY, now.to_rfc3339().as_bytes())?;
+        self.prune(now)?;
+        debug!("Recorded usage of {} namespaces", records.len());
+        Ok(records)
+    }
+
+    /// Drop records older than the retention
+    fn prune(&self, now: DateTime<Utc>) -> Result<()> {
+        let retention = chrono::Duration::from_std(self.config.retention).unwrap_or_default();
+        let cutoff = record_key(now - retention, "");
+        for key in self
+            .state
+            .storage
+            .keys_with_prefix(USAGE_RECORD_KEY_PREFIX.as_bytes())?
+        {
+            if key.as_ref() >= cutoff.as_bytes() {
+                break;
+            }
+            self.state.storage.delete(&key)?;
+        }
+        Ok(())
+    }
+}
+
+fn record_key(end: DateTime<Utc>, namespace: &str) -> String {
+    format!(
+        "{}{:020}/{}",
+        USAGE_RECORD_KEY_PREFIX,
+        end.timestamp_millis().max(0),
+        namespace
+    )
+}
+
+fn is_running(pod: &Pod) -> bool {
+    pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running")
+}
+
+/// Ephemeral storage requested by the pod's containers
+fn storage_request_bytes(pod: &Pod) -> i64 {
+    pod.spec
+        .iter()
+        .flat_map(|spec| &spec.containers)
+        .filter_map(|c| {
+            c.resources
+                .as_ref()?
+                .requests
+                .as_ref()?
+                .get("ephemeral-storage")
+        })
+        .filter_map(|q| ResourceQuantities::parse_memory(&q.0).ok())
+        .sum()
+}
+
+/// Records whose period ended within `[from, to)`, oldest first
+pub fn usage_records(
+    state: &AppState,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<UsageRecord>> {
+    let mut records = Vec::new();
+    for (_, data) in state.storage.scan(USAGE_RECORD_KEY_PREFIX.as_bytes())? {
+        let record: UsageRecord = serde_json::from_slice(&data)?;
+        if from.is_some_and(|from| record.end < from) || to.is_some_and(|to| record.end >= to) {
+            continue;
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Name, unit, help text and per-record value of a reported counter
+type CounterFamily = (
+    &'static str,
+    Option<&'static str>,
+    &'static str,
+    fn(&UsageRecord) -> f64,
+);
+
+/// Render records as OpenMetrics counters per namespace, with one sample
+/// per period holding the total since the first record
+pub fn render_openmetrics(records: &[UsageRecord]) -> String {
+    let mut by_namespace: BTreeMap<&str, Vec<&UsageRecord>> = BTreeMap::new();
+    for record in records {
+        by_namespace
+            .entry(record.namespace.as_str())
+            .or_default()
+            .push(record);
+    }
+
+    let families: [CounterFamily; 3] = [
+        (
+            "reddwarf_namespace_cpu_seconds",
+            Some("seconds"),
+            "CPU-seconds requested by running pods",
+            |r| r.cpu_seconds,
+        ),
+        (
+            "reddwarf_namespace_memory_byte_hours",
+            None,
+            "Byte-hours of memory requested by running pods",
+            |r| r.memory_byte_hours,
+        ),
+        (
+            "reddwarf_namespace_storage_gigabyte_hours",
+            None,
+            "Gigabyte-hours of ephemeral storage requested by running pods",
+            |r| r.storage_gigabyte_hours,
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, unit, help, value) in families {
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        if let Some(unit) = unit {
+            let _ = writeln!(out, "# UNIT {} {}", name, unit);
+        }
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        for (namespace, records) in &by_namespace {
+            let mut total = 0.0;
+            for record in records {
+                total += value(record);
+                let _ = writeln!(
+                    out,
+                    "{}_total{{namespace=\"{}\"}} {} {}",
+                    name,
+                    escape_label_value(namespace),
+                    total,
+                    record.end.timestamp_millis() as f64 / 1000.0
+                );
+            }
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::create_resource;
+    use reddwarf_core::k8s_openapi::api::core::v1::{
+        Container, PodSpec, PodStatus, ResourceRequirements,
+    };
+    use reddwarf_core::k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    fn setup_state() -> Arc<AppState> {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        Arc::new(AppState::new(storage, version_store))
+    }
+
+    fn pod(namespace: &str, name: &str, phase: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some(namespace.to_string());
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                image: Some("app:latest".to_string()),
+                resources: Some(ResourceRequirements {
+                    requests: Some(
+                        [
+                            ("cpu", "500m"),
+                            ("memory", "1Gi"),
+                            ("ephemeral-storage", "2000000000"),
+                        ]
+                        .into_iter()
+                        .map(|(k, v)| (k.to_string(), Quantity(v.to_string())))
+                        .collect(),
+                    ),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            phase: Some(phase.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    #[tokio::test]
+    async fn test_record_and_render_usage() {
+        let state = setup_state();
+        for name in ["default", "shop"] {
+            let mut namespace = Namespace::default();
+            namespace.metadata.name = Some(name.to_string());
+            create_resource(&state, namespace).await.unwrap();
+        }
+        create_resource(&state, pod("shop", "cart", "Running"))
+            .await
+            .unwrap();
+        create_resource(&state, pod("shop", "queued", "Pending"))
+            .await
+            .unwrap();
+
+        let recorder = UsageRecorder::new(
+            state.clone(),
+            UsageRecorderConfig {
+                interval: Duration::from_secs(3600),
+                retention: Duration::from_secs(3 * 3600 + 1800),
+            },
+        );
+        let t0 = "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let hour = chrono::Duration::hours(1);
+
+        // Only namespaces with running pods are recorded
+        let records = recorder.record(t0).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].start, t0 - hour);
+        assert_eq!(records[0].pods, 1);
+        assert_eq!(records[0].cpu_seconds, 1800.0);
+        assert_eq!(records[0].memory_byte_hours, 1024.0 * 1024.0 * 1024.0);
+        assert_eq!(records[0].storage_gigabyte_hours, 2.0);
+
+        // The next period starts where the last ended; a gap longer than
+        // two intervals is not charged
+        let records = recorder.record(t0 + hour / 2).await.unwrap();
+        assert_eq!(records[0].start, t0);
+        assert_eq!(records[0].cpu_seconds, 900.0);
+        let records = recorder.record(t0 + hour * 4).await.unwrap();
+        assert_eq!(records[0].start, t0 + hour * 3);
+
+        // The first record fell out of the retention
+        let all = usage_records(&state, None, None).unwrap();
+        assert_eq!(all.len(), 2);
+        let ranged = usage_records(&state, Some(t0), Some(t0 + hour)).unwrap();
+        assert_eq!(ranged.len(), 1);
+
+        let text = render_openmetrics(&all);
+        assert!(text.contains("# UNIT reddwarf_namespace_cpu_seconds seconds\n"));
+        assert!(text
+            .contains("reddwarf_namespace_cpu_seconds_total{namespace=\"shop\"} 900 1767227400\n"));
+        assert!(text.contains(
+            "reddwarf_namespace_cpu_seconds_total{namespace=\"shop\"} 2700 1767240000\n"
+        ));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
@@ -0,0 +1,119 @@
+//! Static bearer tokens guarding the admin endpoints
+//!
+//! The debug API, the node proxy, interactive sessions and node bootstrap
+//! are each enabled with one or more tokens an operator hands out. A request
+//! presents one in its `Authorization: Bearer` header, read by the [`Bearer`]
+//! extractor, and [`authorize`] lets it through if it matches.
+
+use crate::{ApiError, Result};
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap};
+use std::convert::Infallible;
+use tracing::warn;
+
+/// A token that grants access to an endpoint
+#[derive(Clone)]
+pub struct BearerToken(String);
+
+impl BearerToken {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+
+    /// Whether `presented` is this token, compared in constant time
+    pub fn matches(&self, presented: &Bearer) -> bool {
+        match presented.0.as_deref() {
+            Some(presented) => constant_time_eq(presented.as_bytes(), self.0.as_bytes()),
+            None => false,
+        }
+    }
+}
+
+/// The token a request presents in its `Authorization: Bearer` header, if
+/// it has one
+#[derive(Debug, Clone, Default)]
+pub struct Bearer(Option<String>);
+
+impl Bearer {
+    /// Present `token`, as a client would
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(Some(token.into()))
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        Self(token.map(String::from))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Bearer {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+/// Let a request presenting one of `tokens` through, and answer any other
+/// with `401`; `endpoint` names what the tokens guard, e.g. "Debug API"
+pub fn authorize(tokens: &[BearerToken], presented: &Bearer, endpoint: &str) -> Result<()> {
+    if tokens.iter().any(|token| token.matches(presented)) {
+        return Ok(());
+    }
+    warn!(
+        "Rejected a {} request without a valid bearer token",
+        endpoint
+    );
+    Err(ApiError::Unauthorized(format!(
+        "{} requires a valid bearer token",
+        endpoint
+    )))
+}
+
+/// Compare two byte slices without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn presented(authorization: &str) -> Bearer {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        );
+        Bearer::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_authorize() {
+        let tokens = [BearerToken::new("s3cret"), BearerToken::new("other")];
+        assert!(authorize(&tokens, &presented("Bearer s3cret"), "Debug API").is_ok());
+        assert!(authorize(&tokens, &presented("Bearer other"), "Debug API").is_ok());
+
+        for rejected in [
+            presented("Bearer wrong"),
+            presented("Bearer s3cret2"),
+            presented("Basic s3cret"),
+            Bearer::from_headers(&HeaderMap::new()),
+        ] {
+            let err = authorize(&tokens, &rejected, "Debug API").unwrap_err();
+            assert!(matches!(err, ApiError::Unauthorized(m) if m.starts_with("Debug API")));
+        }
+        assert!(authorize(&[], &presented("Bearer s3cret"), "Debug API").is_err());
+    }
+}
@@ -0,0 +1,481 @@
+//! TLS bootstrap of joining nodes: an agent started with `--join` presents a
+//! bootstrap token and a certificate signing request to [`BOOTSTRAP_PATH`],
+//! and the control plane answers with a certificate signed by the cluster
+//! CA. The agent serves with that certificate and presents it as its client
+//! certificate, so no certificates have to be distributed to nodes up front.
+//!
+//! A bootstrap token only vouches for joining, not for a particular node, so
+//! the certificate names no more than the node and the addresses the control
+//! plane knows it by, and a node name is certified for one key: a later
+//! request for it is only signed when it is for that same key, i.e. comes
+//! from the node renewing its own certificate.
+
+use crate::auth::BearerToken;
+use crate::{ApiError, Result};
+use miette::{Context, IntoDiagnostic};
+use rcgen::{
+    CertificateParams, CertificateSigningRequestParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair, PublicKeyData, SanType,
+};
+use reddwarf_core::is_valid_name;
+use reddwarf_storage::KVStore;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Path joining nodes post their certificate signing request to
+pub const BOOTSTRAP_PATH: &str = "/apis/reddwarf.io/v1alpha1/bootstrap/certificates";
+
+/// Storage key prefix of the public key each node name is certified for
+const ISSUED_KEY_PREFIX: &str = "bootstrap/issued/";
+
+/// How long a joining node waits for the control plane
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Body of a certificate request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeCertificateRequest {
+    /// Name of the joining node, the issued certificate's common name
+    pub node_name: String,
+    /// PEM-encoded PKCS#10 certificate signing request
+    pub csr: String,
+}
+
+/// Answer to a certificate request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeCertificateResponse {
+    /// PEM-encoded node certificate
+    pub certificate: String,
+    /// PEM-encoded cluster CA certificate the node should trust
+    pub ca_certificate: String,
+}
+
+/// Signs the certificate requests of joining nodes that present one of the
+/// bootstrap tokens
+#[derive(Clone)]
+pub struct BootstrapSigner {
+    ca_cert_pem: String,
+    /// The cluster CA certificate, as the issuer of node certificates
+    ca_params: CertificateParams,
+    ca_key_pem: String,
+    pub(crate) tokens: Vec<BearerToken>,
+    /// Serializes checking and recording the key a node name is issued for
+    issue_lock: Arc<Mutex<()>>,
+}
+
+impl BootstrapSigner {
+    /// Sign with the cluster CA, accepting any of `tokens`
+    pub fn new(ca_cert_pem: &[u8], ca_key_pem: &[u8], tokens: Vec<String>) -> miette::Result<Self> {
+        let ca_cert_pem = String::from_utf8(ca_cert_pem.to_vec())
+            .into_diagnostic()
+            .wrap_err("CA certificate is not valid PEM")?;
+        let ca_params = CertificateParams::from_ca_cert_pem(&ca_cert_pem)
+            .into_diagnostic()
+            .wrap_err("failed to load CA certificate")?;
+        if !matches!(ca_params.is_ca, IsCa::Ca(_)) {
+            return Err(miette::miette!("CA certificate is not a CA"));
+        }
+        let ca_key_pem = String::from_utf8(ca_key_pem.to_vec())
+            .into_diagnostic()
+            .wrap_err("CA key is not valid PEM")?;
+        KeyPair::from_pem(&ca_key_pem)
+            .into_diagnostic()
+            .wrap_err("failed to load CA key")?;
+        Ok(Self {
+            ca_cert_pem,
+            ca_params,
+            ca_key_pem,
+            tokens: tokens.into_iter().map(BearerToken::new).collect(),
+            issue_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Issue a certificate for the CSR's key, valid for both server and
+    /// client auth, with `node_name` as its common name and DNS SAN. Of the
+    /// SANs requested only those in `node_sans`, the node's own addresses,
+    /// are kept. Refused when the node name is certified for another key.
+    pub fn sign(
+        &self,
+        storage: &dyn KVStore,
+        request: &NodeCertificateRequest,
+        node_sans: &[SanType],
+    ) -> Result<NodeCertificateResponse> {
+        let node_name = request.node_name.as_str();
+        if !is_valid_name(node_name) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid node name '{}'",
+                node_name
+            )));
+        }
+        let node_san = node_name.try_into().map(SanType::DnsName).map_err(|e| {
+            ApiError::BadRequest(format!("Invalid node name '{}': {}", node_name, e))
+        })?;
+        let mut csr = CertificateSigningRequestParams::from_pem(&request.csr).map_err(|e| {
+            ApiError::BadRequest(format!("Invalid certificate signing request: {}", e))
+        })?;
+
+        // Only the key and the node's own SANs are taken from the request;
+        // what the certificate may be used for is decided here
+        let params = &mut csr.params;
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, node_name);
+        params.is_ca = IsCa::ExplicitNoCa;
+        params.key_usages = Vec::new();
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        params.custom_extensions = Vec::new();
+        let requested = std::mem::take(&mut params.subject_alt_names);
+        params.subject_alt_names.push(node_san);
+        for san in requested {
+            if params.subject_alt_names.contains(&san) {
+                continue;
+            }
+            if node_sans.contains(&san) {
+                params.subject_alt_names.push(san);
+            } else {
+                warn!(
+                    "Dropped SAN {:?} from the certificate request of node {}",
+                    san, node_name
+                );
+            }
+        }
+
+        let ca_key = KeyPair::from_pem(&self.ca_key_pem)
+            .map_err(|e| ApiError::Internal(format!("Failed to load CA key: {}", e)))?;
+        let ca_cert = self
+            .ca_params
+            .clone()
+            .self_signed(&ca_key)
+            .map_err(|e| ApiError::Internal(format!("Failed to load CA: {}", e)))?;
+
+        let _issuing = self.issue_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let issued_key = format!("{}{}", ISSUED_KEY_PREFIX, node_name);
+        let public_key = csr.public_key.der_bytes().to_vec();
+        match storage.get(issued_key.as_bytes())? {
+            Some(issued) if issued.as_ref() != public_key.as_slice() => {
+                return Err(ApiError::Conflict(format!(
+                    "Node {} already has a certificate for another key; only the node may \
+                     renew it, with its own key",
+                    node_name
+                )));
+            }
+            _ => {}
+        }
+        let certificate = csr
+            .signed_by(&ca_cert, &ca_key)
+            .map_err(|e| ApiError::Internal(format!("Failed to sign certificate: {}", e)))?;
+        storage.put(issued_key.as_bytes(), &public_key)?;
+        info!("Issued a node certificate to {}", node_name);
+        Ok(NodeCertificateResponse {
+            certificate: certificate.pem(),
+            ca_certificate: self.ca_cert_pem.clone(),
+        })
+    }
+}
+
+/// Join the cluster at `url`: request a certificate for `node_name` and the
+/// `san_entries` with `token`, and write it to `tls_dir` as `server.pem` and
+/// `server-key.pem`, next to the cluster CA as `ca.pem`, where
+/// `TlsMode::AutoGenerate` loads them from
+pub async fn request_node_certificate(
+    url: &str,
+    token: &str,
+    ca_pem: Option<&[u8]>,
+    node_name: &str,
+    san_entries: &[String],
+    tls_dir: &Path,
+) -> miette::Result<()> {
+    let key = KeyPair::generate()
+        .into_diagnostic()
+        .wrap_err("failed to generate node key pair")?;
+    let mut params = CertificateParams::new(san_entries.to_vec())
+        .into_diagnostic()
+        .wrap_err("failed to create certificate request params")?;
+    params
+        .distinguished_name
+        .push(DnType::CommonName, node_name);
+    let csr = params
+        .serialize_request(&key)
+        .and_then(|csr| csr.pem())
+        .into_diagnostic()
+        .wrap_err("failed to create certificate signing request")?;
+
+    let mut builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+    if let Some(pem) = ca_pem {
+        let cert = reqwest::Certificate::from_pem(pem)
+            .into_diagnostic()
+            .wrap_err("invalid cluster CA certificate")?;
+        builder = builder.add_root_certificate(cert);
+    }
+    let client = builder
+        .build()
+        .into_diagnostic()
+        .wrap_err("failed to build HTTP client")?;
+
+    let endpoint = format!("{}{}", url.trim_end_matches('/'), BOOTSTRAP_PATH);
+    info!("Requesting a node certificate from {}", endpoint);
+    let response = client
+        .post(&endpoint)
+        .bearer_auth(token)
+        .json(&NodeCertificateRequest {
+            node_name: node_name.to_string(),
+            csr,
+        })
+        .send()
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to reach {}", endpoint))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(miette::miette!(
+            help =
+                "Check the join token and that the control plane runs with --bootstrap-token-file",
+            "Certificate request was rejected ({}): {}",
+            status,
+            body
+        ));
+    }
+    let issued: NodeCertificateResponse = response
+        .json()
+        .await
+        .into_diagnostic()
+        .wrap_err("invalid certificate response")?;
+
+    std::fs::create_dir_all(tls_dir)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to create TLS directory {}", tls_dir.display()))?;
+    let files = [
+        ("ca.pem", issued.ca_certificate.as_bytes()),
+        ("server.pem", issued.certificate.as_bytes()),
+    ];
+    for (name, contents) in files {
+        let path = tls_dir.join(name);
+        std::fs::write(&path, contents)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    }
+    let key_path = tls_dir.join("server-key.pem");
+    crate::tls::write_private(&key_path, key.serialize_pem().as_bytes())
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", key_path.display()))?;
+    info!("Node certificate written to {}", tls_dir.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::{resolve_tls, TlsMode};
+    use rcgen::BasicConstraints;
+    use reddwarf_storage::RedbBackend;
+    use tempfile::tempdir;
+    use x509_parser::pem::parse_x509_pem;
+
+    /// Check that the PEM certificate `cert` was issued by the CA `ca` and
+    /// return its SANs, rendered as strings
+    fn issued_sans(cert: &str, ca: &str) -> Vec<String> {
+        let (_, cert) = parse_x509_pem(cert.as_bytes()).unwrap();
+        let cert = cert.parse_x509().unwrap();
+        let (_, ca) = parse_x509_pem(ca.as_bytes()).unwrap();
+        let ca = ca.parse_x509().unwrap();
+        assert_eq!(cert.issuer(), ca.subject());
+        cert.verify_signature(Some(ca.public_key())).unwrap();
+        cert.subject_alternative_name()
+            .unwrap()
+            .unwrap()
+            .value
+            .general_names
+            .iter()
+            .map(|name| match name {
+                x509_parser::extensions::GeneralName::DNSName(dns) => dns.to_string(),
+                x509_parser::extensions::GeneralName::IPAddress(ip) => {
+                    let octets: [u8; 4] = (*ip).try_into().unwrap();
+                    std::net::Ipv4Addr::from(octets).to_string()
+                }
+                other => panic!("unexpected SAN {:?}", other),
+            })
+            .collect()
+    }
+
+    fn csr(key: &KeyPair, sans: &[&str]) -> String {
+        let sans: Vec<String> = sans.iter().map(|s| s.to_string()).collect();
+        CertificateParams::new(sans)
+            .unwrap()
+            .serialize_request(key)
+            .unwrap()
+            .pem()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sign_keeps_only_the_node_sans() {
+        // A CA of the operator's, not the auto-generated one
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::default();
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "Operator Root");
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_pem = ca_params.self_signed(&ca_key).unwrap().pem();
+        let signer = BootstrapSigner::new(
+            ca_pem.as_bytes(),
+            ca_key.serialize_pem().as_bytes(),
+            vec!["join-secret".to_string()],
+        )
+        .unwrap();
+
+        let dir = tempdir().unwrap();
+        let storage = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        let node_key = KeyPair::generate().unwrap();
+        let request = |node_name: &str, csr: String| NodeCertificateRequest {
+            node_name: node_name.to_string(),
+            csr,
+        };
+        let node_sans = [
+            SanType::IpAddress("10.0.0.5".parse().unwrap()),
+            SanType::DnsName("node1.example".try_into().unwrap()),
+        ];
+
+        let garbage = request("node1", "not a CSR".to_string());
+        assert!(matches!(
+            signer.sign(&storage, &garbage, &node_sans),
+            Err(ApiError::BadRequest(_))
+        ));
+        let bad_name = request("no such name!", csr(&node_key, &[]));
+        assert!(matches!(
+            signer.sign(&storage, &bad_name, &node_sans),
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let wanted = ["10.0.0.5", "10.0.0.6", "node1.example", "api.example"];
+        let issued = signer
+            .sign(
+                &storage,
+                &request("node1", csr(&node_key, &wanted)),
+                &node_sans,
+            )
+            .unwrap();
+        assert_eq!(issued.ca_certificate, ca_pem);
+        assert_eq!(
+            issued_sans(&issued.certificate, &ca_pem),
+            ["node1", "10.0.0.5", "node1.example"]
+        );
+
+        // The node may renew with its key, but the name is not handed out
+        // for another key
+        signer
+            .sign(&storage, &request("node1", csr(&node_key, &[])), &node_sans)
+            .unwrap();
+        let other_key = KeyPair::generate().unwrap();
+        assert!(matches!(
+            signer.sign(
+                &storage,
+                &request("node1", csr(&other_key, &[])),
+                &node_sans
+            ),
+            Err(ApiError::Conflict(_))
+        ));
+        signer
+            .sign(&storage, &request("node2", csr(&other_key, &[])), &[])
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_join_with_bootstrap_token() {
+        use crate::handlers::sign_node_certificate;
+        use crate::AppState;
+        use axum::routing::post;
+        use axum::Router;
+        use reddwarf_versioning::VersionStore;
+        use std::net::SocketAddr;
+
+        let dir = tempdir().unwrap();
+        let material = resolve_tls(&TlsMode::AutoGenerate {
+            data_dir: dir.path().join("tls"),
+            san_entries: vec!["127.0.0.1".to_string()],
+        })
+        .unwrap()
+        .unwrap();
+        let signer = BootstrapSigner::new(
+            material.ca_pem.as_deref().unwrap(),
+            material.ca_key_pem.as_deref().unwrap(),
+            vec!["join-secret".to_string()],
+        )
+        .unwrap();
+
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store).with_bootstrap(signer));
+        let router = Router::new()
+            .route(BOOTSTRAP_PATH, post(sign_node_certificate))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+
+        let sans = vec![
+            "localhost".to_string(),
+            "10.0.0.5".to_string(),
+            "127.0.0.1".to_string(),
+        ];
+        let node_dir = dir.path().join("node1");
+        assert!(
+            request_node_certificate(&url, "wrong", None, "node1", &sans, &node_dir)
+                .await
+                .is_err()
+        );
+        assert!(!node_dir.join("server.pem").exists());
+
+        request_node_certificate(&url, "join-secret", None, "node1", &sans, &node_dir)
+            .await
+            .unwrap();
+        let ca_pem = String::from_utf8(material.ca_pem.unwrap()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(node_dir.join("ca.pem")).unwrap(),
+            ca_pem
+        );
+
+        // The issued certificate carries the node name and the address it
+        // joined from, but not the other SANs requested, and the agent
+        // loads it as its TLS material
+        let issued = std::fs::read_to_string(node_dir.join("server.pem")).unwrap();
+        assert_eq!(issued_sans(&issued, &ca_pem), ["node1", "127.0.0.1"]);
+        let loaded = resolve_tls(&TlsMode::AutoGenerate {
+            data_dir: node_dir,
+            san_entries: Vec::new(),
+        })
+        .unwrap()
+        .unwrap();
+        assert!(loaded.ca_key_pem.is_none());
+        crate::tls::server_config(&loaded).unwrap();
+        reqwest::Identity::from_pkcs8_pem(&loaded.cert_pem, &loaded.key_pem).unwrap();
+
+        // Another node can't join under the same name with the token
+        let err = request_node_certificate(
+            &url,
+            "join-secret",
+            None,
+            "node1",
+            &sans,
+            &dir.path().join("impostor"),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("409"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Health of the components embedded in the process
+//!
+//! The scheduler, controllers and node agent run as tasks next to the API
+//! server. Whatever supervises them reports here when a component starts,
+//! fails and is restarted, and `/api/v1/componentstatuses` serves the
+//! result as `v1` ComponentStatuses, so `kubectl get componentstatuses`
+//! shows which components are failing and how often they were restarted.
+
+use chrono::{DateTime, Utc};
+use reddwarf_core::k8s_openapi::api::core::v1::{ComponentCondition, ComponentStatus};
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+/// Annotation of a ComponentStatus with how often the component was
+/// restarted
+pub const RESTART_COUNT_ANNOTATION: &str = "reddwarf.io/restart-count";
+
+/// What is known about one component
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentHealth {
+    /// Whether the component is running
+    pub healthy: bool,
+    /// Times the component was restarted after failing
+    pub restarts: u32,
+    /// How the component last failed: its error, panic message, or that it
+    /// returned
+    pub last_error: Option<String>,
+    /// When the component last failed
+    pub last_failure: Option<DateTime<Utc>>,
+}
+
+/// Components of the process by name, shared between their supervisor and
+/// the API server
+#[derive(Debug, Clone, Default)]
+pub struct ComponentRegistry {
+    components: Arc<RwLock<BTreeMap<String, ComponentHealth>>>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut ComponentHealth)) {
+        let mut components = self.components.write().unwrap_or_else(|e| e.into_inner());
+        f(components.entry(name.to_string()).or_default());
+    }
+
+    /// Record that `name` is running
+    pub fn started(&self, name: &str) {
+        self.update(name, |health| health.healthy = true);
+    }
+
+    /// Record that `name` stopped with `error`
+    pub fn failed(&self, name: &str, error: impl Into<String>) {
+        self.update(name, |health| {
+            health.healthy = false;
+            health.last_error = Some(error.into());
+            health.last_failure = Some(Utc::now());
+        });
+    }
+
+    /// Record that `name` is being restarted after a failure
+    pub fn restarting(&self, name: &str) {
+        self.update(name, |health| health.restarts += 1);
+    }
+
+    /// What is known about `name`
+    pub fn get(&self, name: &str) -> Option<ComponentHealth> {
+        let components = self.components.read().unwrap_or_else(|e| e.into_inner());
+        components.get(name).cloned()
+    }
+
+    /// All components, by name
+    pub fn list(&self) -> Vec<(String, ComponentHealth)> {
+        let components = self.components.read().unwrap_or_else(|e| e.into_inner());
+        components
+            .iter()
+            .map(|(name, health)| (name.clone(), health.clone()))
+            .collect()
+    }
+}
+
+/// `health` of the component `name` as a ComponentStatus
+pub fn component_status(name: &str, health: &ComponentHealth) -> ComponentStatus {
+    let message = match (health.healthy, health.restarts) {
+        (true, 0) => "ok".to_string(),
+        (true, restarts) => format!("ok, after {} restart(s)", restarts),
+        (false, _) => "failed, restarting".to_string(),
+    };
+    ComponentStatus {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            annotations: Some(BTreeMap::from([(
+                RESTART_COUNT_ANNOTATION.to_string(),
+                health.restarts.to_string(),
+            )])),
+            ..Default::default()
+        },
+        conditions: Some(vec![ComponentCondition {
+            type_: "Healthy".to_string(),
+            status: if health.healthy { "True" } else { "False" }.to_string(),
+            message: Some(message),
+            error: health
+                .last_error
+                .as_ref()
+                .map(|error| match health.last_failure {
+                    Some(at) => format!("{} (at {})", error, at.to_rfc3339()),
+                    None => error.clone(),
+                }),
+        }]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_tracks_failures_and_restarts() {
+        let registry = ComponentRegistry::new();
+        registry.started("scheduler");
+        registry.started("pod-controller");
+        assert_eq!(
+            registry.get("scheduler"),
+            Some(ComponentHealth {
+                healthy: true,
+                ..Default::default()
+            })
+        );
+
+        registry.failed("scheduler", "panicked: index out of bounds");
+        let status = component_status("scheduler", &registry.get("scheduler").unwrap());
+        let condition = &status.conditions.unwrap()[0];
+        assert_eq!(condition.status, "False");
+        assert!(condition
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("panicked: index out of bounds"));
+
+        registry.restarting("scheduler");
+        registry.started("scheduler");
+        let health = registry.get("scheduler").unwrap();
+        assert!(health.healthy);
+        assert_eq!(health.restarts, 1);
+        let status = component_status("scheduler", &health);
+        assert_eq!(
+            status.metadata.annotations.unwrap()[RESTART_COUNT_ANNOTATION],
+            "1"
+        );
+        assert_eq!(status.conditions.unwrap()[0].status, "True");
+
+        let names: Vec<String> = registry.list().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["pod-controller", "scheduler"]);
+    }
+}
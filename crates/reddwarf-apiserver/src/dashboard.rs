@@ -0,0 +1,156 @@
+//! Embedded web dashboard, enabled with the `dashboard` feature
+//!
+//! Static assets are compiled into the binary and served under
+//! `/dashboard`. The page reads nodes and pods from the regular list
+//! endpoints, follows them over the SSE watch API, and draws the version DAG
+//! from `/dashboard/api/commits`.
+
+use crate::{AppState, Result};
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const INDEX_HTML: &str = include_str!("../assets/dashboard/index.html");
+const APP_JS: &str = include_str!("../assets/dashboard/app.js");
+const STYLE_CSS: &str = include_str!("../assets/dashboard/style.css");
+
+/// Commits returned when the request sets no limit
+const DEFAULT_COMMIT_LIMIT: usize = 50;
+
+/// Most commits a single request may ask for
+const MAX_COMMIT_LIMIT: usize = 500;
+
+/// Routes of the dashboard and its assets
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/dashboard",
+            get(|| async { Redirect::permanent("/dashboard/") }),
+        )
+        .route("/dashboard/", get(index))
+        .route("/dashboard/app.js", get(app_js))
+        .route("/dashboard/style.css", get(style_css))
+        .route("/dashboard/api/commits", get(list_commits))
+}
+
+async fn index() -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+        INDEX_HTML,
+    )
+        .into_response()
+}
+
+async fn app_js() -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        APP_JS,
+    )
+        .into_response()
+}
+
+async fn style_css() -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/css; charset=utf-8")],
+        STYLE_CSS,
+    )
+        .into_response()
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CommitQuery {
+    pub limit: Option<usize>,
+}
+
+/// A commit of the version DAG without the resource contents
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSummary {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub message: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// `{change type} {resource key}` of each change
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitList {
+    pub head: Option<String>,
+    pub items: Vec<CommitSummary>,
+}
+
+/// GET /dashboard/api/commits
+///
+/// The most recent commits, newest first.
+pub async fn list_commits(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CommitQuery>,
+) -> Result<Json<CommitList>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_COMMIT_LIMIT)
+        .min(MAX_COMMIT_LIMIT);
+
+    let mut commits = state.version_store.list_commits()?;
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.timestamp));
+    let items = commits
+        .into_iter()
+        .take(limit)
+        .map(|commit| CommitSummary {
+            changes: commit
+                .changes
+                .iter()
+                .map(|c| format!("{:?} {}", c.change_type, c.resource_key))
+                .collect(),
+            id: commit.id,
+            parents: commit.parents,
+            message: commit.message,
+            timestamp: commit.timestamp,
+        })
+        .collect();
+
+    Ok(Json(CommitList {
+        head: state.version_store.head_id(),
+        items,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::create_resource;
+    use reddwarf_core::Namespace;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_list_commits_newest_first() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+
+        for name in ["a", "b", "c"] {
+            let mut namespace = Namespace::default();
+            namespace.metadata.name = Some(name.to_string());
+            create_resource(&state, namespace).await.unwrap();
+        }
+
+        let Json(list) = list_commits(State(state), Query(CommitQuery { limit: Some(2) }))
+            .await
+            .unwrap();
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.head.as_deref(), Some(list.items[0].id.as_str()));
+        assert_eq!(
+            list.items[0].changes,
+            vec!["Create v1/Namespace/c".to_string()]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Request deadlines
+//!
+//! Every request except the long-running ones (watches, the replication
+//! stream and node proxying) must produce its response within the server's
+//! maximum request duration, or within `?timeoutSeconds=` when the client
+//! asks for less (`0` asks for nothing); otherwise it is answered with a 504
+//! Status and the handler is dropped. Watches end after their own
+//! `timeoutSeconds` instead, see [`crate::watch::WatchParams`].
+//!
+//! The deadline is only checked when a handler yields. Storage calls into
+//! redb block their worker thread, so a request stuck in one is answered
+//! once the call returns rather than at its deadline, and the write it
+//! started is not undone.
+
+use crate::replica::REPLICATION_PATH;
+use crate::ApiError;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+use tracing::warn;
+
+/// Default maximum request duration, as in Kubernetes
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Whether `request` streams for as long as the client wants and is exempt
+/// from the request deadline
+pub fn is_long_running(request: &Request) -> bool {
+    let path = request.uri().path();
+    let watch = request
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .any(|pair| pair == "watch=true" || pair == "watch=1");
+    watch
+        || path.contains("/watch/")
+        || path == REPLICATION_PATH
+        || (path.starts_with("/api/v1/nodes/") && path.contains("/proxy"))
+}
+
+/// The `timeoutSeconds` a request asks for, if any; `0`, as in Kubernetes,
+/// is the same as none
+fn requested_timeout(request: &Request) -> Option<Duration> {
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("timeoutSeconds="))
+        .and_then(|seconds| seconds.parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+/// Middleware answering requests that outlive their deadline with 504
+pub async fn enforce_deadline(
+    State(max): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if is_long_running(&request) {
+        return next.run(request).await;
+    }
+    let timeout = requested_timeout(&request).map_or(max, |requested| requested.min(max));
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!("{} {} did not complete within {:?}", method, path, timeout);
+            ApiError::Timeout(format!(
+                "The request did not complete within {}s",
+                timeout.as_secs_f64()
+            ))
+            .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_deadline() {
+        let stuck = || async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            "done"
+        };
+        let router = Router::new()
+            .route("/api/v1/pods", get(stuck))
+            .route("/api/v1/nodes/node1/proxy/logs", get(stuck))
+            .route("/healthz", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                Duration::from_secs(2),
+                enforce_deadline,
+            ));
+        let send = |uri: &str| {
+            router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = send("/healthz").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // timeoutSeconds shortens the deadline, never lengthens it
+        let started = tokio::time::Instant::now();
+        let response = send("/api/v1/pods?timeoutSeconds=1").await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(3));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["reason"], "Timeout");
+        assert_eq!(status["code"], 504);
+
+        let started = tokio::time::Instant::now();
+        let response = send("/api/v1/pods?timeoutSeconds=3600").await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        // timeoutSeconds=0 leaves the server's deadline
+        let started = tokio::time::Instant::now();
+        let response = send("/api/v1/pods?timeoutSeconds=0").await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() >= Duration::from_secs(2));
+
+        // Watches and proxied streams are not cut off
+        let (watch, proxy) = tokio::join!(
+            tokio::time::timeout(Duration::from_secs(3), send("/api/v1/pods?watch=true")),
+            tokio::time::timeout(
+                Duration::from_secs(3),
+                send("/api/v1/nodes/node1/proxy/logs")
+            ),
+        );
+        assert!(watch.is_err());
+        assert!(proxy.is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::auth::BearerToken;
+use crate::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Backend for the `/debug/zones` and `/debug/decisions` admin endpoints
+///
+/// Implemented by the node agent over its `ZoneRuntime` and its pod
+/// controller's decision log. Zones and decisions are returned as opaque
+/// JSON so the runtime's own view is shown verbatim.
+#[async_trait]
+pub trait ZoneDebugBackend: Send + Sync {
+    /// List all zones as the runtime sees them
+    async fn list_zones(&self) -> Result<Vec<serde_json::Value>>;
+
+    /// Get information about a single zone
+    async fn get_zone(&self, zone_name: &str) -> Result<serde_json::Value>;
+
+    /// Forcefully halt a zone
+    async fn halt_zone(&self, zone_name: &str) -> Result<()>;
+
+    /// The last `limit` decisions of the pod controller, oldest first,
+    /// optionally only those about pods of `namespace` or named `pod`
+    async fn list_decisions(
+        &self,
+        namespace: Option<&str>,
+        pod: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>>;
+}
+
+/// Debug API configuration: the runtime backend plus the bearer token
+/// required to access it
+#[derive(Clone)]
+pub struct ZoneDebug {
+    pub backend: Arc<dyn ZoneDebugBackend>,
+    pub token: BearerToken,
+}
+
+impl ZoneDebug {
+    /// Create a new debug API configuration
+    pub fn new(backend: Arc<dyn ZoneDebugBackend>, token: impl Into<String>) -> Self {
+        Self {
+            backend,
+            token: BearerToken::new(token),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use reddwarf_core::{ErrorClass, ErrorKind};
 use serde_json::json;
 
 /// API error type
@@ -29,22 +30,128 @@ pub enum ApiError {
 
     /// Method not allowed (405)
     MethodNotAllowed(String),
+
+    /// Missing or invalid credentials (401)
+    Unauthorized(String),
+
+    /// The request is refused, e.g. deleting a protected object (403)
+    Forbidden(String),
+
+    /// Requested resource version is no longer available (410)
+    Gone(String),
+
+    /// An upstream node agent could not be reached or failed (502)
+    BadGateway(String),
+
+    /// The request did not complete within its deadline (504)
+    Timeout(String),
+
+    /// The request body is larger than the server accepts (413)
+    PayloadTooLarge(String),
+
+    /// The client did not send the request body in time (408)
+    RequestTimeout(String),
+
+    /// The request is refused for now and may be retried, e.g. an eviction
+    /// a disruption budget blocks (429)
+    TooManyRequests(String),
 }
 
 /// Result type for API operations
 pub type Result<T> = std::result::Result<T, ApiError>;
 
+impl ErrorClass for ApiError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ApiError::NotFound(_) => ErrorKind::NotFound,
+            ApiError::AlreadyExists(_) => ErrorKind::AlreadyExists,
+            ApiError::Conflict(_) => ErrorKind::Conflict,
+            ApiError::BadRequest(_)
+            | ApiError::ValidationFailed(_)
+            | ApiError::UnsupportedMediaType(_)
+            | ApiError::MethodNotAllowed(_)
+            | ApiError::PayloadTooLarge(_) => ErrorKind::Invalid,
+            ApiError::Unauthorized(_) => ErrorKind::Unauthorized,
+            ApiError::Forbidden(_) => ErrorKind::Forbidden,
+            ApiError::Gone(_) => ErrorKind::Expired,
+            ApiError::BadGateway(_) | ApiError::TooManyRequests(_) => ErrorKind::Unavailable,
+            ApiError::Timeout(_) | ApiError::RequestTimeout(_) => ErrorKind::Timeout,
+            ApiError::Internal(_) => ErrorKind::Internal,
+        }
+    }
+
+    fn user_facing_reason(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "BadRequest",
+            ApiError::UnsupportedMediaType(_) => "UnsupportedMediaType",
+            ApiError::MethodNotAllowed(_) => "MethodNotAllowed",
+            ApiError::TooManyRequests(_) => "TooManyRequests",
+            ApiError::PayloadTooLarge(_) => "RequestEntityTooLarge",
+            ApiError::RequestTimeout(_) => "RequestTimeout",
+            _ => self.kind().reason(),
+        }
+    }
+}
+
+impl ApiError {
+    /// An error of `kind` with `message`, for errors of other crates
+    fn of_kind(kind: ErrorKind, message: String) -> Self {
+        match kind {
+            ErrorKind::NotFound => ApiError::NotFound(message),
+            ErrorKind::AlreadyExists => ApiError::AlreadyExists(message),
+            ErrorKind::Conflict => ApiError::Conflict(message),
+            ErrorKind::Invalid => ApiError::BadRequest(message),
+            ErrorKind::Unauthorized => ApiError::Unauthorized(message),
+            ErrorKind::Forbidden => ApiError::Forbidden(message),
+            ErrorKind::Expired => ApiError::Gone(message),
+            ErrorKind::Unavailable => ApiError::BadGateway(message),
+            ErrorKind::Timeout => ApiError::Timeout(message),
+            ErrorKind::Internal => ApiError::Internal(message),
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::AlreadyExists(_) | ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Gone(_) => StatusCode::GONE,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            ApiError::AlreadyExists(msg) => (StatusCode::CONFLICT, msg),
-            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            ApiError::ValidationFailed(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
-            ApiError::UnsupportedMediaType(msg) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg),
-            ApiError::MethodNotAllowed(msg) => (StatusCode::METHOD_NOT_ALLOWED, msg),
+        let status = self.status();
+        let reason = self.user_facing_reason();
+        let message = match self {
+            ApiError::NotFound(msg)
+            | ApiError::AlreadyExists(msg)
+            | ApiError::Conflict(msg)
+            | ApiError::BadRequest(msg)
+            | ApiError::Internal(msg)
+            | ApiError::ValidationFailed(msg)
+            | ApiError::UnsupportedMediaType(msg)
+            | ApiError::MethodNotAllowed(msg)
+            | ApiError::Unauthorized(msg)
+            | ApiError::Forbidden(msg)
+            | ApiError::Gone(msg)
+            | ApiError::BadGateway(msg)
+            | ApiError::Timeout(msg)
+            | ApiError::TooManyRequests(msg)
+            | ApiError::PayloadTooLarge(msg)
+            | ApiError::RequestTimeout(msg) => msg,
         };
 
         let body = Json(json!({
@@ -52,6 +159,7 @@ impl IntoResponse for ApiError {
             "kind": "Status",
             "status": "Failure",
             "message": message,
+            "reason": reason,
             "code": status.as_u16()
         }));
 
@@ -64,35 +172,21 @@ impl From<reddwarf_core::ReddwarfError> for ApiError {
         use reddwarf_core::ReddwarfError;
 
         match err {
-            ReddwarfError::ResourceNotFound { .. } => ApiError::NotFound(err.to_string()),
-            ReddwarfError::ResourceAlreadyExists { .. } => ApiError::AlreadyExists(err.to_string()),
-            ReddwarfError::Conflict { .. } => ApiError::Conflict(err.to_string()),
             ReddwarfError::ValidationFailed { .. } => ApiError::ValidationFailed(err.to_string()),
-            ReddwarfError::InvalidResource { .. } => ApiError::BadRequest(err.to_string()),
-            _ => ApiError::Internal(err.to_string()),
+            _ => ApiError::of_kind(err.kind(), err.to_string()),
         }
     }
 }
 
 impl From<reddwarf_storage::StorageError> for ApiError {
     fn from(err: reddwarf_storage::StorageError) -> Self {
-        use reddwarf_storage::StorageError;
-
-        match err {
-            StorageError::KeyNotFound { .. } => ApiError::NotFound(err.to_string()),
-            _ => ApiError::Internal(err.to_string()),
-        }
+        ApiError::of_kind(err.kind(), err.to_string())
     }
 }
 
 impl From<reddwarf_versioning::VersioningError> for ApiError {
     fn from(err: reddwarf_versioning::VersioningError) -> Self {
-        use reddwarf_versioning::VersioningError;
-
-        match err {
-            VersioningError::Conflict { .. } => ApiError::Conflict(err.to_string()),
-            _ => ApiError::Internal(err.to_string()),
-        }
+        ApiError::of_kind(err.kind(), err.to_string())
     }
 }
 
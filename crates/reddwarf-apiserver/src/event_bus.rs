@@ -1,4 +1,12 @@
-pub use reddwarf_core::{ResourceEvent, WatchEventType};
+pub use reddwarf_core::{
+    EventBus, InProcessEventBus, ResourceEvent, SnapshotSource, SubscriberStats, WatchEventType,
+};
+
+use crate::handlers::common::unseal_with;
+use reddwarf_core::{GroupVersionKind, ReddwarfError, ResourceKey};
+use reddwarf_storage::{EnvelopeEncryptor, KVStore, KeyEncoder, RedbBackend};
+use std::fmt::Write;
+use std::sync::Arc;
 
 /// Configuration for the event bus
 #[derive(Debug, Clone)]
@@ -13,10 +21,114 @@ impl Default for EventBusConfig {
     }
 }
 
+/// Resync snapshots read from storage: every stored object of the kind as
+/// an ADDED event at the resource version it was last written at
+pub struct StoredSnapshots {
+    storage: Arc<RedbBackend>,
+    encryption: Option<Arc<EnvelopeEncryptor>>,
+}
+
+impl StoredSnapshots {
+    pub fn new(storage: Arc<RedbBackend>, encryption: Option<Arc<EnvelopeEncryptor>>) -> Self {
+        Self {
+            storage,
+            encryption,
+        }
+    }
+}
+
+impl SnapshotSource for StoredSnapshots {
+    fn snapshot(&self, gvk: &GroupVersionKind) -> reddwarf_core::Result<Vec<ResourceEvent>> {
+        let internal = |e: &dyn std::fmt::Display| ReddwarfError::internal_error(e.to_string());
+        let prefix = KeyEncoder::encode_prefix(&gvk.api_version(), &gvk.kind, None);
+        let stored = self
+            .storage
+            .as_ref()
+            .scan(prefix.as_bytes())
+            .map_err(|e| internal(&e))?;
+
+        let mut events = Vec::with_capacity(stored.len());
+        for (key, data) in stored.iter() {
+            let data = unseal_with(self.encryption.as_deref(), key, data)
+                .map_err(|e| ReddwarfError::internal_error(format!("{:?}", e)))?;
+            let object: serde_json::Value =
+                serde_json::from_slice(&data).map_err(|e| internal(&e))?;
+            let metadata = &object["metadata"];
+            let field = |name: &str| metadata[name].as_str().unwrap_or_default().to_string();
+            let key = ResourceKey::new(gvk.clone(), field("namespace"), field("name"));
+            let resource_version = field("resourceVersion");
+            events.push(ResourceEvent::added(key, object, resource_version));
+        }
+        Ok(events)
+    }
+}
+
+/// A metric family of subscriber stats: name, type, help and value
+type StatsFamily = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&SubscriberStats) -> u64,
+);
+
+/// Render the delivery stats of the bus's named subscribers in the
+/// Prometheus text format, next to the histograms of [`Metrics`]
+///
+/// [`Metrics`]: reddwarf_core::Metrics
+pub fn render_subscriber_stats(stats: &[SubscriberStats]) -> String {
+    let families: [StatsFamily; 4] = [
+        (
+            "reddwarf_event_bus_backlog_events",
+            "gauge",
+            "Events published that a subscriber had not received yet",
+            |s| s.backlog,
+        ),
+        (
+            "reddwarf_event_bus_lagged_total",
+            "counter",
+            "Times a subscriber fell so far behind that events were dropped",
+            |s| s.lagged,
+        ),
+        (
+            "reddwarf_event_bus_dropped_events_total",
+            "counter",
+            "Events dropped before a subscriber received them",
+            |s| s.dropped,
+        ),
+        (
+            "reddwarf_event_bus_resynced_events_total",
+            "counter",
+            "Events sent to a subscriber in resync snapshots",
+            |s| s.resynced,
+        ),
+    ];
+
+    let mut out = String::new();
+    if stats.is_empty() {
+        return out;
+    }
+    for (name, type_, help, value) in families {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, type_);
+        for subscriber in stats {
+            let _ = writeln!(
+                out,
+                "{}{{subscriber=\"{}\"}} {}",
+                name,
+                subscriber.name,
+                value(subscriber)
+            );
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::handlers::common::{create_resource, delete_resource, update_resource};
+    use crate::handlers::common::{
+        create_resource, delete_resource, update_resource, update_status,
+    };
     use crate::AppState;
     use reddwarf_core::{GroupVersionKind, Pod, Resource, ResourceKey, WatchEventType};
     use reddwarf_storage::RedbBackend;
@@ -77,7 +189,7 @@ mod tests {
         let object = serde_json::json!({"kind": "Pod"});
         let event = ResourceEvent::added(key, object, "v1".to_string());
 
-        state.event_tx.send(event).unwrap();
+        state.event_bus.publish(event);
 
         let received = rx.recv().await.unwrap();
         assert!(matches!(received.event_type, WatchEventType::Added));
@@ -94,7 +206,7 @@ mod tests {
         let key = ResourceKey::new(gvk, "default", "test");
         let event = ResourceEvent::added(key, serde_json::json!({}), "v1".to_string());
 
-        state.event_tx.send(event).unwrap();
+        state.event_bus.publish(event);
 
         let e1 = rx1.recv().await.unwrap();
         let e2 = rx2.recv().await.unwrap();
@@ -108,7 +220,7 @@ mod tests {
         let mut rx = state.subscribe();
 
         let pod = make_test_pod("create-test", "default");
-        create_resource(&*state, pod).await.unwrap();
+        create_resource(&state, pod).await.unwrap();
 
         let event = rx.recv().await.unwrap();
         assert!(matches!(event.event_type, WatchEventType::Added));
@@ -121,12 +233,13 @@ mod tests {
         let state = make_state();
 
         let pod = make_test_pod("update-test", "default");
-        let created = create_resource(&*state, pod).await.unwrap();
+        let mut created = create_resource(&state, pod).await.unwrap();
 
         // Subscribe after create so we only get the update event
         let mut rx = state.subscribe();
 
-        let updated = update_resource(&*state, created).await.unwrap();
+        created.metadata.labels = Some([("app".to_string(), "web".to_string())].into());
+        let updated = update_resource(&state, created).await.unwrap();
         assert!(updated.resource_version().is_some());
 
         let event = rx.recv().await.unwrap();
@@ -134,19 +247,45 @@ mod tests {
         assert_eq!(event.resource_key.name, "update-test");
     }
 
+    #[tokio::test]
+    async fn test_no_op_update_publishes_nothing() {
+        let state = make_state();
+
+        let pod = make_test_pod("noop-test", "default");
+        let created = create_resource(&state, pod).await.unwrap();
+        let mut rx = state.subscribe();
+
+        // Unchanged content, whatever its resource version, is not written
+        let mut stale = created.clone();
+        stale.metadata.resource_version = Some("stale".to_string());
+        let updated = update_resource(&state, stale).await.unwrap();
+        assert_eq!(updated.metadata.uid, created.metadata.uid);
+        update_status(&state, created.clone()).await.unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Err(tokio::sync::broadcast::error::TryRecvError::Empty)
+        ));
+
+        let mut changed = created;
+        changed.metadata.labels = Some([("app".to_string(), "web".to_string())].into());
+        update_resource(&state, changed).await.unwrap();
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event.event_type, WatchEventType::Modified));
+    }
+
     #[tokio::test]
     async fn test_event_published_after_delete() {
         let state = make_state();
 
         let pod = make_test_pod("delete-test", "default");
-        create_resource(&*state, pod).await.unwrap();
+        create_resource(&state, pod).await.unwrap();
 
         // Subscribe after create
         let mut rx = state.subscribe();
 
         let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
         let key = ResourceKey::new(gvk, "default", "delete-test");
-        delete_resource(&*state, &key).await.unwrap();
+        delete_resource(&state, &key).await.unwrap();
 
         let event = rx.recv().await.unwrap();
         assert!(matches!(event.event_type, WatchEventType::Deleted));
@@ -161,15 +300,15 @@ mod tests {
         // Create pods in two different namespaces
         let pod1 = make_test_pod("pod-ns1", "namespace-a");
         let pod2 = make_test_pod("pod-ns2", "namespace-b");
-        create_resource(&*state, pod1).await.unwrap();
-        create_resource(&*state, pod2).await.unwrap();
+        create_resource(&state, pod1).await.unwrap();
+        create_resource(&state, pod2).await.unwrap();
 
         // Receive both events
         let event1 = rx.recv().await.unwrap();
         let event2 = rx.recv().await.unwrap();
 
         // Verify we can filter by namespace
-        let events = vec![event1, event2];
+        let events = [event1, event2];
         let ns_a_events: Vec<_> = events
             .iter()
             .filter(|e| e.resource_key.namespace == "namespace-a")
@@ -184,4 +323,40 @@ mod tests {
         assert_eq!(ns_a_events[0].resource_key.name, "pod-ns1");
         assert_eq!(ns_b_events[0].resource_key.name, "pod-ns2");
     }
+
+    #[tokio::test]
+    async fn test_resync_snapshots_stored_objects() {
+        let state = make_state();
+        let created = create_resource(&state, make_test_pod("web", "default"))
+            .await
+            .unwrap();
+        create_resource(&state, make_test_pod("db", "shop"))
+            .await
+            .unwrap();
+
+        let mut subscription = state.event_bus.subscribe_as("pod-controller");
+        let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
+        assert_eq!(state.resync("pod-controller", &gvk).unwrap(), 2);
+
+        let mut names = Vec::new();
+        for _ in 0..2 {
+            let event = subscription.recv().await.unwrap();
+            assert!(matches!(event.event_type, WatchEventType::Added));
+            if event.resource_key.name == "web" {
+                assert_eq!(event.resource_key.namespace, "default");
+                assert_eq!(
+                    event.resource_version.as_str(),
+                    created.metadata.resource_version.as_deref().unwrap()
+                );
+            }
+            names.push(event.resource_key.name);
+        }
+        names.sort();
+        assert_eq!(names, ["db", "web"]);
+
+        let metrics = render_subscriber_stats(&state.event_bus.subscriber_stats());
+        assert!(metrics
+            .contains("reddwarf_event_bus_resynced_events_total{subscriber=\"pod-controller\"} 2"));
+        assert!(metrics.contains("# TYPE reddwarf_event_bus_backlog_events gauge"));
+    }
 }
@@ -0,0 +1,56 @@
+//! Background expiry of stored events: removes events not seen for the
+//! event store's TTL, so an event storm is bounded by time rather than
+//! growing the database until someone cleans it up
+
+use crate::{AppState, Result};
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+/// Configuration for the event sweeper
+#[derive(Debug, Clone)]
+pub struct EventSweeperConfig {
+    /// How often expired events are removed
+    pub interval: Duration,
+}
+
+impl Default for EventSweeperConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Periodically removes expired events from the event store
+pub struct EventSweeper {
+    state: Arc<AppState>,
+    config: EventSweeperConfig,
+}
+
+impl EventSweeper {
+    pub fn new(state: Arc<AppState>, config: EventSweeperConfig) -> Self {
+        Self { state, config }
+    }
+
+    /// Run the sweep loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!("Starting event sweeper (TTL {:?})", self.state.events.ttl());
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Event sweeper shutting down");
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(self.config.interval) => {
+                    if let Err(e) = self.state.events.expire(Utc::now()) {
+                        error!("Failed to expire events: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Field selectors of lists and watches, e.g.
+//! `?fieldSelector=spec.nodeName=node1,status.phase!=Failed`: a
+//! comma-separated list of terms on fields of an object, all of which an
+//! object must meet to be selected. A field an object does not have
+//! compares as the empty string, so `spec.nodeName=` selects unbound pods.
+
+use crate::{ApiError, Result};
+use serde_json::Value;
+
+/// Fields objects of every kind can be selected by
+pub const METADATA_FIELDS: &[&str] = &["metadata.name", "metadata.namespace"];
+
+/// One `field=value`, `field==value` or `field!=value` term
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Requirement {
+    field: String,
+    value: String,
+    equal: bool,
+}
+
+/// A parsed field selector; the empty selector selects every object
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelector {
+    requirements: Vec<Requirement>,
+}
+
+impl FieldSelector {
+    /// Parse `selector`, which may name the [`METADATA_FIELDS`] and
+    /// `fields`, the other fields `plural` can be selected by
+    pub fn parse(selector: Option<&str>, plural: &str, fields: &[&str]) -> Result<Self> {
+        let mut requirements = Vec::new();
+        for term in selector.unwrap_or_default().split(',') {
+            let term = term.trim();
+            if term.is_empty() {
+                continue;
+            }
+            let (field, value, equal) = if let Some((field, value)) = term.split_once("!=") {
+                (field, value, false)
+            } else if let Some((field, value)) =
+                term.split_once("==").or_else(|| term.split_once('='))
+            {
+                (field, value, true)
+            } else {
+                return Err(ApiError::BadRequest(format!(
+                    "field selector '{}' must be field=value, field==value or field!=value",
+                    term
+                )));
+            };
+            let field = field.trim();
+            if !METADATA_FIELDS.contains(&field) && !fields.contains(&field) {
+                return Err(ApiError::BadRequest(format!(
+                    "field selector '{}' is not supported for {}",
+                    field, plural
+                )));
+            }
+            requirements.push(Requirement {
+                field: field.to_string(),
+                value: value.trim().to_string(),
+                equal,
+            });
+        }
+        Ok(Self { requirements })
+    }
+
+    /// Whether the selector selects every object
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    /// Whether `object`, as JSON, meets every term
+    pub fn matches(&self, object: &Value) -> bool {
+        self.requirements
+            .iter()
+            .all(|r| (field_value(object, &r.field) == r.value) == r.equal)
+    }
+}
+
+/// Value of the field at the dotted `path` of `object` as selectors compare
+/// it: strings as they are, other values as JSON, absent fields as ""
+fn field_value(object: &Value, path: &str) -> String {
+    match path
+        .split('.')
+        .try_fold(object, |value, name| value.get(name))
+    {
+        Some(Value::String(s)) => s.clone(),
+        None | Some(Value::Null) => String::new(),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const POD_FIELDS: &[&str] = &["spec.nodeName", "status.phase"];
+
+    #[test]
+    fn test_field_selector_matches() {
+        let pod = json!({
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {"nodeName": "node1"},
+            "status": {"phase": "Running"}
+        });
+        let unbound = json!({"metadata": {"name": "db", "namespace": "default"}});
+        let select = |selector: &str| FieldSelector::parse(Some(selector), "pods", POD_FIELDS);
+
+        let on_node = select("spec.nodeName=node1,status.phase==Running").unwrap();
+        assert!(on_node.matches(&pod));
+        assert!(!on_node.matches(&unbound));
+
+        let pending = select("spec.nodeName=").unwrap();
+        assert!(!pending.matches(&pod));
+        assert!(pending.matches(&unbound));
+
+        let not_web = select("metadata.name!=web, metadata.namespace=default").unwrap();
+        assert!(!not_web.matches(&pod));
+        assert!(not_web.matches(&unbound));
+
+        let everything = FieldSelector::parse(None, "pods", POD_FIELDS).unwrap();
+        assert!(everything.is_empty());
+        assert!(everything.matches(&pod));
+    }
+
+    #[test]
+    fn test_field_selector_rejects_unsupported_fields() {
+        for selector in ["spec.hostname=web", "status.phase", "spec.nodeName=node1"] {
+            let result = FieldSelector::parse(Some(selector), "services", &[]);
+            assert!(
+                matches!(result, Err(ApiError::BadRequest(_))),
+                "{}",
+                selector
+            );
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! ApplySet pruning
+//!
+//! Objects applied as part of a set carry the
+//! [`APPLYSET_PART_OF_LABEL`] label. After re-applying a set, the client
+//! prunes it with the objects it just applied; every other member of the set
+//! is deleted through its kind's delete strategy, and objects outside the set
+//! are never touched. Protected members are left in place.
+
+use crate::handlers::common::unseal;
+use crate::handlers::generic::RegisteredKind;
+use crate::handlers::protection::check_deletion_protection;
+use crate::response::ApiResponse;
+use crate::validation::validate_name;
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Path, State};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use reddwarf_core::applyset::APPLYSET_PART_OF_LABEL;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::DeleteOptions;
+use reddwarf_core::{applyset_of, GroupVersionKind, ResourceKey};
+use reddwarf_storage::{IndexKey, KVStore};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// An object in an ApplySet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplySetObject {
+    pub api_version: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+/// Body of `POST /apis/reddwarf.io/v1/applysets/{id}/prune`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneRequest {
+    /// Members the latest apply of the set contains
+    #[serde(default)]
+    pub keep: Vec<ApplySetObject>,
+    /// Report what would be pruned without deleting it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Members of the set that were (or, on a dry run, would be) deleted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneResponse {
+    pub pruned: Vec<ApplySetObject>,
+    pub dry_run: bool,
+}
+
+/// Reject an update that moves an object from one ApplySet to another, so
+/// one set's prune never deletes an object another set applied. Objects may
+/// join a set or leave it by dropping the label.
+pub(crate) fn check_applyset_ownership(
+    key: &ResourceKey,
+    prev_data: &[u8],
+    metadata: &reddwarf_core::ObjectMeta,
+) -> Result<()> {
+    let Some(next) = applyset_of(metadata) else {
+        return Ok(());
+    };
+    let prev: serde_json::Value = serde_json::from_slice(prev_data)?;
+    match prev["metadata"]["labels"][APPLYSET_PART_OF_LABEL].as_str() {
+        Some(owner) if owner != next => Err(ApiError::Conflict(format!(
+            "{} belongs to ApplySet {}, not {}",
+            key, owner, next
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// POST /apis/reddwarf.io/v1/applysets/{id}/prune
+pub(crate) async fn prune_applyset(
+    kinds: Arc<[RegisteredKind]>,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<PruneRequest>,
+) -> Result<Response> {
+    validate_name(&id)?;
+
+    let prefix = IndexKey::encode_prefix_for_label(APPLYSET_PART_OF_LABEL, Some(&id));
+    let mut pruned = Vec::new();
+    for storage_key in state.storage.scan_index(&prefix)? {
+        let Some(data) = state.storage.get(storage_key.as_bytes())? else {
+            continue;
+        };
+        let object: serde_json::Value =
+            serde_json::from_slice(&unseal(&state, storage_key.as_bytes(), &data)?)?;
+        let member = ApplySetObject {
+            api_version: object["apiVersion"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            kind: object["kind"].as_str().unwrap_or_default().to_string(),
+            namespace: object["metadata"]["namespace"].as_str().map(String::from),
+            name: object["metadata"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        if request.keep.contains(&member) {
+            continue;
+        }
+        let Some(registered) = kinds
+            .iter()
+            .find(|k| k.api_version == member.api_version && k.kind == member.kind)
+        else {
+            continue;
+        };
+
+        let gvk = GroupVersionKind::from_api_version_kind(&member.api_version, &member.kind);
+        let key = ResourceKey::new(
+            gvk,
+            member.namespace.clone().unwrap_or_default(),
+            member.name.clone(),
+        );
+        match check_deletion_protection(&state, &key, None) {
+            Ok(()) => {}
+            Err(ApiError::Forbidden(_)) => {
+                warn!("Not pruning protected {} from ApplySet {}", key, id);
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+
+        if !request.dry_run {
+            match (registered.delete)(&state, &key, &DeleteOptions::default()).await {
+                Ok(_) | Err(ApiError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        pruned.push(member);
+    }
+
+    info!(
+        "Pruned {} objects from ApplySet {}{}",
+        pruned.len(),
+        id,
+        if request.dry_run { " (dry run)" } else { "" }
+    );
+    Ok(ApiResponse::ok(PruneResponse {
+        pruned,
+        dry_run: request.dry_run,
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::ResourceRegistry;
+    use crate::AppState;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request, StatusCode};
+    use reddwarf_core::{Pod, Service};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_prune_deletes_only_dropped_members() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let router = ResourceRegistry::new()
+            .register::<Service>()
+            .register::<Pod>()
+            .into_router()
+            .with_state(state);
+
+        let send = |method: Method, uri: &str, body: serde_json::Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default(),
+                )
+            }
+        };
+        let service = |name: &str, set: Option<&str>| {
+            let mut service = serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "Service",
+                "metadata": {"name": name}
+            });
+            if let Some(set) = set {
+                service["metadata"]["labels"] =
+                    serde_json::json!({ "applyset.kubernetes.io/part-of": set });
+            }
+            service
+        };
+
+        for (name, set) in [
+            ("web", Some("shop")),
+            ("db", Some("shop")),
+            ("blog", Some("cms")),
+            ("dns", None),
+        ] {
+            let (status, _) = send(
+                Method::POST,
+                "/api/v1/namespaces/default/services",
+                service(name, set),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        // A dry run reports the dropped member without deleting it
+        let keep = serde_json::json!([
+            {"apiVersion": "v1", "kind": "Service", "namespace": "default", "name": "web"}
+        ]);
+        let (status, body) = send(
+            Method::POST,
+            "/apis/reddwarf.io/v1/applysets/shop/prune",
+            serde_json::json!({"keep": keep, "dryRun": true}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["dryRun"], true);
+        assert_eq!(body["pruned"][0]["name"], "db");
+        assert_eq!(body["pruned"].as_array().unwrap().len(), 1);
+        let (status, _) = send(
+            Method::GET,
+            "/api/v1/namespaces/default/services/db",
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send(
+            Method::POST,
+            "/apis/reddwarf.io/v1/applysets/shop/prune",
+            serde_json::json!({ "keep": keep }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["pruned"][0]["name"], "db");
+        for (name, expected) in [
+            ("web", StatusCode::OK),
+            ("db", StatusCode::NOT_FOUND),
+            ("blog", StatusCode::OK),
+            ("dns", StatusCode::OK),
+        ] {
+            let uri = format!("/api/v1/namespaces/default/services/{}", name);
+            let (status, _) = send(Method::GET, &uri, serde_json::Value::Null).await;
+            assert_eq!(status, expected, "{}", name);
+        }
+
+        // Objects cannot move between sets, but unowned objects may join one
+        let (status, _) = send(
+            Method::PATCH,
+            "/api/v1/namespaces/default/services/blog",
+            serde_json::json!({"metadata": {"labels": {"applyset.kubernetes.io/part-of": "shop"}}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send(
+            Method::PATCH,
+            "/api/v1/namespaces/default/services/dns",
+            serde_json::json!({"metadata": {"labels": {"applyset.kubernetes.io/part-of": "shop"}}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::handlers::generic::ResourceKind;
+use crate::{AppState, Result};
+use async_trait::async_trait;
+use reddwarf_core::resources::{
+    default_horizontal_pod_autoscaler, AUTOSCALING_API_VERSION, HORIZONTAL_POD_AUTOSCALER_KIND,
+};
+use reddwarf_core::HorizontalPodAutoscaler;
+
+#[async_trait]
+impl ResourceKind for HorizontalPodAutoscaler {
+    const API_VERSION: &'static str = AUTOSCALING_API_VERSION;
+    const KIND: &'static str = HORIZONTAL_POD_AUTOSCALER_KIND;
+    const PLURAL: &'static str = "horizontalpodautoscalers";
+    const SHORT_NAMES: &'static [&'static str] = &["hpa"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    async fn admit(_state: &AppState, autoscaler: &mut HorizontalPodAutoscaler) -> Result<()> {
+        default_horizontal_pod_autoscaler(autoscaler);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::{get_resource, ListPath};
+    use crate::handlers::generic::ResourceHandlers;
+    use crate::ApiError;
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use reddwarf_core::{GroupVersionKind, ResourceKey};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn autoscaler(min_replicas: i32) -> HorizontalPodAutoscaler {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "autoscaling/v2",
+            "kind": "HorizontalPodAutoscaler",
+            "metadata": {"name": "web"},
+            "spec": {
+                "scaleTargetRef": {"apiVersion": "apps/v1", "kind": "Deployment", "name": "web"},
+                "minReplicas": min_replicas,
+                "maxReplicas": 4
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_autoscaler_defaults_cpu_target() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let namespace = || {
+            Path(ListPath {
+                namespace: Some("default".to_string()),
+            })
+        };
+
+        ResourceHandlers::<HorizontalPodAutoscaler>::create(
+            State(state.clone()),
+            namespace(),
+            Json(autoscaler(2)),
+        )
+        .await
+        .unwrap();
+
+        let key = ResourceKey::new(
+            GroupVersionKind::from_api_version_kind(
+                AUTOSCALING_API_VERSION,
+                HORIZONTAL_POD_AUTOSCALER_KIND,
+            ),
+            "default",
+            "web",
+        );
+        let stored: HorizontalPodAutoscaler = get_resource(&state, &key).await.unwrap();
+        let metrics = stored.spec.unwrap().metrics.unwrap();
+        let resource = metrics[0].resource.as_ref().unwrap();
+        assert_eq!(resource.name, "cpu");
+        assert_eq!(resource.target.average_utilization, Some(80));
+
+        // More replicas at least than at most
+        let result = ResourceHandlers::<HorizontalPodAutoscaler>::create(
+            State(state),
+            namespace(),
+            Json(autoscaler(5)),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::ValidationFailed(_))));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::auth::{authorize, Bearer};
+use crate::bootstrap::NodeCertificateRequest;
+use crate::handlers::common::get_resource;
+use crate::handlers::generic::ResourceHandlers;
+use crate::response::ApiResponse;
+use crate::{ApiError, AppState, Result};
+use axum::extract::{ConnectInfo, State};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use rcgen::SanType;
+use reddwarf_core::{Node, ResourceKey};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// POST /apis/reddwarf.io/v1alpha1/bootstrap/certificates
+pub async fn sign_node_certificate(
+    State(state): State<Arc<AppState>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    bearer: Bearer,
+    Json(request): Json<NodeCertificateRequest>,
+) -> Result<Response> {
+    let signer = state
+        .bootstrap
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Node bootstrap is not enabled".to_string()))?;
+    authorize(&signer.tokens, &bearer, "Node bootstrap")?;
+
+    let peer = connect_info.map(|Extension(ConnectInfo(addr))| addr);
+    let node_sans = node_sans(&state, &request.node_name, peer).await?;
+    let issued = signer.sign(state.storage.as_ref(), &request, &node_sans)?;
+    Ok(ApiResponse::ok(issued).into_response())
+}
+
+/// The addresses a joining node may have in its certificate: the one it
+/// connects from, and those of its Node if it is registered already
+async fn node_sans(
+    state: &AppState,
+    node_name: &str,
+    peer: Option<SocketAddr>,
+) -> Result<Vec<SanType>> {
+    let mut sans: Vec<SanType> = peer
+        .map(|addr| SanType::IpAddress(addr.ip().to_canonical()))
+        .into_iter()
+        .collect();
+
+    let key = ResourceKey::cluster_scoped(ResourceHandlers::<Node>::gvk(), node_name);
+    let node: Node = match get_resource(state, &key).await {
+        Ok(node) => node,
+        Err(ApiError::NotFound(_)) => return Ok(sans),
+        Err(e) => return Err(e),
+    };
+    let addresses = node.status.and_then(|s| s.addresses).unwrap_or_default();
+    for address in addresses {
+        if let Ok(ip) = address.address.parse() {
+            sans.push(SanType::IpAddress(ip));
+        } else if let Ok(name) = address.address.as_str().try_into() {
+            sans.push(SanType::DnsName(name));
+        }
+    }
+    Ok(sans)
+}
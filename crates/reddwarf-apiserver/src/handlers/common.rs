@@ -1,21 +1,82 @@
-use crate::event_bus::ResourceEvent;
+use crate::event_bus::{EventBus, ResourceEvent};
+use crate::field_selector::FieldSelector;
+use crate::handlers::applyset::check_applyset_ownership;
+use crate::response::ApiResponse;
+use crate::watch::{watch_resource_stream, WatchParams};
 use crate::{ApiError, AppState, Result};
-use reddwarf_core::{Resource, ResourceKey};
-use reddwarf_storage::{KVStore, KeyEncoder};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use reddwarf_core::{GroupVersionKind, Resource, ResourceKey, STATUS_ANNOTATION_PREFIX};
+use reddwarf_storage::{EnvelopeEncryptor, KVStore, KeyEncoder};
 use reddwarf_versioning::{Change, CommitBuilder};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tracing::{debug, info};
 use uuid::Uuid;
 
+/// Storage key prefixes of the kinds encrypted at rest when encryption is
+/// enabled
+const ENCRYPTED_PREFIXES: &[&str] = &["v1/Secret/"];
+
+/// Prepare `data` to be stored under `storage_key`: encrypted when its kind
+/// is encrypted at rest and encryption is enabled
+///
+/// Commits record the value as stored, so the version history holds no
+/// plaintext either.
+pub(crate) fn seal(state: &AppState, storage_key: &str, data: Vec<u8>) -> Result<Vec<u8>> {
+    match &state.encryption {
+        Some(encryptor)
+            if ENCRYPTED_PREFIXES
+                .iter()
+                .any(|prefix| storage_key.starts_with(prefix)) =>
+        {
+            Ok(encryptor.encrypt(storage_key.as_bytes(), &data)?)
+        }
+        _ => Ok(data),
+    }
+}
+
+/// Plaintext of a value stored under `storage_key`
+pub(crate) fn unseal(state: &AppState, storage_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    unseal_with(state.encryption.as_deref(), storage_key, data)
+}
+
+/// Plaintext of a value stored under `storage_key`, decrypted with
+/// `encryption` if it was encrypted
+pub(crate) fn unseal_with(
+    encryption: Option<&EnvelopeEncryptor>,
+    storage_key: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    if !EnvelopeEncryptor::is_encrypted(data) {
+        return Ok(data.to_vec());
+    }
+    let encryptor = encryption.ok_or_else(|| {
+        ApiError::Internal(format!(
+            "{} is encrypted at rest but no encryption key is configured",
+            String::from_utf8_lossy(storage_key)
+        ))
+    })?;
+    Ok(encryptor.decrypt(storage_key, data)?)
+}
+
+/// The value stored under `storage_key`, as stored and in plaintext
+fn read_stored(state: &AppState, storage_key: &str) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let Some(stored) = state.storage.as_ref().get(storage_key.as_bytes())? else {
+        return Ok(None);
+    };
+    let plaintext = unseal(state, storage_key.as_bytes(), &stored)?;
+    Ok(Some((stored.to_vec(), plaintext)))
+}
+
 /// Get a resource from storage
 pub async fn get_resource<T: Resource>(state: &AppState, key: &ResourceKey) -> Result<T> {
     debug!("Getting resource: {}", key);
 
     let storage_key = KeyEncoder::encode_resource_key(key);
-    let data = state
-        .storage
-        .as_ref()
-        .get(storage_key.as_bytes())?
+    let (_, data) = read_stored(state, &storage_key)?
         .ok_or_else(|| ApiError::NotFound(format!("Resource not found: {}", key)))?;
 
     let resource: T = serde_json::from_slice(&data)?;
@@ -39,11 +100,12 @@ pub async fn create_resource<T: Resource>(state: &AppState, mut resource: T) ->
         )));
     }
 
-    // Set UID and initial resource version
+    // Set UID, creation time and initial resource version
     resource.set_uid(Uuid::new_v4().to_string());
+    resource.metadata_mut().creation_timestamp = Some(Time(Utc::now()));
 
     // Serialize resource
-    let data = serde_json::to_vec(&resource)?;
+    let data = seal(state, &storage_key, serde_json::to_vec(&resource)?)?;
 
     // Create commit
     let change = Change::create(
@@ -63,7 +125,8 @@ pub async fn create_resource<T: Resource>(state: &AppState, mut resource: T) ->
     // Set resource version to commit ID
     resource.set_resource_version(reddwarf_core::ResourceVersion::new(commit.id().to_string()));
 
-    // Store in storage
+    // Store with the resource version, so reads return the current one
+    let data = seal(state, &storage_key, serde_json::to_vec(&resource)?)?;
     state.storage.as_ref().put(storage_key.as_bytes(), &data)?;
 
     info!("Created resource: {} with version {}", key, commit.id());
@@ -71,13 +134,46 @@ pub async fn create_resource<T: Resource>(state: &AppState, mut resource: T) ->
     // Publish ADDED event (best-effort)
     if let Ok(object) = serde_json::to_value(&resource) {
         let event = ResourceEvent::added(key, object, commit.id().to_string());
-        let _ = state.event_tx.send(event);
+        state.event_bus.publish(event);
     }
 
     Ok(resource)
 }
 
+/// Whether `next` has the same content as the stored `prev`, ignoring
+/// `metadata.resourceVersion`
+fn content_unchanged(prev: &serde_json::Value, next: &serde_json::Value) -> bool {
+    let without_version = |value: &serde_json::Value| {
+        let mut value = value.clone();
+        if let Some(metadata) = value["metadata"].as_object_mut() {
+            metadata.remove("resourceVersion");
+        }
+        value
+    };
+    without_version(prev) == without_version(next)
+}
+
+/// Refuse a write of `next` based on a copy of the object other than the
+/// stored `current` one
+///
+/// Writes that carry no `metadata.resourceVersion` are unconditional.
+pub fn check_resource_version(current: &ObjectMeta, next: &ObjectMeta) -> Result<()> {
+    match &next.resource_version {
+        Some(version) if current.resource_version.as_ref() != Some(version) => {
+            Err(ApiError::Conflict(format!(
+                "{} was modified since resourceVersion {}; read it again and retry",
+                next.name.as_deref().unwrap_or_default(),
+                version
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Update a resource in storage
+///
+/// Writes that leave the content unchanged are not committed and publish no
+/// event; the stored resource is returned as is.
 pub async fn update_resource<T: Resource>(state: &AppState, mut resource: T) -> Result<T> {
     let key = resource
         .resource_key()
@@ -88,14 +184,18 @@ pub async fn update_resource<T: Resource>(state: &AppState, mut resource: T) ->
     let storage_key = KeyEncoder::encode_resource_key(&key);
 
     // Get previous version
-    let prev_data = state
-        .storage
-        .as_ref()
-        .get(storage_key.as_bytes())?
+    let (prev_data, prev_plaintext) = read_stored(state, &storage_key)?
         .ok_or_else(|| ApiError::NotFound(format!("Resource not found: {}", key)))?;
+    check_applyset_ownership(&key, &prev_plaintext, resource.metadata())?;
+
+    let prev_json: serde_json::Value = serde_json::from_slice(&prev_plaintext)?;
+    if content_unchanged(&prev_json, &serde_json::to_value(&resource)?) {
+        debug!("Skipping no-op update of {}", key);
+        return Ok(serde_json::from_value(prev_json)?);
+    }
 
     // Serialize new resource
-    let new_data = serde_json::to_vec(&resource)?;
+    let new_data = seal(state, &storage_key, serde_json::to_vec(&resource)?)?;
 
     // Create commit
     let change = Change::update(
@@ -116,18 +216,19 @@ pub async fn update_resource<T: Resource>(state: &AppState, mut resource: T) ->
     // Set resource version to commit ID
     resource.set_resource_version(reddwarf_core::ResourceVersion::new(commit.id().to_string()));
 
-    // Update in storage
+    // Update in storage, with the resource version
+    let final_data = seal(state, &storage_key, serde_json::to_vec(&resource)?)?;
     state
         .storage
         .as_ref()
-        .put(storage_key.as_bytes(), &new_data)?;
+        .put(storage_key.as_bytes(), &final_data)?;
 
     info!("Updated resource: {} with version {}", key, commit.id());
 
     // Publish MODIFIED event (best-effort)
     if let Ok(object) = serde_json::to_value(&resource) {
         let event = ResourceEvent::modified(key, object, commit.id().to_string());
-        let _ = state.event_tx.send(event);
+        state.event_bus.publish(event);
     }
 
     Ok(resource)
@@ -135,15 +236,22 @@ pub async fn update_resource<T: Resource>(state: &AppState, mut resource: T) ->
 
 /// Delete a resource from storage
 pub async fn delete_resource(state: &AppState, key: &ResourceKey) -> Result<()> {
+    delete_resource_with(state, key, |_| {}).await
+}
+
+/// Delete a resource, publishing its last-known state as amended by
+/// `amend` in the DELETED event
+pub(crate) async fn delete_resource_with(
+    state: &AppState,
+    key: &ResourceKey,
+    amend: impl FnOnce(&mut serde_json::Value),
+) -> Result<()> {
     info!("Deleting resource: {}", key);
 
     let storage_key = KeyEncoder::encode_resource_key(key);
 
     // Get current version
-    let prev_data = state
-        .storage
-        .as_ref()
-        .get(storage_key.as_bytes())?
+    let (prev_data, prev_plaintext) = read_stored(state, &storage_key)?
         .ok_or_else(|| ApiError::NotFound(format!("Resource not found: {}", key)))?;
 
     // Create commit
@@ -167,19 +275,49 @@ pub async fn delete_resource(state: &AppState, key: &ResourceKey) -> Result<()>
     info!("Deleted resource: {} at version {}", key, commit.id());
 
     // Publish DELETED event with last-known state (best-effort)
-    if let Ok(object) = serde_json::from_slice::<serde_json::Value>(&prev_data) {
+    if let Ok(mut object) = serde_json::from_slice::<serde_json::Value>(&prev_plaintext) {
+        amend(&mut object);
         let event = ResourceEvent::deleted(key.clone(), object, commit.id().to_string());
-        let _ = state.event_tx.send(event);
+        state.event_bus.publish(event);
     }
 
     Ok(())
 }
 
+/// Replace the existing `status.reddwarf.io/` annotations with the incoming ones
+fn merge_status_annotations(
+    existing_json: &mut serde_json::Value,
+    incoming: &serde_json::Map<String, serde_json::Value>,
+) {
+    let metadata = &mut existing_json["metadata"];
+    if !metadata["annotations"].is_object() {
+        metadata["annotations"] = serde_json::Value::Object(Default::default());
+    }
+    let Some(annotations) = metadata["annotations"].as_object_mut() else {
+        return;
+    };
+
+    annotations.retain(|k, _| !k.starts_with(STATUS_ANNOTATION_PREFIX));
+    annotations.extend(
+        incoming
+            .iter()
+            .filter(|(k, _)| k.starts_with(STATUS_ANNOTATION_PREFIX))
+            .map(|(k, v)| (k.clone(), v.clone())),
+    );
+    if annotations.is_empty() {
+        if let Some(m) = metadata.as_object_mut() {
+            m.remove("annotations");
+        }
+    }
+}
+
 /// Update only the status subresource of a resource
 ///
 /// This reads the existing resource, replaces only `.status` from the incoming
-/// resource, preserving `.spec` and `.metadata` (except bumping `resourceVersion`).
-/// Publishes a MODIFIED event on the event bus.
+/// resource, preserving `.spec` and `.metadata` (except bumping `resourceVersion`
+/// and replacing the `status.reddwarf.io/` annotations).
+/// Publishes a MODIFIED event on the event bus; a status write that changes
+/// nothing is neither committed nor published.
 pub async fn update_status<T: Resource>(state: &AppState, resource: T) -> Result<T> {
     let key = resource
         .resource_key()
@@ -190,14 +328,11 @@ pub async fn update_status<T: Resource>(state: &AppState, resource: T) -> Result
     let storage_key = KeyEncoder::encode_resource_key(&key);
 
     // Read existing resource from storage
-    let existing_data = state
-        .storage
-        .as_ref()
-        .get(storage_key.as_bytes())?
+    let (existing_data, existing_plaintext) = read_stored(state, &storage_key)?
         .ok_or_else(|| ApiError::NotFound(format!("Resource not found: {}", key)))?;
 
     // Parse existing and incoming as JSON values
-    let mut existing_json: serde_json::Value = serde_json::from_slice(&existing_data)?;
+    let mut existing_json: serde_json::Value = serde_json::from_slice(&existing_plaintext)?;
     let incoming_json = serde_json::to_value(&resource)?;
 
     // Replace only the status field from the incoming resource
@@ -205,8 +340,18 @@ pub async fn update_status<T: Resource>(state: &AppState, resource: T) -> Result
         existing_json["status"] = status.clone();
     }
 
+    // Status annotations travel with the status; other annotations are untouched
+    if let Some(incoming) = incoming_json["metadata"]["annotations"].as_object() {
+        merge_status_annotations(&mut existing_json, incoming);
+    }
+
+    if content_unchanged(&serde_json::from_slice(&existing_plaintext)?, &existing_json) {
+        debug!("Skipping no-op status update of {}", key);
+        return Ok(serde_json::from_value(existing_json)?);
+    }
+
     // Serialize the merged resource
-    let merged_data = serde_json::to_vec(&existing_json)?;
+    let merged_data = seal(state, &storage_key, serde_json::to_vec(&existing_json)?)?;
 
     // Create commit
     let change = Change::update(
@@ -229,7 +374,7 @@ pub async fn update_status<T: Resource>(state: &AppState, resource: T) -> Result
         serde_json::Value::String(commit.id().to_string());
 
     // Serialize again with updated resource version
-    let final_data = serde_json::to_vec(&existing_json)?;
+    let final_data = seal(state, &storage_key, serde_json::to_vec(&existing_json)?)?;
 
     // Store in storage
     state
@@ -248,7 +393,7 @@ pub async fn update_status<T: Resource>(state: &AppState, resource: T) -> Result
 
     // Publish MODIFIED event (best-effort)
     let event = ResourceEvent::modified(key, existing_json, commit.id().to_string());
-    let _ = state.event_tx.send(event);
+    state.event_bus.publish(event);
 
     Ok(updated)
 }
@@ -260,8 +405,8 @@ pub async fn list_resources<T: Resource>(state: &AppState, prefix: &str) -> Resu
     let results = state.storage.as_ref().scan(prefix.as_bytes())?;
 
     let mut resources = Vec::new();
-    for (_key, data) in results.iter() {
-        let resource: T = serde_json::from_slice(data)?;
+    for (key, data) in results.iter() {
+        let resource: T = serde_json::from_slice(&unseal(state, key, data)?)?;
         resources.push(resource);
     }
 
@@ -269,6 +414,60 @@ pub async fn list_resources<T: Resource>(state: &AppState, prefix: &str) -> Resu
     Ok(resources)
 }
 
+/// Path parameters of a list route: the namespace is absent on
+/// all-namespaces and cluster-scoped routes
+#[derive(Debug, Default, Deserialize)]
+pub struct ListPath {
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// The `items` `selector` selects
+pub fn select_items<T: Serialize>(items: Vec<T>, selector: &FieldSelector) -> Result<Vec<T>> {
+    if selector.is_empty() {
+        return Ok(items);
+    }
+    let mut selected = Vec::new();
+    for item in items {
+        if selector.matches(&serde_json::to_value(&item)?) {
+            selected.push(item);
+        }
+    }
+    Ok(selected)
+}
+
+/// Shared body of every list handler: watch `api_version`/`kind` when the
+/// request asks to, framed as its `headers` negotiate, otherwise list it as
+/// a `{kind}List`, scoped to `namespace` and the objects `selector` selects
+/// in both cases
+pub async fn list_or_watch<T: Resource>(
+    state: &Arc<AppState>,
+    api_version: &str,
+    kind: &str,
+    namespace: Option<String>,
+    selector: FieldSelector,
+    params: &WatchParams,
+    headers: &HeaderMap,
+) -> Result<Response> {
+    if params.is_watch() {
+        let gvk = GroupVersionKind::from_api_version_kind(api_version, kind);
+        return watch_resource_stream(state, gvk, namespace, selector, params, headers);
+    }
+
+    let prefix = KeyEncoder::encode_prefix(api_version, kind, namespace.as_deref());
+    let resource_version = list_resource_version(state);
+    let items: Vec<T> = select_items(list_resources(state, &prefix).await?, &selector)?;
+
+    let response = ListResponse::new(
+        api_version.to_string(),
+        format!("{}List", kind),
+        items,
+        resource_version,
+    );
+
+    Ok(ApiResponse::ok(response).into_response())
+}
+
 /// List response wrapper
 #[derive(Serialize)]
 pub struct ListResponse<T: Serialize> {
@@ -287,14 +486,23 @@ pub struct ListMetadata {
 }
 
 impl<T: Serialize> ListResponse<T> {
-    pub fn new(api_version: String, kind: String, items: Vec<T>) -> Self {
+    /// A list taken at `resource_version`, see [`list_resource_version`]
+    pub fn new(api_version: String, kind: String, items: Vec<T>, resource_version: String) -> Self {
         Self {
             api_version,
             kind,
             items,
-            metadata: ListMetadata {
-                resource_version: Uuid::new_v4().to_string(),
-            },
+            metadata: ListMetadata { resource_version },
         }
     }
 }
+
+/// Resource version of a list about to be read: the HEAD commit, so that a
+/// watch from it replays every change the list may have missed. Read it
+/// before the list, not after.
+pub fn list_resource_version(state: &AppState) -> String {
+    state
+        .version_store
+        .head_id()
+        .unwrap_or_else(|| "0".to_string())
+}
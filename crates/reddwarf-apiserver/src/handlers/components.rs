@@ -0,0 +1,71 @@
+use crate::components::component_status;
+use crate::handlers::common::{list_resource_version, ListResponse};
+use crate::response::ApiResponse;
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Path, State};
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+/// GET /api/v1/componentstatuses
+pub async fn list_component_statuses(State(state): State<Arc<AppState>>) -> Result<Response> {
+    let items = state
+        .components
+        .list()
+        .iter()
+        .map(|(name, health)| component_status(name, health))
+        .collect();
+    let list = ListResponse::new(
+        "v1".to_string(),
+        "ComponentStatusList".to_string(),
+        items,
+        list_resource_version(&state),
+    );
+    Ok(ApiResponse::ok(list).into_response())
+}
+
+/// GET /api/v1/componentstatuses/{name}
+pub async fn get_component_status(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Response> {
+    let health = state
+        .components
+        .get(&name)
+        .ok_or_else(|| ApiError::NotFound(format!("ComponentStatus {} not found", name)))?;
+    Ok(ApiResponse::ok(component_status(&name, &health)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use axum::http::StatusCode;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_component_statuses() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        state.components.started("scheduler");
+        state.components.failed("node-agent", "exited");
+
+        let resp = list_component_statuses(State(state.clone())).await.unwrap();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let list: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list["kind"], "ComponentStatusList");
+        assert_eq!(list["items"][0]["metadata"]["name"], "node-agent");
+        assert_eq!(list["items"][0]["conditions"][0]["status"], "False");
+        assert_eq!(list["items"][1]["conditions"][0]["status"], "True");
+
+        let resp = get_component_status(State(state.clone()), Path("scheduler".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let result = get_component_status(State(state), Path("missing".to_string())).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+    }
+}
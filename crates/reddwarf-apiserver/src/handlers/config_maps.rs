@@ -0,0 +1,120 @@
+use crate::handlers::generic::ResourceKind;
+use crate::{ApiError, Result};
+use reddwarf_core::resources::{is_immutable, CONFIG_MAP_KIND, MAX_CONFIG_MAP_SIZE};
+use reddwarf_core::ConfigMap;
+
+impl ResourceKind for ConfigMap {
+    const API_VERSION: &'static str = "v1";
+    const KIND: &'static str = CONFIG_MAP_KIND;
+    const PLURAL: &'static str = "configmaps";
+    const SHORT_NAMES: &'static [&'static str] = &["cm"];
+    const NAMESPACED: bool = true;
+    /// Room for the largest data allowed, escaped as JSON, and metadata
+    const MAX_BODY_BYTES: Option<usize> = Some(2 * MAX_CONFIG_MAP_SIZE);
+
+    /// An immutable ConfigMap keeps its data, and stays immutable, until it
+    /// is deleted and recreated
+    fn validate_update(current: &ConfigMap, config_map: &ConfigMap) -> Result<()> {
+        if !is_immutable(current) {
+            return Ok(());
+        }
+        if !is_immutable(config_map) {
+            return Err(ApiError::ValidationFailed(
+                "immutable: field is immutable when `immutable` is set".to_string(),
+            ));
+        }
+        if current.data != config_map.data || current.binary_data != config_map.binary_data {
+            return Err(ApiError::ValidationFailed(
+                "data: field is immutable when `immutable` is set".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::ListPath;
+    use crate::handlers::generic::{ObjectPath, ResourceHandlers};
+    use crate::AppState;
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_config_map_size_cap_and_immutability() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let namespace = || {
+            Path(ListPath {
+                namespace: Some("default".to_string()),
+            })
+        };
+        let object = || {
+            Path(ObjectPath {
+                namespace: Some("default".to_string()),
+                name: "settings".to_string(),
+            })
+        };
+
+        let oversized: ConfigMap = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "big"},
+            "data": {"blob": "x".repeat(1024 * 1024)}
+        }))
+        .unwrap();
+        let created = ResourceHandlers::<ConfigMap>::create(
+            State(state.clone()),
+            namespace(),
+            Json(oversized),
+        )
+        .await;
+        assert!(matches!(created, Err(ApiError::ValidationFailed(_))));
+
+        let config_map: ConfigMap = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "settings"},
+            "data": {"color": "blue"}
+        }))
+        .unwrap();
+        ResourceHandlers::<ConfigMap>::create(State(state.clone()), namespace(), Json(config_map))
+            .await
+            .unwrap();
+
+        // Mutable until marked immutable
+        ResourceHandlers::<ConfigMap>::patch(
+            State(state.clone()),
+            object(),
+            Json(serde_json::json!({"data": {"color": "red"}, "immutable": true})),
+        )
+        .await
+        .unwrap();
+
+        for patch in [
+            serde_json::json!({"data": {"color": "green"}}),
+            serde_json::json!({"immutable": false}),
+        ] {
+            let patched =
+                ResourceHandlers::<ConfigMap>::patch(State(state.clone()), object(), Json(patch))
+                    .await;
+            assert!(matches!(patched, Err(ApiError::ValidationFailed(_))));
+        }
+
+        // Metadata may still change
+        ResourceHandlers::<ConfigMap>::patch(
+            State(state.clone()),
+            object(),
+            Json(serde_json::json!({"metadata": {"labels": {"team": "web"}}})),
+        )
+        .await
+        .unwrap();
+    }
+}
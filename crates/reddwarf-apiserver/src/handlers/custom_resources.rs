@@ -0,0 +1,769 @@
+//! CustomResourceDefinitions, and the objects of the kinds they define
+//!
+//! A CRD is served through the generic handlers like any built-in kind.
+//! Objects of the kinds CRDs define are served by the dynamic handlers
+//! here, at `/apis/{group}/{version}/...`, which look up the CRD of each
+//! request's group and plural: objects are kept as JSON, checked against the
+//! schema of the version they are written at, and stored at the CRD's
+//! storage version. Versions differ only in name (conversion strategy
+//! `None`), so an object read at another served version just carries that
+//! version's `apiVersion`.
+
+use crate::field_selector::FieldSelector;
+use crate::handlers::common::{
+    create_resource, delete_resource, get_resource, list_resource_version, list_resources,
+    select_items, update_resource, update_status, ListResponse,
+};
+use crate::handlers::generic::{ResourceHandlers, ResourceKind};
+use crate::handlers::protection::{check_deletion_protection, CONFIRM_DELETE_HEADER};
+use crate::response::{conditional_ok, status_deleted, ApiResponse};
+use crate::server::builtin_resources;
+use crate::watch::{watch_converted_stream, WatchParams};
+use crate::{ApiError, AppState, Result};
+use async_trait::async_trait;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use reddwarf_core::k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinitionVersion;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::DeleteOptions;
+use reddwarf_core::resources::{
+    crd_is_namespaced, crd_name, established_status, has_status_subresource, served_version,
+    storage_version, validate_custom_object, CRD_API_VERSION, CRD_KIND,
+};
+use reddwarf_core::{CustomObject, CustomResourceDefinition, GroupVersionKind, ResourceKey};
+use reddwarf_storage::KeyEncoder;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::info;
+
+/// API group reserved for reddwarf's own kinds, with its subdomains
+const RESERVED_GROUP: &str = "reddwarf.io";
+
+#[async_trait]
+impl ResourceKind for CustomResourceDefinition {
+    const API_VERSION: &'static str = CRD_API_VERSION;
+    const KIND: &'static str = CRD_KIND;
+    const PLURAL: &'static str = "customresourcedefinitions";
+    const SHORT_NAMES: &'static [&'static str] = &["crd", "crds"];
+    const NAMESPACED: bool = false;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    /// A valid definition is served as soon as it is stored
+    fn normalize(crd: &mut CustomResourceDefinition) {
+        crd.status = Some(established_status(crd));
+    }
+
+    /// Stored objects are keyed by the group, kind and storage version, so
+    /// those stay as they were created
+    fn validate_update(
+        current: &CustomResourceDefinition,
+        crd: &CustomResourceDefinition,
+    ) -> Result<()> {
+        let storage = |crd| storage_version(crd).map(|v| v.name.as_str());
+        for (field, changed) in [
+            ("spec.group", current.spec.group != crd.spec.group),
+            ("spec.scope", current.spec.scope != crd.spec.scope),
+            (
+                "spec.names.kind",
+                current.spec.names.kind != crd.spec.names.kind,
+            ),
+            ("spec.versions[storage]", storage(current) != storage(crd)),
+        ] {
+            if changed {
+                return Err(ApiError::ValidationFailed(format!(
+                    "{}: field is immutable",
+                    field
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Custom kinds may not join the groups of the built-in ones
+    async fn admit(_state: &AppState, crd: &mut CustomResourceDefinition) -> Result<()> {
+        let group = crd.spec.group.as_str();
+        let builtin = builtin_resources()
+            .kinds()
+            .iter()
+            .any(|k| k.api_version.split_once('/').map(|(g, _)| g) == Some(group));
+        if builtin || group == RESERVED_GROUP || group.ends_with(&format!(".{}", RESERVED_GROUP)) {
+            return Err(ApiError::ValidationFailed(format!(
+                "spec.group: group '{}' is reserved for built-in kinds",
+                group
+            )));
+        }
+        Ok(())
+    }
+
+    /// Deleting a definition deletes every object of its kind
+    async fn delete(
+        state: &AppState,
+        key: &ResourceKey,
+        _options: &DeleteOptions,
+    ) -> Result<Response> {
+        let crd: CustomResourceDefinition = get_resource(state, key).await?;
+        if let Some(version) = storage_version(&crd) {
+            let api_version = format!("{}/{}", crd.spec.group, version.name);
+            let prefix = KeyEncoder::encode_prefix(&api_version, &crd.spec.names.kind, None);
+            let objects: Vec<CustomObject> = list_resources(state, &prefix).await?;
+            info!(
+                "Deleting {} {} objects with their definition",
+                objects.len(),
+                crd.spec.names.kind
+            );
+            for object in objects {
+                let object_key = ResourceKey::new(
+                    GroupVersionKind::from_api_version_kind(&api_version, &crd.spec.names.kind),
+                    object.metadata.namespace.unwrap_or_default(),
+                    object.metadata.name.unwrap_or_default(),
+                );
+                delete_resource(state, &object_key).await?;
+            }
+        }
+        delete_resource(state, key).await?;
+        Ok(status_deleted(&key.name, CRD_KIND))
+    }
+}
+
+/// Every stored CustomResourceDefinition
+pub(crate) async fn custom_resource_definitions(
+    state: &AppState,
+) -> Result<Vec<CustomResourceDefinition>> {
+    let prefix = KeyEncoder::encode_prefix(CRD_API_VERSION, CRD_KIND, None);
+    list_resources(state, &prefix).await
+}
+
+/// Path parameters of the routes of custom objects
+#[derive(Debug, Deserialize)]
+pub struct CustomObjectPath {
+    group: String,
+    version: String,
+    #[serde(default)]
+    namespace: Option<String>,
+    plural: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// A custom kind as served at the version of a request
+struct ServedKind {
+    crd: CustomResourceDefinition,
+    version: CustomResourceDefinitionVersion,
+    /// API version of the request
+    api_version: String,
+    /// API version objects are stored at
+    storage_api_version: String,
+}
+
+impl ServedKind {
+    /// Look up the CRD serving `path`'s group, version and plural
+    async fn resolve(state: &AppState, path: &CustomObjectPath) -> Result<Self> {
+        let not_found = || {
+            ApiError::NotFound(format!(
+                "the server could not find the requested resource {} in {}/{}",
+                path.plural, path.group, path.version
+            ))
+        };
+        let key = ResourceKey::cluster_scoped(
+            ResourceHandlers::<CustomResourceDefinition>::gvk(),
+            crd_name(&path.group, &path.plural),
+        );
+        let crd: CustomResourceDefinition = match get_resource(state, &key).await {
+            Err(ApiError::NotFound(_)) => return Err(not_found()),
+            result => result?,
+        };
+        // Cluster-scoped kinds have no namespaced routes
+        if path.namespace.is_some() && !crd_is_namespaced(&crd) {
+            return Err(not_found());
+        }
+        let version = served_version(&crd, &path.version)
+            .cloned()
+            .ok_or_else(not_found)?;
+        let storage = storage_version(&crd)
+            .ok_or_else(|| ApiError::Internal(format!("{} has no storage version", key.name)))?;
+        Ok(Self {
+            api_version: format!("{}/{}", crd.spec.group, version.name),
+            storage_api_version: format!("{}/{}", crd.spec.group, storage.name),
+            crd,
+            version,
+        })
+    }
+
+    fn kind(&self) -> &str {
+        &self.crd.spec.names.kind
+    }
+
+    /// Field selector of `params`, which may also name the served version's
+    /// selectable fields
+    fn field_selector(&self, params: &WatchParams) -> Result<FieldSelector> {
+        let fields: Vec<&str> = self
+            .version
+            .selectable_fields
+            .iter()
+            .flatten()
+            .map(|f| f.json_path.trim_start_matches('.'))
+            .collect();
+        params.field_selector(&self.crd.spec.names.plural, &fields)
+    }
+
+    /// GroupVersionKind objects are stored under
+    fn gvk(&self) -> GroupVersionKind {
+        GroupVersionKind::from_api_version_kind(&self.storage_api_version, self.kind())
+    }
+
+    /// Key of the object named by `path`; namespaced kinds are only reached
+    /// through their namespace
+    fn key(&self, path: &CustomObjectPath) -> Result<ResourceKey> {
+        let namespace = match &path.namespace {
+            Some(namespace) => namespace.clone(),
+            None if crd_is_namespaced(&self.crd) => {
+                return Err(ApiError::NotFound(format!(
+                    "{} is namespaced; use /apis/{}/namespaces/{{namespace}}/{}",
+                    self.kind(),
+                    self.api_version,
+                    path.plural
+                )))
+            }
+            None => String::new(),
+        };
+        Ok(ResourceKey::new(
+            self.gvk(),
+            namespace,
+            path.name.clone().unwrap_or_default(),
+        ))
+    }
+
+    /// Decode a request body sent at the request's version, bound to the
+    /// URL's namespace and name, as the object to store
+    fn decode(&self, path: &CustomObjectPath, body: serde_json::Value) -> Result<CustomObject> {
+        let mut object: CustomObject = serde_json::from_value(body)?;
+        if !object.api_version.is_empty() && object.api_version != self.api_version {
+            return Err(ApiError::BadRequest(format!(
+                "apiVersion '{}' does not match the request path ({})",
+                object.api_version, self.api_version
+            )));
+        }
+        if !object.kind.is_empty() && object.kind != self.kind() {
+            return Err(ApiError::BadRequest(format!(
+                "kind '{}' does not match the request path ({})",
+                object.kind,
+                self.kind()
+            )));
+        }
+        let key = self.key(path)?;
+        object.api_version = self.storage_api_version.clone();
+        object.kind = self.kind().to_string();
+        if crd_is_namespaced(&self.crd) {
+            object.metadata.namespace = Some(key.namespace);
+        } else {
+            object.metadata.namespace = None;
+        }
+        if path.name.is_some() {
+            object.metadata.name = Some(key.name);
+        }
+        Ok(object)
+    }
+
+    /// Check `object` against the request version's schema
+    fn validate(&self, object: &mut CustomObject) -> Result<()> {
+        validate_custom_object(&self.version, object)
+            .map_err(|e| ApiError::ValidationFailed(e.to_string()))
+    }
+
+    /// Whether the status is written only through the status subresource
+    fn status_subresource(&self) -> bool {
+        has_status_subresource(&self.version)
+    }
+
+    /// A stored object as served at the request's version
+    fn encode(&self, mut object: CustomObject) -> CustomObject {
+        object.api_version = self.api_version.clone();
+        object
+    }
+
+    /// Add a `Warning: 299` header when the request's version is deprecated
+    fn warn(&self, mut response: Response) -> Response {
+        if self.version.deprecated == Some(true) {
+            let warning =
+                self.version.deprecation_warning.clone().unwrap_or_else(|| {
+                    format!("{} {} is deprecated", self.api_version, self.kind())
+                });
+            if let Ok(value) = HeaderValue::from_str(&format!("299 - \"{}\"", warning)) {
+                response.headers_mut().insert(header::WARNING, value);
+            }
+        }
+        response
+    }
+}
+
+/// GET /apis/{group}/{version}/[namespaces/{namespace}/]{plural}/{name}
+pub async fn get_custom_object(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    let object: CustomObject = get_resource(&state, &served.key(&path)?).await?;
+
+    Ok(served.warn(conditional_ok(&headers, served.encode(object))))
+}
+
+/// GET /apis/{group}/{version}/[namespaces/{namespace}/]{plural}, also
+/// across all namespaces
+pub async fn list_custom_objects(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    Query(params): Query<WatchParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    let selector = served.field_selector(&params)?;
+
+    if params.is_watch() {
+        let api_version = served.api_version.clone();
+        let stream = watch_converted_stream(
+            &state,
+            served.gvk(),
+            path.namespace,
+            selector,
+            &params,
+            &headers,
+            move |mut object| {
+                object["apiVersion"] = serde_json::Value::String(api_version.clone());
+                Some(object)
+            },
+        )?;
+        return Ok(served.warn(stream));
+    }
+
+    let prefix = KeyEncoder::encode_prefix(
+        &served.storage_api_version,
+        served.kind(),
+        path.namespace.as_deref(),
+    );
+    let resource_version = list_resource_version(&state);
+    let objects: Vec<CustomObject> =
+        select_items(list_resources(&state, &prefix).await?, &selector)?;
+    let list_kind = served
+        .crd
+        .spec
+        .names
+        .list_kind
+        .clone()
+        .unwrap_or_else(|| format!("{}List", served.kind()));
+    let response = ListResponse::new(
+        served.api_version.clone(),
+        list_kind,
+        objects.into_iter().map(|o| served.encode(o)).collect(),
+        resource_version,
+    );
+
+    Ok(served.warn(ApiResponse::ok(response).into_response()))
+}
+
+/// POST /apis/{group}/{version}/[namespaces/{namespace}/]{plural}
+pub async fn create_custom_object(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    info!(
+        "Creating {} in namespace: {}",
+        served.kind(),
+        path.namespace.as_deref().unwrap_or_default()
+    );
+
+    let mut object = served.decode(&path, body)?;
+    if served.status_subresource() {
+        object.fields.remove("status");
+    }
+    served.validate(&mut object)?;
+
+    let created = create_resource(&state, object).await?;
+
+    Ok(served.warn(ApiResponse::created(served.encode(created)).into_response()))
+}
+
+/// PUT /apis/{group}/{version}/[namespaces/{namespace}/]{plural}/{name}
+pub async fn replace_custom_object(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    info!("Replacing {}: {}", served.kind(), served.key(&path)?.name);
+
+    let _update = state.update_lock.lock().await;
+    let current: CustomObject = get_resource(&state, &served.key(&path)?).await?;
+    let object = served.decode(&path, body)?;
+
+    store_update(&state, &served, current, object).await
+}
+
+/// PATCH /apis/{group}/{version}/[namespaces/{namespace}/]{plural}/{name}
+/// with a JSON merge patch
+pub async fn patch_custom_object(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    info!("Patching {}: {}", served.kind(), served.key(&path)?.name);
+
+    let _update = state.update_lock.lock().await;
+    let current: CustomObject = get_resource(&state, &served.key(&path)?).await?;
+    let mut json = serde_json::to_value(served.encode(current.clone()))?;
+    json_patch::merge(&mut json, &patch);
+    let object = served.decode(&path, json)?;
+
+    store_update(&state, &served, current, object).await
+}
+
+/// Validate and store `object` in place of `current`, keeping the current
+/// status when it is written through the status subresource
+async fn store_update(
+    state: &AppState,
+    served: &ServedKind,
+    current: CustomObject,
+    mut object: CustomObject,
+) -> Result<Response> {
+    if served.status_subresource() {
+        match current.fields.get("status") {
+            Some(status) => object.fields.insert("status".to_string(), status.clone()),
+            None => object.fields.remove("status"),
+        };
+    }
+    served.validate(&mut object)?;
+
+    let updated = update_resource(state, object).await?;
+
+    Ok(served.warn(ApiResponse::ok(served.encode(updated)).into_response()))
+}
+
+/// DELETE /apis/{group}/{version}/[namespaces/{namespace}/]{plural}/{name},
+/// refused for protected objects unless confirmed
+pub async fn delete_custom_object(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    let key = served.key(&path)?;
+    info!("Deleting {}: {}", served.kind(), key.name);
+
+    let confirmation = headers
+        .get(CONFIRM_DELETE_HEADER)
+        .and_then(|v| v.to_str().ok());
+    check_deletion_protection(&state, &key, confirmation)?;
+    delete_resource(&state, &key).await?;
+
+    Ok(served.warn(status_deleted(&key.name, served.kind())))
+}
+
+/// PUT /apis/{group}/{version}/[namespaces/{namespace}/]{plural}/{name}/status
+pub async fn update_custom_object_status(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<CustomObjectPath>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response> {
+    let served = ServedKind::resolve(&state, &path).await?;
+    if !served.status_subresource() {
+        return Err(ApiError::NotFound(format!(
+            "{} has no status subresource at {}",
+            served.kind(),
+            served.api_version
+        )));
+    }
+    info!(
+        "Updating {} status: {}",
+        served.kind(),
+        served.key(&path)?.name
+    );
+
+    let current: CustomObject = get_resource(&state, &served.key(&path)?).await?;
+    let incoming = served.decode(&path, body)?;
+
+    // The schema checks the status as part of the object it lands in
+    let mut object = current;
+    match incoming.fields.get("status") {
+        Some(status) => object.fields.insert("status".to_string(), status.clone()),
+        None => object.fields.remove("status"),
+    };
+    object.metadata.annotations = incoming.metadata.annotations;
+    served.validate(&mut object)?;
+
+    let updated = update_status(&state, object).await?;
+
+    Ok(served.warn(ApiResponse::ok(served.encode(updated)).into_response()))
+}
+
+/// Routes serving the objects of every kind a CRD defines
+pub(crate) fn custom_object_routes() -> Router<Arc<AppState>> {
+    let mut router = Router::new();
+    for collection in [
+        "/apis/{group}/{version}/namespaces/{namespace}/{plural}",
+        "/apis/{group}/{version}/{plural}",
+    ] {
+        let object = format!("{}/{{name}}", collection);
+        router = router
+            .route(
+                collection,
+                get(list_custom_objects).post(create_custom_object),
+            )
+            .route(
+                &object,
+                get(get_custom_object)
+                    .put(replace_custom_object)
+                    .patch(patch_custom_object)
+                    .delete(delete_custom_object),
+            )
+            .route(
+                &format!("{}/status", object),
+                put(update_custom_object_status),
+            );
+    }
+    router
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request, StatusCode};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use serde_json::{json, Value};
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    fn widget_crd(scope: &str) -> Value {
+        json!({
+            "apiVersion": CRD_API_VERSION,
+            "kind": CRD_KIND,
+            "metadata": {"name": "widgets.example.com"},
+            "spec": {
+                "group": "example.com",
+                "names": {"kind": "Widget", "plural": "widgets"},
+                "scope": scope,
+                "versions": [
+                    {
+                        "name": "v1",
+                        "served": true,
+                        "storage": true,
+                        "schema": {"openAPIV3Schema": {
+                            "type": "object",
+                            "properties": {
+                                "spec": {
+                                    "type": "object",
+                                    "required": ["size"],
+                                    "properties": {"size": {"type": "integer", "minimum": 1}}
+                                },
+                                "status": {
+                                    "type": "object",
+                                    "properties": {"ready": {"type": "boolean"}}
+                                }
+                            }
+                        }},
+                        "subresources": {"status": {}}
+                    },
+                    {
+                        "name": "v1beta1",
+                        "served": true,
+                        "storage": false,
+                        "deprecated": true,
+                        "schema": {"openAPIV3Schema": {
+                            "type": "object",
+                            "x-kubernetes-preserve-unknown-fields": true
+                        }}
+                    }
+                ]
+            }
+        })
+    }
+
+    fn router() -> Router {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        builtin_resources()
+            .into_router()
+            .merge(custom_object_routes())
+            .with_state(state)
+    }
+
+    async fn send(router: &Router, method: Method, uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_custom_objects_served_through_their_definition() {
+        let router = router();
+        let widgets = "/apis/example.com/v1/namespaces/default/widgets";
+        let widget = json!({
+            "apiVersion": "example.com/v1",
+            "kind": "Widget",
+            "metadata": {"name": "small"},
+            "spec": {"size": 2, "color": "red"},
+            "status": {"ready": true}
+        });
+
+        // Nothing is served before the definition exists
+        let (status, _) = send(&router, Method::POST, widgets, widget.clone()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, crd) = send(
+            &router,
+            Method::POST,
+            "/apis/apiextensions.k8s.io/v1/customresourcedefinitions",
+            widget_crd("Namespaced"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(crd["status"]["conditions"][1]["type"], "Established");
+        assert_eq!(crd["status"]["storedVersions"], json!(["v1"]));
+
+        // Unknown fields are pruned and the status is left to its subresource
+        let (status, created) = send(&router, Method::POST, widgets, widget.clone()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["metadata"]["namespace"], "default");
+        assert_eq!(created["spec"], json!({"size": 2}));
+        assert!(created.get("status").is_none());
+
+        let (status, body) = send(
+            &router,
+            Method::POST,
+            widgets,
+            json!({"metadata": {"name": "broken"}, "spec": {"size": 0}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+
+        let (status, updated) = send(
+            &router,
+            Method::PUT,
+            &format!("{}/small/status", widgets),
+            json!({"status": {"ready": true}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["status"]["ready"], true);
+        assert_eq!(updated["spec"]["size"], 2);
+
+        let (status, patched) = send(
+            &router,
+            Method::PATCH,
+            &format!("{}/small", widgets),
+            json!({"spec": {"size": 3}, "status": {"ready": false}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(patched["spec"]["size"], 3);
+        assert_eq!(patched["status"]["ready"], true);
+
+        // Served at every served version, stored at the storage version
+        let (status, beta) = send(
+            &router,
+            Method::GET,
+            "/apis/example.com/v1beta1/namespaces/default/widgets/small",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(beta["apiVersion"], "example.com/v1beta1");
+        let (status, list) = send(
+            &router,
+            Method::GET,
+            "/apis/example.com/v1/widgets",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(list["kind"], "WidgetList");
+        assert_eq!(list["items"][0]["metadata"]["name"], "small");
+
+        // Built-in kinds in a group are still routed to their handlers
+        let (status, _) = send(
+            &router,
+            Method::GET,
+            "/apis/apiextensions.k8s.io/v1/customresourcedefinitions/widgets.example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&router, Method::GET, "/apis/apps/v1/gadgets", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Deleting the definition deletes its objects
+        let (status, _) = send(
+            &router,
+            Method::DELETE,
+            "/apis/apiextensions.k8s.io/v1/customresourcedefinitions/widgets.example.com",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            &router,
+            Method::GET,
+            &format!("{}/small", widgets),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_crd_admission() {
+        let router = router();
+        let crds = "/apis/apiextensions.k8s.io/v1/customresourcedefinitions";
+
+        let mut apps = widget_crd("Cluster");
+        apps["metadata"]["name"] = json!("widgets.apps");
+        apps["spec"]["group"] = json!("apps");
+        let (status, _) = send(&router, Method::POST, crds, apps).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, _) = send(&router, Method::POST, crds, widget_crd("Cluster")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = send(
+            &router,
+            Method::PATCH,
+            &format!("{}/widgets.example.com", crds),
+            json!({"spec": {"scope": "Namespaced"}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Cluster-scoped kinds are served without a namespace only
+        let (status, _) = send(
+            &router,
+            Method::POST,
+            "/apis/example.com/v1/namespaces/default/widgets",
+            json!({"metadata": {"name": "big"}, "spec": {"size": 9}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, created) = send(
+            &router,
+            Method::POST,
+            "/apis/example.com/v1/widgets",
+            json!({"metadata": {"name": "big"}, "spec": {"size": 9}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created["metadata"].get("namespace").is_none());
+    }
+}
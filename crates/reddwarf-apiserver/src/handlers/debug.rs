@@ -0,0 +1,327 @@
+use crate::auth::{self, Bearer};
+use crate::debug::ZoneDebug;
+use crate::history_squasher::squash;
+use crate::response::{status_success, ApiResponse};
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+/// Decisions returned when a query does not say
+const DEFAULT_DECISION_LIMIT: usize = 100;
+
+/// Query parameters of `/debug/decisions`
+#[derive(Debug, Default, Deserialize)]
+pub struct DecisionQuery {
+    pub namespace: Option<String>,
+    pub pod: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Commits of each resource a squash keeps when a query does not say
+const DEFAULT_SQUASH_KEEP_LAST: usize = 100;
+
+/// Query parameters of `/debug/history/squash`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SquashQuery {
+    /// Key of the resource whose history is squashed, e.g.
+    /// `v1/Pod/default/web`; every resource's when absent
+    pub resource_key: Option<String>,
+    pub keep_last: Option<usize>,
+}
+
+/// Resolve the debug configuration and check the caller's bearer token
+fn authorize<'a>(state: &'a AppState, bearer: &Bearer) -> Result<&'a ZoneDebug> {
+    let debug = state
+        .zone_debug
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Debug API is not enabled".to_string()))?;
+    auth::authorize(std::slice::from_ref(&debug.token), bearer, "Debug API")?;
+    Ok(debug)
+}
+
+/// GET /debug/zones
+pub async fn list_debug_zones(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+) -> Result<Response> {
+    let debug = authorize(&state, &bearer)?;
+
+    let zones = debug.backend.list_zones().await?;
+
+    Ok(ApiResponse::ok(json!({ "items": zones })).into_response())
+}
+
+/// GET /debug/zones/{name}
+pub async fn get_debug_zone(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+    Path(name): Path<String>,
+) -> Result<Response> {
+    let debug = authorize(&state, &bearer)?;
+
+    let zone = debug.backend.get_zone(&name).await?;
+
+    Ok(ApiResponse::ok(zone).into_response())
+}
+
+/// POST /debug/zones/{name}/halt
+pub async fn halt_debug_zone(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+    Path(name): Path<String>,
+) -> Result<Response> {
+    let debug = authorize(&state, &bearer)?;
+
+    info!("Force halting zone via debug API: {}", name);
+    debug.backend.halt_zone(&name).await?;
+
+    Ok(status_success(&format!("Zone {} halted", name)))
+}
+
+/// GET /debug/decisions
+pub async fn list_debug_decisions(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+    Query(query): Query<DecisionQuery>,
+) -> Result<Response> {
+    let debug = authorize(&state, &bearer)?;
+
+    let decisions = debug
+        .backend
+        .list_decisions(
+            query.namespace.as_deref(),
+            query.pod.as_deref(),
+            query.limit.unwrap_or(DEFAULT_DECISION_LIMIT),
+        )
+        .await?;
+
+    Ok(ApiResponse::ok(json!({ "items": decisions })).into_response())
+}
+
+/// POST /debug/history/squash
+pub async fn squash_debug_history(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+    Query(query): Query<SquashQuery>,
+) -> Result<Response> {
+    authorize(&state, &bearer)?;
+
+    let keep_last = query.keep_last.unwrap_or(DEFAULT_SQUASH_KEEP_LAST);
+    info!(
+        "Squashing history via debug API: {} (keeping the last {})",
+        query.resource_key.as_deref().unwrap_or("all resources"),
+        keep_last
+    );
+    let report = squash(&state, query.resource_key, keep_last).await?;
+
+    Ok(ApiResponse::ok(json!({ "runs": report.runs, "removed": report.removed })).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::ZoneDebugBackend;
+    use async_trait::async_trait;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::{Change, CommitBuilder, VersionStore};
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct FakeBackend {
+        halted: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ZoneDebugBackend for FakeBackend {
+        async fn list_zones(&self) -> Result<Vec<serde_json::Value>> {
+            Ok(vec![
+                json!({ "zone_name": "reddwarf-default-web", "state": "Running" }),
+            ])
+        }
+
+        async fn get_zone(&self, zone_name: &str) -> Result<serde_json::Value> {
+            if zone_name == "reddwarf-default-web" {
+                Ok(json!({ "zone_name": zone_name, "state": "Running" }))
+            } else {
+                Err(ApiError::NotFound(format!("Zone {} not found", zone_name)))
+            }
+        }
+
+        async fn halt_zone(&self, zone_name: &str) -> Result<()> {
+            self.halted.lock().unwrap().push(zone_name.to_string());
+            Ok(())
+        }
+
+        async fn list_decisions(
+            &self,
+            namespace: Option<&str>,
+            pod: Option<&str>,
+            limit: usize,
+        ) -> Result<Vec<serde_json::Value>> {
+            let decisions = [
+                json!({ "namespace": "default", "pod": "web", "action": "Provision" }),
+                json!({ "namespace": "default", "pod": "db", "action": "Terminate" }),
+            ];
+            Ok(decisions
+                .into_iter()
+                .filter(|d| namespace.is_none_or(|ns| d["namespace"] == ns))
+                .filter(|d| pod.is_none_or(|name| d["pod"] == name))
+                .take(limit)
+                .collect())
+        }
+    }
+
+    fn setup_state(backend: Option<Arc<FakeBackend>>) -> Arc<AppState> {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+
+        let mut state = AppState::new(storage, version_store);
+        if let Some(backend) = backend {
+            state = state.with_zone_debug(ZoneDebug::new(backend, "s3cret"));
+        }
+        Arc::new(state)
+    }
+
+    fn bearer(token: &str) -> Bearer {
+        Bearer::new(token)
+    }
+
+    #[tokio::test]
+    async fn test_debug_api_disabled_returns_not_found() {
+        let state = setup_state(None);
+
+        let result = list_debug_zones(State(state), bearer("s3cret")).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_debug_api_rejects_bad_token() {
+        let state = setup_state(Some(Arc::new(FakeBackend::default())));
+
+        let result = list_debug_zones(State(state.clone()), bearer("wrong")).await;
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+
+        let result = list_debug_zones(State(state), Bearer::default()).await;
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_debug_api_list_get_and_halt() {
+        let backend = Arc::new(FakeBackend::default());
+        let state = setup_state(Some(backend.clone()));
+
+        let resp = list_debug_zones(State(state.clone()), bearer("s3cret"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+        let result = get_debug_zone(
+            State(state.clone()),
+            bearer("s3cret"),
+            Path("missing".to_string()),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+
+        halt_debug_zone(
+            State(state),
+            bearer("s3cret"),
+            Path("reddwarf-default-web".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            *backend.halted.lock().unwrap(),
+            vec!["reddwarf-default-web".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_debug_api_lists_decisions() {
+        let state = setup_state(Some(Arc::new(FakeBackend::default())));
+
+        let result = list_debug_decisions(
+            State(state.clone()),
+            bearer("wrong"),
+            Query(DecisionQuery::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+
+        let resp = list_debug_decisions(
+            State(state),
+            bearer("s3cret"),
+            Query(DecisionQuery {
+                pod: Some("db".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["action"], "Terminate");
+    }
+
+    #[tokio::test]
+    async fn test_debug_api_squashes_history() {
+        let state = setup_state(Some(Arc::new(FakeBackend::default())));
+        let key = "v1/Pod/default/web";
+        let mut content = "{}".to_string();
+        state
+            .version_store
+            .create_commit(
+                CommitBuilder::new().change(Change::create(key.to_string(), content.clone())),
+            )
+            .unwrap();
+        for n in 0..5 {
+            let next = json!({ "n": n }).to_string();
+            state
+                .version_store
+                .create_commit(CommitBuilder::new().change(Change::update(
+                    key.to_string(),
+                    next.clone(),
+                    content,
+                )))
+                .unwrap();
+            content = next;
+        }
+
+        let result = squash_debug_history(
+            State(state.clone()),
+            bearer("wrong"),
+            Query(SquashQuery::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+
+        let resp = squash_debug_history(
+            State(state.clone()),
+            bearer("s3cret"),
+            Query(SquashQuery {
+                resource_key: Some(key.to_string()),
+                keep_last: Some(1),
+            }),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        // The creation and last update are kept, the other four squashed
+        assert_eq!(body["removed"], 3);
+        assert_eq!(state.version_store.list_commits().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::handlers::generic::ResourceKind;
+use crate::{ApiError, AppState, Result};
+use async_trait::async_trait;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use reddwarf_core::resources::{
+    default_daemon_set, default_deployment, APPS_API_VERSION, DAEMON_SET_KIND, DEPLOYMENT_KIND,
+    REPLICA_SET_KIND,
+};
+use reddwarf_core::{DaemonSet, Deployment, ReplicaSet};
+
+/// Refuse changing the selector of a workload, which would orphan its pods
+fn validate_selector_unchanged(current: &LabelSelector, selector: &LabelSelector) -> Result<()> {
+    if current != selector {
+        return Err(ApiError::ValidationFailed(
+            "spec.selector: field is immutable".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ResourceKind for Deployment {
+    const API_VERSION: &'static str = APPS_API_VERSION;
+    const KIND: &'static str = DEPLOYMENT_KIND;
+    const PLURAL: &'static str = "deployments";
+    const SHORT_NAMES: &'static [&'static str] = &["deploy"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    fn validate_update(current: &Deployment, deployment: &Deployment) -> Result<()> {
+        match (&current.spec, &deployment.spec) {
+            (Some(current), Some(spec)) => {
+                validate_selector_unchanged(&current.selector, &spec.selector)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn admit(_state: &AppState, deployment: &mut Deployment) -> Result<()> {
+        default_deployment(deployment);
+        Ok(())
+    }
+}
+
+impl ResourceKind for ReplicaSet {
+    const API_VERSION: &'static str = APPS_API_VERSION;
+    const KIND: &'static str = REPLICA_SET_KIND;
+    const PLURAL: &'static str = "replicasets";
+    const SHORT_NAMES: &'static [&'static str] = &["rs"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    fn validate_update(current: &ReplicaSet, replica_set: &ReplicaSet) -> Result<()> {
+        match (&current.spec, &replica_set.spec) {
+            (Some(current), Some(spec)) => {
+                validate_selector_unchanged(&current.selector, &spec.selector)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceKind for DaemonSet {
+    const API_VERSION: &'static str = APPS_API_VERSION;
+    const KIND: &'static str = DAEMON_SET_KIND;
+    const PLURAL: &'static str = "daemonsets";
+    const SHORT_NAMES: &'static [&'static str] = &["ds"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    fn validate_update(current: &DaemonSet, daemon_set: &DaemonSet) -> Result<()> {
+        match (&current.spec, &daemon_set.spec) {
+            (Some(current), Some(spec)) => {
+                validate_selector_unchanged(&current.selector, &spec.selector)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn admit(_state: &AppState, daemon_set: &mut DaemonSet) -> Result<()> {
+        default_daemon_set(daemon_set);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::{get_resource, ListPath};
+    use crate::handlers::generic::{ObjectPath, ResourceHandlers};
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use reddwarf_core::{GroupVersionKind, ResourceKey};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_create_deployment_defaults_and_immutable_selector() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+
+        let deployment: Deployment = serde_json::from_value(serde_json::json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": "web"},
+            "spec": {
+                "selector": {"matchLabels": {"app": "web"}},
+                "template": {
+                    "metadata": {"labels": {"app": "web"}},
+                    "spec": {"containers": [{"name": "web", "image": "nginx"}]}
+                }
+            }
+        }))
+        .unwrap();
+        ResourceHandlers::<Deployment>::create(
+            State(state.clone()),
+            Path(ListPath {
+                namespace: Some("default".to_string()),
+            }),
+            Json(deployment),
+        )
+        .await
+        .unwrap();
+
+        let key = ResourceKey::new(
+            GroupVersionKind::from_api_version_kind(APPS_API_VERSION, DEPLOYMENT_KIND),
+            "default",
+            "web",
+        );
+        let stored: Deployment = get_resource(&state, &key).await.unwrap();
+        let spec = stored.spec.unwrap();
+        assert_eq!(spec.replicas, Some(1));
+        assert_eq!(
+            spec.strategy.unwrap().type_.as_deref(),
+            Some("RollingUpdate")
+        );
+
+        let patched = ResourceHandlers::<Deployment>::patch(
+            State(state.clone()),
+            Path(ObjectPath {
+                namespace: Some("default".to_string()),
+                name: "web".to_string(),
+            }),
+            Json(serde_json::json!({
+                "spec": {
+                    "selector": {"matchLabels": {"app": "api"}},
+                    "template": {"metadata": {"labels": {"app": "api"}}}
+                }
+            })),
+        )
+        .await;
+        assert!(matches!(patched, Err(ApiError::ValidationFailed(_))));
+    }
+}
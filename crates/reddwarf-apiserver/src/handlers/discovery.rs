@@ -0,0 +1,444 @@
+//! API discovery: `/api`, `/apis`, the resource list of each group version,
+//! `/version` and `/openapi/v3`, built from the kinds in the
+//! [`ResourceRegistry`] so kubectl and other clients can find what is served.
+//! `/apis` and the resource lists also cover the kinds defined by stored
+//! CustomResourceDefinitions, which have no OpenAPI documents.
+//!
+//! [`ResourceRegistry`]: crate::handlers::generic::ResourceRegistry
+
+use crate::handlers::custom_resources::custom_resource_definitions;
+use crate::handlers::generic::RegisteredKind;
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::{
+    APIGroup, APIGroupList, APIResource, APIResourceList, APIVersions, GroupVersionForDiscovery,
+    ServerAddressByClientCIDR,
+};
+use reddwarf_core::k8s_openapi::apimachinery::pkg::version::Info;
+use reddwarf_core::resources::{crd_is_namespaced, has_status_subresource, storage_version};
+use reddwarf_core::CustomResourceDefinition;
+use serde_json::json;
+use std::sync::Arc;
+
+/// Verbs of every kind served through the generic handlers
+const OBJECT_VERBS: &[&str] = &[
+    "create", "delete", "get", "list", "patch", "update", "watch",
+];
+
+/// Kubernetes version the API is modelled on, reported by `/version`
+const KUBERNETES_MAJOR: &str = "1";
+const KUBERNETES_MINOR: &str = "31";
+
+/// Split an API version into its group ("" for the core group) and version
+fn split_api_version(api_version: &str) -> (&str, &str) {
+    api_version.split_once('/').unwrap_or(("", api_version))
+}
+
+/// Resources of `group_version`, with their status subresources
+fn resource_list(kinds: &[RegisteredKind], group_version: &str) -> Option<APIResourceList> {
+    let mut resources = Vec::new();
+    for kind in kinds.iter().filter(|k| k.api_version == group_version) {
+        resources.push(APIResource {
+            name: kind.plural.to_string(),
+            singular_name: kind.kind.to_lowercase(),
+            namespaced: kind.namespaced,
+            kind: kind.kind.to_string(),
+            verbs: OBJECT_VERBS.iter().map(|v| v.to_string()).collect(),
+            short_names: (!kind.short_names.is_empty())
+                .then(|| kind.short_names.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        });
+        if kind.status_subresource {
+            resources.push(APIResource {
+                name: format!("{}/status", kind.plural),
+                singular_name: String::new(),
+                namespaced: kind.namespaced,
+                kind: kind.kind.to_string(),
+                verbs: vec!["patch".to_string(), "update".to_string()],
+                ..Default::default()
+            });
+        }
+    }
+    if group_version == "v1" {
+        // Served by their own handlers, from the event store
+        resources.push(APIResource {
+            name: "events".to_string(),
+            singular_name: "event".to_string(),
+            namespaced: true,
+            kind: "Event".to_string(),
+            verbs: ["create", "get", "list"].map(String::from).to_vec(),
+            short_names: Some(vec!["ev".to_string()]),
+            ..Default::default()
+        });
+        // Served from the component registry
+        resources.push(APIResource {
+            name: "componentstatuses".to_string(),
+            singular_name: "componentstatus".to_string(),
+            namespaced: false,
+            kind: "ComponentStatus".to_string(),
+            verbs: ["get", "list"].map(String::from).to_vec(),
+            short_names: Some(vec!["cs".to_string()]),
+            ..Default::default()
+        });
+    }
+    (!resources.is_empty()).then(|| APIResourceList {
+        group_version: group_version.to_string(),
+        resources,
+    })
+}
+
+/// Named API groups, each with the versions its kinds are served at
+fn group_list(kinds: &[RegisteredKind]) -> APIGroupList {
+    let mut groups: Vec<APIGroup> = Vec::new();
+    for kind in kinds {
+        let (group, version) = split_api_version(kind.api_version);
+        if group.is_empty() {
+            continue;
+        }
+        let discovered = GroupVersionForDiscovery {
+            group_version: kind.api_version.to_string(),
+            version: version.to_string(),
+        };
+        match groups.iter_mut().find(|g| g.name == group) {
+            Some(existing) if !existing.versions.contains(&discovered) => {
+                existing.versions.push(discovered)
+            }
+            Some(_) => {}
+            None => groups.push(APIGroup {
+                name: group.to_string(),
+                preferred_version: Some(discovered.clone()),
+                versions: vec![discovered],
+                server_address_by_client_cidrs: None,
+            }),
+        }
+    }
+    APIGroupList { groups }
+}
+
+/// Add the resources of the kinds `crds` serve at `group_version` to `list`
+fn with_custom_resources(
+    list: Option<APIResourceList>,
+    crds: &[CustomResourceDefinition],
+    group_version: &str,
+) -> Option<APIResourceList> {
+    let mut resources = list.map(|l| l.resources).unwrap_or_default();
+    for crd in crds {
+        let names = &crd.spec.names;
+        let served = crd
+            .spec
+            .versions
+            .iter()
+            .find(|v| v.served && format!("{}/{}", crd.spec.group, v.name) == group_version);
+        let Some(version) = served else {
+            continue;
+        };
+        resources.push(APIResource {
+            name: names.plural.clone(),
+            singular_name: names
+                .singular
+                .clone()
+                .unwrap_or_else(|| names.kind.to_lowercase()),
+            namespaced: crd_is_namespaced(crd),
+            kind: names.kind.clone(),
+            verbs: OBJECT_VERBS.iter().map(|v| v.to_string()).collect(),
+            short_names: names.short_names.clone(),
+            categories: names.categories.clone(),
+            ..Default::default()
+        });
+        if has_status_subresource(version) {
+            resources.push(APIResource {
+                name: format!("{}/status", names.plural),
+                singular_name: String::new(),
+                namespaced: crd_is_namespaced(crd),
+                kind: names.kind.clone(),
+                verbs: vec!["update".to_string()],
+                ..Default::default()
+            });
+        }
+    }
+    (!resources.is_empty()).then(|| APIResourceList {
+        group_version: group_version.to_string(),
+        resources,
+    })
+}
+
+/// Add the groups of `crds` to `groups`, each preferring its storage version
+fn add_custom_groups(groups: &mut APIGroupList, crds: &[CustomResourceDefinition]) {
+    for crd in crds {
+        let discovered = |version: &str| GroupVersionForDiscovery {
+            group_version: format!("{}/{}", crd.spec.group, version),
+            version: version.to_string(),
+        };
+        let versions: Vec<GroupVersionForDiscovery> = crd
+            .spec
+            .versions
+            .iter()
+            .filter(|v| v.served)
+            .map(|v| discovered(&v.name))
+            .collect();
+        match groups.groups.iter_mut().find(|g| g.name == crd.spec.group) {
+            Some(existing) => {
+                for version in versions {
+                    if !existing.versions.contains(&version) {
+                        existing.versions.push(version);
+                    }
+                }
+            }
+            None if !versions.is_empty() => groups.groups.push(APIGroup {
+                name: crd.spec.group.clone(),
+                preferred_version: storage_version(crd)
+                    .filter(|v| v.served)
+                    .map(|v| discovered(&v.name))
+                    .or_else(|| versions.first().cloned()),
+                versions,
+                server_address_by_client_cidrs: None,
+            }),
+            None => {}
+        }
+    }
+}
+
+/// Routes answering discovery requests for `kinds`
+pub(crate) fn discovery_routes(kinds: Arc<[RegisteredKind]>) -> Router<Arc<AppState>> {
+    let core_kinds = kinds.clone();
+    let group_kinds = kinds.clone();
+    let apis_kinds = kinds.clone();
+    let index_kinds = kinds.clone();
+    Router::new()
+        .route("/version", get(version))
+        .route(
+            "/api",
+            get(|| async {
+                Json(APIVersions {
+                    versions: vec!["v1".to_string()],
+                    server_address_by_client_cidrs: vec![ServerAddressByClientCIDR {
+                        client_cidr: "0.0.0.0/0".to_string(),
+                        server_address: String::new(),
+                    }],
+                })
+            }),
+        )
+        .route(
+            "/api/{version}",
+            get(move |Path(version): Path<String>| async move {
+                served(resource_list(&core_kinds, &version), &version)
+            }),
+        )
+        .route(
+            "/apis",
+            get(move |State(state): State<Arc<AppState>>| async move {
+                let mut groups = group_list(&apis_kinds);
+                add_custom_groups(&mut groups, &custom_resource_definitions(&state).await?);
+                Ok::<_, ApiError>(Json(groups))
+            }),
+        )
+        .route(
+            "/openapi/v3",
+            get(move || async move { Json(openapi_index(&index_kinds)) }),
+        )
+        .route(
+            "/openapi/v3/{*path}",
+            get(move |Path(path): Path<String>| async move {
+                openapi_document(&kinds, &path)
+                    .map(Json)
+                    .ok_or_else(|| ApiError::NotFound(format!("No OpenAPI document for {}", path)))
+            }),
+        )
+        .route(
+            "/apis/{group}/{version}",
+            get(
+                move |State(state): State<Arc<AppState>>,
+                      Path((group, version)): Path<(String, String)>| async move {
+                    let group_version = format!("{}/{}", group, version);
+                    let crds = custom_resource_definitions(&state).await?;
+                    let list = resource_list(&group_kinds, &group_version);
+                    served(
+                        with_custom_resources(list, &crds, &group_version),
+                        &group_version,
+                    )
+                },
+            ),
+        )
+}
+
+/// Path of the OpenAPI v3 document of a group version, relative to
+/// `/openapi/v3/`: `api/v1` or `apis/{group}/{version}`
+fn openapi_path(api_version: &str) -> String {
+    match split_api_version(api_version) {
+        ("", version) => format!("api/{}", version),
+        _ => format!("apis/{}", api_version),
+    }
+}
+
+/// GET /openapi/v3: the group versions with a document
+fn openapi_index(kinds: &[RegisteredKind]) -> serde_json::Value {
+    let paths: serde_json::Map<String, serde_json::Value> = kinds
+        .iter()
+        .map(|kind| {
+            let path = openapi_path(kind.api_version);
+            let url = format!("/openapi/v3/{}", path);
+            (path, json!({ "serverRelativeURL": url }))
+        })
+        .collect();
+    json!({ "paths": paths })
+}
+
+/// OpenAPI v3 document of a group version. It carries no schemas, only the
+/// object operations with their group-version-kind and the `fieldValidation`
+/// parameter, which tells kubectl to leave validation to the server instead
+/// of validating against a schema itself.
+fn openapi_document(kinds: &[RegisteredKind], path: &str) -> Option<serde_json::Value> {
+    let served: Vec<&RegisteredKind> = kinds
+        .iter()
+        .filter(|k| openapi_path(k.api_version) == path)
+        .collect();
+    let api_version = served.first()?.api_version;
+
+    let mut paths = serde_json::Map::new();
+    for kind in served {
+        let (group, version) = split_api_version(kind.api_version);
+        let object_path = if kind.namespaced {
+            format!(
+                "/{}/namespaces/{{namespace}}/{}/{{name}}",
+                path, kind.plural
+            )
+        } else {
+            format!("/{}/{}/{{name}}", path, kind.plural)
+        };
+        let operation = json!({
+            "x-kubernetes-group-version-kind": {
+                "group": group,
+                "version": version,
+                "kind": kind.kind,
+            },
+            "parameters": [
+                { "name": "fieldValidation", "in": "query", "schema": { "type": "string" } },
+            ],
+        });
+        paths.insert(
+            object_path,
+            json!({ "get": operation, "put": operation, "patch": operation }),
+        );
+    }
+
+    Some(json!({
+        "openapi": "3.0.0",
+        "info": { "title": "Kubernetes", "version": api_version },
+        "paths": paths,
+    }))
+}
+
+fn served(list: Option<APIResourceList>, group_version: &str) -> Result<Json<APIResourceList>> {
+    list.map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("API version {} is not served", group_version)))
+}
+
+/// GET /version
+async fn version() -> Json<Info> {
+    Json(Info {
+        major: KUBERNETES_MAJOR.to_string(),
+        minor: KUBERNETES_MINOR.to_string(),
+        git_version: format!(
+            "v{}.{}.0-reddwarf.{}",
+            KUBERNETES_MAJOR,
+            KUBERNETES_MINOR,
+            env!("CARGO_PKG_VERSION")
+        ),
+        platform: format!("{}/{}", std::env::consts::OS, std::env::consts::ARCH),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::builtin_resources;
+
+    #[test]
+    fn test_discovery_documents() {
+        let registry = builtin_resources();
+        let kinds = registry.kinds();
+
+        let core = resource_list(kinds, "v1").unwrap();
+        let pods = core.resources.iter().find(|r| r.name == "pods").unwrap();
+        assert!(pods.namespaced);
+        assert_eq!(pods.kind, "Pod");
+        assert!(pods.verbs.contains(&"watch".to_string()));
+        assert_eq!(pods.short_names, Some(vec!["po".to_string()]));
+        assert!(core.resources.iter().any(|r| r.name == "pods/status"));
+        assert!(!core.resources.iter().any(|r| r.name == "services/status"));
+
+        let groups = group_list(kinds);
+        let node = groups
+            .groups
+            .iter()
+            .find(|g| g.name == "node.k8s.io")
+            .unwrap();
+        assert_eq!(node.versions[0].group_version, "node.k8s.io/v1");
+        assert!(groups.groups.iter().all(|g| !g.name.is_empty()));
+
+        assert!(resource_list(kinds, "v2").is_none());
+
+        let index = openapi_index(kinds);
+        assert_eq!(
+            index["paths"]["apis/node.k8s.io/v1"]["serverRelativeURL"],
+            "/openapi/v3/apis/node.k8s.io/v1"
+        );
+        let document = openapi_document(kinds, "api/v1").unwrap();
+        let patch = &document["paths"]["/api/v1/namespaces/{namespace}/pods/{name}"]["patch"];
+        assert_eq!(patch["x-kubernetes-group-version-kind"]["kind"], "Pod");
+        assert_eq!(patch["parameters"][0]["name"], "fieldValidation");
+        assert!(document["paths"]["/api/v1/nodes/{name}"].is_object());
+        let apps = openapi_document(kinds, "apis/apps/v1").unwrap();
+        assert!(
+            apps["paths"]["/apis/apps/v1/namespaces/{namespace}/deployments/{name}"].is_object()
+        );
+        assert!(
+            apps["paths"]["/apis/apps/v1/namespaces/{namespace}/daemonsets/{name}"].is_object()
+        );
+        assert!(openapi_document(kinds, "apis/batch/v1").is_none());
+    }
+
+    #[test]
+    fn test_discovery_of_custom_resources() {
+        let crd: CustomResourceDefinition = serde_json::from_value(json!({
+            "metadata": {"name": "widgets.example.com"},
+            "spec": {
+                "group": "example.com",
+                "names": {"kind": "Widget", "plural": "widgets", "shortNames": ["wd"]},
+                "scope": "Cluster",
+                "versions": [
+                    {"name": "v1beta1", "served": true, "storage": false},
+                    {"name": "v1", "served": true, "storage": true, "subresources": {"status": {}}},
+                    {"name": "v1alpha1", "served": false, "storage": false}
+                ]
+            }
+        }))
+        .unwrap();
+        let crds = [crd];
+
+        let mut groups = group_list(builtin_resources().kinds());
+        add_custom_groups(&mut groups, &crds);
+        let group = groups
+            .groups
+            .iter()
+            .find(|g| g.name == "example.com")
+            .unwrap();
+        assert_eq!(group.versions.len(), 2);
+        assert_eq!(
+            group.preferred_version.as_ref().unwrap().group_version,
+            "example.com/v1"
+        );
+
+        let list = with_custom_resources(None, &crds, "example.com/v1").unwrap();
+        assert_eq!(list.resources[0].name, "widgets");
+        assert_eq!(list.resources[0].singular_name, "widget");
+        assert!(!list.resources[0].namespaced);
+        assert_eq!(list.resources[1].name, "widgets/status");
+        let beta = with_custom_resources(None, &crds, "example.com/v1beta1").unwrap();
+        assert_eq!(beta.resources.len(), 1);
+        assert!(with_custom_resources(None, &crds, "example.com/v1alpha1").is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::handlers::common::list_resources;
+use crate::handlers::generic::ResourceKind;
+use crate::handlers::usage::namespace_pods;
+use crate::{ApiError, AppState, Result};
+use reddwarf_core::resources::{
+    budget_selects, disruption_budget_status, eviction_allowed, is_healthy,
+    POD_DISRUPTION_BUDGET_KIND, POLICY_API_VERSION,
+};
+use reddwarf_core::{Pod, PodDisruptionBudget};
+use reddwarf_storage::KeyEncoder;
+
+impl ResourceKind for PodDisruptionBudget {
+    const API_VERSION: &'static str = POLICY_API_VERSION;
+    const KIND: &'static str = POD_DISRUPTION_BUDGET_KIND;
+    const PLURAL: &'static str = "poddisruptionbudgets";
+    const SHORT_NAMES: &'static [&'static str] = &["pdb"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+}
+
+/// Refuse evicting `pod` when a disruption budget covering it would drop
+/// below the healthy pods it requires
+///
+/// Budgets are checked against the pods as stored, not against their
+/// status, so evictions made in quick succession each see the ones before.
+pub(crate) async fn check_disruption_budgets(state: &AppState, pod: &Pod) -> Result<()> {
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+    let prefix = KeyEncoder::encode_prefix(
+        POLICY_API_VERSION,
+        POD_DISRUPTION_BUDGET_KIND,
+        Some(namespace),
+    );
+    let budgets: Vec<PodDisruptionBudget> = list_resources(state, &prefix).await?;
+    let budgets: Vec<&PodDisruptionBudget> =
+        budgets.iter().filter(|b| budget_selects(b, pod)).collect();
+    if budgets.is_empty() {
+        return Ok(());
+    }
+
+    let pods = namespace_pods(state, namespace)?;
+    let healthy = is_healthy(pod);
+    for budget in budgets {
+        let selected: Vec<&Pod> = pods.iter().filter(|p| budget_selects(budget, p)).collect();
+        let status = disruption_budget_status(budget, &selected)
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+        if !eviction_allowed(budget, &status, healthy) {
+            return Err(ApiError::TooManyRequests(format!(
+                "Cannot evict pod as it would violate the pod's disruption budget {}: \
+                 it needs {} healthy pods and has {}",
+                budget.metadata.name.as_deref().unwrap_or_default(),
+                status.desired_healthy,
+                status.current_healthy
+            )));
+        }
+    }
+    Ok(())
+}
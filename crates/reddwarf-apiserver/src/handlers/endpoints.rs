@@ -0,0 +1,18 @@
+use crate::handlers::generic::ResourceKind;
+use reddwarf_core::resources::{DISCOVERY_API_VERSION, ENDPOINTS_KIND, ENDPOINT_SLICE_KIND};
+use reddwarf_core::{EndpointSlice, Endpoints};
+
+impl ResourceKind for Endpoints {
+    const API_VERSION: &'static str = "v1";
+    const KIND: &'static str = ENDPOINTS_KIND;
+    const PLURAL: &'static str = "endpoints";
+    const SHORT_NAMES: &'static [&'static str] = &["ep"];
+    const NAMESPACED: bool = true;
+}
+
+impl ResourceKind for EndpointSlice {
+    const API_VERSION: &'static str = DISCOVERY_API_VERSION;
+    const KIND: &'static str = ENDPOINT_SLICE_KIND;
+    const PLURAL: &'static str = "endpointslices";
+    const NAMESPACED: bool = true;
+}
@@ -0,0 +1,229 @@
+//! `v1` Events, served from the event store rather than the generic
+//! resource handlers: events live outside the commit history, so they can
+//! be recorded, listed and read but not watched, updated or deleted.
+//! Expired events are removed by the [`EventSweeper`].
+//!
+//! [`EventSweeper`]: crate::event_sweeper::EventSweeper
+
+use crate::field_selector::FieldSelector;
+use crate::handlers::common::{list_resource_version, select_items, ListPath, ListResponse};
+use crate::response::ApiResponse;
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::Utc;
+use reddwarf_core::recorder::{EVENT_NORMAL, EVENT_WARNING};
+use reddwarf_core::Event;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Query parameters of an event list
+#[derive(Debug, Default, Deserialize)]
+pub struct EventListParams {
+    #[serde(default)]
+    pub watch: Option<String>,
+    /// Comma-separated `field=value` terms on the involved object, the type
+    /// and the reason, as `kubectl describe` sends to find an object's events
+    #[serde(rename = "fieldSelector", default)]
+    pub field_selector: Option<String>,
+}
+
+/// Fields besides the name and namespace events can be selected by
+const EVENT_FIELDS: &[&str] = &[
+    "involvedObject.kind",
+    "involvedObject.namespace",
+    "involvedObject.name",
+    "involvedObject.uid",
+    "involvedObject.fieldPath",
+    "type",
+    "reason",
+];
+
+/// GET /api/v1/namespaces/{namespace}/events
+/// GET /api/v1/events
+pub async fn list_events(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<ListPath>,
+    Query(params): Query<EventListParams>,
+) -> Result<Response> {
+    if params
+        .watch
+        .as_deref()
+        .is_some_and(|v| v == "true" || v == "1")
+    {
+        return Err(ApiError::MethodNotAllowed(
+            "events cannot be watched".to_string(),
+        ));
+    }
+
+    let selector = FieldSelector::parse(params.field_selector.as_deref(), "events", EVENT_FIELDS)?;
+    let resource_version = list_resource_version(&state);
+    let items = select_items(state.events.list(path.namespace.as_deref())?, &selector)?;
+
+    let response = ListResponse::new(
+        "v1".to_string(),
+        "EventList".to_string(),
+        items,
+        resource_version,
+    );
+    Ok(ApiResponse::ok(response).into_response())
+}
+
+/// GET /api/v1/namespaces/{namespace}/events/{name}
+pub async fn get_event(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<Response> {
+    let event = state
+        .events
+        .get(&namespace, &name)?
+        .ok_or_else(|| ApiError::NotFound(format!("Event {}/{} not found", namespace, name)))?;
+    Ok(ApiResponse::ok(event).into_response())
+}
+
+/// POST /api/v1/namespaces/{namespace}/events
+///
+/// A repeat of a recorded event is folded into it, so the response is the
+/// event as stored, with its count
+pub async fn create_event(
+    State(state): State<Arc<AppState>>,
+    Path(namespace): Path<String>,
+    Json(mut event): Json<Event>,
+) -> Result<Response> {
+    if event.involved_object.name.is_none() {
+        return Err(ApiError::ValidationFailed(
+            "involvedObject.name: Required value".to_string(),
+        ));
+    }
+    if event.reason.as_deref().unwrap_or_default().is_empty() {
+        return Err(ApiError::ValidationFailed(
+            "reason: Required value".to_string(),
+        ));
+    }
+    match event.type_.as_deref() {
+        None | Some(EVENT_NORMAL) | Some(EVENT_WARNING) => {}
+        Some(other) => {
+            return Err(ApiError::ValidationFailed(format!(
+                "type '{}' must be {} or {}",
+                other, EVENT_NORMAL, EVENT_WARNING
+            )))
+        }
+    }
+
+    // The URL's namespace wins over the body's
+    event.metadata.namespace = Some(namespace);
+    let stored = state.events.record(event, Utc::now())?;
+    debug!(
+        "Recorded event {}/{} (count {})",
+        stored.metadata.namespace.as_deref().unwrap_or_default(),
+        stored.metadata.name.as_deref().unwrap_or_default(),
+        stored.count.unwrap_or(1)
+    );
+    Ok(ApiResponse::created(stored).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_record_and_select_events() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let router = Router::new()
+            .route(
+                "/api/v1/namespaces/{namespace}/events",
+                get(list_events).post(create_event),
+            )
+            .route(
+                "/api/v1/namespaces/{namespace}/events/{name}",
+                get(get_event),
+            )
+            .with_state(state);
+
+        let send = |method: Method, uri: &str, body: serde_json::Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice(&body).unwrap_or_default())
+            }
+        };
+        let event = |pod: &str, reason: &str| {
+            serde_json::json!({
+                "involvedObject": {"kind": "Pod", "namespace": "default", "name": pod},
+                "type": "Warning",
+                "reason": reason,
+                "message": "Liveness probe failed",
+                "source": {"component": "reddwarf-agent", "host": "node1"}
+            })
+        };
+        let uri = "/api/v1/namespaces/default/events";
+
+        let (status, created): (_, serde_json::Value) =
+            send(Method::POST, uri, event("web", "ProbeFailed")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["count"], 1);
+        let name = created["metadata"]["name"].as_str().unwrap().to_string();
+
+        // A repeat is folded into the first event
+        let (_, repeated): (_, serde_json::Value) =
+            send(Method::POST, uri, event("web", "ProbeFailed")).await;
+        assert_eq!(repeated["metadata"]["name"], name.as_str());
+        assert_eq!(repeated["count"], 2);
+
+        send(Method::POST, uri, event("db", "ProbeFailed")).await;
+        let (status, _): (_, serde_json::Value) = send(Method::POST, uri, event("db", "")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, fetched): (_, serde_json::Value) = send(
+            Method::GET,
+            &format!("{}/{}", uri, name),
+            serde_json::json!({}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["involvedObject"]["name"], "web");
+
+        let (_, list): (_, serde_json::Value) = send(
+            Method::GET,
+            &format!(
+                "{}?fieldSelector=involvedObject.kind=Pod,involvedObject.name=db",
+                uri
+            ),
+            serde_json::json!({}),
+        )
+        .await;
+        assert_eq!(list["kind"], "EventList");
+        let items = list["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["involvedObject"]["name"], "db");
+
+        let (status, _): (_, serde_json::Value) = send(
+            Method::GET,
+            &format!("{}?fieldSelector=spec.nodeName=node1", uri),
+            serde_json::json!({}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
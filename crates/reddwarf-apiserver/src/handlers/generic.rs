@@ -0,0 +1,652 @@
+//! Generic handlers for resource kinds
+//!
+//! A kind implements [`ResourceKind`] to describe where it is served and to
+//! hook validation, admission and its delete strategy; [`ResourceHandlers`]
+//! provides the CRUD, list/watch and status handlers, and
+//! [`ResourceRegistry`] mounts them, and API discovery, for every registered
+//! kind.
+
+use crate::handlers::applyset::prune_applyset;
+use crate::handlers::common::{
+    create_resource, delete_resource, get_resource, list_or_watch, update_resource, update_status,
+    ListPath,
+};
+use crate::handlers::discovery::discovery_routes;
+use crate::handlers::protection::{check_deletion_protection, CONFIRM_DELETE_HEADER};
+use crate::response::{conditional_ok, status_deleted, ApiResponse};
+use crate::validation::validate_resource;
+use crate::watch::{force_watch, WatchParams};
+use crate::{ApiError, AppState, Result};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::handler::Handler;
+use axum::http::HeaderMap;
+use axum::middleware::map_request;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put, MethodRouter};
+use axum::{Json, Router};
+use futures_util::future::BoxFuture;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::DeleteOptions;
+use reddwarf_core::{GroupVersionKind, Resource, ResourceKey};
+use serde::Deserialize;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tracing::info;
+
+/// A resource kind served by [`ResourceHandlers`]
+#[async_trait]
+pub trait ResourceKind: Resource + 'static {
+    /// API version, e.g. `v1` or `node.k8s.io/v1`
+    const API_VERSION: &'static str;
+    /// Kind, e.g. `Pod`
+    const KIND: &'static str;
+    /// Lowercase plural naming the collection in URLs, e.g. `pods`
+    const PLURAL: &'static str;
+    /// Abbreviations clients accept for the plural, e.g. `po`
+    const SHORT_NAMES: &'static [&'static str] = &[];
+    /// Whether objects live in a namespace
+    const NAMESPACED: bool;
+    /// Whether the kind has a `/status` subresource
+    const STATUS_SUBRESOURCE: bool = false;
+    /// Fields besides the name and namespace lists and watches can select
+    /// objects by, e.g. `spec.nodeName`
+    const FIELD_SELECTORS: &'static [&'static str] = &[];
+    /// Largest request body accepted for objects of the kind, when it is
+    /// not the server's default; see [`crate::limits::RequestLimits`]
+    const MAX_BODY_BYTES: Option<usize> = None;
+
+    /// Normalize an object about to be created, replaced or patched, before
+    /// it is validated
+    fn normalize(_resource: &mut Self) {}
+
+    /// Validate an object about to be created, replaced or patched
+    fn validate_object(resource: &Self) -> Result<()> {
+        validate_resource(resource)
+    }
+
+    /// Validate a replacement or patch of the stored `current` object
+    fn validate_update(_current: &Self, _resource: &Self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Default and admit a validated object about to be created
+    async fn admit(_state: &AppState, _resource: &mut Self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Validate a write through the status subresource of the stored
+    /// `current` object
+    fn validate_status(_current: &Self, _resource: &Self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Prepare an object written through the status subresource
+    fn prepare_status(_resource: &mut Self) {}
+
+    /// Merge the `status` of a PATCH of the status subresource into the
+    /// stored `status`; the default is a JSON merge patch
+    fn merge_status(status: &mut serde_json::Value, patch: &serde_json::Value) {
+        json_patch::merge(status, patch);
+    }
+
+    /// Delete strategy; the default removes the object immediately
+    async fn delete(
+        state: &AppState,
+        key: &ResourceKey,
+        _options: &DeleteOptions,
+    ) -> Result<Response> {
+        delete_resource(state, key).await?;
+        Ok(status_deleted(&key.name, Self::KIND))
+    }
+}
+
+/// Path parameters of an object route
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectPath {
+    #[serde(default)]
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+/// Query parameters of a DELETE, overriding the body's `DeleteOptions`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteParams {
+    pub grace_period_seconds: Option<i64>,
+}
+
+/// CRUD, list/watch and status handlers for the kind `T`
+pub struct ResourceHandlers<T>(PhantomData<T>);
+
+impl<T: ResourceKind> ResourceHandlers<T> {
+    /// GroupVersionKind of `T`
+    pub fn gvk() -> GroupVersionKind {
+        GroupVersionKind::from_api_version_kind(T::API_VERSION, T::KIND)
+    }
+
+    fn key(path: ObjectPath) -> ResourceKey {
+        ResourceKey::new(Self::gvk(), path.namespace.unwrap_or_default(), path.name)
+    }
+
+    /// Force the URL's namespace and name onto a request body
+    fn bind(resource: &mut T, namespace: Option<String>, name: Option<String>) {
+        let metadata = resource.metadata_mut();
+        if T::NAMESPACED {
+            metadata.namespace = namespace;
+        }
+        if name.is_some() {
+            metadata.name = name;
+        }
+    }
+
+    /// GET {object}, answering 304 Not Modified when the client's copy,
+    /// named by its ETag in `If-None-Match`, is still current
+    pub async fn get(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ObjectPath>,
+        headers: HeaderMap,
+    ) -> Result<Response> {
+        let resource: T = get_resource(&state, &Self::key(path)).await?;
+
+        Ok(conditional_ok(&headers, resource))
+    }
+
+    /// GET {collection}, also across all namespaces
+    pub async fn list(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ListPath>,
+        Query(params): Query<WatchParams>,
+        headers: HeaderMap,
+    ) -> Result<Response> {
+        let selector = params.field_selector(T::PLURAL, T::FIELD_SELECTORS)?;
+        list_or_watch::<T>(
+            &state,
+            T::API_VERSION,
+            T::KIND,
+            path.namespace,
+            selector,
+            &params,
+            &headers,
+        )
+        .await
+    }
+
+    /// POST {collection}
+    pub async fn create(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ListPath>,
+        Json(mut resource): Json<T>,
+    ) -> Result<Response> {
+        info!(
+            "Creating {} in namespace: {}",
+            T::KIND,
+            path.namespace.as_deref().unwrap_or_default()
+        );
+
+        Self::bind(&mut resource, path.namespace, None);
+        T::normalize(&mut resource);
+        T::validate_object(&resource)?;
+        T::admit(&state, &mut resource).await?;
+
+        let created = create_resource(&state, resource).await?;
+
+        Ok(ApiResponse::created(created).into_response())
+    }
+
+    /// PUT {object}
+    pub async fn replace(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ObjectPath>,
+        Json(mut resource): Json<T>,
+    ) -> Result<Response> {
+        info!("Replacing {}: {}", T::KIND, path.name);
+
+        let _update = state.update_lock.lock().await;
+        let current: T = get_resource(&state, &Self::key(path.clone())).await?;
+        Self::bind(&mut resource, path.namespace, Some(path.name));
+        T::normalize(&mut resource);
+        T::validate_object(&resource)?;
+        T::validate_update(&current, &resource)?;
+
+        let updated = update_resource(&state, resource).await?;
+
+        Ok(ApiResponse::ok(updated).into_response())
+    }
+
+    /// PATCH {object} with a JSON merge patch
+    pub async fn patch(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ObjectPath>,
+        Json(patch): Json<serde_json::Value>,
+    ) -> Result<Response> {
+        info!("Patching {}: {}", T::KIND, path.name);
+
+        let _update = state.update_lock.lock().await;
+        let current: T = get_resource(&state, &Self::key(path)).await?;
+
+        let mut json = serde_json::to_value(&current)?;
+        json_patch::merge(&mut json, &patch);
+        let mut resource: T = serde_json::from_value(json)?;
+        T::normalize(&mut resource);
+        T::validate_object(&resource)?;
+        T::validate_update(&current, &resource)?;
+
+        let updated = update_resource(&state, resource).await?;
+
+        Ok(ApiResponse::ok(updated).into_response())
+    }
+
+    /// DELETE {object}, refused for protected objects unless confirmed.
+    /// Options come from an optional `DeleteOptions` body and the query.
+    pub async fn delete(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ObjectPath>,
+        Query(params): Query<DeleteParams>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<Response> {
+        info!("Deleting {}: {}", T::KIND, path.name);
+
+        let mut options = if body.is_empty() {
+            DeleteOptions::default()
+        } else {
+            serde_json::from_slice::<Option<DeleteOptions>>(&body)?.unwrap_or_default()
+        };
+        if params.grace_period_seconds.is_some() {
+            options.grace_period_seconds = params.grace_period_seconds;
+        }
+        if options.grace_period_seconds.is_some_and(|g| g < 0) {
+            return Err(ApiError::BadRequest(
+                "gracePeriodSeconds must not be negative".to_string(),
+            ));
+        }
+
+        let key = Self::key(path);
+        let confirmation = headers
+            .get(CONFIRM_DELETE_HEADER)
+            .and_then(|v| v.to_str().ok());
+        check_deletion_protection(&state, &key, confirmation)?;
+
+        T::delete(&state, &key, &options).await
+    }
+
+    /// PUT {object}/status
+    pub async fn update_status(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ObjectPath>,
+        Json(mut resource): Json<T>,
+    ) -> Result<Response> {
+        info!("Updating {} status: {}", T::KIND, path.name);
+
+        let current: T = get_resource(&state, &Self::key(path.clone())).await?;
+        Self::bind(&mut resource, path.namespace, Some(path.name));
+        T::validate_status(&current, &resource)?;
+        T::prepare_status(&mut resource);
+
+        let updated = update_status(&state, resource).await?;
+
+        Ok(ApiResponse::ok(updated).into_response())
+    }
+
+    /// PATCH {object}/status with a patch of which only `status` applies,
+    /// merged by [`ResourceKind::merge_status`]
+    pub async fn patch_status(
+        State(state): State<Arc<AppState>>,
+        Path(path): Path<ObjectPath>,
+        Json(patch): Json<serde_json::Value>,
+    ) -> Result<Response> {
+        info!("Patching {} status: {}", T::KIND, path.name);
+
+        let Some(status_patch) = patch.get("status") else {
+            return Err(ApiError::BadRequest(
+                "a status patch must set status".to_string(),
+            ));
+        };
+
+        let _update = state.update_lock.lock().await;
+        let current: T = get_resource(&state, &Self::key(path)).await?;
+
+        let mut json = serde_json::to_value(&current)?;
+        T::merge_status(&mut json["status"], status_patch);
+        let mut resource: T = serde_json::from_value(json)?;
+        T::validate_status(&current, &resource)?;
+        T::prepare_status(&mut resource);
+
+        let updated = update_status(&state, resource).await?;
+
+        Ok(ApiResponse::ok(updated).into_response())
+    }
+
+    /// URL prefix of `T`'s API group version
+    pub fn group_path() -> String {
+        if T::API_VERSION.contains('/') {
+            format!("/apis/{}", T::API_VERSION)
+        } else {
+            format!("/api/{}", T::API_VERSION)
+        }
+    }
+
+    /// URL of the collection of `T`, in `{namespace}` for namespaced kinds
+    pub fn collection_path() -> String {
+        if T::NAMESPACED {
+            format!(
+                "{}/namespaces/{{namespace}}/{}",
+                Self::group_path(),
+                T::PLURAL
+            )
+        } else {
+            format!("{}/{}", Self::group_path(), T::PLURAL)
+        }
+    }
+
+    /// Routes serving `T`
+    pub fn routes() -> Router<Arc<AppState>> {
+        let group = Self::group_path();
+        let collection = Self::collection_path();
+        let object = format!("{}/{{name}}", collection);
+
+        let mut router = Router::new()
+            .route(&collection, get(Self::list).post(Self::create))
+            .route(
+                &object,
+                get(Self::get)
+                    .put(Self::replace)
+                    .patch(Self::patch)
+                    .delete(Self::delete),
+            )
+            .route(
+                &format!("{}/watch/{}", group, T::PLURAL),
+                legacy_watch(Self::list),
+            );
+        if T::NAMESPACED {
+            router = router
+                .route(&format!("{}/{}", group, T::PLURAL), get(Self::list))
+                .route(
+                    &format!("{}/watch/namespaces/{{namespace}}/{}", group, T::PLURAL),
+                    legacy_watch(Self::list),
+                );
+        }
+        if T::STATUS_SUBRESOURCE {
+            router = router.route(
+                &format!("{}/status", object),
+                put(Self::update_status).patch(Self::patch_status),
+            );
+        }
+        router
+    }
+}
+
+/// GET route for the legacy `/watch/` form of a list route, which always
+/// watches
+pub(crate) fn legacy_watch<H, T>(handler: H) -> MethodRouter<Arc<AppState>>
+where
+    H: Handler<T, Arc<AppState>>,
+    T: 'static,
+{
+    get(handler).layer(map_request(force_watch))
+}
+
+/// A kind's [`ResourceKind::delete`] strategy, callable without knowing
+/// the kind statically
+pub type DeleteFn =
+    for<'a> fn(&'a AppState, &'a ResourceKey, &'a DeleteOptions) -> BoxFuture<'a, Result<Response>>;
+
+fn delete_kind<'a, T: ResourceKind>(
+    state: &'a AppState,
+    key: &'a ResourceKey,
+    options: &'a DeleteOptions,
+) -> BoxFuture<'a, Result<Response>> {
+    T::delete(state, key, options)
+}
+
+/// A kind registered with a [`ResourceRegistry`]
+#[derive(Debug, Clone)]
+pub struct RegisteredKind {
+    pub api_version: &'static str,
+    pub kind: &'static str,
+    pub plural: &'static str,
+    pub short_names: &'static [&'static str],
+    pub namespaced: bool,
+    pub status_subresource: bool,
+    pub max_body_bytes: Option<usize>,
+    pub delete: DeleteFn,
+}
+
+/// The kinds served through [`ResourceHandlers`], and their routes
+#[derive(Default)]
+pub struct ResourceRegistry {
+    kinds: Vec<RegisteredKind>,
+    router: Router<Arc<AppState>>,
+}
+
+impl ResourceRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve the kind `T`
+    pub fn register<T: ResourceKind>(mut self) -> Self {
+        self.kinds.push(RegisteredKind {
+            api_version: T::API_VERSION,
+            kind: T::KIND,
+            plural: T::PLURAL,
+            short_names: T::SHORT_NAMES,
+            namespaced: T::NAMESPACED,
+            status_subresource: T::STATUS_SUBRESOURCE,
+            max_body_bytes: T::MAX_BODY_BYTES,
+            delete: delete_kind::<T>,
+        });
+        self.router = self.router.merge(ResourceHandlers::<T>::routes());
+        self
+    }
+
+    /// Registered kinds, in registration order
+    pub fn kinds(&self) -> &[RegisteredKind] {
+        &self.kinds
+    }
+
+    /// Routes serving every registered kind, API discovery of them, and
+    /// ApplySet pruning across them
+    pub fn into_router(self) -> Router<Arc<AppState>> {
+        let kinds: Arc<[RegisteredKind]> = self.kinds.into();
+        let prune_kinds = kinds.clone();
+        self.router.merge(discovery_routes(kinds)).route(
+            "/apis/reddwarf.io/v1/applysets/{id}/prune",
+            post(move |state, path, body| prune_applyset(prune_kinds.clone(), state, path, body)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request, StatusCode};
+    use reddwarf_core::{Pod, Service};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_registry_serves_kinds_through_hooks() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+
+        let registry = ResourceRegistry::new()
+            .register::<Service>()
+            .register::<Pod>();
+        assert_eq!(registry.kinds()[0].plural, "services");
+        let router = registry.into_router().with_state(state);
+
+        let send = |method: Method, uri: &str, body: serde_json::Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice(&body).unwrap_or_default())
+            }
+        };
+
+        // The URL's namespace wins over the body's
+        let service = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Service",
+            "metadata": {"name": "web", "namespace": "elsewhere"}
+        });
+        let (status, created): (_, serde_json::Value) =
+            send(Method::POST, "/api/v1/namespaces/default/services", service).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["metadata"]["namespace"], "default");
+
+        let (status, patched): (_, serde_json::Value) = send(
+            Method::PATCH,
+            "/api/v1/namespaces/default/services/web",
+            serde_json::json!({"metadata": {"labels": {"app": "web"}}}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(patched["metadata"]["labels"]["app"], "web");
+
+        // Services have no status subresource; the default delete removes
+        let (status, _) = send(
+            Method::PUT,
+            "/api/v1/namespaces/default/services/web/status",
+            created,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(
+            Method::DELETE,
+            "/api/v1/namespaces/default/services/web",
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            Method::GET,
+            "/api/v1/namespaces/default/services/web",
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Pods override the delete strategy with graceful termination
+        let pod = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": "nginx"},
+            "spec": {"containers": [{"name": "nginx", "image": "nginx"}]}
+        });
+        let (status, _) = send(Method::POST, "/api/v1/namespaces/default/pods", pod).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, deleted): (_, serde_json::Value) = send(
+            Method::DELETE,
+            "/api/v1/namespaces/default/pods/nginx",
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(deleted["metadata"]["deletionTimestamp"].is_string());
+        assert_eq!(deleted["status"]["phase"], "Terminating");
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        use axum::http::header;
+
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let router = ResourceRegistry::new()
+            .register::<Service>()
+            .into_router()
+            .with_state(state);
+
+        let send = |method: Method, if_none_match: Option<&str>, body: serde_json::Value| {
+            let mut request = Request::builder()
+                .method(method)
+                .uri("/api/v1/namespaces/default/services/web")
+                .header("content-type", "application/json");
+            if let Some(etag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            let request = request.body(Body::from(body.to_string())).unwrap();
+            router.clone().oneshot(request)
+        };
+
+        let service = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Service",
+            "metadata": {"name": "web", "namespace": "default"}
+        });
+        let request = Request::post("/api/v1/namespaces/default/services")
+            .header("content-type", "application/json")
+            .body(Body::from(service.to_string()))
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap();
+
+        let response = send(Method::GET, None, serde_json::Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "no-cache, private"
+        );
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let fetched: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            etag,
+            format!(
+                "\"{}\"",
+                fetched["metadata"]["resourceVersion"].as_str().unwrap()
+            )
+        );
+
+        // An unchanged object is not sent again
+        let response = send(
+            Method::GET,
+            Some(&format!("\"stale\", {}", etag)),
+            serde_json::Value::Null,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+        assert!(to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // A changed one is, under a new ETag
+        send(
+            Method::PATCH,
+            None,
+            serde_json::json!({"metadata": {"labels": {"app": "web"}}}),
+        )
+        .await
+        .unwrap();
+        let response = send(Method::GET, Some(&etag), serde_json::Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[header::ETAG], etag.as_str());
+    }
+}
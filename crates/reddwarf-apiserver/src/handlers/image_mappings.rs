@@ -0,0 +1,19 @@
+use crate::handlers::common::list_resources;
+use crate::handlers::generic::ResourceKind;
+use crate::{AppState, Result};
+use reddwarf_core::resources::{IMAGE_MAPPING_API_VERSION, IMAGE_MAPPING_KIND};
+use reddwarf_core::ImageMapping;
+use reddwarf_storage::KeyEncoder;
+
+impl ResourceKind for ImageMapping {
+    const API_VERSION: &'static str = IMAGE_MAPPING_API_VERSION;
+    const KIND: &'static str = IMAGE_MAPPING_KIND;
+    const PLURAL: &'static str = "imagemappings";
+    const NAMESPACED: bool = false;
+}
+
+/// All ImageMappings
+pub(crate) async fn image_mappings(state: &AppState) -> Result<Vec<ImageMapping>> {
+    let prefix = KeyEncoder::encode_prefix(IMAGE_MAPPING_API_VERSION, IMAGE_MAPPING_KIND, None);
+    list_resources(state, &prefix).await
+}
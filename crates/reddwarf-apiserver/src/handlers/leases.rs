@@ -0,0 +1,86 @@
+use crate::handlers::common::check_resource_version;
+use crate::handlers::generic::ResourceKind;
+use crate::Result;
+use reddwarf_core::resources::{COORDINATION_API_VERSION, LEASE_KIND};
+use reddwarf_core::Lease;
+
+/// Leases are renewed by compare-and-swap: a replacement must carry the
+/// resourceVersion of the stored lease, so of two holders racing to renew or
+/// take over a lease only the first succeeds
+impl ResourceKind for Lease {
+    const API_VERSION: &'static str = COORDINATION_API_VERSION;
+    const KIND: &'static str = LEASE_KIND;
+    const PLURAL: &'static str = "leases";
+    const NAMESPACED: bool = true;
+
+    fn validate_update(current: &Lease, lease: &Lease) -> Result<()> {
+        check_resource_version(&current.metadata, &lease.metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::{create_resource, get_resource};
+    use crate::handlers::generic::{ObjectPath, ResourceHandlers};
+    use crate::{ApiError, AppState};
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::MicroTime;
+    use reddwarf_core::resources::NODE_LEASE_NAMESPACE;
+    use reddwarf_core::{GroupVersionKind, ResourceKey};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_lease_renewal_is_compare_and_swap() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let object = || {
+            Path(ObjectPath {
+                namespace: Some(NODE_LEASE_NAMESPACE.to_string()),
+                name: "node1".to_string(),
+            })
+        };
+        let renew = |mut lease: Lease| {
+            lease.spec.as_mut().unwrap().renew_time = Some(MicroTime(chrono::Utc::now()));
+            Json(lease)
+        };
+
+        let lease: Lease = serde_json::from_value(serde_json::json!({
+            "apiVersion": COORDINATION_API_VERSION,
+            "kind": LEASE_KIND,
+            "metadata": {"name": "node1", "namespace": NODE_LEASE_NAMESPACE},
+            "spec": {"holderIdentity": "node1", "leaseDurationSeconds": 40}
+        }))
+        .unwrap();
+        create_resource(&state, lease).await.unwrap();
+        let key = ResourceKey::new(
+            GroupVersionKind::from_api_version_kind(COORDINATION_API_VERSION, LEASE_KIND),
+            NODE_LEASE_NAMESPACE,
+            "node1",
+        );
+        let read: Lease = get_resource(&state, &key).await.unwrap();
+
+        // Renewing the stored version succeeds; renewing it again from the
+        // same, now stale, copy conflicts
+        ResourceHandlers::<Lease>::replace(State(state.clone()), object(), renew(read.clone()))
+            .await
+            .unwrap();
+        let stale =
+            ResourceHandlers::<Lease>::replace(State(state.clone()), object(), renew(read.clone()))
+                .await;
+        assert!(matches!(stale, Err(ApiError::Conflict(_))));
+
+        // A write without a resourceVersion is unconditional
+        let mut unconditional = read;
+        unconditional.metadata.resource_version = None;
+        ResourceHandlers::<Lease>::replace(State(state), object(), renew(unconditional))
+            .await
+            .unwrap();
+    }
+}
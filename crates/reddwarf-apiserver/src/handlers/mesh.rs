@@ -0,0 +1,303 @@
+use crate::handlers::common::{
+    create_resource, delete_resource, get_resource, list_resource_version, list_resources,
+    select_items, update_resource, ListResponse,
+};
+use crate::response::{status_deleted, with_deprecation_warning, ApiResponse};
+use crate::validation::validate_resource;
+use crate::watch::{watch_converted_stream, WatchParams};
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use reddwarf_core::resources::{MultiVersion, MESH_API_VERSION, MESH_POLICY_KIND};
+use reddwarf_core::{GroupVersionKind, MeshPolicy, ResourceKey};
+use reddwarf_storage::KeyEncoder;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::info;
+
+/// API group of mesh resources
+const MESH_GROUP: &str = "mesh.reddwarf.io";
+
+/// Key GVK of mesh policies, which are stored at their storage version
+fn mesh_policy_gvk() -> GroupVersionKind {
+    GroupVersionKind::from_api_version_kind(MESH_API_VERSION, MESH_POLICY_KIND)
+}
+
+/// API group/version of a request's `{version}` path segment, if served
+fn served_api_version(version: &str) -> Result<&'static str> {
+    MeshPolicy::served_version(&format!("{}/{}", MESH_GROUP, version))
+        .map(|v| v.api_version)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "{} is not served at {}/{}",
+                MESH_POLICY_KIND, MESH_GROUP, version
+            ))
+        })
+}
+
+/// Decode a request body sent at `api_version`
+fn decode_policy(api_version: &str, body: serde_json::Value) -> Result<MeshPolicy> {
+    if let Some(sent) = body.get("apiVersion").and_then(|v| v.as_str()) {
+        if sent != api_version {
+            return Err(ApiError::BadRequest(format!(
+                "apiVersion '{}' does not match the request path ({})",
+                sent, api_version
+            )));
+        }
+    }
+    MeshPolicy::decode(api_version, body).map_err(|e| ApiError::BadRequest(e.to_string()))
+}
+
+/// Encode a stored policy for a response at `api_version`
+fn encode_policy(policy: &MeshPolicy, api_version: &str) -> Result<serde_json::Value> {
+    policy
+        .encode(api_version)
+        .map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+/// Path of a mesh policy list, namespaced or across all namespaces
+#[derive(Deserialize)]
+pub struct MeshPolicyListPath {
+    version: String,
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+/// GET /apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies/{name}
+pub async fn get_mesh_policy(
+    State(state): State<Arc<AppState>>,
+    Path((version, namespace, name)): Path<(String, String, String)>,
+) -> Result<Response> {
+    let api_version = served_api_version(&version)?;
+    let key = ResourceKey::new(mesh_policy_gvk(), namespace, name);
+
+    let policy: MeshPolicy = get_resource(&state, &key).await?;
+    let body = encode_policy(&policy, api_version)?;
+
+    Ok(with_deprecation_warning::<MeshPolicy>(
+        api_version,
+        ApiResponse::ok(body).into_response(),
+    ))
+}
+
+/// GET /apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies
+/// GET /apis/mesh.reddwarf.io/{version}/meshpolicies
+pub async fn list_mesh_policies(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<MeshPolicyListPath>,
+    Query(params): Query<WatchParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let api_version = served_api_version(&path.version)?;
+    let namespace = path.namespace;
+    let selector = params.field_selector("meshpolicies", &[])?;
+
+    if params.is_watch() {
+        let stream = watch_converted_stream(
+            &state,
+            mesh_policy_gvk(),
+            namespace,
+            selector,
+            &params,
+            &headers,
+            move |object| MeshPolicy::from_storage_version(api_version, object).ok(),
+        )?;
+        return Ok(with_deprecation_warning::<MeshPolicy>(api_version, stream));
+    }
+
+    let prefix =
+        KeyEncoder::encode_prefix(MESH_API_VERSION, MESH_POLICY_KIND, namespace.as_deref());
+    let resource_version = list_resource_version(&state);
+    let policies: Vec<MeshPolicy> =
+        select_items(list_resources(&state, &prefix).await?, &selector)?;
+    let items = policies
+        .iter()
+        .map(|p| encode_policy(p, api_version))
+        .collect::<Result<Vec<_>>>()?;
+
+    let response = ListResponse::new(
+        api_version.to_string(),
+        format!("{}List", MESH_POLICY_KIND),
+        items,
+        resource_version,
+    );
+
+    Ok(with_deprecation_warning::<MeshPolicy>(
+        api_version,
+        ApiResponse::ok(response).into_response(),
+    ))
+}
+
+/// POST /apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies
+pub async fn create_mesh_policy(
+    State(state): State<Arc<AppState>>,
+    Path((version, namespace)): Path<(String, String)>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response> {
+    info!("Creating mesh policy in namespace: {}", namespace);
+
+    let api_version = served_api_version(&version)?;
+    let mut policy = decode_policy(api_version, body)?;
+    policy.metadata.namespace = Some(namespace);
+    validate_resource(&policy)?;
+
+    let created = create_resource(&state, policy).await?;
+    let body = encode_policy(&created, api_version)?;
+
+    Ok(with_deprecation_warning::<MeshPolicy>(
+        api_version,
+        ApiResponse::created(body).into_response(),
+    ))
+}
+
+/// PUT /apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies/{name}
+pub async fn replace_mesh_policy(
+    State(state): State<Arc<AppState>>,
+    Path((version, namespace, name)): Path<(String, String, String)>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response> {
+    info!("Replacing mesh policy: {}/{}", namespace, name);
+
+    let api_version = served_api_version(&version)?;
+    let mut policy = decode_policy(api_version, body)?;
+    policy.metadata.namespace = Some(namespace);
+    policy.metadata.name = Some(name);
+    validate_resource(&policy)?;
+
+    let updated = update_resource(&state, policy).await?;
+    let body = encode_policy(&updated, api_version)?;
+
+    Ok(with_deprecation_warning::<MeshPolicy>(
+        api_version,
+        ApiResponse::ok(body).into_response(),
+    ))
+}
+
+/// DELETE /apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies/{name}
+pub async fn delete_mesh_policy(
+    State(state): State<Arc<AppState>>,
+    Path((version, namespace, name)): Path<(String, String, String)>,
+) -> Result<Response> {
+    info!("Deleting mesh policy: {}/{}", namespace, name);
+
+    let api_version = served_api_version(&version)?;
+    let key = ResourceKey::new(mesh_policy_gvk(), namespace, name.clone());
+
+    delete_resource(&state, &key).await?;
+
+    Ok(with_deprecation_warning::<MeshPolicy>(
+        api_version,
+        status_deleted(&name, MESH_POLICY_KIND),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_core::MeshPolicySpec;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    async fn setup_state() -> Arc<AppState> {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+
+        Arc::new(AppState::new(storage, version_store))
+    }
+
+    #[tokio::test]
+    async fn test_mesh_policies_listed_per_namespace_and_cluster_wide() {
+        let state = setup_state().await;
+
+        for (ns, name) in [("default", "web"), ("prod", "api")] {
+            let policy = MeshPolicy::new(
+                ns,
+                name,
+                MeshPolicySpec {
+                    ports: vec![8080],
+                    ..Default::default()
+                },
+            );
+            create_resource(&state, policy).await.unwrap();
+        }
+
+        let prefix = KeyEncoder::encode_prefix(MESH_API_VERSION, MESH_POLICY_KIND, Some("prod"));
+        let prod: Vec<MeshPolicy> = list_resources(&state, &prefix).await.unwrap();
+        assert_eq!(prod.len(), 1);
+        assert_eq!(prod[0].metadata.name.as_deref(), Some("api"));
+
+        let prefix = KeyEncoder::encode_prefix(MESH_API_VERSION, MESH_POLICY_KIND, None);
+        let all: Vec<MeshPolicy> = list_resources(&state, &prefix).await.unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mesh_policy_served_at_both_versions() {
+        use axum::body::to_bytes;
+        use axum::http::header;
+        use reddwarf_core::resources::MESH_V1BETA1_API_VERSION;
+
+        let state = setup_state().await;
+        let beta = serde_json::json!({
+            "apiVersion": MESH_V1BETA1_API_VERSION,
+            "metadata": {"name": "web"},
+            "spec": {"podSelector": {"matchLabels": {"app": "web"}}, "ports": [{"port": 8080}]}
+        });
+        let response = create_mesh_policy(
+            State(state.clone()),
+            Path(("v1beta1".to_string(), "default".to_string())),
+            Json(beta),
+        )
+        .await
+        .unwrap();
+        assert!(response.headers().get(header::WARNING).is_none());
+
+        // Stored at the storage version
+        let key = ResourceKey::new(mesh_policy_gvk(), "default", "web");
+        let stored: MeshPolicy = get_resource(&state, &key).await.unwrap();
+        assert_eq!(stored.spec.ports, vec![8080]);
+
+        let get = |version: &str| {
+            get_mesh_policy(
+                State(state.clone()),
+                Path((
+                    version.to_string(),
+                    "default".to_string(),
+                    "web".to_string(),
+                )),
+            )
+        };
+        let response = get("v1alpha1").await.unwrap();
+        let warning = response.headers()[header::WARNING]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(warning.starts_with("299 - \"mesh.reddwarf.io/v1alpha1 MeshPolicy is deprecated"));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let alpha: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(alpha["spec"]["ports"], serde_json::json!([8080]));
+
+        let body = to_bytes(get("v1beta1").await.unwrap().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let beta: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(beta["apiVersion"], MESH_V1BETA1_API_VERSION);
+        assert_eq!(beta["spec"]["ports"], serde_json::json!([{"port": 8080}]));
+
+        assert!(matches!(get("v2").await, Err(ApiError::NotFound(_))));
+
+        // A body claiming another version than the path is rejected
+        let mismatched = create_mesh_policy(
+            State(state.clone()),
+            Path(("v1alpha1".to_string(), "default".to_string())),
+            Json(serde_json::json!({"apiVersion": MESH_V1BETA1_API_VERSION, "metadata": {"name": "x"}, "spec": {"ports": [{"port": 1}]}})),
+        )
+        .await;
+        assert!(matches!(mismatched, Err(ApiError::BadRequest(_))));
+    }
+}
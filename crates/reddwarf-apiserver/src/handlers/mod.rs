@@ -1,12 +1,47 @@
+pub mod applyset;
+pub mod autoscalers;
+pub mod bootstrap;
 pub mod common;
+pub mod components;
+pub mod config_maps;
+pub mod custom_resources;
+pub mod debug;
+pub mod deployments;
+pub mod discovery;
+pub mod disruption_budgets;
+pub mod endpoints;
+pub mod events;
+pub mod generic;
+pub mod image_mappings;
+pub mod leases;
+pub mod mesh;
 pub mod namespaces;
+pub mod network_policies;
+pub mod node_proxy;
 pub mod nodes;
+pub mod persistent_volumes;
 pub mod pods;
+pub mod priority_classes;
+pub mod protection;
+pub mod replication;
+pub mod runtime_classes;
+pub mod secrets;
 pub mod services;
+pub mod sessions;
+pub mod usage;
 
 // Re-export handler functions
+pub use applyset::{ApplySetObject, PruneRequest, PruneResponse};
+pub use bootstrap::sign_node_certificate;
 pub use common::*;
-pub use namespaces::*;
-pub use nodes::*;
+pub use components::{get_component_status, list_component_statuses};
+pub use debug::*;
+pub use events::{create_event, get_event, list_events};
+pub use generic::*;
+pub use mesh::*;
+pub use node_proxy::*;
 pub use pods::*;
-pub use services::*;
+pub use protection::CONFIRM_DELETE_HEADER;
+pub use replication::get_replication_stream;
+pub use sessions::{attach_pod, exec_pod};
+pub use usage::{get_cluster_usage, get_usage_reports};
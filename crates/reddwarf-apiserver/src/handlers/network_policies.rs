@@ -0,0 +1,11 @@
+use crate::handlers::generic::ResourceKind;
+use reddwarf_core::resources::{NETWORK_POLICY_API_VERSION, NETWORK_POLICY_KIND};
+use reddwarf_core::NetworkPolicy;
+
+impl ResourceKind for NetworkPolicy {
+    const API_VERSION: &'static str = NETWORK_POLICY_API_VERSION;
+    const KIND: &'static str = NETWORK_POLICY_KIND;
+    const PLURAL: &'static str = "networkpolicies";
+    const SHORT_NAMES: &'static [&'static str] = &["netpol"];
+    const NAMESPACED: bool = true;
+}
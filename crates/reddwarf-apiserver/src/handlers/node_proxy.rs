@@ -0,0 +1,299 @@
+use crate::auth::{self, Bearer};
+use crate::handlers::common::get_resource;
+use crate::handlers::generic::ResourceHandlers;
+use crate::proxy::NodeProxy;
+use crate::{ApiError, AppState, Result};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use reddwarf_core::{Node, ResourceKey};
+use serde::Deserialize;
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Request headers passed on to the node agent
+const FORWARDED_REQUEST_HEADERS: &[header::HeaderName] =
+    &[header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE];
+
+/// Response headers passed back to the caller
+const FORWARDED_RESPONSE_HEADERS: &[header::HeaderName] =
+    &[header::CONTENT_TYPE, header::CACHE_CONTROL];
+
+#[derive(Debug, Deserialize)]
+pub struct NodeProxyPath {
+    name: String,
+}
+
+/// Resolve the proxy configuration and check the caller's bearer token
+fn authorize<'a>(state: &'a AppState, headers: &HeaderMap) -> Result<&'a NodeProxy> {
+    let proxy = state
+        .node_proxy
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Node proxy is not enabled".to_string()))?;
+    auth::authorize(
+        std::slice::from_ref(&proxy.token),
+        &Bearer::from_headers(headers),
+        "Node proxy",
+    )?;
+    Ok(proxy)
+}
+
+/// Host and port of the agent running on `node`: its InternalIP, falling
+/// back to its Hostname, and `status.daemonEndpoints.kubeletEndpoint.port`
+fn agent_endpoint(node: &Node) -> Result<(String, i32)> {
+    let name = node.metadata.name.as_deref().unwrap_or_default();
+    let status = node.status.as_ref();
+
+    let port = status
+        .and_then(|s| s.daemon_endpoints.as_ref())
+        .and_then(|d| d.kubelet_endpoint.as_ref())
+        .map(|e| e.port)
+        .filter(|port| *port > 0)
+        .ok_or_else(|| {
+            ApiError::BadGateway(format!("Node {} does not publish an agent endpoint", name))
+        })?;
+
+    let addresses = status
+        .and_then(|s| s.addresses.as_deref())
+        .unwrap_or_default();
+    let address = ["InternalIP", "Hostname"]
+        .iter()
+        .find_map(|type_| addresses.iter().find(|a| a.type_ == *type_))
+        .map(|a| a.address.as_str())
+        .ok_or_else(|| ApiError::BadGateway(format!("Node {} has no usable address", name)))?;
+
+    let host = match address.parse::<Ipv6Addr>() {
+        Ok(_) => format!("[{}]", address),
+        Err(_) => address.to_string(),
+    };
+    Ok((host, port))
+}
+
+/// ANY /api/v1/nodes/{name}/proxy/{*path}
+///
+/// Forwards the request to the agent on the node, e.g. for its metrics or
+/// its zone debug API, and relays the agent's response.
+pub async fn proxy_node(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<NodeProxyPath>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response> {
+    let proxy = authorize(&state, &headers)?;
+
+    let key = ResourceKey::cluster_scoped(ResourceHandlers::<Node>::gvk(), &path.name);
+    let node: Node = get_resource(&state, &key).await?;
+    let (host, port) = agent_endpoint(&node)?;
+
+    let prefix = format!("/api/v1/nodes/{}/proxy", path.name);
+    let target_path = match uri.path().strip_prefix(&prefix) {
+        Some(rest) if !rest.is_empty() => rest,
+        _ => "/",
+    };
+    let mut url = format!("{}://{}:{}{}", proxy.scheme, host, port, target_path);
+    if let Some(query) = uri.query() {
+        url.push('?');
+        url.push_str(query);
+    }
+    debug!("Proxying {} {} to node {}", method, url, path.name);
+
+    let mut request = proxy.client.request(method, &url).body(body);
+    for name in FORWARDED_REQUEST_HEADERS {
+        if let Some(value) = headers.get(name) {
+            request = request.header(name, value);
+        }
+    }
+
+    let upstream = request.send().await.map_err(|e| {
+        ApiError::BadGateway(format!(
+            "Failed to reach agent on node {}: {}",
+            path.name, e
+        ))
+    })?;
+
+    let status =
+        StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut response_headers = HeaderMap::new();
+    for name in FORWARDED_RESPONSE_HEADERS {
+        if let Some(value) = upstream.headers().get(name) {
+            response_headers.insert(name, value.clone());
+        }
+    }
+    let body = upstream.bytes().await.map_err(|e| {
+        ApiError::BadGateway(format!(
+            "Failed to read response from node {}: {}",
+            path.name, e
+        ))
+    })?;
+
+    Ok((status, response_headers, body).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::create_resource;
+    use axum::http::HeaderValue;
+    use axum::routing::get;
+    use axum::Router;
+    use reddwarf_core::k8s_openapi::api::core::v1::{
+        DaemonEndpoint, NodeAddress, NodeDaemonEndpoints, NodeStatus,
+    };
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    fn setup_state(proxy: Option<NodeProxy>) -> Arc<AppState> {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+
+        let mut state = AppState::new(storage, version_store);
+        if let Some(proxy) = proxy {
+            state = state.with_node_proxy(proxy);
+        }
+        Arc::new(state)
+    }
+
+    /// Serve a stand-in node agent, returning its port
+    async fn spawn_agent() -> u16 {
+        let agent = Router::new()
+            .route("/metrics", get(|| async { "reddwarf_pods 3\n" }))
+            .route(
+                "/debug/zones",
+                get(|headers: HeaderMap, uri: Uri| async move {
+                    let authorization = headers
+                        .get(header::AUTHORIZATION)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    format!("{} {}", authorization, uri.query().unwrap_or_default())
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, agent).await.unwrap() });
+        port
+    }
+
+    fn node(name: &str, port: Option<i32>) -> Node {
+        let mut node = Node::default();
+        node.metadata.name = Some(name.to_string());
+        node.status = Some(NodeStatus {
+            addresses: Some(vec![NodeAddress {
+                type_: "InternalIP".to_string(),
+                address: "127.0.0.1".to_string(),
+            }]),
+            daemon_endpoints: port.map(|port| NodeDaemonEndpoints {
+                kubelet_endpoint: Some(DaemonEndpoint { port }),
+            }),
+            ..Default::default()
+        });
+        node
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        headers
+    }
+
+    async fn proxy(state: &Arc<AppState>, uri: &str, headers: HeaderMap) -> Result<Response> {
+        let name = uri.split('/').nth(4).unwrap().to_string();
+        proxy_node(
+            State(state.clone()),
+            Path(NodeProxyPath { name }),
+            Method::GET,
+            uri.parse().unwrap(),
+            headers,
+            Bytes::new(),
+        )
+        .await
+    }
+
+    async fn body_text(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_node_proxy_requires_token() {
+        let state = setup_state(None);
+        let result = proxy(
+            &state,
+            "/api/v1/nodes/node1/proxy/metrics",
+            bearer("s3cret"),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+
+        let state = setup_state(Some(NodeProxy::new("s3cret")));
+        let result = proxy(&state, "/api/v1/nodes/node1/proxy/metrics", bearer("wrong")).await;
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_node_proxy_forwards_to_agent() {
+        let state = setup_state(Some(NodeProxy::new("s3cret")));
+        let port = spawn_agent().await;
+        create_resource(&state, node("node1", Some(port as i32)))
+            .await
+            .unwrap();
+        create_resource(&state, node("node2", None)).await.unwrap();
+
+        let response = proxy(
+            &state,
+            "/api/v1/nodes/node1/proxy/metrics",
+            bearer("s3cret"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, "reddwarf_pods 3\n");
+
+        // The query string and the caller's credentials reach the agent
+        let response = proxy(
+            &state,
+            "/api/v1/nodes/node1/proxy/debug/zones?limit=1",
+            bearer("s3cret"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Bearer s3cret limit=1");
+
+        let response = proxy(
+            &state,
+            "/api/v1/nodes/node1/proxy/missing",
+            bearer("s3cret"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let result = proxy(
+            &state,
+            "/api/v1/nodes/node2/proxy/metrics",
+            bearer("s3cret"),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadGateway(_))));
+
+        let result = proxy(
+            &state,
+            "/api/v1/nodes/node3/proxy/metrics",
+            bearer("s3cret"),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+    }
+}
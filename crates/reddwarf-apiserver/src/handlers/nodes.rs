@@ -1,113 +1,107 @@
-use crate::handlers::common::{
-    create_resource, delete_resource, get_resource, list_resources, update_resource, update_status,
-    ListResponse,
-};
-use crate::response::{status_deleted, ApiResponse};
-use crate::validation::validate_resource;
-use crate::watch::{watch_resource_stream, WatchParams};
-use crate::{AppState, Result};
-use axum::extract::{Path, Query, State};
-use axum::response::{IntoResponse, Response};
-use axum::Json;
-use reddwarf_core::{GroupVersionKind, Node, ResourceKey};
-use reddwarf_storage::KeyEncoder;
-use std::sync::Arc;
-use tracing::info;
-
-/// GET /api/v1/nodes/{name}
-pub async fn get_node(
-    State(state): State<Arc<AppState>>,
-    Path(name): Path<String>,
-) -> Result<Response> {
-    let gvk = GroupVersionKind::from_api_version_kind("v1", "Node");
-    let key = ResourceKey::cluster_scoped(gvk, name);
-
-    let node: Node = get_resource(&state, &key).await?;
-
-    Ok(ApiResponse::ok(node).into_response())
+use crate::handlers::generic::ResourceKind;
+use crate::{ApiError, AppState, Result};
+use async_trait::async_trait;
+use reddwarf_core::version::{check_agent_skew, node_version, Version};
+use reddwarf_core::Node;
+use serde_json::Value;
+
+/// Refuse nodes whose agent reports a version this API server does not
+/// support talking to
+fn check_version_skew(node: &Node) -> Result<()> {
+    let Some(agent) = node_version(node) else {
+        return Ok(());
+    };
+    check_agent_skew(Version::current(), agent).map_err(|reason| {
+        ApiError::Forbidden(format!(
+            "Node {} has an unsupported version skew: {}",
+            node.metadata.name.as_deref().unwrap_or_default(),
+            reason
+        ))
+    })
 }
 
-/// GET /api/v1/nodes
-pub async fn list_nodes(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<WatchParams>,
-) -> Result<Response> {
-    if params.is_watch() {
-        let gvk = GroupVersionKind::from_api_version_kind("v1", "Node");
-        return Ok(watch_resource_stream(&state, gvk, None).into_response());
+/// Check the version a write reports, unless it is the one already stored,
+/// so nodes registered before the API server was upgraded can still be
+/// cordoned and drained
+fn check_reported_version(current: &Node, node: &Node) -> Result<()> {
+    if node_version(current) == node_version(node) {
+        return Ok(());
     }
-
-    let prefix = KeyEncoder::encode_prefix("v1", "Node", None);
-    let nodes: Vec<Node> = list_resources(&state, &prefix).await?;
-
-    let response = ListResponse::new("v1".to_string(), "NodeList".to_string(), nodes);
-
-    Ok(ApiResponse::ok(response).into_response())
-}
-
-/// POST /api/v1/nodes
-pub async fn create_node(
-    State(state): State<Arc<AppState>>,
-    Json(node): Json<Node>,
-) -> Result<Response> {
-    info!("Creating node");
-
-    validate_resource(&node)?;
-
-    let created = create_resource(&state, node).await?;
-
-    Ok(ApiResponse::created(created).into_response())
-}
-
-/// PUT /api/v1/nodes/{name}
-pub async fn replace_node(
-    State(state): State<Arc<AppState>>,
-    Path(name): Path<String>,
-    Json(mut node): Json<Node>,
-) -> Result<Response> {
-    info!("Replacing node: {}", name);
-
-    node.metadata.name = Some(name);
-    validate_resource(&node)?;
-
-    let updated = update_resource(&state, node).await?;
-
-    Ok(ApiResponse::ok(updated).into_response())
+    check_version_skew(node)
 }
 
-/// PUT /api/v1/nodes/{name}/status
-pub async fn update_node_status(
-    State(state): State<Arc<AppState>>,
-    Path(name): Path<String>,
-    Json(mut node): Json<Node>,
-) -> Result<Response> {
-    info!("Updating node status: {}", name);
-
-    node.metadata.name = Some(name);
-
-    let updated = update_status(&state, node).await?;
-
-    Ok(ApiResponse::ok(updated).into_response())
+/// Merge the `conditions` of a status patch into the stored `conditions`
+/// by type, as a strategic merge patch does. A condition whose status is
+/// unchanged keeps its transition time.
+fn merge_conditions(stored: &mut Value, patched: Vec<Value>) {
+    if !stored.is_array() {
+        *stored = Value::Array(Vec::new());
+    }
+    let Value::Array(conditions) = stored else {
+        return;
+    };
+    for mut condition in patched {
+        match conditions
+            .iter_mut()
+            .find(|c| c["type"] == condition["type"])
+        {
+            Some(existing) => {
+                if existing["status"] == condition["status"]
+                    && !existing["lastTransitionTime"].is_null()
+                {
+                    condition["lastTransitionTime"] = existing["lastTransitionTime"].clone();
+                }
+                *existing = condition;
+            }
+            None => conditions.push(condition),
+        }
+    }
 }
 
-/// DELETE /api/v1/nodes/{name}
-pub async fn delete_node(
-    State(state): State<Arc<AppState>>,
-    Path(name): Path<String>,
-) -> Result<Response> {
-    info!("Deleting node: {}", name);
+#[async_trait]
+impl ResourceKind for Node {
+    const API_VERSION: &'static str = "v1";
+    const KIND: &'static str = "Node";
+    const PLURAL: &'static str = "nodes";
+    const SHORT_NAMES: &'static [&'static str] = &["no"];
+    const NAMESPACED: bool = false;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    fn validate_update(current: &Node, node: &Node) -> Result<()> {
+        check_reported_version(current, node)
+    }
 
-    let gvk = GroupVersionKind::from_api_version_kind("v1", "Node");
-    let key = ResourceKey::cluster_scoped(gvk, name.clone());
+    async fn admit(_state: &AppState, node: &mut Node) -> Result<()> {
+        check_version_skew(node)
+    }
 
-    delete_resource(&state, &key).await?;
+    fn validate_status(current: &Node, node: &Node) -> Result<()> {
+        check_reported_version(current, node)
+    }
 
-    Ok(status_deleted(&name, "Node"))
+    /// Conditions are merged by type, so a heartbeat only sends the
+    /// conditions it reports and the rest of the status stays as stored
+    fn merge_status(status: &mut Value, patch: &Value) {
+        let mut patch = patch.clone();
+        let conditions = patch.as_object_mut().and_then(|p| p.remove("conditions"));
+        json_patch::merge(status, &patch);
+        match conditions {
+            Some(Value::Array(conditions)) => {
+                merge_conditions(&mut status["conditions"], conditions)
+            }
+            Some(conditions) => {
+                json_patch::merge(status, &serde_json::json!({ "conditions": conditions }))
+            }
+            None => {}
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::common::{create_resource, update_status};
+    use crate::AppState;
     use reddwarf_core::k8s_openapi::api::core::v1::{NodeCondition, NodeStatus};
     use reddwarf_core::Resource;
     use reddwarf_storage::RedbBackend;
@@ -131,7 +125,7 @@ mod tests {
         // Create a node
         let mut node = Node::default();
         node.metadata.name = Some("test-node".to_string());
-        let created = create_resource(&*state, node).await.unwrap();
+        let created = create_resource(&state, node).await.unwrap();
 
         // Update status with conditions
         let mut status_node = created.clone();
@@ -146,7 +140,7 @@ mod tests {
             ..Default::default()
         });
 
-        let updated = update_status(&*state, status_node).await.unwrap();
+        let updated = update_status(&state, status_node).await.unwrap();
 
         let conditions = updated
             .status
@@ -162,4 +156,119 @@ mod tests {
         // Resource version should be bumped
         assert_ne!(updated.resource_version(), created.resource_version());
     }
+
+    #[tokio::test]
+    async fn test_version_skew_is_rejected() {
+        use reddwarf_core::k8s_openapi::api::core::v1::NodeSystemInfo;
+
+        let state = setup_state().await;
+        let with_version = |version: &str| {
+            let mut node = Node::default();
+            node.metadata.name = Some("node1".to_string());
+            node.status = Some(NodeStatus {
+                node_info: Some(NodeSystemInfo {
+                    kubelet_version: version.to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            node
+        };
+        let current = Version::current();
+        let newer = Version::new(current.major, current.minor + 1, 0).node_version_string();
+        let next_major = Version::new(current.major + 1, 0, 0).node_version_string();
+
+        // Registration
+        let mut node = with_version(&current.node_version_string());
+        Node::admit(&state, &mut node).await.unwrap();
+        assert!(matches!(
+            Node::admit(&state, &mut with_version(&newer)).await,
+            Err(ApiError::Forbidden(_))
+        ));
+        // Nodes without a reddwarf version are not checked
+        Node::admit(&state, &mut with_version("v1.31.0"))
+            .await
+            .unwrap();
+
+        // Heartbeats reporting a changed version are checked, unchanged
+        // ones are not
+        assert!(Node::validate_status(&node, &with_version(&next_major)).is_err());
+        let skewed = with_version(&newer);
+        assert!(Node::validate_status(&skewed, &skewed).is_ok());
+        assert!(Node::validate_update(&skewed, &skewed).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_status_delta_merges_conditions() {
+        use crate::handlers::generic::ResourceHandlers;
+        use axum::body::{to_bytes, Body};
+        use axum::http::{Method, Request, StatusCode};
+        use tower::ServiceExt;
+
+        let state = setup_state().await;
+        let node: Node = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "node1"},
+            "status": {
+                "capacity": {"cpu": "8", "pods": "110"},
+                "conditions": [
+                    {"type": "Ready", "status": "True", "reason": "KubeletReady",
+                     "lastHeartbeatTime": "2026-01-01T00:00:00Z",
+                     "lastTransitionTime": "2026-01-01T00:00:00Z"},
+                    {"type": "MemoryPressure", "status": "False",
+                     "lastTransitionTime": "2026-01-01T00:00:00Z"}
+                ]
+            }
+        }))
+        .unwrap();
+        create_resource(&state, node).await.unwrap();
+
+        let router = ResourceHandlers::<Node>::routes().with_state(state);
+        let patch = |conditions: serde_json::Value| {
+            let request = Request::builder()
+                .method(Method::PATCH)
+                .uri("/api/v1/nodes/node1/status")
+                .header("content-type", "application/merge-patch+json")
+                .body(Body::from(
+                    serde_json::json!({"status": {"conditions": conditions}}).to_string(),
+                ))
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()["status"].clone()
+            }
+        };
+
+        // A heartbeat: the Ready condition with a new heartbeat time only
+        let status = patch(serde_json::json!([
+            {"type": "Ready", "status": "True", "reason": "KubeletReady",
+             "lastHeartbeatTime": "2026-01-01T00:00:10Z",
+             "lastTransitionTime": "2026-01-01T00:00:10Z"}
+        ]))
+        .await;
+        assert_eq!(status["capacity"]["cpu"], "8");
+        let conditions = status["conditions"].as_array().unwrap();
+        assert_eq!(conditions.len(), 2);
+        assert_eq!(conditions[0]["lastHeartbeatTime"], "2026-01-01T00:00:10Z");
+        // Still Ready since it last transitioned
+        assert_eq!(conditions[0]["lastTransitionTime"], "2026-01-01T00:00:00Z");
+        assert_eq!(conditions[1]["type"], "MemoryPressure");
+
+        // A condition changing status transitions, a new one is added
+        let status = patch(serde_json::json!([
+            {"type": "MemoryPressure", "status": "True",
+             "lastTransitionTime": "2026-01-01T00:00:20Z"},
+            {"type": "DiskPressure", "status": "False",
+             "lastTransitionTime": "2026-01-01T00:00:20Z"}
+        ]))
+        .await;
+        let conditions = status["conditions"].as_array().unwrap();
+        assert_eq!(conditions.len(), 3);
+        assert_eq!(conditions[0]["type"], "Ready");
+        assert_eq!(conditions[1]["status"], "True");
+        assert_eq!(conditions[1]["lastTransitionTime"], "2026-01-01T00:00:20Z");
+        assert_eq!(conditions[2]["type"], "DiskPressure");
+    }
 }
@@ -0,0 +1,131 @@
+use crate::handlers::generic::ResourceKind;
+use crate::{ApiError, Result};
+use reddwarf_core::resources::{PERSISTENT_VOLUME_CLAIM_KIND, PERSISTENT_VOLUME_KIND};
+use reddwarf_core::{PersistentVolume, PersistentVolumeClaim};
+
+impl ResourceKind for PersistentVolume {
+    const API_VERSION: &'static str = "v1";
+    const KIND: &'static str = PERSISTENT_VOLUME_KIND;
+    const PLURAL: &'static str = "persistentvolumes";
+    const SHORT_NAMES: &'static [&'static str] = &["pv"];
+    const NAMESPACED: bool = false;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    /// The storage backing a volume is fixed once it is created
+    fn validate_update(current: &PersistentVolume, volume: &PersistentVolume) -> Result<()> {
+        let (Some(current), Some(spec)) = (&current.spec, &volume.spec) else {
+            return Ok(());
+        };
+        if current.local != spec.local || current.host_path != spec.host_path {
+            return Err(ApiError::ValidationFailed(
+                "spec: the volume source is immutable".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ResourceKind for PersistentVolumeClaim {
+    const API_VERSION: &'static str = "v1";
+    const KIND: &'static str = PERSISTENT_VOLUME_CLAIM_KIND;
+    const PLURAL: &'static str = "persistentvolumeclaims";
+    const SHORT_NAMES: &'static [&'static str] = &["pvc"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+
+    /// The spec of a claim is fixed, except that `volumeName` is set once
+    /// when the claim is bound
+    fn validate_update(
+        current: &PersistentVolumeClaim,
+        claim: &PersistentVolumeClaim,
+    ) -> Result<()> {
+        let (Some(current), Some(spec)) = (&current.spec, &claim.spec) else {
+            return Ok(());
+        };
+        if current.volume_name.is_some() && current.volume_name != spec.volume_name {
+            return Err(ApiError::ValidationFailed(
+                "spec.volumeName: field is immutable once the claim is bound".to_string(),
+            ));
+        }
+        let mut unbound = spec.clone();
+        unbound.volume_name = current.volume_name.clone();
+        if *current != unbound {
+            return Err(ApiError::ValidationFailed(
+                "spec: field is immutable except for spec.volumeName".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::ListPath;
+    use crate::handlers::generic::{ObjectPath, ResourceHandlers};
+    use crate::AppState;
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_claim_spec_immutable_but_bindable() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let object = || {
+            Path(ObjectPath {
+                namespace: Some("default".to_string()),
+                name: "data".to_string(),
+            })
+        };
+
+        let claim: PersistentVolumeClaim = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "PersistentVolumeClaim",
+            "metadata": {"name": "data"},
+            "spec": {
+                "accessModes": ["ReadWriteOnce"],
+                "resources": {"requests": {"storage": "1Gi"}}
+            }
+        }))
+        .unwrap();
+        ResourceHandlers::<PersistentVolumeClaim>::create(
+            State(state.clone()),
+            Path(ListPath {
+                namespace: Some("default".to_string()),
+            }),
+            Json(claim),
+        )
+        .await
+        .unwrap();
+
+        let resized = ResourceHandlers::<PersistentVolumeClaim>::patch(
+            State(state.clone()),
+            object(),
+            Json(serde_json::json!({"spec": {"resources": {"requests": {"storage": "2Gi"}}}})),
+        )
+        .await;
+        assert!(matches!(resized, Err(ApiError::ValidationFailed(_))));
+
+        ResourceHandlers::<PersistentVolumeClaim>::patch(
+            State(state.clone()),
+            object(),
+            Json(serde_json::json!({"spec": {"volumeName": "pvc-1"}})),
+        )
+        .await
+        .unwrap();
+
+        let rebound = ResourceHandlers::<PersistentVolumeClaim>::patch(
+            State(state.clone()),
+            object(),
+            Json(serde_json::json!({"spec": {"volumeName": "pvc-2"}})),
+        )
+        .await;
+        assert!(matches!(rebound, Err(ApiError::ValidationFailed(_))));
+    }
+}
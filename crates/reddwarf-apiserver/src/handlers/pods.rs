@@ -1,137 +1,324 @@
 use crate::handlers::common::{
-    create_resource, delete_resource, get_resource, list_resources, update_resource, update_status,
-    ListResponse,
+    delete_resource, delete_resource_with, get_resource, update_resource,
 };
-use crate::response::{status_deleted, ApiResponse};
+use crate::handlers::disruption_budgets::check_disruption_budgets;
+use crate::handlers::generic::ResourceKind;
+use crate::handlers::image_mappings::image_mappings;
+use crate::handlers::priority_classes::{global_default_priority_class, priority_class_key};
+use crate::handlers::runtime_classes::runtime_class_key;
+use crate::response::{status_created, status_deleted, ApiResponse};
 use crate::validation::validate_resource;
-use crate::watch::{watch_resource_stream, WatchParams};
-use crate::{AppState, Result};
-use axum::extract::{Path, Query, State};
+use crate::{ApiError, AppState, Result};
+use async_trait::async_trait;
+use axum::extract::{Path, State};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use reddwarf_core::{GroupVersionKind, Pod, ResourceKey};
-use reddwarf_storage::KeyEncoder;
+use reddwarf_core::annotations::{zone_brand, ZONE_BRAND_ANNOTATION};
+use reddwarf_core::k8s_openapi::api::core::v1::PodCondition;
+use reddwarf_core::k8s_openapi::api::policy::v1::Eviction;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::DeleteOptions;
+use reddwarf_core::resources::PREEMPT_LOWER_PRIORITY;
+use reddwarf_core::{
+    pod_lx_image, pod_port_conflicts, pod_qos_class, GroupVersionKind, Pod, PriorityClass,
+    ResourceKey, RuntimeClass,
+};
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 const DEFAULT_TERMINATION_GRACE_PERIOD: i64 = 30;
 
-/// GET /api/v1/namespaces/{namespace}/pods/{name}
-pub async fn get_pod(
-    State(state): State<Arc<AppState>>,
-    Path((namespace, name)): Path<(String, String)>,
-) -> Result<Response> {
-    let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
-    let key = ResourceKey::new(gvk, namespace, name);
+/// Condition marking a pod that is being terminated by a disruption
+const DISRUPTION_TARGET: &str = "DisruptionTarget";
+/// `DisruptionTarget` reason for pods evicted through the Eviction API
+const EVICTION_BY_EVICTION_API: &str = "EvictionByEvictionAPI";
 
-    let pod: Pod = get_resource(&state, &key).await?;
-
-    Ok(ApiResponse::ok(pod).into_response())
-}
-
-/// GET /api/v1/namespaces/{namespace}/pods
-/// GET /api/v1/pods (all namespaces)
-pub async fn list_pods(
-    State(state): State<Arc<AppState>>,
-    Path(namespace): Path<Option<String>>,
-    Query(params): Query<WatchParams>,
-) -> Result<Response> {
-    if params.is_watch() {
-        let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
-        return Ok(watch_resource_stream(&state, gvk, namespace).into_response());
+/// Record the pod's QoS class in `status.qosClass`.
+///
+/// The class is derived from the (immutable) container resources, so it is
+/// computed once at admission and re-applied on status writes that omit it.
+fn stamp_qos_class(pod: &mut Pod) {
+    if pod.spec.is_none() {
+        return;
+    }
+    let qos = pod_qos_class(pod);
+    let status = pod.status.get_or_insert_with(Default::default);
+    if status.qos_class.is_none() {
+        status.qos_class = Some(qos.to_string());
     }
+}
 
-    let prefix = if let Some(ns) = namespace {
-        KeyEncoder::encode_prefix("v1", "Pod", Some(&ns))
-    } else {
-        KeyEncoder::encode_prefix("v1", "Pod", None)
+/// Apply the pod's RuntimeClass at admission: reject unknown classes, set
+/// `spec.overhead` from the class, and merge in its node selector and
+/// tolerations so the pod only schedules onto nodes that can run it.
+async fn admit_runtime_class(state: &AppState, pod: &mut Pod) -> Result<()> {
+    let Some(spec) = pod.spec.as_mut() else {
+        return Ok(());
+    };
+    let Some(class_name) = spec.runtime_class_name.clone() else {
+        if let Some(brand) = zone_brand(&pod.metadata) {
+            warn!(
+                "Pod {} uses the deprecated {} annotation (brand '{}'); set spec.runtimeClassName instead",
+                pod.metadata.name.as_deref().unwrap_or_default(),
+                ZONE_BRAND_ANNOTATION,
+                brand
+            );
+        }
+        return Ok(());
     };
 
-    let pods: Vec<Pod> = list_resources(&state, &prefix).await?;
-
-    let response = ListResponse::new("v1".to_string(), "PodList".to_string(), pods);
-
-    Ok(ApiResponse::ok(response).into_response())
-}
-
-/// POST /api/v1/namespaces/{namespace}/pods
-pub async fn create_pod(
-    State(state): State<Arc<AppState>>,
-    Path(namespace): Path<String>,
-    Json(mut pod): Json<Pod>,
-) -> Result<Response> {
-    info!("Creating pod in namespace: {}", namespace);
+    let class: RuntimeClass = match get_resource(state, &runtime_class_key(&class_name)).await {
+        Ok(class) => class,
+        Err(ApiError::NotFound(_)) => {
+            return Err(ApiError::BadRequest(format!(
+                "RuntimeClass '{}' not found",
+                class_name
+            )))
+        }
+        Err(e) => return Err(e),
+    };
 
-    // Ensure namespace matches
-    pod.metadata.namespace = Some(namespace.clone());
+    if let Some(fixed) = class.overhead.and_then(|o| o.pod_fixed) {
+        match &spec.overhead {
+            Some(overhead) if *overhead != fixed => {
+                return Err(ApiError::BadRequest(format!(
+                    "spec.overhead does not match the overhead of RuntimeClass '{}'",
+                    class_name
+                )))
+            }
+            _ => spec.overhead = Some(fixed),
+        }
+    }
 
-    // Validate
-    validate_resource(&pod)?;
+    if let Some(scheduling) = class.scheduling {
+        let selector = spec.node_selector.get_or_insert_with(Default::default);
+        for (key, value) in scheduling.node_selector.unwrap_or_default() {
+            match selector.get(&key) {
+                Some(existing) if *existing != value => {
+                    return Err(ApiError::BadRequest(format!(
+                        "nodeSelector {}={} conflicts with RuntimeClass '{}' ({}={})",
+                        key, existing, class_name, key, value
+                    )))
+                }
+                _ => {
+                    selector.insert(key, value);
+                }
+            }
+        }
+        if selector.is_empty() {
+            spec.node_selector = None;
+        }
 
-    // Create
-    let created = create_resource(&state, pod).await?;
+        let tolerations = spec.tolerations.get_or_insert_with(Vec::new);
+        for toleration in scheduling.tolerations.unwrap_or_default() {
+            if !tolerations.contains(&toleration) {
+                tolerations.push(toleration);
+            }
+        }
+        if tolerations.is_empty() {
+            spec.tolerations = None;
+        }
+    }
 
-    Ok(ApiResponse::created(created).into_response())
+    Ok(())
 }
 
-/// PUT /api/v1/namespaces/{namespace}/pods/{name}
-pub async fn replace_pod(
-    State(state): State<Arc<AppState>>,
-    Path((namespace, name)): Path<(String, String)>,
-    Json(mut pod): Json<Pod>,
-) -> Result<Response> {
-    info!("Replacing pod: {}/{}", namespace, name);
+/// Resolve the pod's priority at admission from the PriorityClass named by
+/// `spec.priorityClassName`, or from the global default class when it names
+/// none: set `spec.priority` and `spec.preemptionPolicy` from the class,
+/// rejecting unknown classes and values that contradict it.
+async fn admit_priority(state: &AppState, pod: &mut Pod) -> Result<()> {
+    let Some(spec) = pod.spec.as_mut() else {
+        return Ok(());
+    };
+    let class = match spec.priority_class_name.clone() {
+        Some(class_name) => {
+            match get_resource::<PriorityClass>(state, &priority_class_key(&class_name)).await {
+                Ok(class) => Some(class),
+                Err(ApiError::NotFound(_)) => {
+                    return Err(ApiError::BadRequest(format!(
+                        "PriorityClass '{}' not found",
+                        class_name
+                    )))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        None => global_default_priority_class(state).await?,
+    };
 
-    // Ensure metadata matches
-    pod.metadata.namespace = Some(namespace.clone());
-    pod.metadata.name = Some(name.clone());
+    let priority = class.as_ref().map_or(0, |c| c.value);
+    if spec.priority.is_some_and(|p| p != priority) {
+        return Err(ApiError::BadRequest(format!(
+            "spec.priority must not be set other than by its PriorityClass, which gives {}",
+            priority
+        )));
+    }
+    let preemption_policy = class
+        .as_ref()
+        .and_then(|c| c.preemption_policy.clone())
+        .unwrap_or_else(|| PREEMPT_LOWER_PRIORITY.to_string());
+    match &spec.preemption_policy {
+        Some(policy) if *policy != preemption_policy => {
+            return Err(ApiError::BadRequest(format!(
+                "spec.preemptionPolicy {} does not match the PriorityClass, which gives {}",
+                policy, preemption_policy
+            )))
+        }
+        _ => spec.preemption_policy = Some(preemption_policy),
+    }
 
-    // Validate
-    validate_resource(&pod)?;
+    spec.priority = Some(priority);
+    if let Some(class) = class {
+        spec.priority_class_name = class.metadata.name;
+    }
+    Ok(())
+}
 
-    // Update
-    let updated = update_resource(&state, pod).await?;
+/// Require the images of an lx-branded pod to resolve, through the
+/// ImageMappings, to the single lx image its zone is installed from, so a
+/// pod that no node could materialize is rejected up front.
+async fn admit_lx_image(state: &AppState, pod: &Pod) -> Result<()> {
+    let brand = match pod
+        .spec
+        .as_ref()
+        .and_then(|s| s.runtime_class_name.as_deref())
+    {
+        Some(class_name) => {
+            let class: RuntimeClass = get_resource(state, &runtime_class_key(class_name)).await?;
+            Some(class.handler)
+        }
+        None => zone_brand(&pod.metadata).map(str::to_string),
+    };
+    if brand.as_deref() != Some("lx") {
+        return Ok(());
+    }
 
-    Ok(ApiResponse::ok(updated).into_response())
+    let mappings = image_mappings(state).await?;
+    pod_lx_image(pod, &mappings).map_err(ApiError::BadRequest)?;
+    Ok(())
 }
 
-/// DELETE /api/v1/namespaces/{namespace}/pods/{name}
-///
-/// Initiates graceful termination: sets deletion_timestamp and phase=Terminating
-/// instead of immediately removing the pod from storage. The controller will
-/// drive the zone shutdown state machine and call finalize_pod() when cleanup
-/// is complete.
-pub async fn delete_pod(
-    State(state): State<Arc<AppState>>,
-    Path((namespace, name)): Path<(String, String)>,
-) -> Result<Response> {
-    info!("Deleting pod: {}/{}", namespace, name);
+/// Volume types the zone runtime can realize
+const SUPPORTED_VOLUME_TYPES: &[&str] = &["emptyDir", "persistentVolumeClaim"];
 
-    let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
-    let key = ResourceKey::new(gvk, namespace.clone(), name.clone());
+/// Reject pods whose spec no zone can realize, so they fail at create time
+/// with a message saying what to change instead of failing on the node.
+/// Every violation is reported at once.
+fn admit_zone_constraints(pod: &Pod) -> Result<()> {
+    let Some(spec) = pod.spec.as_ref() else {
+        return Ok(());
+    };
+    let mut violations = Vec::new();
+
+    for (i, volume) in spec.volumes.iter().flatten().enumerate() {
+        let source = serde_json::to_value(volume)
+            .ok()
+            .and_then(|v| v.as_object()?.keys().find(|k| *k != "name").cloned())
+            .unwrap_or_else(|| "none".to_string());
+        if !SUPPORTED_VOLUME_TYPES.contains(&source.as_str()) {
+            violations.push(format!(
+                "spec.volumes[{}]: volume '{}' is of type {}, which zones cannot mount (supported: {})",
+                i,
+                volume.name,
+                source,
+                SUPPORTED_VOLUME_TYPES.join(", ")
+            ));
+        }
+    }
 
-    let mut pod: Pod = get_resource(&state, &key).await?;
+    if spec.host_network == Some(true) {
+        violations.push(
+            "spec.hostNetwork: every zone has its own network stack; expose ports on the node with containers[].ports[].hostPort instead"
+                .to_string(),
+        );
+    }
+    for (field, set) in [("hostPID", spec.host_pid), ("hostIPC", spec.host_ipc)] {
+        if set == Some(true) {
+            violations.push(format!(
+                "spec.{}: zones cannot share the node's namespaces; remove it",
+                field
+            ));
+        }
+    }
 
-    // Idempotent: if deletion_timestamp is already set, return current state
-    if pod.metadata.deletion_timestamp.is_some() {
-        info!(
-            "Pod {}/{} already has deletion_timestamp set, returning current state",
-            namespace, name
+    if spec.os.as_ref().is_some_and(|os| os.name != "linux") {
+        violations.push(
+            "spec.os.name: zones only run Linux (lx) and illumos workloads; remove it or set it to linux"
+                .to_string(),
         );
-        return Ok(ApiResponse::ok(pod).into_response());
     }
+    if spec
+        .security_context
+        .as_ref()
+        .is_some_and(|sc| sc.windows_options.is_some())
+    {
+        violations.push(
+            "spec.securityContext.windowsOptions: not supported by zones; remove it".to_string(),
+        );
+    }
+
+    let containers = [
+        (
+            "initContainers",
+            spec.init_containers.as_deref().unwrap_or_default(),
+        ),
+        ("containers", spec.containers.as_slice()),
+    ];
+    for (field, containers) in containers {
+        for (i, container) in containers.iter().enumerate() {
+            let Some(sc) = container.security_context.as_ref() else {
+                continue;
+            };
+            if sc.privileged == Some(true) {
+                violations.push(format!(
+                    "spec.{}[{}].securityContext.privileged: zones cannot be granted the node's full privileges; remove it",
+                    field, i
+                ));
+            }
+            if sc.windows_options.is_some() {
+                violations.push(format!(
+                    "spec.{}[{}].securityContext.windowsOptions: not supported by zones; remove it",
+                    field, i
+                ));
+            }
+        }
+    }
+    violations.extend(pod_port_conflicts(pod));
 
-    // Set deletion metadata
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(ApiError::ValidationFailed(format!(
+        "Pod cannot run in a zone: {}",
+        violations.join("; ")
+    )))
+}
+
+/// Start graceful termination: set deletion_timestamp and phase=Terminating
+/// instead of removing the pod from storage. The controller drives the zone
+/// shutdown state machine and calls finalize_pod() when cleanup is complete.
+async fn begin_termination(
+    state: &AppState,
+    mut pod: Pod,
+    grace_period: Option<i64>,
+) -> Result<Pod> {
     pod.metadata.deletion_timestamp = Some(
         reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(chrono::Utc::now()),
     );
 
-    // Grace period from spec, defaulting to 30s
-    let grace_period = pod
-        .spec
-        .as_ref()
-        .and_then(|s| s.termination_grace_period_seconds)
+    // Grace period from the caller, else from spec, defaulting to 30s and
+    // capped by the cluster's ceiling
+    let grace_period = grace_period
+        .or_else(|| {
+            pod.spec
+                .as_ref()
+                .and_then(|s| s.termination_grace_period_seconds)
+        })
         .unwrap_or(DEFAULT_TERMINATION_GRACE_PERIOD);
+    let grace_period = match state.max_grace_period_seconds {
+        Some(max) => grace_period.min(max),
+        None => grace_period,
+    };
     pod.metadata.deletion_grace_period_seconds = Some(grace_period);
 
     // Set phase to Terminating
@@ -139,83 +326,247 @@ pub async fn delete_pod(
     status.phase = Some("Terminating".to_string());
 
     // Update resource — emits a MODIFIED event (not DELETED)
-    let updated = update_resource(&state, pod).await?;
+    update_resource(state, pod).await
+}
 
-    Ok(ApiResponse::ok(updated).into_response())
+/// Remove a pod from storage at once, skipping the termination state
+/// machine. The DELETED event carries the pod with a zero grace period, so
+/// the node's controller still tears down its zone and releases its IP and
+/// other allocations, on a best-effort basis.
+async fn force_delete(state: &AppState, key: &ResourceKey) -> Result<()> {
+    warn!("Force deleting pod {}/{}", key.namespace, key.name);
+    delete_resource_with(state, key, |object| {
+        let metadata = &mut object["metadata"];
+        if metadata["deletionTimestamp"].is_null() {
+            metadata["deletionTimestamp"] = serde_json::json!(chrono::Utc::now());
+        }
+        metadata["deletionGracePeriodSeconds"] = serde_json::json!(0);
+    })
+    .await
 }
 
-/// POST /api/v1/namespaces/{namespace}/pods/{name}/finalize
-///
-/// Performs the actual storage removal of a pod. Called by the controller after
-/// zone cleanup is complete.
-pub async fn finalize_pod(
-    State(state): State<Arc<AppState>>,
-    Path((namespace, name)): Path<(String, String)>,
-) -> Result<Response> {
-    info!("Finalizing pod: {}/{}", namespace, name);
+fn scheduling_gates(pod: &Pod) -> impl Iterator<Item = &str> {
+    pod.spec
+        .iter()
+        .flat_map(|spec| spec.scheduling_gates.iter().flatten())
+        .map(|gate| gate.name.as_str())
+}
 
-    let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
-    let key = ResourceKey::new(gvk, namespace, name.clone());
+/// A pod with scheduling gates must leave its placement to the scheduler
+fn validate_scheduling_gates(pod: &Pod) -> Result<()> {
+    let bound = pod
+        .spec
+        .as_ref()
+        .is_some_and(|spec| spec.node_name.is_some());
+    if bound && scheduling_gates(pod).next().is_some() {
+        return Err(ApiError::ValidationFailed(
+            "spec.schedulingGates: cannot be set if spec.nodeName is set".to_string(),
+        ));
+    }
+    Ok(())
+}
 
-    delete_resource(&state, &key).await?;
+#[async_trait]
+impl ResourceKind for Pod {
+    const API_VERSION: &'static str = "v1";
+    const KIND: &'static str = "Pod";
+    const PLURAL: &'static str = "pods";
+    const SHORT_NAMES: &'static [&'static str] = &["po"];
+    const NAMESPACED: bool = true;
+    const STATUS_SUBRESOURCE: bool = true;
+    const FIELD_SELECTORS: &'static [&'static str] = &[
+        "spec.nodeName",
+        "spec.restartPolicy",
+        "spec.schedulerName",
+        "spec.serviceAccountName",
+        "spec.hostNetwork",
+        "status.phase",
+        "status.podIP",
+        "status.nominatedNodeName",
+    ];
+
+    fn validate_object(pod: &Pod) -> Result<()> {
+        validate_resource(pod)?;
+        validate_scheduling_gates(pod)
+    }
 
-    Ok(status_deleted(&name, "Pod"))
-}
+    /// Scheduling gates are set at creation and may only be removed, the
+    /// last removal making the pod schedulable
+    fn validate_update(current: &Pod, pod: &Pod) -> Result<()> {
+        let current: Vec<&str> = scheduling_gates(current).collect();
+        match scheduling_gates(pod).find(|gate| !current.contains(gate)) {
+            Some(gate) => Err(ApiError::ValidationFailed(format!(
+                "spec.schedulingGates: only deletion is allowed, but found new scheduling gate '{}'",
+                gate
+            ))),
+            None => Ok(()),
+        }
+    }
 
-/// PUT /api/v1/namespaces/{namespace}/pods/{name}/status
-pub async fn update_pod_status(
-    State(state): State<Arc<AppState>>,
-    Path((namespace, name)): Path<(String, String)>,
-    Json(mut pod): Json<Pod>,
-) -> Result<Response> {
-    info!("Updating pod status: {}/{}", namespace, name);
+    async fn admit(state: &AppState, pod: &mut Pod) -> Result<()> {
+        admit_zone_constraints(pod)?;
+        admit_runtime_class(state, pod).await?;
+        admit_priority(state, pod).await?;
+        admit_lx_image(state, pod).await?;
+        stamp_qos_class(pod);
+        Ok(())
+    }
+
+    fn prepare_status(pod: &mut Pod) {
+        if pod.status.is_some() {
+            stamp_qos_class(pod);
+        }
+    }
+
+    /// Initiates graceful termination (see [`begin_termination`]); a zero
+    /// grace period force deletes the pod (see [`force_delete`])
+    async fn delete(
+        state: &AppState,
+        key: &ResourceKey,
+        options: &DeleteOptions,
+    ) -> Result<Response> {
+        let pod: Pod = get_resource(state, key).await?;
+
+        if options.grace_period_seconds == Some(0) {
+            force_delete(state, key).await?;
+            return Ok(status_deleted(&key.name, "Pod"));
+        }
 
-    // Ensure metadata matches the URL path
-    pod.metadata.namespace = Some(namespace);
-    pod.metadata.name = Some(name);
+        // Idempotent: if deletion_timestamp is already set, return current state
+        if pod.metadata.deletion_timestamp.is_some() {
+            info!(
+                "Pod {}/{} already has deletion_timestamp set, returning current state",
+                key.namespace, key.name
+            );
+            return Ok(ApiResponse::ok(pod).into_response());
+        }
 
-    let updated = update_status(&state, pod).await?;
+        let updated = begin_termination(state, pod, options.grace_period_seconds).await?;
 
-    Ok(ApiResponse::ok(updated).into_response())
+        Ok(ApiResponse::ok(updated).into_response())
+    }
 }
 
-/// PATCH /api/v1/namespaces/{namespace}/pods/{name}
-pub async fn patch_pod(
+/// POST /api/v1/namespaces/{namespace}/pods/{name}/eviction
+///
+/// Evicts a pod through the Eviction subresource, so drains and other
+/// controllers go through one place instead of issuing DELETEs. The pod is
+/// terminated gracefully (honouring `deleteOptions.gracePeriodSeconds` and a
+/// UID precondition) and carries a `DisruptionTarget` condition recording
+/// the eviction. An eviction that would break a PodDisruptionBudget covering
+/// the pod is refused with 429, for the caller to retry later.
+pub async fn evict_pod(
     State(state): State<Arc<AppState>>,
     Path((namespace, name)): Path<(String, String)>,
-    Json(patch): Json<serde_json::Value>,
+    Json(eviction): Json<Eviction>,
 ) -> Result<Response> {
-    info!("Patching pod: {}/{}", namespace, name);
+    if eviction
+        .metadata
+        .name
+        .as_deref()
+        .is_some_and(|evicted| evicted != name)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Eviction name does not match pod {}/{}",
+            namespace, name
+        )));
+    }
 
-    // Get current pod
     let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
     let key = ResourceKey::new(gvk, namespace.clone(), name.clone());
-
+    // Held until the pod is terminating, so concurrent evictions are
+    // checked against each other's outcome
+    let _update = state.update_lock.lock().await;
     let mut pod: Pod = get_resource(&state, &key).await?;
 
-    // Apply patch (simplified - just merge JSON)
-    let mut pod_json = serde_json::to_value(&pod)?;
-    json_patch::merge(&mut pod_json, &patch);
+    let options = eviction.delete_options.unwrap_or_default();
+    if options.grace_period_seconds.is_some_and(|g| g < 0) {
+        return Err(ApiError::BadRequest(
+            "gracePeriodSeconds must not be negative".to_string(),
+        ));
+    }
+    if let Some(uid) = options.preconditions.and_then(|p| p.uid) {
+        if pod.metadata.uid.as_deref() != Some(uid.as_str()) {
+            return Err(ApiError::Conflict(format!(
+                "Pod {}/{} does not have UID {}",
+                namespace, name, uid
+            )));
+        }
+    }
+
+    // Terminating and finished pods disrupt nothing
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    if pod.metadata.deletion_timestamp.is_none() && !matches!(phase, Some("Succeeded" | "Failed")) {
+        check_disruption_budgets(&state, &pod).await?;
+    }
+
+    // A zero grace period evicts at once; evicting a terminating pod is
+    // otherwise a no-op
+    if options.grace_period_seconds == Some(0) {
+        force_delete(&state, &key).await?;
+    } else if pod.metadata.deletion_timestamp.is_none() {
+        info!(
+            "Evicting pod {}/{} (grace period: {:?})",
+            namespace, name, options.grace_period_seconds
+        );
+        let status = pod.status.get_or_insert_with(Default::default);
+        let conditions = status.conditions.get_or_insert_with(Vec::new);
+        conditions.retain(|c| c.type_ != DISRUPTION_TARGET);
+        conditions.push(PodCondition {
+            type_: DISRUPTION_TARGET.to_string(),
+            status: "True".to_string(),
+            reason: Some(EVICTION_BY_EVICTION_API.to_string()),
+            message: Some("Eviction API: evicting".to_string()),
+            last_transition_time: Some(
+                reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(
+                    chrono::Utc::now(),
+                ),
+            ),
+            ..Default::default()
+        });
+        begin_termination(&state, pod, options.grace_period_seconds).await?;
+    }
+
+    Ok(status_created(&format!("Pod {} evicted", name)))
+}
 
-    // Deserialize back
-    pod = serde_json::from_value(pod_json)?;
+/// POST /api/v1/namespaces/{namespace}/pods/{name}/finalize
+///
+/// Performs the actual storage removal of a pod. Called by the controller after
+/// zone cleanup is complete.
+pub async fn finalize_pod(
+    State(state): State<Arc<AppState>>,
+    Path((namespace, name)): Path<(String, String)>,
+) -> Result<Response> {
+    info!("Finalizing pod: {}/{}", namespace, name);
 
-    // Validate
-    validate_resource(&pod)?;
+    let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
+    let key = ResourceKey::new(gvk, namespace, name.clone());
 
-    // Update
-    let updated = update_resource(&state, pod).await?;
+    delete_resource(&state, &key).await?;
 
-    Ok(ApiResponse::ok(updated).into_response())
+    Ok(status_deleted(&name, "Pod"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::watch::WatchEventType;
-    use reddwarf_core::k8s_openapi::api::core::v1::PodStatus;
+    use crate::handlers::common::{create_resource, list_resources, update_status, ListPath};
+    use crate::handlers::generic::{DeleteParams, ObjectPath, ResourceHandlers};
+    use crate::watch::{WatchEventType, WatchParams};
+    use axum::body::Bytes;
+    use axum::extract::Query;
+    use axum::http::{header, HeaderMap, HeaderValue};
+    use reddwarf_core::k8s_openapi::api::core::v1::{
+        Container, ContainerPort, HostPathVolumeSource, PersistentVolumeClaimVolumeSource,
+        PodCondition, PodSchedulingGate, PodStatus, SecurityContext, Volume,
+    };
+    use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::{
+        DeleteOptions, Preconditions,
+    };
+    use reddwarf_core::PodDisruptionBudget;
     use reddwarf_core::Resource;
-    use reddwarf_storage::RedbBackend;
+    use reddwarf_storage::{KeyEncoder, RedbBackend};
     use reddwarf_versioning::VersionStore;
     use std::sync::Arc;
     use tempfile::tempdir;
@@ -238,17 +589,68 @@ mod tests {
         pod
     }
 
+    #[tokio::test]
+    async fn test_scheduling_gates_may_only_be_removed() {
+        let state = setup_state().await;
+
+        let mut pod = make_test_pod("gated", "default");
+        let spec = pod.spec.as_mut().unwrap();
+        spec.containers[0].name = "app".to_string();
+        spec.containers[0].image = Some("app:latest".to_string());
+        spec.scheduling_gates = Some(
+            ["quota", "capacity"]
+                .into_iter()
+                .map(|name| PodSchedulingGate {
+                    name: name.to_string(),
+                })
+                .collect(),
+        );
+
+        let mut bound = pod.clone();
+        bound.spec.as_mut().unwrap().node_name = Some("node1".to_string());
+        assert!(matches!(
+            Pod::validate_object(&bound),
+            Err(ApiError::ValidationFailed(_))
+        ));
+        create_resource(&state, pod).await.unwrap();
+
+        let patch = |gates: serde_json::Value| {
+            ResourceHandlers::<Pod>::patch(
+                State(state.clone()),
+                Path(ObjectPath {
+                    namespace: Some("default".to_string()),
+                    name: "gated".to_string(),
+                }),
+                Json(serde_json::json!({"spec": {"schedulingGates": gates}})),
+            )
+        };
+        let added = patch(serde_json::json!([{"name": "quota"}, {"name": "other"}])).await;
+        assert!(matches!(added, Err(ApiError::ValidationFailed(_))));
+        patch(serde_json::json!([{"name": "capacity"}]))
+            .await
+            .unwrap();
+        patch(serde_json::Value::Null).await.unwrap();
+
+        let key = ResourceKey::new(
+            GroupVersionKind::from_api_version_kind("v1", "Pod"),
+            "default",
+            "gated",
+        );
+        let pod: Pod = get_resource(&state, &key).await.unwrap();
+        assert_eq!(pod.spec.unwrap().scheduling_gates, None);
+    }
+
     #[tokio::test]
     async fn test_create_and_get_pod() {
         let state = setup_state().await;
 
         let pod = make_test_pod("test-pod", "default");
-        let created = create_resource(&*state, pod).await.unwrap();
+        let created = create_resource(&state, pod).await.unwrap();
         assert!(created.resource_version().is_some());
 
         let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
         let key = ResourceKey::new(gvk, "default", "test-pod");
-        let retrieved: Pod = get_resource(&*state, &key).await.unwrap();
+        let retrieved: Pod = get_resource(&state, &key).await.unwrap();
 
         assert_eq!(retrieved.metadata.name, Some("test-pod".to_string()));
     }
@@ -259,15 +661,350 @@ mod tests {
 
         for i in 0..3 {
             let pod = make_test_pod(&format!("test-pod-{}", i), "default");
-            create_resource(&*state, pod).await.unwrap();
+            create_resource(&state, pod).await.unwrap();
         }
 
         let prefix = KeyEncoder::encode_prefix("v1", "Pod", Some("default"));
-        let pods: Vec<Pod> = list_resources(&*state, &prefix).await.unwrap();
+        let pods: Vec<Pod> = list_resources(&state, &prefix).await.unwrap();
 
         assert_eq!(pods.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_watch_resumes_from_list_resource_version() {
+        use axum::body::to_bytes;
+        use futures_util::StreamExt;
+
+        let state = setup_state().await;
+        let mut first = create_resource(&state, make_test_pod("first", "default"))
+            .await
+            .unwrap();
+
+        let list = |params: WatchParams, headers: HeaderMap| {
+            ResourceHandlers::<Pod>::list(
+                State(state.clone()),
+                Path(ListPath {
+                    namespace: Some("default".to_string()),
+                }),
+                Query(params),
+                headers,
+            )
+        };
+        // Read from a watch until it has sent `events` lines starting with
+        // `prefix`
+        let read = |response: Response, prefix: &'static str, events: usize| async move {
+            let mut body = response.into_body().into_data_stream();
+            let mut text = String::new();
+            while text.lines().filter(|l| l.starts_with(prefix)).count() < events {
+                let chunk = tokio::time::timeout(std::time::Duration::from_secs(2), body.next())
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .unwrap();
+                text.push_str(&String::from_utf8_lossy(&chunk));
+            }
+            text
+        };
+        let body = to_bytes(
+            list(WatchParams::default(), HeaderMap::new())
+                .await
+                .unwrap()
+                .into_body(),
+            usize::MAX,
+        )
+        .await
+        .unwrap();
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let rv = listed["metadata"]["resourceVersion"].as_str().unwrap();
+        assert_eq!(
+            Some(rv),
+            first.resource_version().as_ref().map(|v| v.0.as_str())
+        );
+
+        // Changes between the list and the watch
+        create_resource(&state, make_test_pod("second", "default"))
+            .await
+            .unwrap();
+        create_resource(&state, make_test_pod("elsewhere", "other"))
+            .await
+            .unwrap();
+        first.metadata.labels = Some([("app".to_string(), "web".to_string())].into());
+        let updated = update_resource(&state, first).await.unwrap();
+
+        let watch = |resource_version: &str| WatchParams {
+            watch: Some("true".to_string()),
+            resource_version: Some(resource_version.to_string()),
+            ..Default::default()
+        };
+
+        // Chunked JSON, one event per line, by default
+        let response = list(watch(rv), HeaderMap::new()).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let text = read(response, "{", 2).await;
+        let events: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(events[0]["type"], "ADDED");
+        assert_eq!(events[0]["object"]["metadata"]["name"], "second");
+        assert_eq!(events[1]["type"], "MODIFIED");
+        let updated_rv = updated.resource_version().unwrap().0;
+        assert_eq!(
+            events[1]["object"]["metadata"]["resourceVersion"].as_str(),
+            Some(updated_rv.as_str())
+        );
+
+        // Server-Sent Events when accepted, each with its commit as ID; a
+        // reconnecting EventSource resumes from its Last-Event-ID rather
+        // than the resource version of the URL it first opened
+        let second_rv = events[0]["object"]["metadata"]["resourceVersion"]
+            .as_str()
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/event-stream"),
+        );
+        headers.insert("last-event-id", HeaderValue::from_str(second_rv).unwrap());
+        let response = list(watch("unknown"), headers).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let text = read(response, "data: ", 1).await;
+        assert!(text.contains(&format!("id: {}\n", updated_rv)), "{}", text);
+        assert!(!text.contains(second_rv), "{}", text);
+
+        // A resource version that is not a commit must be relisted
+        let gone = list(watch("unknown"), HeaderMap::new()).await;
+        assert!(matches!(gone, Err(ApiError::Gone(_))));
+    }
+
+    #[tokio::test]
+    async fn test_lagging_watch_ends_and_resumes() {
+        use crate::event_bus::EventBusConfig;
+        use axum::body::to_bytes;
+        use futures_util::StreamExt;
+
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::with_event_bus_config(
+            storage,
+            version_store,
+            EventBusConfig { capacity: 2 },
+        ));
+        let first = create_resource(&state, make_test_pod("first", "default"))
+            .await
+            .unwrap();
+        let rv = first.resource_version().unwrap().0;
+
+        let watch = |resource_version: Option<String>| {
+            ResourceHandlers::<Pod>::list(
+                State(state.clone()),
+                Path(ListPath {
+                    namespace: Some("default".to_string()),
+                }),
+                Query(WatchParams {
+                    watch: Some("true".to_string()),
+                    resource_version,
+                    ..Default::default()
+                }),
+                HeaderMap::new(),
+            )
+        };
+
+        // More changes than the event bus holds before the watcher reads
+        let response = watch(None).await.unwrap();
+        for i in 0..4 {
+            create_resource(&state, make_test_pod(&format!("pod-{}", i), "default"))
+                .await
+                .unwrap();
+        }
+        let body = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            to_bytes(response.into_body(), usize::MAX),
+        )
+        .await
+        .expect("a lagging watch ends")
+        .unwrap();
+        assert!(body.is_empty(), "{}", String::from_utf8_lossy(&body));
+
+        // Resuming replays every change it missed, in order
+        let response = watch(Some(rv)).await.unwrap();
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while text.lines().count() < 4 {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(2), body.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        let events: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let names: Vec<_> = events
+            .iter()
+            .map(|e| e["object"]["metadata"]["name"].as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [Some("pod-0"), Some("pod-1"), Some("pod-2"), Some("pod-3")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_timeout_seconds() {
+        use axum::body::to_bytes;
+        use futures_util::StreamExt;
+        use std::time::Duration;
+
+        let state = setup_state().await;
+        let watch = |timeout_seconds: u64| {
+            ResourceHandlers::<Pod>::list(
+                State(state.clone()),
+                Path(ListPath::default()),
+                Query(WatchParams {
+                    watch: Some("true".to_string()),
+                    timeout_seconds: Some(timeout_seconds),
+                    ..Default::default()
+                }),
+                HeaderMap::new(),
+            )
+        };
+
+        let response = watch(1).await.unwrap();
+        let body = tokio::time::timeout(
+            Duration::from_secs(3),
+            to_bytes(response.into_body(), usize::MAX),
+        )
+        .await
+        .expect("the watch ends after timeoutSeconds");
+        assert!(body.unwrap().is_empty());
+
+        // 0 is no timeout: the watch stays open for later changes
+        let response = watch(0).await.unwrap();
+        let mut body = response.into_body().into_data_stream();
+        create_resource(&state, make_test_pod("web", "default"))
+            .await
+            .unwrap();
+        let chunk = tokio::time::timeout(Duration::from_secs(2), body.next())
+            .await
+            .expect("an event")
+            .unwrap()
+            .unwrap();
+        let event: serde_json::Value = serde_json::from_slice(&chunk).unwrap();
+        assert_eq!(event["type"], "ADDED");
+        assert_eq!(event["object"]["metadata"]["name"], "web");
+    }
+
+    #[tokio::test]
+    async fn test_list_and_watch_select_fields() {
+        use futures_util::StreamExt;
+
+        let state = setup_state().await;
+        let list = |selector: &str, watch_from: Option<&str>| {
+            ResourceHandlers::<Pod>::list(
+                State(state.clone()),
+                Path(ListPath::default()),
+                Query(WatchParams {
+                    watch: watch_from.map(|_| "true".to_string()),
+                    resource_version: watch_from.map(str::to_string),
+                    field_selector: Some(selector.to_string()),
+                    ..Default::default()
+                }),
+                HeaderMap::new(),
+            )
+        };
+        let names = |response: Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let list: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let mut names: Vec<String> = list["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["metadata"]["name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let mut pods = Vec::new();
+        for (name, namespace, node) in [
+            ("web", "default", Some("node1")),
+            ("db", "other", Some("node1")),
+            ("cache", "default", Some("node2")),
+            ("pending", "default", None),
+        ] {
+            let mut pod = make_test_pod(name, namespace);
+            pod.spec.as_mut().unwrap().node_name = node.map(str::to_string);
+            pods.push(create_resource(&state, pod).await.unwrap());
+        }
+
+        let on_node1 = list("spec.nodeName=node1", None).await.unwrap();
+        assert_eq!(names(on_node1).await, ["db", "web"]);
+        let unbound = list("spec.nodeName=,metadata.namespace=default", None)
+            .await
+            .unwrap();
+        assert_eq!(names(unbound).await, ["pending"]);
+        let elsewhere = list("spec.nodeName!=node1", None).await.unwrap();
+        assert_eq!(names(elsewhere).await, ["cache", "pending"]);
+        let unsupported = list("spec.hostname=web", None).await;
+        assert!(matches!(unsupported, Err(ApiError::BadRequest(_))));
+
+        // Entering the selection is an addition to a watch, leaving it a
+        // deletion, and changes outside it are not sent
+        let since = pods[3].resource_version().unwrap().0;
+        for (i, phase) in [(0, "Running"), (2, "Pending"), (0, "Succeeded")] {
+            let mut pod = pods[i].clone();
+            pod.status = Some(PodStatus {
+                phase: Some(phase.to_string()),
+                ..Default::default()
+            });
+            pods[i] = update_resource(&state, pod).await.unwrap();
+        }
+        let response = list("status.phase=Running", Some(&since)).await.unwrap();
+        let mut body = response.into_body().into_data_stream();
+        let mut events = Vec::new();
+        while events.len() < 3 {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(2), body.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            for line in String::from_utf8_lossy(&chunk).lines() {
+                let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                events.push((
+                    event["type"].as_str().unwrap().to_string(),
+                    event["object"]["metadata"]["name"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                ));
+            }
+            if events.len() == 2 {
+                // A live change entering the selection
+                let mut pod = pods[1].clone();
+                pod.status = Some(PodStatus {
+                    phase: Some("Running".to_string()),
+                    ..Default::default()
+                });
+                update_resource(&state, pod).await.unwrap();
+            }
+        }
+        let expected = [("ADDED", "web"), ("DELETED", "web"), ("ADDED", "db")];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(t, n)| (t.to_string(), n.to_string()))
+            .collect();
+        assert_eq!(events, expected);
+    }
+
     #[tokio::test]
     async fn test_update_pod_status_changes_phase_not_spec() {
         let state = setup_state().await;
@@ -275,7 +1012,7 @@ mod tests {
         // Create a pod with spec
         let mut pod = make_test_pod("status-test", "default");
         pod.spec.as_mut().unwrap().containers[0].name = "nginx".to_string();
-        let created = create_resource(&*state, pod).await.unwrap();
+        let created = create_resource(&state, pod).await.unwrap();
         let original_version = created.resource_version();
 
         // Update status only
@@ -286,7 +1023,7 @@ mod tests {
             ..Default::default()
         });
 
-        let updated = update_status(&*state, status_pod).await.unwrap();
+        let updated = update_status(&state, status_pod).await.unwrap();
 
         // Status should be updated
         assert_eq!(
@@ -305,12 +1042,240 @@ mod tests {
         assert_ne!(updated.resource_version(), original_version);
     }
 
+    #[tokio::test]
+    async fn test_admit_runtime_class() {
+        use reddwarf_core::k8s_openapi::api::node::v1::{Overhead, Scheduling};
+        use reddwarf_core::k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+        let state = setup_state().await;
+        let mut pod = make_test_pod("linux", "default");
+        pod.spec.as_mut().unwrap().runtime_class_name = Some("lx".to_string());
+        pod.spec.as_mut().unwrap().node_selector =
+            Some([("disk".to_string(), "ssd".to_string())].into());
+
+        assert!(matches!(
+            admit_runtime_class(&state, &mut pod).await,
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let mut class = RuntimeClass {
+            handler: "lx".to_string(),
+            overhead: Some(Overhead {
+                pod_fixed: Some([("memory".to_string(), Quantity("64Mi".to_string()))].into()),
+            }),
+            scheduling: Some(Scheduling {
+                node_selector: Some([("lx".to_string(), "true".to_string())].into()),
+                tolerations: None,
+            }),
+            ..Default::default()
+        };
+        class.metadata.name = Some("lx".to_string());
+        create_resource(&state, class).await.unwrap();
+
+        admit_runtime_class(&state, &mut pod).await.unwrap();
+        let spec = pod.spec.as_ref().unwrap();
+        assert_eq!(spec.overhead.as_ref().unwrap()["memory"].0, "64Mi");
+        let selector = spec.node_selector.as_ref().unwrap();
+        assert_eq!(selector["disk"], "ssd");
+        assert_eq!(selector["lx"], "true");
+
+        // A pod selector contradicting the class is rejected
+        let mut pod = make_test_pod("conflict", "default");
+        pod.spec.as_mut().unwrap().runtime_class_name = Some("lx".to_string());
+        pod.spec.as_mut().unwrap().node_selector =
+            Some([("lx".to_string(), "false".to_string())].into());
+        assert!(admit_runtime_class(&state, &mut pod).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_admit_priority() {
+        use reddwarf_core::resources::PREEMPT_NEVER;
+
+        let state = setup_state().await;
+        let mut pod = make_test_pod("plain", "default");
+        admit_priority(&state, &mut pod).await.unwrap();
+        let spec = pod.spec.as_ref().unwrap();
+        assert_eq!(spec.priority, Some(0));
+        assert_eq!(
+            spec.preemption_policy.as_deref(),
+            Some(PREEMPT_LOWER_PRIORITY)
+        );
+
+        let mut pod = make_test_pod("batch", "default");
+        pod.spec.as_mut().unwrap().priority_class_name = Some("batch".to_string());
+        assert!(matches!(
+            admit_priority(&state, &mut pod).await,
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let mut class = PriorityClass {
+            value: -10,
+            global_default: Some(true),
+            preemption_policy: Some(PREEMPT_NEVER.to_string()),
+            ..Default::default()
+        };
+        class.metadata.name = Some("batch".to_string());
+        create_resource(&state, class).await.unwrap();
+        admit_priority(&state, &mut pod).await.unwrap();
+        assert_eq!(pod.spec.as_ref().unwrap().priority, Some(-10));
+
+        // The global default applies to pods naming no class
+        let mut pod = make_test_pod("defaulted", "default");
+        admit_priority(&state, &mut pod).await.unwrap();
+        let spec = pod.spec.as_ref().unwrap();
+        assert_eq!(spec.priority_class_name.as_deref(), Some("batch"));
+        assert_eq!(spec.priority, Some(-10));
+        assert_eq!(spec.preemption_policy.as_deref(), Some(PREEMPT_NEVER));
+
+        // A priority contradicting the class is rejected
+        let mut pod = make_test_pod("forged", "default");
+        pod.spec.as_mut().unwrap().priority = Some(1000);
+        assert!(admit_priority(&state, &mut pod).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_admit_lx_image() {
+        use reddwarf_core::{ImageMapping, ImageMappingSpec};
+
+        let state = setup_state().await;
+        let mut pod = make_test_pod("lx", "default");
+        pod.metadata.annotations =
+            Some([(ZONE_BRAND_ANNOTATION.to_string(), "lx".to_string())].into());
+        pod.spec.as_mut().unwrap().containers[0].image = Some("alpine:3.20".to_string());
+        assert!(matches!(
+            Pod::admit(&state, &mut pod).await,
+            Err(ApiError::BadRequest(_))
+        ));
+
+        let mapping = ImageMapping::new(
+            "alpine",
+            ImageMappingSpec {
+                repository: "alpine".to_string(),
+                tags: [("3.20".to_string(), "/images/alpine-3.20.tar.gz".to_string())].into(),
+            },
+        );
+        create_resource(&state, mapping).await.unwrap();
+        Pod::admit(&state, &mut pod).await.unwrap();
+
+        // Images of other brands are not resolved
+        let mut pod = make_test_pod("native", "default");
+        pod.spec.as_mut().unwrap().containers[0].image = Some("ubuntu:24.04".to_string());
+        Pod::admit(&state, &mut pod).await.unwrap();
+    }
+
+    #[test]
+    fn test_admit_zone_constraints() {
+        let pod = make_test_pod("plain", "default");
+        assert!(admit_zone_constraints(&pod).is_ok());
+
+        let mut pod = make_test_pod("unrealizable", "default");
+        let spec = pod.spec.as_mut().unwrap();
+        spec.host_network = Some(true);
+        spec.volumes = Some(vec![
+            Volume {
+                name: "scratch".to_string(),
+                empty_dir: Some(Default::default()),
+                ..Default::default()
+            },
+            Volume {
+                name: "data".to_string(),
+                persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                    claim_name: "data".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Volume {
+                name: "docker-sock".to_string(),
+                host_path: Some(HostPathVolumeSource {
+                    path: "/var/run/docker.sock".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ]);
+        spec.containers[0].security_context = Some(SecurityContext {
+            privileged: Some(true),
+            ..Default::default()
+        });
+        spec.containers.push(Container {
+            name: "sidecar".to_string(),
+            ports: Some(vec![ContainerPort {
+                container_port: 80,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        });
+        spec.containers[0].ports = spec.containers[1].ports.clone();
+
+        let Err(ApiError::ValidationFailed(message)) = admit_zone_constraints(&pod) else {
+            panic!("pod should be rejected");
+        };
+        assert!(message.contains("spec.volumes[2]: volume 'docker-sock' is of type hostPath"));
+        assert!(!message.contains("scratch"));
+        assert!(!message.contains("'data'"));
+        assert!(message.contains("spec.hostNetwork"));
+        assert!(message.contains("spec.containers[0].securityContext.privileged"));
+        assert!(message.contains("spec.containers[1].ports[0]: containerPort 80/TCP"));
+    }
+
+    #[test]
+    fn test_stamp_qos_class_preserves_existing() {
+        let mut pod = make_test_pod("qos", "default");
+        stamp_qos_class(&mut pod);
+        assert_eq!(
+            pod.status.as_ref().unwrap().qos_class.as_deref(),
+            Some("BestEffort")
+        );
+
+        pod.status.as_mut().unwrap().qos_class = Some("Guaranteed".to_string());
+        stamp_qos_class(&mut pod);
+        assert_eq!(
+            pod.status.as_ref().unwrap().qos_class.as_deref(),
+            Some("Guaranteed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_pod_status_replaces_status_annotations_only() {
+        let state = setup_state().await;
+
+        let mut pod = make_test_pod("annotated", "default");
+        pod.metadata.annotations = Some(
+            [
+                ("team".to_string(), "web".to_string()),
+                ("status.reddwarf.io/maxbw".to_string(), "5M".to_string()),
+            ]
+            .into(),
+        );
+        let created = create_resource(&state, pod).await.unwrap();
+
+        let mut status_pod = created.clone();
+        status_pod.metadata.annotations = Some(
+            [
+                ("team".to_string(), "changed".to_string()),
+                ("status.reddwarf.io/egress-bandwidth".to_string(), "1000".to_string()),
+            ]
+            .into(),
+        );
+        status_pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            ..Default::default()
+        });
+
+        let updated = update_status(&state, status_pod).await.unwrap();
+        let annotations = updated.metadata.annotations.unwrap();
+        assert_eq!(annotations["team"], "web");
+        assert_eq!(annotations["status.reddwarf.io/egress-bandwidth"], "1000");
+        assert!(!annotations.contains_key("status.reddwarf.io/maxbw"));
+    }
+
     #[tokio::test]
     async fn test_update_pod_status_bumps_resource_version() {
         let state = setup_state().await;
 
         let pod = make_test_pod("version-test", "default");
-        let created = create_resource(&*state, pod).await.unwrap();
+        let created = create_resource(&state, pod).await.unwrap();
         let v1 = created.resource_version();
 
         // First status update
@@ -319,7 +1284,7 @@ mod tests {
             phase: Some("Running".to_string()),
             ..Default::default()
         });
-        let updated1 = update_status(&*state, update1).await.unwrap();
+        let updated1 = update_status(&state, update1).await.unwrap();
         let v2 = updated1.resource_version();
 
         assert_ne!(v1, v2);
@@ -330,7 +1295,7 @@ mod tests {
             phase: Some("Succeeded".to_string()),
             ..Default::default()
         });
-        let updated2 = update_status(&*state, update2).await.unwrap();
+        let updated2 = update_status(&state, update2).await.unwrap();
         let v3 = updated2.resource_version();
 
         assert_ne!(v2, v3);
@@ -341,7 +1306,7 @@ mod tests {
         let state = setup_state().await;
 
         let pod = make_test_pod("event-test", "default");
-        let created = create_resource(&*state, pod).await.unwrap();
+        let created = create_resource(&state, pod).await.unwrap();
 
         // Subscribe after create
         let mut rx = state.subscribe();
@@ -351,7 +1316,7 @@ mod tests {
             phase: Some("Running".to_string()),
             ..Default::default()
         });
-        update_status(&*state, status_pod).await.unwrap();
+        update_status(&state, status_pod).await.unwrap();
 
         let event = rx.recv().await.unwrap();
         assert!(matches!(event.event_type, WatchEventType::Modified));
@@ -363,13 +1328,13 @@ mod tests {
         let state = setup_state().await;
 
         let pod = make_test_pod("graceful-pod", "default");
-        create_resource(&*state, pod).await.unwrap();
+        create_resource(&state, pod).await.unwrap();
 
         // Simulate what delete_pod handler does: read, set deletion_timestamp, update
         let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
         let key = ResourceKey::new(gvk.clone(), "default", "graceful-pod");
 
-        let mut pod: Pod = get_resource(&*state, &key).await.unwrap();
+        let mut pod: Pod = get_resource(&state, &key).await.unwrap();
         assert!(pod.metadata.deletion_timestamp.is_none());
 
         // Set deletion metadata
@@ -382,10 +1347,10 @@ mod tests {
         let status = pod.status.get_or_insert_with(Default::default);
         status.phase = Some("Terminating".to_string());
 
-        let updated = update_resource(&*state, pod).await.unwrap();
+        let updated = update_resource(&state, pod).await.unwrap();
 
         // Pod should still exist in storage
-        let retrieved: Pod = get_resource(&*state, &key).await.unwrap();
+        let retrieved: Pod = get_resource(&state, &key).await.unwrap();
         assert!(retrieved.metadata.deletion_timestamp.is_some());
         assert_eq!(retrieved.metadata.deletion_grace_period_seconds, Some(30));
         assert_eq!(
@@ -397,18 +1362,179 @@ mod tests {
         assert!(updated.resource_version().is_some());
     }
 
+    #[tokio::test]
+    async fn test_evict_pod_terminates_gracefully() {
+        let state = setup_state().await;
+        create_resource(&state, make_test_pod("evicted", "default"))
+            .await
+            .unwrap();
+        let key = ResourceKey::new(
+            GroupVersionKind::from_api_version_kind("v1", "Pod"),
+            "default",
+            "evicted",
+        );
+        let path = || Path(("default".to_string(), "evicted".to_string()));
+        let eviction = |grace: i64, uid: Option<&str>| {
+            let mut eviction = Eviction::default();
+            eviction.metadata.name = Some("evicted".to_string());
+            eviction.delete_options = Some(DeleteOptions {
+                grace_period_seconds: Some(grace),
+                preconditions: uid.map(|uid| Preconditions {
+                    uid: Some(uid.to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            Json(eviction)
+        };
+
+        // A stale UID precondition and a negative grace period are rejected
+        let result = evict_pod(State(state.clone()), path(), eviction(5, Some("stale"))).await;
+        assert!(matches!(result, Err(ApiError::Conflict(_))));
+        let result = evict_pod(State(state.clone()), path(), eviction(-1, None)).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let response = evict_pod(State(state.clone()), path(), eviction(5, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::CREATED);
+        let pod: Pod = get_resource(&state, &key).await.unwrap();
+        assert!(pod.metadata.deletion_timestamp.is_some());
+        assert_eq!(pod.metadata.deletion_grace_period_seconds, Some(5));
+        let status = pod.status.unwrap();
+        assert_eq!(status.phase.as_deref(), Some("Terminating"));
+        let condition = &status.conditions.unwrap()[0];
+        assert_eq!(condition.type_, "DisruptionTarget");
+        assert_eq!(condition.reason.as_deref(), Some("EvictionByEvictionAPI"));
+
+        // Evicting again leaves the terminating pod alone
+        evict_pod(State(state.clone()), path(), eviction(60, None))
+            .await
+            .unwrap();
+        let pod: Pod = get_resource(&state, &key).await.unwrap();
+        assert_eq!(pod.metadata.deletion_grace_period_seconds, Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_evict_pod_honours_disruption_budget() {
+        let state = setup_state().await;
+        for name in ["web-1", "web-2"] {
+            let mut pod = make_test_pod(name, "default");
+            pod.metadata.labels = Some([("app".to_string(), "web".to_string())].into());
+            pod.status = Some(PodStatus {
+                phase: Some("Running".to_string()),
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: "True".to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            });
+            create_resource(&state, pod).await.unwrap();
+        }
+        let budget: PodDisruptionBudget = serde_json::from_value(serde_json::json!({
+            "apiVersion": "policy/v1",
+            "kind": "PodDisruptionBudget",
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {"minAvailable": 1, "selector": {"matchLabels": {"app": "web"}}}
+        }))
+        .unwrap();
+        create_resource(&state, budget).await.unwrap();
+        let evict = |name: &str| {
+            let mut eviction = Eviction::default();
+            eviction.metadata.name = Some(name.to_string());
+            evict_pod(
+                State(state.clone()),
+                Path(("default".to_string(), name.to_string())),
+                Json(eviction),
+            )
+        };
+
+        evict("web-1").await.unwrap();
+        // The terminating web-1 no longer counts, so web-2 must stay
+        let result = evict("web-2").await;
+        assert!(matches!(result, Err(ApiError::TooManyRequests(_))));
+        // Evicting the already terminating pod again disrupts nothing
+        evict("web-1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_pod_grace_period() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store).with_max_grace_period(10));
+
+        for name in ["slow", "quick", "forced"] {
+            let mut pod = make_test_pod(name, "default");
+            pod.spec.as_mut().unwrap().termination_grace_period_seconds = Some(60);
+            create_resource(&state, pod).await.unwrap();
+        }
+        let delete = |name: &str, query: Option<i64>, body: &str| {
+            ResourceHandlers::<Pod>::delete(
+                State(state.clone()),
+                Path(ObjectPath {
+                    namespace: Some("default".to_string()),
+                    name: name.to_string(),
+                }),
+                Query(DeleteParams {
+                    grace_period_seconds: query,
+                }),
+                HeaderMap::new(),
+                Bytes::from(body.to_string()),
+            )
+        };
+        let key = |name: &str| {
+            ResourceKey::new(
+                GroupVersionKind::from_api_version_kind("v1", "Pod"),
+                "default",
+                name,
+            )
+        };
+
+        // The spec's grace period is capped by the ceiling
+        delete("slow", None, "").await.unwrap();
+        let pod: Pod = get_resource(&state, &key("slow")).await.unwrap();
+        assert_eq!(pod.metadata.deletion_grace_period_seconds, Some(10));
+
+        // The query overrides the DeleteOptions body
+        let negative = delete("quick", Some(-1), "").await;
+        assert!(matches!(negative, Err(ApiError::BadRequest(_))));
+        let negative = delete("quick", None, r#"{"gracePeriodSeconds": -1}"#).await;
+        assert!(matches!(negative, Err(ApiError::BadRequest(_))));
+        delete("quick", Some(3), r#"{"gracePeriodSeconds": 5}"#)
+            .await
+            .unwrap();
+        let pod: Pod = get_resource(&state, &key("quick")).await.unwrap();
+        assert_eq!(pod.metadata.deletion_grace_period_seconds, Some(3));
+
+        // A zero grace period removes the pod at once, announcing it with a
+        // zero grace period so the node cleans up after it
+        let mut rx = state.subscribe();
+        delete("forced", None, r#"{"gracePeriodSeconds": 0}"#)
+            .await
+            .unwrap();
+        let gone: Result<Pod> = get_resource(&state, &key("forced")).await;
+        assert!(matches!(gone, Err(ApiError::NotFound(_))));
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event.event_type, WatchEventType::Deleted));
+        let metadata = &event.object["metadata"];
+        assert_eq!(metadata["deletionGracePeriodSeconds"], 0);
+        assert!(metadata["deletionTimestamp"].is_string());
+    }
+
     #[tokio::test]
     async fn test_delete_pod_idempotent() {
         let state = setup_state().await;
 
         let pod = make_test_pod("idem-pod", "default");
-        create_resource(&*state, pod).await.unwrap();
+        create_resource(&state, pod).await.unwrap();
 
         let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
         let key = ResourceKey::new(gvk, "default", "idem-pod");
 
         // First delete: set deletion_timestamp
-        let mut pod: Pod = get_resource(&*state, &key).await.unwrap();
+        let mut pod: Pod = get_resource(&state, &key).await.unwrap();
         pod.metadata.deletion_timestamp = Some(
             reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(
                 chrono::Utc::now(),
@@ -418,10 +1544,10 @@ mod tests {
         pod.status
             .get_or_insert_with(Default::default)
             .phase = Some("Terminating".to_string());
-        update_resource(&*state, pod).await.unwrap();
+        update_resource(&state, pod).await.unwrap();
 
         // Second "delete" attempt should see deletion_timestamp is already set
-        let pod2: Pod = get_resource(&*state, &key).await.unwrap();
+        let pod2: Pod = get_resource(&state, &key).await.unwrap();
         assert!(pod2.metadata.deletion_timestamp.is_some());
 
         // Pod should still exist in storage (not removed)
@@ -436,19 +1562,19 @@ mod tests {
         let state = setup_state().await;
 
         let pod = make_test_pod("finalize-pod", "default");
-        create_resource(&*state, pod).await.unwrap();
+        create_resource(&state, pod).await.unwrap();
 
         let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
         let key = ResourceKey::new(gvk, "default", "finalize-pod");
 
         // Pod should exist
-        let _: Pod = get_resource(&*state, &key).await.unwrap();
+        let _: Pod = get_resource(&state, &key).await.unwrap();
 
         // Finalize (actual storage removal)
         delete_resource(&state, &key).await.unwrap();
 
         // Pod should be gone
-        let result: std::result::Result<Pod, _> = get_resource(&*state, &key).await;
+        let result: std::result::Result<Pod, _> = get_resource(&state, &key).await;
         assert!(result.is_err());
     }
 
@@ -457,7 +1583,7 @@ mod tests {
         let state = setup_state().await;
 
         let pod = make_test_pod("event-del-pod", "default");
-        create_resource(&*state, pod).await.unwrap();
+        create_resource(&state, pod).await.unwrap();
 
         // Subscribe to events
         let mut rx = state.subscribe();
@@ -465,7 +1591,7 @@ mod tests {
         let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
         let key = ResourceKey::new(gvk, "default", "event-del-pod");
 
-        let mut pod: Pod = get_resource(&*state, &key).await.unwrap();
+        let mut pod: Pod = get_resource(&state, &key).await.unwrap();
         pod.metadata.deletion_timestamp = Some(
             reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(
                 chrono::Utc::now(),
@@ -474,7 +1600,7 @@ mod tests {
         pod.status
             .get_or_insert_with(Default::default)
             .phase = Some("Terminating".to_string());
-        update_resource(&*state, pod).await.unwrap();
+        update_resource(&state, pod).await.unwrap();
 
         // Should get a MODIFIED event
         let event = rx.recv().await.unwrap();
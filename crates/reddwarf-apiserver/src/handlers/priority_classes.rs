@@ -0,0 +1,60 @@
+use crate::handlers::common::list_resources;
+use crate::handlers::generic::{ResourceHandlers, ResourceKind};
+use crate::{ApiError, AppState, Result};
+use async_trait::async_trait;
+use reddwarf_core::resources::{PRIORITY_CLASS_KIND, SCHEDULING_API_VERSION};
+use reddwarf_core::{PriorityClass, ResourceKey};
+use reddwarf_storage::KeyEncoder;
+
+#[async_trait]
+impl ResourceKind for PriorityClass {
+    const API_VERSION: &'static str = SCHEDULING_API_VERSION;
+    const KIND: &'static str = PRIORITY_CLASS_KIND;
+    const PLURAL: &'static str = "priorityclasses";
+    const SHORT_NAMES: &'static [&'static str] = &["pc"];
+    const NAMESPACED: bool = false;
+
+    /// Pods keep the priority resolved when they were admitted, so a class
+    /// keeps its value until it is deleted and recreated
+    fn validate_update(current: &PriorityClass, class: &PriorityClass) -> Result<()> {
+        if current.value != class.value {
+            return Err(ApiError::ValidationFailed(
+                "value: field is immutable".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Only one class may be the global default
+    async fn admit(state: &AppState, class: &mut PriorityClass) -> Result<()> {
+        if class.global_default != Some(true) {
+            return Ok(());
+        }
+        match global_default_priority_class(state).await? {
+            Some(existing) if existing.metadata.name != class.metadata.name => {
+                Err(ApiError::ValidationFailed(format!(
+                    "globalDefault: PriorityClass '{}' is already marked as the global default",
+                    existing.metadata.name.unwrap_or_default()
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Key of the PriorityClass `name`
+pub(crate) fn priority_class_key(name: impl Into<String>) -> ResourceKey {
+    ResourceKey::cluster_scoped(ResourceHandlers::<PriorityClass>::gvk(), name)
+}
+
+/// The class pods naming no PriorityClass are given, if one is marked
+/// `globalDefault`
+pub(crate) async fn global_default_priority_class(
+    state: &AppState,
+) -> Result<Option<PriorityClass>> {
+    let prefix = KeyEncoder::encode_prefix(SCHEDULING_API_VERSION, PRIORITY_CLASS_KIND, None);
+    let classes: Vec<PriorityClass> = list_resources(state, &prefix).await?;
+    Ok(classes
+        .into_iter()
+        .find(|class| class.global_default == Some(true)))
+}
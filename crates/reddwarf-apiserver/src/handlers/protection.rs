@@ -0,0 +1,173 @@
+//! Deletion protection
+//!
+//! Objects annotated with [`PROTECTED_ANNOTATION`]` = "true"` are only
+//! deleted when the request repeats the object's name in the
+//! [`CONFIRM_DELETE_HEADER`] header; otherwise the deletion is refused with
+//! 403. Clients that cannot set headers, like kubectl, remove the annotation
+//! first. ApplySet pruning never deletes protected objects.
+
+use crate::{ApiError, AppState, Result};
+use reddwarf_core::{ResourceKey, PROTECTED_ANNOTATION};
+use reddwarf_storage::{IndexKey, KVStore, KeyEncoder};
+
+/// Header confirming the deletion of a protected object by naming it
+pub const CONFIRM_DELETE_HEADER: &str = "x-reddwarf-confirm-delete";
+
+/// Refuse to delete the object at `key` if it is protected and the deletion
+/// was not confirmed with its name. Namespaces report how many objects the
+/// deletion would take with them.
+pub(crate) fn check_deletion_protection(
+    state: &AppState,
+    key: &ResourceKey,
+    confirmation: Option<&str>,
+) -> Result<()> {
+    let storage_key = KeyEncoder::encode_resource_key(key);
+    let Some(data) = state.storage.get(storage_key.as_bytes())? else {
+        return Ok(());
+    };
+    let object: serde_json::Value = serde_json::from_slice(&data)?;
+    if object["metadata"]["annotations"][PROTECTED_ANNOTATION].as_str() != Some("true")
+        || confirmation == Some(key.name.as_str())
+    {
+        return Ok(());
+    }
+
+    let contents = if key.gvk.kind == "Namespace" {
+        let prefix = IndexKey::encode_prefix_for_namespace(&key.name);
+        format!(
+            " and still holds {} objects",
+            state.storage.scan_index(&prefix)?.len()
+        )
+    } else {
+        String::new()
+    };
+    Err(ApiError::Forbidden(format!(
+        "{} {} is protected by the {} annotation{}; remove the annotation or set the {} header to its name to delete it",
+        key.gvk.kind, key.name, PROTECTED_ANNOTATION, contents, CONFIRM_DELETE_HEADER
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::ResourceRegistry;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request, StatusCode};
+    use reddwarf_core::{Namespace, Service};
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_protected_objects_need_confirmation() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let router = ResourceRegistry::new()
+            .register::<Namespace>()
+            .register::<Service>()
+            .into_router()
+            .with_state(state);
+
+        let send = |method: Method, uri: &str, confirm: Option<&str>, body: Value| {
+            let mut request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json");
+            if let Some(confirm) = confirm {
+                request = request.header(CONFIRM_DELETE_HEADER, confirm);
+            }
+            let request = request.body(Body::from(body.to_string())).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or_default(),
+                )
+            }
+        };
+        let protected = serde_json::json!({ PROTECTED_ANNOTATION: "true" });
+
+        let (status, _) = send(
+            Method::POST,
+            "/api/v1/namespaces",
+            None,
+            serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "Namespace",
+                "metadata": {"name": "shop", "annotations": protected}
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = send(
+            Method::POST,
+            "/api/v1/namespaces/shop/services",
+            None,
+            serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "Service",
+                "metadata": {
+                    "name": "web",
+                    "annotations": protected,
+                    "labels": {"applyset.kubernetes.io/part-of": "shop"}
+                }
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // Unconfirmed or wrongly confirmed deletes are refused
+        for confirm in [None, Some("default")] {
+            let (status, body) = send(
+                Method::DELETE,
+                "/api/v1/namespaces/shop",
+                confirm,
+                Value::Null,
+            )
+            .await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert_eq!(body["reason"], "Forbidden");
+            assert!(body["message"]
+                .as_str()
+                .unwrap()
+                .contains("still holds 1 objects"));
+        }
+
+        // Pruning leaves protected members in place
+        let (status, body) = send(
+            Method::POST,
+            "/apis/reddwarf.io/v1/applysets/shop/prune",
+            None,
+            serde_json::json!({ "keep": [] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["pruned"], serde_json::json!([]));
+
+        // Confirmed deletes go through
+        let (status, _) = send(
+            Method::DELETE,
+            "/api/v1/namespaces/shop/services/web",
+            Some("web"),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            Method::DELETE,
+            "/api/v1/namespaces/shop",
+            Some("shop"),
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
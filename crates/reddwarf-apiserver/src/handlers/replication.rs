@@ -0,0 +1,362 @@
+use crate::replica::ReplicationMessage;
+use crate::{AppState, Result};
+use axum::body::{Body, Bytes};
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use reddwarf_storage::KVStore;
+use reddwarf_versioning::Commit;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often an idle replication stream sends a heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+pub struct ReplicationParams {
+    /// Last commit the follower applied
+    pub since: Option<String>,
+}
+
+/// GET /apis/reddwarf.io/v1alpha1/replication/commits
+///
+/// Streams the commits made after `since` to a read replica, one JSON
+/// message per line, then each new commit as it is made. A follower without
+/// a known `since` gets a snapshot of the storage first. A follower that
+/// falls behind the event bus is disconnected and resumes from its HEAD,
+/// which is kept from being squashed away while it is connected.
+pub async fn get_replication_stream(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReplicationParams>,
+) -> Result<Response> {
+    // Subscribe before reading history so no commit falls between the two
+    let rx = state.subscribe();
+    let since = params
+        .since
+        .as_deref()
+        .and_then(|id| state.version_store.get_commit(id).ok());
+    let backlog = match since {
+        Some(since) => {
+            let mut commits: Vec<Commit> = state
+                .version_store
+                .list_commits()?
+                .into_iter()
+                .filter(|c| c.timestamp > since.timestamp)
+                .collect();
+            commits.sort_by_key(|c| c.timestamp);
+            commits
+                .into_iter()
+                .map(|commit| ReplicationMessage::Commit { commit })
+                .collect()
+        }
+        None => {
+            // HEAD before the scan, as for lists
+            let head = state.version_store.get_head()?;
+            let entries = state
+                .storage
+                .scan(b"")?
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    Some((
+                        String::from_utf8(key.to_vec()).ok()?,
+                        String::from_utf8(value.to_vec()).ok()?,
+                    ))
+                })
+                .collect();
+            vec![ReplicationMessage::Snapshot { head, entries }]
+        }
+    };
+
+    let position = match backlog.first() {
+        Some(ReplicationMessage::Snapshot { head, .. }) => head.as_ref().map(|c| c.id.clone()),
+        _ => params.since.clone(),
+    };
+    let pin = state.version_store.pin(&position.unwrap_or_default());
+
+    let live = futures_util::stream::unfold((rx, state), |(mut rx, state)| async move {
+        let message = tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) => state
+                    .version_store
+                    .get_commit(&event.resource_version)
+                    .ok()
+                    .map(|commit| ReplicationMessage::Commit { commit }),
+                // Lagged or closed: end the stream
+                Err(_) => return None,
+            },
+            _ = tokio::time::sleep(HEARTBEAT_INTERVAL) => Some(ReplicationMessage::Heartbeat),
+        };
+        Some((message, (rx, state)))
+    })
+    .filter_map(futures_util::future::ready);
+
+    let lines = futures_util::stream::iter(backlog)
+        .chain(live)
+        .inspect(move |message| {
+            if let ReplicationMessage::Commit { commit } = message {
+                pin.advance(&commit.id);
+            }
+        })
+        .filter_map(|message| async move {
+            let mut line = serde_json::to_vec(&message).ok()?;
+            line.push(b'\n');
+            Some(Ok::<_, Infallible>(Bytes::from(line)))
+        });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::{create_resource, get_resource, update_resource};
+    use crate::replica::{ReplicaFollower, ReplicaFollowerConfig};
+    use reddwarf_core::{
+        GroupVersionKind, Namespace, Resource, ResourceKey, Secret, WatchEventType,
+    };
+    use reddwarf_storage::{EnvelopeEncryptor, KeyEncoder, LocalKms, RedbBackend};
+    use reddwarf_versioning::VersionStore;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// State encrypting Secrets with the keyring at `keyring`
+    fn encrypted_state(dir: &Path, name: &str, keyring: &Path) -> Arc<AppState> {
+        let storage = Arc::new(RedbBackend::new(dir.join(name)).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let encryptor = EnvelopeEncryptor::new(Arc::new(LocalKms::open(keyring).unwrap()));
+        Arc::new(AppState::new(storage, version_store).with_encryption(Arc::new(encryptor)))
+    }
+
+    fn namespace(name: &str) -> Namespace {
+        let mut ns = Namespace::default();
+        ns.metadata.name = Some(name.to_string());
+        ns
+    }
+
+    fn secret(password: &str) -> Secret {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {"name": "creds", "namespace": "default"},
+            "data": {"password": password}
+        }))
+        .unwrap()
+    }
+
+    fn secret_key() -> ResourceKey {
+        ResourceKey::new(
+            GroupVersionKind::from_api_version_kind("v1", "Secret"),
+            "default",
+            "creds",
+        )
+    }
+
+    /// The first `count` messages of the stream `since` the given commit
+    async fn messages(
+        state: &Arc<AppState>,
+        since: Option<&str>,
+        count: usize,
+    ) -> Vec<ReplicationMessage> {
+        let response = get_replication_stream(
+            State(state.clone()),
+            Query(ReplicationParams {
+                since: since.map(str::to_string),
+            }),
+        )
+        .await
+        .unwrap();
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while text.lines().count() < count {
+            let chunk = tokio::time::timeout(Duration::from_secs(2), body.next())
+                .await
+                .expect("replication messages")
+                .unwrap()
+                .unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        text.lines()
+            .take(count)
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn commit_ids(messages: &[ReplicationMessage]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|message| match message {
+                ReplicationMessage::Commit { commit } => commit.id.clone(),
+                other => panic!("expected a commit, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_from_a_commit_with_encrypted_secrets() {
+        let dir = tempdir().unwrap();
+        let keyring = dir.path().join("keys");
+        LocalKms::rotate(&keyring, Some("k1")).unwrap();
+        let leader = encrypted_state(dir.path(), "leader.redb", &keyring);
+        let replica = encrypted_state(dir.path(), "replica.redb", &keyring);
+        let follower = ReplicaFollower::new(replica.clone(), ReplicaFollowerConfig::default());
+
+        let first = create_resource(&leader, namespace("default"))
+            .await
+            .unwrap()
+            .resource_version()
+            .unwrap()
+            .0;
+        let created = create_resource(&leader, secret("aHVudGVyMg=="))
+            .await
+            .unwrap();
+        let mut changed = secret("aHVudGVyMw==");
+        changed.metadata = created.metadata.clone();
+        let updated = update_resource(&leader, changed).await.unwrap();
+
+        // A replica at the first commit is sent every later one, oldest
+        // first, with the Secret still encrypted on the wire
+        let backlog = messages(&leader, Some(&first), 2).await;
+        assert_eq!(
+            commit_ids(&backlog),
+            [
+                created.resource_version().unwrap().0,
+                updated.resource_version().unwrap().0
+            ]
+        );
+        for message in &backlog {
+            let line = serde_json::to_string(message).unwrap();
+            assert!(!line.contains("aHVudGVy"));
+        }
+
+        let mut events = replica.subscribe();
+        for message in backlog {
+            follower.apply(message).unwrap();
+        }
+
+        // Its watchers are sent the decrypted changes, and it serves the
+        // Secret at the leader's resource version, encrypted at rest
+        let added = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .expect("replicated event")
+            .unwrap();
+        assert!(matches!(added.event_type, WatchEventType::Added));
+        assert_eq!(added.object["data"]["password"], "aHVudGVyMg==");
+        let modified = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .expect("replicated event")
+            .unwrap();
+        assert!(matches!(modified.event_type, WatchEventType::Modified));
+        assert_eq!(modified.object["data"]["password"], "aHVudGVyMw==");
+        assert_eq!(
+            modified.object["metadata"]["resourceVersion"],
+            updated.resource_version().unwrap().0
+        );
+        let stored = replica
+            .storage
+            .get(KeyEncoder::encode_resource_key(&secret_key()).as_bytes())
+            .unwrap()
+            .unwrap();
+        assert!(EnvelopeEncryptor::is_encrypted(&stored));
+        let read: Secret = get_resource(&replica, &secret_key()).await.unwrap();
+        assert_eq!(read.data.unwrap()["password"].0, b"hunter3");
+        assert_eq!(
+            read.metadata.resource_version,
+            updated.metadata.resource_version
+        );
+        assert_eq!(
+            replica.version_store.head_id(),
+            leader.version_store.head_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_a_gap() {
+        let dir = tempdir().unwrap();
+        let keyring = dir.path().join("keys");
+        LocalKms::rotate(&keyring, Some("k1")).unwrap();
+        let leader = encrypted_state(dir.path(), "leader.redb", &keyring);
+        let replica = encrypted_state(dir.path(), "replica.redb", &keyring);
+        let follower = ReplicaFollower::new(replica.clone(), ReplicaFollowerConfig::default());
+
+        create_resource(&leader, namespace("default"))
+            .await
+            .unwrap();
+        let created = create_resource(&leader, secret("aHVudGVyMg=="))
+            .await
+            .unwrap();
+        for message in messages(&leader, None, 1).await {
+            follower.apply(message).unwrap();
+        }
+        let position = replica.version_store.head_id().unwrap();
+        assert_eq!(position, created.resource_version().unwrap().0);
+
+        // The leader moves on while the replica is disconnected
+        let mut changed = secret("aHVudGVyMw==");
+        changed.metadata = created.metadata.clone();
+        let updated = update_resource(&leader, changed).await.unwrap();
+        let other = create_resource(&leader, namespace("other")).await.unwrap();
+
+        // Resuming from its HEAD sends only what it missed, then new commits
+        // as they are made
+        let response = get_replication_stream(
+            State(leader.clone()),
+            Query(ReplicationParams {
+                since: Some(position.clone()),
+            }),
+        )
+        .await
+        .unwrap();
+        let later = create_resource(&leader, namespace("later")).await.unwrap();
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while text.lines().count() < 3 {
+            let chunk = tokio::time::timeout(Duration::from_secs(2), body.next())
+                .await
+                .expect("replication messages")
+                .unwrap()
+                .unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        let resumed: Vec<ReplicationMessage> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(
+            commit_ids(&resumed),
+            [
+                updated.resource_version().unwrap().0,
+                other.resource_version().unwrap().0,
+                later.resource_version().unwrap().0
+            ]
+        );
+        for message in resumed {
+            follower.apply(message).unwrap();
+        }
+        let read: Secret = get_resource(&replica, &secret_key()).await.unwrap();
+        assert_eq!(read.data.unwrap()["password"].0, b"hunter3");
+        assert_eq!(
+            replica.version_store.head_id(),
+            leader.version_store.head_id()
+        );
+
+        // A position the leader no longer has is answered with a snapshot
+        let resumed = messages(&leader, Some("squashed-away"), 1).await;
+        let ReplicationMessage::Snapshot { head, entries } = &resumed[0] else {
+            panic!("expected a snapshot, got {:?}", resumed[0]);
+        };
+        assert_eq!(
+            head.as_ref().map(|c| c.id.clone()),
+            leader.version_store.head_id()
+        );
+        let secret_entry = KeyEncoder::encode_resource_key(&secret_key());
+        let (_, value) = entries.iter().find(|(k, _)| *k == secret_entry).unwrap();
+        assert!(EnvelopeEncryptor::is_encrypted(value.as_bytes()));
+    }
+}
@@ -0,0 +1,15 @@
+use crate::handlers::generic::{ResourceHandlers, ResourceKind};
+use reddwarf_core::resources::{RUNTIME_CLASS_API_VERSION, RUNTIME_CLASS_KIND};
+use reddwarf_core::{ResourceKey, RuntimeClass};
+
+impl ResourceKind for RuntimeClass {
+    const API_VERSION: &'static str = RUNTIME_CLASS_API_VERSION;
+    const KIND: &'static str = RUNTIME_CLASS_KIND;
+    const PLURAL: &'static str = "runtimeclasses";
+    const NAMESPACED: bool = false;
+}
+
+/// Key of the RuntimeClass `name`
+pub(crate) fn runtime_class_key(name: impl Into<String>) -> ResourceKey {
+    ResourceKey::cluster_scoped(ResourceHandlers::<RuntimeClass>::gvk(), name)
+}
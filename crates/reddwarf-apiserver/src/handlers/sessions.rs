@@ -0,0 +1,508 @@
+//! The `exec` and `attach` pod subresources
+//!
+//! Both upgrade to a WebSocket speaking the Kubernetes channel protocol, so
+//! `kubectl exec` and `kubectl attach` work unchanged: every binary message
+//! starts with a channel byte (0 stdin, 1 stdout, 2 stderr, 3 the final
+//! Status, 4 terminal resizes, 255 closing stdin). The session itself is
+//! opened through the node agent's [`ContainerSessionBackend`] before the
+//! upgrade, so a pod that cannot be reached is answered with a plain error.
+//!
+//! [`ContainerSessionBackend`]: crate::sessions::ContainerSessionBackend
+
+use crate::auth::{self, Bearer};
+use crate::handlers::common::get_resource;
+use crate::sessions::ContainerSessions;
+use crate::{ApiError, AppState, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use reddwarf_core::{
+    GroupVersionKind, Pod, ProcessSession, ResourceKey, SessionInput, SessionOutput,
+    SessionRequest, SessionTarget, TerminalSize,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Channel protocols offered to clients, newest first
+const CHANNEL_PROTOCOLS: [&str; 3] = ["v5.channel.k8s.io", "v4.channel.k8s.io", "channel.k8s.io"];
+
+const STDIN_CHANNEL: u8 = 0;
+const STDOUT_CHANNEL: u8 = 1;
+const STDERR_CHANNEL: u8 = 2;
+const STATUS_CHANNEL: u8 = 3;
+const RESIZE_CHANNEL: u8 = 4;
+const CLOSE_CHANNEL: u8 = 255;
+
+/// Query parameters of `exec` and `attach`; `command` repeats, one
+/// parameter per argument
+#[derive(Debug, Default, PartialEq)]
+struct SessionParams {
+    container: Option<String>,
+    command: Vec<String>,
+    stdin: bool,
+    stdout: bool,
+    stderr: bool,
+    tty: bool,
+}
+
+impl SessionParams {
+    fn parse(query: Vec<(String, String)>) -> Self {
+        let mut params = Self::default();
+        for (key, value) in query {
+            let flag = value == "true" || value == "1";
+            match key.as_str() {
+                "container" => params.container = Some(value),
+                "command" => params.command.push(value),
+                "stdin" => params.stdin = flag,
+                "stdout" => params.stdout = flag,
+                "stderr" => params.stderr = flag,
+                "tty" => params.tty = flag,
+                _ => {}
+            }
+        }
+        params
+    }
+}
+
+/// Terminal size sent on the resize channel
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResizeMessage {
+    width: u16,
+    height: u16,
+}
+
+/// Resolve the session configuration and check the caller's bearer token
+fn authorize<'a>(state: &'a AppState, bearer: &Bearer) -> Result<&'a ContainerSessions> {
+    let sessions = state
+        .sessions
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Interactive sessions are not enabled".to_string()))?;
+    auth::authorize(
+        std::slice::from_ref(&sessions.token),
+        bearer,
+        "Interactive session API",
+    )?;
+    Ok(sessions)
+}
+
+/// Check the request against the pod and open the session
+async fn open_session(
+    state: &AppState,
+    bearer: &Bearer,
+    namespace: &str,
+    name: &str,
+    params: &mut SessionParams,
+    attach: bool,
+) -> Result<ProcessSession> {
+    let sessions = authorize(state, bearer)?;
+
+    let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
+    let key = ResourceKey::new(gvk, namespace, name);
+    let pod: Pod = get_resource(state, &key).await?;
+
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    if phase != Some("Running") {
+        return Err(ApiError::BadRequest(format!(
+            "Pod {}/{} is not running (phase: {})",
+            namespace,
+            name,
+            phase.unwrap_or("Unknown")
+        )));
+    }
+
+    let containers = pod
+        .spec
+        .as_ref()
+        .map(|s| s.containers.as_slice())
+        .unwrap_or_default();
+    let container = match &params.container {
+        Some(wanted) => containers.iter().find(|c| &c.name == wanted),
+        None => containers.first(),
+    }
+    .ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Container {} is not valid for pod {}/{}",
+            params.container.as_deref().unwrap_or_default(),
+            namespace,
+            name
+        ))
+    })?;
+
+    if !(params.stdin || params.stdout || params.stderr) {
+        return Err(ApiError::BadRequest(
+            "At least one of stdin, stdout or stderr must be requested".to_string(),
+        ));
+    }
+
+    let target = if attach {
+        // Attaching joins the container's own process, which only reads
+        // stdin or has a terminal when its spec asks for one
+        if params.stdin && container.stdin != Some(true) {
+            return Err(ApiError::BadRequest(format!(
+                "Container {} does not set stdin; cannot attach to its stdin",
+                container.name
+            )));
+        }
+        params.tty = params.tty && container.tty == Some(true);
+        SessionTarget::Attach
+    } else {
+        if params.command.is_empty() {
+            return Err(ApiError::BadRequest(
+                "A command to execute is required".to_string(),
+            ));
+        }
+        SessionTarget::Exec(params.command.clone())
+    };
+
+    info!(
+        "Opening {} session with {}/{} container {}",
+        if attach { "attach" } else { "exec" },
+        namespace,
+        name,
+        container.name
+    );
+    sessions
+        .backend
+        .open(
+            &pod,
+            SessionRequest {
+                container: container.name.clone(),
+                target,
+                stdin: params.stdin,
+                tty: params.tty,
+            },
+        )
+        .await
+}
+
+/// GET /api/v1/namespaces/{namespace}/pods/{name}/exec
+pub async fn exec_pod(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+    Path((namespace, name)): Path<(String, String)>,
+    Query(query): Query<Vec<(String, String)>>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let mut params = SessionParams::parse(query);
+    let session = open_session(&state, &bearer, &namespace, &name, &mut params, false).await?;
+    Ok(upgrade(ws, session, params))
+}
+
+/// GET /api/v1/namespaces/{namespace}/pods/{name}/attach
+pub async fn attach_pod(
+    State(state): State<Arc<AppState>>,
+    bearer: Bearer,
+    Path((namespace, name)): Path<(String, String)>,
+    Query(query): Query<Vec<(String, String)>>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let mut params = SessionParams::parse(query);
+    let session = open_session(&state, &bearer, &namespace, &name, &mut params, true).await?;
+    Ok(upgrade(ws, session, params))
+}
+
+fn upgrade(ws: WebSocketUpgrade, session: ProcessSession, params: SessionParams) -> Response {
+    ws.protocols(CHANNEL_PROTOCOLS)
+        .on_upgrade(move |socket| relay(socket, session, params))
+        .into_response()
+}
+
+/// Pass messages between the WebSocket and the session until the process
+/// exits or the client goes away
+async fn relay(mut socket: WebSocket, mut session: ProcessSession, params: SessionParams) {
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Binary(data))) => {
+                    let Some(input) = decode_input(&data) else {
+                        continue;
+                    };
+                    if session.input.send(input).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    debug!("Session client went away");
+                    return;
+                }
+                Some(Ok(_)) => {}
+            },
+            output = session.output.recv() => {
+                let Some(output) = output else {
+                    break;
+                };
+                let exited = matches!(output, SessionOutput::Exit(_));
+                if let Some(frame) = encode_output(output, &params) {
+                    if socket.send(Message::Binary(frame.into())).await.is_err() {
+                        return;
+                    }
+                }
+                if exited {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// The session input carried by a client message, if any
+fn decode_input(data: &[u8]) -> Option<SessionInput> {
+    let (&channel, payload) = data.split_first()?;
+    match channel {
+        STDIN_CHANNEL => Some(SessionInput::Stdin(payload.to_vec())),
+        RESIZE_CHANNEL => {
+            let resize: ResizeMessage = serde_json::from_slice(payload).ok()?;
+            Some(SessionInput::Resize(TerminalSize {
+                width: resize.width,
+                height: resize.height,
+            }))
+        }
+        CLOSE_CHANNEL if payload.first() == Some(&STDIN_CHANNEL) => Some(SessionInput::CloseStdin),
+        _ => None,
+    }
+}
+
+/// The message carrying session output to the client, if it asked for it
+fn encode_output(output: SessionOutput, params: &SessionParams) -> Option<Vec<u8>> {
+    let (channel, payload) = match output {
+        SessionOutput::Stdout(data) if params.stdout => (STDOUT_CHANNEL, data),
+        SessionOutput::Stderr(data) if params.stderr => (STDERR_CHANNEL, data),
+        SessionOutput::Stdout(_) | SessionOutput::Stderr(_) => return None,
+        SessionOutput::Exit(code) => (
+            STATUS_CHANNEL,
+            serde_json::to_vec(&exit_status(code)).unwrap_or_default(),
+        ),
+    };
+    let mut frame = Vec::with_capacity(payload.len() + 1);
+    frame.push(channel);
+    frame.extend(payload);
+    Some(frame)
+}
+
+/// The Status reporting how the process exited, as `kubectl` expects it
+fn exit_status(code: i32) -> serde_json::Value {
+    if code == 0 {
+        return json!({ "metadata": {}, "status": "Success" });
+    }
+    json!({
+        "metadata": {},
+        "status": "Failure",
+        "message": format!("command terminated with non-zero exit code: {}", code),
+        "reason": "NonZeroExitCode",
+        "details": {
+            "causes": [{ "reason": "ExitCode", "message": code.to_string() }]
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::create_resource;
+    use crate::sessions::ContainerSessionBackend;
+    use async_trait::async_trait;
+    use reddwarf_core::k8s_openapi::api::core::v1::{Container, PodSpec, PodStatus};
+    use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct FakeBackend {
+        opened: Mutex<Vec<SessionRequest>>,
+    }
+
+    #[async_trait]
+    impl ContainerSessionBackend for FakeBackend {
+        async fn open(&self, _pod: &Pod, request: SessionRequest) -> Result<ProcessSession> {
+            self.opened.lock().unwrap().push(request);
+            Ok(ProcessSession::pair().0)
+        }
+    }
+
+    async fn setup_state(backend: Arc<FakeBackend>) -> Arc<AppState> {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+
+        let state = AppState::new(storage, version_store)
+            .with_container_sessions(ContainerSessions::new(backend, "s3cret"));
+
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("shell".to_string()),
+                namespace: Some("default".to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "sh".to_string(),
+                    image: Some("alpine".to_string()),
+                    stdin: Some(true),
+                    tty: Some(true),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                ..Default::default()
+            }),
+        };
+        create_resource(&state, pod).await.unwrap();
+        Arc::new(state)
+    }
+
+    fn bearer(token: &str) -> Bearer {
+        Bearer::new(token)
+    }
+
+    fn params(query: &[(&str, &str)]) -> SessionParams {
+        SessionParams::parse(
+            query
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_params_collect_repeated_commands() {
+        let parsed = params(&[
+            ("command", "sh"),
+            ("command", "-c"),
+            ("command", "echo hi"),
+            ("stdout", "true"),
+            ("tty", "1"),
+        ]);
+        assert_eq!(parsed.command, vec!["sh", "-c", "echo hi"]);
+        assert!(parsed.stdout && parsed.tty);
+        assert!(!parsed.stdin && !parsed.stderr);
+    }
+
+    #[tokio::test]
+    async fn test_open_session_checks_token_and_request() {
+        let backend = Arc::new(FakeBackend::default());
+        let state = setup_state(backend.clone()).await;
+        let exec = || params(&[("command", "ls"), ("stdout", "true")]);
+
+        let result = open_session(
+            &state,
+            &bearer("wrong"),
+            "default",
+            "shell",
+            &mut exec(),
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::Unauthorized(_))));
+
+        let result = open_session(
+            &state,
+            &bearer("s3cret"),
+            "default",
+            "missing",
+            &mut exec(),
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+
+        let mut no_command = params(&[("stdout", "true")]);
+        let result = open_session(
+            &state,
+            &bearer("s3cret"),
+            "default",
+            "shell",
+            &mut no_command,
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let mut wrong_container =
+            params(&[("command", "ls"), ("stdout", "true"), ("container", "db")]);
+        let result = open_session(
+            &state,
+            &bearer("s3cret"),
+            "default",
+            "shell",
+            &mut wrong_container,
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        open_session(
+            &state,
+            &bearer("s3cret"),
+            "default",
+            "shell",
+            &mut exec(),
+            false,
+        )
+        .await
+        .unwrap();
+        let mut attach = params(&[("stdin", "true"), ("stdout", "true"), ("tty", "true")]);
+        open_session(
+            &state,
+            &bearer("s3cret"),
+            "default",
+            "shell",
+            &mut attach,
+            true,
+        )
+        .await
+        .unwrap();
+
+        let opened = backend.opened.lock().unwrap();
+        assert_eq!(opened.len(), 2);
+        assert_eq!(opened[0].container, "sh");
+        assert!(matches!(&opened[0].target, SessionTarget::Exec(cmd) if cmd == &["ls"]));
+        assert!(matches!(opened[1].target, SessionTarget::Attach));
+        assert!(opened[1].stdin && opened[1].tty);
+    }
+
+    #[test]
+    fn test_channel_frames() {
+        assert!(matches!(
+            decode_input(b"\x00ls\n"),
+            Some(SessionInput::Stdin(data)) if data == b"ls\n"
+        ));
+        assert!(matches!(
+            decode_input(b"\x04{\"Width\":120,\"Height\":40}"),
+            Some(SessionInput::Resize(TerminalSize {
+                width: 120,
+                height: 40
+            }))
+        ));
+        assert!(matches!(
+            decode_input(&[CLOSE_CHANNEL, STDIN_CHANNEL]),
+            Some(SessionInput::CloseStdin)
+        ));
+        assert!(decode_input(&[]).is_none());
+
+        let stdout_only = params(&[("stdout", "true")]);
+        assert_eq!(
+            encode_output(SessionOutput::Stdout(b"hi".to_vec()), &stdout_only),
+            Some(b"\x01hi".to_vec())
+        );
+        assert_eq!(
+            encode_output(SessionOutput::Stderr(b"oops".to_vec()), &stdout_only),
+            None
+        );
+
+        let frame = encode_output(SessionOutput::Exit(2), &stdout_only).unwrap();
+        assert_eq!(frame[0], STATUS_CHANNEL);
+        let status: serde_json::Value = serde_json::from_slice(&frame[1..]).unwrap();
+        assert_eq!(status["reason"], "NonZeroExitCode");
+        assert_eq!(status["details"]["causes"][0]["message"], "2");
+        assert_eq!(exit_status(0)["status"], "Success");
+    }
+}
@@ -0,0 +1,422 @@
+use crate::accounting::{render_openmetrics, usage_records, OPENMETRICS_CONTENT_TYPE};
+use crate::handlers::common::list_resources;
+use crate::response::ApiResponse;
+use crate::{ApiError, AppState, Result};
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use reddwarf_core::{Namespace, Node, Pod, ResourceQuantities};
+use reddwarf_storage::{IndexKey, KVStore, KeyEncoder};
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+
+/// API version of the `ClusterUsage` summary
+pub const CLUSTER_USAGE_API_VERSION: &str = "reddwarf.io/v1alpha1";
+
+// IPAM layout as written by the runtime's `Ipam` and `NodeCidrAllocator`
+const IPAM_CIDR_KEY: &[u8] = b"ipam/_cidr";
+const IPAM_ALLOC_PREFIX: &[u8] = b"ipam/alloc/";
+const NODE_CIDR_KEY: &[u8] = b"ipam/nodes/_cidr";
+const NODE_CIDR_ALLOC_PREFIX: &[u8] = b"ipam/nodes/alloc/";
+
+/// CPU, memory and pod totals
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub cpu_millicores: i64,
+    pub memory_bytes: i64,
+    pub pods: i64,
+}
+
+impl UsageTotals {
+    fn add_pod(&mut self, pod: &Pod) {
+        let requests = pod
+            .spec
+            .as_ref()
+            .map(ResourceQuantities::pod_requests)
+            .unwrap_or_default();
+        self.cpu_millicores += requests.cpu_millicores;
+        self.memory_bytes += requests.memory_bytes;
+        self.pods += 1;
+    }
+
+    fn add(&mut self, other: &UsageTotals) {
+        self.cpu_millicores += other.cpu_millicores;
+        self.memory_bytes += other.memory_bytes;
+        self.pods += other.pods;
+    }
+}
+
+/// Cluster-wide requested vs allocatable resources
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterTotals {
+    pub nodes: usize,
+    pub allocatable: UsageTotals,
+    pub requested: UsageTotals,
+}
+
+/// Resources requested by the pods of one namespace
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceUsage {
+    pub name: String,
+    pub requested: UsageTotals,
+}
+
+/// Utilization of an address pool
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolUsage {
+    pub cidr: String,
+    pub capacity: u64,
+    pub allocated: u64,
+}
+
+/// Utilization of the pod IP pool and of the node subnet pool
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpamUsage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_ips: Option<PoolUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_subnets: Option<PoolUsage>,
+}
+
+/// Body of `GET /apis/reddwarf.io/v1alpha1/clusterusage`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterUsage {
+    pub api_version: String,
+    pub kind: String,
+    pub cluster: ClusterTotals,
+    pub namespaces: Vec<NamespaceUsage>,
+    pub ipam: IpamUsage,
+}
+
+/// Whether a pod still holds its resources
+fn is_active(pod: &Pod) -> bool {
+    !matches!(
+        pod.status.as_ref().and_then(|s| s.phase.as_deref()),
+        Some("Succeeded") | Some("Failed")
+    )
+}
+
+/// Pods in `namespace`, found through the namespace index rather than a
+/// scan of all pods
+pub(crate) fn namespace_pods(state: &AppState, namespace: &str) -> Result<Vec<Pod>> {
+    let prefix = format!(
+        "{}v1/Pod/",
+        IndexKey::encode_prefix_for_namespace(namespace)
+    );
+    let mut pods = Vec::new();
+    for storage_key in state.storage.scan_index(&prefix)? {
+        let Some(data) = state.storage.get(storage_key.as_bytes())? else {
+            continue;
+        };
+        pods.push(serde_json::from_slice(&data)?);
+    }
+    Ok(pods)
+}
+
+/// Requests of the active pods in `namespace`
+fn namespace_usage(state: &AppState, namespace: &str) -> Result<UsageTotals> {
+    let mut totals = UsageTotals::default();
+    for pod in namespace_pods(state, namespace)? {
+        if is_active(&pod) {
+            totals.add_pod(&pod);
+        }
+    }
+    Ok(totals)
+}
+
+fn node_allocatable(node: &Node) -> UsageTotals {
+    let Some(allocatable) = node.status.as_ref().and_then(|s| s.allocatable.as_ref()) else {
+        return UsageTotals::default();
+    };
+    let quantities = ResourceQuantities::from_k8s_resource_map(allocatable);
+    UsageTotals {
+        cpu_millicores: quantities.cpu_millicores,
+        memory_bytes: quantities.memory_bytes,
+        pods: allocatable
+            .get("pods")
+            .and_then(|q| q.0.parse().ok())
+            .unwrap_or(0),
+    }
+}
+
+/// Parse `a.b.c.d/len` into the network address and prefix length
+fn parse_cidr(cidr: &str) -> Option<(Ipv4Addr, u32)> {
+    let (addr, len) = cidr.split_once('/')?;
+    let len: u32 = len.parse().ok()?;
+    (len <= 32).then_some((addr.parse().ok()?, len))
+}
+
+/// Usable pod IPs of a CIDR: all but the network, gateway and broadcast
+/// addresses
+fn pod_ip_capacity(cidr: &str) -> Option<(String, u64)> {
+    let (_, len) = parse_cidr(cidr)?;
+    Some((cidr.to_string(), (1u64 << (32 - len)).saturating_sub(3)))
+}
+
+/// Node subnets of a cluster CIDR stored as `{cidr}:{node prefix length}`
+fn node_subnet_capacity(config: &str) -> Option<(String, u64)> {
+    let (cidr, node_len) = config.rsplit_once(':')?;
+    let (_, len) = parse_cidr(cidr)?;
+    let node_len: u32 = node_len.parse().ok()?;
+    (len <= node_len && node_len <= 32).then(|| (cidr.to_string(), 1u64 << (node_len - len)))
+}
+
+/// Utilization of the pool configured at `config_key`, whose allocations
+/// are stored under `alloc_prefix`
+fn pool_usage(
+    state: &AppState,
+    config_key: &[u8],
+    alloc_prefix: &[u8],
+    capacity: fn(&str) -> Option<(String, u64)>,
+) -> Result<Option<PoolUsage>> {
+    let Some(config) = state.storage.get(config_key)? else {
+        return Ok(None);
+    };
+    let Some((cidr, capacity)) = capacity(&String::from_utf8_lossy(&config)) else {
+        return Ok(None);
+    };
+    let allocated = state.storage.keys_with_prefix(alloc_prefix)?.len() as u64;
+    Ok(Some(PoolUsage {
+        cidr,
+        capacity,
+        allocated,
+    }))
+}
+
+fn ipam_usage(state: &AppState) -> Result<IpamUsage> {
+    Ok(IpamUsage {
+        pod_ips: pool_usage(state, IPAM_CIDR_KEY, IPAM_ALLOC_PREFIX, pod_ip_capacity)?,
+        node_subnets: pool_usage(
+            state,
+            NODE_CIDR_KEY,
+            NODE_CIDR_ALLOC_PREFIX,
+            node_subnet_capacity,
+        )?,
+    })
+}
+
+/// Summarize requested vs allocatable resources per namespace and across
+/// the cluster, plus IPAM pool utilization
+pub async fn cluster_usage(state: &AppState) -> Result<ClusterUsage> {
+    let mut cluster = ClusterTotals::default();
+
+    let nodes: Vec<Node> =
+        list_resources(state, &KeyEncoder::encode_prefix("v1", "Node", None)).await?;
+    cluster.nodes = nodes.len();
+    for node in &nodes {
+        cluster.allocatable.add(&node_allocatable(node));
+    }
+
+    let namespaces: Vec<Namespace> =
+        list_resources(state, &KeyEncoder::encode_prefix("v1", "Namespace", None)).await?;
+    let mut usage = Vec::new();
+    for namespace in namespaces {
+        let Some(name) = namespace.metadata.name else {
+            continue;
+        };
+        let requested = namespace_usage(state, &name)?;
+        cluster.requested.add(&requested);
+        usage.push(NamespaceUsage { name, requested });
+    }
+
+    Ok(ClusterUsage {
+        api_version: CLUSTER_USAGE_API_VERSION.to_string(),
+        kind: "ClusterUsage".to_string(),
+        cluster,
+        namespaces: usage,
+        ipam: ipam_usage(state)?,
+    })
+}
+
+/// GET /apis/reddwarf.io/v1alpha1/clusterusage
+pub async fn get_cluster_usage(State(state): State<Arc<AppState>>) -> Result<Response> {
+    let usage = cluster_usage(&state).await?;
+    Ok(ApiResponse::ok(usage).into_response())
+}
+
+/// Query parameters of the usage reports
+#[derive(Debug, Default, Deserialize)]
+pub struct UsageReportParams {
+    /// RFC 3339 start of the reported range (inclusive)
+    pub from: Option<String>,
+    /// RFC 3339 end of the reported range (exclusive)
+    pub to: Option<String>,
+}
+
+fn parse_time(param: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>> {
+    value
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| ApiError::BadRequest(format!("Invalid {} '{}': {}", param, v, e)))
+        })
+        .transpose()
+}
+
+/// GET /apis/reddwarf.io/v1alpha1/usagereports?from=&to=
+///
+/// The recorded per-namespace usage whose periods ended within the range,
+/// in the OpenMetrics text format
+pub async fn get_usage_reports(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<UsageReportParams>,
+) -> Result<Response> {
+    let from = parse_time("from", params.from.as_deref())?;
+    let to = parse_time("to", params.to.as_deref())?;
+    let records = usage_records(&state, from, to)?;
+    Ok((
+        [(header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)],
+        render_openmetrics(&records),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::create_resource;
+    use reddwarf_core::k8s_openapi::api::core::v1::{
+        Container, NodeStatus, PodSpec, PodStatus, ResourceRequirements,
+    };
+    use reddwarf_core::k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+    use reddwarf_storage::RedbBackend;
+    use reddwarf_versioning::VersionStore;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    fn setup_state() -> Arc<AppState> {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        Arc::new(AppState::new(storage, version_store))
+    }
+
+    fn quantities(entries: &[(&str, &str)]) -> BTreeMap<String, Quantity> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), Quantity(v.to_string())))
+            .collect()
+    }
+
+    fn pod(namespace: &str, name: &str, cpu: &str, memory: &str, phase: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some(namespace.to_string());
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                image: Some("app:latest".to_string()),
+                resources: Some(ResourceRequirements {
+                    requests: Some(quantities(&[("cpu", cpu), ("memory", memory)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            phase: Some(phase.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn namespace(name: &str) -> Namespace {
+        let mut namespace = Namespace::default();
+        namespace.metadata.name = Some(name.to_string());
+        namespace
+    }
+
+    #[tokio::test]
+    async fn test_cluster_usage() {
+        let state = setup_state();
+        for name in ["default", "shop"] {
+            create_resource(&state, namespace(name)).await.unwrap();
+        }
+        let mut node = Node::default();
+        node.metadata.name = Some("node1".to_string());
+        node.status = Some(NodeStatus {
+            allocatable: Some(quantities(&[
+                ("cpu", "4"),
+                ("memory", "8Gi"),
+                ("pods", "110"),
+            ])),
+            ..Default::default()
+        });
+        create_resource(&state, node).await.unwrap();
+
+        create_resource(&state, pod("default", "web", "500m", "256Mi", "Running"))
+            .await
+            .unwrap();
+        create_resource(&state, pod("shop", "cart", "1", "1Gi", "Pending"))
+            .await
+            .unwrap();
+        create_resource(&state, pod("shop", "migrate", "2", "2Gi", "Succeeded"))
+            .await
+            .unwrap();
+
+        state.storage.put(IPAM_CIDR_KEY, b"10.88.0.0/24").unwrap();
+        state
+            .storage
+            .put(b"ipam/alloc/10.88.0.2", b"default/web")
+            .unwrap();
+        state
+            .storage
+            .put(b"ipam/alloc/10.88.0.3", b"shop/cart")
+            .unwrap();
+
+        let usage = cluster_usage(&state).await.unwrap();
+        assert_eq!(usage.kind, "ClusterUsage");
+        assert_eq!(usage.cluster.nodes, 1);
+        assert_eq!(
+            usage.cluster.allocatable,
+            UsageTotals {
+                cpu_millicores: 4000,
+                memory_bytes: 8 * 1024 * 1024 * 1024,
+                pods: 110,
+            }
+        );
+
+        // The completed pod no longer counts
+        assert_eq!(
+            usage.cluster.requested,
+            UsageTotals {
+                cpu_millicores: 1500,
+                memory_bytes: (256 + 1024) * 1024 * 1024,
+                pods: 2,
+            }
+        );
+        let shop = usage.namespaces.iter().find(|n| n.name == "shop").unwrap();
+        assert_eq!(shop.requested.cpu_millicores, 1000);
+        assert_eq!(shop.requested.pods, 1);
+
+        assert_eq!(
+            usage.ipam.pod_ips,
+            Some(PoolUsage {
+                cidr: "10.88.0.0/24".to_string(),
+                capacity: 253,
+                allocated: 2,
+            })
+        );
+        assert_eq!(usage.ipam.node_subnets, None);
+    }
+
+    #[test]
+    fn test_node_subnet_capacity() {
+        assert_eq!(
+            node_subnet_capacity("10.88.0.0/16:24"),
+            Some(("10.88.0.0/16".to_string(), 256))
+        );
+        assert_eq!(node_subnet_capacity("10.88.0.0/24:16"), None);
+        assert_eq!(node_subnet_capacity("garbage"), None);
+    }
+}
@@ -0,0 +1,316 @@
+//! Background squashing of the commit history: status heartbeats leave long
+//! runs of tiny updates of each pod and node, which are collapsed so the
+//! history, and every walk of it, stays proportional to real change
+
+use crate::{ApiError, AppState, Result};
+use reddwarf_versioning::SquashReport;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+/// Configuration for the history squasher
+#[derive(Debug, Clone)]
+pub struct HistorySquasherConfig {
+    /// How often the history is squashed
+    pub interval: Duration,
+    /// Commits of each resource left as they are, newest first, so recent
+    /// watches and replicas can still resume from them
+    pub keep_last: usize,
+}
+
+impl Default for HistorySquasherConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(600),
+            keep_last: 100,
+        }
+    }
+}
+
+/// Periodically squashes runs of single-resource updates in the history
+pub struct HistorySquasher {
+    state: Arc<AppState>,
+    config: HistorySquasherConfig,
+}
+
+impl HistorySquasher {
+    pub fn new(state: Arc<AppState>, config: HistorySquasherConfig) -> Self {
+        Self { state, config }
+    }
+
+    /// Run the squash loop; returns at once on read replicas, whose history
+    /// is the leader's
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        if self.state.leader.is_some() {
+            return Ok(());
+        }
+        info!(
+            "Starting history squasher (keeping the last {} commits of each resource)",
+            self.config.keep_last
+        );
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("History squasher shutting down");
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(self.config.interval) => {
+                    match squash(&self.state, None, self.config.keep_last).await {
+                        Ok(report) if report.removed > 0 => info!(
+                            "Squashed {} runs of the history, removing {} commits",
+                            report.runs, report.removed
+                        ),
+                        Ok(_) => {}
+                        Err(e) => error!("Failed to squash the history: {:?}", e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Squash the history of `resource_key`, or of every resource, keeping the
+/// last `keep_last` commits of each
+pub(crate) async fn squash(
+    state: &AppState,
+    resource_key: Option<String>,
+    keep_last: usize,
+) -> Result<SquashReport> {
+    let version_store = state.version_store.clone();
+    let report = tokio::task::spawn_blocking(move || match resource_key {
+        Some(key) => version_store.squash_linear_history(&key, keep_last),
+        None => version_store.squash_all_linear_history(keep_last),
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("history squash task failed: {}", e)))??;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field_selector::FieldSelector;
+    use crate::handlers::common::{create_resource, delete_resource, update_resource};
+    use crate::handlers::replication::{get_replication_stream, ReplicationParams};
+    use crate::watch::{watch_resource_stream, WatchParams};
+    use axum::extract::{Query, State};
+    use axum::http::HeaderMap;
+    use futures_util::StreamExt;
+    use reddwarf_core::{ConfigMap, GroupVersionKind, Resource, ResourceKey};
+    use reddwarf_storage::{KVStore, KeyEncoder, RedbBackend};
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    fn state() -> (tempfile::TempDir, Arc<AppState>) {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        (dir, Arc::new(AppState::new(storage, version_store)))
+    }
+
+    fn config_map(name: &str, n: u32) -> ConfigMap {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": name, "namespace": "default"},
+            "data": {"n": n.to_string()}
+        }))
+        .unwrap()
+    }
+
+    fn key(name: &str) -> ResourceKey {
+        ResourceKey::new(
+            GroupVersionKind::from_api_version_kind("v1", "ConfigMap"),
+            "default",
+            name,
+        )
+    }
+
+    /// Write `n` to the config map `name`, creating it at 0, and return the
+    /// commit ID of the write
+    async fn write(state: &AppState, name: &str, n: u32) -> String {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let written = if n == 0 {
+            create_resource(state, config_map(name, 0)).await
+        } else {
+            update_resource(state, config_map(name, n)).await
+        };
+        written.unwrap().resource_version().unwrap().0
+    }
+
+    fn exists(state: &AppState, commit_id: &str) -> bool {
+        state.version_store.get_commit(commit_id).is_ok()
+    }
+
+    fn storage_key(name: &str) -> String {
+        KeyEncoder::encode_resource_key(&key(name))
+    }
+
+    #[tokio::test]
+    async fn test_squash_only_adjacent_updates() {
+        let (_dir, state) = state();
+        // web: created, three updates, deleted, recreated, four updates;
+        // db is updated in between
+        let mut web = vec![write(&state, "web", 0).await];
+        let db_created = write(&state, "db", 0).await;
+        for n in 1..=3 {
+            web.push(write(&state, "web", n).await);
+        }
+        let db_updated = write(&state, "db", 1).await;
+        delete_resource(&state, &key("web")).await.unwrap();
+        let deleted = state.version_store.head_id().unwrap();
+        for n in 0..=4 {
+            web.push(write(&state, "web", n).await);
+        }
+
+        // Updates 1-3 and 1-3 after the recreation are squashed into their
+        // last; the deletion ends a run, and the creations and the last
+        // update are kept
+        let report = squash(&state, Some(storage_key("web")), 1).await.unwrap();
+        assert_eq!(report.runs, 2);
+        assert_eq!(report.removed, 4);
+        for removed in [&web[1], &web[2], &web[5], &web[6]] {
+            assert!(!exists(&state, removed));
+        }
+        for kept in [&web[0], &web[3], &deleted, &web[4], &web[7], &web[8]] {
+            assert!(exists(&state, kept));
+        }
+
+        // A squashed commit goes from the state before its run to its own
+        let squashed = state.version_store.get_commit(&web[3]).unwrap();
+        let change = &squashed.changes[0];
+        let data = |content: &str| {
+            let object: serde_json::Value = serde_json::from_str(content).unwrap();
+            object["data"]["n"].clone()
+        };
+        assert_eq!(data(&change.content), "3");
+        assert_eq!(data(change.previous_content.as_deref().unwrap()), "0");
+
+        // The other config map's commits are untouched
+        assert!(exists(&state, &db_created));
+        assert!(exists(&state, &db_updated));
+        assert!(state.version_store.check_integrity().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_squash_keeps_commits_of_connected_watches_and_replicas() {
+        let (_dir, state) = state();
+        let mut web = vec![write(&state, "web", 0).await];
+        for n in 1..=6 {
+            web.push(write(&state, "web", n).await);
+        }
+
+        // A watch resuming from update 2 and a replica from update 4
+        let watch = watch_resource_stream(
+            &state,
+            GroupVersionKind::from_api_version_kind("v1", "ConfigMap"),
+            Some("default".to_string()),
+            FieldSelector::default(),
+            &WatchParams {
+                watch: Some("true".to_string()),
+                resource_version: Some(web[2].clone()),
+                ..Default::default()
+            },
+            &HeaderMap::new(),
+        )
+        .unwrap();
+        let replica = get_replication_stream(
+            State(state.clone()),
+            Query(ReplicationParams {
+                since: Some(web[4].clone()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        // Runs end at the commits they are positioned at
+        let report = squash(&state, Some(storage_key("web")), 1).await.unwrap();
+        assert_eq!(report.runs, 2);
+        assert_eq!(report.removed, 2);
+        for removed in [&web[1], &web[3]] {
+            assert!(!exists(&state, removed));
+        }
+        for kept in [&web[2], &web[4], &web[5], &web[6]] {
+            assert!(exists(&state, kept));
+        }
+
+        // The watch's pin follows the events it is sent: once it has seen
+        // update 6, update 2 is free to go but the replica's 4 is not
+        let mut body = watch.into_body().into_data_stream();
+        let mut text = String::new();
+        while text.lines().count() < 4 {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(2), body.next())
+                .await
+                .expect("replayed events")
+                .unwrap()
+                .unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        let last: serde_json::Value = serde_json::from_str(text.lines().last().unwrap()).unwrap();
+        assert_eq!(
+            last["object"]["metadata"]["resourceVersion"],
+            web[6].as_str()
+        );
+        let report = squash(&state, Some(storage_key("web")), 1).await.unwrap();
+        assert_eq!(report.removed, 1);
+        assert!(!exists(&state, &web[2]));
+        assert!(exists(&state, &web[4]));
+
+        // Disconnected, nothing is held back
+        drop(body);
+        drop(replica);
+        let report = squash(&state, Some(storage_key("web")), 1).await.unwrap();
+        assert_eq!(report.removed, 1);
+        assert!(!exists(&state, &web[4]));
+    }
+
+    #[tokio::test]
+    async fn test_resource_versions_resolve_after_squash() {
+        let (_dir, state) = state();
+        for name in ["web", "db"] {
+            for n in 0..=5 {
+                write(&state, name, n).await;
+            }
+        }
+        let list_version = state.version_store.head_id().unwrap();
+
+        let report = squash(&state, None, 1).await.unwrap();
+        assert_eq!(report.removed, 6);
+
+        // Every stored object's resource version, and the list's, is still
+        // a commit watches can resume from
+        for (_, value) in state
+            .storage
+            .scan(KeyEncoder::encode_prefix("v1", "ConfigMap", None).as_bytes())
+            .unwrap()
+        {
+            let object: ConfigMap = serde_json::from_slice(&value).unwrap();
+            let rv = object.resource_version().unwrap().0;
+            assert!(exists(&state, &rv));
+            assert!(state.version_store.commits_since(&rv).is_ok());
+        }
+        assert!(state
+            .version_store
+            .commits_since(&list_version)
+            .unwrap()
+            .is_empty());
+
+        // Resuming from before the squashed runs replays the surviving
+        // commits, ending at each object's current state
+        let db_created = state
+            .version_store
+            .list_commits()
+            .unwrap()
+            .into_iter()
+            .filter(|c| c.changes[0].resource_key == storage_key("db"))
+            .min_by_key(|c| c.timestamp)
+            .unwrap();
+        let replayed = state.version_store.commits_since(&db_created.id).unwrap();
+        assert_eq!(replayed.len(), 2);
+        let last = &replayed[replayed.len() - 1].changes[0];
+        let object: serde_json::Value = serde_json::from_str(&last.content).unwrap();
+        assert_eq!(object["data"]["n"], "5");
+    }
+}
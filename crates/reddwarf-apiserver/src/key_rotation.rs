@@ -0,0 +1,240 @@
+//! Background re-encryption of stored values: picks up a rotated primary
+//! key from the KMS provider and moves values encrypted under older keys
+//! over to it, one transaction per value, while the server keeps serving
+//! them under their old keys
+
+use crate::{ApiError, AppState, Result};
+use reddwarf_storage::EnvelopeEncryptor;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+/// Configuration for the key rotator
+#[derive(Debug, Clone)]
+pub struct KeyRotatorConfig {
+    /// How often the KMS provider is checked for a new primary key
+    pub interval: Duration,
+}
+
+impl Default for KeyRotatorConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Re-encrypts stored values whenever the primary encryption key rotates
+pub struct KeyRotator {
+    state: Arc<AppState>,
+    config: KeyRotatorConfig,
+}
+
+impl KeyRotator {
+    pub fn new(state: Arc<AppState>, config: KeyRotatorConfig) -> Self {
+        Self { state, config }
+    }
+
+    /// Run the rotation loop; returns at once when encryption is disabled,
+    /// and on read replicas, which copy the leader's re-encrypted values
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        let Some(encryptor) = self.state.encryption.clone() else {
+            return Ok(());
+        };
+        if self.state.leader.is_some() {
+            return Ok(());
+        }
+        info!(
+            "Starting key rotator ({} KMS provider)",
+            encryptor.provider().name()
+        );
+
+        // Finish any rotation made while the server was down
+        if let Err(e) = self.reencrypt(encryptor.clone()).await {
+            error!("Failed to re-encrypt stored values: {:?}", e);
+        }
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Key rotator shutting down");
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(self.config.interval) => {
+                    if let Err(e) = self.check(encryptor.clone()).await {
+                        error!("Failed to rotate the encryption key: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reload the provider and re-encrypt if its primary key changed
+    async fn check(&self, encryptor: Arc<EnvelopeEncryptor>) -> Result<()> {
+        let provider = encryptor.clone();
+        let changed = tokio::task::spawn_blocking(move || provider.provider().reload())
+            .await
+            .map_err(|e| ApiError::Internal(format!("key reload task failed: {}", e)))??;
+        if changed {
+            info!("Primary encryption key rotated, re-encrypting stored values");
+            self.reencrypt(encryptor).await?;
+        }
+        Ok(())
+    }
+
+    /// Move every value encrypted under an older key to the primary key
+    async fn reencrypt(&self, encryptor: Arc<EnvelopeEncryptor>) -> Result<usize> {
+        let storage = self.state.storage.clone();
+        let rewritten =
+            tokio::task::spawn_blocking(move || encryptor.reencrypt(storage.as_ref(), b""))
+                .await
+                .map_err(|e| ApiError::Internal(format!("re-encryption task failed: {}", e)))??;
+        Ok(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::{get_resource, ListPath};
+    use crate::handlers::generic::ResourceHandlers;
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use reddwarf_core::{ResourceKey, Secret};
+    use reddwarf_storage::{KVStore, KeyEncoder, LocalKms, RedbBackend};
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        keyring: std::path::PathBuf,
+        encryptor: Arc<EnvelopeEncryptor>,
+        state: Arc<AppState>,
+    }
+
+    fn fixture() -> Fixture {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let keyring = dir.path().join("keys");
+        LocalKms::rotate(&keyring, Some("k1")).unwrap();
+        let encryptor = Arc::new(EnvelopeEncryptor::new(Arc::new(
+            LocalKms::open(&keyring).unwrap(),
+        )));
+        let state =
+            Arc::new(AppState::new(storage, version_store).with_encryption(encryptor.clone()));
+        Fixture {
+            _dir: dir,
+            keyring,
+            encryptor,
+            state,
+        }
+    }
+
+    async fn create_secret(state: &Arc<AppState>, name: &str, password: &str) -> ResourceKey {
+        let secret: Secret = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {"name": name},
+            "stringData": {"password": password}
+        }))
+        .unwrap();
+        ResourceHandlers::<Secret>::create(
+            State(state.clone()),
+            Path(ListPath {
+                namespace: Some("default".to_string()),
+            }),
+            Json(secret),
+        )
+        .await
+        .unwrap();
+        ResourceKey::new(ResourceHandlers::<Secret>::gvk(), "default", name)
+    }
+
+    async fn password(state: &Arc<AppState>, key: &ResourceKey) -> Vec<u8> {
+        let secret: Secret = get_resource(state, key).await.unwrap();
+        secret.data.unwrap()["password"].0.clone()
+    }
+
+    /// Whether the stored value of `key` is still under an older key
+    fn under_old_key(fixture: &Fixture, key: &ResourceKey) -> bool {
+        let stored = fixture
+            .state
+            .storage
+            .get(KeyEncoder::encode_resource_key(key).as_bytes())
+            .unwrap()
+            .unwrap();
+        assert!(EnvelopeEncryptor::is_encrypted(&stored));
+        fixture.encryptor.needs_rotation(&stored).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rotation_reencrypts_secrets() {
+        let fixture = fixture();
+        let rotator = KeyRotator::new(fixture.state.clone(), KeyRotatorConfig::default());
+        let key = create_secret(&fixture.state, "creds", "hunter2").await;
+
+        // Nothing to do until the keyring changes
+        rotator.check(fixture.encryptor.clone()).await.unwrap();
+        assert!(!under_old_key(&fixture, &key));
+
+        LocalKms::rotate(&fixture.keyring, Some("k2")).unwrap();
+        fixture.encryptor.provider().reload().unwrap();
+
+        // Still readable under the retained old key before re-encryption
+        assert!(under_old_key(&fixture, &key));
+        assert_eq!(password(&fixture.state, &key).await, b"hunter2");
+
+        assert_eq!(
+            rotator.reencrypt(fixture.encryptor.clone()).await.unwrap(),
+            1
+        );
+        assert!(!under_old_key(&fixture, &key));
+        assert_eq!(password(&fixture.state, &key).await, b"hunter2");
+
+        // check() picks up the next rotation on its own
+        LocalKms::rotate(&fixture.keyring, Some("k3")).unwrap();
+        rotator.check(fixture.encryptor.clone()).await.unwrap();
+        assert!(!under_old_key(&fixture, &key));
+        assert_eq!(fixture.encryptor.provider().primary_key_id().unwrap(), "k3");
+        assert_eq!(password(&fixture.state, &key).await, b"hunter2");
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_rotation_finishes_on_restart() {
+        let fixture = fixture();
+        let first = create_secret(&fixture.state, "first", "hunter2").await;
+        let second = create_secret(&fixture.state, "second", "hunter3").await;
+
+        // The server stops after re-encrypting only the first value
+        LocalKms::rotate(&fixture.keyring, Some("k2")).unwrap();
+        fixture.encryptor.provider().reload().unwrap();
+        let prefix = KeyEncoder::encode_resource_key(&first);
+        assert_eq!(
+            fixture
+                .encryptor
+                .reencrypt(fixture.state.storage.as_ref(), prefix.as_bytes())
+                .unwrap(),
+            1
+        );
+        assert!(!under_old_key(&fixture, &first));
+        assert!(under_old_key(&fixture, &second));
+
+        // Both keys serve reads in the meantime
+        assert_eq!(password(&fixture.state, &first).await, b"hunter2");
+        assert_eq!(password(&fixture.state, &second).await, b"hunter3");
+
+        // On start the rotator finishes the remaining values before it waits
+        let token = CancellationToken::new();
+        token.cancel();
+        KeyRotator::new(fixture.state.clone(), KeyRotatorConfig::default())
+            .run(token)
+            .await
+            .unwrap();
+        assert!(!under_old_key(&fixture, &first));
+        assert!(!under_old_key(&fixture, &second));
+        assert_eq!(password(&fixture.state, &first).await, b"hunter2");
+        assert_eq!(password(&fixture.state, &second).await, b"hunter3");
+    }
+}
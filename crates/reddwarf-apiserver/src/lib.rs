@@ -5,21 +5,63 @@
 //! - Kubernetes API endpoints
 //! - Resource handlers (GET, POST, PUT, PATCH, DELETE)
 //! - LIST with filtering and pagination
+//! - Field selectors on LIST and WATCH
+//! - Limits on request bodies and connections against oversized and
+//!   slow-loris requests
 //! - WATCH mechanism for streaming updates
+//! - Proxying of operator requests to node agents
+//! - Interactive `exec` and `attach` sessions with containers
+//! - Health of the embedded components as ComponentStatuses
+//! - A read-only node-local API served by each agent
+//! - Per-namespace usage accounting for chargeback
+//! - Read replicas following a leader's commit stream
+//! - TLS bootstrap of joining nodes from bootstrap tokens
+//! - Online re-encryption of stored values after a key rotation
+//! - Squashing of the commit history's runs of single-resource updates
+//! - An optional web dashboard (`dashboard` feature)
 
+pub mod accounting;
+pub mod auth;
+pub mod bootstrap;
+pub mod components;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod debug;
+pub mod deadline;
 pub mod error;
 pub mod event_bus;
+pub mod event_sweeper;
+pub mod field_selector;
 pub mod handlers;
+pub mod history_squasher;
+pub mod key_rotation;
+pub mod limits;
+pub mod node_api;
+pub mod proxy;
+pub mod replica;
 pub mod response;
 pub mod server;
+pub mod sessions;
 pub mod state;
 pub mod tls;
 pub mod validation;
 pub mod watch;
 
 // Re-export commonly used types
+pub use accounting::{UsageRecorder, UsageRecorderConfig};
+pub use bootstrap::BootstrapSigner;
+pub use components::{ComponentHealth, ComponentRegistry};
+pub use debug::{ZoneDebug, ZoneDebugBackend};
 pub use error::{ApiError, Result};
 pub use event_bus::ResourceEvent;
+pub use event_sweeper::{EventSweeper, EventSweeperConfig};
+pub use history_squasher::{HistorySquasher, HistorySquasherConfig};
+pub use key_rotation::{KeyRotator, KeyRotatorConfig};
+pub use limits::RequestLimits;
+pub use node_api::{NodeApiBackend, NodeApiConfig, NodeApiServer, NodeStats, PodStats, StatsSummary};
+pub use proxy::NodeProxy;
+pub use replica::{Leader, ReplicaFollower, ReplicaFollowerConfig};
 pub use server::{ApiServer, Config};
+pub use sessions::{ContainerSessionBackend, ContainerSessions};
 pub use state::AppState;
 pub use tls::{TlsMaterial, TlsMode};
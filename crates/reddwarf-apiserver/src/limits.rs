@@ -0,0 +1,456 @@
+//! Request limits
+//!
+//! Bounds on what a client may make the server hold on to, so that neither
+//! oversized payloads nor clients trickling bytes in (slow-loris) can exhaust
+//! its memory or connections:
+//! - a request body larger than the limit of the kind it is sent to is
+//!   answered with a 413 Status, without reading it when its
+//!   `Content-Length` already says so;
+//! - a request body not received within the body read timeout is answered
+//!   with a 408 Status;
+//! - a connection that does not send complete request headers within the
+//!   header read timeout is closed;
+//! - a connection accepted beyond the connection cap is closed straight away.
+
+use crate::handlers::generic::RegisteredKind;
+use crate::ApiError;
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_server::accept::Accept;
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+/// Default largest request body, as in Kubernetes
+pub const DEFAULT_MAX_BODY_BYTES: usize = 3 * 1024 * 1024;
+
+/// Default time a client has to send a request body
+pub const DEFAULT_BODY_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default time a client has to send the headers of a request
+pub const DEFAULT_HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default cap on open connections
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+/// Limits on the requests and connections of an API server
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    /// Largest request body of kinds without a limit of their own
+    pub max_body_bytes: usize,
+    /// Largest request body per kind, by plural, overriding the kinds'
+    /// [`ResourceKind::MAX_BODY_BYTES`](crate::handlers::generic::ResourceKind::MAX_BODY_BYTES)
+    pub max_body_bytes_per_kind: HashMap<String, usize>,
+    /// Longest a client may take to send a request body
+    pub body_read_timeout: Duration,
+    /// Longest a client may take to send the headers of a request
+    pub header_read_timeout: Duration,
+    /// Most connections open at once
+    pub max_connections: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            max_body_bytes_per_kind: HashMap::new(),
+            body_read_timeout: DEFAULT_BODY_READ_TIMEOUT,
+            header_read_timeout: DEFAULT_HEADER_READ_TIMEOUT,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// These limits, with the body limits of `kinds` where none is
+    /// configured for them
+    pub fn with_kinds(mut self, kinds: &[RegisteredKind]) -> Self {
+        for kind in kinds {
+            if let Some(bytes) = kind.max_body_bytes {
+                self.max_body_bytes_per_kind
+                    .entry(kind.plural.to_string())
+                    .or_insert(bytes);
+            }
+        }
+        self
+    }
+
+    /// Largest body accepted for a request to `path`
+    pub fn max_body_bytes_for(&self, path: &str) -> usize {
+        resource_plural(path)
+            .and_then(|plural| self.max_body_bytes_per_kind.get(plural))
+            .copied()
+            .unwrap_or(self.max_body_bytes)
+    }
+
+    /// Apply the header read timeout to the connections `builder` serves
+    pub fn configure_http(&self, builder: &mut Builder<TokioExecutor>) {
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .header_read_timeout(self.header_read_timeout);
+        builder.http2().timer(TokioTimer::new());
+    }
+}
+
+/// Plural of the kind a request to `path` is about, e.g. `pods` for
+/// `/api/v1/namespaces/default/pods/web/status`
+fn resource_plural(path: &str) -> Option<&str> {
+    let rest = match path.strip_prefix("/api/") {
+        Some(rest) => rest.split_once('/')?.1,
+        None => {
+            let rest = path.strip_prefix("/apis/")?;
+            let (_group, rest) = rest.split_once('/')?;
+            rest.split_once('/')?.1
+        }
+    };
+    let rest = rest.strip_prefix("watch/").unwrap_or(rest);
+    let mut segments = rest.split('/');
+    let first = segments.next().filter(|s| !s.is_empty())?;
+    match (first, segments.next(), segments.next()) {
+        ("namespaces", Some(_namespace), Some(plural)) if !plural.is_empty() => Some(plural),
+        _ => Some(first),
+    }
+}
+
+/// Middleware reading request bodies up to the limit of their kind within
+/// the body read timeout, answering 413 or 408 otherwise
+pub async fn limit_body(
+    State(limits): State<Arc<RequestLimits>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = limits.max_body_bytes_for(request.uri().path());
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limit as u64) {
+        return too_large(limit).into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match tokio::time::timeout(limits.body_read_timeout, read_body(body, limit)).await {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => return e.into_response(),
+        Err(_) => {
+            warn!(
+                "{} {}: request body not received within {:?}",
+                parts.method,
+                parts.uri.path(),
+                limits.body_read_timeout
+            );
+            return ApiError::RequestTimeout(format!(
+                "The request body was not received within {}s",
+                limits.body_read_timeout.as_secs_f64()
+            ))
+            .into_response();
+        }
+    };
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// Read `body`, failing as soon as it grows past `limit` bytes
+async fn read_body(body: Body, limit: usize) -> crate::Result<Bytes> {
+    let mut stream = body.into_data_stream();
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk
+            .map_err(|e| ApiError::BadRequest(format!("Failed to read request body: {}", e)))?;
+        if data.len() + chunk.len() > limit {
+            return Err(too_large(limit));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(data))
+}
+
+fn too_large(limit: usize) -> ApiError {
+    ApiError::PayloadTooLarge(format!(
+        "The request body is larger than the limit of {} bytes",
+        limit
+    ))
+}
+
+/// Acceptor closing connections beyond a cap before handing the others to
+/// the `inner` acceptor, e.g. the TLS handshake
+#[derive(Debug, Clone)]
+pub struct ConnectionLimit<A> {
+    permits: Arc<Semaphore>,
+    max: usize,
+    inner: A,
+}
+
+impl<A> ConnectionLimit<A> {
+    /// Allow at most `max` connections open at once
+    pub fn new(max: usize, inner: A) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max)),
+            max,
+            inner,
+        }
+    }
+}
+
+impl<A, S> Accept<TcpStream, S> for ConnectionLimit<A>
+where
+    A: Accept<TcpStream, S> + Clone + Send + 'static,
+    A::Future: Send,
+    S: Send + 'static,
+{
+    type Stream = LimitedStream<A::Stream>;
+    type Service = A::Service;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: TcpStream, service: S) -> Self::Future {
+        let permit = self.permits.clone().try_acquire_owned();
+        let max = self.max;
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let Ok(permit) = permit else {
+                warn!(
+                    "Closing connection from {:?}: {} connections are open",
+                    stream.peer_addr().ok(),
+                    max
+                );
+                return Err(io::Error::other("too many open connections"));
+            };
+            let (stream, service) = inner.accept(stream, service).await?;
+            Ok((
+                LimitedStream {
+                    inner: stream,
+                    _permit: permit,
+                },
+                service,
+            ))
+        })
+    }
+}
+
+/// A connection holding one of the slots of a [`ConnectionLimit`] until it
+/// is dropped
+#[derive(Debug)]
+pub struct LimitedStream<S> {
+    inner: S,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for LimitedStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for LimitedStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::Router;
+    use axum_server::accept::DefaultAcceptor;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_body_limit_per_kind() {
+        let mut limits = RequestLimits::default();
+        limits
+            .max_body_bytes_per_kind
+            .insert("configmaps".to_string(), 10);
+        let kinds = crate::server::builtin_resources().kinds().to_vec();
+        let limits = limits.with_kinds(&kinds);
+
+        for (path, limit) in [
+            ("/api/v1/namespaces/default/configmaps", 10),
+            ("/api/v1/namespaces/default/configmaps/app", 10),
+            ("/api/v1/configmaps", 10),
+            ("/api/v1/watch/namespaces/default/configmaps", 10),
+            ("/api/v1/namespaces/default/secrets/token", 2 * 1024 * 1024),
+            (
+                "/api/v1/namespaces/default/pods/web/status",
+                DEFAULT_MAX_BODY_BYTES,
+            ),
+            ("/api/v1/namespaces/default", DEFAULT_MAX_BODY_BYTES),
+            (
+                "/apis/apps/v1/namespaces/default/deployments",
+                DEFAULT_MAX_BODY_BYTES,
+            ),
+            ("/healthz", DEFAULT_MAX_BODY_BYTES),
+        ] {
+            assert_eq!(limits.max_body_bytes_for(path), limit, "{}", path);
+        }
+        assert_eq!(
+            resource_plural("/apis/apps/v1/namespaces/default/deployments/web"),
+            Some("deployments")
+        );
+        assert_eq!(
+            resource_plural("/api/v1/namespaces/default"),
+            Some("namespaces")
+        );
+        assert_eq!(resource_plural("/api/v1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_and_read_timeout() {
+        let limits = RequestLimits {
+            max_body_bytes: 16,
+            body_read_timeout: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let router = Router::new()
+            .route(
+                "/api/v1/namespaces/default/pods",
+                post(|body: Bytes| async move { body }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(limits),
+                limit_body,
+            ));
+        let send = |body: Body, length: Option<usize>| {
+            let mut request = Request::post("/api/v1/namespaces/default/pods");
+            if let Some(length) = length {
+                request = request.header(header::CONTENT_LENGTH, length);
+            }
+            router.clone().oneshot(request.body(body).unwrap())
+        };
+        let status_of = |response: Response| async move {
+            let code = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(status["kind"], "Status");
+            assert_eq!(status["code"], code.as_u16());
+            (code, status["reason"].as_str().unwrap().to_string())
+        };
+
+        let response = send(Body::from("{}"), Some(2)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"{}");
+
+        // Refused by its Content-Length, or once too much of it has arrived
+        let response = send(Body::from("x".repeat(17)), Some(17)).await.unwrap();
+        assert_eq!(
+            status_of(response).await,
+            (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "RequestEntityTooLarge".to_string()
+            )
+        );
+        let chunks = futures_util::stream::iter(
+            ["0123456789", "0123456789"].map(|c| Ok::<_, io::Error>(Bytes::from(c))),
+        );
+        let response = send(Body::from_stream(chunks), None).await.unwrap();
+        assert_eq!(status_of(response).await.0, StatusCode::PAYLOAD_TOO_LARGE);
+
+        // A body that never finishes arriving
+        let trickle = futures_util::stream::once(async { Ok::<_, io::Error>(Bytes::from("{")) })
+            .chain(futures_util::stream::pending());
+        let response = send(Body::from_stream(trickle), None).await.unwrap();
+        assert_eq!(
+            status_of(response).await,
+            (StatusCode::REQUEST_TIMEOUT, "RequestTimeout".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_cap_and_header_timeout() {
+        let limits = RequestLimits {
+            header_read_timeout: Duration::from_secs(1),
+            max_connections: 1,
+            ..Default::default()
+        };
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server = axum_server::from_tcp(listener).acceptor(ConnectionLimit::new(
+            limits.max_connections,
+            DefaultAcceptor,
+        ));
+        limits.configure_http(server.http_builder());
+        let app = Router::new().route("/healthz", axum::routing::get(|| async { "ok" }));
+        tokio::spawn(server.serve(app.into_make_service()));
+
+        // Closed without a response: by the server, not by the test timing
+        // out
+        async fn closed(stream: &mut tokio::net::TcpStream) -> bool {
+            let mut buf = [0u8; 64];
+            matches!(
+                tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await,
+                Ok(Ok(0) | Err(_))
+            )
+        }
+
+        // A client that never finishes its headers holds the only slot
+        let mut slow = tokio::net::TcpStream::connect(addr).await.unwrap();
+        slow.write_all(b"GET /healthz HTTP/1.1\r\nHost: a")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut refused = tokio::net::TcpStream::connect(addr).await.unwrap();
+        assert!(closed(&mut refused).await);
+
+        // ... until the header read timeout closes it
+        assert!(closed(&mut slow).await);
+        drop(slow);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET /healthz HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.ends_with("ok"));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::response::ApiResponse;
+use crate::tls::{self, TlsMaterial};
+use crate::Result;
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use reddwarf_core::Pod;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tower_http::trace::TraceLayer;
+use tracing::info;
+
+/// Backend for the node-local API
+///
+/// Answers from what the agent itself knows: the pods its controller last
+/// saw and the usage of its zones. Nothing is read from the cluster's
+/// storage, so the API keeps answering while the control plane is down.
+#[async_trait]
+pub trait NodeApiBackend: Send + Sync {
+    /// Pods assigned to this node, as last seen by its agent
+    async fn pods(&self) -> Result<Vec<Pod>>;
+
+    /// Resource summary of the node and its pods
+    async fn stats_summary(&self) -> Result<StatsSummary>;
+}
+
+/// Body of `/stats/summary`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsSummary {
+    pub node: NodeStats,
+    pub pods: Vec<PodStats>,
+}
+
+/// Node-wide figures of `/stats/summary`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStats {
+    pub node_name: String,
+    pub cpu_count: u32,
+    pub memory_total_bytes: u64,
+    pub memory_available_bytes: u64,
+    /// Zones installed on the node, including warm and orphaned ones
+    pub zone_count: usize,
+}
+
+/// Per-pod figures of `/stats/summary`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodStats {
+    pub namespace: String,
+    pub name: String,
+    pub uid: Option<String>,
+    pub phase: Option<String>,
+    /// Zone backing the pod, if the runtime knows it
+    pub zone_name: Option<String>,
+    /// State of that zone as the runtime reports it
+    pub zone_state: Option<String>,
+    pub cpu_request_millicores: i64,
+    pub memory_request_bytes: i64,
+}
+
+/// Node-local API configuration
+#[derive(Clone)]
+pub struct NodeApiConfig {
+    /// Address to listen on
+    pub listen_addr: SocketAddr,
+    /// Certificate served to clients and CA their certificates must be
+    /// issued by
+    pub tls: TlsMaterial,
+}
+
+/// Read-only, kubelet-style HTTPS API of one node: `/pods`,
+/// `/stats/summary` and `/healthz`
+///
+/// Served on its own port so monitoring agents can pull node-scoped data
+/// without a round trip through the API server. Only clients presenting a
+/// certificate issued by the cluster CA are accepted.
+pub struct NodeApiServer {
+    config: NodeApiConfig,
+    backend: Arc<dyn NodeApiBackend>,
+}
+
+impl NodeApiServer {
+    /// Create a new node-local API server
+    pub fn new(config: NodeApiConfig, backend: Arc<dyn NodeApiBackend>) -> Self {
+        Self { config, backend }
+    }
+
+    /// Build the router
+    fn build_router(&self) -> Router {
+        router(self.backend.clone())
+    }
+
+    /// Run the server, shutting down gracefully when `token` is cancelled.
+    pub async fn run(self, token: CancellationToken) -> std::result::Result<(), std::io::Error> {
+        let app = self.build_router();
+
+        let server_config = tls::mutual_tls_server_config(&self.config.tls)
+            .map_err(|e| std::io::Error::other(format!("TLS setup failed: {e}")))?;
+        let rustls_config =
+            axum_server::tls_rustls::RustlsConfig::from_config(Arc::new(server_config));
+
+        info!(
+            "Starting node-local API on {} (HTTPS, client certificates required)",
+            self.config.listen_addr
+        );
+
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            shutdown_handle.graceful_shutdown(Some(std::time::Duration::from_secs(10)));
+        });
+
+        axum_server::bind_rustls(self.config.listen_addr, rustls_config)
+            .handle(handle)
+            .serve(app.into_make_service())
+            .await
+    }
+}
+
+/// Routes of the node-local API over `backend`
+fn router(backend: Arc<dyn NodeApiBackend>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/pods", get(list_pods))
+        .route("/stats/summary", get(get_stats_summary))
+        .layer(TraceLayer::new_for_http())
+        .with_state(backend)
+}
+
+/// GET /healthz
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// GET /pods
+async fn list_pods(State(backend): State<Arc<dyn NodeApiBackend>>) -> Result<Response> {
+    let pods = backend.pods().await?;
+
+    Ok(ApiResponse::ok(json!({
+        "apiVersion": "v1",
+        "kind": "PodList",
+        "metadata": {},
+        "items": pods,
+    }))
+    .into_response())
+}
+
+/// GET /stats/summary
+async fn get_stats_summary(State(backend): State<Arc<dyn NodeApiBackend>>) -> Result<Response> {
+    let summary = backend.stats_summary().await?;
+
+    Ok(ApiResponse::ok(summary).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    struct FakeBackend;
+
+    #[async_trait]
+    impl NodeApiBackend for FakeBackend {
+        async fn pods(&self) -> Result<Vec<Pod>> {
+            let mut pod = Pod::default();
+            pod.metadata.name = Some("web".to_string());
+            pod.metadata.namespace = Some("default".to_string());
+            Ok(vec![pod])
+        }
+
+        async fn stats_summary(&self) -> Result<StatsSummary> {
+            Ok(StatsSummary {
+                node: NodeStats {
+                    node_name: "node1".to_string(),
+                    cpu_count: 4,
+                    zone_count: 1,
+                    ..Default::default()
+                },
+                pods: vec![PodStats {
+                    namespace: "default".to_string(),
+                    name: "web".to_string(),
+                    zone_state: Some("running".to_string()),
+                    cpu_request_millicores: 250,
+                    ..Default::default()
+                }],
+            })
+        }
+    }
+
+    async fn get_json(uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router(Arc::new(FakeBackend))
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_node_api_routes() {
+        let (status, _) = get_json("/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = get_json("/pods").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["kind"], "PodList");
+        assert_eq!(body["items"][0]["metadata"]["name"], "web");
+
+        let (status, body) = get_json("/stats/summary").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["node"]["nodeName"], "node1");
+        assert_eq!(body["pods"][0]["zoneState"], "running");
+        assert_eq!(body["pods"][0]["cpuRequestMillicores"], 250);
+
+        // Nothing but the read-only endpoints is served
+        let (status, _) = get_json("/api/v1/pods").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::auth::BearerToken;
+use std::time::Duration;
+
+/// How long to wait for a node agent to accept a proxied connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a proxied request may take in total
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Node proxy configuration: the client used to reach node agents plus the
+/// bearer token required to access `/api/v1/nodes/{name}/proxy`
+///
+/// The caller's `Authorization` header is forwarded as is, so agents that
+/// share the token also accept proxied `/debug/zones` requests.
+#[derive(Clone)]
+pub struct NodeProxy {
+    pub client: reqwest::Client,
+    /// `https` when node agents serve TLS, `http` otherwise
+    pub scheme: &'static str,
+    pub token: BearerToken,
+}
+
+impl NodeProxy {
+    /// Create a proxy that talks plain HTTP to node agents
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            client: build_client(None, None),
+            scheme: "http",
+            token: BearerToken::new(token),
+        }
+    }
+
+    /// Talk HTTPS to node agents, optionally trusting an additional CA
+    /// certificate such as the cluster CA, and presenting `identity` as the
+    /// client certificate (the node certificate of a joined agent)
+    pub fn with_tls(mut self, ca_pem: Option<&[u8]>, identity: Option<reqwest::Identity>) -> Self {
+        self.client = build_client(ca_pem, identity);
+        self.scheme = "https";
+        self
+    }
+}
+
+fn build_client(ca_pem: Option<&[u8]>, identity: Option<reqwest::Identity>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+
+    if let Some(pem) = ca_pem {
+        if let Ok(cert) = reqwest::Certificate::from_pem(pem) {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if let Some(identity) = identity {
+        builder = builder.identity(identity);
+    }
+
+    builder.build().unwrap_or_else(|_| reqwest::Client::new())
+}
@@ -0,0 +1,482 @@
+//! Read replicas: a `reddwarf serve --read-replica` process follows the
+//! commit stream of a leader into its own storage copy and serves GET, LIST
+//! and WATCH from it, forwarding every write to the leader
+//!
+//! The leader streams newline-delimited [`ReplicationMessage`]s from
+//! [`REPLICATION_PATH`]: a snapshot of its storage when the follower has no
+//! position it still knows, then each commit as it is made. Only committed
+//! changes replicate, so reads of state kept outside of commits (usage
+//! records, IP allocations) are forwarded too.
+
+use crate::event_bus::{EventBus, ResourceEvent};
+use crate::handlers::common::{seal, unseal};
+use crate::{ApiError, AppState, Result};
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use reddwarf_core::{GroupVersionKind, ResourceKey};
+use reddwarf_storage::KVStore;
+use reddwarf_versioning::{ChangeType, Commit};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Path of the leader's replication stream
+pub const REPLICATION_PATH: &str = "/apis/reddwarf.io/v1alpha1/replication/commits";
+
+/// Reads served by the leader only, as they depend on uncommitted state or
+/// on reaching node agents
+const LEADER_ONLY_READS: &[&str] = &[
+    "/apis/reddwarf.io/v1alpha1/clusterusage",
+    "/apis/reddwarf.io/v1alpha1/usagereports",
+];
+
+/// Request headers passed on to the leader
+const FORWARDED_REQUEST_HEADERS: &[header::HeaderName] =
+    &[header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE];
+
+/// How long to wait for the leader to accept a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a forwarded write may take in total
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest request body forwarded to the leader
+const MAX_FORWARDED_BODY: usize = 16 * 1024 * 1024;
+
+/// One line of the replication stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ReplicationMessage {
+    /// Every stored key and value as of `head`, replacing the follower's copy
+    Snapshot {
+        head: Option<Commit>,
+        entries: Vec<(String, String)>,
+    },
+    /// A commit made after the last message
+    Commit { commit: Commit },
+    /// Sent while the leader is idle, so followers notice dead connections
+    Heartbeat,
+}
+
+/// The leader a read replica follows
+#[derive(Clone)]
+pub struct Leader {
+    /// Base URL, e.g. `https://leader:6443`
+    pub url: String,
+    pub client: reqwest::Client,
+}
+
+impl Leader {
+    /// Follow the leader at `url`, trusting `ca_pem` in addition to the
+    /// system roots when it serves TLS with its own CA
+    pub fn new(url: impl Into<String>, ca_pem: Option<&[u8]>) -> Self {
+        // No overall timeout: the replication stream stays open
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none());
+        if let Some(cert) = ca_pem.and_then(|pem| reqwest::Certificate::from_pem(pem).ok()) {
+            builder = builder.add_root_certificate(cert);
+        }
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            client: builder.build().unwrap_or_else(|_| reqwest::Client::new()),
+        }
+    }
+}
+
+/// Whether a read replica forwards `method` on `path` to the leader
+fn forwarded(method: &Method, path: &str) -> bool {
+    if !matches!(*method, Method::GET | Method::HEAD) {
+        return true;
+    }
+    LEADER_ONLY_READS.contains(&path)
+        || (path.starts_with("/api/v1/nodes/") && path.contains("/proxy"))
+}
+
+/// Middleware of read replicas: relay writes (and leader-only reads) to the
+/// leader and its response back to the caller
+pub async fn forward_to_leader(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match &state.leader {
+        Some(leader) if forwarded(request.method(), request.uri().path()) => {
+            forward(leader, request)
+                .await
+                .unwrap_or_else(|e| e.into_response())
+        }
+        _ => next.run(request).await,
+    }
+}
+
+async fn forward(leader: &Leader, request: Request) -> Result<Response> {
+    let (parts, body) = request.into_parts();
+    let path_and_query = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let url = format!("{}{}", leader.url, path_and_query);
+    debug!("Forwarding {} {} to the leader", parts.method, url);
+
+    let body = to_bytes(body, MAX_FORWARDED_BODY)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to read request body: {}", e)))?;
+    let mut forwarded = leader
+        .client
+        .request(parts.method, &url)
+        .timeout(FORWARD_TIMEOUT)
+        .body(body);
+    for (name, value) in parts.headers.iter() {
+        if FORWARDED_REQUEST_HEADERS.contains(name) || name.as_str().starts_with("x-reddwarf-") {
+            forwarded = forwarded.header(name, value);
+        }
+    }
+
+    let upstream = forwarded
+        .send()
+        .await
+        .map_err(|e| ApiError::BadGateway(format!("Failed to reach the leader: {}", e)))?;
+    let status =
+        StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut headers = HeaderMap::new();
+    if let Some(value) = upstream.headers().get(header::CONTENT_TYPE) {
+        headers.insert(header::CONTENT_TYPE, value.clone());
+    }
+    let body = upstream.bytes().await.map_err(|e| {
+        ApiError::BadGateway(format!("Failed to read the leader's response: {}", e))
+    })?;
+    Ok((status, headers, Body::from(body)).into_response())
+}
+
+/// Configuration for the replica follower
+#[derive(Debug, Clone)]
+pub struct ReplicaFollowerConfig {
+    /// Wait between reconnection attempts
+    pub retry_interval: Duration,
+    /// Reconnect when the leader sends nothing, not even a heartbeat, for
+    /// this long
+    pub idle_timeout: Duration,
+}
+
+impl Default for ReplicaFollowerConfig {
+    fn default() -> Self {
+        Self {
+            retry_interval: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+/// Keeps a read replica's storage in step with the leader's commit stream
+pub struct ReplicaFollower {
+    state: Arc<AppState>,
+    config: ReplicaFollowerConfig,
+}
+
+impl ReplicaFollower {
+    pub fn new(state: Arc<AppState>, config: ReplicaFollowerConfig) -> Self {
+        Self { state, config }
+    }
+
+    /// Follow the leader until cancelled, reconnecting whenever the stream
+    /// breaks
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        let leader = self
+            .state
+            .leader
+            .clone()
+            .ok_or_else(|| ApiError::Internal("No leader to follow".to_string()))?;
+        info!("Following leader at {}", leader.url);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Replica follower shutting down");
+                    return Ok(());
+                }
+                result = self.follow(&leader) => {
+                    if let Err(e) = result {
+                        warn!("Lost the leader's commit stream: {:?}", e);
+                    }
+                }
+            }
+            tokio::select! {
+                _ = token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(self.config.retry_interval) => {}
+            }
+        }
+    }
+
+    /// Stream commits from the leader, starting after the local HEAD
+    async fn follow(&self, leader: &Leader) -> Result<()> {
+        let mut url = format!("{}{}", leader.url, REPLICATION_PATH);
+        if let Some(head) = self.state.version_store.head_id() {
+            url.push_str("?since=");
+            url.push_str(&head);
+        }
+        let mut response = leader
+            .client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| ApiError::BadGateway(format!("Failed to reach the leader: {}", e)))?;
+
+        let mut buffer = Vec::new();
+        loop {
+            let chunk = tokio::time::timeout(self.config.idle_timeout, response.chunk())
+                .await
+                .map_err(|_| ApiError::BadGateway("The leader went quiet".to_string()))?
+                .map_err(|e| {
+                    ApiError::BadGateway(format!("Failed to read from the leader: {}", e))
+                })?
+                .ok_or_else(|| ApiError::BadGateway("The leader closed the stream".to_string()))?;
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                if line.len() > 1 {
+                    self.apply(serde_json::from_slice(&line)?)?;
+                }
+            }
+        }
+    }
+
+    /// Apply one message of the leader's stream to the local copy
+    pub fn apply(&self, message: ReplicationMessage) -> Result<()> {
+        match message {
+            ReplicationMessage::Snapshot { head, entries } => {
+                info!("Replacing local copy with the leader's snapshot");
+                let kept: HashSet<&str> = entries.iter().map(|(key, _)| key.as_str()).collect();
+                let mut txn = self.state.storage.transaction()?;
+                for key in self.state.storage.keys()? {
+                    if !kept.contains(String::from_utf8_lossy(&key).as_ref()) {
+                        txn.delete(&key)?;
+                    }
+                }
+                for (key, value) in &entries {
+                    txn.put(key.as_bytes(), value.as_bytes())?;
+                }
+                txn.commit()?;
+                if let Some(head) = head {
+                    self.state.version_store.apply_commit(&head)?;
+                }
+            }
+            ReplicationMessage::Commit { commit } => {
+                // Already applied from an earlier connection
+                if self.state.version_store.get_commit(&commit.id).is_ok() {
+                    return Ok(());
+                }
+                let mut txn = self.state.storage.transaction()?;
+                for change in &commit.changes {
+                    match change.change_type {
+                        ChangeType::Create | ChangeType::Update => txn.put(
+                            change.resource_key.as_bytes(),
+                            &self.with_resource_version(
+                                &change.resource_key,
+                                &change.content,
+                                &commit.id,
+                            ),
+                        )?,
+                        ChangeType::Delete => txn.delete(change.resource_key.as_bytes())?,
+                    }
+                }
+                txn.commit()?;
+                self.state.version_store.apply_commit(&commit)?;
+                self.publish(&commit);
+            }
+            ReplicationMessage::Heartbeat => {}
+        }
+        Ok(())
+    }
+
+    /// Publish the events of a replicated commit to the replica's watchers
+    fn publish(&self, commit: &Commit) {
+        for change in &commit.changes {
+            let content = match change.change_type {
+                ChangeType::Delete => change.previous_content.as_deref(),
+                _ => Some(change.content.as_str()),
+            };
+            let Some(mut object) = content
+                .and_then(|c| {
+                    unseal(&self.state, change.resource_key.as_bytes(), c.as_bytes()).ok()
+                })
+                .and_then(|c| serde_json::from_slice::<serde_json::Value>(&c).ok())
+            else {
+                continue;
+            };
+            let Some(key) = object_key(&object) else {
+                continue;
+            };
+            if let Some(metadata) = object["metadata"].as_object_mut() {
+                metadata.insert("resourceVersion".to_string(), commit.id.clone().into());
+            }
+            let version = commit.id.clone();
+            let event = match change.change_type {
+                ChangeType::Create => ResourceEvent::added(key, object, version),
+                ChangeType::Update => ResourceEvent::modified(key, object, version),
+                ChangeType::Delete => ResourceEvent::deleted(key, object, version),
+            };
+            self.state.event_bus.publish(event);
+        }
+    }
+
+    /// Committed content as the leader stores it under `storage_key`, at
+    /// the commit's resource version; a value encrypted at rest is
+    /// decrypted to set it and encrypted again
+    fn with_resource_version(&self, storage_key: &str, content: &str, version: &str) -> Vec<u8> {
+        let plaintext = match unseal(&self.state, storage_key.as_bytes(), content.as_bytes()) {
+            Ok(plaintext) => plaintext,
+            Err(e) => {
+                warn!("Storing {} as the leader sent it: {:?}", storage_key, e);
+                return content.as_bytes().to_vec();
+            }
+        };
+        let Ok(mut object) = serde_json::from_slice::<serde_json::Value>(&plaintext) else {
+            return content.as_bytes().to_vec();
+        };
+        if let Some(metadata) = object["metadata"].as_object_mut() {
+            metadata.insert("resourceVersion".to_string(), version.into());
+        }
+        serde_json::to_vec(&object)
+            .map_err(ApiError::from)
+            .and_then(|data| seal(&self.state, storage_key, data))
+            .unwrap_or_else(|_| content.as_bytes().to_vec())
+    }
+}
+
+/// Key of a stored API object, from its own apiVersion, kind and metadata
+fn object_key(object: &serde_json::Value) -> Option<ResourceKey> {
+    let gvk = GroupVersionKind::from_api_version_kind(
+        object["apiVersion"].as_str()?,
+        object["kind"].as_str()?,
+    );
+    let name = object["metadata"]["name"].as_str()?;
+    Some(match object["metadata"]["namespace"].as_str() {
+        Some(namespace) => ResourceKey::new(gvk, namespace, name),
+        None => ResourceKey::cluster_scoped(gvk, name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::common::create_resource;
+    use crate::handlers::{get_replication_stream, ResourceRegistry};
+    use axum::routing::get;
+    use axum::Router;
+    use reddwarf_core::Namespace;
+    use reddwarf_storage::{KeyEncoder, RedbBackend};
+    use reddwarf_versioning::VersionStore;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    fn test_state(dir: &std::path::Path, name: &str) -> AppState {
+        let storage = Arc::new(RedbBackend::new(dir.join(name)).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        AppState::new(storage, version_store)
+    }
+
+    fn router(state: Arc<AppState>) -> Router {
+        ResourceRegistry::new()
+            .register::<Namespace>()
+            .into_router()
+            .route(REPLICATION_PATH, get(get_replication_stream))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                forward_to_leader,
+            ))
+            .with_state(state)
+    }
+
+    fn namespace(name: &str) -> Namespace {
+        let mut ns = Namespace::default();
+        ns.metadata.name = Some(name.to_string());
+        ns
+    }
+
+    fn stored(state: &AppState, name: &str) -> bool {
+        let key = ResourceKey::cluster_scoped(
+            GroupVersionKind::from_api_version_kind("v1", "Namespace"),
+            name,
+        );
+        state
+            .storage
+            .exists(KeyEncoder::encode_resource_key(&key).as_bytes())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replica_follows_leader_and_forwards_writes() {
+        let dir = tempdir().unwrap();
+        let leader = Arc::new(test_state(dir.path(), "leader.redb"));
+        create_resource(&leader, namespace("before")).await.unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let leader_router = router(leader.clone());
+        tokio::spawn(async move { axum::serve(listener, leader_router).await });
+
+        let replica =
+            Arc::new(test_state(dir.path(), "replica.redb").with_leader(Leader::new(url, None)));
+        let mut events = replica.subscribe();
+        let token = CancellationToken::new();
+        let follower = ReplicaFollower::new(replica.clone(), ReplicaFollowerConfig::default());
+        let follower_token = token.clone();
+        tokio::spawn(async move { follower.run(follower_token).await });
+
+        // Writes go to the leader and come back through the commit stream
+        let response = router(replica.clone())
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/v1/namespaces")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "apiVersion": "v1",
+                            "kind": "Namespace",
+                            "metadata": {"name": "after"}
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(stored(&leader, "after"));
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.resource_key.name, "after");
+        assert!(stored(&replica, "before"));
+        assert!(stored(&replica, "after"));
+        assert_eq!(
+            replica.version_store.head_id(),
+            leader.version_store.head_id()
+        );
+
+        // Reads are served from the replica's copy
+        let response = router(replica.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/namespaces/before")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        token.cancel();
+    }
+}
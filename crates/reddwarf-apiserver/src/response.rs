@@ -1,6 +1,8 @@
-use axum::http::StatusCode;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use reddwarf_core::resources::MultiVersion;
+use reddwarf_core::Resource;
 use serde::Serialize;
 use serde_json::json;
 
@@ -39,6 +41,64 @@ impl<T: Serialize> IntoResponse for ApiResponse<T> {
     }
 }
 
+/// Add a `Warning: 299` header to `response`, as Kubernetes does, when it
+/// answers a request at a deprecated API version of `T`
+pub fn with_deprecation_warning<T: MultiVersion>(
+    api_version: &str,
+    mut response: Response,
+) -> Response {
+    if let Some(warning) = T::deprecation_warning(api_version) {
+        if let Ok(value) = HeaderValue::from_str(&format!("299 - \"{}\"", warning)) {
+            response.headers_mut().insert(header::WARNING, value);
+        }
+    }
+    response
+}
+
+/// `Cache-Control` of object responses: clients may keep a copy but must
+/// revalidate it with `If-None-Match` before every use
+pub const CACHE_CONTROL_REVALIDATE: &str = "no-cache, private";
+
+/// Strong ETag of an object at `resource_version`
+pub fn etag(resource_version: &str) -> String {
+    format!("\"{}\"", resource_version)
+}
+
+/// Whether the request's `If-None-Match` lists `etag` (or is `*`); weak
+/// validators match too, as RFC 9110 asks of GET
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// Respond with `resource`, tagged with its resourceVersion as ETag; when
+/// the request's `If-None-Match` already holds that ETag, answer 304 Not
+/// Modified without serializing the object
+pub fn conditional_ok<T: Resource>(headers: &HeaderMap, resource: T) -> Response {
+    let Some(etag) = resource
+        .resource_version()
+        .and_then(|version| HeaderValue::from_str(&etag(version.as_str())).ok())
+    else {
+        return ApiResponse::ok(resource).into_response();
+    };
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_CONTROL_REVALIDATE),
+        ),
+    ];
+    if if_none_match(headers, etag.to_str().unwrap_or_default()) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (cache_headers, ApiResponse::ok(resource)).into_response()
+}
+
 /// Create a success Status response
 pub fn status_success(message: &str) -> Response {
     Json(json!({
@@ -51,6 +111,21 @@ pub fn status_success(message: &str) -> Response {
     .into_response()
 }
 
+/// Create a Status response for a created subresource, e.g. an Eviction
+pub fn status_created(message: &str) -> Response {
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "apiVersion": "v1",
+            "kind": "Status",
+            "status": "Success",
+            "message": message,
+            "code": 201
+        })),
+    )
+        .into_response()
+}
+
 /// Create a deletion Status response
 pub fn status_deleted(name: &str, kind: &str) -> Response {
     (
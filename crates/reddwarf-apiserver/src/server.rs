@@ -1,15 +1,67 @@
+use crate::bootstrap::BOOTSTRAP_PATH;
+use crate::deadline::{enforce_deadline, DEFAULT_REQUEST_TIMEOUT};
+use crate::event_bus::{render_subscriber_stats, EventBus};
 use crate::handlers::*;
+use crate::limits::{limit_body, ConnectionLimit, RequestLimits};
+use crate::replica::{forward_to_leader, REPLICATION_PATH};
 use crate::tls::{self, TlsMaterial, TlsMode};
 use crate::AppState;
-use axum::routing::get;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::{any, get};
 use axum::Router;
+use reddwarf_core::{
+    ConfigMap, CustomResourceDefinition, DaemonSet, Deployment, EndpointSlice, Endpoints,
+    HorizontalPodAutoscaler, ImageMapping, Lease, Namespace, NetworkPolicy, Node, PersistentVolume,
+    PersistentVolumeClaim, Pod, PodDisruptionBudget, PriorityClass, ReplicaSet, RuntimeClass,
+    Secret, Service,
+};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
+/// Routes of the embedded web dashboard
+#[cfg(feature = "dashboard")]
+fn dashboard_routes() -> Router<Arc<AppState>> {
+    crate::dashboard::router()
+}
+
+/// No dashboard routes when built without the `dashboard` feature
+#[cfg(not(feature = "dashboard"))]
+fn dashboard_routes() -> Router<Arc<AppState>> {
+    Router::new()
+}
+
+/// Kinds served by the generic resource handlers
+pub fn builtin_resources() -> ResourceRegistry {
+    ResourceRegistry::new()
+        .register::<Pod>()
+        .register::<Node>()
+        .register::<Service>()
+        .register::<Namespace>()
+        .register::<ConfigMap>()
+        .register::<Secret>()
+        .register::<PersistentVolume>()
+        .register::<PersistentVolumeClaim>()
+        .register::<RuntimeClass>()
+        .register::<ImageMapping>()
+        .register::<NetworkPolicy>()
+        .register::<Deployment>()
+        .register::<ReplicaSet>()
+        .register::<DaemonSet>()
+        .register::<Endpoints>()
+        .register::<EndpointSlice>()
+        .register::<Lease>()
+        .register::<HorizontalPodAutoscaler>()
+        .register::<PodDisruptionBudget>()
+        .register::<PriorityClass>()
+        .register::<CustomResourceDefinition>()
+}
+
 /// API server configuration
 #[derive(Clone)]
 pub struct Config {
@@ -17,6 +69,11 @@ pub struct Config {
     pub listen_addr: SocketAddr,
     /// TLS configuration
     pub tls_mode: TlsMode,
+    /// Longest a request other than a watch or proxied stream may take
+    /// before it is answered with 504
+    pub request_timeout: Duration,
+    /// Limits on request bodies and connections
+    pub limits: RequestLimits,
 }
 
 impl Default for Config {
@@ -24,6 +81,8 @@ impl Default for Config {
         Self {
             listen_addr: "127.0.0.1:6443".parse().unwrap(),
             tls_mode: TlsMode::Disabled,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            limits: RequestLimits::default(),
         }
     }
 }
@@ -51,62 +110,116 @@ impl ApiServer {
 
     /// Build the router
     fn build_router(&self) -> Router {
+        let resources = builtin_resources();
+        let limits = Arc::new(self.config.limits.clone().with_kinds(resources.kinds()));
+
         Router::new()
             // Health checks
             .route("/healthz", get(healthz))
             .route("/livez", get(livez))
             .route("/readyz", get(readyz))
-            // Pods
+            .route("/metrics", get(metrics))
+            // Built-in kinds
+            .merge(resources.into_router())
+            // Kinds defined by CustomResourceDefinitions
+            .merge(custom_resources::custom_object_routes())
             .route(
-                "/api/v1/namespaces/{namespace}/pods",
-                get(list_pods).post(create_pod),
+                "/api/v1/namespaces/{namespace}/pods/{name}/finalize",
+                axum::routing::post(finalize_pod),
             )
             .route(
-                "/api/v1/namespaces/{namespace}/pods/{name}",
-                get(get_pod)
-                    .put(replace_pod)
-                    .patch(patch_pod)
-                    .delete(delete_pod),
+                "/api/v1/namespaces/{namespace}/pods/{name}/eviction",
+                axum::routing::post(evict_pod),
             )
+            // Interactive sessions with containers
             .route(
-                "/api/v1/namespaces/{namespace}/pods/{name}/status",
-                axum::routing::put(update_pod_status),
+                "/api/v1/namespaces/{namespace}/pods/{name}/exec",
+                get(exec_pod),
             )
             .route(
-                "/api/v1/namespaces/{namespace}/pods/{name}/finalize",
-                axum::routing::post(finalize_pod),
+                "/api/v1/namespaces/{namespace}/pods/{name}/attach",
+                get(attach_pod),
+            )
+            // Events, kept in the event store
+            .route(
+                "/api/v1/namespaces/{namespace}/events",
+                get(list_events).post(create_event),
+            )
+            .route(
+                "/api/v1/namespaces/{namespace}/events/{name}",
+                get(get_event),
+            )
+            .route("/api/v1/events", get(list_events))
+            // Health of the embedded components
+            .route("/api/v1/componentstatuses", get(list_component_statuses))
+            .route(
+                "/api/v1/componentstatuses/{name}",
+                get(get_component_status),
+            )
+            // Aggregated resource usage
+            .route(
+                "/apis/reddwarf.io/v1alpha1/clusterusage",
+                get(get_cluster_usage),
+            )
+            .route(
+                "/apis/reddwarf.io/v1alpha1/usagereports",
+                get(get_usage_reports),
             )
-            .route("/api/v1/pods", get(list_pods))
-            // Nodes
-            .route("/api/v1/nodes", get(list_nodes).post(create_node))
+            // Certificates of joining nodes
+            .route(BOOTSTRAP_PATH, axum::routing::post(sign_node_certificate))
+            // Commit stream followed by read replicas
+            .route(REPLICATION_PATH, get(get_replication_stream))
+            // Proxy to node agents
+            .route("/api/v1/nodes/{name}/proxy", any(proxy_node))
+            .route("/api/v1/nodes/{name}/proxy/{*path}", any(proxy_node))
+            // Mesh policies
             .route(
-                "/api/v1/nodes/{name}",
-                get(get_node).put(replace_node).delete(delete_node),
+                "/apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies",
+                get(list_mesh_policies).post(create_mesh_policy),
             )
             .route(
-                "/api/v1/nodes/{name}/status",
-                axum::routing::put(update_node_status),
+                "/apis/mesh.reddwarf.io/{version}/namespaces/{namespace}/meshpolicies/{name}",
+                get(get_mesh_policy)
+                    .put(replace_mesh_policy)
+                    .delete(delete_mesh_policy),
             )
-            // Services
             .route(
-                "/api/v1/namespaces/{namespace}/services",
-                get(list_services).post(create_service),
+                "/apis/mesh.reddwarf.io/{version}/meshpolicies",
+                get(list_mesh_policies),
             )
             .route(
-                "/api/v1/namespaces/{namespace}/services/{name}",
-                get(get_service).put(replace_service).delete(delete_service),
+                "/apis/mesh.reddwarf.io/{version}/watch/namespaces/{namespace}/meshpolicies",
+                legacy_watch(list_mesh_policies),
             )
-            // Namespaces
             .route(
-                "/api/v1/namespaces",
-                get(list_namespaces).post(create_namespace),
+                "/apis/mesh.reddwarf.io/{version}/watch/meshpolicies",
+                legacy_watch(list_mesh_policies),
             )
+            // Zone runtime debug API
+            .route("/debug/zones", get(list_debug_zones))
+            .route("/debug/zones/{name}", get(get_debug_zone))
             .route(
-                "/api/v1/namespaces/{name}",
-                get(get_namespace)
-                    .put(replace_namespace)
-                    .delete(delete_namespace),
+                "/debug/zones/{name}/halt",
+                axum::routing::post(halt_debug_zone),
             )
+            .route("/debug/decisions", get(list_debug_decisions))
+            .route(
+                "/debug/history/squash",
+                axum::routing::post(squash_debug_history),
+            )
+            .merge(dashboard_routes())
+            // Answer requests that outlive their deadline with 504
+            .layer(axum::middleware::from_fn_with_state(
+                self.config.request_timeout,
+                enforce_deadline,
+            ))
+            // Read replicas hand writes to their leader
+            .layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                forward_to_leader,
+            ))
+            // Bound request bodies before they are read, forwarded or not
+            .layer(axum::middleware::from_fn_with_state(limits, limit_body))
             // Add tracing and state
             .layer(TraceLayer::new_for_http())
             .with_state(self.state.clone())
@@ -120,17 +233,31 @@ impl ApiServer {
             .resolve_tls_material()
             .map_err(|e| std::io::Error::other(format!("TLS setup failed: {e}")))?;
 
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            shutdown_handle.graceful_shutdown(Some(Duration::from_secs(10)));
+        });
+
+        let limits = &self.config.limits;
+        let connections =
+            ConnectionLimit::new(limits.max_connections, axum_server::accept::DefaultAcceptor);
+
+        // Handlers may read the peer address, e.g. to certify a joining node
+        // for the address it connects from
         match tls_material {
             None => {
                 info!(
                     "Starting API server on {} (plain HTTP)",
                     self.config.listen_addr
                 );
-                let listener = TcpListener::bind(self.config.listen_addr).await?;
-                axum::serve(listener, app)
-                    .with_graceful_shutdown(async move {
-                        token.cancelled().await;
-                    })
+                let mut server = axum_server::bind(self.config.listen_addr)
+                    .acceptor(connections)
+                    .handle(handle);
+                limits.configure_http(server.http_builder());
+                server
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                     .await
             }
             Some(material) => {
@@ -138,27 +265,20 @@ impl ApiServer {
                     "Starting API server on {} (HTTPS)",
                     self.config.listen_addr
                 );
-                let rustls_config = axum_server::tls_rustls::RustlsConfig::from_pem(
-                    material.cert_pem,
-                    material.key_pem,
-                )
-                .await
-                .map_err(|e| {
+                let server_config = tls::server_config(&material).map_err(|e| {
                     std::io::Error::other(format!("failed to build RustlsConfig: {e}"))
                 })?;
+                let rustls_config =
+                    axum_server::tls_rustls::RustlsConfig::from_config(Arc::new(server_config));
+                let acceptor = axum_server::tls_rustls::RustlsAcceptor::new(rustls_config)
+                    .acceptor(connections);
 
-                let handle = axum_server::Handle::new();
-                let shutdown_handle = handle.clone();
-
-                tokio::spawn(async move {
-                    token.cancelled().await;
-                    shutdown_handle
-                        .graceful_shutdown(Some(std::time::Duration::from_secs(10)));
-                });
-
-                axum_server::bind_rustls(self.config.listen_addr, rustls_config)
-                    .handle(handle)
-                    .serve(app.into_make_service())
+                let mut server = axum_server::bind(self.config.listen_addr)
+                    .acceptor(acceptor)
+                    .handle(handle);
+                limits.configure_http(server.http_builder());
+                server
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                     .await
             }
         }
@@ -180,6 +300,18 @@ async fn readyz() -> &'static str {
     "ok"
 }
 
+/// Metrics in the Prometheus text format
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        format!(
+            "{}{}",
+            state.metrics.render(),
+            render_subscriber_stats(&state.event_bus.subscriber_stats())
+        ),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,4 +340,67 @@ mod tests {
         // Router should build successfully
         assert!(std::mem::size_of_val(&router) > 0);
     }
+
+    #[tokio::test]
+    async fn test_list_and_legacy_watch_routes() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+        let router = ApiServer::new(Config::default(), state).build_router();
+
+        let get = |uri: &str| {
+            router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+        let content_type = |response: &axum::response::Response| {
+            response.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // All-namespaces and namespaced lists
+        for uri in [
+            "/api/v1/services",
+            "/api/v1/namespaces/default/services",
+            "/api/v1/pods",
+            "/api/v1/nodes",
+        ] {
+            let response = get(uri).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert!(content_type(&response).starts_with("application/json"));
+        }
+
+        // Legacy watch paths watch whatever the query says
+        for uri in [
+            "/api/v1/watch/namespaces/default/pods",
+            "/api/v1/watch/services?watch=false",
+            "/api/v1/watch/nodes?resourceVersion=0",
+            "/api/v1/watch/namespaces",
+            "/apis/mesh.reddwarf.io/v1alpha1/watch/meshpolicies",
+        ] {
+            let response = get(uri).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(content_type(&response), "application/json", "{}", uri);
+        }
+
+        // Server-Sent Events framing is negotiated
+        let response = router
+            .clone()
+            .oneshot(
+                Request::get("/api/v1/pods?watch=true")
+                    .header(header::ACCEPT, "application/json, text/event-stream;q=0.9")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(content_type(&response), "text/event-stream");
+    }
 }
@@ -0,0 +1,36 @@
+use crate::auth::BearerToken;
+use crate::Result;
+use async_trait::async_trait;
+use reddwarf_core::{Pod, ProcessSession, SessionRequest};
+use std::sync::Arc;
+
+/// Backend for the `exec` and `attach` pod subresources
+///
+/// By the time `open` is called the pod is known to be running and the
+/// request to name one of its containers and an exec command or attach, so
+/// an implementation only has to start or join the process in the pod's
+/// zone and relay its streams. The node agent's implementation refuses pods
+/// scheduled to other nodes.
+#[async_trait]
+pub trait ContainerSessionBackend: Send + Sync {
+    /// Open a session with a container of a running pod
+    async fn open(&self, pod: &Pod, request: SessionRequest) -> Result<ProcessSession>;
+}
+
+/// Interactive session configuration: the runtime backend plus the bearer
+/// token required to open sessions
+#[derive(Clone)]
+pub struct ContainerSessions {
+    pub backend: Arc<dyn ContainerSessionBackend>,
+    pub token: BearerToken,
+}
+
+impl ContainerSessions {
+    /// Create a new interactive session configuration
+    pub fn new(backend: Arc<dyn ContainerSessionBackend>, token: impl Into<String>) -> Self {
+        Self {
+            backend,
+            token: BearerToken::new(token),
+        }
+    }
+}
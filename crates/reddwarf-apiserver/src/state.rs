@@ -1,5 +1,15 @@
-use crate::event_bus::{EventBusConfig, ResourceEvent};
-use reddwarf_storage::RedbBackend;
+use crate::bootstrap::BootstrapSigner;
+use crate::components::ComponentRegistry;
+use crate::debug::ZoneDebug;
+use crate::event_bus::{
+    EventBus, EventBusConfig, InProcessEventBus, ResourceEvent, StoredSnapshots,
+};
+use crate::proxy::NodeProxy;
+use crate::replica::Leader;
+use crate::sessions::ContainerSessions;
+use crate::Result;
+use reddwarf_core::{GroupVersionKind, Metrics};
+use reddwarf_storage::{EnvelopeEncryptor, EventStore, EventStoreConfig, RedbBackend};
 use reddwarf_versioning::VersionStore;
 use std::sync::Arc;
 use tokio::sync::broadcast;
@@ -13,8 +23,51 @@ pub struct AppState {
     /// Version store
     pub version_store: Arc<VersionStore>,
 
-    /// Event bus sender — broadcast channel for resource mutation events
-    pub event_tx: broadcast::Sender<ResourceEvent>,
+    /// Event bus of resource mutation events, shared with in-process
+    /// controllers
+    pub event_bus: Arc<InProcessEventBus>,
+
+    /// Stored events, aggregated and expired outside the commit history
+    pub events: Arc<EventStore>,
+
+    /// Held from reading an object to writing its replacement, so that
+    /// preconditions checked against the stored object still hold when the
+    /// write lands
+    pub update_lock: Arc<tokio::sync::Mutex<()>>,
+
+    /// Zone runtime debug API (disabled when `None`)
+    pub zone_debug: Option<ZoneDebug>,
+
+    /// Interactive `exec` and `attach` sessions (disabled when `None`)
+    pub sessions: Option<ContainerSessions>,
+
+    /// Proxy to node agents at `/api/v1/nodes/{name}/proxy` (disabled when `None`)
+    pub node_proxy: Option<NodeProxy>,
+
+    /// Health of the components running in this process, served as
+    /// ComponentStatuses
+    pub components: ComponentRegistry,
+
+    /// Metrics served at `/metrics`, shared with the node agent's controllers
+    pub metrics: Arc<Metrics>,
+
+    /// Ceiling of the grace period of terminating pods (unlimited when `None`)
+    pub max_grace_period_seconds: Option<i64>,
+
+    /// Leader this read replica follows and forwards writes to (`None` on
+    /// the leader)
+    pub leader: Option<Leader>,
+
+    /// Signer of joining nodes' certificates (bootstrap disabled when `None`)
+    pub bootstrap: Option<BootstrapSigner>,
+
+    /// Whether new namespaces have their egress locked down unless they opt
+    /// out
+    pub default_deny_egress: bool,
+
+    /// Envelope encryption of sensitive stored values (stored in plaintext
+    /// when `None`)
+    pub encryption: Option<Arc<EnvelopeEncryptor>>,
 }
 
 impl AppState {
@@ -29,16 +82,97 @@ impl AppState {
         version_store: Arc<VersionStore>,
         config: EventBusConfig,
     ) -> Self {
-        let (event_tx, _) = broadcast::channel(config.capacity);
+        let events = Arc::new(EventStore::new(
+            storage.clone(),
+            EventStoreConfig::default(),
+        ));
+        let event_bus = Arc::new(InProcessEventBus::new(config.capacity));
+        event_bus.set_snapshot_source(Arc::new(StoredSnapshots::new(storage.clone(), None)));
         Self {
             storage,
             version_store,
-            event_tx,
+            event_bus,
+            events,
+            update_lock: Arc::new(tokio::sync::Mutex::new(())),
+            zone_debug: None,
+            sessions: None,
+            node_proxy: None,
+            components: ComponentRegistry::new(),
+            metrics: Arc::new(Metrics::new()),
+            max_grace_period_seconds: None,
+            leader: None,
+            bootstrap: None,
+            default_deny_egress: false,
+            encryption: None,
         }
     }
 
+    /// Enable the `/debug/zones` endpoints backed by the given runtime view
+    pub fn with_zone_debug(mut self, zone_debug: ZoneDebug) -> Self {
+        self.zone_debug = Some(zone_debug);
+        self
+    }
+
+    /// Enable the `exec` and `attach` pod subresources
+    pub fn with_container_sessions(mut self, sessions: ContainerSessions) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Enable the `/api/v1/nodes/{name}/proxy` subresource
+    pub fn with_node_proxy(mut self, node_proxy: NodeProxy) -> Self {
+        self.node_proxy = Some(node_proxy);
+        self
+    }
+
+    /// Cap the grace period pods are given to terminate, whether requested
+    /// on deletion or set in their spec
+    pub fn with_max_grace_period(mut self, seconds: i64) -> Self {
+        self.max_grace_period_seconds = Some(seconds);
+        self
+    }
+
+    /// Serve reads from this server's copy, following `leader` and
+    /// forwarding writes to it
+    pub fn with_leader(mut self, leader: Leader) -> Self {
+        self.leader = Some(leader);
+        self
+    }
+
+    /// Sign the certificate requests of nodes joining with a bootstrap token
+    pub fn with_bootstrap(mut self, signer: BootstrapSigner) -> Self {
+        self.bootstrap = Some(signer);
+        self
+    }
+
+    /// Lock down the egress of namespaces created from now on, unless they
+    /// set `reddwarf.io/egress-lockdown: "false"`
+    pub fn with_default_deny_egress(mut self) -> Self {
+        self.default_deny_egress = true;
+        self
+    }
+
+    /// Encrypt sensitive values at rest with `encryptor`
+    pub fn with_encryption(mut self, encryptor: Arc<EnvelopeEncryptor>) -> Self {
+        self.event_bus
+            .set_snapshot_source(Arc::new(StoredSnapshots::new(
+                self.storage.clone(),
+                Some(encryptor.clone()),
+            )));
+        self.encryption = Some(encryptor);
+        self
+    }
+
     /// Subscribe to resource events
     pub fn subscribe(&self) -> broadcast::Receiver<ResourceEvent> {
-        self.event_tx.subscribe()
+        self.event_bus.subscribe()
+    }
+
+    /// Send the named `subscriber` of the event bus an ADDED event for
+    /// every stored object of `gvk`, so that after lagging it catches up
+    /// from storage in this process rather than relisting over HTTP.
+    /// Returns how many objects the snapshot held.
+    pub fn resync(&self, subscriber: &str, gvk: &GroupVersionKind) -> Result<usize> {
+        Ok(self.event_bus.resync(subscriber, gvk)?)
     }
 }
@@ -1,6 +1,10 @@
 use miette::{Context, IntoDiagnostic};
-use rcgen::{BasicConstraints, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+use rcgen::{
+    BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair,
+};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::info;
 
 /// How TLS should be configured for the API server.
@@ -27,6 +31,9 @@ pub struct TlsMaterial {
     pub cert_pem: Vec<u8>,
     pub key_pem: Vec<u8>,
     pub ca_pem: Option<Vec<u8>>,
+    /// CA private key, available when the CA was auto-generated.
+    /// Used to issue mesh certificates from the cluster CA.
+    pub ca_key_pem: Option<Vec<u8>>,
 }
 
 /// Resolve TLS material from the given mode.
@@ -60,11 +67,14 @@ pub fn resolve_tls(mode: &TlsMode) -> miette::Result<Option<TlsMaterial>> {
                     .wrap_err_with(|| {
                         format!("failed to read server key at {}", key_path.display())
                     })?;
+                // Older installs did not persist the CA key
+                let ca_key_pem = std::fs::read(data_dir.join("ca-key.pem")).ok();
 
                 Ok(Some(TlsMaterial {
                     cert_pem,
                     key_pem,
                     ca_pem: Some(ca_pem),
+                    ca_key_pem,
                 }))
             } else {
                 info!(
@@ -91,11 +101,85 @@ pub fn resolve_tls(mode: &TlsMode) -> miette::Result<Option<TlsMaterial>> {
                 cert_pem,
                 key_pem,
                 ca_pem: None,
+                ca_key_pem: None,
             }))
         }
     }
 }
 
+/// Server TLS config for `material`. With a CA, clients may present a
+/// certificate it issued, e.g. the node certificate of a joined agent
+/// proxying to another node; clients without one are still accepted.
+pub fn server_config(material: &TlsMaterial) -> miette::Result<rustls::ServerConfig> {
+    let builder = rustls::ServerConfig::builder();
+    let builder = match &material.ca_pem {
+        Some(ca_pem) => {
+            let verifier = rustls::server::WebPkiClientVerifier::builder(client_roots(ca_pem)?)
+                .allow_unauthenticated()
+                .build()
+                .into_diagnostic()
+                .wrap_err("failed to build client certificate verifier")?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    with_server_cert(builder, material)
+}
+
+/// Server TLS config for `material` that only accepts clients presenting a
+/// certificate issued by its CA, such as the node-local API's
+pub fn mutual_tls_server_config(material: &TlsMaterial) -> miette::Result<rustls::ServerConfig> {
+    let ca_pem = material.ca_pem.as_deref().ok_or_else(|| {
+        miette::miette!("client certificate authentication requires the cluster CA certificate")
+    })?;
+    let verifier = rustls::server::WebPkiClientVerifier::builder(client_roots(ca_pem)?)
+        .build()
+        .into_diagnostic()
+        .wrap_err("failed to build client certificate verifier")?;
+    let builder = rustls::ServerConfig::builder().with_client_cert_verifier(verifier);
+    with_server_cert(builder, material)
+}
+
+/// The CA certificates client certificates are verified against
+fn client_roots(ca_pem: &[u8]) -> miette::Result<Arc<rustls::RootCertStore>> {
+    let mut roots = rustls::RootCertStore::empty();
+    let mut ca_reader = ca_pem;
+    for cert in rustls_pemfile::certs(&mut ca_reader) {
+        let cert = cert
+            .into_diagnostic()
+            .wrap_err("invalid CA certificate PEM")?;
+        roots
+            .add(cert)
+            .into_diagnostic()
+            .wrap_err("invalid CA certificate")?;
+    }
+    Ok(Arc::new(roots))
+}
+
+/// Finish `builder` with the certificate and key of `material`
+fn with_server_cert(
+    builder: rustls::ConfigBuilder<rustls::ServerConfig, rustls::server::WantsServerCert>,
+    material: &TlsMaterial,
+) -> miette::Result<rustls::ServerConfig> {
+    let mut cert_reader = material.cert_pem.as_slice();
+    let cert_chain = rustls_pemfile::certs(&mut cert_reader)
+        .collect::<Result<Vec<_>, _>>()
+        .into_diagnostic()
+        .wrap_err("invalid TLS certificate PEM")?;
+    let mut key_reader = material.key_pem.as_slice();
+    let key = rustls_pemfile::private_key(&mut key_reader)
+        .into_diagnostic()
+        .wrap_err("invalid TLS key PEM")?
+        .ok_or_else(|| miette::miette!("no private key found in TLS key PEM"))?;
+
+    let mut config = builder
+        .with_single_cert(cert_chain, key)
+        .into_diagnostic()
+        .wrap_err("invalid TLS certificate or key")?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
 /// Generate a self-signed CA and server certificate, writing PEM files to `data_dir`.
 fn generate_self_signed(data_dir: &Path, san_entries: &[String]) -> miette::Result<TlsMaterial> {
     std::fs::create_dir_all(data_dir)
@@ -108,6 +192,11 @@ fn generate_self_signed(data_dir: &Path, san_entries: &[String]) -> miette::Resu
     let mut ca_params = CertificateParams::new(vec!["Reddwarf CA".to_string()])
         .into_diagnostic()
         .wrap_err("failed to create CA certificate params")?;
+    // The mesh proxy rebuilds this subject to sign node certificates
+    ca_params.distinguished_name = DistinguishedName::new();
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "Reddwarf CA");
     ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
 
     let ca_cert = ca_params
@@ -132,17 +221,22 @@ fn generate_self_signed(data_dir: &Path, san_entries: &[String]) -> miette::Resu
 
     // --- Serialize ---
     let ca_pem = ca_cert.pem();
+    let ca_key_pem = ca_key.serialize_pem();
     let cert_pem = server_cert.pem();
     let key_pem = server_key.serialize_pem();
 
     // --- Write files ---
     let ca_path = data_dir.join("ca.pem");
+    let ca_key_path = data_dir.join("ca-key.pem");
     let cert_path = data_dir.join("server.pem");
     let key_path = data_dir.join("server-key.pem");
 
     std::fs::write(&ca_path, &ca_pem)
         .into_diagnostic()
         .wrap_err_with(|| format!("failed to write CA cert to {}", ca_path.display()))?;
+    write_private(&ca_key_path, ca_key_pem.as_bytes())
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write CA key to {}", ca_key_path.display()))?;
     std::fs::write(&cert_path, &cert_pem)
         .into_diagnostic()
         .wrap_err_with(|| format!("failed to write server cert to {}", cert_path.display()))?;
@@ -151,7 +245,7 @@ fn generate_self_signed(data_dir: &Path, san_entries: &[String]) -> miette::Resu
         .wrap_err_with(|| format!("failed to write server key to {}", key_path.display()))?;
 
     info!(
-        "TLS certificates written to {}  (ca.pem, server.pem, server-key.pem)",
+        "TLS certificates written to {}  (ca.pem, ca-key.pem, server.pem, server-key.pem)",
         data_dir.display()
     );
 
@@ -159,9 +253,30 @@ fn generate_self_signed(data_dir: &Path, san_entries: &[String]) -> miette::Resu
         cert_pem: cert_pem.into_bytes(),
         key_pem: key_pem.into_bytes(),
         ca_pem: Some(ca_pem.into_bytes()),
+        ca_key_pem: Some(ca_key_pem.into_bytes()),
     })
 }
 
+/// Write a file readable only by its owner
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(contents)
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, contents)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,9 +297,11 @@ mod tests {
         assert!(!material.cert_pem.is_empty());
         assert!(!material.key_pem.is_empty());
         assert!(material.ca_pem.is_some());
+        assert!(material.ca_key_pem.is_some());
 
         // Verify files were written
         assert!(tls_dir.join("ca.pem").exists());
+        assert!(tls_dir.join("ca-key.pem").exists());
         assert!(tls_dir.join("server.pem").exists());
         assert!(tls_dir.join("server-key.pem").exists());
     }
@@ -244,6 +361,20 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_mutual_tls_requires_ca() {
+        let dir = tempdir().unwrap();
+        let mode = TlsMode::AutoGenerate {
+            data_dir: dir.path().join("tls"),
+            san_entries: vec!["localhost".to_string()],
+        };
+        let mut material = resolve_tls(&mode).unwrap().unwrap();
+
+        assert!(mutual_tls_server_config(&material).is_ok());
+        material.ca_pem = None;
+        assert!(mutual_tls_server_config(&material).is_err());
+    }
+
     #[test]
     fn test_disabled_returns_none() {
         let result = resolve_tls(&TlsMode::Disabled).unwrap();
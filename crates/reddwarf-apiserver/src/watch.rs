@@ -1,13 +1,24 @@
 use crate::event_bus::ResourceEvent;
-use crate::AppState;
+use crate::field_selector::FieldSelector;
+use crate::handlers::common::unseal;
+use crate::{ApiError, AppState, Result};
+use axum::body::{Body, Bytes};
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, Uri};
 use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use futures_util::StreamExt;
 use reddwarf_core::GroupVersionKind;
 pub use reddwarf_core::WatchEventType;
+use reddwarf_storage::KeyEncoder;
+use reddwarf_versioning::{ChangeType, VersioningError};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tracing::warn;
 
 /// Watch event
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +59,14 @@ pub struct WatchParams {
     /// Resource version to start watching from
     #[serde(rename = "resourceVersion")]
     pub resource_version: Option<String>,
+    /// End a watch after this many seconds, or never for `0`; bounds a list
+    /// like any other request (see [`crate::deadline`])
+    #[serde(rename = "timeoutSeconds")]
+    pub timeout_seconds: Option<u64>,
+    /// Terms on fields objects must meet to be listed or watched (see
+    /// [`crate::field_selector`])
+    #[serde(rename = "fieldSelector")]
+    pub field_selector: Option<String>,
 }
 
 impl WatchParams {
@@ -57,38 +76,184 @@ impl WatchParams {
             .as_deref()
             .is_some_and(|v| v == "true" || v == "1")
     }
+
+    /// Resource version to replay changes after: unset for "0" and the
+    /// empty string, which mean "start from now"
+    pub fn resume_from(&self) -> Option<&str> {
+        self.resource_version
+            .as_deref()
+            .filter(|rv| !rv.is_empty() && *rv != "0")
+    }
+
+    /// How long a watch runs before it ends: unset for `0`, which means
+    /// "until the client goes away"
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_seconds
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// The field selector of the request, which may name the metadata
+    /// fields and `fields`, the other fields `plural` can be selected by
+    pub fn field_selector(&self, plural: &str, fields: &[&str]) -> Result<FieldSelector> {
+        FieldSelector::parse(self.field_selector.as_deref(), plural, fields)
+    }
+}
+
+/// Header an `EventSource` reconnects with, naming the ID of the last event
+/// it received
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// Framing of a watch stream, negotiated from the request's `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchFraming {
+    /// One JSON watch event per line of a chunked response, as Kubernetes
+    /// clients expect; the default
+    JsonLines,
+    /// Server-Sent Events with the commit ID of each change as its event
+    /// ID, for browser `EventSource` clients, when `text/event-stream` is
+    /// accepted
+    EventStream,
+}
+
+impl WatchFraming {
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let event_stream = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|range| range.split(';').next())
+            .any(|media| media.trim().eq_ignore_ascii_case("text/event-stream"));
+        if event_stream {
+            Self::EventStream
+        } else {
+            Self::JsonLines
+        }
+    }
 }
 
-/// Kubernetes wire-format watch event for SSE
+/// Rewrite a request on a legacy `/watch/` route into a watch of the
+/// collection it names, whatever its `watch` parameter says
+pub async fn force_watch(mut request: Request) -> Request {
+    let mut query: Vec<&str> = request
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && *pair != "watch" && !pair.starts_with("watch="))
+        .collect();
+    query.push("watch=true");
+    let path_and_query = format!("{}?{}", request.uri().path(), query.join("&"));
+
+    let mut parts = request.uri().clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+    request
+}
+
+/// Kubernetes wire-format watch event
 #[derive(Serialize)]
-struct SseWatchEvent {
+struct WireWatchEvent {
     #[serde(rename = "type")]
     event_type: WatchEventType,
     object: serde_json::Value,
 }
 
-impl From<&ResourceEvent> for SseWatchEvent {
-    fn from(event: &ResourceEvent) -> Self {
-        Self {
-            event_type: event.event_type.clone(),
-            object: event.object.clone(),
-        }
-    }
+/// Create a stream that watches for resource events filtered by GVK and
+/// optional namespace, framed as `headers` negotiate (see [`WatchFraming`])
+///
+/// With a `resourceVersion` (e.g. from a list response), changes committed
+/// after it are replayed before live events, so nothing between the list
+/// and the watch is missed. A `Last-Event-ID` header, sent by a reconnecting
+/// `EventSource`, takes its place. A watch that falls behind the event bus
+/// ends instead of skipping events, for the client to resume the same way.
+///
+/// Only the changes to objects `selector` selects are sent. A change that
+/// takes an object out of the selection is sent as its deletion, and one
+/// that brings it in as its addition, as clients caching the selection
+/// expect.
+pub fn watch_resource_stream(
+    state: &Arc<AppState>,
+    gvk: GroupVersionKind,
+    namespace: Option<String>,
+    selector: FieldSelector,
+    params: &WatchParams,
+    headers: &HeaderMap,
+) -> Result<Response> {
+    watch_converted_stream(state, gvk, namespace, selector, params, headers, Some)
 }
 
-/// Create an SSE stream that watches for resource events filtered by GVK and optional namespace
-pub fn watch_resource_stream(
+/// Like [`watch_resource_stream`], passing each event's object through
+/// `convert` (e.g. to the API version the client watches at); events it
+/// returns `None` for are dropped
+pub fn watch_converted_stream<F>(
     state: &Arc<AppState>,
     gvk: GroupVersionKind,
     namespace: Option<String>,
-) -> Sse<impl futures_util::Stream<Item = std::result::Result<Event, Infallible>>> {
+    selector: FieldSelector,
+    params: &WatchParams,
+    headers: &HeaderMap,
+    convert: F,
+) -> Result<Response>
+where
+    F: Fn(serde_json::Value) -> Option<serde_json::Value> + Clone + Send + Sync + 'static,
+{
+    // Subscribe before reading history so no commit falls between the two
     let rx = state.subscribe();
-    let stream = BroadcastStream::new(rx);
+    let last_event_id = headers
+        .get(LAST_EVENT_ID)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty());
+    let resume_from = last_event_id.or(params.resume_from());
+    let replayed = match resume_from {
+        Some(rv) => replay_events(state, &gvk, namespace.as_deref(), &selector, rv)?,
+        None => Vec::new(),
+    };
+    // Keep the last resource version the client saw from being squashed
+    // away while it is connected, so it can resume from it
+    let position = resume_from
+        .map(str::to_string)
+        .or_else(|| state.version_store.head_id())
+        .unwrap_or_default();
+    let pin = state.version_store.pin(&position);
+    let replayed_versions: Arc<HashSet<String>> =
+        Arc::new(replayed.iter().map(|(rv, _)| rv.clone()).collect());
+
+    let replay_convert = convert.clone();
+    let replay = futures_util::stream::iter(replayed).filter_map(move |(rv, mut wire_event)| {
+        let convert = replay_convert.clone();
+        async move {
+            wire_event.object = convert(wire_event.object)?;
+            let data = serde_json::to_string(&wire_event).ok()?;
+            Some((rv, data))
+        }
+    });
+
+    // A watcher that falls too far behind the event bus has missed events
+    // it can no longer be sent; end its watch so it resumes from the last
+    // resource version it saw and has them replayed
+    let stream = BroadcastStream::new(rx).take_while(|result| {
+        if let Err(BroadcastStreamRecvError::Lagged(missed)) = result {
+            warn!(
+                "Ending watch that fell {} events behind; it resumes from its last resource version",
+                missed
+            );
+        }
+        std::future::ready(result.is_ok())
+    });
+    let state = state.clone();
 
     let filtered = stream.filter_map(
         move |result: std::result::Result<ResourceEvent, BroadcastStreamRecvError>| {
             let gvk = gvk.clone();
             let namespace = namespace.clone();
+            let convert = convert.clone();
+            let replayed_versions = replayed_versions.clone();
+            let selector = selector.clone();
+            let state = state.clone();
             async move {
                 let event = result.ok()?;
 
@@ -104,12 +269,169 @@ pub fn watch_resource_stream(
                     }
                 }
 
-                let sse_event = SseWatchEvent::from(&event);
-                let data = serde_json::to_string(&sse_event).ok()?;
-                Some(Ok(Event::default().data(data)))
+                // Already sent from history
+                if replayed_versions.contains(&event.resource_version) {
+                    return None;
+                }
+
+                let event_type = select_event(&selector, &event.event_type, &event.object, || {
+                    previous_object(&state, &event)
+                })?;
+                let wire_event = WireWatchEvent {
+                    event_type,
+                    object: convert(event.object)?,
+                };
+                let data = serde_json::to_string(&wire_event).ok()?;
+                Some((event.resource_version, data))
             }
         },
     );
 
-    Sse::new(filtered).keep_alive(KeepAlive::default())
+    // Clients re-establish the watch from the last resource version they
+    // saw once it ends
+    let timeout = params.timeout();
+    let expired = async move {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let events = replay
+        .chain(filtered)
+        .take_until(expired)
+        .inspect(move |(rv, _)| pin.advance(rv));
+
+    Ok(match WatchFraming::negotiate(headers) {
+        WatchFraming::EventStream => Sse::new(
+            events.map(|(rv, data)| Ok::<_, Infallible>(Event::default().id(rv).data(data))),
+        )
+        .keep_alive(KeepAlive::default())
+        .into_response(),
+        WatchFraming::JsonLines => {
+            let lines = events.map(|(_, mut data)| {
+                data.push('\n');
+                Ok::<_, Infallible>(Bytes::from(data))
+            });
+            (
+                [(header::CONTENT_TYPE, "application/json")],
+                Body::from_stream(lines),
+            )
+                .into_response()
+        }
+    })
+}
+
+/// Events for the changes to the objects of `gvk` (in `namespace`, if set)
+/// `selector` selects committed after `resource_version`, each with its
+/// commit ID
+///
+/// The commits to replay and their order come from the commit DAG (see
+/// [`VersionStore::commits_since`](reddwarf_versioning::VersionStore::commits_since)):
+/// a change is replayed after those it builds on, whatever the clocks that
+/// stamped them said.
+fn replay_events(
+    state: &AppState,
+    gvk: &GroupVersionKind,
+    namespace: Option<&str>,
+    selector: &FieldSelector,
+    resource_version: &str,
+) -> Result<Vec<(String, WireWatchEvent)>> {
+    let commits = state
+        .version_store
+        .commits_since(resource_version)
+        .map_err(|e| match e {
+            VersioningError::CommitNotFound { .. } => ApiError::Gone(format!(
+                "too old resource version: {} is not a known commit",
+                resource_version
+            )),
+            e => e.into(),
+        })?;
+
+    let prefix = KeyEncoder::encode_prefix(&gvk.api_version(), &gvk.kind, namespace);
+    let mut events = Vec::new();
+    for commit in commits {
+        for change in &commit.changes {
+            if !change.resource_key.starts_with(&prefix) {
+                continue;
+            }
+            let (event_type, content) = match change.change_type {
+                ChangeType::Create => (WatchEventType::Added, Some(&change.content)),
+                ChangeType::Update => (WatchEventType::Modified, Some(&change.content)),
+                ChangeType::Delete => (WatchEventType::Deleted, change.previous_content.as_ref()),
+            };
+            let Some(mut object) =
+                content.and_then(|c| change_object(state, &change.resource_key, c))
+            else {
+                continue;
+            };
+            let previous = || {
+                let previous = change.previous_content.as_deref()?;
+                change_object(state, &change.resource_key, previous)
+            };
+            let Some(event_type) = select_event(selector, &event_type, &object, previous) else {
+                continue;
+            };
+            set_resource_version(&mut object, &commit.id);
+            events.push((commit.id.clone(), WireWatchEvent { event_type, object }));
+        }
+    }
+    Ok(events)
+}
+
+/// Type of the event a watch with `selector` sends for a change of
+/// `event_type` leaving `object`, if any: a modification is an addition or
+/// a deletion to a watch whose selection it brings the object into or
+/// takes it out of. `previous` is only asked for with a non-empty selector.
+fn select_event(
+    selector: &FieldSelector,
+    event_type: &WatchEventType,
+    object: &serde_json::Value,
+    previous: impl FnOnce() -> Option<serde_json::Value>,
+) -> Option<WatchEventType> {
+    let selected = selector.matches(object);
+    if selector.is_empty() || !matches!(event_type, WatchEventType::Modified) {
+        return selected.then(|| event_type.clone());
+    }
+    let was_selected = previous().is_some_and(|p| selector.matches(&p));
+    match (was_selected, selected) {
+        (true, true) => Some(WatchEventType::Modified),
+        (false, true) => Some(WatchEventType::Added),
+        (true, false) => Some(WatchEventType::Deleted),
+        (false, false) => None,
+    }
+}
+
+/// Content of the object of `event` before its change, from its commit
+fn previous_object(state: &AppState, event: &ResourceEvent) -> Option<serde_json::Value> {
+    let commit = state
+        .version_store
+        .get_commit(&event.resource_version)
+        .ok()?;
+    let key = KeyEncoder::encode_resource_key(&event.resource_key);
+    let change = commit.changes.iter().find(|c| c.resource_key == key)?;
+    change_object(state, &key, change.previous_content.as_deref()?)
+}
+
+/// The object a commit's change stored under `storage_key` holds in
+/// `content`, decrypted first if it is encrypted at rest like a Secret's
+fn change_object(state: &AppState, storage_key: &str, content: &str) -> Option<serde_json::Value> {
+    let plaintext = match unseal(state, storage_key.as_bytes(), content.as_bytes()) {
+        Ok(plaintext) => plaintext,
+        Err(e) => {
+            warn!("Skipping the change of {} in a watch: {:?}", storage_key, e);
+            return None;
+        }
+    };
+    serde_json::from_slice(&plaintext).ok()
+}
+
+/// Stored content predates its commit, so stamp the commit ID on it
+fn set_resource_version(object: &mut serde_json::Value, resource_version: &str) {
+    if let Some(metadata) = object.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+        metadata.insert(
+            "resourceVersion".to_string(),
+            serde_json::Value::from(resource_version),
+        );
+    }
 }
@@ -0,0 +1,285 @@
+//! Wire-format compatibility with a real kubectl
+//!
+//! Runs kubectl (from `$KUBECTL`, or `kubectl` on the PATH) against an
+//! in-process API server. The suite is skipped when kubectl is not
+//! installed.
+
+use reddwarf_apiserver::handlers::common::create_resource;
+use reddwarf_apiserver::{ApiServer, AppState, Config, TlsMode};
+use reddwarf_core::Namespace;
+use reddwarf_storage::RedbBackend;
+use reddwarf_versioning::VersionStore;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+const POD: &str = r#"
+apiVersion: v1
+kind: Pod
+metadata:
+  name: web
+  labels:
+    app: web
+spec:
+  containers:
+  - name: web
+    image: nginx:1.27
+"#;
+
+const SERVICE: &str = r#"
+apiVersion: v1
+kind: Service
+metadata:
+  name: web
+spec:
+  selector:
+    app: web
+  ports:
+  - port: 80
+"#;
+
+/// An API server on a free local port and a kubeconfig pointing at it
+struct TestCluster {
+    dir: TempDir,
+    kubectl: PathBuf,
+    shutdown: CancellationToken,
+}
+
+impl TestCluster {
+    /// Start the server, or `None` when kubectl is not available
+    fn start() -> Option<Self> {
+        let kubectl = PathBuf::from(std::env::var_os("KUBECTL").unwrap_or("kubectl".into()));
+        let available = Command::new(&kubectl)
+            .args(["version", "--client"])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !available {
+            eprintln!("kubectl not found, skipping kubectl compatibility tests");
+            return None;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let state = Arc::new(AppState::new(storage, version_store));
+
+        let listen_addr: SocketAddr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let server = ApiServer::new(
+            Config {
+                listen_addr,
+                tls_mode: TlsMode::Disabled,
+                ..Config::default()
+            },
+            state.clone(),
+        );
+
+        let shutdown = CancellationToken::new();
+        let token = shutdown.clone();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                let mut namespace = Namespace::default();
+                namespace.metadata.name = Some("default".to_string());
+                create_resource(&state, namespace).await.unwrap();
+                server.run(token).await.unwrap();
+            });
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(listen_addr).is_err() {
+            assert!(Instant::now() < deadline, "API server did not start");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        write_kubeconfig(&dir.path().join("kubeconfig"), listen_addr);
+        Some(Self {
+            dir,
+            kubectl,
+            shutdown,
+        })
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.kubectl);
+        command
+            .args(args)
+            .env("KUBECONFIG", self.dir.path().join("kubeconfig"))
+            .env("HOME", self.dir.path());
+        command
+    }
+
+    fn run(&self, args: &[&str], stdin: Option<&str>) -> Output {
+        let mut child = self
+            .command(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut input = child.stdin.take().unwrap();
+        input
+            .write_all(stdin.unwrap_or_default().as_bytes())
+            .unwrap();
+        drop(input);
+        child.wait_with_output().unwrap()
+    }
+
+    /// Run kubectl, expecting success, and return its standard output
+    fn kubectl(&self, args: &[&str]) -> String {
+        self.kubectl_with_input(args, None)
+    }
+
+    fn kubectl_with_input(&self, args: &[&str], stdin: Option<&str>) -> String {
+        let output = self.run(args, stdin);
+        assert!(
+            output.status.success(),
+            "kubectl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn apply(&self, manifest: &str) -> String {
+        self.kubectl_with_input(&["apply", "-f", "-"], Some(manifest))
+    }
+}
+
+impl Drop for TestCluster {
+    fn drop(&mut self) {
+        self.shutdown.cancel();
+    }
+}
+
+fn write_kubeconfig(path: &Path, server: SocketAddr) {
+    let kubeconfig = format!(
+        "apiVersion: v1
+kind: Config
+clusters:
+- name: reddwarf
+  cluster:
+    server: http://{server}
+users:
+- name: reddwarf
+  user: {{}}
+contexts:
+- name: reddwarf
+  context:
+    cluster: reddwarf
+    user: reddwarf
+current-context: reddwarf
+"
+    );
+    std::fs::write(path, kubeconfig).unwrap();
+}
+
+fn json(output: &str) -> serde_json::Value {
+    serde_json::from_str(output).unwrap()
+}
+
+#[test]
+fn test_kubectl_discovery() {
+    let Some(cluster) = TestCluster::start() else {
+        return;
+    };
+
+    let version = json(&cluster.kubectl(&["version", "-o", "json"]));
+    assert_eq!(version["serverVersion"]["major"], "1");
+
+    let resources = cluster.kubectl(&["api-resources", "-o", "name"]);
+    let resources: Vec<&str> = resources.lines().collect();
+    for expected in [
+        "pods",
+        "nodes",
+        "namespaces",
+        "services",
+        "runtimeclasses.node.k8s.io",
+    ] {
+        assert!(resources.contains(&expected), "{} not discovered", expected);
+    }
+
+    // Short names resolve through discovery
+    assert_eq!(
+        cluster.kubectl(&["get", "ns", "-o", "name"]).trim(),
+        "namespace/default"
+    );
+}
+
+#[test]
+fn test_kubectl_apply_get_describe_delete() {
+    let Some(cluster) = TestCluster::start() else {
+        return;
+    };
+
+    assert_eq!(cluster.apply(POD).trim(), "pod/web created");
+    assert_eq!(cluster.apply(POD).trim(), "pod/web unchanged");
+    let relabelled = POD.replace("app: web", "app: shop");
+    assert_eq!(cluster.apply(&relabelled).trim(), "pod/web configured");
+
+    let pod = json(&cluster.kubectl(&["get", "pod", "web", "-o", "json"]));
+    assert_eq!(pod["metadata"]["labels"]["app"], "shop");
+    assert_eq!(pod["spec"]["containers"][0]["image"], "nginx:1.27");
+    assert!(
+        pod["metadata"]["annotations"]["kubectl.kubernetes.io/last-applied-configuration"]
+            .is_string()
+    );
+
+    let yaml = cluster.kubectl(&["get", "po", "web", "-o", "yaml"]);
+    assert!(yaml.contains("image: nginx:1.27"));
+
+    cluster.kubectl(&["label", "pod", "web", "tier=frontend"]);
+    let pod = json(&cluster.kubectl(&["get", "pod", "web", "-o", "json"]));
+    assert_eq!(pod["metadata"]["labels"]["tier"], "frontend");
+
+    let described = cluster.kubectl(&["describe", "pod", "web"]);
+    assert!(described.contains("Name:"));
+    assert!(described.contains("nginx:1.27"));
+
+    assert_eq!(cluster.apply(SERVICE).trim(), "service/web created");
+    assert_eq!(
+        cluster.kubectl(&["delete", "service", "web"]).trim(),
+        "service \"web\" deleted"
+    );
+    assert_eq!(cluster.kubectl(&["get", "svc", "-o", "name"]), "");
+
+    let missing = cluster.run(&["get", "pod", "nope"], None);
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("NotFound"));
+}
+
+#[test]
+fn test_kubectl_watch() {
+    let Some(cluster) = TestCluster::start() else {
+        return;
+    };
+
+    let mut watch = cluster
+        .command(&["get", "namespaces", "--watch", "-o", "name"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let (tx, lines) = mpsc::channel();
+    let stdout = BufReader::new(watch.stdout.take().unwrap());
+    std::thread::spawn(move || {
+        for line in stdout.lines().map_while(|line| line.ok()) {
+            let _ = tx.send(line);
+        }
+    });
+    let next = || lines.recv_timeout(Duration::from_secs(10));
+
+    assert_eq!(next().unwrap(), "namespace/default");
+    cluster.kubectl(&["create", "namespace", "shop"]);
+    let shop = next();
+    let _ = watch.kill();
+    let _ = watch.wait();
+    assert_eq!(shop.unwrap(), "namespace/shop");
+}
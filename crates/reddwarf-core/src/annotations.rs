@@ -0,0 +1,221 @@
+//! Annotations and labels reddwarf reads off pods, namespaces and nodes,
+//! with typed helpers to parse and render their values
+//!
+//! Admission runs [`validate_annotations`] over the annotations of pods,
+//! pod templates and namespaces, so a malformed value is refused when it is
+//! written rather than failing the pod once a node tries to run it.
+
+use crate::resources::{is_valid_label, ResourceError};
+use crate::STATUS_ANNOTATION_PREFIX;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+
+/// Pod annotation selecting a zone brand directly. Deprecated in favour of
+/// `spec.runtimeClassName`, whose RuntimeClass handler names the brand.
+pub const ZONE_BRAND_ANNOTATION: &str = "reddwarf.io/zone-brand";
+
+/// Node label listing the zone brands the node runs, comma-separated
+pub const ZONE_BRANDS_LABEL: &str = "reddwarf.io/zone-brands";
+
+/// Pod (or namespace default) annotation limiting inbound bandwidth
+pub const INGRESS_BANDWIDTH_ANNOTATION: &str = "kubernetes.io/ingress-bandwidth";
+
+/// Pod (or namespace default) annotation limiting outbound bandwidth
+pub const EGRESS_BANDWIDTH_ANNOTATION: &str = "kubernetes.io/egress-bandwidth";
+
+/// Pod annotation listing zonecfg attributes as `name=value,name=value`
+pub const ZONE_ATTRS_ANNOTATION: &str = "reddwarf.io/zone-attrs";
+
+/// Pod annotation recording the address the scheduler reserved for the pod
+/// when binding it, which the node's IPAM then hands the pod
+pub const RESERVED_POD_IP_ANNOTATION: &str = "reddwarf.io/reserved-pod-ip";
+
+/// Status annotation reporting the inbound limit applied, in bits per second
+pub const STATUS_INGRESS_BANDWIDTH_ANNOTATION: &str = "status.reddwarf.io/ingress-bandwidth";
+
+/// Status annotation reporting the outbound limit applied, in bits per second
+pub const STATUS_EGRESS_BANDWIDTH_ANNOTATION: &str = "status.reddwarf.io/egress-bandwidth";
+
+/// Status annotation reporting the `maxbw` set on the pod's VNIC
+pub const STATUS_MAXBW_ANNOTATION: &str = "status.reddwarf.io/maxbw";
+
+/// Value of the annotation `key` on the object of `metadata`
+pub fn annotation<'a>(metadata: &'a ObjectMeta, key: &str) -> Option<&'a str> {
+    metadata
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(key))
+        .map(String::as_str)
+}
+
+/// Zone brand the object of `metadata` selects with its annotation
+pub fn zone_brand(metadata: &ObjectMeta) -> Option<&str> {
+    annotation(metadata, ZONE_BRAND_ANNOTATION)
+}
+
+/// Address reserved for the pod of `metadata` at bind time
+pub fn reserved_pod_ip(metadata: &ObjectMeta) -> Option<Ipv4Addr> {
+    annotation(metadata, RESERVED_POD_IP_ANNOTATION)?
+        .parse()
+        .ok()
+}
+
+/// Zone brands a node advertises in its label, `None` without the label
+pub fn zone_brands(metadata: &ObjectMeta) -> Option<Vec<&str>> {
+    let label = metadata.labels.as_ref()?.get(ZONE_BRANDS_LABEL)?;
+    Some(label.split(',').map(str::trim).collect())
+}
+
+/// Render `brands` as the value of the zone brands label
+pub fn format_zone_brands(brands: &[String]) -> String {
+    brands.join(",")
+}
+
+/// Parse a bandwidth quantity in bits per second, e.g. `"10M"`, `"1G"`,
+/// `"500k"`, `"10Mi"` or a bare number. Returns `None` for malformed or zero values.
+pub fn parse_bandwidth(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, suffix) = value.split_at(split);
+    let n: u64 = digits.parse().ok()?;
+
+    let multiplier: u64 = match suffix {
+        "" => 1,
+        "k" | "K" => 1_000,
+        "M" => 1_000_000,
+        "G" => 1_000_000_000,
+        "T" => 1_000_000_000_000,
+        "Ki" => 1 << 10,
+        "Mi" => 1 << 20,
+        "Gi" => 1 << 30,
+        "Ti" => 1 << 40,
+        _ => return None,
+    };
+
+    n.checked_mul(multiplier).filter(|bps| *bps > 0)
+}
+
+/// Parse the zone attributes annotation into its `(name, value)` pairs
+pub fn parse_zone_attrs(value: &str) -> Result<Vec<(String, String)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| match entry.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!(
+                "Invalid {} entry '{}': use comma-separated name=value pairs",
+                ZONE_ATTRS_ANNOTATION, entry
+            )),
+        })
+        .collect()
+}
+
+/// Render `(name, value)` pairs as the zone attributes annotation
+pub fn format_zone_attrs(attrs: &[(String, String)]) -> String {
+    attrs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Refuse malformed values of the annotations reddwarf reads. Status
+/// annotations are the node's to write and are not checked.
+pub fn validate_annotations(
+    annotations: Option<&BTreeMap<String, String>>,
+) -> Result<(), ResourceError> {
+    for (key, value) in annotations.into_iter().flatten() {
+        if key.starts_with(STATUS_ANNOTATION_PREFIX) {
+            continue;
+        }
+        match key.as_str() {
+            ZONE_BRAND_ANNOTATION if !is_valid_label(value) => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "annotation {} '{}' must be a DNS label naming a zone brand",
+                    key, value
+                )));
+            }
+            INGRESS_BANDWIDTH_ANNOTATION | EGRESS_BANDWIDTH_ANNOTATION
+                if parse_bandwidth(value).is_none() =>
+            {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "annotation {} '{}' must be a positive bandwidth such as 10M or 1G",
+                    key, value
+                )));
+            }
+            ZONE_ATTRS_ANNOTATION => {
+                parse_zone_attrs(value).map_err(ResourceError::ValidationFailed)?;
+            }
+            RESERVED_POD_IP_ANNOTATION if value.parse::<Ipv4Addr>().is_err() => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "annotation {} '{}' must be an IPv4 address",
+                    key, value
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bandwidth() {
+        assert_eq!(parse_bandwidth("10M"), Some(10_000_000));
+        assert_eq!(parse_bandwidth("1G"), Some(1_000_000_000));
+        assert_eq!(parse_bandwidth("500k"), Some(500_000));
+        assert_eq!(parse_bandwidth("1Mi"), Some(1_048_576));
+        assert_eq!(parse_bandwidth("1200"), Some(1200));
+        assert_eq!(parse_bandwidth("0M"), None);
+        assert_eq!(parse_bandwidth("10X"), None);
+        assert_eq!(parse_bandwidth(""), None);
+    }
+
+    #[test]
+    fn test_zone_attrs_round_trip() {
+        let attrs = parse_zone_attrs(" limitpriv = default , , bootargs=-v").unwrap();
+        assert_eq!(
+            attrs,
+            vec![
+                ("limitpriv".to_string(), "default".to_string()),
+                ("bootargs".to_string(), "-v".to_string()),
+            ]
+        );
+        assert_eq!(format_zone_attrs(&attrs), "limitpriv=default,bootargs=-v");
+        assert!(parse_zone_attrs("limitpriv").is_err());
+        assert!(parse_zone_attrs("=default").is_err());
+    }
+
+    #[test]
+    fn test_validate_annotations() {
+        let annotations = |key: &str, value: &str| BTreeMap::from([(key.into(), value.into())]);
+
+        assert!(validate_annotations(None).is_ok());
+        for (key, value) in [
+            (ZONE_BRAND_ANNOTATION, "lx"),
+            (INGRESS_BANDWIDTH_ANNOTATION, "10M"),
+            (ZONE_ATTRS_ANNOTATION, "limitpriv=default"),
+            ("example.com/anything", "goes"),
+            (STATUS_MAXBW_ANNOTATION, "reported"),
+        ] {
+            assert!(validate_annotations(Some(&annotations(key, value))).is_ok());
+        }
+        for (key, value) in [
+            (ZONE_BRAND_ANNOTATION, "Not A Brand"),
+            (EGRESS_BANDWIDTH_ANNOTATION, "fast"),
+            (ZONE_ATTRS_ANNOTATION, "limitpriv"),
+            (RESERVED_POD_IP_ANNOTATION, "10.88.0"),
+        ] {
+            assert!(validate_annotations(Some(&annotations(key, value))).is_err());
+        }
+    }
+}
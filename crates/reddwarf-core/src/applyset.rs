@@ -0,0 +1,39 @@
+//! ApplySet membership (`applyset.kubernetes.io/part-of`)
+//!
+//! Tooling that applies a set of manifests labels every object it applies
+//! with the set's id, so the server can later prune exactly the members a
+//! new apply of the set no longer contains.
+
+use crate::ObjectMeta;
+
+/// Label naming the ApplySet an object was applied as part of
+pub const APPLYSET_PART_OF_LABEL: &str = "applyset.kubernetes.io/part-of";
+
+/// The ApplySet an object belongs to, if any
+pub fn applyset_of(metadata: &ObjectMeta) -> Option<&str> {
+    metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(APPLYSET_PART_OF_LABEL))
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applyset_of() {
+        let mut metadata = ObjectMeta::default();
+        assert_eq!(applyset_of(&metadata), None);
+
+        metadata.labels = Some(
+            [(
+                APPLYSET_PART_OF_LABEL.to_string(),
+                "applyset-web-v1".to_string(),
+            )]
+            .into(),
+        );
+        assert_eq!(applyset_of(&metadata), Some("applyset-web-v1"));
+    }
+}
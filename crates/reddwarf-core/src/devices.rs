@@ -0,0 +1,122 @@
+//! Host devices passed through to pods, advertised as extended resources
+//! named `devices.reddwarf.io/{class}`
+
+use crate::Pod;
+use std::collections::BTreeMap;
+
+/// Extended resource name prefix for device classes
+pub const DEVICE_RESOURCE_PREFIX: &str = "devices.reddwarf.io/";
+
+/// Storage key prefix under which device assignments are recorded
+pub const DEVICE_KEY_PREFIX: &str = "devices/";
+
+/// A class of interchangeable devices a node offers, e.g. spare disks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevicePool {
+    /// Class name, the last segment of the resource name (e.g. `disk`)
+    pub class: String,
+    /// Device paths in the global zone (e.g. `/dev/dsk/c1t1d0`)
+    pub paths: Vec<String>,
+}
+
+impl DevicePool {
+    /// Parse `class=path[,path...]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (class, paths) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'class=path[,path...]', got '{}'", spec))?;
+        let class = class.trim();
+        if !crate::is_valid_label(class) {
+            return Err(format!("device class '{}' is not a valid label", class));
+        }
+        let paths: Vec<String> = paths
+            .split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        if let Some(path) = paths.iter().find(|p| !p.starts_with("/dev/")) {
+            return Err(format!("device path '{}' is not under /dev", path));
+        }
+        Ok(Self {
+            class: class.to_string(),
+            paths,
+        })
+    }
+
+    /// Extended resource name pods request this class by
+    pub fn resource_name(&self) -> String {
+        format!("{}{}", DEVICE_RESOURCE_PREFIX, self.class)
+    }
+}
+
+/// Storage key prefix covering every device assigned on a node
+pub fn node_device_prefix(node_name: &str) -> String {
+    format!("{}{}/", DEVICE_KEY_PREFIX, node_name)
+}
+
+/// Number of devices of each class a pod requests, keyed by resource name.
+///
+/// Extended resources are whole numbers set in `limits` (any `requests` must
+/// equal them), so limits are used with requests as the fallback.
+pub fn pod_device_requests(pod: &Pod) -> BTreeMap<String, i64> {
+    let mut requests = BTreeMap::new();
+    let Some(spec) = pod.spec.as_ref() else {
+        return requests;
+    };
+
+    for resources in spec.containers.iter().filter_map(|c| c.resources.as_ref()) {
+        let Some(amounts) = resources.limits.as_ref().or(resources.requests.as_ref()) else {
+            continue;
+        };
+        for (name, quantity) in amounts {
+            if !name.starts_with(DEVICE_RESOURCE_PREFIX) {
+                continue;
+            }
+            if let Ok(count) = quantity.0.parse::<i64>() {
+                if count > 0 {
+                    *requests.entry(name.clone()).or_insert(0) += count;
+                }
+            }
+        }
+    }
+    requests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, PodSpec, ResourceRequirements};
+    use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+    #[test]
+    fn test_device_pool_and_pod_requests() {
+        let pool = DevicePool::parse("disk=/dev/dsk/c1t1d0, /dev/dsk/c1t2d0").unwrap();
+        assert_eq!(pool.paths.len(), 2);
+        assert_eq!(pool.resource_name(), "devices.reddwarf.io/disk");
+        assert!(DevicePool::parse("disk").is_err());
+        assert!(DevicePool::parse("disk=/etc/passwd").is_err());
+
+        let container = |count: &str| Container {
+            resources: Some(ResourceRequirements {
+                limits: Some(BTreeMap::from([
+                    ("cpu".to_string(), Quantity("1".to_string())),
+                    (pool.resource_name(), Quantity(count.to_string())),
+                ])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pod = Pod {
+            spec: Some(PodSpec {
+                containers: vec![container("1"), container("2")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            pod_device_requests(&pod),
+            BTreeMap::from([("devices.reddwarf.io/disk".to_string(), 3)])
+        );
+    }
+}
@@ -144,6 +144,141 @@ pub enum ReddwarfError {
 /// Result type alias for Reddwarf operations
 pub type Result<T> = std::result::Result<T, ReddwarfError>;
 
+/// What went wrong, in terms shared by the error types of every crate, so
+/// that controllers and clients decide how to react to an error by its
+/// class rather than by its message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The object does not exist
+    NotFound,
+    /// An object of that name already exists
+    AlreadyExists,
+    /// The object or the state it depends on changed concurrently; re-read
+    /// and try again
+    Conflict,
+    /// The request or configuration is invalid and fails the same way every
+    /// time
+    Invalid,
+    /// The caller is not authenticated
+    Unauthorized,
+    /// The caller may not do this
+    Forbidden,
+    /// The resource version asked for is no longer available; relist
+    Expired,
+    /// A dependency is unreachable, overloaded or out of capacity for now
+    Unavailable,
+    /// The operation did not complete in time
+    Timeout,
+    /// An unexpected failure, which may not happen again
+    Internal,
+}
+
+impl ErrorKind {
+    /// The kind of error an HTTP response with `status` reports
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            409 => Self::Conflict,
+            410 => Self::Expired,
+            408 | 504 => Self::Timeout,
+            429 | 502 | 503 => Self::Unavailable,
+            400..=499 => Self::Invalid,
+            _ => Self::Internal,
+        }
+    }
+
+    /// The kind of error a Kubernetes `Status` with `reason` reports, if
+    /// the reason is known
+    pub fn from_reason(reason: &str) -> Option<Self> {
+        Some(match reason {
+            "NotFound" => Self::NotFound,
+            "AlreadyExists" => Self::AlreadyExists,
+            "Conflict" => Self::Conflict,
+            "Invalid" | "BadRequest" | "UnsupportedMediaType" | "MethodNotAllowed" => Self::Invalid,
+            "Unauthorized" => Self::Unauthorized,
+            "Forbidden" => Self::Forbidden,
+            "Expired" | "Gone" => Self::Expired,
+            "ServiceUnavailable" | "TooManyRequests" => Self::Unavailable,
+            "Timeout" | "ServerTimeout" => Self::Timeout,
+            "InternalError" => Self::Internal,
+            _ => return None,
+        })
+    }
+
+    /// The Kubernetes `Status` reason of this kind of error
+    pub fn reason(self) -> &'static str {
+        match self {
+            Self::NotFound => "NotFound",
+            Self::AlreadyExists => "AlreadyExists",
+            Self::Conflict => "Conflict",
+            Self::Invalid => "Invalid",
+            Self::Unauthorized => "Unauthorized",
+            Self::Forbidden => "Forbidden",
+            Self::Expired => "Expired",
+            Self::Unavailable => "ServiceUnavailable",
+            Self::Timeout => "Timeout",
+            Self::Internal => "InternalError",
+        }
+    }
+}
+
+/// Classification of an error, implemented by the error type of every
+/// crate
+pub trait ErrorClass {
+    /// What went wrong
+    fn kind(&self) -> ErrorKind;
+
+    /// Whether the same operation may succeed when tried again later, or
+    /// after re-reading what it acts on; errors in the request itself, its
+    /// credentials, or about objects that (no longer) exist are final
+    fn is_retryable(&self) -> bool {
+        !matches!(
+            self.kind(),
+            ErrorKind::NotFound
+                | ErrorKind::AlreadyExists
+                | ErrorKind::Invalid
+                | ErrorKind::Unauthorized
+                | ErrorKind::Forbidden
+        )
+    }
+
+    /// Whether the error is about an object that already exists or changed
+    /// concurrently, both reported as HTTP 409
+    fn is_conflict(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Conflict | ErrorKind::AlreadyExists)
+    }
+
+    /// Whether the error is about an object that does not exist
+    fn is_not_found(&self) -> bool {
+        self.kind() == ErrorKind::NotFound
+    }
+
+    /// The short, stable reason reported to users, as the `reason` of a
+    /// Kubernetes `Status`
+    fn user_facing_reason(&self) -> &'static str {
+        self.kind().reason()
+    }
+}
+
+impl ErrorClass for ReddwarfError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ResourceNotFound { .. } | Self::NamespaceNotFound { .. } => ErrorKind::NotFound,
+            Self::ResourceAlreadyExists { .. } => ErrorKind::AlreadyExists,
+            Self::Conflict { .. } => ErrorKind::Conflict,
+            Self::InvalidResource { .. }
+            | Self::ValidationFailed { .. }
+            | Self::InvalidApiVersion { .. }
+            | Self::InvalidKind { .. } => ErrorKind::Invalid,
+            Self::StorageError { .. }
+            | Self::SerializationError { .. }
+            | Self::InternalError { .. } => ErrorKind::Internal,
+        }
+    }
+}
+
 impl ReddwarfError {
     /// Create a ResourceNotFound error
     pub fn resource_not_found(resource_key: impl Into<String>) -> Self {
@@ -260,4 +395,35 @@ mod tests {
         );
         assert!(matches!(err, ReddwarfError::ValidationFailed { .. }));
     }
+
+    #[test]
+    fn test_error_class() {
+        let err = ReddwarfError::resource_not_found("v1/Pod/default/web");
+        assert!(err.is_not_found());
+        assert!(!err.is_retryable());
+        assert_eq!(err.user_facing_reason(), "NotFound");
+
+        let err = ReddwarfError::conflict("v1/Pod/default/web", "2", "3", Vec::new());
+        assert!(err.is_conflict());
+        assert!(err.is_retryable());
+
+        let err = ReddwarfError::validation_failed("Pod", "no containers", "add one");
+        assert!(!err.is_retryable());
+        assert_eq!(err.user_facing_reason(), "Invalid");
+
+        assert_eq!(ErrorKind::from_status(404), ErrorKind::NotFound);
+        assert_eq!(ErrorKind::from_status(422), ErrorKind::Invalid);
+        assert_eq!(ErrorKind::from_status(503), ErrorKind::Unavailable);
+        assert_eq!(ErrorKind::from_status(500), ErrorKind::Internal);
+        for kind in [
+            ErrorKind::NotFound,
+            ErrorKind::AlreadyExists,
+            ErrorKind::Conflict,
+            ErrorKind::Expired,
+            ErrorKind::Unavailable,
+            ErrorKind::Internal,
+        ] {
+            assert_eq!(ErrorKind::from_reason(kind.reason()), Some(kind));
+        }
+    }
 }
@@ -1,5 +1,11 @@
+use crate::error::{ReddwarfError, Result};
 use crate::types::{GroupVersionKind, ResourceKey};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
 
 /// Watch event type
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,3 +78,253 @@ impl ResourceEvent {
         }
     }
 }
+
+/// How a named subscriber is keeping up with an event bus
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriberStats {
+    pub name: String,
+    /// Events published that it had not received yet, as of its last receive
+    pub backlog: u64,
+    /// Times it fell so far behind that events were dropped
+    pub lagged: u64,
+    /// Events dropped before it received them
+    pub dropped: u64,
+    /// Events it was sent in snapshots by [`EventBus::resync`]
+    pub resynced: u64,
+}
+
+/// Delivery counters of one named subscriber, shared with its subscription
+#[derive(Debug, Default)]
+struct Counters {
+    backlog: AtomicU64,
+    lagged: AtomicU64,
+    dropped: AtomicU64,
+    resynced: AtomicU64,
+}
+
+/// Current objects of a kind as ADDED events, what [`EventBus::resync`]
+/// sends a lagging subscriber
+pub trait SnapshotSource: Send + Sync {
+    fn snapshot(&self, gvk: &GroupVersionKind) -> Result<Vec<ResourceEvent>>;
+}
+
+/// Events received by a named subscriber: the published events, with the
+/// snapshots it asked for ahead of them
+pub struct Subscription {
+    receiver: broadcast::Receiver<ResourceEvent>,
+    snapshots: mpsc::UnboundedReceiver<ResourceEvent>,
+    counters: Arc<Counters>,
+}
+
+impl Subscription {
+    /// Next event, like [`broadcast::Receiver::recv`]: `Lagged` tells how
+    /// many published events the subscriber missed by falling behind
+    pub async fn recv(&mut self) -> std::result::Result<ResourceEvent, RecvError> {
+        let result = tokio::select! {
+            biased;
+            Some(event) = self.snapshots.recv() => Ok(event),
+            result = self.receiver.recv() => result,
+        };
+        if let Err(RecvError::Lagged(n)) = &result {
+            self.counters.lagged.fetch_add(1, Ordering::Relaxed);
+            self.counters.dropped.fetch_add(*n, Ordering::Relaxed);
+        }
+        self.counters
+            .backlog
+            .store(self.receiver.len() as u64, Ordering::Relaxed);
+        result
+    }
+}
+
+/// Where resource events are published and subscribed to
+///
+/// Controllers and the scheduler take an `Arc<dyn EventBus>` rather than a
+/// clone of the API server's channel, so they don't depend on running in the
+/// same process as the API server. [`InProcessEventBus`] is the only
+/// implementation today; one fed by a remote watch would slot in here.
+pub trait EventBus: Send + Sync {
+    /// Publish an event to every current subscriber
+    fn publish(&self, event: ResourceEvent);
+
+    /// Receive the events published from now on
+    fn subscribe(&self) -> broadcast::Receiver<ResourceEvent>;
+
+    /// Receive the events published from now on as `subscriber`, whose
+    /// delivery is tracked by name and who can ask for a [`resync`]
+    ///
+    /// [`resync`]: EventBus::resync
+    fn subscribe_as(&self, subscriber: &str) -> Subscription;
+
+    /// Send `subscriber` an ADDED event for every current object of `gvk`,
+    /// ahead of the events published meanwhile, and return how many. A
+    /// subscriber that lagged catches up this way instead of relisting over
+    /// HTTP; objects deleted while it lagged are not in the snapshot.
+    fn resync(&self, subscriber: &str, gvk: &GroupVersionKind) -> Result<usize>;
+
+    /// How each named subscriber is keeping up
+    fn subscriber_stats(&self) -> Vec<SubscriberStats>;
+}
+
+/// A named subscriber as the bus knows it
+struct Subscriber {
+    counters: Arc<Counters>,
+    snapshots: mpsc::UnboundedSender<ResourceEvent>,
+}
+
+/// Event bus backed by a broadcast channel in this process
+#[derive(Clone)]
+pub struct InProcessEventBus {
+    sender: broadcast::Sender<ResourceEvent>,
+    subscribers: Arc<Mutex<BTreeMap<String, Subscriber>>>,
+    snapshots: Arc<RwLock<Option<Arc<dyn SnapshotSource>>>>,
+}
+
+impl InProcessEventBus {
+    /// Create a bus buffering up to `capacity` events per slow subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            subscribers: Arc::new(Mutex::new(BTreeMap::new())),
+            snapshots: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Take resync snapshots from `source`; until one is set, resyncs fail
+    pub fn set_snapshot_source(&self, source: Arc<dyn SnapshotSource>) {
+        *self.snapshots.write().unwrap() = Some(source);
+    }
+}
+
+impl EventBus for InProcessEventBus {
+    fn publish(&self, event: ResourceEvent) {
+        // Nobody listening is not an error
+        let _ = self.sender.send(event);
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<ResourceEvent> {
+        self.sender.subscribe()
+    }
+
+    fn subscribe_as(&self, subscriber: &str) -> Subscription {
+        let (sender, snapshots) = mpsc::unbounded_channel();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        // A resubscribing subscriber keeps its counters
+        let counters = subscribers
+            .remove(subscriber)
+            .map(|s| s.counters)
+            .unwrap_or_default();
+        subscribers.insert(
+            subscriber.to_string(),
+            Subscriber {
+                counters: counters.clone(),
+                snapshots: sender,
+            },
+        );
+        Subscription {
+            receiver: self.sender.subscribe(),
+            snapshots,
+            counters,
+        }
+    }
+
+    fn resync(&self, subscriber: &str, gvk: &GroupVersionKind) -> Result<usize> {
+        let source = self.snapshots.read().unwrap().clone().ok_or_else(|| {
+            ReddwarfError::internal_error("The event bus has no snapshot source to resync from")
+        })?;
+        let events = source.snapshot(gvk)?;
+
+        let subscribers = self.subscribers.lock().unwrap();
+        let target = subscribers
+            .get(subscriber)
+            .filter(|s| !s.snapshots.is_closed())
+            .ok_or_else(|| {
+                ReddwarfError::internal_error(format!("No subscriber '{}' to resync", subscriber))
+            })?;
+        let count = events.len();
+        for event in events {
+            let _ = target.snapshots.send(event);
+        }
+        target
+            .counters
+            .resynced
+            .fetch_add(count as u64, Ordering::Relaxed);
+        Ok(count)
+    }
+
+    fn subscriber_stats(&self) -> Vec<SubscriberStats> {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        // Forget subscribers whose subscription was dropped
+        subscribers.retain(|_, s| !s.snapshots.is_closed());
+        subscribers
+            .iter()
+            .map(|(name, s)| SubscriberStats {
+                name: name.clone(),
+                backlog: s.counters.backlog.load(Ordering::Relaxed),
+                lagged: s.counters.lagged.load(Ordering::Relaxed),
+                dropped: s.counters.dropped.load(Ordering::Relaxed),
+                resynced: s.counters.resynced.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pods(Vec<&'static str>);
+
+    impl SnapshotSource for Pods {
+        fn snapshot(&self, gvk: &GroupVersionKind) -> Result<Vec<ResourceEvent>> {
+            Ok(self
+                .0
+                .iter()
+                .map(|name| {
+                    let key = ResourceKey::new(gvk.clone(), "default", *name);
+                    ResourceEvent::added(key, serde_json::json!({}), "1".to_string())
+                })
+                .collect())
+        }
+    }
+
+    fn event(name: &str) -> ResourceEvent {
+        let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
+        let key = ResourceKey::new(gvk, "default", name);
+        ResourceEvent::modified(key, serde_json::json!({}), "2".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_lag_is_counted_and_resync_comes_first() {
+        let bus = InProcessEventBus::new(2);
+        let mut slow = bus.subscribe_as("slow");
+        let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
+        assert!(bus.resync("slow", &gvk).is_err());
+        bus.set_snapshot_source(Arc::new(Pods(vec!["a", "b"])));
+
+        for name in ["p1", "p2", "p3", "p4", "p5"] {
+            bus.publish(event(name));
+        }
+        assert!(matches!(slow.recv().await, Err(RecvError::Lagged(3))));
+        let stats = &bus.subscriber_stats()[0];
+        assert_eq!((stats.lagged, stats.dropped, stats.backlog), (1, 3, 2));
+
+        // The snapshot is received before the events still buffered
+        assert_eq!(bus.resync("slow", &gvk).unwrap(), 2);
+        let received = slow.recv().await.unwrap();
+        assert!(matches!(received.event_type, WatchEventType::Added));
+        assert_eq!(received.resource_key.name, "a");
+        assert_eq!(slow.recv().await.unwrap().resource_key.name, "b");
+        assert_eq!(slow.recv().await.unwrap().resource_key.name, "p4");
+        assert!(bus.resync("nobody", &gvk).is_err());
+
+        let stats = &bus.subscriber_stats()[0];
+        assert_eq!(
+            (stats.name.as_str(), stats.backlog, stats.resynced),
+            ("slow", 1, 2)
+        );
+
+        drop(slow);
+        assert!(bus.subscriber_stats().is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Ports declared by pod containers (`containers[].ports[]`), and the host
+//! ports among them (`hostPort`)
+
+use crate::Pod;
+use std::collections::HashMap;
+
+/// Storage key prefix under which host port reservations are recorded
+pub const HOST_PORT_KEY_PREFIX: &str = "hostports/";
+
+/// A container port exposed on the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostPort {
+    /// `TCP`, `UDP` or `SCTP`
+    pub protocol: String,
+    /// Node address to bind; `None` means all addresses
+    pub host_ip: Option<String>,
+    pub host_port: u16,
+    pub container_port: u16,
+}
+
+impl HostPort {
+    /// Storage key reserving this port on `node_name`.
+    ///
+    /// The host IP is not part of the key: a port is reserved on every node
+    /// address at once, which is stricter than Kubernetes but avoids tracking
+    /// overlapping wildcard and specific bindings.
+    pub fn reservation_key(&self, node_name: &str) -> String {
+        format!(
+            "{}{}/{}/{}",
+            HOST_PORT_KEY_PREFIX, node_name, self.protocol, self.host_port
+        )
+    }
+}
+
+/// Storage key prefix covering every host port reserved on a node
+pub fn node_host_port_prefix(node_name: &str) -> String {
+    format!("{}{}/", HOST_PORT_KEY_PREFIX, node_name)
+}
+
+/// Value recorded against a reservation key: the owning pod
+pub fn host_port_owner(namespace: &str, pod_name: &str) -> String {
+    format!("{}/{}", namespace, pod_name)
+}
+
+/// Host ports requested by all of a pod's containers
+pub fn pod_host_ports(pod: &Pod) -> Vec<HostPort> {
+    let Some(spec) = pod.spec.as_ref() else {
+        return Vec::new();
+    };
+
+    spec.containers
+        .iter()
+        .flat_map(|c| c.ports.iter().flatten())
+        .filter_map(|p| {
+            let host_port = u16::try_from(p.host_port?).ok().filter(|p| *p != 0)?;
+            Some(HostPort {
+                protocol: p.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
+                host_ip: p.host_ip.clone().filter(|ip| !ip.is_empty()),
+                host_port,
+                container_port: u16::try_from(p.container_port).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Ports declared twice in a pod, one message per clash
+///
+/// All containers of a pod run in one zone and share its network stack, so
+/// they reach each other on 127.0.0.1 and no two of them can listen on the
+/// same port. Sidecars (init containers with `restartPolicy: Always`) run
+/// alongside the containers and count as well; other init containers run
+/// before them, one at a time.
+pub fn pod_port_conflicts(pod: &Pod) -> Vec<String> {
+    let Some(spec) = pod.spec.as_ref() else {
+        return Vec::new();
+    };
+    let sidecars = spec
+        .init_containers
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, c)| c.restart_policy.as_deref() == Some("Always"))
+        .map(|(i, c)| ("initContainers", i, c));
+    let containers = spec
+        .containers
+        .iter()
+        .enumerate()
+        .map(|(i, c)| ("containers", i, c));
+
+    let mut owners: HashMap<(&str, i32, String), &str> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for (field, i, container) in sidecars.chain(containers) {
+        for (j, port) in container.ports.iter().flatten().enumerate() {
+            let protocol = port.protocol.clone().unwrap_or_else(|| "TCP".to_string());
+            let host_port = port.host_port.filter(|p| *p != 0);
+            let declared = [
+                ("containerPort", Some(port.container_port)),
+                ("hostPort", host_port),
+            ];
+            for (kind, number) in declared {
+                let Some(number) = number else {
+                    continue;
+                };
+                let key = (kind, number, protocol.clone());
+                if let Some(owner) = owners.get(&key) {
+                    conflicts.push(format!(
+                        "spec.{}[{}].ports[{}]: {} {}/{} is already used by container '{}'; \
+                         the containers of a pod share one network stack",
+                        field, i, j, kind, number, protocol, owner
+                    ));
+                } else {
+                    owners.insert(key, container.name.as_str());
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec};
+
+    #[test]
+    fn test_pod_host_ports() {
+        let pod = Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "web".to_string(),
+                    ports: Some(vec![
+                        ContainerPort {
+                            container_port: 8080,
+                            host_port: Some(80),
+                            ..Default::default()
+                        },
+                        ContainerPort {
+                            container_port: 53,
+                            host_port: Some(5353),
+                            protocol: Some("UDP".to_string()),
+                            host_ip: Some("192.168.1.5".to_string()),
+                            ..Default::default()
+                        },
+                        // No hostPort: not exposed on the node
+                        ContainerPort {
+                            container_port: 9090,
+                            ..Default::default()
+                        },
+                    ]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let ports = pod_host_ports(&pod);
+        assert_eq!(ports.len(), 2);
+        assert_eq!(ports[0].protocol, "TCP");
+        assert_eq!(ports[0].host_port, 80);
+        assert_eq!(ports[0].container_port, 8080);
+        assert_eq!(ports[0].reservation_key("node1"), "hostports/node1/TCP/80");
+        assert_eq!(ports[1].host_ip.as_deref(), Some("192.168.1.5"));
+    }
+
+    #[test]
+    fn test_pod_port_conflicts() {
+        let container = |name: &str, ports: &[(i32, Option<i32>)]| Container {
+            name: name.to_string(),
+            ports: Some(
+                ports
+                    .iter()
+                    .map(|(container_port, host_port)| ContainerPort {
+                        container_port: *container_port,
+                        host_port: *host_port,
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let pod = |init: Vec<Container>, containers: Vec<Container>| Pod {
+            spec: Some(PodSpec {
+                init_containers: Some(init),
+                containers,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // A web server and a sidecar talking to it over 127.0.0.1:8080
+        let ok = pod(
+            vec![container("migrate", &[(8080, None)])],
+            vec![
+                container("web", &[(8080, Some(80))]),
+                container("proxy", &[(9090, None)]),
+            ],
+        );
+        assert!(pod_port_conflicts(&ok).is_empty());
+
+        let mut sidecar = container("mesh", &[(9090, Some(80))]);
+        sidecar.restart_policy = Some("Always".to_string());
+        let clashing = pod(
+            vec![sidecar],
+            vec![
+                container("web", &[(8080, Some(80))]),
+                container("proxy", &[(9090, None), (8080, None)]),
+            ],
+        );
+        assert_eq!(
+            pod_port_conflicts(&clashing),
+            vec![
+                "spec.containers[0].ports[0]: hostPort 80/TCP is already used by container \
+                 'mesh'; the containers of a pod share one network stack",
+                "spec.containers[1].ports[0]: containerPort 9090/TCP is already used by \
+                 container 'mesh'; the containers of a pod share one network stack",
+                "spec.containers[1].ports[1]: containerPort 8080/TCP is already used by \
+                 container 'web'; the containers of a pod share one network stack",
+            ]
+        );
+    }
+}
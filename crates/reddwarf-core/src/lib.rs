@@ -2,26 +2,83 @@
 //!
 //! This crate provides:
 //! - Core Kubernetes resource abstractions
-//! - Error types with miette diagnostics
+//! - Error types with miette diagnostics, and the classification shared by
+//!   the errors of all crates
 //! - Type-safe resource keys and identifiers
 //! - Serialization helpers
+//! - Component versions and the skew allowed between them
+//! - The annotations reddwarf recognizes, with typed helpers for their values
 
+pub mod annotations;
+pub mod applyset;
+pub mod devices;
 pub mod error;
 pub mod events;
+pub mod host_ports;
+pub mod metrics;
+pub mod platform;
+pub mod pod_ips;
+pub mod recorder;
 pub mod resources;
+pub mod session;
+pub mod startup;
+pub mod termination;
+pub mod topology;
 pub mod types;
+pub mod version;
 
 // Re-export commonly used types
-pub use error::{ReddwarfError, Result};
-pub use events::{ResourceEvent, WatchEventType};
-pub use resources::{is_valid_name, Resource, ResourceError, ResourceQuantities};
+pub use applyset::applyset_of;
+pub use devices::{pod_device_requests, DevicePool};
+pub use error::{ErrorClass, ErrorKind, ReddwarfError, Result};
+pub use events::{
+    EventBus, InProcessEventBus, ResourceEvent, SnapshotSource, SubscriberStats, Subscription,
+    WatchEventType,
+};
+pub use host_ports::{pod_host_ports, pod_port_conflicts, HostPort};
+pub use metrics::Metrics;
+pub use platform::Platform;
+pub use recorder::{EventRecorder, EventSink, MemoryEventSink};
+pub use resources::{
+    is_valid_label, is_valid_name, pod_lx_image, pod_qos_class, pod_zone_brand, CustomObject,
+    ImageMapping, ImageMappingSpec, MeshPolicy, MeshPolicySpec, QosClass, Resource, ResourceError,
+    ResourceQuantities,
+};
+pub use session::{
+    ProcessSession, SessionEnd, SessionInput, SessionOutput, SessionRequest, SessionTarget,
+    TerminalSize,
+};
+pub use startup::PodStartup;
+pub use termination::{Termination, TerminationReason};
 pub use types::{GroupVersionKind, ResourceKey, ResourceVersion};
+pub use version::Version;
 
 // Re-export k8s-openapi types for convenience
 pub use k8s_openapi;
-pub use k8s_openapi::api::core::v1::{Namespace, Node, Pod, Service};
+pub use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet};
+pub use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
+pub use k8s_openapi::api::coordination::v1::Lease;
+pub use k8s_openapi::api::core::v1::{
+    ConfigMap, Endpoints, Event, Namespace, Node, PersistentVolume, PersistentVolumeClaim, Pod,
+    Secret, Service,
+};
+pub use k8s_openapi::api::discovery::v1::EndpointSlice;
+pub use k8s_openapi::api::networking::v1::NetworkPolicy;
+pub use k8s_openapi::api::node::v1::RuntimeClass;
+pub use k8s_openapi::api::policy::v1::PodDisruptionBudget;
+pub use k8s_openapi::api::scheduling::v1::PriorityClass;
+pub use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
 pub use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
+/// Annotation prefix reserved for values reported by the node (e.g. applied
+/// limits). Annotations under this prefix are written through the status
+/// subresource rather than by regular updates.
+pub const STATUS_ANNOTATION_PREFIX: &str = "status.reddwarf.io/";
+
+/// Annotation that, set to `"true"`, makes the API server refuse to delete
+/// the object unless the deletion is confirmed
+pub const PROTECTED_ANNOTATION: &str = "reddwarf.io/protected";
+
 /// Serialize a resource to JSON
 pub fn to_json<T: serde::Serialize>(resource: &T) -> Result<String> {
     serde_json::to_string(resource).map_err(|e| {
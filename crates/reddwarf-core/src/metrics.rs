@@ -0,0 +1,249 @@
+//! In-process metrics, rendered in the Prometheus text exposition format
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Upper bounds, in seconds, of the buckets of latency histograms
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// A histogram with fixed bucket bounds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket (not cumulative)
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        if let Some(i) = self.bounds.iter().position(|b| value <= *b) {
+            self.buckets[i] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Number of observations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of all observed values
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    fn write(&self, name: &str, labels: &str, out: &mut String) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, n) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += n;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}{}le=\"{}\"}} {}",
+                name, labels, sep, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+            name, labels, sep, self.count
+        );
+        let braces = |labels: &str| {
+            if labels.is_empty() {
+                String::new()
+            } else {
+                format!("{{{}}}", labels)
+            }
+        };
+        let _ = writeln!(out, "{}_sum{} {}", name, braces(labels), self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, braces(labels), self.count);
+    }
+}
+
+/// Percentiles of a set of latencies
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl LatencySummary {
+    /// Summarize `values`; `None` if there are none
+    pub fn from_values(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let percentile = |p: f64| {
+            let rank = ((p * values.len() as f64).ceil() as usize).max(1);
+            values[rank - 1]
+        };
+        Some(Self {
+            count: values.len(),
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            max: values[values.len() - 1],
+        })
+    }
+}
+
+struct Family {
+    help: &'static str,
+    /// Series by rendered label set
+    series: BTreeMap<String, Histogram>,
+}
+
+/// Renders metrics kept elsewhere, e.g. counters of a client, in the
+/// Prometheus text format
+type Collector = Box<dyn Fn() -> String + Send + Sync>;
+
+/// Histograms shared by the components of one process, served at `/metrics`
+#[derive(Default)]
+pub struct Metrics {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+    collectors: Mutex<Vec<Collector>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `value` in the latency histogram `name` for `labels`
+    pub fn observe(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        let mut families = self.families.lock().unwrap();
+        families
+            .entry(name)
+            .or_insert_with(|| Family {
+                help,
+                series: BTreeMap::new(),
+            })
+            .series
+            .entry(format_labels(labels))
+            .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+            .observe(value);
+    }
+
+    /// A copy of the histogram `name` for `labels`, if anything was recorded
+    pub fn histogram(&self, name: &str, labels: &[(&str, &str)]) -> Option<Histogram> {
+        let families = self.families.lock().unwrap();
+        families
+            .get(name)
+            .and_then(|f| f.series.get(&format_labels(labels)))
+            .cloned()
+    }
+
+    /// Render the output of `collector` after the histograms on every
+    /// [`render`](Self::render)
+    pub fn add_collector(&self, collector: impl Fn() -> String + Send + Sync + 'static) {
+        self.collectors.lock().unwrap().push(Box::new(collector));
+    }
+
+    /// Render every metric in the Prometheus text format (version 0.0.4)
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} histogram", name);
+            for (labels, histogram) in &family.series {
+                histogram.write(name, labels, &mut out);
+            }
+        }
+        for collector in self.collectors.lock().unwrap().iter() {
+            out.push_str(&collector());
+        }
+        out
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", k, v)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_histogram() {
+        let metrics = Metrics::new();
+        metrics.observe("op_seconds", "Operation latency", &[("op", "a")], 0.2);
+        metrics.observe("op_seconds", "Operation latency", &[("op", "a")], 7.0);
+        metrics.observe("op_seconds", "Operation latency", &[("op", "a")], 900.0);
+
+        let histogram = metrics.histogram("op_seconds", &[("op", "a")]).unwrap();
+        assert_eq!(histogram.count(), 3);
+        assert_eq!(histogram.sum(), 907.2);
+        assert!(metrics.histogram("op_seconds", &[("op", "b")]).is_none());
+
+        let text = metrics.render();
+        assert!(
+            text.starts_with("# HELP op_seconds Operation latency\n# TYPE op_seconds histogram\n")
+        );
+        assert!(text.contains("op_seconds_bucket{op=\"a\",le=\"0.1\"} 0\n"));
+        assert!(text.contains("op_seconds_bucket{op=\"a\",le=\"0.25\"} 1\n"));
+        assert!(text.contains("op_seconds_bucket{op=\"a\",le=\"10\"} 2\n"));
+        assert!(text.contains("op_seconds_bucket{op=\"a\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("op_seconds_count{op=\"a\"} 3\n"));
+    }
+
+    #[test]
+    fn test_render_collectors() {
+        let metrics = Metrics::new();
+        metrics.observe("op_seconds", "Operation latency", &[], 0.2);
+        metrics.add_collector(|| "# TYPE up gauge\nup 1\n".to_string());
+
+        let text = metrics.render();
+        assert!(text.contains("op_seconds_count 1\n"));
+        assert!(text.ends_with("# TYPE up gauge\nup 1\n"));
+    }
+
+    #[test]
+    fn test_latency_summary() {
+        assert!(LatencySummary::from_values(vec![]).is_none());
+
+        let summary =
+            LatencySummary::from_values((1..=100).rev().map(f64::from).collect()).unwrap();
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50, 50.0);
+        assert_eq!(summary.p90, 90.0);
+        assert_eq!(summary.p99, 99.0);
+        assert_eq!(summary.max, 100.0);
+    }
+}
@@ -0,0 +1,105 @@
+//! Node architecture / OS identification and image platform matching
+
+use crate::Pod;
+use std::fmt;
+
+/// Well-known node label carrying the CPU architecture (e.g. `amd64`)
+pub const ARCH_LABEL: &str = "kubernetes.io/arch";
+
+/// Well-known node label carrying the operating system (e.g. `illumos`)
+pub const OS_LABEL: &str = "kubernetes.io/os";
+
+/// Pod annotation listing the platforms the pod's image is built for,
+/// as comma-separated `os/arch` pairs (e.g. `linux/amd64,linux/arm64`)
+pub const IMAGE_PLATFORMS_ANNOTATION: &str = "reddwarf.io/image-platforms";
+
+/// An `os/arch` platform pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    pub os: String,
+    pub arch: String,
+}
+
+impl Platform {
+    /// Parse `os/arch` (an optional `/variant` suffix is ignored).
+    /// The architecture is normalized to Kubernetes naming.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().split('/');
+        let os = parts.next().filter(|p| !p.is_empty())?;
+        let arch = parts.next().filter(|p| !p.is_empty())?;
+        Some(Self {
+            os: os.to_string(),
+            arch: normalize_arch(arch).to_string(),
+        })
+    }
+
+    /// Platform of the running host
+    pub fn host() -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: normalize_arch(std::env::consts::ARCH).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.os, self.arch)
+    }
+}
+
+/// Map Rust/uname architecture names to the names Kubernetes uses
+pub fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "x86_64" | "amd64" | "i86pc" => "amd64",
+        "aarch64" | "arm64" => "arm64",
+        "sparc64" | "sparcv9" | "sun4v" => "sparc64",
+        other => other,
+    }
+}
+
+/// Platforms declared for the pod's image via annotation, if any
+pub fn pod_image_platforms(pod: &Pod) -> Option<Vec<Platform>> {
+    let value = pod
+        .metadata
+        .annotations
+        .as_ref()?
+        .get(IMAGE_PLATFORMS_ANNOTATION)?;
+    let platforms: Vec<Platform> = value.split(',').filter_map(Platform::parse).collect();
+    (!platforms.is_empty()).then_some(platforms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_parse_normalizes_arch() {
+        let p = Platform::parse("linux/x86_64").unwrap();
+        assert_eq!(p.os, "linux");
+        assert_eq!(p.arch, "amd64");
+
+        let p = Platform::parse("linux/arm64/v8").unwrap();
+        assert_eq!(p.arch, "arm64");
+
+        assert!(Platform::parse("linux").is_none());
+        assert!(Platform::parse("/amd64").is_none());
+    }
+
+    #[test]
+    fn test_pod_image_platforms() {
+        let mut pod = Pod::default();
+        assert!(pod_image_platforms(&pod).is_none());
+
+        pod.metadata.annotations = Some(
+            [(
+                IMAGE_PLATFORMS_ANNOTATION.to_string(),
+                "linux/amd64, linux/aarch64".to_string(),
+            )]
+            .into(),
+        );
+        let platforms = pod_image_platforms(&pod).unwrap();
+        assert_eq!(platforms.len(), 2);
+        assert_eq!(platforms[1].to_string(), "linux/arm64");
+    }
+}
@@ -0,0 +1,97 @@
+//! Pod IP allocations, shared by the node IPAM that hands pods their
+//! addresses and the scheduler, which may reserve a pod's address out of its
+//! node's pod CIDR when binding it
+
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+
+/// Storage key prefix under which pod IP allocations are recorded
+pub const POD_IP_KEY_PREFIX: &str = "ipam/alloc/";
+
+/// Storage key allocating `ip`
+pub fn pod_ip_key(ip: Ipv4Addr) -> String {
+    format!("{}{}", POD_IP_KEY_PREFIX, ip)
+}
+
+/// Address allocated under a storage key, if it is one
+pub fn pod_ip_from_key(key: &str) -> Option<Ipv4Addr> {
+    key.strip_prefix(POD_IP_KEY_PREFIX)?.parse().ok()
+}
+
+/// Value recorded against an allocation key: the owning pod
+pub fn pod_ip_owner(namespace: &str, pod_name: &str) -> String {
+    format!("{}/{}", namespace, pod_name)
+}
+
+/// Addresses pods may be given out of `cidr`, as its first and last host:
+/// the network address and the gateway after it, and the broadcast address,
+/// are left out. `None` if `cidr` is malformed or too small to hold a pod.
+pub fn pod_cidr_hosts(cidr: &str) -> Option<(Ipv4Addr, Ipv4Addr)> {
+    let (network, prefix_len) = cidr.split_once('/')?;
+    let network = u32::from(network.parse::<Ipv4Addr>().ok()?);
+    let prefix_len: u32 = prefix_len.parse().ok().filter(|p| *p <= 30)?;
+    let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+    let network = network & mask;
+    let broadcast = network | !mask;
+    Some((Ipv4Addr::from(network + 2), Ipv4Addr::from(broadcast - 1)))
+}
+
+/// Lowest address of `cidr` not in `allocated`
+pub fn first_free_pod_ip(cidr: &str, allocated: &HashSet<Ipv4Addr>) -> Option<Ipv4Addr> {
+    let (first, last) = pod_cidr_hosts(cidr)?;
+    (u32::from(first)..=u32::from(last))
+        .map(Ipv4Addr::from)
+        .find(|ip| !allocated.contains(ip))
+}
+
+/// How many addresses of `cidr` are not in `allocated`
+pub fn free_pod_ips(cidr: &str, allocated: &HashSet<Ipv4Addr>) -> u64 {
+    let Some((first, last)) = pod_cidr_hosts(cidr) else {
+        return 0;
+    };
+    let hosts = u64::from(u32::from(last) - u32::from(first)) + 1;
+    let used = allocated
+        .iter()
+        .filter(|ip| (first..=last).contains(*ip))
+        .count() as u64;
+    hosts - used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pod_cidr_hosts() {
+        assert_eq!(
+            pod_cidr_hosts("10.88.1.0/24"),
+            Some((Ipv4Addr::new(10, 88, 1, 2), Ipv4Addr::new(10, 88, 1, 254)))
+        );
+        assert_eq!(
+            pod_cidr_hosts("10.88.0.0/30"),
+            Some((Ipv4Addr::new(10, 88, 0, 2), Ipv4Addr::new(10, 88, 0, 2)))
+        );
+        assert_eq!(pod_cidr_hosts("10.88.0.0/31"), None);
+        assert_eq!(pod_cidr_hosts("10.88.0.0"), None);
+        assert_eq!(
+            pod_ip_from_key(&pod_ip_key(Ipv4Addr::new(10, 88, 0, 7))),
+            Some(Ipv4Addr::new(10, 88, 0, 7))
+        );
+    }
+
+    #[test]
+    fn test_free_pod_ips() {
+        let cidr = "10.88.0.0/29";
+        let mut allocated =
+            HashSet::from([Ipv4Addr::new(10, 88, 0, 2), Ipv4Addr::new(10, 9, 0, 1)]);
+        assert_eq!(free_pod_ips(cidr, &allocated), 4);
+        assert_eq!(
+            first_free_pod_ip(cidr, &allocated),
+            Some(Ipv4Addr::new(10, 88, 0, 3))
+        );
+
+        allocated.extend((3..=6).map(|i| Ipv4Addr::new(10, 88, 0, i)));
+        assert_eq!(free_pod_ips(cidr, &allocated), 0);
+        assert_eq!(first_free_pod_ip(cidr, &allocated), None);
+    }
+}
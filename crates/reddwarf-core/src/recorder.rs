@@ -0,0 +1,185 @@
+//! Recording `v1` Events about objects, the trail `kubectl describe` shows
+//! under an object: when a pod was scheduled or failed to be, when its zone
+//! was provisioned, when its probes failed
+//!
+//! Components emit events through an [`EventRecorder`], which fills in the
+//! involved object and the source and hands the event to an [`EventSink`].
+//! The sink decides where it goes: straight into the API server's event
+//! store for components running next to it, or over HTTP for node agents.
+
+use crate::resources::Resource;
+use k8s_openapi::api::core::v1::{Event, EventSource, ObjectReference};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::sync::{Arc, Mutex};
+
+/// Type of events about normal operation
+pub const EVENT_NORMAL: &str = "Normal";
+
+/// Type of events about something going wrong
+pub const EVENT_WARNING: &str = "Warning";
+
+/// Reason of the event recorded when a pod is bound to a node
+pub const REASON_SCHEDULED: &str = "Scheduled";
+
+/// Reason of the event recorded when no node fits a pod
+pub const REASON_FAILED_SCHEDULING: &str = "FailedScheduling";
+
+/// Reason of the event recorded when a pod's zone is up
+pub const REASON_PROVISIONED: &str = "Provisioned";
+
+/// Reason of the event recorded when a pod's liveness or startup probe
+/// fails past its threshold
+pub const REASON_PROBE_FAILED: &str = "ProbeFailed";
+
+/// Reason of the event recorded when a node agent registers its node
+pub const REASON_REGISTERED_NODE: &str = "RegisteredNode";
+
+/// Where recorded events go
+pub trait EventSink: Send + Sync {
+    /// Store or send `event`. Recording is best effort: a sink logs what it
+    /// fails to deliver rather than failing the caller.
+    fn record(&self, event: Event);
+}
+
+/// Sink keeping events in memory, for tests and for inspecting what a
+/// component records
+#[derive(Debug, Default)]
+pub struct MemoryEventSink {
+    events: Mutex<Vec<Event>>,
+}
+
+impl MemoryEventSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events recorded so far, oldest first
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventSink for MemoryEventSink {
+    fn record(&self, event: Event) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+/// Reference to `object` as the involved object of an event
+pub fn object_reference<T: Resource>(object: &T) -> ObjectReference {
+    let metadata = object.metadata();
+    ObjectReference {
+        api_version: Some(object.api_version()),
+        kind: Some(object.kind()),
+        namespace: metadata.namespace.clone(),
+        name: metadata.name.clone(),
+        uid: metadata.uid.clone(),
+        resource_version: metadata.resource_version.clone(),
+        ..Default::default()
+    }
+}
+
+/// Records events on behalf of one component
+#[derive(Clone)]
+pub struct EventRecorder {
+    sink: Arc<dyn EventSink>,
+    component: String,
+    host: Option<String>,
+}
+
+impl EventRecorder {
+    /// Record events from `component` (e.g. "default-scheduler") into `sink`
+    pub fn new(sink: Arc<dyn EventSink>, component: impl Into<String>) -> Self {
+        Self {
+            sink,
+            component: component.into(),
+            host: None,
+        }
+    }
+
+    /// Name the node the component runs on as the source of its events
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Record a `Normal` event about `object`
+    pub fn normal<T: Resource>(&self, object: &T, reason: &str, message: impl Into<String>) {
+        self.sink
+            .record(self.event(object, EVENT_NORMAL, reason, message.into()));
+    }
+
+    /// Record a `Warning` event about `object`
+    pub fn warning<T: Resource>(&self, object: &T, reason: &str, message: impl Into<String>) {
+        self.sink
+            .record(self.event(object, EVENT_WARNING, reason, message.into()));
+    }
+
+    /// The event, named by the store it ends up in and stamped when stored.
+    /// Events about cluster-scoped objects such as nodes go in `default`.
+    fn event<T: Resource>(&self, object: &T, type_: &str, reason: &str, message: String) -> Event {
+        let involved_object = object_reference(object);
+        Event {
+            metadata: ObjectMeta {
+                namespace: Some(
+                    involved_object
+                        .namespace
+                        .clone()
+                        .unwrap_or_else(|| "default".to_string()),
+                ),
+                ..Default::default()
+            },
+            involved_object,
+            type_: Some(type_.to_string()),
+            reason: Some(reason.to_string()),
+            message: Some(message),
+            source: Some(EventSource {
+                component: Some(self.component.clone()),
+                host: self.host.clone(),
+            }),
+            reporting_component: Some(self.component.clone()),
+            reporting_instance: self.host.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Node, Pod};
+
+    #[test]
+    fn test_recorder_fills_in_object_and_source() {
+        let sink = Arc::new(MemoryEventSink::new());
+        let recorder = EventRecorder::new(sink.clone(), "reddwarf-agent").with_host("node1");
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("web".to_string());
+        pod.metadata.namespace = Some("shop".to_string());
+        pod.metadata.uid = Some("1234".to_string());
+        recorder.warning(&pod, REASON_PROBE_FAILED, "Liveness probe failed");
+
+        let mut node = Node::default();
+        node.metadata.name = Some("node1".to_string());
+        recorder.normal(&node, REASON_REGISTERED_NODE, "Registered node node1");
+
+        let events = sink.events();
+        assert_eq!(events.len(), 2);
+        let event = &events[0];
+        assert_eq!(event.metadata.namespace.as_deref(), Some("shop"));
+        assert_eq!(event.involved_object.kind.as_deref(), Some("Pod"));
+        assert_eq!(event.involved_object.name.as_deref(), Some("web"));
+        assert_eq!(event.involved_object.uid.as_deref(), Some("1234"));
+        assert_eq!(event.type_.as_deref(), Some(EVENT_WARNING));
+        assert_eq!(event.reason.as_deref(), Some(REASON_PROBE_FAILED));
+        let source = event.source.as_ref().unwrap();
+        assert_eq!(source.component.as_deref(), Some("reddwarf-agent"));
+        assert_eq!(source.host.as_deref(), Some("node1"));
+
+        // Nodes have no namespace; their events go in default
+        assert_eq!(events[1].metadata.namespace.as_deref(), Some("default"));
+        assert_eq!(events[1].involved_object.namespace, None);
+        assert_eq!(events[1].type_.as_deref(), Some(EVENT_NORMAL));
+    }
+}
@@ -0,0 +1,145 @@
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::core::v1::ConfigMap;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+/// Kind of the ConfigMap resource
+pub const CONFIG_MAP_KIND: &str = "ConfigMap";
+
+/// Most bytes the keys and values of a ConfigMap may add up to
+pub const MAX_CONFIG_MAP_SIZE: usize = 1024 * 1024;
+
+/// Bytes of the keys and values held in `data` and `binaryData`
+pub fn config_map_size(config_map: &ConfigMap) -> usize {
+    let data: usize = config_map
+        .data
+        .iter()
+        .flatten()
+        .map(|(key, value)| key.len() + value.len())
+        .sum();
+    let binary: usize = config_map
+        .binary_data
+        .iter()
+        .flatten()
+        .map(|(key, value)| key.len() + value.0.len())
+        .sum();
+    data + binary
+}
+
+/// Whether the data of `config_map` may no longer change
+pub fn is_immutable(config_map: &ConfigMap) -> bool {
+    config_map.immutable == Some(true)
+}
+
+/// Whether `key` may name an entry: alphanumerics, '-', '_' and '.', at
+/// most 253 characters, and not "." or ".."
+pub(crate) fn is_valid_config_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 253
+        && key != "."
+        && key != ".."
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+impl Resource for ConfigMap {
+    fn api_version(&self) -> String {
+        "v1".to_string()
+    }
+
+    fn kind(&self) -> String {
+        CONFIG_MAP_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+
+        let data_keys = self.data.iter().flatten().map(|(key, _)| key);
+        let binary_keys = self.binary_data.iter().flatten().map(|(key, _)| key);
+        for key in data_keys.chain(binary_keys) {
+            if !is_valid_config_key(key) {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "ConfigMap key '{}' must consist of alphanumerics, '-', '_' or '.'",
+                    key
+                )));
+            }
+        }
+        if let (Some(data), Some(binary_data)) = (&self.data, &self.binary_data) {
+            if let Some(key) = data.keys().find(|key| binary_data.contains_key(*key)) {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "ConfigMap key '{}' is in both data and binaryData",
+                    key
+                )));
+            }
+        }
+
+        let size = config_map_size(self);
+        if size > MAX_CONFIG_MAP_SIZE {
+            return Err(ResourceError::ValidationFailed(format!(
+                "ConfigMap data is {} bytes, must have at most {} bytes",
+                size, MAX_CONFIG_MAP_SIZE
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::ByteString;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_config_map_validation() {
+        let mut config_map = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("settings".to_string()),
+                ..Default::default()
+            },
+            data: Some(BTreeMap::from([(
+                "app.properties".to_string(),
+                "color=blue".to_string(),
+            )])),
+            binary_data: Some(BTreeMap::from([(
+                "logo.png".to_string(),
+                ByteString(vec![0; 10]),
+            )])),
+            ..Default::default()
+        };
+        assert!(config_map.validate().is_ok());
+        assert_eq!(config_map_size(&config_map), 14 + 10 + 8 + 10);
+        assert!(!is_immutable(&config_map));
+
+        let mut invalid = config_map.clone();
+        invalid
+            .data
+            .as_mut()
+            .unwrap()
+            .insert("logo.png".to_string(), String::new());
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config_map.clone();
+        invalid
+            .data
+            .as_mut()
+            .unwrap()
+            .insert("bad/key".to_string(), String::new());
+        assert!(invalid.validate().is_err());
+
+        config_map.binary_data = Some(BTreeMap::from([(
+            "blob".to_string(),
+            ByteString(vec![0; MAX_CONFIG_MAP_SIZE]),
+        )]));
+        let err = config_map.validate().unwrap_err();
+        assert!(err.to_string().contains("at most 1048576 bytes"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Kinds served at more than one API version
+//!
+//! A multi-version kind is persisted at a single storage version, which is
+//! also the schema of its Rust type. Objects sent or requested at any other
+//! served version are converted to or from the storage version at the API
+//! boundary, so a new version can be served (and an old one deprecated)
+//! without touching stored data.
+
+use super::{Resource, ResourceError};
+use serde_json::Value;
+
+/// An API version a kind is served at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServedVersion {
+    /// API group/version, e.g. "mesh.reddwarf.io/v1beta1"
+    pub api_version: &'static str,
+    /// Still served, but clients should move to the preferred version
+    pub deprecated: bool,
+}
+
+/// A kind served at several API versions
+pub trait MultiVersion: Resource + Sized {
+    /// Kind name, as in `kind`
+    const KIND: &'static str;
+
+    /// Version objects are stored at, and the schema of `Self`
+    const STORAGE_VERSION: &'static str;
+
+    /// Every served version, oldest first; includes the storage version
+    const SERVED_VERSIONS: &'static [ServedVersion];
+
+    /// Convert `object` from the served version `api_version` to the
+    /// storage version. Only called for versions other than the storage one.
+    fn convert_from(api_version: &str, object: Value) -> Result<Value, ResourceError>;
+
+    /// Convert `object` from the storage version to the served version
+    /// `api_version`. Only called for versions other than the storage one.
+    fn convert_to(api_version: &str, object: Value) -> Result<Value, ResourceError>;
+
+    /// The served version `api_version`, if it is one
+    fn served_version(api_version: &str) -> Option<ServedVersion> {
+        Self::SERVED_VERSIONS
+            .iter()
+            .find(|v| v.api_version == api_version)
+            .copied()
+    }
+
+    /// The newest version that is not deprecated
+    fn preferred_version() -> &'static str {
+        Self::SERVED_VERSIONS
+            .iter()
+            .rev()
+            .find(|v| !v.deprecated)
+            .map(|v| v.api_version)
+            .unwrap_or(Self::STORAGE_VERSION)
+    }
+
+    /// Warning to return to clients using `api_version`, if it is deprecated
+    fn deprecation_warning(api_version: &str) -> Option<String> {
+        Self::served_version(api_version)
+            .filter(|v| v.deprecated)
+            .map(|v| {
+                format!(
+                    "{} {} is deprecated; use {}",
+                    v.api_version,
+                    Self::KIND,
+                    Self::preferred_version()
+                )
+            })
+    }
+
+    /// Decode an object sent at `api_version`
+    fn decode(api_version: &str, object: Value) -> Result<Self, ResourceError> {
+        let object = Self::to_storage_version(api_version, object)?;
+        serde_json::from_value(object)
+            .map_err(|e| ResourceError::ConversionFailed(format!("invalid {}: {}", Self::KIND, e)))
+    }
+
+    /// Encode `self` at `api_version`
+    fn encode(&self, api_version: &str) -> Result<Value, ResourceError> {
+        let object = serde_json::to_value(self).map_err(|e| {
+            ResourceError::ConversionFailed(format!("cannot encode {}: {}", Self::KIND, e))
+        })?;
+        Self::from_storage_version(api_version, object)
+    }
+
+    /// Convert a raw object at `api_version` to the storage version
+    fn to_storage_version(api_version: &str, object: Value) -> Result<Value, ResourceError> {
+        Self::check_served(api_version)?;
+        let mut object = if api_version == Self::STORAGE_VERSION {
+            object
+        } else {
+            Self::convert_from(api_version, object)?
+        };
+        set_type_meta(&mut object, Self::STORAGE_VERSION, Self::KIND);
+        Ok(object)
+    }
+
+    /// Convert a raw object at the storage version to `api_version`
+    fn from_storage_version(api_version: &str, object: Value) -> Result<Value, ResourceError> {
+        Self::check_served(api_version)?;
+        let mut object = if api_version == Self::STORAGE_VERSION {
+            object
+        } else {
+            Self::convert_to(api_version, object)?
+        };
+        set_type_meta(&mut object, api_version, Self::KIND);
+        Ok(object)
+    }
+
+    /// Fail unless `api_version` is served
+    fn check_served(api_version: &str) -> Result<(), ResourceError> {
+        match Self::served_version(api_version) {
+            Some(_) => Ok(()),
+            None => Err(ResourceError::ConversionFailed(format!(
+                "{} is not served at {}",
+                Self::KIND,
+                api_version
+            ))),
+        }
+    }
+}
+
+fn set_type_meta(object: &mut Value, api_version: &str, kind: &str) {
+    if let Some(map) = object.as_object_mut() {
+        map.insert("apiVersion".to_string(), Value::from(api_version));
+        map.insert("kind".to_string(), Value::from(kind));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    const OLD: &str = "test.reddwarf.io/v1alpha1";
+    const HUB: &str = "test.reddwarf.io/v1beta1";
+    const NEW: &str = "test.reddwarf.io/v1";
+
+    thread_local! {
+        /// Converters called on this thread, as ("from" | "to", version)
+        static CALLS: RefCell<Vec<(&'static str, String)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn calls() -> Vec<(&'static str, String)> {
+        CALLS.with(|c| c.borrow_mut().drain(..).collect())
+    }
+
+    /// Stored at v1beta1 as `spec.replicas`; v1alpha1 calls it `spec.size`
+    /// and v1 `spec.scale.replicas`
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Widget {
+        #[serde(default)]
+        api_version: String,
+        #[serde(default)]
+        kind: String,
+        #[serde(default)]
+        metadata: ObjectMeta,
+        spec: WidgetSpec,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct WidgetSpec {
+        replicas: u32,
+    }
+
+    impl Resource for Widget {
+        fn api_version(&self) -> String {
+            HUB.to_string()
+        }
+
+        fn kind(&self) -> String {
+            "Widget".to_string()
+        }
+
+        fn metadata(&self) -> &ObjectMeta {
+            &self.metadata
+        }
+
+        fn metadata_mut(&mut self) -> &mut ObjectMeta {
+            &mut self.metadata
+        }
+
+        fn validate(&self) -> Result<(), ResourceError> {
+            Ok(())
+        }
+    }
+
+    impl MultiVersion for Widget {
+        const KIND: &'static str = "Widget";
+        const STORAGE_VERSION: &'static str = HUB;
+        const SERVED_VERSIONS: &'static [ServedVersion] = &[
+            ServedVersion {
+                api_version: OLD,
+                deprecated: true,
+            },
+            ServedVersion {
+                api_version: HUB,
+                deprecated: false,
+            },
+            ServedVersion {
+                api_version: NEW,
+                deprecated: false,
+            },
+        ];
+
+        fn convert_from(api_version: &str, mut object: Value) -> Result<Value, ResourceError> {
+            CALLS.with(|c| c.borrow_mut().push(("from", api_version.to_string())));
+            let replicas = match api_version {
+                OLD => object["spec"]["size"].take(),
+                NEW => object["spec"]["scale"]["replicas"].take(),
+                other => {
+                    return Err(ResourceError::ConversionFailed(format!(
+                        "no conversion from {}",
+                        other
+                    )))
+                }
+            };
+            object["spec"] = json!({ "replicas": replicas });
+            Ok(object)
+        }
+
+        fn convert_to(api_version: &str, mut object: Value) -> Result<Value, ResourceError> {
+            CALLS.with(|c| c.borrow_mut().push(("to", api_version.to_string())));
+            let replicas = object["spec"]["replicas"].take();
+            object["spec"] = match api_version {
+                OLD => json!({ "size": replicas }),
+                NEW => json!({ "scale": { "replicas": replicas } }),
+                other => {
+                    return Err(ResourceError::ConversionFailed(format!(
+                        "no conversion to {}",
+                        other
+                    )))
+                }
+            };
+            Ok(object)
+        }
+    }
+
+    fn old_widget() -> Value {
+        json!({
+            "apiVersion": OLD,
+            "kind": "Widget",
+            "metadata": {"name": "w", "namespace": "default"},
+            "spec": {"size": 3}
+        })
+    }
+
+    #[test]
+    fn test_round_trip_through_the_storage_version() {
+        let widget = Widget::decode(OLD, old_widget()).unwrap();
+        assert_eq!(widget.api_version, HUB);
+        assert_eq!(widget.kind, "Widget");
+        assert_eq!(widget.spec.replicas, 3);
+        assert_eq!(widget.metadata.name.as_deref(), Some("w"));
+
+        assert_eq!(widget.encode(OLD).unwrap(), old_widget());
+
+        // Old to new goes through the hub and back out unchanged
+        let new = widget.encode(NEW).unwrap();
+        assert_eq!(new["apiVersion"], NEW);
+        assert_eq!(new["spec"], json!({"scale": {"replicas": 3}}));
+        assert_eq!(Widget::decode(NEW, new).unwrap(), widget);
+    }
+
+    #[test]
+    fn test_unknown_version_is_rejected() {
+        let widget = Widget::decode(OLD, old_widget()).unwrap();
+        calls();
+
+        for err in [
+            Widget::decode("test.reddwarf.io/v2", old_widget()).unwrap_err(),
+            widget.encode("test.reddwarf.io/v2").unwrap_err(),
+            Widget::to_storage_version("v1", old_widget()).unwrap_err(),
+        ] {
+            assert!(
+                matches!(&err, ResourceError::ConversionFailed(m) if m.starts_with("Widget is not served at")),
+                "{:?}",
+                err
+            );
+        }
+        // Rejected before any converter is asked
+        assert!(calls().is_empty());
+    }
+
+    #[test]
+    fn test_versions_pick_their_converter() {
+        calls();
+
+        // The storage version is passed through without converting
+        let hub = json!({"metadata": {"name": "w"}, "spec": {"replicas": 2}});
+        let widget = Widget::decode(HUB, hub).unwrap();
+        assert_eq!(widget.api_version, HUB);
+        let encoded = widget.encode(HUB).unwrap();
+        assert_eq!(encoded["spec"], json!({"replicas": 2}));
+        assert!(calls().is_empty());
+
+        // Every other served version uses its own converter, in the right direction
+        widget.encode(OLD).unwrap();
+        assert_eq!(calls(), vec![("to", OLD.to_string())]);
+        widget.encode(NEW).unwrap();
+        assert_eq!(calls(), vec![("to", NEW.to_string())]);
+        Widget::decode(OLD, old_widget()).unwrap();
+        assert_eq!(calls(), vec![("from", OLD.to_string())]);
+
+        assert_eq!(Widget::preferred_version(), NEW);
+        assert!(Widget::deprecation_warning(HUB).is_none());
+        assert_eq!(
+            Widget::deprecation_warning(OLD).unwrap(),
+            "test.reddwarf.io/v1alpha1 Widget is deprecated; use test.reddwarf.io/v1"
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use super::schema::apply_schema;
+use super::{is_valid_label, is_valid_name, validate_base, Resource, ResourceError};
+use crate::annotations::validate_annotations;
+use chrono::Utc;
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::{
+    CustomResourceDefinition, CustomResourceDefinitionCondition, CustomResourceDefinitionStatus,
+    CustomResourceDefinitionVersion,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// API group/version of CustomResourceDefinition
+pub const CRD_API_VERSION: &str = "apiextensions.k8s.io/v1";
+
+/// Kind of the CustomResourceDefinition resource
+pub const CRD_KIND: &str = "CustomResourceDefinition";
+
+/// Scope of a custom kind whose objects live in a namespace
+pub const SCOPE_NAMESPACED: &str = "Namespaced";
+
+/// Scope of a custom kind whose objects are cluster-wide
+pub const SCOPE_CLUSTER: &str = "Cluster";
+
+/// Conversion strategy of a CRD whose versions differ only in name, the only
+/// one reddwarf supports
+pub const CONVERSION_NONE: &str = "None";
+
+/// An object of a kind defined by a CustomResourceDefinition: its type
+/// fields and metadata, and everything else as JSON the kind's schema
+/// describes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomObject {
+    #[serde(rename = "apiVersion", default)]
+    pub api_version: String,
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub metadata: ObjectMeta,
+    #[serde(flatten)]
+    pub fields: serde_json::Map<String, Value>,
+}
+
+impl Resource for CustomObject {
+    fn api_version(&self) -> String {
+        self.api_version.clone()
+    }
+
+    fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        validate_annotations(self.metadata.annotations.as_ref())
+    }
+}
+
+/// Whether the objects of `crd` live in a namespace
+pub fn crd_is_namespaced(crd: &CustomResourceDefinition) -> bool {
+    crd.spec.scope == SCOPE_NAMESPACED
+}
+
+/// Name a CRD must have: its plural and group, e.g. `widgets.example.com`
+pub fn crd_name(group: &str, plural: &str) -> String {
+    format!("{}.{}", plural, group)
+}
+
+/// The version of `crd` its objects are stored at
+pub fn storage_version(crd: &CustomResourceDefinition) -> Option<&CustomResourceDefinitionVersion> {
+    crd.spec.versions.iter().find(|v| v.storage)
+}
+
+/// The version `version` of `crd`, if it is served
+pub fn served_version<'a>(
+    crd: &'a CustomResourceDefinition,
+    version: &str,
+) -> Option<&'a CustomResourceDefinitionVersion> {
+    crd.spec
+        .versions
+        .iter()
+        .find(|v| v.name == version && v.served)
+}
+
+/// Whether `version` has a `/status` subresource
+pub fn has_status_subresource(version: &CustomResourceDefinitionVersion) -> bool {
+    version
+        .subresources
+        .as_ref()
+        .is_some_and(|s| s.status.is_some())
+}
+
+/// Check `object` against the schema of `version`, pruning the fields it
+/// doesn't declare and applying its defaults
+pub fn validate_custom_object(
+    version: &CustomResourceDefinitionVersion,
+    object: &mut CustomObject,
+) -> Result<(), ResourceError> {
+    object.validate()?;
+    let Some(schema) = version
+        .schema
+        .as_ref()
+        .and_then(|s| s.open_api_v3_schema.as_ref())
+    else {
+        return Ok(());
+    };
+    let mut fields = Value::Object(std::mem::take(&mut object.fields));
+    let result = apply_schema(schema, &mut fields);
+    if let Value::Object(fields) = fields {
+        object.fields = fields;
+    }
+    result.map_err(ResourceError::ValidationFailed)
+}
+
+/// Status of a CRD whose names were accepted and whose kind is served, with
+/// the `NamesAccepted` and `Established` conditions
+pub fn established_status(crd: &CustomResourceDefinition) -> CustomResourceDefinitionStatus {
+    let condition = |type_: &str, reason: &str, message: &str| {
+        let previous = crd
+            .status
+            .as_ref()
+            .and_then(|s| s.conditions.as_deref())
+            .unwrap_or_default()
+            .iter()
+            .find(|c| c.type_ == type_ && c.status == "True");
+        CustomResourceDefinitionCondition {
+            type_: type_.to_string(),
+            status: "True".to_string(),
+            reason: Some(reason.to_string()),
+            message: Some(message.to_string()),
+            last_transition_time: previous
+                .and_then(|c| c.last_transition_time.clone())
+                .or_else(|| Some(Time(Utc::now()))),
+        }
+    };
+
+    let mut stored_versions = crd
+        .status
+        .as_ref()
+        .and_then(|s| s.stored_versions.clone())
+        .unwrap_or_default();
+    if let Some(version) = storage_version(crd) {
+        if !stored_versions.contains(&version.name) {
+            stored_versions.push(version.name.clone());
+        }
+    }
+
+    CustomResourceDefinitionStatus {
+        accepted_names: Some(crd.spec.names.clone()),
+        conditions: Some(vec![
+            condition("NamesAccepted", "NoConflicts", "no conflicts found"),
+            condition(
+                "Established",
+                "InitialNamesAccepted",
+                "the initial names have been accepted",
+            ),
+        ]),
+        stored_versions: Some(stored_versions),
+    }
+}
+
+fn invalid(message: String) -> ResourceError {
+    ResourceError::ValidationFailed(message)
+}
+
+impl Resource for CustomResourceDefinition {
+    fn api_version(&self) -> String {
+        CRD_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        CRD_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn is_namespaced(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = &self.spec;
+        let names = &spec.names;
+
+        if !spec.group.contains('.') || !is_valid_name(&spec.group) {
+            return Err(invalid(format!(
+                "spec.group '{}' must be a DNS subdomain with at least one dot",
+                spec.group
+            )));
+        }
+        let expected = crd_name(&spec.group, &names.plural);
+        if self.metadata.name.as_deref() != Some(expected.as_str()) {
+            return Err(invalid(format!(
+                "metadata.name must be spec.names.plural+\".\"+spec.group: {}",
+                expected
+            )));
+        }
+        let labels = std::iter::once(("spec.names.plural", &names.plural))
+            .chain(names.singular.iter().map(|s| ("spec.names.singular", s)))
+            .chain(
+                names
+                    .short_names
+                    .iter()
+                    .flatten()
+                    .map(|s| ("spec.names.shortNames", s)),
+            );
+        for (field, value) in labels {
+            if !is_valid_label(value) {
+                return Err(invalid(format!(
+                    "{} '{}' must be a lowercase DNS-1123 label",
+                    field, value
+                )));
+            }
+        }
+        if !names.kind.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !names.kind.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(invalid(format!(
+                "spec.names.kind '{}' must be alphanumeric, starting with a letter",
+                names.kind
+            )));
+        }
+        if spec.scope != SCOPE_NAMESPACED && spec.scope != SCOPE_CLUSTER {
+            return Err(invalid(format!(
+                "spec.scope '{}' must be {} or {}",
+                spec.scope, SCOPE_NAMESPACED, SCOPE_CLUSTER
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        for version in &spec.versions {
+            if !is_valid_label(&version.name) || !seen.insert(version.name.as_str()) {
+                return Err(invalid(format!(
+                    "spec.versions '{}' must be a unique DNS-1123 label",
+                    version.name
+                )));
+            }
+            let root = version
+                .schema
+                .as_ref()
+                .and_then(|s| s.open_api_v3_schema.as_ref());
+            if root.and_then(|s| s.type_.as_deref()) != Some("object") {
+                return Err(invalid(format!(
+                    "spec.versions[{}].schema.openAPIV3Schema must be of type object",
+                    version.name
+                )));
+            }
+        }
+        if spec.versions.iter().filter(|v| v.storage).count() != 1 {
+            return Err(invalid(
+                "spec.versions must have exactly one storage version".to_string(),
+            ));
+        }
+        if !spec.versions.iter().any(|v| v.served) {
+            return Err(invalid(
+                "spec.versions must have at least one served version".to_string(),
+            ));
+        }
+        match spec.conversion.as_ref().map(|c| c.strategy.as_str()) {
+            None | Some(CONVERSION_NONE) => Ok(()),
+            Some(strategy) => Err(invalid(format!(
+                "spec.conversion.strategy '{}' is not supported, only {}",
+                strategy, CONVERSION_NONE
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn crd() -> CustomResourceDefinition {
+        serde_json::from_value(json!({
+            "metadata": {"name": "widgets.example.com"},
+            "spec": {
+                "group": "example.com",
+                "names": {"kind": "Widget", "plural": "widgets", "shortNames": ["wd"]},
+                "scope": "Namespaced",
+                "versions": [
+                    {
+                        "name": "v1",
+                        "served": true,
+                        "storage": true,
+                        "schema": {"openAPIV3Schema": {
+                            "type": "object",
+                            "properties": {"spec": {
+                                "type": "object",
+                                "properties": {"size": {"type": "integer", "default": 1}}
+                            }}
+                        }},
+                        "subresources": {"status": {}}
+                    },
+                    {
+                        "name": "v1beta1",
+                        "served": false,
+                        "storage": false,
+                        "schema": {"openAPIV3Schema": {"type": "object"}}
+                    }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_crd() {
+        assert!(crd().validate().is_ok());
+
+        let invalid: [fn(&mut CustomResourceDefinition); 6] = [
+            |c| c.metadata.name = Some("widget.example.com".to_string()),
+            |c| c.spec.group = "example".to_string(),
+            |c| c.spec.scope = "Global".to_string(),
+            |c| c.spec.names.kind = "my-widget".to_string(),
+            |c| c.spec.versions[1].storage = true,
+            |c| c.spec.versions[0].schema = None,
+        ];
+        for mutate in invalid {
+            let mut crd = crd();
+            mutate(&mut crd);
+            assert!(crd.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_crd_versions_and_status() {
+        let crd = crd();
+        assert!(crd_is_namespaced(&crd));
+        assert_eq!(storage_version(&crd).unwrap().name, "v1");
+        assert!(served_version(&crd, "v1").is_some_and(has_status_subresource));
+        assert!(served_version(&crd, "v1beta1").is_none());
+
+        let status = established_status(&crd);
+        assert_eq!(status.stored_versions, Some(vec!["v1".to_string()]));
+        let conditions = status.conditions.unwrap();
+        assert!(conditions
+            .iter()
+            .any(|c| c.type_ == "Established" && c.status == "True"));
+
+        let mut object: CustomObject = serde_json::from_value(json!({
+            "apiVersion": "example.com/v1",
+            "kind": "Widget",
+            "metadata": {"name": "small", "namespace": "default"},
+            "spec": {"color": "red"}
+        }))
+        .unwrap();
+        validate_custom_object(served_version(&crd, "v1").unwrap(), &mut object).unwrap();
+        assert_eq!(object.fields["spec"], json!({"size": 1}));
+        assert_eq!(object.resource_key().unwrap().gvk.kind, "Widget");
+    }
+}
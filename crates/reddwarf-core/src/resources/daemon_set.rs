@@ -0,0 +1,184 @@
+use super::deployment::{scaled_value, validate_workload_selector, APPS_API_VERSION};
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::apps::v1::{DaemonSet, DaemonSetUpdateStrategy, RollingUpdateDaemonSet};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+
+/// Kind of the DaemonSet resource
+pub const DAEMON_SET_KIND: &str = "DaemonSet";
+
+/// Update strategy replacing the pods of an old template node by node
+pub const DAEMON_SET_ROLLING_UPDATE: &str = "RollingUpdate";
+
+/// Update strategy replacing the pods of an old template only once they are
+/// deleted
+pub const DAEMON_SET_ON_DELETE: &str = "OnDelete";
+
+/// Whether `daemon_set` leaves pods of an old template running until they
+/// are deleted
+pub fn is_on_delete(daemon_set: &DaemonSet) -> bool {
+    daemon_set
+        .spec
+        .as_ref()
+        .and_then(|s| s.update_strategy.as_ref())
+        .and_then(|s| s.type_.as_deref())
+        == Some(DAEMON_SET_ON_DELETE)
+}
+
+/// Nodes of `desired` a rolling update of `daemon_set` may leave without a
+/// ready pod, at least 1
+pub fn daemon_set_max_unavailable(
+    daemon_set: &DaemonSet,
+    desired: i32,
+) -> Result<i32, ResourceError> {
+    let max_unavailable = daemon_set
+        .spec
+        .as_ref()
+        .and_then(|s| s.update_strategy.as_ref())
+        .and_then(|s| s.rolling_update.as_ref())
+        .and_then(|r| r.max_unavailable.as_ref());
+    match max_unavailable {
+        Some(value) => Ok(scaled_value(value, desired, false)?.max(1)),
+        None => Ok(1),
+    }
+}
+
+/// Fill in the update strategy of a new DaemonSet
+pub fn default_daemon_set(daemon_set: &mut DaemonSet) {
+    let Some(spec) = daemon_set.spec.as_mut() else {
+        return;
+    };
+    let strategy = spec
+        .update_strategy
+        .get_or_insert_with(DaemonSetUpdateStrategy::default);
+    let type_ = strategy
+        .type_
+        .get_or_insert_with(|| DAEMON_SET_ROLLING_UPDATE.to_string());
+    if type_ == DAEMON_SET_ROLLING_UPDATE {
+        strategy
+            .rolling_update
+            .get_or_insert_with(RollingUpdateDaemonSet::default)
+            .max_unavailable
+            .get_or_insert(IntOrString::Int(1));
+    }
+}
+
+impl Resource for DaemonSet {
+    fn api_version(&self) -> String {
+        APPS_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        DAEMON_SET_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+        validate_workload_selector(&spec.selector, Some(&spec.template))?;
+
+        let strategy = spec.update_strategy.as_ref();
+        match strategy.and_then(|s| s.type_.as_deref()) {
+            None | Some(DAEMON_SET_ROLLING_UPDATE) => {
+                daemon_set_max_unavailable(self, 1)?;
+            }
+            Some(DAEMON_SET_ON_DELETE) => {
+                if strategy.is_some_and(|s| s.rolling_update.is_some()) {
+                    return Err(ResourceError::ValidationFailed(
+                        "spec.updateStrategy.rollingUpdate may not be set with the OnDelete strategy"
+                            .to_string(),
+                    ));
+                }
+            }
+            Some(other) => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "spec.updateStrategy.type '{}' must be RollingUpdate or OnDelete",
+                    other
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::DaemonSetSpec;
+    use k8s_openapi::api::core::v1::{Container, PodSpec, PodTemplateSpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use std::collections::BTreeMap;
+
+    fn daemon_set() -> DaemonSet {
+        let labels = BTreeMap::from([("app".to_string(), "logs".to_string())]);
+        DaemonSet {
+            metadata: ObjectMeta {
+                name: Some("logs".to_string()),
+                ..Default::default()
+            },
+            spec: Some(DaemonSetSpec {
+                selector: LabelSelector {
+                    match_labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        containers: vec![Container {
+                            name: "collector".to_string(),
+                            image: Some("fluent-bit".to_string()),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_daemon_set_update_strategy() {
+        let mut ds = daemon_set();
+        assert!(ds.validate().is_ok());
+        default_daemon_set(&mut ds);
+        assert!(!is_on_delete(&ds));
+        assert_eq!(daemon_set_max_unavailable(&ds, 10).unwrap(), 1);
+
+        let strategy = ds.spec.as_mut().unwrap().update_strategy.as_mut().unwrap();
+        strategy.rolling_update = Some(RollingUpdateDaemonSet {
+            max_unavailable: Some(IntOrString::String("30%".to_string())),
+            ..Default::default()
+        });
+        assert_eq!(daemon_set_max_unavailable(&ds, 10).unwrap(), 3);
+        assert_eq!(daemon_set_max_unavailable(&ds, 2).unwrap(), 1);
+
+        // rollingUpdate only goes with the RollingUpdate strategy
+        let strategy = ds.spec.as_mut().unwrap().update_strategy.as_mut().unwrap();
+        strategy.type_ = Some(DAEMON_SET_ON_DELETE.to_string());
+        assert!(ds.validate().is_err());
+        let strategy = ds.spec.as_mut().unwrap().update_strategy.as_mut().unwrap();
+        strategy.rolling_update = None;
+        assert!(ds.validate().is_ok());
+        assert!(is_on_delete(&ds));
+
+        let mut ds = daemon_set();
+        ds.spec.as_mut().unwrap().template.spec = Some(PodSpec::default());
+        assert!(ds.validate().is_err());
+    }
+}
@@ -0,0 +1,365 @@
+use super::selector::{selector_is_empty, selector_matches, validate_selector};
+use super::{validate_base, Resource, ResourceError};
+use crate::annotations::validate_annotations;
+use k8s_openapi::api::apps::v1::{
+    Deployment, DeploymentStrategy, ReplicaSet, RollingUpdateDeployment,
+};
+use k8s_openapi::api::core::v1::PodTemplateSpec;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+
+/// API group/version of Deployments and ReplicaSets
+pub const APPS_API_VERSION: &str = "apps/v1";
+
+/// Kind of the Deployment resource
+pub const DEPLOYMENT_KIND: &str = "Deployment";
+
+/// Kind of the ReplicaSet resource
+pub const REPLICA_SET_KIND: &str = "ReplicaSet";
+
+/// Label carrying the hash of the pod template a ReplicaSet and its pods
+/// were created from
+pub const POD_TEMPLATE_HASH_LABEL: &str = "pod-template-hash";
+
+/// Annotation numbering the rollouts of a Deployment, set on the ReplicaSet
+/// of each rollout
+pub const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+
+/// Deployment annotation asking to roll back to the given revision, or to
+/// the one before the current revision when `"0"`
+pub const ROLLBACK_ANNOTATION: &str = "reddwarf.io/rollback-to-revision";
+
+/// Old ReplicaSets kept for rollback when a Deployment does not say
+pub const DEFAULT_REVISION_HISTORY_LIMIT: i32 = 10;
+
+/// Surge and unavailability of a rolling update when a Deployment does not say
+pub const DEFAULT_ROLLING_UPDATE_PERCENT: &str = "25%";
+
+/// Stable hash of `template`, naming the ReplicaSet of one rollout
+///
+/// FNV-1a over the template's JSON, which serializes maps in key order, so
+/// the hash survives restarts and upgrades.
+pub fn pod_template_hash(template: &PodTemplateSpec) -> String {
+    let json = serde_json::to_vec(template).unwrap_or_default();
+    let hash = json.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:010x}", hash & 0xff_ffff_ffff)
+}
+
+/// Revision recorded in `metadata`, 0 when it has none
+pub fn revision(metadata: &ObjectMeta) -> i64 {
+    metadata
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(REVISION_ANNOTATION))
+        .and_then(|r| r.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Desired replicas of `deployment`, 1 when unset
+pub fn deployment_replicas(deployment: &Deployment) -> i32 {
+    deployment
+        .spec
+        .as_ref()
+        .and_then(|s| s.replicas)
+        .unwrap_or(1)
+}
+
+/// Whether `deployment` replaces all pods at once instead of rolling
+pub fn is_recreate(deployment: &Deployment) -> bool {
+    deployment
+        .spec
+        .as_ref()
+        .and_then(|s| s.strategy.as_ref())
+        .and_then(|s| s.type_.as_deref())
+        == Some("Recreate")
+}
+
+/// An absolute number or percentage of `total`, rounded up or down
+pub fn scaled_value(value: &IntOrString, total: i32, round_up: bool) -> Result<i32, ResourceError> {
+    match value {
+        IntOrString::Int(n) if *n >= 0 => Ok(*n),
+        IntOrString::String(s) => {
+            let percent: i64 = s
+                .strip_suffix('%')
+                .and_then(|p| p.parse().ok())
+                .filter(|p| *p >= 0)
+                .ok_or_else(|| {
+                    ResourceError::ValidationFailed(format!(
+                        "'{}' must be a non-negative integer or percentage",
+                        s
+                    ))
+                })?;
+            let scaled = percent * i64::from(total);
+            let value = if round_up {
+                (scaled + 99) / 100
+            } else {
+                scaled / 100
+            };
+            Ok(value as i32)
+        }
+        IntOrString::Int(n) => Err(ResourceError::ValidationFailed(format!(
+            "{} must not be negative",
+            n
+        ))),
+    }
+}
+
+/// Pods a rolling update of `deployment` may add above, and take away
+/// below, its desired replicas, as `(max_surge, max_unavailable)`
+///
+/// Surge rounds up and unavailability down; when both come out as zero one
+/// pod may be unavailable, so the rollout can make progress.
+pub fn rolling_update_limits(deployment: &Deployment) -> Result<(i32, i32), ResourceError> {
+    let replicas = deployment_replicas(deployment);
+    let default = IntOrString::String(DEFAULT_ROLLING_UPDATE_PERCENT.to_string());
+    let rolling = deployment
+        .spec
+        .as_ref()
+        .and_then(|s| s.strategy.as_ref())
+        .and_then(|s| s.rolling_update.as_ref());
+    let surge = rolling
+        .and_then(|r| r.max_surge.as_ref())
+        .unwrap_or(&default);
+    let unavailable = rolling
+        .and_then(|r| r.max_unavailable.as_ref())
+        .unwrap_or(&default);
+
+    let surge = scaled_value(surge, replicas, true)?;
+    let unavailable = scaled_value(unavailable, replicas, false)?.min(replicas);
+    if surge == 0 && unavailable == 0 {
+        return Ok((0, 1));
+    }
+    Ok((surge, unavailable))
+}
+
+/// Fill in the replicas, strategy and history limit of a new Deployment
+pub fn default_deployment(deployment: &mut Deployment) {
+    let Some(spec) = deployment.spec.as_mut() else {
+        return;
+    };
+    spec.replicas.get_or_insert(1);
+    spec.revision_history_limit
+        .get_or_insert(DEFAULT_REVISION_HISTORY_LIMIT);
+    let strategy = spec
+        .strategy
+        .get_or_insert_with(DeploymentStrategy::default);
+    let type_ = strategy
+        .type_
+        .get_or_insert_with(|| "RollingUpdate".to_string());
+    if type_ == "RollingUpdate" {
+        let rolling = strategy
+            .rolling_update
+            .get_or_insert_with(RollingUpdateDeployment::default);
+        let default = IntOrString::String(DEFAULT_ROLLING_UPDATE_PERCENT.to_string());
+        rolling.max_surge.get_or_insert_with(|| default.clone());
+        rolling.max_unavailable.get_or_insert(default);
+    }
+}
+
+/// Check that `selector` selects the pods of `template`
+pub(super) fn validate_workload_selector(
+    selector: &LabelSelector,
+    template: Option<&PodTemplateSpec>,
+) -> Result<(), ResourceError> {
+    if selector_is_empty(selector) {
+        return Err(ResourceError::ValidationFailed(
+            "spec.selector must not be empty".to_string(),
+        ));
+    }
+    validate_selector(selector)?;
+    if let Some(template) = template {
+        if !selector_matches(
+            selector,
+            template.metadata.as_ref().and_then(|m| m.labels.as_ref()),
+        ) {
+            return Err(ResourceError::ValidationFailed(
+                "spec.selector does not match the labels of spec.template".to_string(),
+            ));
+        }
+        if template
+            .spec
+            .as_ref()
+            .is_none_or(|s| s.containers.is_empty())
+        {
+            return Err(ResourceError::ValidationFailed(
+                "spec.template must have at least one container".to_string(),
+            ));
+        }
+        validate_annotations(
+            template
+                .metadata
+                .as_ref()
+                .and_then(|m| m.annotations.as_ref()),
+        )?;
+    }
+    Ok(())
+}
+
+fn validate_replicas(replicas: Option<i32>) -> Result<(), ResourceError> {
+    match replicas {
+        Some(n) if n < 0 => Err(ResourceError::ValidationFailed(format!(
+            "spec.replicas {} must not be negative",
+            n
+        ))),
+        _ => Ok(()),
+    }
+}
+
+impl Resource for Deployment {
+    fn api_version(&self) -> String {
+        APPS_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        DEPLOYMENT_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+        validate_replicas(spec.replicas)?;
+        validate_workload_selector(&spec.selector, Some(&spec.template))?;
+
+        let strategy = spec.strategy.as_ref();
+        match strategy.and_then(|s| s.type_.as_deref()) {
+            None | Some("RollingUpdate") => {
+                rolling_update_limits(self)?;
+            }
+            Some("Recreate") => {
+                if strategy.is_some_and(|s| s.rolling_update.is_some()) {
+                    return Err(ResourceError::ValidationFailed(
+                        "spec.strategy.rollingUpdate may not be set with the Recreate strategy"
+                            .to_string(),
+                    ));
+                }
+            }
+            Some(other) => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "spec.strategy.type '{}' must be RollingUpdate or Recreate",
+                    other
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Resource for ReplicaSet {
+    fn api_version(&self) -> String {
+        APPS_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        REPLICA_SET_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+        validate_replicas(spec.replicas)?;
+        validate_workload_selector(&spec.selector, spec.template.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::DeploymentSpec;
+    use k8s_openapi::api::core::v1::{Container, PodSpec};
+    use std::collections::BTreeMap;
+
+    fn deployment(replicas: i32) -> Deployment {
+        let labels = BTreeMap::from([("app".to_string(), "web".to_string())]);
+        Deployment {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(replicas),
+                selector: LabelSelector {
+                    match_labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        containers: vec![Container {
+                            name: "web".to_string(),
+                            image: Some("nginx".to_string()),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deployment_rollout_settings() {
+        let mut d = deployment(10);
+        assert!(d.validate().is_ok());
+        default_deployment(&mut d);
+        assert_eq!(rolling_update_limits(&d).unwrap(), (3, 2));
+        assert!(!is_recreate(&d));
+
+        // Zero surge and unavailability still lets one pod go at a time
+        let rolling = d.spec.as_mut().unwrap().strategy.as_mut().unwrap();
+        rolling.rolling_update = Some(RollingUpdateDeployment {
+            max_surge: Some(IntOrString::Int(0)),
+            max_unavailable: Some(IntOrString::String("5%".to_string())),
+        });
+        assert_eq!(rolling_update_limits(&d).unwrap(), (0, 1));
+
+        let template = d.spec.as_ref().unwrap().template.clone();
+        let hash = pod_template_hash(&template);
+        assert_eq!(hash.len(), 10);
+        assert_eq!(pod_template_hash(&template), hash);
+        let mut changed = template;
+        changed.spec.as_mut().unwrap().containers[0].image = Some("nginx:2".to_string());
+        assert_ne!(pod_template_hash(&changed), hash);
+
+        // The selector must pick the template's pods
+        let mut d = deployment(1);
+        d.spec.as_mut().unwrap().selector.match_labels =
+            Some(BTreeMap::from([("app".to_string(), "api".to_string())]));
+        assert!(d.validate().is_err());
+        let mut d = deployment(1);
+        d.spec.as_mut().unwrap().strategy = Some(DeploymentStrategy {
+            type_: Some("BlueGreen".to_string()),
+            rolling_update: None,
+        });
+        assert!(d.validate().is_err());
+        assert!(scaled_value(&IntOrString::String("x".to_string()), 1, true).is_err());
+    }
+}
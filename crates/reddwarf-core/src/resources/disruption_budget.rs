@@ -0,0 +1,268 @@
+use super::deployment::scaled_value;
+use super::selector::{selector_matches, validate_selector};
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::policy::v1::{PodDisruptionBudget, PodDisruptionBudgetStatus};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+
+/// API group/version of PodDisruptionBudgets and Evictions
+pub const POLICY_API_VERSION: &str = "policy/v1";
+
+/// Kind of the PodDisruptionBudget resource
+pub const POD_DISRUPTION_BUDGET_KIND: &str = "PodDisruptionBudget";
+
+/// Unhealthy pod eviction policy letting pods that are not ready be evicted
+/// whether or not the budget is met
+pub const ALWAYS_ALLOW: &str = "AlwaysAllow";
+
+/// Unhealthy pod eviction policy letting pods that are not ready be evicted
+/// only while the budget is met, the default
+pub const IF_HEALTHY_BUDGET: &str = "IfHealthyBudget";
+
+/// Whether `budget` covers `pod`: in the same namespace and matched by its
+/// selector. A budget without a selector covers no pods, one with an empty
+/// selector every pod of its namespace.
+pub fn budget_selects(budget: &PodDisruptionBudget, pod: &Pod) -> bool {
+    let Some(selector) = budget.spec.as_ref().and_then(|s| s.selector.as_ref()) else {
+        return false;
+    };
+    budget.metadata.namespace == pod.metadata.namespace
+        && selector_matches(selector, pod.metadata.labels.as_ref())
+}
+
+/// Whether `pod` counts towards a budget: neither terminating nor finished
+fn is_expected(pod: &Pod) -> bool {
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    pod.metadata.deletion_timestamp.is_none() && !matches!(phase, Some("Succeeded" | "Failed"))
+}
+
+/// Whether `pod` reports its `Ready` condition as `True`
+pub fn is_healthy(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .any(|c| c.type_ == "Ready" && c.status == "True")
+}
+
+/// Status of `budget` over the `pods` it selects: how many are expected
+/// and healthy, how many must stay healthy, and how many may be disrupted
+pub fn disruption_budget_status(
+    budget: &PodDisruptionBudget,
+    pods: &[&Pod],
+) -> Result<PodDisruptionBudgetStatus, ResourceError> {
+    let expected: Vec<&Pod> = pods.iter().copied().filter(|p| is_expected(p)).collect();
+    let expected_pods = expected.len() as i32;
+    let current_healthy = expected.iter().filter(|p| is_healthy(p)).count() as i32;
+
+    let spec = budget.spec.as_ref();
+    let desired_healthy = match (
+        spec.and_then(|s| s.min_available.as_ref()),
+        spec.and_then(|s| s.max_unavailable.as_ref()),
+    ) {
+        (Some(min_available), _) => scaled_value(min_available, expected_pods, true)?,
+        (None, Some(max_unavailable)) => {
+            (expected_pods - scaled_value(max_unavailable, expected_pods, true)?).max(0)
+        }
+        (None, None) => 0,
+    };
+
+    Ok(PodDisruptionBudgetStatus {
+        current_healthy,
+        desired_healthy,
+        disruptions_allowed: (current_healthy - desired_healthy).max(0),
+        expected_pods,
+        observed_generation: budget.metadata.generation,
+        ..Default::default()
+    })
+}
+
+/// Whether `budget`, in `status`, lets a pod that is `healthy` or not be
+/// evicted
+pub fn eviction_allowed(
+    budget: &PodDisruptionBudget,
+    status: &PodDisruptionBudgetStatus,
+    healthy: bool,
+) -> bool {
+    if healthy {
+        return status.disruptions_allowed > 0;
+    }
+    let policy = budget
+        .spec
+        .as_ref()
+        .and_then(|s| s.unhealthy_pod_eviction_policy.as_deref());
+    policy == Some(ALWAYS_ALLOW) || status.current_healthy >= status.desired_healthy
+}
+
+fn validate_budget_value(field: &str, value: &IntOrString) -> Result<(), ResourceError> {
+    let scaled = scaled_value(value, 100, true)
+        .map_err(|e| ResourceError::ValidationFailed(format!("spec.{}: {}", field, e)))?;
+    if matches!(value, IntOrString::String(_)) && scaled > 100 {
+        return Err(ResourceError::ValidationFailed(format!(
+            "spec.{} must not be more than 100%",
+            field
+        )));
+    }
+    Ok(())
+}
+
+impl Resource for PodDisruptionBudget {
+    fn api_version(&self) -> String {
+        POLICY_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        POD_DISRUPTION_BUDGET_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+        match (&spec.min_available, &spec.max_unavailable) {
+            (Some(_), Some(_)) => {
+                return Err(ResourceError::ValidationFailed(
+                    "spec.minAvailable and spec.maxUnavailable may not both be set".to_string(),
+                ))
+            }
+            (Some(min_available), None) => validate_budget_value("minAvailable", min_available)?,
+            (None, Some(max_unavailable)) => {
+                validate_budget_value("maxUnavailable", max_unavailable)?
+            }
+            (None, None) => {
+                return Err(ResourceError::MissingField(
+                    "spec.minAvailable or spec.maxUnavailable".to_string(),
+                ))
+            }
+        }
+        if let Some(selector) = &spec.selector {
+            validate_selector(selector)?;
+        }
+        match spec.unhealthy_pod_eviction_policy.as_deref() {
+            None | Some(ALWAYS_ALLOW | IF_HEALTHY_BUDGET) => Ok(()),
+            Some(other) => Err(ResourceError::ValidationFailed(format!(
+                "spec.unhealthyPodEvictionPolicy '{}' must be IfHealthyBudget or AlwaysAllow",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodCondition, PodStatus};
+    use k8s_openapi::api::policy::v1::PodDisruptionBudgetSpec;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, Time};
+    use std::collections::BTreeMap;
+
+    fn budget(
+        min_available: Option<IntOrString>,
+        max_unavailable: Option<IntOrString>,
+    ) -> PodDisruptionBudget {
+        PodDisruptionBudget {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                namespace: Some("default".to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodDisruptionBudgetSpec {
+                min_available,
+                max_unavailable,
+                selector: Some(LabelSelector {
+                    match_labels: Some(BTreeMap::from([("app".to_string(), "web".to_string())])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            status: None,
+        }
+    }
+
+    fn pod(app: &str, ready: bool) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                namespace: Some("default".to_string()),
+                labels: Some(BTreeMap::from([("app".to_string(), app.to_string())])),
+                ..Default::default()
+            },
+            spec: None,
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: if ready { "True" } else { "False" }.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(budget(Some(IntOrString::Int(2)), None).validate().is_ok());
+        assert!(budget(None, Some(IntOrString::String("25%".to_string())))
+            .validate()
+            .is_ok());
+        assert!(budget(None, None).validate().is_err());
+        assert!(budget(Some(IntOrString::Int(1)), Some(IntOrString::Int(1)))
+            .validate()
+            .is_err());
+        assert!(budget(Some(IntOrString::String("150%".to_string())), None)
+            .validate()
+            .is_err());
+        assert!(budget(Some(IntOrString::Int(-1)), None).validate().is_err());
+    }
+
+    #[test]
+    fn test_status_and_eviction() {
+        let ready = [pod("web", true), pod("web", true), pod("web", true)];
+        let unready = pod("web", false);
+        let mut terminating = pod("web", true);
+        terminating.metadata.deletion_timestamp = Some(Time(chrono::Utc::now()));
+
+        let min_two = budget(Some(IntOrString::Int(2)), None);
+        assert!(budget_selects(&min_two, &ready[0]));
+        assert!(!budget_selects(&min_two, &pod("api", true)));
+
+        let pods = [&ready[0], &ready[1], &ready[2], &unready, &terminating];
+        let status = disruption_budget_status(&min_two, &pods).unwrap();
+        assert_eq!(status.expected_pods, 4);
+        assert_eq!(status.current_healthy, 3);
+        assert_eq!(status.desired_healthy, 2);
+        assert_eq!(status.disruptions_allowed, 1);
+        assert!(eviction_allowed(&min_two, &status, true));
+        assert!(eviction_allowed(&min_two, &status, false));
+
+        // Once only two are healthy neither a healthy nor, without
+        // AlwaysAllow, an unhealthy pod may go below the budget
+        let pods = [&ready[0], &ready[1], &unready, &unready];
+        let max_half = budget(None, Some(IntOrString::String("50%".to_string())));
+        let status = disruption_budget_status(&max_half, &pods).unwrap();
+        assert_eq!(status.desired_healthy, 2);
+        assert_eq!(status.disruptions_allowed, 0);
+        assert!(!eviction_allowed(&max_half, &status, true));
+        assert!(eviction_allowed(&max_half, &status, false));
+
+        let pods = [&ready[0], &unready, &unready];
+        let status = disruption_budget_status(&min_two, &pods).unwrap();
+        assert!(!eviction_allowed(&min_two, &status, false));
+        let mut always = min_two.clone();
+        always.spec.as_mut().unwrap().unhealthy_pod_eviction_policy =
+            Some(ALWAYS_ALLOW.to_string());
+        assert!(eviction_allowed(&always, &status, false));
+    }
+}
@@ -0,0 +1,193 @@
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::core::v1::Endpoints;
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Kind of the Endpoints resource
+pub const ENDPOINTS_KIND: &str = "Endpoints";
+
+/// API version of the EndpointSlice resource
+pub const DISCOVERY_API_VERSION: &str = "discovery.k8s.io/v1";
+
+/// Kind of the EndpointSlice resource
+pub const ENDPOINT_SLICE_KIND: &str = "EndpointSlice";
+
+/// Label naming the Service an EndpointSlice belongs to
+pub const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// Label naming the controller that maintains an EndpointSlice
+pub const ENDPOINT_SLICE_MANAGED_BY_LABEL: &str = "endpointslice.kubernetes.io/managed-by";
+
+/// Value of [`ENDPOINT_SLICE_MANAGED_BY_LABEL`] on slices reddwarf maintains
+pub const ENDPOINT_SLICE_CONTROLLER: &str = "endpointslice-controller.reddwarf.io";
+
+/// Address types an EndpointSlice may declare
+pub const ADDRESS_TYPES: &[&str] = &["IPv4", "IPv6", "FQDN"];
+
+/// Whether the slice is maintained by reddwarf on behalf of `service`
+pub fn is_slice_of(slice: &EndpointSlice, service: &str) -> bool {
+    let labels = slice.metadata.labels.as_ref();
+    labels
+        .and_then(|l| l.get(ENDPOINT_SLICE_MANAGED_BY_LABEL))
+        .is_some_and(|value| value == ENDPOINT_SLICE_CONTROLLER)
+        && labels
+            .and_then(|l| l.get(SERVICE_NAME_LABEL))
+            .is_some_and(|value| value == service)
+}
+
+fn validate_port(port: i32, field: &str) -> Result<(), ResourceError> {
+    if !(1..=65535).contains(&port) {
+        return Err(ResourceError::ValidationFailed(format!(
+            "{} {} must be between 1 and 65535",
+            field, port
+        )));
+    }
+    Ok(())
+}
+
+impl Resource for Endpoints {
+    fn api_version(&self) -> String {
+        "v1".to_string()
+    }
+
+    fn kind(&self) -> String {
+        ENDPOINTS_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        for subset in self.subsets.iter().flatten() {
+            let addresses = subset.addresses.iter().flatten();
+            for address in addresses.chain(subset.not_ready_addresses.iter().flatten()) {
+                if address.ip.parse::<IpAddr>().is_err() {
+                    return Err(ResourceError::ValidationFailed(format!(
+                        "Endpoints address '{}' must be an IP address",
+                        address.ip
+                    )));
+                }
+            }
+            for port in subset.ports.iter().flatten() {
+                validate_port(port.port, "Endpoints port")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Resource for EndpointSlice {
+    fn api_version(&self) -> String {
+        DISCOVERY_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        ENDPOINT_SLICE_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        if !ADDRESS_TYPES.contains(&self.address_type.as_str()) {
+            return Err(ResourceError::ValidationFailed(format!(
+                "EndpointSlice addressType '{}' must be one of {}",
+                self.address_type,
+                ADDRESS_TYPES.join(", ")
+            )));
+        }
+        for endpoint in &self.endpoints {
+            if endpoint.addresses.is_empty() {
+                return Err(ResourceError::ValidationFailed(
+                    "EndpointSlice endpoint must have at least one address".to_string(),
+                ));
+            }
+            for address in &endpoint.addresses {
+                let valid = match self.address_type.as_str() {
+                    "IPv4" => address.parse::<Ipv4Addr>().is_ok(),
+                    "IPv6" => address.parse::<Ipv6Addr>().is_ok(),
+                    _ => !address.is_empty(),
+                };
+                if !valid {
+                    return Err(ResourceError::ValidationFailed(format!(
+                        "EndpointSlice address '{}' is not a valid {} address",
+                        address, self.address_type
+                    )));
+                }
+            }
+        }
+        for port in self.ports.iter().flatten() {
+            if let Some(port) = port.port {
+                validate_port(port, "EndpointSlice port")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{EndpointAddress, EndpointSubset};
+    use k8s_openapi::api::discovery::v1::Endpoint;
+
+    #[test]
+    fn test_endpoints_addresses_must_be_ips() {
+        let mut endpoints = Endpoints {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                ..Default::default()
+            },
+            subsets: Some(vec![EndpointSubset {
+                addresses: Some(vec![EndpointAddress {
+                    ip: "10.88.0.2".to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }]),
+        };
+        assert!(endpoints.validate().is_ok());
+
+        endpoints.subsets.as_mut().unwrap()[0]
+            .addresses
+            .as_mut()
+            .unwrap()[0]
+            .ip = "web-0".to_string();
+        assert!(endpoints.validate().is_err());
+    }
+
+    #[test]
+    fn test_endpoint_slice_address_type() {
+        let mut slice = EndpointSlice {
+            metadata: ObjectMeta {
+                name: Some("web-abc".to_string()),
+                ..Default::default()
+            },
+            address_type: "IPv4".to_string(),
+            endpoints: vec![Endpoint {
+                addresses: vec!["10.88.0.2".to_string()],
+                ..Default::default()
+            }],
+            ports: None,
+        };
+        assert!(slice.validate().is_ok());
+
+        slice.address_type = "IPv6".to_string();
+        assert!(slice.validate().is_err());
+        slice.address_type = "IPX".to_string();
+        assert!(slice.validate().is_err());
+    }
+}
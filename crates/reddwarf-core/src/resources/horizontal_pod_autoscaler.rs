@@ -0,0 +1,301 @@
+use super::deployment::{APPS_API_VERSION, DEPLOYMENT_KIND, REPLICA_SET_KIND};
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::autoscaling::v2::{
+    HorizontalPodAutoscaler, MetricSpec, MetricTarget, ResourceMetricSource,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+/// API group/version of HorizontalPodAutoscalers
+pub const AUTOSCALING_API_VERSION: &str = "autoscaling/v2";
+
+/// Kind of the HorizontalPodAutoscaler resource
+pub const HORIZONTAL_POD_AUTOSCALER_KIND: &str = "HorizontalPodAutoscaler";
+
+/// CPU utilization, in percent of the requests, an autoscaler without
+/// metrics aims for
+pub const DEFAULT_CPU_UTILIZATION: i32 = 80;
+
+/// Seconds of recommendations a scale-down waits out when the autoscaler
+/// does not say, so a brief dip does not remove pods
+pub const DEFAULT_SCALE_DOWN_STABILIZATION_SECONDS: i32 = 300;
+
+/// Deviation of the utilization from its target, as a fraction of the
+/// target, that does not change the replicas
+pub const AUTOSCALING_TOLERANCE: f64 = 0.1;
+
+/// Resources the utilization of pods is known for
+const SCALABLE_RESOURCES: &[&str] = &["cpu", "memory"];
+
+/// Fill in the replica floor and, without metrics, the CPU target of a new
+/// autoscaler
+pub fn default_horizontal_pod_autoscaler(autoscaler: &mut HorizontalPodAutoscaler) {
+    let Some(spec) = autoscaler.spec.as_mut() else {
+        return;
+    };
+    spec.min_replicas.get_or_insert(1);
+    let metrics = spec.metrics.get_or_insert_with(Vec::new);
+    if metrics.is_empty() {
+        metrics.push(MetricSpec {
+            type_: "Resource".to_string(),
+            resource: Some(ResourceMetricSource {
+                name: "cpu".to_string(),
+                target: MetricTarget {
+                    type_: "Utilization".to_string(),
+                    average_utilization: Some(DEFAULT_CPU_UTILIZATION),
+                    ..Default::default()
+                },
+            }),
+            ..Default::default()
+        });
+    }
+}
+
+/// Fewest and most replicas `autoscaler` scales its target between
+pub fn autoscaler_bounds(autoscaler: &HorizontalPodAutoscaler) -> (i32, i32) {
+    let spec = autoscaler.spec.as_ref();
+    let min = spec.and_then(|s| s.min_replicas).unwrap_or(1);
+    let max = spec.map(|s| s.max_replicas).unwrap_or(min);
+    (min, max.max(min))
+}
+
+/// Target utilization, in percent, of each resource `autoscaler` scales on
+pub fn utilization_targets(autoscaler: &HorizontalPodAutoscaler) -> Vec<(String, i32)> {
+    autoscaler
+        .spec
+        .as_ref()
+        .and_then(|s| s.metrics.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|metric| metric.resource.as_ref())
+        .filter_map(|resource| {
+            let target = resource.target.average_utilization?;
+            Some((resource.name.clone(), target))
+        })
+        .collect()
+}
+
+/// Seconds of recommendations a scale-up and a scale-down of `autoscaler`
+/// are stabilized over
+pub fn stabilization_windows(autoscaler: &HorizontalPodAutoscaler) -> (i32, i32) {
+    let behavior = autoscaler.spec.as_ref().and_then(|s| s.behavior.as_ref());
+    let up = behavior
+        .and_then(|b| b.scale_up.as_ref())
+        .and_then(|r| r.stabilization_window_seconds)
+        .unwrap_or(0);
+    let down = behavior
+        .and_then(|b| b.scale_down.as_ref())
+        .and_then(|r| r.stabilization_window_seconds)
+        .unwrap_or(DEFAULT_SCALE_DOWN_STABILIZATION_SECONDS);
+    (up, down)
+}
+
+/// Replicas bringing `utilization` of `replicas` pods, in percent, to
+/// `target`; unchanged within [`AUTOSCALING_TOLERANCE`] of the target
+pub fn replicas_for_utilization(replicas: i32, utilization: f64, target: i32) -> i32 {
+    let ratio = utilization / f64::from(target.max(1));
+    if (ratio - 1.0).abs() <= AUTOSCALING_TOLERANCE {
+        return replicas;
+    }
+    (ratio * f64::from(replicas)).ceil() as i32
+}
+
+fn validate_metric(metric: &MetricSpec) -> Result<(), ResourceError> {
+    let resource = match (metric.type_.as_str(), &metric.resource) {
+        ("Resource", Some(resource)) => resource,
+        ("Resource", None) => {
+            return Err(ResourceError::MissingField(
+                "spec.metrics.resource".to_string(),
+            ))
+        }
+        (other, _) => {
+            return Err(ResourceError::ValidationFailed(format!(
+                "spec.metrics.type '{}' is not supported, only Resource",
+                other
+            )))
+        }
+    };
+    if !SCALABLE_RESOURCES.contains(&resource.name.as_str()) {
+        return Err(ResourceError::ValidationFailed(format!(
+            "spec.metrics.resource.name '{}' must be cpu or memory",
+            resource.name
+        )));
+    }
+    match (
+        resource.target.type_.as_str(),
+        resource.target.average_utilization,
+    ) {
+        ("Utilization", Some(utilization)) if utilization > 0 => Ok(()),
+        ("Utilization", _) => Err(ResourceError::ValidationFailed(
+            "spec.metrics.resource.target.averageUtilization must be positive".to_string(),
+        )),
+        (other, _) => Err(ResourceError::ValidationFailed(format!(
+            "spec.metrics.resource.target.type '{}' is not supported, only Utilization",
+            other
+        ))),
+    }
+}
+
+impl Resource for HorizontalPodAutoscaler {
+    fn api_version(&self) -> String {
+        AUTOSCALING_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        HORIZONTAL_POD_AUTOSCALER_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+
+        let target = &spec.scale_target_ref;
+        if target.name.is_empty() {
+            return Err(ResourceError::MissingField(
+                "spec.scaleTargetRef.name".to_string(),
+            ));
+        }
+        let api_version = target.api_version.as_deref().unwrap_or(APPS_API_VERSION);
+        if api_version != APPS_API_VERSION
+            || !matches!(target.kind.as_str(), DEPLOYMENT_KIND | REPLICA_SET_KIND)
+        {
+            return Err(ResourceError::ValidationFailed(format!(
+                "spec.scaleTargetRef {}/{} must be an apps/v1 Deployment or ReplicaSet",
+                api_version, target.kind
+            )));
+        }
+
+        if spec.max_replicas < 1 {
+            return Err(ResourceError::ValidationFailed(format!(
+                "spec.maxReplicas {} must be at least 1",
+                spec.max_replicas
+            )));
+        }
+        if let Some(min) = spec.min_replicas {
+            if min < 1 || min > spec.max_replicas {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "spec.minReplicas {} must be between 1 and spec.maxReplicas {}",
+                    min, spec.max_replicas
+                )));
+            }
+        }
+
+        for metric in spec.metrics.iter().flatten() {
+            validate_metric(metric)?;
+        }
+        let behavior = spec.behavior.as_ref();
+        for rules in [
+            behavior.and_then(|b| b.scale_up.as_ref()),
+            behavior.and_then(|b| b.scale_down.as_ref()),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(window) = rules.stabilization_window_seconds {
+                if !(0..=3600).contains(&window) {
+                    return Err(ResourceError::ValidationFailed(format!(
+                        "spec.behavior stabilizationWindowSeconds {} must be between 0 and 3600",
+                        window
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::autoscaling::v2::{
+        CrossVersionObjectReference, HPAScalingRules, HorizontalPodAutoscalerBehavior,
+        HorizontalPodAutoscalerSpec,
+    };
+
+    fn autoscaler(min: Option<i32>, max: i32) -> HorizontalPodAutoscaler {
+        HorizontalPodAutoscaler {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                ..Default::default()
+            },
+            spec: Some(HorizontalPodAutoscalerSpec {
+                scale_target_ref: CrossVersionObjectReference {
+                    api_version: Some(APPS_API_VERSION.to_string()),
+                    kind: DEPLOYMENT_KIND.to_string(),
+                    name: "web".to_string(),
+                },
+                min_replicas: min,
+                max_replicas: max,
+                ..Default::default()
+            }),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_and_default() {
+        let mut hpa = autoscaler(None, 5);
+        assert!(hpa.validate().is_ok());
+        default_horizontal_pod_autoscaler(&mut hpa);
+        assert_eq!(autoscaler_bounds(&hpa), (1, 5));
+        assert_eq!(
+            utilization_targets(&hpa),
+            vec![("cpu".to_string(), DEFAULT_CPU_UTILIZATION)]
+        );
+        assert_eq!(
+            stabilization_windows(&hpa),
+            (0, DEFAULT_SCALE_DOWN_STABILIZATION_SECONDS)
+        );
+        assert!(hpa.validate().is_ok());
+
+        assert!(autoscaler(Some(6), 5).validate().is_err());
+        assert!(autoscaler(Some(0), 5).validate().is_err());
+        assert!(autoscaler(None, 0).validate().is_err());
+
+        let mut hpa = autoscaler(None, 5);
+        hpa.spec.as_mut().unwrap().scale_target_ref.kind = "StatefulSet".to_string();
+        assert!(hpa.validate().is_err());
+
+        let mut hpa = autoscaler(None, 5);
+        default_horizontal_pod_autoscaler(&mut hpa);
+        let spec = hpa.spec.as_mut().unwrap();
+        spec.metrics.as_mut().unwrap()[0]
+            .resource
+            .as_mut()
+            .unwrap()
+            .name = "gpu".to_string();
+        assert!(hpa.validate().is_err());
+
+        let mut hpa = autoscaler(None, 5);
+        hpa.spec.as_mut().unwrap().behavior = Some(HorizontalPodAutoscalerBehavior {
+            scale_down: Some(HPAScalingRules {
+                stabilization_window_seconds: Some(60),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(stabilization_windows(&hpa), (0, 60));
+    }
+
+    #[test]
+    fn test_replicas_for_utilization() {
+        // Within tolerance of the target nothing changes
+        assert_eq!(replicas_for_utilization(4, 85.0, 80), 4);
+        // 4 pods at 160% of 80% need 8
+        assert_eq!(replicas_for_utilization(4, 160.0, 80), 8);
+        // 4 pods at 20% of 80% need 1
+        assert_eq!(replicas_for_utilization(4, 20.0, 80), 1);
+        // Rounded up, so the target is not exceeded
+        assert_eq!(replicas_for_utilization(3, 100.0, 80), 4);
+    }
+}
@@ -0,0 +1,280 @@
+use super::{validate_base, Resource, ResourceError};
+use crate::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// API group/version of image mappings
+pub const IMAGE_MAPPING_API_VERSION: &str = "images.reddwarf.io/v1alpha1";
+
+/// Kind of the image mapping resource
+pub const IMAGE_MAPPING_KIND: &str = "ImageMapping";
+
+/// Tag of image references that name none
+pub const DEFAULT_IMAGE_TAG: &str = "latest";
+
+/// Translates the tags of one image repository (as named in
+/// `containers[].image`) to the lx images nodes install zones from, so pod
+/// specs stay portable while the cluster decides how images materialize
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMapping {
+    #[serde(default = "image_mapping_api_version")]
+    pub api_version: String,
+    #[serde(default = "image_mapping_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub metadata: ObjectMeta,
+    pub spec: ImageMappingSpec,
+}
+
+/// Desired state of an [`ImageMapping`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMappingSpec {
+    /// Repository as written in image references, e.g. `alpine` or
+    /// `registry.example.com/team/app`
+    pub repository: String,
+    /// lx image (path of a Linux rootfs archive) by tag
+    pub tags: BTreeMap<String, String>,
+}
+
+fn image_mapping_api_version() -> String {
+    IMAGE_MAPPING_API_VERSION.to_string()
+}
+
+fn image_mapping_kind() -> String {
+    IMAGE_MAPPING_KIND.to_string()
+}
+
+impl ImageMapping {
+    pub fn new(name: &str, spec: ImageMappingSpec) -> Self {
+        Self {
+            api_version: image_mapping_api_version(),
+            kind: image_mapping_kind(),
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            spec,
+        }
+    }
+}
+
+/// A `containers[].image` reference split into repository and tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageReference {
+    pub repository: String,
+    pub tag: String,
+}
+
+impl ImageReference {
+    /// Parse `repository[:tag]`; a `:` before the last `/` belongs to a
+    /// registry port. Digest references are not supported.
+    pub fn parse(image: &str) -> Result<Self, String> {
+        if image.contains('@') {
+            return Err(format!(
+                "image '{}': digest references are not supported; use a tag",
+                image
+            ));
+        }
+        let last_segment = image.rfind('/').map_or(0, |i| i + 1);
+        let (repository, tag) = match image[last_segment..].rfind(':') {
+            Some(i) => image.split_at(last_segment + i),
+            None => (image, ":"),
+        };
+        let tag = match &tag[1..] {
+            "" => DEFAULT_IMAGE_TAG,
+            tag => tag,
+        };
+        if repository.is_empty() {
+            return Err(format!("image '{}' has no repository", image));
+        }
+        Ok(Self {
+            repository: repository.to_string(),
+            tag: tag.to_string(),
+        })
+    }
+}
+
+/// The lx image an image reference maps to
+pub fn resolve_lx_image(image: &str, mappings: &[ImageMapping]) -> Result<String, String> {
+    let reference = ImageReference::parse(image)?;
+    mappings
+        .iter()
+        .filter(|m| m.spec.repository == reference.repository)
+        .find_map(|m| m.spec.tags.get(&reference.tag))
+        .cloned()
+        .ok_or_else(|| {
+            format!(
+                "no ImageMapping maps image '{}' (repository '{}', tag '{}')",
+                image, reference.repository, reference.tag
+            )
+        })
+}
+
+/// The lx image an lx-branded pod's zone is installed from; `None` if no
+/// container names an image. An lx zone has a single root filesystem, so
+/// every container must map to the same lx image.
+pub fn pod_lx_image(pod: &Pod, mappings: &[ImageMapping]) -> Result<Option<String>, String> {
+    let mut resolved: Option<String> = None;
+    let containers = pod.spec.iter().flat_map(|s| &s.containers);
+    for image in containers.filter_map(|c| c.image.as_deref()) {
+        let lx_image = resolve_lx_image(image, mappings)?;
+        match &resolved {
+            Some(other) if *other != lx_image => {
+                return Err(format!(
+                    "containers map to different lx images ({} and {}); an lx pod runs one image",
+                    other, lx_image
+                ));
+            }
+            _ => resolved = Some(lx_image),
+        }
+    }
+    Ok(resolved)
+}
+
+impl Resource for ImageMapping {
+    fn api_version(&self) -> String {
+        IMAGE_MAPPING_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        IMAGE_MAPPING_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn is_namespaced(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+
+        let reference = ImageReference::parse(&self.spec.repository)
+            .map_err(ResourceError::ValidationFailed)?;
+        if reference.repository != self.spec.repository {
+            return Err(ResourceError::ValidationFailed(format!(
+                "repository '{}' must not include a tag",
+                self.spec.repository
+            )));
+        }
+        if self.spec.tags.is_empty() {
+            return Err(ResourceError::ValidationFailed(
+                "ImageMapping must map at least one tag".to_string(),
+            ));
+        }
+        if let Some((tag, _)) = self.spec.tags.iter().find(|(tag, lx_image)| {
+            tag.is_empty() || tag.contains(['/', ':']) || lx_image.is_empty()
+        }) {
+            return Err(ResourceError::ValidationFailed(format!(
+                "tag '{}' must be a plain tag mapped to a non-empty lx image",
+                tag
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, PodSpec};
+
+    fn alpine() -> ImageMapping {
+        ImageMapping::new(
+            "alpine",
+            ImageMappingSpec {
+                repository: "alpine".to_string(),
+                tags: [
+                    ("3.20".to_string(), "/images/alpine-3.20.tar.gz".to_string()),
+                    (
+                        "latest".to_string(),
+                        "/images/alpine-latest.tar.gz".to_string(),
+                    ),
+                ]
+                .into(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_parse_image_reference() {
+        let parse = |image| {
+            let r = ImageReference::parse(image).unwrap();
+            (r.repository, r.tag)
+        };
+        assert_eq!(parse("alpine:3.20"), ("alpine".into(), "3.20".into()));
+        assert_eq!(parse("alpine"), ("alpine".into(), "latest".into()));
+        assert_eq!(
+            parse("registry:5000/team/app"),
+            ("registry:5000/team/app".into(), "latest".into())
+        );
+        assert_eq!(
+            parse("registry:5000/team/app:v2"),
+            ("registry:5000/team/app".into(), "v2".into())
+        );
+        assert!(ImageReference::parse("alpine@sha256:abc").is_err());
+        assert!(ImageReference::parse(":3.20").is_err());
+    }
+
+    #[test]
+    fn test_resolve_pod_lx_image() {
+        let mappings = [alpine()];
+        assert_eq!(
+            resolve_lx_image("alpine:3.20", &mappings).unwrap(),
+            "/images/alpine-3.20.tar.gz"
+        );
+        assert_eq!(
+            resolve_lx_image("alpine", &mappings).unwrap(),
+            "/images/alpine-latest.tar.gz"
+        );
+        assert!(resolve_lx_image("alpine:3.19", &mappings).is_err());
+        assert!(resolve_lx_image("ubuntu:24.04", &mappings).is_err());
+
+        let pod = |images: &[&str]| Pod {
+            spec: Some(PodSpec {
+                containers: images
+                    .iter()
+                    .map(|image| Container {
+                        image: Some(image.to_string()),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(pod_lx_image(&pod(&[]), &mappings).unwrap(), None);
+        assert_eq!(
+            pod_lx_image(&pod(&["alpine:3.20", "alpine:3.20"]), &mappings).unwrap(),
+            Some("/images/alpine-3.20.tar.gz".to_string())
+        );
+        assert!(pod_lx_image(&pod(&["alpine:3.20", "alpine"]), &mappings).is_err());
+    }
+
+    #[test]
+    fn test_image_mapping_validation() {
+        let mut mapping = alpine();
+        assert!(mapping.validate().is_ok());
+        assert!(!mapping.is_namespaced());
+
+        mapping.spec.repository = "alpine:3.20".to_string();
+        assert!(mapping.validate().is_err());
+
+        mapping = alpine();
+        mapping.spec.tags.insert("edge".to_string(), String::new());
+        assert!(mapping.validate().is_err());
+
+        mapping.spec.tags.clear();
+        assert!(mapping.validate().is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use super::{validate_base, Resource, ResourceError};
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::coordination::v1::Lease;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+/// API version of the Lease resource
+pub const COORDINATION_API_VERSION: &str = "coordination.k8s.io/v1";
+
+/// Kind of the Lease resource
+pub const LEASE_KIND: &str = "Lease";
+
+/// Namespace holding the Lease each node agent renews as its heartbeat,
+/// named after the node
+pub const NODE_LEASE_NAMESPACE: &str = "kube-node-lease";
+
+/// When the lease lapses unless renewed: its last renewal plus its duration.
+/// `None` when it was never renewed or has no duration.
+pub fn lease_expiry(lease: &Lease) -> Option<DateTime<Utc>> {
+    let spec = lease.spec.as_ref()?;
+    let renewed = spec.renew_time.as_ref()?.0;
+    let duration = spec.lease_duration_seconds?;
+    Some(renewed + chrono::Duration::seconds(duration.into()))
+}
+
+/// The identity holding the lease at `now`, if it is held and has not lapsed
+pub fn lease_holder(lease: &Lease, now: DateTime<Utc>) -> Option<&str> {
+    let holder = lease.spec.as_ref()?.holder_identity.as_deref()?;
+    lease_expiry(lease)
+        .is_some_and(|expiry| expiry > now)
+        .then_some(holder)
+}
+
+impl Resource for Lease {
+    fn api_version(&self) -> String {
+        COORDINATION_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        LEASE_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let Some(spec) = &self.spec else {
+            return Ok(());
+        };
+        if spec.holder_identity.as_deref() == Some("") {
+            return Err(ResourceError::ValidationFailed(
+                "Lease holderIdentity must not be empty".to_string(),
+            ));
+        }
+        if spec.lease_duration_seconds.is_some_and(|d| d <= 0) {
+            return Err(ResourceError::ValidationFailed(
+                "Lease leaseDurationSeconds must be positive".to_string(),
+            ));
+        }
+        if spec.lease_transitions.is_some_and(|t| t < 0) {
+            return Err(ResourceError::ValidationFailed(
+                "Lease leaseTransitions must not be negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::coordination::v1::LeaseSpec;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::MicroTime;
+
+    fn lease(holder: &str, renewed_secs_ago: i64, duration: i32) -> Lease {
+        Lease {
+            metadata: ObjectMeta {
+                name: Some("node1".to_string()),
+                namespace: Some(NODE_LEASE_NAMESPACE.to_string()),
+                ..Default::default()
+            },
+            spec: Some(LeaseSpec {
+                holder_identity: Some(holder.to_string()),
+                lease_duration_seconds: Some(duration),
+                renew_time: Some(MicroTime(
+                    Utc::now() - chrono::Duration::seconds(renewed_secs_ago),
+                )),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_lease_holder_lapses_after_duration() {
+        let now = Utc::now();
+        assert!(lease("node1", 10, 40).validate().is_ok());
+        assert_eq!(lease_holder(&lease("node1", 10, 40), now), Some("node1"));
+        assert_eq!(lease_holder(&lease("node1", 50, 40), now), None);
+
+        let mut never_renewed = lease("node1", 0, 40);
+        never_renewed.spec.as_mut().unwrap().renew_time = None;
+        assert_eq!(lease_holder(&never_renewed, now), None);
+    }
+
+    #[test]
+    fn test_lease_duration_must_be_positive() {
+        assert!(lease("node1", 0, 0).validate().is_err());
+        assert!(lease("", 0, 40).validate().is_err());
+    }
+}
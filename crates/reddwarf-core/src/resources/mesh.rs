@@ -0,0 +1,325 @@
+use super::conversion::{MultiVersion, ServedVersion};
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// API group/version mesh resources are stored at (deprecated for clients)
+pub const MESH_API_VERSION: &str = "mesh.reddwarf.io/v1alpha1";
+
+/// Preferred API group/version of mesh resources
+pub const MESH_V1BETA1_API_VERSION: &str = "mesh.reddwarf.io/v1beta1";
+
+/// Kind of the mesh policy resource
+pub const MESH_POLICY_KIND: &str = "MeshPolicy";
+
+/// Selects pod ports whose traffic the node mesh proxies wrap in mTLS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshPolicy {
+    #[serde(default = "mesh_api_version")]
+    pub api_version: String,
+    #[serde(default = "mesh_policy_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub metadata: ObjectMeta,
+    pub spec: MeshPolicySpec,
+}
+
+/// Desired state of a [`MeshPolicy`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshPolicySpec {
+    /// Labels a pod must carry to be meshed; empty selects every pod in the namespace
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pod_selector: BTreeMap<String, String>,
+    /// Destination ports whose connections are tunnelled over mTLS
+    pub ports: Vec<u16>,
+}
+
+fn mesh_api_version() -> String {
+    MESH_API_VERSION.to_string()
+}
+
+fn mesh_policy_kind() -> String {
+    MESH_POLICY_KIND.to_string()
+}
+
+impl MeshPolicy {
+    pub fn new(namespace: &str, name: &str, spec: MeshPolicySpec) -> Self {
+        Self {
+            api_version: mesh_api_version(),
+            kind: mesh_policy_kind(),
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..Default::default()
+            },
+            spec,
+        }
+    }
+
+    /// Whether this policy applies to a pod with the given namespace and labels
+    pub fn selects(&self, namespace: &str, labels: Option<&BTreeMap<String, String>>) -> bool {
+        if self.metadata.namespace.as_deref() != Some(namespace) {
+            return false;
+        }
+        self.spec
+            .pod_selector
+            .iter()
+            .all(|(k, v)| labels.and_then(|l| l.get(k)) == Some(v))
+    }
+}
+
+impl Resource for MeshPolicy {
+    fn api_version(&self) -> String {
+        MESH_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        MESH_POLICY_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+
+        if self.spec.ports.is_empty() {
+            return Err(ResourceError::ValidationFailed(
+                "MeshPolicy must select at least one port".to_string(),
+            ));
+        }
+        if self.spec.ports.contains(&0) {
+            return Err(ResourceError::ValidationFailed(
+                "MeshPolicy ports must be non-zero".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl MultiVersion for MeshPolicy {
+    const KIND: &'static str = MESH_POLICY_KIND;
+    const STORAGE_VERSION: &'static str = MESH_API_VERSION;
+    const SERVED_VERSIONS: &'static [ServedVersion] = &[
+        ServedVersion {
+            api_version: MESH_API_VERSION,
+            deprecated: true,
+        },
+        ServedVersion {
+            api_version: MESH_V1BETA1_API_VERSION,
+            deprecated: false,
+        },
+    ];
+
+    fn convert_from(api_version: &str, object: Value) -> Result<Value, ResourceError> {
+        match api_version {
+            MESH_V1BETA1_API_VERSION => {
+                let policy: v1beta1::MeshPolicy = serde_json::from_value(object)
+                    .map_err(|e| ResourceError::ConversionFailed(e.to_string()))?;
+                let policy = MeshPolicy::try_from(policy)?;
+                serde_json::to_value(policy)
+                    .map_err(|e| ResourceError::ConversionFailed(e.to_string()))
+            }
+            other => Err(ResourceError::ConversionFailed(format!(
+                "no conversion from {}",
+                other
+            ))),
+        }
+    }
+
+    fn convert_to(api_version: &str, object: Value) -> Result<Value, ResourceError> {
+        match api_version {
+            MESH_V1BETA1_API_VERSION => {
+                let policy: MeshPolicy = serde_json::from_value(object)
+                    .map_err(|e| ResourceError::ConversionFailed(e.to_string()))?;
+                serde_json::to_value(v1beta1::MeshPolicy::from(policy))
+                    .map_err(|e| ResourceError::ConversionFailed(e.to_string()))
+            }
+            other => Err(ResourceError::ConversionFailed(format!(
+                "no conversion to {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl TryFrom<v1beta1::MeshPolicy> for MeshPolicy {
+    type Error = ResourceError;
+
+    fn try_from(policy: v1beta1::MeshPolicy) -> Result<Self, ResourceError> {
+        let selector = policy.spec.pod_selector;
+        if selector
+            .match_expressions
+            .as_ref()
+            .is_some_and(|e| !e.is_empty())
+        {
+            return Err(ResourceError::ConversionFailed(
+                "podSelector.matchExpressions is not supported yet; use matchLabels".to_string(),
+            ));
+        }
+        Ok(Self {
+            api_version: mesh_api_version(),
+            kind: mesh_policy_kind(),
+            metadata: policy.metadata,
+            spec: MeshPolicySpec {
+                pod_selector: selector.match_labels.unwrap_or_default(),
+                ports: policy.spec.ports.into_iter().map(|p| p.port).collect(),
+            },
+        })
+    }
+}
+
+impl From<MeshPolicy> for v1beta1::MeshPolicy {
+    fn from(policy: MeshPolicy) -> Self {
+        let selector = policy.spec.pod_selector;
+        Self {
+            api_version: MESH_V1BETA1_API_VERSION.to_string(),
+            kind: mesh_policy_kind(),
+            metadata: policy.metadata,
+            spec: v1beta1::MeshPolicySpec {
+                pod_selector: v1beta1::LabelSelector {
+                    match_labels: (!selector.is_empty()).then_some(selector),
+                    match_expressions: None,
+                },
+                ports: policy
+                    .spec
+                    .ports
+                    .into_iter()
+                    .map(|port| v1beta1::MeshPolicyPort { port })
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// The v1beta1 schema of mesh resources: pods are chosen with a standard
+/// label selector and ports are objects, leaving room for per-port options
+pub mod v1beta1 {
+    pub use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use serde::{Deserialize, Serialize};
+
+    /// Selects pod ports whose traffic the node mesh proxies wrap in mTLS
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct MeshPolicy {
+        #[serde(default)]
+        pub api_version: String,
+        #[serde(default)]
+        pub kind: String,
+        #[serde(default)]
+        pub metadata: ObjectMeta,
+        pub spec: MeshPolicySpec,
+    }
+
+    /// Desired state of a [`MeshPolicy`]
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct MeshPolicySpec {
+        /// Pods to mesh; an empty selector selects every pod in the namespace
+        #[serde(default)]
+        pub pod_selector: LabelSelector,
+        /// Destination ports whose connections are tunnelled over mTLS
+        pub ports: Vec<MeshPolicyPort>,
+    }
+
+    /// A meshed destination port
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct MeshPolicyPort {
+        pub port: u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mesh_policy_selects_by_namespace_and_labels() {
+        let policy = MeshPolicy::new(
+            "default",
+            "web",
+            MeshPolicySpec {
+                pod_selector: [("app".to_string(), "web".to_string())].into(),
+                ports: vec![8080],
+            },
+        );
+
+        let web = [("app".to_string(), "web".to_string())].into();
+        let db = [("app".to_string(), "db".to_string())].into();
+        assert!(policy.selects("default", Some(&web)));
+        assert!(!policy.selects("default", Some(&db)));
+        assert!(!policy.selects("default", None));
+        assert!(!policy.selects("other", Some(&web)));
+    }
+
+    #[test]
+    fn test_mesh_policy_validation() {
+        let mut policy = MeshPolicy::new("default", "web", MeshPolicySpec::default());
+        assert!(policy.validate().is_err());
+
+        policy.spec.ports = vec![0];
+        assert!(policy.validate().is_err());
+
+        policy.spec.ports = vec![443];
+        assert!(policy.validate().is_ok());
+        assert_eq!(policy.gvk().api_path(), "apis/mesh.reddwarf.io/v1alpha1");
+    }
+
+    #[test]
+    fn test_mesh_policy_v1beta1_conversion() {
+        let beta = serde_json::json!({
+            "apiVersion": MESH_V1BETA1_API_VERSION,
+            "kind": "MeshPolicy",
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {
+                "podSelector": {"matchLabels": {"app": "web"}},
+                "ports": [{"port": 8080}, {"port": 8443}]
+            }
+        });
+
+        let policy = MeshPolicy::decode(MESH_V1BETA1_API_VERSION, beta.clone()).unwrap();
+        assert_eq!(policy.api_version, MESH_API_VERSION);
+        assert_eq!(policy.spec.ports, vec![8080, 8443]);
+        assert_eq!(policy.spec.pod_selector["app"], "web");
+
+        // Round-trips back to the same v1beta1 object
+        assert_eq!(policy.encode(MESH_V1BETA1_API_VERSION).unwrap(), beta);
+        let alpha = policy.encode(MESH_API_VERSION).unwrap();
+        assert_eq!(alpha["spec"]["podSelector"]["app"], "web");
+
+        let mut expressions = beta;
+        expressions["spec"]["podSelector"]["matchExpressions"] =
+            serde_json::json!([{"key": "app", "operator": "Exists"}]);
+        assert!(MeshPolicy::decode(MESH_V1BETA1_API_VERSION, expressions).is_err());
+        assert!(MeshPolicy::decode("mesh.reddwarf.io/v2", alpha).is_err());
+    }
+
+    #[test]
+    fn test_mesh_policy_versions() {
+        assert_eq!(MeshPolicy::preferred_version(), MESH_V1BETA1_API_VERSION);
+        assert!(MeshPolicy::deprecation_warning(MESH_V1BETA1_API_VERSION).is_none());
+        assert_eq!(
+            MeshPolicy::deprecation_warning(MESH_API_VERSION).unwrap(),
+            "mesh.reddwarf.io/v1alpha1 MeshPolicy is deprecated; use mesh.reddwarf.io/v1beta1"
+        );
+    }
+
+    #[test]
+    fn test_mesh_policy_deserializes_without_type_meta() {
+        let policy: MeshPolicy =
+            serde_json::from_str(r#"{"metadata":{"name":"p"},"spec":{"ports":[80]}}"#).unwrap();
+        assert_eq!(policy.kind, MESH_POLICY_KIND);
+        assert!(policy.spec.pod_selector.is_empty());
+    }
+}
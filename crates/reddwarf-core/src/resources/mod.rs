@@ -1,7 +1,95 @@
+pub mod config_map;
+pub mod conversion;
+pub mod custom_resource;
+pub mod daemon_set;
+pub mod deployment;
+pub mod disruption_budget;
+pub mod endpoints;
+pub mod horizontal_pod_autoscaler;
+pub mod image_mapping;
+pub mod lease;
+pub mod mesh;
+pub mod network_policy;
+pub mod persistent_volume;
+pub mod priority_class;
+pub mod qos;
 pub mod quantities;
-
+pub mod runtime_class;
+pub mod schema;
+pub mod secret;
+pub mod selector;
+
+pub use config_map::{config_map_size, is_immutable, CONFIG_MAP_KIND, MAX_CONFIG_MAP_SIZE};
+pub use conversion::{MultiVersion, ServedVersion};
+pub use custom_resource::{
+    crd_is_namespaced, crd_name, established_status, has_status_subresource, served_version,
+    storage_version, validate_custom_object, CustomObject, CONVERSION_NONE, CRD_API_VERSION,
+    CRD_KIND, SCOPE_CLUSTER, SCOPE_NAMESPACED,
+};
+pub use daemon_set::{
+    daemon_set_max_unavailable, default_daemon_set, is_on_delete, DAEMON_SET_KIND,
+    DAEMON_SET_ON_DELETE, DAEMON_SET_ROLLING_UPDATE,
+};
+pub use deployment::{
+    default_deployment, deployment_replicas, is_recreate, pod_template_hash, revision,
+    rolling_update_limits, scaled_value, APPS_API_VERSION, DEFAULT_REVISION_HISTORY_LIMIT,
+    DEPLOYMENT_KIND, POD_TEMPLATE_HASH_LABEL, REPLICA_SET_KIND, REVISION_ANNOTATION,
+    ROLLBACK_ANNOTATION,
+};
+pub use disruption_budget::{
+    budget_selects, disruption_budget_status, eviction_allowed, is_healthy, ALWAYS_ALLOW,
+    IF_HEALTHY_BUDGET, POD_DISRUPTION_BUDGET_KIND, POLICY_API_VERSION,
+};
+pub use endpoints::{
+    is_slice_of, ADDRESS_TYPES, DISCOVERY_API_VERSION, ENDPOINTS_KIND, ENDPOINT_SLICE_CONTROLLER,
+    ENDPOINT_SLICE_KIND, ENDPOINT_SLICE_MANAGED_BY_LABEL, SERVICE_NAME_LABEL,
+};
+pub use horizontal_pod_autoscaler::{
+    autoscaler_bounds, default_horizontal_pod_autoscaler, replicas_for_utilization,
+    stabilization_windows, utilization_targets, AUTOSCALING_API_VERSION, AUTOSCALING_TOLERANCE,
+    DEFAULT_CPU_UTILIZATION, DEFAULT_SCALE_DOWN_STABILIZATION_SECONDS,
+    HORIZONTAL_POD_AUTOSCALER_KIND,
+};
+pub use image_mapping::{
+    pod_lx_image, resolve_lx_image, ImageMapping, ImageMappingSpec, ImageReference,
+    IMAGE_MAPPING_API_VERSION, IMAGE_MAPPING_KIND,
+};
+pub use lease::{
+    lease_expiry, lease_holder, COORDINATION_API_VERSION, LEASE_KIND, NODE_LEASE_NAMESPACE,
+};
+pub use mesh::{
+    MeshPolicy, MeshPolicySpec, MESH_API_VERSION, MESH_POLICY_KIND, MESH_V1BETA1_API_VERSION,
+};
+pub use network_policy::{
+    default_deny_egress_policy, egress_lockdown, is_managed, DEFAULT_DENY_EGRESS_POLICY,
+    EGRESS_LOCKDOWN_ANNOTATION, MANAGED_BY_LABEL, MANAGED_BY_REDDWARF, NETWORK_POLICY_API_VERSION,
+    NETWORK_POLICY_KIND,
+};
+pub use persistent_volume::{
+    claim_phase, claim_request_bytes, volume_capacity_bytes, volume_matches_claim, volume_node,
+    volume_path, volume_phase, CLAIM_PENDING, HOSTNAME_LABEL, PERSISTENT_VOLUME_CLAIM_KIND,
+    PERSISTENT_VOLUME_KIND, PHASE_BOUND, PROVISIONED_BY_ANNOTATION, RECLAIM_DELETE, RECLAIM_RETAIN,
+    SELECTED_NODE_ANNOTATION, VOLUME_AVAILABLE, VOLUME_RELEASED, ZFS_PROVISIONER,
+    ZFS_STORAGE_CLASS,
+};
+pub use priority_class::{
+    pod_priority, scheduling_order, system_priority_classes, HIGHEST_USER_DEFINABLE_PRIORITY,
+    PREEMPT_LOWER_PRIORITY, PREEMPT_NEVER, PRIORITY_CLASS_KIND, SCHEDULING_API_VERSION,
+    SYSTEM_CLUSTER_CRITICAL, SYSTEM_NODE_CRITICAL,
+};
+pub use qos::{pod_qos_class, QosClass};
 pub use quantities::ResourceQuantities;
-
+pub use runtime_class::{
+    pod_zone_brand, DEFAULT_ZONE_BRAND, RUNTIME_CLASS_API_VERSION, RUNTIME_CLASS_KIND,
+};
+pub use schema::apply_schema;
+pub use secret::{
+    merge_string_data, secret_size, secret_type, MAX_SECRET_SIZE, SECRET_KIND,
+    SECRET_TYPE_DOCKER_CONFIG_JSON, SECRET_TYPE_OPAQUE, SECRET_TYPE_TLS,
+};
+pub use selector::{selector_is_empty, selector_matches, validate_selector};
+
+use crate::annotations::validate_annotations;
 use crate::{GroupVersionKind, ResourceKey, ResourceVersion};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 use serde::{Deserialize, Serialize};
@@ -100,6 +188,9 @@ pub enum ResourceError {
 
     #[error("Validation failed: {0}")]
     ValidationFailed(String),
+
+    #[error("Conversion failed: {0}")]
+    ConversionFailed(String),
 }
 
 /// Validate a Kubernetes resource name (DNS-1123 subdomain)
@@ -125,6 +216,11 @@ pub fn is_valid_name(name: &str) -> bool {
         .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-' || *c == '.')
 }
 
+/// Check if a string is a valid DNS-1123 label (e.g. a hostname)
+pub fn is_valid_label(label: &str) -> bool {
+    label.len() <= 63 && !label.contains('.') && is_valid_name(label)
+}
+
 // Implement Resource trait for common k8s-openapi types
 use k8s_openapi::api::core::v1::{Namespace, Node, Pod, Service};
 
@@ -148,6 +244,7 @@ impl Resource for Pod {
     fn validate(&self) -> Result<(), ResourceError> {
         // Call base validation
         validate_base(&self.metadata)?;
+        validate_annotations(self.metadata.annotations.as_ref())?;
 
         // Pod-specific validation
         if let Some(spec) = &self.spec {
@@ -156,6 +253,24 @@ impl Resource for Pod {
                     "Pod must have at least one container".to_string(),
                 ));
             }
+            for (field, value) in [("hostname", &spec.hostname), ("subdomain", &spec.subdomain)] {
+                if let Some(value) = value {
+                    if !is_valid_label(value) {
+                        return Err(ResourceError::ValidationFailed(format!(
+                            "Pod {} '{}' must be a DNS-1123 label",
+                            field, value
+                        )));
+                    }
+                }
+            }
+            for container in &spec.containers {
+                if container.stdin_once == Some(true) && container.stdin != Some(true) {
+                    return Err(ResourceError::ValidationFailed(format!(
+                        "Container '{}' sets stdinOnce without stdin",
+                        container.name
+                    )));
+                }
+            }
         } else {
             return Err(ResourceError::MissingField("spec".to_string()));
         }
@@ -220,6 +335,12 @@ impl Resource for Namespace {
     fn is_namespaced(&self) -> bool {
         false
     }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        // Namespaces carry the bandwidth defaults of their pods
+        validate_annotations(self.metadata.annotations.as_ref())
+    }
 }
 
 #[cfg(test)]
@@ -240,6 +361,67 @@ mod tests {
         assert!(!is_valid_name("my_app")); // underscore
     }
 
+    #[test]
+    fn test_pod_hostname_must_be_label() {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("web-0".to_string());
+        pod.spec = Some(k8s_openapi::api::core::v1::PodSpec {
+            containers: vec![Default::default()],
+            hostname: Some("web-0".to_string()),
+            subdomain: Some("web".to_string()),
+            ..Default::default()
+        });
+        assert!(pod.validate().is_ok());
+
+        pod.spec.as_mut().unwrap().subdomain = Some("web.svc".to_string());
+        assert!(pod.validate().is_err());
+        pod.spec.as_mut().unwrap().subdomain = None;
+        pod.spec.as_mut().unwrap().hostname = Some("a".repeat(64));
+        assert!(pod.validate().is_err());
+    }
+
+    #[test]
+    fn test_pod_stdin_once_needs_stdin() {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("shell".to_string());
+        pod.spec = Some(k8s_openapi::api::core::v1::PodSpec {
+            containers: vec![k8s_openapi::api::core::v1::Container {
+                name: "shell".to_string(),
+                stdin_once: Some(true),
+                tty: Some(true),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        assert!(pod.validate().is_err());
+
+        pod.spec.as_mut().unwrap().containers[0].stdin = Some(true);
+        assert!(pod.validate().is_ok());
+    }
+
+    #[test]
+    fn test_pod_and_namespace_annotations_are_validated() {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("limited".to_string());
+        pod.spec = Some(k8s_openapi::api::core::v1::PodSpec {
+            containers: vec![Default::default()],
+            ..Default::default()
+        });
+        pod.metadata.annotations = Some(
+            [(
+                crate::annotations::EGRESS_BANDWIDTH_ANNOTATION.to_string(),
+                "lots".to_string(),
+            )]
+            .into(),
+        );
+        assert!(pod.validate().is_err());
+
+        let mut namespace = Namespace::default();
+        namespace.metadata.name = Some("team".to_string());
+        namespace.metadata.annotations = pod.metadata.annotations.clone();
+        assert!(namespace.validate().is_err());
+    }
+
     #[test]
     fn test_pod_resource_key() {
         let mut pod = Pod::default();
@@ -0,0 +1,268 @@
+use super::selector::validate_selector;
+use super::{validate_base, Resource, ResourceError};
+use crate::Namespace;
+use k8s_openapi::api::networking::v1::{
+    NetworkPolicy, NetworkPolicyPeer, NetworkPolicyPort, NetworkPolicySpec,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// API group/version of NetworkPolicy
+pub const NETWORK_POLICY_API_VERSION: &str = "networking.k8s.io/v1";
+
+/// Kind of the NetworkPolicy resource
+pub const NETWORK_POLICY_KIND: &str = "NetworkPolicy";
+
+/// Namespace annotation that, set to `"true"`, locks down the egress of every
+/// pod in the namespace with a default-deny policy; `"false"` opts a new
+/// namespace out of the cluster-wide default
+pub const EGRESS_LOCKDOWN_ANNOTATION: &str = "reddwarf.io/egress-lockdown";
+
+/// Name of the default-deny egress policy of locked-down namespaces
+pub const DEFAULT_DENY_EGRESS_POLICY: &str = "default-deny-egress";
+
+/// Label marking objects reddwarf creates and removes on its own
+pub const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+
+/// Value of [`MANAGED_BY_LABEL`] on objects reddwarf manages
+pub const MANAGED_BY_REDDWARF: &str = "reddwarf";
+
+/// Whether `namespace` asks for its egress to be locked down
+pub fn egress_lockdown(namespace: &Namespace) -> bool {
+    namespace
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(EGRESS_LOCKDOWN_ANNOTATION))
+        .is_some_and(|value| value == "true")
+}
+
+/// Whether `policy` was created by reddwarf and may be removed by it
+pub fn is_managed(policy: &NetworkPolicy) -> bool {
+    policy
+        .metadata
+        .labels
+        .as_ref()
+        .and_then(|l| l.get(MANAGED_BY_LABEL))
+        .is_some_and(|value| value == MANAGED_BY_REDDWARF)
+}
+
+/// Policy selecting every pod of `namespace` with no egress rule, so no
+/// traffic may leave them unless another policy allows it
+pub fn default_deny_egress_policy(namespace: &str) -> NetworkPolicy {
+    NetworkPolicy {
+        metadata: ObjectMeta {
+            name: Some(DEFAULT_DENY_EGRESS_POLICY.to_string()),
+            namespace: Some(namespace.to_string()),
+            labels: Some(BTreeMap::from([(
+                MANAGED_BY_LABEL.to_string(),
+                MANAGED_BY_REDDWARF.to_string(),
+            )])),
+            ..Default::default()
+        },
+        spec: Some(NetworkPolicySpec {
+            pod_selector: LabelSelector::default(),
+            policy_types: Some(vec!["Egress".to_string()]),
+            egress: None,
+            ingress: None,
+        }),
+    }
+}
+
+impl Resource for NetworkPolicy {
+    fn api_version(&self) -> String {
+        NETWORK_POLICY_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        NETWORK_POLICY_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let Some(spec) = self.spec.as_ref() else {
+            return Ok(());
+        };
+        for policy_type in spec.policy_types.as_deref().unwrap_or_default() {
+            if policy_type != "Ingress" && policy_type != "Egress" {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "policyTypes entry '{}' must be Ingress or Egress",
+                    policy_type
+                )));
+            }
+        }
+        validate_selector(&spec.pod_selector)?;
+        for rule in spec.ingress.iter().flatten() {
+            validate_rule(rule.from.as_deref(), rule.ports.as_deref())?;
+        }
+        for rule in spec.egress.iter().flatten() {
+            validate_rule(rule.to.as_deref(), rule.ports.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+/// Check the peers and ports of one ingress or egress rule
+fn validate_rule(
+    peers: Option<&[NetworkPolicyPeer]>,
+    ports: Option<&[NetworkPolicyPort]>,
+) -> Result<(), ResourceError> {
+    for peer in peers.unwrap_or_default() {
+        if let Some(block) = &peer.ip_block {
+            if peer.pod_selector.is_some() || peer.namespace_selector.is_some() {
+                return Err(ResourceError::ValidationFailed(
+                    "a peer with an ipBlock cannot also select pods or namespaces".to_string(),
+                ));
+            }
+            let (network, prefix_len) = parse_cidr(&block.cidr)?;
+            for except in block.except.iter().flatten() {
+                let (except_network, except_len) = parse_cidr(except)?;
+                if network.is_ipv4() != except_network.is_ipv4() || except_len <= prefix_len {
+                    return Err(ResourceError::ValidationFailed(format!(
+                        "ipBlock except '{}' must be a smaller range within '{}'",
+                        except, block.cidr
+                    )));
+                }
+            }
+        }
+        for selector in [&peer.pod_selector, &peer.namespace_selector]
+            .into_iter()
+            .flatten()
+        {
+            validate_selector(selector)?;
+        }
+    }
+
+    for port in ports.unwrap_or_default() {
+        let protocol = port.protocol.as_deref().unwrap_or("TCP");
+        if !matches!(protocol, "TCP" | "UDP" | "SCTP") {
+            return Err(ResourceError::ValidationFailed(format!(
+                "port protocol '{}' must be TCP, UDP or SCTP",
+                protocol
+            )));
+        }
+        match (&port.port, port.end_port) {
+            (Some(IntOrString::Int(number)), end_port) => {
+                let end = end_port.unwrap_or(*number);
+                if !(1..=65535).contains(number) || end < *number || end > 65535 {
+                    return Err(ResourceError::ValidationFailed(format!(
+                        "port range {}-{} is not within 1-65535",
+                        number, end
+                    )));
+                }
+            }
+            (_, Some(_)) => {
+                return Err(ResourceError::ValidationFailed(
+                    "endPort requires a numeric port".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Address and prefix length of a CIDR
+fn parse_cidr(cidr: &str) -> Result<(IpAddr, u8), ResourceError> {
+    let invalid = || ResourceError::ValidationFailed(format!("ipBlock '{}' is not a CIDR", cidr));
+    let (address, prefix_len) = cidr.split_once('/').ok_or_else(invalid)?;
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let prefix_len: u8 = prefix_len.parse().map_err(|_| invalid())?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    if prefix_len > max {
+        return Err(invalid());
+    }
+    Ok((address, prefix_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::networking::v1::{IPBlock, NetworkPolicyIngressRule};
+
+    #[test]
+    fn test_egress_lockdown_policy() {
+        let mut namespace = Namespace::default();
+        assert!(!egress_lockdown(&namespace));
+        namespace.metadata.annotations = Some(BTreeMap::from([(
+            EGRESS_LOCKDOWN_ANNOTATION.to_string(),
+            "true".to_string(),
+        )]));
+        assert!(egress_lockdown(&namespace));
+
+        let policy = default_deny_egress_policy("prod");
+        assert!(is_managed(&policy));
+        assert!(policy.validate().is_ok());
+        assert_eq!(policy.metadata.namespace.as_deref(), Some("prod"));
+        let spec = policy.spec.as_ref().unwrap();
+        assert_eq!(spec.policy_types, Some(vec!["Egress".to_string()]));
+        assert!(spec.egress.is_none());
+
+        let mut invalid = policy.clone();
+        invalid.spec.as_mut().unwrap().policy_types = Some(vec!["Both".to_string()]);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let with_rule = |peer: NetworkPolicyPeer, port: NetworkPolicyPort| {
+            let mut policy = default_deny_egress_policy("prod");
+            policy.spec.as_mut().unwrap().ingress = Some(vec![NetworkPolicyIngressRule {
+                from: Some(vec![peer]),
+                ports: Some(vec![port]),
+            }]);
+            policy
+        };
+        let block = |cidr: &str, except: &[&str]| NetworkPolicyPeer {
+            ip_block: Some(IPBlock {
+                cidr: cidr.to_string(),
+                except: Some(except.iter().map(|e| e.to_string()).collect()),
+            }),
+            ..Default::default()
+        };
+        let port = |number: i32, end_port: Option<i32>| NetworkPolicyPort {
+            port: Some(IntOrString::Int(number)),
+            end_port,
+            ..Default::default()
+        };
+
+        let valid = with_rule(
+            block("10.0.0.0/8", &["10.1.0.0/16"]),
+            port(8000, Some(8080)),
+        );
+        assert!(valid.validate().is_ok());
+
+        for invalid in [
+            with_rule(block("10.0.0.0/33", &[]), port(80, None)),
+            with_rule(block("10.0.0.0/16", &["10.0.0.0/8"]), port(80, None)),
+            with_rule(block("10.0.0.0/8", &[]), port(0, None)),
+            with_rule(block("10.0.0.0/8", &[]), port(8080, Some(8000))),
+            with_rule(
+                block("10.0.0.0/8", &[]),
+                NetworkPolicyPort {
+                    protocol: Some("ICMP".to_string()),
+                    ..Default::default()
+                },
+            ),
+            with_rule(
+                NetworkPolicyPeer {
+                    pod_selector: Some(LabelSelector::default()),
+                    ..block("10.0.0.0/8", &[])
+                },
+                port(80, None),
+            ),
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}
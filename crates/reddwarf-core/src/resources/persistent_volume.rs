@@ -0,0 +1,344 @@
+use super::quantities::ResourceQuantities;
+use super::selector::{selector_matches, validate_selector};
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::core::v1::{PersistentVolume, PersistentVolumeClaim};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::collections::BTreeMap;
+
+/// Kind of the PersistentVolume resource
+pub const PERSISTENT_VOLUME_KIND: &str = "PersistentVolume";
+
+/// Kind of the PersistentVolumeClaim resource
+pub const PERSISTENT_VOLUME_CLAIM_KIND: &str = "PersistentVolumeClaim";
+
+/// Provisioner of the volumes created as ZFS datasets by a node's volume
+/// binder
+pub const ZFS_PROVISIONER: &str = "reddwarf.io/zfs";
+
+/// Storage class of the volumes a node's volume binder provisions; claims
+/// without a storage class are provisioned in it too
+pub const ZFS_STORAGE_CLASS: &str = "zfs";
+
+/// Annotation naming the provisioner that created a PersistentVolume
+pub const PROVISIONED_BY_ANNOTATION: &str = "pv.kubernetes.io/provisioned-by";
+
+/// Annotation naming the node that should bind a PersistentVolumeClaim
+/// before any pod using it is scheduled
+pub const SELECTED_NODE_ANNOTATION: &str = "volume.kubernetes.io/selected-node";
+
+/// Node label a volume's node affinity pins it to a node with
+pub const HOSTNAME_LABEL: &str = "kubernetes.io/hostname";
+
+/// Phase of volumes and claims that are bound
+pub const PHASE_BOUND: &str = "Bound";
+
+/// Phase of volumes free to bind a claim
+pub const VOLUME_AVAILABLE: &str = "Available";
+
+/// Phase of volumes whose claim was deleted and that are kept by their
+/// reclaim policy
+pub const VOLUME_RELEASED: &str = "Released";
+
+/// Phase of claims not bound yet
+pub const CLAIM_PENDING: &str = "Pending";
+
+/// Reclaim policy deleting a volume along with its claim
+pub const RECLAIM_DELETE: &str = "Delete";
+
+/// Reclaim policy keeping a volume after its claim is deleted
+pub const RECLAIM_RETAIN: &str = "Retain";
+
+const ACCESS_MODES: &[&str] = &[
+    "ReadWriteOnce",
+    "ReadOnlyMany",
+    "ReadWriteMany",
+    "ReadWriteOncePod",
+];
+
+fn storage_bytes(
+    resources: Option<&BTreeMap<String, Quantity>>,
+    field: &str,
+) -> Result<i64, ResourceError> {
+    let quantity = resources
+        .and_then(|r| r.get("storage"))
+        .ok_or_else(|| ResourceError::MissingField(format!("{}.storage", field)))?;
+    match ResourceQuantities::parse_memory(&quantity.0) {
+        Ok(bytes) if bytes > 0 => Ok(bytes),
+        Ok(_) => Err(ResourceError::ValidationFailed(format!(
+            "{}.storage must be positive",
+            field
+        ))),
+        Err(e) => Err(ResourceError::ValidationFailed(format!(
+            "{}.storage '{}' is invalid: {}",
+            field, quantity.0, e
+        ))),
+    }
+}
+
+fn validate_access_modes(modes: Option<&Vec<String>>) -> Result<(), ResourceError> {
+    let modes = modes
+        .filter(|m| !m.is_empty())
+        .ok_or_else(|| ResourceError::MissingField("spec.accessModes".to_string()))?;
+    match modes.iter().find(|m| !ACCESS_MODES.contains(&m.as_str())) {
+        Some(mode) => Err(ResourceError::ValidationFailed(format!(
+            "spec.accessModes '{}' must be one of {}",
+            mode,
+            ACCESS_MODES.join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Bytes of storage `claim` requests
+pub fn claim_request_bytes(claim: &PersistentVolumeClaim) -> Result<i64, ResourceError> {
+    storage_bytes(
+        claim
+            .spec
+            .as_ref()
+            .and_then(|s| s.resources.as_ref())
+            .and_then(|r| r.requests.as_ref()),
+        "spec.resources.requests",
+    )
+}
+
+/// Bytes of storage `volume` provides
+pub fn volume_capacity_bytes(volume: &PersistentVolume) -> Result<i64, ResourceError> {
+    storage_bytes(
+        volume.spec.as_ref().and_then(|s| s.capacity.as_ref()),
+        "spec.capacity",
+    )
+}
+
+/// Host path of the local or hostPath storage backing `volume`
+pub fn volume_path(volume: &PersistentVolume) -> Option<&str> {
+    let spec = volume.spec.as_ref()?;
+    spec.local
+        .as_ref()
+        .map(|l| l.path.as_str())
+        .or_else(|| spec.host_path.as_ref().map(|h| h.path.as_str()))
+}
+
+/// Node `volume` is pinned to by a `kubernetes.io/hostname In [node]`
+/// term of its node affinity, or `None` when any node can mount it
+pub fn volume_node(volume: &PersistentVolume) -> Option<&str> {
+    volume
+        .spec
+        .as_ref()?
+        .node_affinity
+        .as_ref()?
+        .required
+        .as_ref()?
+        .node_selector_terms
+        .iter()
+        .flat_map(|term| term.match_expressions.iter().flatten())
+        .find(|expr| expr.key == HOSTNAME_LABEL && expr.operator == "In")
+        .and_then(|expr| expr.values.as_ref()?.first())
+        .map(String::as_str)
+}
+
+/// Phase of `volume`, `Available` when it has no status yet
+pub fn volume_phase(volume: &PersistentVolume) -> &str {
+    volume
+        .status
+        .as_ref()
+        .and_then(|s| s.phase.as_deref())
+        .unwrap_or(VOLUME_AVAILABLE)
+}
+
+/// Phase of `claim`, `Pending` when it has no status yet
+pub fn claim_phase(claim: &PersistentVolumeClaim) -> &str {
+    claim
+        .status
+        .as_ref()
+        .and_then(|s| s.phase.as_deref())
+        .unwrap_or(CLAIM_PENDING)
+}
+
+/// Whether the unbound `volume` can satisfy `claim`: same storage class
+/// and volume mode, every requested access mode, enough capacity, and the
+/// labels the claim's selector asks for
+pub fn volume_matches_claim(volume: &PersistentVolume, claim: &PersistentVolumeClaim) -> bool {
+    let (Some(volume_spec), Some(claim_spec)) = (&volume.spec, &claim.spec) else {
+        return false;
+    };
+    let class = |c: &Option<String>| c.clone().unwrap_or_default();
+    let mode = |m: &Option<String>| m.clone().unwrap_or_else(|| "Filesystem".to_string());
+    if class(&volume_spec.storage_class_name) != class(&claim_spec.storage_class_name)
+        || mode(&volume_spec.volume_mode) != mode(&claim_spec.volume_mode)
+    {
+        return false;
+    }
+
+    let volume_modes = volume_spec.access_modes.as_deref().unwrap_or_default();
+    if !claim_spec
+        .access_modes
+        .iter()
+        .flatten()
+        .all(|m| volume_modes.contains(m))
+    {
+        return false;
+    }
+
+    let enough = match (volume_capacity_bytes(volume), claim_request_bytes(claim)) {
+        (Ok(capacity), Ok(request)) => capacity >= request,
+        _ => false,
+    };
+    enough
+        && claim_spec
+            .selector
+            .as_ref()
+            .is_none_or(|s| selector_matches(s, volume.metadata.labels.as_ref()))
+}
+
+impl Resource for PersistentVolume {
+    fn api_version(&self) -> String {
+        "v1".to_string()
+    }
+
+    fn kind(&self) -> String {
+        PERSISTENT_VOLUME_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn is_namespaced(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+        volume_capacity_bytes(self)?;
+        validate_access_modes(spec.access_modes.as_ref())?;
+        if volume_path(self).is_none() {
+            return Err(ResourceError::ValidationFailed(
+                "spec must set a local or hostPath volume source".to_string(),
+            ));
+        }
+        match spec.persistent_volume_reclaim_policy.as_deref() {
+            None | Some(RECLAIM_DELETE) | Some(RECLAIM_RETAIN) => Ok(()),
+            Some(other) => Err(ResourceError::ValidationFailed(format!(
+                "spec.persistentVolumeReclaimPolicy '{}' must be Delete or Retain",
+                other
+            ))),
+        }
+    }
+}
+
+impl Resource for PersistentVolumeClaim {
+    fn api_version(&self) -> String {
+        "v1".to_string()
+    }
+
+    fn kind(&self) -> String {
+        PERSISTENT_VOLUME_CLAIM_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let spec = self
+            .spec
+            .as_ref()
+            .ok_or_else(|| ResourceError::MissingField("spec".to_string()))?;
+        validate_access_modes(spec.access_modes.as_ref())?;
+        claim_request_bytes(self)?;
+        if let Some(selector) = &spec.selector {
+            validate_selector(selector)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume() -> PersistentVolume {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "pv-1", "labels": {"tier": "fast"}},
+            "spec": {
+                "capacity": {"storage": "10Gi"},
+                "accessModes": ["ReadWriteOnce"],
+                "local": {"path": "/rpool/volumes/pv-1"},
+                "nodeAffinity": {"required": {"nodeSelectorTerms": [{
+                    "matchExpressions": [
+                        {"key": "kubernetes.io/hostname", "operator": "In", "values": ["node1"]}
+                    ]
+                }]}}
+            }
+        }))
+        .unwrap()
+    }
+
+    fn claim(storage: &str) -> PersistentVolumeClaim {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "data", "namespace": "default"},
+            "spec": {
+                "accessModes": ["ReadWriteOnce"],
+                "resources": {"requests": {"storage": storage}}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_volume_matches_claim() {
+        let pv = volume();
+        assert!(pv.validate().is_ok());
+        assert_eq!(volume_node(&pv), Some("node1"));
+        assert_eq!(volume_path(&pv), Some("/rpool/volumes/pv-1"));
+        assert_eq!(volume_phase(&pv), VOLUME_AVAILABLE);
+
+        let mut pvc = claim("5Gi");
+        assert!(pvc.validate().is_ok());
+        assert!(volume_matches_claim(&pv, &pvc));
+        assert!(!volume_matches_claim(&pv, &claim("20Gi")));
+
+        pvc.spec.as_mut().unwrap().access_modes = Some(vec!["ReadWriteMany".to_string()]);
+        assert!(!volume_matches_claim(&pv, &pvc));
+
+        let mut pvc = claim("1Gi");
+        pvc.spec.as_mut().unwrap().selector = Some(
+            serde_json::from_value(serde_json::json!({"matchLabels": {"tier": "slow"}})).unwrap(),
+        );
+        assert!(!volume_matches_claim(&pv, &pvc));
+        pvc.spec.as_mut().unwrap().storage_class_name = Some("zfs".to_string());
+        pvc.spec.as_mut().unwrap().selector = None;
+        assert!(!volume_matches_claim(&pv, &pvc));
+    }
+
+    #[test]
+    fn test_validate_volume_and_claim() {
+        let mut pv = volume();
+        pv.spec.as_mut().unwrap().local = None;
+        assert!(pv.validate().is_err());
+
+        let mut pv = volume();
+        pv.spec.as_mut().unwrap().persistent_volume_reclaim_policy = Some("Recycle".to_string());
+        assert!(pv.validate().is_err());
+
+        assert!(claim("0").validate().is_err());
+        assert!(claim("lots").validate().is_err());
+        let mut pvc = claim("1Gi");
+        pvc.spec.as_mut().unwrap().access_modes = Some(vec!["ReadWriteSometimes".to_string()]);
+        assert!(pvc.validate().is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use super::{validate_base, Resource, ResourceError};
+use crate::Pod;
+use k8s_openapi::api::scheduling::v1::PriorityClass;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::cmp::Ordering;
+
+/// API group/version of PriorityClass
+pub const SCHEDULING_API_VERSION: &str = "scheduling.k8s.io/v1";
+
+/// Kind of the PriorityClass resource
+pub const PRIORITY_CLASS_KIND: &str = "PriorityClass";
+
+/// Highest value a PriorityClass other than the system ones may have
+pub const HIGHEST_USER_DEFINABLE_PRIORITY: i32 = 1_000_000_000;
+
+/// Built-in class of pods critical to the cluster
+pub const SYSTEM_CLUSTER_CRITICAL: &str = "system-cluster-critical";
+
+/// Built-in class of pods critical to their node, above every other class
+pub const SYSTEM_NODE_CRITICAL: &str = "system-node-critical";
+
+/// Preemption policy letting a pod preempt pods of lower priority, the default
+pub const PREEMPT_LOWER_PRIORITY: &str = "PreemptLowerPriority";
+
+/// Preemption policy keeping a pod from preempting others
+pub const PREEMPT_NEVER: &str = "Never";
+
+/// The built-in PriorityClasses the API server creates on startup
+pub fn system_priority_classes() -> Vec<PriorityClass> {
+    [
+        (
+            SYSTEM_CLUSTER_CRITICAL,
+            2 * HIGHEST_USER_DEFINABLE_PRIORITY,
+            "Used for system critical pods that must run in the cluster, but can be moved to another node if necessary.",
+        ),
+        (
+            SYSTEM_NODE_CRITICAL,
+            2 * HIGHEST_USER_DEFINABLE_PRIORITY + 1000,
+            "Used for system critical pods that must not be moved from their current node.",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value, description)| PriorityClass {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            ..Default::default()
+        },
+        value,
+        description: Some(description.to_string()),
+        preemption_policy: Some(PREEMPT_LOWER_PRIORITY.to_string()),
+        ..Default::default()
+    })
+    .collect()
+}
+
+/// Priority of `pod` as resolved at admission, 0 if it has none
+pub fn pod_priority(pod: &Pod) -> i32 {
+    pod.spec.as_ref().and_then(|s| s.priority).unwrap_or(0)
+}
+
+/// Order in which pending pods are scheduled: higher priority first, then
+/// the oldest first
+pub fn scheduling_order(a: &Pod, b: &Pod) -> Ordering {
+    pod_priority(b).cmp(&pod_priority(a)).then_with(|| {
+        a.metadata
+            .creation_timestamp
+            .cmp(&b.metadata.creation_timestamp)
+    })
+}
+
+impl Resource for PriorityClass {
+    fn api_version(&self) -> String {
+        SCHEDULING_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        PRIORITY_CLASS_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn is_namespaced(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        let name = self.metadata.name.as_deref().unwrap_or_default();
+        let system = system_priority_classes()
+            .into_iter()
+            .find(|c| c.metadata.name.as_deref() == Some(name));
+        match system {
+            Some(class) if class.value != self.value => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "value of the built-in PriorityClass '{}' must be {}",
+                    name, class.value
+                )));
+            }
+            Some(_) => {}
+            None if name.starts_with("system-") => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "PriorityClass names with the prefix 'system-' are reserved, '{}' is not a built-in class",
+                    name
+                )));
+            }
+            None if self.value > HIGHEST_USER_DEFINABLE_PRIORITY => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "value {} must not exceed {}",
+                    self.value, HIGHEST_USER_DEFINABLE_PRIORITY
+                )));
+            }
+            None => {}
+        }
+        match self.preemption_policy.as_deref() {
+            None | Some(PREEMPT_LOWER_PRIORITY) | Some(PREEMPT_NEVER) => Ok(()),
+            Some(policy) => Err(ResourceError::ValidationFailed(format!(
+                "preemptionPolicy '{}' must be {} or {}",
+                policy, PREEMPT_LOWER_PRIORITY, PREEMPT_NEVER
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::PodSpec;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+    fn class(name: &str, value: i32) -> PriorityClass {
+        PriorityClass {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            value,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_priority_class() {
+        assert!(class("high", 1000).validate().is_ok());
+        assert!(class("high", HIGHEST_USER_DEFINABLE_PRIORITY + 1)
+            .validate()
+            .is_err());
+        assert!(class("system-custom", 1000).validate().is_err());
+        for system in system_priority_classes() {
+            assert!(system.validate().is_ok());
+        }
+        assert!(class(SYSTEM_NODE_CRITICAL, 1000).validate().is_err());
+
+        let mut never = class("batch", -10);
+        never.preemption_policy = Some(PREEMPT_NEVER.to_string());
+        assert!(never.validate().is_ok());
+        never.preemption_policy = Some("Sometimes".to_string());
+        assert!(never.validate().is_err());
+    }
+
+    #[test]
+    fn test_scheduling_order() {
+        let pod = |name: &str, priority: Option<i32>, created: i64| Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                creation_timestamp: chrono::DateTime::from_timestamp(created, 0).map(Time),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                priority,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut pods = [
+            pod("low-old", Some(-5), 1),
+            pod("none-new", None, 3),
+            pod("none-old", None, 2),
+            pod("high-new", Some(100), 4),
+        ];
+        pods.sort_by(scheduling_order);
+        let names: Vec<_> = pods
+            .iter()
+            .map(|p| p.metadata.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["high-new", "none-old", "none-new", "low-old"]);
+    }
+}
@@ -0,0 +1,173 @@
+use super::ResourceQuantities;
+use k8s_openapi::api::core::v1::{Container, Pod};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Pod quality-of-service class, following Kubernetes semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QosClass {
+    /// Every container has CPU and memory limits equal to its requests
+    Guaranteed,
+    /// At least one container has a request or limit, but not Guaranteed
+    Burstable,
+    /// No container sets any CPU or memory request or limit
+    BestEffort,
+}
+
+impl QosClass {
+    /// Kubernetes string form (as used in `status.qosClass`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QosClass::Guaranteed => "Guaranteed",
+            QosClass::Burstable => "Burstable",
+            QosClass::BestEffort => "BestEffort",
+        }
+    }
+
+    /// Parse the Kubernetes string form
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Guaranteed" => Some(QosClass::Guaranteed),
+            "Burstable" => Some(QosClass::Burstable),
+            "BestEffort" => Some(QosClass::BestEffort),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for QosClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Effective (cpu millicores, memory bytes) of a resource list, `None` per
+/// resource when it is not set
+fn cpu_and_memory(map: Option<&BTreeMap<String, Quantity>>) -> (Option<i64>, Option<i64>) {
+    let Some(map) = map else {
+        return (None, None);
+    };
+    let cpu = map
+        .get("cpu")
+        .and_then(|q| ResourceQuantities::parse_cpu(&q.0).ok());
+    let memory = map
+        .get("memory")
+        .and_then(|q| ResourceQuantities::parse_memory(&q.0).ok());
+    (cpu, memory)
+}
+
+fn container_is_guaranteed(container: &Container) -> bool {
+    let resources = container.resources.as_ref();
+    let (limit_cpu, limit_mem) = cpu_and_memory(resources.and_then(|r| r.limits.as_ref()));
+    let (req_cpu, req_mem) = cpu_and_memory(resources.and_then(|r| r.requests.as_ref()));
+
+    // Requests default to limits when unset
+    match (limit_cpu, limit_mem) {
+        (Some(lc), Some(lm)) => req_cpu.unwrap_or(lc) == lc && req_mem.unwrap_or(lm) == lm,
+        _ => false,
+    }
+}
+
+fn container_has_resources(container: &Container) -> bool {
+    let resources = container.resources.as_ref();
+    let (limit_cpu, limit_mem) = cpu_and_memory(resources.and_then(|r| r.limits.as_ref()));
+    let (req_cpu, req_mem) = cpu_and_memory(resources.and_then(|r| r.requests.as_ref()));
+    [limit_cpu, limit_mem, req_cpu, req_mem]
+        .iter()
+        .any(|v| v.is_some_and(|v| v > 0))
+}
+
+/// Classify a pod into its QoS class from its containers' requests and limits
+pub fn pod_qos_class(pod: &Pod) -> QosClass {
+    let containers: &[Container] = pod.spec.as_ref().map(|s| &s.containers[..]).unwrap_or(&[]);
+
+    if containers.is_empty() || !containers.iter().any(container_has_resources) {
+        return QosClass::BestEffort;
+    }
+
+    if containers.iter().all(container_is_guaranteed) {
+        QosClass::Guaranteed
+    } else {
+        QosClass::Burstable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodSpec, ResourceRequirements};
+
+    fn resources(pairs: &[(&str, &str)]) -> Option<BTreeMap<String, Quantity>> {
+        if pairs.is_empty() {
+            return None;
+        }
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), Quantity(v.to_string())))
+                .collect(),
+        )
+    }
+
+    fn pod_with(requests: &[(&str, &str)], limits: &[(&str, &str)]) -> Pod {
+        Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "app".to_string(),
+                    resources: Some(ResourceRequirements {
+                        requests: resources(requests),
+                        limits: resources(limits),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_best_effort() {
+        assert_eq!(pod_qos_class(&pod_with(&[], &[])), QosClass::BestEffort);
+        assert_eq!(pod_qos_class(&Pod::default()), QosClass::BestEffort);
+    }
+
+    #[test]
+    fn test_guaranteed() {
+        let limits = [("cpu", "1"), ("memory", "1Gi")];
+        assert_eq!(pod_qos_class(&pod_with(&[], &limits)), QosClass::Guaranteed);
+        assert_eq!(
+            pod_qos_class(&pod_with(&[("cpu", "1000m"), ("memory", "1Gi")], &limits)),
+            QosClass::Guaranteed
+        );
+    }
+
+    #[test]
+    fn test_burstable() {
+        assert_eq!(
+            pod_qos_class(&pod_with(&[("cpu", "100m")], &[])),
+            QosClass::Burstable
+        );
+        assert_eq!(
+            pod_qos_class(&pod_with(
+                &[("cpu", "500m"), ("memory", "1Gi")],
+                &[("cpu", "1"), ("memory", "1Gi")]
+            )),
+            QosClass::Burstable
+        );
+    }
+
+    #[test]
+    fn test_qos_class_round_trip() {
+        for class in [
+            QosClass::Guaranteed,
+            QosClass::Burstable,
+            QosClass::BestEffort,
+        ] {
+            assert_eq!(QosClass::parse(class.as_str()), Some(class));
+        }
+    }
+}
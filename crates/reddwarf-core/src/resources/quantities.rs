@@ -61,6 +61,26 @@ impl ResourceQuantities {
         }
     }
 
+    /// Total requests of a pod: those of its containers plus the RuntimeClass
+    /// overhead charged on top of them
+    pub fn pod_requests(spec: &k8s_openapi::api::core::v1::PodSpec) -> Self {
+        let mut total = spec
+            .overhead
+            .as_ref()
+            .map(Self::from_k8s_resource_map)
+            .unwrap_or_default();
+        for requests in spec
+            .containers
+            .iter()
+            .filter_map(|c| c.resources.as_ref()?.requests.as_ref())
+        {
+            let requests = Self::from_k8s_resource_map(requests);
+            total.cpu_millicores += requests.cpu_millicores;
+            total.memory_bytes += requests.memory_bytes;
+        }
+        total
+    }
+
     /// Get CPU and memory from a resource map (test format)
     pub fn from_resource_map(resources: &HashMap<String, String>) -> Self {
         let cpu_millicores = resources
@@ -133,6 +153,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pod_requests() {
+        use k8s_openapi::api::core::v1::{Container, PodSpec, ResourceRequirements};
+        use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+        let requests = |cpu: &str, memory: &str| {
+            std::collections::BTreeMap::from([
+                ("cpu".to_string(), Quantity(cpu.to_string())),
+                ("memory".to_string(), Quantity(memory.to_string())),
+            ])
+        };
+        let container = |cpu, memory| Container {
+            resources: Some(ResourceRequirements {
+                requests: Some(requests(cpu, memory)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let spec = PodSpec {
+            containers: vec![
+                container("500m", "128Mi"),
+                container("1", "1Gi"),
+                Container::default(),
+            ],
+            overhead: Some(requests("100m", "64Mi")),
+            ..Default::default()
+        };
+
+        let total = ResourceQuantities::pod_requests(&spec);
+        assert_eq!(total.cpu_millicores, 1600);
+        assert_eq!(total.memory_bytes, (128 + 1024 + 64) * 1024 * 1024);
+    }
+
     #[test]
     fn test_cpu_as_zone_cap() {
         assert_eq!(ResourceQuantities::cpu_as_zone_cap(500), "0.50");
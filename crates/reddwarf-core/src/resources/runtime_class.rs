@@ -0,0 +1,178 @@
+use super::{is_valid_label, validate_base, Resource, ResourceError};
+use crate::annotations::{zone_brand, ZONE_BRAND_ANNOTATION};
+use crate::Pod;
+use k8s_openapi::api::node::v1::RuntimeClass;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// API group/version of RuntimeClass
+pub const RUNTIME_CLASS_API_VERSION: &str = "node.k8s.io/v1";
+
+/// Kind of the RuntimeClass resource
+pub const RUNTIME_CLASS_KIND: &str = "RuntimeClass";
+
+/// Brand of pods that select neither a RuntimeClass nor a brand
+pub const DEFAULT_ZONE_BRAND: &str = "reddwarf";
+
+/// Pods already warned about selecting their brand with the deprecated
+/// annotation, so filtering every node for a pod warns only once
+static DEPRECATED_BRAND_WARNED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Zone brand a pod selects, given the handlers of known RuntimeClasses by
+/// name; `None` if it selects none. Fails if the pod names a RuntimeClass
+/// that does not exist.
+///
+/// A pod without `spec.runtimeClassName` may still select its brand with
+/// the deprecated [`ZONE_BRAND_ANNOTATION`]. That keeps working until the
+/// annotation is removed, with a warning logged the first time for each
+/// pod.
+pub fn pod_zone_brand(
+    pod: &Pod,
+    handlers: &HashMap<String, String>,
+) -> Result<Option<String>, String> {
+    if let Some(class) = pod
+        .spec
+        .as_ref()
+        .and_then(|s| s.runtime_class_name.as_deref())
+    {
+        return match handlers.get(class) {
+            Some(handler) => Ok(Some(handler.clone())),
+            None => Err(format!("RuntimeClass '{}' not found", class)),
+        };
+    }
+
+    let Some(brand) = zone_brand(&pod.metadata) else {
+        return Ok(None);
+    };
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+    let name = pod.metadata.name.as_deref().unwrap_or_default();
+    let pod_key = pod
+        .metadata
+        .uid
+        .clone()
+        .unwrap_or_else(|| format!("{}/{}", namespace, name));
+    if DEPRECATED_BRAND_WARNED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(pod_key)
+    {
+        warn!(
+            "Pod {}/{} selects zone brand '{}' with the deprecated {} annotation; set spec.runtimeClassName instead",
+            namespace, name, brand, ZONE_BRAND_ANNOTATION
+        );
+    }
+    Ok(Some(brand.to_string()))
+}
+
+impl Resource for RuntimeClass {
+    fn api_version(&self) -> String {
+        RUNTIME_CLASS_API_VERSION.to_string()
+    }
+
+    fn kind(&self) -> String {
+        RUNTIME_CLASS_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn is_namespaced(&self) -> bool {
+        false
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+        if !is_valid_label(&self.handler) {
+            return Err(ResourceError::ValidationFailed(format!(
+                "handler '{}' must be a DNS label naming a zone brand",
+                self.handler
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::PodSpec;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    /// Log output written into a shared buffer
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pod_zone_brand() {
+        let handlers = HashMap::from([("linux".to_string(), "lx".to_string())]);
+
+        let mut pod = Pod::default();
+        assert_eq!(pod_zone_brand(&pod, &handlers).unwrap(), None);
+
+        pod.metadata.annotations =
+            Some([(ZONE_BRAND_ANNOTATION.to_string(), "lx".to_string())].into());
+        assert_eq!(
+            pod_zone_brand(&pod, &handlers).unwrap().as_deref(),
+            Some("lx")
+        );
+
+        pod.metadata.annotations = None;
+        pod.spec = Some(PodSpec {
+            runtime_class_name: Some("linux".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            pod_zone_brand(&pod, &handlers).unwrap().as_deref(),
+            Some("lx")
+        );
+
+        pod.spec.as_mut().unwrap().runtime_class_name = Some("gvisor".to_string());
+        assert!(pod_zone_brand(&pod, &handlers).is_err());
+    }
+
+    #[test]
+    fn test_deprecated_brand_annotation_warns_once() {
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("legacy-web".to_string());
+        pod.metadata.uid = Some("legacy-web-uid".to_string());
+        pod.metadata.annotations =
+            Some([(ZONE_BRAND_ANNOTATION.to_string(), "lx".to_string())].into());
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                let brand = pod_zone_brand(&pod, &HashMap::new()).unwrap();
+                assert_eq!(brand.as_deref(), Some("lx"));
+            }
+        });
+
+        let logs = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let warnings: Vec<&str> = logs.lines().filter(|l| l.contains("WARN")).collect();
+        assert_eq!(warnings.len(), 1, "{}", logs);
+        assert!(warnings[0].contains("default/legacy-web"));
+        assert!(warnings[0].contains("deprecated reddwarf.io/zone-brand annotation"));
+    }
+}
@@ -0,0 +1,342 @@
+//! Structural OpenAPI v3 schemas of custom resources: validating objects
+//! against them, pruning the fields they don't declare and applying their
+//! defaults, as the Kubernetes API server does for CustomResourceDefinitions
+
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::{
+    JSONSchemaProps, JSONSchemaPropsOrArray, JSONSchemaPropsOrBool,
+};
+use serde_json::Value;
+
+/// Fields of every object that its schema need not declare
+const OBJECT_FIELDS: &[&str] = &["apiVersion", "kind", "metadata"];
+
+/// Check `object` against the `schema` of its kind, dropping the fields the
+/// schema doesn't declare and filling in the defaults of those it omits.
+/// `apiVersion`, `kind` and `metadata` are left alone.
+///
+/// `pattern` and `format` are not checked.
+pub fn apply_schema(schema: &JSONSchemaProps, object: &mut Value) -> Result<(), String> {
+    let Some(fields) = object.as_object_mut() else {
+        return Err("object must be of type object".to_string());
+    };
+    let mut kept = serde_json::Map::new();
+    for field in OBJECT_FIELDS {
+        if let Some(value) = fields.remove(*field) {
+            kept.insert(field.to_string(), value);
+        }
+    }
+    let result = apply(schema, object, "");
+    if let Some(fields) = object.as_object_mut() {
+        fields.extend(kept);
+    }
+    result
+}
+
+/// Apply `schema` to the `value` at `path`
+fn apply(schema: &JSONSchemaProps, value: &mut Value, path: &str) -> Result<(), String> {
+    let at = if path.is_empty() { "<root>" } else { path };
+    if value.is_null() {
+        return match schema.nullable {
+            Some(true) => Ok(()),
+            _ if schema.type_.is_none() && !is_preserving(schema) => Ok(()),
+            _ => Err(format!("{}: must not be null", at)),
+        };
+    }
+
+    if schema.x_kubernetes_int_or_string == Some(true) {
+        if !value.is_string() && !is_integer(value) {
+            return Err(format!("{}: must be an integer or a string", at));
+        }
+    } else if let Some(type_) = schema.type_.as_deref() {
+        let matches = match type_ {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => is_integer(value),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            _ => return Err(format!("{}: unknown type '{}' in schema", at, type_)),
+        };
+        if !matches {
+            return Err(format!("{}: must be of type {}", at, type_));
+        }
+    }
+
+    if let Some(allowed) = &schema.enum_ {
+        if !allowed.iter().any(|a| a.0 == *value) {
+            let allowed: Vec<String> = allowed.iter().map(|a| a.0.to_string()).collect();
+            return Err(format!(
+                "{}: must be one of {}, got {}",
+                at,
+                allowed.join(", "),
+                value
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(fields) => apply_object(schema, fields, path)?,
+        Value::Array(items) => apply_array(schema, items, path)?,
+        Value::String(s) => {
+            let length = s.chars().count() as i64;
+            if schema.min_length.is_some_and(|min| length < min) {
+                return Err(format!(
+                    "{}: must be at least {} characters",
+                    at,
+                    schema.min_length.unwrap_or_default()
+                ));
+            }
+            if schema.max_length.is_some_and(|max| length > max) {
+                return Err(format!(
+                    "{}: must be at most {} characters",
+                    at,
+                    schema.max_length.unwrap_or_default()
+                ));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.minimum {
+                let exclusive = schema.exclusive_minimum == Some(true);
+                if n < min || (exclusive && n == min) {
+                    return Err(format!(
+                        "{}: must be greater than {}{}",
+                        at,
+                        if exclusive { "" } else { "or equal to " },
+                        min
+                    ));
+                }
+            }
+            if let Some(max) = schema.maximum {
+                let exclusive = schema.exclusive_maximum == Some(true);
+                if n > max || (exclusive && n == max) {
+                    return Err(format!(
+                        "{}: must be less than {}{}",
+                        at,
+                        if exclusive { "" } else { "or equal to " },
+                        max
+                    ));
+                }
+            }
+            if let Some(multiple) = schema.multiple_of.filter(|m| *m > 0.0) {
+                if (n / multiple).fract() != 0.0 {
+                    return Err(format!("{}: must be a multiple of {}", at, multiple));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    // Junctors validate without pruning, as in Kubernetes
+    for sub in schema.all_of.iter().flatten() {
+        apply(sub, &mut value.clone(), path)?;
+    }
+    if let Some(any_of) = &schema.any_of {
+        if !any_of
+            .iter()
+            .any(|sub| apply(sub, &mut value.clone(), path).is_ok())
+        {
+            return Err(format!("{}: must match at least one schema of anyOf", at));
+        }
+    }
+    if let Some(one_of) = &schema.one_of {
+        let matching = one_of
+            .iter()
+            .filter(|sub| apply(sub, &mut value.clone(), path).is_ok())
+            .count();
+        if matching != 1 {
+            return Err(format!("{}: must match exactly one schema of oneOf", at));
+        }
+    }
+    if let Some(not) = &schema.not {
+        if apply(not, &mut value.clone(), path).is_ok() {
+            return Err(format!("{}: must not match the schema of not", at));
+        }
+    }
+    Ok(())
+}
+
+fn apply_object(
+    schema: &JSONSchemaProps,
+    fields: &mut serde_json::Map<String, Value>,
+    path: &str,
+) -> Result<(), String> {
+    let properties = schema.properties.as_ref();
+    for (name, property) in properties.into_iter().flatten() {
+        if let (None, Some(default)) = (fields.get(name), &property.default) {
+            fields.insert(name.clone(), default.0.clone());
+        }
+    }
+    for name in schema.required.iter().flatten() {
+        if !fields.contains_key(name) {
+            return Err(format!("{}.{}: Required value", path, name));
+        }
+    }
+
+    let additional = match &schema.additional_properties {
+        Some(JSONSchemaPropsOrBool::Schema(additional)) => Some(additional.as_ref()),
+        _ => None,
+    };
+    let allow_unknown = is_preserving(schema)
+        || matches!(
+            schema.additional_properties,
+            Some(JSONSchemaPropsOrBool::Bool(true))
+        );
+    let mut unknown = Vec::new();
+    for (name, value) in fields.iter_mut() {
+        let field_path = format!("{}.{}", path, name);
+        match properties.and_then(|p| p.get(name)).or(additional) {
+            Some(property) => apply(property, value, &field_path)?,
+            None if allow_unknown => {}
+            None => unknown.push(name.clone()),
+        }
+    }
+    for name in unknown {
+        fields.remove(&name);
+    }
+
+    let count = fields.len() as i64;
+    if let Some(min) = schema.min_properties.filter(|min| count < *min) {
+        return Err(format!("{}: must have at least {} properties", path, min));
+    }
+    if let Some(max) = schema.max_properties.filter(|max| count > *max) {
+        return Err(format!("{}: must have at most {} properties", path, max));
+    }
+    Ok(())
+}
+
+fn apply_array(schema: &JSONSchemaProps, items: &mut [Value], path: &str) -> Result<(), String> {
+    let count = items.len() as i64;
+    if schema.min_items.is_some_and(|min| count < min) {
+        return Err(format!(
+            "{}: must have at least {} items",
+            path,
+            schema.min_items.unwrap_or_default()
+        ));
+    }
+    if schema.max_items.is_some_and(|max| count > max) {
+        return Err(format!(
+            "{}: must have at most {} items",
+            path,
+            schema.max_items.unwrap_or_default()
+        ));
+    }
+    if schema.unique_items == Some(true) {
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].contains(item) {
+                return Err(format!("{}[{}]: duplicate item", path, i));
+            }
+        }
+    }
+    if let Some(JSONSchemaPropsOrArray::Schema(item_schema)) = &schema.items {
+        for (i, item) in items.iter_mut().enumerate() {
+            apply(item_schema, item, &format!("{}[{}]", path, i))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether fields the schema doesn't declare are kept
+fn is_preserving(schema: &JSONSchemaProps) -> bool {
+    schema.x_kubernetes_preserve_unknown_fields == Some(true)
+}
+
+fn is_integer(value: &Value) -> bool {
+    value.is_i64() || value.is_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> JSONSchemaProps {
+        serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "spec": {
+                    "type": "object",
+                    "required": ["image"],
+                    "properties": {
+                        "image": {"type": "string", "minLength": 1},
+                        "replicas": {"type": "integer", "minimum": 0, "default": 1},
+                        "mode": {"type": "string", "enum": ["fast", "safe"]},
+                        "ports": {"type": "array", "items": {"type": "integer"}, "maxItems": 2},
+                        "labels": {"type": "object", "additionalProperties": {"type": "string"}},
+                        "extra": {"type": "object", "x-kubernetes-preserve-unknown-fields": true}
+                    }
+                },
+                "status": {"type": "object", "properties": {"ready": {"type": "boolean"}}}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_schema_prunes_and_defaults() {
+        let mut object = json!({
+            "apiVersion": "example.com/v1",
+            "kind": "Widget",
+            "metadata": {"name": "w"},
+            "spec": {
+                "image": "nginx",
+                "unknown": true,
+                "labels": {"app": "web"},
+                "extra": {"anything": [1, 2]}
+            },
+            "junk": 1
+        });
+        apply_schema(&schema(), &mut object).unwrap();
+        assert_eq!(
+            object,
+            json!({
+                "apiVersion": "example.com/v1",
+                "kind": "Widget",
+                "metadata": {"name": "w"},
+                "spec": {
+                    "image": "nginx",
+                    "replicas": 1,
+                    "labels": {"app": "web"},
+                    "extra": {"anything": [1, 2]}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_schema_rejects_invalid_values() {
+        let invalid = [
+            (json!({"spec": {}}), ".spec.image: Required value"),
+            (json!({"spec": {"image": ""}}), ".spec.image"),
+            (
+                json!({"spec": {"image": "a", "replicas": -1}}),
+                ".spec.replicas",
+            ),
+            (json!({"spec": {"image": "a", "replicas": 1.5}}), "integer"),
+            (
+                json!({"spec": {"image": "a", "mode": "slow"}}),
+                ".spec.mode",
+            ),
+            (
+                json!({"spec": {"image": "a", "ports": [1, 2, 3]}}),
+                ".spec.ports",
+            ),
+            (
+                json!({"spec": {"image": "a", "ports": ["80"]}}),
+                ".spec.ports[0]",
+            ),
+            (
+                json!({"spec": {"image": "a", "labels": {"app": 1}}}),
+                ".spec.labels.app",
+            ),
+            (
+                json!({"status": {"ready": "yes"}, "spec": {"image": "a"}}),
+                ".status.ready",
+            ),
+        ];
+        for (mut object, expected) in invalid {
+            let error = apply_schema(&schema(), &mut object).unwrap_err();
+            assert!(error.contains(expected), "{} lacks {}", error, expected);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use super::config_map::is_valid_config_key;
+use super::{validate_base, Resource, ResourceError};
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::ByteString;
+
+/// Kind of the Secret resource
+pub const SECRET_KIND: &str = "Secret";
+
+/// Most bytes the keys and values of a Secret may add up to
+pub const MAX_SECRET_SIZE: usize = 1024 * 1024;
+
+/// Type of Secrets that don't set one
+pub const SECRET_TYPE_OPAQUE: &str = "Opaque";
+
+/// Type of Secrets holding registry credentials, used as image pull secrets
+pub const SECRET_TYPE_DOCKER_CONFIG_JSON: &str = "kubernetes.io/dockerconfigjson";
+
+/// Type of Secrets holding a TLS certificate and key
+pub const SECRET_TYPE_TLS: &str = "kubernetes.io/tls";
+
+/// Keys a Secret of type `type_` must hold
+fn required_keys(type_: &str) -> &'static [&'static str] {
+    match type_ {
+        SECRET_TYPE_DOCKER_CONFIG_JSON => &[".dockerconfigjson"],
+        SECRET_TYPE_TLS => &["tls.crt", "tls.key"],
+        _ => &[],
+    }
+}
+
+/// Type of `secret`, `Opaque` when unset
+pub fn secret_type(secret: &Secret) -> &str {
+    secret.type_.as_deref().unwrap_or(SECRET_TYPE_OPAQUE)
+}
+
+/// Bytes of the keys and values held in `data` and `stringData`
+pub fn secret_size(secret: &Secret) -> usize {
+    let data: usize = secret
+        .data
+        .iter()
+        .flatten()
+        .map(|(key, value)| key.len() + value.0.len())
+        .sum();
+    let string_data: usize = secret
+        .string_data
+        .iter()
+        .flatten()
+        .map(|(key, value)| key.len() + value.len())
+        .sum();
+    data + string_data
+}
+
+/// Fold the write-only `stringData` of `secret` into `data`, where its
+/// entries take precedence, as the API server does before storing it
+pub fn merge_string_data(secret: &mut Secret) {
+    let Some(string_data) = secret.string_data.take() else {
+        return;
+    };
+    let data = secret.data.get_or_insert_with(Default::default);
+    for (key, value) in string_data {
+        data.insert(key, ByteString(value.into_bytes()));
+    }
+}
+
+impl Resource for Secret {
+    fn api_version(&self) -> String {
+        "v1".to_string()
+    }
+
+    fn kind(&self) -> String {
+        SECRET_KIND.to_string()
+    }
+
+    fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+
+    fn validate(&self) -> Result<(), ResourceError> {
+        validate_base(&self.metadata)?;
+
+        let data_keys = self.data.iter().flatten().map(|(key, _)| key);
+        let string_keys = self.string_data.iter().flatten().map(|(key, _)| key);
+        for key in data_keys.chain(string_keys) {
+            if !is_valid_config_key(key) {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "Secret key '{}' must consist of alphanumerics, '-', '_' or '.'",
+                    key
+                )));
+            }
+        }
+
+        let type_ = secret_type(self);
+        for key in required_keys(type_) {
+            let in_data = self.data.as_ref().is_some_and(|d| d.contains_key(*key));
+            let in_string_data = self
+                .string_data
+                .as_ref()
+                .is_some_and(|d| d.contains_key(*key));
+            if !in_data && !in_string_data {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "Secret of type {} must have the key '{}'",
+                    type_, key
+                )));
+            }
+        }
+
+        let size = secret_size(self);
+        if size > MAX_SECRET_SIZE {
+            return Err(ResourceError::ValidationFailed(format!(
+                "Secret data is {} bytes, must have at most {} bytes",
+                size, MAX_SECRET_SIZE
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn secret(type_: Option<&str>) -> Secret {
+        Secret {
+            metadata: ObjectMeta {
+                name: Some("creds".to_string()),
+                ..Default::default()
+            },
+            type_: type_.map(String::from),
+            data: Some(BTreeMap::from([(
+                "password".to_string(),
+                ByteString(b"hunter2".to_vec()),
+            )])),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_secret_validation() {
+        let mut opaque = secret(None);
+        assert!(opaque.validate().is_ok());
+        assert_eq!(secret_type(&opaque), SECRET_TYPE_OPAQUE);
+        assert_eq!(secret_size(&opaque), 8 + 7);
+
+        opaque
+            .data
+            .as_mut()
+            .unwrap()
+            .insert("bad/key".to_string(), ByteString(Vec::new()));
+        assert!(opaque.validate().is_err());
+
+        // Typed Secrets must hold their well-known keys
+        let mut tls = secret(Some(SECRET_TYPE_TLS));
+        let err = tls.validate().unwrap_err();
+        assert!(err.to_string().contains("tls.crt"));
+        tls.string_data = Some(BTreeMap::from([
+            ("tls.crt".to_string(), "cert".to_string()),
+            ("tls.key".to_string(), "key".to_string()),
+        ]));
+        assert!(tls.validate().is_ok());
+
+        let mut oversized = secret(None);
+        oversized.data = Some(BTreeMap::from([(
+            "blob".to_string(),
+            ByteString(vec![0; MAX_SECRET_SIZE]),
+        )]));
+        assert!(oversized.validate().is_err());
+    }
+
+    #[test]
+    fn test_merge_string_data() {
+        let mut secret = secret(None);
+        secret.string_data = Some(BTreeMap::from([
+            ("password".to_string(), "s3cret".to_string()),
+            ("user".to_string(), "admin".to_string()),
+        ]));
+
+        merge_string_data(&mut secret);
+
+        assert!(secret.string_data.is_none());
+        let data = secret.data.unwrap();
+        assert_eq!(data["password"].0, b"s3cret");
+        assert_eq!(data["user"].0, b"admin");
+    }
+}
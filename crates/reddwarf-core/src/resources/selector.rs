@@ -0,0 +1,115 @@
+use super::ResourceError;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use std::collections::BTreeMap;
+
+/// Whether `selector` selects nothing: no labels and no expressions
+pub fn selector_is_empty(selector: &LabelSelector) -> bool {
+    selector.match_labels.as_ref().is_none_or(|l| l.is_empty())
+        && selector
+            .match_expressions
+            .as_ref()
+            .is_none_or(|e| e.is_empty())
+}
+
+/// Whether an object carrying `labels` is selected by `selector`
+///
+/// An empty selector matches everything, as in Kubernetes; callers that
+/// must not select every object check [`selector_is_empty`] first.
+pub fn selector_matches(
+    selector: &LabelSelector,
+    labels: Option<&BTreeMap<String, String>>,
+) -> bool {
+    let empty = BTreeMap::new();
+    let labels = labels.unwrap_or(&empty);
+
+    let labels_match = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .all(|(key, value)| labels.get(key) == Some(value));
+
+    labels_match
+        && selector.match_expressions.iter().flatten().all(|expr| {
+            let value = labels.get(&expr.key);
+            let values = expr.values.as_deref().unwrap_or_default();
+            match expr.operator.as_str() {
+                "In" => value.is_some_and(|v| values.contains(v)),
+                "NotIn" => value.is_none_or(|v| !values.contains(v)),
+                "Exists" => value.is_some(),
+                "DoesNotExist" => value.is_none(),
+                _ => false,
+            }
+        })
+}
+
+/// Check the operators and values of a selector's expressions
+pub fn validate_selector(selector: &LabelSelector) -> Result<(), ResourceError> {
+    for expr in selector.match_expressions.iter().flatten() {
+        let has_values = expr.values.as_ref().is_some_and(|v| !v.is_empty());
+        match (expr.operator.as_str(), has_values) {
+            ("In" | "NotIn", true) | ("Exists" | "DoesNotExist", false) => {}
+            ("In" | "NotIn", false) => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "selector operator {} on '{}' needs values",
+                    expr.operator, expr.key
+                )))
+            }
+            ("Exists" | "DoesNotExist", true) => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "selector operator {} on '{}' takes no values",
+                    expr.operator, expr.key
+                )))
+            }
+            (operator, _) => {
+                return Err(ResourceError::ValidationFailed(format!(
+                    "unknown selector operator '{}'",
+                    operator
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelectorRequirement;
+
+    #[test]
+    fn test_selector_matches() {
+        let labels = BTreeMap::from([
+            ("app".to_string(), "web".to_string()),
+            ("tier".to_string(), "frontend".to_string()),
+        ]);
+        let requirement = |key: &str, operator: &str, values: &[&str]| LabelSelectorRequirement {
+            key: key.to_string(),
+            operator: operator.to_string(),
+            values: (!values.is_empty()).then(|| values.iter().map(|v| v.to_string()).collect()),
+        };
+
+        let mut selector = LabelSelector::default();
+        assert!(selector_is_empty(&selector));
+        assert!(selector_matches(&selector, None));
+
+        selector.match_labels = Some(BTreeMap::from([("app".to_string(), "web".to_string())]));
+        assert!(selector_matches(&selector, Some(&labels)));
+        assert!(!selector_matches(&selector, None));
+
+        selector.match_expressions = Some(vec![
+            requirement("tier", "In", &["frontend", "edge"]),
+            requirement("canary", "DoesNotExist", &[]),
+        ]);
+        assert!(validate_selector(&selector).is_ok());
+        assert!(selector_matches(&selector, Some(&labels)));
+
+        selector.match_expressions = Some(vec![requirement("tier", "NotIn", &["frontend"])]);
+        assert!(!selector_matches(&selector, Some(&labels)));
+
+        selector.match_expressions = Some(vec![requirement("tier", "Exists", &["frontend"])]);
+        assert!(validate_selector(&selector).is_err());
+        selector.match_expressions = Some(vec![requirement("tier", "Matches", &["x"])]);
+        assert!(validate_selector(&selector).is_err());
+        assert!(!selector_matches(&selector, Some(&labels)));
+    }
+}
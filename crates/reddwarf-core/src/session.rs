@@ -0,0 +1,90 @@
+//! Interactive sessions with a process in a pod's zone, behind the `exec`
+//! and `attach` subresources
+//!
+//! Input flows to the process and output back over channels, so the API
+//! server relays a session to its client without knowing how the runtime
+//! reaches the process.
+
+use tokio::sync::mpsc;
+
+/// Messages buffered in each direction of a session
+const SESSION_BUFFER: usize = 64;
+
+/// What a session connects to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionTarget {
+    /// A new process running the command in the container's zone
+    Exec(Vec<String>),
+    /// The container's own process, which must have been started with
+    /// `stdin` or `tty`
+    Attach,
+}
+
+/// A session requested with a container of a pod
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRequest {
+    /// Name of the container
+    pub container: String,
+    pub target: SessionTarget,
+    /// Pass the client's input to the process
+    pub stdin: bool,
+    /// Run the process on a pseudo-terminal; all its output then arrives as
+    /// stdout
+    pub tty: bool,
+}
+
+/// Size of a session's terminal, in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Input from the client to the process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionInput {
+    Stdin(Vec<u8>),
+    /// End of input; the process reads EOF
+    CloseStdin,
+    /// The client's terminal was resized
+    Resize(TerminalSize),
+}
+
+/// Output from the process to the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionOutput {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    /// The process exited with this status; nothing follows
+    Exit(i32),
+}
+
+/// The client's end of a session
+pub struct ProcessSession {
+    pub input: mpsc::Sender<SessionInput>,
+    pub output: mpsc::Receiver<SessionOutput>,
+}
+
+/// The process's end of a session, driven by the runtime
+pub struct SessionEnd {
+    pub input: mpsc::Receiver<SessionInput>,
+    pub output: mpsc::Sender<SessionOutput>,
+}
+
+impl ProcessSession {
+    /// A client end connected to a process end
+    pub fn pair() -> (ProcessSession, SessionEnd) {
+        let (input_tx, input_rx) = mpsc::channel(SESSION_BUFFER);
+        let (output_tx, output_rx) = mpsc::channel(SESSION_BUFFER);
+        (
+            ProcessSession {
+                input: input_tx,
+                output: output_rx,
+            },
+            SessionEnd {
+                input: input_rx,
+                output: output_tx,
+            },
+        )
+    }
+}
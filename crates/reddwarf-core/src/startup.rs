@@ -0,0 +1,277 @@
+//! Pod startup stages: when a pod was created, scheduled, started
+//! provisioning, had its zone booted and became ready, recorded on the pod,
+//! and the latency of each stage
+
+use crate::metrics::{LatencySummary, Metrics};
+use crate::Pod;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+
+/// When the scheduler bound the pod to a node
+pub const SCHEDULED_AT_ANNOTATION: &str = "reddwarf.io/scheduled-at";
+
+/// When the node started provisioning the pod's zone
+pub const PROVISION_STARTED_AT_ANNOTATION: &str = "status.reddwarf.io/provision-started-at";
+
+/// When the pod's zone finished booting
+pub const ZONE_BOOTED_AT_ANNOTATION: &str = "status.reddwarf.io/zone-booted-at";
+
+/// When the node reported the pod Ready
+pub const READY_AT_ANNOTATION: &str = "status.reddwarf.io/ready-at";
+
+/// Histogram of the latency of each startup stage, labelled by `stage`
+pub const STARTUP_STAGE_METRIC: &str = "reddwarf_pod_startup_stage_seconds";
+
+/// Histogram of the latency from pod creation to Ready
+pub const STARTUP_METRIC: &str = "reddwarf_pod_startup_seconds";
+
+/// Format a stage timestamp for its annotation
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Stage timestamps of one pod
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PodStartup {
+    pub created: Option<DateTime<Utc>>,
+    pub scheduled: Option<DateTime<Utc>>,
+    pub provision_started: Option<DateTime<Utc>>,
+    pub zone_booted: Option<DateTime<Utc>>,
+    pub ready: Option<DateTime<Utc>>,
+}
+
+impl PodStartup {
+    /// Read the timestamps recorded on a pod
+    pub fn from_pod(pod: &Pod) -> Self {
+        let annotation = |key: &str| {
+            pod.metadata
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get(key))
+                .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+                .map(|t| t.with_timezone(&Utc))
+        };
+        Self {
+            created: pod.metadata.creation_timestamp.as_ref().map(|t| t.0),
+            scheduled: annotation(SCHEDULED_AT_ANNOTATION),
+            provision_started: annotation(PROVISION_STARTED_AT_ANNOTATION),
+            zone_booted: annotation(ZONE_BOOTED_AT_ANNOTATION),
+            ready: annotation(READY_AT_ANNOTATION),
+        }
+    }
+
+    /// Annotations recording the stages a node observes (provisioning,
+    /// zone boot and readiness), for the pod's status update
+    pub fn status_annotations(&self) -> BTreeMap<String, String> {
+        [
+            (PROVISION_STARTED_AT_ANNOTATION, self.provision_started),
+            (ZONE_BOOTED_AT_ANNOTATION, self.zone_booted),
+            (READY_AT_ANNOTATION, self.ready),
+        ]
+        .into_iter()
+        .filter_map(|(key, time)| Some((key.to_string(), format_timestamp(time?))))
+        .collect()
+    }
+
+    /// Seconds spent in each stage whose start and end were both recorded:
+    /// `scheduling` (created → scheduled), `provision_queue` (scheduled →
+    /// provision started), `zone_boot` (provision started → zone booted)
+    /// and `ready` (zone booted → Ready)
+    pub fn stage_seconds(&self) -> Vec<(&'static str, f64)> {
+        [
+            ("scheduling", self.created, self.scheduled),
+            ("provision_queue", self.scheduled, self.provision_started),
+            ("zone_boot", self.provision_started, self.zone_booted),
+            ("ready", self.zone_booted, self.ready),
+        ]
+        .into_iter()
+        .filter_map(|(stage, start, end)| Some((stage, seconds_between(start?, end?))))
+        .collect()
+    }
+
+    /// Seconds from creation to Ready, if both were recorded
+    pub fn total_seconds(&self) -> Option<f64> {
+        Some(seconds_between(self.created?, self.ready?))
+    }
+
+    /// Record the pod's stage latencies in `metrics`
+    pub fn record(&self, metrics: &Metrics) {
+        for (stage, seconds) in self.stage_seconds() {
+            metrics.observe(
+                STARTUP_STAGE_METRIC,
+                "Latency of each pod startup stage",
+                &[("stage", stage)],
+                seconds,
+            );
+        }
+        if let Some(seconds) = self.total_seconds() {
+            metrics.observe(
+                STARTUP_METRIC,
+                "Latency from pod creation to Ready",
+                &[],
+                seconds,
+            );
+        }
+    }
+}
+
+fn seconds_between(start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    (end - start).num_milliseconds().max(0) as f64 / 1000.0
+}
+
+/// Latency percentiles of one stage across pods
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageSummary {
+    /// Stage name, or `total` for creation → Ready
+    pub stage: &'static str,
+    pub count: usize,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// Summarize the stage latencies of `pods`, skipping stages none of them
+/// recorded
+pub fn summarize_startup(pods: &[PodStartup]) -> Vec<StageSummary> {
+    let mut stages: Vec<(&'static str, Vec<f64>)> = [
+        "scheduling",
+        "provision_queue",
+        "zone_boot",
+        "ready",
+        "total",
+    ]
+    .into_iter()
+    .map(|stage| (stage, Vec::new()))
+    .collect();
+
+    for pod in pods {
+        let mut seconds = pod.stage_seconds();
+        if let Some(total) = pod.total_seconds() {
+            seconds.push(("total", total));
+        }
+        for (stage, value) in seconds {
+            if let Some((_, values)) = stages.iter_mut().find(|(s, _)| *s == stage) {
+                values.push(value);
+            }
+        }
+    }
+
+    stages
+        .into_iter()
+        .filter_map(|(stage, values)| {
+            let summary = LatencySummary::from_values(values)?;
+            Some(StageSummary {
+                stage,
+                count: summary.count,
+                p50: summary.p50,
+                p90: summary.p90,
+                p99: summary.p99,
+                max: summary.max,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+    fn pod_with_stages(created: DateTime<Utc>, offsets: [i64; 4]) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.creation_timestamp = Some(Time(created));
+        let keys = [
+            SCHEDULED_AT_ANNOTATION,
+            PROVISION_STARTED_AT_ANNOTATION,
+            ZONE_BOOTED_AT_ANNOTATION,
+            READY_AT_ANNOTATION,
+        ];
+        pod.metadata.annotations = Some(
+            keys.iter()
+                .zip(offsets)
+                .map(|(k, ms)| {
+                    (
+                        k.to_string(),
+                        format_timestamp(created + Duration::milliseconds(ms)),
+                    )
+                })
+                .collect(),
+        );
+        pod
+    }
+
+    #[test]
+    fn test_stage_seconds_and_metrics() {
+        let created: DateTime<Utc> = "2026-01-01T00:00:00Z".parse().unwrap();
+        let pod = pod_with_stages(created, [500, 1500, 31_500, 32_000]);
+
+        let startup = PodStartup::from_pod(&pod);
+        assert_eq!(
+            startup.stage_seconds(),
+            vec![
+                ("scheduling", 0.5),
+                ("provision_queue", 1.0),
+                ("zone_boot", 30.0),
+                ("ready", 0.5)
+            ]
+        );
+        assert_eq!(startup.total_seconds(), Some(32.0));
+        assert_eq!(
+            startup.status_annotations().get(READY_AT_ANNOTATION),
+            pod.metadata
+                .annotations
+                .as_ref()
+                .unwrap()
+                .get(READY_AT_ANNOTATION)
+        );
+        assert_eq!(startup.status_annotations().len(), 3);
+
+        let metrics = Metrics::new();
+        startup.record(&metrics);
+        let boot = metrics
+            .histogram(STARTUP_STAGE_METRIC, &[("stage", "zone_boot")])
+            .unwrap();
+        assert_eq!(boot.sum(), 30.0);
+        assert_eq!(metrics.histogram(STARTUP_METRIC, &[]).unwrap().count(), 1);
+
+        // Stages missing either end are skipped
+        let unscheduled = PodStartup {
+            created: Some(created),
+            ..Default::default()
+        };
+        assert!(unscheduled.stage_seconds().is_empty());
+        assert_eq!(unscheduled.total_seconds(), None);
+    }
+
+    #[test]
+    fn test_summarize_startup() {
+        let created: DateTime<Utc> = "2026-01-01T00:00:00Z".parse().unwrap();
+        let pods: Vec<PodStartup> = (1..=10)
+            .map(|i| {
+                PodStartup::from_pod(&pod_with_stages(
+                    created,
+                    [100, 200, 200 + i * 1000, 200 + i * 1000],
+                ))
+            })
+            .collect();
+
+        let summary = summarize_startup(&pods);
+        assert_eq!(
+            summary.iter().map(|s| s.stage).collect::<Vec<_>>(),
+            vec![
+                "scheduling",
+                "provision_queue",
+                "zone_boot",
+                "ready",
+                "total"
+            ]
+        );
+        let boot = &summary[2];
+        assert_eq!(boot.count, 10);
+        assert_eq!(boot.p50, 5.0);
+        assert_eq!(boot.p90, 9.0);
+        assert_eq!(boot.max, 10.0);
+    }
+}
@@ -0,0 +1,244 @@
+//! Why a pod's containers terminated, as a fixed set of machine-readable
+//! reasons set both as the pod's `status.reason` and as the `reason` of
+//! every container's terminated state, next to the exit code, so automation
+//! can branch on them instead of parsing messages
+
+use crate::Pod;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::{
+    ContainerState, ContainerStateTerminated, ContainerStatus, PodStatus,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use std::fmt;
+
+/// Exit code of a process killed with SIGKILL, reported when the runtime
+/// killed the containers and knows no exit code of its own
+pub const KILLED_EXIT_CODE: i32 = 137;
+
+/// Why a pod's containers terminated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TerminationReason {
+    /// Exited with status 0
+    Completed,
+    /// Exited with a non-zero status, or went down for an unknown reason
+    Error,
+    /// Killed for exceeding its memory limit
+    OOMKilled,
+    /// Evicted by the node to reclaim resources
+    Evicted,
+    /// Stopped because its node shut down
+    NodeShutdown,
+    /// Killed after failing its liveness probe
+    ProbeFailure,
+}
+
+impl TerminationReason {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TerminationReason::Completed => "Completed",
+            TerminationReason::Error => "Error",
+            TerminationReason::OOMKilled => "OOMKilled",
+            TerminationReason::Evicted => "Evicted",
+            TerminationReason::NodeShutdown => "NodeShutdown",
+            TerminationReason::ProbeFailure => "ProbeFailure",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Completed" => Some(TerminationReason::Completed),
+            "Error" => Some(TerminationReason::Error),
+            "OOMKilled" => Some(TerminationReason::OOMKilled),
+            "Evicted" => Some(TerminationReason::Evicted),
+            "NodeShutdown" => Some(TerminationReason::NodeShutdown),
+            "ProbeFailure" => Some(TerminationReason::ProbeFailure),
+            _ => None,
+        }
+    }
+
+    /// Exit code reported when the runtime does not know one
+    pub const fn default_exit_code(&self) -> i32 {
+        match self {
+            TerminationReason::Completed => 0,
+            TerminationReason::Error => 1,
+            _ => KILLED_EXIT_CODE,
+        }
+    }
+
+    /// Phase of a pod whose containers terminated for this reason
+    pub const fn pod_phase(&self) -> &'static str {
+        match self {
+            TerminationReason::Completed => "Succeeded",
+            _ => "Failed",
+        }
+    }
+}
+
+impl fmt::Display for TerminationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The reason a terminated pod reports, if it reports one of ours
+pub fn termination_reason(pod: &Pod) -> Option<TerminationReason> {
+    TerminationReason::parse(pod.status.as_ref()?.reason.as_deref()?)
+}
+
+/// How a pod's containers terminated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Termination {
+    pub reason: TerminationReason,
+    pub exit_code: i32,
+    pub message: Option<String>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl Termination {
+    /// Terminated now for `reason`, with its default exit code
+    pub fn new(reason: TerminationReason, message: impl Into<String>) -> Self {
+        Self {
+            reason,
+            exit_code: reason.default_exit_code(),
+            message: Some(message.into()),
+            finished_at: Utc::now(),
+        }
+    }
+
+    /// Exited now with `exit_code`, killed for exceeding the memory limit
+    /// when `oom_killed`
+    pub fn exited(exit_code: i32, oom_killed: bool) -> Self {
+        let reason = match (oom_killed, exit_code) {
+            (true, _) => TerminationReason::OOMKilled,
+            (false, 0) => TerminationReason::Completed,
+            (false, _) => TerminationReason::Error,
+        };
+        Self {
+            reason,
+            exit_code,
+            message: None,
+            finished_at: Utc::now(),
+        }
+    }
+
+    /// Every container of `pod` in the terminated state
+    pub fn container_statuses(&self, pod: &Pod) -> Vec<ContainerStatus> {
+        let status = pod.status.as_ref();
+        let started_at = status.and_then(|s| s.start_time.clone());
+        let previous = status.and_then(|s| s.container_statuses.as_deref());
+        let containers = pod.spec.as_ref().map(|s| s.containers.as_slice());
+
+        containers
+            .unwrap_or_default()
+            .iter()
+            .map(|container| {
+                let restart_count = previous
+                    .and_then(|p| p.iter().find(|s| s.name == container.name))
+                    .map(|s| s.restart_count)
+                    .unwrap_or_default();
+                ContainerStatus {
+                    name: container.name.clone(),
+                    image: container.image.clone().unwrap_or_default(),
+                    ready: false,
+                    restart_count,
+                    started: Some(false),
+                    state: Some(ContainerState {
+                        terminated: Some(ContainerStateTerminated {
+                            exit_code: self.exit_code,
+                            reason: Some(self.reason.as_str().to_string()),
+                            message: self.message.clone(),
+                            started_at: started_at.clone(),
+                            finished_at: Some(Time(self.finished_at)),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Phase, reason, message and container statuses of the terminated
+    /// `pod`, keeping the rest of its status
+    pub fn pod_status(&self, pod: &Pod) -> PodStatus {
+        PodStatus {
+            phase: Some(self.reason.pod_phase().to_string()),
+            reason: Some(self.reason.as_str().to_string()),
+            message: self.message.clone(),
+            container_statuses: Some(self.container_statuses(pod)),
+            ..pod.status.clone().unwrap_or_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, PodSpec};
+
+    #[test]
+    fn test_termination_status() {
+        let mut pod = Pod {
+            spec: Some(PodSpec {
+                containers: vec![
+                    Container {
+                        name: "app".to_string(),
+                        image: Some("nginx".to_string()),
+                        ..Default::default()
+                    },
+                    Container {
+                        name: "sidecar".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Termination::exited(0, false).reason,
+            TerminationReason::Completed
+        );
+        assert_eq!(
+            Termination::exited(2, false).reason,
+            TerminationReason::Error
+        );
+        assert_eq!(
+            Termination::exited(137, true).reason,
+            TerminationReason::OOMKilled
+        );
+
+        let evicted = Termination::new(TerminationReason::Evicted, "low on memory");
+        pod.status = Some(evicted.pod_status(&pod));
+        let status = pod.status.as_ref().unwrap();
+        assert_eq!(status.phase.as_deref(), Some("Failed"));
+        assert_eq!(termination_reason(&pod), Some(TerminationReason::Evicted));
+
+        let containers = status.container_statuses.as_ref().unwrap();
+        assert_eq!(containers.len(), 2);
+        let terminated = containers[0]
+            .state
+            .as_ref()
+            .and_then(|s| s.terminated.as_ref())
+            .unwrap();
+        assert_eq!(containers[0].image, "nginx");
+        assert_eq!(terminated.exit_code, KILLED_EXIT_CODE);
+        assert_eq!(terminated.reason.as_deref(), Some("Evicted"));
+        assert_eq!(terminated.message.as_deref(), Some("low on memory"));
+
+        let completed = Termination::exited(0, false).pod_status(&pod);
+        assert_eq!(completed.phase.as_deref(), Some("Succeeded"));
+        for reason in [
+            TerminationReason::Completed,
+            TerminationReason::Error,
+            TerminationReason::OOMKilled,
+            TerminationReason::Evicted,
+            TerminationReason::NodeShutdown,
+            TerminationReason::ProbeFailure,
+        ] {
+            assert_eq!(TerminationReason::parse(reason.as_str()), Some(reason));
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Node topology labels
+//!
+//! Nodes publish where they sit in the failure-domain hierarchy so topology
+//! spread and affinity rules can act on real placement data.
+
+/// Well-known node label carrying the region the node runs in
+pub const TOPOLOGY_REGION_LABEL: &str = "topology.kubernetes.io/region";
+
+/// Well-known node label carrying the zone (failure domain) within the region
+pub const TOPOLOGY_ZONE_LABEL: &str = "topology.kubernetes.io/zone";
+
+/// Node label identifying the physical chassis, so nodes sharing hardware
+/// can be told apart from nodes that merely share a zone
+pub const CHASSIS_LABEL: &str = "reddwarf.io/chassis";
+
+/// Turn free-form text (e.g. a serial number) into a label value: characters
+/// outside `[A-Za-z0-9._-]` become `-`, the value is cut to 63 characters and
+/// must start and end alphanumeric. `None` if nothing usable remains.
+pub fn to_label_value(raw: &str) -> Option<String> {
+    let value: String = raw
+        .trim()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' | '-' => c,
+            _ => '-',
+        })
+        .take(63)
+        .collect();
+    let value = value.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_label_value() {
+        assert_eq!(to_label_value("us-east-1a").as_deref(), Some("us-east-1a"));
+        assert_eq!(
+            to_label_value(" SN 1234/AB ").as_deref(),
+            Some("SN-1234-AB")
+        );
+        assert_eq!(to_label_value("(none)").as_deref(), Some("none"));
+        assert_eq!(to_label_value("  --  "), None);
+        assert_eq!(to_label_value(&"x".repeat(80)).unwrap().len(), 63);
+    }
+}
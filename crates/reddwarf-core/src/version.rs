@@ -0,0 +1,124 @@
+//! Release versions of reddwarf components and the skew allowed between
+//! them. Node agents report their version as
+//! `status.nodeInfo.kubeletVersion`; an agent may lag the API server by up to
+//! [`MAX_AGENT_MINOR_SKEW`] minor releases of the same major release, but
+//! never run ahead of it, so agents are upgraded after the control plane.
+
+use crate::Node;
+use std::fmt;
+
+/// Version of this build
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Prefix of the `kubeletVersion` node agents report, e.g. `reddwarf-0.1.0`
+pub const NODE_VERSION_PREFIX: &str = "reddwarf-";
+
+/// How many minor releases an agent may lag the API server
+pub const MAX_AGENT_MINOR_SKEW: u64 = 1;
+
+/// A `major.minor.patch` release version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Version of this build
+    pub fn current() -> Self {
+        Self::parse(VERSION).expect("CARGO_PKG_VERSION is a valid version")
+    }
+
+    /// Parse `1.2.3`, optionally prefixed with `v` or `reddwarf-` and
+    /// followed by a pre-release or build suffix (`1.2.3-rc.1`), which is
+    /// ignored
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix(NODE_VERSION_PREFIX).unwrap_or(s);
+        let s = s.strip_prefix('v').unwrap_or(s);
+        let core = s.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+        let version = Self::new(parts.next()??, parts.next()??, parts.next()??);
+        parts.next().is_none().then_some(version)
+    }
+
+    /// The version string node agents report for this version
+    pub fn node_version_string(&self) -> String {
+        format!("{}{}", NODE_VERSION_PREFIX, self)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The reddwarf version a node's agent reports, if it reports one
+pub fn node_version(node: &Node) -> Option<Version> {
+    let reported = &node.status.as_ref()?.node_info.as_ref()?.kubelet_version;
+    reported
+        .starts_with(NODE_VERSION_PREFIX)
+        .then(|| Version::parse(reported))?
+}
+
+/// Check that an agent at `agent` may register with an API server at
+/// `server`, returning why not otherwise
+pub fn check_agent_skew(server: Version, agent: Version) -> Result<(), String> {
+    if agent.major != server.major {
+        return Err(format!(
+            "agent version {} and API server version {} differ in their major release",
+            agent, server
+        ));
+    }
+    if agent.minor > server.minor {
+        return Err(format!(
+            "agent version {} is newer than API server version {}; upgrade the control plane first",
+            agent, server
+        ));
+    }
+    if server.minor - agent.minor > MAX_AGENT_MINOR_SKEW {
+        return Err(format!(
+            "agent version {} lags API server version {} by more than {} minor release(s)",
+            agent, server, MAX_AGENT_MINOR_SKEW
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_skew() {
+        assert_eq!(Version::parse("0.3.1"), Some(Version::new(0, 3, 1)));
+        assert_eq!(
+            Version::parse("reddwarf-1.2.3"),
+            Some(Version::new(1, 2, 3))
+        );
+        assert_eq!(Version::parse("v1.2.3-rc.1"), Some(Version::new(1, 2, 3)));
+        assert_eq!(Version::parse("1.2"), None);
+        assert_eq!(Version::parse("1.2.3.4"), None);
+        assert_eq!(
+            Version::parse(&Version::current().node_version_string()),
+            Some(Version::current())
+        );
+
+        let server = Version::new(1, 4, 0);
+        assert!(check_agent_skew(server, Version::new(1, 4, 2)).is_ok());
+        assert!(check_agent_skew(server, Version::new(1, 3, 9)).is_ok());
+        assert!(check_agent_skew(server, Version::new(1, 2, 0)).is_err());
+        assert!(check_agent_skew(server, Version::new(1, 5, 0)).is_err());
+        assert!(check_agent_skew(server, Version::new(2, 4, 0)).is_err());
+    }
+}
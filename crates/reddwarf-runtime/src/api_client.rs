@@ -1,13 +1,37 @@
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerStats};
 use crate::error::{Result, RuntimeError};
-use k8s_openapi::api::core::v1::{Node, Pod, PodStatus};
-use reqwest::Client;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet};
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
+use k8s_openapi::api::coordination::v1::Lease;
+use k8s_openapi::api::core::v1::{
+    Endpoints, Event, Node, NodeStatus, PersistentVolume, PersistentVolumeClaim, Pod, PodStatus,
+};
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use k8s_openapi::api::networking::v1::NetworkPolicy;
+use k8s_openapi::api::policy::v1::PodDisruptionBudget;
+use reddwarf_core::STATUS_ANNOTATION_PREFIX;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use tracing::{debug, warn};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::debug;
+
+/// Idle connections kept per host in the connection pool
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+/// How long an idle pooled connection is kept before being closed
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// TCP connect timeout for API server requests
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Lightweight HTTP client for the controller/node-agent to talk to the API server
+///
+/// Connections are pooled and all requests go through a circuit breaker so
+/// that a crashed API server is not hammered with retries from every node.
 pub struct ApiClient {
     base_url: String,
     client: Client,
+    breaker: CircuitBreaker,
 }
 
 /// Watch event received from the API server SSE stream
@@ -18,6 +42,64 @@ pub struct WatchEvent<T> {
     pub object: T,
 }
 
+/// Events of a watch opened with [`ApiClient::watch`]
+pub struct WatchStream {
+    resp: Response,
+    /// Received bytes not yet parsed into an event
+    buffer: Vec<u8>,
+}
+
+impl WatchStream {
+    /// Next event, or `None` once the API server closes the stream
+    pub async fn next<T: DeserializeOwned>(&mut self) -> Result<Option<WatchEvent<T>>> {
+        loop {
+            if let Some(data) = take_sse_data(&mut self.buffer) {
+                return serde_json::from_str(&data).map(Some).map_err(|e| {
+                    RuntimeError::internal_error(format!("Failed to parse watch event: {}", e))
+                });
+            }
+            let chunk =
+                self.resp.chunk().await.map_err(|e| {
+                    RuntimeError::internal_error(format!("Watch stream failed: {}", e))
+                })?;
+            match chunk {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Remove the first complete SSE event from `buffer` and return its data,
+/// skipping events without any (keep-alive comments)
+fn take_sse_data(buffer: &mut Vec<u8>) -> Option<String> {
+    loop {
+        let end = buffer.windows(2).position(|w| w == b"\n\n")?;
+        let event: Vec<u8> = buffer.drain(..end + 2).collect();
+        let event = String::from_utf8_lossy(&event);
+        let data: Vec<&str> = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|d| d.strip_prefix(' ').unwrap_or(d))
+            .collect();
+        if !data.is_empty() {
+            return Some(data.join("\n"));
+        }
+    }
+}
+
+/// The error of `request` answered with the failure status of `resp`,
+/// classified by the status and the reason of the Status in its body
+async fn status_error(request: &str, resp: Response) -> RuntimeError {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    RuntimeError::api_request_failed(
+        Some(status.as_u16()),
+        &body,
+        format!("{} failed with status {}: {}", request, status, body),
+    )
+}
+
 impl ApiClient {
     pub fn new(base_url: &str) -> Self {
         Self::with_ca_cert(base_url, None)
@@ -28,17 +110,75 @@ impl ApiClient {
     /// When connecting to a server with a self-signed certificate, pass the
     /// CA PEM bytes here so the client will accept it.
     pub fn with_ca_cert(base_url: &str, ca_pem: Option<&[u8]>) -> Self {
-        let mut builder = Client::builder();
+        Self::with_tls(base_url, ca_pem, None)
+    }
+
+    /// Create a client that optionally trusts an additional CA certificate
+    /// and presents `identity` as its client certificate, such as the node
+    /// certificate of an agent that joined the cluster
+    pub fn with_tls(
+        base_url: &str,
+        ca_pem: Option<&[u8]>,
+        identity: Option<reqwest::Identity>,
+    ) -> Self {
+        let mut builder = Client::builder()
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT);
 
         if let Some(pem) = ca_pem {
             if let Ok(cert) = reqwest::Certificate::from_pem(pem) {
                 builder = builder.add_root_certificate(cert);
             }
         }
+        if let Some(identity) = identity {
+            builder = builder.identity(identity);
+        }
 
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
             client: builder.build().unwrap_or_else(|_| Client::new()),
+            breaker: CircuitBreaker::new(CircuitBreakerConfig::default()),
+        }
+    }
+
+    /// Replace the circuit breaker configuration
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breaker = CircuitBreaker::new(config);
+        self
+    }
+
+    /// Snapshot of the circuit breaker state and counters
+    pub fn circuit_stats(&self) -> CircuitBreakerStats {
+        self.breaker.stats()
+    }
+
+    /// Send a request through the circuit breaker.
+    ///
+    /// Transport errors and 5xx responses count as failures; any other
+    /// response means the API server is alive and closes the circuit.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        if let Err(retry_after) = self.breaker.try_acquire() {
+            return Err(RuntimeError::api_unavailable(&self.base_url, retry_after));
+        }
+
+        match request.send().await {
+            Ok(resp) if resp.status().is_server_error() => {
+                self.breaker.record_failure();
+                Ok(resp)
+            }
+            Ok(resp) => {
+                self.breaker.record_success();
+                Ok(resp)
+            }
+            Err(e) => {
+                self.breaker.record_failure();
+                Err(RuntimeError::api_request_failed(
+                    None,
+                    "",
+                    format!("HTTP request failed: {}", e),
+                ))
+            }
         }
     }
 
@@ -47,20 +187,10 @@ impl ApiClient {
         let url = format!("{}{}", self.base_url, path);
         debug!("GET {}", url);
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+        let resp = self.send(self.client.get(&url)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(RuntimeError::internal_error(format!(
-                "GET {} failed with status {}: {}",
-                path, status, body
-            )));
+            return Err(status_error(&format!("GET {}", path), resp).await);
         }
 
         resp.json::<serde_json::Value>()
@@ -76,20 +206,26 @@ impl ApiClient {
         );
         debug!("GET {}", url);
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
+        let resp = self.send(self.client.get(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("GET pod", resp).await);
+        }
+
+        resp.json::<Pod>()
             .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse pod: {}", e)))
+    }
+
+    /// POST /api/v1/namespaces/{namespace}/pods
+    pub async fn create_pod(&self, namespace: &str, pod: &Pod) -> Result<Pod> {
+        let url = format!("{}/api/v1/namespaces/{}/pods", self.base_url, namespace);
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(pod)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(RuntimeError::internal_error(format!(
-                "GET pod failed with status {}: {}",
-                status, body
-            )));
+            return Err(status_error("POST pod", resp).await);
         }
 
         resp.json::<Pod>()
@@ -97,6 +233,83 @@ impl ApiClient {
             .map_err(|e| RuntimeError::internal_error(format!("Failed to parse pod: {}", e)))
     }
 
+    /// DELETE /api/v1/namespaces/{namespace}/pods/{name}
+    ///
+    /// Starts graceful termination; the pod is removed once its node
+    /// finalizes it.
+    pub async fn delete_pod(&self, namespace: &str, name: &str) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/pods/{}",
+            self.base_url, namespace, name
+        );
+        debug!("DELETE {}", url);
+
+        let resp = self.send(self.client.delete(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("DELETE pod", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// POST /api/v1/namespaces/{namespace}/pods/{name}/eviction
+    ///
+    /// Evicts a pod through the Eviction subresource; drains use this rather
+    /// than `delete_pod` so PodDisruptionBudgets are honoured, and retry the
+    /// evictions a budget refuses
+    pub async fn evict_pod(
+        &self,
+        namespace: &str,
+        name: &str,
+        grace_period_seconds: Option<i64>,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/pods/{}/eviction",
+            self.base_url, namespace, name
+        );
+        debug!("POST {}", url);
+
+        let mut eviction = serde_json::json!({
+            "apiVersion": "policy/v1",
+            "kind": "Eviction",
+            "metadata": {"name": name, "namespace": namespace}
+        });
+        if let Some(grace) = grace_period_seconds {
+            eviction["deleteOptions"] = serde_json::json!({ "gracePeriodSeconds": grace });
+        }
+
+        let resp = self.send(self.client.post(&url).json(&eviction)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST eviction", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// Open a watch on a list path (e.g. `/api/v1/pods`), framed as
+    /// Server-Sent Events
+    pub async fn watch(&self, path: &str) -> Result<WatchStream> {
+        let url = format!("{}{}?watch=true", self.base_url, path);
+        debug!("GET {}", url);
+
+        let request = self
+            .client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+        let resp = self.send(request).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error(&format!("GET {} watch", path), resp).await);
+        }
+
+        Ok(WatchStream {
+            resp,
+            buffer: Vec::new(),
+        })
+    }
+
     /// PUT /api/v1/namespaces/{namespace}/pods/{name}/status
     pub async fn update_pod_status(&self, namespace: &str, name: &str, pod: &Pod) -> Result<Pod> {
         let url = format!(
@@ -105,21 +318,10 @@ impl ApiClient {
         );
         debug!("PUT {}", url);
 
-        let resp = self
-            .client
-            .put(&url)
-            .json(pod)
-            .send()
-            .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+        let resp = self.send(self.client.put(&url).json(pod)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(RuntimeError::internal_error(format!(
-                "PUT pod status failed with status {}: {}",
-                status, body
-            )));
+            return Err(status_error("PUT pod status", resp).await);
         }
 
         resp.json::<Pod>()
@@ -140,33 +342,84 @@ impl ApiClient {
         self.update_pod_status(namespace, name, &pod).await
     }
 
+    /// Update a Pod's status fields together with its `status.reddwarf.io/`
+    /// annotations (which replace any previously reported ones)
+    pub async fn set_pod_status_with_annotations(
+        &self,
+        namespace: &str,
+        name: &str,
+        status: PodStatus,
+        status_annotations: BTreeMap<String, String>,
+    ) -> Result<Pod> {
+        let mut pod = self.get_pod(namespace, name).await?;
+        let annotations = pod.metadata.annotations.get_or_insert_with(BTreeMap::new);
+        annotations.retain(|k, _| !k.starts_with(STATUS_ANNOTATION_PREFIX));
+        annotations.extend(status_annotations);
+        pod.status = Some(status);
+        self.update_pod_status(namespace, name, &pod).await
+    }
+
     /// POST /api/v1/nodes
     pub async fn create_node(&self, node: &Node) -> Result<Node> {
         let url = format!("{}/api/v1/nodes", self.base_url);
         debug!("POST {}", url);
 
-        let resp = self
-            .client
-            .post(&url)
-            .json(node)
-            .send()
+        let resp = self.send(self.client.post(&url).json(node)).await?;
+
+        // A node that already exists fails with a conflict, for the caller
+        // to update it instead
+        if !resp.status().is_success() {
+            return Err(status_error("POST node", resp).await);
+        }
+
+        resp.json::<Node>()
             .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse node: {}", e)))
+    }
+
+    /// POST /api/v1/namespaces/{namespace}/events
+    pub async fn create_event(&self, namespace: &str, event: &Event) -> Result<Event> {
+        let url = format!("{}/api/v1/namespaces/{}/events", self.base_url, namespace);
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(event)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            // 409 means node already exists — that's OK for re-registration
-            if status == reqwest::StatusCode::CONFLICT {
-                warn!("Node already exists, will update via status endpoint");
-                return Err(RuntimeError::ZoneAlreadyExists {
-                    zone_name: "node".to_string(),
-                });
-            }
-            return Err(RuntimeError::internal_error(format!(
-                "POST node failed with status {}: {}",
-                status, body
-            )));
+            return Err(status_error("POST event", resp).await);
+        }
+
+        resp.json::<Event>()
+            .await
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse event: {}", e)))
+    }
+
+    /// PUT /api/v1/nodes/{name}
+    pub async fn replace_node(&self, name: &str, node: &Node) -> Result<Node> {
+        let url = format!("{}/api/v1/nodes/{}", self.base_url, name);
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(node)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT node", resp).await);
+        }
+
+        resp.json::<Node>()
+            .await
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse node: {}", e)))
+    }
+
+    /// PATCH /api/v1/nodes/{name} setting `spec.unschedulable`, which
+    /// cordons the node (`true`) or makes it schedulable again (`false`)
+    pub async fn set_node_unschedulable(&self, name: &str, unschedulable: bool) -> Result<Node> {
+        let url = format!("{}/api/v1/nodes/{}", self.base_url, name);
+        debug!("PATCH {}", url);
+
+        let patch = serde_json::json!({ "spec": { "unschedulable": unschedulable } });
+        let resp = self.send(self.client.patch(&url).json(&patch)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PATCH node", resp).await);
         }
 
         resp.json::<Node>()
@@ -179,21 +432,29 @@ impl ApiClient {
         let url = format!("{}/api/v1/nodes/{}/status", self.base_url, name);
         debug!("PUT {}", url);
 
-        let resp = self
-            .client
-            .put(&url)
-            .json(node)
-            .send()
+        let resp = self.send(self.client.put(&url).json(node)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT node status", resp).await);
+        }
+
+        resp.json::<Node>()
             .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse node: {}", e)))
+    }
+
+    /// PATCH /api/v1/nodes/{name}/status with only the fields of `status`
+    /// that are set; the API server merges conditions by type and keeps the
+    /// rest of the stored status
+    pub async fn patch_node_status(&self, name: &str, status: &NodeStatus) -> Result<Node> {
+        let url = format!("{}/api/v1/nodes/{}/status", self.base_url, name);
+        debug!("PATCH {}", url);
+
+        let patch = serde_json::json!({ "status": status });
+        let resp = self.send(self.client.patch(&url).json(&patch)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(RuntimeError::internal_error(format!(
-                "PUT node status failed with status {}: {}",
-                status, body
-            )));
+            return Err(status_error("PATCH node status", resp).await);
         }
 
         resp.json::<Node>()
@@ -206,20 +467,10 @@ impl ApiClient {
         let url = format!("{}/api/v1/nodes/{}", self.base_url, name);
         debug!("GET {}", url);
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+        let resp = self.send(self.client.get(&url)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(RuntimeError::internal_error(format!(
-                "GET node failed with status {}: {}",
-                status, body
-            )));
+            return Err(status_error("GET node", resp).await);
         }
 
         resp.json::<Node>()
@@ -238,25 +489,596 @@ impl ApiClient {
         );
         debug!("POST {}", url);
 
-        let resp = self
-            .client
-            .post(&url)
-            .send()
+        let resp = self.send(self.client.post(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST finalize pod", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// POST /apis/networking.k8s.io/v1/namespaces/{namespace}/networkpolicies
+    pub async fn create_network_policy(
+        &self,
+        namespace: &str,
+        policy: &NetworkPolicy,
+    ) -> Result<NetworkPolicy> {
+        let url = format!(
+            "{}/apis/networking.k8s.io/v1/namespaces/{}/networkpolicies",
+            self.base_url, namespace
+        );
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(policy)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST network policy", resp).await);
+        }
+
+        resp.json::<NetworkPolicy>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse network policy: {}", e))
+        })
+    }
+
+    /// DELETE /apis/networking.k8s.io/v1/namespaces/{namespace}/networkpolicies/{name}
+    pub async fn delete_network_policy(&self, namespace: &str, name: &str) -> Result<()> {
+        let url = format!(
+            "{}/apis/networking.k8s.io/v1/namespaces/{}/networkpolicies/{}",
+            self.base_url, namespace, name
+        );
+        debug!("DELETE {}", url);
+
+        let resp = self.send(self.client.delete(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("DELETE network policy", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// POST /api/v1/namespaces/{namespace}/endpoints
+    pub async fn create_endpoints(
+        &self,
+        namespace: &str,
+        endpoints: &Endpoints,
+    ) -> Result<Endpoints> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/endpoints",
+            self.base_url, namespace
+        );
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(endpoints)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST endpoints", resp).await);
+        }
+
+        resp.json::<Endpoints>()
+            .await
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse endpoints: {}", e)))
+    }
+
+    /// PUT /api/v1/namespaces/{namespace}/endpoints/{name}
+    pub async fn replace_endpoints(
+        &self,
+        namespace: &str,
+        name: &str,
+        endpoints: &Endpoints,
+    ) -> Result<Endpoints> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/endpoints/{}",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(endpoints)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT endpoints", resp).await);
+        }
+
+        resp.json::<Endpoints>()
+            .await
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse endpoints: {}", e)))
+    }
+
+    /// DELETE /api/v1/namespaces/{namespace}/endpoints/{name}
+    pub async fn delete_endpoints(&self, namespace: &str, name: &str) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/endpoints/{}",
+            self.base_url, namespace, name
+        );
+        debug!("DELETE {}", url);
+
+        let resp = self.send(self.client.delete(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("DELETE endpoints", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// POST /apis/discovery.k8s.io/v1/namespaces/{namespace}/endpointslices
+    pub async fn create_endpoint_slice(
+        &self,
+        namespace: &str,
+        slice: &EndpointSlice,
+    ) -> Result<EndpointSlice> {
+        let url = format!(
+            "{}/apis/discovery.k8s.io/v1/namespaces/{}/endpointslices",
+            self.base_url, namespace
+        );
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(slice)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST endpoint slice", resp).await);
+        }
+
+        resp.json::<EndpointSlice>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse endpoint slice: {}", e))
+        })
+    }
+
+    /// PUT /apis/discovery.k8s.io/v1/namespaces/{namespace}/endpointslices/{name}
+    pub async fn replace_endpoint_slice(
+        &self,
+        namespace: &str,
+        name: &str,
+        slice: &EndpointSlice,
+    ) -> Result<EndpointSlice> {
+        let url = format!(
+            "{}/apis/discovery.k8s.io/v1/namespaces/{}/endpointslices/{}",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(slice)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT endpoint slice", resp).await);
+        }
+
+        resp.json::<EndpointSlice>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse endpoint slice: {}", e))
+        })
+    }
+
+    /// DELETE /apis/discovery.k8s.io/v1/namespaces/{namespace}/endpointslices/{name}
+    pub async fn delete_endpoint_slice(&self, namespace: &str, name: &str) -> Result<()> {
+        let url = format!(
+            "{}/apis/discovery.k8s.io/v1/namespaces/{}/endpointslices/{}",
+            self.base_url, namespace, name
+        );
+        debug!("DELETE {}", url);
+
+        let resp = self.send(self.client.delete(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("DELETE endpoint slice", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// GET /apis/coordination.k8s.io/v1/namespaces/{namespace}/leases/{name}
+    ///
+    /// `None` when there is no such lease.
+    pub async fn get_lease(&self, namespace: &str, name: &str) -> Result<Option<Lease>> {
+        let url = format!(
+            "{}/apis/coordination.k8s.io/v1/namespaces/{}/leases/{}",
+            self.base_url, namespace, name
+        );
+        debug!("GET {}", url);
+
+        let resp = self.send(self.client.get(&url)).await?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(status_error("GET lease", resp).await);
+        }
+
+        resp.json::<Lease>()
             .await
-            .map_err(|e| RuntimeError::internal_error(format!("HTTP request failed: {}", e)))?;
+            .map(Some)
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse lease: {}", e)))
+    }
+
+    /// POST /apis/coordination.k8s.io/v1/namespaces/{namespace}/leases
+    ///
+    /// `None` when another holder created the lease first.
+    pub async fn create_lease(&self, namespace: &str, lease: &Lease) -> Result<Option<Lease>> {
+        let url = format!(
+            "{}/apis/coordination.k8s.io/v1/namespaces/{}/leases",
+            self.base_url, namespace
+        );
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(lease)).await?;
+
+        if resp.status() == reqwest::StatusCode::CONFLICT {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(status_error("POST lease", resp).await);
+        }
+
+        resp.json::<Lease>()
+            .await
+            .map(Some)
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse lease: {}", e)))
+    }
+
+    /// PUT /apis/coordination.k8s.io/v1/namespaces/{namespace}/leases/{name}
+    ///
+    /// `None` when the lease was modified since `lease` was read, i.e. its
+    /// resourceVersion no longer matches.
+    pub async fn replace_lease(
+        &self,
+        namespace: &str,
+        name: &str,
+        lease: &Lease,
+    ) -> Result<Option<Lease>> {
+        let url = format!(
+            "{}/apis/coordination.k8s.io/v1/namespaces/{}/leases/{}",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(lease)).await?;
+
+        if resp.status() == reqwest::StatusCode::CONFLICT {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(status_error("PUT lease", resp).await);
+        }
+
+        resp.json::<Lease>()
+            .await
+            .map(Some)
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse lease: {}", e)))
+    }
+
+    /// POST /apis/apps/v1/namespaces/{namespace}/replicasets
+    pub async fn create_replica_set(
+        &self,
+        namespace: &str,
+        replica_set: &ReplicaSet,
+    ) -> Result<ReplicaSet> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/replicasets",
+            self.base_url, namespace
+        );
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(replica_set)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST replica set", resp).await);
+        }
+
+        resp.json::<ReplicaSet>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse replica set: {}", e))
+        })
+    }
+
+    /// PUT /apis/apps/v1/namespaces/{namespace}/replicasets/{name}
+    pub async fn replace_replica_set(
+        &self,
+        namespace: &str,
+        name: &str,
+        replica_set: &ReplicaSet,
+    ) -> Result<ReplicaSet> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/replicasets/{}",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(replica_set)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT replica set", resp).await);
+        }
+
+        resp.json::<ReplicaSet>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse replica set: {}", e))
+        })
+    }
+
+    /// PUT /apis/apps/v1/namespaces/{namespace}/replicasets/{name}/status
+    pub async fn update_replica_set_status(
+        &self,
+        namespace: &str,
+        name: &str,
+        replica_set: &ReplicaSet,
+    ) -> Result<ReplicaSet> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/replicasets/{}/status",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(replica_set)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT replica set status", resp).await);
+        }
+
+        resp.json::<ReplicaSet>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse replica set status: {}", e))
+        })
+    }
+
+    /// DELETE /apis/apps/v1/namespaces/{namespace}/replicasets/{name}
+    pub async fn delete_replica_set(&self, namespace: &str, name: &str) -> Result<()> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/replicasets/{}",
+            self.base_url, namespace, name
+        );
+        debug!("DELETE {}", url);
+
+        let resp = self.send(self.client.delete(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("DELETE replica set", resp).await);
+        }
+
+        Ok(())
+    }
+
+    /// PUT /apis/apps/v1/namespaces/{namespace}/deployments/{name}
+    pub async fn replace_deployment(
+        &self,
+        namespace: &str,
+        name: &str,
+        deployment: &Deployment,
+    ) -> Result<Deployment> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/deployments/{}",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(deployment)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT deployment", resp).await);
+        }
+
+        resp.json::<Deployment>()
+            .await
+            .map_err(|e| RuntimeError::internal_error(format!("Failed to parse deployment: {}", e)))
+    }
+
+    /// PUT /apis/apps/v1/namespaces/{namespace}/deployments/{name}/status
+    pub async fn update_deployment_status(
+        &self,
+        namespace: &str,
+        name: &str,
+        deployment: &Deployment,
+    ) -> Result<Deployment> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/deployments/{}/status",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(deployment)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT deployment status", resp).await);
+        }
+
+        resp.json::<Deployment>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse deployment status: {}", e))
+        })
+    }
+
+    /// PUT /apis/apps/v1/namespaces/{namespace}/daemonsets/{name}/status
+    pub async fn update_daemon_set_status(
+        &self,
+        namespace: &str,
+        name: &str,
+        daemon_set: &DaemonSet,
+    ) -> Result<DaemonSet> {
+        let url = format!(
+            "{}/apis/apps/v1/namespaces/{}/daemonsets/{}/status",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(daemon_set)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT daemon set status", resp).await);
+        }
+
+        resp.json::<DaemonSet>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse daemon set status: {}", e))
+        })
+    }
+
+    /// PUT /apis/autoscaling/v2/namespaces/{namespace}/horizontalpodautoscalers/{name}/status
+    pub async fn update_horizontal_pod_autoscaler_status(
+        &self,
+        namespace: &str,
+        name: &str,
+        autoscaler: &HorizontalPodAutoscaler,
+    ) -> Result<HorizontalPodAutoscaler> {
+        let url = format!(
+            "{}/apis/autoscaling/v2/namespaces/{}/horizontalpodautoscalers/{}/status",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(autoscaler)).await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(RuntimeError::internal_error(format!(
-                "POST finalize pod failed with status {}: {}",
-                status, body
-            )));
+            return Err(status_error("PUT horizontal pod autoscaler status", resp).await);
+        }
+
+        resp.json::<HorizontalPodAutoscaler>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse autoscaler status: {}", e))
+        })
+    }
+
+    /// PUT /apis/policy/v1/namespaces/{namespace}/poddisruptionbudgets/{name}/status
+    pub async fn update_pod_disruption_budget_status(
+        &self,
+        namespace: &str,
+        name: &str,
+        budget: &PodDisruptionBudget,
+    ) -> Result<PodDisruptionBudget> {
+        let url = format!(
+            "{}/apis/policy/v1/namespaces/{}/poddisruptionbudgets/{}/status",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(budget)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT pod disruption budget status", resp).await);
+        }
+
+        resp.json::<PodDisruptionBudget>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse budget status: {}", e))
+        })
+    }
+
+    /// POST /api/v1/persistentvolumes
+    pub async fn create_persistent_volume(
+        &self,
+        volume: &PersistentVolume,
+    ) -> Result<PersistentVolume> {
+        let url = format!("{}/api/v1/persistentvolumes", self.base_url);
+        debug!("POST {}", url);
+
+        let resp = self.send(self.client.post(&url).json(volume)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("POST persistent volume", resp).await);
+        }
+
+        resp.json::<PersistentVolume>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse persistent volume: {}", e))
+        })
+    }
+
+    /// PUT /api/v1/persistentvolumes/{name}
+    pub async fn replace_persistent_volume(
+        &self,
+        name: &str,
+        volume: &PersistentVolume,
+    ) -> Result<PersistentVolume> {
+        let url = format!("{}/api/v1/persistentvolumes/{}", self.base_url, name);
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(volume)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT persistent volume", resp).await);
+        }
+
+        resp.json::<PersistentVolume>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse persistent volume: {}", e))
+        })
+    }
+
+    /// PUT /api/v1/persistentvolumes/{name}/status
+    pub async fn update_persistent_volume_status(
+        &self,
+        name: &str,
+        volume: &PersistentVolume,
+    ) -> Result<PersistentVolume> {
+        let url = format!("{}/api/v1/persistentvolumes/{}/status", self.base_url, name);
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(volume)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT persistent volume status", resp).await);
+        }
+
+        resp.json::<PersistentVolume>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse persistent volume status: {}", e))
+        })
+    }
+
+    /// DELETE /api/v1/persistentvolumes/{name}
+    pub async fn delete_persistent_volume(&self, name: &str) -> Result<()> {
+        let url = format!("{}/api/v1/persistentvolumes/{}", self.base_url, name);
+        debug!("DELETE {}", url);
+
+        let resp = self.send(self.client.delete(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("DELETE persistent volume", resp).await);
         }
 
         Ok(())
     }
 
+    /// PUT /api/v1/namespaces/{namespace}/persistentvolumeclaims/{name}
+    pub async fn replace_persistent_volume_claim(
+        &self,
+        namespace: &str,
+        name: &str,
+        claim: &PersistentVolumeClaim,
+    ) -> Result<PersistentVolumeClaim> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/persistentvolumeclaims/{}",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(claim)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT persistent volume claim", resp).await);
+        }
+
+        resp.json::<PersistentVolumeClaim>().await.map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse persistent volume claim: {}", e))
+        })
+    }
+
+    /// PUT /api/v1/namespaces/{namespace}/persistentvolumeclaims/{name}/status
+    pub async fn update_persistent_volume_claim_status(
+        &self,
+        namespace: &str,
+        name: &str,
+        claim: &PersistentVolumeClaim,
+    ) -> Result<PersistentVolumeClaim> {
+        let url = format!(
+            "{}/api/v1/namespaces/{}/persistentvolumeclaims/{}/status",
+            self.base_url, namespace, name
+        );
+        debug!("PUT {}", url);
+
+        let resp = self.send(self.client.put(&url).json(claim)).await?;
+
+        if !resp.status().is_success() {
+            return Err(status_error("PUT persistent volume claim status", resp).await);
+        }
+
+        resp.json::<PersistentVolumeClaim>().await.map_err(|e| {
+            RuntimeError::internal_error(format!(
+                "Failed to parse persistent volume claim status: {}",
+                e
+            ))
+        })
+    }
+
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
@@ -265,6 +1087,8 @@ impl ApiClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reddwarf_core::{ErrorClass, ErrorKind, Metrics};
+    use std::sync::Arc;
 
     #[test]
     fn test_new_builds_client() {
@@ -278,6 +1102,100 @@ mod tests {
         assert_eq!(client.base_url(), "https://127.0.0.1:6443");
     }
 
+    #[tokio::test]
+    async fn test_open_circuit_rejects_without_network() {
+        // Nothing listens on port 1, so the first request fails at connect time
+        let client = ApiClient::new("http://127.0.0.1:1").with_circuit_breaker(
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                open_duration: Duration::from_secs(60),
+                max_jitter: Duration::ZERO,
+            },
+        );
+
+        let err = client.get_node("n1").await.unwrap_err();
+        assert!(matches!(
+            err,
+            RuntimeError::ApiRequestFailed { status: None, .. }
+        ));
+        assert!(err.is_retryable());
+
+        let err = client.get_node("n1").await.unwrap_err();
+        assert!(matches!(err, RuntimeError::ApiUnavailable { .. }));
+
+        let stats = client.circuit_stats();
+        assert_eq!(stats.times_opened, 1);
+        assert_eq!(stats.rejected_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_stats_in_metrics() {
+        let client = Arc::new(ApiClient::new("http://127.0.0.1:1").with_circuit_breaker(
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                open_duration: Duration::from_secs(60),
+                max_jitter: Duration::ZERO,
+            },
+        ));
+        let metrics = Metrics::new();
+        let stats_client = client.clone();
+        metrics.add_collector(move || stats_client.circuit_stats().render());
+
+        let text = metrics.render();
+        assert!(text.contains("reddwarf_api_circuit_state{state=\"closed\"} 1\n"));
+        assert!(text.contains("reddwarf_api_circuit_opened_total 0\n"));
+
+        // Trip the breaker, then have it reject two requests
+        for _ in 0..3 {
+            assert!(client.get_node("n1").await.is_err());
+        }
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE reddwarf_api_circuit_state gauge\n"));
+        assert!(text.contains("reddwarf_api_circuit_state{state=\"closed\"} 0\n"));
+        assert!(text.contains("reddwarf_api_circuit_state{state=\"open\"} 1\n"));
+        assert!(text.contains("reddwarf_api_circuit_state{state=\"half_open\"} 0\n"));
+        assert!(text.contains("# TYPE reddwarf_api_circuit_opened_total counter\n"));
+        assert!(text.contains("reddwarf_api_circuit_opened_total 1\n"));
+        assert!(text.contains("reddwarf_api_circuit_rejected_requests_total 2\n"));
+    }
+
+    #[test]
+    fn test_request_errors_are_classified_by_status() {
+        let status = r#"{"kind":"Status","reason":"AlreadyExists","code":409}"#;
+        let err = RuntimeError::api_request_failed(Some(409), status, "POST node failed");
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert!(err.is_conflict());
+        assert!(!err.is_retryable());
+
+        let err = RuntimeError::api_request_failed(Some(409), "", "PUT pod failed");
+        assert_eq!(err.kind(), ErrorKind::Conflict);
+        assert!(err.is_retryable());
+
+        let err = RuntimeError::api_request_failed(Some(404), "not json", "GET pod failed");
+        assert!(err.is_not_found());
+        let err = RuntimeError::api_request_failed(Some(503), "", "GET pod failed");
+        assert!(err.is_retryable());
+        assert_eq!(err.user_facing_reason(), "ServiceUnavailable");
+    }
+
+    #[test]
+    fn test_take_sse_data() {
+        let mut buffer = b":\n\ndata: {\"type\":\"ADDED\"}\n\ndata: {\"ty".to_vec();
+        assert_eq!(
+            take_sse_data(&mut buffer).as_deref(),
+            Some("{\"type\":\"ADDED\"}")
+        );
+        // The partial event stays buffered until the rest arrives
+        assert_eq!(take_sse_data(&mut buffer), None);
+        buffer.extend_from_slice(b"pe\":\"DELETED\"}\n\n");
+        assert_eq!(
+            take_sse_data(&mut buffer).as_deref(),
+            Some("{\"type\":\"DELETED\"}")
+        );
+        assert!(buffer.is_empty());
+    }
+
     #[test]
     fn test_with_ca_cert_invalid_pem_falls_back() {
         // Invalid PEM should not panic — just builds a client without the cert
@@ -0,0 +1,181 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Retry policy for pods whose zone fails to provision
+#[derive(Debug, Clone)]
+pub struct ReconcileBackoffConfig {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound of the doubling delay
+    pub max_delay: Duration,
+    /// Upper bound of random jitter added to each delay, so pods that failed
+    /// together don't all retry in lockstep
+    pub max_jitter: Duration,
+    /// Failed attempts after which the pod is marked Failed
+    pub error_budget: u32,
+}
+
+impl Default for ReconcileBackoffConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(300),
+            max_jitter: Duration::from_secs(2),
+            error_budget: 6,
+        }
+    }
+}
+
+/// What to do after a failed attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackoffDecision {
+    /// Leave the pod pending and try again after the delay
+    Retry { attempt: u32, delay: Duration },
+    /// The error budget is spent; give up on the pod
+    Exhausted { attempts: u32, elapsed: Duration },
+}
+
+#[derive(Debug)]
+struct PodErrors {
+    attempts: u32,
+    first_failure: Instant,
+    retry_at: Instant,
+}
+
+/// Per-pod failure tracking with exponential backoff and an error budget
+#[derive(Debug)]
+pub struct ReconcileBackoff {
+    config: ReconcileBackoffConfig,
+    pods: Mutex<HashMap<String, PodErrors>>,
+}
+
+impl ReconcileBackoff {
+    pub fn new(config: ReconcileBackoffConfig) -> Self {
+        Self {
+            config,
+            pods: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Time left before `pod_key` may be retried, if it is backing off
+    pub fn retry_in(&self, pod_key: &str) -> Option<Duration> {
+        let pods = self.pods.lock().unwrap_or_else(|e| e.into_inner());
+        let errors = pods.get(pod_key)?;
+        let now = Instant::now();
+        (errors.retry_at > now).then(|| errors.retry_at - now)
+    }
+
+    /// Record a failed attempt and decide whether to retry
+    pub fn record_failure(&self, pod_key: &str) -> BackoffDecision {
+        let mut pods = self.pods.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let errors = pods.entry(pod_key.to_string()).or_insert(PodErrors {
+            attempts: 0,
+            first_failure: now,
+            retry_at: now,
+        });
+        errors.attempts += 1;
+
+        if errors.attempts >= self.config.error_budget {
+            let decision = BackoffDecision::Exhausted {
+                attempts: errors.attempts,
+                elapsed: now - errors.first_failure,
+            };
+            pods.remove(pod_key);
+            return decision;
+        }
+
+        let delay = self.delay(errors.attempts);
+        errors.retry_at = now + delay;
+        BackoffDecision::Retry {
+            attempt: errors.attempts,
+            delay,
+        }
+    }
+
+    /// Forget a pod's failures, e.g. once it provisions or is deleted
+    pub fn forget(&self, pod_key: &str) {
+        let mut pods = self.pods.lock().unwrap_or_else(|e| e.into_inner());
+        pods.remove(pod_key);
+    }
+
+    /// `initial_delay` doubled per earlier failure, capped, plus jitter
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt - 1).unwrap_or(u32::MAX);
+        let delay = self
+            .config
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.config.max_delay);
+        delay + self.jitter()
+    }
+
+    fn jitter(&self) -> Duration {
+        let max_ms = self.config.max_jitter.as_millis() as u64;
+        if max_ms == 0 {
+            return Duration::ZERO;
+        }
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(max_ms);
+        Duration::from_millis(hasher.finish() % max_ms)
+    }
+}
+
+impl Default for ReconcileBackoff {
+    fn default() -> Self {
+        Self::new(ReconcileBackoffConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backoff_doubles_until_budget_spent() {
+        let backoff = ReconcileBackoff::new(ReconcileBackoffConfig {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(3),
+            max_jitter: Duration::ZERO,
+            error_budget: 4,
+        });
+        assert_eq!(backoff.retry_in("default/web"), None);
+
+        let mut delays = Vec::new();
+        for _ in 0..3 {
+            match backoff.record_failure("default/web") {
+                BackoffDecision::Retry { delay, .. } => delays.push(delay),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert_eq!(
+            delays,
+            [1, 2, 3].map(Duration::from_secs).to_vec(),
+            "doubling is capped at max_delay"
+        );
+        assert_eq!(
+            backoff.retry_in("default/web"),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(backoff.retry_in("default/db"), None);
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        assert_eq!(backoff.retry_in("default/web"), None);
+        assert_eq!(
+            backoff.record_failure("default/web"),
+            BackoffDecision::Exhausted {
+                attempts: 4,
+                elapsed: Duration::from_secs(3)
+            }
+        );
+
+        // A spent budget starts over
+        backoff.record_failure("default/web");
+        backoff.forget("default/web");
+        assert_eq!(backoff.retry_in("default/web"), None);
+    }
+}
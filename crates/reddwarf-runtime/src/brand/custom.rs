@@ -23,6 +23,15 @@ pub fn generate_supervisor_config(processes: &[ContainerProcess]) -> String {
         for (key, value) in &proc.env {
             lines.push(format!("env.{} = \"{}\"", key, value));
         }
+        // Attachable processes keep their input open, and terminal processes
+        // get a pseudo-terminal, served to `attach` sessions on the
+        // supervisor's socket
+        if proc.stdin {
+            lines.push("stdin = true".to_string());
+        }
+        if proc.tty {
+            lines.push("tty = true".to_string());
+        }
         lines.push(String::new());
     }
 
@@ -41,12 +50,16 @@ mod tests {
                 command: vec!["/usr/bin/node".to_string(), "server.js".to_string()],
                 working_dir: Some("/app".to_string()),
                 env: vec![("PORT".to_string(), "3000".to_string())],
+                stdin: false,
+                tty: false,
             },
             ContainerProcess {
                 name: "sidecar".to_string(),
                 command: vec!["/usr/bin/envoy".to_string()],
                 working_dir: None,
                 env: vec![],
+                stdin: true,
+                tty: true,
             },
         ];
 
@@ -56,5 +69,6 @@ mod tests {
         assert!(config.contains("working_dir = \"/app\""));
         assert!(config.contains("env.PORT = \"3000\""));
         assert!(config.contains("[process.sidecar]"));
+        assert!(config.ends_with("stdin = true\ntty = true\n"));
     }
 }
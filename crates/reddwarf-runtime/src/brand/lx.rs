@@ -35,7 +35,19 @@ mod tests {
             processes: vec![],
             cpu_cap: None,
             memory_cap: None,
+            swap_cap: None,
+            cpu_shares: None,
+            dedicated_cpus: None,
+            max_lwps: None,
+            max_processes: None,
             fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
         }
     }
 
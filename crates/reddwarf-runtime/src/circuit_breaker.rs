@@ -0,0 +1,323 @@
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::fmt::Write;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Configuration for the API client circuit breaker
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures before the circuit opens
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before allowing a half-open probe
+    pub open_duration: Duration,
+    /// Upper bound of random jitter added to `open_duration`, so nodes that
+    /// tripped at the same moment don't all probe the API server in lockstep
+    pub max_jitter: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(10),
+            max_jitter: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Externally visible circuit state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests are rejected without touching the network
+    Open,
+    /// A single probe request is allowed through to test recovery
+    HalfOpen,
+}
+
+/// Point-in-time snapshot of circuit breaker metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitBreakerStats {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Number of times the circuit has transitioned to open
+    pub times_opened: u64,
+    /// Requests rejected while the circuit was open
+    pub rejected_requests: u64,
+}
+
+impl CircuitBreakerStats {
+    /// Render the state, as a gauge per state that is 1 for the current one,
+    /// and the counters in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP reddwarf_api_circuit_state Whether the API client circuit breaker is in the state"
+        );
+        let _ = writeln!(out, "# TYPE reddwarf_api_circuit_state gauge");
+        for (state, name) in [
+            (CircuitState::Closed, "closed"),
+            (CircuitState::Open, "open"),
+            (CircuitState::HalfOpen, "half_open"),
+        ] {
+            let _ = writeln!(
+                out,
+                "reddwarf_api_circuit_state{{state=\"{}\"}} {}",
+                name,
+                u8::from(self.state == state)
+            );
+        }
+
+        let counters = [
+            (
+                "reddwarf_api_circuit_opened_total",
+                "Times the API client circuit breaker opened",
+                self.times_opened,
+            ),
+            (
+                "reddwarf_api_circuit_rejected_requests_total",
+                "Requests rejected while the API client circuit breaker was open",
+                self.rejected_requests,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        out
+    }
+}
+
+#[derive(Debug)]
+enum Phase {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { probe_started: Instant },
+}
+
+#[derive(Debug)]
+struct Inner {
+    phase: Phase,
+    consecutive_failures: u32,
+    times_opened: u64,
+    rejected_requests: u64,
+}
+
+/// Circuit breaker guarding calls to the API server
+///
+/// Opens after `failure_threshold` consecutive failures. While open, calls
+/// are rejected immediately. Once the (jittered) open duration elapses a
+/// single probe is let through: success closes the circuit, failure re-opens it.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                phase: Phase::Closed,
+                consecutive_failures: 0,
+                times_opened: 0,
+                rejected_requests: 0,
+            }),
+        }
+    }
+
+    /// Check whether a request may proceed.
+    ///
+    /// Returns the remaining open time when the request must be rejected.
+    pub fn try_acquire(&self) -> std::result::Result<(), Duration> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        match inner.phase {
+            Phase::Closed => Ok(()),
+            Phase::Open { until } if now >= until => {
+                info!("API circuit breaker half-open, sending probe request");
+                inner.phase = Phase::HalfOpen { probe_started: now };
+                Ok(())
+            }
+            Phase::Open { until } => {
+                inner.rejected_requests += 1;
+                Err(until - now)
+            }
+            // A probe that never reported back (e.g. its future was dropped)
+            // must not wedge the breaker, so allow a fresh probe after a while.
+            Phase::HalfOpen { probe_started }
+                if now.duration_since(probe_started) >= self.config.open_duration =>
+            {
+                inner.phase = Phase::HalfOpen { probe_started: now };
+                Ok(())
+            }
+            Phase::HalfOpen { .. } => {
+                inner.rejected_requests += 1;
+                Err(self.config.open_duration)
+            }
+        }
+    }
+
+    /// Record a successful request, closing the circuit
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if !matches!(inner.phase, Phase::Closed) {
+            info!("API circuit breaker closed, API server reachable again");
+        }
+        inner.phase = Phase::Closed;
+        inner.consecutive_failures = 0;
+    }
+
+    /// Record a failed request, opening the circuit when the threshold is hit
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+
+        let should_open = match inner.phase {
+            Phase::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+            Phase::HalfOpen { .. } => true,
+            Phase::Open { .. } => false,
+        };
+
+        if should_open {
+            let open_for = self.config.open_duration + self.jitter();
+            warn!(
+                "API circuit breaker opened after {} consecutive failures, retrying in {:?}",
+                inner.consecutive_failures, open_for
+            );
+            inner.phase = Phase::Open {
+                until: Instant::now() + open_for,
+            };
+            inner.times_opened += 1;
+        }
+    }
+
+    /// Current circuit state
+    pub fn state(&self) -> CircuitState {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match inner.phase {
+            Phase::Closed => CircuitState::Closed,
+            Phase::Open { .. } => CircuitState::Open,
+            Phase::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Snapshot of the breaker's metrics
+    pub fn stats(&self) -> CircuitBreakerStats {
+        let state = self.state();
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        CircuitBreakerStats {
+            state,
+            consecutive_failures: inner.consecutive_failures,
+            times_opened: inner.times_opened,
+            rejected_requests: inner.rejected_requests,
+        }
+    }
+
+    fn jitter(&self) -> Duration {
+        let max_ms = self.config.max_jitter.as_millis() as u64;
+        if max_ms == 0 {
+            return Duration::ZERO;
+        }
+        // RandomState is seeded per instance, which is enough entropy to
+        // spread retries across nodes without pulling in a RNG crate.
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(max_ms);
+        Duration::from_millis(hasher.finish() % max_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(threshold: u32, open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: threshold,
+            open_duration,
+            max_jitter: Duration::ZERO,
+        })
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let cb = breaker(3, Duration::from_secs(60));
+
+        cb.record_failure();
+        cb.record_failure();
+        assert_eq!(cb.state(), CircuitState::Closed);
+        assert!(cb.try_acquire().is_ok());
+
+        cb.record_failure();
+        assert_eq!(cb.state(), CircuitState::Open);
+        assert!(cb.try_acquire().is_err());
+
+        let stats = cb.stats();
+        assert_eq!(stats.times_opened, 1);
+        assert_eq!(stats.rejected_requests, 1);
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let cb = breaker(2, Duration::from_secs(60));
+
+        cb.record_failure();
+        cb.record_success();
+        cb.record_failure();
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_allows_single_probe() {
+        let cb = breaker(1, Duration::ZERO);
+
+        cb.record_failure();
+        assert_eq!(cb.state(), CircuitState::Open);
+
+        // Open duration elapsed: first caller becomes the probe
+        assert!(cb.try_acquire().is_ok());
+        assert_eq!(cb.state(), CircuitState::HalfOpen);
+
+        // Successful probe closes the circuit
+        cb.record_success();
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_failure_reopens() {
+        let cb = breaker(1, Duration::from_millis(0));
+
+        cb.record_failure();
+        assert!(cb.try_acquire().is_ok());
+        cb.record_failure();
+        assert_eq!(cb.state(), CircuitState::Open);
+        assert_eq!(cb.stats().times_opened, 2);
+    }
+
+    #[test]
+    fn test_half_open_rejects_concurrent_requests() {
+        let cb = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            open_duration: Duration::from_secs(60),
+            max_jitter: Duration::ZERO,
+        });
+
+        cb.record_failure();
+        // Force the open window to have elapsed
+        cb.inner.lock().unwrap().phase = Phase::Open {
+            until: Instant::now(),
+        };
+
+        assert!(cb.try_acquire().is_ok());
+        assert!(cb.try_acquire().is_err());
+    }
+}
@@ -1,20 +1,43 @@
 use crate::api_client::ApiClient;
+use crate::backoff::{BackoffDecision, ReconcileBackoff, ReconcileBackoffConfig};
+use crate::decision_log::{DecisionAction, DecisionLog, DecisionOutcome};
+use crate::devices::DeviceTable;
 use crate::error::{Result, RuntimeError};
-use crate::network::{vnic_name_for_pod, Ipam};
+use crate::network::dns::{pod_dns_config, pod_hostname, uses_cluster_dns};
+use crate::network::host_ports::port_forwards;
+use crate::network::{vnic_name_for_pod, BandwidthLimits, HostPortTable, Ipam};
+use crate::pod_cache::PodCache;
+use crate::pod_conditions::{conditions_changed, pod_conditions, PodProgress, Unready};
 use crate::probes::executor::ProbeExecutor;
 use crate::probes::tracker::ProbeTracker;
 use crate::probes::types::extract_probes;
+use crate::svid::SvidIssuer;
 use crate::traits::ZoneRuntime;
 use crate::types::*;
-use chrono::Utc;
-use k8s_openapi::api::core::v1::{Pod, PodCondition, PodStatus};
-use reddwarf_core::{ResourceEvent, ResourceQuantities, WatchEventType};
+use crate::warm_pool::WarmPool;
+use crate::zone::controls::ResourceControls;
+use crate::zone::tunables::{PodTunables, TunablesAllowlist};
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::{PersistentVolume, PersistentVolumeClaim, Pod, PodStatus};
+use reddwarf_core::annotations::reserved_pod_ip;
+use reddwarf_core::recorder::{REASON_PROBE_FAILED, REASON_PROVISIONED};
+use reddwarf_core::resources::{claim_phase, volume_path, PHASE_BOUND};
+use reddwarf_core::{
+    pod_device_requests, pod_host_ports, pod_lx_image, pod_port_conflicts, pod_qos_class,
+    pod_zone_brand, ErrorClass, ErrorKind, EventBus, EventRecorder, GroupVersionKind, ImageMapping,
+    Metrics, PodStartup, QosClass, ResourceQuantities, RuntimeClass, Termination,
+    TerminationReason, WatchEventType,
+};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, Mutex};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+/// Name the pod controller subscribes to the event bus under
+const SUBSCRIBER: &str = "pod-controller";
+
 /// Configuration for the pod controller
 #[derive(Debug, Clone)]
 pub struct PodControllerConfig {
@@ -32,23 +55,57 @@ pub struct PodControllerConfig {
     pub pod_cidr: String,
     /// Interval between periodic full reconciliation cycles
     pub reconcile_interval: Duration,
+    /// Host link pod `hostPort`s are forwarded from (forwarding is disabled
+    /// when unset; ports are still reserved)
+    pub host_port_interface: Option<String>,
+    /// Cluster DNS Service as "namespace/name"; its clusterIP is the
+    /// nameserver of `ClusterFirst` pods
+    pub cluster_dns_service: Option<String>,
+    /// Cluster domain for pod DNS search paths (e.g. "cluster.local")
+    pub cluster_domain: String,
+    /// Sysctls and zone attributes pods may request
+    pub allowed_tunables: TunablesAllowlist,
 }
 
 /// Pod controller that watches for Pod events and drives zone lifecycle
 pub struct PodController {
     runtime: Arc<dyn ZoneRuntime>,
     api_client: Arc<ApiClient>,
-    event_tx: broadcast::Sender<ResourceEvent>,
+    event_bus: Arc<dyn EventBus>,
     config: PodControllerConfig,
     ipam: Ipam,
+    host_ports: Option<HostPortTable>,
+    devices: Option<DeviceTable>,
+    warm_pool: Option<Arc<WarmPool>>,
+    metrics: Option<Arc<Metrics>>,
+    pod_cache: Option<PodCache>,
+    svid_issuer: Option<Arc<SvidIssuer>>,
+    recorder: Option<EventRecorder>,
+    /// Log of what was decided about each pod and why, for postmortems
+    decisions: Option<Arc<DecisionLog>>,
+    /// When the SVID written into each pod's zone is due for rotation
+    svid_renewals: std::sync::Mutex<HashMap<String, DateTime<Utc>>>,
+    /// Provisioning failures of pods still being retried
+    backoff: ReconcileBackoff,
     probe_tracker: Mutex<ProbeTracker>,
+    /// The node's own resolver configuration (`Default` DNS policy)
+    host_dns: DnsConfig,
+    /// Last known clusterIP of the cluster DNS Service
+    cluster_dns_ip: std::sync::RwLock<Option<String>>,
+    /// Handlers (zone brands) of the known RuntimeClasses, by class name
+    runtime_class_handlers: std::sync::RwLock<HashMap<String, String>>,
+    /// Last known ImageMappings, resolving lx pods' images to lx images
+    image_mappings: std::sync::RwLock<Vec<ImageMapping>>,
+    /// Zone states listed once per reconcile cycle, sparing a zoneadm call
+    /// per pod; `None` outside of a cycle
+    zone_states: std::sync::RwLock<Option<HashMap<String, ZoneState>>>,
 }
 
 impl PodController {
     pub fn new(
         runtime: Arc<dyn ZoneRuntime>,
         api_client: Arc<ApiClient>,
-        event_tx: broadcast::Sender<ResourceEvent>,
+        event_bus: Arc<dyn EventBus>,
         config: PodControllerConfig,
         ipam: Ipam,
     ) -> Self {
@@ -57,10 +114,98 @@ impl PodController {
         Self {
             runtime,
             api_client,
-            event_tx,
+            event_bus,
             config,
             ipam,
+            host_ports: None,
+            devices: None,
+            warm_pool: None,
+            metrics: None,
+            pod_cache: None,
+            svid_issuer: None,
+            recorder: None,
+            decisions: None,
+            svid_renewals: std::sync::Mutex::new(HashMap::new()),
+            backoff: ReconcileBackoff::default(),
             probe_tracker,
+            host_dns: DnsConfig::from_resolv_conf(
+                &std::fs::read_to_string("/etc/resolv.conf").unwrap_or_default(),
+            ),
+            cluster_dns_ip: std::sync::RwLock::new(None),
+            runtime_class_handlers: std::sync::RwLock::new(HashMap::new()),
+            image_mappings: std::sync::RwLock::new(Vec::new()),
+            zone_states: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// Track pod host port reservations in `table`
+    pub fn with_host_ports(mut self, table: HostPortTable) -> Self {
+        self.host_ports = Some(table);
+        self
+    }
+
+    /// Assign passed-through devices to pods from `table`
+    pub fn with_devices(mut self, table: DeviceTable) -> Self {
+        self.devices = Some(table);
+        self
+    }
+
+    /// Provision zones from `pool` when it has an idle zone of their brand
+    pub fn with_warm_pool(mut self, pool: Arc<WarmPool>) -> Self {
+        self.warm_pool = Some(pool);
+        self
+    }
+
+    /// Record pod startup latencies in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Retry failed zone provisioning with `config` before failing the pod
+    pub fn with_reconcile_backoff(mut self, config: ReconcileBackoffConfig) -> Self {
+        self.backoff = ReconcileBackoff::new(config);
+        self
+    }
+
+    /// Keep the last-known pods of this node in `cache`, and reconcile from
+    /// it while the API server is unreachable
+    pub fn with_pod_cache(mut self, cache: PodCache) -> Self {
+        self.pod_cache = Some(cache);
+        self
+    }
+
+    /// Write an SVID of its service account from `issuer` into every pod's
+    /// zone, and rotate it before it expires
+    pub fn with_svid_issuer(mut self, issuer: Arc<SvidIssuer>) -> Self {
+        self.svid_issuer = Some(issuer);
+        self
+    }
+
+    /// Record `Provisioned` and `ProbeFailed` events about pods
+    pub fn with_event_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Record every action taken on a pod, with what it was based on, in
+    /// `log`
+    pub fn with_decision_log(mut self, log: Arc<DecisionLog>) -> Self {
+        self.decisions = Some(log);
+        self
+    }
+
+    /// Record a decision about `pod`, when keeping a decision log
+    fn record_decision(
+        &self,
+        pod: &Pod,
+        zone_state: Option<ZoneState>,
+        action: DecisionAction,
+        reason: impl Into<String>,
+        outcome: DecisionOutcome,
+    ) {
+        if let Some(log) = &self.decisions {
+            log.record(pod, zone_state, action, reason, outcome);
         }
     }
 
@@ -79,7 +224,9 @@ impl PodController {
             error!("Initial reconcile failed: {}", e);
         }
 
-        let mut rx = self.event_tx.subscribe();
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut zone_events = self.runtime.subscribe_events();
+        let mut zone_events_open = true;
         let mut reconcile_tick = tokio::time::interval(self.config.reconcile_interval);
         // Consume the first tick — we just did reconcile_all() above
         reconcile_tick.tick().await;
@@ -96,9 +243,40 @@ impl PodController {
                         error!("Periodic reconcile failed: {}", e);
                     }
                 }
+                result = zone_events.recv(), if zone_events_open => {
+                    match result {
+                        Ok(event) => self.handle_zone_event(event).await,
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Missed {} zone events, doing full resync", n);
+                            if let Err(e) = self.reconcile_all().await {
+                                error!("Resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            warn!("Zone event stream closed, relying on periodic reconciles");
+                            zone_events_open = false;
+                        }
+                    }
+                }
                 result = rx.recv() => {
                     match result {
                         Ok(event) => {
+                            if event.gvk.kind == "Service" {
+                                if self.is_cluster_dns_service(&event.resource_key) {
+                                    if let Err(e) = self.reconcile_all().await {
+                                        error!("Reconcile after DNS service change failed: {}", e);
+                                    }
+                                }
+                                continue;
+                            }
+                            if event.gvk.kind == "RuntimeClass" {
+                                self.refresh_runtime_classes().await;
+                                continue;
+                            }
+                            if event.gvk.kind == "ImageMapping" {
+                                self.refresh_image_mappings().await;
+                                continue;
+                            }
                             if event.gvk.kind != "Pod" {
                                 continue;
                             }
@@ -106,6 +284,7 @@ impl PodController {
                                 WatchEventType::Added | WatchEventType::Modified => {
                                     match serde_json::from_value::<Pod>(event.object) {
                                         Ok(pod) => {
+                                            self.cache_pod(&pod);
                                             if let Err(e) = self.reconcile(&pod).await {
                                                 let name = pod.metadata.name.as_deref().unwrap_or("<unknown>");
                                                 error!("Failed to reconcile pod {}: {}", name, e);
@@ -119,6 +298,7 @@ impl PodController {
                                 WatchEventType::Deleted => {
                                     match serde_json::from_value::<Pod>(event.object) {
                                         Ok(pod) => {
+                                            self.uncache_pod(&pod);
                                             if let Err(e) = self.handle_delete(&pod).await {
                                                 let name = pod.metadata.name.as_deref().unwrap_or("<unknown>");
                                                 error!("Failed to handle pod deletion {}: {}", name, e);
@@ -133,9 +313,17 @@ impl PodController {
                             }
                         }
                         Err(broadcast::error::RecvError::Lagged(n)) => {
-                            warn!("Missed {} events, doing full resync", n);
-                            if let Err(e) = self.reconcile_all().await {
-                                error!("Resync after lag failed: {}", e);
+                            // Catch up from the bus's snapshot of pods, and
+                            // relist over HTTP only if it cannot send one
+                            let gvk = GroupVersionKind::from_api_version_kind("v1", "Pod");
+                            match self.event_bus.resync(SUBSCRIBER, &gvk) {
+                                Ok(count) => warn!("Missed {} events, resyncing {} pods", n, count),
+                                Err(e) => {
+                                    warn!("Missed {} events and cannot resync ({}), doing full resync", n, e);
+                                    if let Err(e) = self.reconcile_all().await {
+                                        error!("Resync after lag failed: {}", e);
+                                    }
+                                }
                             }
                         }
                         Err(broadcast::error::RecvError::Closed) => {
@@ -148,33 +336,391 @@ impl PodController {
         }
     }
 
+    /// Report the containers of a started pod as terminated, and the pod as
+    /// Succeeded or Failed depending on why
+    async fn set_terminated(
+        &self,
+        pod: &Pod,
+        termination: &Termination,
+        unready: Unready,
+        zone_state: ZoneState,
+    ) {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+        let status = PodStatus {
+            conditions: Some(pod_conditions(pod, &PodProgress::Started(Some(unready)))),
+            ..termination.pod_status(pod)
+        };
+
+        let result = self
+            .api_client
+            .set_pod_status(namespace, pod_name, status)
+            .await;
+        if let Err(e) = &result {
+            error!(
+                "Failed to update pod status to {}: {}",
+                termination.reason.pod_phase(),
+                e
+            );
+        }
+        self.record_decision(
+            pod,
+            Some(zone_state),
+            DecisionAction::Terminate,
+            format!(
+                "{}: {}",
+                termination.reason.as_str(),
+                termination.message.as_deref().unwrap_or_default()
+            ),
+            (&result).into(),
+        );
+    }
+
+    /// React to a zone state transition reported by the runtime. A zone that
+    /// went down fails its pod, or completes its termination, right away
+    /// instead of on the next periodic reconcile.
+    async fn handle_zone_event(&self, event: ZoneEvent) {
+        match event.kind {
+            ZoneEventKind::Booted => {
+                debug!("Zone {} booted", event.zone_name);
+            }
+            ZoneEventKind::Halted | ZoneEventKind::Panicked => {
+                if event.kind == ZoneEventKind::Panicked {
+                    warn!("Zone {} panicked, reconciling its pod", event.zone_name);
+                }
+                if let Err(e) = self.reconcile_all().await {
+                    error!(
+                        "Reconcile after zone {} went down failed: {}",
+                        event.zone_name, e
+                    );
+                }
+            }
+        }
+    }
+
     /// Reconcile all pods assigned to this node
     async fn reconcile_all(&self) -> Result<()> {
         debug!("Running pod controller reconcile cycle");
 
-        // List all pods via the API client (respects TLS configuration)
-        let body = self.api_client.get_json("/api/v1/pods").await?;
+        let dns_changed = self.refresh_cluster_dns().await;
+        self.refresh_runtime_classes().await;
+        self.refresh_image_mappings().await;
 
-        let items = body["items"].as_array().cloned().unwrap_or_default();
-
-        for item in items {
-            let pod: Pod = match serde_json::from_value(item) {
-                Ok(p) => p,
-                Err(e) => {
-                    warn!("Failed to parse pod from list: {}", e);
-                    continue;
+        // List this node's pods via the API client (respects TLS
+        // configuration), falling back to the last-known pods while it is
+        // unreachable
+        let path = format!(
+            "/api/v1/pods?fieldSelector=spec.nodeName={}",
+            self.config.node_name
+        );
+        let pods = match self.api_client.get_json(&path).await {
+            Ok(body) => {
+                let items = body["items"].as_array().cloned().unwrap_or_default();
+                let pods: Vec<Pod> = items
+                    .into_iter()
+                    .filter_map(|item| match serde_json::from_value(item) {
+                        Ok(p) => Some(p),
+                        Err(e) => {
+                            warn!("Failed to parse pod from list: {}", e);
+                            None
+                        }
+                    })
+                    .collect();
+                self.sync_pod_cache(&pods).await;
+                pods
+            }
+            Err(e) => match &self.pod_cache {
+                Some(cache) => {
+                    let pods = cache.pods()?;
+                    warn!(
+                        "Failed to list pods ({}); reconciling {} cached pod(s)",
+                        e,
+                        pods.len()
+                    );
+                    pods
                 }
-            };
+                None => return Err(e),
+            },
+        };
 
+        self.snapshot_zone_states().await;
+        for pod in pods {
             if let Err(e) = self.reconcile(&pod).await {
                 let pod_name = pod.metadata.name.as_deref().unwrap_or("<unknown>");
                 error!("Failed to reconcile pod {}: {}", pod_name, e);
             }
+            if dns_changed {
+                self.update_pod_dns(&pod).await;
+            }
         }
+        *self.zone_states.write().unwrap() = None;
 
         Ok(())
     }
 
+    /// List the states of all zones for the reconcile cycle about to start.
+    /// Each pod's zone is checked once per cycle, so the listing never goes
+    /// stale for the pod that changes it.
+    async fn snapshot_zone_states(&self) {
+        let states = match self.runtime.list_zones().await {
+            Ok(zones) => Some(
+                zones
+                    .into_iter()
+                    .map(|zone| (zone.zone_name, zone.state))
+                    .collect(),
+            ),
+            Err(e) => {
+                warn!("Failed to list zones ({}); checking them one by one", e);
+                None
+            }
+        };
+        *self.zone_states.write().unwrap() = states;
+    }
+
+    /// State of `zone_name`, from the cycle's listing when there is one
+    async fn zone_state(&self, zone_name: &str) -> Result<ZoneState> {
+        let listed = self
+            .zone_states
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|states| states.get(zone_name).cloned());
+        match listed {
+            Some(Some(state)) => Ok(state),
+            Some(None) => Err(RuntimeError::zone_not_found(zone_name)),
+            None => self.runtime.get_zone_state(zone_name).await,
+        }
+    }
+
+    /// Replace the pod cache with a fresh listing, tearing down the zones of
+    /// cached pods the listing no longer has (e.g. deleted while the API
+    /// server was unreachable)
+    async fn sync_pod_cache(&self, pods: &[Pod]) {
+        let Some(cache) = &self.pod_cache else {
+            return;
+        };
+        let cached = match cache.pods() {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("Failed to read pod cache: {}", e);
+                Vec::new()
+            }
+        };
+
+        let pod_id = |pod: &Pod| (pod.metadata.namespace.clone(), pod.metadata.name.clone());
+        for gone in cached
+            .iter()
+            .filter(|c| !pods.iter().any(|p| pod_id(p) == pod_id(c)))
+        {
+            let name = gone.metadata.name.as_deref().unwrap_or("<unknown>");
+            info!("Cached pod {} no longer exists; cleaning up", name);
+            if let Err(e) = self.handle_delete(gone).await {
+                error!("Failed to clean up removed pod {}: {}", name, e);
+            }
+        }
+
+        if let Err(e) = cache.replace(pods) {
+            warn!("Failed to update pod cache: {}", e);
+        }
+    }
+
+    /// Record a pod event in the pod cache
+    fn cache_pod(&self, pod: &Pod) {
+        if let Some(cache) = &self.pod_cache {
+            if let Err(e) = cache.upsert(pod) {
+                warn!("Failed to update pod cache: {}", e);
+            }
+        }
+    }
+
+    /// Drop a deleted pod from the pod cache
+    fn uncache_pod(&self, pod: &Pod) {
+        let (Some(cache), Some(name)) = (&self.pod_cache, pod.metadata.name.as_deref()) else {
+            return;
+        };
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        if let Err(e) = cache.remove(namespace, name) {
+            warn!("Failed to update pod cache: {}", e);
+        }
+    }
+
+    /// Re-read the handlers of all RuntimeClasses. Lookup failures keep the
+    /// last known set.
+    async fn refresh_runtime_classes(&self) {
+        let body = match self
+            .api_client
+            .get_json("/apis/node.k8s.io/v1/runtimeclasses")
+            .await
+        {
+            Ok(body) => body,
+            Err(e) => {
+                debug!("Runtime classes not available: {}", e);
+                return;
+            }
+        };
+
+        let handlers = body["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| serde_json::from_value::<RuntimeClass>(item.clone()).ok())
+            .filter_map(|class| Some((class.metadata.name?, class.handler)))
+            .collect();
+        *self.runtime_class_handlers.write().unwrap() = handlers;
+    }
+
+    /// Refresh the RuntimeClass handlers if the pod names a class not seen yet
+    async fn ensure_runtime_class_known(&self, pod: &Pod) {
+        let Some(class) = pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.runtime_class_name.as_deref())
+        else {
+            return;
+        };
+        let known = self
+            .runtime_class_handlers
+            .read()
+            .unwrap()
+            .contains_key(class);
+        if !known {
+            self.refresh_runtime_classes().await;
+        }
+    }
+
+    /// Re-read the ImageMappings. Lookup failures keep the last known set.
+    async fn refresh_image_mappings(&self) {
+        let body = match self
+            .api_client
+            .get_json("/apis/images.reddwarf.io/v1alpha1/imagemappings")
+            .await
+        {
+            Ok(body) => body,
+            Err(e) => {
+                debug!("Image mappings not available: {}", e);
+                return;
+            }
+        };
+
+        let mappings = body["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| serde_json::from_value::<ImageMapping>(item.clone()).ok())
+            .collect();
+        *self.image_mappings.write().unwrap() = mappings;
+    }
+
+    /// Refresh the ImageMappings if an lx pod's images don't resolve with
+    /// the known ones
+    async fn ensure_image_mappings_known(&self, pod: &Pod) {
+        if !matches!(self.pod_brand(pod), Ok(ZoneBrand::Lx)) {
+            return;
+        }
+        let resolves = pod_lx_image(pod, &self.image_mappings.read().unwrap()).is_ok();
+        if !resolves {
+            self.refresh_image_mappings().await;
+        }
+    }
+
+    /// Provision a zone, from the warm pool when it can offer one
+    async fn provision_zone(&self, zone_config: &ZoneConfig) -> Result<()> {
+        if let Some(pool) = &self.warm_pool {
+            if pool.claim(zone_config).await? {
+                return Ok(());
+            }
+        }
+        self.runtime.provision(zone_config).await
+    }
+
+    /// Whether `key` names the configured cluster DNS Service
+    fn is_cluster_dns_service(&self, key: &reddwarf_core::ResourceKey) -> bool {
+        self.config
+            .cluster_dns_service
+            .as_deref()
+            .and_then(|s| s.split_once('/'))
+            .is_some_and(|(ns, name)| key.namespace == ns && key.name == name)
+    }
+
+    /// Re-read the cluster DNS Service's clusterIP, returning whether it changed.
+    /// Lookup failures keep the last known address.
+    async fn refresh_cluster_dns(&self) -> bool {
+        let Some((namespace, name)) = self
+            .config
+            .cluster_dns_service
+            .as_deref()
+            .and_then(|s| s.split_once('/'))
+        else {
+            return false;
+        };
+
+        let path = format!("/api/v1/namespaces/{}/services/{}", namespace, name);
+        let ip = match self.api_client.get_json(&path).await {
+            Ok(service) => service["spec"]["clusterIP"]
+                .as_str()
+                .filter(|ip| !ip.is_empty() && *ip != "None")
+                .map(String::from),
+            Err(e) => {
+                debug!(
+                    "Cluster DNS service {}/{} not available: {}",
+                    namespace, name, e
+                );
+                return false;
+            }
+        };
+
+        let mut current = self.cluster_dns_ip.write().unwrap();
+        if *current == ip {
+            return false;
+        }
+        info!(
+            "Cluster DNS service {}/{} address changed: {:?} -> {:?}",
+            namespace, name, *current, ip
+        );
+        *current = ip;
+        true
+    }
+
+    /// Resolver configuration for a pod
+    fn pod_dns_config(&self, pod: &Pod) -> DnsConfig {
+        let cluster_dns = self.cluster_dns_ip.read().unwrap().clone();
+        pod_dns_config(
+            pod,
+            cluster_dns.as_deref(),
+            &self.config.cluster_domain,
+            &self.host_dns,
+        )
+    }
+
+    /// Rewrite the resolv.conf of a running pod on this node that resolves
+    /// through cluster DNS
+    async fn update_pod_dns(&self, pod: &Pod) {
+        let on_this_node = pod.spec.as_ref().and_then(|s| s.node_name.as_deref())
+            == Some(self.config.node_name.as_str());
+        let running = pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running");
+        if !on_this_node
+            || !running
+            || pod.metadata.deletion_timestamp.is_some()
+            || !uses_cluster_dns(pod)
+        {
+            return;
+        }
+
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+        let zone_name = pod_zone_name(namespace, pod_name);
+        let zonepath = format!("{}/{}", self.config.zonepath_prefix, zone_name);
+
+        let dns = self.pod_dns_config(pod);
+        if let Err(e) = self
+            .runtime
+            .set_resolv_conf(&zone_name, &zonepath, &dns)
+            .await
+        {
+            warn!("Failed to update resolv.conf of zone {}: {}", zone_name, e);
+        }
+    }
+
     /// Reconcile a single Pod event
     pub async fn reconcile(&self, pod: &Pod) -> Result<()> {
         let pod_name = pod
@@ -221,49 +767,177 @@ impl PodController {
 
         match phase {
             "" | "Pending" => {
-                // Pod is assigned to us but has no phase — provision it
+                // Pod is assigned to us but has no phase — provision it,
+                // unless an earlier attempt failed recently
+                let pod_key = format!("{}/{}", namespace, pod_name);
+                if let Some(wait) = self.backoff.retry_in(&pod_key) {
+                    debug!(
+                        "Pod {} is backing off after a provisioning failure, retrying in {:?}",
+                        pod_key, wait
+                    );
+                    return Ok(());
+                }
                 info!("Provisioning zone for pod {}/{}", namespace, pod_name);
-                let zone_config = self.pod_to_zone_config(pod)?;
+                self.ensure_runtime_class_known(pod).await;
+                self.ensure_image_mappings_known(pod).await;
+                let mut zone_config = self.pod_to_zone_config(pod)?;
+
+                let provision_started = Utc::now();
+                let provisioned = match self
+                    .mount_claims(pod, &mut zone_config)
+                    .await
+                    .and_then(|()| check_pod_ports(pod))
+                    .and_then(|()| self.apply_tunables(pod, &mut zone_config))
+                    .and_then(|()| self.reserve_host_ports(pod))
+                    .and_then(|()| self.allocate_devices(pod, &mut zone_config))
+                {
+                    Ok(()) => self.provision_zone(&zone_config).await,
+                    Err(e) => Err(e),
+                };
 
-                match self.runtime.provision(&zone_config).await {
+                match provisioned {
                     Ok(()) => {
                         info!("Zone {} provisioned successfully", zone_name);
+                        self.backoff.forget(&pod_key);
+                        if let Some(recorder) = &self.recorder {
+                            recorder.normal(
+                                pod,
+                                REASON_PROVISIONED,
+                                format!("Provisioned zone {}", zone_name),
+                            );
+                        }
+                        let zone_booted = Utc::now();
+                        let mut status_annotations =
+                            self.apply_bandwidth_limits(pod, &zone_config).await;
+                        self.apply_host_ports(pod, &zone_config).await;
+                        self.rotate_svid(pod).await;
+
+                        let startup = PodStartup {
+                            provision_started: Some(provision_started),
+                            zone_booted: Some(zone_booted),
+                            ready: Some(Utc::now()),
+                            ..PodStartup::from_pod(pod)
+                        };
+                        status_annotations.extend(startup.status_annotations());
+
                         // Update pod status to Running
                         let status = PodStatus {
                             phase: Some("Running".to_string()),
-                            conditions: Some(vec![PodCondition {
-                                type_: "Ready".to_string(),
-                                status: "True".to_string(),
-                                ..Default::default()
-                            }]),
+                            conditions: Some(pod_conditions(pod, &PodProgress::Started(None))),
                             pod_ip: Some(self.zone_ip(&zone_config)),
                             ..Default::default()
                         };
 
                         if let Err(e) = self
                             .api_client
-                            .set_pod_status(namespace, pod_name, status)
+                            .set_pod_status_with_annotations(
+                                namespace,
+                                pod_name,
+                                status,
+                                status_annotations,
+                            )
                             .await
                         {
                             error!("Failed to update pod status to Running: {}", e);
                         }
+                        self.record_decision(
+                            pod,
+                            None,
+                            DecisionAction::Provision,
+                            "Pod is assigned to this node and has no zone",
+                            DecisionOutcome::Succeeded,
+                        );
+                        if let Some(metrics) = &self.metrics {
+                            startup.record(metrics);
+                        }
                     }
                     Err(e) => {
                         // Check if it's already provisioned (zone already exists)
-                        if matches!(e, RuntimeError::ZoneAlreadyExists { .. }) {
+                        if e.kind() == ErrorKind::AlreadyExists {
                             debug!("Zone {} already exists, checking state", zone_name);
                             return Ok(());
                         }
-                        error!("Failed to provision zone {}: {}", zone_name, e);
-                        let status = PodStatus {
-                            phase: Some("Failed".to_string()),
-                            conditions: Some(vec![PodCondition {
-                                type_: "Ready".to_string(),
-                                status: "False".to_string(),
-                                message: Some(format!("Zone provisioning failed: {}", e)),
-                                ..Default::default()
-                            }]),
-                            ..Default::default()
+                        // A bad configuration fails the same way every time
+                        let decision = if e.kind() == ErrorKind::Invalid {
+                            self.backoff.forget(&pod_key);
+                            BackoffDecision::Exhausted {
+                                attempts: 1,
+                                elapsed: Duration::ZERO,
+                            }
+                        } else {
+                            self.backoff.record_failure(&pod_key)
+                        };
+
+                        let status = match decision {
+                            BackoffDecision::Retry { attempt, delay } => {
+                                warn!(
+                                    "Failed to provision zone {} (attempt {}), retrying in {:?}: {}",
+                                    zone_name, attempt, delay, e
+                                );
+                                self.record_decision(
+                                    pod,
+                                    None,
+                                    DecisionAction::RetryProvision,
+                                    format!(
+                                        "Provisioning attempt {} failed, retrying in {}s",
+                                        attempt,
+                                        delay.as_secs()
+                                    ),
+                                    DecisionOutcome::Failed(e.to_string()),
+                                );
+                                // Start the next attempt from a clean slate
+                                if let Err(e) = self.runtime.deprovision(&zone_config).await {
+                                    debug!("No partial zone {} to clean up: {}", zone_name, e);
+                                }
+                                let unready = Unready::new(
+                                    "ProvisioningBackoff",
+                                    format!(
+                                        "Zone provisioning attempt {} failed, retrying in {}s: {}",
+                                        attempt,
+                                        delay.as_secs(),
+                                        e
+                                    ),
+                                );
+                                PodStatus {
+                                    phase: Some("Pending".to_string()),
+                                    conditions: Some(pod_conditions(
+                                        pod,
+                                        &PodProgress::Initializing(unready),
+                                    )),
+                                    ..Default::default()
+                                }
+                            }
+                            BackoffDecision::Exhausted { attempts, elapsed } => {
+                                error!("Failed to provision zone {}: {}", zone_name, e);
+                                self.record_decision(
+                                    pod,
+                                    None,
+                                    DecisionAction::Provision,
+                                    format!(
+                                        "Gave up after {} attempt(s) over {}s and failed the pod",
+                                        attempts,
+                                        elapsed.as_secs()
+                                    ),
+                                    DecisionOutcome::Failed(e.to_string()),
+                                );
+                                let unready = Unready::new(
+                                    "ProvisioningFailed",
+                                    format!(
+                                        "Zone provisioning failed after {} attempt(s) over {}s: {}",
+                                        attempts,
+                                        elapsed.as_secs(),
+                                        e
+                                    ),
+                                );
+                                PodStatus {
+                                    phase: Some("Failed".to_string()),
+                                    conditions: Some(pod_conditions(
+                                        pod,
+                                        &PodProgress::Initializing(unready),
+                                    )),
+                                    ..Default::default()
+                                }
+                            }
                         };
 
                         if let Err(e2) = self
@@ -278,8 +952,10 @@ impl PodController {
             }
             "Running" => {
                 // Check zone health
-                match self.runtime.get_zone_state(&zone_name).await {
+                match self.zone_state(&zone_name).await {
                     Ok(ZoneState::Running) => {
+                        self.rotate_svid(pod).await;
+
                         // Zone is running — execute health probes
                         let pod_key = format!("{}/{}", namespace, pod_name);
                         let zone_ip = self.get_pod_ip(pod);
@@ -304,25 +980,14 @@ impl PodController {
                                 "Liveness probe failed for pod {}/{}: {}",
                                 namespace, pod_name, message
                             );
-                            let pod_status = PodStatus {
-                                phase: Some("Failed".to_string()),
-                                conditions: Some(vec![PodCondition {
-                                    type_: "Ready".to_string(),
-                                    status: "False".to_string(),
-                                    reason: Some("LivenessProbeFailure".to_string()),
-                                    message: Some(message),
-                                    ..Default::default()
-                                }]),
-                                ..Default::default()
-                            };
-
-                            if let Err(e) = self
-                                .api_client
-                                .set_pod_status(namespace, pod_name, pod_status)
-                                .await
-                            {
-                                error!("Failed to update pod status to Failed: {}", e);
+                            if let Some(recorder) = &self.recorder {
+                                recorder.warning(pod, REASON_PROBE_FAILED, message.clone());
                             }
+                            let unready = Unready::new("LivenessProbeFailure", message.clone());
+                            let termination =
+                                Termination::new(TerminationReason::ProbeFailure, message);
+                            self.set_terminated(pod, &termination, unready, ZoneState::Running)
+                                .await;
 
                             // Unregister probes for this pod
                             let mut tracker = self.probe_tracker.lock().await;
@@ -336,66 +1001,61 @@ impl PodController {
                                 namespace, pod_name, message
                             );
 
-                            // Only update if currently marked Ready=True
-                            let currently_ready = pod
-                                .status
-                                .as_ref()
-                                .and_then(|s| s.conditions.as_ref())
-                                .and_then(|c| c.iter().find(|c| c.type_ == "Ready"))
-                                .map(|c| c.status == "True")
-                                .unwrap_or(false);
+                            // Only update when a condition flips
+                            let unready = Unready::new("ReadinessProbeFailure", message.clone());
+                            let conditions =
+                                pod_conditions(pod, &PodProgress::Started(Some(unready)));
 
-                            if currently_ready {
+                            if conditions_changed(pod, &conditions) {
                                 let pod_status = PodStatus {
                                     phase: Some("Running".to_string()),
-                                    conditions: Some(vec![PodCondition {
-                                        type_: "Ready".to_string(),
-                                        status: "False".to_string(),
-                                        reason: Some("ReadinessProbeFailure".to_string()),
-                                        message: Some(message),
-                                        ..Default::default()
-                                    }]),
+                                    conditions: Some(conditions),
                                     pod_ip: Some(zone_ip),
                                     ..Default::default()
                                 };
 
-                                if let Err(e) = self
+                                let result = self
                                     .api_client
                                     .set_pod_status(namespace, pod_name, pod_status)
-                                    .await
-                                {
+                                    .await;
+                                if let Err(e) = &result {
                                     error!("Failed to update pod status: {}", e);
                                 }
+                                self.record_decision(
+                                    pod,
+                                    Some(ZoneState::Running),
+                                    DecisionAction::MarkUnready,
+                                    message,
+                                    (&result).into(),
+                                );
                             }
                         } else {
-                            // All probes pass — set Ready=True if not already
-                            let currently_ready = pod
-                                .status
-                                .as_ref()
-                                .and_then(|s| s.conditions.as_ref())
-                                .and_then(|c| c.iter().find(|c| c.type_ == "Ready"))
-                                .map(|c| c.status == "True")
-                                .unwrap_or(false);
-
-                            if !currently_ready {
+                            // All probes pass — containers are ready, and the
+                            // pod is once its readiness gates are
+                            let conditions = pod_conditions(pod, &PodProgress::Started(None));
+
+                            if conditions_changed(pod, &conditions) {
                                 let pod_status = PodStatus {
                                     phase: Some("Running".to_string()),
-                                    conditions: Some(vec![PodCondition {
-                                        type_: "Ready".to_string(),
-                                        status: "True".to_string(),
-                                        ..Default::default()
-                                    }]),
+                                    conditions: Some(conditions),
                                     pod_ip: Some(zone_ip),
                                     ..Default::default()
                                 };
 
-                                if let Err(e) = self
+                                let result = self
                                     .api_client
                                     .set_pod_status(namespace, pod_name, pod_status)
-                                    .await
-                                {
+                                    .await;
+                                if let Err(e) = &result {
                                     error!("Failed to update pod status: {}", e);
                                 }
+                                self.record_decision(
+                                    pod,
+                                    Some(ZoneState::Running),
+                                    DecisionAction::MarkReady,
+                                    "All probes pass",
+                                    (&result).into(),
+                                );
                             }
                         }
                     }
@@ -404,48 +1064,43 @@ impl PodController {
                             "Zone {} is in unexpected state: {} (expected Running)",
                             zone_name, state
                         );
-                        let status = PodStatus {
-                            phase: Some("Failed".to_string()),
-                            conditions: Some(vec![PodCondition {
-                                type_: "Ready".to_string(),
-                                status: "False".to_string(),
-                                message: Some(format!("Zone is in unexpected state: {}", state)),
-                                ..Default::default()
-                            }]),
-                            ..Default::default()
+                        let message = format!("Zone is in unexpected state: {}", state);
+                        let termination = match self.runtime.zone_exit(&zone_name).await {
+                            Ok(Some(exit)) => Termination {
+                                message: Some(format!(
+                                    "Zone exited with status {}",
+                                    exit.exit_code
+                                )),
+                                ..Termination::exited(exit.exit_code, exit.oom_killed)
+                            },
+                            Ok(None)
+                                if matches!(state, ZoneState::ShuttingDown | ZoneState::Down) =>
+                            {
+                                Termination::new(TerminationReason::NodeShutdown, message.clone())
+                            }
+                            Ok(None) => Termination::new(TerminationReason::Error, message.clone()),
+                            Err(e) => {
+                                debug!("Could not get the exit of zone {}: {}", zone_name, e);
+                                Termination::new(TerminationReason::Error, message.clone())
+                            }
                         };
-
-                        if let Err(e) = self
-                            .api_client
-                            .set_pod_status(namespace, pod_name, status)
-                            .await
-                        {
-                            error!("Failed to update pod status to Failed: {}", e);
-                        }
+                        self.set_terminated(pod, &termination, Unready::message(message), state)
+                            .await;
                     }
                     Err(RuntimeError::ZoneNotFound { .. }) => {
                         warn!(
                             "Zone {} not found but pod is Running — marking Failed",
                             zone_name
                         );
-                        let status = PodStatus {
-                            phase: Some("Failed".to_string()),
-                            conditions: Some(vec![PodCondition {
-                                type_: "Ready".to_string(),
-                                status: "False".to_string(),
-                                message: Some("Zone not found".to_string()),
-                                ..Default::default()
-                            }]),
-                            ..Default::default()
-                        };
-
-                        if let Err(e) = self
-                            .api_client
-                            .set_pod_status(namespace, pod_name, status)
-                            .await
-                        {
-                            error!("Failed to update pod status to Failed: {}", e);
-                        }
+                        let termination =
+                            Termination::new(TerminationReason::Error, "Zone not found");
+                        self.set_terminated(
+                            pod,
+                            &termination,
+                            Unready::message("Zone not found"),
+                            ZoneState::Absent,
+                        )
+                        .await;
                     }
                     Err(e) => {
                         debug!("Could not check zone state for {}: {}", zone_name, e);
@@ -468,7 +1123,8 @@ impl PodController {
     /// If the pod has a `deletion_timestamp`, the graceful termination state
     /// machine (`handle_termination`) is responsible for cleanup, so this method
     /// becomes a no-op. Otherwise (e.g. a direct storage delete that bypasses
-    /// the graceful path), fall back to the original immediate cleanup.
+    /// the graceful path, or a force delete with a zero grace period), fall
+    /// back to the original immediate cleanup.
     pub async fn handle_delete(&self, pod: &Pod) -> Result<()> {
         let pod_name = pod
             .metadata
@@ -477,8 +1133,11 @@ impl PodController {
             .ok_or_else(|| RuntimeError::internal_error("Pod has no name"))?;
         let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
 
-        // If deletion_timestamp is set, handle_termination is driving cleanup
-        if pod.metadata.deletion_timestamp.is_some() {
+        // If deletion_timestamp is set, handle_termination is driving cleanup,
+        // unless the pod was force deleted before it could finish
+        if pod.metadata.deletion_timestamp.is_some()
+            && pod.metadata.deletion_grace_period_seconds != Some(0)
+        {
             debug!(
                 "Pod {}/{} has deletion_timestamp, skipping handle_delete (handled by termination state machine)",
                 namespace, pod_name
@@ -503,12 +1162,20 @@ impl PodController {
             namespace, pod_name
         );
 
-        if let Err(e) = self.runtime.deprovision(&zone_config).await {
+        let result = self.runtime.deprovision(&zone_config).await;
+        if let Err(e) = &result {
             warn!(
                 "Failed to deprovision zone for pod {}/{}: {}",
                 namespace, pod_name, e
             );
         }
+        self.record_decision(
+            pod,
+            None,
+            DecisionAction::Deprovision,
+            "Pod was removed without graceful termination",
+            (&result).into(),
+        );
 
         // Release the IP allocation
         if let Err(e) = self.ipam.release(namespace, pod_name) {
@@ -518,8 +1185,13 @@ impl PodController {
             );
         }
 
+        self.release_host_ports(namespace, pod_name).await;
+        self.release_devices(namespace, pod_name);
+
         // Unregister probes
         let pod_key = format!("{}/{}", namespace, pod_name);
+        self.backoff.forget(&pod_key);
+        self.svid_renewals.lock().unwrap().remove(&pod_key);
         let mut tracker = self.probe_tracker.lock().await;
         tracker.unregister_pod(&pod_key);
 
@@ -548,7 +1220,7 @@ impl PodController {
         let grace_expired = self.is_grace_period_expired(pod);
 
         // Query actual zone state
-        let zone_state = match self.runtime.get_zone_state(&zone_name).await {
+        let zone_state = match self.zone_state(&zone_name).await {
             Ok(state) => state,
             Err(RuntimeError::ZoneNotFound { .. }) => ZoneState::Absent,
             Err(e) => {
@@ -572,18 +1244,34 @@ impl PodController {
                         "Grace period expired for pod {}/{}, force halting zone {}",
                         namespace, pod_name, zone_name
                     );
-                    if let Err(e) = self.runtime.halt_zone(&zone_name).await {
+                    let result = self.runtime.halt_zone(&zone_name).await;
+                    if let Err(e) = &result {
                         warn!("Failed to halt zone {}: {}", zone_name, e);
                     }
+                    self.record_decision(
+                        pod,
+                        Some(zone_state),
+                        DecisionAction::Halt,
+                        "Pod is being deleted and its grace period expired",
+                        (&result).into(),
+                    );
                     // Deprovision will happen on next reconcile when zone is stopped
                 } else {
                     info!(
                         "Initiating graceful shutdown for zone {} (pod {}/{})",
                         zone_name, namespace, pod_name
                     );
-                    if let Err(e) = self.runtime.shutdown_zone(&zone_name).await {
+                    let result = self.runtime.shutdown_zone(&zone_name).await;
+                    if let Err(e) = &result {
                         warn!("Failed to shut down zone {}: {}", zone_name, e);
                     }
+                    self.record_decision(
+                        pod,
+                        Some(zone_state),
+                        DecisionAction::Shutdown,
+                        "Pod is being deleted",
+                        (&result).into(),
+                    );
                     // Next reconcile will re-check the zone state
                 }
             }
@@ -593,9 +1281,17 @@ impl PodController {
                         "Grace period expired while zone {} was shutting down, force halting",
                         zone_name
                     );
-                    if let Err(e) = self.runtime.halt_zone(&zone_name).await {
+                    let result = self.runtime.halt_zone(&zone_name).await;
+                    if let Err(e) = &result {
                         warn!("Failed to halt zone {}: {}", zone_name, e);
                     }
+                    self.record_decision(
+                        pod,
+                        Some(zone_state),
+                        DecisionAction::Halt,
+                        "Grace period expired before the zone shut down",
+                        (&result).into(),
+                    );
                 } else {
                     debug!(
                         "Zone {} is gracefully shutting down, waiting for next reconcile",
@@ -631,14 +1327,19 @@ impl PodController {
                     );
                 }
 
+                self.release_host_ports(namespace, pod_name).await;
+                self.release_devices(namespace, pod_name);
+
                 // Unregister probes
                 let pod_key = format!("{}/{}", namespace, pod_name);
+                self.svid_renewals.lock().unwrap().remove(&pod_key);
                 let mut tracker = self.probe_tracker.lock().await;
                 tracker.unregister_pod(&pod_key);
                 drop(tracker);
 
                 // Finalize — remove the pod from API server storage
-                if let Err(e) = self.api_client.finalize_pod(namespace, pod_name).await {
+                let result = self.api_client.finalize_pod(namespace, pod_name).await;
+                if let Err(e) = &result {
                     error!(
                         "Failed to finalize pod {}/{}: {}",
                         namespace, pod_name, e
@@ -646,6 +1347,13 @@ impl PodController {
                 } else {
                     info!("Pod {}/{} finalized and removed", namespace, pod_name);
                 }
+                self.record_decision(
+                    pod,
+                    Some(zone_state.clone()),
+                    DecisionAction::Finalize,
+                    format!("Pod is being deleted and its zone is {}", zone_state),
+                    (&result).into(),
+                );
             }
         }
 
@@ -669,6 +1377,28 @@ impl PodController {
     }
 
     /// Convert a Pod spec to a ZoneConfig with per-pod VNIC and IP
+    /// The zone brand of a pod: the RuntimeClass handler names it; pods
+    /// without a class may still use the legacy annotation
+    fn pod_brand(&self, pod: &Pod) -> Result<ZoneBrand> {
+        let selected =
+            pod_zone_brand(pod, &self.runtime_class_handlers.read().unwrap()).map_err(|msg| {
+                RuntimeError::invalid_config(msg, "Create the RuntimeClass the pod names")
+            })?;
+        let has_class = pod
+            .spec
+            .as_ref()
+            .is_some_and(|s| s.runtime_class_name.is_some());
+        match selected.as_deref() {
+            Some("lx") => Ok(ZoneBrand::Lx),
+            Some("reddwarf") => Ok(ZoneBrand::Reddwarf),
+            Some(handler) if has_class => Err(RuntimeError::invalid_config(
+                format!("RuntimeClass handler '{}' is not a zone brand", handler),
+                "Use the handler 'reddwarf' or 'lx'",
+            )),
+            _ => Ok(self.config.default_brand.clone()),
+        }
+    }
+
     fn pod_to_zone_config(&self, pod: &Pod) -> Result<ZoneConfig> {
         let pod_name = pod
             .metadata
@@ -685,9 +1415,13 @@ impl PodController {
         let zone_name = pod_zone_name(namespace, pod_name);
         let zonepath = format!("{}/{}", self.config.zonepath_prefix, zone_name);
 
-        // Allocate a unique VNIC name and IP for this pod
+        // Allocate a unique VNIC name and IP for this pod, taking the
+        // address the scheduler reserved when it bound the pod if any
         let vnic_name = vnic_name_for_pod(namespace, pod_name);
-        let allocation = self.ipam.allocate(namespace, pod_name)?;
+        let allocation = match reserved_pod_ip(&pod.metadata) {
+            Some(reserved) => self.ipam.allocate_reserved(namespace, pod_name, reserved)?,
+            None => self.ipam.allocate(namespace, pod_name)?,
+        };
 
         let network = NetworkMode::Etherstub(EtherstubConfig {
             etherstub_name: self.config.etherstub_name.clone(),
@@ -725,6 +1459,8 @@ impl PodController {
                     command,
                     working_dir: c.working_dir.clone(),
                     env,
+                    stdin: c.stdin.unwrap_or(false),
+                    tty: c.tty.unwrap_or(false),
                 }
             })
             .collect();
@@ -748,6 +1484,21 @@ impl PodController {
                 (cpu + c_cpu, mem + c_mem)
             });
 
+        // RuntimeClass overhead is part of the zone's budget when it is capped
+        let overhead = spec
+            .overhead
+            .as_ref()
+            .map(ResourceQuantities::from_k8s_resource_map)
+            .unwrap_or_default();
+        let total_cpu_millicores = match total_cpu_millicores {
+            0 => 0,
+            cpu => cpu + overhead.cpu_millicores,
+        };
+        let total_memory_bytes = match total_memory_bytes {
+            0 => 0,
+            mem => mem + overhead.memory_bytes,
+        };
+
         let cpu_cap = if total_cpu_millicores > 0 {
             Some(ResourceQuantities::cpu_as_zone_cap(total_cpu_millicores))
         } else {
@@ -760,32 +1511,335 @@ impl PodController {
             None
         };
 
-        let brand = pod
-            .metadata
-            .annotations
-            .as_ref()
-            .and_then(|a| a.get("reddwarf.io/zone-brand"))
-            .and_then(|v| match v.as_str() {
-                "lx" => Some(ZoneBrand::Lx),
-                "reddwarf" => Some(ZoneBrand::Reddwarf),
-                _ => None,
-            })
-            .unwrap_or_else(|| self.config.default_brand.clone());
+        // Shares, pset binding and process limits follow the pod's QoS class
+        let cpu_requests: i64 = spec
+            .containers
+            .iter()
+            .filter_map(|c| c.resources.as_ref())
+            .filter_map(|r| r.requests.as_ref().or(r.limits.as_ref()))
+            .map(|m| ResourceQuantities::from_k8s_resource_map(m).cpu_millicores)
+            .sum();
+        let cpu_limits: i64 = spec
+            .containers
+            .iter()
+            .filter_map(|c| c.resources.as_ref().and_then(|r| r.limits.as_ref()))
+            .map(|m| ResourceQuantities::from_k8s_resource_map(m).cpu_millicores)
+            .sum();
+        let qos = pod_qos_class(pod);
+        let controls = ResourceControls::for_pod(qos, cpu_requests, cpu_limits);
+
+        // Guaranteed pods get a hard swap cap equal to their memory limit so
+        // they never overcommit; other classes only get the physical cap
+        // (enforced by rcapd paging) and are eviction candidates instead.
+        let swap_cap = match qos {
+            QosClass::Guaranteed => memory_cap.clone(),
+            _ => None,
+        };
 
-        Ok(ZoneConfig {
-            zone_name,
-            brand,
-            zonepath,
-            network,
-            storage: ZoneStorageOpts::default(),
-            lx_image_path: None,
-            processes,
+        let (hostname, fqdn) = pod_hostname(pod, &self.config.cluster_domain);
+
+        // All containers share the zone root, so it is only read-only when
+        // every container asks for it
+        let read_only: Vec<bool> = spec
+            .containers
+            .iter()
+            .map(|c| {
+                c.security_context
+                    .as_ref()
+                    .and_then(|sc| sc.read_only_root_filesystem)
+                    .unwrap_or(false)
+            })
+            .collect();
+        let read_only_root = !read_only.is_empty() && read_only.iter().all(|ro| *ro);
+        if !read_only_root && read_only.iter().any(|ro| *ro) {
+            warn!(
+                "Pod {}/{}: readOnlyRootFilesystem is ignored unless set on every container",
+                namespace, pod_name
+            );
+        }
+
+        let brand = self.pod_brand(pod)?;
+
+        // lx zones are installed from the lx image the pod's container
+        // images map to
+        let lx_image_path = match brand {
+            ZoneBrand::Lx => {
+                pod_lx_image(pod, &self.image_mappings.read().unwrap()).map_err(|msg| {
+                    RuntimeError::invalid_config(
+                        msg,
+                        "Create an ImageMapping for the pod's image repository and tag",
+                    )
+                })?
+            }
+            _ => None,
+        };
+
+        Ok(ZoneConfig {
+            zone_name,
+            brand,
+            zonepath,
+            network,
+            storage: ZoneStorageOpts::default(),
+            lx_image_path,
+            processes,
             cpu_cap,
             memory_cap,
+            swap_cap,
+            cpu_shares: controls.cpu_shares,
+            dedicated_cpus: controls.dedicated_cpus,
+            max_lwps: controls.max_lwps,
+            max_processes: controls.max_processes,
             fs_mounts: vec![],
+            dns: Some(self.pod_dns_config(pod)),
+            hostname: Some(hostname),
+            fqdn,
+            read_only_root,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
         })
     }
 
+    /// Apply `kubernetes.io/{ingress,egress}-bandwidth` limits (from the pod,
+    /// falling back to its namespace) to the zone's VNIC.
+    ///
+    /// Returns the status annotations describing what was applied. Failures
+    /// are logged rather than failing the pod.
+    async fn apply_bandwidth_limits(
+        &self,
+        pod: &Pod,
+        zone_config: &ZoneConfig,
+    ) -> BTreeMap<String, String> {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let ns_annotations = self.namespace_annotations(namespace).await;
+        let limits = BandwidthLimits::for_pod(pod, ns_annotations.as_ref());
+        if limits.is_empty() {
+            return BTreeMap::new();
+        }
+
+        let vnic_name = match &zone_config.network {
+            NetworkMode::Etherstub(e) => &e.vnic_name,
+            NetworkMode::Direct(d) => &d.vnic_name,
+        };
+        match self
+            .runtime
+            .set_link_bandwidth(vnic_name, limits.maxbw_bps())
+            .await
+        {
+            Ok(()) => limits.status_annotations(),
+            Err(e) => {
+                warn!("Failed to apply bandwidth limits to {}: {}", vnic_name, e);
+                BTreeMap::new()
+            }
+        }
+    }
+
+    /// Mount the volumes bound to the pod's PersistentVolumeClaims into its
+    /// zone, failing while any claim is not bound yet
+    async fn mount_claims(&self, pod: &Pod, zone_config: &mut ZoneConfig) -> Result<()> {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let claims = pod
+            .spec
+            .iter()
+            .flat_map(|s| s.volumes.iter().flatten())
+            .filter_map(|v| Some((v.name.as_str(), v.persistent_volume_claim.as_ref()?)));
+
+        let mut sources = HashMap::new();
+        for (volume, claim) in claims {
+            let path = format!(
+                "/api/v1/namespaces/{}/persistentvolumeclaims/{}",
+                namespace, claim.claim_name
+            );
+            let claim_object: PersistentVolumeClaim =
+                serde_json::from_value(self.api_client.get_json(&path).await?).map_err(|e| {
+                    RuntimeError::internal_error(format!("Failed to parse claim: {}", e))
+                })?;
+            let volume_name = claim_object
+                .spec
+                .as_ref()
+                .and_then(|s| s.volume_name.as_deref())
+                .filter(|_| claim_phase(&claim_object) == PHASE_BOUND)
+                .ok_or_else(|| {
+                    RuntimeError::zone_operation_failed(
+                        &zone_config.zone_name,
+                        format!(
+                            "PersistentVolumeClaim {}/{} is not bound yet",
+                            namespace, claim.claim_name
+                        ),
+                    )
+                })?;
+
+            let path = format!("/api/v1/persistentvolumes/{}", volume_name);
+            let bound: PersistentVolume =
+                serde_json::from_value(self.api_client.get_json(&path).await?).map_err(|e| {
+                    RuntimeError::internal_error(format!("Failed to parse volume: {}", e))
+                })?;
+            let source = volume_path(&bound).ok_or_else(|| {
+                RuntimeError::invalid_config(
+                    format!("PersistentVolume {} has no local path", volume_name),
+                    "Back the volume with a local or hostPath source",
+                )
+            })?;
+            sources.insert(
+                volume.to_string(),
+                (source.to_string(), claim.read_only == Some(true)),
+            );
+        }
+
+        zone_config.fs_mounts.extend(claim_fs_mounts(pod, &sources));
+        Ok(())
+    }
+
+    /// Add the pod's sysctls and zone attributes to its zone config, failing
+    /// if it asks for anything the node does not allow
+    fn apply_tunables(&self, pod: &Pod, zone_config: &mut ZoneConfig) -> Result<()> {
+        let tunables = PodTunables::for_pod(pod, &self.config.allowed_tunables)?;
+        zone_config.attrs = tunables.attrs;
+        zone_config.ip_props = tunables.ip_props;
+        Ok(())
+    }
+
+    /// Reserve the pod's host ports on this node. Pods bound by the scheduler
+    /// already hold them; this catches pods created with `nodeName` set.
+    fn reserve_host_ports(&self, pod: &Pod) -> Result<()> {
+        let Some(table) = &self.host_ports else {
+            return Ok(());
+        };
+        let ports = pod_host_ports(pod);
+        if ports.is_empty() {
+            return Ok(());
+        }
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+        table.reserve(namespace, pod_name, &ports)
+    }
+
+    /// Forward the pod's host ports from the host interface to the zone.
+    /// Failures are logged rather than failing the pod.
+    async fn apply_host_ports(&self, pod: &Pod, zone_config: &ZoneConfig) {
+        let ports = pod_host_ports(pod);
+        if ports.is_empty() {
+            return;
+        }
+        let Some(interface) = &self.config.host_port_interface else {
+            warn!(
+                "Zone {} requests host ports but no host port interface is configured",
+                zone_config.zone_name
+            );
+            return;
+        };
+
+        let forwards = port_forwards(interface, &self.zone_ip(zone_config), &ports);
+        if let Err(e) = self
+            .runtime
+            .set_port_forwards(&zone_config.zone_name, &forwards)
+            .await
+        {
+            warn!(
+                "Failed to forward host ports to zone {}: {}",
+                zone_config.zone_name, e
+            );
+        }
+    }
+
+    /// Remove a deleted pod's port forwards and free its host ports
+    async fn release_host_ports(&self, namespace: &str, pod_name: &str) {
+        let zone_name = pod_zone_name(namespace, pod_name);
+        if let Err(e) = self.runtime.set_port_forwards(&zone_name, &[]).await {
+            warn!(
+                "Failed to remove host port forwards of zone {}: {}",
+                zone_name, e
+            );
+        }
+        if let Some(table) = &self.host_ports {
+            if let Err(e) = table.release(namespace, pod_name) {
+                warn!(
+                    "Failed to release host ports of pod {}/{}: {}",
+                    namespace, pod_name, e
+                );
+            }
+        }
+    }
+
+    /// Assign the devices the pod requests and pass them through to its zone
+    fn allocate_devices(&self, pod: &Pod, zone_config: &mut ZoneConfig) -> Result<()> {
+        let requests = pod_device_requests(pod);
+        let Some((resource, requested)) = requests.iter().next() else {
+            return Ok(());
+        };
+        let Some(table) = &self.devices else {
+            return Err(RuntimeError::device_unavailable(resource, *requested, 0));
+        };
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+        zone_config.devices = table.allocate(namespace, pod_name, &requests)?;
+        Ok(())
+    }
+
+    /// Free the devices held by a deleted pod
+    fn release_devices(&self, namespace: &str, pod_name: &str) {
+        if let Some(table) = &self.devices {
+            if let Err(e) = table.release(namespace, pod_name) {
+                warn!(
+                    "Failed to release devices of pod {}/{}: {}",
+                    namespace, pod_name, e
+                );
+            }
+        }
+    }
+
+    /// Write a fresh SVID into the zone of a pod that has none yet or whose
+    /// SVID is due for rotation; failures are retried on the next reconcile
+    async fn rotate_svid(&self, pod: &Pod) {
+        let Some(issuer) = &self.svid_issuer else {
+            return;
+        };
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+        let pod_key = format!("{}/{}", namespace, pod_name);
+        let now = Utc::now();
+        let due = self.svid_renewals.lock().unwrap().get(&pod_key).copied();
+        if due.is_some_and(|due| now < due) {
+            return;
+        }
+
+        let zone_name = pod_zone_name(namespace, pod_name);
+        let zonepath = format!("{}/{}", self.config.zonepath_prefix, zone_name);
+        let written = match issuer.issue(pod, now) {
+            Ok(svid) => self
+                .runtime
+                .write_svid(&zone_name, &zonepath, &svid)
+                .await
+                .map(|()| svid),
+            Err(e) => Err(e),
+        };
+        match written {
+            Ok(svid) => {
+                info!(
+                    "Wrote SVID {} into zone {} (valid until {})",
+                    svid.spiffe_id, zone_name, svid.not_after
+                );
+                self.svid_renewals
+                    .lock()
+                    .unwrap()
+                    .insert(pod_key, svid.renew_at());
+            }
+            Err(e) => warn!("Failed to write SVID into zone {}: {}", zone_name, e),
+        }
+    }
+
+    /// Annotations of a namespace, or `None` if it can't be fetched
+    async fn namespace_annotations(&self, namespace: &str) -> Option<BTreeMap<String, String>> {
+        let path = format!("/api/v1/namespaces/{}", namespace);
+        match self.api_client.get_json(&path).await {
+            Ok(body) => serde_json::from_value(body["metadata"]["annotations"].clone()).ok(),
+            Err(e) => {
+                debug!("Could not fetch namespace {}: {}", namespace, e);
+                None
+            }
+        }
+    }
+
     /// Extract IP address from zone config network
     fn zone_ip(&self, config: &ZoneConfig) -> String {
         match &config.network {
@@ -803,7 +1857,7 @@ impl PodController {
 
         spec.containers
             .iter()
-            .flat_map(|c| extract_probes(c))
+            .flat_map(extract_probes)
             .collect()
     }
 
@@ -836,6 +1890,52 @@ impl PodController {
     }
 }
 
+/// Refuse a pod whose containers declare the same port: they all run in
+/// its zone and share one network stack, so only one of them could listen.
+/// The API server rejects such pods; this covers pods stored before it did.
+fn check_pod_ports(pod: &Pod) -> Result<()> {
+    match pod_port_conflicts(pod).into_iter().next() {
+        Some(conflict) => Err(RuntimeError::invalid_config(
+            conflict,
+            "Give every container of the pod its own ports",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Loopback mounts of the host paths in `sources`, keyed by pod volume name
+/// with whether the claim is mounted read-only, at every path the pod's
+/// containers mount those volumes on
+fn claim_fs_mounts(pod: &Pod, sources: &HashMap<String, (String, bool)>) -> Vec<FsMount> {
+    let mut mounts: Vec<FsMount> = Vec::new();
+    let containers = pod.spec.iter().flat_map(|s| {
+        s.init_containers
+            .iter()
+            .flatten()
+            .chain(s.containers.iter())
+    });
+    for mount in containers.flat_map(|c| c.volume_mounts.iter().flatten()) {
+        let Some((source, claim_read_only)) = sources.get(&mount.name) else {
+            continue;
+        };
+        if mounts.iter().any(|m| m.mountpoint == mount.mount_path) {
+            continue;
+        }
+        let read_only = *claim_read_only || mount.read_only == Some(true);
+        mounts.push(FsMount {
+            source: source.clone(),
+            mountpoint: mount.mount_path.clone(),
+            fs_type: "lofs".to_string(),
+            options: if read_only {
+                vec!["ro".to_string()]
+            } else {
+                vec![]
+            },
+        });
+    }
+    mounts
+}
+
 /// Generate a zone name from namespace and pod name
 ///
 /// Zone names must be valid illumos zone names (alphanumeric, hyphens, max 64 chars).
@@ -862,38 +1962,19 @@ pub fn pod_zone_name(namespace: &str, pod_name: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_api::MockApiServer;
     use crate::network::Ipam;
-    use k8s_openapi::api::core::v1::{Container, PodSpec};
+    use k8s_openapi::api::core::v1::{Container, PodCondition, PodSpec};
+    use reddwarf_core::annotations::ZONE_BRAND_ANNOTATION;
+    use reddwarf_core::{DevicePool, InProcessEventBus};
     use reddwarf_storage::RedbBackend;
     use std::net::Ipv4Addr;
     use std::time::Duration;
     use tempfile::tempdir;
 
-    fn make_test_controller() -> (PodController, tempfile::TempDir) {
-        let dir = tempdir().unwrap();
-        let db_path = dir.path().join("test-controller.redb");
-        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
-        let ipam = Ipam::new(storage, "10.88.0.0/16").unwrap();
-
-        let mock_storage = Arc::new(crate::storage::MockStorageEngine::new(
-            crate::types::StoragePoolConfig::from_pool("rpool"),
-        ));
-        let runtime = Arc::new(crate::mock::MockRuntime::new(mock_storage));
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let (event_tx, _) = broadcast::channel(16);
-
-        let config = PodControllerConfig {
-            node_name: "node1".to_string(),
-            api_url: "http://127.0.0.1:6443".to_string(),
-            zonepath_prefix: "/zones".to_string(),
-            default_brand: ZoneBrand::Reddwarf,
-            etherstub_name: "reddwarf0".to_string(),
-            pod_cidr: "10.88.0.0/16".to_string(),
-            reconcile_interval: Duration::from_secs(30),
-        };
-
-        let controller = PodController::new(runtime, api_client, event_tx, config, ipam);
-        (controller, dir)
+    async fn make_test_controller() -> (PodController, MockApiServer, tempfile::TempDir) {
+        let (controller, _runtime, api, dir) = make_test_controller_with_runtime().await;
+        (controller, api, dir)
     }
 
     #[test]
@@ -917,9 +1998,9 @@ mod tests {
         assert!(name.len() <= 64);
     }
 
-    #[test]
-    fn test_pod_to_zone_config_maps_containers() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_pod_to_zone_config_maps_containers() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("test-pod".to_string());
@@ -968,9 +2049,9 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_pod_to_zone_config_unique_ips() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_pod_to_zone_config_unique_ips() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod_a = Pod::default();
         pod_a.metadata.name = Some("pod-a".to_string());
@@ -1013,9 +2094,9 @@ mod tests {
         assert_eq!(ip_b, "10.88.0.3");
     }
 
-    #[test]
-    fn test_pod_to_zone_config_no_spec_returns_error() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_pod_to_zone_config_no_spec_returns_error() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("test-pod".to_string());
@@ -1025,13 +2106,13 @@ mod tests {
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_pod_to_zone_config_with_cpu_and_memory_limits() {
+    #[tokio::test]
+    async fn test_pod_to_zone_config_with_cpu_and_memory_limits() {
         use k8s_openapi::api::core::v1::ResourceRequirements;
         use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
         use std::collections::BTreeMap;
 
-        let (controller, _dir) = make_test_controller();
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut limits = BTreeMap::new();
         limits.insert("cpu".to_string(), Quantity("1".to_string()));
@@ -1056,15 +2137,18 @@ mod tests {
         let zone_config = controller.pod_to_zone_config(&pod).unwrap();
         assert_eq!(zone_config.cpu_cap, Some("1.00".to_string()));
         assert_eq!(zone_config.memory_cap, Some("512M".to_string()));
+        // Limits only → Guaranteed with a whole CPU → dedicated pset
+        assert_eq!(zone_config.dedicated_cpus, Some(1));
+        assert_eq!(zone_config.cpu_shares, None);
     }
 
-    #[test]
-    fn test_pod_to_zone_config_with_requests_fallback() {
+    #[tokio::test]
+    async fn test_pod_to_zone_config_with_requests_fallback() {
         use k8s_openapi::api::core::v1::ResourceRequirements;
         use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
         use std::collections::BTreeMap;
 
-        let (controller, _dir) = make_test_controller();
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut requests = BTreeMap::new();
         requests.insert("cpu".to_string(), Quantity("500m".to_string()));
@@ -1090,15 +2174,19 @@ mod tests {
         let zone_config = controller.pod_to_zone_config(&pod).unwrap();
         assert_eq!(zone_config.cpu_cap, Some("0.50".to_string()));
         assert_eq!(zone_config.memory_cap, Some("256M".to_string()));
+        // Requests without limits → Burstable, shares follow the request
+        assert_eq!(zone_config.dedicated_cpus, None);
+        assert_eq!(zone_config.cpu_shares, Some(512));
+        assert_eq!(zone_config.max_lwps, Some(4096));
     }
 
-    #[test]
-    fn test_pod_to_zone_config_aggregates_multiple_containers() {
+    #[tokio::test]
+    async fn test_pod_to_zone_config_aggregates_multiple_containers() {
         use k8s_openapi::api::core::v1::ResourceRequirements;
         use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
         use std::collections::BTreeMap;
 
-        let (controller, _dir) = make_test_controller();
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let make_limits = |cpu: &str, mem: &str| {
             let mut limits = BTreeMap::new();
@@ -1139,11 +2227,13 @@ mod tests {
         assert_eq!(zone_config.cpu_cap, Some("1.00".to_string()));
         // 256Mi + 256Mi = 512Mi
         assert_eq!(zone_config.memory_cap, Some("512M".to_string()));
+        // Limits only => Guaranteed, so swap is capped at the memory limit
+        assert_eq!(zone_config.swap_cap, Some("512M".to_string()));
     }
 
-    #[test]
-    fn test_pod_to_zone_config_no_resources() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_pod_to_zone_config_no_resources() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("bare-pod".to_string());
@@ -1160,11 +2250,14 @@ mod tests {
         let zone_config = controller.pod_to_zone_config(&pod).unwrap();
         assert_eq!(zone_config.cpu_cap, None);
         assert_eq!(zone_config.memory_cap, None);
+        // BestEffort → minimal shares and the tightest process limits
+        assert_eq!(zone_config.cpu_shares, Some(2));
+        assert_eq!(zone_config.max_processes, Some(1024));
     }
 
-    #[test]
-    fn test_grace_period_not_expired() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_grace_period_not_expired() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("grace-pod".to_string());
@@ -1176,9 +2269,9 @@ mod tests {
         assert!(!controller.is_grace_period_expired(&pod));
     }
 
-    #[test]
-    fn test_grace_period_expired() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_grace_period_expired() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("expired-pod".to_string());
@@ -1193,9 +2286,9 @@ mod tests {
         assert!(controller.is_grace_period_expired(&pod));
     }
 
-    #[test]
-    fn test_grace_period_no_deletion_timestamp() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_grace_period_no_deletion_timestamp() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let pod = Pod::default();
         assert!(!controller.is_grace_period_expired(&pod));
@@ -1203,7 +2296,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_handle_termination_absent_zone_calls_finalize() {
-        let (controller, _dir) = make_test_controller();
+        let (controller, api, _dir) = make_test_controller().await;
+        let finalize = "/api/v1/namespaces/default/pods/term-pod/finalize";
+        api.respond("POST", finalize, 200, serde_json::json!({}));
 
         // Build a pod with deletion_timestamp that is assigned to our node
         let mut pod = Pod::default();
@@ -1224,16 +2319,15 @@ mod tests {
         });
 
         // Zone doesn't exist in the mock runtime → should be treated as Absent
-        // handle_termination will try to deprovision (fail, that's ok) and then
-        // finalize (will fail since no API server, but the path is exercised)
+        // and the pod finalized straight away
         let result = controller.handle_termination(&pod).await;
-        // The function should complete Ok (finalize failure is logged, not returned)
         assert!(result.is_ok());
+        assert_eq!(api.requests_to("POST", finalize).len(), 1);
     }
 
     #[tokio::test]
     async fn test_handle_termination_running_zone_graceful_shutdown() {
-        let (controller, _dir) = make_test_controller();
+        let (controller, _api, _dir) = make_test_controller().await;
 
         // First, provision a zone so it's Running
         let mut pod = Pod::default();
@@ -1275,7 +2369,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_handle_delete_skips_when_deletion_timestamp_set() {
-        let (controller, _dir) = make_test_controller();
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("skip-pod".to_string());
@@ -1298,15 +2392,41 @@ mod tests {
         assert!(result.is_ok());
     }
 
-    #[test]
-    fn test_pod_to_zone_config_brand_from_annotation() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_handle_delete_cleans_up_force_deleted_pod() {
+        let (controller, _api, _dir) = make_test_controller().await;
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("forced-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.deletion_timestamp = Some(
+            k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(Utc::now()),
+        );
+        pod.metadata.deletion_grace_period_seconds = Some(0);
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                command: Some(vec!["/bin/sh".to_string()]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        controller.ipam.allocate("default", "forced-pod").unwrap();
+
+        controller.handle_delete(&pod).await.unwrap();
+        assert!(controller.ipam.get_all_allocations().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pod_to_zone_config_brand_from_annotation() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("lx-pod".to_string());
         pod.metadata.namespace = Some("default".to_string());
         pod.metadata.annotations = Some(
-            [("reddwarf.io/zone-brand".to_string(), "lx".to_string())]
+            [(ZONE_BRAND_ANNOTATION.to_string(), "lx".to_string())]
                 .into_iter()
                 .collect(),
         );
@@ -1323,9 +2443,133 @@ mod tests {
         assert_eq!(zone_config.brand, ZoneBrand::Lx);
     }
 
-    #[test]
-    fn test_pod_to_zone_config_brand_default() {
-        let (controller, _dir) = make_test_controller();
+    #[tokio::test]
+    async fn test_pod_to_zone_config_brand_from_runtime_class() {
+        let (controller, _api, _dir) = make_test_controller().await;
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("linux-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            runtime_class_name: Some("linux".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        // Unknown class
+        assert!(controller.pod_to_zone_config(&pod).is_err());
+
+        controller
+            .runtime_class_handlers
+            .write()
+            .unwrap()
+            .insert("linux".to_string(), "lx".to_string());
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        assert_eq!(zone_config.brand, ZoneBrand::Lx);
+    }
+
+    #[tokio::test]
+    async fn test_pod_to_zone_config_lx_image_from_mapping() {
+        let (controller, _api, _dir) = make_test_controller().await;
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("alpine-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.annotations = Some(
+            [(ZONE_BRAND_ANNOTATION.to_string(), "lx".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "web".to_string(),
+                image: Some("alpine:3.20".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        // No mapping for the image
+        assert!(controller.pod_to_zone_config(&pod).is_err());
+
+        controller
+            .image_mappings
+            .write()
+            .unwrap()
+            .push(ImageMapping::new(
+                "alpine",
+                reddwarf_core::ImageMappingSpec {
+                    repository: "alpine".to_string(),
+                    tags: [("3.20".to_string(), "/images/alpine-3.20.tar.gz".to_string())].into(),
+                },
+            ));
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        assert_eq!(
+            zone_config.lx_image_path.as_deref(),
+            Some("/images/alpine-3.20.tar.gz")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pod_to_zone_config_hostname_and_subdomain() {
+        let (controller, _api, _dir) = make_test_controller().await;
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("web-0".to_string());
+        pod.metadata.namespace = Some("shop".to_string());
+        pod.spec = Some(PodSpec {
+            hostname: Some("web-0".to_string()),
+            subdomain: Some("web".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        assert_eq!(zone_config.hostname.as_deref(), Some("web-0"));
+        assert_eq!(
+            zone_config.fqdn.as_deref(),
+            Some("web-0.web.shop.svc.cluster.local")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pod_to_zone_config_read_only_root() {
+        let (controller, _api, _dir) = make_test_controller().await;
+
+        let container = |name: &str, read_only: Option<bool>| Container {
+            name: name.to_string(),
+            security_context: Some(k8s_openapi::api::core::v1::SecurityContext {
+                read_only_root_filesystem: read_only,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("ro-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            containers: vec![
+                container("app", Some(true)),
+                container("sidecar", Some(true)),
+            ],
+            ..Default::default()
+        });
+        assert!(controller.pod_to_zone_config(&pod).unwrap().read_only_root);
+
+        // One writable container keeps the shared root writable
+        pod.spec.as_mut().unwrap().containers[1] = container("sidecar", None);
+        assert!(!controller.pod_to_zone_config(&pod).unwrap().read_only_root);
+    }
+
+    #[tokio::test]
+    async fn test_pod_to_zone_config_brand_default() {
+        let (controller, _api, _dir) = make_test_controller().await;
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("default-brand-pod".to_string());
@@ -1344,7 +2588,12 @@ mod tests {
         assert_eq!(zone_config.brand, ZoneBrand::Reddwarf);
     }
 
-    fn make_test_controller_with_runtime() -> (PodController, Arc<crate::mock::MockRuntime>, tempfile::TempDir) {
+    async fn make_test_controller_with_runtime() -> (
+        PodController,
+        Arc<crate::mock::MockRuntime>,
+        MockApiServer,
+        tempfile::TempDir,
+    ) {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test-controller-rt.redb");
         let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
@@ -1354,26 +2603,36 @@ mod tests {
             crate::types::StoragePoolConfig::from_pool("rpool"),
         ));
         let runtime = Arc::new(crate::mock::MockRuntime::new(mock_storage));
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let (event_tx, _) = broadcast::channel(16);
+        let api = MockApiServer::start().await;
+        let event_bus = Arc::new(InProcessEventBus::new(16));
 
         let config = PodControllerConfig {
             node_name: "node1".to_string(),
-            api_url: "http://127.0.0.1:6443".to_string(),
+            api_url: api.url(),
             zonepath_prefix: "/zones".to_string(),
             default_brand: ZoneBrand::Reddwarf,
             etherstub_name: "reddwarf0".to_string(),
             pod_cidr: "10.88.0.0/16".to_string(),
             reconcile_interval: Duration::from_secs(30),
+            host_port_interface: None,
+            cluster_dns_service: None,
+            cluster_domain: "cluster.local".to_string(),
+            allowed_tunables: TunablesAllowlist::default(),
         };
 
-        let controller = PodController::new(runtime.clone() as Arc<dyn ZoneRuntime>, api_client, event_tx, config, ipam);
-        (controller, runtime, dir)
+        let controller = PodController::new(
+            runtime.clone() as Arc<dyn ZoneRuntime>,
+            api.client(),
+            event_bus,
+            config,
+            ipam,
+        );
+        (controller, runtime, api, dir)
     }
 
     #[tokio::test]
     async fn test_reconcile_running_pod_with_no_probes() {
-        let (controller, runtime, _dir) = make_test_controller_with_runtime();
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
 
         // Create a pod that is already Running with a provisioned zone
         let mut pod = Pod::default();
@@ -1408,9 +2667,102 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_running_pod_gets_rotated_svid() {
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
+        let (ca_pem, ca_key) = crate::mesh::identity::tests::test_ca();
+        let issuer = SvidIssuer::new(&ca_pem, &ca_key, "cluster.local").unwrap();
+        let controller = controller.with_svid_issuer(Arc::new(issuer));
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("svid-pod".to_string());
+        pod.metadata.namespace = Some("payments".to_string());
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            service_account_name: Some("billing".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            ..Default::default()
+        });
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        runtime.provision(&zone_config).await.unwrap();
+
+        controller.reconcile(&pod).await.unwrap();
+        let svid = runtime.svid(&zone_config.zone_name).await.unwrap();
+        assert_eq!(
+            svid.spiffe_id,
+            "spiffe://cluster.local/ns/payments/sa/billing"
+        );
+
+        // Not rotated again until it is due
+        controller.reconcile(&pod).await.unwrap();
+        let again = runtime.svid(&zone_config.zone_name).await.unwrap();
+        assert_eq!(again.cert_pem, svid.cert_pem);
+
+        let pod_key = "payments/svid-pod".to_string();
+        controller
+            .svid_renewals
+            .lock()
+            .unwrap()
+            .insert(pod_key, Utc::now());
+        controller.reconcile(&pod).await.unwrap();
+        let rotated = runtime.svid(&zone_config.zone_name).await.unwrap();
+        assert_ne!(rotated.cert_pem, svid.cert_pem);
+    }
+
+    #[tokio::test]
+    async fn test_zone_states_listed_once_per_cycle() {
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("listed-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        runtime.provision(&zone_config).await.unwrap();
+        let zone_name = zone_config.zone_name.clone();
+
+        controller.snapshot_zone_states().await;
+        runtime.halt_zone(&zone_name).await.unwrap();
+
+        // The cycle's listing answers without asking the runtime again
+        assert_eq!(
+            controller.zone_state(&zone_name).await.unwrap(),
+            ZoneState::Running
+        );
+        assert!(matches!(
+            controller.zone_state("reddwarf-unknown").await,
+            Err(RuntimeError::ZoneNotFound { .. })
+        ));
+
+        // Outside of a cycle the runtime is asked directly
+        *controller.zone_states.write().unwrap() = None;
+        assert_eq!(
+            controller.zone_state(&zone_name).await.unwrap(),
+            ZoneState::Installed
+        );
+    }
+
     #[tokio::test]
     async fn test_reconcile_running_pod_liveness_failure() {
-        let (controller, runtime, _dir) = make_test_controller_with_runtime();
+        let (controller, runtime, api, dir) = make_test_controller_with_runtime().await;
+        let sink = Arc::new(reddwarf_core::MemoryEventSink::new());
+        let storage = Arc::new(RedbBackend::new(dir.path().join("decisions.redb")).unwrap());
+        let controller = controller
+            .with_event_recorder(EventRecorder::new(sink.clone(), "reddwarf-agent"))
+            .with_decision_log(Arc::new(DecisionLog::new(storage, "node1").unwrap()));
 
         let mut pod = Pod::default();
         pod.metadata.name = Some("liveness-pod".to_string());
@@ -1462,10 +2814,15 @@ mod tests {
                 .await;
         }
 
+        let pod_path = "/api/v1/namespaces/default/pods/liveness-pod";
+        let status_path = "/api/v1/namespaces/default/pods/liveness-pod/status";
+        let stored = serde_json::to_value(&pod).unwrap();
+        api.respond("GET", pod_path, 200, stored.clone());
+        api.respond("PUT", status_path, 200, stored);
+
         // Reconcile 3 times to hit the failure threshold.
         // On the 3rd reconcile, liveness failure is detected. The controller
-        // then unregisters the probes and tries to set the pod status to Failed
-        // (which fails silently since there's no API server).
+        // then unregisters the probes and sets the pod status to Failed.
         for _ in 0..3 {
             let _ = controller.reconcile(&pod).await;
         }
@@ -1480,11 +2837,39 @@ mod tests {
         // This confirms the unregister happened, which only occurs on liveness failure
         assert!(status.ready);
         assert!(!status.liveness_failed);
+
+        let events = sink.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].type_.as_deref(), Some("Warning"));
+        assert_eq!(events[0].reason.as_deref(), Some(REASON_PROBE_FAILED));
+
+        // Unready while the failures stay under the threshold, then Failed
+        let phases: Vec<_> = api
+            .requests_to("PUT", status_path)
+            .iter()
+            .map(|r| r.json()["status"]["phase"].clone())
+            .collect();
+        assert_eq!(phases, ["Running", "Running", "Failed"]);
+
+        // The decision to fail the pod is logged with what it was based on
+        let decisions = controller
+            .decisions
+            .as_ref()
+            .unwrap()
+            .decisions(Some("default"), Some("liveness-pod"), 1)
+            .unwrap();
+        assert_eq!(decisions[0].action, DecisionAction::Terminate);
+        assert_eq!(decisions[0].zone_state, Some(ZoneState::Running));
+        assert_eq!(decisions[0].phase.as_deref(), Some("Running"));
+        assert!(decisions[0].reason.starts_with("ProbeFailure: "));
+        assert_eq!(decisions[0].outcome, DecisionOutcome::Succeeded);
     }
 
     #[tokio::test]
     async fn test_reconcile_with_deletion_timestamp_uses_termination() {
-        let (controller, _dir) = make_test_controller();
+        let (controller, api, _dir) = make_test_controller().await;
+        let finalize = "/api/v1/namespaces/default/pods/recon-term/finalize";
+        api.respond("POST", finalize, 200, serde_json::json!({}));
 
         // Build a pod with deletion_timestamp, assigned to our node, phase Terminating
         let mut pod = Pod::default();
@@ -1509,8 +2894,470 @@ mod tests {
         });
 
         // reconcile() should detect deletion_timestamp and call handle_termination
-        // Zone is absent → will try to finalize (will fail, but logged)
+        // Zone is absent → the pod is finalized, with nothing else sent
         let result = controller.reconcile(&pod).await;
         assert!(result.is_ok());
+        let requests: Vec<_> = api
+            .requests()
+            .into_iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert_eq!(requests, [format!("POST {}", finalize)]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_bandwidth_limits_sets_vnic_maxbw() {
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("bw-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.annotations = Some(
+            [
+                (
+                    reddwarf_core::annotations::INGRESS_BANDWIDTH_ANNOTATION.to_string(),
+                    "10M".to_string(),
+                ),
+                (
+                    reddwarf_core::annotations::EGRESS_BANDWIDTH_ANNOTATION.to_string(),
+                    "1G".to_string(),
+                ),
+            ]
+            .into(),
+        );
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        let annotations = controller
+            .apply_bandwidth_limits(&pod, &zone_config)
+            .await;
+
+        let vnic = vnic_name_for_pod("default", "bw-pod");
+        assert_eq!(runtime.link_bandwidth(&vnic).await, Some(10_000_000));
+        assert_eq!(annotations["status.reddwarf.io/maxbw"], "10M");
+        assert_eq!(
+            annotations["status.reddwarf.io/egress-bandwidth"],
+            "1000000000"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_host_ports_reserved_forwarded_and_released() {
+        let (controller, runtime, _api, dir) = make_test_controller_with_runtime().await;
+        let storage = Arc::new(RedbBackend::new(dir.path().join("hostports.redb")).unwrap());
+        let mut controller = controller.with_host_ports(HostPortTable::new(storage, "node1"));
+        controller.config.host_port_interface = Some("igb0".to_string());
+
+        let make_pod = |name: &str| {
+            let mut pod = Pod::default();
+            pod.metadata.name = Some(name.to_string());
+            pod.metadata.namespace = Some("default".to_string());
+            pod.spec = Some(PodSpec {
+                containers: vec![Container {
+                    name: "web".to_string(),
+                    ports: Some(vec![k8s_openapi::api::core::v1::ContainerPort {
+                        container_port: 8080,
+                        host_port: Some(80),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+            pod
+        };
+
+        let pod = make_pod("web");
+        controller.reserve_host_ports(&pod).unwrap();
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        controller.apply_host_ports(&pod, &zone_config).await;
+
+        let forwards = runtime.port_forwards(&zone_config.zone_name).await;
+        assert_eq!(forwards.len(), 1);
+        assert_eq!(forwards[0].interface, "igb0");
+        assert_eq!(forwards[0].host_port, 80);
+        assert_eq!(forwards[0].container_port, 8080);
+        assert_eq!(forwards[0].pod_ip, controller.zone_ip(&zone_config));
+
+        // A second pod wanting the same port is refused
+        let other = make_pod("other");
+        assert!(matches!(
+            controller.reserve_host_ports(&other).unwrap_err(),
+            RuntimeError::HostPortConflict { port: 80, .. }
+        ));
+
+        controller.release_host_ports("default", "web").await;
+        assert!(runtime
+            .port_forwards(&zone_config.zone_name)
+            .await
+            .is_empty());
+        controller.reserve_host_ports(&other).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_devices_assigned_to_zone_and_released() {
+        use k8s_openapi::api::core::v1::ResourceRequirements;
+        use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+        let (controller, _api, dir) = make_test_controller().await;
+        let storage = Arc::new(RedbBackend::new(dir.path().join("devices.redb")).unwrap());
+        let pool = DevicePool::parse("disk=/dev/dsk/c1t1d0").unwrap();
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("db".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "db".to_string(),
+                resources: Some(ResourceRequirements {
+                    limits: Some(BTreeMap::from([(
+                        pool.resource_name(),
+                        Quantity("1".to_string()),
+                    )])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        // Without a device table the request can't be met
+        let mut zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        assert!(controller.allocate_devices(&pod, &mut zone_config).is_err());
+
+        let controller = controller.with_devices(DeviceTable::new(storage, "node1", vec![pool]));
+        controller.allocate_devices(&pod, &mut zone_config).unwrap();
+        assert_eq!(zone_config.devices, vec!["/dev/dsk/c1t1d0"]);
+
+        let mut other = pod.clone();
+        other.metadata.name = Some("other".to_string());
+        assert!(matches!(
+            controller
+                .allocate_devices(&other, &mut zone_config)
+                .unwrap_err(),
+            RuntimeError::DeviceUnavailable { available: 0, .. }
+        ));
+
+        controller.release_devices("default", "db");
+        controller
+            .allocate_devices(&other, &mut zone_config)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_provision_zone_claims_warm_zone() {
+        use crate::warm_pool::{WarmPool, WarmPoolSpec};
+
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
+        let pool = Arc::new(WarmPool::new(
+            runtime.clone(),
+            "/zones",
+            vec![WarmPoolSpec::parse("reddwarf=1").unwrap()],
+        ));
+        pool.reconcile().await.unwrap();
+        let controller = controller.with_warm_pool(pool);
+
+        let mut pod = Pod::default();
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        // The first pod takes the warm zone, the second is provisioned from
+        // scratch
+        for name in ["warm", "cold"] {
+            pod.metadata.name = Some(name.to_string());
+            let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+            controller.provision_zone(&zone_config).await.unwrap();
+            assert_eq!(
+                runtime.get_zone_state(&zone_config.zone_name).await.unwrap(),
+                ZoneState::Running
+            );
+        }
+        assert!(runtime
+            .list_zones()
+            .await
+            .unwrap()
+            .iter()
+            .all(|z| !z.zone_name.starts_with("rdwarm-")));
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_records_startup_metrics() {
+        use chrono::SubsecRound;
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+        use reddwarf_core::startup::{
+            format_timestamp, SCHEDULED_AT_ANNOTATION, STARTUP_STAGE_METRIC,
+        };
+
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
+        let metrics = Arc::new(Metrics::new());
+        let sink = Arc::new(reddwarf_core::MemoryEventSink::new());
+        let controller = controller
+            .with_metrics(metrics.clone())
+            .with_event_recorder(EventRecorder::new(sink.clone(), "reddwarf-agent"));
+
+        let created = Utc::now().trunc_subsecs(0) - chrono::Duration::seconds(5);
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("timed".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.creation_timestamp = Some(Time(created));
+        pod.metadata.annotations = Some(
+            [(
+                SCHEDULED_AT_ANNOTATION.to_string(),
+                format_timestamp(created + chrono::Duration::seconds(1)),
+            )]
+            .into(),
+        );
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        controller.reconcile(&pod).await.unwrap();
+        let zone_name = pod_zone_name("default", "timed");
+        assert_eq!(
+            runtime.get_zone_state(&zone_name).await.unwrap(),
+            ZoneState::Running
+        );
+
+        for stage in ["scheduling", "provision_queue", "zone_boot", "ready"] {
+            let histogram = metrics
+                .histogram(STARTUP_STAGE_METRIC, &[("stage", stage)])
+                .unwrap();
+            assert_eq!(histogram.count(), 1, "stage {}", stage);
+        }
+        let scheduling = metrics
+            .histogram(STARTUP_STAGE_METRIC, &[("stage", "scheduling")])
+            .unwrap();
+        assert_eq!(scheduling.sum(), 1.0);
+
+        let events = sink.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].reason.as_deref(), Some(REASON_PROVISIONED));
+        assert_eq!(events[0].involved_object.name.as_deref(), Some("timed"));
+    }
+
+    #[tokio::test]
+    async fn test_cluster_dns_written_at_provision_and_on_change() {
+        let (controller, runtime, _api, _dir) = make_test_controller_with_runtime().await;
+        *controller.cluster_dns_ip.write().unwrap() = Some("10.96.0.10".to_string());
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("dns-pod".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        runtime.provision(&zone_config).await.unwrap();
+        let dns = runtime.resolv_conf(&zone_config.zone_name).await.unwrap();
+        assert_eq!(dns.nameservers, vec!["10.96.0.10"]);
+        assert_eq!(dns.searches[0], "default.svc.cluster.local");
+
+        // The DNS service moved; running ClusterFirst pods are rewritten
+        *controller.cluster_dns_ip.write().unwrap() = Some("10.96.0.53".to_string());
+        pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            ..Default::default()
+        });
+        controller.update_pod_dns(&pod).await;
+        let dns = runtime.resolv_conf(&zone_config.zone_name).await.unwrap();
+        assert_eq!(dns.nameservers, vec!["10.96.0.53"]);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_from_pod_cache_while_api_unreachable() {
+        let (controller, runtime, api, dir) = make_test_controller_with_runtime().await;
+        let storage = Arc::new(RedbBackend::new(dir.path().join("cache.redb")).unwrap());
+        let controller = controller.with_pod_cache(PodCache::new(storage, "node1"));
+
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("web".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "web".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        controller
+            .pod_cache
+            .as_ref()
+            .unwrap()
+            .replace(std::slice::from_ref(&pod))
+            .unwrap();
+
+        // The API server is unavailable: the cached pod is still provisioned
+        api.respond("GET", "/api/v1/pods", 503, serde_json::json!({}));
+        controller.reconcile_all().await.unwrap();
+        assert_eq!(api.requests_to("GET", "/api/v1/pods").len(), 1);
+        let zone_name = pod_zone_name("default", "web");
+        assert_eq!(
+            runtime.get_zone_state(&zone_name).await.unwrap(),
+            ZoneState::Running
+        );
+
+        // Back online, a listing without the pod tears its zone down
+        controller.sync_pod_cache(&[]).await;
+        assert!(runtime.get_zone_state(&zone_name).await.is_err());
+        assert!(controller.pod_cache.as_ref().unwrap().is_empty().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_failures_back_off_then_fail() {
+        let (controller, _runtime, _api, dir) = make_test_controller_with_runtime().await;
+        let storage = Arc::new(RedbBackend::new(dir.path().join("hostports.redb")).unwrap());
+        let controller = controller
+            .with_host_ports(HostPortTable::new(storage, "node1"))
+            .with_reconcile_backoff(ReconcileBackoffConfig {
+                initial_delay: Duration::from_millis(50),
+                max_delay: Duration::from_secs(1),
+                max_jitter: Duration::ZERO,
+                error_budget: 2,
+            });
+
+        let make_pod = |name: &str| {
+            let mut pod = Pod::default();
+            pod.metadata.name = Some(name.to_string());
+            pod.metadata.namespace = Some("default".to_string());
+            pod.spec = Some(PodSpec {
+                node_name: Some("node1".to_string()),
+                containers: vec![Container {
+                    name: "web".to_string(),
+                    ports: Some(vec![k8s_openapi::api::core::v1::ContainerPort {
+                        container_port: 8080,
+                        host_port: Some(80),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+            pod
+        };
+
+        // Another pod holds the host port, so provisioning fails
+        controller.reserve_host_ports(&make_pod("web")).unwrap();
+        let pod = make_pod("other");
+        controller.reconcile(&pod).await.unwrap();
+        assert!(controller.backoff.retry_in("default/other").is_some());
+
+        // Reconciles during the backoff do not retry (a retry would spend
+        // the budget and clear the entry)
+        controller.reconcile(&pod).await.unwrap();
+        assert!(controller.backoff.retry_in("default/other").is_some());
+
+        // Once the delay passes the retry fails again and spends the budget
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        controller.reconcile(&pod).await.unwrap();
+        assert!(controller.backoff.retry_in("default/other").is_none());
+    }
+
+    #[test]
+    fn test_claim_fs_mounts() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "db", "namespace": "default"},
+            "spec": {
+                "initContainers": [{
+                    "name": "init",
+                    "volumeMounts": [{"name": "data", "mountPath": "/var/lib/db"}]
+                }],
+                "containers": [{
+                    "name": "db",
+                    "volumeMounts": [
+                        {"name": "data", "mountPath": "/var/lib/db"},
+                        {"name": "seed", "mountPath": "/seed", "readOnly": true},
+                        {"name": "scratch", "mountPath": "/tmp/scratch"}
+                    ]
+                }],
+                "volumes": [
+                    {"name": "data", "persistentVolumeClaim": {"claimName": "data"}},
+                    {"name": "seed", "persistentVolumeClaim": {"claimName": "seed"}},
+                    {"name": "scratch", "emptyDir": {}}
+                ]
+            }
+        }))
+        .unwrap();
+        let sources = HashMap::from([
+            (
+                "data".to_string(),
+                ("/rpool/volumes/pvc-1".to_string(), false),
+            ),
+            ("seed".to_string(), ("/export/seed".to_string(), false)),
+        ]);
+
+        let mounts = claim_fs_mounts(&pod, &sources);
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts[0].source, "/rpool/volumes/pvc-1");
+        assert_eq!(mounts[0].mountpoint, "/var/lib/db");
+        assert_eq!(mounts[0].fs_type, "lofs");
+        assert!(mounts[0].options.is_empty());
+        assert_eq!(mounts[1].mountpoint, "/seed");
+        assert_eq!(mounts[1].options, vec!["ro"]);
+    }
+
+    #[tokio::test]
+    async fn test_multi_container_pod_shares_zone_network() {
+        let (controller, _api, _dir) = make_test_controller().await;
+        let container = |name: &str, port: i32| Container {
+            name: name.to_string(),
+            ports: Some(vec![k8s_openapi::api::core::v1::ContainerPort {
+                container_port: port,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("app".to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            containers: vec![container("web", 8080), container("client", 9090)],
+            ..Default::default()
+        });
+
+        // Both containers are processes of one zone with one address, and
+        // localhost resolves to the zone's loopback
+        let zone_config = controller.pod_to_zone_config(&pod).unwrap();
+        assert_eq!(zone_config.processes.len(), 2);
+        let NetworkMode::Etherstub(network) = &zone_config.network else {
+            panic!("pod should get an etherstub VNIC");
+        };
+        let hosts = crate::network::dns::hosts_file(
+            &network.ip_address,
+            zone_config.hostname.as_deref().unwrap(),
+            zone_config.fqdn.as_deref(),
+        );
+        assert!(hosts.contains("127.0.0.1\tlocalhost"));
+        assert!(check_pod_ports(&pod).is_ok());
+
+        // So the client can't listen on the web server's port
+        pod.spec.as_mut().unwrap().containers[1] = container("client", 8080);
+        assert!(matches!(
+            check_pod_ports(&pod),
+            Err(RuntimeError::InvalidConfig { .. })
+        ));
     }
 }
@@ -0,0 +1,273 @@
+use crate::error::{Result, RuntimeError};
+use crate::types::ZoneState;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use reddwarf_storage::RedbBackend;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Default bound on the number of decisions kept
+pub const DEFAULT_DECISION_LOG_CAPACITY: usize = 4096;
+
+/// What the pod controller did about a pod
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecisionAction {
+    /// Provisioned and booted the pod's zone
+    Provision,
+    /// Cleaned up after a failed provisioning, to retry after a backoff
+    RetryProvision,
+    /// Reported the pod as terminated, Failed or Succeeded
+    Terminate,
+    /// Reported the pod's containers as ready
+    MarkReady,
+    /// Reported the pod's containers as not ready
+    MarkUnready,
+    /// Began a graceful shutdown of a deleted pod's zone
+    Shutdown,
+    /// Force-halted a deleted pod's zone
+    Halt,
+    /// Released a deleted pod's zone and resources and removed the pod
+    Finalize,
+    /// Deprovisioned the zone of a pod removed without graceful termination
+    Deprovision,
+}
+
+/// Whether a decision's action took effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecisionOutcome {
+    Succeeded,
+    Failed(String),
+}
+
+impl<T> From<&Result<T>> for DecisionOutcome {
+    fn from(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => Self::Succeeded,
+            Err(e) => Self::Failed(e.to_string()),
+        }
+    }
+}
+
+/// One decision of the pod controller: what it saw and what it did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Decision {
+    /// Position in the node's log, increasing across restarts
+    pub sequence: u64,
+    pub time: DateTime<Utc>,
+    pub namespace: String,
+    pub pod: String,
+    pub uid: Option<String>,
+    pub resource_version: Option<String>,
+    /// Hash of the pod as the controller saw it, to tell which decisions
+    /// were made from the same input
+    pub pod_hash: String,
+    /// Phase the pod was in
+    pub phase: Option<String>,
+    /// State the pod's zone was observed in, when it was looked at
+    pub zone_state: Option<ZoneState>,
+    pub action: DecisionAction,
+    /// Why the action was taken
+    pub reason: String,
+    pub outcome: DecisionOutcome,
+}
+
+/// Bounded log of the pod controller's decisions, kept in the storage's
+/// node cache table so that it survives agent restarts and can be read back
+/// through the debug API after the fact
+///
+/// Cache keys:
+/// - `decisions/{node}/{sequence}` → decision JSON, the sequence zero-padded
+///   so keys sort in order
+pub struct DecisionLog {
+    storage: Arc<RedbBackend>,
+    node_name: String,
+    capacity: usize,
+    next: AtomicU64,
+}
+
+impl DecisionLog {
+    /// Open the log of `node_name`, continuing after its last decision
+    pub fn new(storage: Arc<RedbBackend>, node_name: impl Into<String>) -> Result<Self> {
+        let node_name = node_name.into();
+        let prefix = format!("decisions/{}/", node_name);
+        let next = storage
+            .cache_scan(prefix.as_bytes())?
+            .last()
+            .and_then(|(key, _)| key.strip_prefix(prefix.as_bytes()).map(<[u8]>::to_vec))
+            .and_then(|sequence| String::from_utf8(sequence).ok()?.parse::<u64>().ok())
+            .map_or(0, |last| last + 1);
+        Ok(Self {
+            storage,
+            node_name,
+            capacity: DEFAULT_DECISION_LOG_CAPACITY,
+            next: AtomicU64::new(next),
+        })
+    }
+
+    /// Keep at most `capacity` decisions, dropping the oldest
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    fn prefix(&self) -> String {
+        format!("decisions/{}/", self.node_name)
+    }
+
+    /// Append a decision about `pod`. Failing to write it is logged and
+    /// otherwise ignored, as the log must not hold up reconciliation.
+    pub fn record(
+        &self,
+        pod: &Pod,
+        zone_state: Option<ZoneState>,
+        action: DecisionAction,
+        reason: impl Into<String>,
+        outcome: DecisionOutcome,
+    ) {
+        let decision = Decision {
+            sequence: self.next.fetch_add(1, Ordering::SeqCst),
+            time: Utc::now(),
+            namespace: pod
+                .metadata
+                .namespace
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+            pod: pod.metadata.name.clone().unwrap_or_default(),
+            uid: pod.metadata.uid.clone(),
+            resource_version: pod.metadata.resource_version.clone(),
+            pod_hash: pod_hash(pod),
+            phase: pod.status.as_ref().and_then(|s| s.phase.clone()),
+            zone_state,
+            action,
+            reason: reason.into(),
+            outcome,
+        };
+        if let Err(e) = self.append(&decision) {
+            warn!(
+                "Failed to record {:?} decision about pod {}/{}: {}",
+                decision.action, decision.namespace, decision.pod, e
+            );
+        }
+    }
+
+    fn append(&self, decision: &Decision) -> Result<()> {
+        let key = format!("{}{:020}", self.prefix(), decision.sequence);
+        let value = serde_json::to_vec(decision).map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to encode decision: {}", e))
+        })?;
+        self.storage.cache_append(
+            self.prefix().as_bytes(),
+            key.as_bytes(),
+            &value,
+            self.capacity,
+        )?;
+        Ok(())
+    }
+
+    /// The last `limit` decisions, oldest first, optionally only those
+    /// about pods of `namespace` or named `pod`; entries that no longer
+    /// parse are skipped
+    pub fn decisions(
+        &self,
+        namespace: Option<&str>,
+        pod: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Decision>> {
+        let mut decisions: Vec<Decision> = self
+            .storage
+            .cache_scan(self.prefix().as_bytes())?
+            .iter()
+            .filter_map(|(_, value)| serde_json::from_slice(value).ok())
+            .filter(|d: &Decision| namespace.is_none_or(|ns| d.namespace == ns))
+            .filter(|d| pod.is_none_or(|name| d.pod == name))
+            .collect();
+        let skip = decisions.len().saturating_sub(limit);
+        decisions.drain(..skip);
+        Ok(decisions)
+    }
+}
+
+/// FNV-1a over the pod's JSON, which serializes maps in key order
+fn pod_hash(pod: &Pod) -> String {
+    let json = serde_json::to_vec(pod).unwrap_or_default();
+    let hash = json.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use tempfile::tempdir;
+
+    fn pod(namespace: &str, name: &str) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decisions_are_bounded_filtered_and_survive_reopening() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("cache.redb")).unwrap());
+        let log = DecisionLog::new(storage.clone(), "node1")
+            .unwrap()
+            .with_capacity(3);
+
+        log.record(
+            &pod("default", "web"),
+            None,
+            DecisionAction::Provision,
+            "Pod is pending",
+            DecisionOutcome::Succeeded,
+        );
+        log.record(
+            &pod("default", "db"),
+            Some(ZoneState::Running),
+            DecisionAction::Terminate,
+            "Liveness probe failed",
+            DecisionOutcome::Succeeded,
+        );
+        log.record(
+            &pod("other", "web"),
+            Some(ZoneState::Running),
+            DecisionAction::Halt,
+            "Grace period expired",
+            DecisionOutcome::Failed("zone busy".to_string()),
+        );
+
+        let web = log.decisions(None, Some("web"), 10).unwrap();
+        assert_eq!(web.len(), 2);
+        assert_eq!(web[0].action, DecisionAction::Provision);
+        assert_eq!(
+            web[1].outcome,
+            DecisionOutcome::Failed("zone busy".to_string())
+        );
+        assert_eq!(log.decisions(Some("default"), None, 10).unwrap().len(), 2);
+        assert_eq!(log.decisions(None, None, 1).unwrap()[0].sequence, 2);
+
+        // A reopened log continues the sequence and drops the oldest
+        let log = DecisionLog::new(storage, "node1").unwrap().with_capacity(3);
+        log.record(
+            &pod("default", "db"),
+            None,
+            DecisionAction::Finalize,
+            "Zone is down",
+            DecisionOutcome::Succeeded,
+        );
+        let all = log.decisions(None, None, 10).unwrap();
+        let sequences: Vec<u64> = all.iter().map(|d| d.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+        assert_eq!(all[0].pod_hash, pod_hash(&pod("default", "db")));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::{Result, RuntimeError};
+use reddwarf_core::devices::{node_device_prefix, DEVICE_KEY_PREFIX};
+use reddwarf_core::host_ports::host_port_owner;
+use reddwarf_core::DevicePool;
+use reddwarf_storage::KVStore;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Device assignments of one node, backed by a KVStore
+///
+/// Storage keys:
+/// - `devices/{node}/{path}` → `"{namespace}/{pod_name}"`
+pub struct DeviceTable {
+    storage: Arc<dyn KVStore>,
+    node_name: String,
+    pools: Vec<DevicePool>,
+}
+
+impl DeviceTable {
+    pub fn new(
+        storage: Arc<dyn KVStore>,
+        node_name: impl Into<String>,
+        pools: Vec<DevicePool>,
+    ) -> Self {
+        Self {
+            storage,
+            node_name: node_name.into(),
+            pools,
+        }
+    }
+
+    fn key(&self, path: &str) -> String {
+        format!("{}{}/{}", DEVICE_KEY_PREFIX, self.node_name, path)
+    }
+
+    /// Assign devices to a pod for each requested resource, returning the
+    /// paths it holds. Idempotent for devices the pod already holds; fails
+    /// without assigning anything if a class has too few free devices.
+    pub fn allocate(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        requests: &BTreeMap<String, i64>,
+    ) -> Result<Vec<String>> {
+        let owner = host_port_owner(namespace, pod_name);
+
+        let mut assigned = Vec::new();
+        for (resource, requested) in requests {
+            let Some(pool) = self.pools.iter().find(|p| &p.resource_name() == resource) else {
+                return Err(RuntimeError::device_unavailable(resource, *requested, 0));
+            };
+
+            let mut held = Vec::new();
+            let mut free = Vec::new();
+            for path in &pool.paths {
+                match self.storage.get(self.key(path).as_bytes())? {
+                    Some(existing) if existing == owner.as_bytes() => held.push(path.clone()),
+                    Some(_) => {}
+                    None => free.push(path.clone()),
+                }
+            }
+
+            let wanted = usize::try_from(*requested).unwrap_or(0);
+            let available = held.len() + free.len();
+            if available < wanted {
+                return Err(RuntimeError::device_unavailable(
+                    resource,
+                    *requested,
+                    available as i64,
+                ));
+            }
+            held.truncate(wanted);
+            let missing = wanted - held.len();
+            held.extend(free.into_iter().take(missing));
+            assigned.extend(held);
+        }
+
+        for path in &assigned {
+            self.storage
+                .put(self.key(path).as_bytes(), owner.as_bytes())?;
+            debug!("Devices: assigned {} to {}", path, owner);
+        }
+        Ok(assigned)
+    }
+
+    /// Release every device held by a pod, returning how many were freed
+    pub fn release(&self, namespace: &str, pod_name: &str) -> Result<usize> {
+        let owner = host_port_owner(namespace, pod_name);
+        let prefix = node_device_prefix(&self.node_name);
+
+        let mut released = 0;
+        for (key, value) in self.storage.scan(prefix.as_bytes())? {
+            if value == owner.as_bytes() {
+                self.storage.delete(&key)?;
+                released += 1;
+            }
+        }
+
+        if released > 0 {
+            debug!("Devices: released {} device(s) of {}", released, owner);
+        }
+        Ok(released)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_storage::RedbBackend;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_allocate_and_release() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("devices.redb")).unwrap());
+        let pool = DevicePool::parse("disk=/dev/dsk/c1t1d0,/dev/dsk/c1t2d0").unwrap();
+        let table = DeviceTable::new(storage, "node1", vec![pool.clone()]);
+        let one = BTreeMap::from([(pool.resource_name(), 1)]);
+        let two = BTreeMap::from([(pool.resource_name(), 2)]);
+
+        let web = table.allocate("default", "web", &one).unwrap();
+        assert_eq!(web, vec!["/dev/dsk/c1t1d0"]);
+        // Re-allocating for the same pod returns the same device
+        assert_eq!(table.allocate("default", "web", &one).unwrap(), web);
+
+        let err = table.allocate("default", "db", &two).unwrap_err();
+        assert!(matches!(
+            err,
+            RuntimeError::DeviceUnavailable { available: 1, .. }
+        ));
+        assert_eq!(
+            table.allocate("default", "db", &one).unwrap(),
+            vec!["/dev/dsk/c1t2d0"]
+        );
+
+        let gpu = BTreeMap::from([("devices.reddwarf.io/gpu".to_string(), 1)]);
+        assert!(table.allocate("default", "ml", &gpu).is_err());
+
+        assert_eq!(table.release("default", "web").unwrap(), 1);
+        assert_eq!(table.release("default", "db").unwrap(), 1);
+        assert_eq!(table.allocate("default", "db", &two).unwrap().len(), 2);
+    }
+}
@@ -1,4 +1,5 @@
 use miette::Diagnostic;
+use reddwarf_core::{ErrorClass, ErrorKind};
 use thiserror::Error;
 
 /// Runtime error type for zone and container operations
@@ -174,6 +175,91 @@ pub enum RuntimeError {
         failure_threshold: u32,
     },
 
+    /// API server unreachable and the client circuit breaker is open
+    #[error("API server at {base_url} is unavailable, retrying in {retry_after_secs}s")]
+    #[diagnostic(
+        code(reddwarf::runtime::api_unavailable),
+        help("Too many consecutive requests to the API server failed. Check that the API server is running; requests resume automatically once a probe request succeeds")
+    )]
+    ApiUnavailable {
+        #[allow(unused)]
+        base_url: String,
+        #[allow(unused)]
+        retry_after_secs: u64,
+    },
+
+    /// Request to the API server failed, in transport or with a failure
+    /// status
+    #[error("{message}")]
+    #[diagnostic(
+        code(reddwarf::runtime::api_request_failed),
+        help(
+            "Check that the API server is running and reachable, and the request is valid for it"
+        )
+    )]
+    ApiRequestFailed {
+        /// HTTP status of the response, `None` when no response came
+        #[allow(unused)]
+        status: Option<u16>,
+        /// `reason` of the Kubernetes Status returned, if any
+        #[allow(unused)]
+        reason: Option<String>,
+        #[allow(unused)]
+        message: String,
+    },
+
+    /// Mesh proxy setup or tunnel failure
+    #[error("Mesh error: {message}")]
+    #[diagnostic(
+        code(reddwarf::runtime::mesh_error),
+        help("Check that the cluster CA certificate and key are available and that peer nodes run the mesh proxy on the same port")
+    )]
+    MeshError {
+        #[allow(unused)]
+        message: String,
+    },
+
+    /// Host port already reserved by another pod on this node
+    #[error("Host port {port}/{protocol} is already in use by pod {owner}")]
+    #[diagnostic(
+        code(reddwarf::runtime::host_port_conflict),
+        help("Choose a different hostPort, or delete pod {owner} to free the port")
+    )]
+    HostPortConflict {
+        #[allow(unused)]
+        port: u16,
+        #[allow(unused)]
+        protocol: String,
+        #[allow(unused)]
+        owner: String,
+    },
+
+    /// Not enough free devices of a class on this node
+    #[error("Only {available} of {requested} {resource} device(s) are free on this node")]
+    #[diagnostic(
+        code(reddwarf::runtime::device_unavailable),
+        help("Wait for pods holding {resource} devices to finish, or offer more with --device")
+    )]
+    DeviceUnavailable {
+        #[allow(unused)]
+        resource: String,
+        #[allow(unused)]
+        requested: i64,
+        #[allow(unused)]
+        available: i64,
+    },
+
+    /// Workload identity (SVID) issuance failure
+    #[error("Workload identity error: {message}")]
+    #[diagnostic(
+        code(reddwarf::runtime::workload_identity_error),
+        help("Check that the cluster CA certificate and key are available and that the trust domain is a valid DNS name")
+    )]
+    WorkloadIdentityError {
+        #[allow(unused)]
+        message: String,
+    },
+
     /// Internal error
     #[error("Internal runtime error: {message}")]
     #[diagnostic(
@@ -276,9 +362,96 @@ impl RuntimeError {
         }
     }
 
+    pub fn api_unavailable(base_url: impl Into<String>, retry_after: std::time::Duration) -> Self {
+        Self::ApiUnavailable {
+            base_url: base_url.into(),
+            retry_after_secs: retry_after.as_secs().max(1),
+        }
+    }
+
+    /// A failed API server request: `status` and `body` of the response,
+    /// if any came, classify it
+    pub fn api_request_failed(status: Option<u16>, body: &str, message: impl Into<String>) -> Self {
+        let reason = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|status| status["reason"].as_str().map(String::from));
+        Self::ApiRequestFailed {
+            status,
+            reason,
+            message: message.into(),
+        }
+    }
+
+    pub fn mesh_error(message: impl Into<String>) -> Self {
+        Self::MeshError {
+            message: message.into(),
+        }
+    }
+
+    pub fn host_port_conflict(
+        port: u16,
+        protocol: impl Into<String>,
+        owner: impl Into<String>,
+    ) -> Self {
+        Self::HostPortConflict {
+            port,
+            protocol: protocol.into(),
+            owner: owner.into(),
+        }
+    }
+
+    pub fn device_unavailable(resource: impl Into<String>, requested: i64, available: i64) -> Self {
+        Self::DeviceUnavailable {
+            resource: resource.into(),
+            requested,
+            available,
+        }
+    }
+
+    pub fn workload_identity_error(message: impl Into<String>) -> Self {
+        Self::WorkloadIdentityError {
+            message: message.into(),
+        }
+    }
+
     pub fn internal_error(message: impl Into<String>) -> Self {
         Self::InternalError {
             message: message.into(),
         }
     }
 }
+
+impl ErrorClass for RuntimeError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ZoneNotFound { .. } => ErrorKind::NotFound,
+            Self::ZoneAlreadyExists { .. } => ErrorKind::AlreadyExists,
+            // The zone changed state under the operation, and the port is
+            // freed when its owner goes
+            Self::InvalidStateTransition { .. } | Self::HostPortConflict { .. } => {
+                ErrorKind::Conflict
+            }
+            Self::InvalidConfig { .. } | Self::UnsupportedPlatform => ErrorKind::Invalid,
+            Self::ApiUnavailable { .. }
+            | Self::IpamPoolExhausted { .. }
+            | Self::DeviceUnavailable { .. } => ErrorKind::Unavailable,
+            Self::ApiRequestFailed { status, reason, .. } => reason
+                .as_deref()
+                .and_then(ErrorKind::from_reason)
+                .or(status.map(ErrorKind::from_status))
+                .unwrap_or(ErrorKind::Unavailable),
+            Self::CoreError(e) => e.kind(),
+            Self::StorageError(e) => e.kind(),
+            Self::ZoneOperationFailed { .. }
+            | Self::NetworkError { .. }
+            | Self::ZfsError { .. }
+            | Self::CommandFailed { .. }
+            | Self::StorageInitFailed { .. }
+            | Self::ResourceDetectionFailed { .. }
+            | Self::ProbeFailed { .. }
+            | Self::MeshError { .. }
+            | Self::WorkloadIdentityError { .. }
+            | Self::InternalError { .. } => ErrorKind::Internal,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Event sink sending recorded events to the API server
+
+use crate::api_client::ApiClient;
+use k8s_openapi::api::core::v1::Event;
+use reddwarf_core::EventSink;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Sends each recorded event to the API server in the background, so that
+/// recording never holds up the controller doing it
+pub struct ApiEventSink {
+    api_client: Arc<ApiClient>,
+}
+
+impl ApiEventSink {
+    pub fn new(api_client: Arc<ApiClient>) -> Self {
+        Self { api_client }
+    }
+}
+
+impl EventSink for ApiEventSink {
+    fn record(&self, event: Event) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            warn!("No runtime to send event {:?} from", event.reason);
+            return;
+        };
+        let api_client = self.api_client.clone();
+        handle.spawn(async move {
+            let namespace = event.metadata.namespace.as_deref().unwrap_or("default");
+            if let Err(e) = api_client.create_event(namespace, &event).await {
+                warn!(
+                    "Failed to record {} event about {}: {}",
+                    event.reason.as_deref().unwrap_or_default(),
+                    event.involved_object.name.as_deref().unwrap_or_default(),
+                    e
+                );
+            }
+        });
+    }
+}
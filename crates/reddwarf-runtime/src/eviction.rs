@@ -0,0 +1,285 @@
+use crate::api_client::ApiClient;
+use crate::controller::pod_zone_name;
+use crate::error::Result;
+use crate::sysinfo::detect_available_memory;
+use crate::traits::ZoneRuntime;
+use k8s_openapi::api::core::v1::Pod;
+use reddwarf_core::resources::pod_priority;
+use reddwarf_core::{pod_qos_class, QosClass, Termination, TerminationReason};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Reason recorded on pods terminated by the eviction manager
+pub const EVICTED_REASON: &str = TerminationReason::Evicted.as_str();
+
+/// Source of the host's available memory in bytes
+pub type MemoryProbe = fn() -> Result<u64>;
+
+/// Configuration for the eviction manager
+#[derive(Debug, Clone)]
+pub struct EvictionManagerConfig {
+    /// Only consider pods bound to this node
+    pub node_name: String,
+    /// How often to sample available memory
+    pub check_interval: Duration,
+    /// Evict when available memory drops below this many bytes
+    pub memory_available_threshold_bytes: u64,
+}
+
+impl EvictionManagerConfig {
+    pub fn new(node_name: String) -> Self {
+        Self {
+            node_name,
+            check_interval: Duration::from_secs(10),
+            // Matches the Kubernetes default hard eviction threshold
+            memory_available_threshold_bytes: 100 * 1024 * 1024,
+        }
+    }
+}
+
+/// Reclaims memory under node pressure by evicting pods in QoS order
+///
+/// BestEffort pods go first, then Burstable, then Guaranteed. Within a
+/// class, lower priority and more recently started pods are evicted first.
+/// One pod is evicted per check so the next sample reflects the reclaimed memory.
+pub struct EvictionManager {
+    runtime: Arc<dyn ZoneRuntime>,
+    api_client: Arc<ApiClient>,
+    config: EvictionManagerConfig,
+    memory_probe: MemoryProbe,
+}
+
+impl EvictionManager {
+    pub fn new(
+        runtime: Arc<dyn ZoneRuntime>,
+        api_client: Arc<ApiClient>,
+        config: EvictionManagerConfig,
+    ) -> Self {
+        Self {
+            runtime,
+            api_client,
+            config,
+            memory_probe: detect_available_memory,
+        }
+    }
+
+    /// Replace the available-memory source (used by tests)
+    pub fn with_memory_probe(mut self, probe: MemoryProbe) -> Self {
+        self.memory_probe = probe;
+        self
+    }
+
+    /// Run the eviction loop until cancelled
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting eviction manager for node {} (threshold: {} bytes, interval: {:?})",
+            self.config.node_name,
+            self.config.memory_available_threshold_bytes,
+            self.config.check_interval
+        );
+
+        let mut interval = tokio::time::interval(self.config.check_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Eviction manager shutting down");
+                    return Ok(());
+                }
+                _ = interval.tick() => {
+                    if let Err(e) = self.check().await {
+                        error!("Eviction check failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sample available memory and evict one pod if under pressure.
+    ///
+    /// Returns the `namespace/name` of the evicted pod, if any.
+    pub async fn check(&self) -> Result<Option<String>> {
+        let available = (self.memory_probe)()?;
+        if available >= self.config.memory_available_threshold_bytes {
+            return Ok(None);
+        }
+
+        warn!(
+            "Node {} under memory pressure: {} bytes available (threshold {})",
+            self.config.node_name, available, self.config.memory_available_threshold_bytes
+        );
+
+        let body = self.api_client.get_json("/api/v1/pods").await?;
+        let pods: Vec<Pod> = body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect();
+
+        let Some(victim) = select_victim(&pods, &self.config.node_name) else {
+            warn!("Memory pressure but no evictable pods on this node");
+            return Ok(None);
+        };
+
+        self.evict(victim).await.map(Some)
+    }
+
+    async fn evict(&self, pod: &Pod) -> Result<String> {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let name = pod.metadata.name.as_deref().unwrap_or("");
+        let zone_name = pod_zone_name(namespace, name);
+
+        info!(
+            "Evicting pod {}/{} ({}) to reclaim memory",
+            namespace,
+            name,
+            pod_qos(pod)
+        );
+
+        if let Err(e) = self.runtime.halt_zone(&zone_name).await {
+            // The zone may already be gone; still mark the pod evicted so
+            // the scheduler stops counting it against this node.
+            debug!("Halting zone {} during eviction: {}", zone_name, e);
+        }
+
+        let status = Termination::new(
+            TerminationReason::Evicted,
+            "The node was low on resource: memory.",
+        )
+        .pod_status(pod);
+        self.api_client
+            .set_pod_status(namespace, name, status)
+            .await?;
+
+        Ok(format!("{}/{}", namespace, name))
+    }
+}
+
+/// QoS class of a pod, preferring the class recorded at admission
+fn pod_qos(pod: &Pod) -> QosClass {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.qos_class.as_deref())
+        .and_then(QosClass::parse)
+        .unwrap_or_else(|| pod_qos_class(pod))
+}
+
+/// Eviction rank of a QoS class; lower is evicted first
+fn qos_rank(qos: QosClass) -> u8 {
+    match qos {
+        QosClass::BestEffort => 0,
+        QosClass::Burstable => 1,
+        QosClass::Guaranteed => 2,
+    }
+}
+
+/// Pick the next pod to evict among the running pods bound to `node_name`
+pub fn select_victim<'a>(pods: &'a [Pod], node_name: &str) -> Option<&'a Pod> {
+    pods.iter()
+        .filter(|p| {
+            p.spec.as_ref().and_then(|s| s.node_name.as_deref()) == Some(node_name)
+                && p.metadata.deletion_timestamp.is_none()
+                && p.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running")
+        })
+        .min_by_key(|p| {
+            let priority = pod_priority(p);
+            let started = p.status.as_ref().and_then(|s| s.start_time.clone());
+            (
+                qos_rank(pod_qos(p)),
+                priority,
+                std::cmp::Reverse(started.map(|t| t.0)),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, PodSpec, PodStatus, ResourceRequirements};
+    use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+    fn make_pod(name: &str, qos: QosClass, started_secs: i64) -> Pod {
+        let limits = match qos {
+            QosClass::BestEffort => None,
+            QosClass::Burstable => Some([("cpu".to_string(), Quantity("1".to_string()))].into()),
+            QosClass::Guaranteed => Some(
+                [
+                    ("cpu".to_string(), Quantity("1".to_string())),
+                    ("memory".to_string(), Quantity("1Gi".to_string())),
+                ]
+                .into(),
+            ),
+        };
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "app".to_string(),
+                resources: Some(ResourceRequirements {
+                    limits,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            start_time: Some(Time(
+                chrono::DateTime::from_timestamp(started_secs, 0).unwrap(),
+            )),
+            ..Default::default()
+        });
+        pod
+    }
+
+    #[test]
+    fn test_best_effort_evicted_before_burstable_and_guaranteed() {
+        let pods = vec![
+            make_pod("guaranteed", QosClass::Guaranteed, 100),
+            make_pod("burstable", QosClass::Burstable, 100),
+            make_pod("best-effort", QosClass::BestEffort, 100),
+        ];
+        let victim = select_victim(&pods, "node1").unwrap();
+        assert_eq!(victim.metadata.name.as_deref(), Some("best-effort"));
+
+        let victim = select_victim(&pods[..2], "node1").unwrap();
+        assert_eq!(victim.metadata.name.as_deref(), Some("burstable"));
+    }
+
+    #[test]
+    fn test_newest_pod_evicted_first_within_class() {
+        let pods = vec![
+            make_pod("old", QosClass::BestEffort, 100),
+            make_pod("new", QosClass::BestEffort, 200),
+        ];
+        let victim = select_victim(&pods, "node1").unwrap();
+        assert_eq!(victim.metadata.name.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_recorded_qos_class_takes_precedence() {
+        let mut pod = make_pod("labelled", QosClass::BestEffort, 100);
+        pod.status.as_mut().unwrap().qos_class = Some("Guaranteed".to_string());
+        let pods = vec![pod, make_pod("burstable", QosClass::Burstable, 100)];
+        let victim = select_victim(&pods, "node1").unwrap();
+        assert_eq!(victim.metadata.name.as_deref(), Some("burstable"));
+    }
+
+    #[test]
+    fn test_only_running_pods_on_node_are_candidates() {
+        let mut other_node = make_pod("elsewhere", QosClass::BestEffort, 100);
+        other_node.spec.as_mut().unwrap().node_name = Some("node2".to_string());
+        let mut pending = make_pod("pending", QosClass::BestEffort, 100);
+        pending.status.as_mut().unwrap().phase = Some("Pending".to_string());
+
+        assert!(select_victim(&[other_node, pending], "node1").is_none());
+    }
+}
@@ -1,14 +1,28 @@
 use crate::brand::lx::lx_install_args;
 use crate::command::{exec, CommandOutput};
 use crate::error::Result;
+use crate::network::bandwidth::format_maxbw;
+use crate::network::dns::hosts_file;
+use crate::network::host_ports::ipnat_rules;
+use crate::session::{relay_child, relay_supervisor, SUPERVISOR_SOCKET};
 use crate::storage::StorageEngine;
+use crate::svid::{Svid, SVID_BUNDLE_FILE, SVID_CERT_FILE, SVID_DIR, SVID_KEY_FILE};
 use crate::traits::ZoneRuntime;
 use crate::types::*;
-use crate::zone::config::generate_zonecfg;
+use crate::zone::config::{generate_claim_zonecfg, generate_warm_zonecfg, generate_zonecfg};
 use crate::zone::state::parse_zoneadm_line;
 use async_trait::async_trait;
-use std::sync::Arc;
-use tracing::info;
+use reddwarf_core::{ProcessSession, SessionRequest, SessionTarget};
+use std::collections::HashSet;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use tokio::net::UnixStream;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// How often the zone monitor lists the running zones
+const ZONE_MONITOR_INTERVAL: Duration = Duration::from_secs(2);
 
 /// illumos zone runtime implementation
 ///
@@ -16,35 +30,109 @@ use tracing::info;
 /// Storage (ZFS datasets) is delegated to the injected `StorageEngine`.
 pub struct IllumosRuntime {
     storage: Arc<dyn StorageEngine>,
+    events: broadcast::Sender<ZoneEvent>,
+    /// Zones being shut down or halted on request, which the monitor reports
+    /// as halted rather than panicked once they stop
+    stopping: Arc<Mutex<HashSet<String>>>,
+    monitor: Once,
 }
 
 impl IllumosRuntime {
     pub fn new(storage: Arc<dyn StorageEngine>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            events: broadcast::channel(64).0,
+            stopping: Arc::new(Mutex::new(HashSet::new())),
+            monitor: Once::new(),
+        }
     }
-}
 
-#[async_trait]
-impl ZoneRuntime for IllumosRuntime {
-    async fn create_zone(&self, config: &ZoneConfig) -> Result<()> {
-        info!("Creating zone: {}", config.zone_name);
+    /// Write the zone's nodename and hosts file before its first boot
+    async fn write_zone_identity(&self, config: &ZoneConfig, hostname: &str) -> Result<()> {
+        let etc = std::path::Path::new(&config.zonepath).join("root/etc");
+        let io_err = |e: std::io::Error| {
+            crate::error::RuntimeError::zone_operation_failed(&config.zone_name, e.to_string())
+        };
 
-        let zonecfg_content = generate_zonecfg(config)?;
+        // LX images read /etc/hostname, native zones /etc/nodename
+        let nodename_file = match config.brand {
+            ZoneBrand::Lx => "hostname",
+            _ => "nodename",
+        };
+        tokio::fs::write(etc.join(nodename_file), format!("{}\n", hostname))
+            .await
+            .map_err(io_err)?;
+
+        let ip = match &config.network {
+            NetworkMode::Etherstub(e) => &e.ip_address,
+            NetworkMode::Direct(d) => &d.ip_address,
+        };
+        tokio::fs::write(
+            etc.join("hosts"),
+            hosts_file(ip, hostname, config.fqdn.as_deref()),
+        )
+        .await
+        .map_err(io_err)?;
+        Ok(())
+    }
 
+    /// Apply a zonecfg command file to a zone
+    async fn apply_zonecfg(&self, zone_name: &str, content: &str) -> Result<()> {
         // Write config to a temp file, then apply via zonecfg
-        let tmp_path = format!("/tmp/zonecfg-{}.cmd", config.zone_name);
-        tokio::fs::write(&tmp_path, &zonecfg_content)
-            .await
-            .map_err(|e| {
-                crate::error::RuntimeError::zone_operation_failed(&config.zone_name, e.to_string())
-            })?;
+        let tmp_path = format!("/tmp/zonecfg-{}.cmd", zone_name);
+        tokio::fs::write(&tmp_path, content).await.map_err(|e| {
+            crate::error::RuntimeError::zone_operation_failed(zone_name, e.to_string())
+        })?;
 
-        let result = exec("zonecfg", &["-z", &config.zone_name, "-f", &tmp_path]).await;
+        let result = exec("zonecfg", &["-z", zone_name, "-f", &tmp_path]).await;
 
         // Clean up temp file (best-effort)
         let _ = tokio::fs::remove_file(&tmp_path).await;
 
         result?;
+        Ok(())
+    }
+
+    /// Set the pod's protocol properties from inside the booted zone, where
+    /// they apply to its own IP stack and persist across reboots
+    async fn apply_ip_props(&self, config: &ZoneConfig) -> Result<()> {
+        if config.ip_props.is_empty() {
+            return Ok(());
+        }
+        if config.brand == ZoneBrand::Lx {
+            warn!(
+                "Zone {}: protocol properties are not supported for LX zones",
+                config.zone_name
+            );
+            return Ok(());
+        }
+        for prop in &config.ip_props {
+            let assignment = format!("{}={}", prop.name, prop.value);
+            exec(
+                "zlogin",
+                &[
+                    &config.zone_name,
+                    "/usr/sbin/ipadm",
+                    "set-prop",
+                    "-p",
+                    &assignment,
+                    &prop.protocol,
+                ],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ZoneRuntime for IllumosRuntime {
+    async fn create_zone(&self, config: &ZoneConfig) -> Result<()> {
+        info!("Creating zone: {}", config.zone_name);
+
+        let zonecfg_content = generate_zonecfg(config)?;
+        self.apply_zonecfg(&config.zone_name, &zonecfg_content)
+            .await?;
         info!("Zone configured: {}", config.zone_name);
         Ok(())
     }
@@ -65,6 +153,7 @@ impl ZoneRuntime for IllumosRuntime {
 
     async fn shutdown_zone(&self, zone_name: &str) -> Result<()> {
         info!("Shutting down zone: {}", zone_name);
+        self.stopping.lock().unwrap().insert(zone_name.to_string());
         exec("zoneadm", &["-z", zone_name, "shutdown"]).await?;
         info!("Zone shutdown: {}", zone_name);
         Ok(())
@@ -72,6 +161,7 @@ impl ZoneRuntime for IllumosRuntime {
 
     async fn halt_zone(&self, zone_name: &str) -> Result<()> {
         info!("Halting zone: {}", zone_name);
+        self.stopping.lock().unwrap().insert(zone_name.to_string());
         exec("zoneadm", &["-z", zone_name, "halt"]).await?;
         info!("Zone halted: {}", zone_name);
         Ok(())
@@ -98,6 +188,47 @@ impl ZoneRuntime for IllumosRuntime {
         crate::command::exec_unchecked("zlogin", &args).await
     }
 
+    async fn open_session(
+        &self,
+        zone_name: &str,
+        request: &SessionRequest,
+    ) -> Result<ProcessSession> {
+        let (session, end) = ProcessSession::pair();
+        match &request.target {
+            SessionTarget::Exec(command) if !request.tty => {
+                let child = tokio::process::Command::new("zlogin")
+                    .arg("-Q")
+                    .arg(zone_name)
+                    .args(command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|e| {
+                        crate::error::RuntimeError::zone_operation_failed(
+                            zone_name,
+                            format!("Failed to start zlogin: {}", e),
+                        )
+                    })?;
+                relay_child(child, end);
+            }
+            // Terminals and container processes are the supervisor's
+            _ => {
+                let zone = self.get_zone_info(zone_name).await?;
+                let socket = format!("{}/root{}", zone.zonepath, SUPERVISOR_SOCKET);
+                let stream = UnixStream::connect(&socket).await.map_err(|e| {
+                    crate::error::RuntimeError::zone_operation_failed(
+                        zone_name,
+                        format!("Failed to reach the zone supervisor at {}: {}", socket, e),
+                    )
+                })?;
+                relay_supervisor(stream, request, end).await?;
+            }
+        }
+        Ok(session)
+    }
+
     async fn get_zone_state(&self, zone_name: &str) -> Result<ZoneState> {
         let output = exec("zoneadm", &["-z", zone_name, "list", "-p"]).await?;
         let line = output.stdout.trim();
@@ -111,6 +242,13 @@ impl ZoneRuntime for IllumosRuntime {
         parse_zoneadm_line(line)
     }
 
+    fn subscribe_events(&self) -> broadcast::Receiver<ZoneEvent> {
+        self.monitor.call_once(|| {
+            tokio::spawn(monitor_zones(self.events.clone(), self.stopping.clone()));
+        });
+        self.events.subscribe()
+    }
+
     async fn list_zones(&self) -> Result<Vec<ZoneInfo>> {
         let output = exec("zoneadm", &["list", "-cp"]).await?;
         let mut zones = Vec::new();
@@ -173,28 +311,159 @@ impl ZoneRuntime for IllumosRuntime {
         Ok(())
     }
 
+    async fn set_link_bandwidth(&self, vnic_name: &str, maxbw_bps: Option<u64>) -> Result<()> {
+        match maxbw_bps {
+            Some(bps) => {
+                let prop = format!("maxbw={}", format_maxbw(bps));
+                info!("Setting {} on VNIC {}", prop, vnic_name);
+                exec("dladm", &["set-linkprop", "-p", &prop, vnic_name]).await?;
+            }
+            None => {
+                exec("dladm", &["reset-linkprop", "-p", "maxbw", vnic_name]).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_port_forwards(&self, zone_name: &str, forwards: &[PortForward]) -> Result<()> {
+        let dir = std::env::temp_dir().join("reddwarf-hostports");
+        tokio::fs::create_dir_all(&dir).await.map_err(|e| {
+            crate::error::RuntimeError::zone_operation_failed(zone_name, e.to_string())
+        })?;
+        let path = dir.join(format!("{}.ipnat", zone_name));
+        let path_str = path.to_string_lossy().to_string();
+
+        // Remove the rules loaded last time, if any
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            exec("ipnat", &["-r", "-f", &path_str]).await?;
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+
+        if forwards.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "Forwarding {} host port(s) to zone {}",
+            forwards.len(),
+            zone_name
+        );
+        tokio::fs::write(&path, ipnat_rules(forwards))
+            .await
+            .map_err(|e| {
+                crate::error::RuntimeError::zone_operation_failed(zone_name, e.to_string())
+            })?;
+        exec("ipnat", &["-f", &path_str]).await?;
+        Ok(())
+    }
+
+    async fn set_resolv_conf(
+        &self,
+        zone_name: &str,
+        zonepath: &str,
+        dns: &DnsConfig,
+    ) -> Result<()> {
+        let etc = std::path::Path::new(zonepath).join("root/etc");
+        let io_err = |e: std::io::Error| {
+            crate::error::RuntimeError::zone_operation_failed(zone_name, e.to_string())
+        };
+
+        info!("Writing resolv.conf for zone {}", zone_name);
+        tokio::fs::write(etc.join("resolv.conf"), dns.to_resolv_conf())
+            .await
+            .map_err(io_err)?;
+
+        // illumos images only consult DNS for hosts when nsswitch says so
+        let nsswitch_dns = etc.join("nsswitch.dns");
+        if tokio::fs::try_exists(&nsswitch_dns).await.unwrap_or(false) {
+            tokio::fs::copy(&nsswitch_dns, etc.join("nsswitch.conf"))
+                .await
+                .map_err(io_err)?;
+        }
+        Ok(())
+    }
+
+    async fn write_svid(&self, zone_name: &str, zonepath: &str, svid: &Svid) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::path::Path::new(zonepath).join("root").join(SVID_DIR);
+        let io_err = |e: std::io::Error| {
+            crate::error::RuntimeError::zone_operation_failed(zone_name, e.to_string())
+        };
+
+        tokio::fs::create_dir_all(&dir).await.map_err(io_err)?;
+        // Write aside and rename so the workload never reads a certificate
+        // that does not match the key
+        for (file, contents, mode) in [
+            (SVID_KEY_FILE, &svid.key_pem, 0o600),
+            (SVID_CERT_FILE, &svid.cert_pem, 0o644),
+            (SVID_BUNDLE_FILE, &svid.bundle_pem, 0o644),
+        ] {
+            let tmp = dir.join(format!(".{}.tmp", file));
+            tokio::fs::write(&tmp, contents).await.map_err(io_err)?;
+            tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(mode))
+                .await
+                .map_err(io_err)?;
+            tokio::fs::rename(&tmp, dir.join(file))
+                .await
+                .map_err(io_err)?;
+        }
+        debug!("Wrote SVID {} into zone {}", svid.spiffe_id, zone_name);
+        Ok(())
+    }
+
     async fn provision(&self, config: &ZoneConfig) -> Result<()> {
         info!("Provisioning zone: {}", config.zone_name);
 
+        // LX zones of an already cached image get a clone of its root
+        let cached_image = match (&config.brand, &config.lx_image_path) {
+            (ZoneBrand::Lx, Some(image)) => self.storage.image_snapshot(image).await?,
+            _ => None,
+        };
+        let mut storage_opts = config.storage.clone();
+        if cached_image.is_some() {
+            storage_opts.clone_from = cached_image.clone();
+        }
+
         self.storage
-            .create_zone_dataset(&config.zone_name, &config.storage)
+            .create_zone_dataset(&config.zone_name, &storage_opts)
             .await?;
         self.setup_network(&config.zone_name, &config.network)
             .await?;
         self.create_zone(config).await?;
 
-        // LX brand needs image path for install
-        if config.brand == ZoneBrand::Lx {
+        if cached_image.is_some() {
+            // The cloned root is already installed; attach just registers it
+            exec("zoneadm", &["-z", &config.zone_name, "attach"]).await?;
+        } else if config.brand == ZoneBrand::Lx {
+            // LX brand needs image path for install
             let args = lx_install_args(config)?;
             let mut cmd_args = vec!["-z", &config.zone_name, "install"];
             let str_args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
             cmd_args.extend(str_args);
             exec("zoneadm", &cmd_args).await?;
+
+            // Cache the pristine root for later zones of the same image; a
+            // failure only costs them a full install
+            if let Some(image) = &config.lx_image_path {
+                if let Err(e) = self.storage.cache_image(image, &config.zone_name).await {
+                    warn!("Failed to cache image {}: {}", image, e);
+                }
+            }
         } else {
             self.install_zone(&config.zone_name).await?;
         }
 
+        if let Some(dns) = &config.dns {
+            self.set_resolv_conf(&config.zone_name, &config.zonepath, dns)
+                .await?;
+        }
+        if let Some(hostname) = &config.hostname {
+            self.write_zone_identity(config, hostname).await?;
+        }
+
         self.boot_zone(&config.zone_name).await?;
+        self.apply_ip_props(config).await?;
 
         info!("Zone provisioned: {}", config.zone_name);
         Ok(())
@@ -214,4 +483,141 @@ impl ZoneRuntime for IllumosRuntime {
         info!("Zone deprovisioned: {}", config.zone_name);
         Ok(())
     }
+
+    async fn prepare_warm_zone(&self, zone: &WarmZone) -> Result<()> {
+        info!("Preparing warm zone: {}", zone.zone_name);
+
+        self.storage
+            .create_zone_dataset(&zone.zone_name, &ZoneStorageOpts::default())
+            .await?;
+        self.apply_zonecfg(&zone.zone_name, &generate_warm_zonecfg(zone))
+            .await?;
+
+        let mut args = vec!["-z", zone.zone_name.as_str(), "install"];
+        if let Some(image) = &zone.lx_image_path {
+            args.extend(["-s", image.as_str()]);
+        }
+        exec("zoneadm", &args).await?;
+
+        info!("Warm zone installed: {}", zone.zone_name);
+        Ok(())
+    }
+
+    async fn claim_warm_zone(&self, warm_zone_name: &str, config: &ZoneConfig) -> Result<()> {
+        info!(
+            "Claiming warm zone {} as {}",
+            warm_zone_name, config.zone_name
+        );
+
+        // Moving the zonepath also renames its ZFS dataset, to the one named
+        // after the pod's zone
+        exec("zoneadm", &["-z", warm_zone_name, "move", &config.zonepath]).await?;
+        exec(
+            "zonecfg",
+            &[
+                "-z",
+                warm_zone_name,
+                &format!("set zonename={}", config.zone_name),
+            ],
+        )
+        .await?;
+
+        self.setup_network(&config.zone_name, &config.network)
+            .await?;
+        self.apply_zonecfg(&config.zone_name, &generate_claim_zonecfg(config)?)
+            .await?;
+
+        if let Some(dns) = &config.dns {
+            self.set_resolv_conf(&config.zone_name, &config.zonepath, dns)
+                .await?;
+        }
+        if let Some(hostname) = &config.hostname {
+            self.write_zone_identity(config, hostname).await?;
+        }
+
+        self.boot_zone(&config.zone_name).await?;
+        self.apply_ip_props(config).await?;
+
+        info!("Zone provisioned from warm pool: {}", config.zone_name);
+        Ok(())
+    }
+
+    async fn destroy_warm_zone(&self, zone_name: &str) -> Result<()> {
+        info!("Destroying warm zone: {}", zone_name);
+
+        let state = self.get_zone_state(zone_name).await?;
+        if matches!(state, ZoneState::Running | ZoneState::Ready) {
+            self.halt_zone(zone_name).await?;
+        }
+        if state != ZoneState::Configured {
+            self.uninstall_zone(zone_name).await?;
+        }
+        self.delete_zone(zone_name).await?;
+        self.storage.destroy_zone_dataset(zone_name).await?;
+
+        info!("Warm zone destroyed: {}", zone_name);
+        Ok(())
+    }
+}
+
+/// Watch the running zones for as long as the process lives, reporting zones
+/// that come up and go down. zoneadmd records every state change in the
+/// zone's state, so one `zoneadm list` per interval catches crashes within
+/// seconds, however many zones the node runs.
+async fn monitor_zones(
+    events: broadcast::Sender<ZoneEvent>,
+    stopping: Arc<Mutex<HashSet<String>>>,
+) {
+    let mut running: Option<HashSet<String>> = None;
+    let mut interval = tokio::time::interval(ZONE_MONITOR_INTERVAL);
+    loop {
+        interval.tick().await;
+        let output = match exec("zoneadm", &["list", "-p"]).await {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("Zone monitor failed to list zones: {}", e);
+                continue;
+            }
+        };
+        let mut now = HashSet::new();
+        for line in output
+            .stdout
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+        {
+            match parse_zoneadm_line(line) {
+                Ok(info) if info.zone_name != "global" && info.state == ZoneState::Running => {
+                    now.insert(info.zone_name);
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Zone monitor skipped a zoneadm line: {}", e),
+            }
+        }
+
+        // The first listing is the baseline, not a change
+        if let Some(previous) = &running {
+            let send = |zone_name: &str, kind| {
+                let _ = events.send(ZoneEvent {
+                    zone_name: zone_name.to_string(),
+                    kind,
+                });
+            };
+            for zone_name in now.difference(previous) {
+                send(zone_name, ZoneEventKind::Booted);
+            }
+            let mut stopping = stopping.lock().unwrap();
+            for zone_name in previous.difference(&now) {
+                if stopping.remove(zone_name) {
+                    send(zone_name, ZoneEventKind::Halted);
+                } else {
+                    warn!("Zone {} went down unexpectedly", zone_name);
+                    send(zone_name, ZoneEventKind::Panicked);
+                }
+            }
+            // Forget requests for zones that never came down while watched
+            stopping.retain(|zone_name| now.contains(zone_name));
+        }
+        running = Some(now);
+    }
 }
@@ -0,0 +1,155 @@
+use crate::api_client::ApiClient;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::coordination::v1::{Lease, LeaseSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta};
+use reddwarf_core::resources::lease_holder;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// A Lease held by one identity at a time, for node heartbeats and leader
+/// election
+///
+/// Acquiring and renewing read the lease and write it back conditioned on
+/// the resourceVersion read, so of two candidates racing for the same lease
+/// only one wins.
+pub struct LeaseLock {
+    api_client: Arc<ApiClient>,
+    namespace: String,
+    name: String,
+    identity: String,
+    duration: Duration,
+}
+
+impl LeaseLock {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+        identity: impl Into<String>,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            api_client,
+            namespace: namespace.into(),
+            name: name.into(),
+            identity: identity.into(),
+            duration,
+        }
+    }
+
+    /// Acquire the lease if it is free or has lapsed, or renew it if this
+    /// identity already holds it. Returns whether this identity holds the
+    /// lease afterwards.
+    pub async fn try_acquire_or_renew(&self) -> Result<bool> {
+        let now = Utc::now();
+        let Some(current) = self
+            .api_client
+            .get_lease(&self.namespace, &self.name)
+            .await?
+        else {
+            let lease = Lease {
+                metadata: ObjectMeta {
+                    name: Some(self.name.clone()),
+                    namespace: Some(self.namespace.clone()),
+                    ..Default::default()
+                },
+                spec: None,
+            };
+            let lease = claim(lease, &self.identity, self.duration, now);
+            let created = self
+                .api_client
+                .create_lease(&self.namespace, &lease)
+                .await?;
+            if created.is_some() {
+                info!(
+                    "Acquired lease {}/{} as '{}'",
+                    self.namespace, self.name, self.identity
+                );
+            }
+            return Ok(created.is_some());
+        };
+
+        if let Some(holder) = lease_holder(&current, now).filter(|h| *h != self.identity) {
+            debug!(
+                "Lease {}/{} is held by '{}'",
+                self.namespace, self.name, holder
+            );
+            return Ok(false);
+        }
+
+        let taking_over = holder_of(&current) != Some(self.identity.as_str());
+        let lease = claim(current, &self.identity, self.duration, now);
+        let replaced = self
+            .api_client
+            .replace_lease(&self.namespace, &self.name, &lease)
+            .await?;
+        if replaced.is_some() && taking_over {
+            info!(
+                "Acquired lease {}/{} as '{}'",
+                self.namespace, self.name, self.identity
+            );
+        }
+        Ok(replaced.is_some())
+    }
+}
+
+fn holder_of(lease: &Lease) -> Option<&str> {
+    lease.spec.as_ref()?.holder_identity.as_deref()
+}
+
+/// `lease` as held by `identity` and renewed at `now`, counting a
+/// transition when it was held by another identity
+fn claim(mut lease: Lease, identity: &str, duration: Duration, now: DateTime<Utc>) -> Lease {
+    let previous = holder_of(&lease).map(str::to_string);
+    let spec = lease.spec.get_or_insert_with(LeaseSpec::default);
+    if previous.as_deref() != Some(identity) {
+        if previous.is_some() {
+            spec.lease_transitions = Some(spec.lease_transitions.unwrap_or(0) + 1);
+        }
+        spec.holder_identity = Some(identity.to_string());
+        spec.acquire_time = Some(MicroTime(now));
+    }
+    spec.renew_time = Some(MicroTime(now));
+    spec.lease_duration_seconds = Some(duration.as_secs().max(1) as i32);
+    lease
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_new_lease() {
+        let now = Utc::now();
+        let lease = claim(Lease::default(), "node1", Duration::from_secs(40), now);
+        let spec = lease.spec.unwrap();
+        assert_eq!(spec.holder_identity.as_deref(), Some("node1"));
+        assert_eq!(spec.lease_duration_seconds, Some(40));
+        assert_eq!(spec.acquire_time, Some(MicroTime(now)));
+        assert_eq!(spec.renew_time, Some(MicroTime(now)));
+        assert_eq!(spec.lease_transitions, None);
+    }
+
+    #[test]
+    fn test_claim_renews_or_takes_over() {
+        let acquired = Utc::now() - chrono::Duration::seconds(60);
+        let now = Utc::now();
+        let held = claim(Lease::default(), "a", Duration::from_secs(40), acquired);
+
+        // Renewing keeps the acquire time and counts no transition
+        let renewed = claim(held.clone(), "a", Duration::from_secs(40), now);
+        let spec = renewed.spec.unwrap();
+        assert_eq!(spec.acquire_time, Some(MicroTime(acquired)));
+        assert_eq!(spec.renew_time, Some(MicroTime(now)));
+        assert_eq!(spec.lease_transitions, None);
+
+        // Taking over a lapsed lease counts a transition
+        let taken = claim(held, "b", Duration::from_secs(40), now);
+        let spec = taken.spec.unwrap();
+        assert_eq!(spec.holder_identity.as_deref(), Some("b"));
+        assert_eq!(spec.acquire_time, Some(MicroTime(now)));
+        assert_eq!(spec.lease_transitions, Some(1));
+    }
+}
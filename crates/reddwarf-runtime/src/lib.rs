@@ -2,31 +2,57 @@
 #![allow(unused_assignments)]
 
 pub mod api_client;
+pub mod backoff;
 pub mod brand;
+pub mod circuit_breaker;
 pub mod command;
 pub mod controller;
+pub mod decision_log;
+pub mod devices;
 pub mod error;
+pub mod event_sink;
+pub mod eviction;
 #[cfg(target_os = "illumos")]
 pub mod illumos;
+pub mod lease;
+pub mod mesh;
 pub mod mock;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock_api;
 pub mod network;
 pub mod node_agent;
+pub mod pod_cache;
+pub mod pod_conditions;
 pub mod probes;
+pub mod session;
 pub mod node_health;
+pub mod node_timing;
 pub mod storage;
+pub mod svid;
 pub mod sysinfo;
+pub mod topology;
 pub mod traits;
 pub mod types;
+pub mod volume_binder;
+pub mod warm_pool;
+pub mod workloads;
 pub mod zone;
 
 // Re-export primary types
 pub use error::{Result, RuntimeError};
 pub use mock::MockRuntime;
-pub use network::{CidrConfig, IpAllocation, Ipam};
+pub use network::{
+    CidrConfig, EgressLockdownController, EgressLockdownControllerConfig, EgressNatController,
+    EgressNatControllerConfig, EndpointsController, EndpointsControllerConfig, IpAllocation, Ipam,
+    NetworkPolicyController, NetworkPolicyControllerConfig, NodeCidrAllocator, NodeIpamController,
+    NodeIpamControllerConfig, RouteDistributor, RouteDistributorConfig, ServiceRuleExporter,
+    ServiceRuleExporterConfig,
+};
 pub use traits::ZoneRuntime;
 pub use types::{
     ContainerProcess, DirectNicConfig, EtherstubConfig, FsMount, NetworkMode, StoragePoolConfig,
-    ZoneBrand, ZoneConfig, ZoneInfo, ZoneState, ZoneStorageOpts,
+    WarmZone, ZoneBrand, ZoneConfig, ZoneEvent, ZoneEventKind, ZoneExit, ZoneInfo, ZoneState,
+    ZoneStorageOpts,
 };
 
 // Re-export storage types
@@ -35,11 +61,31 @@ pub use storage::ZfsStorageEngine;
 pub use storage::{MockStorageEngine, StorageEngine, VolumeInfo};
 
 // Re-export controller and agent types
-pub use api_client::ApiClient;
+pub use api_client::{ApiClient, WatchStream};
+pub use backoff::{ReconcileBackoff, ReconcileBackoffConfig};
+pub use circuit_breaker::{CircuitBreakerConfig, CircuitBreakerStats, CircuitState};
 pub use controller::{PodController, PodControllerConfig};
+pub use decision_log::{Decision, DecisionAction, DecisionLog, DecisionOutcome};
+pub use devices::DeviceTable;
+pub use event_sink::ApiEventSink;
+pub use eviction::{EvictionManager, EvictionManagerConfig};
+pub use lease::LeaseLock;
+pub use mesh::{MeshIdentity, MeshProxy, MeshProxyConfig};
 pub use node_agent::{NodeAgent, NodeAgentConfig};
+pub use pod_cache::PodCache;
 pub use node_health::{NodeHealthChecker, NodeHealthCheckerConfig};
 pub use probes::{ProbeExecutor, ProbeTracker};
+pub use svid::{Svid, SvidIssuer};
+pub use topology::NodeTopology;
+pub use volume_binder::{VolumeBinder, VolumeBinderConfig};
+pub use warm_pool::{WarmPool, WarmPoolSpec};
+pub use workloads::{
+    DaemonSetController, DaemonSetControllerConfig, DeploymentController, DeploymentControllerConfig,
+    DisruptionBudgetController, DisruptionBudgetControllerConfig, HorizontalPodAutoscalerController,
+    HorizontalPodAutoscalerControllerConfig, MemoryPodMetrics, PodMetrics, PodUsage,
+    ReplicaSetController, ReplicaSetControllerConfig, TtlAfterFinishedController,
+    TtlAfterFinishedControllerConfig,
+};
 
 // Conditionally re-export illumos runtime
 #[cfg(target_os = "illumos")]
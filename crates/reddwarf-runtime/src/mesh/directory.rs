@@ -0,0 +1,195 @@
+use crate::controller::pod_zone_name;
+use crate::network::vnic_name_for_pod;
+use k8s_openapi::api::core::v1::{Node, Pod};
+use reddwarf_core::MeshPolicy;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// A running pod as seen by the mesh
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshPeer {
+    pub namespace: String,
+    pub name: String,
+    pub node_name: String,
+    pub zone_name: String,
+    pub vnic_name: String,
+    /// Ports selected by mesh policies, sorted and deduplicated
+    pub meshed_ports: Vec<u16>,
+}
+
+/// Snapshot of pods, nodes and mesh policies used to route tunnelled traffic
+#[derive(Debug, Default)]
+pub struct MeshDirectory {
+    pods: HashMap<IpAddr, MeshPeer>,
+    node_endpoints: HashMap<String, String>,
+}
+
+impl MeshDirectory {
+    pub fn build(pods: &[Pod], nodes: &[Node], policies: &[MeshPolicy]) -> Self {
+        let mut directory = Self::default();
+
+        for pod in pods {
+            let (Some(namespace), Some(name)) = (
+                pod.metadata.namespace.as_deref(),
+                pod.metadata.name.as_deref(),
+            ) else {
+                continue;
+            };
+            let Some(node_name) = pod.spec.as_ref().and_then(|s| s.node_name.as_deref()) else {
+                continue;
+            };
+            let Some(ip) = pod
+                .status
+                .as_ref()
+                .and_then(|s| s.pod_ip.as_deref())
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+            else {
+                continue;
+            };
+
+            let mut meshed_ports: Vec<u16> = policies
+                .iter()
+                .filter(|p| p.selects(namespace, pod.metadata.labels.as_ref()))
+                .flat_map(|p| p.spec.ports.iter().copied())
+                .collect();
+            meshed_ports.sort_unstable();
+            meshed_ports.dedup();
+
+            directory.pods.insert(
+                ip,
+                MeshPeer {
+                    namespace: namespace.to_string(),
+                    name: name.to_string(),
+                    node_name: node_name.to_string(),
+                    zone_name: pod_zone_name(namespace, name),
+                    vnic_name: vnic_name_for_pod(namespace, name),
+                    meshed_ports,
+                },
+            );
+        }
+
+        for node in nodes {
+            let Some(name) = node.metadata.name.as_deref() else {
+                continue;
+            };
+            if let Some(endpoint) = node_endpoint(node) {
+                directory.node_endpoints.insert(name.to_string(), endpoint);
+            }
+        }
+
+        directory
+    }
+
+    /// Pod owning an address
+    pub fn peer(&self, ip: IpAddr) -> Option<&MeshPeer> {
+        self.pods.get(&ip)
+    }
+
+    /// Whether connections to `dest` are covered by a mesh policy
+    pub fn is_meshed(&self, dest: SocketAddr) -> bool {
+        self.peer(dest.ip())
+            .is_some_and(|p| p.meshed_ports.binary_search(&dest.port()).is_ok())
+    }
+
+    /// Host (address or hostname) where a node's mesh proxy is reachable
+    pub fn node_endpoint(&self, node_name: &str) -> Option<&str> {
+        self.node_endpoints.get(node_name).map(String::as_str)
+    }
+
+    /// Pods on `node_name` that have at least one meshed port
+    pub fn meshed_peers_on<'a>(&'a self, node_name: &'a str) -> impl Iterator<Item = &'a MeshPeer> {
+        self.pods
+            .values()
+            .filter(move |p| p.node_name == node_name && !p.meshed_ports.is_empty())
+    }
+}
+
+/// Prefer the node's InternalIP, falling back to its hostname
+fn node_endpoint(node: &Node) -> Option<String> {
+    let addresses = node.status.as_ref()?.addresses.as_ref()?;
+    ["InternalIP", "Hostname"].iter().find_map(|kind| {
+        addresses
+            .iter()
+            .find(|a| a.type_ == *kind)
+            .map(|a| a.address.clone())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{NodeAddress, NodeStatus, PodSpec, PodStatus};
+    use reddwarf_core::MeshPolicySpec;
+
+    fn make_pod(name: &str, node: &str, ip: &str, app: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.labels = Some([("app".to_string(), app.to_string())].into());
+        pod.spec = Some(PodSpec {
+            node_name: Some(node.to_string()),
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            pod_ip: Some(ip.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn make_node(name: &str, addresses: &[(&str, &str)]) -> Node {
+        let mut node = Node::default();
+        node.metadata.name = Some(name.to_string());
+        node.status = Some(NodeStatus {
+            addresses: Some(
+                addresses
+                    .iter()
+                    .map(|(t, a)| NodeAddress {
+                        type_: t.to_string(),
+                        address: a.to_string(),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        });
+        node
+    }
+
+    #[test]
+    fn test_directory_marks_selected_ports() {
+        let pods = vec![
+            make_pod("web", "node1", "10.88.0.2", "web"),
+            make_pod("db", "node2", "10.88.0.3", "db"),
+        ];
+        let nodes = vec![
+            make_node("node1", &[("Hostname", "host1")]),
+            make_node(
+                "node2",
+                &[("Hostname", "host2"), ("InternalIP", "192.168.1.2")],
+            ),
+        ];
+        let policies = vec![MeshPolicy::new(
+            "default",
+            "db",
+            MeshPolicySpec {
+                pod_selector: [("app".to_string(), "db".to_string())].into(),
+                ports: vec![5432, 5432, 9187],
+            },
+        )];
+
+        let dir = MeshDirectory::build(&pods, &nodes, &policies);
+
+        assert!(dir.is_meshed("10.88.0.3:5432".parse().unwrap()));
+        assert!(!dir.is_meshed("10.88.0.3:80".parse().unwrap()));
+        assert!(!dir.is_meshed("10.88.0.2:5432".parse().unwrap()));
+
+        let db = dir.peer("10.88.0.3".parse().unwrap()).unwrap();
+        assert_eq!(db.meshed_ports, vec![5432, 9187]);
+        assert_eq!(db.zone_name, "reddwarf-default-db");
+
+        assert_eq!(dir.node_endpoint("node1"), Some("host1"));
+        assert_eq!(dir.node_endpoint("node2"), Some("192.168.1.2"));
+        assert_eq!(dir.meshed_peers_on("node2").count(), 1);
+        assert_eq!(dir.meshed_peers_on("node1").count(), 0);
+    }
+}
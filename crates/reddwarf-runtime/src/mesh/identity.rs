@@ -0,0 +1,182 @@
+use crate::error::{Result, RuntimeError};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use std::sync::Arc;
+
+/// Common name of the auto-generated cluster CA.
+///
+/// rcgen can only sign with a `Certificate` it built itself, so the CA is
+/// rebuilt from its key with the same subject; the original `ca.pem` remains
+/// the trust anchor.
+pub const CLUSTER_CA_COMMON_NAME: &str = "Reddwarf CA";
+
+/// The cluster CA, loaded to sign certificates
+pub(crate) struct ClusterCa {
+    /// CA certificate rebuilt from its key, for rcgen to sign with
+    pub cert: Certificate,
+    pub key: KeyPair,
+    /// The original CA certificate, the trust anchor
+    pub der: Vec<u8>,
+}
+
+impl ClusterCa {
+    /// Load the CA from its PEM certificate and key, failing if the key does
+    /// not belong to the certificate
+    pub fn load(ca_cert_pem: &[u8], ca_key_pem: &[u8]) -> std::result::Result<Self, String> {
+        let ca_key_pem = std::str::from_utf8(ca_key_pem)
+            .map_err(|e| format!("CA key is not valid PEM: {}", e))?;
+        let key =
+            KeyPair::from_pem(ca_key_pem).map_err(|e| format!("failed to load CA key: {}", e))?;
+
+        let der = first_certificate(ca_cert_pem)?;
+        // The CA certificate embeds its public key verbatim; a mismatch means
+        // the key belongs to a different CA and peers would reject our certs.
+        let ca_public_key = key.public_key_raw();
+        if !der.windows(ca_public_key.len()).any(|w| w == ca_public_key) {
+            return Err("CA key does not match the CA certificate".to_string());
+        }
+
+        let mut params = CertificateParams::default();
+        params.distinguished_name = ca_distinguished_name();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let cert = params
+            .self_signed(&key)
+            .map_err(|e| format!("failed to load CA: {}", e))?;
+        Ok(Self { cert, key, der })
+    }
+}
+
+/// A node's mesh certificate, issued from the cluster CA
+pub struct MeshIdentity {
+    node_name: String,
+    cert_chain: Vec<CertificateDer<'static>>,
+    key_der: Vec<u8>,
+    roots: Arc<RootCertStore>,
+}
+
+impl MeshIdentity {
+    /// Issue a certificate for `node_name` signed by the cluster CA.
+    ///
+    /// The certificate is valid for both ends of a tunnel (server and client
+    /// auth) and carries the node name as its DNS SAN.
+    pub fn issue(ca_cert_pem: &[u8], ca_key_pem: &[u8], node_name: &str) -> Result<Self> {
+        let ca = ClusterCa::load(ca_cert_pem, ca_key_pem).map_err(RuntimeError::mesh_error)?;
+
+        let node_key = KeyPair::generate()
+            .map_err(|e| RuntimeError::mesh_error(format!("failed to generate key: {}", e)))?;
+        let mut params = CertificateParams::new(vec![node_name.to_string()]).map_err(|e| {
+            RuntimeError::mesh_error(format!("invalid node name '{}': {}", node_name, e))
+        })?;
+        params
+            .distinguished_name
+            .push(DnType::CommonName, node_name);
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        let node_cert = params
+            .signed_by(&node_key, &ca.cert, &ca.key)
+            .map_err(|e| RuntimeError::mesh_error(format!("failed to sign certificate: {}", e)))?;
+
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from(ca.der))
+            .map_err(|e| RuntimeError::mesh_error(format!("invalid CA certificate: {}", e)))?;
+
+        Ok(Self {
+            node_name: node_name.to_string(),
+            cert_chain: vec![node_cert.der().clone()],
+            key_der: node_key.serialize_der(),
+            roots: Arc::new(roots),
+        })
+    }
+
+    pub fn node_name(&self) -> &str {
+        &self.node_name
+    }
+
+    /// TLS acceptor config requiring a client certificate from the cluster CA
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>> {
+        let verifier = WebPkiClientVerifier::builder(self.roots.clone())
+            .build()
+            .map_err(|e| RuntimeError::mesh_error(format!("client verifier: {}", e)))?;
+        let config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(self.cert_chain.clone(), self.private_key())
+            .map_err(|e| RuntimeError::mesh_error(format!("server TLS config: {}", e)))?;
+        Ok(Arc::new(config))
+    }
+
+    /// TLS connector config presenting this node's certificate
+    pub fn client_config(&self) -> Result<Arc<ClientConfig>> {
+        let config = ClientConfig::builder()
+            .with_root_certificates(self.roots.clone())
+            .with_client_auth_cert(self.cert_chain.clone(), self.private_key())
+            .map_err(|e| RuntimeError::mesh_error(format!("client TLS config: {}", e)))?;
+        Ok(Arc::new(config))
+    }
+
+    fn private_key(&self) -> PrivateKeyDer<'static> {
+        PrivateKeyDer::Pkcs8(self.key_der.clone().into())
+    }
+}
+
+/// TLS server name under which a peer node's certificate is verified
+pub fn peer_server_name(node_name: &str) -> Result<ServerName<'static>> {
+    ServerName::try_from(node_name.to_string())
+        .map_err(|e| RuntimeError::mesh_error(format!("invalid node name '{}': {}", node_name, e)))
+}
+
+/// Subject shared by the auto-generated cluster CA and the rebuilt signer
+pub fn ca_distinguished_name() -> DistinguishedName {
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::CommonName, CLUSTER_CA_COMMON_NAME);
+    dn
+}
+
+fn first_certificate(pem: &[u8]) -> std::result::Result<Vec<u8>, String> {
+    let mut reader = pem;
+    let first = rustls_pemfile::certs(&mut reader)
+        .next()
+        .transpose()
+        .map_err(|e| format!("invalid CA certificate PEM: {}", e))?;
+    first
+        .map(|der| der.to_vec())
+        .ok_or_else(|| "no certificate found in CA PEM".to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Generate a cluster CA the same way the API server does
+    pub(crate) fn test_ca() -> (Vec<u8>, Vec<u8>) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::default();
+        params.distinguished_name = ca_distinguished_name();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let cert = params.self_signed(&key).unwrap();
+        (cert.pem().into_bytes(), key.serialize_pem().into_bytes())
+    }
+
+    #[test]
+    fn test_issue_identity_builds_tls_configs() {
+        let (ca_pem, ca_key) = test_ca();
+        let identity = MeshIdentity::issue(&ca_pem, &ca_key, "node1").unwrap();
+        assert_eq!(identity.node_name(), "node1");
+        assert!(identity.server_config().is_ok());
+        assert!(identity.client_config().is_ok());
+    }
+
+    #[test]
+    fn test_issue_rejects_mismatched_ca_key() {
+        let (ca_pem, _) = test_ca();
+        let (_, other_key) = test_ca();
+        assert!(MeshIdentity::issue(&ca_pem, &other_key, "node1").is_err());
+    }
+}
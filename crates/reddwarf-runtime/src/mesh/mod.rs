@@ -0,0 +1,10 @@
+//! Service mesh-lite: node-level mTLS tunnels between pod zones
+pub mod directory;
+pub mod identity;
+pub mod proxy;
+pub mod redirect;
+
+pub use directory::{MeshDirectory, MeshPeer};
+pub use identity::MeshIdentity;
+pub use proxy::{MeshProxy, MeshProxyConfig, MESH_INBOUND_PORT, MESH_OUTBOUND_PORT};
+pub use redirect::{IpnatRedirect, TrafficRedirect};
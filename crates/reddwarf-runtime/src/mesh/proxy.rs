@@ -0,0 +1,500 @@
+use super::directory::MeshDirectory;
+use super::identity::{peer_server_name, MeshIdentity};
+use super::redirect::{redirect_rules, TrafficRedirect};
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use reddwarf_core::resources::{MultiVersion, MESH_V1BETA1_API_VERSION};
+use reddwarf_core::MeshPolicy;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, RwLock};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Port the mesh proxy accepts mTLS tunnels on
+pub const MESH_INBOUND_PORT: u16 = 15443;
+
+/// Port redirected pod traffic is delivered to
+pub const MESH_OUTBOUND_PORT: u16 = 15001;
+
+/// Longest accepted tunnel preamble (`<ip>:<port>\n`)
+const MAX_PREAMBLE_LEN: usize = 64;
+
+/// Configuration for the node mesh proxy
+#[derive(Debug, Clone)]
+pub struct MeshProxyConfig {
+    /// This node's name (also the name in its mesh certificate)
+    pub node_name: String,
+    /// Address accepting mTLS tunnels from peer nodes
+    pub inbound_addr: SocketAddr,
+    /// Address accepting redirected plaintext connections from local pods
+    pub outbound_addr: SocketAddr,
+    /// Address pod zones are redirected to; must reach `outbound_addr`
+    pub redirect_ip: IpAddr,
+    /// Port peer nodes accept tunnels on
+    pub peer_port: u16,
+    /// How often pods, nodes and mesh policies are re-read
+    pub refresh_interval: Duration,
+}
+
+impl MeshProxyConfig {
+    pub fn new(node_name: String, redirect_ip: IpAddr) -> Self {
+        Self {
+            node_name,
+            inbound_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), MESH_INBOUND_PORT),
+            outbound_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), MESH_OUTBOUND_PORT),
+            redirect_ip,
+            peer_port: MESH_INBOUND_PORT,
+            refresh_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+/// Per-node L4 proxy wrapping meshed pod traffic in mTLS
+///
+/// Outbound: connections from local pods to meshed ports are redirected
+/// (ipnat `rdr`) to the outbound listener, which tunnels them over mTLS to
+/// the destination pod's node. Inbound: tunnels from peers are authenticated
+/// against the cluster CA and delivered in plaintext to the local pod.
+pub struct MeshProxy {
+    api_client: Arc<ApiClient>,
+    identity: Arc<MeshIdentity>,
+    redirect: Arc<dyn TrafficRedirect>,
+    config: MeshProxyConfig,
+    directory: Arc<RwLock<MeshDirectory>>,
+    /// Redirect rules last installed per zone
+    installed: Mutex<HashMap<String, String>>,
+}
+
+impl MeshProxy {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        identity: MeshIdentity,
+        redirect: Arc<dyn TrafficRedirect>,
+        config: MeshProxyConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            identity: Arc::new(identity),
+            redirect,
+            config,
+            directory: Arc::new(RwLock::new(MeshDirectory::default())),
+            installed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Bind the configured listeners and serve until cancelled
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        let inbound = bind(self.config.inbound_addr).await?;
+        let outbound = bind(self.config.outbound_addr).await?;
+        info!(
+            "Starting mesh proxy for node {} (inbound: {}, outbound: {})",
+            self.config.node_name, self.config.inbound_addr, self.config.outbound_addr
+        );
+        self.serve(inbound, outbound, token).await
+    }
+
+    /// Serve on already-bound listeners until cancelled
+    pub async fn serve(
+        &self,
+        inbound: TcpListener,
+        outbound: TcpListener,
+        token: CancellationToken,
+    ) -> Result<()> {
+        let acceptor = TlsAcceptor::from(self.identity.server_config()?);
+        let connector = TlsConnector::from(self.identity.client_config()?);
+        let mut refresh = tokio::time::interval(self.config.refresh_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Mesh proxy shutting down");
+                    return Ok(());
+                }
+                _ = refresh.tick() => {
+                    if let Err(e) = self.refresh().await {
+                        error!("Mesh directory refresh failed: {}", e);
+                    }
+                }
+                accepted = inbound.accept() => {
+                    let (stream, peer) = match accepted {
+                        Ok(a) => a,
+                        Err(e) => {
+                            warn!("Mesh inbound accept failed: {}", e);
+                            continue;
+                        }
+                    };
+                    let acceptor = acceptor.clone();
+                    let directory = self.directory.clone();
+                    let node_name = self.config.node_name.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_inbound(acceptor, stream, directory, &node_name).await {
+                            debug!("Mesh tunnel from {} closed: {}", peer, e);
+                        }
+                    });
+                }
+                accepted = outbound.accept() => {
+                    let (stream, client) = match accepted {
+                        Ok(a) => a,
+                        Err(e) => {
+                            warn!("Mesh outbound accept failed: {}", e);
+                            continue;
+                        }
+                    };
+                    let connector = connector.clone();
+                    let directory = self.directory.clone();
+                    let redirect = self.redirect.clone();
+                    let peer_port = self.config.peer_port;
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_outbound(
+                            connector, stream, client, directory, redirect, peer_port,
+                        )
+                        .await
+                        {
+                            debug!("Mesh connection from {} closed: {}", client, e);
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Replace the routing snapshot (normally maintained by `refresh`)
+    pub async fn set_directory(&self, directory: MeshDirectory) {
+        *self.directory.write().await = directory;
+    }
+
+    /// Re-read pods, nodes and mesh policies, then update local redirects
+    async fn refresh(&self) -> Result<()> {
+        let pods: Vec<Pod> = self.list("/api/v1/pods").await?;
+        let nodes: Vec<Node> = self.list("/api/v1/nodes").await?;
+        let path = format!("/apis/{}/meshpolicies", MESH_V1BETA1_API_VERSION);
+        let policies: Vec<MeshPolicy> = self
+            .list::<serde_json::Value>(&path)
+            .await?
+            .into_iter()
+            .filter_map(|p| MeshPolicy::decode(MESH_V1BETA1_API_VERSION, p).ok())
+            .collect();
+        let directory = MeshDirectory::build(&pods, &nodes, &policies);
+
+        let redirect_target =
+            SocketAddr::new(self.config.redirect_ip, self.config.outbound_addr.port());
+        let desired: HashMap<String, String> = directory
+            .meshed_peers_on(&self.config.node_name)
+            .map(|p| {
+                (
+                    p.zone_name.clone(),
+                    redirect_rules(&p.vnic_name, &p.meshed_ports, redirect_target),
+                )
+            })
+            .collect();
+
+        *self.directory.write().await = directory;
+
+        let mut installed = self.installed.lock().await;
+        // Zones that are no longer meshed get an empty rule set
+        let stale: Vec<String> = installed
+            .keys()
+            .filter(|z| !desired.contains_key(*z))
+            .cloned()
+            .collect();
+        for zone in stale {
+            if self.redirect.install(&zone, "").await.is_ok() {
+                installed.remove(&zone);
+            }
+        }
+        for (zone, rules) in desired {
+            if installed.get(&zone) == Some(&rules) {
+                continue;
+            }
+            match self.redirect.install(&zone, &rules).await {
+                Ok(()) => {
+                    info!("Installed mesh redirects for zone {}", zone);
+                    installed.insert(zone, rules);
+                }
+                Err(e) => warn!("Failed to install mesh redirects for zone {}: {}", zone, e),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let body = self.api_client.get_json(path).await?;
+        Ok(body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect())
+    }
+}
+
+async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .map_err(|e| RuntimeError::mesh_error(format!("failed to bind {}: {}", addr, e)))
+}
+
+/// Terminate an mTLS tunnel from a peer node and deliver it to a local pod
+async fn handle_inbound(
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+    directory: Arc<RwLock<MeshDirectory>>,
+    node_name: &str,
+) -> Result<()> {
+    let mut tls = acceptor
+        .accept(stream)
+        .await
+        .map_err(|e| RuntimeError::mesh_error(format!("TLS handshake failed: {}", e)))?;
+
+    let dest = read_preamble(&mut tls).await?;
+    let allowed = {
+        let directory = directory.read().await;
+        directory.is_meshed(dest)
+            && directory
+                .peer(dest.ip())
+                .is_some_and(|p| p.node_name == node_name)
+    };
+    if !allowed {
+        return Err(RuntimeError::mesh_error(format!(
+            "{} is not a meshed destination on this node",
+            dest
+        )));
+    }
+
+    let mut upstream = TcpStream::connect(dest)
+        .await
+        .map_err(|e| RuntimeError::mesh_error(format!("connect {}: {}", dest, e)))?;
+    let _ = tokio::io::copy_bidirectional(&mut tls, &mut upstream).await;
+    Ok(())
+}
+
+/// Tunnel a redirected pod connection to the destination pod's node
+async fn handle_outbound(
+    connector: TlsConnector,
+    mut stream: TcpStream,
+    client: SocketAddr,
+    directory: Arc<RwLock<MeshDirectory>>,
+    redirect: Arc<dyn TrafficRedirect>,
+    peer_port: u16,
+) -> Result<()> {
+    let source_zone = directory
+        .read()
+        .await
+        .peer(client.ip())
+        .map(|p| p.zone_name.clone())
+        .ok_or_else(|| RuntimeError::mesh_error(format!("{} is not a known pod", client)))?;
+
+    let dest = redirect.original_destination(&source_zone, client).await?;
+
+    let (node_name, endpoint) = {
+        let directory = directory.read().await;
+        let node_name = directory
+            .peer(dest.ip())
+            .map(|p| p.node_name.clone())
+            .ok_or_else(|| RuntimeError::mesh_error(format!("{} is not a known pod", dest)))?;
+        let endpoint = directory
+            .node_endpoint(&node_name)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                RuntimeError::mesh_error(format!("no address for node {}", node_name))
+            })?;
+        (node_name, endpoint)
+    };
+
+    let tcp = TcpStream::connect((endpoint.as_str(), peer_port))
+        .await
+        .map_err(|e| {
+            RuntimeError::mesh_error(format!("connect {}:{}: {}", endpoint, peer_port, e))
+        })?;
+    let mut tls = connector
+        .connect(peer_server_name(&node_name)?, tcp)
+        .await
+        .map_err(|e| {
+            RuntimeError::mesh_error(format!("TLS handshake with {}: {}", node_name, e))
+        })?;
+
+    tls.write_all(format!("{}\n", dest).as_bytes())
+        .await
+        .map_err(|e| RuntimeError::mesh_error(format!("tunnel preamble: {}", e)))?;
+    let _ = tokio::io::copy_bidirectional(&mut stream, &mut tls).await;
+    Ok(())
+}
+
+/// Read the `<ip>:<port>\n` destination line that opens every tunnel
+async fn read_preamble<R: AsyncRead + Unpin>(reader: &mut R) -> Result<SocketAddr> {
+    let mut line = Vec::with_capacity(MAX_PREAMBLE_LEN);
+    loop {
+        let byte = reader
+            .read_u8()
+            .await
+            .map_err(|e| RuntimeError::mesh_error(format!("tunnel preamble: {}", e)))?;
+        if byte == b'\n' {
+            break;
+        }
+        if line.len() == MAX_PREAMBLE_LEN {
+            return Err(RuntimeError::mesh_error("tunnel preamble too long"));
+        }
+        line.push(byte);
+    }
+    std::str::from_utf8(&line)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| RuntimeError::mesh_error("malformed tunnel preamble"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::identity::tests::test_ca;
+    use async_trait::async_trait;
+    use k8s_openapi::api::core::v1::{NodeAddress, NodeStatus, PodSpec, PodStatus};
+    use reddwarf_core::MeshPolicySpec;
+    use tokio::net::TcpSocket;
+
+    /// Redirect that reports a fixed original destination
+    struct FixedRedirect(SocketAddr);
+
+    #[async_trait]
+    impl TrafficRedirect for FixedRedirect {
+        async fn install(&self, _zone_name: &str, _rules: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn original_destination(
+            &self,
+            _zone_name: &str,
+            _client: SocketAddr,
+        ) -> Result<SocketAddr> {
+            Ok(self.0)
+        }
+    }
+
+    fn make_pod(name: &str, node: &str, ip: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.spec = Some(PodSpec {
+            node_name: Some(node.to_string()),
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            pod_ip: Some(ip.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn make_node(name: &str) -> Node {
+        let mut node = Node::default();
+        node.metadata.name = Some(name.to_string());
+        node.status = Some(NodeStatus {
+            addresses: Some(vec![NodeAddress {
+                type_: "InternalIP".to_string(),
+                address: "127.0.0.1".to_string(),
+            }]),
+            ..Default::default()
+        });
+        node
+    }
+
+    #[tokio::test]
+    async fn test_read_preamble() {
+        let mut input: &[u8] = b"10.88.0.3:5432\nrest";
+        assert_eq!(
+            read_preamble(&mut input).await.unwrap(),
+            "10.88.0.3:5432".parse::<SocketAddr>().unwrap()
+        );
+
+        let mut garbage: &[u8] = b"not-an-address\n";
+        assert!(read_preamble(&mut garbage).await.is_err());
+
+        let long = vec![b'1'; MAX_PREAMBLE_LEN + 1];
+        assert!(read_preamble(&mut long.as_slice()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_between_two_nodes() {
+        // Pod "server" on node2 at 127.0.0.3 echoes back what it receives
+        let echo = TcpListener::bind("127.0.0.3:0").await.unwrap();
+        let echo_addr = echo.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut s, _) = echo.accept().await.unwrap();
+            let (mut r, mut w) = s.split();
+            let _ = tokio::io::copy(&mut r, &mut w).await;
+        });
+
+        let (ca_pem, ca_key) = test_ca();
+        let pods = vec![
+            make_pod("client", "node1", "127.0.0.2"),
+            make_pod("server", "node2", "127.0.0.3"),
+        ];
+        let nodes = vec![make_node("node1"), make_node("node2")];
+        let policies = vec![MeshPolicy::new(
+            "default",
+            "echo",
+            MeshPolicySpec {
+                ports: vec![echo_addr.port()],
+                ..Default::default()
+            },
+        )];
+
+        let node2_inbound = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let peer_port = node2_inbound.local_addr().unwrap().port();
+        let node1_outbound = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let outbound_addr = node1_outbound.local_addr().unwrap();
+
+        let token = CancellationToken::new();
+        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:1"));
+        for (node, inbound, outbound) in [
+            (
+                "node1",
+                TcpListener::bind("127.0.0.1:0").await.unwrap(),
+                node1_outbound,
+            ),
+            (
+                "node2",
+                node2_inbound,
+                TcpListener::bind("127.0.0.1:0").await.unwrap(),
+            ),
+        ] {
+            let mut config = MeshProxyConfig::new(node.to_string(), "127.0.0.1".parse().unwrap());
+            config.peer_port = peer_port;
+            config.refresh_interval = Duration::from_secs(3600);
+            let proxy = MeshProxy::new(
+                api_client.clone(),
+                MeshIdentity::issue(&ca_pem, &ca_key, node).unwrap(),
+                Arc::new(FixedRedirect(echo_addr)),
+                config,
+            );
+            proxy
+                .set_directory(MeshDirectory::build(&pods, &nodes, &policies))
+                .await;
+            let token = token.clone();
+            tokio::spawn(async move { proxy.serve(inbound, outbound, token).await });
+        }
+
+        // The client pod's connection arrives at node1's outbound listener
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.2:0".parse().unwrap()).unwrap();
+        let mut conn = socket.connect(outbound_addr).await.unwrap();
+        conn.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        tokio::time::timeout(Duration::from_secs(5), conn.read_exact(&mut buf))
+            .await
+            .expect("echo through tunnel timed out")
+            .unwrap();
+        assert_eq!(&buf, b"ping");
+
+        token.cancel();
+    }
+}
@@ -0,0 +1,148 @@
+use crate::command::exec;
+use crate::error::{Result, RuntimeError};
+use async_trait::async_trait;
+use std::net::{IpAddr, SocketAddr};
+
+/// Intercepts meshed traffic leaving pod zones and recovers where it was headed
+#[async_trait]
+pub trait TrafficRedirect: Send + Sync {
+    /// Install the redirect rules for a pod zone, replacing any previous ones
+    async fn install(&self, zone_name: &str, rules: &str) -> Result<()>;
+
+    /// Original destination of a redirected connection from `client`
+    /// (a pod address) inside `zone_name`
+    async fn original_destination(&self, zone_name: &str, client: SocketAddr)
+        -> Result<SocketAddr>;
+}
+
+/// ipnat `rdr` rules sending the zone's outbound connections to meshed ports
+/// through the node's outbound proxy listener
+pub fn redirect_rules(vnic_name: &str, ports: &[u16], proxy: SocketAddr) -> String {
+    ports
+        .iter()
+        .map(|port| {
+            format!(
+                "rdr {} 0.0.0.0/0 port {} -> {} port {} tcp\n",
+                vnic_name,
+                port,
+                proxy.ip(),
+                proxy.port()
+            )
+        })
+        .collect()
+}
+
+/// An active redirect session as listed by `ipnat -l`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NatSession {
+    pub client: SocketAddr,
+    pub original_destination: SocketAddr,
+}
+
+/// Parse the `RDR` session lines of `ipnat -l` output, e.g.
+///
+/// `RDR 10.88.0.1 15001 <- -> 10.88.0.9 8080 [10.88.0.5 40112]`
+pub fn parse_ipnat_sessions(output: &str) -> Vec<NatSession> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line
+                .split_whitespace()
+                .map(|f| f.trim_matches(|c| c == '[' || c == ']'))
+                .filter(|f| !f.is_empty())
+                .collect();
+            match fields.as_slice() {
+                ["RDR", _, _, "<-", "->", dst_ip, dst_port, src_ip, src_port, ..] => {
+                    Some(NatSession {
+                        client: socket_addr(src_ip, src_port)?,
+                        original_destination: socket_addr(dst_ip, dst_port)?,
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn socket_addr(ip: &str, port: &str) -> Option<SocketAddr> {
+    Some(SocketAddr::new(
+        ip.parse::<IpAddr>().ok()?,
+        port.parse().ok()?,
+    ))
+}
+
+/// Redirect via global-zone controlled ipfilter NAT (`ipnat -G <zone>`)
+pub struct IpnatRedirect;
+
+#[async_trait]
+impl TrafficRedirect for IpnatRedirect {
+    async fn install(&self, zone_name: &str, rules: &str) -> Result<()> {
+        let dir = std::env::temp_dir().join("reddwarf-mesh");
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| RuntimeError::mesh_error(format!("{}: {}", dir.display(), e)))?;
+        let path = dir.join(format!("{}.ipnat", zone_name));
+        tokio::fs::write(&path, rules)
+            .await
+            .map_err(|e| RuntimeError::mesh_error(format!("{}: {}", path.display(), e)))?;
+
+        let path = path.to_string_lossy();
+        // Flush the zone's NAT rules, then load the new set
+        exec("ipnat", &["-G", zone_name, "-C"]).await?;
+        exec("ipnat", &["-G", zone_name, "-f", &path]).await?;
+        Ok(())
+    }
+
+    async fn original_destination(
+        &self,
+        zone_name: &str,
+        client: SocketAddr,
+    ) -> Result<SocketAddr> {
+        let output = exec("ipnat", &["-G", zone_name, "-l"]).await?;
+        parse_ipnat_sessions(&output.stdout)
+            .into_iter()
+            .find(|s| s.client == client)
+            .map(|s| s.original_destination)
+            .ok_or_else(|| {
+                RuntimeError::mesh_error(format!(
+                    "no redirect session for {} in zone {}",
+                    client, zone_name
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ipnat_sessions() {
+        let output = "\
+List of active MAP/Redirect filters:
+rdr vnic_default_web 0.0.0.0/0 port 8080 -> 10.88.0.1 port 15001 tcp
+
+List of active sessions:
+RDR 10.88.0.1       15001 <- -> 10.88.0.9       8080  [10.88.0.5 40112]
+MAP 10.88.0.5       40000 <- -> 192.168.1.10    40000 [8.8.8.8 53]
+";
+        let sessions = parse_ipnat_sessions(output);
+        assert_eq!(
+            sessions,
+            vec![NatSession {
+                client: "10.88.0.5:40112".parse().unwrap(),
+                original_destination: "10.88.0.9:8080".parse().unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_redirect_rules() {
+        let rules = redirect_rules("vnic0", &[80, 443], "10.88.0.1:15001".parse().unwrap());
+        assert_eq!(
+            rules,
+            "rdr vnic0 0.0.0.0/0 port 80 -> 10.88.0.1 port 15001 tcp\n\
+             rdr vnic0 0.0.0.0/0 port 443 -> 10.88.0.1 port 15001 tcp\n"
+        );
+    }
+}
@@ -1,18 +1,21 @@
 use crate::command::CommandOutput;
 use crate::error::{Result, RuntimeError};
 use crate::storage::StorageEngine;
+use crate::svid::Svid;
 use crate::traits::ZoneRuntime;
 use crate::types::*;
 use async_trait::async_trait;
+use reddwarf_core::{ProcessSession, SessionInput, SessionOutput, SessionRequest};
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::debug;
 
 /// In-memory zone state for MockRuntime
 #[derive(Debug, Clone)]
 struct MockZone {
-    config: ZoneConfig,
+    brand: ZoneBrand,
+    zonepath: String,
     state: ZoneState,
     zone_id: Option<i32>,
 }
@@ -21,12 +24,19 @@ struct MockZone {
 ///
 /// Maintains an in-memory zone registry and simulates state transitions.
 /// All network operations are no-ops. Storage operations are delegated to
-/// the injected `StorageEngine`.
+/// the injected `StorageEngine`. Boots and halts emit synthetic zone events,
+/// and [`MockRuntime::crash_zone`] simulates a zone panic.
 pub struct MockRuntime {
     zones: Arc<RwLock<HashMap<String, MockZone>>>,
     next_id: Arc<RwLock<i32>>,
     storage: Arc<dyn StorageEngine>,
     exec_results: Arc<RwLock<HashMap<String, VecDeque<CommandOutput>>>>,
+    link_bandwidth: Arc<RwLock<HashMap<String, u64>>>,
+    port_forwards: Arc<RwLock<HashMap<String, Vec<PortForward>>>>,
+    resolv_confs: Arc<RwLock<HashMap<String, DnsConfig>>>,
+    svids: Arc<RwLock<HashMap<String, Svid>>>,
+    exits: Arc<RwLock<HashMap<String, ZoneExit>>>,
+    events: broadcast::Sender<ZoneEvent>,
 }
 
 impl MockRuntime {
@@ -36,9 +46,79 @@ impl MockRuntime {
             next_id: Arc::new(RwLock::new(1)),
             storage,
             exec_results: Arc::new(RwLock::new(HashMap::new())),
+            link_bandwidth: Arc::new(RwLock::new(HashMap::new())),
+            port_forwards: Arc::new(RwLock::new(HashMap::new())),
+            resolv_confs: Arc::new(RwLock::new(HashMap::new())),
+            svids: Arc::new(RwLock::new(HashMap::new())),
+            exits: Arc::new(RwLock::new(HashMap::new())),
+            events: broadcast::channel(64).0,
         }
     }
 
+    fn emit(&self, zone_name: &str, kind: ZoneEventKind) {
+        // Nobody listening is fine
+        let _ = self.events.send(ZoneEvent {
+            zone_name: zone_name.to_string(),
+            kind,
+        });
+    }
+
+    /// Take a running zone down as if it had panicked
+    pub async fn crash_zone(&self, zone_name: &str) -> Result<()> {
+        let mut zones = self.zones.write().await;
+        let zone = zones
+            .get_mut(zone_name)
+            .ok_or_else(|| RuntimeError::zone_not_found(zone_name))?;
+
+        if zone.state != ZoneState::Running {
+            return Err(RuntimeError::invalid_state_transition(
+                zone_name,
+                zone.state.to_string(),
+                "installed",
+                "running",
+            ));
+        }
+
+        zone.state = ZoneState::Installed;
+        zone.zone_id = None;
+        debug!("Mock: zone crashed: {}", zone_name);
+        self.emit(zone_name, ZoneEventKind::Panicked);
+        Ok(())
+    }
+
+    /// Take a running zone down as if its main process had exited with
+    /// `exit`, which [`ZoneRuntime::zone_exit`] then reports
+    pub async fn exit_zone(&self, zone_name: &str, exit: ZoneExit) -> Result<()> {
+        self.crash_zone(zone_name).await?;
+        self.exits.write().await.insert(zone_name.to_string(), exit);
+        Ok(())
+    }
+
+    /// The `maxbw` currently applied to a VNIC, if any
+    pub async fn link_bandwidth(&self, vnic_name: &str) -> Option<u64> {
+        self.link_bandwidth.read().await.get(vnic_name).copied()
+    }
+
+    /// Host port forwards currently installed for a zone
+    pub async fn port_forwards(&self, zone_name: &str) -> Vec<PortForward> {
+        self.port_forwards
+            .read()
+            .await
+            .get(zone_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The resolver configuration last written to a zone, if any
+    pub async fn resolv_conf(&self, zone_name: &str) -> Option<DnsConfig> {
+        self.resolv_confs.read().await.get(zone_name).cloned()
+    }
+
+    /// The SVID last written into a zone
+    pub async fn svid(&self, zone_name: &str) -> Option<Svid> {
+        self.svids.read().await.get(zone_name).cloned()
+    }
+
     /// Queue a custom exec result for a specific zone.
     /// Results are consumed in FIFO order. Once exhausted, falls back to defaults.
     pub async fn set_exec_result(&self, zone_name: &str, output: CommandOutput) {
@@ -60,7 +140,8 @@ impl ZoneRuntime for MockRuntime {
         zones.insert(
             config.zone_name.clone(),
             MockZone {
-                config: config.clone(),
+                brand: config.brand.clone(),
+                zonepath: config.zonepath.clone(),
                 state: ZoneState::Configured,
                 zone_id: None,
             },
@@ -108,7 +189,9 @@ impl ZoneRuntime for MockRuntime {
         zone.zone_id = Some(*next_id);
         *next_id += 1;
         zone.state = ZoneState::Running;
+        self.exits.write().await.remove(zone_name);
         debug!("Mock: zone booted: {}", zone_name);
+        self.emit(zone_name, ZoneEventKind::Booted);
         Ok(())
     }
 
@@ -130,6 +213,7 @@ impl ZoneRuntime for MockRuntime {
         zone.state = ZoneState::Installed;
         zone.zone_id = None;
         debug!("Mock: zone shut down: {}", zone_name);
+        self.emit(zone_name, ZoneEventKind::Halted);
         Ok(())
     }
 
@@ -151,6 +235,7 @@ impl ZoneRuntime for MockRuntime {
         zone.state = ZoneState::Installed;
         zone.zone_id = None;
         debug!("Mock: zone halted: {}", zone_name);
+        self.emit(zone_name, ZoneEventKind::Halted);
         Ok(())
     }
 
@@ -228,6 +313,43 @@ impl ZoneRuntime for MockRuntime {
         }
     }
 
+    async fn open_session(
+        &self,
+        zone_name: &str,
+        _request: &SessionRequest,
+    ) -> Result<ProcessSession> {
+        let zones = self.zones.read().await;
+        let zone = zones
+            .get(zone_name)
+            .ok_or_else(|| RuntimeError::zone_not_found(zone_name))?;
+        if zone.state != ZoneState::Running {
+            return Err(RuntimeError::zone_operation_failed(
+                zone_name,
+                format!(
+                    "Cannot open a session in zone: zone is in state {} (expected Running)",
+                    zone.state
+                ),
+            ));
+        }
+
+        // The mock process echoes its input and exits when it ends
+        let (session, mut end) = ProcessSession::pair();
+        tokio::spawn(async move {
+            while let Some(input) = end.input.recv().await {
+                let output = match input {
+                    SessionInput::Stdin(data) => SessionOutput::Stdout(data),
+                    SessionInput::CloseStdin => break,
+                    SessionInput::Resize(_) => continue,
+                };
+                if end.output.send(output).await.is_err() {
+                    return;
+                }
+            }
+            let _ = end.output.send(SessionOutput::Exit(0)).await;
+        });
+        Ok(session)
+    }
+
     async fn get_zone_state(&self, zone_name: &str) -> Result<ZoneState> {
         let zones = self.zones.read().await;
         let zone = zones
@@ -246,12 +368,20 @@ impl ZoneRuntime for MockRuntime {
             zone_name: zone_name.to_string(),
             zone_id: zone.zone_id,
             state: zone.state.clone(),
-            zonepath: zone.config.zonepath.clone(),
-            brand: zone.config.brand.to_string(),
+            zonepath: zone.zonepath.clone(),
+            brand: zone.brand.to_string(),
             uuid: String::new(),
         })
     }
 
+    fn subscribe_events(&self) -> broadcast::Receiver<ZoneEvent> {
+        self.events.subscribe()
+    }
+
+    async fn zone_exit(&self, zone_name: &str) -> Result<Option<ZoneExit>> {
+        Ok(self.exits.read().await.get(zone_name).copied())
+    }
+
     async fn list_zones(&self) -> Result<Vec<ZoneInfo>> {
         let zones = self.zones.read().await;
         let mut infos = Vec::new();
@@ -261,8 +391,8 @@ impl ZoneRuntime for MockRuntime {
                 zone_name: name.clone(),
                 zone_id: zone.zone_id,
                 state: zone.state.clone(),
-                zonepath: zone.config.zonepath.clone(),
-                brand: zone.config.brand.to_string(),
+                zonepath: zone.zonepath.clone(),
+                brand: zone.brand.to_string(),
                 uuid: String::new(),
             });
         }
@@ -280,14 +410,79 @@ impl ZoneRuntime for MockRuntime {
         Ok(())
     }
 
+    async fn set_link_bandwidth(&self, vnic_name: &str, maxbw_bps: Option<u64>) -> Result<()> {
+        debug!("Mock: maxbw for {}: {:?}", vnic_name, maxbw_bps);
+        let mut links = self.link_bandwidth.write().await;
+        match maxbw_bps {
+            Some(bps) => links.insert(vnic_name.to_string(), bps),
+            None => links.remove(vnic_name),
+        };
+        Ok(())
+    }
+
+    async fn set_port_forwards(&self, zone_name: &str, forwards: &[PortForward]) -> Result<()> {
+        debug!("Mock: {} port forward(s) for {}", forwards.len(), zone_name);
+        let mut all = self.port_forwards.write().await;
+        if forwards.is_empty() {
+            all.remove(zone_name);
+        } else {
+            all.insert(zone_name.to_string(), forwards.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn set_resolv_conf(
+        &self,
+        zone_name: &str,
+        _zonepath: &str,
+        dns: &DnsConfig,
+    ) -> Result<()> {
+        debug!("Mock: resolv.conf for {}: {:?}", zone_name, dns);
+        self.resolv_confs
+            .write()
+            .await
+            .insert(zone_name.to_string(), dns.clone());
+        Ok(())
+    }
+
+    async fn write_svid(&self, zone_name: &str, _zonepath: &str, svid: &Svid) -> Result<()> {
+        debug!("Mock: SVID for {}: {}", zone_name, svid.spiffe_id);
+        self.svids
+            .write()
+            .await
+            .insert(zone_name.to_string(), svid.clone());
+        Ok(())
+    }
+
     async fn provision(&self, config: &ZoneConfig) -> Result<()> {
+        let image = match config.brand {
+            ZoneBrand::Lx => config.lx_image_path.as_deref(),
+            ZoneBrand::Reddwarf => None,
+        };
+        let cached_image = match image {
+            Some(image) => self.storage.image_snapshot(image).await?,
+            None => None,
+        };
+        let mut storage_opts = config.storage.clone();
+        if cached_image.is_some() {
+            storage_opts.clone_from = cached_image.clone();
+        }
+
         self.storage
-            .create_zone_dataset(&config.zone_name, &config.storage)
+            .create_zone_dataset(&config.zone_name, &storage_opts)
             .await?;
         self.setup_network(&config.zone_name, &config.network)
             .await?;
         self.create_zone(config).await?;
+        // Stands in for both install and attaching a cloned root
         self.install_zone(&config.zone_name).await?;
+        if let (Some(image), None) = (image, &cached_image) {
+            self.storage.cache_image(image, &config.zone_name).await?;
+        }
+        if let Some(dns) = &config.dns {
+            self.set_resolv_conf(&config.zone_name, &config.zonepath, dns)
+                .await?;
+        }
         self.boot_zone(&config.zone_name).await?;
         Ok(())
     }
@@ -328,6 +523,84 @@ impl ZoneRuntime for MockRuntime {
         self.storage.destroy_zone_dataset(&config.zone_name).await?;
         Ok(())
     }
+
+    async fn prepare_warm_zone(&self, zone: &WarmZone) -> Result<()> {
+        self.storage
+            .create_zone_dataset(&zone.zone_name, &ZoneStorageOpts::default())
+            .await?;
+        {
+            let mut zones = self.zones.write().await;
+            if zones.contains_key(&zone.zone_name) {
+                return Err(RuntimeError::zone_already_exists(&zone.zone_name));
+            }
+            zones.insert(
+                zone.zone_name.clone(),
+                MockZone {
+                    brand: zone.brand.clone(),
+                    zonepath: zone.zonepath.clone(),
+                    state: ZoneState::Configured,
+                    zone_id: None,
+                },
+            );
+        }
+        self.install_zone(&zone.zone_name).await?;
+        debug!("Mock: warm zone prepared: {}", zone.zone_name);
+        Ok(())
+    }
+
+    async fn claim_warm_zone(&self, warm_zone_name: &str, config: &ZoneConfig) -> Result<()> {
+        {
+            let mut zones = self.zones.write().await;
+            let zone = zones
+                .get(warm_zone_name)
+                .ok_or_else(|| RuntimeError::zone_not_found(warm_zone_name))?;
+            if zone.state != ZoneState::Installed {
+                return Err(RuntimeError::invalid_state_transition(
+                    warm_zone_name,
+                    zone.state.to_string(),
+                    "claimed",
+                    "installed",
+                ));
+            }
+            if zones.contains_key(&config.zone_name) {
+                return Err(RuntimeError::zone_already_exists(&config.zone_name));
+            }
+            let mut zone = zones.remove(warm_zone_name).unwrap();
+            zone.zonepath = config.zonepath.clone();
+            zones.insert(config.zone_name.clone(), zone);
+        }
+        // The dataset follows the zonepath to the pod's zone name
+        self.storage.destroy_zone_dataset(warm_zone_name).await?;
+        self.storage
+            .create_zone_dataset(&config.zone_name, &config.storage)
+            .await?;
+
+        self.setup_network(&config.zone_name, &config.network)
+            .await?;
+        if let Some(dns) = &config.dns {
+            self.set_resolv_conf(&config.zone_name, &config.zonepath, dns)
+                .await?;
+        }
+        self.boot_zone(&config.zone_name).await?;
+        debug!(
+            "Mock: warm zone {} claimed as {}",
+            warm_zone_name, config.zone_name
+        );
+        Ok(())
+    }
+
+    async fn destroy_warm_zone(&self, zone_name: &str) -> Result<()> {
+        let state = self.get_zone_state(zone_name).await?;
+        if state == ZoneState::Running {
+            self.halt_zone(zone_name).await?;
+        }
+        if state != ZoneState::Configured {
+            self.uninstall_zone(zone_name).await?;
+        }
+        self.delete_zone(zone_name).await?;
+        self.storage.destroy_zone_dataset(zone_name).await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -359,7 +632,19 @@ mod tests {
             processes: vec![],
             cpu_cap: None,
             memory_cap: None,
+            swap_cap: None,
+            cpu_shares: None,
+            dedicated_cpus: None,
+            max_lwps: None,
+            max_processes: None,
             fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
         }
     }
 
@@ -376,6 +661,36 @@ mod tests {
         assert_eq!(output.exit_code, 0);
     }
 
+    #[tokio::test]
+    async fn test_open_session_echoes_stdin() {
+        let rt = MockRuntime::new(make_test_storage());
+        let config = make_test_config("session-zone");
+        let request = SessionRequest {
+            container: "shell".to_string(),
+            target: reddwarf_core::SessionTarget::Attach,
+            stdin: true,
+            tty: true,
+        };
+        assert!(rt.open_session("session-zone", &request).await.is_err());
+
+        rt.provision(&config).await.unwrap();
+        let mut session = rt.open_session("session-zone", &request).await.unwrap();
+        session
+            .input
+            .send(SessionInput::Stdin(b"ls\n".to_vec()))
+            .await
+            .unwrap();
+        session.input.send(SessionInput::CloseStdin).await.unwrap();
+        assert!(matches!(
+            session.output.recv().await,
+            Some(SessionOutput::Stdout(data)) if data == b"ls\n"
+        ));
+        assert!(matches!(
+            session.output.recv().await,
+            Some(SessionOutput::Exit(0))
+        ));
+    }
+
     #[tokio::test]
     async fn test_exec_in_zone_not_running_errors() {
         let rt = MockRuntime::new(make_test_storage());
@@ -445,6 +760,31 @@ mod tests {
         assert_eq!(state, ZoneState::Running);
     }
 
+    #[tokio::test]
+    async fn test_provision_clones_cached_image() {
+        let storage = Arc::new(MockStorageEngine::new(StoragePoolConfig::from_pool(
+            "rpool",
+        )));
+        let rt = MockRuntime::new(storage.clone());
+
+        for name in ["lx-a", "lx-b"] {
+            let mut config = make_test_config(name);
+            config.brand = ZoneBrand::Lx;
+            config.lx_image_path = Some("/images/alpine.tar.gz".to_string());
+            rt.provision(&config).await.unwrap();
+        }
+
+        let snapshot = "rpool/images/images-alpine.tar.gz@base";
+        assert_eq!(
+            storage.origin("rpool/zones/lx-a").await.as_deref(),
+            Some(snapshot)
+        );
+        assert_eq!(
+            storage.origin("rpool/zones/lx-b").await.as_deref(),
+            Some(snapshot)
+        );
+    }
+
     #[tokio::test]
     async fn test_deprovision_removes_zone() {
         let rt = MockRuntime::new(make_test_storage());
@@ -515,4 +855,51 @@ mod tests {
         assert_eq!(info.brand, "reddwarf");
         assert!(info.zone_id.is_some());
     }
+
+    #[tokio::test]
+    async fn test_zone_events() {
+        let rt = MockRuntime::new(make_test_storage());
+        let mut events = rt.subscribe_events();
+        rt.provision(&make_test_config("event-zone")).await.unwrap();
+        rt.halt_zone("event-zone").await.unwrap();
+        rt.boot_zone("event-zone").await.unwrap();
+        rt.crash_zone("event-zone").await.unwrap();
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            assert_eq!(event.zone_name, "event-zone");
+            kinds.push(event.kind);
+        }
+        assert_eq!(
+            kinds,
+            vec![
+                ZoneEventKind::Booted,
+                ZoneEventKind::Halted,
+                ZoneEventKind::Booted,
+                ZoneEventKind::Panicked,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zone_exit() {
+        let rt = MockRuntime::new(make_test_storage());
+        rt.provision(&make_test_config("exit-zone")).await.unwrap();
+        assert_eq!(rt.zone_exit("exit-zone").await.unwrap(), None);
+
+        let exit = ZoneExit {
+            exit_code: 137,
+            oom_killed: true,
+        };
+        rt.exit_zone("exit-zone", exit).await.unwrap();
+        assert_eq!(
+            rt.get_zone_state("exit-zone").await.unwrap(),
+            ZoneState::Installed
+        );
+        assert_eq!(rt.zone_exit("exit-zone").await.unwrap(), Some(exit));
+
+        // A new boot forgets the previous exit
+        rt.boot_zone("exit-zone").await.unwrap();
+        assert_eq!(rt.zone_exit("exit-zone").await.unwrap(), None);
+    }
 }
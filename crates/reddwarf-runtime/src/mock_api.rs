@@ -0,0 +1,234 @@
+//! In-process stand-in for the API server, for tests of the controllers and
+//! agents that talk to it through an [`ApiClient`]
+//!
+//! Requests are answered from programmed responses, matched by method and
+//! path, and recorded so tests can assert what was sent. A request nothing
+//! was programmed for gets a `404 NotFound` Status, as from an API server
+//! without the object.
+//!
+//! Built for this crate's tests, and with the `test-util` feature for those
+//! of the crates built on it.
+
+use crate::api_client::ApiClient;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// A request the mock API server received
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query, as sent
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// The body parsed as JSON, or `Null` without one
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+}
+
+/// A programmed response
+struct MockResponse {
+    method: String,
+    path: String,
+    status: u16,
+    body: serde_json::Value,
+}
+
+impl MockResponse {
+    /// Whether this answers `method` on `path`; without a query of its own
+    /// it answers the path whatever the query
+    fn matches(&self, method: &str, path: &str) -> bool {
+        let path = if self.path.contains('?') {
+            path
+        } else {
+            path.split('?').next().unwrap_or_default()
+        };
+        self.method.eq_ignore_ascii_case(method) && self.path == path
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    responses: Vec<MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// Mock API server listening on a loopback port until dropped
+pub struct MockApiServer {
+    addr: SocketAddr,
+    shared: Arc<Mutex<Shared>>,
+    task: JoinHandle<()>,
+}
+
+impl MockApiServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+
+        let serving = shared.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, serving.clone()));
+            }
+        });
+
+        Self { addr, shared, task }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client of this server
+    pub fn client(&self) -> Arc<ApiClient> {
+        Arc::new(ApiClient::new(&self.url()))
+    }
+
+    /// Answer `method` on `path` with `status` and the JSON `body`, in place
+    /// of any earlier response to the same
+    pub fn respond(&self, method: &str, path: &str, status: u16, body: serde_json::Value) {
+        self.shared.lock().unwrap().responses.push(MockResponse {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            body,
+        });
+    }
+
+    /// Every request received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.shared.lock().unwrap().requests.clone()
+    }
+
+    /// The requests received so far with `method` on `path`, whatever their
+    /// query
+    pub fn requests_to(&self, method: &str, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.method.eq_ignore_ascii_case(method))
+            .filter(|r| r.path.split('?').next() == Some(path))
+            .collect()
+    }
+}
+
+impl Drop for MockApiServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Read one request from `stream`, record it and answer it, closing the
+/// connection after
+async fn serve(stream: TcpStream, shared: Arc<Mutex<Shared>>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+        return;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).await.is_err() {
+        return;
+    }
+
+    let (status, response) = {
+        let mut shared = shared.lock().unwrap();
+        let response = shared
+            .responses
+            .iter()
+            .rev()
+            .find(|r| r.matches(&method, &path))
+            .map(|r| (r.status, r.body.clone()));
+        shared.requests.push(RecordedRequest { method, path, body });
+        response.unwrap_or_else(|| {
+            let not_found = serde_json::json!({
+                "kind": "Status",
+                "apiVersion": "v1",
+                "status": "Failure",
+                "reason": "NotFound",
+                "code": 404
+            });
+            (404, not_found)
+        })
+    };
+
+    let response = response.to_string();
+    let reason = reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        response.len()
+    );
+    let mut stream = reader.into_inner();
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_core::ErrorClass;
+
+    #[tokio::test]
+    async fn test_programmed_responses_and_capture() {
+        let server = MockApiServer::start().await;
+        let client = server.client();
+
+        server.respond(
+            "GET",
+            "/api/v1/nodes/n1",
+            200,
+            serde_json::json!({"metadata": {"name": "n1"}}),
+        );
+        let node = client.get_node("n1").await.unwrap();
+        assert_eq!(node.metadata.name.as_deref(), Some("n1"));
+
+        // Unprogrammed requests are not found; later responses win
+        let err = client.get_pod("default", "web").await.unwrap_err();
+        assert!(err.is_not_found());
+        server.respond("GET", "/api/v1/nodes/n1", 503, serde_json::json!({}));
+        assert!(client.get_node("n1").await.is_err());
+
+        let status = serde_json::json!({"phase": "Running"});
+        let pod = serde_json::from_value(serde_json::json!({"status": status})).unwrap();
+        let _ = client.update_pod_status("default", "web", &pod).await;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[1].path, "/api/v1/namespaces/default/pods/web");
+        let updates = server.requests_to("PUT", "/api/v1/namespaces/default/pods/web/status");
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].json()["status"], status);
+    }
+}
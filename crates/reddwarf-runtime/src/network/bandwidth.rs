@@ -0,0 +1,129 @@
+use k8s_openapi::api::core::v1::Pod;
+use reddwarf_core::annotations::{
+    parse_bandwidth, EGRESS_BANDWIDTH_ANNOTATION, INGRESS_BANDWIDTH_ANNOTATION,
+    STATUS_EGRESS_BANDWIDTH_ANNOTATION, STATUS_INGRESS_BANDWIDTH_ANNOTATION,
+    STATUS_MAXBW_ANNOTATION,
+};
+use std::collections::BTreeMap;
+
+/// Render bits per second as a dladm `maxbw` value.
+///
+/// dladm interprets bare numbers as Mbps, so always emit an explicit unit,
+/// rounding sub-kilobit remainders up.
+pub fn format_maxbw(bps: u64) -> String {
+    if bps % 1_000_000_000 == 0 {
+        format!("{}G", bps / 1_000_000_000)
+    } else if bps % 1_000_000 == 0 {
+        format!("{}M", bps / 1_000_000)
+    } else {
+        format!("{}K", bps.div_ceil(1_000))
+    }
+}
+
+/// Per-pod bandwidth limits in bits per second
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+    pub ingress_bps: Option<u64>,
+    pub egress_bps: Option<u64>,
+}
+
+impl BandwidthLimits {
+    /// Resolve limits for a pod. Pod annotations override the defaults
+    /// declared on its namespace with the same annotation keys.
+    pub fn for_pod(pod: &Pod, namespace_annotations: Option<&BTreeMap<String, String>>) -> Self {
+        let pod_annotations = pod.metadata.annotations.as_ref();
+        let lookup = |key: &str| {
+            pod_annotations
+                .and_then(|a| a.get(key))
+                .or_else(|| namespace_annotations.and_then(|a| a.get(key)))
+                .and_then(|v| parse_bandwidth(v))
+        };
+
+        Self {
+            ingress_bps: lookup(INGRESS_BANDWIDTH_ANNOTATION),
+            egress_bps: lookup(EGRESS_BANDWIDTH_ANNOTATION),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ingress_bps.is_none() && self.egress_bps.is_none()
+    }
+
+    /// Value for the VNIC's `maxbw` property.
+    ///
+    /// `maxbw` caps the link as a whole rather than each direction, so the
+    /// stricter of the two limits is applied.
+    pub fn maxbw_bps(&self) -> Option<u64> {
+        match (self.ingress_bps, self.egress_bps) {
+            (Some(i), Some(e)) => Some(i.min(e)),
+            (i, e) => i.or(e),
+        }
+    }
+
+    /// Status annotations reporting the limits that were applied
+    pub fn status_annotations(&self) -> BTreeMap<String, String> {
+        let mut annotations = BTreeMap::new();
+        if let Some(bps) = self.ingress_bps {
+            annotations.insert(
+                STATUS_INGRESS_BANDWIDTH_ANNOTATION.to_string(),
+                bps.to_string(),
+            );
+        }
+        if let Some(bps) = self.egress_bps {
+            annotations.insert(
+                STATUS_EGRESS_BANDWIDTH_ANNOTATION.to_string(),
+                bps.to_string(),
+            );
+        }
+        if let Some(bps) = self.maxbw_bps() {
+            annotations.insert(STATUS_MAXBW_ANNOTATION.to_string(), format_maxbw(bps));
+        }
+        annotations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotations(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_format_maxbw() {
+        assert_eq!(format_maxbw(2_000_000_000), "2G");
+        assert_eq!(format_maxbw(10_000_000), "10M");
+        assert_eq!(format_maxbw(1_048_576), "1049K");
+    }
+
+    #[test]
+    fn test_pod_annotation_overrides_namespace_default() {
+        let mut pod = Pod::default();
+        pod.metadata.annotations = Some(annotations(&[(INGRESS_BANDWIDTH_ANNOTATION, "5M")]));
+        let ns = annotations(&[
+            (INGRESS_BANDWIDTH_ANNOTATION, "100M"),
+            (EGRESS_BANDWIDTH_ANNOTATION, "20M"),
+        ]);
+
+        let limits = BandwidthLimits::for_pod(&pod, Some(&ns));
+        assert_eq!(limits.ingress_bps, Some(5_000_000));
+        assert_eq!(limits.egress_bps, Some(20_000_000));
+        assert_eq!(limits.maxbw_bps(), Some(5_000_000));
+
+        let status = limits.status_annotations();
+        assert_eq!(status[STATUS_MAXBW_ANNOTATION], "5M");
+        assert_eq!(status[STATUS_EGRESS_BANDWIDTH_ANNOTATION], "20000000");
+    }
+
+    #[test]
+    fn test_no_annotations_means_no_limits() {
+        let limits = BandwidthLimits::for_pod(&Pod::default(), None);
+        assert!(limits.is_empty());
+        assert_eq!(limits.maxbw_bps(), None);
+        assert!(limits.status_annotations().is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::types::DnsConfig;
+use k8s_openapi::api::core::v1::Pod;
+use tracing::warn;
+
+/// Cluster domain used when none is configured
+pub const DEFAULT_CLUSTER_DOMAIN: &str = "cluster.local";
+
+/// The resolver only reads the first three nameservers
+const MAX_NAMESERVERS: usize = 3;
+const MAX_SEARCHES: usize = 32;
+
+/// Whether a pod resolves through the cluster DNS service (`ClusterFirst`,
+/// the default, or `ClusterFirstWithHostNet`)
+pub fn uses_cluster_dns(pod: &Pod) -> bool {
+    let policy = pod.spec.as_ref().and_then(|s| s.dns_policy.as_deref());
+    matches!(
+        policy,
+        None | Some("ClusterFirst") | Some("ClusterFirstWithHostNet")
+    )
+}
+
+/// Resolver configuration for a pod from its `dnsPolicy` and `dnsConfig`
+///
+/// - `ClusterFirst` uses `cluster_dns` with the pod's namespace search path,
+///   falling back to the node's resolver when no cluster DNS is known
+/// - `Default` uses the node's resolver (`host`)
+/// - `None` uses only the pod's `dnsConfig`
+///
+/// `dnsConfig` is merged on top in every case; its options replace options
+/// of the same name.
+pub fn pod_dns_config(
+    pod: &Pod,
+    cluster_dns: Option<&str>,
+    cluster_domain: &str,
+    host: &DnsConfig,
+) -> DnsConfig {
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+    let spec = pod.spec.as_ref();
+
+    let mut config = match spec.and_then(|s| s.dns_policy.as_deref()) {
+        Some("None") => DnsConfig::default(),
+        Some("Default") => host.clone(),
+        _ => match cluster_dns {
+            Some(server) => DnsConfig {
+                nameservers: vec![server.to_string()],
+                searches: [
+                    format!("{}.svc.{}", namespace, cluster_domain),
+                    format!("svc.{}", cluster_domain),
+                    cluster_domain.to_string(),
+                ]
+                .into_iter()
+                .chain(host.searches.iter().cloned())
+                .collect(),
+                options: vec!["ndots:5".to_string()],
+            },
+            None => host.clone(),
+        },
+    };
+
+    if let Some(custom) = spec.and_then(|s| s.dns_config.as_ref()) {
+        for ns in custom.nameservers.iter().flatten() {
+            if !config.nameservers.contains(ns) {
+                config.nameservers.push(ns.clone());
+            }
+        }
+        for search in custom.searches.iter().flatten() {
+            if !config.searches.contains(search) {
+                config.searches.push(search.clone());
+            }
+        }
+        for option in custom.options.iter().flatten() {
+            let Some(name) = option.name.as_deref() else {
+                continue;
+            };
+            config.options.retain(|o| o.split(':').next() != Some(name));
+            config.options.push(match option.value.as_deref() {
+                Some(value) => format!("{}:{}", name, value),
+                None => name.to_string(),
+            });
+        }
+    }
+
+    if config.nameservers.len() > MAX_NAMESERVERS {
+        warn!(
+            "Pod {}/{} has {} nameservers; only the first {} are used",
+            namespace,
+            pod.metadata.name.as_deref().unwrap_or_default(),
+            config.nameservers.len(),
+            MAX_NAMESERVERS
+        );
+        config.nameservers.truncate(MAX_NAMESERVERS);
+    }
+    config.searches.truncate(MAX_SEARCHES);
+
+    config
+}
+
+/// A pod's hostname and, when it has a `subdomain`, its fully qualified name
+/// `{hostname}.{subdomain}.{namespace}.svc.{cluster_domain}`
+///
+/// The hostname is `spec.hostname`, or the pod name (cut to a DNS label).
+pub fn pod_hostname(pod: &Pod, cluster_domain: &str) -> (String, Option<String>) {
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+    let spec = pod.spec.as_ref();
+
+    let hostname = match spec.and_then(|s| s.hostname.as_deref()) {
+        Some(hostname) => hostname.to_string(),
+        None => {
+            let name = pod.metadata.name.as_deref().unwrap_or_default();
+            name[..name.len().min(63)]
+                .trim_end_matches(['-', '.'])
+                .to_string()
+        }
+    };
+    let fqdn = spec.and_then(|s| s.subdomain.as_deref()).map(|subdomain| {
+        format!(
+            "{}.{}.{}.svc.{}",
+            hostname, subdomain, namespace, cluster_domain
+        )
+    });
+
+    (hostname, fqdn)
+}
+
+/// Hosts file mapping `ip` to the zone's names, plus the loopback entries
+pub fn hosts_file(ip: &str, hostname: &str, fqdn: Option<&str>) -> String {
+    let names = match fqdn {
+        Some(fqdn) => format!("{} {}", fqdn, hostname),
+        None => hostname.to_string(),
+    };
+    format!(
+        "# Generated by reddwarf\n::1\tlocalhost\n127.0.0.1\tlocalhost loghost\n{}\t{}\n",
+        ip, names
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodDNSConfig, PodDNSConfigOption, PodSpec};
+
+    fn make_pod(policy: Option<&str>, dns_config: Option<PodDNSConfig>) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some("web".to_string());
+        pod.metadata.namespace = Some("shop".to_string());
+        pod.spec = Some(PodSpec {
+            dns_policy: policy.map(String::from),
+            dns_config,
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn host() -> DnsConfig {
+        DnsConfig::from_resolv_conf("nameserver 192.168.1.1\nsearch lan\n")
+    }
+
+    #[test]
+    fn test_cluster_first() {
+        let pod = make_pod(None, None);
+        assert!(uses_cluster_dns(&pod));
+
+        let config = pod_dns_config(&pod, Some("10.96.0.10"), "cluster.local", &host());
+        assert_eq!(config.nameservers, vec!["10.96.0.10"]);
+        assert_eq!(
+            config.searches,
+            vec![
+                "shop.svc.cluster.local",
+                "svc.cluster.local",
+                "cluster.local",
+                "lan"
+            ]
+        );
+        assert_eq!(config.options, vec!["ndots:5"]);
+
+        // Without a cluster DNS service the node's resolver is used
+        let config = pod_dns_config(&pod, None, "cluster.local", &host());
+        assert_eq!(config, host());
+    }
+
+    #[test]
+    fn test_dns_config_merged_over_policy() {
+        let custom = PodDNSConfig {
+            nameservers: Some(vec!["1.1.1.1".to_string()]),
+            searches: Some(vec!["example.com".to_string()]),
+            options: Some(vec![
+                PodDNSConfigOption {
+                    name: Some("ndots".to_string()),
+                    value: Some("2".to_string()),
+                },
+                PodDNSConfigOption {
+                    name: Some("edns0".to_string()),
+                    value: None,
+                },
+            ]),
+        };
+
+        let pod = make_pod(Some("None"), Some(custom.clone()));
+        assert!(!uses_cluster_dns(&pod));
+        let config = pod_dns_config(&pod, Some("10.96.0.10"), "cluster.local", &host());
+        assert_eq!(config.nameservers, vec!["1.1.1.1"]);
+        assert_eq!(config.searches, vec!["example.com"]);
+
+        let pod = make_pod(Some("ClusterFirst"), Some(custom));
+        let config = pod_dns_config(&pod, Some("10.96.0.10"), "cluster.local", &host());
+        assert_eq!(config.nameservers, vec!["10.96.0.10", "1.1.1.1"]);
+        assert_eq!(config.options, vec!["ndots:2", "edns0"]);
+
+        let pod = make_pod(Some("Default"), None);
+        let config = pod_dns_config(&pod, Some("10.96.0.10"), "cluster.local", &host());
+        assert_eq!(config, host());
+    }
+
+    #[test]
+    fn test_pod_hostname() {
+        let mut pod = make_pod(None, None);
+        assert_eq!(
+            pod_hostname(&pod, "cluster.local"),
+            ("web".to_string(), None)
+        );
+
+        let spec = pod.spec.as_mut().unwrap();
+        spec.hostname = Some("web-0".to_string());
+        spec.subdomain = Some("web".to_string());
+        let (hostname, fqdn) = pod_hostname(&pod, "cluster.local");
+        assert_eq!(hostname, "web-0");
+        assert_eq!(fqdn.as_deref(), Some("web-0.web.shop.svc.cluster.local"));
+
+        assert!(hosts_file("10.88.0.5", &hostname, fqdn.as_deref())
+            .ends_with("10.88.0.5\tweb-0.web.shop.svc.cluster.local web-0\n"));
+    }
+}
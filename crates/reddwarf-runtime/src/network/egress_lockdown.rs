@@ -0,0 +1,276 @@
+use crate::api_client::ApiClient;
+use crate::error::Result;
+use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::api::networking::v1::NetworkPolicy;
+use reddwarf_core::resources::{
+    default_deny_egress_policy, egress_lockdown, is_managed, DEFAULT_DENY_EGRESS_POLICY,
+};
+use reddwarf_core::{EventBus, WatchEventType};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the egress lockdown controller subscribes to the event bus under
+const SUBSCRIBER: &str = "egress-lockdown-controller";
+
+/// Configuration for the egress lockdown controller
+#[derive(Debug, Clone)]
+pub struct EgressLockdownControllerConfig {
+    /// Interval between full namespace list resyncs (safety net for missed
+    /// events and policies deleted by hand)
+    pub resync_interval: Duration,
+}
+
+impl Default for EgressLockdownControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Change to a namespace's policies that brings it in line with its
+/// `reddwarf.io/egress-lockdown` annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockdownChange {
+    Create,
+    Remove,
+}
+
+/// Materializes a `default-deny-egress` NetworkPolicy in every namespace
+/// annotated with `reddwarf.io/egress-lockdown: "true"`
+///
+/// Only policies this controller created (labelled managed-by reddwarf) are
+/// removed when a namespace drops the annotation; a policy of the same name
+/// created by someone else is left alone.
+pub struct EgressLockdownController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    config: EgressLockdownControllerConfig,
+}
+
+impl EgressLockdownController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: EgressLockdownControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting egress lockdown controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Egress lockdown controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("Egress lockdown resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            if event.gvk.kind != "Namespace" {
+                                continue;
+                            }
+                            if !matches!(
+                                event.event_type,
+                                WatchEventType::Added | WatchEventType::Modified
+                            ) {
+                                continue;
+                            }
+                            match serde_json::from_value::<Namespace>(event.object) {
+                                Ok(namespace) => {
+                                    if let Err(e) = self.reconcile_namespace(&namespace).await {
+                                        warn!(
+                                            "Failed to reconcile egress lockdown of namespace '{}': {}",
+                                            event.resource_key.name, e
+                                        );
+                                    }
+                                }
+                                Err(e) => warn!("Failed to parse namespace from event: {}", e),
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Missed {} events, doing full egress lockdown resync", n);
+                            if let Err(e) = self.resync().await {
+                                error!("Egress lockdown resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping egress lockdown controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconcile every namespace against the policies listed cluster-wide
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing namespace egress lockdown");
+
+        let body = self
+            .api_client
+            .get_json("/apis/networking.k8s.io/v1/networkpolicies")
+            .await?;
+        let mut policies: HashMap<String, Vec<NetworkPolicy>> = HashMap::new();
+        for item in body["items"].as_array().cloned().unwrap_or_default() {
+            match serde_json::from_value::<NetworkPolicy>(item) {
+                Ok(policy) => policies
+                    .entry(policy.metadata.namespace.clone().unwrap_or_default())
+                    .or_default()
+                    .push(policy),
+                Err(e) => warn!("Failed to parse network policy from list: {}", e),
+            }
+        }
+
+        let body = self.api_client.get_json("/api/v1/namespaces").await?;
+        for item in body["items"].as_array().cloned().unwrap_or_default() {
+            let namespace = match serde_json::from_value::<Namespace>(item) {
+                Ok(namespace) => namespace,
+                Err(e) => {
+                    warn!("Failed to parse namespace from list: {}", e);
+                    continue;
+                }
+            };
+            let Some(name) = namespace.metadata.name.as_deref() else {
+                continue;
+            };
+            let existing = policies.get(name).map(Vec::as_slice).unwrap_or_default();
+            if let Err(e) = self.apply(&namespace, existing).await {
+                warn!(
+                    "Failed to reconcile egress lockdown of namespace '{}': {}",
+                    name, e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile one namespace against its current policies
+    async fn reconcile_namespace(&self, namespace: &Namespace) -> Result<()> {
+        let Some(name) = namespace.metadata.name.as_deref() else {
+            return Ok(());
+        };
+        let body = self
+            .api_client
+            .get_json(&format!(
+                "/apis/networking.k8s.io/v1/namespaces/{}/networkpolicies",
+                name
+            ))
+            .await?;
+        let existing: Vec<NetworkPolicy> = body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect();
+        self.apply(namespace, &existing).await
+    }
+
+    async fn apply(&self, namespace: &Namespace, existing: &[NetworkPolicy]) -> Result<()> {
+        let Some(name) = namespace.metadata.name.as_deref() else {
+            return Ok(());
+        };
+        match lockdown_change(namespace, existing) {
+            Some(LockdownChange::Create) => {
+                self.api_client
+                    .create_network_policy(name, &default_deny_egress_policy(name))
+                    .await?;
+                info!("Locked down egress of namespace '{}'", name);
+            }
+            Some(LockdownChange::Remove) => {
+                self.api_client
+                    .delete_network_policy(name, DEFAULT_DENY_EGRESS_POLICY)
+                    .await?;
+                info!("Lifted egress lockdown of namespace '{}'", name);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// What to change so `namespace` has the default-deny egress policy exactly
+/// when it asks for lockdown, given the policies it has
+fn lockdown_change(namespace: &Namespace, existing: &[NetworkPolicy]) -> Option<LockdownChange> {
+    if namespace.metadata.deletion_timestamp.is_some() {
+        return None;
+    }
+    let current = existing
+        .iter()
+        .find(|p| p.metadata.name.as_deref() == Some(DEFAULT_DENY_EGRESS_POLICY));
+
+    match (egress_lockdown(namespace), current) {
+        (true, None) => Some(LockdownChange::Create),
+        (false, Some(policy)) if is_managed(policy) => Some(LockdownChange::Remove),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_core::resources::EGRESS_LOCKDOWN_ANNOTATION;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_lockdown_change() {
+        let mut namespace = Namespace::default();
+        namespace.metadata.name = Some("prod".to_string());
+        let lockdown = |namespace: &mut Namespace, value: &str| {
+            namespace.metadata.annotations = Some(BTreeMap::from([(
+                EGRESS_LOCKDOWN_ANNOTATION.to_string(),
+                value.to_string(),
+            )]));
+        };
+
+        // Nothing to do without the annotation
+        assert_eq!(lockdown_change(&namespace, &[]), None);
+
+        // Locked down namespaces get the policy once
+        lockdown(&mut namespace, "true");
+        let policy = default_deny_egress_policy("prod");
+        assert_eq!(
+            lockdown_change(&namespace, &[]),
+            Some(LockdownChange::Create)
+        );
+        assert_eq!(
+            lockdown_change(&namespace, std::slice::from_ref(&policy)),
+            None
+        );
+
+        // Opting out removes ours but not a policy someone else created
+        lockdown(&mut namespace, "false");
+        assert_eq!(
+            lockdown_change(&namespace, std::slice::from_ref(&policy)),
+            Some(LockdownChange::Remove)
+        );
+        let mut foreign = policy;
+        foreign.metadata.labels = None;
+        assert_eq!(lockdown_change(&namespace, &[foreign]), None);
+    }
+}
@@ -0,0 +1,678 @@
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use crate::network::ipam::parse_cidr;
+use crate::network::service_rules::{write_atomic, NatRuleSet};
+use chrono::Utc;
+use k8s_openapi::api::core::v1::{Namespace, Pod, PodCondition};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Namespace or pod annotation listing destination CIDRs
+/// (comma-separated) pod traffic reaches without source NAT
+pub const EGRESS_NAT_EXCEPTIONS_ANNOTATION: &str = "reddwarf.io/egress-nat-exceptions";
+
+/// Namespace or pod annotation listing the addresses (comma-separated) pod
+/// egress is translated to instead of the egress link's own; each pod
+/// keeps one address of the pool
+pub const EGRESS_IPS_ANNOTATION: &str = "reddwarf.io/egress-ips";
+
+/// Pod condition reporting whether the pod's egress NAT policy is loaded
+pub const EGRESS_NAT_CONDITION: &str = "reddwarf.io/EgressNAT";
+
+/// Header written at the top of the egress ruleset file
+const RULESET_HEADER: &str = "# Generated by reddwarf from pod egress NAT policy; do not edit\n";
+
+/// Egress NAT policy of one pod
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EgressNatPolicy {
+    /// Destination CIDRs reached without source NAT
+    pub exceptions: Vec<String>,
+    /// Address egress is translated to, when not the egress link's own
+    pub egress_ip: Option<Ipv4Addr>,
+}
+
+impl EgressNatPolicy {
+    /// The policy of `pod` in `namespace`
+    ///
+    /// Each annotation set on the pod overrides the namespace's. `None` when
+    /// neither sets one, so the pod's egress follows the node-wide rules.
+    pub fn for_pod(
+        pod: &Pod,
+        namespace: Option<&Namespace>,
+    ) -> std::result::Result<Option<Self>, String> {
+        let annotation = |key: &str| {
+            pod.metadata
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get(key))
+                .or_else(|| {
+                    namespace
+                        .and_then(|ns| ns.metadata.annotations.as_ref())
+                        .and_then(|a| a.get(key))
+                })
+                .map(|value| split_list(value))
+        };
+        let exceptions = annotation(EGRESS_NAT_EXCEPTIONS_ANNOTATION);
+        let pool = annotation(EGRESS_IPS_ANNOTATION);
+        if exceptions.is_none() && pool.is_none() {
+            return Ok(None);
+        }
+
+        let exceptions = exceptions
+            .unwrap_or_default()
+            .into_iter()
+            .map(|cidr| {
+                let parsed = parse_cidr(cidr).map_err(|_| {
+                    format!(
+                        "{}: '{}' is not an IPv4 CIDR",
+                        EGRESS_NAT_EXCEPTIONS_ANNOTATION, cidr
+                    )
+                })?;
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(parsed.prefix_len))
+                    .unwrap_or(0);
+                let network = Ipv4Addr::from(u32::from(parsed.network) & mask);
+                Ok(format!("{}/{}", network, parsed.prefix_len))
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()?;
+
+        let pool = pool
+            .unwrap_or_default()
+            .into_iter()
+            .map(|ip| {
+                ip.parse::<Ipv4Addr>().map_err(|_| {
+                    format!("{}: '{}' is not an IPv4 address", EGRESS_IPS_ANNOTATION, ip)
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()?;
+        let egress_ip = (!pool.is_empty()).then(|| pool[pool_index(pod, pool.len())]);
+
+        Ok(Some(Self {
+            exceptions,
+            egress_ip,
+        }))
+    }
+
+    /// ipnat rules applying this policy to traffic from `pod_ip` leaving on
+    /// `interface`
+    ///
+    /// ipnat tries the rules with the most specific source first, and rules
+    /// with the same source in order, so these /32 rules take precedence
+    /// over the node's pod CIDR masquerading, and exceptions over the
+    /// egress address. An exception maps the pod's address to itself.
+    pub fn render(&self, interface: &str, pod_ip: &str) -> Vec<String> {
+        let mut rules: Vec<String> = self
+            .exceptions
+            .iter()
+            .map(|cidr| {
+                format!(
+                    "map {} from {}/32 to {} -> {}/32",
+                    interface, pod_ip, cidr, pod_ip
+                )
+            })
+            .collect();
+        if let Some(egress_ip) = self.egress_ip {
+            rules.push(format!(
+                "map {} from {}/32 to any -> {}/32 portmap tcp/udp auto",
+                interface, pod_ip, egress_ip
+            ));
+            rules.push(format!(
+                "map {} from {}/32 to any -> {}/32",
+                interface, pod_ip, egress_ip
+            ));
+        }
+        rules
+    }
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Stable index into a pool of `len` addresses for `pod`, so a pod keeps
+/// its egress address across syncs and restarts
+fn pool_index(pod: &Pod, len: usize) -> usize {
+    let key = format!(
+        "{}/{}",
+        pod.metadata.namespace.as_deref().unwrap_or_default(),
+        pod.metadata.name.as_deref().unwrap_or_default()
+    );
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    (hash % len as u64) as usize
+}
+
+/// Rules of one pod's policy, loaded and unloaded together so they keep
+/// their order
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuleBlock {
+    /// `namespace/name` of the pod
+    pod: String,
+    rules: Vec<String>,
+}
+
+/// What became of one pod's policy in a sync
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Applied,
+    Invalid(String),
+    Failed(String),
+}
+
+/// Configuration for the egress NAT controller
+#[derive(Debug, Clone)]
+pub struct EgressNatControllerConfig {
+    /// Name of this node; only its pods are handled
+    pub node_name: String,
+    /// Link pod egress leaves the node on
+    pub interface: String,
+    /// Ruleset file; always holds the rules currently loaded
+    pub rules_path: PathBuf,
+    /// How often pods and namespaces are re-read
+    pub sync_interval: Duration,
+}
+
+impl EgressNatControllerConfig {
+    pub fn new(node_name: String, interface: String, rules_path: PathBuf) -> Self {
+        Self {
+            node_name,
+            interface,
+            rules_path,
+            sync_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Applies per-namespace and per-pod egress NAT policy to this node's pods
+/// as ipnat rules, and reports the result in each pod's
+/// `reddwarf.io/EgressNAT` condition
+///
+/// A pod's policy comes from the `reddwarf.io/egress-nat-exceptions` and
+/// `reddwarf.io/egress-ips` annotations of the pod or its namespace. Pods
+/// without either keep the node-wide masquerading. The rules of each pod
+/// are loaded as one block and replaced whole when they change, and like
+/// the Service rules the ruleset file is rewritten after every step so it
+/// always matches what is loaded.
+pub struct EgressNatController {
+    api_client: Arc<ApiClient>,
+    nat: Arc<dyn NatRuleSet>,
+    config: EgressNatControllerConfig,
+}
+
+impl EgressNatController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        nat: Arc<dyn NatRuleSet>,
+        config: EgressNatControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            nat,
+            config,
+        }
+    }
+
+    /// Run the sync loop until cancelled
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting egress NAT controller on {} (ruleset: {}, interval: {:?})",
+            self.config.interface,
+            self.config.rules_path.display(),
+            self.config.sync_interval
+        );
+
+        let mut interval = tokio::time::interval(self.config.sync_interval);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Egress NAT controller shutting down");
+                    return Ok(());
+                }
+                _ = interval.tick() => {
+                    if let Err(e) = self.sync().await {
+                        error!("Egress NAT sync failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read this node's pods and their namespaces, reconcile the loaded
+    /// rules against their policies and report the outcome on each pod
+    pub async fn sync(&self) -> Result<()> {
+        let pods: Vec<Pod> = self.list("/api/v1/pods").await?;
+        let namespaces: Vec<Namespace> = self.list("/api/v1/namespaces").await?;
+        let namespaces: HashMap<&str, &Namespace> = namespaces
+            .iter()
+            .filter_map(|ns| Some((ns.metadata.name.as_deref()?, ns)))
+            .collect();
+
+        let pods: Vec<&Pod> = pods
+            .iter()
+            .filter(|p| {
+                p.spec.as_ref().and_then(|s| s.node_name.as_deref())
+                    == Some(self.config.node_name.as_str())
+            })
+            .collect();
+
+        let mut desired = BTreeMap::new();
+        let mut outcomes = BTreeMap::new();
+        for pod in &pods {
+            let Some((key, pod_ip)) = egress_source(pod) else {
+                continue;
+            };
+            let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+            match EgressNatPolicy::for_pod(pod, namespaces.get(namespace).copied()) {
+                Ok(Some(policy)) => {
+                    let rules = policy.render(&self.config.interface, pod_ip);
+                    if !rules.is_empty() {
+                        let block = RuleBlock {
+                            pod: key.clone(),
+                            rules,
+                        };
+                        desired.insert(pod_ip.to_string(), block);
+                    }
+                    outcomes.insert(key, Outcome::Applied);
+                }
+                Ok(None) => {}
+                Err(message) => {
+                    outcomes.insert(key, Outcome::Invalid(message));
+                }
+            }
+        }
+
+        for (pod, message) in self.apply(&desired).await? {
+            outcomes.insert(pod, Outcome::Failed(message));
+        }
+
+        for pod in pods {
+            let Some((key, _)) = egress_source(pod) else {
+                continue;
+            };
+            if let Err(e) = self.report(pod, outcomes.get(&key)).await {
+                warn!("Failed to report egress NAT status of {}: {}", key, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn list<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let body = self.api_client.get_json(path).await?;
+        Ok(body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect())
+    }
+
+    /// Bring the loaded rule blocks from the ruleset file's contents to
+    /// `desired`, keyed by pod IP, returning the pods whose rules failed to
+    /// load and why
+    async fn apply(&self, desired: &BTreeMap<String, RuleBlock>) -> Result<Vec<(String, String)>> {
+        let path = &self.config.rules_path;
+        let mut loaded = match tokio::fs::read_to_string(path).await {
+            Ok(current) => parse_blocks(&current),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(RuntimeError::network_error(format!(
+                    "{}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let stale: Vec<String> = loaded
+            .iter()
+            .filter(|(ip, block)| desired.get(*ip) != Some(*block))
+            .map(|(ip, _)| ip.clone())
+            .collect();
+        for ip in stale {
+            let block = &loaded[&ip];
+            self.nat.remove(&join_rules(&block.rules)).await?;
+            debug!("Unloaded egress NAT rules of {}", block.pod);
+            loaded.remove(&ip);
+            write_atomic(path, &render_blocks(&loaded)).await?;
+        }
+
+        let mut failed = Vec::new();
+        for (ip, block) in desired {
+            if loaded.contains_key(ip) {
+                continue;
+            }
+            match self.nat.add(&join_rules(&block.rules)).await {
+                Ok(()) => {
+                    info!("Loaded egress NAT rules of {}", block.pod);
+                    loaded.insert(ip.clone(), block.clone());
+                    write_atomic(path, &render_blocks(&loaded)).await?;
+                }
+                Err(e) => {
+                    warn!("Failed to load egress NAT rules of {}: {}", block.pod, e);
+                    failed.push((block.pod.clone(), e.to_string()));
+                }
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Set the pod's `reddwarf.io/EgressNAT` condition to `outcome`, or
+    /// drop it when the pod has no policy, if that changes it
+    async fn report(&self, pod: &Pod, outcome: Option<&Outcome>) -> Result<()> {
+        let conditions = pod
+            .status
+            .as_ref()
+            .and_then(|s| s.conditions.as_deref())
+            .unwrap_or_default();
+        let existing = conditions.iter().find(|c| c.type_ == EGRESS_NAT_CONDITION);
+        let wanted = outcome.map(|outcome| {
+            let (status, reason, message) = match outcome {
+                Outcome::Applied => ("True", "RulesApplied", None),
+                Outcome::Invalid(message) => ("False", "InvalidPolicy", Some(message.clone())),
+                Outcome::Failed(message) => ("False", "ApplyFailed", Some(message.clone())),
+            };
+            PodCondition {
+                type_: EGRESS_NAT_CONDITION.to_string(),
+                status: status.to_string(),
+                reason: Some(reason.to_string()),
+                message,
+                last_transition_time: Some(Time(Utc::now())),
+                ..Default::default()
+            }
+        });
+        let unchanged = match (existing, &wanted) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.status == b.status && a.reason == b.reason,
+            _ => false,
+        };
+        if unchanged {
+            return Ok(());
+        }
+
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let name = pod.metadata.name.as_deref().unwrap_or_default();
+        // Re-read so the status written by the pod controller since the
+        // list isn't reverted
+        let mut current = self.api_client.get_pod(namespace, name).await?;
+        let status = current.status.get_or_insert_with(Default::default);
+        let conditions = status.conditions.get_or_insert_with(Vec::new);
+        conditions.retain(|c| c.type_ != EGRESS_NAT_CONDITION);
+        conditions.extend(wanted);
+        self.api_client
+            .update_pod_status(namespace, name, &current)
+            .await?;
+        Ok(())
+    }
+}
+
+/// `namespace/name` and IP of `pod`, if it is running with an address
+fn egress_source(pod: &Pod) -> Option<(String, &str)> {
+    if pod.metadata.deletion_timestamp.is_some() {
+        return None;
+    }
+    let status = pod.status.as_ref()?;
+    if status.phase.as_deref() != Some("Running") {
+        return None;
+    }
+    let pod_ip = status.pod_ip.as_deref()?;
+    let key = format!(
+        "{}/{}",
+        pod.metadata.namespace.as_deref().unwrap_or("default"),
+        pod.metadata.name.as_deref().unwrap_or_default()
+    );
+    Some((key, pod_ip))
+}
+
+fn join_rules(rules: &[String]) -> String {
+    rules.iter().map(|r| format!("{}\n", r)).collect()
+}
+
+/// The ruleset file holding `blocks`, each headed by a comment naming its
+/// pod and address
+fn render_blocks(blocks: &BTreeMap<String, RuleBlock>) -> String {
+    let mut contents = RULESET_HEADER.to_string();
+    for (ip, block) in blocks {
+        contents.push_str(&format!("# {} {}\n", block.pod, ip));
+        contents.push_str(&join_rules(&block.rules));
+    }
+    contents
+}
+
+/// Rule blocks of a ruleset file written by [`render_blocks`]
+fn parse_blocks(contents: &str) -> BTreeMap<String, RuleBlock> {
+    let mut blocks = BTreeMap::new();
+    let mut current: Option<(String, RuleBlock)> = None;
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut fields = comment.split_whitespace();
+            if let (Some(pod), Some(ip), None) = (fields.next(), fields.next(), fields.next()) {
+                blocks.extend(current.take());
+                let block = RuleBlock {
+                    pod: pod.to_string(),
+                    rules: Vec::new(),
+                };
+                current = Some((ip.to_string(), block));
+            }
+            continue;
+        }
+        if let Some((_, block)) = current.as_mut() {
+            block.rules.push(line.to_string());
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::PodStatus;
+    use std::sync::Mutex as StdMutex;
+    use tempfile::tempdir;
+
+    /// Records loaded rules instead of touching the host, failing to load
+    /// rules that mention `fail_on`
+    #[derive(Default)]
+    struct RecordingNat {
+        fail_on: Option<String>,
+        ops: StdMutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NatRuleSet for RecordingNat {
+        async fn add(&self, rules: &str) -> Result<()> {
+            if self.fail_on.as_deref().is_some_and(|f| rules.contains(f)) {
+                return Err(RuntimeError::network_error("ipnat: syntax error"));
+            }
+            self.ops.lock().unwrap().push(format!("add\n{}", rules));
+            Ok(())
+        }
+
+        async fn remove(&self, rules: &str) -> Result<()> {
+            self.ops.lock().unwrap().push(format!("remove\n{}", rules));
+            Ok(())
+        }
+    }
+
+    fn annotated<'a>(
+        annotations: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> BTreeMap<String, String> {
+        annotations
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn make_pod(name: &str, ip: &str, annotations: &[(&str, &str)]) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.annotations = Some(annotated(annotations.iter().copied()));
+        pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            pod_ip: Some(ip.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn block(pod: &Pod) -> (String, RuleBlock) {
+        let (key, ip) = egress_source(pod).unwrap();
+        let policy = EgressNatPolicy::for_pod(pod, None).unwrap().unwrap();
+        (
+            ip.to_string(),
+            RuleBlock {
+                pod: key,
+                rules: policy.render("net0", ip),
+            },
+        )
+    }
+
+    #[test]
+    fn test_policy_from_pod_and_namespace() {
+        let mut namespace = Namespace::default();
+        namespace.metadata.annotations = Some(annotated([
+            (
+                EGRESS_NAT_EXCEPTIONS_ANNOTATION,
+                "10.0.0.0/8, 192.168.1.7/24",
+            ),
+            (EGRESS_IPS_ANNOTATION, "203.0.113.10"),
+        ]));
+
+        // The namespace's policy, with exceptions normalized
+        let pod = make_pod("web", "10.88.0.5", &[]);
+        let policy = EgressNatPolicy::for_pod(&pod, Some(&namespace))
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.exceptions, ["10.0.0.0/8", "192.168.1.0/24"]);
+        assert_eq!(policy.egress_ip, Some(Ipv4Addr::new(203, 0, 113, 10)));
+        assert_eq!(
+            policy.render("net0", "10.88.0.5"),
+            [
+                "map net0 from 10.88.0.5/32 to 10.0.0.0/8 -> 10.88.0.5/32",
+                "map net0 from 10.88.0.5/32 to 192.168.1.0/24 -> 10.88.0.5/32",
+                "map net0 from 10.88.0.5/32 to any -> 203.0.113.10/32 portmap tcp/udp auto",
+                "map net0 from 10.88.0.5/32 to any -> 203.0.113.10/32",
+            ]
+        );
+
+        // The pod's annotations override the namespace's one by one
+        let pod = make_pod(
+            "web",
+            "10.88.0.5",
+            &[(EGRESS_NAT_EXCEPTIONS_ANNOTATION, "")],
+        );
+        let policy = EgressNatPolicy::for_pod(&pod, Some(&namespace))
+            .unwrap()
+            .unwrap();
+        assert!(policy.exceptions.is_empty());
+        assert!(policy.egress_ip.is_some());
+
+        // Pool addresses are spread over pods, and stick to each
+        let pool = "203.0.113.10,203.0.113.11,203.0.113.12";
+        let picks: std::collections::BTreeSet<_> = (0..20)
+            .map(|i| {
+                let pod = make_pod(
+                    &format!("web-{}", i),
+                    "10.88.0.5",
+                    &[(EGRESS_IPS_ANNOTATION, pool)],
+                );
+                EgressNatPolicy::for_pod(&pod, None)
+                    .unwrap()
+                    .unwrap()
+                    .egress_ip
+            })
+            .collect();
+        assert!(picks.len() > 1);
+        let pod = make_pod("web-1", "10.88.0.5", &[(EGRESS_IPS_ANNOTATION, pool)]);
+        assert_eq!(
+            EgressNatPolicy::for_pod(&pod, None).unwrap(),
+            EgressNatPolicy::for_pod(&pod, None).unwrap()
+        );
+
+        assert_eq!(
+            EgressNatPolicy::for_pod(&make_pod("web", "10.88.0.5", &[]), None),
+            Ok(None)
+        );
+        let pod = make_pod(
+            "web",
+            "10.88.0.5",
+            &[(EGRESS_IPS_ANNOTATION, "egress.example")],
+        );
+        assert!(EgressNatPolicy::for_pod(&pod, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_replaces_changed_blocks() {
+        let dir = tempdir().unwrap();
+        let nat = Arc::new(RecordingNat {
+            fail_on: Some("10.88.0.9".to_string()),
+            ..Default::default()
+        });
+        let controller = EgressNatController::new(
+            Arc::new(ApiClient::new("http://127.0.0.1:6443")),
+            nat.clone(),
+            EgressNatControllerConfig::new(
+                "node1".to_string(),
+                "net0".to_string(),
+                dir.path().join("egress.ipnat"),
+            ),
+        );
+
+        let web = make_pod(
+            "web",
+            "10.88.0.5",
+            &[(EGRESS_IPS_ANNOTATION, "203.0.113.10")],
+        );
+        let db = make_pod(
+            "db",
+            "10.88.0.6",
+            &[(EGRESS_NAT_EXCEPTIONS_ANNOTATION, "10.0.0.0/8")],
+        );
+        let desired = BTreeMap::from([block(&web), block(&db)]);
+        assert!(controller.apply(&desired).await.unwrap().is_empty());
+        // Unchanged policy loads nothing
+        assert!(controller.apply(&desired).await.unwrap().is_empty());
+        assert_eq!(nat.ops.lock().unwrap().len(), 2);
+
+        // A changed block is unloaded and loaded whole; a failing one is
+        // reported and retried on the next sync
+        let web = make_pod(
+            "web",
+            "10.88.0.5",
+            &[(EGRESS_IPS_ANNOTATION, "203.0.113.11")],
+        );
+        let broken = make_pod(
+            "broken",
+            "10.88.0.9",
+            &[(EGRESS_IPS_ANNOTATION, "203.0.113.12")],
+        );
+        let desired = BTreeMap::from([block(&web), block(&db), block(&broken)]);
+        let failed = controller.apply(&desired).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "default/broken");
+
+        let ops = nat.ops.lock().unwrap().clone();
+        assert_eq!(ops.len(), 4);
+        assert!(ops[2].starts_with("remove\n") && ops[2].contains("203.0.113.10"));
+        assert!(ops[3].starts_with("add\n") && ops[3].contains("203.0.113.11"));
+
+        let on_disk = std::fs::read_to_string(dir.path().join("egress.ipnat")).unwrap();
+        let loaded = parse_blocks(&on_disk);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["10.88.0.5"], block(&web).1);
+        assert_eq!(loaded["10.88.0.6"], block(&db).1);
+    }
+}
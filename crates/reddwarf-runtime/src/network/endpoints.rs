@@ -0,0 +1,769 @@
+use crate::api_client::ApiClient;
+use crate::error::Result;
+use crate::network::service_rules::target_port;
+use crate::pod_conditions::is_pod_ready;
+use crate::workloads::{controller_reference, list};
+use k8s_openapi::api::core::v1::{
+    EndpointAddress, EndpointPort, EndpointSubset, Endpoints, ObjectReference, Pod, Service,
+};
+use k8s_openapi::api::discovery::v1::{
+    self as discovery, Endpoint, EndpointConditions, EndpointSlice,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use reddwarf_core::resources::{
+    is_slice_of, ENDPOINT_SLICE_CONTROLLER, ENDPOINT_SLICE_MANAGED_BY_LABEL, MANAGED_BY_LABEL,
+    MANAGED_BY_REDDWARF, SERVICE_NAME_LABEL,
+};
+use reddwarf_core::{EventBus, GroupVersionKind};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the endpoints controller subscribes to the event bus under
+const SUBSCRIBER: &str = "endpoints-controller";
+
+/// Configuration for the endpoints controller
+#[derive(Debug, Clone)]
+pub struct EndpointsControllerConfig {
+    /// Interval between full resyncs of every Service's endpoints
+    /// (safety net for missed events and pods that stopped matching)
+    pub resync_interval: Duration,
+}
+
+impl Default for EndpointsControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A Service port as resolved against one pod: name, port and protocol
+type PortKey = (Option<String>, i32, String);
+
+/// Pods of one Service that expose the same ports, split by readiness
+#[derive(Default)]
+struct PortGroup<'a> {
+    ready: Vec<&'a Pod>,
+    not_ready: Vec<&'a Pod>,
+}
+
+/// Objects of one namespace the endpoints of its Services derive from
+#[derive(Default)]
+struct NamespaceState {
+    services: Vec<Service>,
+    pods: Vec<Pod>,
+    endpoints: Vec<Endpoints>,
+    slices: Vec<EndpointSlice>,
+}
+
+/// Maintains an Endpoints object and EndpointSlices for every Service with
+/// a selector, listing the IPs of the pods it selects
+///
+/// Ready pods are listed as addresses and the rest as not-ready addresses,
+/// unless the Service publishes not-ready addresses. Pods whose container
+/// ports differ (named target ports) land in separate subsets and slices.
+/// Services without a selector keep hand-written Endpoints; objects
+/// reddwarf wrote for a Service that is gone are deleted.
+pub struct EndpointsController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    config: EndpointsControllerConfig,
+}
+
+impl EndpointsController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: EndpointsControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting endpoints controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Endpoints controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("Endpoints resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            // A pod may stop matching a Service by changing
+                            // its labels, so every Service of the namespace
+                            // is recomputed
+                            if !matches!(event.gvk.kind.as_str(), "Service" | "Pod") {
+                                continue;
+                            }
+                            let namespace = event.resource_key.namespace.clone();
+                            if let Err(e) = self.reconcile_namespace(&namespace).await {
+                                warn!(
+                                    "Failed to reconcile endpoints in namespace {}: {}",
+                                    namespace, e
+                                );
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            let gvk = GroupVersionKind::from_api_version_kind("v1", "Service");
+                            match self.event_bus.resync(SUBSCRIBER, &gvk) {
+                                Ok(count) => warn!("Missed {} events, resyncing {} Services", n, count),
+                                Err(e) => {
+                                    warn!("Missed {} events and cannot resync ({}), doing full endpoints resync", n, e);
+                                    if let Err(e) = self.resync().await {
+                                        error!("Endpoints resync after lag failed: {}", e);
+                                    }
+                                }
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping endpoints controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconcile the endpoints of every Service in every namespace
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing endpoints");
+
+        let services: Vec<Service> = list(&self.api_client, "/api/v1/services").await?;
+        let pods: Vec<Pod> = list(&self.api_client, "/api/v1/pods").await?;
+        let endpoints: Vec<Endpoints> = list(&self.api_client, "/api/v1/endpoints").await?;
+        let slices: Vec<EndpointSlice> =
+            list(&self.api_client, "/apis/discovery.k8s.io/v1/endpointslices").await?;
+
+        let mut namespaces: BTreeMap<String, NamespaceState> = BTreeMap::new();
+        for service in services {
+            namespace_state(&mut namespaces, &service.metadata)
+                .services
+                .push(service);
+        }
+        for pod in pods {
+            namespace_state(&mut namespaces, &pod.metadata)
+                .pods
+                .push(pod);
+        }
+        for object in endpoints {
+            namespace_state(&mut namespaces, &object.metadata)
+                .endpoints
+                .push(object);
+        }
+        for slice in slices {
+            namespace_state(&mut namespaces, &slice.metadata)
+                .slices
+                .push(slice);
+        }
+
+        for (namespace, state) in &namespaces {
+            if let Err(e) = self.reconcile(namespace, state).await {
+                warn!(
+                    "Failed to reconcile endpoints in namespace {}: {}",
+                    namespace, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Reconcile the endpoints of every Service in `namespace`
+    async fn reconcile_namespace(&self, namespace: &str) -> Result<()> {
+        let state = NamespaceState {
+            services: list(
+                &self.api_client,
+                &format!("/api/v1/namespaces/{}/services", namespace),
+            )
+            .await?,
+            pods: list(
+                &self.api_client,
+                &format!("/api/v1/namespaces/{}/pods", namespace),
+            )
+            .await?,
+            endpoints: list(
+                &self.api_client,
+                &format!("/api/v1/namespaces/{}/endpoints", namespace),
+            )
+            .await?,
+            slices: list(
+                &self.api_client,
+                &format!(
+                    "/apis/discovery.k8s.io/v1/namespaces/{}/endpointslices",
+                    namespace
+                ),
+            )
+            .await?,
+        };
+        self.reconcile(namespace, &state).await
+    }
+
+    async fn reconcile(&self, namespace: &str, state: &NamespaceState) -> Result<()> {
+        let mut selected: HashMap<&str, &Service> = HashMap::new();
+        for service in &state.services {
+            if !has_selector(service) || service.metadata.deletion_timestamp.is_some() {
+                continue;
+            }
+            let name = service.metadata.name.as_deref().unwrap_or_default();
+            selected.insert(name, service);
+
+            let pods: Vec<&Pod> = state.pods.iter().filter(|p| selects(service, p)).collect();
+            self.sync_endpoints(namespace, service, &pods, &state.endpoints)
+                .await?;
+            self.sync_slices(namespace, service, &pods, &state.slices)
+                .await?;
+        }
+
+        for object in &state.endpoints {
+            let name = object.metadata.name.as_deref().unwrap_or_default();
+            if is_managed(&object.metadata) && !selected.contains_key(name) {
+                info!(
+                    "Deleting endpoints {}/{} of a deleted Service",
+                    namespace, name
+                );
+                self.api_client.delete_endpoints(namespace, name).await?;
+            }
+        }
+        for slice in &state.slices {
+            let service = slice_service(slice).unwrap_or_default();
+            if is_slice_of(slice, service) && !selected.contains_key(service) {
+                let name = slice.metadata.name.as_deref().unwrap_or_default();
+                info!(
+                    "Deleting endpoint slice {}/{} of a deleted Service",
+                    namespace, name
+                );
+                self.api_client
+                    .delete_endpoint_slice(namespace, name)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create or update the Endpoints object of `service`
+    async fn sync_endpoints(
+        &self,
+        namespace: &str,
+        service: &Service,
+        pods: &[&Pod],
+        existing: &[Endpoints],
+    ) -> Result<()> {
+        let desired = service_endpoints(service, pods);
+        let name = desired.metadata.name.as_deref().unwrap_or_default();
+        match existing
+            .iter()
+            .find(|e| e.metadata.name.as_deref() == Some(name))
+        {
+            None => {
+                debug!("Creating endpoints {}/{}", namespace, name);
+                self.api_client
+                    .create_endpoints(namespace, &desired)
+                    .await?;
+            }
+            Some(current)
+                if current.subsets != desired.subsets
+                    || current.metadata.labels != desired.metadata.labels =>
+            {
+                debug!("Updating endpoints {}/{}", namespace, name);
+                let mut updated = current.clone();
+                updated.metadata.labels = desired.metadata.labels;
+                updated.subsets = desired.subsets;
+                self.api_client
+                    .replace_endpoints(namespace, name, &updated)
+                    .await?;
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Create, update and delete the EndpointSlices of `service`
+    async fn sync_slices(
+        &self,
+        namespace: &str,
+        service: &Service,
+        pods: &[&Pod],
+        existing: &[EndpointSlice],
+    ) -> Result<()> {
+        let service_name = service.metadata.name.as_deref().unwrap_or_default();
+        let existing: Vec<&EndpointSlice> = existing
+            .iter()
+            .filter(|s| is_slice_of(s, service_name))
+            .collect();
+        let desired = service_endpoint_slices(service, pods);
+
+        for slice in &desired {
+            let name = slice.metadata.name.as_deref().unwrap_or_default();
+            match existing
+                .iter()
+                .find(|s| s.metadata.name.as_deref() == Some(name))
+            {
+                None => {
+                    debug!("Creating endpoint slice {}/{}", namespace, name);
+                    self.api_client
+                        .create_endpoint_slice(namespace, slice)
+                        .await?;
+                }
+                Some(current)
+                    if current.endpoints != slice.endpoints
+                        || current.ports != slice.ports
+                        || current.metadata.labels != slice.metadata.labels =>
+                {
+                    debug!("Updating endpoint slice {}/{}", namespace, name);
+                    let mut updated = (*current).clone();
+                    updated.metadata.labels = slice.metadata.labels.clone();
+                    updated.endpoints = slice.endpoints.clone();
+                    updated.ports = slice.ports.clone();
+                    self.api_client
+                        .replace_endpoint_slice(namespace, name, &updated)
+                        .await?;
+                }
+                Some(_) => {}
+            }
+        }
+
+        for slice in existing {
+            let name = slice.metadata.name.as_deref().unwrap_or_default();
+            if !desired
+                .iter()
+                .any(|s| s.metadata.name.as_deref() == Some(name))
+            {
+                debug!("Deleting endpoint slice {}/{}", namespace, name);
+                self.api_client
+                    .delete_endpoint_slice(namespace, name)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The objects gathered for the namespace of `metadata`
+fn namespace_state<'a>(
+    namespaces: &'a mut BTreeMap<String, NamespaceState>,
+    metadata: &ObjectMeta,
+) -> &'a mut NamespaceState {
+    let namespace = metadata.namespace.as_deref().unwrap_or("default");
+    namespaces.entry(namespace.to_string()).or_default()
+}
+
+/// Whether reddwarf maintains the endpoints of `service`: it selects pods
+/// and does not merely alias an external name
+fn has_selector(service: &Service) -> bool {
+    service.spec.as_ref().is_some_and(|spec| {
+        spec.type_.as_deref() != Some("ExternalName")
+            && spec.selector.as_ref().is_some_and(|s| !s.is_empty())
+    })
+}
+
+/// Whether reddwarf wrote the object of `metadata`
+fn is_managed(metadata: &ObjectMeta) -> bool {
+    metadata
+        .labels
+        .as_ref()
+        .and_then(|l| l.get(MANAGED_BY_LABEL))
+        .is_some_and(|value| value == MANAGED_BY_REDDWARF)
+}
+
+/// Service an EndpointSlice belongs to, by its service-name label
+fn slice_service(slice: &EndpointSlice) -> Option<&str> {
+    slice
+        .metadata
+        .labels
+        .as_ref()?
+        .get(SERVICE_NAME_LABEL)
+        .map(String::as_str)
+}
+
+/// Whether `pod` is an endpoint of `service`: selected by it in its
+/// namespace, assigned an IP, and neither terminating nor finished
+fn selects(service: &Service, pod: &Pod) -> bool {
+    let namespace = service.metadata.namespace.as_deref().unwrap_or("default");
+    if pod.metadata.namespace.as_deref().unwrap_or("default") != namespace
+        || pod.metadata.deletion_timestamp.is_some()
+    {
+        return false;
+    }
+    let Some(status) = pod.status.as_ref() else {
+        return false;
+    };
+    if status.pod_ip.is_none() || matches!(status.phase.as_deref(), Some("Succeeded" | "Failed")) {
+        return false;
+    }
+    let Some(selector) = service.spec.as_ref().and_then(|s| s.selector.as_ref()) else {
+        return false;
+    };
+    let labels = pod.metadata.labels.as_ref();
+    selector
+        .iter()
+        .all(|(k, v)| labels.and_then(|l| l.get(k)) == Some(v))
+}
+
+fn pod_ip(pod: &Pod) -> &str {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.pod_ip.as_deref())
+        .unwrap_or_default()
+}
+
+/// Ports `pod` serves `service` on; `None` when the Service has ports but
+/// none of them resolves on the pod
+fn pod_ports(service: &Service, pod: &Pod) -> Option<Vec<PortKey>> {
+    let ports = service.spec.as_ref().and_then(|s| s.ports.as_deref());
+    let Some(ports) = ports.filter(|p| !p.is_empty()) else {
+        return Some(Vec::new());
+    };
+    let resolved: Vec<PortKey> = ports
+        .iter()
+        .filter_map(|port| {
+            let protocol = port.protocol.clone().unwrap_or_else(|| "TCP".to_string());
+            Some((port.name.clone(), target_port(port, pod)?, protocol))
+        })
+        .collect();
+    (!resolved.is_empty()).then_some(resolved)
+}
+
+/// The pods of `service` grouped by the ports they serve it on
+fn port_groups<'a>(service: &Service, pods: &[&'a Pod]) -> BTreeMap<Vec<PortKey>, PortGroup<'a>> {
+    let publish_not_ready = service
+        .spec
+        .as_ref()
+        .and_then(|s| s.publish_not_ready_addresses)
+        .unwrap_or(false);
+
+    let mut groups: BTreeMap<Vec<PortKey>, PortGroup<'a>> = BTreeMap::new();
+    for pod in pods {
+        let Some(ports) = pod_ports(service, pod) else {
+            continue;
+        };
+        let group = groups.entry(ports).or_default();
+        if publish_not_ready || is_pod_ready(pod) {
+            group.ready.push(pod);
+        } else {
+            group.not_ready.push(pod);
+        }
+    }
+    for group in groups.values_mut() {
+        group.ready.sort_by_key(|p| pod_ip(p));
+        group.not_ready.sort_by_key(|p| pod_ip(p));
+    }
+    groups
+}
+
+fn pod_reference(pod: &Pod) -> ObjectReference {
+    ObjectReference {
+        kind: Some("Pod".to_string()),
+        namespace: pod.metadata.namespace.clone(),
+        name: pod.metadata.name.clone(),
+        uid: pod.metadata.uid.clone(),
+        ..Default::default()
+    }
+}
+
+/// DNS hostname of `pod` within `service`: its `spec.hostname`, when its
+/// `spec.subdomain` names the Service
+fn pod_hostname(service: &Service, pod: &Pod) -> Option<String> {
+    let spec = pod.spec.as_ref()?;
+    if spec.subdomain.is_none() || spec.subdomain != service.metadata.name {
+        return None;
+    }
+    spec.hostname.clone()
+}
+
+/// Labels of the objects listing the endpoints of `service`: its own, plus
+/// `extra`
+fn endpoint_labels(service: &Service, extra: &[(&str, &str)]) -> BTreeMap<String, String> {
+    let mut labels = service.metadata.labels.clone().unwrap_or_default();
+    for (key, value) in extra {
+        labels.insert(key.to_string(), value.to_string());
+    }
+    labels
+}
+
+/// The Endpoints object listing the IPs of `pods` for `service`
+pub fn service_endpoints(service: &Service, pods: &[&Pod]) -> Endpoints {
+    let address = |pod: &&Pod| EndpointAddress {
+        ip: pod_ip(pod).to_string(),
+        hostname: pod_hostname(service, pod),
+        node_name: pod.spec.as_ref().and_then(|s| s.node_name.clone()),
+        target_ref: Some(pod_reference(pod)),
+    };
+    let subsets: Vec<EndpointSubset> = port_groups(service, pods)
+        .into_iter()
+        .map(|(ports, group)| EndpointSubset {
+            addresses: (!group.ready.is_empty()).then(|| group.ready.iter().map(address).collect()),
+            not_ready_addresses: (!group.not_ready.is_empty())
+                .then(|| group.not_ready.iter().map(address).collect()),
+            ports: (!ports.is_empty()).then(|| {
+                ports
+                    .into_iter()
+                    .map(|(name, port, protocol)| EndpointPort {
+                        name,
+                        port,
+                        protocol: Some(protocol),
+                        ..Default::default()
+                    })
+                    .collect()
+            }),
+        })
+        .collect();
+
+    Endpoints {
+        metadata: ObjectMeta {
+            name: service.metadata.name.clone(),
+            namespace: service.metadata.namespace.clone(),
+            labels: Some(endpoint_labels(
+                service,
+                &[(MANAGED_BY_LABEL, MANAGED_BY_REDDWARF)],
+            )),
+            ..Default::default()
+        },
+        subsets: (!subsets.is_empty()).then_some(subsets),
+    }
+}
+
+/// Name of the slice of `service` holding endpoints of `address_type`
+/// that serve `ports`, stable for equal ports
+fn slice_name(service: &str, address_type: &str, ports: &[PortKey]) -> String {
+    let key = serde_json::to_vec(&(address_type, ports)).unwrap_or_default();
+    let hash = key.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{}-{:010x}", service, hash & 0xff_ffff_ffff)
+}
+
+/// The EndpointSlices listing `pods` for `service`, one per address type
+/// and set of ports; a Service with no endpoints gets one empty slice
+pub fn service_endpoint_slices(service: &Service, pods: &[&Pod]) -> Vec<EndpointSlice> {
+    let service_name = service.metadata.name.as_deref().unwrap_or_default();
+
+    let mut slices: BTreeMap<(&str, Vec<PortKey>), Vec<Endpoint>> = BTreeMap::new();
+    for (ports, group) in port_groups(service, pods) {
+        let ready = group.ready.iter().map(|pod| (pod, true));
+        for (pod, ready) in ready.chain(group.not_ready.iter().map(|pod| (pod, false))) {
+            let address_type = match pod_ip(pod).parse::<IpAddr>() {
+                Ok(IpAddr::V6(_)) => "IPv6",
+                _ => "IPv4",
+            };
+            slices
+                .entry((address_type, ports.clone()))
+                .or_default()
+                .push(Endpoint {
+                    addresses: vec![pod_ip(pod).to_string()],
+                    conditions: Some(EndpointConditions {
+                        ready: Some(ready),
+                        serving: Some(ready),
+                        terminating: Some(false),
+                    }),
+                    hostname: pod_hostname(service, pod),
+                    node_name: pod.spec.as_ref().and_then(|s| s.node_name.clone()),
+                    target_ref: Some(pod_reference(pod)),
+                    ..Default::default()
+                });
+        }
+    }
+    if slices.is_empty() {
+        slices.insert(("IPv4", Vec::new()), Vec::new());
+    }
+
+    slices
+        .into_iter()
+        .map(|((address_type, ports), mut endpoints)| {
+            endpoints.sort_by(|a, b| a.addresses.cmp(&b.addresses));
+            EndpointSlice {
+                metadata: ObjectMeta {
+                    name: Some(slice_name(service_name, address_type, &ports)),
+                    namespace: service.metadata.namespace.clone(),
+                    labels: Some(endpoint_labels(
+                        service,
+                        &[
+                            (SERVICE_NAME_LABEL, service_name),
+                            (ENDPOINT_SLICE_MANAGED_BY_LABEL, ENDPOINT_SLICE_CONTROLLER),
+                        ],
+                    )),
+                    owner_references: Some(vec![controller_reference(
+                        "v1",
+                        "Service",
+                        &service.metadata,
+                    )]),
+                    ..Default::default()
+                },
+                address_type: address_type.to_string(),
+                endpoints,
+                ports: (!ports.is_empty()).then(|| {
+                    ports
+                        .into_iter()
+                        .map(|(name, port, protocol)| discovery::EndpointPort {
+                            name,
+                            port: Some(port),
+                            protocol: Some(protocol),
+                            ..Default::default()
+                        })
+                        .collect()
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{
+        Container, ContainerPort, PodCondition, PodSpec, PodStatus, ServicePort, ServiceSpec,
+    };
+    use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+
+    fn make_service(target_port: IntOrString) -> Service {
+        Service {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                namespace: Some("default".to_string()),
+                uid: Some("svc-uid".to_string()),
+                ..Default::default()
+            },
+            spec: Some(ServiceSpec {
+                selector: Some([("app".to_string(), "web".to_string())].into()),
+                ports: Some(vec![ServicePort {
+                    name: Some("http".to_string()),
+                    port: 80,
+                    target_port: Some(target_port),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn make_pod(name: &str, ip: &str, ready: bool, container_port: i32) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some("default".to_string()),
+                labels: Some([("app".to_string(), "web".to_string())].into()),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                node_name: Some("node1".to_string()),
+                containers: vec![Container {
+                    name: "web".to_string(),
+                    ports: Some(vec![ContainerPort {
+                        name: Some("http".to_string()),
+                        container_port,
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                pod_ip: Some(ip.to_string()),
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: if ready { "True" } else { "False" }.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_service_endpoints_split_by_readiness() {
+        let service = make_service(IntOrString::Int(8080));
+        let ready = make_pod("web-b", "10.88.0.3", true, 8080);
+        let also_ready = make_pod("web-a", "10.88.0.2", true, 8080);
+        let unready = make_pod("web-c", "10.88.0.4", false, 8080);
+        let mut other = make_pod("db", "10.88.0.5", true, 5432);
+        other.metadata.labels = Some([("app".to_string(), "db".to_string())].into());
+
+        let pods = [ready, also_ready, unready, other];
+        let selected: Vec<&Pod> = pods.iter().filter(|p| selects(&service, p)).collect();
+        let endpoints = service_endpoints(&service, &selected);
+
+        assert_eq!(endpoints.metadata.name.as_deref(), Some("web"));
+        assert!(is_managed(&endpoints.metadata));
+        let subsets = endpoints.subsets.unwrap();
+        assert_eq!(subsets.len(), 1);
+        let ips: Vec<&str> = subsets[0]
+            .addresses
+            .iter()
+            .flatten()
+            .map(|a| a.ip.as_str())
+            .collect();
+        assert_eq!(ips, ["10.88.0.2", "10.88.0.3"]);
+        let not_ready = subsets[0].not_ready_addresses.as_ref().unwrap();
+        assert_eq!(not_ready[0].ip, "10.88.0.4");
+        assert_eq!(subsets[0].ports.as_ref().unwrap()[0].port, 8080);
+    }
+
+    #[test]
+    fn test_named_target_ports_split_subsets_and_slices() {
+        let service = make_service(IntOrString::String("http".to_string()));
+        let old = make_pod("web-old", "10.88.0.2", true, 8080);
+        let new = make_pod("web-new", "10.88.0.3", true, 9090);
+        let pods = [&old, &new];
+
+        let subsets = service_endpoints(&service, &pods).subsets.unwrap();
+        assert_eq!(subsets.len(), 2);
+
+        let slices = service_endpoint_slices(&service, &pods);
+        assert_eq!(slices.len(), 2);
+        assert_ne!(slices[0].metadata.name, slices[1].metadata.name);
+        for slice in &slices {
+            assert!(is_slice_of(slice, "web"));
+            assert_eq!(slice.address_type, "IPv4");
+            assert_eq!(slice.endpoints.len(), 1);
+            let owner = &slice.metadata.owner_references.as_ref().unwrap()[0];
+            assert_eq!(owner.uid, "svc-uid");
+        }
+
+        // Equal inputs name slices equally, so resyncs update in place
+        assert_eq!(service_endpoint_slices(&service, &pods), slices);
+    }
+
+    #[test]
+    fn test_service_without_endpoints_gets_empty_slice() {
+        let service = make_service(IntOrString::Int(8080));
+        let slices = service_endpoint_slices(&service, &[]);
+        assert_eq!(slices.len(), 1);
+        assert!(slices[0].endpoints.is_empty());
+        assert!(service_endpoints(&service, &[]).subsets.is_none());
+    }
+
+    #[test]
+    fn test_publish_not_ready_addresses() {
+        let mut service = make_service(IntOrString::Int(8080));
+        service.spec.as_mut().unwrap().publish_not_ready_addresses = Some(true);
+        let unready = make_pod("web-0", "10.88.0.2", false, 8080);
+
+        let subsets = service_endpoints(&service, &[&unready]).subsets.unwrap();
+        assert_eq!(subsets[0].addresses.as_ref().unwrap().len(), 1);
+        assert!(subsets[0].not_ready_addresses.is_none());
+    }
+}
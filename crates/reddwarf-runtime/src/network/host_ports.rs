@@ -0,0 +1,172 @@
+use crate::error::{Result, RuntimeError};
+use crate::types::PortForward;
+use reddwarf_core::host_ports::{host_port_owner, node_host_port_prefix};
+use reddwarf_core::HostPort;
+use reddwarf_storage::KVStore;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Host port reservations of one node, backed by a KVStore
+///
+/// Storage keys (shared with the scheduler, which reserves at bind time):
+/// - `hostports/{node}/{protocol}/{port}` → `"{namespace}/{pod_name}"`
+pub struct HostPortTable {
+    storage: Arc<dyn KVStore>,
+    node_name: String,
+}
+
+impl HostPortTable {
+    pub fn new(storage: Arc<dyn KVStore>, node_name: impl Into<String>) -> Self {
+        Self {
+            storage,
+            node_name: node_name.into(),
+        }
+    }
+
+    /// Reserve a pod's host ports. Idempotent for ports the pod already holds;
+    /// fails without reserving anything if another pod holds one of them.
+    pub fn reserve(&self, namespace: &str, pod_name: &str, ports: &[HostPort]) -> Result<()> {
+        let owner = host_port_owner(namespace, pod_name);
+
+        for port in ports {
+            let key = port.reservation_key(&self.node_name);
+            if let Some(existing) = self.storage.get(key.as_bytes())? {
+                let existing = String::from_utf8_lossy(&existing);
+                if existing != owner {
+                    return Err(RuntimeError::host_port_conflict(
+                        port.host_port,
+                        &port.protocol,
+                        existing,
+                    ));
+                }
+            }
+        }
+
+        for port in ports {
+            let key = port.reservation_key(&self.node_name);
+            self.storage.put(key.as_bytes(), owner.as_bytes())?;
+            debug!(
+                "Host ports: reserved {}/{} for {}",
+                port.host_port, port.protocol, owner
+            );
+        }
+        Ok(())
+    }
+
+    /// Release every host port held by a pod, returning how many were freed
+    pub fn release(&self, namespace: &str, pod_name: &str) -> Result<usize> {
+        let owner = host_port_owner(namespace, pod_name);
+        let prefix = node_host_port_prefix(&self.node_name);
+
+        let mut released = 0;
+        for (key, value) in self.storage.scan(prefix.as_bytes())? {
+            if value == owner.as_bytes() {
+                self.storage.delete(&key)?;
+                released += 1;
+            }
+        }
+
+        if released > 0 {
+            debug!("Host ports: released {} port(s) of {}", released, owner);
+        }
+        Ok(released)
+    }
+}
+
+/// Forwards from `interface` to a pod for each of its host ports
+pub fn port_forwards(interface: &str, pod_ip: &str, ports: &[HostPort]) -> Vec<PortForward> {
+    ports
+        .iter()
+        .map(|p| PortForward {
+            interface: interface.to_string(),
+            host_ip: p.host_ip.clone(),
+            host_port: p.host_port,
+            protocol: p.protocol.to_lowercase(),
+            pod_ip: pod_ip.to_string(),
+            container_port: p.container_port,
+        })
+        .collect()
+}
+
+/// ipnat `rdr` rules implementing the given forwards
+pub fn ipnat_rules(forwards: &[PortForward]) -> String {
+    forwards
+        .iter()
+        .map(|f| {
+            let dest = match &f.host_ip {
+                Some(ip) => format!("{}/32", ip),
+                None => "0.0.0.0/0".to_string(),
+            };
+            format!(
+                "rdr {} {} port {} -> {} port {} {}\n",
+                f.interface, dest, f.host_port, f.pod_ip, f.container_port, f.protocol
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_storage::RedbBackend;
+    use tempfile::tempdir;
+
+    fn make_table() -> HostPortTable {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("hostports.redb")).unwrap());
+        std::mem::forget(dir);
+        HostPortTable::new(storage, "node1")
+    }
+
+    fn host_port(protocol: &str, port: u16) -> HostPort {
+        HostPort {
+            protocol: protocol.to_string(),
+            host_ip: None,
+            host_port: port,
+            container_port: 8080,
+        }
+    }
+
+    #[test]
+    fn test_reserve_conflict_and_release() {
+        let table = make_table();
+        let http = [host_port("TCP", 80)];
+
+        table.reserve("default", "web", &http).unwrap();
+        // Re-reserving for the same pod is fine
+        table.reserve("default", "web", &http).unwrap();
+
+        let err = table
+            .reserve(
+                "default",
+                "other",
+                &[host_port("TCP", 443), http[0].clone()],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RuntimeError::HostPortConflict { port: 80, .. }
+        ));
+        // Nothing from the failed request was reserved
+        table
+            .reserve("default", "third", &[host_port("TCP", 443)])
+            .unwrap();
+
+        assert_eq!(table.release("default", "web").unwrap(), 1);
+        table.reserve("default", "other", &http).unwrap();
+    }
+
+    #[test]
+    fn test_ipnat_rules() {
+        let mut ports = vec![host_port("TCP", 80), host_port("UDP", 53)];
+        ports[1].host_ip = Some("192.168.1.5".to_string());
+        ports[1].container_port = 5353;
+
+        let rules = ipnat_rules(&port_forwards("igb0", "10.88.0.5", &ports));
+        assert_eq!(
+            rules,
+            "rdr igb0 0.0.0.0/0 port 80 -> 10.88.0.5 port 8080 tcp\n\
+             rdr igb0 192.168.1.5/32 port 53 -> 10.88.0.5 port 5353 udp\n"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use crate::command::{exec, exec_unchecked};
+use crate::error::{Result, RuntimeError};
+use crate::network::ipam::parse_cidr;
+use crate::network::service_rules::NatRuleSet;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Host datalinks and IP interfaces the pod network is built from
+#[async_trait]
+pub trait HostLinks: Send + Sync {
+    /// Whether a datalink named `link` exists
+    async fn link_exists(&self, link: &str) -> Result<bool>;
+
+    /// Create the etherstub `name`
+    async fn create_etherstub(&self, name: &str) -> Result<()>;
+
+    /// Delete the etherstub `name`
+    async fn delete_etherstub(&self, name: &str) -> Result<()>;
+
+    /// Create the VNIC `vnic` over `link`
+    async fn create_vnic(&self, link: &str, vnic: &str) -> Result<()>;
+
+    /// Delete the VNIC `vnic`
+    async fn delete_vnic(&self, vnic: &str) -> Result<()>;
+
+    /// Plumb `interface` and give it the static address `address`
+    /// ("ip/prefix")
+    async fn create_address(&self, interface: &str, address: &str) -> Result<()>;
+
+    /// Unplumb `interface` along with its addresses
+    async fn delete_interface(&self, interface: &str) -> Result<()>;
+
+    /// Enable IPv4 forwarding between the host's interfaces
+    async fn enable_ipv4_forwarding(&self) -> Result<()>;
+
+    /// Interface of the host's default route
+    async fn default_route_interface(&self) -> Result<String>;
+}
+
+/// Links managed through the global zone's `dladm(8)`, `ipadm(8)`,
+/// `routeadm(8)` and `route(8)` commands
+pub struct DladmHostLinks;
+
+#[async_trait]
+impl HostLinks for DladmHostLinks {
+    async fn link_exists(&self, link: &str) -> Result<bool> {
+        Ok(exec_unchecked("dladm", &["show-link", link])
+            .await?
+            .exit_code
+            == 0)
+    }
+
+    async fn create_etherstub(&self, name: &str) -> Result<()> {
+        exec("dladm", &["create-etherstub", name]).await?;
+        Ok(())
+    }
+
+    async fn delete_etherstub(&self, name: &str) -> Result<()> {
+        exec("dladm", &["delete-etherstub", name]).await?;
+        Ok(())
+    }
+
+    async fn create_vnic(&self, link: &str, vnic: &str) -> Result<()> {
+        exec("dladm", &["create-vnic", "-l", link, vnic]).await?;
+        Ok(())
+    }
+
+    async fn delete_vnic(&self, vnic: &str) -> Result<()> {
+        exec("dladm", &["delete-vnic", vnic]).await?;
+        Ok(())
+    }
+
+    async fn create_address(&self, interface: &str, address: &str) -> Result<()> {
+        exec("ipadm", &["create-ip", interface]).await?;
+        let local = format!("local={}", address);
+        let addrobj = format!("{}/v4", interface);
+        exec(
+            "ipadm",
+            &["create-addr", "-T", "static", "-a", &local, &addrobj],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_interface(&self, interface: &str) -> Result<()> {
+        exec("ipadm", &["delete-ip", interface]).await?;
+        Ok(())
+    }
+
+    async fn enable_ipv4_forwarding(&self) -> Result<()> {
+        exec("routeadm", &["-u", "-e", "ipv4-forwarding"]).await?;
+        Ok(())
+    }
+
+    async fn default_route_interface(&self) -> Result<String> {
+        let output = exec("route", &["-n", "get", "default"]).await?;
+        output
+            .stdout
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("interface:"))
+            .map(|interface| interface.trim().to_string())
+            .ok_or_else(|| RuntimeError::network_error("The host has no default route"))
+    }
+}
+
+/// Configuration for the host network setup
+#[derive(Debug, Clone)]
+pub struct HostNetworkConfig {
+    /// Etherstub pod VNICs are created over
+    pub etherstub_name: String,
+    /// Node pod CIDR; the host VNIC takes its gateway address
+    pub pod_cidr: String,
+    /// Link pod egress is masqueraded on (default: the default route's)
+    pub egress_interface: Option<String>,
+}
+
+impl HostNetworkConfig {
+    pub fn new(etherstub_name: String, pod_cidr: String) -> Self {
+        Self {
+            etherstub_name,
+            pod_cidr,
+            egress_interface: None,
+        }
+    }
+
+    /// Name of the host VNIC holding the pods' gateway address
+    pub fn gateway_vnic(&self) -> String {
+        format!("{}_gw0", self.etherstub_name)
+    }
+}
+
+/// What [`HostNetwork::setup`] created, and so teardown removes
+#[derive(Debug, Default)]
+struct Created {
+    etherstub: bool,
+    gateway_vnic: bool,
+    nat_rules: Option<String>,
+}
+
+/// Wires the host for pod connectivity: the etherstub pods attach to, a
+/// host VNIC on it holding the gateway address of the pod CIDR, IPv4
+/// forwarding, and ipnat rules masquerading pod egress behind the egress
+/// link
+///
+/// Links that already exist are left as they are, and teardown removes only
+/// what setup created. IPv4 forwarding stays enabled after teardown.
+pub struct HostNetwork {
+    links: Arc<dyn HostLinks>,
+    nat: Arc<dyn NatRuleSet>,
+    config: HostNetworkConfig,
+    created: Mutex<Created>,
+}
+
+impl HostNetwork {
+    pub fn new(
+        links: Arc<dyn HostLinks>,
+        nat: Arc<dyn NatRuleSet>,
+        config: HostNetworkConfig,
+    ) -> Self {
+        Self {
+            links,
+            nat,
+            config,
+            created: Mutex::new(Created::default()),
+        }
+    }
+
+    /// Create whatever the pod network is missing
+    pub async fn setup(&self) -> Result<()> {
+        let cidr = parse_cidr(&self.config.pod_cidr)?;
+        let etherstub = &self.config.etherstub_name;
+        let gateway_vnic = self.config.gateway_vnic();
+        let mut created = self.created.lock().await;
+
+        if !self.links.link_exists(etherstub).await? {
+            self.links.create_etherstub(etherstub).await?;
+            created.etherstub = true;
+            info!("Created etherstub {}", etherstub);
+        }
+
+        if !self.links.link_exists(&gateway_vnic).await? {
+            self.links.create_vnic(etherstub, &gateway_vnic).await?;
+            created.gateway_vnic = true;
+            let address = format!("{}/{}", cidr.gateway, cidr.prefix_len);
+            self.links.create_address(&gateway_vnic, &address).await?;
+            info!("Created gateway VNIC {} with {}", gateway_vnic, address);
+        }
+
+        self.links.enable_ipv4_forwarding().await?;
+
+        let egress = match &self.config.egress_interface {
+            Some(interface) => interface.clone(),
+            None => self.links.default_route_interface().await?,
+        };
+        let rules = masquerade_rules(&egress, &self.config.pod_cidr);
+        self.nat.add(&rules).await?;
+        created.nat_rules = Some(rules);
+        info!(
+            "Masquerading egress of {} on {}",
+            self.config.pod_cidr, egress
+        );
+
+        Ok(())
+    }
+
+    /// Remove what `setup` created, in reverse order; failures are logged
+    /// and the remaining steps still run
+    pub async fn teardown(&self) {
+        let mut created = self.created.lock().await;
+        let gateway_vnic = self.config.gateway_vnic();
+
+        if let Some(rules) = created.nat_rules.take() {
+            if let Err(e) = self.nat.remove(&rules).await {
+                warn!("Failed to remove pod egress NAT rules: {}", e);
+            }
+        }
+
+        if std::mem::take(&mut created.gateway_vnic) {
+            if let Err(e) = self.links.delete_interface(&gateway_vnic).await {
+                warn!("Failed to unplumb {}: {}", gateway_vnic, e);
+            }
+            if let Err(e) = self.links.delete_vnic(&gateway_vnic).await {
+                warn!("Failed to delete gateway VNIC {}: {}", gateway_vnic, e);
+            }
+        }
+
+        if std::mem::take(&mut created.etherstub) {
+            if let Err(e) = self
+                .links
+                .delete_etherstub(&self.config.etherstub_name)
+                .await
+            {
+                warn!(
+                    "Failed to delete etherstub {} (pod VNICs may still use it): {}",
+                    self.config.etherstub_name, e
+                );
+            }
+        }
+
+        info!("Host network torn down");
+    }
+}
+
+/// ipnat rules masquerading traffic from `pod_cidr` leaving on `interface`
+/// behind the interface's address
+pub fn masquerade_rules(interface: &str, pod_cidr: &str) -> String {
+    format!(
+        "map {interface} {pod_cidr} -> 0/32 portmap tcp/udp auto\n\
+         map {interface} {pod_cidr} -> 0/32\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::sync::Mutex as StdMutex;
+
+    /// Records link and NAT changes instead of touching the host
+    #[derive(Default)]
+    struct RecordingHost {
+        existing: StdMutex<BTreeSet<String>>,
+        ops: StdMutex<Vec<String>>,
+    }
+
+    impl RecordingHost {
+        fn record(&self, op: String) -> Result<()> {
+            self.ops.lock().unwrap().push(op);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HostLinks for RecordingHost {
+        async fn link_exists(&self, link: &str) -> Result<bool> {
+            Ok(self.existing.lock().unwrap().contains(link))
+        }
+
+        async fn create_etherstub(&self, name: &str) -> Result<()> {
+            self.record(format!("create-etherstub {}", name))
+        }
+
+        async fn delete_etherstub(&self, name: &str) -> Result<()> {
+            self.record(format!("delete-etherstub {}", name))
+        }
+
+        async fn create_vnic(&self, link: &str, vnic: &str) -> Result<()> {
+            self.record(format!("create-vnic {} {}", link, vnic))
+        }
+
+        async fn delete_vnic(&self, vnic: &str) -> Result<()> {
+            self.record(format!("delete-vnic {}", vnic))
+        }
+
+        async fn create_address(&self, interface: &str, address: &str) -> Result<()> {
+            self.record(format!("create-addr {} {}", interface, address))
+        }
+
+        async fn delete_interface(&self, interface: &str) -> Result<()> {
+            self.record(format!("delete-ip {}", interface))
+        }
+
+        async fn enable_ipv4_forwarding(&self) -> Result<()> {
+            self.record("ipv4-forwarding".to_string())
+        }
+
+        async fn default_route_interface(&self) -> Result<String> {
+            Ok("net0".to_string())
+        }
+    }
+
+    #[async_trait]
+    impl NatRuleSet for RecordingHost {
+        async fn add(&self, rules: &str) -> Result<()> {
+            self.record(format!("ipnat add {}", rules.lines().count()))
+        }
+
+        async fn remove(&self, rules: &str) -> Result<()> {
+            self.record(format!("ipnat remove {}", rules.lines().count()))
+        }
+    }
+
+    fn host_network(host: &Arc<RecordingHost>) -> HostNetwork {
+        HostNetwork::new(
+            host.clone(),
+            host.clone(),
+            HostNetworkConfig::new("reddwarf0".to_string(), "10.88.0.0/24".to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_setup_and_teardown() {
+        let host = Arc::new(RecordingHost::default());
+        let network = host_network(&host);
+
+        network.setup().await.unwrap();
+        network.teardown().await;
+        // A second teardown has nothing left to remove
+        network.teardown().await;
+
+        assert_eq!(
+            *host.ops.lock().unwrap(),
+            vec![
+                "create-etherstub reddwarf0",
+                "create-vnic reddwarf0 reddwarf0_gw0",
+                "create-addr reddwarf0_gw0 10.88.0.1/24",
+                "ipv4-forwarding",
+                "ipnat add 2",
+                "ipnat remove 2",
+                "delete-ip reddwarf0_gw0",
+                "delete-vnic reddwarf0_gw0",
+                "delete-etherstub reddwarf0",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_existing_links_are_kept() {
+        let host = Arc::new(RecordingHost::default());
+        host.existing
+            .lock()
+            .unwrap()
+            .extend(["reddwarf0".to_string(), "reddwarf0_gw0".to_string()]);
+        let network = host_network(&host);
+
+        network.setup().await.unwrap();
+        network.teardown().await;
+
+        assert_eq!(
+            *host.ops.lock().unwrap(),
+            vec!["ipv4-forwarding", "ipnat add 2", "ipnat remove 2"]
+        );
+    }
+
+    #[test]
+    fn test_masquerade_rules() {
+        assert_eq!(
+            masquerade_rules("net0", "10.88.0.0/24"),
+            "map net0 10.88.0.0/24 -> 0/32 portmap tcp/udp auto\n\
+             map net0 10.88.0.0/24 -> 0/32\n"
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use crate::error::{Result, RuntimeError};
+use reddwarf_core::pod_ips::{pod_ip_key, pod_ip_owner, POD_IP_KEY_PREFIX};
 use reddwarf_storage::KVStore;
 use std::collections::BTreeMap;
 use std::net::Ipv4Addr;
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Parsed CIDR configuration
 #[derive(Debug, Clone)]
@@ -33,13 +34,17 @@ pub struct IpAllocation {
 /// Storage keys:
 /// - `ipam/_cidr` → the CIDR string (e.g. "10.88.0.0/16")
 /// - `ipam/alloc/{ip}` → `"{namespace}/{pod_name}"`
+///
+/// Allocations share their keys with the reservations the scheduler makes
+/// when it binds a pod with `reserve_pod_ips` on, so a reserved address is
+/// never handed to another pod.
 pub struct Ipam {
     storage: Arc<dyn KVStore>,
     cidr: CidrConfig,
 }
 
 const IPAM_CIDR_KEY: &[u8] = b"ipam/_cidr";
-const IPAM_ALLOC_PREFIX: &[u8] = b"ipam/alloc/";
+const IPAM_ALLOC_PREFIX: &[u8] = POD_IP_KEY_PREFIX.as_bytes();
 
 impl Ipam {
     /// Create a new IPAM instance, persisting the CIDR config
@@ -59,7 +64,7 @@ impl Ipam {
 
     /// Allocate an IP for a pod. Idempotent: returns existing allocation if one exists.
     pub fn allocate(&self, namespace: &str, pod_name: &str) -> Result<IpAllocation> {
-        let pod_key = format!("{}/{}", namespace, pod_name);
+        let pod_key = pod_ip_owner(namespace, pod_name);
 
         // Check if this pod already has an allocation
         let allocations = self.storage.scan(IPAM_ALLOC_PREFIX)?;
@@ -101,7 +106,7 @@ impl Ipam {
 
             if !allocated.contains(&candidate) {
                 // Allocate this IP
-                let alloc_key = format!("ipam/alloc/{}", candidate);
+                let alloc_key = pod_ip_key(candidate);
                 self.storage.put(alloc_key.as_bytes(), pod_key.as_bytes())?;
 
                 debug!("IPAM: allocated {} for {}", candidate, pod_key);
@@ -116,9 +121,50 @@ impl Ipam {
         }
     }
 
+    /// Allocate `reserved`, the address the scheduler reserved for a pod when
+    /// binding it. Falls back to [`Ipam::allocate`] when the address is not
+    /// in this node's CIDR or is held by another pod, as when it was reserved
+    /// against a pod CIDR the node no longer has.
+    pub fn allocate_reserved(
+        &self,
+        namespace: &str,
+        pod_name: &str,
+        reserved: Ipv4Addr,
+    ) -> Result<IpAllocation> {
+        let pod_key = pod_ip_owner(namespace, pod_name);
+        let alloc_key = pod_ip_key(reserved);
+        let in_range = reserved >= self.cidr.first_host && reserved < self.cidr.broadcast;
+        let usable = match self.storage.get(alloc_key.as_bytes())? {
+            Some(owner) => in_range && owner == pod_key.as_bytes(),
+            // Free, unless the pod already holds another address
+            None => {
+                in_range
+                    && !self
+                        .get_all_allocations()?
+                        .values()
+                        .any(|owner| *owner == pod_key)
+            }
+        };
+        if !usable {
+            warn!(
+                "IPAM: address {} reserved for {} is unavailable, allocating another",
+                reserved, pod_key
+            );
+            return self.allocate(namespace, pod_name);
+        }
+
+        self.storage.put(alloc_key.as_bytes(), pod_key.as_bytes())?;
+        debug!("IPAM: allocated reserved {} for {}", reserved, pod_key);
+        Ok(IpAllocation {
+            ip_address: reserved,
+            gateway: self.cidr.gateway,
+            prefix_len: self.cidr.prefix_len,
+        })
+    }
+
     /// Release the IP allocated to a pod
     pub fn release(&self, namespace: &str, pod_name: &str) -> Result<Option<Ipv4Addr>> {
-        let pod_key = format!("{}/{}", namespace, pod_name);
+        let pod_key = pod_ip_owner(namespace, pod_name);
 
         let allocations = self.storage.scan(IPAM_ALLOC_PREFIX)?;
         for (key, value) in &allocations {
@@ -314,6 +360,37 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_allocate_reserved() {
+        let ipam = make_test_ipam("10.88.0.0/24");
+        let reserved = Ipv4Addr::new(10, 88, 0, 9);
+
+        // The scheduler's reservation is recorded under the pod already
+        ipam.storage
+            .put(pod_ip_key(reserved).as_bytes(), b"default/pod-a")
+            .unwrap();
+        let alloc = ipam
+            .allocate_reserved("default", "pod-a", reserved)
+            .unwrap();
+        assert_eq!(alloc.ip_address, reserved);
+        assert_eq!(alloc.gateway, Ipv4Addr::new(10, 88, 0, 1));
+
+        // Held by another pod or outside the CIDR: another address is used
+        let alloc = ipam
+            .allocate_reserved("default", "pod-b", reserved)
+            .unwrap();
+        assert_eq!(alloc.ip_address, Ipv4Addr::new(10, 88, 0, 2));
+        let outside = Ipv4Addr::new(10, 99, 0, 5);
+        let alloc = ipam.allocate_reserved("default", "pod-c", outside).unwrap();
+        assert_eq!(alloc.ip_address, Ipv4Addr::new(10, 88, 0, 3));
+
+        // A free reserved address is taken
+        let free = Ipv4Addr::new(10, 88, 0, 20);
+        let alloc = ipam.allocate_reserved("default", "pod-d", free).unwrap();
+        assert_eq!(alloc.ip_address, free);
+        assert_eq!(ipam.release("default", "pod-d").unwrap(), Some(free));
+    }
+
     #[test]
     fn test_get_all_allocations() {
         let ipam = make_test_ipam("10.88.0.0/16");
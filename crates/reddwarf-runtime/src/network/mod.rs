@@ -1,8 +1,35 @@
+pub mod bandwidth;
+pub mod dns;
+pub mod egress_nat;
+pub mod endpoints;
+pub mod egress_lockdown;
+pub mod host_ports;
+pub mod host_setup;
 pub mod ipam;
+pub mod network_policy;
+pub mod node_cidr;
+pub mod node_ipam;
+pub mod routes;
+pub mod service_rules;
 pub mod types;
 
 pub use crate::types::{DirectNicConfig, EtherstubConfig, NetworkMode};
+pub use bandwidth::BandwidthLimits;
+pub use egress_nat::{EgressNatController, EgressNatControllerConfig, EgressNatPolicy};
+pub use egress_lockdown::{EgressLockdownController, EgressLockdownControllerConfig};
+pub use endpoints::{EndpointsController, EndpointsControllerConfig};
+pub use host_ports::HostPortTable;
+pub use host_setup::{DladmHostLinks, HostLinks, HostNetwork, HostNetworkConfig};
 pub use ipam::{CidrConfig, IpAllocation, Ipam};
+pub use network_policy::{
+    FilterRuleSet, IpfRuleSet, NetworkPolicyController, NetworkPolicyControllerConfig, PodFilter,
+};
+pub use node_cidr::NodeCidrAllocator;
+pub use node_ipam::{NodeIpamController, NodeIpamControllerConfig};
+pub use routes::{HostRouteTable, RouteDistributor, RouteDistributorConfig, RouteTable};
+pub use service_rules::{
+    IpnatRuleSet, NatRuleSet, ServiceRuleExporter, ServiceRuleExporterConfig,
+};
 
 /// Generate a VNIC name from pod namespace and name
 pub fn vnic_name_for_pod(namespace: &str, pod_name: &str) -> String {
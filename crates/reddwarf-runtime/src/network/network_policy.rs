@@ -0,0 +1,954 @@
+use crate::api_client::ApiClient;
+use crate::command::exec;
+use crate::controller::pod_zone_name;
+use crate::error::{Result, RuntimeError};
+use crate::network::ipam::parse_cidr;
+use crate::network::service_rules::write_atomic;
+use crate::network::vnic_name_for_pod;
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use k8s_openapi::api::networking::v1::{NetworkPolicy, NetworkPolicyPeer, NetworkPolicyPort};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use reddwarf_core::resources::selector_matches;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Header written at the top of the network policy ruleset file
+const RULESET_HEADER: &str = "# Generated by reddwarf from NetworkPolicies; do not edit\n";
+
+/// Packet filter rules loaded into the ipfilter instance of a zone
+#[async_trait]
+pub trait FilterRuleSet: Send + Sync {
+    /// Load `rules` (one rule per line) as all the rules of `zone`, in
+    /// place of any loaded before, which stay in force until the new ones
+    /// are loaded
+    async fn replace(&self, zone: &str, rules: &str) -> Result<()>;
+
+    /// Unload `rules` of `zone`, previously loaded with `replace`
+    async fn remove(&self, zone: &str, rules: &str) -> Result<()>;
+}
+
+/// Rules managed through the global zone's `ipf(8)` command, in the
+/// ipfilter instance the global zone controls for each exclusive-IP zone
+/// (`ipf -G <zone>`), so a pod cannot change its own rules. Rules are
+/// loaded into the instance's inactive list and swapped in, so the zone is
+/// never left without its old rules or half of its new ones.
+pub struct IpfRuleSet;
+
+impl IpfRuleSet {
+    async fn load(zone: &str, flag: Option<&str>, rules: &str) -> Result<()> {
+        static LOADS: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "reddwarf-{}-{}-{}.ipf",
+            std::process::id(),
+            LOADS.fetch_add(1, Ordering::Relaxed),
+            if flag == Some("-r") { "remove" } else { "load" }
+        ));
+        tokio::fs::write(&path, rules)
+            .await
+            .map_err(|e| RuntimeError::network_error(format!("{}: {}", path.display(), e)))?;
+
+        let file = path.to_string_lossy();
+        let mut args = vec!["-G", zone];
+        args.extend(flag);
+        args.extend(["-f", &file]);
+        let result = exec("ipf", &args).await;
+        let _ = tokio::fs::remove_file(&path).await;
+        result?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FilterRuleSet for IpfRuleSet {
+    async fn replace(&self, zone: &str, rules: &str) -> Result<()> {
+        // Filtering is off in a zone's instance until enabled
+        exec("ipf", &["-G", zone, "-E"]).await?;
+        exec("ipf", &["-G", zone, "-I", "-Fa"]).await?;
+        Self::load(zone, Some("-I"), rules).await?;
+        exec("ipf", &["-G", zone, "-s"]).await?;
+        // The old rules are inactive now
+        exec("ipf", &["-G", zone, "-I", "-Fa"]).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, zone: &str, rules: &str) -> Result<()> {
+        Self::load(zone, Some("-r"), rules).await
+    }
+}
+
+/// Traffic one rule of a NetworkPolicy allows: from or to `peer` (any
+/// address when `None`), over `protocol` (any when `None`) and, for TCP,
+/// UDP and SCTP, to the inclusive range `ports` (any port when `None`)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Allow {
+    peer: Option<String>,
+    protocol: Option<String>,
+    ports: Option<(i32, i32)>,
+}
+
+impl Allow {
+    /// The ipf rule passing this traffic `direction` (`in` or `out`) on
+    /// `vnic`
+    fn render(&self, direction: &str, vnic: &str) -> String {
+        let mut rule = format!("pass {} quick on {}", direction, vnic);
+        if let Some(protocol) = &self.protocol {
+            rule.push_str(&format!(" proto {}", protocol));
+        }
+        let peer = self.peer.as_deref().unwrap_or("any");
+        let (from, to) = if direction == "in" {
+            (peer, "any")
+        } else {
+            ("any", peer)
+        };
+        rule.push_str(&format!(" from {} to {}", from, to));
+        match self.ports {
+            Some((port, end)) if port == end => rule.push_str(&format!(" port = {}", port)),
+            Some((port, end)) => rule.push_str(&format!(" port {}:{}", port, end)),
+            None => {}
+        }
+        rule.push_str(" keep state");
+        rule
+    }
+}
+
+/// What the NetworkPolicies selecting a pod allow it to receive and send;
+/// a direction no policy isolates is `None` and allows everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PodFilter {
+    ingress: Option<BTreeSet<Allow>>,
+    egress: Option<BTreeSet<Allow>>,
+}
+
+impl PodFilter {
+    /// The filter `policies` put on `pod`, resolving their pod and
+    /// namespace selectors against `pods` and `namespaces`; `None` when no
+    /// policy selects the pod
+    pub fn for_pod<'a>(
+        pod: &'a Pod,
+        policies: &[NetworkPolicy],
+        pods: &'a [Pod],
+        namespaces: &'a [Namespace],
+    ) -> Option<Self> {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let mut filter = Self::default();
+        for policy in policies {
+            let Some(spec) = policy.spec.as_ref() else {
+                continue;
+            };
+            if policy.metadata.namespace.as_deref().unwrap_or("default") != namespace
+                || !selector_matches(&spec.pod_selector, pod.metadata.labels.as_ref())
+            {
+                continue;
+            }
+            let peers = Peers {
+                namespace,
+                pods,
+                namespaces,
+            };
+
+            // Without policyTypes a policy isolates ingress, and egress
+            // when it has egress rules
+            let types = spec.policy_types.clone().unwrap_or_else(|| {
+                let mut types = vec!["Ingress".to_string()];
+                if spec.egress.as_ref().is_some_and(|e| !e.is_empty()) {
+                    types.push("Egress".to_string());
+                }
+                types
+            });
+            if types.iter().any(|t| t == "Ingress") {
+                let allowed = filter.ingress.get_or_insert_with(BTreeSet::new);
+                for rule in spec.ingress.iter().flatten() {
+                    allowed.extend(peers.allow(
+                        rule.from.as_deref(),
+                        rule.ports.as_deref(),
+                        |_| Some(pod),
+                    ));
+                }
+            }
+            if types.iter().any(|t| t == "Egress") {
+                let allowed = filter.egress.get_or_insert_with(BTreeSet::new);
+                for rule in spec.egress.iter().flatten() {
+                    allowed.extend(peers.allow(
+                        rule.to.as_deref(),
+                        rule.ports.as_deref(),
+                        |peer| peer,
+                    ));
+                }
+            }
+        }
+        (filter.ingress.is_some() || filter.egress.is_some()).then_some(filter)
+    }
+
+    /// ipf rules enforcing this filter on `vnic`, the pod's link as seen in
+    /// its zone
+    ///
+    /// Each isolated direction passes what it allows, keeping state so
+    /// replies get through, and blocks the rest. Traffic of a direction
+    /// that is not isolated is passed with state as well, so replies to it
+    /// aren't blocked by the other direction.
+    pub fn render(&self, vnic: &str) -> Vec<String> {
+        let mut rules = Vec::new();
+        for (direction, allowed) in [("in", &self.ingress), ("out", &self.egress)] {
+            match allowed {
+                Some(allowed) => {
+                    rules.extend(allowed.iter().map(|allow| allow.render(direction, vnic)));
+                    rules.push(format!("block {} quick on {} all", direction, vnic));
+                }
+                None => rules.push(format!(
+                    "pass {} quick on {} all keep state",
+                    direction, vnic
+                )),
+            }
+        }
+        rules
+    }
+}
+
+/// Where the peers of a policy's rules are looked up
+struct Peers<'a> {
+    /// Namespace of the policy
+    namespace: &'a str,
+    pods: &'a [Pod],
+    namespaces: &'a [Namespace],
+}
+
+impl<'a> Peers<'a> {
+    /// Traffic a rule allowing `ports` of `peers` admits; named ports are
+    /// looked up on the pod `port_owner` returns for each peer, and not
+    /// matched when it returns none
+    fn allow(
+        &self,
+        peers: Option<&[NetworkPolicyPeer]>,
+        ports: Option<&[NetworkPolicyPort]>,
+        port_owner: impl Fn(Option<&'a Pod>) -> Option<&'a Pod>,
+    ) -> Vec<Allow> {
+        let addresses: Vec<(Option<String>, Option<&'a Pod>)> = match peers {
+            Some(peers) if !peers.is_empty() => peers
+                .iter()
+                .flat_map(|peer| self.resolve(peer))
+                .map(|(cidr, pod)| (Some(cidr), pod))
+                .collect(),
+            _ => vec![(None, None)],
+        };
+
+        let mut allowed = Vec::new();
+        for (peer, pod) in addresses {
+            let owner = port_owner(pod);
+            match ports {
+                Some(ports) if !ports.is_empty() => {
+                    allowed.extend(ports.iter().filter_map(|port| {
+                        let (protocol, ports) = resolve_port(port, owner)?;
+                        Some(Allow {
+                            peer: peer.clone(),
+                            protocol: Some(protocol),
+                            ports,
+                        })
+                    }))
+                }
+                _ => allowed.push(Allow {
+                    peer,
+                    protocol: None,
+                    ports: None,
+                }),
+            }
+        }
+        allowed
+    }
+
+    /// CIDRs of `peer`, with the pod behind each pod address
+    fn resolve(&self, peer: &NetworkPolicyPeer) -> Vec<(String, Option<&'a Pod>)> {
+        if let Some(block) = &peer.ip_block {
+            let excepts: Vec<(u32, u8)> = block
+                .except
+                .iter()
+                .flatten()
+                .filter_map(|cidr| ipv4_prefix(cidr))
+                .collect();
+            let Some(cidr) = ipv4_prefix(&block.cidr) else {
+                debug!("Skipping ipBlock {}, which is not IPv4", block.cidr);
+                return Vec::new();
+            };
+            return subtract_prefixes(cidr, &excepts)
+                .into_iter()
+                .map(|(network, len)| (format!("{}/{}", Ipv4Addr::from(network), len), None))
+                .collect();
+        }
+
+        let namespaces: BTreeSet<&str> = match &peer.namespace_selector {
+            Some(selector) => self
+                .namespaces
+                .iter()
+                .filter(|ns| selector_matches(selector, ns.metadata.labels.as_ref()))
+                .filter_map(|ns| ns.metadata.name.as_deref())
+                .collect(),
+            None => BTreeSet::from([self.namespace]),
+        };
+        self.pods
+            .iter()
+            .filter(|pod| {
+                namespaces.contains(pod.metadata.namespace.as_deref().unwrap_or("default"))
+            })
+            .filter(|pod| {
+                peer.pod_selector
+                    .as_ref()
+                    .is_none_or(|selector| selector_matches(selector, pod.metadata.labels.as_ref()))
+            })
+            .filter_map(|pod| {
+                let ip = pod.status.as_ref()?.pod_ip.as_deref()?;
+                Some((format!("{}/32", ip), Some(pod)))
+            })
+            .collect()
+    }
+}
+
+/// Lowercase protocol and port range of `port`, resolving a named port on
+/// `owner`'s containers; `None` when the name cannot be resolved
+fn resolve_port(
+    port: &NetworkPolicyPort,
+    owner: Option<&Pod>,
+) -> Option<(String, Option<(i32, i32)>)> {
+    let protocol = port.protocol.as_deref().unwrap_or("TCP");
+    let ports = match &port.port {
+        None => None,
+        Some(IntOrString::Int(number)) => Some((*number, port.end_port.unwrap_or(*number))),
+        Some(IntOrString::String(name)) => {
+            let number = owner?
+                .spec
+                .as_ref()?
+                .containers
+                .iter()
+                .flat_map(|c| c.ports.iter().flatten())
+                .find(|p| {
+                    p.name.as_deref() == Some(name.as_str())
+                        && p.protocol.as_deref().unwrap_or("TCP") == protocol
+                })?
+                .container_port;
+            Some((number, number))
+        }
+    };
+    Some((protocol.to_lowercase(), ports))
+}
+
+/// Network and prefix length of an IPv4 CIDR, with host bits cleared
+fn ipv4_prefix(cidr: &str) -> Option<(u32, u8)> {
+    let parsed = parse_cidr(cidr).ok()?;
+    Some((
+        u32::from(parsed.network) & prefix_mask(parsed.prefix_len),
+        parsed.prefix_len,
+    ))
+}
+
+fn prefix_mask(len: u8) -> u32 {
+    u32::MAX.checked_shl(32 - u32::from(len)).unwrap_or(0)
+}
+
+/// The prefixes covering `prefix` except the addresses in `excepts`
+fn subtract_prefixes(prefix: (u32, u8), excepts: &[(u32, u8)]) -> Vec<(u32, u8)> {
+    let (network, len) = prefix;
+    let contains = |(outer, outer_len): (u32, u8), (inner, inner_len): (u32, u8)| {
+        outer_len <= inner_len && inner & prefix_mask(outer_len) == outer
+    };
+    if excepts.iter().any(|e| contains(*e, prefix)) {
+        return Vec::new();
+    }
+    if !excepts.iter().any(|e| contains(prefix, *e)) || len == 32 {
+        return vec![prefix];
+    }
+    let half = 1u32 << (31 - u32::from(len));
+    let mut prefixes = subtract_prefixes((network, len + 1), excepts);
+    prefixes.extend(subtract_prefixes((network | half, len + 1), excepts));
+    prefixes
+}
+
+/// Rules of one pod, loaded and unloaded together so they keep their order
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuleBlock {
+    /// `namespace/name` of the pod
+    pod: String,
+    rules: Vec<String>,
+}
+
+/// Configuration for the network policy controller
+#[derive(Debug, Clone)]
+pub struct NetworkPolicyControllerConfig {
+    /// Name of this node; only its pods are filtered
+    pub node_name: String,
+    /// Ruleset file; always holds the rules currently loaded
+    pub rules_path: PathBuf,
+    /// How often policies, pods and namespaces are re-read
+    pub sync_interval: Duration,
+}
+
+impl NetworkPolicyControllerConfig {
+    pub fn new(node_name: String, rules_path: PathBuf) -> Self {
+        Self {
+            node_name,
+            rules_path,
+            sync_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Enforces NetworkPolicies on this node's pods with ipfilter rules on each
+/// pod's VNIC
+///
+/// Pods in the etherstub reach each other unfiltered until a policy selects
+/// them. The rules of a selected pod are loaded into its zone's
+/// global-zone-controlled ipfilter instance as one block, replaced whole
+/// when the policies, or the pods and namespaces they select, change. Like
+/// the Service rules the ruleset file is rewritten after every step so it
+/// always matches what is loaded.
+pub struct NetworkPolicyController {
+    api_client: Arc<ApiClient>,
+    filter: Arc<dyn FilterRuleSet>,
+    config: NetworkPolicyControllerConfig,
+}
+
+impl NetworkPolicyController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        filter: Arc<dyn FilterRuleSet>,
+        config: NetworkPolicyControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            filter,
+            config,
+        }
+    }
+
+    /// Run the sync loop until cancelled
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting network policy controller (ruleset: {}, interval: {:?})",
+            self.config.rules_path.display(),
+            self.config.sync_interval
+        );
+
+        let mut interval = tokio::time::interval(self.config.sync_interval);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Network policy controller shutting down");
+                    return Ok(());
+                }
+                _ = interval.tick() => {
+                    if let Err(e) = self.sync().await {
+                        error!("Network policy sync failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read policies, pods and namespaces, and reconcile the loaded rules
+    /// of this node's pods against them
+    pub async fn sync(&self) -> Result<()> {
+        let policies: Vec<NetworkPolicy> = self
+            .list("/apis/networking.k8s.io/v1/networkpolicies")
+            .await?;
+        let pods: Vec<Pod> = self.list("/api/v1/pods").await?;
+        let namespaces: Vec<Namespace> = self.list("/api/v1/namespaces").await?;
+
+        let mut desired = BTreeMap::new();
+        for pod in &pods {
+            if pod.spec.as_ref().and_then(|s| s.node_name.as_deref())
+                != Some(self.config.node_name.as_str())
+                || !is_filterable(pod)
+            {
+                continue;
+            }
+            let Some(filter) = PodFilter::for_pod(pod, &policies, &pods, &namespaces) else {
+                continue;
+            };
+            let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+            let name = pod.metadata.name.as_deref().unwrap_or_default();
+            let block = RuleBlock {
+                pod: format!("{}/{}", namespace, name),
+                rules: filter.render(&vnic_name_for_pod(namespace, name)),
+            };
+            desired.insert(pod_zone_name(namespace, name), block);
+        }
+        self.apply(&desired).await
+    }
+
+    async fn list<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let body = self.api_client.get_json(path).await?;
+        Ok(body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect())
+    }
+
+    /// Bring the loaded rule blocks from the ruleset file's contents to
+    /// `desired`, keyed by zone. A changed block replaces the old one whole,
+    /// which stays in force if the new one fails to load; the sync then
+    /// fails once the other blocks are loaded, to be retried. The block of a
+    /// pod that is gone is forgotten even if its zone can no longer be
+    /// reached to unload it.
+    async fn apply(&self, desired: &BTreeMap<String, RuleBlock>) -> Result<()> {
+        let path = &self.config.rules_path;
+        let mut loaded = match tokio::fs::read_to_string(path).await {
+            Ok(current) => parse_blocks(&current),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(RuntimeError::network_error(format!(
+                    "{}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let gone: Vec<String> = loaded
+            .keys()
+            .filter(|zone| !desired.contains_key(*zone))
+            .cloned()
+            .collect();
+        for zone in gone {
+            let block = &loaded[&zone];
+            match self.filter.remove(&zone, &join_rules(&block.rules)).await {
+                Ok(()) => debug!("Unloaded network policy rules of {}", block.pod),
+                Err(e) => debug!("Forgetting network policy rules of {}: {}", block.pod, e),
+            }
+            loaded.remove(&zone);
+            write_atomic(path, &render_blocks(&loaded)).await?;
+        }
+
+        let mut failure = None;
+        for (zone, block) in desired {
+            if loaded.get(zone) == Some(block) {
+                continue;
+            }
+            match self.filter.replace(zone, &join_rules(&block.rules)).await {
+                Ok(()) => {
+                    info!("Loaded network policy rules of {}", block.pod);
+                    loaded.insert(zone.clone(), block.clone());
+                    write_atomic(path, &render_blocks(&loaded)).await?;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to load network policy rules of {}: {}",
+                        block.pod, e
+                    );
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether `pod` runs with an address its zone's rules can filter
+fn is_filterable(pod: &Pod) -> bool {
+    pod.metadata.deletion_timestamp.is_none()
+        && pod
+            .status
+            .as_ref()
+            .is_some_and(|s| s.phase.as_deref() == Some("Running") && s.pod_ip.is_some())
+}
+
+fn join_rules(rules: &[String]) -> String {
+    rules.iter().map(|r| format!("{}\n", r)).collect()
+}
+
+/// The ruleset file holding `blocks`, each headed by a comment naming its
+/// pod and zone
+fn render_blocks(blocks: &BTreeMap<String, RuleBlock>) -> String {
+    let mut contents = RULESET_HEADER.to_string();
+    for (zone, block) in blocks {
+        contents.push_str(&format!("# {} {}\n", block.pod, zone));
+        contents.push_str(&join_rules(&block.rules));
+    }
+    contents
+}
+
+/// Rule blocks of a ruleset file written by [`render_blocks`]
+fn parse_blocks(contents: &str) -> BTreeMap<String, RuleBlock> {
+    let mut blocks = BTreeMap::new();
+    let mut current: Option<(String, RuleBlock)> = None;
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut fields = comment.split_whitespace();
+            if let (Some(pod), Some(zone), None) = (fields.next(), fields.next(), fields.next()) {
+                blocks.extend(current.take());
+                let block = RuleBlock {
+                    pod: pod.to_string(),
+                    rules: Vec::new(),
+                };
+                current = Some((zone.to_string(), block));
+            }
+            continue;
+        }
+        if let Some((_, block)) = current.as_mut() {
+            block.rules.push(line.to_string());
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::MockApiServer;
+    use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec, PodStatus};
+    use k8s_openapi::api::networking::v1::{
+        IPBlock, NetworkPolicyEgressRule, NetworkPolicyIngressRule, NetworkPolicySpec,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+    use std::sync::Mutex as StdMutex;
+    use tempfile::tempdir;
+
+    /// Records loaded rules instead of touching the host, failing to
+    /// unload rules of `unreachable` and to load those of `failing`
+    #[derive(Default)]
+    struct RecordingFilter {
+        unreachable: Option<String>,
+        failing: StdMutex<Option<String>>,
+        ops: StdMutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl FilterRuleSet for RecordingFilter {
+        async fn replace(&self, zone: &str, rules: &str) -> Result<()> {
+            if self.failing.lock().unwrap().as_deref() == Some(zone) {
+                return Err(RuntimeError::network_error("ipf: rule load failed"));
+            }
+            self.ops
+                .lock()
+                .unwrap()
+                .push(format!("replace {}\n{}", zone, rules));
+            Ok(())
+        }
+
+        async fn remove(&self, zone: &str, rules: &str) -> Result<()> {
+            if self.unreachable.as_deref() == Some(zone) {
+                return Err(RuntimeError::network_error("ipf: zone not found"));
+            }
+            self.ops
+                .lock()
+                .unwrap()
+                .push(format!("remove {}\n{}", zone, rules));
+            Ok(())
+        }
+    }
+
+    fn labels(pairs: &[(&str, &str)]) -> Option<BTreeMap<String, String>> {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    fn selector(pairs: &[(&str, &str)]) -> LabelSelector {
+        LabelSelector {
+            match_labels: labels(pairs),
+            ..Default::default()
+        }
+    }
+
+    fn make_pod(namespace: &str, name: &str, app: &str, ip: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some(namespace.to_string());
+        pod.metadata.labels = labels(&[("app", app)]);
+        pod.spec = Some(PodSpec {
+            node_name: Some("node1".to_string()),
+            containers: vec![Container {
+                name: "main".to_string(),
+                ports: Some(vec![ContainerPort {
+                    name: Some("http".to_string()),
+                    container_port: 8080,
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            pod_ip: Some(ip.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn make_namespace(name: &str, team: &str) -> Namespace {
+        let mut namespace = Namespace::default();
+        namespace.metadata.name = Some(name.to_string());
+        namespace.metadata.labels = labels(&[("team", team)]);
+        namespace
+    }
+
+    fn make_policy(namespace: &str, app: &str, spec: NetworkPolicySpec) -> NetworkPolicy {
+        let mut policy = NetworkPolicy::default();
+        policy.metadata.name = Some(format!("{}-policy", app));
+        policy.metadata.namespace = Some(namespace.to_string());
+        policy.spec = Some(NetworkPolicySpec {
+            pod_selector: selector(&[("app", app)]),
+            ..spec
+        });
+        policy
+    }
+
+    fn tcp_port(port: IntOrString) -> NetworkPolicyPort {
+        NetworkPolicyPort {
+            port: Some(port),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pod_filter_from_policies() {
+        let web = make_pod("default", "web", "web", "10.88.0.5");
+        let api = make_pod("default", "api", "api", "10.88.0.6");
+        let monitor = make_pod("ops", "monitor", "monitor", "10.88.0.7");
+        let db = make_pod("default", "db", "db", "10.88.0.8");
+        let pods = vec![web.clone(), api.clone(), monitor.clone(), db.clone()];
+        let namespaces = vec![
+            make_namespace("default", "apps"),
+            make_namespace("ops", "sre"),
+        ];
+
+        let policies = vec![
+            // web accepts its named port from api and anything from the
+            // ops team's namespaces
+            make_policy(
+                "default",
+                "web",
+                NetworkPolicySpec {
+                    ingress: Some(vec![
+                        NetworkPolicyIngressRule {
+                            from: Some(vec![NetworkPolicyPeer {
+                                pod_selector: Some(selector(&[("app", "api")])),
+                                ..Default::default()
+                            }]),
+                            ports: Some(vec![tcp_port(IntOrString::String("http".to_string()))]),
+                        },
+                        NetworkPolicyIngressRule {
+                            from: Some(vec![NetworkPolicyPeer {
+                                namespace_selector: Some(selector(&[("team", "sre")])),
+                                ..Default::default()
+                            }]),
+                            ports: None,
+                        },
+                    ]),
+                    ..Default::default()
+                },
+            ),
+            // api may only reach db on 5432, and a range outside the
+            // cluster minus one subnet
+            make_policy(
+                "default",
+                "api",
+                NetworkPolicySpec {
+                    policy_types: Some(vec!["Egress".to_string()]),
+                    egress: Some(vec![
+                        NetworkPolicyEgressRule {
+                            to: Some(vec![NetworkPolicyPeer {
+                                pod_selector: Some(selector(&[("app", "db")])),
+                                ..Default::default()
+                            }]),
+                            ports: Some(vec![tcp_port(IntOrString::Int(5432))]),
+                        },
+                        NetworkPolicyEgressRule {
+                            to: Some(vec![NetworkPolicyPeer {
+                                ip_block: Some(IPBlock {
+                                    cidr: "192.168.0.0/23".to_string(),
+                                    except: Some(vec!["192.168.1.0/24".to_string()]),
+                                }),
+                                ..Default::default()
+                            }]),
+                            ports: Some(vec![NetworkPolicyPort {
+                                protocol: Some("UDP".to_string()),
+                                port: Some(IntOrString::Int(5000)),
+                                end_port: Some(5010),
+                            }]),
+                        },
+                    ]),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let filter = PodFilter::for_pod(&web, &policies, &pods, &namespaces).unwrap();
+        assert_eq!(
+            filter.render("vnic_default_web"),
+            [
+                "pass in quick on vnic_default_web proto tcp from 10.88.0.6/32 to any port = 8080 keep state",
+                "pass in quick on vnic_default_web from 10.88.0.7/32 to any keep state",
+                "block in quick on vnic_default_web all",
+                "pass out quick on vnic_default_web all keep state",
+            ]
+        );
+
+        let filter = PodFilter::for_pod(&api, &policies, &pods, &namespaces).unwrap();
+        assert_eq!(
+            filter.render("vnic_default_api"),
+            [
+                "pass in quick on vnic_default_api all keep state",
+                "pass out quick on vnic_default_api proto tcp from any to 10.88.0.8/32 port = 5432 keep state",
+                "pass out quick on vnic_default_api proto udp from any to 192.168.0.0/24 port 5000:5010 keep state",
+                "block out quick on vnic_default_api all",
+            ]
+        );
+
+        // Pods no policy selects are not filtered
+        assert!(PodFilter::for_pod(&db, &policies, &pods, &namespaces).is_none());
+        assert!(PodFilter::for_pod(&monitor, &policies, &pods, &namespaces).is_none());
+
+        // A policy without rules denies everything it isolates
+        let deny = vec![make_policy("default", "db", NetworkPolicySpec::default())];
+        let filter = PodFilter::for_pod(&db, &deny, &pods, &namespaces).unwrap();
+        assert_eq!(
+            filter.render("vnic_default_db"),
+            [
+                "block in quick on vnic_default_db all",
+                "pass out quick on vnic_default_db all keep state",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subtract_prefixes() {
+        let prefix = ipv4_prefix("10.0.0.0/8").unwrap();
+        let excepts = [
+            ipv4_prefix("10.128.0.0/9").unwrap(),
+            ipv4_prefix("10.1.0.0/16").unwrap(),
+        ];
+        let rendered: Vec<String> = subtract_prefixes(prefix, &excepts)
+            .into_iter()
+            .map(|(network, len)| format!("{}/{}", Ipv4Addr::from(network), len))
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "10.0.0.0/16",
+                "10.2.0.0/15",
+                "10.4.0.0/14",
+                "10.8.0.0/13",
+                "10.16.0.0/12",
+                "10.32.0.0/11",
+                "10.64.0.0/10",
+            ]
+        );
+        assert!(subtract_prefixes(excepts[1], &[prefix]).is_empty());
+        assert_eq!(subtract_prefixes(prefix, &[]), vec![prefix]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_replaces_changed_blocks() {
+        let dir = tempdir().unwrap();
+        let filter = Arc::new(RecordingFilter {
+            unreachable: Some("gone-zone".to_string()),
+            ..Default::default()
+        });
+        let controller = NetworkPolicyController::new(
+            Arc::new(ApiClient::new("http://127.0.0.1:6443")),
+            filter.clone(),
+            NetworkPolicyControllerConfig::new(
+                "node1".to_string(),
+                dir.path().join("network-policy.ipf"),
+            ),
+        );
+        let block = |pod: &str, rules: &[&str]| RuleBlock {
+            pod: pod.to_string(),
+            rules: rules.iter().map(|r| r.to_string()).collect(),
+        };
+
+        let desired = BTreeMap::from([
+            (
+                "web-zone".to_string(),
+                block("default/web", &["block in quick on vnic0 all"]),
+            ),
+            (
+                "gone-zone".to_string(),
+                block("default/gone", &["block in quick on vnic1 all"]),
+            ),
+        ]);
+        controller.apply(&desired).await.unwrap();
+        // Unchanged rules load nothing
+        controller.apply(&desired).await.unwrap();
+        assert_eq!(filter.ops.lock().unwrap().len(), 2);
+
+        // A changed block replaces the old one whole, and the block of a
+        // pod that is gone is forgotten though its zone cannot be reached
+        let desired = BTreeMap::from([(
+            "web-zone".to_string(),
+            block("default/web", &["block out quick on vnic0 all"]),
+        )]);
+        controller.apply(&desired).await.unwrap();
+        let ops = filter.ops.lock().unwrap().clone();
+        assert_eq!(ops.len(), 3);
+        assert!(ops[2].starts_with("replace web-zone\n") && ops[2].contains("block out"));
+
+        let on_disk = std::fs::read_to_string(dir.path().join("network-policy.ipf")).unwrap();
+        assert_eq!(parse_blocks(&on_disk), desired);
+    }
+
+    #[tokio::test]
+    async fn test_apply_keeps_old_rules_when_loading_fails() {
+        let dir = tempdir().unwrap();
+        let api = MockApiServer::start().await;
+        let filter = Arc::new(RecordingFilter::default());
+        let controller = NetworkPolicyController::new(
+            api.client(),
+            filter.clone(),
+            NetworkPolicyControllerConfig::new(
+                "node1".to_string(),
+                dir.path().join("network-policy.ipf"),
+            ),
+        );
+        let block = |pod: &str, rules: &[&str]| RuleBlock {
+            pod: pod.to_string(),
+            rules: rules.iter().map(|r| r.to_string()).collect(),
+        };
+        let web = |rule: &str| ("web-zone".to_string(), block("default/web", &[rule]));
+        let loaded = || {
+            let on_disk = std::fs::read_to_string(dir.path().join("network-policy.ipf")).unwrap();
+            parse_blocks(&on_disk)
+        };
+
+        let old = BTreeMap::from([web("pass in quick on vnic0 all keep state")]);
+        controller.apply(&old).await.unwrap();
+
+        // The rule runner fails on the web zone: its old rules stay loaded,
+        // the db zone's load anyway and the sync fails, to be retried
+        *filter.failing.lock().unwrap() = Some("web-zone".to_string());
+        let desired = BTreeMap::from([
+            web("block in quick on vnic0 all"),
+            (
+                "db-zone".to_string(),
+                block("default/db", &["block in quick on vnic1 all"]),
+            ),
+        ]);
+        let err = controller.apply(&desired).await.unwrap_err();
+        assert!(err.to_string().contains("rule load failed"));
+        let ops = filter.ops.lock().unwrap().clone();
+        assert_eq!(ops.len(), 2);
+        assert!(ops[1].starts_with("replace db-zone\n"));
+        assert_eq!(loaded()["web-zone"], old["web-zone"]);
+        assert_eq!(loaded().len(), 2);
+
+        *filter.failing.lock().unwrap() = None;
+        controller.apply(&desired).await.unwrap();
+        assert_eq!(loaded(), desired);
+        assert!(api.requests().is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::{Result, RuntimeError};
+use crate::network::ipam::{parse_cidr, CidrConfig};
+use reddwarf_storage::KVStore;
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Allocates per-node pod CIDRs from a cluster-wide range, backed by a KVStore
+///
+/// Storage keys:
+/// - `ipam/nodes/_cidr` → the cluster CIDR and node prefix (e.g. "10.88.0.0/16:24")
+/// - `ipam/nodes/alloc/{network}` → node name owning that subnet
+pub struct NodeCidrAllocator {
+    storage: Arc<dyn KVStore>,
+    cluster: CidrConfig,
+    node_prefix_len: u8,
+}
+
+const NODE_CIDR_KEY: &[u8] = b"ipam/nodes/_cidr";
+const NODE_CIDR_ALLOC_PREFIX: &[u8] = b"ipam/nodes/alloc/";
+
+impl NodeCidrAllocator {
+    /// Create an allocator handing out `/node_prefix_len` subnets of `cluster_cidr`
+    pub fn new(storage: Arc<dyn KVStore>, cluster_cidr: &str, node_prefix_len: u8) -> Result<Self> {
+        let cluster = parse_cidr(cluster_cidr)?;
+
+        // Leave room for the network, gateway, at least one pod and broadcast
+        if node_prefix_len < cluster.prefix_len || node_prefix_len > 30 {
+            return Err(RuntimeError::invalid_config(
+                format!(
+                    "Node CIDR prefix /{} does not fit cluster CIDR '{}'",
+                    node_prefix_len, cluster_cidr
+                ),
+                format!("Use a node prefix between /{} and /30", cluster.prefix_len),
+            ));
+        }
+
+        storage.put(
+            NODE_CIDR_KEY,
+            format!("{}:{}", cluster_cidr, node_prefix_len).as_bytes(),
+        )?;
+
+        debug!(
+            "Node CIDR allocator initialized: cluster={}/{}, node prefix=/{}",
+            cluster.network, cluster.prefix_len, node_prefix_len
+        );
+
+        Ok(Self {
+            storage,
+            cluster,
+            node_prefix_len,
+        })
+    }
+
+    /// Allocate a pod CIDR for a node. Idempotent: returns the node's existing subnet.
+    pub fn allocate(&self, node_name: &str) -> Result<String> {
+        let allocations = self.get_all_allocations()?;
+        if let Some((network, _)) = allocations.iter().find(|(_, node)| *node == node_name) {
+            debug!(
+                "Node CIDR: returning existing allocation {} for {}",
+                network, node_name
+            );
+            return Ok(self.format_subnet(*network));
+        }
+
+        let subnet_size = 1u64 << (32 - self.node_prefix_len);
+        let cluster_start = u32::from(self.cluster.network) as u64;
+        let cluster_end = u32::from(self.cluster.broadcast) as u64;
+
+        let mut candidate = cluster_start;
+        while candidate + subnet_size - 1 <= cluster_end {
+            let network = Ipv4Addr::from(candidate as u32);
+            if !allocations.contains_key(&network) {
+                let key = format!("ipam/nodes/alloc/{}", network);
+                self.storage.put(key.as_bytes(), node_name.as_bytes())?;
+
+                let subnet = self.format_subnet(network);
+                debug!("Node CIDR: allocated {} for {}", subnet, node_name);
+                return Ok(subnet);
+            }
+            candidate += subnet_size;
+        }
+
+        Err(RuntimeError::IpamPoolExhausted {
+            cidr: format!("{}/{}", self.cluster.network, self.cluster.prefix_len),
+        })
+    }
+
+    /// Record `cidr` as belonging to `node_name`, e.g. a podCIDR already set on
+    /// a Node. Returns the node currently owning the subnet when it conflicts.
+    pub fn reserve(&self, node_name: &str, cidr: &str) -> Result<Option<String>> {
+        let subnet = parse_cidr(cidr)?;
+        if subnet.prefix_len != self.node_prefix_len
+            || subnet.network < self.cluster.network
+            || subnet.broadcast > self.cluster.broadcast
+        {
+            return Err(RuntimeError::invalid_config(
+                format!(
+                    "Pod CIDR {} of node '{}' is not a /{} slice of {}/{}",
+                    cidr,
+                    node_name,
+                    self.node_prefix_len,
+                    self.cluster.network,
+                    self.cluster.prefix_len
+                ),
+                "Clear the node's spec.podCIDR so one can be allocated",
+            ));
+        }
+
+        let allocations = self.get_all_allocations()?;
+        match allocations.get(&subnet.network) {
+            Some(owner) if owner != node_name => return Ok(Some(owner.clone())),
+            Some(_) => return Ok(None),
+            None => {}
+        }
+
+        // A node owns a single subnet; drop any other one it was given
+        self.release(node_name)?;
+        let key = format!("ipam/nodes/alloc/{}", subnet.network);
+        self.storage.put(key.as_bytes(), node_name.as_bytes())?;
+        debug!("Node CIDR: reserved {} for {}", cidr, node_name);
+        Ok(None)
+    }
+
+    /// Release the subnet allocated to a node
+    pub fn release(&self, node_name: &str) -> Result<Option<String>> {
+        for (network, node) in self.get_all_allocations()? {
+            if node == node_name {
+                let key = format!("ipam/nodes/alloc/{}", network);
+                self.storage.delete(key.as_bytes())?;
+
+                let subnet = self.format_subnet(network);
+                debug!("Node CIDR: released {} for {}", subnet, node_name);
+                return Ok(Some(subnet));
+            }
+        }
+
+        debug!("Node CIDR: no allocation found for {}", node_name);
+        Ok(None)
+    }
+
+    /// Get all current allocations, keyed by subnet network address
+    pub fn get_all_allocations(&self) -> Result<BTreeMap<Ipv4Addr, String>> {
+        let allocations = self.storage.scan(NODE_CIDR_ALLOC_PREFIX)?;
+        let mut result = BTreeMap::new();
+
+        for (key, value) in &allocations {
+            let key_str = String::from_utf8_lossy(key);
+            let ip_str = &key_str[NODE_CIDR_ALLOC_PREFIX.len()..];
+            if let Ok(ip) = ip_str.parse::<Ipv4Addr>() {
+                result.insert(ip, String::from_utf8_lossy(value).into_owned());
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn format_subnet(&self, network: Ipv4Addr) -> String {
+        format!("{}/{}", network, self.node_prefix_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_storage::RedbBackend;
+    use tempfile::tempdir;
+
+    fn make_allocator(cidr: &str, prefix: u8) -> NodeCidrAllocator {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test-node-cidr.redb");
+        let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
+        std::mem::forget(dir);
+        NodeCidrAllocator::new(storage, cidr, prefix).unwrap()
+    }
+
+    #[test]
+    fn test_allocate_disjoint_subnets() {
+        let allocator = make_allocator("10.88.0.0/16", 24);
+
+        assert_eq!(allocator.allocate("node1").unwrap(), "10.88.0.0/24");
+        assert_eq!(allocator.allocate("node2").unwrap(), "10.88.1.0/24");
+        // Idempotent per node
+        assert_eq!(allocator.allocate("node1").unwrap(), "10.88.0.0/24");
+
+        assert_eq!(
+            allocator.release("node1").unwrap(),
+            Some("10.88.0.0/24".to_string())
+        );
+        assert_eq!(allocator.allocate("node3").unwrap(), "10.88.0.0/24");
+    }
+
+    #[test]
+    fn test_reserve_existing_pod_cidr() {
+        let allocator = make_allocator("10.88.0.0/16", 24);
+
+        assert_eq!(allocator.reserve("node1", "10.88.5.0/24").unwrap(), None);
+        assert_eq!(allocator.allocate("node1").unwrap(), "10.88.5.0/24");
+        // Another node claiming the same range is reported as a conflict
+        assert_eq!(
+            allocator.reserve("node2", "10.88.5.0/24").unwrap(),
+            Some("node1".to_string())
+        );
+        // Not a slice of the cluster CIDR
+        assert!(allocator.reserve("node2", "10.99.0.0/24").is_err());
+        assert!(allocator.reserve("node2", "10.88.0.0/25").is_err());
+    }
+
+    #[test]
+    fn test_allocate_exhaustion() {
+        let allocator = make_allocator("10.0.0.0/23", 24);
+
+        allocator.allocate("node1").unwrap();
+        allocator.allocate("node2").unwrap();
+        assert!(matches!(
+            allocator.allocate("node3").unwrap_err(),
+            RuntimeError::IpamPoolExhausted { .. }
+        ));
+    }
+
+    #[test]
+    fn test_rejects_prefix_outside_cluster() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("db.redb")).unwrap());
+        assert!(NodeCidrAllocator::new(storage.clone(), "10.88.0.0/16", 8).is_err());
+        assert!(NodeCidrAllocator::new(storage, "10.88.0.0/16", 31).is_err());
+    }
+}
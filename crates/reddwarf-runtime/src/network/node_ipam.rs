@@ -0,0 +1,285 @@
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use crate::network::node_cidr::NodeCidrAllocator;
+use k8s_openapi::api::core::v1::Node;
+use reddwarf_core::{EventBus, WatchEventType};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the node IPAM controller subscribes to the event bus under
+const SUBSCRIBER: &str = "node-ipam-controller";
+
+/// Configuration for the node IPAM controller
+#[derive(Debug, Clone)]
+pub struct NodeIpamControllerConfig {
+    /// Interval between full node list resyncs (safety net for missed events)
+    pub resync_interval: Duration,
+}
+
+impl Default for NodeIpamControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Assigns each Node a `spec.podCIDR` slice of the cluster CIDR
+///
+/// Nodes that already carry a podCIDR keep it (and it is recorded so no other
+/// node is given an overlapping range); allocations of deleted Nodes are
+/// released.
+pub struct NodeIpamController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    allocator: NodeCidrAllocator,
+    config: NodeIpamControllerConfig,
+}
+
+impl NodeIpamController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        allocator: NodeCidrAllocator,
+        config: NodeIpamControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            allocator,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting node IPAM controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Node IPAM controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("Node IPAM resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            if event.gvk.kind != "Node" {
+                                continue;
+                            }
+                            match event.event_type {
+                                WatchEventType::Added | WatchEventType::Modified => {
+                                    match serde_json::from_value::<Node>(event.object) {
+                                        Ok(node) => {
+                                            if let Err(e) = self.reconcile_node(&node).await {
+                                                warn!(
+                                                    "Failed to assign pod CIDR to node '{}': {}",
+                                                    event.resource_key.name, e
+                                                );
+                                            }
+                                        }
+                                        Err(e) => warn!("Failed to parse node from event: {}", e),
+                                    }
+                                }
+                                WatchEventType::Deleted => {
+                                    if let Err(e) = self.allocator.release(&event.resource_key.name) {
+                                        warn!(
+                                            "Failed to release pod CIDR of node '{}': {}",
+                                            event.resource_key.name, e
+                                        );
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Missed {} events, doing full node IPAM resync", n);
+                            if let Err(e) = self.resync().await {
+                                error!("Node IPAM resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping node IPAM controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconcile every Node and release allocations of Nodes that are gone
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing node pod CIDRs");
+
+        let body = self.api_client.get_json("/api/v1/nodes").await?;
+        let items = body["items"].as_array().cloned().unwrap_or_default();
+
+        let mut present = HashSet::new();
+        for item in items {
+            let node = match serde_json::from_value::<Node>(item) {
+                Ok(node) => node,
+                Err(e) => {
+                    warn!("Failed to parse node from list: {}", e);
+                    continue;
+                }
+            };
+            let Some(name) = node.metadata.name.clone() else {
+                continue;
+            };
+            if let Err(e) = self.reconcile_node(&node).await {
+                warn!("Failed to assign pod CIDR to node '{}': {}", name, e);
+            }
+            present.insert(name);
+        }
+
+        for owner in self.allocator.get_all_allocations()?.into_values() {
+            if !present.contains(&owner) {
+                self.allocator.release(&owner)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a pod CIDR to a Node that does not have one yet
+    async fn reconcile_node(&self, node: &Node) -> Result<()> {
+        let Some(name) = node.metadata.name.as_deref() else {
+            return Ok(());
+        };
+        let Some(cidr) = self.assign(node)? else {
+            return Ok(());
+        };
+
+        // Re-read so the replace does not clobber a concurrent heartbeat
+        let mut current = self.api_client.get_node(name).await?;
+        let spec = current.spec.get_or_insert_with(Default::default);
+        if spec.pod_cidr.is_some() {
+            return Ok(());
+        }
+        spec.pod_cidr = Some(cidr.clone());
+        spec.pod_cidrs = Some(vec![cidr.clone()]);
+        self.api_client.replace_node(name, &current).await?;
+
+        info!("Assigned pod CIDR {} to node '{}'", cidr, name);
+        Ok(())
+    }
+
+    /// Pod CIDR to write to `node`, or `None` when it already has one
+    fn assign(&self, node: &Node) -> Result<Option<String>> {
+        let Some(name) = node.metadata.name.as_deref() else {
+            return Ok(None);
+        };
+
+        match node.spec.as_ref().and_then(|s| s.pod_cidr.as_deref()) {
+            Some(existing) => {
+                if let Some(owner) = self.allocator.reserve(name, existing)? {
+                    warn!(
+                        "Node '{}' has pod CIDR {} which is allocated to node '{}'",
+                        name, existing, owner
+                    );
+                }
+                Ok(None)
+            }
+            None => self.allocator.allocate(name).map(Some),
+        }
+    }
+}
+
+/// Poll this node's Node object until the IPAM controller has assigned it a
+/// pod CIDR
+pub async fn wait_for_pod_cidr(
+    api_client: &ApiClient,
+    node_name: &str,
+    timeout: Duration,
+) -> Result<String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        match api_client.get_node(node_name).await {
+            Ok(node) => {
+                if let Some(cidr) = node.spec.and_then(|s| s.pod_cidr) {
+                    return Ok(cidr);
+                }
+            }
+            Err(e) => debug!("Waiting for node '{}': {}", node_name, e),
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return Err(RuntimeError::invalid_config(
+                format!(
+                    "Node '{}' was not assigned a pod CIDR within {:?}",
+                    node_name, timeout
+                ),
+                "Check that a node IPAM controller is running with --cluster-cidr",
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::NodeSpec;
+    use reddwarf_core::InProcessEventBus;
+    use reddwarf_storage::RedbBackend;
+    use tempfile::tempdir;
+
+    fn make_controller() -> NodeIpamController {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("ipam.redb")).unwrap());
+        std::mem::forget(dir);
+        NodeIpamController::new(
+            Arc::new(ApiClient::new("http://127.0.0.1:6443")),
+            Arc::new(InProcessEventBus::new(16)),
+            NodeCidrAllocator::new(storage, "10.88.0.0/16", 24).unwrap(),
+            NodeIpamControllerConfig::default(),
+        )
+    }
+
+    fn make_node(name: &str, pod_cidr: Option<&str>) -> Node {
+        let mut node = Node::default();
+        node.metadata.name = Some(name.to_string());
+        node.spec = pod_cidr.map(|cidr| NodeSpec {
+            pod_cidr: Some(cidr.to_string()),
+            ..Default::default()
+        });
+        node
+    }
+
+    #[test]
+    fn test_assign_skips_nodes_with_pod_cidr() {
+        let controller = make_controller();
+
+        // An existing podCIDR is kept and recorded...
+        let node1 = make_node("node1", Some("10.88.0.0/24"));
+        assert_eq!(controller.assign(&node1).unwrap(), None);
+
+        // ...so new nodes are given a different range
+        let node2 = make_node("node2", None);
+        assert_eq!(
+            controller.assign(&node2).unwrap(),
+            Some("10.88.1.0/24".to_string())
+        );
+        // Stable until the node is deleted
+        assert_eq!(
+            controller.assign(&node2).unwrap(),
+            Some("10.88.1.0/24".to_string())
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use crate::api_client::ApiClient;
+use crate::command::exec;
+use crate::error::Result;
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::Node;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Host routing table programmed with routes to other nodes' pod CIDRs
+#[async_trait]
+pub trait RouteTable: Send + Sync {
+    /// Route `cidr` via `gateway`
+    async fn add_route(&self, cidr: &str, gateway: IpAddr) -> Result<()>;
+
+    /// Remove a route previously added with `add_route`
+    async fn delete_route(&self, cidr: &str, gateway: IpAddr) -> Result<()>;
+}
+
+/// Routes managed through the global zone's `route(8)` command
+pub struct HostRouteTable;
+
+#[async_trait]
+impl RouteTable for HostRouteTable {
+    async fn add_route(&self, cidr: &str, gateway: IpAddr) -> Result<()> {
+        let gateway = gateway.to_string();
+        exec("route", &["-n", "add", "-net", cidr, &gateway]).await?;
+        Ok(())
+    }
+
+    async fn delete_route(&self, cidr: &str, gateway: IpAddr) -> Result<()> {
+        let gateway = gateway.to_string();
+        exec("route", &["-n", "delete", "-net", cidr, &gateway]).await?;
+        Ok(())
+    }
+}
+
+/// Configuration for the route distributor
+#[derive(Debug, Clone)]
+pub struct RouteDistributorConfig {
+    /// This node's name; its own pod CIDR is reached locally and never routed
+    pub node_name: String,
+    /// How often Nodes are re-read
+    pub sync_interval: Duration,
+}
+
+impl RouteDistributorConfig {
+    pub fn new(node_name: String) -> Self {
+        Self {
+            node_name,
+            sync_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+/// Keeps a route to every other node's `spec.podCIDR` via that node's
+/// InternalIP, so pod IPs are reachable across nodes without an overlay
+/// encapsulation. Nodes must share an L2 segment or routed underlay.
+pub struct RouteDistributor {
+    api_client: Arc<ApiClient>,
+    routes: Arc<dyn RouteTable>,
+    config: RouteDistributorConfig,
+    /// Routes installed by this distributor (pod CIDR → gateway)
+    installed: Mutex<BTreeMap<String, IpAddr>>,
+}
+
+impl RouteDistributor {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        routes: Arc<dyn RouteTable>,
+        config: RouteDistributorConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            routes,
+            config,
+            installed: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Run the sync loop until cancelled, removing installed routes on exit
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting route distributor for node {} (interval: {:?})",
+            self.config.node_name, self.config.sync_interval
+        );
+
+        let mut interval = tokio::time::interval(self.config.sync_interval);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Route distributor shutting down");
+                    self.apply(BTreeMap::new()).await;
+                    return Ok(());
+                }
+                _ = interval.tick() => {
+                    if let Err(e) = self.sync().await {
+                        error!("Route sync failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read all Nodes and reconcile the routing table against them
+    pub async fn sync(&self) -> Result<()> {
+        let body = self.api_client.get_json("/api/v1/nodes").await?;
+        let nodes: Vec<Node> = body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect();
+
+        self.apply(desired_routes(&nodes, &self.config.node_name))
+            .await;
+        Ok(())
+    }
+
+    /// Add missing routes and remove stale ones
+    async fn apply(&self, desired: BTreeMap<String, IpAddr>) {
+        let mut installed = self.installed.lock().await;
+
+        let stale: Vec<(String, IpAddr)> = installed
+            .iter()
+            .filter(|(cidr, gw)| desired.get(*cidr) != Some(*gw))
+            .map(|(cidr, gw)| (cidr.clone(), *gw))
+            .collect();
+        for (cidr, gateway) in stale {
+            match self.routes.delete_route(&cidr, gateway).await {
+                Ok(()) => {
+                    info!("Removed route {} via {}", cidr, gateway);
+                    installed.remove(&cidr);
+                }
+                Err(e) => warn!("Failed to remove route {} via {}: {}", cidr, gateway, e),
+            }
+        }
+
+        for (cidr, gateway) in desired {
+            if installed.contains_key(&cidr) {
+                continue;
+            }
+            match self.routes.add_route(&cidr, gateway).await {
+                Ok(()) => {
+                    info!("Added route {} via {}", cidr, gateway);
+                    installed.insert(cidr, gateway);
+                }
+                Err(e) => warn!("Failed to add route {} via {}: {}", cidr, gateway, e),
+            }
+        }
+    }
+
+    /// Routes currently installed by this distributor
+    pub async fn installed_routes(&self) -> BTreeMap<String, IpAddr> {
+        self.installed.lock().await.clone()
+    }
+}
+
+/// Pod CIDR → InternalIP for every node other than `local_node`
+pub fn desired_routes(nodes: &[Node], local_node: &str) -> BTreeMap<String, IpAddr> {
+    let mut routes = BTreeMap::new();
+    for node in nodes {
+        let Some(name) = node.metadata.name.as_deref() else {
+            continue;
+        };
+        if name == local_node {
+            continue;
+        }
+        let Some(cidr) = node.spec.as_ref().and_then(|s| s.pod_cidr.as_deref()) else {
+            continue;
+        };
+        let Some(gateway) = internal_ip(node) else {
+            debug!(
+                "Node {} has pod CIDR {} but no InternalIP, not routing",
+                name, cidr
+            );
+            continue;
+        };
+        routes.insert(cidr.to_string(), gateway);
+    }
+    routes
+}
+
+fn internal_ip(node: &Node) -> Option<IpAddr> {
+    node.status
+        .as_ref()?
+        .addresses
+        .as_ref()?
+        .iter()
+        .find(|a| a.type_ == "InternalIP")
+        .and_then(|a| a.address.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{NodeAddress, NodeSpec, NodeStatus};
+    use std::sync::Mutex as StdMutex;
+
+    /// Records route changes instead of touching the host
+    #[derive(Default)]
+    struct RecordingRoutes {
+        ops: StdMutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl RouteTable for RecordingRoutes {
+        async fn add_route(&self, cidr: &str, gateway: IpAddr) -> Result<()> {
+            self.ops
+                .lock()
+                .unwrap()
+                .push(format!("add {} {}", cidr, gateway));
+            Ok(())
+        }
+
+        async fn delete_route(&self, cidr: &str, gateway: IpAddr) -> Result<()> {
+            self.ops
+                .lock()
+                .unwrap()
+                .push(format!("delete {} {}", cidr, gateway));
+            Ok(())
+        }
+    }
+
+    fn make_node(name: &str, pod_cidr: Option<&str>, ip: Option<&str>) -> Node {
+        let mut node = Node::default();
+        node.metadata.name = Some(name.to_string());
+        node.spec = Some(NodeSpec {
+            pod_cidr: pod_cidr.map(String::from),
+            ..Default::default()
+        });
+        node.status = Some(NodeStatus {
+            addresses: ip.map(|ip| {
+                vec![NodeAddress {
+                    type_: "InternalIP".to_string(),
+                    address: ip.to_string(),
+                }]
+            }),
+            ..Default::default()
+        });
+        node
+    }
+
+    #[test]
+    fn test_desired_routes_skips_local_and_unroutable_nodes() {
+        let nodes = vec![
+            make_node("node1", Some("10.88.0.0/24"), Some("192.168.1.1")),
+            make_node("node2", Some("10.88.1.0/24"), Some("192.168.1.2")),
+            make_node("node3", Some("10.88.2.0/24"), None),
+            make_node("node4", None, Some("192.168.1.4")),
+        ];
+
+        let routes = desired_routes(&nodes, "node1");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(
+            routes["10.88.1.0/24"],
+            "192.168.1.2".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_adds_and_removes_routes() {
+        let routes = Arc::new(RecordingRoutes::default());
+        let distributor = RouteDistributor::new(
+            Arc::new(ApiClient::new("http://127.0.0.1:6443")),
+            routes.clone(),
+            RouteDistributorConfig::new("node1".to_string()),
+        );
+
+        let nodes = vec![
+            make_node("node2", Some("10.88.1.0/24"), Some("192.168.1.2")),
+            make_node("node3", Some("10.88.2.0/24"), Some("192.168.1.3")),
+        ];
+        distributor.apply(desired_routes(&nodes, "node1")).await;
+        // Re-applying the same state is a no-op
+        distributor.apply(desired_routes(&nodes, "node1")).await;
+
+        // node3 leaves, node2 changes address
+        let nodes = vec![make_node(
+            "node2",
+            Some("10.88.1.0/24"),
+            Some("192.168.1.22"),
+        )];
+        distributor.apply(desired_routes(&nodes, "node1")).await;
+
+        assert_eq!(
+            *routes.ops.lock().unwrap(),
+            vec![
+                "add 10.88.1.0/24 192.168.1.2",
+                "add 10.88.2.0/24 192.168.1.3",
+                "delete 10.88.1.0/24 192.168.1.2",
+                "delete 10.88.2.0/24 192.168.1.3",
+                "add 10.88.1.0/24 192.168.1.22",
+            ]
+        );
+        assert_eq!(distributor.installed_routes().await.len(), 1);
+    }
+}
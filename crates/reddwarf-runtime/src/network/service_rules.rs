@@ -0,0 +1,555 @@
+use crate::api_client::ApiClient;
+use crate::command::exec;
+use crate::error::{Result, RuntimeError};
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::{Pod, Service, ServicePort};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+/// Header written at the top of every rendered ruleset
+const RULESET_HEADER: &str = "# Generated by reddwarf from Service state; do not edit\n";
+
+/// NAT rules loaded into the host's packet filter
+#[async_trait]
+pub trait NatRuleSet: Send + Sync {
+    /// Load `rules` (one rule per line)
+    async fn add(&self, rules: &str) -> Result<()>;
+
+    /// Unload `rules`, previously loaded with `add`
+    async fn remove(&self, rules: &str) -> Result<()>;
+}
+
+/// Rules managed through the global zone's `ipnat(8)` command
+pub struct IpnatRuleSet;
+
+impl IpnatRuleSet {
+    async fn load(flag: Option<&str>, rules: &str) -> Result<()> {
+        // Several rulesets (Services, pod egress) may load at once
+        static LOADS: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "reddwarf-{}-{}-{}.ipnat",
+            std::process::id(),
+            LOADS.fetch_add(1, Ordering::Relaxed),
+            if flag.is_some() { "remove" } else { "add" }
+        ));
+        tokio::fs::write(&path, rules)
+            .await
+            .map_err(|e| RuntimeError::network_error(format!("{}: {}", path.display(), e)))?;
+
+        let file = path.to_string_lossy();
+        let mut args: Vec<&str> = flag.into_iter().collect();
+        args.extend(["-f", &file]);
+        let result = exec("ipnat", &args).await;
+        let _ = tokio::fs::remove_file(&path).await;
+        result?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NatRuleSet for IpnatRuleSet {
+    async fn add(&self, rules: &str) -> Result<()> {
+        Self::load(None, rules).await
+    }
+
+    async fn remove(&self, rules: &str) -> Result<()> {
+        Self::load(Some("-r"), rules).await
+    }
+}
+
+/// Configuration for the Service rule exporter
+#[derive(Debug, Clone)]
+pub struct ServiceRuleExporterConfig {
+    /// Name of this node, whose pods alone serve Services with
+    /// `internalTrafficPolicy: Local`
+    pub node_name: String,
+    /// Link Service traffic from pods arrives on
+    pub interface: String,
+    /// Declarative ruleset file; always holds the rules currently loaded
+    pub rules_path: PathBuf,
+    /// How often Services and Pods are re-read
+    pub sync_interval: Duration,
+}
+
+impl ServiceRuleExporterConfig {
+    pub fn new(node_name: String, interface: String, rules_path: PathBuf) -> Self {
+        Self {
+            node_name,
+            interface,
+            rules_path,
+            sync_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Renders Service ClusterIPs and their ready endpoints into an ipnat
+/// ruleset file, kube-proxy style, and applies only the lines that changed
+///
+/// Each endpoint gets a `round-robin` `rdr` rule, so connections to a
+/// ClusterIP are spread over the Service's pods. Endpoints are derived from
+/// the Service selector and Running pods with an IP; with
+/// `internalTrafficPolicy: Local` only this node's. `sessionAffinity:
+/// ClientIP` adds `sticky`, which sends a client back to the endpoint it got
+/// first for as long as ipnat keeps its state.
+///
+/// The ruleset file is rewritten (atomically) after every apply step, so it
+/// always matches what is loaded: after a partial failure the next sync
+/// diffs against it and finishes the job, and it can be audited or fed to
+/// `ipnat -f` by hand.
+pub struct ServiceRuleExporter {
+    api_client: Arc<ApiClient>,
+    nat: Arc<dyn NatRuleSet>,
+    config: ServiceRuleExporterConfig,
+}
+
+impl ServiceRuleExporter {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        nat: Arc<dyn NatRuleSet>,
+        config: ServiceRuleExporterConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            nat,
+            config,
+        }
+    }
+
+    /// Run the sync loop until cancelled
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting service rule exporter on {} (ruleset: {}, interval: {:?})",
+            self.config.interface,
+            self.config.rules_path.display(),
+            self.config.sync_interval
+        );
+
+        let mut interval = tokio::time::interval(self.config.sync_interval);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Service rule exporter shutting down");
+                    return Ok(());
+                }
+                _ = interval.tick() => {
+                    if let Err(e) = self.sync().await {
+                        error!("Service rule sync failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read all Services and Pods and reconcile the loaded rules against them
+    pub async fn sync(&self) -> Result<()> {
+        let services: Vec<Service> = self.list("/api/v1/services").await?;
+        let pods: Vec<Pod> = self.list("/api/v1/pods").await?;
+
+        let desired = render_service_rules(
+            &self.config.node_name,
+            &self.config.interface,
+            &services,
+            &pods,
+        );
+        self.apply(&desired).await
+    }
+
+    async fn list<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let body = self.api_client.get_json(path).await?;
+        Ok(body["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect())
+    }
+
+    /// Bring the loaded rules from the ruleset file's contents to `desired`
+    async fn apply(&self, desired: &str) -> Result<()> {
+        let path = &self.config.rules_path;
+        let current = match tokio::fs::read_to_string(path).await {
+            Ok(current) => current,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(RuntimeError::network_error(format!(
+                    "{}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let diff = RuleDiff::between(&current, desired);
+        if diff.is_empty() {
+            return Ok(());
+        }
+        debug!(
+            "Service rules: {} to remove, {} to add",
+            diff.removed.len(),
+            diff.added.len()
+        );
+
+        if !diff.removed.is_empty() {
+            self.nat.remove(&join_rules(&diff.removed)).await?;
+            write_ruleset(path, &diff.kept).await?;
+        }
+        if !diff.added.is_empty() {
+            self.nat.add(&join_rules(&diff.added)).await?;
+        }
+        write_ruleset(path, &rules(desired)).await?;
+
+        info!(
+            "Service rules updated: -{} +{}",
+            diff.removed.len(),
+            diff.added.len()
+        );
+        Ok(())
+    }
+}
+
+/// Line-level difference between two rulesets
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RuleDiff {
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+    /// Rules present in both
+    pub kept: Vec<String>,
+}
+
+impl RuleDiff {
+    pub fn between(current: &str, desired: &str) -> Self {
+        let current = rules(current);
+        let desired = rules(desired);
+        Self {
+            removed: current.difference(&desired).cloned().collect(),
+            added: desired.difference(&current).cloned().collect(),
+            kept: current.intersection(&desired).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+/// Rule lines of a ruleset, ignoring comments and blank lines
+fn rules(ruleset: &str) -> BTreeSet<String> {
+    ruleset
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+fn join_rules<'a>(rules: impl IntoIterator<Item = &'a String>) -> String {
+    rules.into_iter().map(|r| format!("{}\n", r)).collect()
+}
+
+/// Replace the ruleset file via a temporary file and rename
+async fn write_ruleset<'a>(path: &Path, rules: impl IntoIterator<Item = &'a String>) -> Result<()> {
+    write_atomic(path, &format!("{}{}", RULESET_HEADER, join_rules(rules))).await
+}
+
+/// Replace the file at `path` with `contents` via a temporary file and
+/// rename, creating its directory if needed
+pub(crate) async fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let io_err =
+        |e: std::io::Error| RuntimeError::network_error(format!("{}: {}", path.display(), e));
+
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.map_err(io_err)?;
+    }
+    tokio::fs::write(&tmp, contents).await.map_err(io_err)?;
+    tokio::fs::rename(&tmp, path).await.map_err(io_err)?;
+    Ok(())
+}
+
+/// The ipnat ruleset for `services` on `node_name`, sorted so equal state
+/// renders equally
+pub fn render_service_rules(
+    node_name: &str,
+    interface: &str,
+    services: &[Service],
+    pods: &[Pod],
+) -> String {
+    let mut lines = BTreeSet::new();
+
+    for service in services {
+        let Some(spec) = service.spec.as_ref() else {
+            continue;
+        };
+        let Some(cluster_ip) = spec.cluster_ip.as_deref() else {
+            continue;
+        };
+        // Headless Services are resolved by DNS, not NAT
+        if cluster_ip.is_empty() || cluster_ip == "None" {
+            continue;
+        }
+        let Some(selector) = spec.selector.as_ref().filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        let namespace = service.metadata.namespace.as_deref().unwrap_or("default");
+
+        // A node-local Service is only served by pods on this node, and not
+        // at all from here when it has none
+        let node_local = spec.internal_traffic_policy.as_deref() == Some("Local");
+        let endpoints: Vec<&Pod> = pods
+            .iter()
+            .filter(|p| is_endpoint(p, namespace, selector))
+            .filter(|p| {
+                !node_local
+                    || p.spec.as_ref().and_then(|s| s.node_name.as_deref()) == Some(node_name)
+            })
+            .collect();
+        let affinity = match spec.session_affinity.as_deref() {
+            Some("ClientIP") => " sticky",
+            _ => "",
+        };
+
+        for port in spec.ports.iter().flatten() {
+            let protocol = port.protocol.as_deref().unwrap_or("TCP").to_lowercase();
+            for pod in &endpoints {
+                let Some(target_port) = target_port(port, pod) else {
+                    continue;
+                };
+                let pod_ip = pod
+                    .status
+                    .as_ref()
+                    .and_then(|s| s.pod_ip.as_deref())
+                    .unwrap_or_default();
+                lines.insert(format!(
+                    "rdr {} {}/32 port {} -> {} port {} {} round-robin{}",
+                    interface, cluster_ip, port.port, pod_ip, target_port, protocol, affinity
+                ));
+            }
+        }
+    }
+
+    format!("{}{}", RULESET_HEADER, join_rules(&lines))
+}
+
+/// Whether `pod` backs a Service in `namespace` selecting `selector`
+fn is_endpoint(pod: &Pod, namespace: &str, selector: &BTreeMap<String, String>) -> bool {
+    if pod.metadata.namespace.as_deref().unwrap_or("default") != namespace
+        || pod.metadata.deletion_timestamp.is_some()
+    {
+        return false;
+    }
+    let Some(status) = pod.status.as_ref() else {
+        return false;
+    };
+    if status.phase.as_deref() != Some("Running") || status.pod_ip.is_none() {
+        return false;
+    }
+
+    let labels = pod.metadata.labels.as_ref();
+    selector
+        .iter()
+        .all(|(k, v)| labels.and_then(|l| l.get(k)) == Some(v))
+}
+
+/// Pod port a Service port forwards to; named target ports are looked up in
+/// the pod's container ports
+pub(crate) fn target_port(port: &ServicePort, pod: &Pod) -> Option<i32> {
+    match &port.target_port {
+        None => Some(port.port),
+        Some(IntOrString::Int(p)) => Some(*p),
+        Some(IntOrString::String(name)) => pod
+            .spec
+            .as_ref()?
+            .containers
+            .iter()
+            .flat_map(|c| c.ports.iter().flatten())
+            .find(|p| p.name.as_deref() == Some(name.as_str()))
+            .map(|p| p.container_port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec, PodStatus, ServiceSpec};
+    use std::sync::Mutex as StdMutex;
+    use tempfile::tempdir;
+
+    /// Records loaded rules instead of touching the host
+    #[derive(Default)]
+    struct RecordingNat {
+        ops: StdMutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NatRuleSet for RecordingNat {
+        async fn add(&self, rules: &str) -> Result<()> {
+            self.ops.lock().unwrap().push(format!("add\n{}", rules));
+            Ok(())
+        }
+
+        async fn remove(&self, rules: &str) -> Result<()> {
+            self.ops.lock().unwrap().push(format!("remove\n{}", rules));
+            Ok(())
+        }
+    }
+
+    fn make_service(name: &str, cluster_ip: &str, target_port: IntOrString) -> Service {
+        let mut service = Service::default();
+        service.metadata.name = Some(name.to_string());
+        service.metadata.namespace = Some("default".to_string());
+        service.spec = Some(ServiceSpec {
+            cluster_ip: Some(cluster_ip.to_string()),
+            selector: Some([("app".to_string(), name.to_string())].into()),
+            ports: Some(vec![ServicePort {
+                port: 80,
+                target_port: Some(target_port),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        });
+        service
+    }
+
+    fn make_pod(app: &str, ip: &str, phase: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(format!("{}-{}", app, ip));
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.labels = Some([("app".to_string(), app.to_string())].into());
+        pod.spec = Some(PodSpec {
+            containers: vec![Container {
+                name: "main".to_string(),
+                ports: Some(vec![ContainerPort {
+                    name: Some("http".to_string()),
+                    container_port: 8080,
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        pod.status = Some(PodStatus {
+            phase: Some(phase.to_string()),
+            pod_ip: Some(ip.to_string()),
+            ..Default::default()
+        });
+        pod
+    }
+
+    #[test]
+    fn test_render_service_rules() {
+        let services = vec![
+            make_service("web", "10.96.0.10", IntOrString::String("http".to_string())),
+            make_service("db", "None", IntOrString::Int(5432)),
+        ];
+        let pods = vec![
+            make_pod("web", "10.88.0.6", "Running"),
+            make_pod("web", "10.88.0.5", "Running"),
+            make_pod("web", "10.88.0.7", "Pending"),
+            make_pod("db", "10.88.0.8", "Running"),
+        ];
+
+        assert_eq!(
+            render_service_rules("node1", "reddwarf_gw0", &services, &pods),
+            format!(
+                "{}{}{}",
+                RULESET_HEADER,
+                "rdr reddwarf_gw0 10.96.0.10/32 port 80 -> 10.88.0.5 port 8080 tcp round-robin\n",
+                "rdr reddwarf_gw0 10.96.0.10/32 port 80 -> 10.88.0.6 port 8080 tcp round-robin\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_affinity_and_node_local_services() {
+        let mut sticky = make_service("web", "10.96.0.10", IntOrString::Int(8080));
+        sticky.spec.as_mut().unwrap().session_affinity = Some("ClientIP".to_string());
+        let mut local = make_service("db", "10.96.0.11", IntOrString::Int(5432));
+        local.spec.as_mut().unwrap().internal_traffic_policy = Some("Local".to_string());
+        let on_node = |mut pod: Pod, node: &str| {
+            pod.spec.as_mut().unwrap().node_name = Some(node.to_string());
+            pod
+        };
+        let pods = vec![
+            on_node(make_pod("web", "10.88.0.5", "Running"), "node1"),
+            on_node(make_pod("web", "10.88.1.5", "Running"), "node2"),
+            on_node(make_pod("db", "10.88.0.8", "Running"), "node1"),
+            on_node(make_pod("db", "10.88.1.8", "Running"), "node2"),
+        ];
+
+        assert_eq!(
+            render_service_rules("node1", "gw0", &[sticky, local.clone()], &pods),
+            format!(
+                "{}{}{}{}",
+                RULESET_HEADER,
+                "rdr gw0 10.96.0.10/32 port 80 -> 10.88.0.5 port 8080 tcp round-robin sticky\n",
+                "rdr gw0 10.96.0.10/32 port 80 -> 10.88.1.5 port 8080 tcp round-robin sticky\n",
+                "rdr gw0 10.96.0.11/32 port 80 -> 10.88.0.8 port 5432 tcp round-robin\n"
+            )
+        );
+        // Without a pod of its own a node does not forward a local Service
+        assert_eq!(
+            render_service_rules("node3", "gw0", &[local], &pods),
+            RULESET_HEADER
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_loads_only_changed_rules() {
+        let dir = tempdir().unwrap();
+        let nat = Arc::new(RecordingNat::default());
+        let exporter = ServiceRuleExporter::new(
+            Arc::new(ApiClient::new("http://127.0.0.1:6443")),
+            nat.clone(),
+            ServiceRuleExporterConfig::new(
+                "node1".to_string(),
+                "gw0".to_string(),
+                dir.path().join("services.ipnat"),
+            ),
+        );
+        let services = vec![make_service("web", "10.96.0.10", IntOrString::Int(8080))];
+
+        let pods = vec![
+            make_pod("web", "10.88.0.5", "Running"),
+            make_pod("web", "10.88.0.6", "Running"),
+        ];
+        exporter
+            .apply(&render_service_rules("node1", "gw0", &services, &pods))
+            .await
+            .unwrap();
+        // Unchanged state loads nothing
+        exporter
+            .apply(&render_service_rules("node1", "gw0", &services, &pods))
+            .await
+            .unwrap();
+
+        // One endpoint replaced
+        let pods = vec![
+            make_pod("web", "10.88.0.6", "Running"),
+            make_pod("web", "10.88.0.7", "Running"),
+        ];
+        let desired = render_service_rules("node1", "gw0", &services, &pods);
+        exporter.apply(&desired).await.unwrap();
+
+        assert_eq!(
+            *nat.ops.lock().unwrap(),
+            vec![
+                "add\n\
+                 rdr gw0 10.96.0.10/32 port 80 -> 10.88.0.5 port 8080 tcp round-robin\n\
+                 rdr gw0 10.96.0.10/32 port 80 -> 10.88.0.6 port 8080 tcp round-robin\n",
+                "remove\n\
+                 rdr gw0 10.96.0.10/32 port 80 -> 10.88.0.5 port 8080 tcp round-robin\n",
+                "add\n\
+                 rdr gw0 10.96.0.10/32 port 80 -> 10.88.0.7 port 8080 tcp round-robin\n",
+            ]
+        );
+        let on_disk = std::fs::read_to_string(dir.path().join("services.ipnat")).unwrap();
+        assert_eq!(on_disk, desired);
+    }
+}
@@ -1,16 +1,29 @@
 use crate::api_client::ApiClient;
-use crate::error::{Result, RuntimeError};
+use crate::error::Result;
+use crate::lease::LeaseLock;
+use crate::node_timing::heartbeat_interval_for;
 use crate::sysinfo::{
     compute_node_resources, format_memory_quantity, NodeResources, ResourceReservation,
 };
-use k8s_openapi::api::core::v1::{Node, NodeAddress, NodeCondition, NodeStatus};
+use crate::topology::NodeTopology;
+use k8s_openapi::api::core::v1::{
+    DaemonEndpoint, Node, NodeAddress, NodeCondition, NodeDaemonEndpoints, NodeStatus,
+    NodeSystemInfo,
+};
 use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use reddwarf_core::annotations::{format_zone_brands, ZONE_BRANDS_LABEL};
+use reddwarf_core::platform::{ARCH_LABEL, OS_LABEL};
+use reddwarf_core::recorder::REASON_REGISTERED_NODE;
+use reddwarf_core::resources::NODE_LEASE_NAMESPACE;
+use reddwarf_core::version::{Version, VERSION};
+use reddwarf_core::{DevicePool, ErrorClass, EventRecorder, Platform};
 use std::collections::BTreeMap;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 /// Configuration for the node agent
 #[derive(Debug, Clone)]
@@ -19,8 +32,15 @@ pub struct NodeAgentConfig {
     pub node_name: String,
     /// API server URL
     pub api_url: String,
-    /// Interval between heartbeats
+    /// Interval between heartbeats. The `reddwarf.io/heartbeat-interval`
+    /// annotation on the Node takes precedence when set.
     pub heartbeat_interval: Duration,
+    /// How long the node's Lease in `kube-node-lease`, renewed every
+    /// heartbeat, stays valid without renewal
+    pub lease_duration: Duration,
+    /// Longest time between Node status writes while the status is
+    /// unchanged; heartbeats in between only renew the node's Lease
+    pub status_report_interval: Duration,
     /// CPU to reserve for system daemons, in millicores (default: 100 = 100m)
     pub system_reserved_cpu_millicores: i64,
     /// Memory to reserve for system daemons, in bytes (default: 256Mi)
@@ -29,6 +49,16 @@ pub struct NodeAgentConfig {
     pub max_pods: u32,
     /// Zone brands this node supports (advertised via `reddwarf.io/zone-brands` label)
     pub supported_brands: Vec<String>,
+    /// Address other nodes reach this node on, published as its InternalIP
+    pub node_ip: Option<String>,
+    /// Devices offered to pods, advertised as `devices.reddwarf.io/{class}`
+    /// extended resources
+    pub devices: Vec<DevicePool>,
+    /// Region, zone and chassis of this node, published as topology labels
+    pub topology: NodeTopology,
+    /// Port the agent's API server listens on, published as
+    /// `status.daemonEndpoints.kubeletEndpoint` for the node proxy
+    pub agent_port: Option<u16>,
 }
 
 impl NodeAgentConfig {
@@ -37,10 +67,16 @@ impl NodeAgentConfig {
             node_name,
             api_url,
             heartbeat_interval: Duration::from_secs(10),
+            lease_duration: Duration::from_secs(40),
+            status_report_interval: Duration::from_secs(300),
             system_reserved_cpu_millicores: 100,
             system_reserved_memory_bytes: 256 * 1024 * 1024,
             max_pods: 110,
             supported_brands: vec!["reddwarf".into()],
+            node_ip: None,
+            devices: Vec::new(),
+            topology: NodeTopology::default(),
+            agent_port: None,
         }
     }
 }
@@ -51,6 +87,13 @@ pub struct NodeAgent {
     config: NodeAgentConfig,
     /// Detected system resources (None if detection failed at startup).
     detected: Option<NodeResources>,
+    /// Heartbeat interval currently in effect, in milliseconds
+    effective_interval_ms: AtomicU64,
+    /// The node's Lease, renewed as its heartbeat
+    lease: LeaseLock,
+    /// When the Node status was last written
+    last_report: Mutex<Option<Instant>>,
+    recorder: Option<EventRecorder>,
 }
 
 impl NodeAgent {
@@ -82,9 +125,13 @@ impl NodeAgent {
         };
 
         Self {
+            effective_interval_ms: AtomicU64::new(config.heartbeat_interval.as_millis() as u64),
+            lease: node_lease(&api_client, &config),
+            last_report: Mutex::new(None),
             api_client,
             config,
             detected,
+            recorder: None,
         }
     }
 
@@ -95,9 +142,38 @@ impl NodeAgent {
         detected: Option<NodeResources>,
     ) -> Self {
         Self {
+            effective_interval_ms: AtomicU64::new(config.heartbeat_interval.as_millis() as u64),
+            lease: node_lease(&api_client, &config),
+            last_report: Mutex::new(None),
             api_client,
             config,
             detected,
+            recorder: None,
+        }
+    }
+
+    /// Record a `RegisteredNode` event when the node is first registered
+    pub fn with_event_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Heartbeat interval currently in effect
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_millis(self.effective_interval_ms.load(Ordering::Relaxed))
+    }
+
+    /// Pick up a per-node heartbeat interval override from the stored Node
+    fn apply_interval_override(&self, node: &Node) {
+        let interval = heartbeat_interval_for(node, self.config.heartbeat_interval);
+        let previous = self
+            .effective_interval_ms
+            .swap(interval.as_millis() as u64, Ordering::Relaxed);
+        if previous != interval.as_millis() as u64 {
+            info!(
+                "Heartbeat interval for node '{}' is now {:?}",
+                self.config.node_name, interval
+            );
         }
     }
 
@@ -108,25 +184,62 @@ impl NodeAgent {
         let node = self.build_node();
 
         match self.api_client.create_node(&node).await {
-            Ok(_) => {
+            Ok(created) => {
                 info!("Node '{}' registered successfully", self.config.node_name);
+                if let Some(recorder) = &self.recorder {
+                    recorder.normal(
+                        &created,
+                        REASON_REGISTERED_NODE,
+                        format!("Registered node {}", self.config.node_name),
+                    );
+                }
+                self.apply_interval_override(&created);
+                self.mark_reported();
                 Ok(())
             }
-            Err(RuntimeError::ZoneAlreadyExists { .. }) => {
+            Err(e) if e.is_conflict() => {
                 // Node already exists — update its status instead
                 info!(
                     "Node '{}' already exists, updating status",
                     self.config.node_name
                 );
-                self.api_client
+                let updated = self
+                    .api_client
                     .update_node_status(&self.config.node_name, &node)
                     .await?;
-                Ok(())
+                self.apply_interval_override(&updated);
+                self.mark_reported();
+                self.sync_topology_labels(updated).await
             }
             Err(e) => Err(e),
         }
     }
 
+    /// Bring the topology labels of an already registered Node up to date;
+    /// status updates leave labels alone
+    async fn sync_topology_labels(&self, mut node: Node) -> Result<()> {
+        let labels = node.metadata.labels.get_or_insert_with(Default::default);
+        let mut changed = false;
+        for (label, value) in self.config.topology.labels() {
+            if labels.get(&label) != Some(&value) {
+                labels.insert(label, value);
+                changed = true;
+            }
+        }
+        if !changed {
+            return Ok(());
+        }
+
+        self.api_client
+            .replace_node(&self.config.node_name, &node)
+            .await?;
+        info!(
+            "Updated topology labels of node '{}'",
+            self.config.node_name
+        );
+        Ok(())
+    }
+
     /// Run the heartbeat loop
     pub async fn run(&self, token: CancellationToken) -> Result<()> {
         // Register first
@@ -134,7 +247,7 @@ impl NodeAgent {
 
         info!(
             "Starting heartbeat loop (interval: {:?})",
-            self.config.heartbeat_interval
+            self.heartbeat_interval()
         );
 
         loop {
@@ -143,7 +256,7 @@ impl NodeAgent {
                     info!("Node agent shutting down");
                     return Ok(());
                 }
-                _ = tokio::time::sleep(self.config.heartbeat_interval) => {
+                _ = tokio::time::sleep(self.heartbeat_interval()) => {
                     if let Err(e) = self.heartbeat().await {
                         warn!("Heartbeat failed: {} — will retry", e);
                     }
@@ -152,23 +265,71 @@ impl NodeAgent {
         }
     }
 
-    /// Send a heartbeat by updating node status
+    /// Send a heartbeat by renewing the node's Lease, and update the node
+    /// status only when it changed, the status report interval passed, or
+    /// the Lease could not be renewed. When only the conditions changed, or
+    /// only their heartbeat times, just the conditions are sent.
     async fn heartbeat(&self) -> Result<()> {
+        let renewed = match self.lease.try_acquire_or_renew().await {
+            Ok(true) => true,
+            Ok(false) => {
+                warn!(
+                    "Lease of node '{}' is held by another agent",
+                    self.config.node_name
+                );
+                false
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to renew lease of node '{}': {}",
+                    self.config.node_name, e
+                );
+                false
+            }
+        };
+
+        let stored = self.api_client.get_node(&self.config.node_name).await?;
+        self.apply_interval_override(&stored);
         let node = self.build_node();
+        if renewed && !self.report_due() && !status_changed(&stored, &node) {
+            debug!("Renewed lease of node '{}'", self.config.node_name);
+            return Ok(());
+        }
 
-        self.api_client
-            .update_node_status(&self.config.node_name, &node)
-            .await?;
+        let updated = match status_delta(&stored, &node) {
+            Some(delta) => {
+                self.api_client
+                    .patch_node_status(&self.config.node_name, &delta)
+                    .await?
+            }
+            None => {
+                self.api_client
+                    .update_node_status(&self.config.node_name, &node)
+                    .await?
+            }
+        };
+        self.apply_interval_override(&updated);
+        self.mark_reported();
 
         info!("Heartbeat sent for node '{}'", self.config.node_name);
         Ok(())
     }
 
+    /// Whether the status report interval passed since the last status write
+    fn report_due(&self) -> bool {
+        let last_report = *self.last_report.lock().unwrap();
+        last_report.is_none_or(|at| at.elapsed() >= self.config.status_report_interval)
+    }
+
+    fn mark_reported(&self) {
+        *self.last_report.lock().unwrap() = Some(Instant::now());
+    }
+
     /// Build the Node resource with current status
     fn build_node(&self) -> Node {
         let hostname = self.config.node_name.clone();
 
-        let (capacity, allocatable) = if let Some(ref nr) = self.detected {
+        let (mut capacity, mut allocatable) = if let Some(ref nr) = self.detected {
             let cap_cpu = nr.capacity.cpu_count.to_string();
             let cap_mem = format_memory_quantity(nr.capacity.total_memory_bytes);
             let pods = nr.max_pods.to_string();
@@ -210,6 +371,26 @@ impl NodeAgent {
             (capacity, allocatable)
         };
 
+        for pool in &self.config.devices {
+            let count = Quantity(pool.paths.len().to_string());
+            capacity.insert(pool.resource_name(), count.clone());
+            allocatable.insert(pool.resource_name(), count);
+        }
+
+        let platform = Platform::host();
+
+        let mut addresses = Vec::new();
+        if let Some(ref ip) = self.config.node_ip {
+            addresses.push(NodeAddress {
+                type_: "InternalIP".to_string(),
+                address: ip.clone(),
+            });
+        }
+        addresses.push(NodeAddress {
+            type_: "Hostname".to_string(),
+            address: hostname.clone(),
+        });
+
         Node {
             metadata: ObjectMeta {
                 name: Some(self.config.node_name.clone()),
@@ -221,11 +402,14 @@ impl NodeAgent {
                             "reddwarf-zone".to_string(),
                         ),
                         (
-                            "reddwarf.io/zone-brands".to_string(),
-                            self.config.supported_brands.join(","),
+                            ZONE_BRANDS_LABEL.to_string(),
+                            format_zone_brands(&self.config.supported_brands),
                         ),
+                        (ARCH_LABEL.to_string(), platform.arch.clone()),
+                        (OS_LABEL.to_string(), platform.os.clone()),
                     ]
                     .into_iter()
+                    .chain(self.config.topology.labels())
                     .collect(),
                 ),
                 ..Default::default()
@@ -243,12 +427,13 @@ impl NodeAgent {
                         k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(chrono::Utc::now()),
                     ),
                 }]),
-                addresses: Some(vec![NodeAddress {
-                    type_: "Hostname".to_string(),
-                    address: hostname,
-                }]),
+                addresses: Some(addresses),
+                daemon_endpoints: self.config.agent_port.map(|port| NodeDaemonEndpoints {
+                    kubelet_endpoint: Some(DaemonEndpoint { port: port.into() }),
+                }),
                 allocatable: Some(allocatable),
                 capacity: Some(capacity),
+                node_info: Some(build_node_info(&platform)),
                 ..Default::default()
             }),
             ..Default::default()
@@ -256,10 +441,74 @@ impl NodeAgent {
     }
 }
 
+/// The Lease in `kube-node-lease` named after the node, held by its agent
+fn node_lease(api_client: &Arc<ApiClient>, config: &NodeAgentConfig) -> LeaseLock {
+    LeaseLock::new(
+        api_client.clone(),
+        NODE_LEASE_NAMESPACE,
+        &config.node_name,
+        &config.node_name,
+        config.lease_duration,
+    )
+}
+
+/// Whether the built `node` reports a different status than the `stored`
+/// one, ignoring condition timestamps
+fn status_changed(stored: &Node, node: &Node) -> bool {
+    let without_timestamps = |node: &Node| {
+        let mut status = node.status.clone().unwrap_or_default();
+        for condition in status.conditions.iter_mut().flatten() {
+            condition.last_heartbeat_time = None;
+            condition.last_transition_time = None;
+        }
+        status
+    };
+    without_timestamps(stored) != without_timestamps(node)
+}
+
+/// The status to send for the built `node` when it reports nothing new
+/// besides its conditions: just the conditions, which the API server merges
+/// into the `stored` status. `None` when other fields changed and the whole
+/// status is written.
+fn status_delta(stored: &Node, node: &Node) -> Option<NodeStatus> {
+    let mut stored = stored.status.clone().unwrap_or_default();
+    let mut status = node.status.clone().unwrap_or_default();
+    stored.conditions = None;
+    let conditions = status.conditions.take();
+    (stored == status).then(|| NodeStatus {
+        conditions,
+        ..Default::default()
+    })
+}
+
+/// Describe the host platform for `status.nodeInfo`
+fn build_node_info(platform: &Platform) -> NodeSystemInfo {
+    let version = Version::current().node_version_string();
+    NodeSystemInfo {
+        architecture: platform.arch.clone(),
+        operating_system: platform.os.clone(),
+        kernel_version: sys_info::os_release().unwrap_or_default(),
+        os_image: sys_info::os_type().unwrap_or_default(),
+        kubelet_version: version.clone(),
+        container_runtime_version: format!("zones://{}", VERSION),
+        kube_proxy_version: version,
+        ..Default::default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_api::MockApiServer;
     use crate::sysinfo::detect_system_resources;
+    use serde_json::json;
+
+    /// A mock API server and the config of an agent talking to it
+    async fn mock_config() -> (MockApiServer, NodeAgentConfig) {
+        let api = MockApiServer::start().await;
+        let config = NodeAgentConfig::new("test-node".to_string(), api.url());
+        (api, config)
+    }
 
     #[test]
     fn test_node_agent_config_defaults() {
@@ -272,12 +521,69 @@ mod tests {
         assert_eq!(config.max_pods, 110);
     }
 
-    #[test]
-    fn test_build_node_has_ready_condition() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let config =
-            NodeAgentConfig::new("test-node".to_string(), "http://127.0.0.1:6443".to_string());
-        let agent = NodeAgent::new(api_client, config);
+    #[tokio::test]
+    async fn test_heartbeat_interval_annotation_override() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new_with_detected(api.client(), config, None);
+        assert_eq!(agent.heartbeat_interval(), Duration::from_secs(10));
+
+        let mut node = agent.build_node();
+        node.metadata.annotations = Some(
+            [(
+                crate::node_timing::HEARTBEAT_INTERVAL_ANNOTATION.to_string(),
+                "30s".to_string(),
+            )]
+            .into(),
+        );
+        agent.apply_interval_override(&node);
+        assert_eq!(agent.heartbeat_interval(), Duration::from_secs(30));
+
+        // Removing the annotation falls back to the configured interval
+        node.metadata.annotations = None;
+        agent.apply_interval_override(&node);
+        assert_eq!(agent.heartbeat_interval(), Duration::from_secs(10));
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_node_records_platform() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new_with_detected(api.client(), config, None);
+
+        let node = agent.build_node();
+        let host = Platform::host();
+
+        let labels = node.metadata.labels.unwrap();
+        assert_eq!(labels[ARCH_LABEL], host.arch);
+        assert_eq!(labels[OS_LABEL], host.os);
+
+        let info = node.status.unwrap().node_info.unwrap();
+        assert_eq!(info.architecture, host.arch);
+        assert_eq!(info.operating_system, host.os);
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_node_has_topology_labels() {
+        let (api, mut config) = mock_config().await;
+        config.topology = NodeTopology {
+            region: Some("eu-central".to_string()),
+            zone: Some("eu-central-1a".to_string()),
+            chassis: Some("C8260LH12A10123".to_string()),
+        };
+        let agent = NodeAgent::new_with_detected(api.client(), config, None);
+
+        let labels = agent.build_node().metadata.labels.unwrap();
+        assert_eq!(labels["topology.kubernetes.io/region"], "eu-central");
+        assert_eq!(labels["topology.kubernetes.io/zone"], "eu-central-1a");
+        assert_eq!(labels["reddwarf.io/chassis"], "C8260LH12A10123");
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_node_has_ready_condition() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new(api.client(), config);
 
         let node = agent.build_node();
 
@@ -288,14 +594,125 @@ mod tests {
         assert_eq!(conditions[0].type_, "Ready");
         assert_eq!(conditions[0].status, "True");
         assert!(conditions[0].last_heartbeat_time.is_some());
+        assert!(api.requests().is_empty());
     }
 
-    #[test]
-    fn test_build_node_has_allocatable_resources() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let config =
-            NodeAgentConfig::new("test-node".to_string(), "http://127.0.0.1:6443".to_string());
-        let agent = NodeAgent::new(api_client, config);
+    #[tokio::test]
+    async fn test_status_changed_ignores_heartbeat_times() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new(api.client(), config);
+
+        let mut stored = agent.build_node();
+        let conditions = stored.status.as_mut().unwrap().conditions.as_mut().unwrap();
+        conditions[0].last_heartbeat_time = None;
+        assert!(!status_changed(&stored, &agent.build_node()));
+
+        // A node marked NotReady by the health checker is reported again
+        let conditions = stored.status.as_mut().unwrap().conditions.as_mut().unwrap();
+        conditions[0].status = "False".to_string();
+        assert!(status_changed(&stored, &agent.build_node()));
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_status_delta_sends_only_conditions() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new(api.client(), config);
+
+        let mut stored = agent.build_node();
+        stored.status.as_mut().unwrap().conditions.as_mut().unwrap()[0].status =
+            "False".to_string();
+        let node = agent.build_node();
+        let delta = status_delta(&stored, &node).unwrap();
+        assert_eq!(delta.conditions, node.status.as_ref().unwrap().conditions);
+        assert!(delta.capacity.is_none());
+        assert!(delta.node_info.is_none());
+        let body = serde_json::to_value(&delta).unwrap();
+        assert_eq!(body.as_object().unwrap().len(), 1);
+
+        // Anything else changing writes the whole status
+        stored.status.as_mut().unwrap().capacity = None;
+        assert!(status_delta(&stored, &node).is_none());
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_existing_node_updates_status_and_labels() {
+        let server = MockApiServer::start().await;
+        let mut config = NodeAgentConfig::new("test-node".to_string(), server.url());
+        config.topology.zone = Some("eu-central-1a".to_string());
+        let agent = NodeAgent::new_with_detected(server.client(), config, None);
+
+        let already_exists = json!({"kind": "Status", "reason": "AlreadyExists", "code": 409});
+        server.respond("POST", "/api/v1/nodes", 409, already_exists);
+        let stored = json!({"metadata": {"name": "test-node", "labels": {"team": "infra"}}});
+        server.respond("PUT", "/api/v1/nodes/test-node/status", 200, stored);
+        server.respond("PUT", "/api/v1/nodes/test-node", 200, json!({}));
+        agent.register().await.unwrap();
+
+        let paths: Vec<_> = server
+            .requests()
+            .into_iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "POST /api/v1/nodes",
+                "PUT /api/v1/nodes/test-node/status",
+                "PUT /api/v1/nodes/test-node"
+            ]
+        );
+        // Labels the node already has are kept
+        let labels = &server.requests()[2].json()["metadata"]["labels"];
+        assert_eq!(labels["team"], "infra");
+        assert_eq!(labels["topology.kubernetes.io/zone"], "eu-central-1a");
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_writes_status_only_when_due_or_changed() {
+        let server = MockApiServer::start().await;
+        let config = NodeAgentConfig::new("test-node".to_string(), server.url());
+        let agent = NodeAgent::new_with_detected(server.client(), config, None);
+        let node_path = "/api/v1/nodes/test-node";
+        let status_path = "/api/v1/nodes/test-node/status";
+        let leases = format!(
+            "/apis/coordination.k8s.io/v1/namespaces/{}/leases",
+            NODE_LEASE_NAMESPACE
+        );
+        server.respond("POST", &leases, 201, json!({"metadata": {}}));
+        let stored = serde_json::to_value(agent.build_node()).unwrap();
+        server.respond("GET", node_path, 200, stored.clone());
+        server.respond("PATCH", status_path, 200, stored.clone());
+        server.respond("PUT", status_path, 200, stored.clone());
+
+        // First report: only the conditions' heartbeat times are new
+        agent.heartbeat().await.unwrap();
+        let patches = server.requests_to("PATCH", status_path);
+        assert_eq!(patches.len(), 1);
+        let sent = patches[0].json();
+        assert_eq!(sent["status"].as_object().unwrap().len(), 1);
+        assert_eq!(sent["status"]["conditions"][0]["type"], "Ready");
+
+        // The Lease is the heartbeat until the next report is due
+        agent.heartbeat().await.unwrap();
+        assert_eq!(server.requests_to("PATCH", status_path).len(), 1);
+        assert!(server.requests_to("PUT", status_path).is_empty());
+
+        // Other changes write the whole status at once
+        let mut changed = stored;
+        changed["status"]["capacity"] = json!({});
+        server.respond("GET", node_path, 200, changed);
+        agent.heartbeat().await.unwrap();
+        let puts = server.requests_to("PUT", status_path);
+        assert_eq!(puts.len(), 1);
+        assert!(puts[0].json()["status"]["capacity"]["cpu"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_build_node_has_allocatable_resources() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new(api.client(), config);
 
         let node = agent.build_node();
         let status = node.status.unwrap();
@@ -315,14 +732,28 @@ mod tests {
 
         // Capacity CPU should match detected cpu_count
         assert_eq!(cap["cpu"].0, sys.cpu_count.to_string());
+        assert!(api.requests().is_empty());
     }
 
-    #[test]
-    fn test_build_node_allocatable_less_than_capacity() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let config =
-            NodeAgentConfig::new("test-node".to_string(), "http://127.0.0.1:6443".to_string());
-        let agent = NodeAgent::new(api_client, config);
+    #[tokio::test]
+    async fn test_build_node_advertises_devices() {
+        let (api, mut config) = mock_config().await;
+        config.devices = vec![DevicePool::parse("disk=/dev/dsk/c1t1d0,/dev/dsk/c1t2d0").unwrap()];
+        let agent = NodeAgent::new_with_detected(api.client(), config, None);
+
+        let status = agent.build_node().status.unwrap();
+        assert_eq!(status.capacity.unwrap()["devices.reddwarf.io/disk"].0, "2");
+        assert_eq!(
+            status.allocatable.unwrap()["devices.reddwarf.io/disk"].0,
+            "2"
+        );
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_node_allocatable_less_than_capacity() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new(api.client(), config);
 
         // Agent should have detected resources (we're on a real host)
         assert!(agent.detected.is_some(), "detection should succeed in tests");
@@ -358,42 +789,60 @@ mod tests {
             alloc_mem,
             cap_mem,
         );
+        assert!(api.requests().is_empty());
     }
 
-    #[test]
-    fn test_build_node_has_brand_labels() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let mut config =
-            NodeAgentConfig::new("test-node".to_string(), "http://127.0.0.1:6443".to_string());
+    #[tokio::test]
+    async fn test_build_node_has_brand_labels() {
+        let (api, mut config) = mock_config().await;
         config.supported_brands = vec!["reddwarf".into(), "lx".into()];
-        let agent = NodeAgent::new(api_client, config);
+        let agent = NodeAgent::new(api.client(), config);
 
         let node = agent.build_node();
 
         let labels = node.metadata.labels.unwrap();
-        assert_eq!(labels.get("reddwarf.io/zone-brands").unwrap(), "reddwarf,lx");
+        assert_eq!(labels.get(ZONE_BRANDS_LABEL).unwrap(), "reddwarf,lx");
+        assert!(api.requests().is_empty());
     }
 
-    #[test]
-    fn test_build_node_has_brand_labels_default() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let config =
-            NodeAgentConfig::new("test-node".to_string(), "http://127.0.0.1:6443".to_string());
-        let agent = NodeAgent::new(api_client, config);
+    #[tokio::test]
+    async fn test_build_node_publishes_internal_ip() {
+        let (api, mut config) = mock_config().await;
+        config.node_ip = Some("192.168.1.3".to_string());
+        config.agent_port = Some(6443);
+        let agent = NodeAgent::new_with_detected(api.client(), config, None);
+
+        let node = agent.build_node();
+
+        // spec.podCIDR is owned by the node IPAM controller
+        assert!(node.spec.is_none());
+        let status = node.status.unwrap();
+        let endpoint = status.daemon_endpoints.unwrap().kubelet_endpoint.unwrap();
+        assert_eq!(endpoint.port, 6443);
+        let addresses = status.addresses.unwrap();
+        assert_eq!(addresses[0].type_, "InternalIP");
+        assert_eq!(addresses[0].address, "192.168.1.3");
+        assert_eq!(addresses[1].type_, "Hostname");
+        assert!(api.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_node_has_brand_labels_default() {
+        let (api, config) = mock_config().await;
+        let agent = NodeAgent::new(api.client(), config);
 
         let node = agent.build_node();
 
         let labels = node.metadata.labels.unwrap();
-        assert_eq!(labels.get("reddwarf.io/zone-brands").unwrap(), "reddwarf");
+        assert_eq!(labels.get(ZONE_BRANDS_LABEL).unwrap(), "reddwarf");
+        assert!(api.requests().is_empty());
     }
 
-    #[test]
-    fn test_build_node_fallback_on_detection_failure() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
-        let config =
-            NodeAgentConfig::new("test-node".to_string(), "http://127.0.0.1:6443".to_string());
+    #[tokio::test]
+    async fn test_build_node_fallback_on_detection_failure() {
+        let (api, config) = mock_config().await;
         // Simulate detection failure
-        let agent = NodeAgent::new_with_detected(api_client, config, None);
+        let agent = NodeAgent::new_with_detected(api.client(), config, None);
 
         let node = agent.build_node();
         let status = node.status.unwrap();
@@ -412,5 +861,6 @@ mod tests {
             .unwrap_or_else(|_| "1".to_string());
         assert_eq!(alloc["cpu"].0, expected_cpu);
         assert_eq!(cap["cpu"].0, expected_cpu);
+        assert!(api.requests().is_empty());
     }
 }
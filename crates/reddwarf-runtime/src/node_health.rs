@@ -1,95 +1,342 @@
 use crate::api_client::ApiClient;
 use crate::error::Result;
-use chrono::Utc;
+use crate::node_timing::heartbeat_timeout_for;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::coordination::v1::Lease;
 use k8s_openapi::api::core::v1::{Node, NodeCondition};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use reddwarf_core::resources::{LEASE_KIND, NODE_LEASE_NAMESPACE};
+use reddwarf_core::{EventBus, WatchEventType};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+/// Name the node health controller subscribes to the event bus under
+const SUBSCRIBER: &str = "node-health-controller";
+
+/// Delay before retrying a node whose NotReady update failed
+const UPDATE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
 /// Configuration for the node health checker
 #[derive(Debug, Clone)]
 pub struct NodeHealthCheckerConfig {
-    /// Interval between health checks
-    pub check_interval: Duration,
-    /// How long since the last heartbeat before a node is considered stale
+    /// Interval between full node list resyncs (safety net for missed events)
+    pub resync_interval: Duration,
+    /// How long since the last heartbeat before a node is considered stale.
+    /// Nodes may override this via the `reddwarf.io/heartbeat-timeout` annotation.
     pub heartbeat_timeout: Duration,
 }
 
 impl Default for NodeHealthCheckerConfig {
     fn default() -> Self {
         Self {
-            check_interval: Duration::from_secs(15),
+            resync_interval: Duration::from_secs(300),
             // 4x the default heartbeat interval (10s) = 40s
             heartbeat_timeout: Duration::from_secs(40),
         }
     }
 }
 
-/// Periodically checks node heartbeats and marks stale nodes as NotReady
+/// Per-node heartbeat deadlines, fed from Node and node Lease watch events
+#[derive(Default)]
+struct NodeDeadlines {
+    nodes: HashMap<String, (Instant, Node)>,
+    /// Last renewal of each node's Lease, which counts as a heartbeat
+    renewals: HashMap<String, DateTime<Utc>>,
+}
+
+impl NodeDeadlines {
+    /// Track (or stop tracking) a node based on its current Ready condition
+    /// and the last renewal of its Lease
+    fn observe(&mut self, mut node: Node, timeout: Duration) {
+        let Some(name) = node.metadata.name.clone() else {
+            return;
+        };
+        if let Some(renewed) = self.renewals.get(&name) {
+            apply_renewal(&mut node, *renewed);
+        }
+        match heartbeat_remaining(&node, timeout) {
+            Some(remaining) => {
+                self.nodes.insert(name, (Instant::now() + remaining, node));
+            }
+            None => {
+                self.nodes.remove(&name);
+            }
+        }
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.nodes.remove(name);
+    }
+
+    /// Record a renewal of a node's Lease, pushing back the node's deadline
+    fn renew(&mut self, lease: &Lease, timeout: Duration) {
+        let Some(name) = lease.metadata.name.clone() else {
+            return;
+        };
+        let Some(renewed) = lease.spec.as_ref().and_then(|s| s.renew_time.as_ref()) else {
+            return;
+        };
+        self.renewals.insert(name.clone(), renewed.0);
+        if let Some((_, node)) = self.nodes.get(&name) {
+            self.observe(node.clone(), timeout);
+        }
+    }
+
+    fn forget_lease(&mut self, name: &str) {
+        self.renewals.remove(name);
+    }
+
+    fn schedule(&mut self, name: String, deadline: Instant, node: Node) {
+        self.nodes.insert(name, (deadline, node));
+    }
+
+    /// Earliest deadline across all tracked nodes
+    fn next_deadline(&self) -> Option<Instant> {
+        self.nodes.values().map(|(d, _)| *d).min()
+    }
+
+    /// Remove and return all nodes whose deadline has passed
+    fn take_expired(&mut self, now: Instant) -> Vec<(String, Node)> {
+        let expired: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|(_, (d, _))| *d <= now)
+            .map(|(n, _)| n.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|name| self.nodes.remove(&name).map(|(_, node)| (name, node)))
+            .collect()
+    }
+}
+
+/// Count a renewal of the node's Lease as a heartbeat of its Ready condition
+fn apply_renewal(node: &mut Node, renewed: DateTime<Utc>) {
+    let ready = node
+        .status
+        .as_mut()
+        .and_then(|s| s.conditions.as_mut())
+        .and_then(|c| c.iter_mut().find(|c| c.type_ == "Ready"));
+    if let Some(ready) = ready {
+        match &ready.last_heartbeat_time {
+            Some(heartbeat) if heartbeat.0 >= renewed => {}
+            _ => ready.last_heartbeat_time = Some(Time(renewed)),
+        }
+    }
+}
+
+/// Time left until a node's heartbeat goes stale, using the node's
+/// annotation override when present and `default_timeout` otherwise.
+///
+/// Returns `None` for nodes that need no timer: no Ready condition, no
+/// heartbeat timestamp, or already marked NotReady by the health checker.
+fn heartbeat_remaining(node: &Node, default_timeout: Duration) -> Option<Duration> {
+    let timeout = heartbeat_timeout_for(node, default_timeout);
+    let ready = node
+        .status
+        .as_ref()?
+        .conditions
+        .as_ref()?
+        .iter()
+        .find(|c| c.type_ == "Ready")?;
+
+    if ready.status == "False" && ready.reason.as_deref() == Some("NodeStatusUnknown") {
+        return None;
+    }
+
+    let last_heartbeat = ready.last_heartbeat_time.as_ref()?.0;
+    let elapsed = (Utc::now() - last_heartbeat).to_std().unwrap_or(Duration::ZERO);
+    Some(timeout.saturating_sub(elapsed))
+}
+
+/// Watches node heartbeats and marks stale nodes as NotReady
+///
+/// Each node gets a deadline derived from its last heartbeat, the later of
+/// its Ready condition's heartbeat time and the last renewal of its Lease in
+/// `kube-node-lease`, refreshed by Node and Lease watch events from the
+/// event bus. The checker only wakes
+/// when the earliest deadline passes, plus an infrequent full resync.
 pub struct NodeHealthChecker {
     api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
     config: NodeHealthCheckerConfig,
 }
 
 impl NodeHealthChecker {
-    pub fn new(api_client: Arc<ApiClient>, config: NodeHealthCheckerConfig) -> Self {
-        Self { api_client, config }
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: NodeHealthCheckerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
     }
 
     /// Run the health checker loop
     pub async fn run(&self, token: CancellationToken) -> Result<()> {
         info!(
-            "Starting node health checker (interval: {:?}, timeout: {:?})",
-            self.config.check_interval, self.config.heartbeat_timeout
+            "Starting node health checker (resync: {:?}, timeout: {:?})",
+            self.config.resync_interval, self.config.heartbeat_timeout
         );
 
-        let mut interval = tokio::time::interval(self.config.check_interval);
-        // Consume the first immediate tick — nodes just registered
-        interval.tick().await;
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut deadlines = NodeDeadlines::default();
+
+        if let Err(e) = self.resync(&mut deadlines).await {
+            error!("Initial node list failed: {}", e);
+        }
+
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+        // Consume the first immediate tick — we just resynced
+        resync_tick.tick().await;
 
         loop {
+            let next_deadline = deadlines.next_deadline();
+
             tokio::select! {
                 _ = token.cancelled() => {
                     info!("Node health checker shutting down");
                     return Ok(());
                 }
-                _ = interval.tick() => {
-                    if let Err(e) = self.check_all_nodes().await {
-                        error!("Node health check failed: {}", e);
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync(&mut deadlines).await {
+                        error!("Node resync failed: {}", e);
+                    }
+                }
+                _ = async {
+                    match next_deadline {
+                        Some(d) => tokio::time::sleep_until(d).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    self.check_expired(&mut deadlines).await;
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            if event.gvk.kind == LEASE_KIND
+                                && event.resource_key.namespace == NODE_LEASE_NAMESPACE
+                            {
+                                let name = event.resource_key.name;
+                                self.handle_lease_event(
+                                    &mut deadlines,
+                                    event.event_type,
+                                    event.object,
+                                    &name,
+                                );
+                                continue;
+                            }
+                            if event.gvk.kind != "Node" {
+                                continue;
+                            }
+                            match event.event_type {
+                                WatchEventType::Added | WatchEventType::Modified => {
+                                    match serde_json::from_value::<Node>(event.object) {
+                                        Ok(node) => {
+                                            deadlines.observe(node, self.config.heartbeat_timeout)
+                                        }
+                                        Err(e) => warn!("Failed to parse node from event: {}", e),
+                                    }
+                                }
+                                WatchEventType::Deleted => {
+                                    deadlines.remove(&event.resource_key.name);
+                                }
+                                _ => {}
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Missed {} events, doing full node resync", n);
+                            if let Err(e) = self.resync(&mut deadlines).await {
+                                error!("Node resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping node health checker");
+                            return Ok(());
+                        }
                     }
                 }
             }
         }
     }
 
-    /// Check all nodes for stale heartbeats
-    async fn check_all_nodes(&self) -> Result<()> {
-        debug!("Running node health check");
+    /// Track a renewal, or removal, of a node's Lease
+    fn handle_lease_event(
+        &self,
+        deadlines: &mut NodeDeadlines,
+        event_type: WatchEventType,
+        object: serde_json::Value,
+        name: &str,
+    ) {
+        match event_type {
+            WatchEventType::Added | WatchEventType::Modified => {
+                match serde_json::from_value::<Lease>(object) {
+                    Ok(lease) => deadlines.renew(&lease, self.config.heartbeat_timeout),
+                    Err(e) => warn!("Failed to parse lease from event: {}", e),
+                }
+            }
+            WatchEventType::Deleted => deadlines.forget_lease(name),
+            _ => {}
+        }
+    }
+
+    /// Rebuild all node deadlines from full node and node Lease lists
+    async fn resync(&self, deadlines: &mut NodeDeadlines) -> Result<()> {
+        debug!("Resyncing node heartbeat deadlines");
+
+        let mut fresh = NodeDeadlines::default();
+        let leases = self
+            .api_client
+            .get_json(&format!(
+                "/apis/coordination.k8s.io/v1/namespaces/{}/leases",
+                NODE_LEASE_NAMESPACE
+            ))
+            .await?;
+        for item in leases["items"].as_array().cloned().unwrap_or_default() {
+            match serde_json::from_value::<Lease>(item) {
+                Ok(lease) => fresh.renew(&lease, self.config.heartbeat_timeout),
+                Err(e) => warn!("Failed to parse lease from list: {}", e),
+            }
+        }
 
         let body = self.api_client.get_json("/api/v1/nodes").await?;
         let items = body["items"].as_array().cloned().unwrap_or_default();
 
         for item in items {
-            let node: Node = match serde_json::from_value(item) {
-                Ok(n) => n,
-                Err(e) => {
-                    warn!("Failed to parse node from list: {}", e);
+            match serde_json::from_value::<Node>(item) {
+                Ok(node) => fresh.observe(node, self.config.heartbeat_timeout),
+                Err(e) => warn!("Failed to parse node from list: {}", e),
+            }
+        }
+        *deadlines = fresh;
+
+        Ok(())
+    }
+
+    /// Check every node whose deadline has passed
+    async fn check_expired(&self, deadlines: &mut NodeDeadlines) {
+        for (node_name, node) in deadlines.take_expired(Instant::now()) {
+            // Guard against the timer firing slightly early relative to wall clock
+            if let Some(remaining) = heartbeat_remaining(&node, self.config.heartbeat_timeout) {
+                if !remaining.is_zero() {
+                    deadlines.schedule(node_name, Instant::now() + remaining, node);
                     continue;
                 }
-            };
-
-            let node_name = match node.metadata.name.as_deref() {
-                Some(n) => n,
-                None => continue,
-            };
+            }
 
-            if let Err(e) = self.check_node(node_name, &node).await {
+            if let Err(e) = self.check_node(&node_name, &node).await {
                 warn!("Failed to check node {}: {}", node_name, e);
+                deadlines.schedule(node_name, Instant::now() + UPDATE_RETRY_DELAY, node);
             }
         }
-
-        Ok(())
     }
 
     /// Check a single node's heartbeat and mark it NotReady if stale
@@ -135,8 +382,9 @@ impl NodeHealthChecker {
         };
 
         let elapsed = Utc::now() - last_heartbeat;
-        let timeout = chrono::Duration::from_std(self.config.heartbeat_timeout)
-            .unwrap_or(chrono::Duration::seconds(40));
+        let timeout =
+            chrono::Duration::from_std(heartbeat_timeout_for(node, self.config.heartbeat_timeout))
+                .unwrap_or(chrono::Duration::seconds(40));
 
         if elapsed <= timeout {
             debug!(
@@ -201,8 +449,11 @@ impl NodeHealthChecker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_api::MockApiServer;
     use k8s_openapi::api::core::v1::{NodeCondition, NodeStatus};
     use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+    use reddwarf_core::InProcessEventBus;
+    use serde_json::json;
 
     fn make_node(name: &str, ready_status: &str, heartbeat_age_secs: i64) -> Node {
         let heartbeat_time = Utc::now() - chrono::Duration::seconds(heartbeat_age_secs);
@@ -256,59 +507,71 @@ mod tests {
     /// and does not attempt an API call)
     #[tokio::test]
     async fn test_fresh_heartbeat_is_noop() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
+        let server = MockApiServer::start().await;
+        let api_client = server.client();
         let config = NodeHealthCheckerConfig {
-            check_interval: Duration::from_secs(15),
+            resync_interval: Duration::from_secs(300),
             heartbeat_timeout: Duration::from_secs(40),
         };
-        let checker = NodeHealthChecker::new(api_client, config);
+        let checker =
+            NodeHealthChecker::new(api_client, Arc::new(InProcessEventBus::new(16)), config);
 
         // 10 seconds ago — well within the 40s timeout
         let node = make_node("fresh-node", "True", 10);
 
-        // Should succeed without making any API call (would fail since no server)
         let result = checker.check_node("fresh-node", &node).await;
         assert!(result.is_ok());
+        assert!(server.requests().is_empty());
     }
 
-    /// Stale heartbeat on a Ready node should attempt to mark it NotReady.
-    /// Since we have no real API server the update call will fail, proving the
-    /// checker detected the staleness and tried to act.
+    /// Stale heartbeat on a Ready node should mark it NotReady
     #[tokio::test]
     async fn test_stale_heartbeat_triggers_update() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
+        let server = MockApiServer::start().await;
+        server.respond("PUT", "/api/v1/nodes/stale-node/status", 200, json!({}));
+        let api_client = server.client();
         let config = NodeHealthCheckerConfig {
-            check_interval: Duration::from_secs(15),
+            resync_interval: Duration::from_secs(300),
             heartbeat_timeout: Duration::from_secs(40),
         };
-        let checker = NodeHealthChecker::new(api_client, config);
+        let checker =
+            NodeHealthChecker::new(api_client, Arc::new(InProcessEventBus::new(16)), config);
 
         // 60 seconds ago — exceeds the 40s timeout
         let node = make_node("stale-node", "True", 60);
 
-        // Should fail because there's no real API server — but that proves it
-        // detected the staleness and attempted to update the node
-        let result = checker.check_node("stale-node", &node).await;
-        assert!(result.is_err());
+        checker.check_node("stale-node", &node).await.unwrap();
+
+        let updates = server.requests_to("PUT", "/api/v1/nodes/stale-node/status");
+        assert_eq!(updates.len(), 1);
+        let ready = &updates[0].json()["status"]["conditions"][0];
+        assert_eq!(ready["status"], "False");
+        assert_eq!(ready["reason"], "NodeStatusUnknown");
+        // The transition to False is stamped now
+        let transitioned: DateTime<Utc> =
+            serde_json::from_value(ready["lastTransitionTime"].clone()).unwrap();
+        assert!(Utc::now() - transitioned < chrono::Duration::seconds(10));
     }
 
     /// A node already marked NotReady with reason "NodeStatusUnknown" should be
     /// skipped (no redundant update)
     #[tokio::test]
     async fn test_already_notready_is_skipped() {
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
+        let server = MockApiServer::start().await;
+        let api_client = server.client();
         let config = NodeHealthCheckerConfig {
-            check_interval: Duration::from_secs(15),
+            resync_interval: Duration::from_secs(300),
             heartbeat_timeout: Duration::from_secs(40),
         };
-        let checker = NodeHealthChecker::new(api_client, config);
+        let checker =
+            NodeHealthChecker::new(api_client, Arc::new(InProcessEventBus::new(16)), config);
 
         // 120 seconds stale but already marked by us
         let node = make_stale_notready_node("dead-node", 120);
 
-        // Should return Ok without attempting an API call
         let result = checker.check_node("dead-node", &node).await;
         assert!(result.is_ok());
+        assert!(server.requests().is_empty());
     }
 
     /// last_transition_time should be preserved when a node is already False
@@ -337,16 +600,137 @@ mod tests {
             ..Default::default()
         };
 
-        let api_client = Arc::new(ApiClient::new("http://127.0.0.1:6443"));
+        let server = MockApiServer::start().await;
+        server.respond("PUT", "/api/v1/nodes/failing-node/status", 200, json!({}));
         let config = NodeHealthCheckerConfig {
-            check_interval: Duration::from_secs(15),
+            resync_interval: Duration::from_secs(300),
             heartbeat_timeout: Duration::from_secs(40),
         };
-        let checker = NodeHealthChecker::new(api_client, config);
+        let checker = NodeHealthChecker::new(
+            server.client(),
+            Arc::new(InProcessEventBus::new(16)),
+            config,
+        );
+
+        // Updated, as the reason differs, but still False since before
+        checker.check_node("failing-node", &node).await.unwrap();
+        let updates = server.requests_to("PUT", "/api/v1/nodes/failing-node/status");
+        assert_eq!(updates.len(), 1);
+        let ready = &updates[0].json()["status"]["conditions"][0];
+        assert_eq!(ready["reason"], "NodeStatusUnknown");
+        assert_eq!(
+            ready["lastTransitionTime"],
+            serde_json::to_value(Time(original_transition_time)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_deadlines_track_fresh_nodes() {
+        let mut deadlines = NodeDeadlines::default();
+        let timeout = Duration::from_secs(40);
+
+        deadlines.observe(make_node("a", "True", 10), timeout);
+        deadlines.observe(make_node("b", "True", 35), timeout);
+
+        // "b" has less time left, so it determines the next wakeup
+        let next = deadlines.next_deadline().unwrap();
+        assert!(next <= Instant::now() + Duration::from_secs(6));
+        assert!(deadlines.take_expired(Instant::now()).is_empty());
+    }
+
+    #[test]
+    fn test_deadlines_expire_stale_nodes() {
+        let mut deadlines = NodeDeadlines::default();
+        let timeout = Duration::from_secs(40);
+
+        deadlines.observe(make_node("stale", "True", 60), timeout);
+        deadlines.observe(make_node("fresh", "True", 5), timeout);
+
+        let expired = deadlines.take_expired(Instant::now());
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, "stale");
+        assert_eq!(deadlines.nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_lease_renewal_counts_as_heartbeat() {
+        let mut deadlines = NodeDeadlines::default();
+        let timeout = Duration::from_secs(40);
+        let lease: Lease = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "leased", "namespace": NODE_LEASE_NAMESPACE},
+            "spec": {"holderIdentity": "leased", "renewTime": Utc::now()}
+        }))
+        .unwrap();
+
+        // A stale Ready heartbeat is fresh again once the node's Lease is
+        // renewed, whether the renewal is seen before or after the node
+        deadlines.observe(make_node("leased", "True", 60), timeout);
+        deadlines.renew(&lease, timeout);
+        assert!(deadlines.take_expired(Instant::now()).is_empty());
+
+        let mut deadlines = NodeDeadlines::default();
+        deadlines.renew(&lease, timeout);
+        deadlines.observe(make_node("leased", "True", 60), timeout);
+        assert!(deadlines.take_expired(Instant::now()).is_empty());
+
+        // Without the Lease the node's own heartbeat decides
+        deadlines.forget_lease("leased");
+        deadlines.observe(make_node("leased", "True", 60), timeout);
+        assert_eq!(deadlines.take_expired(Instant::now()).len(), 1);
+    }
+
+    #[test]
+    fn test_deadlines_ignore_nodes_already_marked() {
+        let mut deadlines = NodeDeadlines::default();
+        let timeout = Duration::from_secs(40);
+
+        deadlines.observe(make_node("n1", "True", 10), timeout);
+        // Watch event shows we already marked the node NotReady: stop its timer
+        deadlines.observe(make_stale_notready_node("n1", 120), timeout);
+
+        assert!(deadlines.next_deadline().is_none());
+    }
+
+    #[test]
+    fn test_deadlines_honor_timeout_annotation() {
+        let mut deadlines = NodeDeadlines::default();
+        let timeout = Duration::from_secs(40);
+
+        // 60s stale under the default, but this node tolerates 120s
+        let mut node = make_node("flaky", "True", 60);
+        node.metadata.annotations = Some(
+            [(
+                crate::node_timing::HEARTBEAT_TIMEOUT_ANNOTATION.to_string(),
+                "120s".to_string(),
+            )]
+            .into(),
+        );
+        deadlines.observe(node, timeout);
+
+        assert!(deadlines.take_expired(Instant::now()).is_empty());
+        assert!(deadlines.next_deadline().unwrap() > Instant::now() + Duration::from_secs(50));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_annotation_suppresses_update() {
+        let server = MockApiServer::start().await;
+        let api_client = server.client();
+        let checker = NodeHealthChecker::new(
+            api_client,
+            Arc::new(InProcessEventBus::new(16)),
+            NodeHealthCheckerConfig::default(),
+        );
+
+        let mut node = make_node("flaky", "True", 60);
+        node.metadata.annotations = Some(
+            [(
+                crate::node_timing::HEARTBEAT_TIMEOUT_ANNOTATION.to_string(),
+                "2m".to_string(),
+            )]
+            .into(),
+        );
 
-        // Will fail at the API call, but we can verify the logic by checking that
-        // the code path was entered (it didn't skip due to already-notready check)
-        let result = checker.check_node("failing-node", &node).await;
-        assert!(result.is_err()); // proves it tried to update (different reason)
+        assert!(checker.check_node("flaky", &node).await.is_ok());
+        assert!(server.requests().is_empty());
     }
 }
@@ -0,0 +1,101 @@
+use k8s_openapi::api::core::v1::Node;
+use std::time::Duration;
+
+/// Node annotation overriding how often the node agent sends heartbeats
+pub const HEARTBEAT_INTERVAL_ANNOTATION: &str = "reddwarf.io/heartbeat-interval";
+
+/// Node annotation overriding how long the health checker waits for a
+/// heartbeat before marking the node NotReady
+pub const HEARTBEAT_TIMEOUT_ANNOTATION: &str = "reddwarf.io/heartbeat-timeout";
+
+/// Parse a duration such as `"500ms"`, `"30s"`, `"2m"`, `"1h"` or a bare
+/// number of seconds. Returns `None` for malformed or zero values.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let n: u64 = digits.parse().ok()?;
+
+    let duration = match unit {
+        "ms" => Duration::from_millis(n),
+        "" | "s" => Duration::from_secs(n),
+        "m" => Duration::from_secs(n.checked_mul(60)?),
+        "h" => Duration::from_secs(n.checked_mul(3600)?),
+        _ => return None,
+    };
+
+    (!duration.is_zero()).then_some(duration)
+}
+
+/// Read a duration-valued annotation from a Node
+pub fn node_annotation_duration(node: &Node, annotation: &str) -> Option<Duration> {
+    node.metadata
+        .annotations
+        .as_ref()?
+        .get(annotation)
+        .and_then(|v| parse_duration(v))
+}
+
+/// Effective heartbeat timeout for a node, honoring its annotation override
+pub fn heartbeat_timeout_for(node: &Node, default: Duration) -> Duration {
+    node_annotation_duration(node, HEARTBEAT_TIMEOUT_ANNOTATION).unwrap_or(default)
+}
+
+/// Effective heartbeat interval for a node, honoring its annotation override
+pub fn heartbeat_interval_for(node: &Node, default: Duration) -> Duration {
+    node_annotation_duration(node, HEARTBEAT_INTERVAL_ANNOTATION).unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    fn node_with_annotation(key: &str, value: &str) -> Node {
+        Node {
+            metadata: ObjectMeta {
+                name: Some("n1".to_string()),
+                annotations: Some([(key.to_string(), value.to_string())].into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration(" 10s "), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_invalid() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("abc"), None);
+        assert_eq!(parse_duration("10d"), None);
+        assert_eq!(parse_duration("-5s"), None);
+    }
+
+    #[test]
+    fn test_timeout_override_from_annotation() {
+        let default = Duration::from_secs(40);
+
+        let node = node_with_annotation(HEARTBEAT_TIMEOUT_ANNOTATION, "120s");
+        assert_eq!(
+            heartbeat_timeout_for(&node, default),
+            Duration::from_secs(120)
+        );
+
+        let node = node_with_annotation(HEARTBEAT_TIMEOUT_ANNOTATION, "bogus");
+        assert_eq!(heartbeat_timeout_for(&node, default), default);
+
+        assert_eq!(heartbeat_timeout_for(&Node::default(), default), default);
+    }
+}
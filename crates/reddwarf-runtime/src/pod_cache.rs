@@ -0,0 +1,199 @@
+use crate::error::{Result, RuntimeError};
+use k8s_openapi::api::core::v1::Pod;
+use reddwarf_storage::RedbBackend;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Default bound on the number of cached pods
+pub const DEFAULT_POD_CACHE_CAPACITY: usize = 1024;
+
+/// Last-known pods assigned to one node, kept in the storage's node cache
+/// table so zone reconciliation can continue while the API server is
+/// unreachable
+///
+/// Cache keys:
+/// - `pods/{node}/{namespace}/{name}` → pod JSON
+pub struct PodCache {
+    storage: Arc<RedbBackend>,
+    node_name: String,
+    capacity: usize,
+}
+
+impl PodCache {
+    pub fn new(storage: Arc<RedbBackend>, node_name: impl Into<String>) -> Self {
+        Self {
+            storage,
+            node_name: node_name.into(),
+            capacity: DEFAULT_POD_CACHE_CAPACITY,
+        }
+    }
+
+    /// Cache at most `capacity` pods
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    fn prefix(&self) -> String {
+        format!("pods/{}/", self.node_name)
+    }
+
+    fn key(&self, namespace: &str, name: &str) -> String {
+        format!("{}{}/{}", self.prefix(), namespace, name)
+    }
+
+    /// The cache key of a pod assigned to this node
+    fn pod_key(&self, pod: &Pod) -> Option<String> {
+        let node_name = pod.spec.as_ref()?.node_name.as_deref()?;
+        if node_name != self.node_name {
+            return None;
+        }
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        Some(self.key(namespace, pod.metadata.name.as_deref()?))
+    }
+
+    /// Replace the cache with the pods of a full listing that are assigned
+    /// to this node
+    pub fn replace(&self, pods: &[Pod]) -> Result<usize> {
+        let mut entries = Vec::new();
+        for pod in pods {
+            let Some(key) = self.pod_key(pod) else {
+                continue;
+            };
+            if entries.len() == self.capacity {
+                warn!(
+                    "Pod cache is full ({} pods); not caching the rest",
+                    self.capacity
+                );
+                break;
+            }
+            entries.push((key.into_bytes(), encode(pod)?));
+        }
+
+        self.storage
+            .cache_replace(self.prefix().as_bytes(), &entries)?;
+        debug!("Pod cache: cached {} pod(s)", entries.len());
+        Ok(entries.len())
+    }
+
+    /// Record the latest state of a pod; pods of other nodes are dropped
+    pub fn upsert(&self, pod: &Pod) -> Result<()> {
+        let Some(key) = self.pod_key(pod) else {
+            if let Some(name) = pod.metadata.name.as_deref() {
+                let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+                self.remove(namespace, name)?;
+            }
+            return Ok(());
+        };
+
+        let cached = self.storage.cache_scan(key.as_bytes())?;
+        let exists = cached.iter().any(|(k, _)| k.as_ref() == key.as_bytes());
+        if !exists && self.len()? >= self.capacity {
+            warn!(
+                "Pod cache is full ({} pods); not caching {}",
+                self.capacity, key
+            );
+            return Ok(());
+        }
+        self.storage.cache_put(key.as_bytes(), &encode(pod)?)?;
+        Ok(())
+    }
+
+    /// Forget a pod
+    pub fn remove(&self, namespace: &str, name: &str) -> Result<()> {
+        self.storage
+            .cache_delete(self.key(namespace, name).as_bytes())?;
+        Ok(())
+    }
+
+    /// Number of cached pods
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.storage.cache_scan(self.prefix().as_bytes())?.len())
+    }
+
+    /// Whether no pods are cached
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// The cached pods; entries that no longer parse are skipped
+    pub fn pods(&self) -> Result<Vec<Pod>> {
+        let mut pods = Vec::new();
+        for (key, value) in self.storage.cache_scan(self.prefix().as_bytes())? {
+            match serde_json::from_slice(&value) {
+                Ok(pod) => pods.push(pod),
+                Err(e) => warn!(
+                    "Pod cache: skipping unreadable entry {}: {}",
+                    String::from_utf8_lossy(&key),
+                    e
+                ),
+            }
+        }
+        Ok(pods)
+    }
+}
+
+fn encode(pod: &Pod) -> Result<Vec<u8>> {
+    serde_json::to_vec(pod)
+        .map_err(|e| RuntimeError::internal_error(format!("Failed to encode pod: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::PodSpec;
+    use tempfile::tempdir;
+
+    fn pod(name: &str, node: &str) -> Pod {
+        Pod {
+            metadata: k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some("default".to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                node_name: Some(node.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_replace_upsert_and_bound() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("cache.redb")).unwrap());
+        let cache = PodCache::new(storage, "node1").with_capacity(2);
+
+        // Only this node's pods are cached
+        let listed = [pod("a", "node1"), pod("b", "node2"), pod("c", "node1")];
+        assert_eq!(cache.replace(&listed).unwrap(), 2);
+        let names = |cache: &PodCache| {
+            cache
+                .pods()
+                .unwrap()
+                .into_iter()
+                .map(|p| p.metadata.name.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&cache), vec!["a", "c"]);
+
+        // Full: new pods are not cached, known ones are refreshed
+        cache.upsert(&pod("d", "node1")).unwrap();
+        assert_eq!(names(&cache), vec!["a", "c"]);
+        let mut updated = pod("a", "node1");
+        updated.metadata.uid = Some("uid-a".to_string());
+        cache.upsert(&updated).unwrap();
+        assert_eq!(
+            cache.pods().unwrap()[0].metadata.uid.as_deref(),
+            Some("uid-a")
+        );
+
+        // A pod moved off the node is forgotten
+        cache.upsert(&pod("c", "node2")).unwrap();
+        assert_eq!(names(&cache), vec!["a"]);
+
+        cache.remove("default", "a").unwrap();
+        assert!(cache.is_empty().unwrap());
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::Utc;
+use k8s_openapi::api::core::v1::{Pod, PodCondition};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+/// The pod is bound to a node
+pub const POD_SCHEDULED: &str = "PodScheduled";
+/// The pod's zone is provisioned and booted (zones have no separate init
+/// containers, so booting the zone completes initialization)
+pub const INITIALIZED: &str = "Initialized";
+/// The pod's containers pass their readiness probes
+pub const CONTAINERS_READY: &str = "ContainersReady";
+/// The containers are ready and every readiness gate is `True`
+pub const READY: &str = "Ready";
+
+/// Why a condition is not `True`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Unready {
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Unready {
+    pub fn new(reason: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            reason: Some(reason.into()),
+            message: Some(message.into()),
+        }
+    }
+
+    /// Not ready without a machine-readable reason
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            reason: None,
+            message: Some(message.into()),
+        }
+    }
+}
+
+/// How far a pod bound to this node has come, from which its standard
+/// conditions follow
+#[derive(Debug, Clone, PartialEq)]
+pub enum PodProgress {
+    /// The zone is not up yet
+    Initializing(Unready),
+    /// The zone is up; the containers are ready unless a reason is given
+    Started(Option<Unready>),
+}
+
+/// The pod's conditions for `progress`: `PodScheduled`, `Initialized`,
+/// `ContainersReady` and `Ready`, followed by the pod's other conditions
+/// (readiness gates, `DisruptionTarget`) as they are. Transition times are
+/// kept for conditions whose status does not change.
+pub fn pod_conditions(pod: &Pod, progress: &PodProgress) -> Vec<PodCondition> {
+    let existing = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default();
+
+    let (initialized, containers_ready) = match progress {
+        PodProgress::Initializing(unready) => (Some(unready), Some(unready)),
+        PodProgress::Started(unready) => (None, unready.as_ref()),
+    };
+    let gates = readiness_gates_unready(pod, existing);
+    let ready = containers_ready.or(gates.as_ref());
+
+    let mut conditions = vec![
+        condition(existing, POD_SCHEDULED, None),
+        condition(existing, INITIALIZED, initialized),
+        condition(existing, CONTAINERS_READY, containers_ready),
+        condition(existing, READY, ready),
+    ];
+    conditions.extend(
+        existing
+            .iter()
+            .filter(|c| {
+                ![POD_SCHEDULED, INITIALIZED, CONTAINERS_READY, READY].contains(&c.type_.as_str())
+            })
+            .cloned(),
+    );
+    conditions
+}
+
+/// Whether `conditions` differ from the pod's in a status or reason; a
+/// changed message alone (e.g. of a still failing probe) is not worth a
+/// status update
+pub fn conditions_changed(pod: &Pod, conditions: &[PodCondition]) -> bool {
+    let existing = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default();
+    existing.len() != conditions.len()
+        || existing
+            .iter()
+            .zip(conditions)
+            .any(|(a, b)| a.type_ != b.type_ || a.status != b.status || a.reason != b.reason)
+}
+
+/// Whether the pod reports its `Ready` condition as `True`
+pub fn is_pod_ready(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .any(|c| c.type_ == READY && c.status == "True")
+}
+
+/// Why the pod's readiness gates hold it back, if they do
+fn readiness_gates_unready(pod: &Pod, existing: &[PodCondition]) -> Option<Unready> {
+    let gates = pod.spec.as_ref()?.readiness_gates.as_deref()?;
+    let messages: Vec<String> = gates
+        .iter()
+        .filter_map(
+            |gate| match existing.iter().find(|c| c.type_ == gate.condition_type) {
+                None => Some(format!(
+                    "corresponding condition of pod readiness gate \"{}\" does not exist.",
+                    gate.condition_type
+                )),
+                Some(c) if c.status != "True" => Some(format!(
+                    "the status of pod readiness gate \"{}\" is not \"True\", but {}",
+                    gate.condition_type, c.status
+                )),
+                Some(_) => None,
+            },
+        )
+        .collect();
+    if messages.is_empty() {
+        return None;
+    }
+    Some(Unready::new("ReadinessGatesNotReady", messages.join(", ")))
+}
+
+fn condition(existing: &[PodCondition], type_: &str, unready: Option<&Unready>) -> PodCondition {
+    let status = if unready.is_some() { "False" } else { "True" };
+    let last_transition_time = match existing.iter().find(|c| c.type_ == type_) {
+        Some(c) if c.status == status => c.last_transition_time.clone(),
+        _ => Some(Time(Utc::now())),
+    };
+    PodCondition {
+        type_: type_.to_string(),
+        status: status.to_string(),
+        reason: unready.and_then(|u| u.reason.clone()),
+        message: unready.and_then(|u| u.message.clone()),
+        last_transition_time,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodReadinessGate, PodSpec, PodStatus};
+
+    fn find<'a>(conditions: &'a [PodCondition], type_: &str) -> &'a PodCondition {
+        conditions.iter().find(|c| c.type_ == type_).unwrap()
+    }
+
+    #[test]
+    fn test_pod_conditions_transitions_and_readiness_gates() {
+        let mut pod = Pod {
+            spec: Some(PodSpec {
+                readiness_gates: Some(vec![PodReadinessGate {
+                    condition_type: "example.com/lb-ready".to_string(),
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Provisioning: scheduled, nothing else
+        let initializing = Unready::new("ProvisioningBackoff", "retrying");
+        let conditions = pod_conditions(&pod, &PodProgress::Initializing(initializing));
+        assert_eq!(find(&conditions, POD_SCHEDULED).status, "True");
+        assert_eq!(find(&conditions, INITIALIZED).status, "False");
+        assert_eq!(find(&conditions, CONTAINERS_READY).status, "False");
+        assert_eq!(
+            find(&conditions, READY).reason.as_deref(),
+            Some("ProvisioningBackoff")
+        );
+        pod.status = Some(PodStatus {
+            conditions: Some(conditions.clone()),
+            ..Default::default()
+        });
+
+        // Containers ready, but the readiness gate has not been reported
+        let started = pod_conditions(&pod, &PodProgress::Started(None));
+        assert_eq!(find(&started, INITIALIZED).status, "True");
+        assert_eq!(find(&started, CONTAINERS_READY).status, "True");
+        let ready = find(&started, READY);
+        assert_eq!(ready.status, "False");
+        assert_eq!(ready.reason.as_deref(), Some("ReadinessGatesNotReady"));
+        assert_eq!(
+            find(&started, POD_SCHEDULED).last_transition_time,
+            find(&conditions, POD_SCHEDULED).last_transition_time
+        );
+        assert!(conditions_changed(&pod, &started));
+
+        // The gate turns True and the pod becomes Ready; the gate's own
+        // condition is kept
+        let mut with_gate = started.clone();
+        with_gate.push(PodCondition {
+            type_: "example.com/lb-ready".to_string(),
+            status: "True".to_string(),
+            ..Default::default()
+        });
+        pod.status.as_mut().unwrap().conditions = Some(with_gate);
+        let ready = pod_conditions(&pod, &PodProgress::Started(None));
+        assert_eq!(find(&ready, READY).status, "True");
+        assert_eq!(find(&ready, "example.com/lb-ready").status, "True");
+
+        pod.status.as_mut().unwrap().conditions = Some(ready);
+        assert!(!conditions_changed(
+            &pod,
+            &pod_conditions(&pod, &PodProgress::Started(None))
+        ));
+    }
+}
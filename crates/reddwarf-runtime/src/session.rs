@@ -0,0 +1,309 @@
+//! Interactive sessions with processes in zones
+//!
+//! Commands that need no terminal run through `zlogin` with their standard
+//! streams piped. Pseudo-terminals and container processes belong to the
+//! reddwarf brand's supervisor inside the zone: the agent connects to the
+//! supervisor's [`SUPERVISOR_SOCKET`] from the global zone, sends a JSON
+//! header line naming the session, and then exchanges frames of a channel
+//! byte, a big-endian `u32` length and the payload, with the channels
+//! numbered as in the Kubernetes streaming protocol.
+
+use crate::error::{Result, RuntimeError};
+use reddwarf_core::{SessionEnd, SessionInput, SessionOutput, SessionRequest, SessionTarget};
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::Child;
+use tracing::debug;
+
+/// Path of the supervisor's session socket inside a reddwarf brand zone
+pub const SUPERVISOR_SOCKET: &str = "/var/run/reddwarf/supervisor.sock";
+
+const STDIN_CHANNEL: u8 = 0;
+const STDOUT_CHANNEL: u8 = 1;
+const STDERR_CHANNEL: u8 = 2;
+/// Payload: the exit status as a big-endian `i32`
+const EXIT_CHANNEL: u8 = 3;
+/// Payload: width and height as big-endian `u16`s
+const RESIZE_CHANNEL: u8 = 4;
+const CLOSE_STDIN_CHANNEL: u8 = 255;
+
+/// Largest frame payload accepted from the supervisor
+const MAX_FRAME: usize = 1024 * 1024;
+
+/// Bytes read from a piped stream at a time
+const READ_CHUNK: usize = 8192;
+
+/// First line sent to the supervisor
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionHeader<'a> {
+    container: &'a str,
+    /// Command to start; attaches to the container's process when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<&'a [String]>,
+    stdin: bool,
+    tty: bool,
+}
+
+fn header_line(request: &SessionRequest) -> Vec<u8> {
+    let header = SessionHeader {
+        container: &request.container,
+        command: match &request.target {
+            SessionTarget::Exec(command) => Some(command),
+            SessionTarget::Attach => None,
+        },
+        stdin: request.stdin,
+        tty: request.tty,
+    };
+    let mut line = serde_json::to_vec(&header).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+fn encode_input(input: &SessionInput) -> Vec<u8> {
+    let (channel, payload) = match input {
+        SessionInput::Stdin(data) => (STDIN_CHANNEL, data.clone()),
+        SessionInput::CloseStdin => (CLOSE_STDIN_CHANNEL, Vec::new()),
+        SessionInput::Resize(size) => {
+            let mut payload = size.width.to_be_bytes().to_vec();
+            payload.extend(size.height.to_be_bytes());
+            (RESIZE_CHANNEL, payload)
+        }
+    };
+    let mut frame = vec![channel];
+    frame.extend((payload.len() as u32).to_be_bytes());
+    frame.extend(payload);
+    frame
+}
+
+/// Read the next output frame; `None` at the end of the stream
+async fn read_output<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SessionOutput>> {
+    let mut head = [0u8; 5];
+    match reader.read_exact(&mut head).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(session_error(e)),
+    }
+    let len = u32::from_be_bytes([head[1], head[2], head[3], head[4]]) as usize;
+    if len > MAX_FRAME {
+        return Err(RuntimeError::internal_error(format!(
+            "Supervisor sent a {} byte frame, more than the {} allowed",
+            len, MAX_FRAME
+        )));
+    }
+    let mut payload = vec![0u8; len];
+    reader
+        .read_exact(&mut payload)
+        .await
+        .map_err(session_error)?;
+
+    Ok(Some(match head[0] {
+        STDOUT_CHANNEL => SessionOutput::Stdout(payload),
+        STDERR_CHANNEL => SessionOutput::Stderr(payload),
+        EXIT_CHANNEL if payload.len() == 4 => SessionOutput::Exit(i32::from_be_bytes([
+            payload[0], payload[1], payload[2], payload[3],
+        ])),
+        channel => {
+            return Err(RuntimeError::internal_error(format!(
+                "Supervisor sent a frame on unknown channel {}",
+                channel
+            )))
+        }
+    }))
+}
+
+fn session_error(e: std::io::Error) -> RuntimeError {
+    RuntimeError::internal_error(format!("Session stream failed: {}", e))
+}
+
+/// Relay a session over a connection to a zone's supervisor until the
+/// process exits or either side goes away
+pub async fn relay_supervisor<S>(stream: S, request: &SessionRequest, end: SessionEnd) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    writer
+        .write_all(&header_line(request))
+        .await
+        .map_err(session_error)?;
+
+    let SessionEnd { mut input, output } = end;
+    tokio::spawn(async move {
+        while let Some(message) = input.recv().await {
+            if writer.write_all(&encode_input(&message)).await.is_err() {
+                break;
+            }
+        }
+    });
+    tokio::spawn(async move {
+        loop {
+            match read_output(&mut reader).await {
+                Ok(Some(message)) => {
+                    let exited = matches!(message, SessionOutput::Exit(_));
+                    if output.send(message).await.is_err() || exited {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    debug!("Ending session: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Relay a session with a child process whose standard streams are piped,
+/// reporting its exit status once all its output is passed on
+pub fn relay_child(mut child: Child, end: SessionEnd) {
+    let SessionEnd { mut input, output } = end;
+
+    let mut stdin = child.stdin.take();
+    tokio::spawn(async move {
+        while let Some(message) = input.recv().await {
+            match message {
+                SessionInput::Stdin(data) => {
+                    if let Some(pipe) = stdin.as_mut() {
+                        if pipe.write_all(&data).await.is_err() {
+                            stdin = None;
+                        }
+                    }
+                }
+                SessionInput::CloseStdin => stdin = None,
+                // Piped processes have no terminal to resize
+                SessionInput::Resize(_) => {}
+            }
+        }
+    });
+
+    let stdout = child
+        .stdout
+        .take()
+        .map(|pipe| tokio::spawn(pump(pipe, output.clone(), SessionOutput::Stdout)));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| tokio::spawn(pump(pipe, output.clone(), SessionOutput::Stderr)));
+    tokio::spawn(async move {
+        for pump in [stdout, stderr].into_iter().flatten() {
+            let _ = pump.await;
+        }
+        let code = match child.wait().await {
+            Ok(status) => status.code().unwrap_or(-1),
+            Err(_) => -1,
+        };
+        let _ = output.send(SessionOutput::Exit(code)).await;
+    });
+}
+
+async fn pump<R: AsyncRead + Unpin>(
+    mut pipe: R,
+    output: tokio::sync::mpsc::Sender<SessionOutput>,
+    wrap: fn(Vec<u8>) -> SessionOutput,
+) {
+    let mut buf = vec![0u8; READ_CHUNK];
+    loop {
+        match pipe.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => {
+                if output.send(wrap(buf[..n].to_vec())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_core::{ProcessSession, TerminalSize};
+    use std::process::Stdio;
+
+    async fn collect(session: &mut ProcessSession) -> (Vec<u8>, Vec<u8>, Option<i32>) {
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        while let Some(message) = session.output.recv().await {
+            match message {
+                SessionOutput::Stdout(data) => stdout.extend(data),
+                SessionOutput::Stderr(data) => stderr.extend(data),
+                SessionOutput::Exit(code) => return (stdout, stderr, Some(code)),
+            }
+        }
+        (stdout, stderr, None)
+    }
+
+    #[tokio::test]
+    async fn test_relay_child_passes_streams_and_exit_status() {
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "cat; echo oops >&2; exit 3"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (mut session, end) = ProcessSession::pair();
+        relay_child(child, end);
+
+        session
+            .input
+            .send(SessionInput::Stdin(b"hello\n".to_vec()))
+            .await
+            .unwrap();
+        session.input.send(SessionInput::CloseStdin).await.unwrap();
+
+        let (stdout, stderr, code) = collect(&mut session).await;
+        assert_eq!(stdout, b"hello\n");
+        assert_eq!(stderr, b"oops\n");
+        assert_eq!(code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_relay_supervisor_frames() {
+        let (agent, mut supervisor) = tokio::io::duplex(1024);
+        let request = SessionRequest {
+            container: "shell".to_string(),
+            target: SessionTarget::Attach,
+            stdin: true,
+            tty: true,
+        };
+        let (mut session, end) = ProcessSession::pair();
+        relay_supervisor(agent, &request, end).await.unwrap();
+
+        let mut header = vec![0u8; header_line(&request).len()];
+        supervisor.read_exact(&mut header).await.unwrap();
+        assert_eq!(
+            header,
+            b"{\"container\":\"shell\",\"stdin\":true,\"tty\":true}\n"
+        );
+
+        // Input arrives framed
+        let size = TerminalSize {
+            width: 80,
+            height: 24,
+        };
+        session
+            .input
+            .send(SessionInput::Resize(size))
+            .await
+            .unwrap();
+        let mut frame = [0u8; 9];
+        supervisor.read_exact(&mut frame).await.unwrap();
+        assert_eq!(frame, [RESIZE_CHANNEL, 0, 0, 0, 4, 0, 80, 0, 24]);
+
+        // Output frames end with the exit status
+        supervisor
+            .write_all(&[STDOUT_CHANNEL, 0, 0, 0, 2, b'$', b' '])
+            .await
+            .unwrap();
+        supervisor
+            .write_all(&[EXIT_CHANNEL, 0, 0, 0, 4, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let (stdout, _, code) = collect(&mut session).await;
+        assert_eq!(stdout, b"$ ");
+        assert_eq!(code, Some(0));
+    }
+}
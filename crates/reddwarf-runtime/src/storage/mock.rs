@@ -1,8 +1,8 @@
 use crate::error::Result;
-use crate::storage::{StorageEngine, VolumeInfo};
+use crate::storage::{StorageEngine, VolumeInfo, IMAGE_SNAPSHOT};
 use crate::types::{StoragePoolConfig, ZoneStorageOpts};
 use async_trait::async_trait;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::debug;
@@ -14,6 +14,9 @@ use tracing::debug;
 pub struct MockStorageEngine {
     config: StoragePoolConfig,
     datasets: Arc<RwLock<HashSet<String>>>,
+    snapshots: Arc<RwLock<HashSet<String>>>,
+    /// Origin snapshot of each cloned dataset
+    origins: Arc<RwLock<HashMap<String, String>>>,
 }
 
 impl MockStorageEngine {
@@ -21,8 +24,50 @@ impl MockStorageEngine {
         Self {
             config,
             datasets: Arc::new(RwLock::new(HashSet::new())),
+            snapshots: Arc::new(RwLock::new(HashSet::new())),
+            origins: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+
+    /// The snapshot a dataset was cloned from, if any
+    pub async fn origin(&self, dataset: &str) -> Option<String> {
+        self.origins.read().await.get(dataset).cloned()
+    }
+
+    /// Remove a dataset with its snapshots, first promoting a clone of each
+    /// snapshot the way `zfs promote` would: the snapshot moves to the clone
+    /// and its other clones follow it
+    async fn destroy_dataset(&self, dataset: &str) {
+        let mut snapshots = self.snapshots.write().await;
+        let mut origins = self.origins.write().await;
+        let prefix = format!("{}@", dataset);
+
+        let owned: Vec<String> = snapshots
+            .iter()
+            .filter(|s| s.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for snapshot in owned {
+            snapshots.remove(&snapshot);
+            let Some(clone) = origins
+                .iter()
+                .filter(|(_, origin)| **origin == snapshot)
+                .map(|(clone, _)| clone.clone())
+                .min()
+            else {
+                continue;
+            };
+            let moved = format!("{}@{}", clone, &snapshot[prefix.len()..]);
+            origins.remove(&clone);
+            for origin in origins.values_mut().filter(|o| **o == snapshot) {
+                *origin = moved.clone();
+            }
+            snapshots.insert(moved);
+        }
+
+        origins.remove(dataset);
+        self.datasets.write().await.remove(dataset);
+    }
 }
 
 #[async_trait]
@@ -36,26 +81,66 @@ impl StorageEngine for MockStorageEngine {
         Ok(())
     }
 
-    async fn create_zone_dataset(&self, zone_name: &str, _opts: &ZoneStorageOpts) -> Result<()> {
+    async fn create_zone_dataset(&self, zone_name: &str, opts: &ZoneStorageOpts) -> Result<()> {
         let dataset = self.config.zone_dataset(zone_name);
         self.datasets.write().await.insert(dataset.clone());
+        if let Some(ref clone_from) = opts.clone_from {
+            self.origins
+                .write()
+                .await
+                .insert(dataset.clone(), clone_from.clone());
+        }
         debug!("Mock: created zone dataset {}", dataset);
         Ok(())
     }
 
     async fn destroy_zone_dataset(&self, zone_name: &str) -> Result<()> {
         let dataset = self.config.zone_dataset(zone_name);
-        self.datasets.write().await.remove(&dataset);
+        self.destroy_dataset(&dataset).await;
         debug!("Mock: destroyed zone dataset {}", dataset);
         Ok(())
     }
 
     async fn create_snapshot(&self, dataset: &str, snapshot_name: &str) -> Result<()> {
         let snap = format!("{}@{}", dataset, snapshot_name);
+        self.snapshots.write().await.insert(snap.clone());
         debug!("Mock: created snapshot {}", snap);
         Ok(())
     }
 
+    async fn image_snapshot(&self, image: &str) -> Result<Option<String>> {
+        let snapshot = format!("{}@{}", self.config.image_dataset(image), IMAGE_SNAPSHOT);
+        Ok(self
+            .snapshots
+            .read()
+            .await
+            .contains(&snapshot)
+            .then_some(snapshot))
+    }
+
+    async fn cache_image(&self, image: &str, zone_name: &str) -> Result<String> {
+        let dataset = self.config.image_dataset(image);
+        let snapshot = format!("{}@{}", dataset, IMAGE_SNAPSHOT);
+
+        // Snapshot, clone and promote in one step: the image owns the
+        // snapshot and the zone is its clone
+        self.datasets.write().await.insert(dataset.clone());
+        self.snapshots.write().await.insert(snapshot.clone());
+        self.origins
+            .write()
+            .await
+            .insert(self.config.zone_dataset(zone_name), snapshot.clone());
+        debug!("Mock: cached image {} from zone {}", dataset, zone_name);
+        Ok(snapshot)
+    }
+
+    async fn destroy_image(&self, image: &str) -> Result<()> {
+        let dataset = self.config.image_dataset(image);
+        self.destroy_dataset(&dataset).await;
+        debug!("Mock: destroyed cached image {}", dataset);
+        Ok(())
+    }
+
     async fn create_volume(&self, name: &str, _quota: Option<&str>) -> Result<()> {
         let dataset = self.config.volume_dataset(name);
         self.datasets.write().await.insert(dataset.clone());
@@ -133,6 +218,51 @@ mod tests {
             .contains("testpool/zones/myzone"));
     }
 
+    #[tokio::test]
+    async fn test_mock_image_cache_promotes_clones() {
+        let config = StoragePoolConfig::from_pool("testpool");
+        let engine = MockStorageEngine::new(config);
+        let image = "/images/alpine.tar.gz";
+
+        assert_eq!(engine.image_snapshot(image).await.unwrap(), None);
+
+        engine
+            .create_zone_dataset("first", &ZoneStorageOpts::default())
+            .await
+            .unwrap();
+        let snapshot = engine.cache_image(image, "first").await.unwrap();
+        assert_eq!(snapshot, "testpool/images/images-alpine.tar.gz@base");
+        assert_eq!(
+            engine.image_snapshot(image).await.unwrap().as_deref(),
+            Some(snapshot.as_str())
+        );
+
+        for zone in ["second", "third"] {
+            let opts = ZoneStorageOpts {
+                clone_from: Some(snapshot.clone()),
+                quota: None,
+            };
+            engine.create_zone_dataset(zone, &opts).await.unwrap();
+        }
+
+        // Garbage collecting the image hands its snapshot to a clone
+        engine.destroy_image(image).await.unwrap();
+        assert_eq!(engine.image_snapshot(image).await.unwrap(), None);
+        assert_eq!(engine.origin("testpool/zones/first").await, None);
+        assert_eq!(
+            engine.origin("testpool/zones/second").await.as_deref(),
+            Some("testpool/zones/first@base")
+        );
+
+        // ...and destroying that zone hands it on again
+        engine.destroy_zone_dataset("first").await.unwrap();
+        assert_eq!(engine.origin("testpool/zones/second").await, None);
+        assert_eq!(
+            engine.origin("testpool/zones/third").await.as_deref(),
+            Some("testpool/zones/second@base")
+        );
+    }
+
     #[tokio::test]
     async fn test_mock_volume_lifecycle() {
         let config = StoragePoolConfig::from_pool("testpool");
@@ -10,6 +10,9 @@ use crate::error::Result;
 use crate::types::{StoragePoolConfig, ZoneStorageOpts};
 use async_trait::async_trait;
 
+/// Snapshot of an image dataset that zone roots are cloned from
+pub const IMAGE_SNAPSHOT: &str = "base";
+
 /// Information about a persistent volume
 #[derive(Debug, Clone)]
 pub struct VolumeInfo {
@@ -32,12 +35,27 @@ pub trait StorageEngine: Send + Sync {
     /// Create a dataset for a zone, applying per-zone options (clone_from, quota).
     async fn create_zone_dataset(&self, zone_name: &str, opts: &ZoneStorageOpts) -> Result<()>;
 
-    /// Destroy a zone's dataset (recursive).
+    /// Destroy a zone's dataset (recursive). Clones of its snapshots keep
+    /// their data: one of them is promoted to take each snapshot over first.
     async fn destroy_zone_dataset(&self, zone_name: &str) -> Result<()>;
 
     /// Create a ZFS snapshot.
     async fn create_snapshot(&self, dataset: &str, snapshot_name: &str) -> Result<()>;
 
+    /// The snapshot to clone zone roots of `image` from, if it is cached.
+    async fn image_snapshot(&self, image: &str) -> Result<Option<String>>;
+
+    /// Cache a freshly installed zone's root as the base of `image`:
+    /// snapshot the zone dataset, clone the snapshot to the image dataset
+    /// and promote the clone, so the image owns the snapshot and the zone
+    /// becomes one of its clones. Returns the image snapshot.
+    async fn cache_image(&self, image: &str, zone_name: &str) -> Result<String>;
+
+    /// Destroy a cached image (e.g. when it is garbage collected). Zone
+    /// roots cloned from it are unaffected: one is promoted to take the
+    /// snapshot over first.
+    async fn destroy_image(&self, image: &str) -> Result<()>;
+
     /// Create a persistent volume (ZFS dataset under volumes_dataset).
     async fn create_volume(&self, name: &str, quota: Option<&str>) -> Result<()>;
 
@@ -1,6 +1,6 @@
 use crate::command::{exec, exec_unchecked};
 use crate::error::{Result, RuntimeError};
-use crate::storage::{StorageEngine, VolumeInfo};
+use crate::storage::{StorageEngine, VolumeInfo, IMAGE_SNAPSHOT};
 use crate::types::{StoragePoolConfig, ZoneStorageOpts};
 use async_trait::async_trait;
 use tracing::info;
@@ -17,6 +17,42 @@ impl ZfsStorageEngine {
     pub fn new(config: StoragePoolConfig) -> Self {
         Self { config }
     }
+
+    /// Promote a clone of each of `dataset`'s snapshots that has any, moving
+    /// the snapshot (and its other clones) over to it so `dataset` can be
+    /// destroyed without taking the clones along
+    async fn promote_clones(&self, dataset: &str) -> Result<()> {
+        let snapshots = exec(
+            "zfs",
+            &[
+                "list", "-H", "-o", "name", "-t", "snapshot", "-d", "1", dataset,
+            ],
+        )
+        .await?;
+
+        for snapshot in snapshots.stdout.lines().map(str::trim) {
+            if snapshot.is_empty() {
+                continue;
+            }
+            // Gone if an earlier promotion already moved it
+            let clones =
+                exec_unchecked("zfs", &["get", "-H", "-o", "value", "clones", snapshot]).await?;
+            if clones.exit_code != 0 {
+                continue;
+            }
+            let Some(clone) = clones
+                .stdout
+                .trim()
+                .split(',')
+                .find(|c| !c.is_empty() && *c != "-")
+            else {
+                continue;
+            };
+            info!("Promoting {} to take over {}", clone, snapshot);
+            exec("zfs", &["promote", clone]).await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -71,6 +107,7 @@ impl StorageEngine for ZfsStorageEngine {
     async fn destroy_zone_dataset(&self, zone_name: &str) -> Result<()> {
         let dataset = self.config.zone_dataset(zone_name);
         info!("Destroying ZFS dataset: {}", dataset);
+        self.promote_clones(&dataset).await?;
         exec("zfs", &["destroy", "-r", &dataset]).await?;
         info!("ZFS dataset destroyed: {}", dataset);
         Ok(())
@@ -83,6 +120,33 @@ impl StorageEngine for ZfsStorageEngine {
         Ok(())
     }
 
+    async fn image_snapshot(&self, image: &str) -> Result<Option<String>> {
+        let snapshot = format!("{}@{}", self.config.image_dataset(image), IMAGE_SNAPSHOT);
+        let output = exec_unchecked("zfs", &["list", "-H", "-o", "name", &snapshot]).await?;
+        Ok((output.exit_code == 0).then_some(snapshot))
+    }
+
+    async fn cache_image(&self, image: &str, zone_name: &str) -> Result<String> {
+        let zone_snapshot = format!("{}@{}", self.config.zone_dataset(zone_name), IMAGE_SNAPSHOT);
+        let dataset = self.config.image_dataset(image);
+        info!("Caching image {} from zone {}", dataset, zone_name);
+
+        exec("zfs", &["snapshot", &zone_snapshot]).await?;
+        exec("zfs", &["clone", &zone_snapshot, &dataset]).await?;
+        exec("zfs", &["promote", &dataset]).await?;
+
+        Ok(format!("{}@{}", dataset, IMAGE_SNAPSHOT))
+    }
+
+    async fn destroy_image(&self, image: &str) -> Result<()> {
+        let dataset = self.config.image_dataset(image);
+        info!("Destroying cached image: {}", dataset);
+        self.promote_clones(&dataset).await?;
+        exec("zfs", &["destroy", "-r", &dataset]).await?;
+        info!("Cached image destroyed: {}", dataset);
+        Ok(())
+    }
+
     async fn create_volume(&self, name: &str, quota: Option<&str>) -> Result<()> {
         let dataset = self.config.volume_dataset(name);
         info!("Creating persistent volume: {}", dataset);
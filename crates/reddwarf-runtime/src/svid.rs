@@ -0,0 +1,218 @@
+//! SPIFFE workload identity: X.509 SVIDs naming a pod's service account as
+//! `spiffe://<trust domain>/ns/<namespace>/sa/<service account>`, signed by
+//! the cluster CA and written into the pod's zone, so workloads can
+//! authenticate to external systems with short-lived certificates instead of
+//! long-lived secrets
+
+use crate::error::{Result, RuntimeError};
+use crate::mesh::identity::ClusterCa;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use rcgen::{
+    CertificateParams, DnType, ExtendedKeyUsagePurpose, Ia5String, KeyPair, KeyUsagePurpose,
+    SanType,
+};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+/// Directory of the SVID files inside a zone, relative to its root
+pub const SVID_DIR: &str = "var/run/secrets/spiffe";
+
+/// The SVID certificate, PEM
+pub const SVID_CERT_FILE: &str = "svid.pem";
+
+/// The SVID private key, PKCS#8 PEM
+pub const SVID_KEY_FILE: &str = "svid_key.pem";
+
+/// The trust bundle verifying SVIDs of the trust domain, PEM
+pub const SVID_BUNDLE_FILE: &str = "svid_bundle.pem";
+
+/// Trust domain of SVIDs when none is configured
+pub const DEFAULT_TRUST_DOMAIN: &str = "cluster.local";
+
+/// Lifetime of an issued SVID
+pub const DEFAULT_SVID_TTL: Duration = Duration::from_secs(3600);
+
+/// Allowance for clocks of verifiers running behind the node's
+const CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// SPIFFE ID of `service_account` in `namespace`
+pub fn spiffe_id(trust_domain: &str, namespace: &str, service_account: &str) -> String {
+    format!(
+        "spiffe://{}/ns/{}/sa/{}",
+        trust_domain, namespace, service_account
+    )
+}
+
+/// SPIFFE ID of the service account `pod` runs as
+pub fn pod_spiffe_id(trust_domain: &str, pod: &Pod) -> String {
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+    spiffe_id(trust_domain, namespace, pod_service_account(pod))
+}
+
+/// Service account `pod` runs as
+pub fn pod_service_account(pod: &Pod) -> &str {
+    pod.spec
+        .as_ref()
+        .and_then(|s| s.service_account_name.as_deref())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("default")
+}
+
+/// An X.509 SVID with its key and trust bundle
+#[derive(Debug, Clone)]
+pub struct Svid {
+    pub spiffe_id: String,
+    pub cert_pem: String,
+    pub key_pem: String,
+    pub bundle_pem: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl Svid {
+    /// When the SVID should be replaced: halfway through its lifetime, so a
+    /// failed rotation is retried long before it expires
+    pub fn renew_at(&self) -> DateTime<Utc> {
+        self.not_before + (self.not_after - self.not_before) / 2
+    }
+}
+
+/// Issues SVIDs of one trust domain from the cluster CA
+pub struct SvidIssuer {
+    ca: ClusterCa,
+    bundle_pem: String,
+    trust_domain: String,
+    ttl: Duration,
+}
+
+impl SvidIssuer {
+    pub fn new(ca_cert_pem: &[u8], ca_key_pem: &[u8], trust_domain: &str) -> Result<Self> {
+        let ca = ClusterCa::load(ca_cert_pem, ca_key_pem)
+            .map_err(RuntimeError::workload_identity_error)?;
+        if trust_domain.is_empty()
+            || !trust_domain
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c))
+        {
+            return Err(RuntimeError::workload_identity_error(format!(
+                "invalid trust domain '{}'",
+                trust_domain
+            )));
+        }
+        Ok(Self {
+            ca,
+            bundle_pem: String::from_utf8_lossy(ca_cert_pem).into_owned(),
+            trust_domain: trust_domain.to_string(),
+            ttl: DEFAULT_SVID_TTL,
+        })
+    }
+
+    /// Issue SVIDs valid for `ttl` instead of an hour
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn trust_domain(&self) -> &str {
+        &self.trust_domain
+    }
+
+    /// Issue an SVID for the service account `pod` runs as, valid from `now`
+    pub fn issue(&self, pod: &Pod, now: DateTime<Utc>) -> Result<Svid> {
+        let id = pod_spiffe_id(&self.trust_domain, pod);
+        let err = |what: &str, e: rcgen::Error| {
+            RuntimeError::workload_identity_error(format!("{} for {}: {}", what, id, e))
+        };
+
+        let not_before = now - CLOCK_SKEW;
+        let not_after = now + self.ttl;
+        let mut params = CertificateParams::default();
+        params.subject_alt_names = vec![SanType::URI(
+            Ia5String::try_from(id.clone()).map_err(|e| err("invalid SPIFFE ID", e))?,
+        )];
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "SPIFFE");
+        params.key_usages = vec![
+            KeyUsagePurpose::DigitalSignature,
+            KeyUsagePurpose::KeyEncipherment,
+        ];
+        params.extended_key_usages = vec![
+            ExtendedKeyUsagePurpose::ServerAuth,
+            ExtendedKeyUsagePurpose::ClientAuth,
+        ];
+        params.not_before = offset_date_time(not_before)?;
+        params.not_after = offset_date_time(not_after)?;
+        params.use_authority_key_identifier_extension = true;
+
+        let key = KeyPair::generate().map_err(|e| err("failed to generate key", e))?;
+        let cert = params
+            .signed_by(&key, &self.ca.cert, &self.ca.key)
+            .map_err(|e| err("failed to sign SVID", e))?;
+
+        Ok(Svid {
+            spiffe_id: id,
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+            bundle_pem: self.bundle_pem.clone(),
+            not_before,
+            not_after,
+        })
+    }
+}
+
+fn offset_date_time(at: DateTime<Utc>) -> Result<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp(at.timestamp()).map_err(|e| {
+        RuntimeError::workload_identity_error(format!("invalid validity time {}: {}", at, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::identity::tests::test_ca;
+    use k8s_openapi::api::core::v1::PodSpec;
+
+    #[test]
+    fn test_issue_svid() {
+        let (ca_pem, ca_key) = test_ca();
+        let issuer = SvidIssuer::new(&ca_pem, &ca_key, "example.org")
+            .unwrap()
+            .with_ttl(Duration::from_secs(600));
+
+        let mut pod = Pod::default();
+        pod.metadata.namespace = Some("payments".to_string());
+        assert_eq!(
+            pod_spiffe_id(issuer.trust_domain(), &pod),
+            "spiffe://example.org/ns/payments/sa/default"
+        );
+        pod.spec = Some(PodSpec {
+            service_account_name: Some("billing".to_string()),
+            ..Default::default()
+        });
+
+        let now = Utc::now();
+        let svid = issuer.issue(&pod, now).unwrap();
+        assert_eq!(
+            svid.spiffe_id,
+            "spiffe://example.org/ns/payments/sa/billing"
+        );
+        assert_eq!(svid.not_after, now + chrono::Duration::seconds(600));
+        assert!(svid.renew_at() > now && svid.renew_at() < svid.not_after);
+        assert!(svid.cert_pem.starts_with("-----BEGIN CERTIFICATE-----"));
+        assert!(svid.key_pem.contains("PRIVATE KEY"));
+        assert_eq!(svid.bundle_pem.as_bytes(), ca_pem.as_slice());
+
+        // The SPIFFE ID is carried as the certificate's URI SAN
+        let mut reader = svid.cert_pem.as_bytes();
+        let der = rustls_pemfile::certs(&mut reader).next().unwrap().unwrap();
+        assert!(der
+            .windows(svid.spiffe_id.len())
+            .any(|w| w == svid.spiffe_id.as_bytes()));
+
+        assert!(SvidIssuer::new(&ca_pem, &ca_key, "Not A Domain").is_err());
+        let (_, other_key) = test_ca();
+        assert!(SvidIssuer::new(&ca_pem, &other_key, "example.org").is_err());
+    }
+}
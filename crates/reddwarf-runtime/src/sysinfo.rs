@@ -52,6 +52,16 @@ pub fn detect_system_resources() -> Result<SystemResources, RuntimeError> {
     })
 }
 
+/// Detect the host's currently available (free + reclaimable) memory in bytes.
+pub fn detect_available_memory() -> Result<u64, RuntimeError> {
+    let mem = sys_info::mem_info().map_err(|e| {
+        RuntimeError::resource_detection_failed(format!("failed to detect memory: {e}"))
+    })?;
+
+    // sys_info::mem_info().avail is in KiB
+    Ok(mem.avail * 1024)
+}
+
 /// Detect system resources and compute allocatable values after subtracting
 /// the given reservation. Allocatable values are clamped so they never go
 /// negative.
@@ -0,0 +1,163 @@
+use crate::command::exec_unchecked;
+use reddwarf_core::topology::{
+    to_label_value, CHASSIS_LABEL, TOPOLOGY_REGION_LABEL, TOPOLOGY_ZONE_LABEL,
+};
+use std::collections::BTreeMap;
+use tracing::{debug, info};
+
+/// SMF property group holding the node's configured region and zone
+const SMF_TOPOLOGY_PROPERTY_GROUP: &str = "topology";
+
+/// Placeholder serial numbers firmware reports when none was set
+const PLACEHOLDER_SERIALS: &[&str] = &[
+    "to be filled by o.e.m.",
+    "default string",
+    "not specified",
+    "system serial number",
+    "chassis serial number",
+    "none",
+    "0",
+    "0123456789",
+];
+
+/// Where this node sits in the failure-domain hierarchy, published as the
+/// `topology.kubernetes.io/region`, `topology.kubernetes.io/zone` and
+/// `reddwarf.io/chassis` node labels
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeTopology {
+    pub region: Option<String>,
+    pub zone: Option<String>,
+    pub chassis: Option<String>,
+}
+
+impl NodeTopology {
+    /// Fill in what was not configured: region and zone from the
+    /// `topology/region` and `topology/zone` properties of the SMF service
+    /// the agent runs as, the chassis from the SMBIOS serial numbers
+    pub async fn detect(mut self) -> Self {
+        if let Ok(fmri) = std::env::var("SMF_FMRI") {
+            if self.region.is_none() {
+                self.region = smf_property(&fmri, "region").await;
+            }
+            if self.zone.is_none() {
+                self.zone = smf_property(&fmri, "zone").await;
+            }
+        }
+        if self.chassis.is_none() {
+            self.chassis = smbios_serial().await;
+        }
+
+        info!(
+            region = self.region.as_deref().unwrap_or("-"),
+            zone = self.zone.as_deref().unwrap_or("-"),
+            chassis = self.chassis.as_deref().unwrap_or("-"),
+            "Node topology"
+        );
+        self
+    }
+
+    /// The node labels describing this topology; values that can't be made
+    /// into label values are left out
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        [
+            (TOPOLOGY_REGION_LABEL, &self.region),
+            (TOPOLOGY_ZONE_LABEL, &self.zone),
+            (CHASSIS_LABEL, &self.chassis),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| {
+            let value = to_label_value(value.as_deref()?)?;
+            Some((label.to_string(), value))
+        })
+        .collect()
+    }
+}
+
+async fn smf_property(fmri: &str, name: &str) -> Option<String> {
+    let property = format!("{}/{}", SMF_TOPOLOGY_PROPERTY_GROUP, name);
+    match exec_unchecked("svcprop", &["-p", &property, fmri]).await {
+        Ok(output) if output.exit_code == 0 => parse_svcprop(&output.stdout),
+        Ok(_) => None,
+        Err(e) => {
+            debug!("Cannot read SMF property {}: {}", property, e);
+            None
+        }
+    }
+}
+
+/// A single svcprop value; astring values escape spaces with backslashes
+fn parse_svcprop(output: &str) -> Option<String> {
+    let value = output.trim().replace("\\ ", " ");
+    match value.as_str() {
+        "" | "\"\"" => None,
+        _ => Some(value),
+    }
+}
+
+/// Serial number of the chassis, falling back to that of the system
+async fn smbios_serial() -> Option<String> {
+    for table in ["SMB_TYPE_CHASSIS", "SMB_TYPE_SYSTEM"] {
+        match exec_unchecked("smbios", &["-t", table]).await {
+            Ok(output) if output.exit_code == 0 => {
+                if let Some(serial) = parse_smbios_serial(&output.stdout) {
+                    return Some(serial);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                debug!("Cannot read SMBIOS: {}", e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// The `Serial Number:` of `smbios -t` output, unless it is a placeholder
+fn parse_smbios_serial(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Serial Number:"))
+        .map(str::trim)
+        .filter(|serial| !PLACEHOLDER_SERIALS.contains(&serial.to_lowercase().as_str()))
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_topology_sources() {
+        let chassis = "ID    SIZE TYPE\n\
+                       3     107  SMB_TYPE_CHASSIS (type 3) (system enclosure or chassis)\n\
+                       \n  Manufacturer: Supermicro\n  Version: 0123456789\n\
+                       \n  Serial Number: C8260LH12A10123\n  Asset Tag: \n";
+        assert_eq!(
+            parse_smbios_serial(chassis).as_deref(),
+            Some("C8260LH12A10123")
+        );
+        assert_eq!(
+            parse_smbios_serial("  Serial Number: To Be Filled By O.E.M.\n"),
+            None
+        );
+        assert_eq!(parse_smbios_serial("  Manufacturer: QEMU\n"), None);
+
+        assert_eq!(parse_svcprop("rack\\ 12\n").as_deref(), Some("rack 12"));
+        assert_eq!(parse_svcprop("\"\"\n"), None);
+    }
+
+    #[test]
+    fn test_topology_labels() {
+        let topology = NodeTopology {
+            region: Some("eu-central".to_string()),
+            zone: Some("rack 12".to_string()),
+            chassis: Some("!!".to_string()),
+        };
+        let labels = topology.labels();
+        assert_eq!(labels[TOPOLOGY_REGION_LABEL], "eu-central");
+        assert_eq!(labels[TOPOLOGY_ZONE_LABEL], "rack-12");
+        assert!(!labels.contains_key(CHASSIS_LABEL));
+        assert!(NodeTopology::default().labels().is_empty());
+    }
+}
@@ -1,6 +1,12 @@
 use crate::error::Result;
-use crate::types::{NetworkMode, ZoneConfig, ZoneInfo, ZoneState};
+use crate::svid::Svid;
+use crate::types::{
+    DnsConfig, NetworkMode, PortForward, WarmZone, ZoneConfig, ZoneEvent, ZoneExit, ZoneInfo,
+    ZoneState,
+};
 use async_trait::async_trait;
+use reddwarf_core::{ProcessSession, SessionRequest};
+use tokio::sync::broadcast;
 
 /// Trait for zone runtime implementations
 ///
@@ -46,6 +52,16 @@ pub trait ZoneRuntime: Send + Sync {
     /// List all managed zones
     async fn list_zones(&self) -> Result<Vec<ZoneInfo>>;
 
+    /// Subscribe to zone state transitions (booted, halted, panicked) as the
+    /// runtime detects them
+    fn subscribe_events(&self) -> broadcast::Receiver<ZoneEvent>;
+
+    /// How a zone that went down without being asked to exited, if the
+    /// runtime knows; runtimes that cannot tell report `None`
+    async fn zone_exit(&self, _zone_name: &str) -> Result<Option<ZoneExit>> {
+        Ok(None)
+    }
+
     // --- Exec ---
 
     /// Execute a command inside a running zone
@@ -59,6 +75,15 @@ pub trait ZoneRuntime: Send + Sync {
         command: &[String],
     ) -> Result<crate::command::CommandOutput>;
 
+    /// Open an interactive session with a running zone: a new command, or
+    /// for `attach` the container's own process, with its standard streams,
+    /// or a pseudo-terminal, connected to the returned session
+    async fn open_session(
+        &self,
+        zone_name: &str,
+        request: &SessionRequest,
+    ) -> Result<ProcessSession>;
+
     // --- Networking ---
 
     /// Set up network for a zone
@@ -67,6 +92,23 @@ pub trait ZoneRuntime: Send + Sync {
     /// Tear down network for a zone
     async fn teardown_network(&self, zone_name: &str, network: &NetworkMode) -> Result<()>;
 
+    /// Set (or with `None`, clear) the maximum bandwidth of a VNIC in bits per second
+    async fn set_link_bandwidth(&self, vnic_name: &str, maxbw_bps: Option<u64>) -> Result<()>;
+
+    /// Replace the host port forwards of a zone (an empty slice removes them)
+    async fn set_port_forwards(&self, zone_name: &str, forwards: &[PortForward]) -> Result<()>;
+
+    /// Write a zone's `/etc/resolv.conf`; takes effect for new lookups
+    /// without a reboot
+    async fn set_resolv_conf(&self, zone_name: &str, zonepath: &str, dns: &DnsConfig)
+        -> Result<()>;
+
+    // --- Workload identity ---
+
+    /// Write `svid`, its key and trust bundle into the zone's
+    /// `/var/run/secrets/spiffe`, replacing the previous ones
+    async fn write_svid(&self, zone_name: &str, zonepath: &str, svid: &Svid) -> Result<()>;
+
     // --- High-level lifecycle ---
 
     /// Full provisioning: create dataset -> setup network -> create zone -> install -> boot
@@ -74,4 +116,19 @@ pub trait ZoneRuntime: Send + Sync {
 
     /// Full deprovisioning: halt -> uninstall -> delete -> teardown network -> destroy dataset
     async fn deprovision(&self, config: &ZoneConfig) -> Result<()>;
+
+    // --- Warm pool ---
+
+    /// Create dataset -> create zone -> install, leaving an idle zone with no
+    /// network or resources for a pod to claim
+    async fn prepare_warm_zone(&self, zone: &WarmZone) -> Result<()>;
+
+    /// Provision `config` from an installed warm zone instead of from
+    /// scratch: rename the zone and move its zonepath -> setup network ->
+    /// add the pod's configuration -> boot
+    async fn claim_warm_zone(&self, warm_zone_name: &str, config: &ZoneConfig) -> Result<()>;
+
+    /// Remove a warm zone that was never claimed: halt if running ->
+    /// uninstall if installed -> delete -> destroy dataset
+    async fn destroy_warm_zone(&self, zone_name: &str) -> Result<()>;
 }
@@ -123,6 +123,64 @@ pub struct DirectNicConfig {
     pub prefix_len: u8,
 }
 
+/// A node port forwarded to a pod port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortForward {
+    /// Host link the traffic arrives on
+    pub interface: String,
+    /// Host address to match; `None` matches any address on the link
+    pub host_ip: Option<String>,
+    pub host_port: u16,
+    /// Lowercase protocol name (`tcp`, `udp`, `sctp`)
+    pub protocol: String,
+    pub pod_ip: String,
+    pub container_port: u16,
+}
+
+/// Resolver configuration written to a zone's `/etc/resolv.conf`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsConfig {
+    pub nameservers: Vec<String>,
+    pub searches: Vec<String>,
+    /// Resolver options, e.g. `ndots:5` or `rotate`
+    pub options: Vec<String>,
+}
+
+impl DnsConfig {
+    /// Parse the directives this type models out of a resolv.conf
+    pub fn from_resolv_conf(contents: &str) -> Self {
+        let mut config = Self::default();
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("nameserver") => config.nameservers.extend(fields.next().map(String::from)),
+                // The last of `domain`/`search` wins, as in the resolver
+                Some("search") | Some("domain") => {
+                    config.searches = fields.map(String::from).collect()
+                }
+                Some("options") => config.options.extend(fields.map(String::from)),
+                _ => {}
+            }
+        }
+        config
+    }
+
+    /// Render as resolv.conf contents
+    pub fn to_resolv_conf(&self) -> String {
+        let mut out = String::new();
+        for ns in &self.nameservers {
+            out.push_str(&format!("nameserver {}\n", ns));
+        }
+        if !self.searches.is_empty() {
+            out.push_str(&format!("search {}\n", self.searches.join(" ")));
+        }
+        if !self.options.is_empty() {
+            out.push_str(&format!("options {}\n", self.options.join(" ")));
+        }
+        out
+    }
+}
+
 /// Global storage pool configuration
 ///
 /// Derived from a single `--storage-pool` flag (e.g., "rpool"), with optional
@@ -174,6 +232,23 @@ impl StoragePoolConfig {
         format!("{}/{}", self.zones_dataset, zone_name)
     }
 
+    /// Derive the full dataset path of a cached image, named after the
+    /// image reference with characters ZFS rejects replaced
+    pub fn image_dataset(&self, image: &str) -> String {
+        let name: String = image
+            .trim_start_matches('/')
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        format!("{}/{}", self.images_dataset, name)
+    }
+
     /// Derive the full dataset path for a volume
     pub fn volume_dataset(&self, volume_name: &str) -> String {
         format!("{}/{}", self.volumes_dataset, volume_name)
@@ -200,6 +275,10 @@ pub struct ContainerProcess {
     pub working_dir: Option<String>,
     /// Environment variables
     pub env: Vec<(String, String)>,
+    /// Keep the process's standard input open for `attach`
+    pub stdin: bool,
+    /// Run the process on a pseudo-terminal
+    pub tty: bool,
 }
 
 /// Filesystem mount specification
@@ -215,6 +294,22 @@ pub struct FsMount {
     pub options: Vec<String>,
 }
 
+/// A zonecfg `attr` resource (string-typed)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZoneAttr {
+    pub name: String,
+    pub value: String,
+}
+
+/// A protocol property set inside the zone with `ipadm set-prop`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpProp {
+    /// `tcp`, `udp`, `ip`, ...
+    pub protocol: String,
+    pub name: String,
+    pub value: String,
+}
+
 /// Complete zone configuration for provisioning
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoneConfig {
@@ -236,8 +331,48 @@ pub struct ZoneConfig {
     pub cpu_cap: Option<String>,
     /// Memory cap (e.g., "512M", "2G")
     pub memory_cap: Option<String>,
+    /// Swap (virtual memory) cap; set equal to `memory_cap` for Guaranteed
+    /// pods so they can never grow past their reservation
+    pub swap_cap: Option<String>,
+    /// Fair share scheduler CPU shares (relative weight under contention)
+    pub cpu_shares: Option<u32>,
+    /// Number of CPUs in a dedicated processor set (replaces cap and shares)
+    pub dedicated_cpus: Option<u32>,
+    /// Maximum number of LWPs (threads) in the zone
+    pub max_lwps: Option<u32>,
+    /// Maximum number of processes in the zone
+    pub max_processes: Option<u32>,
     /// Additional filesystem mounts
     pub fs_mounts: Vec<FsMount>,
+    /// Resolver configuration; `None` leaves the zone image's resolv.conf
+    pub dns: Option<DnsConfig>,
+    /// Zone nodename; `None` leaves the zone image's default
+    pub hostname: Option<String>,
+    /// Fully qualified name listed for the zone's address in its hosts file
+    pub fqdn: Option<String>,
+    /// Make the root filesystem read-only (an immutable zone), with writable
+    /// tmpfs at /tmp and /var/run
+    pub read_only_root: bool,
+    /// Extra zonecfg attributes
+    pub attrs: Vec<ZoneAttr>,
+    /// Protocol properties applied inside the zone after boot
+    pub ip_props: Vec<IpProp>,
+    /// Global zone device paths passed through to the zone
+    pub devices: Vec<String>,
+}
+
+/// An idle zone installed ahead of time, with only its brand and zonepath
+/// configured, for a pod to claim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmZone {
+    /// Zone name (must be unique on the host)
+    pub zone_name: String,
+    /// Zone brand
+    pub brand: ZoneBrand,
+    /// Zone root path
+    pub zonepath: String,
+    /// LX brand image path the zone is installed from (only for Lx brand)
+    pub lx_image_path: Option<String>,
 }
 
 /// Information about an existing zone
@@ -257,6 +392,35 @@ pub struct ZoneInfo {
     pub uuid: String,
 }
 
+/// A zone state transition reported by the runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneEvent {
+    /// Zone name
+    pub zone_name: String,
+    pub kind: ZoneEventKind,
+}
+
+/// Kind of zone state transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneEventKind {
+    /// The zone came up
+    Booted,
+    /// The zone was shut down or halted on request
+    Halted,
+    /// The zone went down without being asked to
+    Panicked,
+}
+
+/// How the last process of a zone that went down by itself exited, as far
+/// as the runtime knows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneExit {
+    /// Exit status of the zone's main process
+    pub exit_code: i32,
+    /// Whether the process was killed for exceeding the zone's memory cap
+    pub oom_killed: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -286,4 +450,18 @@ mod tests {
         assert_eq!(ZoneState::parse("configured"), Some(ZoneState::Configured));
         assert_eq!(ZoneState::parse("bogus"), None);
     }
+
+    #[test]
+    fn test_dns_config_resolv_conf_roundtrip() {
+        let config = DnsConfig::from_resolv_conf(
+            "# comment\nnameserver 10.0.0.1\nnameserver 10.0.0.2\ndomain old\nsearch a.example b.example\noptions ndots:5 rotate\n",
+        );
+        assert_eq!(config.nameservers, vec!["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(config.searches, vec!["a.example", "b.example"]);
+        assert_eq!(config.options, vec!["ndots:5", "rotate"]);
+        assert_eq!(
+            DnsConfig::from_resolv_conf(&config.to_resolv_conf()),
+            config
+        );
+    }
 }
@@ -0,0 +1,659 @@
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use crate::storage::StorageEngine;
+use crate::workloads::list;
+use k8s_openapi::api::core::v1::{
+    LocalVolumeSource, NodeSelector, NodeSelectorRequirement, NodeSelectorTerm, ObjectReference,
+    PersistentVolume, PersistentVolumeClaim, PersistentVolumeClaimStatus, PersistentVolumeSpec,
+    PersistentVolumeStatus, Pod, VolumeNodeAffinity,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use reddwarf_core::resources::{
+    claim_phase, claim_request_bytes, volume_capacity_bytes, volume_matches_claim, volume_node,
+    volume_path, volume_phase, HOSTNAME_LABEL, PERSISTENT_VOLUME_CLAIM_KIND, PHASE_BOUND,
+    PROVISIONED_BY_ANNOTATION, RECLAIM_DELETE, SELECTED_NODE_ANNOTATION, VOLUME_AVAILABLE,
+    VOLUME_RELEASED, ZFS_PROVISIONER, ZFS_STORAGE_CLASS,
+};
+use reddwarf_core::{EventBus, WatchEventType};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the volume binder subscribes to the event bus under
+const SUBSCRIBER: &str = "volume-binder";
+
+/// Configuration for the volume binder
+#[derive(Debug, Clone)]
+pub struct VolumeBinderConfig {
+    /// Name of this node; only its claims and volumes are handled
+    pub node_name: String,
+    /// Interval between full resyncs of the claims and volumes
+    pub resync_interval: Duration,
+}
+
+impl VolumeBinderConfig {
+    pub fn new(node_name: String) -> Self {
+        Self {
+            node_name,
+            resync_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A change bringing a claim or volume closer to its desired state
+#[derive(Debug, PartialEq)]
+enum VolumeAction<'a> {
+    /// Bind `claim` to `volume`, or finish a binding left half done
+    Bind {
+        claim: &'a PersistentVolumeClaim,
+        volume: &'a PersistentVolume,
+    },
+    /// Create a ZFS volume for `claim` and bind it
+    Provision { claim: &'a PersistentVolumeClaim },
+    /// Destroy the dataset of a provisioned volume whose claim is gone,
+    /// and delete the volume
+    Reclaim { volume: &'a PersistentVolume },
+    /// Mark a volume whose claim is gone as released, keeping its data
+    Release { volume: &'a PersistentVolume },
+}
+
+/// `namespace/name` of the claim `volume` is bound to
+fn claim_ref_key(volume: &PersistentVolume) -> Option<String> {
+    let claim_ref = volume.spec.as_ref()?.claim_ref.as_ref()?;
+    Some(format!(
+        "{}/{}",
+        claim_ref.namespace.as_deref()?,
+        claim_ref.name.as_deref()?
+    ))
+}
+
+fn claim_key(claim: &PersistentVolumeClaim) -> String {
+    format!(
+        "{}/{}",
+        claim.metadata.namespace.as_deref().unwrap_or("default"),
+        claim.metadata.name.as_deref().unwrap_or_default()
+    )
+}
+
+/// Whether the binder of `node` may provision a volume for `claim`
+fn is_provisionable(claim: &PersistentVolumeClaim) -> bool {
+    claim
+        .spec
+        .as_ref()
+        .and_then(|s| s.storage_class_name.as_deref())
+        .is_none_or(|class| class == ZFS_STORAGE_CLASS)
+}
+
+/// Changes binding the claims meant for `node` and reclaiming the volumes
+/// of deleted claims
+///
+/// A claim is meant for a node once the node is selected for it through
+/// the `volume.kubernetes.io/selected-node` annotation or a pod using it is
+/// scheduled there, so volumes are bound where they will be mounted. Such a
+/// claim binds the smallest Available volume that matches it and that the
+/// node can mount, and when there is none a ZFS volume is provisioned.
+fn volume_plan<'a>(
+    node: &str,
+    pods: &[Pod],
+    claims: &'a [PersistentVolumeClaim],
+    volumes: &'a [PersistentVolume],
+) -> Vec<VolumeAction<'a>> {
+    let wanted: HashSet<String> = pods
+        .iter()
+        .filter(|p| p.spec.as_ref().and_then(|s| s.node_name.as_deref()) == Some(node))
+        .filter(|p| p.metadata.deletion_timestamp.is_none())
+        .flat_map(|p| {
+            let namespace = p.metadata.namespace.as_deref().unwrap_or("default");
+            p.spec
+                .iter()
+                .flat_map(|s| s.volumes.iter().flatten())
+                .filter_map(|v| v.persistent_volume_claim.as_ref())
+                .map(move |c| format!("{}/{}", namespace, c.claim_name))
+        })
+        .collect();
+    let mounts_here = |volume: &PersistentVolume| volume_node(volume).is_none_or(|n| n == node);
+
+    let mut actions = Vec::new();
+    let mut taken = HashSet::new();
+    for claim in claims {
+        if claim.metadata.deletion_timestamp.is_some() {
+            continue;
+        }
+        let key = claim_key(claim);
+        let selected = claim
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|a| a.get(SELECTED_NODE_ANNOTATION));
+        if selected.map(String::as_str) != Some(node) && !wanted.contains(&key) {
+            continue;
+        }
+
+        let volume_name = claim.spec.as_ref().and_then(|s| s.volume_name.as_deref());
+        if let Some(volume_name) = volume_name {
+            let volume = volumes
+                .iter()
+                .find(|v| v.metadata.name.as_deref() == Some(volume_name));
+            if let Some(volume) = volume {
+                if claim_phase(claim) != PHASE_BOUND || volume_phase(volume) != PHASE_BOUND {
+                    actions.push(VolumeAction::Bind { claim, volume });
+                }
+            }
+            continue;
+        }
+
+        let best = volumes
+            .iter()
+            .filter(|v| volume_phase(v) == VOLUME_AVAILABLE && mounts_here(v))
+            .filter(|v| claim_ref_key(v).is_none_or(|k| k == key))
+            .filter(|v| !taken.contains(&v.metadata.name))
+            .filter(|v| volume_matches_claim(v, claim))
+            .min_by_key(|v| volume_capacity_bytes(v).unwrap_or(i64::MAX));
+        match best {
+            Some(volume) => {
+                taken.insert(&volume.metadata.name);
+                actions.push(VolumeAction::Bind { claim, volume });
+            }
+            None if is_provisionable(claim) => actions.push(VolumeAction::Provision { claim }),
+            None => {}
+        }
+    }
+
+    // A claim recreated under the same name is a different claim
+    let live: HashMap<String, Option<&str>> = claims
+        .iter()
+        .map(|c| (claim_key(c), c.metadata.uid.as_deref()))
+        .collect();
+    for volume in volumes.iter().filter(|v| mounts_here(v)) {
+        let Some(key) = claim_ref_key(volume) else {
+            continue;
+        };
+        let bound_uid = volume
+            .spec
+            .as_ref()
+            .and_then(|s| s.claim_ref.as_ref())
+            .and_then(|r| r.uid.as_deref());
+        let claim_live = live
+            .get(&key)
+            .is_some_and(|uid| bound_uid.is_none() || bound_uid == *uid);
+        if claim_live || volume_phase(volume) == VOLUME_AVAILABLE {
+            continue;
+        }
+        let reclaim_policy = volume
+            .spec
+            .as_ref()
+            .and_then(|s| s.persistent_volume_reclaim_policy.as_deref());
+        let provisioned = volume
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|a| a.get(PROVISIONED_BY_ANNOTATION))
+            .is_some_and(|p| p == ZFS_PROVISIONER);
+        if reclaim_policy == Some(RECLAIM_DELETE)
+            && provisioned
+            && volume_node(volume) == Some(node)
+        {
+            actions.push(VolumeAction::Reclaim { volume });
+        } else if volume_phase(volume) != VOLUME_RELEASED {
+            actions.push(VolumeAction::Release { volume });
+        }
+    }
+    actions
+}
+
+/// The volume provisioned on `node` for `claim`, backed by `dataset`
+fn provisioned_volume(
+    name: &str,
+    dataset: &str,
+    node: &str,
+    claim: &PersistentVolumeClaim,
+) -> PersistentVolume {
+    let spec = claim.spec.clone().unwrap_or_default();
+    PersistentVolume {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            annotations: Some(BTreeMap::from([(
+                PROVISIONED_BY_ANNOTATION.to_string(),
+                ZFS_PROVISIONER.to_string(),
+            )])),
+            ..Default::default()
+        },
+        spec: Some(PersistentVolumeSpec {
+            capacity: spec.resources.and_then(|r| r.requests),
+            access_modes: spec.access_modes,
+            storage_class_name: spec.storage_class_name,
+            volume_mode: spec.volume_mode,
+            persistent_volume_reclaim_policy: Some(RECLAIM_DELETE.to_string()),
+            local: Some(LocalVolumeSource {
+                path: format!("/{}", dataset),
+                fs_type: Some("zfs".to_string()),
+            }),
+            node_affinity: Some(VolumeNodeAffinity {
+                required: Some(NodeSelector {
+                    node_selector_terms: vec![NodeSelectorTerm {
+                        match_expressions: Some(vec![NodeSelectorRequirement {
+                            key: HOSTNAME_LABEL.to_string(),
+                            operator: "In".to_string(),
+                            values: Some(vec![node.to_string()]),
+                        }]),
+                        ..Default::default()
+                    }],
+                }),
+            }),
+            ..Default::default()
+        }),
+        status: Some(PersistentVolumeStatus {
+            phase: Some(VOLUME_AVAILABLE.to_string()),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Binds the PersistentVolumeClaims used on this node to PersistentVolumes,
+/// provisioning ZFS volumes through the storage engine when no existing
+/// volume fits
+///
+/// Provisioned volumes are datasets under the pool's volumes dataset,
+/// pinned to this node and deleted along with their claim. Volumes of
+/// other provisioners, or with the `Retain` policy, are only marked
+/// `Released` when their claim is deleted.
+pub struct VolumeBinder {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    storage: Arc<dyn StorageEngine>,
+    config: VolumeBinderConfig,
+}
+
+impl VolumeBinder {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        storage: Arc<dyn StorageEngine>,
+        config: VolumeBinderConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            storage,
+            config,
+        }
+    }
+
+    /// Run the binder loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting volume binder for node {} (resync: {:?})",
+            self.config.node_name, self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Volume binder shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("Volume binder resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            let relevant = match event.gvk.kind.as_str() {
+                                PERSISTENT_VOLUME_CLAIM_KIND => true,
+                                // Only pods scheduled here with claims to bind
+                                "Pod" => {
+                                    !matches!(event.event_type, WatchEventType::Deleted)
+                                        && event.object["spec"]["nodeName"].as_str()
+                                            == Some(self.config.node_name.as_str())
+                                        && event.object["spec"]["volumes"]
+                                            .as_array()
+                                            .into_iter()
+                                            .flatten()
+                                            .any(|v| v.get("persistentVolumeClaim").is_some())
+                                }
+                                _ => false,
+                            };
+                            if relevant {
+                                if let Err(e) = self.resync().await {
+                                    error!("Volume binder resync failed: {}", e);
+                                }
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Missed {} events, doing full volume binder resync", n);
+                            if let Err(e) = self.resync().await {
+                                error!("Volume binder resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping volume binder");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bind the claims meant for this node and reclaim released volumes
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing volume bindings");
+
+        // Volumes before claims: a volume bound to a claim created after
+        // the claims were listed would look released
+        let pods: Vec<Pod> = list(&self.api_client, "/api/v1/pods").await?;
+        let volumes: Vec<PersistentVolume> =
+            list(&self.api_client, "/api/v1/persistentvolumes").await?;
+        let claims: Vec<PersistentVolumeClaim> =
+            list(&self.api_client, "/api/v1/persistentvolumeclaims").await?;
+
+        for action in volume_plan(&self.config.node_name, &pods, &claims, &volumes) {
+            let result = match &action {
+                VolumeAction::Bind { claim, volume } => self.bind(claim, volume).await,
+                VolumeAction::Provision { claim } => self.provision(claim).await,
+                VolumeAction::Reclaim { volume } => self.reclaim(volume).await,
+                VolumeAction::Release { volume } => self.release(volume).await,
+            };
+            if let Err(e) = result {
+                warn!("Failed to apply {:?}: {}", action_summary(&action), e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Point `volume` and `claim` at each other and mark both Bound
+    async fn bind(&self, claim: &PersistentVolumeClaim, volume: &PersistentVolume) -> Result<()> {
+        let namespace = claim.metadata.namespace.as_deref().unwrap_or("default");
+        let claim_name = claim.metadata.name.as_deref().unwrap_or_default();
+        let volume_name = volume.metadata.name.as_deref().unwrap_or_default();
+
+        let mut volume = volume.clone();
+        let spec = volume.spec.get_or_insert_with(Default::default);
+        let claim_ref = ObjectReference {
+            api_version: Some("v1".to_string()),
+            kind: Some(PERSISTENT_VOLUME_CLAIM_KIND.to_string()),
+            namespace: Some(namespace.to_string()),
+            name: Some(claim_name.to_string()),
+            uid: claim.metadata.uid.clone(),
+            ..Default::default()
+        };
+        if spec.claim_ref.as_ref() != Some(&claim_ref) {
+            spec.claim_ref = Some(claim_ref);
+            volume = self
+                .api_client
+                .replace_persistent_volume(volume_name, &volume)
+                .await?;
+        }
+        if volume_phase(&volume) != PHASE_BOUND {
+            volume.status = Some(PersistentVolumeStatus {
+                phase: Some(PHASE_BOUND.to_string()),
+                ..Default::default()
+            });
+            self.api_client
+                .update_persistent_volume_status(volume_name, &volume)
+                .await?;
+        }
+
+        let mut claim = claim.clone();
+        let spec = claim.spec.get_or_insert_with(Default::default);
+        if spec.volume_name.as_deref() != Some(volume_name) {
+            spec.volume_name = Some(volume_name.to_string());
+            claim = self
+                .api_client
+                .replace_persistent_volume_claim(namespace, claim_name, &claim)
+                .await?;
+        }
+        let volume_spec = volume.spec.as_ref();
+        claim.status = Some(PersistentVolumeClaimStatus {
+            phase: Some(PHASE_BOUND.to_string()),
+            capacity: volume_spec.and_then(|s| s.capacity.clone()),
+            access_modes: volume_spec.and_then(|s| s.access_modes.clone()),
+            ..Default::default()
+        });
+        self.api_client
+            .update_persistent_volume_claim_status(namespace, claim_name, &claim)
+            .await?;
+
+        info!(
+            "Bound claim {}/{} to volume {}",
+            namespace, claim_name, volume_name
+        );
+        Ok(())
+    }
+
+    /// Create a ZFS volume sized to `claim`'s request and bind the claim to
+    /// it. Picks up where an earlier attempt left off.
+    async fn provision(&self, claim: &PersistentVolumeClaim) -> Result<()> {
+        let uid = claim.metadata.uid.as_deref().unwrap_or_default();
+        let name = format!("pvc-{}", uid);
+        let bytes = claim_request_bytes(claim).map_err(|e| {
+            RuntimeError::invalid_config(
+                format!("Claim {} has no valid size: {}", claim_key(claim), e),
+                "Set spec.resources.requests.storage to a quantity such as 10Gi",
+            )
+        })?;
+
+        let exists = self
+            .storage
+            .list_volumes()
+            .await?
+            .iter()
+            .any(|v| v.name == name);
+        if !exists {
+            self.storage
+                .create_volume(&name, Some(&bytes.to_string()))
+                .await?;
+        }
+
+        let dataset = self.storage.pool_config().volume_dataset(&name);
+        let volume = provisioned_volume(&name, &dataset, &self.config.node_name, claim);
+        let volume = self.api_client.create_persistent_volume(&volume).await?;
+        info!(
+            "Provisioned volume {} ({} bytes) for claim {}",
+            name,
+            bytes,
+            claim_key(claim)
+        );
+        self.bind(claim, &volume).await
+    }
+
+    /// Destroy the dataset of a provisioned volume and delete the volume
+    async fn reclaim(&self, volume: &PersistentVolume) -> Result<()> {
+        let name = volume.metadata.name.as_deref().unwrap_or_default();
+        let dataset = self.storage.pool_config().volume_dataset(name);
+        // Only a dataset this binder provisioned for the volume is destroyed
+        if volume_path(volume) == Some(format!("/{}", dataset).as_str()) {
+            let exists = self
+                .storage
+                .list_volumes()
+                .await?
+                .iter()
+                .any(|v| v.name == name);
+            if exists {
+                self.storage.destroy_volume(name).await?;
+            }
+        }
+        self.api_client.delete_persistent_volume(name).await?;
+        info!("Reclaimed volume {} of a deleted claim", name);
+        Ok(())
+    }
+
+    /// Mark a volume whose claim was deleted as Released
+    async fn release(&self, volume: &PersistentVolume) -> Result<()> {
+        let name = volume.metadata.name.as_deref().unwrap_or_default();
+        let mut volume = volume.clone();
+        volume.status = Some(PersistentVolumeStatus {
+            phase: Some(VOLUME_RELEASED.to_string()),
+            ..Default::default()
+        });
+        self.api_client
+            .update_persistent_volume_status(name, &volume)
+            .await?;
+        info!("Released volume {} of a deleted claim", name);
+        Ok(())
+    }
+}
+
+/// Short description of `action` for logs
+fn action_summary(action: &VolumeAction<'_>) -> String {
+    let volume_name = |v: &PersistentVolume| v.metadata.name.clone().unwrap_or_default();
+    match action {
+        VolumeAction::Bind { claim, volume } => {
+            format!("binding of {} to {}", claim_key(claim), volume_name(volume))
+        }
+        VolumeAction::Provision { claim } => format!("provisioning for {}", claim_key(claim)),
+        VolumeAction::Reclaim { volume } => format!("reclaim of {}", volume_name(volume)),
+        VolumeAction::Release { volume } => format!("release of {}", volume_name(volume)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(name: &str, storage: &str, annotations: serde_json::Value) -> PersistentVolumeClaim {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": name,
+                "namespace": "default",
+                "uid": format!("uid-{}", name),
+                "annotations": annotations
+            },
+            "spec": {
+                "accessModes": ["ReadWriteOnce"],
+                "resources": {"requests": {"storage": storage}},
+                "storageClassName": "local"
+            }
+        }))
+        .unwrap()
+    }
+
+    fn volume(name: &str, storage: &str, node: &str) -> PersistentVolume {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {"name": name},
+            "spec": {
+                "capacity": {"storage": storage},
+                "accessModes": ["ReadWriteOnce"],
+                "storageClassName": "local",
+                "local": {"path": format!("/export/{}", name)},
+                "nodeAffinity": {"required": {"nodeSelectorTerms": [{
+                    "matchExpressions": [
+                        {"key": "kubernetes.io/hostname", "operator": "In", "values": [node]}
+                    ]
+                }]}}
+            }
+        }))
+        .unwrap()
+    }
+
+    fn pod_using(claim: &str, node: &str) -> Pod {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "app", "namespace": "default"},
+            "spec": {
+                "nodeName": node,
+                "containers": [{"name": "app"}],
+                "volumes": [{"name": "data", "persistentVolumeClaim": {"claimName": claim}}]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_volume_plan_binds_claims_meant_for_node() {
+        let claims = vec![
+            claim("used", "5Gi", serde_json::json!({})),
+            claim(
+                "selected",
+                "1Gi",
+                serde_json::json!({SELECTED_NODE_ANNOTATION: "node1"}),
+            ),
+            claim(
+                "elsewhere",
+                "1Gi",
+                serde_json::json!({SELECTED_NODE_ANNOTATION: "node2"}),
+            ),
+        ];
+        let volumes = vec![
+            volume("big", "10Gi", "node1"),
+            volume("small", "6Gi", "node1"),
+            volume("remote", "1Gi", "node2"),
+        ];
+        let pods = vec![pod_using("used", "node1")];
+
+        let plan = volume_plan("node1", &pods, &claims, &volumes);
+        // The smallest fitting volume of this node goes to each claim
+        assert_eq!(
+            plan,
+            vec![
+                VolumeAction::Bind {
+                    claim: &claims[0],
+                    volume: &volumes[1],
+                },
+                VolumeAction::Bind {
+                    claim: &claims[1],
+                    volume: &volumes[0],
+                },
+            ]
+        );
+
+        // Claims of the ZFS class, or none, are provisioned when nothing fits
+        let mut claims = vec![claim("used", "50Gi", serde_json::json!({}))];
+        assert!(volume_plan("node1", &pods, &claims, &volumes).is_empty());
+        claims[0].spec.as_mut().unwrap().storage_class_name = None;
+        assert_eq!(
+            volume_plan("node1", &pods, &claims, &volumes),
+            vec![VolumeAction::Provision { claim: &claims[0] }]
+        );
+    }
+
+    #[test]
+    fn test_volume_plan_reclaims_volumes_of_deleted_claims() {
+        let bound = claim("data", "1Gi", serde_json::json!({}));
+        let mut provisioned = provisioned_volume(
+            "pvc-uid-data",
+            "rpool/volumes/pvc-uid-data",
+            "node1",
+            &bound,
+        );
+        provisioned.spec.as_mut().unwrap().claim_ref = Some(ObjectReference {
+            namespace: Some("default".to_string()),
+            name: Some("data".to_string()),
+            ..Default::default()
+        });
+        provisioned.status.as_mut().unwrap().phase = Some(PHASE_BOUND.to_string());
+        let mut retained = provisioned.clone();
+        retained.metadata.name = Some("retained".to_string());
+        retained
+            .spec
+            .as_mut()
+            .unwrap()
+            .persistent_volume_reclaim_policy = Some("Retain".to_string());
+        let volumes = vec![provisioned, retained];
+
+        // Nothing happens while the claim exists
+        let claims = vec![bound];
+        assert!(volume_plan("node1", &[], &claims, &volumes).is_empty());
+
+        assert_eq!(
+            volume_plan("node1", &[], &[], &volumes),
+            vec![
+                VolumeAction::Reclaim {
+                    volume: &volumes[0]
+                },
+                VolumeAction::Release {
+                    volume: &volumes[1]
+                },
+            ]
+        );
+        // Another node leaves them alone
+        assert!(volume_plan("node2", &[], &[], &volumes).is_empty());
+    }
+}
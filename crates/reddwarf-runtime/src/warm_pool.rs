@@ -0,0 +1,395 @@
+//! Warm zone pool: idle zones installed ahead of time for each brand, so a
+//! pod's zone costs a boot instead of a `zoneadm install`
+
+use crate::error::Result;
+use crate::traits::ZoneRuntime;
+use crate::types::{WarmZone, ZoneBrand, ZoneConfig, ZoneInfo, ZoneState};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name prefix of warm zones, distinct from pod zones (`reddwarf-...`)
+pub const WARM_ZONE_PREFIX: &str = "rdwarm-";
+
+/// Interval between pool resyncs when no claim triggers one
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many warm zones to keep installed for one brand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmPoolSpec {
+    /// Brand of the pool's zones
+    pub brand: ZoneBrand,
+    /// Number of idle zones to keep
+    pub size: usize,
+    /// Image LX zones are installed from; only pods installing the same
+    /// image can claim them
+    pub lx_image_path: Option<String>,
+}
+
+impl WarmPoolSpec {
+    /// Parse `brand=size`, or `lx=size:/path/to/image` for LX zones
+    pub fn parse(spec: &str) -> std::result::Result<Self, String> {
+        let (brand, rest) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'brand=size[:image]', got '{}'", spec))?;
+        let (size, image) = match rest.split_once(':') {
+            Some((size, image)) => (size, Some(image.trim().to_string())),
+            None => (rest, None),
+        };
+        let size = size
+            .trim()
+            .parse::<usize>()
+            .map_err(|e| format!("invalid pool size '{}': {}", size, e))?;
+
+        let brand = match brand.trim() {
+            "lx" => ZoneBrand::Lx,
+            "reddwarf" => ZoneBrand::Reddwarf,
+            other => return Err(format!("unknown zone brand '{}'", other)),
+        };
+        match (&brand, &image) {
+            (ZoneBrand::Lx, None) => {
+                return Err("LX warm zones need an image, as 'lx=size:/path'".to_string())
+            }
+            (ZoneBrand::Reddwarf, Some(_)) => {
+                return Err("only LX warm zones take an image".to_string())
+            }
+            _ => {}
+        }
+
+        Ok(Self {
+            brand,
+            size,
+            lx_image_path: image,
+        })
+    }
+
+    /// Name prefix of this pool's zones, followed by an index
+    fn zone_prefix(&self) -> String {
+        format!("{}{}-", WARM_ZONE_PREFIX, self.brand)
+    }
+}
+
+/// Keeps a pool of installed, idle zones per brand and hands them to pods
+///
+/// Pool membership is read from the runtime's zone list on every use, so
+/// warm zones left over from a previous agent run are adopted (or trimmed)
+/// rather than leaked.
+pub struct WarmPool {
+    runtime: Arc<dyn ZoneRuntime>,
+    zonepath_prefix: String,
+    specs: Vec<WarmPoolSpec>,
+    /// Warm zones being claimed, prepared or destroyed
+    busy: Mutex<HashSet<String>>,
+    /// Wakes the resync loop after a claim so the pool is refilled promptly
+    refill: Notify,
+}
+
+impl WarmPool {
+    pub fn new(
+        runtime: Arc<dyn ZoneRuntime>,
+        zonepath_prefix: impl Into<String>,
+        specs: Vec<WarmPoolSpec>,
+    ) -> Self {
+        Self {
+            runtime,
+            zonepath_prefix: zonepath_prefix.into(),
+            specs,
+            busy: Mutex::new(HashSet::new()),
+            refill: Notify::new(),
+        }
+    }
+
+    /// Keep the pool at its configured sizes until cancelled
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!("Starting warm zone pool ({} brand(s))", self.specs.len());
+
+        let mut resync = tokio::time::interval(RESYNC_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Warm zone pool shutting down");
+                    return Ok(());
+                }
+                _ = resync.tick() => {}
+                _ = self.refill.notified() => {}
+            }
+
+            if let Err(e) = self.reconcile().await {
+                error!("Warm zone pool reconcile failed: {}", e);
+            }
+        }
+    }
+
+    /// Provision `config` from an idle warm zone of its brand. Returns
+    /// `Ok(false)`, touching nothing, when the pool has none to offer.
+    pub async fn claim(&self, config: &ZoneConfig) -> Result<bool> {
+        let Some(spec) = self
+            .specs
+            .iter()
+            .find(|s| s.brand == config.brand && s.lx_image_path == config.lx_image_path)
+        else {
+            return Ok(false);
+        };
+
+        let zones = self.runtime.list_zones().await?;
+        let Some(warm) = self
+            .members(spec, &zones)
+            .filter(|z| z.state == ZoneState::Installed)
+            .find(|z| self.reserve(&z.zone_name))
+            .map(|z| z.zone_name.clone())
+        else {
+            debug!("Warm zone pool: no idle {} zone", spec.brand);
+            return Ok(false);
+        };
+
+        let result = self.runtime.claim_warm_zone(&warm, config).await;
+        self.unreserve(&warm);
+        self.refill.notify_one();
+        result?;
+
+        info!(
+            "Zone {} provisioned from warm zone {}",
+            config.zone_name, warm
+        );
+        Ok(true)
+    }
+
+    /// Bring each brand's pool to its size: install missing zones, and
+    /// destroy surplus zones, zones left half-installed by an interrupted
+    /// run, and zones of brands no longer pooled
+    pub async fn reconcile(&self) -> Result<()> {
+        let zones = self.runtime.list_zones().await?;
+
+        for zone in zones.iter().filter(|z| {
+            z.zone_name.starts_with(WARM_ZONE_PREFIX)
+                && !self
+                    .specs
+                    .iter()
+                    .any(|s| z.zone_name.starts_with(&s.zone_prefix()))
+        }) {
+            self.destroy(&zone.zone_name).await;
+        }
+
+        for spec in &self.specs {
+            let mut idle = Vec::new();
+            for zone in self.members(spec, &zones) {
+                if zone.state == ZoneState::Installed {
+                    idle.push(zone.zone_name.clone());
+                } else {
+                    warn!(
+                        "Warm zone {} is {}, destroying it",
+                        zone.zone_name, zone.state
+                    );
+                    self.destroy(&zone.zone_name).await;
+                }
+            }
+            idle.sort();
+
+            while idle.len() > spec.size {
+                let surplus = idle.pop().unwrap();
+                self.destroy(&surplus).await;
+            }
+
+            let in_use: HashSet<String> = zones.iter().map(|z| z.zone_name.clone()).collect();
+            let mut index = 0;
+            for _ in idle.len()..spec.size {
+                let zone_name = loop {
+                    let name = format!("{}{}", spec.zone_prefix(), index);
+                    index += 1;
+                    if !in_use.contains(&name) {
+                        break name;
+                    }
+                };
+                self.prepare(spec, zone_name).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Zones of `spec`'s pool that no claim or other operation is using
+    fn members<'a>(
+        &'a self,
+        spec: &'a WarmPoolSpec,
+        zones: &'a [ZoneInfo],
+    ) -> impl Iterator<Item = &'a ZoneInfo> + 'a {
+        let prefix = spec.zone_prefix();
+        let busy = self.busy.lock().unwrap().clone();
+        zones
+            .iter()
+            .filter(move |z| z.zone_name.starts_with(&prefix) && !busy.contains(&z.zone_name))
+    }
+
+    async fn prepare(&self, spec: &WarmPoolSpec, zone_name: String) {
+        if !self.reserve(&zone_name) {
+            return;
+        }
+        let zone = WarmZone {
+            zonepath: format!("{}/{}", self.zonepath_prefix, zone_name),
+            zone_name,
+            brand: spec.brand.clone(),
+            lx_image_path: spec.lx_image_path.clone(),
+        };
+        match self.runtime.prepare_warm_zone(&zone).await {
+            Ok(()) => info!("Warm zone {} installed", zone.zone_name),
+            // Left for the next reconcile to destroy and retry
+            Err(e) => error!("Failed to prepare warm zone {}: {}", zone.zone_name, e),
+        }
+        self.unreserve(&zone.zone_name);
+    }
+
+    async fn destroy(&self, zone_name: &str) {
+        if !self.reserve(zone_name) {
+            return;
+        }
+        match self.runtime.destroy_warm_zone(zone_name).await {
+            Ok(()) => info!("Warm zone {} destroyed", zone_name),
+            Err(e) => error!("Failed to destroy warm zone {}: {}", zone_name, e),
+        }
+        self.unreserve(zone_name);
+    }
+
+    /// Mark a warm zone busy; false if something else already is using it
+    fn reserve(&self, zone_name: &str) -> bool {
+        self.busy.lock().unwrap().insert(zone_name.to_string())
+    }
+
+    fn unreserve(&self, zone_name: &str) {
+        self.busy.lock().unwrap().remove(zone_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockRuntime;
+    use crate::storage::MockStorageEngine;
+    use crate::types::{EtherstubConfig, NetworkMode, StoragePoolConfig, ZoneStorageOpts};
+
+    fn make_pool(specs: Vec<WarmPoolSpec>) -> (Arc<MockRuntime>, WarmPool) {
+        let storage = Arc::new(MockStorageEngine::new(StoragePoolConfig::from_pool(
+            "rpool",
+        )));
+        let runtime = Arc::new(MockRuntime::new(storage));
+        let pool = WarmPool::new(runtime.clone(), "/zones", specs);
+        (runtime, pool)
+    }
+
+    fn make_zone_config(zone_name: &str, brand: ZoneBrand) -> ZoneConfig {
+        ZoneConfig {
+            zone_name: zone_name.to_string(),
+            brand,
+            zonepath: format!("/zones/{}", zone_name),
+            network: NetworkMode::Etherstub(EtherstubConfig {
+                etherstub_name: "reddwarf0".to_string(),
+                vnic_name: "vnic0".to_string(),
+                ip_address: "10.0.0.2".to_string(),
+                gateway: "10.0.0.1".to_string(),
+                prefix_len: 16,
+            }),
+            storage: ZoneStorageOpts::default(),
+            lx_image_path: None,
+            processes: vec![],
+            cpu_cap: None,
+            memory_cap: None,
+            swap_cap: None,
+            cpu_shares: None,
+            dedicated_cpus: None,
+            max_lwps: None,
+            max_processes: None,
+            fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
+        }
+    }
+
+    async fn zone_names(runtime: &MockRuntime) -> Vec<String> {
+        let mut names: Vec<String> = runtime
+            .list_zones()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|z| z.zone_name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = WarmPoolSpec::parse("reddwarf=2").unwrap();
+        assert_eq!(spec.brand, ZoneBrand::Reddwarf);
+        assert_eq!(spec.size, 2);
+
+        let spec = WarmPoolSpec::parse("lx=1:/images/alpine.tar.gz").unwrap();
+        assert_eq!(spec.lx_image_path.as_deref(), Some("/images/alpine.tar.gz"));
+
+        assert!(WarmPoolSpec::parse("lx=1").is_err());
+        assert!(WarmPoolSpec::parse("reddwarf=1:/images/a.tar.gz").is_err());
+        assert!(WarmPoolSpec::parse("bhyve=1").is_err());
+        assert!(WarmPoolSpec::parse("reddwarf").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_claim_and_refill() {
+        let (runtime, pool) = make_pool(vec![WarmPoolSpec::parse("reddwarf=2").unwrap()]);
+        let config = make_zone_config("reddwarf-default-web", ZoneBrand::Reddwarf);
+
+        // Nothing to claim before the pool is filled
+        assert!(!pool.claim(&config).await.unwrap());
+
+        pool.reconcile().await.unwrap();
+        assert_eq!(
+            zone_names(&runtime).await,
+            vec!["rdwarm-reddwarf-0", "rdwarm-reddwarf-1"]
+        );
+
+        assert!(pool.claim(&config).await.unwrap());
+        assert_eq!(
+            runtime.get_zone_state(&config.zone_name).await.unwrap(),
+            ZoneState::Running
+        );
+        let info = runtime.get_zone_info(&config.zone_name).await.unwrap();
+        assert_eq!(info.zonepath, "/zones/reddwarf-default-web");
+
+        // An LX pod cannot use the reddwarf pool
+        let lx = make_zone_config("reddwarf-default-lx", ZoneBrand::Lx);
+        assert!(!pool.claim(&lx).await.unwrap());
+
+        pool.reconcile().await.unwrap();
+        assert_eq!(
+            zone_names(&runtime).await,
+            vec![
+                "rdwarm-reddwarf-0",
+                "rdwarm-reddwarf-1",
+                "reddwarf-default-web"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_trims_surplus_and_unpooled_brands() {
+        let (runtime, pool) = make_pool(vec![
+            WarmPoolSpec::parse("reddwarf=3").unwrap(),
+            WarmPoolSpec::parse("lx=1:/images/alpine.tar.gz").unwrap(),
+        ]);
+        pool.reconcile().await.unwrap();
+        assert_eq!(zone_names(&runtime).await.len(), 4);
+
+        // Restarted with a smaller pool and no LX zones
+        let pool = WarmPool::new(
+            runtime.clone(),
+            "/zones",
+            vec![WarmPoolSpec::parse("reddwarf=1").unwrap()],
+        );
+        pool.reconcile().await.unwrap();
+        assert_eq!(zone_names(&runtime).await, vec!["rdwarm-reddwarf-0"]);
+    }
+}
@@ -0,0 +1,717 @@
+use super::{controller_of, controller_reference, list};
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use crate::pod_conditions::is_pod_ready;
+use k8s_openapi::api::apps::v1::{DaemonSet, DaemonSetStatus};
+use k8s_openapi::api::core::v1::{Node, Pod, Taint, Toleration};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use reddwarf_core::resources::{
+    daemon_set_max_unavailable, is_on_delete, pod_template_hash, APPS_API_VERSION, DAEMON_SET_KIND,
+    POD_TEMPLATE_HASH_LABEL,
+};
+use reddwarf_core::{ErrorClass, EventBus, GroupVersionKind, WatchEventType};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the DaemonSet controller subscribes to the event bus under
+const SUBSCRIBER: &str = "daemonset-controller";
+
+/// Configuration for the DaemonSet controller
+#[derive(Debug, Clone)]
+pub struct DaemonSetControllerConfig {
+    /// Interval between full resyncs of every DaemonSet against the nodes
+    /// (safety net for missed events, and how node readiness, label and
+    /// taint changes are picked up)
+    pub resync_interval: Duration,
+}
+
+impl Default for DaemonSetControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How a DaemonSet's pods must change to run one pod on each of its nodes
+#[derive(Debug, Default, PartialEq)]
+struct DaemonPlan<'a> {
+    /// Nodes that need a pod
+    create: Vec<&'a str>,
+    /// Pods on nodes the DaemonSet no longer runs on, duplicates, and pods
+    /// of an old template being replaced
+    delete: Vec<&'a Pod>,
+    status: DaemonSetStatus,
+}
+
+/// Runs one pod of every DaemonSet on each Ready node its pod template
+/// selects and tolerates, and reports the counts in its status
+///
+/// Pods are created with `spec.nodeName` already set, so they bypass the
+/// scheduler; cordoned nodes still get their pods, as in Kubernetes. Pods
+/// on nodes that stop matching or leave the cluster are deleted, and when
+/// the template changes the `RollingUpdate` strategy replaces the old pods
+/// within `maxUnavailable` nodes at a time, while `OnDelete` leaves them
+/// until they are deleted.
+pub struct DaemonSetController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    config: DaemonSetControllerConfig,
+}
+
+impl DaemonSetController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: DaemonSetControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting DaemonSet controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("DaemonSet controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("DaemonSet resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            let namespace = event.resource_key.namespace.clone();
+                            let name = match (event.gvk.kind.as_str(), &event.event_type) {
+                                (DAEMON_SET_KIND, WatchEventType::Deleted) => continue,
+                                (DAEMON_SET_KIND, _) => event.resource_key.name.clone(),
+                                ("Pod", _) => {
+                                    let Ok(meta) = serde_json::from_value::<ObjectMeta>(
+                                        event.object["metadata"].clone(),
+                                    ) else {
+                                        continue;
+                                    };
+                                    match controller_of(&meta, DAEMON_SET_KIND) {
+                                        Some(owner) => owner.name.clone(),
+                                        None => continue,
+                                    }
+                                }
+                                // Node status changes with every heartbeat;
+                                // only joins and departures warrant a resync
+                                // ahead of the next tick
+                                ("Node", WatchEventType::Added | WatchEventType::Deleted) => {
+                                    if let Err(e) = self.resync().await {
+                                        error!("DaemonSet resync failed: {}", e);
+                                    }
+                                    continue;
+                                }
+                                _ => continue,
+                            };
+                            if let Err(e) = self.reconcile_named(&namespace, &name).await {
+                                warn!(
+                                    "Failed to reconcile DaemonSet {}/{}: {}",
+                                    namespace, name, e
+                                );
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            let gvk = GroupVersionKind::from_api_version_kind(APPS_API_VERSION, DAEMON_SET_KIND);
+                            match self.event_bus.resync(SUBSCRIBER, &gvk) {
+                                Ok(count) => warn!("Missed {} events, resyncing {} DaemonSets", n, count),
+                                Err(e) => {
+                                    warn!("Missed {} events and cannot resync ({}), doing full DaemonSet resync", n, e);
+                                    if let Err(e) = self.resync().await {
+                                        error!("DaemonSet resync after lag failed: {}", e);
+                                    }
+                                }
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping DaemonSet controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconcile every DaemonSet, and delete pods whose DaemonSet is gone
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing DaemonSets");
+
+        // Pods first: a pod listed before its DaemonSet was created would
+        // look orphaned
+        let pods: Vec<Pod> = list(&self.api_client, "/api/v1/pods").await?;
+        let nodes: Vec<Node> = list(&self.api_client, "/api/v1/nodes").await?;
+        let daemon_sets: Vec<DaemonSet> =
+            list(&self.api_client, "/apis/apps/v1/daemonsets").await?;
+
+        let mut owned: HashMap<&str, Vec<&Pod>> = HashMap::new();
+        for pod in &pods {
+            if let Some(owner) = controller_of(&pod.metadata, DAEMON_SET_KIND) {
+                owned.entry(owner.uid.as_str()).or_default().push(pod);
+            }
+        }
+
+        let mut live = HashSet::new();
+        for daemon_set in &daemon_sets {
+            let uid = daemon_set.metadata.uid.as_deref().unwrap_or_default();
+            live.insert(uid);
+            let pods = owned.get(uid).map(Vec::as_slice).unwrap_or_default();
+            if let Err(e) = self.reconcile(daemon_set, &nodes, pods).await {
+                warn!(
+                    "Failed to reconcile DaemonSet {}/{}: {}",
+                    daemon_set.metadata.namespace.as_deref().unwrap_or_default(),
+                    daemon_set.metadata.name.as_deref().unwrap_or_default(),
+                    e
+                );
+            }
+        }
+
+        for (uid, pods) in owned {
+            if live.contains(uid) {
+                continue;
+            }
+            for pod in pods
+                .into_iter()
+                .filter(|p| p.metadata.deletion_timestamp.is_none())
+            {
+                let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+                let name = pod.metadata.name.as_deref().unwrap_or_default();
+                info!("Deleting pod {}/{} of a deleted DaemonSet", namespace, name);
+                if let Err(e) = self.api_client.delete_pod(namespace, name).await {
+                    warn!(
+                        "Failed to delete orphaned pod {}/{}: {}",
+                        namespace, name, e
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile the DaemonSet `namespace/name` against the nodes and its
+    /// pods
+    async fn reconcile_named(&self, namespace: &str, name: &str) -> Result<()> {
+        let daemon_set: DaemonSet = match self
+            .api_client
+            .get_json(&format!(
+                "/apis/apps/v1/namespaces/{}/daemonsets/{}",
+                namespace, name
+            ))
+            .await
+        {
+            Ok(body) => serde_json::from_value(body).map_err(|e| {
+                RuntimeError::internal_error(format!("Failed to parse DaemonSet: {}", e))
+            })?,
+            // Deleted; the next resync cleans up
+            Err(e) if e.is_not_found() => {
+                debug!("DaemonSet {}/{} not found: {}", namespace, name, e);
+                return Ok(());
+            }
+            // Reported by the caller, and the next resync catches up
+            Err(e) => return Err(e),
+        };
+        let uid = daemon_set.metadata.uid.as_deref();
+        let nodes: Vec<Node> = list(&self.api_client, "/api/v1/nodes").await?;
+        let pods: Vec<Pod> = list(
+            &self.api_client,
+            &format!("/api/v1/namespaces/{}/pods", namespace),
+        )
+        .await?;
+        let owned: Vec<&Pod> = pods
+            .iter()
+            .filter(|p| controller_of(&p.metadata, DAEMON_SET_KIND).map(|o| o.uid.as_str()) == uid)
+            .collect();
+        self.reconcile(&daemon_set, &nodes, &owned).await
+    }
+
+    async fn reconcile(&self, daemon_set: &DaemonSet, nodes: &[Node], pods: &[&Pod]) -> Result<()> {
+        if daemon_set.metadata.deletion_timestamp.is_some() {
+            return Ok(());
+        }
+        let namespace = daemon_set.metadata.namespace.as_deref().unwrap_or_default();
+        let name = daemon_set.metadata.name.as_deref().unwrap_or_default();
+
+        let plan = daemon_plan(daemon_set, nodes, pods)?;
+        for node in &plan.create {
+            info!(
+                "Creating pod of DaemonSet {}/{} on node {}",
+                namespace, name, node
+            );
+            let pod = pod_for_node(daemon_set, node);
+            self.api_client.create_pod(namespace, &pod).await?;
+        }
+        for pod in &plan.delete {
+            let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+            info!(
+                "Deleting pod {}/{} of DaemonSet {}",
+                namespace, pod_name, name
+            );
+            self.api_client.delete_pod(namespace, pod_name).await?;
+        }
+
+        let mut status = plan.status;
+        status.observed_generation = daemon_set.metadata.generation;
+        if daemon_set.status.as_ref() != Some(&status) {
+            let mut updated = daemon_set.clone();
+            updated.status = Some(status);
+            self.api_client
+                .update_daemon_set_status(namespace, name, &updated)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `node` reports its `Ready` condition as `True`
+fn is_node_ready(node: &Node) -> bool {
+    node.status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .any(|c| c.type_ == "Ready" && c.status == "True")
+}
+
+/// Whether `toleration` tolerates `taint`
+///
+/// An empty key with the `Exists` operator tolerates every taint, and an
+/// empty effect every effect.
+fn tolerates(toleration: &Toleration, taint: &Taint) -> bool {
+    let exists = toleration.operator.as_deref() == Some("Exists");
+    let key_matches = match toleration.key.as_deref() {
+        None | Some("") => exists,
+        Some(key) => key == taint.key,
+    };
+    let value_matches = exists
+        || toleration.value.as_deref().unwrap_or_default()
+            == taint.value.as_deref().unwrap_or_default();
+    let effect_matches = toleration
+        .effect
+        .as_deref()
+        .is_none_or(|effect| effect.is_empty() || effect == taint.effect);
+    key_matches && value_matches && effect_matches
+}
+
+/// Whether the pods of `daemon_set` belong on `node`: its labels match the
+/// template's `nodeSelector`, and the template tolerates each of its
+/// `NoSchedule` and `NoExecute` taints
+fn runs_on(daemon_set: &DaemonSet, node: &Node) -> bool {
+    let pod_spec = daemon_set
+        .spec
+        .as_ref()
+        .and_then(|s| s.template.spec.as_ref());
+    let labels = node.metadata.labels.as_ref();
+
+    let selected = pod_spec
+        .and_then(|s| s.node_selector.as_ref())
+        .into_iter()
+        .flatten()
+        .all(|(key, value)| labels.and_then(|l| l.get(key)) == Some(value));
+
+    let tolerations = pod_spec
+        .and_then(|s| s.tolerations.as_deref())
+        .unwrap_or_default();
+    let tolerated = node
+        .spec
+        .as_ref()
+        .and_then(|s| s.taints.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .filter(|taint| taint.effect != "PreferNoSchedule")
+        .all(|taint| tolerations.iter().any(|t| tolerates(t, taint)));
+
+    selected && tolerated
+}
+
+/// Whether `pod` was created from the template with `hash`
+fn is_current(pod: &Pod, hash: &str) -> bool {
+    pod.metadata
+        .labels
+        .as_ref()
+        .and_then(|l| l.get(POD_TEMPLATE_HASH_LABEL))
+        .is_some_and(|h| h == hash)
+}
+
+/// What to create and delete so `daemon_set` runs one pod of its current
+/// template on each node it belongs on, and the status it then reports
+///
+/// A node still holding a terminating pod gets its new pod once the old one
+/// is gone. Of duplicate pods on a node the current, ready, oldest one is
+/// kept. Outdated pods that aren't ready are replaced right away; ready
+/// ones only while fewer than `maxUnavailable` nodes lack a ready pod.
+fn daemon_plan<'a>(
+    daemon_set: &DaemonSet,
+    nodes: &'a [Node],
+    pods: &[&'a Pod],
+) -> Result<DaemonPlan<'a>> {
+    let hash = daemon_set
+        .spec
+        .as_ref()
+        .map(|s| pod_template_hash(&s.template))
+        .unwrap_or_default();
+
+    let mut by_node: HashMap<&str, Vec<&'a Pod>> = HashMap::new();
+    for pod in pods {
+        let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+        if matches!(phase, Some("Succeeded" | "Failed")) {
+            continue;
+        }
+        let node = pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.node_name.as_deref())
+            .unwrap_or_default();
+        by_node.entry(node).or_default().push(pod);
+    }
+
+    let mut plan = DaemonPlan::default();
+    let mut status = DaemonSetStatus::default();
+    let mut unavailable = 0;
+    let mut outdated = Vec::new();
+
+    for node in nodes {
+        let node_name = node.metadata.name.as_deref().unwrap_or_default();
+        let on_node = by_node.remove(node_name).unwrap_or_default();
+        let terminating = on_node
+            .iter()
+            .any(|p| p.metadata.deletion_timestamp.is_some());
+        let mut active: Vec<&'a Pod> = on_node
+            .into_iter()
+            .filter(|p| p.metadata.deletion_timestamp.is_none())
+            .collect();
+
+        if !runs_on(daemon_set, node) {
+            status.number_misscheduled += active.len() as i32;
+            plan.delete.extend(active);
+            continue;
+        }
+        let ready_node = is_node_ready(node);
+        if ready_node {
+            status.desired_number_scheduled += 1;
+        }
+
+        active.sort_by_key(|pod| {
+            (
+                !is_current(pod, &hash),
+                !is_pod_ready(pod),
+                pod.metadata.creation_timestamp.as_ref().map(|t| t.0),
+            )
+        });
+        let mut active = active.into_iter();
+        let Some(kept) = active.next() else {
+            if ready_node {
+                unavailable += 1;
+                if !terminating {
+                    plan.create.push(node_name);
+                }
+            }
+            continue;
+        };
+        plan.delete.extend(active);
+
+        status.current_number_scheduled += 1;
+        let ready = is_pod_ready(kept);
+        if ready {
+            status.number_ready += 1;
+        } else if ready_node {
+            unavailable += 1;
+        }
+        if is_current(kept, &hash) {
+            *status.updated_number_scheduled.get_or_insert(0) += 1;
+        } else {
+            outdated.push((kept, ready));
+        }
+    }
+
+    // Pods on nodes that left the cluster
+    for pods in by_node.into_values() {
+        let active = pods
+            .into_iter()
+            .filter(|p| p.metadata.deletion_timestamp.is_none());
+        for pod in active {
+            status.number_misscheduled += 1;
+            plan.delete.push(pod);
+        }
+    }
+
+    if !is_on_delete(daemon_set) {
+        let max_unavailable =
+            daemon_set_max_unavailable(daemon_set, status.desired_number_scheduled)
+                .map_err(|e| RuntimeError::internal_error(e.to_string()))?;
+        let mut budget = max_unavailable - unavailable;
+        for (pod, ready) in outdated {
+            if !ready {
+                plan.delete.push(pod);
+            } else if budget > 0 {
+                budget -= 1;
+                plan.delete.push(pod);
+            }
+        }
+    }
+
+    status.number_available = Some(status.number_ready);
+    status.number_unavailable =
+        Some((status.desired_number_scheduled - status.number_ready).max(0));
+    plan.status = status;
+    Ok(plan)
+}
+
+/// A new pod of `daemon_set`, from its template, bound to `node`
+fn pod_for_node(daemon_set: &DaemonSet, node: &str) -> Pod {
+    let name = daemon_set.metadata.name.as_deref().unwrap_or_default();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let template = daemon_set
+        .spec
+        .as_ref()
+        .map(|s| s.template.clone())
+        .unwrap_or_default();
+    let hash = pod_template_hash(&template);
+    let template_meta = template.metadata.unwrap_or_default();
+    let mut labels = template_meta.labels.unwrap_or_default();
+    labels.insert(POD_TEMPLATE_HASH_LABEL.to_string(), hash);
+    let mut spec = template.spec.unwrap_or_default();
+    spec.node_name = Some(node.to_string());
+
+    Pod {
+        metadata: ObjectMeta {
+            name: Some(format!("{}-{}", name, &suffix[..5])),
+            namespace: daemon_set.metadata.namespace.clone(),
+            labels: Some(labels),
+            annotations: template_meta.annotations,
+            owner_references: Some(vec![controller_reference(
+                APPS_API_VERSION,
+                DAEMON_SET_KIND,
+                &daemon_set.metadata,
+            )]),
+            ..Default::default()
+        },
+        spec: Some(spec),
+        status: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::{DaemonSetSpec, DaemonSetUpdateStrategy};
+    use k8s_openapi::api::core::v1::{
+        NodeCondition, NodeSpec, NodeStatus, PodCondition, PodSpec, PodStatus, PodTemplateSpec,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, Time};
+    use std::collections::BTreeMap;
+
+    fn daemon_set(node_selector: Option<(&str, &str)>) -> DaemonSet {
+        let labels = BTreeMap::from([("app".to_string(), "logs".to_string())]);
+        DaemonSet {
+            metadata: ObjectMeta {
+                name: Some("logs".to_string()),
+                namespace: Some("default".to_string()),
+                uid: Some("uid-1".to_string()),
+                ..Default::default()
+            },
+            spec: Some(DaemonSetSpec {
+                selector: LabelSelector {
+                    match_labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        node_selector: node_selector
+                            .map(|(k, v)| BTreeMap::from([(k.to_string(), v.to_string())])),
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            status: None,
+        }
+    }
+
+    fn node(name: &str, ready: bool, taint: Option<&str>) -> Node {
+        Node {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                labels: Some(BTreeMap::from([("disk".to_string(), "ssd".to_string())])),
+                ..Default::default()
+            },
+            spec: Some(NodeSpec {
+                taints: taint.map(|key| {
+                    vec![Taint {
+                        key: key.to_string(),
+                        effect: "NoSchedule".to_string(),
+                        ..Default::default()
+                    }]
+                }),
+                ..Default::default()
+            }),
+            status: Some(NodeStatus {
+                conditions: Some(vec![NodeCondition {
+                    type_: "Ready".to_string(),
+                    status: if ready { "True" } else { "False" }.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn daemon_pod(ds: &DaemonSet, node: &str, ready: bool, age_secs: i64) -> Pod {
+        let mut pod = pod_for_node(ds, node);
+        pod.metadata.creation_timestamp = Some(Time(
+            chrono::Utc::now() - chrono::Duration::seconds(age_secs),
+        ));
+        pod.status = Some(PodStatus {
+            phase: Some("Running".to_string()),
+            conditions: Some(vec![PodCondition {
+                type_: "Ready".to_string(),
+                status: if ready { "True" } else { "False" }.to_string(),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        });
+        pod
+    }
+
+    fn names<'a>(pods: &[&'a Pod]) -> Vec<&'a str> {
+        pods.iter()
+            .map(|p| p.metadata.name.as_deref().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_one_pod_per_eligible_ready_node() {
+        let ds = daemon_set(Some(("disk", "ssd")));
+        let mut unlabeled = node("node4", true, None);
+        unlabeled.metadata.labels = None;
+        let nodes = [
+            node("node1", true, None),
+            node("node2", false, None),
+            node("node3", true, Some("dedicated")),
+            unlabeled,
+        ];
+        let on_node1 = daemon_pod(&ds, "node1", true, 60);
+        let duplicate = daemon_pod(&ds, "node1", true, 10);
+        let on_node4 = daemon_pod(&ds, "node4", true, 60);
+        let on_gone = daemon_pod(&ds, "node9", true, 60);
+        let pods = [&on_node1, &duplicate, &on_node4, &on_gone];
+
+        let plan = daemon_plan(&ds, &nodes, &pods).unwrap();
+        // node2 isn't Ready, node3's taint isn't tolerated and node4 lacks
+        // the selected label; the newer duplicate goes
+        assert!(plan.create.is_empty());
+        let mut deleted = names(&plan.delete);
+        deleted.sort();
+        let mut expected = names(&[&duplicate, &on_node4, &on_gone]);
+        expected.sort();
+        assert_eq!(deleted, expected);
+        assert_eq!(plan.status.desired_number_scheduled, 1);
+        assert_eq!(plan.status.current_number_scheduled, 1);
+        assert_eq!(plan.status.number_misscheduled, 2);
+        assert_eq!(plan.status.number_ready, 1);
+
+        // Tolerating the taint and readiness bring the other nodes in
+        let mut ds = daemon_set(None);
+        ds.spec
+            .as_mut()
+            .unwrap()
+            .template
+            .spec
+            .as_mut()
+            .unwrap()
+            .tolerations = Some(vec![Toleration {
+            key: Some("dedicated".to_string()),
+            operator: Some("Exists".to_string()),
+            ..Default::default()
+        }]);
+        let nodes = [
+            node("node1", true, None),
+            node("node3", true, Some("dedicated")),
+        ];
+        let plan = daemon_plan(&ds, &nodes, &[]).unwrap();
+        assert_eq!(plan.create, ["node1", "node3"]);
+        assert_eq!(plan.status.number_unavailable, Some(2));
+
+        let pod = pod_for_node(&ds, "node3");
+        assert_eq!(pod.spec.unwrap().node_name.as_deref(), Some("node3"));
+        let owner = controller_of(&pod.metadata, DAEMON_SET_KIND).unwrap();
+        assert_eq!(owner.uid, "uid-1");
+    }
+
+    #[test]
+    fn test_rolling_update_replaces_within_max_unavailable() {
+        let old = daemon_set(None);
+        let nodes = [
+            node("node1", true, None),
+            node("node2", true, None),
+            node("node3", true, None),
+        ];
+        let ready1 = daemon_pod(&old, "node1", true, 60);
+        let ready2 = daemon_pod(&old, "node2", true, 60);
+        let unready3 = daemon_pod(&old, "node3", false, 60);
+        let pods = [&ready1, &ready2, &unready3];
+
+        let mut new = old.clone();
+        new.spec
+            .as_mut()
+            .unwrap()
+            .template
+            .spec
+            .as_mut()
+            .unwrap()
+            .host_network = Some(true);
+
+        // The unready pod is replaced, and already uses up maxUnavailable
+        let plan = daemon_plan(&new, &nodes, &pods).unwrap();
+        assert_eq!(names(&plan.delete), names(&[&unready3]));
+        assert_eq!(plan.status.updated_number_scheduled, None);
+
+        // Once every node is ready again, one ready pod goes at a time
+        let ready3 = daemon_pod(&new, "node3", true, 5);
+        let plan = daemon_plan(&new, &nodes, &[&ready1, &ready2, &ready3]).unwrap();
+        assert_eq!(plan.delete.len(), 1);
+        assert_eq!(plan.status.updated_number_scheduled, Some(1));
+
+        // A node whose old pod is terminating waits for it to go
+        let mut terminating = ready1.clone();
+        terminating.metadata.deletion_timestamp = Some(Time(chrono::Utc::now()));
+        let plan = daemon_plan(&new, &nodes, &[&terminating, &ready2, &ready3]).unwrap();
+        assert!(plan.create.is_empty());
+        assert!(plan.delete.is_empty());
+
+        // OnDelete leaves old pods alone
+        let mut on_delete = new.clone();
+        on_delete.spec.as_mut().unwrap().update_strategy = Some(DaemonSetUpdateStrategy {
+            type_: Some("OnDelete".to_string()),
+            rolling_update: None,
+        });
+        let plan = daemon_plan(&on_delete, &nodes, &pods).unwrap();
+        assert!(plan.delete.is_empty());
+    }
+}
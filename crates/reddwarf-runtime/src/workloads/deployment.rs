@@ -0,0 +1,848 @@
+use super::{controller_of, controller_reference, list};
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use chrono::Utc;
+use k8s_openapi::api::apps::v1::{
+    Deployment, DeploymentCondition, DeploymentStatus, ReplicaSet, ReplicaSetSpec,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use reddwarf_core::resources::{
+    deployment_replicas, is_recreate, pod_template_hash, revision, rolling_update_limits,
+    APPS_API_VERSION, DEFAULT_REVISION_HISTORY_LIMIT, DEPLOYMENT_KIND, POD_TEMPLATE_HASH_LABEL,
+    REPLICA_SET_KIND, REVISION_ANNOTATION, ROLLBACK_ANNOTATION,
+};
+use reddwarf_core::{ErrorClass, EventBus, GroupVersionKind, WatchEventType};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the Deployment controller subscribes to the event bus under
+const SUBSCRIBER: &str = "deployment-controller";
+
+/// Configuration for the Deployment controller
+#[derive(Debug, Clone)]
+pub struct DeploymentControllerConfig {
+    /// Interval between full resyncs of every Deployment and its
+    /// ReplicaSets (safety net for missed events and orphaned ReplicaSets)
+    pub resync_interval: Duration,
+}
+
+impl Default for DeploymentControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How a Deployment replaces the pods of old ReplicaSets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    /// Remove every old pod before creating new ones
+    Recreate,
+    /// Replace pods a few at a time, staying within the given surge above
+    /// and unavailability below the desired replicas
+    RollingUpdate {
+        max_surge: i32,
+        max_unavailable: i32,
+    },
+}
+
+/// Rolls out each Deployment's pod template through ReplicaSets
+///
+/// Every distinct template gets a ReplicaSet named after the template's
+/// hash and numbered with a revision. The ReplicaSet of the current
+/// template is scaled up while the older ones are scaled down, all at once
+/// (`Recreate`) or within `maxSurge`/`maxUnavailable` (`RollingUpdate`).
+/// Old ReplicaSets are kept, scaled to zero, up to `revisionHistoryLimit`,
+/// so a Deployment annotated `reddwarf.io/rollback-to-revision` can return
+/// to the template of an earlier revision.
+pub struct DeploymentController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    config: DeploymentControllerConfig,
+}
+
+impl DeploymentController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: DeploymentControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting Deployment controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("Deployment controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("Deployment resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            let namespace = event.resource_key.namespace.clone();
+                            let name = match (event.gvk.kind.as_str(), &event.event_type) {
+                                (DEPLOYMENT_KIND, WatchEventType::Deleted) => continue,
+                                (DEPLOYMENT_KIND, _) => event.resource_key.name.clone(),
+                                (REPLICA_SET_KIND, _) => {
+                                    let Ok(meta) = serde_json::from_value::<ObjectMeta>(
+                                        event.object["metadata"].clone(),
+                                    ) else {
+                                        continue;
+                                    };
+                                    match controller_of(&meta, DEPLOYMENT_KIND) {
+                                        Some(owner) => owner.name.clone(),
+                                        None => continue,
+                                    }
+                                }
+                                _ => continue,
+                            };
+                            if let Err(e) = self.reconcile_named(&namespace, &name).await {
+                                warn!(
+                                    "Failed to reconcile Deployment {}/{}: {}",
+                                    namespace, name, e
+                                );
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            let gvk = GroupVersionKind::from_api_version_kind(APPS_API_VERSION, DEPLOYMENT_KIND);
+                            match self.event_bus.resync(SUBSCRIBER, &gvk) {
+                                Ok(count) => warn!("Missed {} events, resyncing {} Deployments", n, count),
+                                Err(e) => {
+                                    warn!("Missed {} events and cannot resync ({}), doing full Deployment resync", n, e);
+                                    if let Err(e) = self.resync().await {
+                                        error!("Deployment resync after lag failed: {}", e);
+                                    }
+                                }
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping Deployment controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconcile every Deployment, and delete ReplicaSets whose Deployment
+    /// is gone (their pods follow through the ReplicaSet controller)
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing Deployments");
+
+        // ReplicaSets first: one listed before its Deployment was created
+        // would look orphaned
+        let replica_sets: Vec<ReplicaSet> =
+            list(&self.api_client, "/apis/apps/v1/replicasets").await?;
+        let deployments: Vec<Deployment> =
+            list(&self.api_client, "/apis/apps/v1/deployments").await?;
+
+        let mut owned: HashMap<&str, Vec<ReplicaSet>> = HashMap::new();
+        for replica_set in &replica_sets {
+            if let Some(owner) = controller_of(&replica_set.metadata, DEPLOYMENT_KIND) {
+                owned
+                    .entry(owner.uid.as_str())
+                    .or_default()
+                    .push(replica_set.clone());
+            }
+        }
+
+        for deployment in &deployments {
+            let uid = deployment.metadata.uid.as_deref().unwrap_or_default();
+            let replica_sets = owned.remove(uid).unwrap_or_default();
+            if let Err(e) = self.reconcile(deployment, replica_sets).await {
+                warn!(
+                    "Failed to reconcile Deployment {}/{}: {}",
+                    deployment.metadata.namespace.as_deref().unwrap_or_default(),
+                    deployment.metadata.name.as_deref().unwrap_or_default(),
+                    e
+                );
+            }
+        }
+
+        for replica_set in owned.into_values().flatten() {
+            let namespace = replica_set
+                .metadata
+                .namespace
+                .as_deref()
+                .unwrap_or_default();
+            let name = replica_set.metadata.name.as_deref().unwrap_or_default();
+            info!(
+                "Deleting ReplicaSet {}/{} of a deleted Deployment",
+                namespace, name
+            );
+            if let Err(e) = self.api_client.delete_replica_set(namespace, name).await {
+                warn!(
+                    "Failed to delete orphaned ReplicaSet {}/{}: {}",
+                    namespace, name, e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile the Deployment `namespace/name` against its ReplicaSets
+    async fn reconcile_named(&self, namespace: &str, name: &str) -> Result<()> {
+        let deployment: Deployment = match self
+            .api_client
+            .get_json(&format!(
+                "/apis/apps/v1/namespaces/{}/deployments/{}",
+                namespace, name
+            ))
+            .await
+        {
+            Ok(body) => serde_json::from_value(body).map_err(|e| {
+                RuntimeError::internal_error(format!("Failed to parse Deployment: {}", e))
+            })?,
+            // Deleted; the next resync cleans up
+            Err(e) if e.is_not_found() => {
+                debug!("Deployment {}/{} not found: {}", namespace, name, e);
+                return Ok(());
+            }
+            // Reported by the caller, and the next resync catches up
+            Err(e) => return Err(e),
+        };
+        let uid = deployment.metadata.uid.as_deref();
+        let replica_sets: Vec<ReplicaSet> = list(
+            &self.api_client,
+            &format!("/apis/apps/v1/namespaces/{}/replicasets", namespace),
+        )
+        .await?;
+        let owned = replica_sets
+            .into_iter()
+            .filter(|rs| {
+                controller_of(&rs.metadata, DEPLOYMENT_KIND).map(|o| o.uid.as_str()) == uid
+            })
+            .collect();
+        self.reconcile(&deployment, owned).await
+    }
+
+    async fn reconcile(&self, deployment: &Deployment, owned: Vec<ReplicaSet>) -> Result<()> {
+        let Some(spec) = deployment.spec.as_ref() else {
+            return Ok(());
+        };
+        if deployment.metadata.deletion_timestamp.is_some() {
+            return Ok(());
+        }
+        let namespace = deployment.metadata.namespace.as_deref().unwrap_or_default();
+        let name = deployment.metadata.name.as_deref().unwrap_or_default();
+
+        if let Some(target) = rollback_target(deployment) {
+            return self.rollback(deployment, &owned, target).await;
+        }
+
+        let desired = deployment_replicas(deployment);
+        let strategy = if is_recreate(deployment) {
+            Strategy::Recreate
+        } else {
+            let (max_surge, max_unavailable) = rolling_update_limits(deployment)
+                .map_err(|e| RuntimeError::internal_error(e.to_string()))?;
+            Strategy::RollingUpdate {
+                max_surge,
+                max_unavailable,
+            }
+        };
+
+        let hash = pod_template_hash(&spec.template);
+        let latest = owned
+            .iter()
+            .map(|rs| revision(&rs.metadata))
+            .max()
+            .unwrap_or_default();
+        let (current, mut olds): (Vec<_>, Vec<_>) = owned
+            .into_iter()
+            .partition(|rs| template_hash(rs) == Some(hash.as_str()));
+        olds.sort_by_key(|rs| revision(&rs.metadata));
+
+        if spec.paused == Some(true) {
+            let new = current.into_iter().next();
+            return self
+                .update_status(deployment, desired, strategy, new.as_ref(), &olds)
+                .await;
+        }
+
+        let (mut new, created) = match current.into_iter().next() {
+            Some(rs) => (rs, false),
+            None => (new_replica_set(deployment, &hash, latest + 1), true),
+        };
+        let mut new_changed = false;
+        // Rolled back to the template of an old ReplicaSet: it becomes the
+        // latest revision again
+        if !created && revision(&new.metadata) < latest {
+            new.metadata
+                .annotations
+                .get_or_insert_with(BTreeMap::new)
+                .insert(REVISION_ANNOTATION.to_string(), (latest + 1).to_string());
+            new_changed = true;
+        }
+
+        let (new_replicas, old_replicas) = plan_rollout(desired, strategy, &new, &olds);
+        if spec_replicas(&new) != new_replicas {
+            new.spec.get_or_insert_with(Default::default).replicas = Some(new_replicas);
+            new_changed = true;
+        }
+
+        let new_name = new.metadata.name.clone().unwrap_or_default();
+        if created {
+            info!(
+                "Rolling out revision {} of Deployment {}/{} as ReplicaSet {}",
+                latest + 1,
+                namespace,
+                name,
+                new_name
+            );
+            new = self.api_client.create_replica_set(namespace, &new).await?;
+        } else if new_changed {
+            new = self
+                .api_client
+                .replace_replica_set(namespace, &new_name, &new)
+                .await?;
+        }
+
+        for (old, replicas) in olds.iter_mut().zip(old_replicas) {
+            if spec_replicas(old) == replicas {
+                continue;
+            }
+            let old_name = old.metadata.name.clone().unwrap_or_default();
+            debug!(
+                "Scaling old ReplicaSet {}/{} to {}",
+                namespace, old_name, replicas
+            );
+            old.spec.get_or_insert_with(Default::default).replicas = Some(replicas);
+            *old = self
+                .api_client
+                .replace_replica_set(namespace, &old_name, old)
+                .await?;
+        }
+
+        let history_limit = spec
+            .revision_history_limit
+            .unwrap_or(DEFAULT_REVISION_HISTORY_LIMIT);
+        for old in surplus_history(&olds, history_limit) {
+            let old_name = old.metadata.name.as_deref().unwrap_or_default();
+            info!(
+                "Deleting ReplicaSet {}/{} beyond the revision history limit",
+                namespace, old_name
+            );
+            self.api_client
+                .delete_replica_set(namespace, old_name)
+                .await?;
+        }
+
+        self.update_status(deployment, desired, strategy, Some(&new), &olds)
+            .await
+    }
+
+    async fn update_status(
+        &self,
+        deployment: &Deployment,
+        desired: i32,
+        strategy: Strategy,
+        new: Option<&ReplicaSet>,
+        olds: &[ReplicaSet],
+    ) -> Result<()> {
+        let status = deployment_status(deployment, desired, strategy, new, olds);
+        if deployment.status.as_ref() == Some(&status) {
+            return Ok(());
+        }
+        let namespace = deployment.metadata.namespace.as_deref().unwrap_or_default();
+        let name = deployment.metadata.name.as_deref().unwrap_or_default();
+        let mut updated = deployment.clone();
+        updated.status = Some(status);
+        self.api_client
+            .update_deployment_status(namespace, name, &updated)
+            .await?;
+        Ok(())
+    }
+
+    /// Put the template of revision `target` (the previous revision when 0)
+    /// back into the Deployment and clear the rollback annotation
+    async fn rollback(
+        &self,
+        deployment: &Deployment,
+        owned: &[ReplicaSet],
+        target: i64,
+    ) -> Result<()> {
+        let namespace = deployment.metadata.namespace.as_deref().unwrap_or_default();
+        let name = deployment.metadata.name.as_deref().unwrap_or_default();
+        let target = if target == 0 {
+            let mut revisions: Vec<i64> = owned.iter().map(|rs| revision(&rs.metadata)).collect();
+            revisions.sort_unstable();
+            revisions.iter().rev().nth(1).copied().unwrap_or(-1)
+        } else {
+            target
+        };
+
+        let mut updated = deployment.clone();
+        if let Some(annotations) = updated.metadata.annotations.as_mut() {
+            annotations.remove(ROLLBACK_ANNOTATION);
+        }
+        let template = owned
+            .iter()
+            .find(|rs| revision(&rs.metadata) == target)
+            .and_then(|rs| rs.spec.as_ref()?.template.clone());
+        match (template, updated.spec.as_mut()) {
+            (Some(mut template), Some(spec)) => {
+                if let Some(labels) = template.metadata.as_mut().and_then(|m| m.labels.as_mut()) {
+                    labels.remove(POD_TEMPLATE_HASH_LABEL);
+                }
+                info!(
+                    "Rolling back Deployment {}/{} to revision {}",
+                    namespace, name, target
+                );
+                spec.template = template;
+            }
+            _ => warn!(
+                "Cannot roll back Deployment {}/{}: revision {} not found",
+                namespace, name, target
+            ),
+        }
+        self.api_client
+            .replace_deployment(namespace, name, &updated)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The revision a Deployment asks to roll back to; unparseable revisions
+/// match nothing, so the request is dropped
+fn rollback_target(deployment: &Deployment) -> Option<i64> {
+    let value = deployment
+        .metadata
+        .annotations
+        .as_ref()?
+        .get(ROLLBACK_ANNOTATION)?;
+    Some(value.parse().unwrap_or(-1))
+}
+
+fn template_hash(replica_set: &ReplicaSet) -> Option<&str> {
+    replica_set
+        .metadata
+        .labels
+        .as_ref()?
+        .get(POD_TEMPLATE_HASH_LABEL)
+        .map(String::as_str)
+}
+
+fn spec_replicas(replica_set: &ReplicaSet) -> i32 {
+    replica_set
+        .spec
+        .as_ref()
+        .and_then(|s| s.replicas)
+        .unwrap_or_default()
+}
+
+fn available_replicas(replica_set: &ReplicaSet) -> i32 {
+    replica_set
+        .status
+        .as_ref()
+        .and_then(|s| s.available_replicas)
+        .unwrap_or_default()
+}
+
+fn status_replicas(replica_set: &ReplicaSet) -> i32 {
+    replica_set
+        .status
+        .as_ref()
+        .map(|s| s.replicas)
+        .unwrap_or_default()
+}
+
+/// The ReplicaSet rolling out `deployment`'s template with `hash`, at zero
+/// replicas, its template and selector narrowed to the hash
+fn new_replica_set(deployment: &Deployment, hash: &str, revision: i64) -> ReplicaSet {
+    let spec = deployment.spec.clone().unwrap_or_default();
+    let name = deployment.metadata.name.as_deref().unwrap_or_default();
+
+    let mut template = spec.template;
+    let template_meta = template.metadata.get_or_insert_with(Default::default);
+    let labels = template_meta.labels.get_or_insert_with(BTreeMap::new);
+    labels.insert(POD_TEMPLATE_HASH_LABEL.to_string(), hash.to_string());
+    let labels = labels.clone();
+
+    let mut selector = spec.selector;
+    selector
+        .match_labels
+        .get_or_insert_with(BTreeMap::new)
+        .insert(POD_TEMPLATE_HASH_LABEL.to_string(), hash.to_string());
+
+    ReplicaSet {
+        metadata: ObjectMeta {
+            name: Some(format!("{}-{}", name, hash)),
+            namespace: deployment.metadata.namespace.clone(),
+            labels: Some(labels),
+            annotations: Some(BTreeMap::from([(
+                REVISION_ANNOTATION.to_string(),
+                revision.to_string(),
+            )])),
+            owner_references: Some(vec![controller_reference(
+                APPS_API_VERSION,
+                DEPLOYMENT_KIND,
+                &deployment.metadata,
+            )]),
+            ..Default::default()
+        },
+        spec: Some(ReplicaSetSpec {
+            replicas: Some(0),
+            min_ready_seconds: spec.min_ready_seconds,
+            selector,
+            template: Some(template),
+        }),
+        status: None,
+    }
+}
+
+/// Replicas of the new ReplicaSet and of each old one for the next step of
+/// the rollout, given the pods each has available
+///
+/// A rolling update scales the new ReplicaSet up as far as the surge
+/// allows, and the old ones down (unavailable pods first, oldest
+/// ReplicaSets first) as far as keeping `desired - max_unavailable` pods
+/// available allows. `Recreate` scales the old ones to zero and only scales
+/// up the new one once their pods are gone.
+fn plan_rollout(
+    desired: i32,
+    strategy: Strategy,
+    new: &ReplicaSet,
+    olds: &[ReplicaSet],
+) -> (i32, Vec<i32>) {
+    let mut old_replicas: Vec<i32> = olds.iter().map(spec_replicas).collect();
+    let new_replicas = spec_replicas(new);
+
+    let (max_surge, max_unavailable) = match strategy {
+        Strategy::Recreate => {
+            let old_pods_left = olds.iter().any(|rs| status_replicas(rs) > 0);
+            if old_replicas.iter().any(|r| *r > 0) || old_pods_left {
+                return (new_replicas, vec![0; olds.len()]);
+            }
+            return (desired, old_replicas);
+        }
+        Strategy::RollingUpdate {
+            max_surge,
+            max_unavailable,
+        } => (max_surge, max_unavailable),
+    };
+    if old_replicas.iter().all(|r| *r == 0) {
+        return (desired, old_replicas);
+    }
+
+    let total: i32 = new_replicas + old_replicas.iter().sum::<i32>();
+    let new_target = if new_replicas >= desired {
+        desired
+    } else {
+        (new_replicas + (desired + max_surge - total).max(0)).min(desired)
+    };
+
+    let min_available = desired - max_unavailable;
+    let new_unavailable = (new_replicas - available_replicas(new)).max(0);
+    let mut budget = (total - min_available - new_unavailable).max(0);
+
+    // Unavailable old pods cost no availability
+    for (old, replicas) in olds.iter().zip(old_replicas.iter_mut()) {
+        let unavailable = (*replicas - available_replicas(old)).max(0);
+        let scale_down = unavailable.min(budget);
+        *replicas -= scale_down;
+        budget -= scale_down;
+    }
+
+    let all_available: i32 =
+        available_replicas(new) + olds.iter().map(available_replicas).sum::<i32>();
+    let mut budget = budget.min((all_available - min_available).max(0));
+    for replicas in old_replicas.iter_mut() {
+        let scale_down = (*replicas).min(budget);
+        *replicas -= scale_down;
+        budget -= scale_down;
+    }
+
+    (new_target, old_replicas)
+}
+
+/// Old ReplicaSets, ordered oldest first, that are scaled down and exceed
+/// the revision history limit
+fn surplus_history(olds: &[ReplicaSet], limit: i32) -> Vec<&ReplicaSet> {
+    let idle: Vec<&ReplicaSet> = olds
+        .iter()
+        .filter(|rs| spec_replicas(rs) == 0 && status_replicas(rs) == 0)
+        .collect();
+    let surplus = idle.len().saturating_sub(limit.max(0) as usize);
+    idle.into_iter().take(surplus).collect()
+}
+
+/// Status of `deployment` from its ReplicaSets, keeping the transition
+/// times of conditions that did not change
+fn deployment_status(
+    deployment: &Deployment,
+    desired: i32,
+    strategy: Strategy,
+    new: Option<&ReplicaSet>,
+    olds: &[ReplicaSet],
+) -> DeploymentStatus {
+    let all = || new.into_iter().chain(olds);
+    let replicas: i32 = all().map(status_replicas).sum();
+    let updated = new.map(status_replicas).unwrap_or_default();
+    let ready: i32 = all()
+        .filter_map(|rs| rs.status.as_ref()?.ready_replicas)
+        .sum();
+    let available: i32 = all().map(available_replicas).sum();
+    let scheduled: i32 = all().map(spec_replicas).sum();
+
+    let max_unavailable = match strategy {
+        Strategy::Recreate => desired,
+        Strategy::RollingUpdate {
+            max_unavailable, ..
+        } => max_unavailable,
+    };
+    let existing = deployment
+        .status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default();
+    let available_condition = if available >= desired - max_unavailable {
+        (
+            "True",
+            "MinimumReplicasAvailable",
+            "Deployment has minimum availability.",
+        )
+    } else {
+        (
+            "False",
+            "MinimumReplicasUnavailable",
+            "Deployment does not have minimum availability.",
+        )
+    };
+    let complete = updated == desired
+        && available >= desired
+        && olds.iter().all(|rs| status_replicas(rs) == 0);
+    let progressing_condition = if complete {
+        (
+            "True",
+            "NewReplicaSetAvailable",
+            "ReplicaSet has successfully progressed.",
+        )
+    } else {
+        ("True", "ReplicaSetUpdated", "ReplicaSet is progressing.")
+    };
+
+    DeploymentStatus {
+        replicas: Some(replicas),
+        updated_replicas: Some(updated),
+        ready_replicas: Some(ready),
+        available_replicas: Some(available),
+        unavailable_replicas: Some((scheduled - available).max(0)),
+        conditions: Some(vec![
+            condition(existing, "Available", available_condition),
+            condition(existing, "Progressing", progressing_condition),
+        ]),
+        ..Default::default()
+    }
+}
+
+fn condition(
+    existing: &[DeploymentCondition],
+    type_: &str,
+    (status, reason, message): (&str, &str, &str),
+) -> DeploymentCondition {
+    let previous = existing.iter().find(|c| c.type_ == type_);
+    let now = Some(Time(Utc::now()));
+    let last_transition_time = match previous {
+        Some(c) if c.status == status => c.last_transition_time.clone(),
+        _ => now.clone(),
+    };
+    let last_update_time = match previous {
+        Some(c) if c.status == status && c.reason.as_deref() == Some(reason) => {
+            c.last_update_time.clone()
+        }
+        _ => now,
+    };
+    DeploymentCondition {
+        type_: type_.to_string(),
+        status: status.to_string(),
+        reason: Some(reason.to_string()),
+        message: Some(message.to_string()),
+        last_transition_time,
+        last_update_time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::{DeploymentSpec, ReplicaSetStatus};
+    use k8s_openapi::api::core::v1::{PodSpec, PodTemplateSpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+
+    fn replica_set(revision: i64, replicas: i32, available: i32) -> ReplicaSet {
+        ReplicaSet {
+            metadata: ObjectMeta {
+                annotations: Some(BTreeMap::from([(
+                    REVISION_ANNOTATION.to_string(),
+                    revision.to_string(),
+                )])),
+                ..Default::default()
+            },
+            spec: Some(ReplicaSetSpec {
+                replicas: Some(replicas),
+                ..Default::default()
+            }),
+            status: Some(ReplicaSetStatus {
+                replicas,
+                available_replicas: Some(available),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_plan_rollout() {
+        let rolling = Strategy::RollingUpdate {
+            max_surge: 1,
+            max_unavailable: 1,
+        };
+
+        // Surge one new pod and take one old pod away
+        let plan = plan_rollout(4, rolling, &replica_set(2, 0, 0), &[replica_set(1, 4, 4)]);
+        assert_eq!(plan, (1, vec![3]));
+
+        // Wait for the new pod before removing more old ones
+        let plan = plan_rollout(4, rolling, &replica_set(2, 1, 0), &[replica_set(1, 3, 3)]);
+        assert_eq!(plan, (2, vec![3]));
+        let plan = plan_rollout(4, rolling, &replica_set(2, 2, 2), &[replica_set(1, 3, 3)]);
+        assert_eq!(plan, (2, vec![1]));
+
+        // Broken old pods go first without costing availability
+        let olds = [replica_set(1, 2, 0), replica_set(2, 2, 2)];
+        let plan = plan_rollout(4, rolling, &replica_set(3, 1, 1), &olds);
+        assert_eq!(plan, (1, vec![0, 2]));
+
+        // Finished rollouts just follow the desired replicas
+        assert_eq!(
+            plan_rollout(6, rolling, &replica_set(2, 4, 4), &[replica_set(1, 0, 0)]),
+            (6, vec![0])
+        );
+
+        // Recreate waits for the old pods to be gone
+        let plan = plan_rollout(
+            3,
+            Strategy::Recreate,
+            &replica_set(2, 0, 0),
+            &[replica_set(1, 3, 3)],
+        );
+        assert_eq!(plan, (0, vec![0]));
+        let mut stopping = replica_set(1, 0, 0);
+        stopping.status.as_mut().unwrap().replicas = 1;
+        let plan = plan_rollout(3, Strategy::Recreate, &replica_set(2, 0, 0), &[stopping]);
+        assert_eq!(plan, (0, vec![0]));
+        let plan = plan_rollout(
+            3,
+            Strategy::Recreate,
+            &replica_set(2, 0, 0),
+            &[replica_set(1, 0, 0)],
+        );
+        assert_eq!(plan, (3, vec![0]));
+
+        let olds: Vec<ReplicaSet> = (1..=4).map(|r| replica_set(r, 0, 0)).collect();
+        let surplus: Vec<i64> = surplus_history(&olds, 2)
+            .iter()
+            .map(|rs| revision(&rs.metadata))
+            .collect();
+        assert_eq!(surplus, [1, 2]);
+    }
+
+    #[test]
+    fn test_new_replica_set_and_status() {
+        let labels = BTreeMap::from([("app".to_string(), "web".to_string())]);
+        let mut deployment = Deployment {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                namespace: Some("default".to_string()),
+                uid: Some("uid-1".to_string()),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(2),
+                selector: LabelSelector {
+                    match_labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec::default()),
+                },
+                ..Default::default()
+            }),
+            status: None,
+        };
+        let hash = pod_template_hash(&deployment.spec.as_ref().unwrap().template);
+
+        let new = new_replica_set(&deployment, &hash, 3);
+        assert_eq!(new.metadata.name, Some(format!("web-{}", hash)));
+        assert_eq!(template_hash(&new), Some(hash.as_str()));
+        assert_eq!(revision(&new.metadata), 3);
+        let new_spec = new.spec.as_ref().unwrap();
+        assert_eq!(
+            new_spec.selector.match_labels.as_ref().unwrap()[POD_TEMPLATE_HASH_LABEL],
+            hash
+        );
+        let owner = controller_of(&new.metadata, DEPLOYMENT_KIND).unwrap();
+        assert_eq!(owner.uid, "uid-1");
+
+        // Conditions keep their transition time while nothing changes
+        let rolling = Strategy::RollingUpdate {
+            max_surge: 1,
+            max_unavailable: 0,
+        };
+        let done = replica_set(3, 2, 2);
+        let status = deployment_status(&deployment, 2, rolling, Some(&done), &[]);
+        assert_eq!(status.available_replicas, Some(2));
+        let conditions = status.conditions.clone().unwrap();
+        assert_eq!(
+            conditions[1].reason.as_deref(),
+            Some("NewReplicaSetAvailable")
+        );
+        deployment.status = Some(status.clone());
+        assert_eq!(
+            deployment_status(&deployment, 2, rolling, Some(&done), &[]),
+            status
+        );
+
+        let degraded = replica_set(3, 2, 1);
+        let status = deployment_status(&deployment, 2, rolling, Some(&degraded), &[]);
+        let available = &status.conditions.as_ref().unwrap()[0];
+        assert_eq!(available.status, "False");
+        assert_eq!(status.unavailable_replicas, Some(1));
+
+        deployment.metadata.annotations = Some(BTreeMap::from([(
+            ROLLBACK_ANNOTATION.to_string(),
+            "0".to_string(),
+        )]));
+        assert_eq!(rollback_target(&deployment), Some(0));
+    }
+}
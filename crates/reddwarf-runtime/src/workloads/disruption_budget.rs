@@ -0,0 +1,271 @@
+use super::list;
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use chrono::Utc;
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::policy::v1::{PodDisruptionBudget, PodDisruptionBudgetStatus};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{Condition, Time};
+use reddwarf_core::resources::{
+    budget_selects, disruption_budget_status, POD_DISRUPTION_BUDGET_KIND,
+};
+use reddwarf_core::{ErrorClass, EventBus};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the disruption budget controller subscribes to the event bus under
+const SUBSCRIBER: &str = "disruption-budget-controller";
+
+/// Path listing the budgets of every namespace
+const ALL_BUDGETS: &str = "/apis/policy/v1/poddisruptionbudgets";
+
+/// Configuration for the PodDisruptionBudget controller
+#[derive(Debug, Clone)]
+pub struct DisruptionBudgetControllerConfig {
+    /// Interval between full resyncs of every budget's status
+    pub resync_interval: Duration,
+}
+
+impl Default for DisruptionBudgetControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Keeps the status of every PodDisruptionBudget current: how many of its
+/// pods are expected and healthy, how many must stay healthy and how many
+/// may be disrupted
+///
+/// The status is informational; the API server checks evictions against
+/// the pods it stores, so a stale status never lets one through.
+pub struct DisruptionBudgetController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    config: DisruptionBudgetControllerConfig,
+}
+
+impl DisruptionBudgetController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: DisruptionBudgetControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting PodDisruptionBudget controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("PodDisruptionBudget controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync(ALL_BUDGETS).await {
+                        error!("PodDisruptionBudget resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        // A budget or one of the pods it may select changed
+                        Ok(event)
+                            if event.gvk.kind == POD_DISRUPTION_BUDGET_KIND
+                                || event.gvk.kind == "Pod" =>
+                        {
+                            let namespace = &event.resource_key.namespace;
+                            let path = format!(
+                                "/apis/policy/v1/namespaces/{}/poddisruptionbudgets",
+                                namespace
+                            );
+                            if let Err(e) = self.resync(&path).await {
+                                warn!(
+                                    "Failed to sync PodDisruptionBudgets in {}: {}",
+                                    namespace, e
+                                );
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Missed {} events, resyncing PodDisruptionBudgets", n);
+                            if let Err(e) = self.resync(ALL_BUDGETS).await {
+                                error!("PodDisruptionBudget resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping PodDisruptionBudget controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Update the status of every budget listed at `path`
+    async fn resync(&self, path: &str) -> Result<()> {
+        let budgets: Vec<PodDisruptionBudget> = list(&self.api_client, path).await?;
+        for budget in &budgets {
+            if let Err(e) = self.reconcile(budget).await {
+                warn!(
+                    "Failed to reconcile PodDisruptionBudget {}/{}: {}",
+                    budget.metadata.namespace.as_deref().unwrap_or_default(),
+                    budget.metadata.name.as_deref().unwrap_or_default(),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn reconcile(&self, budget: &PodDisruptionBudget) -> Result<()> {
+        if budget.metadata.deletion_timestamp.is_some() {
+            return Ok(());
+        }
+        let namespace = budget.metadata.namespace.as_deref().unwrap_or_default();
+        let name = budget.metadata.name.as_deref().unwrap_or_default();
+        let pods: Vec<Pod> = list(
+            &self.api_client,
+            &format!("/api/v1/namespaces/{}/pods", namespace),
+        )
+        .await?;
+        let selected: Vec<&Pod> = pods.iter().filter(|p| budget_selects(budget, p)).collect();
+        let status = budget_status(budget, &selected)?;
+        if budget.status.as_ref() == Some(&status) {
+            return Ok(());
+        }
+
+        let mut updated = budget.clone();
+        updated.status = Some(status);
+        match self
+            .api_client
+            .update_pod_disruption_budget_status(namespace, name, &updated)
+            .await
+        {
+            Ok(_) => Ok(()),
+            // Changed or deleted since it was listed; the next sync catches up
+            Err(e) if e.is_conflict() || e.is_not_found() => {
+                debug!(
+                    "PodDisruptionBudget {}/{} changed while syncing: {}",
+                    namespace, name, e
+                );
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Status of `budget` over the `pods` it selects, with its
+/// `DisruptionAllowed` condition
+fn budget_status(budget: &PodDisruptionBudget, pods: &[&Pod]) -> Result<PodDisruptionBudgetStatus> {
+    let mut status = disruption_budget_status(budget, pods).map_err(|e| {
+        RuntimeError::invalid_config(
+            format!(
+                "PodDisruptionBudget {} is invalid: {}",
+                budget.metadata.name.as_deref().unwrap_or_default(),
+                e
+            ),
+            "Fix spec.minAvailable or spec.maxUnavailable of the budget",
+        )
+    })?;
+
+    let (condition_status, reason, message) = if status.disruptions_allowed > 0 {
+        ("True", "SufficientPods", "")
+    } else {
+        (
+            "False",
+            "InsufficientPods",
+            "not enough healthy pods to allow a disruption",
+        )
+    };
+    let previous = budget
+        .status
+        .as_ref()
+        .and_then(|s| s.conditions.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .find(|c| c.type_ == "DisruptionAllowed");
+    let last_transition_time = match previous {
+        Some(c) if c.status == condition_status => c.last_transition_time.clone(),
+        _ => Time(Utc::now()),
+    };
+    status.conditions = Some(vec![Condition {
+        type_: "DisruptionAllowed".to_string(),
+        status: condition_status.to_string(),
+        reason: reason.to_string(),
+        message: message.to_string(),
+        last_transition_time,
+        observed_generation: budget.metadata.generation,
+    }]);
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodCondition, PodStatus};
+
+    fn budget(min_available: i32) -> PodDisruptionBudget {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "web", "namespace": "default", "generation": 2},
+            "spec": {"minAvailable": min_available, "selector": {"matchLabels": {"app": "web"}}}
+        }))
+        .unwrap()
+    }
+
+    fn ready_pod() -> Pod {
+        Pod {
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: "True".to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_budget_status_condition() {
+        let pods = [ready_pod(), ready_pod()];
+        let selected: Vec<&Pod> = pods.iter().collect();
+
+        let mut loose = budget(1);
+        let status = budget_status(&loose, &selected).unwrap();
+        assert_eq!(status.disruptions_allowed, 1);
+        assert_eq!(status.observed_generation, Some(2));
+        let condition = &status.conditions.as_ref().unwrap()[0];
+        assert_eq!(condition.status, "True");
+        assert_eq!(condition.reason, "SufficientPods");
+
+        // An unchanged condition keeps its transition time
+        loose.status = Some(status.clone());
+        assert_eq!(budget_status(&loose, &selected).unwrap(), status);
+
+        let status = budget_status(&budget(2), &selected).unwrap();
+        assert_eq!(status.disruptions_allowed, 0);
+        let condition = &status.conditions.unwrap()[0];
+        assert_eq!(condition.status, "False");
+        assert_eq!(condition.reason, "InsufficientPods");
+    }
+}
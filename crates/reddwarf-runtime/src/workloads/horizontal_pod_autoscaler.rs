@@ -0,0 +1,801 @@
+use super::list;
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use async_trait::async_trait;
+use chrono::Utc;
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
+use k8s_openapi::api::autoscaling::v2::{
+    HorizontalPodAutoscaler, HorizontalPodAutoscalerCondition, HorizontalPodAutoscalerStatus,
+    MetricStatus, MetricValueStatus, ResourceMetricStatus,
+};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, Time};
+use reddwarf_core::resources::{
+    autoscaler_bounds, replicas_for_utilization, selector_matches, stabilization_windows,
+    utilization_targets, DEPLOYMENT_KIND, HORIZONTAL_POD_AUTOSCALER_KIND, REPLICA_SET_KIND,
+};
+use reddwarf_core::{ErrorClass, EventBus, ResourceQuantities, WatchEventType};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the autoscaler controller subscribes to the event bus under
+const SUBSCRIBER: &str = "horizontal-pod-autoscaler-controller";
+
+/// Resource usage of one pod
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PodUsage {
+    pub cpu_millicores: i64,
+    pub memory_bytes: i64,
+}
+
+impl PodUsage {
+    /// Usage of `resource` (`cpu` in millicores, `memory` in bytes)
+    fn of(&self, resource: &str) -> Option<i64> {
+        match resource {
+            "cpu" => Some(self.cpu_millicores),
+            "memory" => Some(self.memory_bytes),
+            _ => None,
+        }
+    }
+}
+
+/// Where the autoscaler controller reads the usage of running pods from
+#[async_trait]
+pub trait PodMetrics: Send + Sync {
+    /// Current usage of the pod `namespace/name`, `None` while unknown
+    async fn pod_usage(&self, namespace: &str, name: &str) -> Result<Option<PodUsage>>;
+}
+
+/// Pod usage kept in memory, recorded by whatever samples it
+#[derive(Debug, Default)]
+pub struct MemoryPodMetrics {
+    usage: Mutex<HashMap<(String, String), PodUsage>>,
+}
+
+impl MemoryPodMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the latest usage of the pod `namespace/name`
+    pub fn record(&self, namespace: &str, name: &str, usage: PodUsage) {
+        let mut pods = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        pods.insert((namespace.to_string(), name.to_string()), usage);
+    }
+
+    /// Forget the usage of the pod `namespace/name`
+    pub fn forget(&self, namespace: &str, name: &str) {
+        let mut pods = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        pods.remove(&(namespace.to_string(), name.to_string()));
+    }
+}
+
+#[async_trait]
+impl PodMetrics for MemoryPodMetrics {
+    async fn pod_usage(&self, namespace: &str, name: &str) -> Result<Option<PodUsage>> {
+        let pods = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(pods
+            .get(&(namespace.to_string(), name.to_string()))
+            .copied())
+    }
+}
+
+/// Configuration for the HorizontalPodAutoscaler controller
+#[derive(Debug, Clone)]
+pub struct HorizontalPodAutoscalerControllerConfig {
+    /// Interval between evaluations of every autoscaler against the current
+    /// usage of its pods
+    pub sync_interval: Duration,
+}
+
+impl Default for HorizontalPodAutoscalerControllerConfig {
+    fn default() -> Self {
+        Self {
+            sync_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+/// The Deployment or ReplicaSet an autoscaler scales
+enum ScaleTarget {
+    Deployment(Deployment),
+    ReplicaSet(ReplicaSet),
+}
+
+impl ScaleTarget {
+    fn replicas(&self) -> i32 {
+        let replicas = match self {
+            ScaleTarget::Deployment(d) => d.spec.as_ref().and_then(|s| s.replicas),
+            ScaleTarget::ReplicaSet(r) => r.spec.as_ref().and_then(|s| s.replicas),
+        };
+        replicas.unwrap_or(1)
+    }
+
+    fn selector(&self) -> Option<&LabelSelector> {
+        match self {
+            ScaleTarget::Deployment(d) => d.spec.as_ref().map(|s| &s.selector),
+            ScaleTarget::ReplicaSet(r) => r.spec.as_ref().map(|s| &s.selector),
+        }
+    }
+}
+
+/// Why an evaluation settled on its replicas
+#[derive(Debug, Clone, PartialEq)]
+enum Recommendation {
+    /// Scaling is off: the target was scaled to zero by hand
+    Disabled,
+    /// The target is outside the autoscaler's bounds
+    OutOfBounds(i32),
+    /// Replicas for the measured utilization of each resource, in percent
+    Measured {
+        replicas: i32,
+        utilization: Vec<(String, i32, i64)>,
+    },
+    /// The usage of the pods is not known
+    NoMetrics(String),
+}
+
+/// Scales each HorizontalPodAutoscaler's Deployment or ReplicaSet between
+/// its `minReplicas` and `maxReplicas` so the average utilization of the
+/// pods' CPU or memory requests stays near the target
+///
+/// Usage comes from a [`PodMetrics`] source. Recommendations are stabilized
+/// over the scale-up and scale-down windows of the autoscaler's
+/// `behavior`: a scale-down only goes as low as the highest replicas
+/// recommended within its window, so a brief dip does not remove pods.
+pub struct HorizontalPodAutoscalerController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    metrics: Arc<dyn PodMetrics>,
+    config: HorizontalPodAutoscalerControllerConfig,
+    /// Recent recommendations of each autoscaler, by `namespace/name`
+    recommendations: Mutex<HashMap<String, Vec<(Instant, i32)>>>,
+}
+
+impl HorizontalPodAutoscalerController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        metrics: Arc<dyn PodMetrics>,
+        config: HorizontalPodAutoscalerControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            metrics,
+            config,
+            recommendations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting HorizontalPodAutoscaler controller (sync: {:?})",
+            self.config.sync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut sync_tick = tokio::time::interval(self.config.sync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("HorizontalPodAutoscaler controller shutting down");
+                    return Ok(());
+                }
+                _ = sync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("HorizontalPodAutoscaler sync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) if event.gvk.kind == HORIZONTAL_POD_AUTOSCALER_KIND => {
+                            let namespace = &event.resource_key.namespace;
+                            let name = &event.resource_key.name;
+                            if matches!(event.event_type, WatchEventType::Deleted) {
+                                self.forget(namespace, name);
+                                continue;
+                            }
+                            let Ok(autoscaler) =
+                                serde_json::from_value::<HorizontalPodAutoscaler>(event.object)
+                            else {
+                                continue;
+                            };
+                            if let Err(e) = self.reconcile(&autoscaler).await {
+                                warn!(
+                                    "Failed to reconcile HorizontalPodAutoscaler {}/{}: {}",
+                                    namespace, name, e
+                                );
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            // Every autoscaler is evaluated on each sync anyway
+                            warn!("Missed {} events, resyncing HorizontalPodAutoscalers", n);
+                            if let Err(e) = self.resync().await {
+                                error!("HorizontalPodAutoscaler resync after lag failed: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping HorizontalPodAutoscaler controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Evaluate every autoscaler
+    async fn resync(&self) -> Result<()> {
+        let autoscalers: Vec<HorizontalPodAutoscaler> = list(
+            &self.api_client,
+            "/apis/autoscaling/v2/horizontalpodautoscalers",
+        )
+        .await?;
+        for autoscaler in &autoscalers {
+            if let Err(e) = self.reconcile(autoscaler).await {
+                warn!(
+                    "Failed to reconcile HorizontalPodAutoscaler {}/{}: {}",
+                    autoscaler.metadata.namespace.as_deref().unwrap_or_default(),
+                    autoscaler.metadata.name.as_deref().unwrap_or_default(),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn forget(&self, namespace: &str, name: &str) {
+        let mut recommendations = self
+            .recommendations
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        recommendations.remove(&format!("{}/{}", namespace, name));
+    }
+
+    async fn reconcile(&self, autoscaler: &HorizontalPodAutoscaler) -> Result<()> {
+        if autoscaler.metadata.deletion_timestamp.is_some() {
+            return Ok(());
+        }
+        let namespace = autoscaler.metadata.namespace.as_deref().unwrap_or_default();
+        let name = autoscaler.metadata.name.as_deref().unwrap_or_default();
+        let Some(spec) = autoscaler.spec.as_ref() else {
+            return Ok(());
+        };
+        let target_ref = &spec.scale_target_ref;
+        let conditions = autoscaler
+            .status
+            .as_ref()
+            .and_then(|s| s.conditions.clone())
+            .unwrap_or_default();
+
+        let target = match self
+            .get_target(namespace, &target_ref.kind, &target_ref.name)
+            .await
+        {
+            Ok(target) => target,
+            Err(e) if e.is_not_found() => {
+                let message = format!("{} {} not found", target_ref.kind, target_ref.name);
+                let status = HorizontalPodAutoscalerStatus {
+                    conditions: Some(vec![condition(
+                        &conditions,
+                        "AbleToScale",
+                        ("False", "FailedGetScale", &message),
+                    )]),
+                    ..autoscaler.status.clone().unwrap_or_default()
+                };
+                return self.update_status(autoscaler, status).await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let current = target.replicas();
+        let recommendation = self.recommend(autoscaler, &target, current).await?;
+        let (min, max) = autoscaler_bounds(autoscaler);
+        let mut desired = match &recommendation {
+            Recommendation::Disabled => 0,
+            Recommendation::OutOfBounds(replicas) => *replicas,
+            Recommendation::NoMetrics(_) => current,
+            Recommendation::Measured { replicas, .. } => {
+                let key = format!("{}/{}", namespace, name);
+                let (up, down) = stabilization_windows(autoscaler);
+                let mut recommendations = self
+                    .recommendations
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                let history = recommendations.entry(key).or_default();
+                stabilize(
+                    current,
+                    *replicas,
+                    history,
+                    Instant::now(),
+                    Duration::from_secs(up.max(0) as u64),
+                    Duration::from_secs(down.max(0) as u64),
+                )
+            }
+        };
+        let limited = desired != 0 && (desired < min || desired > max);
+        if desired != 0 {
+            desired = desired.clamp(min, max);
+        }
+
+        let mut last_scale_time = autoscaler
+            .status
+            .as_ref()
+            .and_then(|s| s.last_scale_time.clone());
+        if desired != current {
+            info!(
+                "Scaling {} {}/{} from {} to {} replicas for HorizontalPodAutoscaler {}",
+                target_ref.kind, namespace, target_ref.name, current, desired, name
+            );
+            match self.scale(namespace, target, desired).await {
+                Ok(()) => last_scale_time = Some(Time(Utc::now())),
+                // Changed since it was read; the next sync scales it
+                Err(e) if e.is_conflict() => {
+                    debug!(
+                        "{} {}/{} changed while scaling: {}",
+                        target_ref.kind, namespace, target_ref.name, e
+                    );
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let status = autoscaler_status(
+            autoscaler,
+            &conditions,
+            &recommendation,
+            (current, desired),
+            limited,
+            last_scale_time,
+        );
+        self.update_status(autoscaler, status).await
+    }
+
+    async fn get_target(&self, namespace: &str, kind: &str, name: &str) -> Result<ScaleTarget> {
+        let (plural, parse): (
+            &str,
+            fn(serde_json::Value) -> serde_json::Result<ScaleTarget>,
+        ) = match kind {
+            DEPLOYMENT_KIND => ("deployments", |v| {
+                serde_json::from_value(v).map(ScaleTarget::Deployment)
+            }),
+            REPLICA_SET_KIND => ("replicasets", |v| {
+                serde_json::from_value(v).map(ScaleTarget::ReplicaSet)
+            }),
+            other => {
+                return Err(RuntimeError::invalid_config(
+                    format!("Cannot scale a {}", other),
+                    "Point scaleTargetRef at a Deployment or ReplicaSet",
+                ))
+            }
+        };
+        let body = self
+            .api_client
+            .get_json(&format!(
+                "/apis/apps/v1/namespaces/{}/{}/{}",
+                namespace, plural, name
+            ))
+            .await?;
+        parse(body).map_err(|e| {
+            RuntimeError::internal_error(format!("Failed to parse {} {}: {}", kind, name, e))
+        })
+    }
+
+    /// How many replicas `target`, at `current` replicas, should have
+    async fn recommend(
+        &self,
+        autoscaler: &HorizontalPodAutoscaler,
+        target: &ScaleTarget,
+        current: i32,
+    ) -> Result<Recommendation> {
+        let (min, max) = autoscaler_bounds(autoscaler);
+        if current == 0 {
+            return Ok(Recommendation::Disabled);
+        }
+        if current > max {
+            return Ok(Recommendation::OutOfBounds(max));
+        }
+        if current < min {
+            return Ok(Recommendation::OutOfBounds(min));
+        }
+
+        let namespace = autoscaler.metadata.namespace.as_deref().unwrap_or_default();
+        let Some(selector) = target.selector() else {
+            return Ok(Recommendation::NoMetrics(
+                "the scale target has no selector".to_string(),
+            ));
+        };
+        let pods: Vec<Pod> = list(
+            &self.api_client,
+            &format!("/api/v1/namespaces/{}/pods", namespace),
+        )
+        .await?;
+        let mut samples = Vec::new();
+        for pod in pods.iter().filter(|p| {
+            selector_matches(selector, p.metadata.labels.as_ref())
+                && p.metadata.deletion_timestamp.is_none()
+                && p.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running")
+        }) {
+            let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+            if let Some(usage) = self.metrics.pod_usage(namespace, pod_name).await? {
+                samples.push((pod, usage));
+            }
+        }
+
+        let mut replicas = 0;
+        let mut utilization = Vec::new();
+        for (resource, target) in utilization_targets(autoscaler) {
+            match average_utilization(&resource, &samples) {
+                Ok((percent, average)) => {
+                    replicas = replicas.max(replicas_for_utilization(current, percent, target));
+                    utilization.push((resource, percent.round() as i32, average));
+                }
+                Err(message) => return Ok(Recommendation::NoMetrics(message)),
+            }
+        }
+        Ok(Recommendation::Measured {
+            replicas,
+            utilization,
+        })
+    }
+
+    async fn scale(&self, namespace: &str, target: ScaleTarget, replicas: i32) -> Result<()> {
+        match target {
+            ScaleTarget::Deployment(mut deployment) => {
+                let name = deployment.metadata.name.clone().unwrap_or_default();
+                deployment
+                    .spec
+                    .get_or_insert_with(Default::default)
+                    .replicas = Some(replicas);
+                self.api_client
+                    .replace_deployment(namespace, &name, &deployment)
+                    .await?;
+            }
+            ScaleTarget::ReplicaSet(mut replica_set) => {
+                let name = replica_set.metadata.name.clone().unwrap_or_default();
+                replica_set
+                    .spec
+                    .get_or_insert_with(Default::default)
+                    .replicas = Some(replicas);
+                self.api_client
+                    .replace_replica_set(namespace, &name, &replica_set)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn update_status(
+        &self,
+        autoscaler: &HorizontalPodAutoscaler,
+        status: HorizontalPodAutoscalerStatus,
+    ) -> Result<()> {
+        if autoscaler.status.as_ref() == Some(&status) {
+            return Ok(());
+        }
+        let namespace = autoscaler.metadata.namespace.as_deref().unwrap_or_default();
+        let name = autoscaler.metadata.name.as_deref().unwrap_or_default();
+        let mut updated = autoscaler.clone();
+        updated.status = Some(status);
+        self.api_client
+            .update_horizontal_pod_autoscaler_status(namespace, name, &updated)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Average utilization of `resource` over `samples`, in percent of the
+/// pods' requests, and the average usage per pod
+fn average_utilization(
+    resource: &str,
+    samples: &[(&Pod, PodUsage)],
+) -> std::result::Result<(f64, i64), String> {
+    if samples.is_empty() {
+        return Err(format!("no {} usage is known for the pods", resource));
+    }
+    let mut usage = 0;
+    let mut requests = 0;
+    for (pod, sample) in samples {
+        let requested = pod
+            .spec
+            .as_ref()
+            .map(ResourceQuantities::pod_requests)
+            .unwrap_or_default();
+        let requested = match resource {
+            "cpu" => requested.cpu_millicores,
+            _ => requested.memory_bytes,
+        };
+        if requested <= 0 {
+            return Err(format!(
+                "pod {} has no {} request",
+                pod.metadata.name.as_deref().unwrap_or_default(),
+                resource
+            ));
+        }
+        usage += sample.of(resource).unwrap_or_default();
+        requests += requested;
+    }
+    Ok((
+        usage as f64 * 100.0 / requests as f64,
+        usage / samples.len() as i64,
+    ))
+}
+
+/// `replicas` recommended now, stabilized against the recommendations
+/// kept in `history`
+///
+/// A scale-up goes only as high as the fewest replicas recommended within
+/// the `up` window, a scale-down only as low as the most recommended within
+/// the `down` window. `history` is pruned to the longer window.
+fn stabilize(
+    current: i32,
+    replicas: i32,
+    history: &mut Vec<(Instant, i32)>,
+    now: Instant,
+    up: Duration,
+    down: Duration,
+) -> i32 {
+    let keep = up.max(down);
+    history.retain(|(at, _)| now.duration_since(*at) <= keep);
+    history.push((now, replicas));
+
+    let within = |window: Duration| {
+        history
+            .iter()
+            .filter(move |(at, _)| now.duration_since(*at) <= window)
+            .map(|(_, replicas)| *replicas)
+    };
+    let up_limit = within(up).min().unwrap_or(replicas);
+    let down_limit = within(down).max().unwrap_or(replicas);
+    current.max(up_limit).min(down_limit)
+}
+
+/// Status of `autoscaler` after an evaluation recommending
+/// `recommendation` moved its target from `current` to `desired` replicas
+fn autoscaler_status(
+    autoscaler: &HorizontalPodAutoscaler,
+    conditions: &[HorizontalPodAutoscalerCondition],
+    recommendation: &Recommendation,
+    (current, desired): (i32, i32),
+    limited: bool,
+    last_scale_time: Option<Time>,
+) -> HorizontalPodAutoscalerStatus {
+    let (min, max) = autoscaler_bounds(autoscaler);
+    let active = match recommendation {
+        Recommendation::Disabled => (
+            "False",
+            "ScalingDisabled",
+            "scaling is disabled since the replica count of the target is zero".to_string(),
+        ),
+        Recommendation::NoMetrics(message) => (
+            "False",
+            "FailedGetResourceMetric",
+            format!("the usage of the pods is unknown: {}", message),
+        ),
+        Recommendation::OutOfBounds(_) | Recommendation::Measured { .. } => (
+            "True",
+            "ValidMetricFound",
+            "the replica count was computed from the pods' resource utilization".to_string(),
+        ),
+    };
+    let limit = match recommendation {
+        Recommendation::OutOfBounds(_) if desired == max => (
+            "True",
+            "TooManyReplicas",
+            format!("the desired replica count is more than the maximum {}", max),
+        ),
+        Recommendation::OutOfBounds(_) => (
+            "True",
+            "TooFewReplicas",
+            format!("the desired replica count is less than the minimum {}", min),
+        ),
+        _ if limited => (
+            "True",
+            "ScalingLimited",
+            format!("the desired replica count is limited to {}..{}", min, max),
+        ),
+        _ => (
+            "False",
+            "DesiredWithinRange",
+            "the desired count is within the acceptable range".to_string(),
+        ),
+    };
+
+    let current_metrics = match recommendation {
+        Recommendation::Measured { utilization, .. } => Some(
+            utilization
+                .iter()
+                .map(|(resource, percent, average)| MetricStatus {
+                    type_: "Resource".to_string(),
+                    resource: Some(ResourceMetricStatus {
+                        name: resource.clone(),
+                        current: MetricValueStatus {
+                            average_utilization: Some(*percent),
+                            average_value: Some(Quantity(match resource.as_str() {
+                                "cpu" => format!("{}m", average),
+                                _ => average.to_string(),
+                            })),
+                            value: None,
+                        },
+                    }),
+                    ..Default::default()
+                })
+                .collect(),
+        ),
+        _ => autoscaler
+            .status
+            .as_ref()
+            .and_then(|s| s.current_metrics.clone()),
+    };
+
+    HorizontalPodAutoscalerStatus {
+        conditions: Some(vec![
+            condition(
+                conditions,
+                "AbleToScale",
+                ("True", "ReadyForNewScale", "the target can be scaled"),
+            ),
+            condition(conditions, "ScalingActive", (active.0, active.1, &active.2)),
+            condition(conditions, "ScalingLimited", (limit.0, limit.1, &limit.2)),
+        ]),
+        current_metrics,
+        current_replicas: Some(current),
+        desired_replicas: desired,
+        last_scale_time,
+        observed_generation: autoscaler.metadata.generation,
+    }
+}
+
+fn condition(
+    existing: &[HorizontalPodAutoscalerCondition],
+    type_: &str,
+    (status, reason, message): (&str, &str, &str),
+) -> HorizontalPodAutoscalerCondition {
+    let last_transition_time = match existing.iter().find(|c| c.type_ == type_) {
+        Some(c) if c.status == status => c.last_transition_time.clone(),
+        _ => Some(Time(Utc::now())),
+    };
+    HorizontalPodAutoscalerCondition {
+        type_: type_.to_string(),
+        status: status.to_string(),
+        reason: Some(reason.to_string()),
+        message: Some(message.to_string()),
+        last_transition_time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{Container, PodSpec, ResourceRequirements};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use std::collections::BTreeMap;
+
+    fn pod(name: &str, cpu_request: Option<&str>) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "web".to_string(),
+                    resources: cpu_request.map(|cpu| ResourceRequirements {
+                        requests: Some(BTreeMap::from([(
+                            "cpu".to_string(),
+                            Quantity(cpu.to_string()),
+                        )])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            status: None,
+        }
+    }
+
+    fn usage(cpu_millicores: i64) -> PodUsage {
+        PodUsage {
+            cpu_millicores,
+            memory_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_average_utilization() {
+        let a = pod("a", Some("500m"));
+        let b = pod("b", Some("500m"));
+        let (percent, average) =
+            average_utilization("cpu", &[(&a, usage(600)), (&b, usage(400))]).unwrap();
+        assert_eq!(percent, 100.0);
+        assert_eq!(average, 500);
+
+        assert!(average_utilization("cpu", &[]).is_err());
+        let unrequested = pod("c", None);
+        assert!(average_utilization("cpu", &[(&unrequested, usage(100))]).is_err());
+    }
+
+    #[test]
+    fn test_stabilize() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let up = Duration::ZERO;
+        let down = Duration::from_secs(300);
+        let mut history = Vec::new();
+
+        // Scale-ups apply at once
+        assert_eq!(stabilize(2, 6, &mut history, at(0), up, down), 6);
+        // A dip within the window keeps the highest recent recommendation
+        assert_eq!(stabilize(6, 3, &mut history, at(60), up, down), 6);
+        assert_eq!(stabilize(6, 3, &mut history, at(200), up, down), 6);
+        // Once the peak leaves the window, the scale-down goes through
+        assert_eq!(stabilize(6, 3, &mut history, at(400), up, down), 3);
+        assert!(history.iter().all(|(t, _)| *t >= at(100)));
+    }
+
+    #[test]
+    fn test_status_reports_limits_and_missing_metrics() {
+        let autoscaler: HorizontalPodAutoscaler = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "web", "namespace": "default", "generation": 2},
+            "spec": {
+                "scaleTargetRef": {"apiVersion": "apps/v1", "kind": "Deployment", "name": "web"},
+                "minReplicas": 2,
+                "maxReplicas": 4
+            }
+        }))
+        .unwrap();
+
+        let status = autoscaler_status(
+            &autoscaler,
+            &[],
+            &Recommendation::OutOfBounds(4),
+            (6, 4),
+            false,
+            None,
+        );
+        assert_eq!(status.current_replicas, Some(6));
+        assert_eq!(status.desired_replicas, 4);
+        assert_eq!(status.observed_generation, Some(2));
+        let conditions = status.conditions.unwrap();
+        assert_eq!(conditions[2].reason.as_deref(), Some("TooManyReplicas"));
+
+        let status = autoscaler_status(
+            &autoscaler,
+            &conditions,
+            &Recommendation::NoMetrics("no cpu usage is known for the pods".to_string()),
+            (3, 3),
+            false,
+            None,
+        );
+        let active = &status.conditions.unwrap()[1];
+        assert_eq!(active.status, "False");
+        assert_eq!(active.reason.as_deref(), Some("FailedGetResourceMetric"));
+
+        let status = autoscaler_status(
+            &autoscaler,
+            &[],
+            &Recommendation::Measured {
+                replicas: 3,
+                utilization: vec![("cpu".to_string(), 90, 450)],
+            },
+            (3, 3),
+            false,
+            None,
+        );
+        let metric = &status.current_metrics.unwrap()[0];
+        let current = &metric.resource.as_ref().unwrap().current;
+        assert_eq!(current.average_utilization, Some(90));
+        assert_eq!(current.average_value, Some(Quantity("450m".to_string())));
+    }
+}
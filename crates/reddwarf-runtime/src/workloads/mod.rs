@@ -0,0 +1,71 @@
+//! Built-in workload controllers: Deployments roll out ReplicaSets, which
+//! keep a number of pods running, DaemonSets run a pod on every node,
+//! HorizontalPodAutoscalers scale Deployments and ReplicaSets with the
+//! usage of their pods, PodDisruptionBudgets report how many of their
+//! pods may be disrupted, and finished pods are deleted once their TTL runs
+//! out
+pub mod daemon_set;
+pub mod deployment;
+pub mod disruption_budget;
+pub mod horizontal_pod_autoscaler;
+pub mod replica_set;
+pub mod ttl_after_finished;
+
+pub use daemon_set::{DaemonSetController, DaemonSetControllerConfig};
+pub use deployment::{DeploymentController, DeploymentControllerConfig};
+pub use disruption_budget::{DisruptionBudgetController, DisruptionBudgetControllerConfig};
+pub use horizontal_pod_autoscaler::{
+    HorizontalPodAutoscalerController, HorizontalPodAutoscalerControllerConfig, MemoryPodMetrics,
+    PodMetrics, PodUsage,
+};
+pub use replica_set::{ReplicaSetController, ReplicaSetControllerConfig};
+pub use ttl_after_finished::{TtlAfterFinishedController, TtlAfterFinishedControllerConfig};
+
+use crate::api_client::ApiClient;
+use crate::error::Result;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use serde::de::DeserializeOwned;
+use tracing::warn;
+
+/// The reference naming `owner`, of `kind`, as the controller of an object
+pub(crate) fn controller_reference(
+    api_version: &str,
+    kind: &str,
+    owner: &ObjectMeta,
+) -> OwnerReference {
+    OwnerReference {
+        api_version: api_version.to_string(),
+        kind: kind.to_string(),
+        name: owner.name.clone().unwrap_or_default(),
+        uid: owner.uid.clone().unwrap_or_default(),
+        controller: Some(true),
+        block_owner_deletion: Some(true),
+    }
+}
+
+/// The controller of `kind` that owns the object of `metadata`, if any
+fn controller_of<'a>(metadata: &'a ObjectMeta, kind: &str) -> Option<&'a OwnerReference> {
+    metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|r| r.controller == Some(true) && r.kind == kind)
+}
+
+/// Objects listed at `path`, skipping any that fail to parse
+pub(crate) async fn list<T: DeserializeOwned>(api_client: &ApiClient, path: &str) -> Result<Vec<T>> {
+    let body = api_client.get_json(path).await?;
+    Ok(body["items"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|item| match serde_json::from_value(item) {
+            Ok(object) => Some(object),
+            Err(e) => {
+                warn!("Failed to parse object listed at {}: {}", path, e);
+                None
+            }
+        })
+        .collect())
+}
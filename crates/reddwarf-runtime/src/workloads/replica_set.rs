@@ -0,0 +1,461 @@
+use super::{controller_of, controller_reference, list};
+use crate::api_client::ApiClient;
+use crate::error::{Result, RuntimeError};
+use crate::pod_conditions::is_pod_ready;
+use k8s_openapi::api::apps::v1::{ReplicaSet, ReplicaSetStatus};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use reddwarf_core::resources::{APPS_API_VERSION, REPLICA_SET_KIND};
+use reddwarf_core::{ErrorClass, EventBus, GroupVersionKind, WatchEventType};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Name the ReplicaSet controller subscribes to the event bus under
+const SUBSCRIBER: &str = "replicaset-controller";
+
+/// Configuration for the ReplicaSet controller
+#[derive(Debug, Clone)]
+pub struct ReplicaSetControllerConfig {
+    /// Interval between full resyncs of every ReplicaSet and its pods
+    /// (safety net for missed events and orphaned pods)
+    pub resync_interval: Duration,
+}
+
+impl Default for ReplicaSetControllerConfig {
+    fn default() -> Self {
+        Self {
+            resync_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How a ReplicaSet's pods must change to reach its replica count
+#[derive(Debug, PartialEq)]
+enum ScaleChange<'a> {
+    Create(usize),
+    Delete(Vec<&'a Pod>),
+}
+
+/// Keeps the number of running pods of every ReplicaSet at its
+/// `spec.replicas`, creating pods from its template and deleting the
+/// surplus, and reports the counts in its status
+///
+/// A ReplicaSet owns the pods whose controller reference carries its UID;
+/// pods whose ReplicaSet is gone are deleted on resync.
+pub struct ReplicaSetController {
+    api_client: Arc<ApiClient>,
+    event_bus: Arc<dyn EventBus>,
+    config: ReplicaSetControllerConfig,
+}
+
+impl ReplicaSetController {
+    pub fn new(
+        api_client: Arc<ApiClient>,
+        event_bus: Arc<dyn EventBus>,
+        config: ReplicaSetControllerConfig,
+    ) -> Self {
+        Self {
+            api_client,
+            event_bus,
+            config,
+        }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting ReplicaSet controller (resync: {:?})",
+            self.config.resync_interval
+        );
+
+        let mut rx = self.event_bus.subscribe_as(SUBSCRIBER);
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("ReplicaSet controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.resync().await {
+                        error!("ReplicaSet resync failed: {}", e);
+                    }
+                }
+                result = rx.recv() => {
+                    match result {
+                        Ok(event) => {
+                            let namespace = event.resource_key.namespace.clone();
+                            let name = match (event.gvk.kind.as_str(), &event.event_type) {
+                                (REPLICA_SET_KIND, WatchEventType::Deleted) => continue,
+                                (REPLICA_SET_KIND, _) => event.resource_key.name.clone(),
+                                ("Pod", _) => {
+                                    let Ok(meta) = serde_json::from_value::<ObjectMeta>(
+                                        event.object["metadata"].clone(),
+                                    ) else {
+                                        continue;
+                                    };
+                                    match controller_of(&meta, REPLICA_SET_KIND) {
+                                        Some(owner) => owner.name.clone(),
+                                        None => continue,
+                                    }
+                                }
+                                _ => continue,
+                            };
+                            if let Err(e) = self.reconcile_named(&namespace, &name).await {
+                                warn!(
+                                    "Failed to reconcile ReplicaSet {}/{}: {}",
+                                    namespace, name, e
+                                );
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            let gvk = GroupVersionKind::from_api_version_kind(APPS_API_VERSION, REPLICA_SET_KIND);
+                            match self.event_bus.resync(SUBSCRIBER, &gvk) {
+                                Ok(count) => warn!("Missed {} events, resyncing {} ReplicaSets", n, count),
+                                Err(e) => {
+                                    warn!("Missed {} events and cannot resync ({}), doing full ReplicaSet resync", n, e);
+                                    if let Err(e) = self.resync().await {
+                                        error!("ReplicaSet resync after lag failed: {}", e);
+                                    }
+                                }
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("Event bus closed, stopping ReplicaSet controller");
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reconcile every ReplicaSet, and delete pods whose ReplicaSet is gone
+    async fn resync(&self) -> Result<()> {
+        debug!("Resyncing ReplicaSets");
+
+        // Pods first: a pod listed before its ReplicaSet was created would
+        // look orphaned
+        let pods: Vec<Pod> = list(&self.api_client, "/api/v1/pods").await?;
+        let replica_sets: Vec<ReplicaSet> =
+            list(&self.api_client, "/apis/apps/v1/replicasets").await?;
+
+        let mut owned: HashMap<&str, Vec<&Pod>> = HashMap::new();
+        for pod in &pods {
+            if let Some(owner) = controller_of(&pod.metadata, REPLICA_SET_KIND) {
+                owned.entry(owner.uid.as_str()).or_default().push(pod);
+            }
+        }
+
+        let mut live = HashSet::new();
+        for replica_set in &replica_sets {
+            let uid = replica_set.metadata.uid.as_deref().unwrap_or_default();
+            live.insert(uid);
+            let pods = owned.get(uid).map(Vec::as_slice).unwrap_or_default();
+            if let Err(e) = self.reconcile(replica_set, pods).await {
+                warn!(
+                    "Failed to reconcile ReplicaSet {}/{}: {}",
+                    replica_set
+                        .metadata
+                        .namespace
+                        .as_deref()
+                        .unwrap_or_default(),
+                    replica_set.metadata.name.as_deref().unwrap_or_default(),
+                    e
+                );
+            }
+        }
+
+        for (uid, pods) in owned {
+            if live.contains(uid) {
+                continue;
+            }
+            for pod in pods
+                .into_iter()
+                .filter(|p| p.metadata.deletion_timestamp.is_none())
+            {
+                let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+                let name = pod.metadata.name.as_deref().unwrap_or_default();
+                info!(
+                    "Deleting pod {}/{} of a deleted ReplicaSet",
+                    namespace, name
+                );
+                if let Err(e) = self.api_client.delete_pod(namespace, name).await {
+                    warn!(
+                        "Failed to delete orphaned pod {}/{}: {}",
+                        namespace, name, e
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile the ReplicaSet `namespace/name` against its pods
+    async fn reconcile_named(&self, namespace: &str, name: &str) -> Result<()> {
+        let replica_set: ReplicaSet = match self
+            .api_client
+            .get_json(&format!(
+                "/apis/apps/v1/namespaces/{}/replicasets/{}",
+                namespace, name
+            ))
+            .await
+        {
+            Ok(body) => serde_json::from_value(body).map_err(|e| {
+                RuntimeError::internal_error(format!("Failed to parse ReplicaSet: {}", e))
+            })?,
+            // Deleted; the next resync cleans up
+            Err(e) if e.is_not_found() => {
+                debug!("ReplicaSet {}/{} not found: {}", namespace, name, e);
+                return Ok(());
+            }
+            // Reported by the caller, and the next resync catches up
+            Err(e) => return Err(e),
+        };
+        let uid = replica_set.metadata.uid.as_deref();
+        let pods: Vec<Pod> = list(
+            &self.api_client,
+            &format!("/api/v1/namespaces/{}/pods", namespace),
+        )
+        .await?;
+        let owned: Vec<&Pod> = pods
+            .iter()
+            .filter(|p| controller_of(&p.metadata, REPLICA_SET_KIND).map(|o| o.uid.as_str()) == uid)
+            .collect();
+        self.reconcile(&replica_set, &owned).await
+    }
+
+    async fn reconcile(&self, replica_set: &ReplicaSet, pods: &[&Pod]) -> Result<()> {
+        if replica_set.metadata.deletion_timestamp.is_some() {
+            return Ok(());
+        }
+        let namespace = replica_set
+            .metadata
+            .namespace
+            .as_deref()
+            .unwrap_or_default();
+        let name = replica_set.metadata.name.as_deref().unwrap_or_default();
+        let replicas = replica_set
+            .spec
+            .as_ref()
+            .and_then(|s| s.replicas)
+            .unwrap_or(1)
+            .max(0) as usize;
+        let active: Vec<&Pod> = pods.iter().copied().filter(|p| is_active(p)).collect();
+
+        match scale_change(replicas, &active) {
+            Some(ScaleChange::Create(count)) => {
+                info!(
+                    "Creating {} pod(s) for ReplicaSet {}/{}",
+                    count, namespace, name
+                );
+                for _ in 0..count {
+                    let pod = pod_from_template(replica_set);
+                    self.api_client.create_pod(namespace, &pod).await?;
+                }
+            }
+            Some(ScaleChange::Delete(surplus)) => {
+                info!(
+                    "Removing {} pod(s) of ReplicaSet {}/{}",
+                    surplus.len(),
+                    namespace,
+                    name
+                );
+                // Scaling down is not a disruption, so surplus pods are
+                // deleted rather than evicted past their disruption budgets
+                for pod in surplus {
+                    let pod_name = pod.metadata.name.as_deref().unwrap_or_default();
+                    self.api_client.delete_pod(namespace, pod_name).await?;
+                }
+            }
+            None => {}
+        }
+
+        let status = replica_set_status(&active);
+        if replica_set.status.as_ref() != Some(&status) {
+            let mut updated = replica_set.clone();
+            updated.status = Some(status);
+            self.api_client
+                .update_replica_set_status(namespace, name, &updated)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `pod` counts towards its ReplicaSet's replicas: not terminating
+/// and not finished
+fn is_active(pod: &Pod) -> bool {
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    pod.metadata.deletion_timestamp.is_none() && !matches!(phase, Some("Succeeded" | "Failed"))
+}
+
+/// What to create or remove so `active` pods become `replicas`
+///
+/// Surplus pods are picked cheapest to lose first: unscheduled, then
+/// pending, then not ready, then the most recently created.
+fn scale_change<'a>(replicas: usize, active: &[&'a Pod]) -> Option<ScaleChange<'a>> {
+    if active.len() < replicas {
+        return Some(ScaleChange::Create(replicas - active.len()));
+    }
+    if active.len() == replicas {
+        return None;
+    }
+    let mut candidates = active.to_vec();
+    candidates.sort_by_key(|pod| {
+        let scheduled = pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.node_name.as_ref())
+            .is_some();
+        let running = pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running");
+        let created = pod.metadata.creation_timestamp.as_ref().map(|t| t.0);
+        (
+            scheduled,
+            running,
+            is_pod_ready(pod),
+            std::cmp::Reverse(created),
+        )
+    });
+    candidates.truncate(active.len() - replicas);
+    Some(ScaleChange::Delete(candidates))
+}
+
+/// A new pod of `replica_set`, from its template, with a generated name
+fn pod_from_template(replica_set: &ReplicaSet) -> Pod {
+    let name = replica_set.metadata.name.as_deref().unwrap_or_default();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let template = replica_set
+        .spec
+        .as_ref()
+        .and_then(|s| s.template.clone())
+        .unwrap_or_default();
+    let template_meta = template.metadata.unwrap_or_default();
+
+    Pod {
+        metadata: ObjectMeta {
+            name: Some(format!("{}-{}", name, &suffix[..5])),
+            namespace: replica_set.metadata.namespace.clone(),
+            labels: template_meta.labels,
+            annotations: template_meta.annotations,
+            owner_references: Some(vec![controller_reference(
+                APPS_API_VERSION,
+                REPLICA_SET_KIND,
+                &replica_set.metadata,
+            )]),
+            ..Default::default()
+        },
+        spec: template.spec,
+        status: None,
+    }
+}
+
+/// Pod counts of a ReplicaSet with the `active` pods
+fn replica_set_status(active: &[&Pod]) -> ReplicaSetStatus {
+    let ready = active.iter().filter(|p| is_pod_ready(p)).count() as i32;
+    ReplicaSetStatus {
+        replicas: active.len() as i32,
+        fully_labeled_replicas: Some(active.len() as i32),
+        ready_replicas: Some(ready),
+        available_replicas: Some(ready),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::ReplicaSetSpec;
+    use k8s_openapi::api::core::v1::{PodCondition, PodSpec, PodStatus, PodTemplateSpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+    use std::collections::BTreeMap;
+
+    fn pod(name: &str, node: Option<&str>, ready: bool, age_secs: i64) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                creation_timestamp: Some(Time(
+                    chrono::Utc::now() - chrono::Duration::seconds(age_secs),
+                )),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                node_name: node.map(String::from),
+                ..Default::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some(if node.is_some() { "Running" } else { "Pending" }.to_string()),
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: if ready { "True" } else { "False" }.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_scale_change() {
+        let old_ready = pod("old-ready", Some("node1"), true, 600);
+        let new_ready = pod("new-ready", Some("node1"), true, 10);
+        let unready = pod("unready", Some("node1"), false, 300);
+        let pending = pod("pending", None, false, 5);
+        let active = [&old_ready, &new_ready, &unready, &pending];
+
+        assert_eq!(scale_change(6, &active), Some(ScaleChange::Create(2)));
+        assert_eq!(scale_change(4, &active), None);
+
+        // Unscheduled first, then unready, then the newest ready pod
+        let Some(ScaleChange::Delete(surplus)) = scale_change(1, &active) else {
+            panic!("expected a scale-down");
+        };
+        let names: Vec<_> = surplus
+            .iter()
+            .map(|p| p.metadata.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["pending", "unready", "new-ready"]);
+
+        let status = replica_set_status(&active);
+        assert_eq!(status.replicas, 4);
+        assert_eq!(status.ready_replicas, Some(2));
+
+        // New pods carry the template and point back at their ReplicaSet
+        let labels = BTreeMap::from([("app".to_string(), "web".to_string())]);
+        let replica_set = ReplicaSet {
+            metadata: ObjectMeta {
+                name: Some("web-abc".to_string()),
+                namespace: Some("default".to_string()),
+                uid: Some("uid-1".to_string()),
+                ..Default::default()
+            },
+            spec: Some(ReplicaSetSpec {
+                template: Some(PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels.clone()),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec::default()),
+                }),
+                ..Default::default()
+            }),
+            status: None,
+        };
+        let created = pod_from_template(&replica_set);
+        assert!(created
+            .metadata
+            .name
+            .as_ref()
+            .unwrap()
+            .starts_with("web-abc-"));
+        assert_eq!(created.metadata.labels, Some(labels));
+        let owner = controller_of(&created.metadata, REPLICA_SET_KIND).unwrap();
+        assert_eq!(owner.uid, "uid-1");
+    }
+}
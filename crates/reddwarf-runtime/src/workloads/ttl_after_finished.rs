@@ -0,0 +1,243 @@
+use super::list;
+use crate::api_client::ApiClient;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use reddwarf_core::ErrorClass;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Phases of pods that have finished and are kept only for inspection
+const FINISHED_PHASES: &[&str] = &["Succeeded", "Failed"];
+
+/// Configuration for the TTL-after-finished controller
+#[derive(Debug, Clone)]
+pub struct TtlAfterFinishedControllerConfig {
+    /// How long a finished pod is kept before it is deleted
+    pub ttl: Duration,
+    /// Interval between sweeps for expired pods
+    pub resync_interval: Duration,
+}
+
+impl Default for TtlAfterFinishedControllerConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(3600),
+            resync_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Deletes pods that have Succeeded or Failed once they have been finished
+/// for longer than the TTL, so dead pods do not pile up in storage and
+/// lists on long-running clusters
+///
+/// Jobs are not served yet; once they are, finished Jobs expire the same
+/// way.
+pub struct TtlAfterFinishedController {
+    api_client: Arc<ApiClient>,
+    config: TtlAfterFinishedControllerConfig,
+}
+
+impl TtlAfterFinishedController {
+    pub fn new(api_client: Arc<ApiClient>, config: TtlAfterFinishedControllerConfig) -> Self {
+        Self { api_client, config }
+    }
+
+    /// Run the controller loop
+    pub async fn run(&self, token: CancellationToken) -> Result<()> {
+        info!(
+            "Starting TTL-after-finished controller (ttl: {:?}, resync: {:?})",
+            self.config.ttl, self.config.resync_interval
+        );
+
+        let mut resync_tick = tokio::time::interval(self.config.resync_interval);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    info!("TTL-after-finished controller shutting down");
+                    return Ok(());
+                }
+                _ = resync_tick.tick() => {
+                    if let Err(e) = self.sweep().await {
+                        error!("TTL-after-finished sweep failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Delete every finished pod whose TTL has run out
+    async fn sweep(&self) -> Result<()> {
+        let now = Utc::now();
+        for phase in FINISHED_PHASES {
+            let path = format!("/api/v1/pods?fieldSelector=status.phase={}", phase);
+            let pods: Vec<Pod> = list(&self.api_client, &path).await?;
+            for pod in pods.iter().filter(|p| expired(p, self.config.ttl, now)) {
+                let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+                let name = pod.metadata.name.as_deref().unwrap_or_default();
+                info!(
+                    "Deleting pod {}/{}: {} for longer than {:?}",
+                    namespace, name, phase, self.config.ttl
+                );
+                match self.api_client.delete_pod(namespace, name).await {
+                    Ok(()) => {}
+                    // Deleted since it was listed
+                    Err(e) if e.is_not_found() => {
+                        debug!("Pod {}/{} is already gone", namespace, name);
+                    }
+                    Err(e) => warn!("Failed to delete pod {}/{}: {}", namespace, name, e),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// When `pod` finished: when its last container terminated, or failing
+/// that when its conditions last changed or it was created
+fn finished_at(pod: &Pod) -> Option<DateTime<Utc>> {
+    let status = pod.status.as_ref();
+    let terminated = status
+        .and_then(|s| s.container_statuses.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.state.as_ref()?.terminated.as_ref()?.finished_at.as_ref())
+        .map(|t| t.0)
+        .max();
+    let transitioned = || {
+        status
+            .and_then(|s| s.conditions.as_ref())
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c.last_transition_time.as_ref())
+            .map(|t| t.0)
+            .max()
+    };
+    terminated
+        .or_else(transitioned)
+        .or_else(|| pod.metadata.creation_timestamp.as_ref().map(|t| t.0))
+}
+
+/// Whether `pod` finished longer than `ttl` before `now` and is not already
+/// being deleted
+fn expired(pod: &Pod, ttl: Duration, now: DateTime<Utc>) -> bool {
+    let finished = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.phase.as_deref())
+        .is_some_and(|phase| FINISHED_PHASES.contains(&phase));
+    if !finished || pod.metadata.deletion_timestamp.is_some() {
+        return false;
+    }
+    let Ok(ttl) = chrono::Duration::from_std(ttl) else {
+        return false;
+    };
+    finished_at(pod).is_some_and(|at| at + ttl <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_api::MockApiServer;
+
+    fn pod(phase: &str, status: serde_json::Value) -> Pod {
+        let mut status = status;
+        status["phase"] = serde_json::json!(phase);
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "job",
+                "namespace": "default",
+                "creationTimestamp": "2026-01-01T00:00:00Z"
+            },
+            "status": status
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_finished_pods_expire_after_ttl() {
+        let ttl = Duration::from_secs(3600);
+        let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+
+        let succeeded = pod(
+            "Succeeded",
+            serde_json::json!({
+                "conditions": [{"type": "Ready", "status": "False", "lastTransitionTime": "2026-01-01T00:05:00Z"}],
+                "containerStatuses": [
+                    {"name": "a", "ready": false, "restartCount": 0, "image": "a", "imageID": "",
+                     "state": {"terminated": {"exitCode": 0, "finishedAt": "2026-01-01T00:10:00Z"}}},
+                    {"name": "b", "ready": false, "restartCount": 0, "image": "b", "imageID": "",
+                     "state": {"terminated": {"exitCode": 0, "finishedAt": "2026-01-01T00:20:00Z"}}}
+                ]
+            }),
+        );
+        // Measured from the last container to terminate
+        assert_eq!(finished_at(&succeeded), Some(at("2026-01-01T00:20:00Z")));
+        assert!(!expired(&succeeded, ttl, at("2026-01-01T01:19:59Z")));
+        assert!(expired(&succeeded, ttl, at("2026-01-01T01:20:00Z")));
+
+        // A pod that failed before any container ran
+        let rejected = pod(
+            "Failed",
+            serde_json::json!({
+                "conditions": [{"type": "PodScheduled", "status": "True", "lastTransitionTime": "2026-01-01T00:05:00Z"}]
+            }),
+        );
+        assert_eq!(finished_at(&rejected), Some(at("2026-01-01T00:05:00Z")));
+        assert!(expired(&rejected, ttl, at("2026-01-01T01:05:00Z")));
+        assert_eq!(
+            finished_at(&pod("Failed", serde_json::json!({}))),
+            Some(at("2026-01-01T00:00:00Z"))
+        );
+
+        let late = at("2027-01-01T00:00:00Z");
+        assert!(!expired(&pod("Running", serde_json::json!({})), ttl, late));
+        let mut deleting = rejected.clone();
+        deleting.metadata.deletion_timestamp = deleting.metadata.creation_timestamp.clone();
+        assert!(!expired(&deleting, ttl, late));
+    }
+
+    #[tokio::test]
+    async fn test_sweep_deletes_only_expired_pods() {
+        let server = MockApiServer::start().await;
+        let mut old = pod("Succeeded", serde_json::json!({}));
+        old.metadata.name = Some("old".to_string());
+        let mut recent = pod("Failed", serde_json::json!({}));
+        recent.metadata.name = Some("recent".to_string());
+        recent.metadata.creation_timestamp = Some(
+            k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(Utc::now()),
+        );
+        for (phase, pod) in [("Succeeded", &old), ("Failed", &recent)] {
+            server.respond(
+                "GET",
+                &format!("/api/v1/pods?fieldSelector=status.phase={}", phase),
+                200,
+                serde_json::json!({"items": [pod]}),
+            );
+        }
+        server.respond(
+            "DELETE",
+            "/api/v1/namespaces/default/pods/old",
+            200,
+            serde_json::json!({}),
+        );
+
+        let controller = TtlAfterFinishedController::new(
+            server.client(),
+            TtlAfterFinishedControllerConfig::default(),
+        );
+        controller.sweep().await.unwrap();
+
+        let deleted: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|r| r.method == "DELETE")
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(deleted, ["/api/v1/namespaces/default/pods/old"]);
+    }
+}
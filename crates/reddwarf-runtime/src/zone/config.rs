@@ -1,14 +1,49 @@
 use crate::error::Result;
-use crate::types::{NetworkMode, ZoneConfig};
+use crate::types::{NetworkMode, WarmZone, ZoneBrand, ZoneConfig};
 
 /// Generate a zonecfg command file from a ZoneConfig
 pub fn generate_zonecfg(config: &ZoneConfig) -> Result<String> {
-    let mut lines = Vec::new();
+    let mut lines = base_lines(&config.brand, &config.zonepath);
+    lines.extend(resource_lines(config));
+    lines.push("verify".to_string());
+    lines.push("commit".to_string());
+
+    Ok(lines.join("\n"))
+}
+
+/// Generate the zonecfg command file of an idle warm zone: brand and
+/// zonepath only, so it can be installed before any pod needs it
+pub fn generate_warm_zonecfg(zone: &WarmZone) -> String {
+    let mut lines = base_lines(&zone.brand, &zone.zonepath);
+    lines.push("verify".to_string());
+    lines.push("commit".to_string());
+
+    lines.join("\n")
+}
+
+/// Generate the zonecfg command file that gives a claimed warm zone the
+/// network and resources of `config`. The warm zone has none of its own, so
+/// everything is added rather than replaced.
+pub fn generate_claim_zonecfg(config: &ZoneConfig) -> Result<String> {
+    let mut lines = resource_lines(config);
+    lines.push("verify".to_string());
+    lines.push("commit".to_string());
+
+    Ok(lines.join("\n"))
+}
+
+fn base_lines(brand: &ZoneBrand, zonepath: &str) -> Vec<String> {
+    vec![
+        "create".to_string(),
+        format!("set brand={}", brand),
+        format!("set zonepath={}", zonepath),
+        "set ip-type=exclusive".to_string(),
+    ]
+}
 
-    lines.push("create".to_string());
-    lines.push(format!("set brand={}", config.brand));
-    lines.push(format!("set zonepath={}", config.zonepath));
-    lines.push("set ip-type=exclusive".to_string());
+/// Network, resource controls, mounts, devices and attributes of a pod
+fn resource_lines(config: &ZoneConfig) -> Vec<String> {
+    let mut lines = Vec::new();
 
     // Network resource
     let (vnic_name, ip_address, gateway, prefix_len) = match &config.network {
@@ -31,20 +66,61 @@ pub fn generate_zonecfg(config: &ZoneConfig) -> Result<String> {
     lines.push(format!("set defrouter={}", gateway));
     lines.push("end".to_string());
 
-    // CPU cap
-    if let Some(ref cpu_cap) = config.cpu_cap {
-        lines.push("add capped-cpu".to_string());
-        lines.push(format!("set ncpus={}", cpu_cap));
+    // Dedicated CPUs (temporary pset) are exclusive with capped-cpu and
+    // cpu-shares: the zone owns whole CPUs, so neither applies.
+    if let Some(ncpus) = config.dedicated_cpus {
+        lines.push("add dedicated-cpu".to_string());
+        lines.push(format!("set ncpus={}", ncpus));
         lines.push("end".to_string());
+    } else {
+        // CPU cap
+        if let Some(ref cpu_cap) = config.cpu_cap {
+            lines.push("add capped-cpu".to_string());
+            lines.push(format!("set ncpus={}", cpu_cap));
+            lines.push("end".to_string());
+        }
+
+        // CPU shares only take effect under the fair share scheduler
+        if let Some(shares) = config.cpu_shares {
+            lines.push("set scheduling-class=FSS".to_string());
+            lines.push(format!("set cpu-shares={}", shares));
+        }
+    }
+
+    // Process/thread limits
+    if let Some(max_lwps) = config.max_lwps {
+        lines.push(format!("set max-lwps={}", max_lwps));
+    }
+    if let Some(max_processes) = config.max_processes {
+        lines.push(format!("set max-processes={}", max_processes));
     }
 
     // Memory cap
     if let Some(ref memory_cap) = config.memory_cap {
         lines.push("add capped-memory".to_string());
         lines.push(format!("set physical={}", memory_cap));
+        if let Some(ref swap_cap) = config.swap_cap {
+            lines.push(format!("set swap={}", swap_cap));
+        }
         lines.push("end".to_string());
     }
 
+    // Immutable zone: `strict` makes the whole root read-only from inside
+    // the zone, so scratch space comes from tmpfs mounts
+    if config.read_only_root {
+        lines.push("set file-mac-profile=strict".to_string());
+        for dir in ["/tmp", "/var/run"] {
+            if config.fs_mounts.iter().any(|m| m.mountpoint == dir) {
+                continue;
+            }
+            lines.push("add fs".to_string());
+            lines.push(format!("set dir={}", dir));
+            lines.push("set special=swap".to_string());
+            lines.push("set type=tmpfs".to_string());
+            lines.push("end".to_string());
+        }
+    }
+
     // Filesystem mounts
     for mount in &config.fs_mounts {
         lines.push("add fs".to_string());
@@ -57,10 +133,23 @@ pub fn generate_zonecfg(config: &ZoneConfig) -> Result<String> {
         lines.push("end".to_string());
     }
 
-    lines.push("verify".to_string());
-    lines.push("commit".to_string());
+    // Passed-through devices appear at the same path in the zone's /dev
+    for device in &config.devices {
+        lines.push("add device".to_string());
+        lines.push(format!("set match={}", device));
+        lines.push("end".to_string());
+    }
 
-    Ok(lines.join("\n"))
+    // Operator-allowed attributes requested by the pod
+    for attr in &config.attrs {
+        lines.push("add attr".to_string());
+        lines.push(format!("set name={}", attr.name));
+        lines.push("set type=string".to_string());
+        lines.push(format!("set value=\"{}\"", attr.value.replace('"', "")));
+        lines.push("end".to_string());
+    }
+
+    lines
 }
 
 #[cfg(test)]
@@ -89,7 +178,19 @@ mod tests {
             processes: vec![],
             cpu_cap: Some("2.0".to_string()),
             memory_cap: Some("1G".to_string()),
+            swap_cap: None,
+            cpu_shares: Some(2048),
+            dedicated_cpus: None,
+            max_lwps: Some(2000),
+            max_processes: Some(1000),
             fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
         };
 
         let result = generate_zonecfg(&config).unwrap();
@@ -101,6 +202,12 @@ mod tests {
         assert!(result.contains("set defrouter=10.0.0.1"));
         assert!(result.contains("set ncpus=2.0"));
         assert!(result.contains("set physical=1G"));
+        assert!(!result.contains("set swap="));
+        assert!(result.contains("set scheduling-class=FSS"));
+        assert!(result.contains("set cpu-shares=2048"));
+        assert!(result.contains("set max-lwps=2000"));
+        assert!(result.contains("set max-processes=1000"));
+        assert!(!result.contains("add dedicated-cpu"));
         assert!(result.contains("verify"));
         assert!(result.contains("commit"));
     }
@@ -128,15 +235,29 @@ mod tests {
                 command: vec!["/usr/bin/node".to_string(), "server.js".to_string()],
                 working_dir: Some("/app".to_string()),
                 env: vec![("PORT".to_string(), "3000".to_string())],
+                stdin: false,
+                tty: false,
             }],
             cpu_cap: None,
             memory_cap: Some("512M".to_string()),
+            swap_cap: Some("512M".to_string()),
+            cpu_shares: None,
+            dedicated_cpus: Some(2),
+            max_lwps: None,
+            max_processes: None,
             fs_mounts: vec![FsMount {
                 source: "/data/app-config".to_string(),
                 mountpoint: "/etc/app".to_string(),
                 fs_type: "lofs".to_string(),
                 options: vec!["ro".to_string()],
             }],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
         };
 
         let result = generate_zonecfg(&config).unwrap();
@@ -144,7 +265,7 @@ mod tests {
         assert!(result.contains("set physical=vnic1"));
         assert!(result.contains("set allowed-address=192.168.1.10/24"));
         assert!(result.contains("set defrouter=192.168.1.1"));
-        assert!(result.contains("set physical=512M"));
+        assert!(result.contains("add capped-memory\nset physical=512M\nset swap=512M\nend"));
         assert!(result.contains("add fs"));
         assert!(result.contains("set dir=/etc/app"));
         assert!(result.contains("set special=/data/app-config"));
@@ -152,5 +273,168 @@ mod tests {
         assert!(result.contains("add options ro"));
         // No cpu cap
         assert!(!result.contains("capped-cpu"));
+        assert!(result.contains("add dedicated-cpu\nset ncpus=2\nend"));
+        assert!(!result.contains("cpu-shares"));
+    }
+
+    #[test]
+    fn test_dedicated_cpu_replaces_cap_and_shares() {
+        let mut config = ZoneConfig {
+            zone_name: "pset-zone".to_string(),
+            brand: ZoneBrand::Reddwarf,
+            zonepath: "/zones/pset-zone".to_string(),
+            network: NetworkMode::Etherstub(EtherstubConfig {
+                etherstub_name: "reddwarf0".to_string(),
+                vnic_name: "vnic2".to_string(),
+                ip_address: "10.0.0.3".to_string(),
+                gateway: "10.0.0.1".to_string(),
+                prefix_len: 16,
+            }),
+            storage: ZoneStorageOpts::default(),
+            lx_image_path: None,
+            processes: vec![],
+            cpu_cap: Some("2.00".to_string()),
+            memory_cap: None,
+            swap_cap: None,
+            cpu_shares: Some(2048),
+            dedicated_cpus: Some(2),
+            max_lwps: None,
+            max_processes: None,
+            fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
+        };
+
+        let result = generate_zonecfg(&config).unwrap();
+        assert!(result.contains("add dedicated-cpu"));
+        assert!(!result.contains("add capped-cpu"));
+        assert!(!result.contains("cpu-shares"));
+
+        config.dedicated_cpus = None;
+        let result = generate_zonecfg(&config).unwrap();
+        assert!(result.contains("add capped-cpu"));
+        assert!(result.contains("set cpu-shares=2048"));
+    }
+
+    #[test]
+    fn test_read_only_root_is_immutable_with_tmpfs() {
+        let mut config = ZoneConfig {
+            zone_name: "ro-zone".to_string(),
+            brand: ZoneBrand::Reddwarf,
+            zonepath: "/zones/ro-zone".to_string(),
+            network: NetworkMode::Etherstub(EtherstubConfig {
+                etherstub_name: "reddwarf0".to_string(),
+                vnic_name: "vnic3".to_string(),
+                ip_address: "10.0.0.4".to_string(),
+                gateway: "10.0.0.1".to_string(),
+                prefix_len: 16,
+            }),
+            storage: ZoneStorageOpts::default(),
+            lx_image_path: None,
+            processes: vec![],
+            cpu_cap: None,
+            memory_cap: None,
+            swap_cap: None,
+            cpu_shares: None,
+            dedicated_cpus: None,
+            max_lwps: None,
+            max_processes: None,
+            fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: true,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
+        };
+
+        let result = generate_zonecfg(&config).unwrap();
+        assert!(result.contains("set file-mac-profile=strict"));
+        assert!(result.contains("add fs\nset dir=/tmp\nset special=swap\nset type=tmpfs\nend"));
+        assert!(result.contains("add fs\nset dir=/var/run\nset special=swap\nset type=tmpfs\nend"));
+
+        // An explicit mount at /tmp replaces the tmpfs one
+        config.fs_mounts.push(FsMount {
+            source: "/scratch".to_string(),
+            mountpoint: "/tmp".to_string(),
+            fs_type: "lofs".to_string(),
+            options: vec![],
+        });
+        let result = generate_zonecfg(&config).unwrap();
+        assert!(!result.contains("set dir=/tmp\nset special=swap"));
+        assert!(result.contains("set dir=/tmp\nset special=/scratch"));
+
+        config.read_only_root = false;
+        let result = generate_zonecfg(&config).unwrap();
+        assert!(!result.contains("file-mac-profile"));
+        assert!(!result.contains("type=tmpfs"));
+
+        config.attrs.push(ZoneAttr {
+            name: "comment".to_string(),
+            value: "web tier".to_string(),
+        });
+        config.devices.push("/dev/dsk/c1t1d0".to_string());
+        let result = generate_zonecfg(&config).unwrap();
+        assert!(result.contains("add device\nset match=/dev/dsk/c1t1d0\nend"));
+        assert!(result
+            .contains("add attr\nset name=comment\nset type=string\nset value=\"web tier\"\nend"));
+    }
+
+    #[test]
+    fn test_warm_zone_and_claim_zonecfg() {
+        let warm = WarmZone {
+            zone_name: "rdwarm-reddwarf-0".to_string(),
+            brand: ZoneBrand::Reddwarf,
+            zonepath: "/zones/rdwarm-reddwarf-0".to_string(),
+            lx_image_path: None,
+        };
+        assert_eq!(
+            generate_warm_zonecfg(&warm),
+            "create\nset brand=reddwarf\nset zonepath=/zones/rdwarm-reddwarf-0\n\
+             set ip-type=exclusive\nverify\ncommit"
+        );
+
+        let config = ZoneConfig {
+            zone_name: "claimed".to_string(),
+            brand: ZoneBrand::Reddwarf,
+            zonepath: "/zones/claimed".to_string(),
+            network: NetworkMode::Etherstub(EtherstubConfig {
+                etherstub_name: "reddwarf0".to_string(),
+                vnic_name: "vnic4".to_string(),
+                ip_address: "10.0.0.5".to_string(),
+                gateway: "10.0.0.1".to_string(),
+                prefix_len: 16,
+            }),
+            storage: ZoneStorageOpts::default(),
+            lx_image_path: None,
+            processes: vec![],
+            cpu_cap: Some("1.0".to_string()),
+            memory_cap: None,
+            swap_cap: None,
+            cpu_shares: None,
+            dedicated_cpus: None,
+            max_lwps: None,
+            max_processes: None,
+            fs_mounts: vec![],
+            dns: None,
+            hostname: None,
+            fqdn: None,
+            read_only_root: false,
+            attrs: vec![],
+            ip_props: vec![],
+            devices: vec![],
+        };
+        let result = generate_claim_zonecfg(&config).unwrap();
+        assert!(!result.contains("create"));
+        assert!(!result.contains("set zonepath"));
+        assert!(result.starts_with("add net\nset physical=vnic4"));
+        assert!(result.contains("add capped-cpu\nset ncpus=1.0\nend"));
+        assert!(result.ends_with("verify\ncommit"));
     }
 }
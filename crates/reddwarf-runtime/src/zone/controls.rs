@@ -0,0 +1,91 @@
+use reddwarf_core::QosClass;
+
+/// FSS shares granted per full CPU requested (mirrors Linux cgroup cpu.shares)
+const SHARES_PER_CPU: i64 = 1024;
+
+/// Minimum shares for any zone, so BestEffort pods are not starved entirely
+const MIN_CPU_SHARES: u32 = 2;
+
+/// Zone resource controls derived from a pod's QoS class and CPU requests
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceControls {
+    pub cpu_shares: Option<u32>,
+    pub dedicated_cpus: Option<u32>,
+    pub max_lwps: Option<u32>,
+    pub max_processes: Option<u32>,
+}
+
+impl ResourceControls {
+    /// Derive controls for a pod.
+    ///
+    /// - Guaranteed pods with a whole-CPU limit get a dedicated pset.
+    /// - Everyone else gets FSS shares proportional to their CPU request.
+    /// - Thread/process limits scale with QoS so BestEffort pods can't
+    ///   exhaust the host's process table.
+    pub fn for_pod(qos: QosClass, cpu_request_millicores: i64, cpu_limit_millicores: i64) -> Self {
+        let dedicated_cpus = match qos {
+            QosClass::Guaranteed
+                if cpu_limit_millicores >= 1000 && cpu_limit_millicores % 1000 == 0 =>
+            {
+                u32::try_from(cpu_limit_millicores / 1000).ok()
+            }
+            _ => None,
+        };
+
+        let cpu_shares = if dedicated_cpus.is_some() {
+            None
+        } else {
+            let shares = match qos {
+                QosClass::BestEffort => MIN_CPU_SHARES,
+                _ => u32::try_from(cpu_request_millicores * SHARES_PER_CPU / 1000)
+                    .unwrap_or(u32::MAX)
+                    .max(MIN_CPU_SHARES),
+            };
+            Some(shares)
+        };
+
+        let (max_lwps, max_processes) = match qos {
+            QosClass::Guaranteed => (8192, 4096),
+            QosClass::Burstable => (4096, 2048),
+            QosClass::BestEffort => (2048, 1024),
+        };
+
+        Self {
+            cpu_shares,
+            dedicated_cpus,
+            max_lwps: Some(max_lwps),
+            max_processes: Some(max_processes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guaranteed_whole_cpus_get_pset() {
+        let c = ResourceControls::for_pod(QosClass::Guaranteed, 2000, 2000);
+        assert_eq!(c.dedicated_cpus, Some(2));
+        assert_eq!(c.cpu_shares, None);
+        assert_eq!(c.max_lwps, Some(8192));
+    }
+
+    #[test]
+    fn test_guaranteed_fractional_cpu_gets_shares() {
+        let c = ResourceControls::for_pod(QosClass::Guaranteed, 500, 500);
+        assert_eq!(c.dedicated_cpus, None);
+        assert_eq!(c.cpu_shares, Some(512));
+    }
+
+    #[test]
+    fn test_burstable_and_best_effort_shares() {
+        let c = ResourceControls::for_pod(QosClass::Burstable, 250, 1000);
+        assert_eq!(c.cpu_shares, Some(256));
+        assert_eq!(c.max_processes, Some(2048));
+
+        let c = ResourceControls::for_pod(QosClass::BestEffort, 0, 0);
+        assert_eq!(c.cpu_shares, Some(MIN_CPU_SHARES));
+        assert_eq!(c.max_processes, Some(1024));
+    }
+}
@@ -1,5 +1,8 @@
 pub mod config;
+pub mod controls;
 pub mod state;
+pub mod tunables;
 
-pub use config::generate_zonecfg;
+pub use config::{generate_claim_zonecfg, generate_warm_zonecfg, generate_zonecfg};
 pub use state::parse_zoneadm_line;
+pub use tunables::{PodTunables, TunablesAllowlist};
@@ -0,0 +1,231 @@
+use crate::error::{Result, RuntimeError};
+use crate::types::{IpProp, ZoneAttr};
+use k8s_openapi::api::core::v1::Pod;
+use reddwarf_core::annotations::{annotation, parse_zone_attrs, ZONE_ATTRS_ANNOTATION};
+
+/// Sysctls allowed when the operator does not configure an allowlist; these
+/// only affect the pod's own (exclusive-IP) network stack
+pub const DEFAULT_ALLOWED_SYSCTLS: &[&str] = &[
+    "net.ipv4.ip_local_port_range",
+    "net.ipv4.ip_unprivileged_port_start",
+];
+
+/// Operator allowlist of pod sysctls and zone attributes. Entries match
+/// exactly, or by prefix when they end in `*` (e.g. `net.ipv4.tcp_*`).
+#[derive(Debug, Clone)]
+pub struct TunablesAllowlist {
+    pub sysctls: Vec<String>,
+    pub zone_attrs: Vec<String>,
+}
+
+impl Default for TunablesAllowlist {
+    fn default() -> Self {
+        Self {
+            sysctls: DEFAULT_ALLOWED_SYSCTLS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            zone_attrs: Vec::new(),
+        }
+    }
+}
+
+impl TunablesAllowlist {
+    fn allows(patterns: &[String], name: &str) -> bool {
+        patterns.iter().any(|p| match p.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => p == name,
+        })
+    }
+}
+
+/// Zone attributes and in-zone protocol properties requested by a pod
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PodTunables {
+    pub attrs: Vec<ZoneAttr>,
+    pub ip_props: Vec<IpProp>,
+}
+
+impl PodTunables {
+    /// Collect the pod's `securityContext.sysctls` and zone attribute
+    /// annotation, failing on anything the allowlist does not permit or that
+    /// has no illumos equivalent
+    pub fn for_pod(pod: &Pod, allowlist: &TunablesAllowlist) -> Result<Self> {
+        let mut tunables = Self::default();
+
+        let sysctls = pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.security_context.as_ref())
+            .and_then(|sc| sc.sysctls.as_ref());
+        for sysctl in sysctls.into_iter().flatten() {
+            if !TunablesAllowlist::allows(&allowlist.sysctls, &sysctl.name) {
+                return Err(RuntimeError::invalid_config(
+                    format!("Sysctl '{}' is not allowed on this node", sysctl.name),
+                    "Add it to the node's --allowed-sysctls",
+                ));
+            }
+            tunables
+                .ip_props
+                .extend(sysctl_ip_props(&sysctl.name, &sysctl.value)?);
+        }
+
+        let attrs = annotation(&pod.metadata, ZONE_ATTRS_ANNOTATION)
+            .map(parse_zone_attrs)
+            .transpose()
+            .map_err(|e| RuntimeError::invalid_config(e, "Use comma-separated name=value pairs"))?;
+        for (name, value) in attrs.into_iter().flatten() {
+            if !TunablesAllowlist::allows(&allowlist.zone_attrs, &name) {
+                return Err(RuntimeError::invalid_config(
+                    format!("Zone attribute '{}' is not allowed on this node", name),
+                    "Add it to the node's --allowed-zone-attrs",
+                ));
+            }
+            tunables.attrs.push(ZoneAttr { name, value });
+        }
+
+        Ok(tunables)
+    }
+}
+
+/// illumos protocol properties implementing a Linux sysctl
+fn sysctl_ip_props(name: &str, value: &str) -> Result<Vec<IpProp>> {
+    let invalid = || {
+        RuntimeError::invalid_config(
+            format!("Invalid value '{}' for sysctl '{}'", value, name),
+            "Use the value format of the Linux sysctl",
+        )
+    };
+    let number = |v: &str| v.trim().parse::<u64>().map_err(|_| invalid());
+    let prop = |protocol: &str, prop: &str, value: u64| IpProp {
+        protocol: protocol.to_string(),
+        name: prop.to_string(),
+        value: value.to_string(),
+    };
+
+    let props = match name {
+        "net.ipv4.ip_local_port_range" => {
+            let mut range = value.split_whitespace();
+            let (Some(low), Some(high), None) = (range.next(), range.next(), range.next()) else {
+                return Err(invalid());
+            };
+            let (low, high) = (number(low)?, number(high)?);
+            ["tcp", "udp"]
+                .into_iter()
+                .flat_map(|p| {
+                    [
+                        prop(p, "smallest_anon_port", low),
+                        prop(p, "largest_anon_port", high),
+                    ]
+                })
+                .collect()
+        }
+        "net.ipv4.ip_unprivileged_port_start" => {
+            let port = number(value)?;
+            vec![
+                prop("tcp", "smallest_nonpriv_port", port),
+                prop("udp", "smallest_nonpriv_port", port),
+            ]
+        }
+        "net.core.somaxconn" => vec![prop("tcp", "_conn_req_max_q", number(value)?)],
+        // Linux uses seconds, illumos milliseconds
+        "net.ipv4.tcp_keepalive_time" => {
+            vec![prop("tcp", "_keepalive_interval", number(value)? * 1000)]
+        }
+        "net.ipv4.tcp_fin_timeout" => {
+            vec![prop(
+                "tcp",
+                "_fin_wait_2_flush_interval",
+                number(value)? * 1000,
+            )]
+        }
+        _ => {
+            return Err(RuntimeError::invalid_config(
+                format!("Sysctl '{}' has no illumos equivalent", name),
+                "Remove it from the pod's securityContext.sysctls",
+            ))
+        }
+    };
+    Ok(props)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{PodSecurityContext, PodSpec, Sysctl};
+
+    fn make_pod(sysctls: &[(&str, &str)], attrs: Option<&str>) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.annotations =
+            attrs.map(|a| [(ZONE_ATTRS_ANNOTATION.to_string(), a.to_string())].into());
+        pod.spec = Some(PodSpec {
+            security_context: Some(PodSecurityContext {
+                sysctls: Some(
+                    sysctls
+                        .iter()
+                        .map(|(name, value)| Sysctl {
+                            name: name.to_string(),
+                            value: value.to_string(),
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        pod
+    }
+
+    #[test]
+    fn test_sysctls_map_to_ipadm_props() {
+        let pod = make_pod(&[("net.ipv4.ip_local_port_range", "20000 40000")], None);
+        let tunables = PodTunables::for_pod(&pod, &TunablesAllowlist::default()).unwrap();
+        assert_eq!(tunables.ip_props.len(), 4);
+        assert_eq!(
+            tunables.ip_props[1],
+            IpProp {
+                protocol: "tcp".to_string(),
+                name: "largest_anon_port".to_string(),
+                value: "40000".to_string(),
+            }
+        );
+
+        // Not in the default allowlist
+        let pod = make_pod(&[("net.core.somaxconn", "1024")], None);
+        assert!(PodTunables::for_pod(&pod, &TunablesAllowlist::default()).is_err());
+
+        let allowlist = TunablesAllowlist {
+            sysctls: vec!["net.*".to_string()],
+            zone_attrs: vec![],
+        };
+        let tunables = PodTunables::for_pod(&pod, &allowlist).unwrap();
+        assert_eq!(tunables.ip_props[0].name, "_conn_req_max_q");
+
+        // Allowed, but nothing to map it to
+        let pod = make_pod(&[("net.ipv4.tcp_syncookies", "1")], None);
+        assert!(PodTunables::for_pod(&pod, &allowlist).is_err());
+    }
+
+    #[test]
+    fn test_zone_attrs_annotation() {
+        let allowlist = TunablesAllowlist {
+            sysctls: vec![],
+            zone_attrs: vec!["comment".to_string()],
+        };
+
+        let pod = make_pod(&[], Some("comment=web tier"));
+        let tunables = PodTunables::for_pod(&pod, &allowlist).unwrap();
+        assert_eq!(
+            tunables.attrs,
+            vec![ZoneAttr {
+                name: "comment".to_string(),
+                value: "web tier".to_string(),
+            }]
+        );
+
+        let pod = make_pod(&[], Some("comment=ok,owner=me"));
+        assert!(PodTunables::for_pod(&pod, &allowlist).is_err());
+        let pod = make_pod(&[], Some("comment"));
+        assert!(PodTunables::for_pod(&pod, &allowlist).is_err());
+    }
+}
@@ -1,19 +1,24 @@
 // Allow unused assignments for diagnostic fields - they're used by the macros
 #![allow(unused_assignments)]
 
+use crate::types::UnschedulableReasons;
 use miette::Diagnostic;
+use reddwarf_core::{ErrorClass, ErrorKind};
 use thiserror::Error;
 
 /// Scheduler error type
 #[derive(Error, Debug, Diagnostic)]
 pub enum SchedulerError {
     /// No suitable nodes found
-    #[error("No suitable nodes found for pod {pod_name}")]
+    #[error("No suitable nodes found for pod {pod_name}: {reasons}")]
     #[diagnostic(
         code(scheduler::no_suitable_nodes),
         help("Check node resources, taints, and pod requirements")
     )]
-    NoSuitableNodes { pod_name: String, reason: String },
+    NoSuitableNodes {
+        pod_name: String,
+        reasons: UnschedulableReasons,
+    },
 
     /// Scheduling failed
     #[error("Scheduling failed: {message}")]
@@ -47,10 +52,10 @@ pub type Result<T> = std::result::Result<T, SchedulerError>;
 
 impl SchedulerError {
     /// Create a NoSuitableNodes error
-    pub fn no_suitable_nodes(pod_name: impl Into<String>, reason: impl Into<String>) -> Self {
+    pub fn no_suitable_nodes(pod_name: impl Into<String>, reasons: UnschedulableReasons) -> Self {
         Self::NoSuitableNodes {
             pod_name: pod_name.into(),
-            reason: reason.into(),
+            reasons,
         }
     }
 
@@ -68,4 +73,33 @@ impl SchedulerError {
             message: message.into(),
         }
     }
+
+    /// Reason recorded on the pod's `PodScheduled=False` condition, as
+    /// kube-scheduler sets it
+    pub fn condition_reason(&self) -> &'static str {
+        match self {
+            Self::NoSuitableNodes { .. } => "Unschedulable",
+            _ => "SchedulerError",
+        }
+    }
+
+    /// Message recorded on the pod's `PodScheduled=False` condition
+    pub fn condition_message(&self) -> String {
+        match self {
+            Self::NoSuitableNodes { reasons, .. } => reasons.to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl ErrorClass for SchedulerError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            // Capacity may free up, or a fitting node join
+            Self::NoSuitableNodes { .. } => ErrorKind::Unavailable,
+            Self::SchedulingFailed { .. } | Self::InternalError { .. } => ErrorKind::Internal,
+            Self::StorageError(e) => e.kind(),
+            Self::CoreError(e) => e.kind(),
+        }
+    }
 }
@@ -1,5 +1,9 @@
 use crate::types::{FilterResult, ResourceQuantities, SchedulingContext};
-use reddwarf_core::Node;
+use k8s_openapi::api::core::v1::Taint;
+use reddwarf_core::annotations::zone_brands;
+use reddwarf_core::platform::{normalize_arch, pod_image_platforms, ARCH_LABEL};
+use reddwarf_core::resources::DEFAULT_ZONE_BRAND;
+use reddwarf_core::{pod_device_requests, pod_host_ports, pod_zone_brand, Node};
 use tracing::debug;
 
 /// Filter predicate trait
@@ -39,8 +43,14 @@ impl FilterPredicate for PodFitsResources {
             None => return FilterResult::fail(node_name, "Pod has no spec".to_string()),
         };
 
-        let mut total_cpu = 0i64;
-        let mut total_memory = 0i64;
+        // RuntimeClass overhead is charged on top of the containers
+        let overhead = pod_spec
+            .overhead
+            .as_ref()
+            .map(ResourceQuantities::from_k8s_resource_map)
+            .unwrap_or_default();
+        let mut total_cpu = overhead.cpu_millicores;
+        let mut total_memory = overhead.memory_bytes;
 
         for container in &pod_spec.containers {
             if let Some(resources) = &container.resources {
@@ -75,7 +85,8 @@ impl FilterPredicate for PodFitsResources {
                     "Insufficient CPU: requested {} milli, available {} milli",
                     total_cpu, node_resources.cpu_millicores
                 ),
-            );
+            )
+            .with_summary("Insufficient cpu");
         }
 
         if total_memory > node_resources.memory_bytes {
@@ -85,7 +96,8 @@ impl FilterPredicate for PodFitsResources {
                     "Insufficient memory: requested {} bytes, available {} bytes",
                     total_memory, node_resources.memory_bytes
                 ),
-            );
+            )
+            .with_summary("Insufficient memory");
         }
 
         FilterResult::pass(node_name)
@@ -130,7 +142,8 @@ impl FilterPredicate for NodeSelectorMatch {
                 return FilterResult::fail(
                     node_name,
                     format!("Node selector mismatch: {}={}", key, value),
-                );
+                )
+                .with_summary("node(s) didn't match Pod's node affinity/selector");
             }
         }
 
@@ -142,6 +155,64 @@ impl FilterPredicate for NodeSelectorMatch {
     }
 }
 
+/// kube-scheduler's summary of a taint the pod does not tolerate
+fn untolerated_taint_summary(taint: &Taint) -> String {
+    format!(
+        "node(s) had untolerated taint {{{}: {}}}",
+        taint.key,
+        taint.value.as_deref().unwrap_or_default()
+    )
+}
+
+/// Taint key under which pods tolerate cordoned nodes
+pub const UNSCHEDULABLE_TAINT_KEY: &str = "node.kubernetes.io/unschedulable";
+
+/// Filter out cordoned nodes (`spec.unschedulable`), unless the pod
+/// tolerates the `node.kubernetes.io/unschedulable` taint
+pub struct NodeUnschedulable;
+
+impl FilterPredicate for NodeUnschedulable {
+    fn filter(&self, context: &SchedulingContext, node: &Node) -> FilterResult {
+        let node_name = node
+            .metadata
+            .name
+            .as_ref()
+            .unwrap_or(&"unknown".to_string())
+            .clone();
+
+        let unschedulable = node
+            .spec
+            .as_ref()
+            .and_then(|s| s.unschedulable)
+            .unwrap_or(false);
+        if !unschedulable {
+            return FilterResult::pass(node_name);
+        }
+
+        let tolerated = context
+            .pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.tolerations.as_ref())
+            .is_some_and(|tolerations| {
+                tolerations.iter().any(|t| {
+                    t.key.as_deref() == Some(UNSCHEDULABLE_TAINT_KEY)
+                        && t.effect.as_deref().is_none_or(|e| e == "NoSchedule")
+                })
+            });
+        if tolerated {
+            return FilterResult::pass(node_name);
+        }
+
+        FilterResult::fail(node_name, "Node is cordoned".to_string())
+            .with_summary("node(s) were unschedulable")
+    }
+
+    fn name(&self) -> &str {
+        "NodeUnschedulable"
+    }
+}
+
 /// Filter for taints and tolerations
 pub struct TaintToleration;
 
@@ -170,11 +241,12 @@ impl FilterPredicate for TaintToleration {
             Some(t) => t,
             None => {
                 // No tolerations but node has taints = fail
-                if !taints.is_empty() {
+                if let Some(taint) = taints.first() {
                     return FilterResult::fail(
                         node_name,
                         "Node has taints but pod has no tolerations".to_string(),
-                    );
+                    )
+                    .with_summary(untolerated_taint_summary(taint));
                 }
                 return FilterResult::pass(node_name);
             }
@@ -208,7 +280,8 @@ impl FilterPredicate for TaintToleration {
                         "Pod does not tolerate taint: {}={}",
                         taint_key, taint_effect
                     ),
-                );
+                )
+                .with_summary(untolerated_taint_summary(taint));
             }
         }
 
@@ -232,54 +305,233 @@ impl FilterPredicate for ZoneBrandMatch {
             .unwrap_or(&"unknown".to_string())
             .clone();
 
-        // Read pod annotation "reddwarf.io/zone-brand" (default: "reddwarf")
-        let pod_brand = context
-            .pod
+        // The pod's RuntimeClass handler, or its legacy brand annotation
+        let pod_brand = match pod_zone_brand(&context.pod, &context.runtime_class_handlers) {
+            Ok(brand) => brand.unwrap_or_else(|| DEFAULT_ZONE_BRAND.to_string()),
+            Err(reason) => return FilterResult::fail(node_name, reason),
+        };
+
+        // Nodes without the zone brands label pass (backward compat)
+        let Some(supported) = zone_brands(&node.metadata) else {
+            return FilterResult::pass(node_name);
+        };
+
+        if supported.contains(&pod_brand.as_str()) {
+            FilterResult::pass(node_name)
+        } else {
+            FilterResult::fail(
+                node_name,
+                format!(
+                    "Node does not support zone brand '{}': available brands are {:?}",
+                    pod_brand, supported
+                ),
+            )
+            .with_summary(format!("node(s) didn't support zone brand '{}'", pod_brand))
+        }
+    }
+
+    fn name(&self) -> &str {
+        "ZoneBrandMatch"
+    }
+}
+
+/// Filter for image platform compatibility with the node's architecture
+///
+/// Pods declare the platforms their image supports via the
+/// `reddwarf.io/image-platforms` annotation. The OS half of each platform is
+/// not compared: zone brands (e.g. lx) decide which OS ABI a node can run.
+pub struct PlatformMatch;
+
+impl FilterPredicate for PlatformMatch {
+    fn filter(&self, context: &SchedulingContext, node: &Node) -> FilterResult {
+        let node_name = node
             .metadata
-            .annotations
+            .name
             .as_ref()
-            .and_then(|a| a.get("reddwarf.io/zone-brand"))
-            .map(|s| s.as_str())
-            .unwrap_or("reddwarf");
+            .unwrap_or(&"unknown".to_string())
+            .clone();
+
+        let platforms = match pod_image_platforms(&context.pod) {
+            Some(p) => p,
+            None => return FilterResult::pass(node_name),
+        };
 
-        // Read node label "reddwarf.io/zone-brands" — if absent, pass (backward compat)
-        let node_brands_label = node
+        // Prefer the arch label, fall back to nodeInfo; unknown arch = pass
+        let node_arch = node
             .metadata
             .labels
             .as_ref()
-            .and_then(|l| l.get("reddwarf.io/zone-brands"));
-
-        let node_brands_label = match node_brands_label {
-            Some(label) => label,
+            .and_then(|l| l.get(ARCH_LABEL))
+            .map(|s| s.as_str())
+            .or_else(|| {
+                node.status
+                    .as_ref()
+                    .and_then(|s| s.node_info.as_ref())
+                    .map(|i| i.architecture.as_str())
+            })
+            .filter(|a| !a.is_empty());
+
+        let node_arch = match node_arch {
+            Some(a) => normalize_arch(a),
             None => return FilterResult::pass(node_name),
         };
 
-        // Split node brands by comma, check if pod brand is in the list
-        let supported: Vec<&str> = node_brands_label.split(',').map(|s| s.trim()).collect();
-
-        if supported.contains(&pod_brand) {
+        if platforms.iter().any(|p| p.arch == node_arch) {
             FilterResult::pass(node_name)
         } else {
             FilterResult::fail(
                 node_name,
                 format!(
-                    "Node does not support zone brand '{}': available brands are {:?}",
-                    pod_brand, supported
+                    "Image platforms [{}] not compatible with node architecture '{}'",
+                    platforms
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    node_arch
                 ),
             )
+            .with_summary("node(s) didn't match the pod's image platforms")
         }
     }
 
     fn name(&self) -> &str {
-        "ZoneBrandMatch"
+        "PlatformMatch"
+    }
+}
+
+/// Filter for host port conflicts
+///
+/// A node passes only if none of the pod's `hostPort`s (per protocol) is
+/// already reserved there by another pod.
+pub struct HostPortsAvailable;
+
+impl FilterPredicate for HostPortsAvailable {
+    fn filter(&self, context: &SchedulingContext, node: &Node) -> FilterResult {
+        let node_name = node
+            .metadata
+            .name
+            .as_ref()
+            .unwrap_or(&"unknown".to_string())
+            .clone();
+
+        let Some(used) = context.used_host_ports.get(&node_name) else {
+            return FilterResult::pass(node_name);
+        };
+
+        for port in pod_host_ports(&context.pod) {
+            if used.contains(&(port.protocol.clone(), port.host_port)) {
+                return FilterResult::fail(
+                    node_name,
+                    format!(
+                        "Host port {}/{} is already in use",
+                        port.host_port, port.protocol
+                    ),
+                )
+                .with_summary("node(s) didn't have free ports for the requested pod ports");
+            }
+        }
+
+        FilterResult::pass(node_name)
+    }
+
+    fn name(&self) -> &str {
+        "HostPortsAvailable"
+    }
+}
+
+/// Filter for device availability
+///
+/// A node passes only if it advertises enough of every device class
+/// (`devices.reddwarf.io/*`) the pod requests that other pods don't hold.
+pub struct DevicesAvailable;
+
+impl FilterPredicate for DevicesAvailable {
+    fn filter(&self, context: &SchedulingContext, node: &Node) -> FilterResult {
+        let node_name = node
+            .metadata
+            .name
+            .as_ref()
+            .unwrap_or(&"unknown".to_string())
+            .clone();
+
+        let requests = pod_device_requests(&context.pod);
+        if requests.is_empty() {
+            return FilterResult::pass(node_name);
+        }
+
+        let allocatable = node.status.as_ref().and_then(|s| s.allocatable.as_ref());
+        let used = context.used_devices.get(&node_name);
+
+        for (resource, requested) in requests {
+            let total = allocatable
+                .and_then(|a| a.get(&resource))
+                .and_then(|q| q.0.parse::<i64>().ok())
+                .unwrap_or(0);
+            let in_use = used.and_then(|u| u.get(&resource)).copied().unwrap_or(0);
+            if requested > total - in_use {
+                return FilterResult::fail(
+                    node_name,
+                    format!(
+                        "Insufficient {}: requested {}, available {}",
+                        resource,
+                        requested,
+                        (total - in_use).max(0)
+                    ),
+                )
+                .with_summary(format!("Insufficient {}", resource));
+            }
+        }
+
+        FilterResult::pass(node_name)
+    }
+
+    fn name(&self) -> &str {
+        "DevicesAvailable"
+    }
+}
+
+/// Filter for pod IP exhaustion
+///
+/// A node passes unless the scheduler reserves pod IPs on it and its pod
+/// CIDR has none left, so a pod is never bound to a node that could only
+/// fail to give it an address.
+pub struct PodIpAvailable;
+
+impl FilterPredicate for PodIpAvailable {
+    fn filter(&self, context: &SchedulingContext, node: &Node) -> FilterResult {
+        let node_name = node
+            .metadata
+            .name
+            .as_ref()
+            .unwrap_or(&"unknown".to_string())
+            .clone();
+
+        match context.free_pod_ips.get(&node_name) {
+            Some(0) => FilterResult::fail(
+                node_name,
+                "No free pod IPs left in the node's pod CIDR".to_string(),
+            )
+            .with_summary("node(s) had no free pod IPs"),
+            _ => FilterResult::pass(node_name),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "PodIpAvailable"
     }
 }
 
 /// Get default filter predicates
 pub fn default_filters() -> Vec<Box<dyn FilterPredicate>> {
     vec![
+        Box::new(NodeUnschedulable),
         Box::new(ZoneBrandMatch),
+        Box::new(PlatformMatch),
+        Box::new(HostPortsAvailable),
         Box::new(PodFitsResources),
+        Box::new(DevicesAvailable),
+        Box::new(PodIpAvailable),
         Box::new(NodeSelectorMatch),
         Box::new(TaintToleration),
     ]
@@ -288,8 +540,9 @@ pub fn default_filters() -> Vec<Box<dyn FilterPredicate>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reddwarf_core::annotations::{ZONE_BRANDS_LABEL, ZONE_BRAND_ANNOTATION};
     use reddwarf_core::{Node, Pod};
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, HashMap};
 
     fn create_test_node(name: &str, cpu: &str, memory: &str) -> Node {
         let mut node = Node::default();
@@ -399,7 +652,7 @@ mod tests {
             node.metadata
                 .labels
                 .get_or_insert_with(BTreeMap::new)
-                .insert("reddwarf.io/zone-brands".to_string(), brands.to_string());
+                .insert(ZONE_BRANDS_LABEL.to_string(), brands.to_string());
         }
         node
     }
@@ -410,7 +663,7 @@ mod tests {
             pod.metadata
                 .annotations
                 .get_or_insert_with(BTreeMap::new)
-                .insert("reddwarf.io/zone-brand".to_string(), brand.to_string());
+                .insert(ZONE_BRAND_ANNOTATION.to_string(), brand.to_string());
         }
         pod
     }
@@ -438,6 +691,42 @@ mod tests {
         assert!(result.reason.unwrap().contains("does not support zone brand 'lx'"));
     }
 
+    #[test]
+    fn test_zone_brand_match_runtime_class() {
+        let node = create_branded_node("node1", Some("reddwarf,lx"));
+        let mut pod = create_branded_pod(None);
+        pod.spec.as_mut().unwrap().runtime_class_name = Some("linux".to_string());
+
+        // Unknown RuntimeClass
+        let context = SchedulingContext::new(pod.clone(), vec![node.clone()]);
+        let result = ZoneBrandMatch.filter(&context, &node);
+        assert!(!result.passed);
+        assert!(result.reason.unwrap().contains("RuntimeClass 'linux'"));
+
+        let handlers = [("linux".to_string(), "lx".to_string())].into();
+        let context =
+            SchedulingContext::new(pod, vec![node.clone()]).with_runtime_class_handlers(handlers);
+        assert!(ZoneBrandMatch.filter(&context, &node).passed);
+
+        let node = create_branded_node("node2", Some("reddwarf"));
+        assert!(!ZoneBrandMatch.filter(&context, &node).passed);
+    }
+
+    #[test]
+    fn test_pod_fits_resources_counts_overhead() {
+        let node = create_test_node("node1", "4", "8Gi");
+        let mut pod = create_test_pod("1", "7Gi");
+        let context = SchedulingContext::new(pod.clone(), vec![node.clone()]);
+        assert!(PodFitsResources.filter(&context, &node).passed);
+
+        pod.spec.as_mut().unwrap().overhead = Some(BTreeMap::from([(
+            "memory".to_string(),
+            k8s_openapi::apimachinery::pkg::api::resource::Quantity("2Gi".to_string()),
+        )]));
+        let context = SchedulingContext::new(pod, vec![node.clone()]);
+        assert!(!PodFitsResources.filter(&context, &node).passed);
+    }
+
     #[test]
     fn test_zone_brand_match_no_annotation() {
         let node = create_branded_node("node1", Some("reddwarf"));
@@ -470,4 +759,192 @@ mod tests {
         let result = filter.filter(&context, &node);
         assert!(result.passed);
     }
+
+    fn create_arch_node(name: &str, arch: &str) -> Node {
+        let mut node = create_test_node(name, "4", "8Gi");
+        node.metadata
+            .labels
+            .get_or_insert_with(BTreeMap::new)
+            .insert(ARCH_LABEL.to_string(), arch.to_string());
+        node
+    }
+
+    fn create_platform_pod(platforms: &str) -> Pod {
+        let mut pod = create_test_pod("1", "1Gi");
+        pod.metadata.annotations.get_or_insert_with(BTreeMap::new).insert(
+            reddwarf_core::platform::IMAGE_PLATFORMS_ANNOTATION.to_string(),
+            platforms.to_string(),
+        );
+        pod
+    }
+
+    #[test]
+    fn test_platform_match_pass() {
+        let node = create_arch_node("node1", "arm64");
+        let pod = create_platform_pod("linux/amd64,linux/arm64");
+        let context = SchedulingContext::new(pod, vec![node.clone()]);
+
+        let result = PlatformMatch.filter(&context, &node);
+        assert!(result.passed);
+    }
+
+    #[test]
+    fn test_platform_match_fail() {
+        let node = create_arch_node("node1", "amd64");
+        let pod = create_platform_pod("linux/arm64");
+        let context = SchedulingContext::new(pod, vec![node.clone()]);
+
+        let result = PlatformMatch.filter(&context, &node);
+        assert!(!result.passed);
+        assert!(result.reason.unwrap().contains("node architecture 'amd64'"));
+    }
+
+    #[test]
+    fn test_platform_match_unknown_arch_or_no_annotation() {
+        let node = create_test_node("node1", "4", "8Gi");
+        let pod = create_platform_pod("linux/arm64");
+        let context = SchedulingContext::new(pod, vec![node.clone()]);
+        assert!(PlatformMatch.filter(&context, &node).passed);
+
+        let node = create_arch_node("node1", "amd64");
+        let pod = create_test_pod("1", "1Gi");
+        let context = SchedulingContext::new(pod, vec![node.clone()]);
+        assert!(PlatformMatch.filter(&context, &node).passed);
+    }
+
+    #[test]
+    fn test_host_ports_available() {
+        let node = create_test_node("node1", "4", "8Gi");
+        let mut pod = create_test_pod("1", "1Gi");
+        pod.spec.as_mut().unwrap().containers[0].ports =
+            Some(vec![k8s_openapi::api::core::v1::ContainerPort {
+                container_port: 53,
+                host_port: Some(53),
+                protocol: Some("UDP".to_string()),
+                ..Default::default()
+            }]);
+
+        let used = [(
+            "node1".to_string(),
+            [("TCP".to_string(), 53)].into_iter().collect(),
+        )]
+        .into_iter()
+        .collect();
+        let context =
+            SchedulingContext::new(pod.clone(), vec![node.clone()]).with_used_host_ports(used);
+        // Same port number, different protocol
+        assert!(HostPortsAvailable.filter(&context, &node).passed);
+
+        let used = [(
+            "node1".to_string(),
+            [("UDP".to_string(), 53)].into_iter().collect(),
+        )]
+        .into_iter()
+        .collect();
+        let context = SchedulingContext::new(pod, vec![node.clone()]).with_used_host_ports(used);
+        let result = HostPortsAvailable.filter(&context, &node);
+        assert!(!result.passed);
+        assert!(result.reason.unwrap().contains("53/UDP"));
+    }
+
+    #[test]
+    fn test_node_unschedulable() {
+        use k8s_openapi::api::core::v1::{NodeSpec, Toleration};
+
+        let mut node = create_test_node("node1", "4", "8Gi");
+        let mut pod = Pod {
+            spec: Some(Default::default()),
+            ..Default::default()
+        };
+        let passes = |pod: &Pod, node: &Node| {
+            NodeUnschedulable
+                .filter(&SchedulingContext::new(pod.clone(), vec![]), node)
+                .passed
+        };
+        assert!(passes(&pod, &node));
+
+        node.spec = Some(NodeSpec {
+            unschedulable: Some(true),
+            ..Default::default()
+        });
+        assert!(!passes(&pod, &node));
+
+        pod.spec.as_mut().unwrap().tolerations = Some(vec![Toleration {
+            key: Some(UNSCHEDULABLE_TAINT_KEY.to_string()),
+            operator: Some("Exists".to_string()),
+            effect: Some("NoSchedule".to_string()),
+            ..Default::default()
+        }]);
+        assert!(passes(&pod, &node));
+    }
+
+    #[test]
+    fn test_devices_available() {
+        use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+        let mut node = create_test_node("node1", "4", "8Gi");
+        node.status
+            .as_mut()
+            .unwrap()
+            .allocatable
+            .as_mut()
+            .unwrap()
+            .insert(
+                "devices.reddwarf.io/disk".to_string(),
+                Quantity("2".to_string()),
+            );
+        let mut pod = create_test_pod("1", "1Gi");
+        pod.spec.as_mut().unwrap().containers[0]
+            .resources
+            .as_mut()
+            .unwrap()
+            .requests
+            .as_mut()
+            .unwrap()
+            .insert(
+                "devices.reddwarf.io/disk".to_string(),
+                Quantity("2".to_string()),
+            );
+
+        let context = SchedulingContext::new(pod.clone(), vec![node.clone()]);
+        assert!(DevicesAvailable.filter(&context, &node).passed);
+
+        let used = [(
+            "node1".to_string(),
+            BTreeMap::from([("devices.reddwarf.io/disk".to_string(), 1)]),
+        )]
+        .into_iter()
+        .collect();
+        let context =
+            SchedulingContext::new(pod.clone(), vec![node.clone()]).with_used_devices(used);
+        let result = DevicesAvailable.filter(&context, &node);
+        assert!(!result.passed);
+        assert!(result.reason.unwrap().contains("available 1"));
+
+        // Nodes without the device class never fit
+        let other = create_test_node("node2", "4", "8Gi");
+        assert!(!DevicesAvailable.filter(&context, &other).passed);
+    }
+
+    #[test]
+    fn test_pod_ip_available() {
+        let node = create_test_node("node1", "4", "8Gi");
+        let pod = create_test_pod("1", "1Gi");
+
+        // Nodes the scheduler doesn't reserve addresses on always pass
+        let context = SchedulingContext::new(pod.clone(), vec![node.clone()]);
+        assert!(PodIpAvailable.filter(&context, &node).passed);
+
+        let free = |n: u64| HashMap::from([("node1".to_string(), n)]);
+        let context =
+            SchedulingContext::new(pod.clone(), vec![node.clone()]).with_free_pod_ips(free(3));
+        assert!(PodIpAvailable.filter(&context, &node).passed);
+        let context = SchedulingContext::new(pod, vec![node.clone()]).with_free_pod_ips(free(0));
+        let result = PodIpAvailable.filter(&context, &node);
+        assert!(!result.passed);
+        assert_eq!(
+            result.summary.as_deref(),
+            Some("node(s) had no free pod IPs")
+        );
+    }
 }
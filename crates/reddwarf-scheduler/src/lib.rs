@@ -5,14 +5,20 @@
 //! - Filter predicates (resource requirements, node selectors)
 //! - Scoring functions (least allocated)
 //! - Pod binding to nodes
+//! - A persistent queue of pods backing off after failed attempts
+//! - Dry runs of the pipeline against a snapshot of cluster state
 
 pub mod error;
 pub mod filter;
+pub mod queue;
 pub mod scheduler;
 pub mod score;
+pub mod simulate;
 pub mod types;
 
 // Re-export commonly used types
 pub use error::{Result, SchedulerError};
+pub use queue::{QueuedPod, SchedulingQueue};
 pub use scheduler::Scheduler;
-pub use types::{FilterResult, SchedulingContext, ScoreResult};
+pub use simulate::{ClusterSnapshot, PodPlacement, SimulationReport, Simulator};
+pub use types::{FilterResult, NodeDecision, SchedulingContext, ScoreResult, UnschedulableReasons};
@@ -0,0 +1,223 @@
+use crate::{Result, SchedulerError};
+use chrono::{DateTime, Utc};
+use reddwarf_core::Pod;
+use reddwarf_storage::{KVStore, RedbBackend};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Storage key prefix of the scheduling queue entries
+/// (`schedulingqueue/{namespace}/{name}`)
+pub const SCHEDULING_QUEUE_KEY_PREFIX: &str = "schedulingqueue/";
+
+/// Retry state of a pod that failed to schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPod {
+    /// UID of the pod, so a pod recreated under the same name starts afresh
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// Failed scheduling attempts so far
+    pub attempts: u32,
+    /// Earliest time of the next attempt
+    pub next_attempt_at: DateTime<Utc>,
+    /// Node selected in an earlier attempt whose binding did not complete;
+    /// tried first while it still fits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nominated_node: Option<String>,
+}
+
+/// Pods that failed to schedule, with their backoff and nominated node,
+/// kept in storage so a restarted scheduler resumes where it left off
+/// instead of re-evaluating every pending pod at once
+pub struct SchedulingQueue {
+    storage: Arc<RedbBackend>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl SchedulingQueue {
+    pub fn new(
+        storage: Arc<RedbBackend>,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        Self {
+            storage,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    fn key(namespace: &str, name: &str) -> String {
+        format!("{}{}/{}", SCHEDULING_QUEUE_KEY_PREFIX, namespace, name)
+    }
+
+    fn pod_key(pod: &Pod) -> Option<String> {
+        Some(Self::key(
+            pod.metadata.namespace.as_deref()?,
+            pod.metadata.name.as_deref()?,
+        ))
+    }
+
+    /// The queue entry of `pod`; entries of an earlier pod of the same name
+    /// are ignored
+    pub fn get(&self, pod: &Pod) -> Result<Option<QueuedPod>> {
+        let Some(key) = Self::pod_key(pod) else {
+            return Ok(None);
+        };
+        let Some(data) = self.storage.get(key.as_bytes())? else {
+            return Ok(None);
+        };
+        let entry: QueuedPod = serde_json::from_slice(&data).map_err(|e| {
+            SchedulerError::internal_error(format!("Failed to deserialize queue entry: {}", e))
+        })?;
+        Ok((entry.uid == pod.metadata.uid).then_some(entry))
+    }
+
+    /// Time left before `pod` may be attempted again, if it is backing off
+    pub fn backoff_remaining(&self, pod: &Pod, now: DateTime<Utc>) -> Result<Option<Duration>> {
+        Ok(self
+            .get(pod)?
+            .and_then(|entry| (entry.next_attempt_at - now).to_std().ok())
+            .filter(|remaining| !remaining.is_zero()))
+    }
+
+    /// Record a failed attempt, backing the pod off for the initial delay
+    /// doubled per earlier failure, up to the maximum
+    pub fn record_failure(&self, pod: &Pod, now: DateTime<Utc>) -> Result<QueuedPod> {
+        let mut entry = self.get(pod)?.unwrap_or(QueuedPod {
+            uid: pod.metadata.uid.clone(),
+            attempts: 0,
+            next_attempt_at: now,
+            nominated_node: None,
+        });
+        entry.attempts += 1;
+        let factor = 1u32.checked_shl(entry.attempts - 1).unwrap_or(u32::MAX);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        entry.next_attempt_at = now + chrono::Duration::from_std(backoff).unwrap_or_default();
+        self.put(pod, &entry)?;
+        Ok(entry)
+    }
+
+    /// Remember the node selected for `pod` for its next attempt
+    pub fn nominate(&self, pod: &Pod, node_name: &str, now: DateTime<Utc>) -> Result<()> {
+        let mut entry = self.get(pod)?.unwrap_or(QueuedPod {
+            uid: pod.metadata.uid.clone(),
+            attempts: 0,
+            next_attempt_at: now,
+            nominated_node: None,
+        });
+        entry.nominated_node = Some(node_name.to_string());
+        self.put(pod, &entry)
+    }
+
+    fn put(&self, pod: &Pod, entry: &QueuedPod) -> Result<()> {
+        let Some(key) = Self::pod_key(pod) else {
+            return Ok(());
+        };
+        let data = serde_json::to_vec(entry).map_err(|e| {
+            SchedulerError::internal_error(format!("Failed to serialize queue entry: {}", e))
+        })?;
+        self.storage.put(key.as_bytes(), &data)?;
+        Ok(())
+    }
+
+    /// Forget `pod`, e.g. once it is bound
+    pub fn remove(&self, pod: &Pod) -> Result<()> {
+        if let Some(key) = Self::pod_key(pod) {
+            self.storage.delete(key.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Drop the entries of pods that are no longer pending
+    pub fn retain_pending(&self, pending: &[Pod]) -> Result<()> {
+        let pending: HashSet<String> = pending.iter().filter_map(Self::pod_key).collect();
+        for (key, _) in self.storage.scan(SCHEDULING_QUEUE_KEY_PREFIX.as_bytes())? {
+            if !pending.contains(String::from_utf8_lossy(&key).as_ref()) {
+                self.storage.delete(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of queued pods
+    pub fn len(&self) -> Result<usize> {
+        Ok(self
+            .storage
+            .scan(SCHEDULING_QUEUE_KEY_PREFIX.as_bytes())?
+            .len())
+    }
+
+    /// Whether no pods are queued
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn pod(name: &str, uid: &str) -> Pod {
+        let mut pod = Pod::default();
+        pod.metadata.name = Some(name.to_string());
+        pod.metadata.namespace = Some("default".to_string());
+        pod.metadata.uid = Some(uid.to_string());
+        pod
+    }
+
+    #[test]
+    fn test_queue_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("queue.redb");
+        let queue =
+            |storage| SchedulingQueue::new(storage, Duration::from_secs(1), Duration::from_secs(3));
+        let now = Utc::now();
+
+        {
+            let queue = queue(Arc::new(RedbBackend::new(&db_path).unwrap()));
+            let web = pod("web", "uid-1");
+            for expected in [1, 2, 3, 3] {
+                let entry = queue.record_failure(&web, now).unwrap();
+                assert_eq!(
+                    entry.next_attempt_at - now,
+                    chrono::Duration::seconds(expected)
+                );
+            }
+            queue.nominate(&web, "node1", now).unwrap();
+            queue.record_failure(&pod("db", "uid-2"), now).unwrap();
+        }
+
+        // A restarted scheduler keeps the backoff and nomination
+        let queue = queue(Arc::new(RedbBackend::new(&db_path).unwrap()));
+        let web = pod("web", "uid-1");
+        let entry = queue.get(&web).unwrap().unwrap();
+        assert_eq!(entry.attempts, 4);
+        assert_eq!(entry.nominated_node.as_deref(), Some("node1"));
+        assert_eq!(
+            queue.backoff_remaining(&web, now).unwrap(),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            queue
+                .backoff_remaining(&web, now + chrono::Duration::seconds(3))
+                .unwrap(),
+            None
+        );
+
+        // A recreated pod starts afresh
+        assert_eq!(queue.get(&pod("web", "uid-3")).unwrap(), None);
+
+        queue.retain_pending(std::slice::from_ref(&web)).unwrap();
+        assert_eq!(queue.len().unwrap(), 1);
+        queue.remove(&web).unwrap();
+        assert!(queue.is_empty().unwrap());
+    }
+}
@@ -1,28 +1,62 @@
 use crate::filter::{default_filters, FilterPredicate};
+use crate::queue::SchedulingQueue;
 use crate::score::{calculate_weighted_score, default_scores, ScoreFunction};
-use crate::types::SchedulingContext;
+use crate::types::{NodeDecision, SchedulingContext, UnschedulableReasons};
 use crate::{Result, SchedulerError};
-use reddwarf_core::{Node, Pod, ResourceEvent};
+use chrono::Utc;
+use k8s_openapi::api::core::v1::PodCondition;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use reddwarf_core::annotations::RESERVED_POD_IP_ANNOTATION;
+use reddwarf_core::host_ports::{host_port_owner, HOST_PORT_KEY_PREFIX};
+use reddwarf_core::pod_ips::{
+    first_free_pod_ip, free_pod_ips, pod_ip_from_key, pod_ip_key, pod_ip_owner, POD_IP_KEY_PREFIX,
+};
+use reddwarf_core::recorder::{REASON_FAILED_SCHEDULING, REASON_SCHEDULED};
+use reddwarf_core::resources::{scheduling_order, RUNTIME_CLASS_API_VERSION, RUNTIME_CLASS_KIND};
+use reddwarf_core::startup::{format_timestamp, SCHEDULED_AT_ANNOTATION};
+use reddwarf_core::{
+    pod_device_requests, pod_host_ports, EventBus, EventRecorder, Node, Pod, ResourceEvent,
+    RuntimeClass,
+};
 use reddwarf_storage::{KVStore, KeyEncoder, RedbBackend};
 use reddwarf_versioning::{Change, CommitBuilder, VersionStore};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::Ipv4Addr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::broadcast;
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+/// Pod condition recording whether the pod is bound to a node
+const POD_SCHEDULED: &str = "PodScheduled";
+/// `PodScheduled` reason of a pod held back by its scheduling gates
+const SCHEDULING_GATED: &str = "SchedulingGated";
+pub(crate) const SCHEDULING_GATED_MESSAGE: &str =
+    "Scheduling is blocked due to non-empty scheduling gates";
+
 /// Configuration for the scheduler
 #[derive(Clone)]
 pub struct SchedulerConfig {
     /// Interval between scheduling cycles
     pub schedule_interval: Duration,
+    /// Backoff of a pod after its first failed scheduling attempt, doubled
+    /// per further failure
+    pub initial_backoff: Duration,
+    /// Upper bound of a pod's scheduling backoff
+    pub max_backoff: Duration,
+    /// Reserve each pod's IP out of its node's pod CIDR when binding it, so
+    /// pods are only bound to nodes with an address left for them
+    pub reserve_pod_ips: bool,
 }
 
 impl Default for SchedulerConfig {
     fn default() -> Self {
         Self {
             schedule_interval: Duration::from_secs(1),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            reserve_pod_ips: false,
         }
     }
 }
@@ -31,10 +65,13 @@ impl Default for SchedulerConfig {
 pub struct Scheduler {
     storage: Arc<RedbBackend>,
     version_store: Arc<VersionStore>,
-    event_tx: broadcast::Sender<ResourceEvent>,
+    event_bus: Arc<dyn EventBus>,
     config: SchedulerConfig,
     filters: Vec<Box<dyn FilterPredicate>>,
     scorers: Vec<Box<dyn ScoreFunction>>,
+    /// Pods backing off after failed attempts, persisted across restarts
+    queue: SchedulingQueue,
+    recorder: Option<EventRecorder>,
 }
 
 impl Scheduler {
@@ -42,19 +79,29 @@ impl Scheduler {
     pub fn new(
         storage: Arc<RedbBackend>,
         version_store: Arc<VersionStore>,
-        event_tx: broadcast::Sender<ResourceEvent>,
+        event_bus: Arc<dyn EventBus>,
         config: SchedulerConfig,
     ) -> Self {
+        let queue =
+            SchedulingQueue::new(storage.clone(), config.initial_backoff, config.max_backoff);
         Self {
             storage,
             version_store,
-            event_tx,
+            event_bus,
             config,
             filters: default_filters(),
             scorers: default_scores(),
+            queue,
+            recorder: None,
         }
     }
 
+    /// Record `Scheduled` and `FailedScheduling` events about pods
+    pub fn with_event_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     /// Run the scheduler loop
     pub async fn run(&self, token: CancellationToken) -> Result<()> {
         info!("Starting scheduler");
@@ -78,8 +125,11 @@ impl Scheduler {
     async fn schedule_cycle(&self) -> Result<()> {
         debug!("Running scheduling cycle");
 
-        // Get all unscheduled pods
-        let unscheduled_pods = self.get_unscheduled_pods().await?;
+        // Get all unscheduled pods, highest priority first so they claim
+        // nodes before the pods of lower priority in the same cycle
+        let mut unscheduled_pods = self.get_unscheduled_pods().await?;
+        unscheduled_pods.sort_by(scheduling_order);
+        self.queue.retain_pending(&unscheduled_pods)?;
 
         if unscheduled_pods.is_empty() {
             debug!("No unscheduled pods found");
@@ -93,12 +143,13 @@ impl Scheduler {
 
         if nodes.is_empty() {
             warn!("No nodes available for scheduling");
-            return Ok(());
+        } else {
+            info!("Found {} available nodes", nodes.len());
         }
 
-        info!("Found {} available nodes", nodes.len());
-
-        // Schedule each pod
+        // Schedule each pod that is neither gated nor backing off from an
+        // earlier failure
+        let now = Utc::now();
         for pod in unscheduled_pods {
             let pod_name = pod
                 .metadata
@@ -107,12 +158,58 @@ impl Scheduler {
                 .unwrap_or(&"unknown".to_string())
                 .clone();
 
-            match self.schedule_pod(pod, &nodes).await {
+            if is_gated(&pod) {
+                debug!("Pod {} is waiting for its scheduling gates", pod_name);
+                if let Err(e) =
+                    self.record_not_scheduled(&pod, SCHEDULING_GATED, SCHEDULING_GATED_MESSAGE)
+                {
+                    error!("Failed to record scheduling gates of {}: {}", pod_name, e);
+                }
+                continue;
+            }
+
+            if let Some(remaining) = self.queue.backoff_remaining(&pod, now)? {
+                debug!(
+                    "Pod {} is backing off, next attempt in {:?}",
+                    pod_name, remaining
+                );
+                continue;
+            }
+
+            match self.schedule_pod(pod.clone(), &nodes).await {
                 Ok(node_name) => {
                     info!("Scheduled pod {} to node {}", pod_name, node_name);
+                    if let Some(recorder) = &self.recorder {
+                        recorder.normal(
+                            &pod,
+                            REASON_SCHEDULED,
+                            format!(
+                                "Successfully assigned {}/{} to {}",
+                                pod.metadata.namespace.as_deref().unwrap_or("default"),
+                                pod_name,
+                                node_name
+                            ),
+                        );
+                    }
+                    self.queue.remove(&pod)?;
                 }
                 Err(e) => {
                     error!("Failed to schedule pod {}: {}", pod_name, e);
+                    if let Some(recorder) = &self.recorder {
+                        recorder.warning(&pod, REASON_FAILED_SCHEDULING, e.condition_message());
+                    }
+                    if let Err(e) = self.record_not_scheduled(
+                        &pod,
+                        e.condition_reason(),
+                        &e.condition_message(),
+                    ) {
+                        error!("Failed to record scheduling failure of {}: {}", pod_name, e);
+                    }
+                    let entry = self.queue.record_failure(&pod, Utc::now())?;
+                    debug!(
+                        "Pod {} failed {} attempt(s), next attempt at {}",
+                        pod_name, entry.attempts, entry.next_attempt_at
+                    );
                 }
             }
         }
@@ -160,104 +257,177 @@ impl Scheduler {
         Ok(nodes)
     }
 
-    /// Schedule a single pod
-    async fn schedule_pod(&self, mut pod: Pod, nodes: &[Node]) -> Result<String> {
-        let pod_name = pod
-            .metadata
-            .name
+    /// Get host ports reserved on each node (`hostports/{node}/{protocol}/{port}`)
+    fn get_used_host_ports(&self) -> Result<HashMap<String, HashSet<(String, u16)>>> {
+        let results = self
+            .storage
             .as_ref()
-            .ok_or_else(|| SchedulerError::internal_error("Pod has no name"))?
-            .clone();
+            .scan(HOST_PORT_KEY_PREFIX.as_bytes())?;
+
+        let mut used: HashMap<String, HashSet<(String, u16)>> = HashMap::new();
+        for (key, _owner) in results.iter() {
+            let key = String::from_utf8_lossy(key);
+            let mut parts = key[HOST_PORT_KEY_PREFIX.len()..].split('/');
+            if let (Some(node), Some(protocol), Some(Ok(port))) = (
+                parts.next(),
+                parts.next(),
+                parts.next().map(str::parse::<u16>),
+            ) {
+                used.entry(node.to_string())
+                    .or_default()
+                    .insert((protocol.to_string(), port));
+            }
+        }
 
-        let context = SchedulingContext::new(pod.clone(), nodes.to_vec());
+        Ok(used)
+    }
 
-        // Phase 1: Filter nodes
-        let mut feasible_nodes = Vec::new();
+    /// Get the pod IPs allocated or reserved across the cluster
+    /// (`ipam/alloc/{ip}`)
+    fn get_used_pod_ips(&self) -> Result<HashSet<Ipv4Addr>> {
+        let results = self.storage.as_ref().scan(POD_IP_KEY_PREFIX.as_bytes())?;
+        Ok(results
+            .iter()
+            .filter_map(|(key, _owner)| pod_ip_from_key(&String::from_utf8_lossy(key)))
+            .collect())
+    }
 
-        for node in nodes {
-            let node_name = node
-                .metadata
-                .name
-                .as_ref()
-                .unwrap_or(&"unknown".to_string())
-                .clone();
+    /// Get the pod IPs still free on each node with a pod CIDR, when the
+    /// scheduler reserves them
+    fn get_free_pod_ips(&self, nodes: &[Node]) -> Result<HashMap<String, u64>> {
+        if !self.config.reserve_pod_ips {
+            return Ok(HashMap::new());
+        }
+        let used = self.get_used_pod_ips()?;
+        Ok(nodes
+            .iter()
+            .filter_map(|node| {
+                let name = node.metadata.name.clone()?;
+                let cidr = node.spec.as_ref()?.pod_cidr.as_deref()?;
+                Some((name, free_pod_ips(cidr, &used)))
+            })
+            .collect())
+    }
 
-            let mut passed = true;
+    /// Get devices requested by the pods bound to each node that have not
+    /// finished
+    fn get_used_devices(&self) -> Result<HashMap<String, BTreeMap<String, i64>>> {
+        let prefix = KeyEncoder::encode_prefix("v1", "Pod", None);
+        let results = self.storage.as_ref().scan(prefix.as_bytes())?;
 
-            for filter in &self.filters {
-                let result = filter.filter(&context, node);
-                if !result.passed {
-                    debug!(
-                        "Node {} filtered out by {}: {}",
-                        node_name,
-                        filter.name(),
-                        result.reason.unwrap_or_default()
-                    );
-                    passed = false;
-                    break;
-                }
+        let mut used: HashMap<String, BTreeMap<String, i64>> = HashMap::new();
+        for (_key, data) in results.iter() {
+            let pod: Pod = serde_json::from_slice(data).map_err(|e| {
+                SchedulerError::internal_error(format!("Failed to deserialize pod: {}", e))
+            })?;
+            let Some(node) = pod.spec.as_ref().and_then(|s| s.node_name.clone()) else {
+                continue;
+            };
+            let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+            if matches!(phase, Some("Succeeded") | Some("Failed")) {
+                continue;
             }
-
-            if passed {
-                feasible_nodes.push(node.clone());
+            for (resource, count) in pod_device_requests(&pod) {
+                *used
+                    .entry(node.clone())
+                    .or_default()
+                    .entry(resource)
+                    .or_insert(0) += count;
             }
         }
 
-        if feasible_nodes.is_empty() {
-            return Err(SchedulerError::no_suitable_nodes(
-                pod_name,
-                "All nodes filtered out".to_string(),
-            ));
+        Ok(used)
+    }
+
+    /// Get the handler of each RuntimeClass, by class name
+    fn get_runtime_class_handlers(&self) -> Result<HashMap<String, String>> {
+        let prefix = KeyEncoder::encode_prefix(RUNTIME_CLASS_API_VERSION, RUNTIME_CLASS_KIND, None);
+        let results = self.storage.as_ref().scan(prefix.as_bytes())?;
+
+        let mut handlers = HashMap::new();
+        for (_key, data) in results.iter() {
+            let class: RuntimeClass = serde_json::from_slice(data).map_err(|e| {
+                SchedulerError::internal_error(format!(
+                    "Failed to deserialize runtime class: {}",
+                    e
+                ))
+            })?;
+            if let Some(name) = class.metadata.name {
+                handlers.insert(name, class.handler);
+            }
         }
 
-        info!(
-            "Pod {} has {} feasible nodes",
-            pod_name,
-            feasible_nodes.len()
-        );
+        Ok(handlers)
+    }
 
-        // Phase 2: Score nodes
-        let mut node_scores: Vec<(String, i32)> = Vec::new();
+    /// Schedule a single pod
+    async fn schedule_pod(&self, mut pod: Pod, nodes: &[Node]) -> Result<String> {
+        let pod_name = pod
+            .metadata
+            .name
+            .as_ref()
+            .ok_or_else(|| SchedulerError::internal_error("Pod has no name"))?
+            .clone();
 
-        for node in &feasible_nodes {
-            let node_name = node
-                .metadata
-                .name
-                .as_ref()
-                .unwrap_or(&"unknown".to_string())
-                .clone();
+        let context = SchedulingContext::new(pod.clone(), nodes.to_vec())
+            .with_used_host_ports(self.get_used_host_ports()?)
+            .with_used_devices(self.get_used_devices()?)
+            .with_runtime_class_handlers(self.get_runtime_class_handlers()?)
+            .with_free_pod_ips(self.get_free_pod_ips(nodes)?);
 
-            let mut scores = Vec::new();
+        // Phase 1 and 2: filter, then score the feasible nodes
+        let mut reasons = UnschedulableReasons::new(nodes.len());
+        let decisions = evaluate_nodes(&self.filters, &self.scorers, &context, nodes, &mut reasons);
+        let feasible = decisions.iter().filter(|d| d.is_feasible()).count();
 
-            for scorer in &self.scorers {
-                let score = scorer.score(&context, node);
-                scores.push(score);
-            }
+        if feasible == 0 {
+            return Err(SchedulerError::no_suitable_nodes(pod_name, reasons));
+        }
 
-            let final_score = calculate_weighted_score(&scores);
-            node_scores.push((node_name, final_score));
+        info!("Pod {} has {} feasible nodes", pod_name, feasible);
+
+        // A node nominated by an earlier attempt is kept while it still fits
+        let nominated = self.queue.get(&pod)?.and_then(|e| e.nominated_node);
+        if let Some(node_name) = nominated.filter(|nominated| {
+            decisions
+                .iter()
+                .any(|d| d.is_feasible() && d.node_name == *nominated)
+        }) {
+            info!("Pod {} keeps its nominated node {}", pod_name, node_name);
+            self.bind_or_nominate(&mut pod, &node_name).await?;
+            return Ok(node_name);
         }
 
         // Phase 3: Select best node
-        node_scores.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by score descending
-
-        let best_node = node_scores
-            .first()
-            .ok_or_else(|| SchedulerError::internal_error("No nodes scored"))?
-            .0
-            .clone();
+        let best = best_node(&decisions)
+            .ok_or_else(|| SchedulerError::internal_error("No nodes scored"))?;
+        let best_node = best.node_name.clone();
 
         info!(
             "Selected node {} for pod {} with score {}",
-            best_node, pod_name, node_scores[0].1
+            best_node,
+            pod_name,
+            best.score.unwrap_or_default()
         );
 
         // Phase 4: Bind pod to node
-        self.bind_pod(&mut pod, &best_node).await?;
+        self.bind_or_nominate(&mut pod, &best_node).await?;
 
         Ok(best_node)
     }
 
+    /// Bind a pod to the selected node; if binding fails, nominate the node
+    /// so the next attempt tries it first
+    async fn bind_or_nominate(&self, pod: &mut Pod, node_name: &str) -> Result<()> {
+        let Err(e) = self.bind_pod(pod, node_name).await else {
+            return Ok(());
+        };
+        if let Err(e) = self.queue.nominate(pod, node_name, Utc::now()) {
+            warn!("Failed to nominate node {}: {}", node_name, e);
+        }
+        Err(e)
+    }
+
     /// Bind a pod to a node (update spec.nodeName) with versioning and event publishing
     async fn bind_pod(&self, pod: &mut Pod, node_name: &str) -> Result<()> {
         let pod_name = pod
@@ -297,7 +467,167 @@ impl Scheduler {
         } else {
             return Err(SchedulerError::internal_error("Pod has no spec"));
         }
+        pod.metadata
+            .annotations
+            .get_or_insert_with(Default::default)
+            .insert(
+                SCHEDULED_AT_ANNOTATION.to_string(),
+                format_timestamp(Utc::now()),
+            );
+
+        set_pod_scheduled(pod, "True", None, None);
+
+        let reserved_ip = self.reserve_pod_ip(pod, &namespace, &pod_name, node_name)?;
+
+        let commit_id = match self.persist_pod(
+            &storage_key,
+            pod,
+            &prev_data,
+            format!("Bind pod {} to node {}", pod_name, node_name),
+        ) {
+            Ok(commit_id) => commit_id,
+            Err(e) => {
+                // The pod isn't bound, so its address goes back to the pool
+                if let Some(ip) = reserved_ip {
+                    if let Err(e) = self.storage.as_ref().delete(pod_ip_key(ip).as_bytes()) {
+                        warn!("Failed to release pod IP {} of pod {}: {}", ip, pod_name, e);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        // Reserve host ports so later pods in this and future cycles avoid them
+        let owner = host_port_owner(&namespace, &pod_name);
+        for port in pod_host_ports(pod) {
+            self.storage
+                .as_ref()
+                .put(port.reservation_key(node_name).as_bytes(), owner.as_bytes())?;
+        }
+
+        info!(
+            "Successfully bound pod {} to node {} at version {}",
+            pod_name, node_name, commit_id
+        );
+
+        self.publish_modified(key, pod, commit_id);
+
+        Ok(())
+    }
+
+    /// Reserve an IP for `pod` out of the pod CIDR of `node_name` and record
+    /// it on the pod, so the node's IPAM hands the pod that address. Nothing
+    /// is reserved unless `reserve_pod_ips` is on and the node has a pod
+    /// CIDR.
+    fn reserve_pod_ip(
+        &self,
+        pod: &mut Pod,
+        namespace: &str,
+        pod_name: &str,
+        node_name: &str,
+    ) -> Result<Option<Ipv4Addr>> {
+        if !self.config.reserve_pod_ips {
+            return Ok(None);
+        }
+        let gvk = reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Node");
+        let node_key = KeyEncoder::encode_resource_key(
+            &reddwarf_core::ResourceKey::cluster_scoped(gvk, node_name),
+        );
+        let Some(data) = self.storage.as_ref().get(node_key.as_bytes())? else {
+            return Ok(None);
+        };
+        let node: Node = serde_json::from_slice(&data).map_err(|e| {
+            SchedulerError::internal_error(format!("Failed to deserialize node: {}", e))
+        })?;
+        let Some(cidr) = node.spec.and_then(|s| s.pod_cidr) else {
+            return Ok(None);
+        };
+
+        let ip = first_free_pod_ip(&cidr, &self.get_used_pod_ips()?).ok_or_else(|| {
+            SchedulerError::internal_error(format!(
+                "No free pod IPs left in pod CIDR {} of node {}",
+                cidr, node_name
+            ))
+        })?;
+        self.storage.as_ref().put(
+            pod_ip_key(ip).as_bytes(),
+            pod_ip_owner(namespace, pod_name).as_bytes(),
+        )?;
+        pod.metadata
+            .annotations
+            .get_or_insert_with(Default::default)
+            .insert(RESERVED_POD_IP_ANNOTATION.to_string(), ip.to_string());
+        debug!(
+            "Reserved pod IP {} for pod {} on node {}",
+            ip, pod_name, node_name
+        );
+
+        Ok(Some(ip))
+    }
+
+    /// Record why a pod is not scheduled on its `PodScheduled` condition,
+    /// as kube-scheduler does. Nothing is written while the recorded
+    /// condition is unchanged, so a pod that stays unschedulable doesn't add
+    /// a commit every cycle.
+    fn record_not_scheduled(&self, pod: &Pod, reason: &str, message: &str) -> Result<()> {
+        let (Some(pod_name), Some(namespace)) = (
+            pod.metadata.name.as_deref(),
+            pod.metadata.namespace.as_deref(),
+        ) else {
+            return Ok(());
+        };
+
+        let key = reddwarf_core::ResourceKey::new(
+            reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Pod"),
+            namespace,
+            pod_name,
+        );
+        let storage_key = KeyEncoder::encode_resource_key(&key);
+
+        // Re-read the pod: it may have been deleted or bound meanwhile
+        let Some(prev_data) = self.storage.as_ref().get(storage_key.as_bytes())? else {
+            return Ok(());
+        };
+        let mut current: Pod = serde_json::from_slice(&prev_data).map_err(|e| {
+            SchedulerError::internal_error(format!("Failed to deserialize pod: {}", e))
+        })?;
+        if current
+            .spec
+            .as_ref()
+            .is_some_and(|spec| spec.node_name.is_some())
+        {
+            return Ok(());
+        }
+
+        if !set_pod_scheduled(
+            &mut current,
+            "False",
+            Some(reason),
+            Some(message.to_string()),
+        ) {
+            return Ok(());
+        }
 
+        let commit_id = self.persist_pod(
+            &storage_key,
+            &mut current,
+            &prev_data,
+            format!("Record scheduling failure of pod {}", pod_name),
+        )?;
+        self.publish_modified(key, &current, commit_id);
+
+        Ok(())
+    }
+
+    /// Commit `pod` as an update of `prev_data` and store it at the new
+    /// resource version; returns the commit id
+    fn persist_pod(
+        &self,
+        storage_key: &str,
+        pod: &mut Pod,
+        prev_data: &[u8],
+        message: String,
+    ) -> Result<String> {
         // Serialize new pod
         let new_data = serde_json::to_vec(&pod).map_err(|e| {
             SchedulerError::internal_error(format!("Failed to serialize pod: {}", e))
@@ -305,18 +635,14 @@ impl Scheduler {
 
         // Create a versioned commit
         let change = Change::update(
-            storage_key.clone(),
+            storage_key.to_string(),
             String::from_utf8_lossy(&new_data).to_string(),
-            String::from_utf8_lossy(&prev_data).to_string(),
+            String::from_utf8_lossy(prev_data).to_string(),
         );
 
         let commit = self
             .version_store
-            .create_commit(
-                CommitBuilder::new()
-                    .change(change)
-                    .message(format!("Bind pod {} to node {}", pod_name, node_name)),
-            )
+            .create_commit(CommitBuilder::new().change(change).message(message))
             .map_err(|e| {
                 SchedulerError::internal_error(format!("Failed to create commit: {}", e))
             })?;
@@ -334,40 +660,147 @@ impl Scheduler {
             .as_ref()
             .put(storage_key.as_bytes(), &final_data)?;
 
-        info!(
-            "Successfully bound pod {} to node {} at version {}",
-            pod_name,
-            node_name,
-            commit.id()
-        );
+        Ok(commit.id().to_string())
+    }
 
-        // Publish MODIFIED event (best-effort)
-        if let Ok(object) = serde_json::to_value(&*pod) {
-            let event = ResourceEvent::modified(key, object, commit.id().to_string());
-            let _ = self.event_tx.send(event);
+    /// Publish a MODIFIED event for `pod` (best-effort)
+    fn publish_modified(&self, key: reddwarf_core::ResourceKey, pod: &Pod, commit_id: String) {
+        if let Ok(object) = serde_json::to_value(pod) {
+            let event = ResourceEvent::modified(key, object, commit_id);
+            self.event_bus.publish(event);
         }
+    }
+}
 
-        Ok(())
+/// Run `filters` over `nodes` for the pod of `context`, stopping at the
+/// first that rejects a node, and score the nodes that pass them all;
+/// rejections are counted in `reasons`
+pub(crate) fn evaluate_nodes(
+    filters: &[Box<dyn FilterPredicate>],
+    scorers: &[Box<dyn ScoreFunction>],
+    context: &SchedulingContext,
+    nodes: &[Node],
+    reasons: &mut UnschedulableReasons,
+) -> Vec<NodeDecision> {
+    nodes
+        .iter()
+        .map(|node| {
+            let node_name = node
+                .metadata
+                .name
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string());
+
+            for filter in filters {
+                let result = filter.filter(context, node);
+                if !result.passed {
+                    debug!(
+                        "Node {} filtered out by {}: {}",
+                        node_name,
+                        filter.name(),
+                        result.reason.as_deref().unwrap_or_default()
+                    );
+                    reasons.record(&result);
+                    return NodeDecision {
+                        node_name,
+                        rejected_by: Some(filter.name().to_string()),
+                        reason: result.reason,
+                        score: None,
+                    };
+                }
+            }
+
+            let scores: Vec<_> = scorers.iter().map(|s| s.score(context, node)).collect();
+            NodeDecision {
+                node_name,
+                rejected_by: None,
+                reason: None,
+                score: Some(calculate_weighted_score(&scores)),
+            }
+        })
+        .collect()
+}
+
+/// The feasible node with the highest score, the first of them on a tie
+pub(crate) fn best_node(decisions: &[NodeDecision]) -> Option<&NodeDecision> {
+    decisions
+        .iter()
+        .filter(|d| d.is_feasible())
+        .min_by_key(|d| std::cmp::Reverse(d.score))
+}
+
+/// Whether the pod still has scheduling gates, which keep the scheduler
+/// from placing it until external controllers remove them
+pub(crate) fn is_gated(pod: &Pod) -> bool {
+    pod.spec
+        .as_ref()
+        .and_then(|spec| spec.scheduling_gates.as_ref())
+        .is_some_and(|gates| !gates.is_empty())
+}
+
+/// Set the pod's `PodScheduled` condition, keeping its transition time
+/// while the status is unchanged; returns whether anything changed
+fn set_pod_scheduled(
+    pod: &mut Pod,
+    status: &str,
+    reason: Option<&str>,
+    message: Option<String>,
+) -> bool {
+    let conditions = pod
+        .status
+        .get_or_insert_with(Default::default)
+        .conditions
+        .get_or_insert_with(Vec::new);
+    let existing = conditions.iter().position(|c| c.type_ == POD_SCHEDULED);
+
+    let last_transition_time = match existing.map(|i| &conditions[i]) {
+        Some(c) if c.status == status => {
+            if c.reason.as_deref() == reason && c.message == message {
+                return false;
+            }
+            c.last_transition_time.clone()
+        }
+        _ => Some(Time(Utc::now())),
+    };
+    let condition = PodCondition {
+        type_: POD_SCHEDULED.to_string(),
+        status: status.to_string(),
+        reason: reason.map(str::to_string),
+        message,
+        last_transition_time,
+        ..Default::default()
+    };
+    match existing {
+        Some(i) => conditions[i] = condition,
+        None => conditions.push(condition),
     }
+    true
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use reddwarf_core::WatchEventType;
+    use k8s_openapi::api::core::v1::PodSchedulingGate;
+    use reddwarf_core::{InProcessEventBus, MemoryEventSink, WatchEventType};
     use reddwarf_storage::RedbBackend;
     use reddwarf_versioning::VersionStore;
     use std::collections::BTreeMap;
     use tempfile::tempdir;
+    use tokio::sync::broadcast;
 
     fn create_test_scheduler() -> (Scheduler, broadcast::Receiver<ResourceEvent>) {
         let dir = tempdir().unwrap();
         let db_path = dir.path().join("test.redb");
         let storage = Arc::new(RedbBackend::new(&db_path).unwrap());
         let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
-        let (event_tx, event_rx) = broadcast::channel(64);
-        let scheduler =
-            Scheduler::new(storage, version_store, event_tx, SchedulerConfig::default());
+        let event_bus = Arc::new(InProcessEventBus::new(64));
+        let event_rx = event_bus.subscribe();
+        let scheduler = Scheduler::new(
+            storage,
+            version_store,
+            event_bus,
+            SchedulerConfig::default(),
+        );
         (scheduler, event_rx)
     }
 
@@ -491,6 +924,132 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_unschedulable_reasons_recorded_on_pod() {
+        let (scheduler, mut rx) = create_test_scheduler();
+        let sink = Arc::new(MemoryEventSink::new());
+        let scheduler =
+            scheduler.with_event_recorder(EventRecorder::new(sink.clone(), "default-scheduler"));
+
+        let mut labelled = create_test_node("node3", "8", "16Gi");
+        labelled.metadata.labels = Some([("disk".to_string(), "hdd".to_string())].into());
+        let nodes = vec![
+            create_test_node("node1", "1", "8Gi"),
+            create_test_node("node2", "1", "8Gi"),
+            labelled,
+        ];
+        for node in &nodes {
+            let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::cluster_scoped(
+                reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Node"),
+                node.metadata.name.as_deref().unwrap(),
+            ));
+            scheduler
+                .storage
+                .as_ref()
+                .put(key.as_bytes(), &serde_json::to_vec(node).unwrap())
+                .unwrap();
+        }
+
+        let mut pod = create_test_pod("big-pod", "default", "2", "1Gi");
+        pod.spec.as_mut().unwrap().node_selector =
+            Some([("disk".to_string(), "ssd".to_string())].into());
+        store_pod(&scheduler, &pod);
+
+        let err = scheduler.schedule_pod(pod, &nodes).await.unwrap_err();
+        let message = "0/3 nodes are available: 2 Insufficient cpu, \
+                       1 node(s) didn't match Pod's node affinity/selector.";
+        assert_eq!(err.condition_reason(), "Unschedulable");
+        assert_eq!(err.condition_message(), message);
+
+        let read_pod = || -> Pod {
+            let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::new(
+                reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Pod"),
+                "default",
+                "big-pod",
+            ));
+            let data = scheduler.storage.as_ref().get(key.as_bytes()).unwrap();
+            serde_json::from_slice(&data.unwrap()).unwrap()
+        };
+
+        scheduler.schedule_cycle().await.unwrap();
+        let recorded = read_pod();
+        let condition = &recorded
+            .status
+            .as_ref()
+            .unwrap()
+            .conditions
+            .as_ref()
+            .unwrap()[0];
+        assert_eq!(condition.type_, "PodScheduled");
+        assert_eq!(condition.status, "False");
+        assert_eq!(condition.reason.as_deref(), Some("Unschedulable"));
+        assert_eq!(condition.message.as_deref(), Some(message));
+        assert!(matches!(
+            rx.try_recv().unwrap().event_type,
+            WatchEventType::Modified
+        ));
+        let events = sink.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].reason.as_deref(), Some(REASON_FAILED_SCHEDULING));
+        assert_eq!(events[0].message.as_deref(), Some(message));
+        assert_eq!(events[0].involved_object.name.as_deref(), Some("big-pod"));
+
+        // An unchanged failure is not written again
+        scheduler.schedule_cycle().await.unwrap();
+        assert_eq!(
+            read_pod().metadata.resource_version,
+            recorded.metadata.resource_version
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gated_pod_waits_for_its_gates() {
+        let (scheduler, _rx) = create_test_scheduler();
+
+        let node = create_test_node("node1", "4", "8Gi");
+        let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::cluster_scoped(
+            reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Node"),
+            "node1",
+        ));
+        scheduler
+            .storage
+            .as_ref()
+            .put(key.as_bytes(), &serde_json::to_vec(&node).unwrap())
+            .unwrap();
+
+        let mut pod = create_test_pod("gated", "default", "1", "1Gi");
+        pod.spec.as_mut().unwrap().scheduling_gates = Some(vec![PodSchedulingGate {
+            name: "quota".to_string(),
+        }]);
+        store_pod(&scheduler, &pod);
+
+        let read_pod = || -> Pod {
+            let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::new(
+                reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Pod"),
+                "default",
+                "gated",
+            ));
+            let data = scheduler.storage.as_ref().get(key.as_bytes()).unwrap();
+            serde_json::from_slice(&data.unwrap()).unwrap()
+        };
+
+        scheduler.schedule_cycle().await.unwrap();
+        let gated = read_pod();
+        assert_eq!(gated.spec.as_ref().unwrap().node_name, None);
+        let condition = &gated.status.as_ref().unwrap().conditions.as_ref().unwrap()[0];
+        assert_eq!(condition.status, "False");
+        assert_eq!(condition.reason.as_deref(), Some(SCHEDULING_GATED));
+        assert!(scheduler.queue.is_empty().unwrap());
+
+        // Removing the last gate makes the pod schedulable
+        let mut ungated = gated;
+        ungated.spec.as_mut().unwrap().scheduling_gates = Some(vec![]);
+        store_pod(&scheduler, &ungated);
+        scheduler.schedule_cycle().await.unwrap();
+        assert_eq!(read_pod().spec.unwrap().node_name.as_deref(), Some("node1"));
+    }
+
     #[tokio::test]
     async fn test_bind_pod_publishes_modified_event() {
         let (scheduler, mut rx) = create_test_scheduler();
@@ -507,6 +1066,9 @@ mod tests {
 
         // Verify the event object has the updated node name
         let bound_pod: Pod = serde_json::from_value(event.object).unwrap();
+        assert!(reddwarf_core::PodStartup::from_pod(&bound_pod)
+            .scheduled
+            .is_some());
         assert_eq!(bound_pod.spec.unwrap().node_name, Some("node1".to_string()));
     }
 
@@ -522,4 +1084,194 @@ mod tests {
         assert!(pod.metadata.resource_version.is_some());
         assert!(!pod.metadata.resource_version.as_ref().unwrap().is_empty());
     }
+
+    #[tokio::test]
+    async fn test_host_port_reserved_at_bind_blocks_second_pod() {
+        let (scheduler, _rx) = create_test_scheduler();
+        let nodes = vec![create_test_node("node1", "4", "8Gi")];
+
+        let with_host_port = |name: &str| {
+            let mut pod = create_test_pod(name, "default", "100m", "128Mi");
+            pod.spec.as_mut().unwrap().containers[0].ports =
+                Some(vec![k8s_openapi::api::core::v1::ContainerPort {
+                    container_port: 8080,
+                    host_port: Some(80),
+                    ..Default::default()
+                }]);
+            pod
+        };
+
+        let first = with_host_port("first");
+        store_pod(&scheduler, &first);
+        assert_eq!(
+            scheduler.schedule_pod(first, &nodes).await.unwrap(),
+            "node1"
+        );
+
+        let used = scheduler.get_used_host_ports().unwrap();
+        assert!(used["node1"].contains(&("TCP".to_string(), 80)));
+
+        let second = with_host_port("second");
+        store_pod(&scheduler, &second);
+        assert!(scheduler.schedule_pod(second, &nodes).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_pod_scheduled_first() {
+        let (scheduler, _rx) = create_test_scheduler();
+
+        let node = create_test_node("node1", "4", "8Gi");
+        let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::cluster_scoped(
+            reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Node"),
+            "node1",
+        ));
+        scheduler
+            .storage
+            .as_ref()
+            .put(key.as_bytes(), &serde_json::to_vec(&node).unwrap())
+            .unwrap();
+
+        // Both want the node's only host port 80; "a-low" is listed first
+        for (name, priority) in [("a-low", 0), ("b-high", 1000)] {
+            let mut pod = create_test_pod(name, "default", "100m", "128Mi");
+            let spec = pod.spec.as_mut().unwrap();
+            spec.priority = Some(priority);
+            spec.containers[0].ports = Some(vec![k8s_openapi::api::core::v1::ContainerPort {
+                container_port: 8080,
+                host_port: Some(80),
+                ..Default::default()
+            }]);
+            store_pod(&scheduler, &pod);
+        }
+
+        scheduler.schedule_cycle().await.unwrap();
+        let node_of = |name: &str| {
+            let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::new(
+                reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Pod"),
+                "default",
+                name,
+            ));
+            let data = scheduler.storage.as_ref().get(key.as_bytes()).unwrap();
+            let pod: Pod = serde_json::from_slice(&data.unwrap()).unwrap();
+            pod.spec.unwrap().node_name
+        };
+        assert_eq!(node_of("b-high").as_deref(), Some("node1"));
+        assert_eq!(node_of("a-low"), None);
+    }
+
+    #[tokio::test]
+    async fn test_pod_ip_reserved_at_bind() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let config = SchedulerConfig {
+            reserve_pod_ips: true,
+            ..Default::default()
+        };
+        let scheduler = Scheduler::new(
+            storage,
+            version_store,
+            Arc::new(InProcessEventBus::new(64)),
+            config,
+        );
+
+        // A /30 pod CIDR holds a single pod
+        let mut node = create_test_node("node1", "4", "8Gi");
+        node.spec = Some(k8s_openapi::api::core::v1::NodeSpec {
+            pod_cidr: Some("10.88.0.0/30".to_string()),
+            ..Default::default()
+        });
+        let gvk = reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Node");
+        let node_key = KeyEncoder::encode_resource_key(
+            &reddwarf_core::ResourceKey::cluster_scoped(gvk, "node1"),
+        );
+        scheduler
+            .storage
+            .as_ref()
+            .put(node_key.as_bytes(), &serde_json::to_vec(&node).unwrap())
+            .unwrap();
+        let nodes = vec![node];
+
+        let mut first = create_test_pod("first", "default", "100m", "128Mi");
+        store_pod(&scheduler, &first);
+        scheduler.bind_pod(&mut first, "node1").await.unwrap();
+        assert_eq!(
+            reddwarf_core::annotations::reserved_pod_ip(&first.metadata),
+            Some(Ipv4Addr::new(10, 88, 0, 2))
+        );
+        let owner = scheduler
+            .storage
+            .as_ref()
+            .get(pod_ip_key(Ipv4Addr::new(10, 88, 0, 2)).as_bytes())
+            .unwrap();
+        assert_eq!(owner.as_deref(), Some(&b"default/first"[..]));
+
+        // The pool is exhausted, so the second pod is not bound anywhere
+        let second = create_test_pod("second", "default", "100m", "128Mi");
+        store_pod(&scheduler, &second);
+        let err = scheduler.schedule_pod(second, &nodes).await.unwrap_err();
+        assert!(err.to_string().contains("no free pod IPs"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_backoff_and_nomination_survive_restart() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let version_store = Arc::new(VersionStore::new(storage.clone()).unwrap());
+        let start = || {
+            let config = SchedulerConfig {
+                initial_backoff: Duration::from_secs(60),
+                ..Default::default()
+            };
+            let event_bus = Arc::new(InProcessEventBus::new(64));
+            Scheduler::new(storage.clone(), version_store.clone(), event_bus, config)
+        };
+        let store_node = |scheduler: &Scheduler, node: &Node| {
+            let key = KeyEncoder::encode_resource_key(&reddwarf_core::ResourceKey::cluster_scoped(
+                reddwarf_core::GroupVersionKind::from_api_version_kind("v1", "Node"),
+                node.metadata.name.as_deref().unwrap(),
+            ));
+            scheduler
+                .storage
+                .as_ref()
+                .put(key.as_bytes(), &serde_json::to_vec(node).unwrap())
+                .unwrap();
+        };
+
+        let scheduler = start();
+        store_node(&scheduler, &create_test_node("small", "1", "1Gi"));
+        let pod = create_test_pod("big-pod", "default", "4", "4Gi");
+        store_pod(&scheduler, &pod);
+        scheduler.schedule_cycle().await.unwrap();
+        assert_eq!(scheduler.queue.get(&pod).unwrap().unwrap().attempts, 1);
+
+        // After a restart the pod keeps backing off even though it now fits
+        let scheduler = start();
+        store_node(&scheduler, &create_test_node("large", "8", "16Gi"));
+        scheduler.schedule_cycle().await.unwrap();
+        let entry = scheduler.queue.get(&pod).unwrap().unwrap();
+        assert_eq!(entry.attempts, 1);
+        assert!(scheduler
+            .get_unscheduled_pods()
+            .await
+            .unwrap()
+            .iter()
+            .any(|p| p.metadata.name == pod.metadata.name));
+
+        // A nominated node is preferred over the best-scored one
+        let nodes = vec![
+            create_test_node("node1", "8", "16Gi"),
+            create_test_node("node2", "64", "256Gi"),
+        ];
+        let small = create_test_pod("small-pod", "default", "1", "1Gi");
+        store_pod(&scheduler, &small);
+        scheduler
+            .queue
+            .nominate(&small, "node1", Utc::now())
+            .unwrap();
+        assert_eq!(
+            scheduler.schedule_pod(small, &nodes).await.unwrap(),
+            "node1"
+        );
+    }
 }
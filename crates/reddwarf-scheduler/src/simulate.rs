@@ -0,0 +1,366 @@
+//! Dry runs of the scheduling pipeline against a snapshot of cluster state
+//!
+//! The simulator runs the same filters and scores as the [`Scheduler`]
+//! but binds nothing: each pod placed is only charged to its node in the
+//! snapshot, so the pods after it see that node's remaining room. Unlike the
+//! live scheduler, which compares a pod against a node's whole allocatable
+//! CPU and memory, the simulation also charges the requests of the pods the
+//! snapshot already has bound, which is what capacity planning needs.
+//!
+//! [`Scheduler`]: crate::Scheduler
+
+use crate::filter::{default_filters, FilterPredicate};
+use crate::scheduler::{best_node, evaluate_nodes, is_gated, SCHEDULING_GATED_MESSAGE};
+use crate::score::{default_scores, ScoreFunction};
+use crate::types::{NodeDecision, ResourceQuantities, SchedulingContext, UnschedulableReasons};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use reddwarf_core::{pod_device_requests, pod_host_ports, Node, Pod, RuntimeClass};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Cluster state a simulation starts from
+#[derive(Debug, Clone, Default)]
+pub struct ClusterSnapshot {
+    /// Nodes pods may be placed on
+    pub nodes: Vec<Node>,
+    /// Pods already in the cluster; those bound to a node and not finished
+    /// hold their requests, host ports and devices there
+    pub pods: Vec<Pod>,
+    /// RuntimeClasses pods may name
+    pub runtime_classes: Vec<RuntimeClass>,
+}
+
+/// Where a simulated pod would go, and how every node fared for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodPlacement {
+    pub namespace: String,
+    pub name: String,
+    /// Node the pod would be bound to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// Why the pod would not be scheduled, or that it already names a node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Decision for each node, in snapshot order; empty when the pipeline
+    /// did not run for the pod
+    pub nodes: Vec<NodeDecision>,
+}
+
+/// Outcome of simulating a set of pods, in the order they were given
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SimulationReport {
+    pub placements: Vec<PodPlacement>,
+}
+
+impl SimulationReport {
+    /// Number of pods that would be placed on a node
+    pub fn scheduled(&self) -> usize {
+        self.placements
+            .iter()
+            .filter(|p| p.node_name.is_some())
+            .count()
+    }
+
+    /// Number of pods that fit no node, or wait for scheduling gates
+    pub fn unschedulable(&self) -> usize {
+        self.placements.len() - self.scheduled()
+    }
+}
+
+/// What the pods bound to a node hold there
+#[derive(Debug, Default)]
+struct NodeUsage {
+    requests: ResourceQuantities,
+    host_ports: HashSet<(String, u16)>,
+    devices: BTreeMap<String, i64>,
+}
+
+impl NodeUsage {
+    fn charge(&mut self, pod: &Pod) {
+        if let Some(spec) = &pod.spec {
+            let requests = ResourceQuantities::pod_requests(spec);
+            self.requests.cpu_millicores += requests.cpu_millicores;
+            self.requests.memory_bytes += requests.memory_bytes;
+        }
+        for port in pod_host_ports(pod) {
+            self.host_ports.insert((port.protocol, port.host_port));
+        }
+        for (resource, count) in pod_device_requests(pod) {
+            *self.devices.entry(resource).or_insert(0) += count;
+        }
+    }
+}
+
+/// Runs the scheduling pipeline over pods without binding them
+pub struct Simulator {
+    filters: Vec<Box<dyn FilterPredicate>>,
+    scorers: Vec<Box<dyn ScoreFunction>>,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    /// A simulator running the scheduler's default filters and scores
+    pub fn new() -> Self {
+        Self {
+            filters: default_filters(),
+            scorers: default_scores(),
+        }
+    }
+
+    /// Place `pods` one after another on the nodes of `snapshot`
+    ///
+    /// Pods that already name a node are charged to it as they are, and
+    /// gated pods are reported as unschedulable.
+    pub fn run(&self, snapshot: &ClusterSnapshot, pods: &[Pod]) -> SimulationReport {
+        let mut usage: HashMap<String, NodeUsage> = HashMap::new();
+        for pod in snapshot.pods.iter().filter(|p| is_active(p)) {
+            if let Some(node) = bound_node(pod) {
+                usage.entry(node.to_string()).or_default().charge(pod);
+            }
+        }
+        let handlers: HashMap<String, String> = snapshot
+            .runtime_classes
+            .iter()
+            .filter_map(|c| Some((c.metadata.name.clone()?, c.handler.clone())))
+            .collect();
+
+        let mut report = SimulationReport::default();
+        for pod in pods {
+            let mut placement = PodPlacement {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .clone()
+                    .unwrap_or_else(|| "default".to_string()),
+                name: pod.metadata.name.clone().unwrap_or_default(),
+                node_name: None,
+                message: None,
+                nodes: Vec::new(),
+            };
+
+            if let Some(node) = bound_node(pod) {
+                usage.entry(node.to_string()).or_default().charge(pod);
+                placement.node_name = Some(node.to_string());
+                placement.message = Some(format!("Already bound to {}", node));
+            } else if is_gated(pod) {
+                placement.message = Some(SCHEDULING_GATED_MESSAGE.to_string());
+            } else {
+                let nodes: Vec<Node> = snapshot
+                    .nodes
+                    .iter()
+                    .map(|node| remaining(node, &usage))
+                    .collect();
+                let context = SchedulingContext::new(pod.clone(), nodes.clone())
+                    .with_used_host_ports(
+                        usage
+                            .iter()
+                            .map(|(node, u)| (node.clone(), u.host_ports.clone()))
+                            .collect(),
+                    )
+                    .with_used_devices(
+                        usage
+                            .iter()
+                            .map(|(node, u)| (node.clone(), u.devices.clone()))
+                            .collect(),
+                    )
+                    .with_runtime_class_handlers(handlers.clone());
+
+                let mut reasons = UnschedulableReasons::new(nodes.len());
+                placement.nodes =
+                    evaluate_nodes(&self.filters, &self.scorers, &context, &nodes, &mut reasons);
+                match best_node(&placement.nodes) {
+                    Some(best) => {
+                        let node = best.node_name.clone();
+                        usage.entry(node.clone()).or_default().charge(pod);
+                        placement.node_name = Some(node);
+                    }
+                    None => placement.message = Some(reasons.to_string()),
+                }
+            }
+            report.placements.push(placement);
+        }
+        report
+    }
+}
+
+fn bound_node(pod: &Pod) -> Option<&str> {
+    pod.spec.as_ref().and_then(|s| s.node_name.as_deref())
+}
+
+/// Whether `pod` still holds what it requested: not finished
+fn is_active(pod: &Pod) -> bool {
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    !matches!(phase, Some("Succeeded") | Some("Failed"))
+}
+
+/// `node` with the CPU and memory its pods request taken out of its
+/// allocatable
+fn remaining(node: &Node, usage: &HashMap<String, NodeUsage>) -> Node {
+    let mut node = node.clone();
+    let used = node
+        .metadata
+        .name
+        .as_ref()
+        .and_then(|name| usage.get(name))
+        .map(|u| u.requests.clone())
+        .unwrap_or_default();
+    if let Some(allocatable) = node.status.as_mut().and_then(|s| s.allocatable.as_mut()) {
+        let total = ResourceQuantities::from_k8s_resource_map(allocatable);
+        allocatable.insert(
+            "cpu".to_string(),
+            Quantity(format!(
+                "{}m",
+                (total.cpu_millicores - used.cpu_millicores).max(0)
+            )),
+        );
+        allocatable.insert(
+            "memory".to_string(),
+            Quantity((total.memory_bytes - used.memory_bytes).max(0).to_string()),
+        );
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{
+        Container, NodeStatus, PodSchedulingGate, PodSpec, ResourceRequirements,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    fn make_node(name: &str, cpu: &str, memory: &str) -> Node {
+        Node {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            status: Some(NodeStatus {
+                allocatable: Some(BTreeMap::from([
+                    ("cpu".to_string(), Quantity(cpu.to_string())),
+                    ("memory".to_string(), Quantity(memory.to_string())),
+                ])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn make_pod(name: &str, cpu: &str, memory: &str) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some("default".to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "app".to_string(),
+                    resources: Some(ResourceRequirements {
+                        requests: Some(BTreeMap::from([
+                            ("cpu".to_string(), Quantity(cpu.to_string())),
+                            ("memory".to_string(), Quantity(memory.to_string())),
+                        ])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_placed_pods_use_up_nodes() {
+        let snapshot = ClusterSnapshot {
+            nodes: vec![
+                make_node("node1", "2", "4Gi"),
+                make_node("node2", "2", "4Gi"),
+            ],
+            ..Default::default()
+        };
+        let pods: Vec<Pod> = (0..5)
+            .map(|i| make_pod(&format!("web-{}", i), "1", "1Gi"))
+            .collect();
+
+        let report = Simulator::new().run(&snapshot, &pods);
+
+        assert_eq!(report.scheduled(), 4);
+        assert_eq!(report.unschedulable(), 1);
+        // Least allocated spreads the pods over both nodes
+        let on_node1 = report
+            .placements
+            .iter()
+            .filter(|p| p.node_name.as_deref() == Some("node1"))
+            .count();
+        assert_eq!(on_node1, 2);
+
+        let last = &report.placements[4];
+        assert_eq!(
+            last.message.as_deref(),
+            Some("0/2 nodes are available: 2 Insufficient cpu.")
+        );
+        assert!(last
+            .nodes
+            .iter()
+            .all(|d| d.rejected_by.as_deref() == Some("PodFitsResources")));
+    }
+
+    #[test]
+    fn test_bound_pods_are_charged() {
+        let mut running = make_pod("db", "1500m", "1Gi");
+        running.spec.as_mut().unwrap().node_name = Some("node1".to_string());
+        let mut finished = make_pod("job", "2", "1Gi");
+        finished.spec.as_mut().unwrap().node_name = Some("node2".to_string());
+        finished.status = Some(k8s_openapi::api::core::v1::PodStatus {
+            phase: Some("Succeeded".to_string()),
+            ..Default::default()
+        });
+        let snapshot = ClusterSnapshot {
+            nodes: vec![
+                make_node("node1", "2", "4Gi"),
+                make_node("node2", "2", "4Gi"),
+            ],
+            pods: vec![running, finished],
+            ..Default::default()
+        };
+
+        let report = Simulator::new().run(&snapshot, &[make_pod("web", "1", "1Gi")]);
+
+        let placement = &report.placements[0];
+        assert_eq!(placement.node_name.as_deref(), Some("node2"));
+        assert_eq!(placement.nodes[0].node_name, "node1");
+        assert_eq!(
+            placement.nodes[0].rejected_by.as_deref(),
+            Some("PodFitsResources")
+        );
+    }
+
+    #[test]
+    fn test_gated_pods_are_not_placed() {
+        let snapshot = ClusterSnapshot {
+            nodes: vec![make_node("node1", "2", "4Gi")],
+            ..Default::default()
+        };
+        let mut gated = make_pod("web", "1", "1Gi");
+        gated.spec.as_mut().unwrap().scheduling_gates = Some(vec![PodSchedulingGate {
+            name: "example.com/quota".to_string(),
+        }]);
+
+        let report = Simulator::new().run(&snapshot, &[gated]);
+
+        assert_eq!(report.unschedulable(), 1);
+        assert_eq!(
+            report.placements[0].message.as_deref(),
+            Some(SCHEDULING_GATED_MESSAGE)
+        );
+        assert!(report.placements[0].nodes.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
 pub use reddwarf_core::ResourceQuantities;
 use reddwarf_core::{Node, Pod};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
 /// Scheduling context containing pod and available nodes
 #[derive(Debug, Clone)]
@@ -8,12 +11,58 @@ pub struct SchedulingContext {
     pub pod: Pod,
     /// Available nodes
     pub nodes: Vec<Node>,
+    /// Host ports already reserved on each node, as `(protocol, port)`
+    pub used_host_ports: HashMap<String, HashSet<(String, u16)>>,
+    /// Devices already requested by pods on each node, by resource name
+    pub used_devices: HashMap<String, BTreeMap<String, i64>>,
+    /// Handlers (zone brands) of the known RuntimeClasses, by class name
+    pub runtime_class_handlers: HashMap<String, String>,
+    /// Pod IPs still free on each node whose addresses the scheduler
+    /// reserves; nodes it doesn't reserve for are absent
+    pub free_pod_ips: HashMap<String, u64>,
 }
 
 impl SchedulingContext {
     /// Create a new scheduling context
     pub fn new(pod: Pod, nodes: Vec<Node>) -> Self {
-        Self { pod, nodes }
+        Self {
+            pod,
+            nodes,
+            used_host_ports: HashMap::new(),
+            used_devices: HashMap::new(),
+            runtime_class_handlers: HashMap::new(),
+            free_pod_ips: HashMap::new(),
+        }
+    }
+
+    /// Set the host ports already reserved on each node
+    pub fn with_used_host_ports(
+        mut self,
+        used_host_ports: HashMap<String, HashSet<(String, u16)>>,
+    ) -> Self {
+        self.used_host_ports = used_host_ports;
+        self
+    }
+
+    /// Set the devices already requested by pods on each node
+    pub fn with_used_devices(
+        mut self,
+        used_devices: HashMap<String, BTreeMap<String, i64>>,
+    ) -> Self {
+        self.used_devices = used_devices;
+        self
+    }
+
+    /// Set the handlers of the known RuntimeClasses
+    pub fn with_runtime_class_handlers(mut self, handlers: HashMap<String, String>) -> Self {
+        self.runtime_class_handlers = handlers;
+        self
+    }
+
+    /// Set the pod IPs still free on each node
+    pub fn with_free_pod_ips(mut self, free_pod_ips: HashMap<String, u64>) -> Self {
+        self.free_pod_ips = free_pod_ips;
+        self
     }
 }
 
@@ -26,6 +75,9 @@ pub struct FilterResult {
     pub passed: bool,
     /// Reason for failure (if any)
     pub reason: Option<String>,
+    /// Short, node-independent form of the reason that failures are counted
+    /// by, e.g. "Insufficient cpu"; defaults to the reason
+    pub summary: Option<String>,
 }
 
 impl FilterResult {
@@ -35,6 +87,7 @@ impl FilterResult {
             node_name,
             passed: true,
             reason: None,
+            summary: None,
         }
     }
 
@@ -44,8 +97,64 @@ impl FilterResult {
             node_name,
             passed: false,
             reason: Some(reason),
+            summary: None,
+        }
+    }
+
+    /// Set the summary failures are counted by
+    pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+}
+
+/// Why a pod fits no node, as kube-scheduler reports it: how many of the
+/// nodes considered were rejected for each reason
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnschedulableReasons {
+    /// Number of nodes considered
+    pub nodes: usize,
+    /// Number of nodes rejected, by failure summary
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl UnschedulableReasons {
+    /// No rejections yet out of `nodes` nodes
+    pub fn new(nodes: usize) -> Self {
+        Self {
+            nodes,
+            counts: BTreeMap::new(),
         }
     }
+
+    /// Count a failed filter result under its summary
+    pub fn record(&mut self, result: &FilterResult) {
+        let reason = result
+            .summary
+            .clone()
+            .or_else(|| result.reason.clone())
+            .unwrap_or_else(|| "unknown reason".to_string());
+        *self.counts.entry(reason).or_insert(0) += 1;
+    }
+}
+
+impl fmt::Display for UnschedulableReasons {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nodes == 0 {
+            return write!(f, "no nodes available to schedule pods");
+        }
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(reason, count)| format!("{} {}", count, reason))
+            .collect();
+        write!(
+            f,
+            "0/{} nodes are available: {}.",
+            self.nodes,
+            counts.join(", ")
+        )
+    }
 }
 
 /// Result of scoring a node
@@ -64,6 +173,31 @@ impl ScoreResult {
     }
 }
 
+/// How a node fared for a pod: the filter that rejected it, or the score
+/// it got when it passed them all
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDecision {
+    /// Node name
+    pub node_name: String,
+    /// Filter that rejected the node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected_by: Option<String>,
+    /// Why the filter rejected the node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Weighted score of a feasible node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i32>,
+}
+
+impl NodeDecision {
+    /// Whether the node passed every filter
+    pub fn is_feasible(&self) -> bool {
+        self.score.is_some()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,4 +212,31 @@ mod tests {
         assert!(!fail.passed);
         assert_eq!(fail.reason, Some("Insufficient CPU".to_string()));
     }
+
+    #[test]
+    fn test_unschedulable_reasons_message() {
+        assert_eq!(
+            UnschedulableReasons::new(0).to_string(),
+            "no nodes available to schedule pods"
+        );
+
+        let mut reasons = UnschedulableReasons::new(3);
+        for node in ["node1", "node2"] {
+            reasons.record(
+                &FilterResult::fail(
+                    node.to_string(),
+                    "Insufficient CPU: 2000 > 1000".to_string(),
+                )
+                .with_summary("Insufficient cpu"),
+            );
+        }
+        reasons.record(&FilterResult::fail(
+            "node3".to_string(),
+            "Pod has no spec".to_string(),
+        ));
+        assert_eq!(
+            reasons.to_string(),
+            "0/3 nodes are available: 2 Insufficient cpu, 1 Pod has no spec."
+        );
+    }
 }
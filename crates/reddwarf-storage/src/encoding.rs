@@ -135,6 +135,46 @@ impl IndexKey {
         }
     }
 
+    /// Index entries of a stored object: its namespace and each of its
+    /// labels. Empty for values that are not Kubernetes-style objects.
+    pub fn for_object(object: &serde_json::Value) -> Vec<IndexKey> {
+        let str_field = |value: &serde_json::Value, field: &str| {
+            value.get(field).and_then(|v| v.as_str()).map(String::from)
+        };
+        let metadata = object.get("metadata").unwrap_or(&serde_json::Value::Null);
+        let (Some(api_version), Some(kind), Some(name)) = (
+            str_field(object, "apiVersion"),
+            str_field(object, "kind"),
+            str_field(metadata, "name"),
+        ) else {
+            return Vec::new();
+        };
+        let namespace = str_field(metadata, "namespace");
+
+        let mut keys = Vec::new();
+        if let Some(ns) = &namespace {
+            keys.push(IndexKey::Namespace {
+                namespace: ns.clone(),
+                api_version: api_version.clone(),
+                kind: kind.clone(),
+                name: name.clone(),
+            });
+        }
+        if let Some(labels) = metadata.get("labels").and_then(|l| l.as_object()) {
+            for (key, value) in labels {
+                keys.push(IndexKey::Label {
+                    key: key.clone(),
+                    value: value.as_str().unwrap_or_default().to_string(),
+                    api_version: api_version.clone(),
+                    kind: kind.clone(),
+                    namespace: namespace.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        keys
+    }
+
     /// Encode a prefix for scanning
     pub fn encode_prefix_for_namespace(namespace: &str) -> String {
         format!("namespace/{}/", namespace)
@@ -232,4 +272,26 @@ mod tests {
         };
         assert_eq!(key.encode(), "label/app/nginx/v1/Pod/default/nginx-pod");
     }
+
+    #[test]
+    fn test_index_keys_for_object() {
+        let pod = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": "nginx", "namespace": "default", "labels": {"app": "web"}}
+        });
+        let keys: Vec<String> = IndexKey::for_object(&pod)
+            .iter()
+            .map(IndexKey::encode)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "namespace/default/v1/Pod/nginx",
+                "label/app/web/v1/Pod/default/nginx"
+            ]
+        );
+
+        assert!(IndexKey::for_object(&serde_json::json!({"id": "c1"})).is_empty());
+    }
 }
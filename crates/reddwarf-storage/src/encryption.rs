@@ -0,0 +1,632 @@
+//! Envelope encryption of stored values
+//!
+//! Each value is sealed with its own data encryption key (DEK) under
+//! AES-256-GCM, and the DEK is stored alongside it wrapped by a key
+//! encryption key held by a [`KmsProvider`]: a local keyring file, or an
+//! external KMS/HSM reached through a command. Rotating the primary key of
+//! the provider only re-wraps new values; [`EnvelopeEncryptor::reencrypt`]
+//! moves existing ones over while the old keys stay readable, so rotation
+//! needs no downtime.
+
+use crate::error::{Result, StorageError};
+use crate::kv::KVStore;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info};
+
+/// Prefix marking a stored value as an encryption envelope
+pub const ENVELOPE_PREFIX: &[u8] = b"reddwarf:enc:v1:";
+
+/// Length of data and key encryption keys, in bytes
+pub const KEY_LEN: usize = 32;
+
+/// A data encryption key wrapped by a KMS provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrappedKey {
+    /// Key the DEK was wrapped under
+    pub key_id: String,
+    /// The wrapped DEK
+    pub ciphertext: Vec<u8>,
+}
+
+/// Holder of the key encryption keys that wrap and unwrap DEKs
+///
+/// Calls may block (an external KMS is a process call-out), so async callers
+/// run them on a blocking thread.
+pub trait KmsProvider: Send + Sync {
+    /// Name recorded in envelopes, so values are unwrapped by the provider
+    /// that wrapped them
+    fn name(&self) -> &str;
+
+    /// Id of the key new DEKs are wrapped under
+    fn primary_key_id(&self) -> Result<String>;
+
+    /// Wrap a DEK under the primary key
+    fn wrap(&self, dek: &[u8]) -> Result<WrappedKey>;
+
+    /// Unwrap a DEK wrapped under any key the provider still holds
+    fn unwrap(&self, wrapped: &WrappedKey) -> Result<Vec<u8>>;
+
+    /// Pick up a rotated key, returning whether the primary key changed
+    fn reload(&self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Fresh random key material
+pub fn generate_key() -> Result<[u8; KEY_LEN]> {
+    let mut key = [0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| StorageError::encryption_error("failed to generate a random key"))?;
+    Ok(key)
+}
+
+/// Seal `plaintext` under `key`, returning the random nonce and ciphertext
+fn seal(key: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<([u8; NONCE_LEN], Vec<u8>)> {
+    let key = aead_key(key)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| StorageError::encryption_error("failed to generate a nonce"))?;
+
+    let mut data = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(aad),
+        &mut data,
+    )
+    .map_err(|_| StorageError::encryption_error("failed to seal value"))?;
+    Ok((nonce, data))
+}
+
+/// Open what [`seal`] sealed under `key`
+fn open(key: &[u8], aad: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let key = aead_key(key)?;
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| StorageError::encryption_error("invalid nonce length"))?;
+
+    let mut data = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(aad), &mut data)
+        .map_err(|_| {
+            StorageError::encryption_error("failed to open value: wrong key or tampered data")
+        })?;
+    Ok(plaintext.to_vec())
+}
+
+fn aead_key(key: &[u8]) -> Result<LessSafeKey> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| StorageError::encryption_error(format!("keys must be {} bytes", KEY_LEN)))
+}
+
+fn decode_base64(value: &str, what: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(value.trim())
+        .map_err(|e| StorageError::encryption_error(format!("invalid base64 in {}: {}", what, e)))
+}
+
+/// Keys of a keyring file, primary first
+#[derive(Clone)]
+struct Keyring {
+    keys: Vec<(String, Vec<u8>)>,
+}
+
+impl Keyring {
+    /// Parse `<id>:<base64 key>` lines; the first key is the primary, and
+    /// blank lines and `#` comments are skipped
+    fn parse(contents: &str) -> Result<Self> {
+        let mut keys: Vec<(String, Vec<u8>)> = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, key) = line.split_once(':').ok_or_else(|| {
+                StorageError::encryption_error(format!(
+                    "keyring line {}: expected <key id>:<base64 key>",
+                    number + 1
+                ))
+            })?;
+            let id = id.trim();
+            if id.is_empty() || keys.iter().any(|(existing, _)| existing == id) {
+                return Err(StorageError::encryption_error(format!(
+                    "keyring line {}: key id '{}' is empty or repeated",
+                    number + 1,
+                    id
+                )));
+            }
+            let key = decode_base64(key, &format!("keyring key '{}'", id))?;
+            if key.len() != KEY_LEN {
+                return Err(StorageError::encryption_error(format!(
+                    "keyring key '{}' is {} bytes, expected {}",
+                    id,
+                    key.len(),
+                    KEY_LEN
+                )));
+            }
+            keys.push((id.to_string(), key));
+        }
+        if keys.is_empty() {
+            return Err(StorageError::encryption_error("keyring holds no keys"));
+        }
+        Ok(Self { keys })
+    }
+
+    fn primary(&self) -> &(String, Vec<u8>) {
+        &self.keys[0]
+    }
+
+    fn get(&self, key_id: &str) -> Option<&[u8]> {
+        self.keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, key)| key.as_slice())
+    }
+}
+
+/// Provider wrapping DEKs with keys read from a local keyring file
+///
+/// The file holds one `<key id>:<base64 32-byte key>` line per key, primary
+/// first. Keys rotated out of the primary slot stay in the file until no
+/// value is encrypted under them.
+pub struct LocalKms {
+    path: PathBuf,
+    keyring: RwLock<Keyring>,
+}
+
+impl LocalKms {
+    /// Name of local keyring providers in envelopes
+    pub const NAME: &'static str = "local";
+
+    /// Load the keyring at `path`
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let keyring = Self::read(&path)?;
+        Ok(Self {
+            path,
+            keyring: RwLock::new(keyring),
+        })
+    }
+
+    fn read(path: &Path) -> Result<Keyring> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            StorageError::io_error(
+                format!("failed to read keyring {}: {}", path.display(), e),
+                Some(Box::new(e)),
+            )
+        })?;
+        Keyring::parse(&contents)
+    }
+
+    /// Generate a key and make it the primary of the keyring at `path`,
+    /// keeping the previous keys so existing values stay readable, and
+    /// return its id. The file is created if it doesn't exist.
+    pub fn rotate(path: &Path, key_id: Option<&str>) -> Result<String> {
+        let existing = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let key_id = key_id
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("key-{}", chrono::Utc::now().format("%Y%m%d%H%M%S")));
+        if key_id.is_empty() || key_id.contains(':') || key_id.contains(char::is_whitespace) {
+            return Err(StorageError::encryption_error(format!(
+                "invalid key id '{}'",
+                key_id
+            )));
+        }
+
+        let contents = format!(
+            "{}:{}\n{}",
+            key_id,
+            BASE64.encode(generate_key()?),
+            existing
+        );
+        // Refuse to write a keyring that wouldn't load, e.g. a repeated id
+        Keyring::parse(&contents)?;
+
+        let tmp = path.with_extension("tmp");
+        {
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let mut file = options.open(&tmp)?;
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp, path)?;
+        info!("Rotated keyring {} to key {}", path.display(), key_id);
+        Ok(key_id)
+    }
+}
+
+impl KmsProvider for LocalKms {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn primary_key_id(&self) -> Result<String> {
+        Ok(self.keyring.read().unwrap().primary().0.clone())
+    }
+
+    fn wrap(&self, dek: &[u8]) -> Result<WrappedKey> {
+        let keyring = self.keyring.read().unwrap();
+        let (key_id, key) = keyring.primary();
+        let (nonce, sealed) = seal(key, key_id.as_bytes(), dek)?;
+        let mut ciphertext = nonce.to_vec();
+        ciphertext.extend(sealed);
+        Ok(WrappedKey {
+            key_id: key_id.clone(),
+            ciphertext,
+        })
+    }
+
+    fn unwrap(&self, wrapped: &WrappedKey) -> Result<Vec<u8>> {
+        let keyring = self.keyring.read().unwrap();
+        let key = keyring.get(&wrapped.key_id).ok_or_else(|| {
+            StorageError::encryption_error(format!(
+                "key '{}' is not in keyring {}",
+                wrapped.key_id,
+                self.path.display()
+            ))
+        })?;
+        if wrapped.ciphertext.len() < NONCE_LEN {
+            return Err(StorageError::encryption_error("wrapped key is truncated"));
+        }
+        let (nonce, sealed) = wrapped.ciphertext.split_at(NONCE_LEN);
+        open(key, wrapped.key_id.as_bytes(), nonce, sealed)
+    }
+
+    fn reload(&self) -> Result<bool> {
+        let keyring = Self::read(&self.path)?;
+        let mut current = self.keyring.write().unwrap();
+        let changed = current.primary().0 != keyring.primary().0;
+        *current = keyring;
+        Ok(changed)
+    }
+}
+
+/// Provider calling out to an external KMS or HSM through an executable
+///
+/// The executable is run as:
+/// - `<command> key-id`: prints the id of the current primary key
+/// - `<command> wrap`: reads a base64 DEK on stdin and prints
+///   `<key id>:<base64 wrapped DEK>`
+/// - `<command> unwrap <key id>`: reads a base64 wrapped DEK on stdin and
+///   prints the base64 DEK
+pub struct ExternalKms {
+    command: PathBuf,
+    primary: RwLock<Option<String>>,
+}
+
+impl ExternalKms {
+    /// Name of external providers in envelopes
+    pub const NAME: &'static str = "external";
+
+    pub fn new(command: impl Into<PathBuf>) -> Self {
+        Self {
+            command: command.into(),
+            primary: RwLock::new(None),
+        }
+    }
+
+    /// Run the command with `args`, feeding it `input`, and return its
+    /// trimmed standard output
+    fn call(&self, args: &[&str], input: &str) -> Result<String> {
+        debug!("Calling KMS {} {:?}", self.command.display(), args);
+        let mut child = Command::new(&self.command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                StorageError::encryption_error(format!(
+                    "failed to run KMS command {}: {}",
+                    self.command.display(),
+                    e
+                ))
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(StorageError::encryption_error(format!(
+                "KMS command {} {} failed ({}): {}",
+                self.command.display(),
+                args.join(" "),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn query_key_id(&self) -> Result<String> {
+        let key_id = self.call(&["key-id"], "")?;
+        if key_id.is_empty() {
+            return Err(StorageError::encryption_error(
+                "KMS command printed an empty key id",
+            ));
+        }
+        Ok(key_id)
+    }
+}
+
+impl KmsProvider for ExternalKms {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn primary_key_id(&self) -> Result<String> {
+        if let Some(key_id) = self.primary.read().unwrap().clone() {
+            return Ok(key_id);
+        }
+        let key_id = self.query_key_id()?;
+        *self.primary.write().unwrap() = Some(key_id.clone());
+        Ok(key_id)
+    }
+
+    fn wrap(&self, dek: &[u8]) -> Result<WrappedKey> {
+        let output = self.call(&["wrap"], &BASE64.encode(dek))?;
+        let (key_id, ciphertext) = output.split_once(':').ok_or_else(|| {
+            StorageError::encryption_error("KMS wrap printed no '<key id>:<wrapped key>'")
+        })?;
+        Ok(WrappedKey {
+            key_id: key_id.to_string(),
+            ciphertext: decode_base64(ciphertext, "KMS wrap output")?,
+        })
+    }
+
+    fn unwrap(&self, wrapped: &WrappedKey) -> Result<Vec<u8>> {
+        let output = self.call(
+            &["unwrap", &wrapped.key_id],
+            &BASE64.encode(&wrapped.ciphertext),
+        )?;
+        decode_base64(&output, "KMS unwrap output")
+    }
+
+    fn reload(&self) -> Result<bool> {
+        let key_id = self.query_key_id()?;
+        let mut primary = self.primary.write().unwrap();
+        let changed = primary.as_deref().is_some_and(|current| current != key_id);
+        *primary = Some(key_id);
+        Ok(changed)
+    }
+}
+
+/// A value encrypted under a DEK, with the DEK wrapped by a provider
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    provider: String,
+    key_id: String,
+    wrapped_key: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Encrypts and decrypts stored values in envelopes
+///
+/// Values are bound to the storage key they are written under, so a value
+/// copied to another key fails to decrypt.
+pub struct EnvelopeEncryptor {
+    provider: Arc<dyn KmsProvider>,
+}
+
+impl EnvelopeEncryptor {
+    pub fn new(provider: Arc<dyn KmsProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// The provider wrapping this encryptor's DEKs
+    pub fn provider(&self) -> &dyn KmsProvider {
+        self.provider.as_ref()
+    }
+
+    /// Whether `data` is an envelope rather than a plaintext value
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.starts_with(ENVELOPE_PREFIX)
+    }
+
+    fn envelope(data: &[u8]) -> Result<Envelope> {
+        serde_json::from_slice(&data[ENVELOPE_PREFIX.len()..]).map_err(|e| {
+            StorageError::encryption_error(format!("malformed encryption envelope: {}", e))
+        })
+    }
+
+    /// Encrypt `plaintext` to be stored under `key`
+    pub fn encrypt(&self, key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let dek = generate_key()?;
+        let (nonce, ciphertext) = seal(&dek, key, plaintext)?;
+        let wrapped = self.provider.wrap(&dek)?;
+
+        let envelope = Envelope {
+            provider: self.provider.name().to_string(),
+            key_id: wrapped.key_id,
+            wrapped_key: BASE64.encode(&wrapped.ciphertext),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let mut data = ENVELOPE_PREFIX.to_vec();
+        data.extend(serde_json::to_vec(&envelope)?);
+        Ok(data)
+    }
+
+    /// Decrypt a value stored under `key`; plaintext values written before
+    /// encryption was enabled are returned as they are
+    pub fn decrypt(&self, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        if !Self::is_encrypted(data) {
+            return Ok(data.to_vec());
+        }
+        let envelope = Self::envelope(data)?;
+        if envelope.provider != self.provider.name() {
+            return Err(StorageError::encryption_error(format!(
+                "value was encrypted by the {} provider, not {}",
+                envelope.provider,
+                self.provider.name()
+            )));
+        }
+        let dek = self.provider.unwrap(&WrappedKey {
+            key_id: envelope.key_id,
+            ciphertext: decode_base64(&envelope.wrapped_key, "envelope wrapped key")?,
+        })?;
+        open(
+            &dek,
+            key,
+            &decode_base64(&envelope.nonce, "envelope nonce")?,
+            &decode_base64(&envelope.ciphertext, "envelope ciphertext")?,
+        )
+    }
+
+    /// Whether `data` is encrypted under a key other than the primary
+    pub fn needs_rotation(&self, data: &[u8]) -> Result<bool> {
+        if !Self::is_encrypted(data) {
+            return Ok(false);
+        }
+        Ok(Self::envelope(data)?.key_id != self.provider.primary_key_id()?)
+    }
+
+    /// Re-encrypt the values under `prefix` that aren't encrypted under the
+    /// primary key, returning how many were rewritten
+    ///
+    /// Each value is re-read and rewritten in its own transaction, so
+    /// concurrent writers are never overwritten with stale data and the
+    /// store stays available throughout.
+    pub fn reencrypt(&self, store: &dyn KVStore, prefix: &[u8]) -> Result<usize> {
+        let mut rewritten = 0;
+        for (key, value) in store.scan(prefix)? {
+            if !self.needs_rotation(&value)? {
+                continue;
+            }
+            let mut txn = store.transaction()?;
+            let Some(current) = txn.get(&key)? else {
+                txn.rollback()?;
+                continue;
+            };
+            if !self.needs_rotation(&current)? {
+                txn.rollback()?;
+                continue;
+            }
+            let plaintext = self.decrypt(&key, &current)?;
+            txn.put(&key, &self.encrypt(&key, &plaintext)?)?;
+            txn.commit()?;
+            rewritten += 1;
+        }
+        if rewritten > 0 {
+            info!(
+                "Re-encrypted {} values under key {}",
+                rewritten,
+                self.provider.primary_key_id()?
+            );
+        }
+        Ok(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RedbBackend;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_envelope_round_trip_and_binding() {
+        let dir = tempdir().unwrap();
+        let keyring = dir.path().join("keys");
+        LocalKms::rotate(&keyring, Some("k1")).unwrap();
+        let encryptor = EnvelopeEncryptor::new(Arc::new(LocalKms::open(&keyring).unwrap()));
+
+        let sealed = encryptor
+            .encrypt(b"v1/Secret/default/a", b"hunter2")
+            .unwrap();
+        assert!(EnvelopeEncryptor::is_encrypted(&sealed));
+        assert!(!sealed.windows(7).any(|w| w == b"hunter2"));
+        assert_eq!(
+            encryptor.decrypt(b"v1/Secret/default/a", &sealed).unwrap(),
+            b"hunter2"
+        );
+        // Bound to its storage key
+        assert!(encryptor.decrypt(b"v1/Secret/default/b", &sealed).is_err());
+        // Plaintext written before encryption was enabled passes through
+        assert_eq!(encryptor.decrypt(b"k", b"{}").unwrap(), b"{}");
+        assert!(!encryptor.needs_rotation(&sealed).unwrap());
+    }
+
+    #[test]
+    fn test_rotation_reencrypts_under_new_primary() {
+        let dir = tempdir().unwrap();
+        let store = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        let keyring = dir.path().join("keys");
+        LocalKms::rotate(&keyring, Some("old")).unwrap();
+        let kms = Arc::new(LocalKms::open(&keyring).unwrap());
+        let encryptor = EnvelopeEncryptor::new(kms.clone());
+
+        for name in ["a", "b"] {
+            let key = format!("v1/Secret/default/{}", name);
+            let sealed = encryptor.encrypt(key.as_bytes(), name.as_bytes()).unwrap();
+            store.put(key.as_bytes(), &sealed).unwrap();
+        }
+        store.put(b"v1/ConfigMap/default/c", b"{}").unwrap();
+        assert!(!kms.reload().unwrap());
+
+        assert_eq!(LocalKms::rotate(&keyring, Some("new")).unwrap(), "new");
+        assert!(LocalKms::rotate(&keyring, Some("old")).is_err());
+        assert!(kms.reload().unwrap());
+        assert_eq!(kms.primary_key_id().unwrap(), "new");
+
+        assert_eq!(encryptor.reencrypt(&store, b"").unwrap(), 2);
+        assert_eq!(encryptor.reencrypt(&store, b"").unwrap(), 0);
+        let value = store.get(b"v1/Secret/default/a").unwrap().unwrap();
+        assert!(!encryptor.needs_rotation(&value).unwrap());
+        assert_eq!(
+            encryptor.decrypt(b"v1/Secret/default/a", &value).unwrap(),
+            b"a"
+        );
+        assert_eq!(
+            store.get(b"v1/ConfigMap/default/c").unwrap().unwrap(),
+            &b"{}"[..]
+        );
+
+        // Values under a key dropped from the keyring no longer decrypt
+        let contents = std::fs::read_to_string(&keyring).unwrap();
+        std::fs::write(&keyring, contents.replace("new:", "newer:")).unwrap();
+        assert!(kms.reload().unwrap());
+        assert!(encryptor.decrypt(b"v1/Secret/default/a", &value).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_kms_call_out() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A toy KMS that "wraps" DEKs by passing them through as they are
+        let dir = tempdir().unwrap();
+        let command = dir.path().join("kms");
+        std::fs::write(
+            &command,
+            "#!/bin/sh\ncase \"$1\" in\n  key-id) echo hsm-1 ;;\n  wrap) printf 'hsm-1:'; cat ;;\n  unwrap) [ \"$2\" = hsm-1 ] && cat ;;\n  *) exit 1 ;;\nesac\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&command, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let kms = Arc::new(ExternalKms::new(&command));
+        assert_eq!(kms.primary_key_id().unwrap(), "hsm-1");
+        let encryptor = EnvelopeEncryptor::new(kms);
+        let sealed = encryptor.encrypt(b"k", b"secret").unwrap();
+        assert_eq!(encryptor.decrypt(b"k", &sealed).unwrap(), b"secret");
+        assert!(!encryptor.needs_rotation(&sealed).unwrap());
+    }
+}
@@ -2,6 +2,7 @@
 #![allow(unused_assignments)]
 
 use miette::Diagnostic;
+use reddwarf_core::{ErrorClass, ErrorKind};
 use thiserror::Error;
 
 /// Storage error type
@@ -35,6 +36,14 @@ pub enum StorageError {
     )]
     TransactionError { message: String },
 
+    /// Migration error
+    #[error("Migration error: {message}")]
+    #[diagnostic(
+        code(storage::migration_error),
+        help("The database was left at its last successfully migrated version; restore the pre-migration backup if needed")
+    )]
+    MigrationError { message: String },
+
     /// Serialization error
     #[error("Serialization error: {message}")]
     #[diagnostic(
@@ -58,6 +67,22 @@ pub enum StorageError {
         #[source]
         source: Option<Box<dyn std::error::Error + Send + Sync>>,
     },
+
+    /// Encryption error
+    #[error("Encryption error: {message}")]
+    #[diagnostic(
+        code(storage::encryption_error),
+        help("Check the encryption key file or KMS command, and that the key a value was encrypted under is still available")
+    )]
+    EncryptionError { message: String },
+
+    /// Import error
+    #[error("Import error: {message}")]
+    #[diagnostic(
+        code(storage::import_error),
+        help("Import a complete export from this or an older release into a new, empty database")
+    )]
+    ImportError { message: String },
 }
 
 /// Result type for storage operations
@@ -87,6 +112,13 @@ impl StorageError {
         }
     }
 
+    /// Create a MigrationError
+    pub fn migration_error(message: impl Into<String>) -> Self {
+        Self::MigrationError {
+            message: message.into(),
+        }
+    }
+
     /// Create a SerializationError
     pub fn serialization_error(
         message: impl Into<String>,
@@ -108,6 +140,35 @@ impl StorageError {
             source,
         }
     }
+
+    /// Create an EncryptionError
+    pub fn encryption_error(message: impl Into<String>) -> Self {
+        Self::EncryptionError {
+            message: message.into(),
+        }
+    }
+
+    /// Create an ImportError
+    pub fn import_error(message: impl Into<String>) -> Self {
+        Self::ImportError {
+            message: message.into(),
+        }
+    }
+}
+
+impl ErrorClass for StorageError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::KeyNotFound { .. } => ErrorKind::NotFound,
+            Self::ImportError { .. } => ErrorKind::Invalid,
+            Self::DatabaseError { .. }
+            | Self::TransactionError { .. }
+            | Self::MigrationError { .. }
+            | Self::SerializationError { .. }
+            | Self::IoError { .. }
+            | Self::EncryptionError { .. } => ErrorKind::Internal,
+        }
+    }
 }
 
 impl From<redb::Error> for StorageError {
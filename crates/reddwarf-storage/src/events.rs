@@ -0,0 +1,362 @@
+//! Event storage
+//!
+//! Events live in their own table, outside the resources table and the
+//! commit history, so an event storm neither grows the history nor slows
+//! down resource scans. Repeats of an event (same involved object, type,
+//! reason, source and message) are folded into the first one by bumping its
+//! `count` and `lastTimestamp`, and events not seen for the TTL are removed
+//! by [`EventStore::expire`].
+//!
+//! The table holds three kinds of entries:
+//! - `object/{namespace}/{name}` -> the event as JSON
+//! - `aggregate/{aggregation key}` -> `{namespace}/{name}` of the event
+//!   repeats are folded into
+//! - `expiry/{lastTimestamp millis}/{namespace}/{name}` -> empty, ordered by
+//!   when the event was last seen
+
+use crate::redb_backend::EVENTS_TABLE;
+use crate::{RedbBackend, Result, StorageError};
+use chrono::{DateTime, Utc};
+use redb::{ReadableTable, Table};
+use reddwarf_core::k8s_openapi::api::core::v1::Event;
+use reddwarf_core::k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use reddwarf_core::EventSink;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+const OBJECT_PREFIX: &str = "object/";
+const AGGREGATE_PREFIX: &str = "aggregate/";
+const EXPIRY_PREFIX: &str = "expiry/";
+
+/// Configuration for the event store
+#[derive(Debug, Clone)]
+pub struct EventStoreConfig {
+    /// How long an event is kept after it was last seen
+    pub ttl: Duration,
+}
+
+impl Default for EventStoreConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// Expiring, aggregating store of events
+pub struct EventStore {
+    backend: Arc<RedbBackend>,
+    config: EventStoreConfig,
+}
+
+impl EventStore {
+    pub fn new(backend: Arc<RedbBackend>, config: EventStoreConfig) -> Self {
+        Self { backend, config }
+    }
+
+    /// How long an event is kept after it was last seen
+    pub fn ttl(&self) -> Duration {
+        self.config.ttl
+    }
+
+    /// Record `event` as seen at `now`. A repeat of a stored event bumps
+    /// that event's `count` and `lastTimestamp` and returns it; otherwise
+    /// the event is stored with a generated name if it has none, a count of
+    /// one and both timestamps defaulting to `now`.
+    pub fn record(&self, mut event: Event, now: DateTime<Utc>) -> Result<Event> {
+        let aggregate_key = format!("{}{}", AGGREGATE_PREFIX, aggregation_key(&event));
+
+        let write_txn = self.backend.begin_write()?;
+        let stored = {
+            let mut table = write_txn.open_table(EVENTS_TABLE)?;
+            let existing = match table.get(aggregate_key.as_bytes())? {
+                Some(target) => {
+                    let object_key = format!(
+                        "{}{}",
+                        OBJECT_PREFIX,
+                        String::from_utf8_lossy(target.value())
+                    );
+                    let value = table.get(object_key.as_bytes())?;
+                    value.map(|v| decode(v.value())).transpose()?
+                }
+                None => None,
+            };
+
+            let stored = match existing {
+                Some(mut existing) => {
+                    table.remove(expiry_key(&existing).as_bytes())?;
+                    existing.count = Some(existing.count.unwrap_or(1) + 1);
+                    existing.last_timestamp = Some(Time(now));
+                    existing
+                }
+                None => {
+                    if event.metadata.name.is_none() {
+                        let base = event.involved_object.name.as_deref().unwrap_or("event");
+                        let nanos = now.timestamp_nanos_opt().unwrap_or_default();
+                        event.metadata.name = Some(format!("{}.{:x}", base, nanos));
+                    }
+                    event.metadata.namespace = Some(event_namespace(&event).to_string());
+                    event.count = Some(event.count.unwrap_or(1));
+                    let first = event.first_timestamp.get_or_insert(Time(now)).clone();
+                    event.last_timestamp.get_or_insert(first);
+                    table.insert(aggregate_key.as_bytes(), name_key(&event).as_bytes())?;
+                    event
+                }
+            };
+
+            let value = serde_json::to_vec(&stored).map_err(|e| {
+                StorageError::serialization_error(
+                    format!("Failed to serialize event: {}", e),
+                    Some(Box::new(e)),
+                )
+            })?;
+            let object_key = format!("{}{}", OBJECT_PREFIX, name_key(&stored));
+            table.insert(object_key.as_bytes(), value.as_slice())?;
+            table.insert(expiry_key(&stored).as_bytes(), [].as_slice())?;
+            stored
+        };
+        write_txn.commit()?;
+
+        Ok(stored)
+    }
+
+    /// Get one event
+    pub fn get(&self, namespace: &str, name: &str) -> Result<Option<Event>> {
+        let read_txn = self.backend.db().begin_read()?;
+        let table = read_txn.open_table(EVENTS_TABLE)?;
+        let key = format!("{}{}/{}", OBJECT_PREFIX, namespace, name);
+        let value = table.get(key.as_bytes())?;
+        value.map(|v| decode(v.value())).transpose()
+    }
+
+    /// Events in `namespace`, or in every namespace when `None`
+    pub fn list(&self, namespace: Option<&str>) -> Result<Vec<Event>> {
+        let prefix = match namespace {
+            Some(namespace) => format!("{}{}/", OBJECT_PREFIX, namespace),
+            None => OBJECT_PREFIX.to_string(),
+        };
+        let read_txn = self.backend.db().begin_read()?;
+        let table = read_txn.open_table(EVENTS_TABLE)?;
+
+        let mut events = Vec::new();
+        for entry in table.range(prefix.as_bytes()..)? {
+            let (key, value) = entry?;
+            if !key.value().starts_with(prefix.as_bytes()) {
+                break;
+            }
+            events.push(decode(value.value())?);
+        }
+
+        Ok(events)
+    }
+
+    /// Remove every event last seen more than the TTL before `now`,
+    /// returning how many were removed
+    pub fn expire(&self, now: DateTime<Utc>) -> Result<usize> {
+        let cutoff = chrono::Duration::from_std(self.config.ttl)
+            .ok()
+            .and_then(|ttl| now.checked_sub_signed(ttl))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let cutoff = format!("{}{}", EXPIRY_PREFIX, expiry_millis(cutoff));
+
+        let write_txn = self.backend.begin_write()?;
+        let expired = {
+            let mut table = write_txn.open_table(EVENTS_TABLE)?;
+            let mut expired = Vec::new();
+            for entry in table.range(EXPIRY_PREFIX.as_bytes()..cutoff.as_bytes())? {
+                let (key, _) = entry?;
+                expired.push(String::from_utf8_lossy(key.value()).to_string());
+            }
+            for key in &expired {
+                remove_expired(&mut table, key)?;
+            }
+            expired.len()
+        };
+        write_txn.commit()?;
+
+        if expired > 0 {
+            debug!("Expired {} event(s)", expired);
+        }
+        Ok(expired)
+    }
+}
+
+/// Components running next to the API server record their events straight
+/// into its store
+impl EventSink for EventStore {
+    fn record(&self, event: Event) {
+        if let Err(e) = EventStore::record(self, event, Utc::now()) {
+            warn!("Failed to record event: {}", e);
+        }
+    }
+}
+
+/// Remove the event an expiry entry points at, and its aggregation entry
+/// unless a newer event took it over
+fn remove_expired(table: &mut Table<&[u8], &[u8]>, expiry: &str) -> Result<()> {
+    table.remove(expiry.as_bytes())?;
+    // expiry/{millis}/{namespace}/{name}
+    let Some((_, name_key)) = expiry[EXPIRY_PREFIX.len()..].split_once('/') else {
+        return Ok(());
+    };
+    let object_key = format!("{}{}", OBJECT_PREFIX, name_key);
+    let event = match table.remove(object_key.as_bytes())? {
+        Some(value) => decode(value.value())?,
+        None => return Ok(()),
+    };
+
+    let aggregate_key = format!("{}{}", AGGREGATE_PREFIX, aggregation_key(&event));
+    let points_here = table
+        .get(aggregate_key.as_bytes())?
+        .is_some_and(|target| target.value() == name_key.as_bytes());
+    if points_here {
+        table.remove(aggregate_key.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn decode(value: &[u8]) -> Result<Event> {
+    serde_json::from_slice(value).map_err(|e| {
+        StorageError::serialization_error(
+            format!("Failed to deserialize event: {}", e),
+            Some(Box::new(e)),
+        )
+    })
+}
+
+/// Namespace of an event, falling back to its involved object's
+fn event_namespace(event: &Event) -> &str {
+    event
+        .metadata
+        .namespace
+        .as_deref()
+        .or(event.involved_object.namespace.as_deref())
+        .unwrap_or("default")
+}
+
+fn name_key(event: &Event) -> String {
+    format!(
+        "{}/{}",
+        event_namespace(event),
+        event.metadata.name.as_deref().unwrap_or_default()
+    )
+}
+
+fn expiry_key(event: &Event) -> String {
+    let last_seen = event
+        .last_timestamp
+        .as_ref()
+        .or(event.first_timestamp.as_ref())
+        .map(|t| t.0)
+        .unwrap_or_default();
+    format!(
+        "{}{}/{}",
+        EXPIRY_PREFIX,
+        expiry_millis(last_seen),
+        name_key(event)
+    )
+}
+
+/// Zero-padded so expiry keys sort by time
+fn expiry_millis(time: DateTime<Utc>) -> String {
+    format!("{:020}", time.timestamp_millis().max(0))
+}
+
+/// What makes two events repeats of each other, as in Kubernetes' event
+/// aggregation: the involved object, the type, the reason, the source and
+/// the message
+fn aggregation_key(event: &Event) -> String {
+    let object = &event.involved_object;
+    let source = event.source.as_ref();
+    [
+        event_namespace(event),
+        object.kind.as_deref().unwrap_or_default(),
+        object.namespace.as_deref().unwrap_or_default(),
+        object.name.as_deref().unwrap_or_default(),
+        object.uid.as_deref().unwrap_or_default(),
+        object.field_path.as_deref().unwrap_or_default(),
+        event.type_.as_deref().unwrap_or_default(),
+        event.reason.as_deref().unwrap_or_default(),
+        source
+            .and_then(|s| s.component.as_deref())
+            .unwrap_or_default(),
+        source.and_then(|s| s.host.as_deref()).unwrap_or_default(),
+        event.message.as_deref().unwrap_or_default(),
+    ]
+    .join("\u{0}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KVStore;
+    use reddwarf_core::k8s_openapi::api::core::v1::ObjectReference;
+    use tempfile::tempdir;
+
+    fn event(name: &str, message: &str) -> Event {
+        Event {
+            involved_object: ObjectReference {
+                kind: Some("Pod".to_string()),
+                namespace: Some("default".to_string()),
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            type_: Some("Warning".to_string()),
+            reason: Some("ProbeFailed".to_string()),
+            message: Some(message.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_event_aggregation_and_expiry() {
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let store = EventStore::new(backend.clone(), EventStoreConfig::default());
+        // Event timestamps are serialized with second precision
+        let start = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+        let minutes = |m: i64| start + chrono::Duration::minutes(m);
+
+        // A storm of the same event is stored once
+        let first = store.record(event("web", "probe failed"), start).unwrap();
+        for m in 1..=50 {
+            store
+                .record(event("web", "probe failed"), minutes(m))
+                .unwrap();
+        }
+        let other = store
+            .record(event("db", "probe failed"), minutes(30))
+            .unwrap();
+        let events = store.list(Some("default")).unwrap();
+        assert_eq!(events.len(), 2);
+        let web = store
+            .get("default", first.metadata.name.as_deref().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(web.count, Some(51));
+        assert_eq!(web.first_timestamp, Some(Time(start)));
+        assert_eq!(web.last_timestamp, Some(Time(minutes(50))));
+
+        // Events are not resources and survive a backup
+        assert!(backend.scan(b"").unwrap().is_empty());
+        backend.backup_to(dir.path().join("backup.redb")).unwrap();
+
+        // Only what has not been seen for an hour expires
+        assert_eq!(store.expire(minutes(89)).unwrap(), 0);
+        assert_eq!(store.expire(minutes(91)).unwrap(), 1);
+        assert!(store
+            .get("default", other.metadata.name.as_deref().unwrap())
+            .unwrap()
+            .is_none());
+        assert_eq!(store.expire(minutes(111)).unwrap(), 1);
+        assert!(store.list(None).unwrap().is_empty());
+
+        // With its aggregation entry gone, a repeat starts a new event
+        let again = store
+            .record(event("web", "probe failed"), minutes(120))
+            .unwrap();
+        assert_eq!(again.count, Some(1));
+        assert_ne!(again.metadata.name, first.metadata.name);
+    }
+}
@@ -0,0 +1,692 @@
+//! Line-delimited export and import of a whole store
+//!
+//! An export holds every stored object, and optionally the version
+//! history, in a format independent of the storage backend, so it can seed
+//! a fresh cluster in a disaster recovery drill or move a cluster between
+//! backends. Format `reddwarf-export` version 1 is UTF-8 JSON, one record
+//! per line, each an object whose `type` is one of:
+//!
+//! - `header`: the first line; `format`, `version`, the source's
+//!   `schemaVersion`, `exportedAt` and whether it includes `history`
+//! - `object`: a stored resource under `key`, as `object`, or as `sealed`
+//!   (the encryption envelope, still encrypted and bound to its key) for
+//!   values encrypted at rest
+//! - `entry`: any other stored value under `key`, such as an IPAM
+//!   allocation, as base64 `value`
+//! - `commit`: a commit of the version history
+//! - `head`: the id of the HEAD `commit`
+//! - `end`: the last line, with the number of `records` since the header,
+//!   so that a truncated export is refused
+//!
+//! Events and the node cache are not exported: both are rebuilt by a
+//! running cluster. Imports go into a new database only, and are refused
+//! when the export's references do not resolve (see [`ImportIssue`]).
+
+use crate::encryption::EnvelopeEncryptor;
+use crate::migrations::migrations;
+use crate::{KVStore, Migrator, RedbBackend, Result, StorageError};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{BufRead, Write};
+use tracing::info;
+
+/// Name of the export format, in the header
+pub const EXPORT_FORMAT: &str = "reddwarf-export";
+
+/// Newest version of the export format this release reads and writes
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Prefix of the version history's keys
+const HISTORY_PREFIX: &str = "version:";
+
+/// Prefix of the keys of commits in the version history
+const COMMIT_PREFIX: &str = "version:commit:";
+
+/// Key of HEAD in the version history
+const HEAD_KEY: &str = "version:head";
+
+/// First line of an export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportHeader {
+    pub format: String,
+    pub version: u32,
+    /// Storage schema version of the exported database
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Whether the export includes the version history
+    pub history: bool,
+}
+
+/// One line of an export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportRecord {
+    Header(ExportHeader),
+    Object {
+        key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        object: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sealed: Option<String>,
+    },
+    Entry {
+        key: String,
+        value: String,
+    },
+    Commit {
+        commit: Value,
+    },
+    Head {
+        commit: String,
+    },
+    End {
+        records: u64,
+    },
+}
+
+/// How much an export or import covered
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub objects: usize,
+    pub entries: usize,
+    pub commits: usize,
+}
+
+/// A reference in an export that does not resolve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportIssue {
+    /// Two records store a value under the same key
+    DuplicateKey { key: String },
+    /// An object whose apiVersion, kind, namespace or name does not match
+    /// the key it is stored under
+    KeyMismatch { key: String },
+    /// An object refers to one the export does not hold, such as an owner
+    /// or the volume a claim is bound to
+    MissingReference { key: String, reference: String },
+    /// A commit whose parent is not in the history
+    MissingParent { commit: String, parent: String },
+    /// HEAD names a commit that is not in the history
+    MissingHead { commit: String },
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportIssue::DuplicateKey { key } => write!(f, "duplicate key {}", key),
+            ImportIssue::KeyMismatch { key } => {
+                write!(f, "object stored under {} names another object", key)
+            }
+            ImportIssue::MissingReference { key, reference } => {
+                write!(f, "{} refers to missing {}", key, reference)
+            }
+            ImportIssue::MissingParent { commit, parent } => {
+                write!(f, "commit {} has missing parent {}", commit, parent)
+            }
+            ImportIssue::MissingHead { commit } => write!(f, "HEAD is missing commit {}", commit),
+        }
+    }
+}
+
+fn write_record(out: &mut dyn Write, record: &ExportRecord) -> Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Whether a stored value is a resource rather than some other entry
+fn is_object(value: &Value) -> bool {
+    ["apiVersion", "kind", "metadata"]
+        .iter()
+        .all(|field| value.get(field).is_some())
+}
+
+/// Export every value of `store`, and the commits and HEAD of `history`
+/// when given, to `out`
+pub fn export(
+    store: &RedbBackend,
+    history: Option<&RedbBackend>,
+    out: &mut dyn Write,
+) -> Result<ExportSummary> {
+    let header = ExportHeader {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        schema_version: Migrator::current_version(store)?,
+        exported_at: Utc::now(),
+        history: history.is_some(),
+    };
+    write_record(out, &ExportRecord::Header(header))?;
+
+    let mut summary = ExportSummary::default();
+    let mut records = 0;
+    for (key, value) in store.scan(b"")? {
+        let key = String::from_utf8_lossy(&key).to_string();
+        // History kept alongside the resources is exported as commits
+        if key.starts_with(HISTORY_PREFIX) {
+            continue;
+        }
+        let record = if EnvelopeEncryptor::is_encrypted(&value) {
+            summary.objects += 1;
+            ExportRecord::Object {
+                key,
+                object: None,
+                sealed: Some(String::from_utf8_lossy(&value).to_string()),
+            }
+        } else {
+            match serde_json::from_slice::<Value>(&value) {
+                Ok(object) if is_object(&object) => {
+                    summary.objects += 1;
+                    ExportRecord::Object {
+                        key,
+                        object: Some(object),
+                        sealed: None,
+                    }
+                }
+                _ => {
+                    summary.entries += 1;
+                    ExportRecord::Entry {
+                        key,
+                        value: BASE64.encode(&value),
+                    }
+                }
+            }
+        };
+        write_record(out, &record)?;
+        records += 1;
+    }
+
+    if let Some(history) = history {
+        for (_, value) in history.scan(COMMIT_PREFIX.as_bytes())? {
+            let commit = serde_json::from_slice(&value)?;
+            write_record(out, &ExportRecord::Commit { commit })?;
+            summary.commits += 1;
+            records += 1;
+        }
+        if let Some(head) = history.get(HEAD_KEY.as_bytes())? {
+            let commit = String::from_utf8_lossy(&head).to_string();
+            write_record(out, &ExportRecord::Head { commit })?;
+            records += 1;
+        }
+    }
+
+    write_record(out, &ExportRecord::End { records })?;
+    out.flush()?;
+    info!(
+        "Exported {} objects, {} entries and {} commits",
+        summary.objects, summary.entries, summary.commits
+    );
+    Ok(summary)
+}
+
+/// An export read back in full
+#[derive(Debug, Clone)]
+pub struct ExportDump {
+    pub header: ExportHeader,
+    /// Records between the header and the end, in order
+    pub records: Vec<ExportRecord>,
+}
+
+impl ExportDump {
+    /// What the export holds
+    pub fn summary(&self) -> ExportSummary {
+        let mut summary = ExportSummary::default();
+        for record in &self.records {
+            match record {
+                ExportRecord::Object { .. } => summary.objects += 1,
+                ExportRecord::Entry { .. } => summary.entries += 1,
+                ExportRecord::Commit { .. } => summary.commits += 1,
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// Check that every key is unique, that objects are stored under their
+    /// own key, and that owner references, claim and volume bindings, commit
+    /// parents and HEAD resolve within the export
+    pub fn check(&self) -> Vec<ImportIssue> {
+        let mut issues = Vec::new();
+        let mut keys = BTreeSet::new();
+        let mut objects = BTreeMap::new();
+        let mut commits = BTreeMap::new();
+        let mut head = None;
+        for record in &self.records {
+            match record {
+                ExportRecord::Object { key, .. } | ExportRecord::Entry { key, .. } => {
+                    if !keys.insert(key.as_str()) {
+                        issues.push(ImportIssue::DuplicateKey { key: key.clone() });
+                    }
+                    if let ExportRecord::Object {
+                        object: Some(object),
+                        ..
+                    } = record
+                    {
+                        objects.insert(key.as_str(), object);
+                    }
+                }
+                ExportRecord::Commit { commit } => {
+                    let id = commit["id"].as_str().unwrap_or_default();
+                    commits.insert(id, commit);
+                }
+                ExportRecord::Head { commit } => head = Some(commit),
+                _ => {}
+            }
+        }
+
+        for (key, object) in &objects {
+            let metadata = &object["metadata"];
+            let field = |value: &Value| value.as_str().unwrap_or_default().to_string();
+            let (api_version, kind) = (field(&object["apiVersion"]), field(&object["kind"]));
+            let namespace = metadata["namespace"].as_str().filter(|ns| !ns.is_empty());
+            let object_key = |kind: &str, namespace: Option<&str>, name: &str| match namespace {
+                Some(ns) => format!("{}/{}/{}/{}", api_version, kind, ns, name),
+                None => format!("{}/{}/{}", api_version, kind, name),
+            };
+            if object_key(&kind, namespace, &field(&metadata["name"])) != *key {
+                issues.push(ImportIssue::KeyMismatch {
+                    key: key.to_string(),
+                });
+            }
+
+            let mut missing = |reference: String| {
+                issues.push(ImportIssue::MissingReference {
+                    key: key.to_string(),
+                    reference,
+                })
+            };
+
+            for owner in metadata["ownerReferences"].as_array().into_iter().flatten() {
+                let (owner_version, owner_kind) =
+                    (field(&owner["apiVersion"]), field(&owner["kind"]));
+                let name = field(&owner["name"]);
+                // Owners are in the dependent's namespace or cluster-scoped
+                let candidates = [
+                    namespace.map(|ns| format!("{}/{}/{}/{}", owner_version, owner_kind, ns, name)),
+                    Some(format!("{}/{}/{}", owner_version, owner_kind, name)),
+                ];
+                let resolved = candidates.iter().flatten().any(|candidate| {
+                    keys.contains(candidate.as_str())
+                        && objects.get(candidate.as_str()).is_none_or(|o| {
+                            o["metadata"]["uid"] == owner["uid"] || owner["uid"].is_null()
+                        })
+                });
+                if !resolved {
+                    missing(format!("owner {} {}", owner_kind, name));
+                }
+            }
+
+            let spec = &object["spec"];
+            let phase = object["status"]["phase"].as_str();
+            if kind == "PersistentVolumeClaim" {
+                if let Some(volume) = spec["volumeName"].as_str() {
+                    if !keys.contains(object_key("PersistentVolume", None, volume).as_str()) {
+                        missing(format!("PersistentVolume {}", volume));
+                    }
+                }
+            }
+            // Released volumes keep the reference to their deleted claim
+            if kind == "PersistentVolume" && phase == Some("Bound") {
+                let claim = &spec["claimRef"];
+                if let (Some(ns), Some(name)) =
+                    (claim["namespace"].as_str(), claim["name"].as_str())
+                {
+                    let claim_key = object_key("PersistentVolumeClaim", Some(ns), name);
+                    if !keys.contains(claim_key.as_str()) {
+                        missing(format!("PersistentVolumeClaim {}/{}", ns, name));
+                    }
+                }
+            }
+        }
+
+        for (id, commit) in &commits {
+            for parent in commit["parents"].as_array().into_iter().flatten() {
+                let parent = parent.as_str().unwrap_or_default();
+                if !commits.contains_key(parent) {
+                    issues.push(ImportIssue::MissingParent {
+                        commit: id.to_string(),
+                        parent: parent.to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(head) = head {
+            if !commits.contains_key(head.as_str()) {
+                issues.push(ImportIssue::MissingHead {
+                    commit: head.clone(),
+                });
+            }
+        }
+
+        issues
+    }
+}
+
+/// Read an export, refusing one of another format or a newer version, and
+/// one cut short before its end record
+pub fn read_export(input: impl BufRead) -> Result<ExportDump> {
+    let mut lines = input.lines().enumerate();
+    let parse = |number: usize, line: std::io::Result<String>| -> Result<ExportRecord> {
+        serde_json::from_str(&line?).map_err(|e| {
+            StorageError::import_error(format!(
+                "line {} is not an export record: {}",
+                number + 1,
+                e
+            ))
+        })
+    };
+
+    let header = match lines.next() {
+        Some((number, line)) => match parse(number, line)? {
+            ExportRecord::Header(header) => header,
+            _ => return Err(StorageError::import_error("the export has no header")),
+        },
+        None => return Err(StorageError::import_error("the export is empty")),
+    };
+    if header.format != EXPORT_FORMAT {
+        return Err(StorageError::import_error(format!(
+            "'{}' is not a {} file",
+            header.format, EXPORT_FORMAT
+        )));
+    }
+    if header.version > EXPORT_VERSION {
+        return Err(StorageError::import_error(format!(
+            "export format version {} is newer than the supported version {}",
+            header.version, EXPORT_VERSION
+        )));
+    }
+
+    let mut records = Vec::new();
+    for (number, line) in lines.by_ref() {
+        match parse(number, line)? {
+            ExportRecord::Header(_) => {
+                return Err(StorageError::import_error(format!(
+                    "line {} is a second header",
+                    number + 1
+                )))
+            }
+            ExportRecord::End { records: count } => {
+                if count != records.len() as u64 {
+                    return Err(StorageError::import_error(format!(
+                        "the export ends after {} records but holds {}",
+                        count,
+                        records.len()
+                    )));
+                }
+                if lines.any(|(_, line)| line.is_ok_and(|l| !l.trim().is_empty())) {
+                    return Err(StorageError::import_error("records follow the end record"));
+                }
+                return Ok(ExportDump { header, records });
+            }
+            record => records.push(record),
+        }
+    }
+    Err(StorageError::import_error(
+        "the export has no end record; it was cut short",
+    ))
+}
+
+/// Write `dump` into the new, empty `store`, and its history into
+/// `history` when given, after checking it. The store is left at the
+/// export's schema version, for the usual migrations to bring it up to date.
+pub fn import(
+    dump: &ExportDump,
+    store: &RedbBackend,
+    history: Option<&RedbBackend>,
+) -> Result<ExportSummary> {
+    let issues = dump.check();
+    if let Some(issue) = issues.first() {
+        return Err(StorageError::import_error(format!(
+            "{} unresolved references, the first: {}",
+            issues.len(),
+            issue
+        )));
+    }
+    let existing = store.keys()?.len();
+    if existing > 0 {
+        return Err(StorageError::import_error(format!(
+            "the target database already holds {} values",
+            existing
+        )));
+    }
+    let history = history.filter(|_| dump.header.history);
+    if let Some(history) = history {
+        if !history
+            .keys_with_prefix(HISTORY_PREFIX.as_bytes())?
+            .is_empty()
+        {
+            return Err(StorageError::import_error(
+                "the target history database already holds commits",
+            ));
+        }
+    }
+    Migrator::new(migrations()).record_applied(store, dump.header.schema_version)?;
+
+    let mut txn = store.transaction()?;
+    for record in &dump.records {
+        match record {
+            ExportRecord::Object {
+                key,
+                object: Some(object),
+                ..
+            } => txn.put(key.as_bytes(), &serde_json::to_vec(object)?)?,
+            ExportRecord::Object {
+                key,
+                sealed: Some(sealed),
+                ..
+            } => txn.put(key.as_bytes(), sealed.as_bytes())?,
+            ExportRecord::Object { key, .. } => {
+                return Err(StorageError::import_error(format!(
+                    "object {} has neither a value nor a sealed value",
+                    key
+                )))
+            }
+            ExportRecord::Entry { key, value } => {
+                let value = BASE64.decode(value).map_err(|e| {
+                    StorageError::import_error(format!("entry {} is not base64: {}", key, e))
+                })?;
+                txn.put(key.as_bytes(), &value)?;
+            }
+            _ => {}
+        }
+    }
+    txn.commit()?;
+
+    if let Some(history) = history {
+        let mut txn = history.transaction()?;
+        for record in &dump.records {
+            match record {
+                ExportRecord::Commit { commit } => {
+                    let id = commit["id"].as_str().unwrap_or_default();
+                    let key = format!("{}{}", COMMIT_PREFIX, id);
+                    txn.put(key.as_bytes(), &serde_json::to_vec(commit)?)?;
+                }
+                ExportRecord::Head { commit } => {
+                    txn.put(HEAD_KEY.as_bytes(), commit.as_bytes())?;
+                }
+                _ => {}
+            }
+        }
+        txn.commit()?;
+    }
+
+    let mut summary = dump.summary();
+    if history.is_none() {
+        summary.commits = 0;
+    }
+    info!(
+        "Imported {} objects, {} entries and {} commits",
+        summary.objects, summary.entries, summary.commits
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IndexKey;
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    fn pod(name: &str, owner: Option<(&str, &str)>) -> Vec<u8> {
+        let mut pod = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": name, "namespace": "default", "uid": format!("uid-{}", name)}
+        });
+        if let Some((name, uid)) = owner {
+            pod["metadata"]["ownerReferences"] = serde_json::json!([{
+                "apiVersion": "apps/v1", "kind": "ReplicaSet", "name": name, "uid": uid
+            }]);
+        }
+        serde_json::to_vec(&pod).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let dir = tempdir().unwrap();
+        let source = RedbBackend::new(dir.path().join("source.redb")).unwrap();
+        Migrator::new(migrations()).run(&source).unwrap();
+        let replica_set = serde_json::json!({
+            "apiVersion": "apps/v1",
+            "kind": "ReplicaSet",
+            "metadata": {"name": "web", "namespace": "default", "uid": "rs-1"}
+        });
+        source
+            .put(
+                b"apps/v1/ReplicaSet/default/web",
+                &serde_json::to_vec(&replica_set).unwrap(),
+            )
+            .unwrap();
+        source
+            .put(
+                b"v1/Pod/default/web-1",
+                &pod("web-1", Some(("web", "rs-1"))),
+            )
+            .unwrap();
+        source
+            .put(
+                b"v1/Secret/default/db",
+                b"reddwarf:enc:v1:{\"sealed\":true}",
+            )
+            .unwrap();
+        source
+            .put(b"ipam/alloc/10.0.0.2", b"default/web-1")
+            .unwrap();
+        source
+            .put(b"version:commit:c1", br#"{"id":"c1","parents":[]}"#)
+            .unwrap();
+        source
+            .put(b"version:commit:c2", br#"{"id":"c2","parents":["c1"]}"#)
+            .unwrap();
+        source.put(b"version:head", b"c2").unwrap();
+
+        let mut out = Vec::new();
+        let summary = export(&source, Some(&source), &mut out).unwrap();
+        assert_eq!(
+            summary,
+            ExportSummary {
+                objects: 3,
+                entries: 1,
+                commits: 2
+            }
+        );
+
+        let dump = read_export(Cursor::new(&out)).unwrap();
+        assert_eq!(dump.header.schema_version, 3);
+        assert!(dump.check().is_empty());
+
+        let target = RedbBackend::new(dir.path().join("target.redb")).unwrap();
+        let history = RedbBackend::new(dir.path().join("target.history")).unwrap();
+        assert_eq!(import(&dump, &target, Some(&history)).unwrap(), summary);
+        assert_eq!(Migrator::current_version(&target).unwrap(), 3);
+        assert_eq!(
+            target
+                .get(b"v1/Secret/default/db")
+                .unwrap()
+                .unwrap()
+                .as_ref(),
+            b"reddwarf:enc:v1:{\"sealed\":true}"
+        );
+        assert_eq!(
+            target
+                .get(b"ipam/alloc/10.0.0.2")
+                .unwrap()
+                .unwrap()
+                .as_ref(),
+            b"default/web-1"
+        );
+        // Indices are maintained as the objects are written
+        assert_eq!(
+            target
+                .scan_index(&IndexKey::encode_prefix_for_namespace("default"))
+                .unwrap()
+                .len(),
+            2,
+            "both plaintext objects are indexed by namespace"
+        );
+        assert_eq!(
+            history.get(b"version:head").unwrap().unwrap().as_ref(),
+            b"c2"
+        );
+
+        // Only into a new database
+        assert!(import(&dump, &target, None).is_err());
+    }
+
+    #[test]
+    fn test_broken_references_and_truncation_are_refused() {
+        let dir = tempdir().unwrap();
+        let source = RedbBackend::new(dir.path().join("source.redb")).unwrap();
+        source
+            .put(
+                b"v1/Pod/default/orphan",
+                &pod("orphan", Some(("gone", "rs-2"))),
+            )
+            .unwrap();
+        source
+            .put(b"v1/Pod/default/misfiled", &pod("other", None))
+            .unwrap();
+        source
+            .put(b"version:commit:c2", br#"{"id":"c2","parents":["c1"]}"#)
+            .unwrap();
+        source.put(b"version:head", b"c3").unwrap();
+
+        let mut out = Vec::new();
+        export(&source, Some(&source), &mut out).unwrap();
+        let dump = read_export(Cursor::new(&out)).unwrap();
+        let issues = dump.check();
+        assert_eq!(
+            issues,
+            vec![
+                ImportIssue::KeyMismatch {
+                    key: "v1/Pod/default/misfiled".to_string()
+                },
+                ImportIssue::MissingReference {
+                    key: "v1/Pod/default/orphan".to_string(),
+                    reference: "owner ReplicaSet gone".to_string()
+                },
+                ImportIssue::MissingParent {
+                    commit: "c2".to_string(),
+                    parent: "c1".to_string()
+                },
+                ImportIssue::MissingHead {
+                    commit: "c3".to_string()
+                },
+            ]
+        );
+        let target = RedbBackend::new(dir.path().join("target.redb")).unwrap();
+        assert!(import(&dump, &target, None).is_err());
+        assert!(target.keys().unwrap().is_empty());
+
+        // A cut-short export has no end record
+        let text = String::from_utf8(out).unwrap();
+        let truncated: Vec<&str> = text.lines().take(3).collect();
+        assert!(read_export(Cursor::new(truncated.join("\n"))).is_err());
+        assert!(read_export(Cursor::new("{\"type\":\"end\",\"records\":0}\n")).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Storage integrity checks and repairs
+//!
+//! Verifies that every JSON value is complete and that the index table
+//! holds exactly the entries derived from the stored objects, and repairs
+//! what can be re-derived from primary data.
+
+use crate::redb_backend::{index_entries, INDICES_TABLE, RESOURCES_TABLE};
+use crate::{RedbBackend, Result};
+use redb::ReadableTable;
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::info;
+
+/// A violated storage invariant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A value that starts as a JSON object but does not parse, e.g. one
+    /// cut short by a crash or a bad disk
+    CorruptValue { key: String },
+    /// An object has no entry for one of its index keys
+    MissingIndex { index_key: String, key: String },
+    /// An index entry that no stored object derives, or that points at the
+    /// wrong key
+    StaleIndex { index_key: String },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityIssue::CorruptValue { key } => write!(f, "corrupt value at {}", key),
+            IntegrityIssue::MissingIndex { index_key, key } => {
+                write!(f, "missing index entry {} for {}", index_key, key)
+            }
+            IntegrityIssue::StaleIndex { index_key } => {
+                write!(f, "stale index entry {}", index_key)
+            }
+        }
+    }
+}
+
+/// Check every value and index entry, from one read snapshot
+pub fn check(backend: &RedbBackend) -> Result<Vec<IntegrityIssue>> {
+    let db = backend.db();
+    let read_txn = db.begin_read()?;
+    let resources = read_txn.open_table(RESOURCES_TABLE)?;
+    let indices = read_txn.open_table(INDICES_TABLE)?;
+
+    let mut issues = Vec::new();
+    let mut expected = BTreeMap::new();
+    for entry in resources.iter()? {
+        let (key, value) = entry?;
+        let key = String::from_utf8_lossy(key.value()).to_string();
+        let value = value.value();
+        if is_corrupt(value) {
+            issues.push(IntegrityIssue::CorruptValue { key });
+            continue;
+        }
+        for index_key in index_entries(value) {
+            expected.insert(index_key, key.clone());
+        }
+    }
+
+    for entry in indices.iter()? {
+        let (index_key, target) = entry?;
+        let index_key = String::from_utf8_lossy(index_key.value()).to_string();
+        match expected.remove(&index_key) {
+            Some(key) if key.as_bytes() == target.value() => {}
+            _ => issues.push(IntegrityIssue::StaleIndex { index_key }),
+        }
+    }
+    issues.extend(
+        expected
+            .into_iter()
+            .map(|(index_key, key)| IntegrityIssue::MissingIndex { index_key, key }),
+    );
+
+    Ok(issues)
+}
+
+/// Replace the index table with the entries derived from the stored
+/// objects; returns how many entries it now holds
+pub fn rebuild_indices(backend: &RedbBackend) -> Result<usize> {
+    let db = backend.db();
+    let write_txn = db.begin_write()?;
+    let count = {
+        let resources = write_txn.open_table(RESOURCES_TABLE)?;
+        let mut indices = write_txn.open_table(INDICES_TABLE)?;
+        indices.retain(|_, _| false)?;
+        let mut count = 0;
+        for entry in resources.iter()? {
+            let (key, value) = entry?;
+            for index_key in index_entries(value.value()) {
+                indices.insert(index_key.as_bytes(), key.value())?;
+                count += 1;
+            }
+        }
+        count
+    };
+    write_txn.commit()?;
+
+    info!("Rebuilt index table with {} entries", count);
+    Ok(count)
+}
+
+/// Delete every corrupt value; returns their keys
+pub fn drop_corrupt_values(backend: &RedbBackend) -> Result<Vec<String>> {
+    let db = backend.db();
+    let write_txn = db.begin_write()?;
+    let mut dropped = Vec::new();
+    {
+        let mut resources = write_txn.open_table(RESOURCES_TABLE)?;
+        resources.retain(|key, value| {
+            if is_corrupt(value) {
+                dropped.push(String::from_utf8_lossy(key).to_string());
+                false
+            } else {
+                true
+            }
+        })?;
+    }
+    write_txn.commit()?;
+
+    for key in &dropped {
+        info!("Dropped corrupt value at {}", key);
+    }
+    Ok(dropped)
+}
+
+/// Values written as JSON objects must parse; other values (plain strings
+/// such as allocation owners) are opaque
+fn is_corrupt(value: &[u8]) -> bool {
+    value.first() == Some(&b'{') && serde_json::from_slice::<serde_json::Value>(value).is_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KVStore;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_check_and_repair() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        let pod = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": "nginx", "namespace": "default"}
+        });
+        backend
+            .put(b"v1/Pod/default/nginx", pod.to_string().as_bytes())
+            .unwrap();
+        backend
+            .put(b"ipam/alloc/10.0.0.2", b"default/nginx")
+            .unwrap();
+        assert!(check(&backend).unwrap().is_empty());
+
+        // Damage the index table behind the backend's back, and cut a
+        // value short
+        {
+            let write_txn = backend.db().begin_write().unwrap();
+            {
+                let mut indices = write_txn.open_table(INDICES_TABLE).unwrap();
+                indices
+                    .remove(b"namespace/default/v1/Pod/nginx".as_slice())
+                    .unwrap();
+                indices
+                    .insert(
+                        b"namespace/default/v1/Pod/gone".as_slice(),
+                        b"v1".as_slice(),
+                    )
+                    .unwrap();
+                let mut resources = write_txn.open_table(RESOURCES_TABLE).unwrap();
+                resources
+                    .insert(b"v1/Pod/default/torn".as_slice(), b"{\"apiVer".as_slice())
+                    .unwrap();
+            }
+            write_txn.commit().unwrap();
+        }
+
+        let issues = check(&backend).unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                IntegrityIssue::CorruptValue {
+                    key: "v1/Pod/default/torn".to_string()
+                },
+                IntegrityIssue::StaleIndex {
+                    index_key: "namespace/default/v1/Pod/gone".to_string()
+                },
+                IntegrityIssue::MissingIndex {
+                    index_key: "namespace/default/v1/Pod/nginx".to_string(),
+                    key: "v1/Pod/default/nginx".to_string()
+                },
+            ]
+        );
+
+        assert_eq!(rebuild_indices(&backend).unwrap(), 1);
+        assert_eq!(
+            drop_corrupt_values(&backend).unwrap(),
+            vec!["v1/Pod/default/torn".to_string()]
+        );
+        assert!(check(&backend).unwrap().is_empty());
+        assert!(backend.exists(b"ipam/alloc/10.0.0.2").unwrap());
+    }
+}
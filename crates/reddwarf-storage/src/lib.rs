@@ -5,14 +5,29 @@
 //! - redb-based implementation
 //! - Key encoding and indexing
 //! - Transaction support
+//! - Versioned schema migrations
+//! - Integrity checks and repair
+//! - An expiring, aggregating event store
+//! - Envelope encryption of values with pluggable KMS providers
+//! - A line-delimited export format for backups and migrations
 
 pub mod encoding;
+pub mod encryption;
 pub mod error;
+pub mod events;
+pub mod export;
+pub mod integrity;
 pub mod kv;
+pub mod migrations;
 pub mod redb_backend;
 
 // Re-export commonly used types
 pub use encoding::{IndexKey, KeyEncoder};
+pub use encryption::{EnvelopeEncryptor, ExternalKms, KmsProvider, LocalKms, WrappedKey};
 pub use error::{Result, StorageError};
+pub use events::{EventStore, EventStoreConfig};
+pub use export::{ExportDump, ExportHeader, ExportRecord, ExportSummary, ImportIssue};
+pub use integrity::IntegrityIssue;
 pub use kv::{KVStore, Transaction};
-pub use redb_backend::RedbBackend;
+pub use migrations::{Migration, MigrationReport, MigrationRun, Migrator};
+pub use redb_backend::{DurabilityMode, RedbBackend};
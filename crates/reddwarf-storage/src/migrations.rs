@@ -0,0 +1,665 @@
+//! Versioned schema migrations
+//!
+//! Each [`Migration`] rewrites stored data from one schema version to the
+//! next (a new key encoding, a new value format, ...). Applied versions are
+//! recorded in the `schema_migrations` table, and each migration commits
+//! atomically with its record, so an interrupted run resumes at the first
+//! migration that did not commit.
+//!
+//! A migration may also move entries out to the history database kept next
+//! to the live resources. Those copies commit just before the step does; if
+//! the run stops in between, the step runs again and copies them anew.
+
+use crate::redb_backend::{index_entries, INDICES_TABLE, RESOURCES_TABLE, SCHEMA_TABLE};
+use crate::{RedbBackend, Result, StorageError};
+use bytes::Bytes;
+use redb::{ReadableTable, TableDefinition, WriteTransaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// A migration step body
+pub type MigrationFn = fn(&mut MigrationTxn<'_>) -> Result<()>;
+
+/// Called with the pending migrations before any of them is applied
+pub type BackupHook = Box<dyn Fn(&[MigrationReport]) -> Result<()> + Send + Sync>;
+
+/// One schema change
+#[derive(Clone, Copy)]
+pub struct Migration {
+    /// Schema version this migration brings the database to
+    pub version: u32,
+    /// Short description, recorded with the version
+    pub name: &'static str,
+    /// Rewrites the data; must only go through the given transaction
+    pub up: MigrationFn,
+}
+
+/// Every migration of the storage schema, in version order
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            name: "initial-schema",
+            up: |_| Ok(()),
+        },
+        Migration {
+            version: 2,
+            name: "build-indices",
+            up: build_indices,
+        },
+        Migration {
+            version: 3,
+            name: "split-history",
+            up: split_history,
+        },
+    ]
+}
+
+/// Index every stored object; writes maintain the index from here on
+fn build_indices(txn: &mut MigrationTxn<'_>) -> Result<()> {
+    for (key, value) in txn.scan_resources(b"")? {
+        for index_key in index_entries(&value) {
+            txn.put_index(index_key.as_bytes(), &key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Move the version history out to the history database, so its growth no
+/// longer slows down scans of the live resources
+fn split_history(txn: &mut MigrationTxn<'_>) -> Result<()> {
+    for (key, value) in txn.scan_resources(b"version:")? {
+        txn.put_history(&key, &value)?;
+        txn.delete_resource(&key)?;
+    }
+    Ok(())
+}
+
+/// Row of the `schema_migrations` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AppliedMigration {
+    version: u32,
+    name: String,
+    /// Seconds since the Unix epoch
+    applied_at: u64,
+}
+
+/// What a migration did, or would do in a dry run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub version: u32,
+    pub name: &'static str,
+    /// Keys written, including those copied to the history database
+    pub puts: usize,
+    /// Keys deleted
+    pub deletes: usize,
+}
+
+/// Result of [`Migrator::run`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationRun {
+    /// Schema version before the run
+    pub from_version: u32,
+    /// Schema version after the run; unchanged by a dry run
+    pub to_version: u32,
+    /// Migrations applied (or, in a dry run, that would be), in order
+    pub migrations: Vec<MigrationReport>,
+    pub dry_run: bool,
+}
+
+/// Write access to the data tables for a migration, counting its changes
+pub struct MigrationTxn<'a> {
+    txn: &'a WriteTransaction,
+    has_history: bool,
+    /// Entries for the history database, written when the step commits
+    history_puts: Vec<(Bytes, Bytes)>,
+    puts: usize,
+    deletes: usize,
+}
+
+impl<'a> MigrationTxn<'a> {
+    /// Every resource entry whose key starts with `prefix`
+    pub fn scan_resources(&self, prefix: &[u8]) -> Result<Vec<(Bytes, Bytes)>> {
+        self.scan(RESOURCES_TABLE, prefix)
+    }
+
+    pub fn put_resource(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.put(RESOURCES_TABLE, key, value)
+    }
+
+    pub fn delete_resource(&mut self, key: &[u8]) -> Result<()> {
+        self.delete(RESOURCES_TABLE, key)
+    }
+
+    /// Every index entry whose key starts with `prefix`
+    pub fn scan_indices(&self, prefix: &[u8]) -> Result<Vec<(Bytes, Bytes)>> {
+        self.scan(INDICES_TABLE, prefix)
+    }
+
+    pub fn put_index(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.put(INDICES_TABLE, key, value)
+    }
+
+    pub fn delete_index(&mut self, key: &[u8]) -> Result<()> {
+        self.delete(INDICES_TABLE, key)
+    }
+
+    /// Copy an entry to the history database; fails unless the migrator
+    /// was given one
+    pub fn put_history(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        if !self.has_history {
+            return Err(StorageError::migration_error(
+                "This migration moves entries to the history database, which was not given",
+            ));
+        }
+        self.history_puts
+            .push((Bytes::copy_from_slice(key), Bytes::copy_from_slice(value)));
+        self.puts += 1;
+        Ok(())
+    }
+
+    fn scan(
+        &self,
+        definition: TableDefinition<&[u8], &[u8]>,
+        prefix: &[u8],
+    ) -> Result<Vec<(Bytes, Bytes)>> {
+        let table = self.txn.open_table(definition)?;
+        let mut results = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            if key.value().starts_with(prefix) {
+                results.push((
+                    Bytes::from(key.value().to_vec()),
+                    Bytes::from(value.value().to_vec()),
+                ));
+            }
+        }
+        Ok(results)
+    }
+
+    fn put(
+        &mut self,
+        definition: TableDefinition<&[u8], &[u8]>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<()> {
+        let mut table = self.txn.open_table(definition)?;
+        table.insert(key, value)?;
+        self.puts += 1;
+        Ok(())
+    }
+
+    fn delete(&mut self, definition: TableDefinition<&[u8], &[u8]>, key: &[u8]) -> Result<()> {
+        let mut table = self.txn.open_table(definition)?;
+        if table.remove(key)?.is_some() {
+            self.deletes += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Applies the pending migrations of a database
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    dry_run: bool,
+    backup: Option<BackupHook>,
+    history: Option<Arc<RedbBackend>>,
+}
+
+impl Migrator {
+    /// Create a migrator for `migrations`, which must have distinct versions
+    pub fn new(mut migrations: Vec<Migration>) -> Self {
+        migrations.sort_by_key(|m| m.version);
+        Self {
+            migrations,
+            dry_run: false,
+            backup: None,
+            history: None,
+        }
+    }
+
+    /// Let migrations move entries to `history`, the history database kept
+    /// next to the one being migrated. It only gains entries, so the backup
+    /// hook need not cover it.
+    pub fn with_history(mut self, history: Arc<RedbBackend>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Run the pending migrations and report what they change, then roll
+    /// them back instead of committing
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Call `hook` before applying anything, e.g. to back the database up;
+    /// an error from the hook aborts the run. Not called for dry runs or
+    /// when nothing is pending.
+    pub fn with_backup_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&[MigrationReport]) -> Result<()> + Send + Sync + 'static,
+    {
+        self.backup = Some(Box::new(hook));
+        self
+    }
+
+    /// Newest schema version this migrator knows
+    pub fn latest_version(&self) -> u32 {
+        self.migrations.last().map(|m| m.version).unwrap_or(0)
+    }
+
+    /// Schema version of `backend`: the newest applied migration, or 0
+    pub fn current_version(backend: &RedbBackend) -> Result<u32> {
+        Ok(applied(backend)?.keys().next_back().copied().unwrap_or(0))
+    }
+
+    /// Record every migration up to `version` as applied without running
+    /// it, for data written at that schema version by other means, such as
+    /// an import
+    pub fn record_applied(&self, backend: &RedbBackend, version: u32) -> Result<()> {
+        if version > self.latest_version() {
+            return Err(StorageError::migration_error(format!(
+                "Schema version {} is newer than the latest known version {}",
+                version,
+                self.latest_version()
+            )));
+        }
+        let applied = applied(backend)?;
+        if let Some(current) = applied.keys().next_back().filter(|v| **v > version) {
+            return Err(StorageError::migration_error(format!(
+                "Database is at schema version {} already, past {}",
+                current, version
+            )));
+        }
+
+        let db = backend.db();
+        let txn = db.begin_write()?;
+        for migration in &self.migrations {
+            if migration.version <= version && !applied.contains_key(&migration.version) {
+                record(&txn, migration)?;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Apply (or, in a dry run, try) every migration not yet recorded in
+    /// `backend`, in version order
+    pub fn run(&self, backend: &RedbBackend) -> Result<MigrationRun> {
+        if let Some(pair) = self
+            .migrations
+            .windows(2)
+            .find(|w| w[0].version == w[1].version)
+        {
+            return Err(StorageError::migration_error(format!(
+                "Migrations '{}' and '{}' share version {}",
+                pair[0].name, pair[1].name, pair[0].version
+            )));
+        }
+
+        let applied = applied(backend)?;
+        let from_version = applied.keys().next_back().copied().unwrap_or(0);
+        if from_version > self.latest_version() {
+            return Err(StorageError::migration_error(format!(
+                "Database schema version {} is newer than the latest known version {}; \
+                 refusing to run an older binary against it",
+                from_version,
+                self.latest_version()
+            )));
+        }
+
+        let pending: Vec<&Migration> = self
+            .migrations
+            .iter()
+            .filter(|m| !applied.contains_key(&m.version))
+            .collect();
+        let mut run = MigrationRun {
+            from_version,
+            to_version: from_version,
+            migrations: Vec::new(),
+            dry_run: self.dry_run,
+        };
+        if pending.is_empty() {
+            return Ok(run);
+        }
+
+        let db = backend.db();
+        if self.dry_run {
+            // All steps share one transaction so each sees its
+            // predecessors' changes, and nothing is committed
+            let txn = db.begin_write()?;
+            for migration in pending {
+                match apply(&txn, migration, self.history.is_some()) {
+                    Ok((report, _)) => run.migrations.push(report),
+                    Err(e) => {
+                        txn.abort()?;
+                        return Err(e);
+                    }
+                }
+            }
+            txn.abort()?;
+            return Ok(run);
+        }
+
+        if let Some(backup) = &self.backup {
+            let plan: Vec<MigrationReport> = pending
+                .iter()
+                .map(|m| MigrationReport {
+                    version: m.version,
+                    name: m.name,
+                    puts: 0,
+                    deletes: 0,
+                })
+                .collect();
+            backup(&plan)?;
+        }
+
+        for migration in pending {
+            let txn = db.begin_write()?;
+            let (report, history_puts) = match apply(&txn, migration, self.history.is_some()) {
+                Ok(applied) => applied,
+                Err(e) => {
+                    txn.abort()?;
+                    return Err(e);
+                }
+            };
+            if let Some(history) = self.history.as_ref().filter(|_| !history_puts.is_empty()) {
+                let history_txn = history.db().begin_write()?;
+                {
+                    let mut table = history_txn.open_table(RESOURCES_TABLE)?;
+                    for (key, value) in &history_puts {
+                        table.insert(key.as_ref(), value.as_ref())?;
+                    }
+                }
+                history_txn.commit()?;
+            }
+            record(&txn, migration)?;
+            txn.commit()?;
+            info!(
+                "Applied storage migration {} ({}): {} puts, {} deletes",
+                report.version, report.name, report.puts, report.deletes
+            );
+            run.to_version = migration.version;
+            run.migrations.push(report);
+        }
+
+        Ok(run)
+    }
+}
+
+/// Run one step in `txn`, returning its report and the entries it copies
+/// to the history database
+fn apply(
+    txn: &WriteTransaction,
+    migration: &Migration,
+    has_history: bool,
+) -> Result<(MigrationReport, Vec<(Bytes, Bytes)>)> {
+    let mut migration_txn = MigrationTxn {
+        txn,
+        has_history,
+        history_puts: Vec::new(),
+        puts: 0,
+        deletes: 0,
+    };
+    (migration.up)(&mut migration_txn).map_err(|e| {
+        StorageError::migration_error(format!(
+            "Migration {} ({}) failed: {}",
+            migration.version, migration.name, e
+        ))
+    })?;
+    let report = MigrationReport {
+        version: migration.version,
+        name: migration.name,
+        puts: migration_txn.puts,
+        deletes: migration_txn.deletes,
+    };
+    Ok((report, migration_txn.history_puts))
+}
+
+fn record(txn: &WriteTransaction, migration: &Migration) -> Result<()> {
+    let row = AppliedMigration {
+        version: migration.version,
+        name: migration.name.to_string(),
+        applied_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    let mut table = txn.open_table(SCHEMA_TABLE)?;
+    table.insert(
+        migration.version.to_be_bytes().as_slice(),
+        serde_json::to_vec(&row)?.as_slice(),
+    )?;
+    Ok(())
+}
+
+fn applied(backend: &RedbBackend) -> Result<BTreeMap<u32, AppliedMigration>> {
+    let read_txn = backend.db().begin_read()?;
+    let table = read_txn.open_table(SCHEMA_TABLE)?;
+    let mut applied = BTreeMap::new();
+    for entry in table.iter()? {
+        let (_, value) = entry?;
+        let row: AppliedMigration = serde_json::from_slice(value.value())?;
+        applied.insert(row.version, row);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KVStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    /// Moves every `v1/` key to `v2/`
+    fn rename_keys(txn: &mut MigrationTxn<'_>) -> Result<()> {
+        for (key, value) in txn.scan_resources(b"v1/")? {
+            let mut renamed = b"v2/".to_vec();
+            renamed.extend_from_slice(&key[3..]);
+            txn.put_resource(&renamed, &value)?;
+            txn.delete_resource(&key)?;
+        }
+        Ok(())
+    }
+
+    fn test_migrations() -> Vec<Migration> {
+        vec![
+            Migration {
+                version: 1,
+                name: "noop",
+                up: |_| Ok(()),
+            },
+            Migration {
+                version: 2,
+                name: "rename-keys",
+                up: rename_keys,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_migrations_apply_once() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        backend.put(b"v1/a", b"1").unwrap();
+        backend.put(b"v1/b", b"2").unwrap();
+
+        let backups = Arc::new(AtomicUsize::new(0));
+        let counter = backups.clone();
+        let migrator = Migrator::new(test_migrations()).with_backup_hook(move |plan| {
+            assert_eq!(plan.len(), 2);
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+
+        let run = migrator.run(&backend).unwrap();
+        assert_eq!((run.from_version, run.to_version), (0, 2));
+        assert_eq!(run.migrations[1].puts, 2);
+        assert_eq!(run.migrations[1].deletes, 2);
+        assert_eq!(backend.get(b"v2/a").unwrap(), Some(Bytes::from("1")));
+        assert!(backend.keys_with_prefix(b"v1/").unwrap().is_empty());
+        assert_eq!(Migrator::current_version(&backend).unwrap(), 2);
+
+        // Nothing pending: no migration and no backup
+        let run = migrator.run(&backend).unwrap();
+        assert!(run.migrations.is_empty());
+        assert_eq!(run.to_version, 2);
+        assert_eq!(backups.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dry_run_and_failures_leave_data_untouched() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        backend.put(b"v1/a", b"1").unwrap();
+
+        let run = Migrator::new(test_migrations())
+            .with_dry_run(true)
+            .run(&backend)
+            .unwrap();
+        assert!(run.dry_run);
+        assert_eq!(run.to_version, 0);
+        assert_eq!(run.migrations[1].puts, 1);
+        assert!(backend.exists(b"v1/a").unwrap());
+        assert_eq!(Migrator::current_version(&backend).unwrap(), 0);
+
+        // A failing backup hook aborts before anything is applied
+        let err = Migrator::new(test_migrations())
+            .with_backup_hook(|_| Err(StorageError::io_error("disk full", None)))
+            .run(&backend)
+            .unwrap_err();
+        assert!(err.to_string().contains("disk full"));
+        assert_eq!(Migrator::current_version(&backend).unwrap(), 0);
+
+        // A failing step leaves the versions before it applied
+        let mut failing = test_migrations();
+        failing.push(Migration {
+            version: 3,
+            name: "broken",
+            up: |txn| {
+                txn.put_resource(b"v3/a", b"1")?;
+                Err(StorageError::serialization_error("bad value", None))
+            },
+        });
+        let err = Migrator::new(failing).run(&backend).unwrap_err();
+        assert!(matches!(err, StorageError::MigrationError { .. }));
+        assert_eq!(Migrator::current_version(&backend).unwrap(), 2);
+        assert!(!backend.exists(b"v3/a").unwrap());
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        Migrator::new(test_migrations()).run(&backend).unwrap();
+
+        let err = Migrator::new(test_migrations()[..1].to_vec())
+            .run(&backend)
+            .unwrap_err();
+        assert!(err.to_string().contains("newer than the latest known"));
+    }
+
+    #[test]
+    fn test_backup_to() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        backend.put(b"key", b"value").unwrap();
+        Migrator::new(migrations()).run(&backend).unwrap();
+
+        let dest = dir.path().join("backup.redb");
+        backend.backup_to(&dest).unwrap();
+        assert!(backend.backup_to(&dest).is_err());
+        drop(backend);
+
+        let restored = RedbBackend::new(&dest).unwrap();
+        assert_eq!(restored.get(b"key").unwrap(), Some(Bytes::from("value")));
+        assert_eq!(
+            Migrator::current_version(&restored).unwrap(),
+            Migrator::new(migrations()).latest_version()
+        );
+    }
+
+    #[test]
+    fn test_build_indices_migration() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+
+        // An object written before the backend maintained indices
+        let pod = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": "nginx", "namespace": "default"}
+        });
+        {
+            let write_txn = backend.db().begin_write().unwrap();
+            write_txn
+                .open_table(RESOURCES_TABLE)
+                .unwrap()
+                .insert(
+                    b"v1/Pod/default/nginx".as_slice(),
+                    pod.to_string().as_bytes(),
+                )
+                .unwrap();
+            write_txn.commit().unwrap();
+        }
+        assert_eq!(crate::integrity::check(&backend).unwrap().len(), 1);
+
+        Migrator::new(migrations()).run(&backend).unwrap();
+        assert!(crate::integrity::check(&backend).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_split_history_migration() {
+        let dir = tempdir().unwrap();
+        let backend = RedbBackend::new(dir.path().join("test.redb")).unwrap();
+        let history = Arc::new(RedbBackend::new(dir.path().join("test.history")).unwrap());
+        Migrator::new(migrations()[..2].to_vec())
+            .run(&backend)
+            .unwrap();
+        backend.put(b"version:commit:a", b"{}").unwrap();
+        backend.put(b"version:head", b"a").unwrap();
+        backend.put(b"v1/Pod/default/nginx", b"{}").unwrap();
+
+        // Without the history database there is nowhere to move it to
+        let err = Migrator::new(migrations()).run(&backend).unwrap_err();
+        assert!(err.to_string().contains("history database"));
+        assert_eq!(Migrator::current_version(&backend).unwrap(), 2);
+
+        let run = Migrator::new(migrations())
+            .with_history(history.clone())
+            .with_dry_run(true)
+            .run(&backend)
+            .unwrap();
+        assert_eq!((run.migrations[0].puts, run.migrations[0].deletes), (2, 2));
+        assert!(history.keys().unwrap().is_empty());
+        assert_eq!(backend.keys_with_prefix(b"version:").unwrap().len(), 2);
+
+        // A run stopped after copying but before the step committed left a
+        // copy behind; the next run copies again and finishes the move
+        history.put(b"version:commit:a", b"{}").unwrap();
+        let run = Migrator::new(migrations())
+            .with_history(history.clone())
+            .run(&backend)
+            .unwrap();
+        assert_eq!((run.from_version, run.to_version), (2, 3));
+        assert!(backend.keys_with_prefix(b"version:").unwrap().is_empty());
+        assert!(backend.exists(b"v1/Pod/default/nginx").unwrap());
+        assert_eq!(
+            history.get(b"version:head").unwrap(),
+            Some(Bytes::from("a"))
+        );
+        assert_eq!(history.keys().unwrap().len(), 2);
+
+        let run = Migrator::new(migrations())
+            .with_history(history)
+            .run(&backend)
+            .unwrap();
+        assert!(run.migrations.is_empty());
+    }
+}
@@ -2,6 +2,7 @@
 #![allow(unused_assignments)]
 
 use miette::Diagnostic;
+use reddwarf_core::{ErrorClass, ErrorKind};
 use thiserror::Error;
 
 /// Versioning error type
@@ -87,3 +88,16 @@ impl VersioningError {
         }
     }
 }
+
+impl ErrorClass for VersioningError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::CommitNotFound { .. } => ErrorKind::NotFound,
+            Self::Conflict { .. } => ErrorKind::Conflict,
+            Self::InvalidOperation { .. } => ErrorKind::Invalid,
+            Self::StorageError(e) => e.kind(),
+            Self::CoreError(e) => e.kind(),
+            Self::InternalError { .. } => ErrorKind::Internal,
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Integrity of the commit DAG: HEAD names a stored commit, every commit
+//! parses and HEAD is the newest tip of the DAG
+
+use crate::{Commit, Result, VersionStore};
+use reddwarf_storage::KVStore;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use tracing::info;
+
+/// A violated commit DAG invariant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DagIssue {
+    /// Commits exist but no HEAD is recorded
+    NoHead,
+    /// HEAD names a commit that is not stored
+    MissingHead { head: String },
+    /// A stored commit that does not parse
+    CorruptCommit { commit_id: String },
+    /// A commit names a parent that is not stored
+    MissingParent { commit_id: String, parent: String },
+    /// A newer tip than HEAD exists, e.g. a commit written just before a
+    /// crash that lost the HEAD update
+    HeadBehind { head: String, latest: String },
+}
+
+impl DagIssue {
+    /// Whether `reddwarf repair` fixes this by re-deriving HEAD
+    pub fn is_head_issue(&self) -> bool {
+        matches!(
+            self,
+            DagIssue::NoHead | DagIssue::MissingHead { .. } | DagIssue::HeadBehind { .. }
+        )
+    }
+}
+
+impl fmt::Display for DagIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DagIssue::NoHead => write!(f, "commits exist but HEAD is not set"),
+            DagIssue::MissingHead { head } => write!(f, "HEAD names missing commit {}", head),
+            DagIssue::CorruptCommit { commit_id } => write!(f, "corrupt commit {}", commit_id),
+            DagIssue::MissingParent { commit_id, parent } => {
+                write!(f, "commit {} names missing parent {}", commit_id, parent)
+            }
+            DagIssue::HeadBehind { head, latest } => {
+                write!(f, "HEAD {} is behind newer commit {}", head, latest)
+            }
+        }
+    }
+}
+
+pub(crate) const COMMIT_PREFIX: &str = "version:commit:";
+
+impl VersionStore {
+    /// Check the commit DAG against the stored HEAD
+    pub fn check_integrity(&self) -> Result<Vec<DagIssue>> {
+        let (commits, corrupt) = self.load_commits()?;
+        let mut issues: Vec<DagIssue> = corrupt
+            .into_iter()
+            .map(|commit_id| DagIssue::CorruptCommit { commit_id })
+            .collect();
+
+        for commit in commits.values() {
+            for parent in &commit.parents {
+                if !commits.contains_key(parent) {
+                    issues.push(DagIssue::MissingParent {
+                        commit_id: commit.id.clone(),
+                        parent: parent.clone(),
+                    });
+                }
+            }
+        }
+
+        let head = self.head_id();
+        let latest = latest_tip(&commits);
+        match (head, latest) {
+            (None, Some(_)) => issues.push(DagIssue::NoHead),
+            (Some(head), _) if !commits.contains_key(&head) => {
+                issues.push(DagIssue::MissingHead { head })
+            }
+            // HEAD may lag only if something newer exists
+            (Some(head), Some(latest))
+                if head != latest.id && commits[&head].timestamp < latest.timestamp =>
+            {
+                issues.push(DagIssue::HeadBehind {
+                    head,
+                    latest: latest.id.clone(),
+                })
+            }
+            _ => {}
+        }
+
+        Ok(issues)
+    }
+
+    /// Point HEAD at the newest tip of the DAG (the most recent commit no
+    /// other commit names as a parent); returns the new HEAD
+    pub fn rederive_head(&self) -> Result<Option<String>> {
+        let (commits, _) = self.load_commits()?;
+        let Some(latest) = latest_tip(&commits) else {
+            return Ok(None);
+        };
+        let latest = latest.id.clone();
+        if self.head_id().as_deref() != Some(latest.as_str()) {
+            info!("Re-derived HEAD from the commit DAG: {}", latest);
+            self.set_head(latest.clone())?;
+        }
+        Ok(Some(latest))
+    }
+
+    /// Every commit that parses, by id, and the ids of those that do not
+    pub(crate) fn load_commits(&self) -> Result<(HashMap<String, Commit>, Vec<String>)> {
+        let mut commits = HashMap::new();
+        let mut corrupt = Vec::new();
+        for (key, value) in self.history.scan(COMMIT_PREFIX.as_bytes())? {
+            let id = String::from_utf8_lossy(&key[COMMIT_PREFIX.len()..]).to_string();
+            match serde_json::from_slice::<Commit>(&value) {
+                Ok(commit) => {
+                    commits.insert(id, commit);
+                }
+                Err(_) => corrupt.push(id),
+            }
+        }
+        corrupt.sort();
+        Ok((commits, corrupt))
+    }
+}
+
+/// The newest commit that no other commit names as a parent
+fn latest_tip(commits: &HashMap<String, Commit>) -> Option<&Commit> {
+    let parents: HashSet<&String> = commits.values().flat_map(|c| &c.parents).collect();
+    commits
+        .values()
+        .filter(|c| !parents.contains(&c.id))
+        .max_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Change, CommitBuilder};
+    use reddwarf_storage::RedbBackend;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_check_integrity_and_rederive_head() {
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let store = VersionStore::new(backend.clone()).unwrap();
+
+        let first = store
+            .create_commit(CommitBuilder::new().change(Change::create(
+                "v1/Pod/default/a".to_string(),
+                "{}".to_string(),
+            )))
+            .unwrap();
+        let second = store
+            .create_commit(CommitBuilder::new().parent(first.id.clone()))
+            .unwrap();
+        assert!(store.check_integrity().unwrap().is_empty());
+
+        // A crash between writing a commit and moving HEAD
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let lost = CommitBuilder::new().parent(second.id.clone()).build();
+        backend
+            .put(
+                format!("{}{}", COMMIT_PREFIX, lost.id).as_bytes(),
+                &serde_json::to_vec(&lost).unwrap(),
+            )
+            .unwrap();
+        backend.put(b"version:commit:torn", b"{\"id\":").unwrap();
+
+        let issues = store.check_integrity().unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                DagIssue::CorruptCommit {
+                    commit_id: "torn".to_string()
+                },
+                DagIssue::HeadBehind {
+                    head: second.id.clone(),
+                    latest: lost.id.clone()
+                },
+            ]
+        );
+        assert!(!issues[0].is_head_issue());
+        assert!(issues[1].is_head_issue());
+
+        assert_eq!(store.rederive_head().unwrap(), Some(lost.id.clone()));
+        assert_eq!(store.get_head().unwrap().unwrap().id, lost.id);
+
+        // A HEAD naming a missing commit
+        backend.put(b"version:head", b"gone").unwrap();
+        let reloaded = VersionStore::new(backend.clone()).unwrap();
+        assert!(reloaded
+            .check_integrity()
+            .unwrap()
+            .contains(&DagIssue::MissingHead {
+                head: "gone".to_string()
+            }));
+        reloaded.rederive_head().unwrap();
+        assert_eq!(reloaded.get_head().unwrap().unwrap().id, lost.id);
+    }
+}
@@ -5,14 +5,20 @@
 //! - Commit operations for resource changes
 //! - Conflict detection and representation
 //! - DAG traversal for WATCH operations
+//! - DAG integrity checks and HEAD recovery
+//! - Squashing of runs of single-resource updates
 
 pub mod commit;
 pub mod conflict;
 pub mod error;
+pub mod integrity;
+pub mod squash;
 pub mod store;
 
 // Re-export commonly used types
 pub use commit::{Change, ChangeType, Commit, CommitBuilder};
 pub use conflict::{Conflict, ConflictSide};
 pub use error::{Result, VersioningError};
+pub use integrity::DagIssue;
+pub use squash::{HistoryPin, SquashReport};
 pub use store::VersionStore;
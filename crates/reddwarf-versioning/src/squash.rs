@@ -0,0 +1,379 @@
+//! Squashing of per-resource micro-commits: status heartbeats and other
+//! small writes leave long runs of single-change updates of one resource,
+//! which are collapsed so history stays small and DAG traversal fast
+
+use crate::integrity::COMMIT_PREFIX;
+use crate::{Change, ChangeType, Commit, Result, VersionStore, VersioningError};
+use reddwarf_storage::KVStore;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// Outcome of squashing the history of one or more resources
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SquashReport {
+    /// Runs of commits collapsed into their last commit
+    pub runs: usize,
+    /// Commits removed from the history
+    pub removed: usize,
+}
+
+impl SquashReport {
+    fn add(&mut self, other: SquashReport) {
+        self.runs += other.runs;
+        self.removed += other.removed;
+    }
+}
+
+/// Commits held by [`HistoryPin`]s, by pin
+#[derive(Debug, Default)]
+pub(crate) struct Pins {
+    next: u64,
+    commits: HashMap<u64, String>,
+}
+
+/// Keeps the commit a connected watch or replica is positioned at from
+/// being squashed away, so it can resume from it if it is disconnected.
+/// Moves along as the client is sent newer commits, and releases the
+/// commit when dropped.
+pub struct HistoryPin {
+    id: u64,
+    pins: Arc<parking_lot::Mutex<Pins>>,
+}
+
+impl HistoryPin {
+    /// Hold `commit_id` instead of the commit held so far
+    pub fn advance(&self, commit_id: &str) {
+        if let Some(held) = self.pins.lock().commits.get_mut(&self.id) {
+            commit_id.clone_into(held);
+        }
+    }
+}
+
+impl Drop for HistoryPin {
+    fn drop(&mut self) {
+        self.pins.lock().commits.remove(&self.id);
+    }
+}
+
+impl VersionStore {
+    /// Keep `commit_id` from being squashed away while the pin is held
+    pub fn pin(&self, commit_id: &str) -> HistoryPin {
+        let mut pins = self.pins.lock();
+        let id = pins.next;
+        pins.next += 1;
+        pins.commits.insert(id, commit_id.to_string());
+        HistoryPin {
+            id,
+            pins: Arc::clone(&self.pins),
+        }
+    }
+
+    /// Commits held by pins
+    fn pinned(&self) -> HashSet<String> {
+        self.pins.lock().commits.values().cloned().collect()
+    }
+
+    /// Collapse runs of consecutive single-change updates of `resource_key`
+    /// into the last commit of each run, which keeps its ID, timestamp and
+    /// resulting content and takes the previous content and parents of the
+    /// first. The first commit of the resource, its last `keep_last_n`
+    /// commits, merges, commits changing other resources too and commits
+    /// more than one commit builds on are never squashed, so every state a
+    /// resource was created, deleted or branched in survives. Neither are
+    /// [pinned](Self::pin) commits, which end a run instead.
+    ///
+    /// Watches and replicas resuming from a removed commit are told their
+    /// resource version is too old, as if it had been compacted away.
+    pub fn squash_linear_history(
+        &self,
+        resource_key: &str,
+        keep_last_n: usize,
+    ) -> Result<SquashReport> {
+        let (commits, _) = self.load_commits()?;
+        let children = children(&commits);
+        let pinned = self.pinned();
+        let runs = squashable_runs(&commits, &children, &pinned, resource_key, keep_last_n);
+        self.squash_runs(&commits, resource_key, runs)
+    }
+
+    /// [`squash_linear_history`](Self::squash_linear_history) of every
+    /// resource with history
+    pub fn squash_all_linear_history(&self, keep_last_n: usize) -> Result<SquashReport> {
+        let (commits, _) = self.load_commits()?;
+        let children = children(&commits);
+        let pinned = self.pinned();
+        let resource_keys: HashSet<&str> = commits
+            .values()
+            .filter(|c| c.changes.len() == 1)
+            .map(|c| c.changes[0].resource_key.as_str())
+            .collect();
+
+        let mut report = SquashReport::default();
+        for resource_key in resource_keys {
+            let runs = squashable_runs(&commits, &children, &pinned, resource_key, keep_last_n);
+            report.add(self.squash_runs(&commits, resource_key, runs)?);
+        }
+        Ok(report)
+    }
+
+    /// Replace each run of commits with one commit, in one transaction
+    fn squash_runs(
+        &self,
+        commits: &HashMap<String, Commit>,
+        resource_key: &str,
+        runs: Vec<Vec<&str>>,
+    ) -> Result<SquashReport> {
+        if runs.is_empty() {
+            return Ok(SquashReport::default());
+        }
+
+        let head = self.head_id();
+        let mut new_head = None;
+        let mut report = SquashReport::default();
+        let mut txn = self.history.transaction()?;
+        for run in &runs {
+            let first = &commits[run[0]];
+            let last = &commits[run[run.len() - 1]];
+            let squashed = Commit {
+                id: last.id.clone(),
+                parents: first.parents.clone(),
+                changes: vec![Change::new(
+                    ChangeType::Update,
+                    resource_key.to_string(),
+                    last.changes[0].content.clone(),
+                    first.changes[0].previous_content.clone(),
+                )],
+                message: format!("{} (squashed {} updates)", last.message, run.len()),
+                author: last.author.clone(),
+                timestamp: last.timestamp,
+            };
+            let json = serde_json::to_vec(&squashed).map_err(|e| {
+                VersioningError::internal_error(format!("Failed to serialize commit: {}", e))
+            })?;
+            txn.put(
+                format!("{}{}", COMMIT_PREFIX, squashed.id).as_bytes(),
+                &json,
+            )?;
+            for removed in &run[..run.len() - 1] {
+                txn.delete(format!("{}{}", COMMIT_PREFIX, removed).as_bytes())?;
+                if head.as_deref() == Some(*removed) {
+                    new_head = Some(squashed.id.clone());
+                }
+            }
+            report.runs += 1;
+            report.removed += run.len() - 1;
+        }
+        txn.commit()?;
+        if let Some(head) = new_head {
+            self.set_head(head)?;
+        }
+
+        info!(
+            "Squashed {} commits of {} into {}",
+            report.removed + report.runs,
+            resource_key,
+            report.runs
+        );
+        Ok(report)
+    }
+}
+
+/// IDs of the commits naming each commit as a parent
+fn children(commits: &HashMap<String, Commit>) -> HashMap<&str, Vec<&str>> {
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for commit in commits.values() {
+        for parent in &commit.parents {
+            children
+                .entry(parent.as_str())
+                .or_default()
+                .push(commit.id.as_str());
+        }
+    }
+    children
+}
+
+/// Runs of two or more consecutive commits of `resource_key` that may be
+/// collapsed into their last, oldest first; a `pinned` commit may only be
+/// the last of its run
+fn squashable_runs<'a>(
+    commits: &'a HashMap<String, Commit>,
+    children: &HashMap<&str, Vec<&str>>,
+    pinned: &HashSet<String>,
+    resource_key: &str,
+    keep_last_n: usize,
+) -> Vec<Vec<&'a str>> {
+    let mut history: Vec<&Commit> = commits
+        .values()
+        .filter(|c| c.changes.iter().any(|ch| ch.resource_key == resource_key))
+        .collect();
+    history.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)));
+
+    // The first state and the last `keep_last_n` are kept as they are
+    let end = history.len().saturating_sub(keep_last_n.max(1));
+    let is_update = |c: &Commit| {
+        c.changes.len() == 1
+            && c.changes[0].change_type == ChangeType::Update
+            && c.parents.len() <= 1
+    };
+    // `next` follows `prev` in a straight line: it builds on nothing else,
+    // and nothing else builds on `prev`
+    let follows = |prev: &Commit, next: &Commit| {
+        (next.parents.is_empty() || next.parents == [prev.id.clone()])
+            && children
+                .get(prev.id.as_str())
+                .is_none_or(|c| c.iter().all(|child| *child == next.id))
+    };
+
+    let mut runs = Vec::new();
+    let mut run: Vec<&Commit> = Vec::new();
+    for commit in history.iter().take(end).skip(1).copied() {
+        let extends = is_update(commit) && run.last().is_none_or(|prev| follows(prev, commit));
+        if !extends {
+            if run.len() > 1 {
+                runs.push(run.iter().map(|c| c.id.as_str()).collect());
+            }
+            run.clear();
+        }
+        if is_update(commit) {
+            run.push(commit);
+        }
+        if pinned.contains(&commit.id) {
+            if run.len() > 1 {
+                runs.push(run.iter().map(|c| c.id.as_str()).collect());
+            }
+            run.clear();
+        }
+    }
+    if run.len() > 1 {
+        runs.push(run.iter().map(|c| c.id.as_str()).collect());
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommitBuilder;
+    use reddwarf_storage::RedbBackend;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    const POD: &str = "v1/Pod/default/web";
+
+    fn store() -> (tempfile::TempDir, VersionStore) {
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        (dir, VersionStore::new(backend).unwrap())
+    }
+
+    fn commit(store: &VersionStore, parent: Option<&Commit>, change: Change) -> Commit {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let mut builder = CommitBuilder::new().change(change);
+        if let Some(parent) = parent {
+            builder = builder.parent(parent.id.clone());
+        }
+        store.create_commit(builder).unwrap()
+    }
+
+    fn update(n: u32) -> Change {
+        Change::update(
+            POD.to_string(),
+            format!("{{\"n\":{}}}", n),
+            format!("{{\"n\":{}}}", n - 1),
+        )
+    }
+
+    #[test]
+    fn test_squash_linear_history() {
+        let (_dir, store) = store();
+        let created = commit(
+            &store,
+            None,
+            Change::create(POD.to_string(), "{\"n\":0}".to_string()),
+        );
+        let mut chain = vec![created.clone()];
+        for n in 1..=5 {
+            let next = commit(&store, chain.last(), update(n));
+            chain.push(next);
+        }
+        // Another resource's commit builds on the second update
+        let other = commit(
+            &store,
+            Some(&chain[2]),
+            Change::create("v1/Pod/default/db".to_string(), "{}".to_string()),
+        );
+        let last = commit(&store, chain.last(), update(6));
+        chain.push(last.clone());
+
+        // Updates 1-2 collapse into 2, which the other commit builds on, and
+        // 3-4 into 4; 5 and 6 are kept
+        let report = store.squash_linear_history(POD, 2).unwrap();
+        assert_eq!(
+            report,
+            SquashReport {
+                runs: 2,
+                removed: 2
+            }
+        );
+
+        let squashed = store.get_commit(&chain[4].id).unwrap();
+        assert_eq!(squashed.parents, vec![chain[2].id.clone()]);
+        assert_eq!(squashed.changes[0].content, "{\"n\":4}");
+        assert_eq!(
+            squashed.changes[0].previous_content.as_deref(),
+            Some("{\"n\":2}")
+        );
+        assert_eq!(
+            store.get_commit(&chain[2].id).unwrap().parents,
+            vec![created.id.clone()]
+        );
+        assert!(store.get_commit(&chain[1].id).is_err());
+        assert!(store.get_commit(&chain[3].id).is_err());
+        for kept in [&created, &chain[5], &other, &last] {
+            assert!(store.get_commit(&kept.id).is_ok());
+        }
+        assert_eq!(store.head_id(), Some(last.id.clone()));
+        assert!(store.check_integrity().unwrap().is_empty());
+
+        // Nothing left to squash
+        assert_eq!(
+            store.squash_linear_history(POD, 2).unwrap(),
+            SquashReport::default()
+        );
+    }
+
+    #[test]
+    fn test_squash_all_parentless_history() {
+        let (_dir, store) = store();
+        for key in ["v1/Pod/default/a", "v1/Pod/default/b"] {
+            commit(
+                &store,
+                None,
+                Change::create(key.to_string(), "{}".to_string()),
+            );
+            for n in 1..=4 {
+                let mut change = update(n);
+                change.resource_key = key.to_string();
+                commit(&store, None, change);
+            }
+            commit(
+                &store,
+                None,
+                Change::delete(key.to_string(), "{}".to_string()),
+            );
+        }
+
+        // Per resource: the creation, updates 1-3 squashed into one, the
+        // last update and the deletion
+        let report = store.squash_all_linear_history(2).unwrap();
+        assert_eq!(
+            report,
+            SquashReport {
+                runs: 2,
+                removed: 4
+            }
+        );
+        assert_eq!(store.list_commits().unwrap().len(), 8);
+    }
+}
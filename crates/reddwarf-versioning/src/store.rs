@@ -1,3 +1,4 @@
+use crate::squash::Pins;
 use crate::{Change, Commit, CommitBuilder, Conflict, ConflictSide, Result, VersioningError};
 use reddwarf_storage::{KVStore, RedbBackend};
 use std::collections::{HashMap, HashSet};
@@ -5,24 +6,31 @@ use std::sync::Arc;
 use tracing::{debug, info};
 
 /// Version store for managing DAG-based resource versions
+///
+/// Commits and HEAD live in a history database, which may be the database of
+/// the live resources or one of its own. Kept apart, history growth never
+/// slows down scans of the live resources or bloats their database.
 pub struct VersionStore {
-    storage: Arc<RedbBackend>,
+    pub(crate) history: Arc<RedbBackend>,
     /// Current HEAD commit ID (latest commit)
     head: parking_lot::RwLock<Option<String>>,
+    /// Commits connected watches and replicas are positioned at
+    pub(crate) pins: Arc<parking_lot::Mutex<Pins>>,
 }
 
 impl VersionStore {
-    /// Create a new VersionStore
+    /// Create a new VersionStore keeping history in `storage`
     pub fn new(storage: Arc<RedbBackend>) -> Result<Self> {
         info!("Initializing VersionStore");
 
         let store = Self {
-            storage,
+            history: storage,
             head: parking_lot::RwLock::new(None),
+            pins: Arc::default(),
         };
 
         // Load HEAD from storage
-        if let Some(head_bytes) = store.storage.get(b"version:head")? {
+        if let Some(head_bytes) = store.history.get(b"version:head")? {
             let head_id = String::from_utf8_lossy(&head_bytes).to_string();
             info!("Loaded HEAD: {}", head_id);
             *store.head.write() = Some(head_id);
@@ -31,25 +39,39 @@ impl VersionStore {
         Ok(store)
     }
 
+    /// The database the history is kept in
+    pub fn history(&self) -> Arc<RedbBackend> {
+        Arc::clone(&self.history)
+    }
+
     /// Create a new commit
     pub fn create_commit(&self, builder: CommitBuilder) -> Result<Commit> {
         let commit = builder.build();
         debug!("Creating commit: {}", commit.id);
+        self.store_commit(&commit)?;
+        info!("Created commit: {}", commit.id);
+        Ok(commit)
+    }
 
+    /// Store a commit made by another store, such as the leader a read
+    /// replica follows, under its own ID and make it HEAD
+    pub fn apply_commit(&self, commit: &Commit) -> Result<()> {
+        debug!("Applying commit: {}", commit.id);
+        self.store_commit(commit)
+    }
+
+    fn store_commit(&self, commit: &Commit) -> Result<()> {
         // Serialize and store the commit
-        let commit_json = serde_json::to_string(&commit).map_err(|e| {
+        let commit_json = serde_json::to_string(commit).map_err(|e| {
             VersioningError::internal_error(format!("Failed to serialize commit: {}", e))
         })?;
 
         let commit_key = format!("version:commit:{}", commit.id);
-        self.storage
+        self.history
             .put(commit_key.as_bytes(), commit_json.as_bytes())?;
 
         // Update HEAD
-        self.set_head(commit.id.clone())?;
-
-        info!("Created commit: {}", commit.id);
-        Ok(commit)
+        self.set_head(commit.id.clone())
     }
 
     /// Get a commit by ID
@@ -58,7 +80,7 @@ impl VersionStore {
 
         let commit_key = format!("version:commit:{}", commit_id);
         let commit_bytes = self
-            .storage
+            .history
             .get(commit_key.as_bytes())?
             .ok_or_else(|| VersioningError::commit_not_found(commit_id))?;
 
@@ -79,20 +101,25 @@ impl VersionStore {
         }
     }
 
+    /// ID of the current HEAD commit, if any
+    pub fn head_id(&self) -> Option<String> {
+        self.head.read().clone()
+    }
+
     /// Set the HEAD commit
-    fn set_head(&self, commit_id: String) -> Result<()> {
-        self.storage.put(b"version:head", commit_id.as_bytes())?;
+    pub(crate) fn set_head(&self, commit_id: String) -> Result<()> {
+        self.history.put(b"version:head", commit_id.as_bytes())?;
         *self.head.write() = Some(commit_id);
         Ok(())
     }
 
     /// Get all commits (for debugging)
     pub fn list_commits(&self) -> Result<Vec<Commit>> {
-        let keys = self.storage.keys_with_prefix(b"version:commit:")?;
+        let keys = self.history.keys_with_prefix(b"version:commit:")?;
         let mut commits = Vec::new();
 
         for key in keys {
-            let commit_bytes = self.storage.get(&key)?.unwrap();
+            let commit_bytes = self.history.get(&key)?.unwrap();
             let commit: Commit = serde_json::from_slice(&commit_bytes).map_err(|e| {
                 VersioningError::internal_error(format!("Failed to deserialize commit: {}", e))
             })?;
@@ -232,6 +259,87 @@ impl VersionStore {
 
         Ok(commits)
     }
+
+    /// Commits made after `since`, parents before children, as a watch
+    /// resuming from `since` replays them
+    ///
+    /// Commits descending from `since` follow it and its ancestors precede
+    /// it, whatever their timestamps say. Commits unrelated to it, such as
+    /// the root commits the API server writes, follow it when they are
+    /// newer. Fails with `CommitNotFound` when `since` is not a commit, e.g.
+    /// one squashed away.
+    pub fn commits_since(&self, since: &str) -> Result<Vec<Commit>> {
+        let since = self.get_commit(since)?;
+        let (mut commits, _) = self.load_commits()?;
+
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for commit in commits.values() {
+            for parent in &commit.parents {
+                children
+                    .entry(parent.as_str())
+                    .or_default()
+                    .push(commit.id.as_str());
+            }
+        }
+        let reachable = |next: &dyn Fn(&str) -> Vec<String>| {
+            let mut seen = HashSet::new();
+            let mut to_visit = next(&since.id);
+            while let Some(commit_id) = to_visit.pop() {
+                if seen.insert(commit_id.clone()) {
+                    to_visit.extend(next(&commit_id));
+                }
+            }
+            seen
+        };
+        let descendants = reachable(&|id| {
+            children
+                .get(id)
+                .map(|c| c.iter().map(|c| c.to_string()).collect())
+                .unwrap_or_default()
+        });
+        let ancestors = reachable(&|id| {
+            commits
+                .get(id)
+                .map(|c| c.parents.clone())
+                .unwrap_or_default()
+        });
+
+        commits.retain(|id, commit| {
+            *id != since.id
+                && !ancestors.contains(id)
+                && (descendants.contains(id) || commit.timestamp > since.timestamp)
+        });
+
+        // Oldest first, but each commit after those of its parents that are
+        // replayed too
+        let mut by_time: Vec<&Commit> = commits.values().collect();
+        by_time.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)));
+        let by_time: Vec<String> = by_time.into_iter().map(|c| c.id.clone()).collect();
+        let mut ordered = Vec::with_capacity(commits.len());
+        let mut expanded = HashSet::new();
+        for id in by_time {
+            let mut stack = vec![(id, false)];
+            while let Some((commit_id, parents_done)) = stack.pop() {
+                if parents_done {
+                    if let Some(commit) = commits.remove(&commit_id) {
+                        ordered.push(commit);
+                    }
+                    continue;
+                }
+                let Some(commit) = commits.get(&commit_id) else {
+                    continue;
+                };
+                if !expanded.insert(commit_id.clone()) {
+                    continue;
+                }
+                let parents: Vec<String> = commit.parents.iter().rev().cloned().collect();
+                stack.push((commit_id, true));
+                stack.extend(parents.into_iter().map(|parent| (parent, false)));
+            }
+        }
+
+        Ok(ordered)
+    }
 }
 
 #[cfg(test)]
@@ -269,6 +377,67 @@ mod tests {
         assert_eq!(head.id, commit.id);
     }
 
+    #[test]
+    fn test_commits_since() {
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(RedbBackend::new(dir.path().join("test.redb")).unwrap());
+        let store = VersionStore::new(backend).unwrap();
+        let commit = |parents: &[&str], name: &str| {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            let change = Change::create(format!("v1/Pod/default/{}", name), "{}".to_string());
+            store
+                .create_commit(
+                    CommitBuilder::new()
+                        .parents(parents.iter().map(|p| p.to_string()).collect())
+                        .change(change),
+                )
+                .unwrap()
+                .id
+        };
+        let ids = |since: &str| {
+            let commits = store.commits_since(since).unwrap();
+            commits.into_iter().map(|c| c.id).collect::<Vec<_>>()
+        };
+
+        // Unrelated root commits, as the API server writes, by timestamp
+        let first = commit(&[], "first");
+        let second = commit(&[], "second");
+        assert_eq!(ids(&first), vec![second.clone()]);
+        assert!(ids(&second).is_empty());
+
+        // A branch off `a` merged back: its commits follow their parents
+        let a = commit(&[&second], "a");
+        let b = commit(&[&a], "b");
+        let c = commit(&[&a], "c");
+        let merge = commit(&[&b, &c], "merge");
+        assert_eq!(ids(&a), vec![b.clone(), c.clone(), merge.clone()]);
+        let since_first = ids(&first);
+        assert_eq!(since_first.len(), 5);
+        let position = |id: &String| since_first.iter().position(|c| c == id).unwrap();
+        assert!(position(&a) < position(&b) && position(&a) < position(&c));
+        assert!(position(&b) < position(&merge) && position(&c) < position(&merge));
+
+        // The DAG wins over clocks: a descendant stamped before `merge`
+        // follows it, and an ancestor stamped after it precedes it
+        let skewed = |parents: Vec<String>, timestamp: &str| {
+            let mut commit = CommitBuilder::new().parents(parents).build();
+            commit.timestamp = timestamp.parse().unwrap();
+            store.apply_commit(&commit).unwrap();
+            commit.id
+        };
+        let behind = skewed(vec![merge.clone()], "2000-01-01T00:00:00Z");
+        assert_eq!(ids(&merge), vec![behind.clone()]);
+        let ahead = skewed(Vec::new(), "2100-01-01T00:00:00Z");
+        let child = commit(&[&ahead], "child");
+        assert!(!ids(&child).contains(&ahead));
+        assert_eq!(ids(&merge), vec![behind, ahead, child]);
+
+        assert!(matches!(
+            store.commits_since("no-such-commit"),
+            Err(VersioningError::CommitNotFound { .. })
+        ));
+    }
+
     #[test]
     fn test_conflict_detection() {
         let dir = tempdir().unwrap();
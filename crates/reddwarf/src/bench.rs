@@ -0,0 +1,389 @@
+//! `reddwarf bench`: synthetic pod create/delete load against a running
+//! API server, normally an agent on the mock runtime so that zones cost
+//! nothing, reported as JSON
+
+use reddwarf_core::k8s_openapi::api::core::v1::{Container, PodSpec};
+use reddwarf_core::metrics::LatencySummary;
+use reddwarf_core::{ObjectMeta, Pod};
+use reddwarf_runtime::{ApiClient, WatchStream};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Label on the pods of a bench run, valued with the run's id
+const BENCH_RUN_LABEL: &str = "reddwarf.io/bench-run";
+
+/// How long to wait after the load for outstanding pods to be scheduled
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Parameters of a bench run
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// API server URL
+    pub api_url: String,
+    /// Namespace the pods are created in
+    pub namespace: String,
+    /// Pods created up front and kept alive through the churn
+    pub pods: usize,
+    /// Pods replaced (deleted and created) per second during the churn
+    pub churn_per_sec: f64,
+    /// How long the churn runs
+    pub duration: Duration,
+    /// Maximum API requests in flight
+    pub concurrency: usize,
+    /// Pod watches opened to measure event fan-out
+    pub watchers: usize,
+}
+
+/// Parse a churn rate: `50/s`, `600/m`, or a bare number per second
+pub fn parse_rate(rate: &str) -> Result<f64, String> {
+    let (count, per_secs) = match rate.split_once('/') {
+        Some((count, "s")) => (count, 1.0),
+        Some((count, "m")) => (count, 60.0),
+        Some((_, unit)) => return Err(format!("unknown rate unit '{}'", unit)),
+        None => (rate, 1.0),
+    };
+    let count: f64 = count
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid rate '{}': {}", rate, e))?;
+    if !count.is_finite() || count < 0.0 {
+        return Err(format!("invalid rate '{}'", rate));
+    }
+    Ok(count / per_secs)
+}
+
+/// What the load tasks and watchers observed
+#[derive(Default)]
+struct Observations {
+    /// When each pod's create request was sent
+    sent: HashMap<String, Instant>,
+    /// Pods seen bound to a node
+    bound: HashSet<String>,
+    /// First ADDED receipt of each pod, and how many watchers saw it
+    received: HashMap<String, (Instant, usize)>,
+    create_ms: Vec<f64>,
+    create_errors: usize,
+    delete_ms: Vec<f64>,
+    delete_errors: usize,
+    schedule_ms: Vec<f64>,
+    first_bound: Option<Instant>,
+    last_bound: Option<Instant>,
+    watch_events: usize,
+    delivery_ms: Vec<f64>,
+    fanout_spread_ms: Vec<f64>,
+}
+
+/// Run the load described by `config` and return the report
+pub async fn run(config: &BenchConfig) -> miette::Result<Value> {
+    let client = Arc::new(ApiClient::new(&config.api_url));
+    let run_id = format!(
+        "{:x}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    );
+    let prefix = format!("bench-{}-", run_id);
+    let watchers = config.watchers.max(1);
+    let obs = Arc::new(Mutex::new(Observations::default()));
+    let token = CancellationToken::new();
+
+    // Watches are open before the first create so that they see every pod
+    let path = format!("/api/v1/namespaces/{}/pods", config.namespace);
+    let mut watch_tasks = JoinSet::new();
+    for i in 0..watchers {
+        let stream = client
+            .watch(&path)
+            .await
+            .map_err(|e| miette::miette!("Failed to watch pods at {}: {}", config.api_url, e))?;
+        watch_tasks.spawn(watch_pods(
+            stream,
+            prefix.clone(),
+            watchers,
+            // One watcher is enough to time scheduling
+            i == 0,
+            obs.clone(),
+            token.clone(),
+        ));
+    }
+
+    let load = Load {
+        client: client.clone(),
+        namespace: config.namespace.clone(),
+        run_id: run_id.clone(),
+        permits: Arc::new(Semaphore::new(config.concurrency.max(1))),
+        obs: obs.clone(),
+    };
+    let mut requests = JoinSet::new();
+    let mut live = VecDeque::new();
+    let mut next_index = 0;
+    let mut next_name = || {
+        next_index += 1;
+        format!("{}{}", prefix, next_index)
+    };
+
+    let started = Instant::now();
+    info!("Bench {}: creating {} pods", run_id, config.pods);
+    for _ in 0..config.pods {
+        let name = next_name();
+        load.create(&mut requests, name.clone());
+        live.push_back(name);
+    }
+    while requests.join_next().await.is_some() {}
+    let fill_secs = started.elapsed().as_secs_f64();
+
+    if config.churn_per_sec > 0.0 && !config.duration.is_zero() {
+        info!(
+            "Bench {}: churning {}/s for {:?}",
+            run_id, config.churn_per_sec, config.duration
+        );
+        let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / config.churn_per_sec));
+        let churn_end = Instant::now() + config.duration;
+        while Instant::now() < churn_end {
+            ticker.tick().await;
+            if let Some(old) = live.pop_front() {
+                load.delete(&mut requests, old, true);
+            }
+            let name = next_name();
+            load.create(&mut requests, name.clone());
+            live.push_back(name);
+            while requests.try_join_next().is_some() {}
+        }
+        while requests.join_next().await.is_some() {}
+    }
+    let load_secs = started.elapsed().as_secs_f64();
+
+    let settle_deadline = Instant::now() + SETTLE_TIMEOUT;
+    let unbound = loop {
+        let unbound = {
+            let obs = obs.lock().unwrap();
+            live.iter().filter(|n| !obs.bound.contains(*n)).count()
+        };
+        if unbound == 0 || Instant::now() >= settle_deadline {
+            break unbound;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+
+    info!("Bench {}: deleting {} pods", run_id, live.len());
+    for name in live.drain(..) {
+        load.delete(&mut requests, name, false);
+    }
+    while requests.join_next().await.is_some() {}
+    token.cancel();
+    while watch_tasks.join_next().await.is_some() {}
+
+    let obs = std::mem::take(&mut *obs.lock().unwrap());
+    let bound_span = match (obs.first_bound, obs.last_bound) {
+        (Some(first), Some(last)) => (last - first).as_secs_f64(),
+        _ => 0.0,
+    };
+    let throughput = if bound_span > 0.0 {
+        obs.bound.len() as f64 / bound_span
+    } else {
+        0.0
+    };
+
+    Ok(json!({
+        "config": {
+            "api_url": config.api_url,
+            "namespace": config.namespace,
+            "pods": config.pods,
+            "churn_per_sec": config.churn_per_sec,
+            "duration_secs": config.duration.as_secs_f64(),
+            "concurrency": config.concurrency,
+            "watchers": watchers,
+        },
+        "fill_secs": fill_secs,
+        "load_secs": load_secs,
+        "api": {
+            "create": {
+                "requests": obs.create_ms.len() + obs.create_errors,
+                "errors": obs.create_errors,
+                "latency_ms": summary_json(obs.create_ms),
+            },
+            "delete": {
+                "requests": obs.delete_ms.len() + obs.delete_errors,
+                "errors": obs.delete_errors,
+                "latency_ms": summary_json(obs.delete_ms),
+            },
+        },
+        "scheduling": {
+            "bound": obs.bound.len(),
+            "unbound_at_end": unbound,
+            "throughput_per_sec": throughput,
+            "latency_ms": summary_json(obs.schedule_ms),
+        },
+        "watch": {
+            "events": obs.watch_events,
+            "delivery_ms": summary_json(obs.delivery_ms),
+            "fanout_spread_ms": summary_json(obs.fanout_spread_ms),
+        },
+    }))
+}
+
+/// Issues the run's API requests, at most `permits` at a time
+struct Load {
+    client: Arc<ApiClient>,
+    namespace: String,
+    run_id: String,
+    permits: Arc<Semaphore>,
+    obs: Arc<Mutex<Observations>>,
+}
+
+impl Load {
+    fn create(&self, requests: &mut JoinSet<()>, name: String) {
+        let client = self.client.clone();
+        let namespace = self.namespace.clone();
+        let pod = bench_pod(&name, &self.run_id);
+        let permits = self.permits.clone();
+        let obs = self.obs.clone();
+        requests.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let sent = Instant::now();
+            obs.lock().unwrap().sent.insert(name, sent);
+            let result = client.create_pod(&namespace, &pod).await;
+            let mut obs = obs.lock().unwrap();
+            match result {
+                Ok(_) => obs.create_ms.push(millis(sent.elapsed())),
+                Err(e) => {
+                    warn!("Bench create failed: {}", e);
+                    obs.create_errors += 1;
+                }
+            }
+        });
+    }
+
+    /// Delete `name`, timing the request if `measure` is set
+    fn delete(&self, requests: &mut JoinSet<()>, name: String, measure: bool) {
+        let client = self.client.clone();
+        let namespace = self.namespace.clone();
+        let permits = self.permits.clone();
+        let obs = self.obs.clone();
+        requests.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let sent = Instant::now();
+            let result = client.delete_pod(&namespace, &name).await;
+            if !measure {
+                return;
+            }
+            let mut obs = obs.lock().unwrap();
+            match result {
+                Ok(()) => obs.delete_ms.push(millis(sent.elapsed())),
+                Err(e) => {
+                    warn!("Bench delete of {} failed: {}", name, e);
+                    obs.delete_errors += 1;
+                }
+            }
+        });
+    }
+}
+
+/// Record delivery lag of the run's pod events and, if `track_scheduling`,
+/// when each pod is first seen bound
+async fn watch_pods(
+    mut stream: WatchStream,
+    prefix: String,
+    watchers: usize,
+    track_scheduling: bool,
+    obs: Arc<Mutex<Observations>>,
+    token: CancellationToken,
+) {
+    loop {
+        let event = tokio::select! {
+            _ = token.cancelled() => return,
+            event = stream.next::<Pod>() => event,
+        };
+        let event = match event {
+            Ok(Some(event)) => event,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Bench watch failed: {}", e);
+                return;
+            }
+        };
+        let Some(name) = event
+            .object
+            .metadata
+            .name
+            .as_deref()
+            .filter(|n| n.starts_with(&prefix))
+        else {
+            continue;
+        };
+
+        let now = Instant::now();
+        let mut obs = obs.lock().unwrap();
+        obs.watch_events += 1;
+        let Some(sent) = obs.sent.get(name).copied() else {
+            continue;
+        };
+
+        if event.event_type == "ADDED" {
+            obs.delivery_ms.push(millis(now - sent));
+            let (first, seen) = {
+                let entry = obs.received.entry(name.to_string()).or_insert((now, 0));
+                entry.1 += 1;
+                *entry
+            };
+            if seen == watchers {
+                obs.fanout_spread_ms.push(millis(now - first));
+            }
+        }
+
+        let bound = event
+            .object
+            .spec
+            .as_ref()
+            .is_some_and(|s| s.node_name.is_some());
+        if track_scheduling && bound && obs.bound.insert(name.to_string()) {
+            obs.schedule_ms.push(millis(now - sent));
+            obs.first_bound.get_or_insert(now);
+            obs.last_bound = Some(now);
+        }
+    }
+}
+
+fn bench_pod(name: &str, run_id: &str) -> Pod {
+    Pod {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            labels: Some([(BENCH_RUN_LABEL.to_string(), run_id.to_string())].into()),
+            ..Default::default()
+        },
+        spec: Some(PodSpec {
+            containers: vec![Container {
+                name: "bench".to_string(),
+                image: Some("bench".to_string()),
+                command: Some(vec!["/bin/true".to_string()]),
+                ..Default::default()
+            }],
+            termination_grace_period_seconds: Some(0),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn summary_json(values: Vec<f64>) -> Value {
+    match LatencySummary::from_values(values) {
+        Some(s) => json!({
+            "count": s.count,
+            "p50": s.p50,
+            "p90": s.p90,
+            "p99": s.p99,
+            "max": s.max,
+        }),
+        None => Value::Null,
+    }
+}
@@ -0,0 +1,387 @@
+//! `reddwarf init`: preflight checks and first-time setup of a node. The
+//! agent's arguments are written to a file, one per line, that the agent
+//! reads back as `reddwarf agent @<file>` and the SMF service starts it with.
+//! A bootstrap token written next to it lets other nodes join with
+//! `reddwarf agent --join`.
+
+use reddwarf_apiserver::tls::resolve_tls;
+use reddwarf_runtime::command::exec_unchecked;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Where `svccfg import` finds the service manifest of an installed package
+pub const DEFAULT_MANIFEST: &str = "/opt/reddwarf/lib/svc/manifest/reddwarf.xml";
+
+/// The SMF service `--install-service` configures and enables
+const SERVICE_FMRI: &str = "svc:/system/reddwarf:default";
+
+/// Parameters of `reddwarf init`
+#[derive(Debug, Clone)]
+pub struct InitConfig {
+    /// Node name to register as (the hostname when unset)
+    pub node_name: Option<String>,
+    /// Address other nodes reach this node on
+    pub node_ip: Option<String>,
+    /// Address the API server listens on
+    pub bind: String,
+    /// Path to the redb database file
+    pub data_dir: String,
+    /// Base ZFS storage pool
+    pub storage_pool: String,
+    /// Pod network CIDR
+    pub pod_cidr: String,
+    /// Etherstub for pod networking
+    pub etherstub_name: String,
+    /// Where the agent's arguments are written
+    pub config_path: PathBuf,
+    /// Overwrite an existing config file
+    pub force: bool,
+    /// Report failed preflight checks as warnings and carry on
+    pub ignore_preflight_errors: bool,
+    /// Import the SMF manifest at this path, point the service at the
+    /// config file and enable it
+    pub install_service: Option<PathBuf>,
+}
+
+/// Outcome of one preflight check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+struct Check {
+    name: &'static str,
+    status: CheckStatus,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run the preflight checks, then write TLS material, an admin token and the
+/// agent's config file, and optionally install the SMF service
+pub async fn run(config: &InitConfig) -> miette::Result<()> {
+    if config.config_path.exists() && !config.force {
+        return Err(miette::miette!(
+            help = "Pass --force to overwrite it, or start the agent with `reddwarf agent @{}`",
+            "{} already exists",
+            config.config_path.display()
+        ));
+    }
+
+    let node_name = match &config.node_name {
+        Some(name) => name.clone(),
+        None => hostname().await?,
+    };
+
+    println!("Running preflight checks");
+    let checks = preflight(config).await;
+    let mut failed = 0;
+    for check in &checks {
+        let label = match check.status {
+            CheckStatus::Pass => "[ OK ]",
+            CheckStatus::Warn => "[WARN]",
+            CheckStatus::Fail if config.ignore_preflight_errors => "[WARN]",
+            CheckStatus::Fail => {
+                failed += 1;
+                "[FAIL]"
+            }
+        };
+        println!("{} {}: {}", label, check.name, check.detail);
+    }
+    if failed > 0 {
+        return Err(miette::miette!(
+            help = "Fix the failed checks, or pass --ignore-preflight-errors to continue anyway",
+            "{} preflight check(s) failed",
+            failed
+        ));
+    }
+
+    // The same TLS directory and names the agent derives from its flags,
+    // so it loads what is generated here
+    let tls_args = crate::TlsArgs {
+        tls: true,
+        tls_cert: None,
+        tls_key: None,
+        bootstrap_token_file: None,
+    };
+    let mut tls_mode = crate::tls_mode_from_args(&tls_args, &config.data_dir)?;
+    if let reddwarf_apiserver::TlsMode::AutoGenerate { san_entries, .. } = &mut tls_mode {
+        san_entries.push(node_name.clone());
+        san_entries.extend(config.node_ip.clone());
+    }
+    resolve_tls(&tls_mode)?;
+    let ca_path = match &tls_mode {
+        reddwarf_apiserver::TlsMode::AutoGenerate { data_dir, .. } => data_dir.join("ca.pem"),
+        _ => unreachable!("init always auto-generates TLS material"),
+    };
+
+    // Admin token for the /debug/zones API and the node proxy
+    let admin_token = uuid::Uuid::new_v4().simple().to_string();
+
+    // Bootstrap token other nodes join with
+    let join_token = uuid::Uuid::new_v4().simple().to_string();
+    let token_path = config.config_path.with_file_name("bootstrap-tokens");
+    write_private(
+        &token_path,
+        &format!(
+            "# Bootstrap tokens of joining nodes, one per line, written by `reddwarf init`\n{}\n",
+            join_token
+        ),
+    )?;
+
+    let mut args = vec![
+        format!("--node-name={}", node_name),
+        format!("--bind={}", config.bind),
+        format!("--data-dir={}", config.data_dir),
+        format!("--storage-pool={}", config.storage_pool),
+        format!("--pod-cidr={}", config.pod_cidr),
+        format!("--etherstub-name={}", config.etherstub_name),
+        "--tls".to_string(),
+        format!("--debug-token={}", admin_token),
+        format!("--bootstrap-token-file={}", token_path.display()),
+    ];
+    if let Some(node_ip) = &config.node_ip {
+        args.push(format!("--node-ip={}", node_ip));
+    }
+    write_config(&config.config_path, &args)?;
+    println!("Wrote agent config to {}", config.config_path.display());
+
+    match &config.install_service {
+        Some(manifest) => {
+            install_service(manifest, &config.config_path).await?;
+            println!("Enabled {}", SERVICE_FMRI);
+        }
+        None => println!(
+            "Start the agent with `reddwarf agent @{}`, or rerun with --install-service",
+            config.config_path.display()
+        ),
+    }
+    println!(
+        "Admin token (send as 'Authorization: Bearer <token>' to /debug/zones and the node proxy): {}",
+        admin_token
+    );
+    let port = config.bind.rsplit(':').next().unwrap_or("6443");
+    let address = config.node_ip.as_deref().unwrap_or(&node_name);
+    println!(
+        "Join other nodes with `reddwarf agent --tls --join https://{}:{} --join-token {} --join-ca <copy of {}>`",
+        address,
+        port,
+        join_token,
+        ca_path.display()
+    );
+    Ok(())
+}
+
+/// Replace each `@<file>` argument by the arguments in the file, one per
+/// line; blank lines and lines starting with `#` are skipped
+pub fn expand_arg_files(args: impl IntoIterator<Item = String>) -> miette::Result<Vec<String>> {
+    let mut expanded = Vec::new();
+    for arg in args {
+        match arg.strip_prefix('@').filter(|path| !path.is_empty()) {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path).map_err(|e| {
+                    miette::miette!("Failed to read arguments from {}: {}", path, e)
+                })?;
+                expanded.extend(
+                    contents
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(str::to_string),
+                );
+            }
+            None => expanded.push(arg),
+        }
+    }
+    Ok(expanded)
+}
+
+async fn preflight(config: &InitConfig) -> Vec<Check> {
+    vec![
+        check_privileges().await,
+        check_zfs_pool(&config.storage_pool).await,
+        check_etherstub(&config.etherstub_name).await,
+        check_port(&config.bind),
+        check_data_dir(&config.data_dir),
+    ]
+}
+
+/// Zones, datasets and datalinks can only be managed as root
+async fn check_privileges() -> Check {
+    const NAME: &str = "privileges";
+    match exec_unchecked("id", &["-u"]).await {
+        Ok(out) if out.stdout.trim() == "0" => {
+            Check::new(NAME, CheckStatus::Pass, "running as root")
+        }
+        Ok(out) => Check::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "running as uid {}; zones and datalinks can only be managed as root",
+                out.stdout.trim()
+            ),
+        ),
+        Err(e) => Check::new(NAME, CheckStatus::Fail, e.to_string()),
+    }
+}
+
+async fn check_zfs_pool(pool: &str) -> Check {
+    const NAME: &str = "ZFS pool";
+    match exec_unchecked("zpool", &["list", "-H", "-o", "name,health", pool]).await {
+        Ok(out) if out.exit_code == 0 => {
+            let health = out.stdout.split_whitespace().nth(1).unwrap_or("UNKNOWN");
+            let status = if health == "ONLINE" {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Warn
+            };
+            Check::new(NAME, status, format!("{} is {}", pool, health))
+        }
+        Ok(out) => Check::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "pool {} not found ({}); create it or pass --storage-pool",
+                pool,
+                out.stderr.trim()
+            ),
+        ),
+        Err(e) => Check::new(NAME, CheckStatus::Fail, format!("zpool unavailable: {}", e)),
+    }
+}
+
+/// An existing etherstub is reused; otherwise creating (and removing) a
+/// temporary one proves the agent will be able to
+async fn check_etherstub(name: &str) -> Check {
+    const NAME: &str = "etherstub";
+    match exec_unchecked("dladm", &["show-etherstub", name]).await {
+        Ok(out) if out.exit_code == 0 => {
+            return Check::new(NAME, CheckStatus::Pass, format!("{} exists", name))
+        }
+        Ok(_) => {}
+        Err(e) => return Check::new(NAME, CheckStatus::Fail, format!("dladm unavailable: {}", e)),
+    }
+    match exec_unchecked("dladm", &["create-etherstub", "-t", name]).await {
+        Ok(out) if out.exit_code == 0 => {
+            let _ = exec_unchecked("dladm", &["delete-etherstub", "-t", name]).await;
+            Check::new(NAME, CheckStatus::Pass, format!("{} can be created", name))
+        }
+        Ok(out) => Check::new(
+            NAME,
+            CheckStatus::Fail,
+            format!("cannot create {}: {}", name, out.stderr.trim()),
+        ),
+        Err(e) => Check::new(NAME, CheckStatus::Fail, e.to_string()),
+    }
+}
+
+fn check_port(bind: &str) -> Check {
+    const NAME: &str = "API port";
+    match std::net::TcpListener::bind(bind) {
+        Ok(_) => Check::new(NAME, CheckStatus::Pass, format!("{} is free", bind)),
+        Err(e) => Check::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "cannot listen on {}: {}; stop what holds it or pass --bind",
+                bind, e
+            ),
+        ),
+    }
+}
+
+fn check_data_dir(data_dir: &str) -> Check {
+    const NAME: &str = "data directory";
+    if Path::new(data_dir).exists() {
+        return Check::new(
+            NAME,
+            CheckStatus::Warn,
+            format!("{} exists and will be reused", data_dir),
+        );
+    }
+    Check::new(
+        NAME,
+        CheckStatus::Pass,
+        format!("{} will be created", data_dir),
+    )
+}
+
+async fn hostname() -> miette::Result<String> {
+    let out = exec_unchecked("hostname", &[])
+        .await
+        .map_err(|e| miette::miette!(help = "Pass --node-name", "Failed to run hostname: {}", e))?;
+    let name = out.stdout.trim();
+    if name.is_empty() {
+        return Err(miette::miette!(
+            help = "Pass --node-name",
+            "Could not determine the hostname"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Write the config file readable by root only, as it holds the admin token
+fn write_config(path: &Path, args: &[String]) -> miette::Result<()> {
+    let contents = format!(
+        "# reddwarf agent arguments, written by `reddwarf init`\n{}\n",
+        args.join("\n")
+    );
+    write_private(path, &contents)
+}
+
+fn write_private(path: &Path, contents: &str) -> miette::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| miette::miette!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| miette::miette!("Failed to write {}: {}", path.display(), e))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| miette::miette!("Failed to write {}: {}", path.display(), e))
+}
+
+async fn install_service(manifest: &Path, config_path: &Path) -> miette::Result<()> {
+    let manifest = manifest.to_string_lossy();
+    let config_path = std::fs::canonicalize(config_path)
+        .unwrap_or_else(|_| config_path.to_path_buf())
+        .to_string_lossy()
+        .to_string();
+    let config_prop = format!("application/config_file = astring: \"{}\"", config_path);
+    let steps: [(&str, Vec<&str>); 4] = [
+        ("svccfg", vec!["import", &manifest]),
+        ("svccfg", vec!["-s", SERVICE_FMRI, "setprop", &config_prop]),
+        ("svcadm", vec!["refresh", SERVICE_FMRI]),
+        ("svcadm", vec!["enable", SERVICE_FMRI]),
+    ];
+    for (program, args) in steps {
+        let out = exec_unchecked(program, &args)
+            .await
+            .map_err(|e| miette::miette!("Failed to run {}: {}", program, e))?;
+        if out.exit_code != 0 {
+            return Err(miette::miette!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                out.stderr.trim()
+            ));
+        }
+    }
+    Ok(())
+}
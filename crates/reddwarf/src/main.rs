@@ -1,22 +1,76 @@
+mod bench;
+mod init;
+mod simulate;
+mod supervisor;
+mod upgrade;
+
+use async_trait::async_trait;
 use clap::{Parser, Subcommand};
-use reddwarf_apiserver::{ApiError, ApiServer, AppState, Config as ApiConfig, TlsMode};
-use reddwarf_core::{Namespace, ResourceQuantities};
+use reddwarf_apiserver::bootstrap::request_node_certificate;
+use reddwarf_apiserver::limits::{
+    DEFAULT_BODY_READ_TIMEOUT, DEFAULT_HEADER_READ_TIMEOUT, DEFAULT_MAX_BODY_BYTES,
+    DEFAULT_MAX_CONNECTIONS,
+};
+use reddwarf_apiserver::tls::resolve_tls;
+use reddwarf_apiserver::{
+    ApiError, ApiServer, AppState, BootstrapSigner, Config as ApiConfig, ContainerSessionBackend,
+    ContainerSessions, EventSweeper, EventSweeperConfig, HistorySquasher, HistorySquasherConfig,
+    KeyRotator, KeyRotatorConfig, Leader, NodeApiBackend, NodeApiConfig, NodeApiServer, NodeProxy,
+    NodeStats, PodStats, ReplicaFollower, ReplicaFollowerConfig, RequestLimits, StatsSummary,
+    TlsMaterial, TlsMode, UsageRecorder, UsageRecorderConfig, ZoneDebug, ZoneDebugBackend,
+};
+use reddwarf_core::startup::summarize_startup;
+use reddwarf_core::{
+    DevicePool, EventRecorder, Namespace, Pod, PodStartup, ProcessSession, ResourceQuantities,
+    SessionRequest, Version,
+};
+use reddwarf_runtime::mesh::IpnatRedirect;
+use reddwarf_runtime::network::dns::DEFAULT_CLUSTER_DOMAIN;
+use reddwarf_runtime::network::ipam::parse_cidr;
+use reddwarf_runtime::network::node_ipam::wait_for_pod_cidr;
+use reddwarf_runtime::network::{
+    DladmHostLinks, HostNetwork, HostNetworkConfig, HostPortTable, HostRouteTable, IpfRuleSet,
+    IpnatRuleSet,
+};
+use reddwarf_runtime::controller::pod_zone_name;
+use reddwarf_runtime::sysinfo::{detect_available_memory, detect_system_resources};
+use reddwarf_runtime::zone::TunablesAllowlist;
 use reddwarf_runtime::{
-    ApiClient, Ipam, MockRuntime, MockStorageEngine, NodeAgent, NodeAgentConfig,
-    NodeHealthChecker, NodeHealthCheckerConfig, PodController, PodControllerConfig, StorageEngine,
-    StoragePoolConfig, ZoneBrand,
+    ApiClient, ApiEventSink, DaemonSetController, DaemonSetControllerConfig, DecisionLog,
+    DeploymentController, DeploymentControllerConfig, DeviceTable, DisruptionBudgetController,
+    DisruptionBudgetControllerConfig, EgressLockdownController, EgressLockdownControllerConfig,
+    EgressNatController, EgressNatControllerConfig, EndpointsController, EndpointsControllerConfig,
+    EvictionManager, EvictionManagerConfig, HorizontalPodAutoscalerController,
+    HorizontalPodAutoscalerControllerConfig, Ipam, MemoryPodMetrics, MeshIdentity, MeshProxy,
+    MeshProxyConfig, MockRuntime, MockStorageEngine, NetworkPolicyController,
+    NetworkPolicyControllerConfig, NodeAgent, NodeAgentConfig, NodeCidrAllocator,
+    NodeHealthChecker, NodeHealthCheckerConfig, NodeIpamController, NodeIpamControllerConfig,
+    NodeTopology, PodCache, PodController, PodControllerConfig, ReplicaSetController,
+    ReplicaSetControllerConfig, RouteDistributor, RouteDistributorConfig, RuntimeError,
+    ServiceRuleExporter, ServiceRuleExporterConfig, StorageEngine, StoragePoolConfig, SvidIssuer,
+    TtlAfterFinishedController, TtlAfterFinishedControllerConfig, VolumeBinder, VolumeBinderConfig,
+    WarmPool, WarmPoolSpec, ZoneBrand, ZoneRuntime,
 };
 use reddwarf_scheduler::scheduler::SchedulerConfig;
 use reddwarf_scheduler::Scheduler;
-use reddwarf_storage::RedbBackend;
-use reddwarf_versioning::VersionStore;
+use reddwarf_storage::migrations::migrations;
+use reddwarf_storage::{
+    export, integrity, DurabilityMode, EnvelopeEncryptor, ExternalKms, IntegrityIssue, KVStore,
+    KmsProvider, LocalKms, MigrationRun, Migrator, RedbBackend,
+};
+use reddwarf_versioning::{DagIssue, VersionStore};
 use std::path::PathBuf;
 use std::sync::Arc;
+use supervisor::Supervisor;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[derive(Parser)]
-#[command(name = "reddwarf", about = "Reddwarf Kubernetes Control Plane")]
+#[command(
+    name = "reddwarf",
+    version,
+    about = "Reddwarf Kubernetes Control Plane"
+)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -37,9 +91,95 @@ struct TlsArgs {
     /// Path to a PEM-encoded TLS private key (requires --tls)
     #[arg(long, requires = "tls")]
     tls_key: Option<String>,
+
+    /// File of bootstrap tokens, one per line, with which joining nodes may
+    /// request a certificate signed by the cluster CA (requires --tls with
+    /// an auto-generated CA)
+    #[arg(long, requires = "tls")]
+    bootstrap_token_file: Option<String>,
+}
+
+/// Cluster join arguments of the `agent` subcommand.
+#[derive(clap::Args, Clone, Debug)]
+struct JoinArgs {
+    /// URL of an API server to request this node's certificate from on the
+    /// first start; the issued certificate and the cluster CA replace the
+    /// auto-generated ones (requires --tls without --tls-cert/--tls-key)
+    #[arg(long, requires_all = ["tls", "join_token"], conflicts_with = "tls_cert")]
+    join: Option<String>,
+
+    /// Bootstrap token presented when joining
+    #[arg(long, env = "REDDWARF_JOIN_TOKEN", requires = "join")]
+    join_token: Option<String>,
+
+    /// Path to a PEM-encoded CA certificate to trust for the TLS certificate
+    /// of the API server joined, e.g. its auto-generated CA
+    #[arg(long, requires = "join")]
+    join_ca: Option<String>,
+}
+
+/// API server request and connection limits shared by `serve` and `agent`.
+#[derive(clap::Args, Clone, Debug)]
+struct LimitArgs {
+    /// Largest request body, in bytes, of kinds without a limit of their own
+    #[arg(long, default_value_t = DEFAULT_MAX_BODY_BYTES)]
+    max_body_bytes: usize,
+
+    /// Largest request body of a kind, as "<plural>=<bytes>", e.g.
+    /// "configmaps=1048576" (repeatable)
+    #[arg(long, value_name = "PLURAL=BYTES")]
+    max_body_bytes_for: Vec<String>,
+
+    /// Seconds a client may take to send a request body before it is
+    /// answered with 408 Request Timeout
+    #[arg(long, default_value_t = DEFAULT_BODY_READ_TIMEOUT.as_secs())]
+    body_read_timeout: u64,
+
+    /// Seconds a client may take to send the headers of a request before
+    /// its connection is closed
+    #[arg(long, default_value_t = DEFAULT_HEADER_READ_TIMEOUT.as_secs())]
+    header_read_timeout: u64,
+
+    /// Most connections open at once; further ones are closed when accepted
+    #[arg(long, default_value_t = DEFAULT_MAX_CONNECTIONS)]
+    max_connections: usize,
+}
+
+/// Encryption at rest arguments shared by `serve` and `agent`.
+#[derive(clap::Args, Clone, Debug)]
+struct EncryptionArgs {
+    /// Keyring file of "<key id>:<base64 32-byte key>" lines, primary
+    /// first, encrypting sensitive values at rest; rotate it with
+    /// `reddwarf rotate-encryption-key`
+    #[arg(long, conflicts_with = "kms_command")]
+    encryption_key_file: Option<PathBuf>,
+
+    /// Executable wrapping data keys with an external KMS or HSM, run as
+    /// `<command> key-id`, `<command> wrap` and `<command> unwrap <key id>`
+    #[arg(long)]
+    kms_command: Option<PathBuf>,
+}
+
+/// Read replica arguments of the `serve` subcommand.
+#[derive(clap::Args, Clone, Debug)]
+struct ReplicaArgs {
+    /// Serve GET, LIST and WATCH from a copy of the leader's storage and
+    /// forward writes to the leader (requires --follow)
+    #[arg(long, default_value_t = false, requires = "follow")]
+    read_replica: bool,
+
+    /// URL of the leader API server to follow
+    #[arg(long, requires = "read_replica")]
+    follow: Option<String>,
+
+    /// Path to a PEM-encoded CA certificate to trust for the leader's TLS
+    /// certificate, e.g. its auto-generated CA
+    #[arg(long, requires = "follow")]
+    leader_ca: Option<String>,
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Run the API server only
     Serve {
@@ -49,6 +189,32 @@ enum Commands {
         /// Path to the redb database file
         #[arg(long, default_value = "./reddwarf.redb")]
         data_dir: String,
+        /// Longest grace period, in seconds, a terminating pod is given,
+        /// whether requested on deletion or set in its spec (unlimited when
+        /// unset)
+        #[arg(long, value_parser = clap::value_parser!(i64).range(0..))]
+        max_grace_period: Option<i64>,
+        /// Seconds a request other than a watch or proxied stream may take
+        /// before it is answered with 504 Gateway Timeout
+        #[arg(long, default_value_t = 60)]
+        request_timeout: u64,
+        /// Lock down the egress of namespaces created from now on with a
+        /// default-deny NetworkPolicy, unless they set the annotation
+        /// reddwarf.io/egress-lockdown: "false"
+        #[arg(long, default_value_t = false)]
+        default_deny_egress: bool,
+        /// When commits reach the disk: "immediate" fsyncs every commit,
+        /// "paranoid" adds a two-phase commit, and "eventual" skips the fsync
+        /// and may lose the latest commits on power loss (dev/test and
+        /// benchmarks only)
+        #[arg(long, default_value = "immediate")]
+        durability: String,
+        #[command(flatten)]
+        replica_args: ReplicaArgs,
+        #[command(flatten)]
+        limit_args: LimitArgs,
+        #[command(flatten)]
+        encryption_args: EncryptionArgs,
         #[command(flatten)]
         tls_args: TlsArgs,
     },
@@ -81,9 +247,75 @@ enum Commands {
         /// Pod network CIDR for IPAM allocation
         #[arg(long, default_value = "10.88.0.0/16")]
         pod_cidr: String,
+        /// Cluster-wide pod CIDR to carve per-node pod CIDRs from. When set,
+        /// this node's pod CIDR is read from its Node's spec.podCIDR instead of
+        /// --pod-cidr, and routes to other nodes' pod CIDRs are distributed
+        /// automatically.
+        #[arg(long)]
+        cluster_cidr: Option<String>,
+        /// Prefix length of each node's slice of --cluster-cidr
+        #[arg(long, default_value_t = 24)]
+        node_cidr_mask_size: u8,
+        /// Reserve each pod's IP out of its node's pod CIDR when scheduling
+        /// it, so pods are never bound to a node whose pool is exhausted.
+        /// Takes effect with --cluster-cidr, which gives Nodes a spec.podCIDR.
+        #[arg(long, default_value_t = false)]
+        reserve_pod_ips: bool,
+        /// Address other nodes reach this node on (advertised as its InternalIP)
+        #[arg(long)]
+        node_ip: Option<String>,
         /// Etherstub name for pod networking
         #[arg(long, default_value = "reddwarf0")]
         etherstub_name: String,
+        /// Create the etherstub, a host VNIC holding the pod CIDR's gateway
+        /// address and ipnat rules masquerading pod egress on startup, and
+        /// remove them on shutdown
+        #[arg(long, default_value_t = false)]
+        auto_network_setup: bool,
+        /// Link pod egress is masqueraded on with --auto-network-setup
+        /// (default: the link of the default route)
+        #[arg(long, requires = "auto_network_setup")]
+        egress_interface: Option<String>,
+        /// Host link to forward pod hostPorts from; forwarding is disabled when unset
+        #[arg(long)]
+        host_port_interface: Option<String>,
+        /// Link pod traffic to Service ClusterIPs arrives on. When set, Services
+        /// are rendered into an ipnat ruleset and applied by diffing.
+        #[arg(long)]
+        service_rules_interface: Option<String>,
+        /// Ruleset file holding the currently loaded Service rules
+        #[arg(long, default_value = "/var/run/reddwarf/services.ipnat")]
+        service_rules_file: PathBuf,
+        /// Link pod egress leaves the node on. When set, the egress NAT
+        /// policy annotations of pods and namespaces are rendered into ipnat
+        /// rules for this node's pods.
+        #[arg(long)]
+        egress_nat_interface: Option<String>,
+        /// Ruleset file holding the currently loaded egress NAT rules
+        #[arg(long, default_value = "/var/run/reddwarf/egress.ipnat")]
+        egress_nat_file: PathBuf,
+        /// Ruleset file holding the currently loaded NetworkPolicy rules
+        #[arg(long, default_value = "/var/run/reddwarf/network-policy.ipf")]
+        network_policy_file: PathBuf,
+        /// Cluster DNS Service ("namespace/name", e.g. "kube-system/kube-dns")
+        /// whose clusterIP ClusterFirst pods resolve through; pods use the
+        /// node's resolver when unset
+        #[arg(long)]
+        cluster_dns_service: Option<String>,
+        /// Cluster domain for pod DNS search paths
+        #[arg(long, default_value = DEFAULT_CLUSTER_DOMAIN)]
+        cluster_domain: String,
+        /// Comma-separated sysctls pods may set in securityContext.sysctls
+        /// (a trailing '*' matches a prefix)
+        #[arg(
+            long,
+            default_value = "net.ipv4.ip_local_port_range,net.ipv4.ip_unprivileged_port_start"
+        )]
+        allowed_sysctls: String,
+        /// Comma-separated zonecfg attributes pods may set through the
+        /// reddwarf.io/zone-attrs annotation (a trailing '*' matches a prefix)
+        #[arg(long, default_value = "")]
+        allowed_zone_attrs: String,
         /// CPU to reserve for system daemons (e.g. "100m", "0.1")
         #[arg(long, default_value = "100m")]
         system_reserved_cpu: String,
@@ -93,12 +325,301 @@ enum Commands {
         /// Maximum number of pods this node will accept
         #[arg(long, default_value_t = 110)]
         max_pods: u32,
+        /// Seconds a pod that has Succeeded or Failed is kept before it is
+        /// deleted
+        #[arg(long, default_value_t = 3600)]
+        finished_pod_ttl: u64,
         /// Comma-separated list of zone brands this node supports
         #[arg(long, default_value = "reddwarf")]
         supported_brands: String,
+        /// Region of this node (topology.kubernetes.io/region); read from the
+        /// service's topology/region SMF property when unset
+        #[arg(long)]
+        topology_region: Option<String>,
+        /// Zone of this node within its region (topology.kubernetes.io/zone);
+        /// read from the service's topology/zone SMF property when unset
+        #[arg(long)]
+        topology_zone: Option<String>,
+        /// Chassis this node runs on (reddwarf.io/chassis); the SMBIOS serial
+        /// number when unset
+        #[arg(long)]
+        chassis: Option<String>,
+        /// Devices to offer pods as "class=path[,path...]", advertised as the
+        /// devices.reddwarf.io/<class> resource (repeatable)
+        #[arg(long = "device")]
+        devices: Vec<String>,
+        /// Idle zones to keep installed for fast pod startup, as
+        /// "brand=size" or "lx=size:/path/to/image" (repeatable)
+        #[arg(long = "warm-pool")]
+        warm_pools: Vec<String>,
+        /// Bearer token for the /debug/zones admin API, the
+        /// /api/v1/nodes/{name}/proxy subresource and the exec and attach pod
+        /// subresources (all disabled when unset)
+        #[arg(long, env = "REDDWARF_DEBUG_TOKEN")]
+        debug_token: Option<String>,
+        /// Address of the read-only node-local API (/pods, /stats/summary,
+        /// /healthz), e.g. "0.0.0.0:10250", served over HTTPS to clients
+        /// presenting a certificate issued by the cluster CA (requires --tls
+        /// with an auto-generated CA; disabled when unset)
+        #[arg(long, requires = "tls")]
+        node_api_bind: Option<String>,
+        /// Run the mTLS mesh proxy for pod ports selected by MeshPolicy
+        /// resources (requires --tls with an auto-generated CA)
+        #[arg(long, default_value_t = false)]
+        mesh: bool,
+        /// Issue pods X.509 SVIDs with SPIFFE IDs of this trust domain,
+        /// mounted at /var/run/secrets/spiffe and rotated by the agent
+        /// (requires --tls with an auto-generated CA)
+        #[arg(long)]
+        spiffe_trust_domain: Option<String>,
+        /// Longest grace period, in seconds, a terminating pod is given,
+        /// whether requested on deletion or set in its spec (unlimited when
+        /// unset)
+        #[arg(long, value_parser = clap::value_parser!(i64).range(0..))]
+        max_grace_period: Option<i64>,
+        /// Seconds a request other than a watch or proxied stream may take
+        /// before it is answered with 504 Gateway Timeout
+        #[arg(long, default_value_t = 60)]
+        request_timeout: u64,
+        /// Lock down the egress of namespaces created from now on with a
+        /// default-deny NetworkPolicy, unless they set the annotation
+        /// reddwarf.io/egress-lockdown: "false"
+        #[arg(long, default_value_t = false)]
+        default_deny_egress: bool,
+        /// When commits reach the disk: "immediate" fsyncs every commit,
+        /// "paranoid" adds a two-phase commit, and "eventual" skips the fsync
+        /// and may lose the latest commits on power loss (dev/test and
+        /// benchmarks only)
+        #[arg(long, default_value = "immediate")]
+        durability: String,
+        #[command(flatten)]
+        join_args: JoinArgs,
+        #[command(flatten)]
+        limit_args: LimitArgs,
+        #[command(flatten)]
+        encryption_args: EncryptionArgs,
         #[command(flatten)]
         tls_args: TlsArgs,
     },
+    /// Check that this host can run a node, then write TLS material, an admin
+    /// token and the agent's arguments to a config file started with
+    /// `reddwarf agent @<config>`
+    Init {
+        /// Node name to register as (default: the hostname)
+        #[arg(long)]
+        node_name: Option<String>,
+        /// Address other nodes reach this node on
+        #[arg(long)]
+        node_ip: Option<String>,
+        /// Address the API server listens on
+        #[arg(long, default_value = "0.0.0.0:6443")]
+        bind: String,
+        /// Path to the redb database file
+        #[arg(long, default_value = "/var/lib/reddwarf/reddwarf.redb")]
+        data_dir: String,
+        /// Base ZFS storage pool name
+        #[arg(long, default_value = "rpool")]
+        storage_pool: String,
+        /// Pod network CIDR for IPAM allocation
+        #[arg(long, default_value = "10.88.0.0/16")]
+        pod_cidr: String,
+        /// Etherstub name for pod networking
+        #[arg(long, default_value = "reddwarf0")]
+        etherstub_name: String,
+        /// Where to write the agent's arguments
+        #[arg(long, default_value = "/etc/reddwarf/agent.conf")]
+        config: PathBuf,
+        /// Overwrite an existing config file
+        #[arg(long, default_value_t = false)]
+        force: bool,
+        /// Report failed preflight checks as warnings and carry on
+        #[arg(long, default_value_t = false)]
+        ignore_preflight_errors: bool,
+        /// Import the SMF manifest, point svc:/system/reddwarf at the config
+        /// file and enable it
+        #[arg(long, default_value_t = false)]
+        install_service: bool,
+        /// SMF manifest imported by --install-service
+        #[arg(long, default_value = init::DEFAULT_MANIFEST)]
+        manifest: PathBuf,
+    },
+    /// Drive a running API server with synthetic pod create/delete load and
+    /// print a JSON report of API latency, scheduling throughput and watch
+    /// fan-out lag. Point it at an agent on the mock runtime.
+    Bench {
+        /// API server URL
+        #[arg(long, default_value = "http://127.0.0.1:6443")]
+        api_url: String,
+        /// Namespace to create the pods in
+        #[arg(long, default_value = "default")]
+        namespace: String,
+        /// Pods created up front and kept alive through the churn
+        #[arg(long, default_value_t = 1000)]
+        pods: usize,
+        /// Pods replaced per second during the churn (e.g. "50/s", "600/m")
+        #[arg(long, default_value = "10/s")]
+        churn: String,
+        /// Seconds the churn runs for
+        #[arg(long, default_value_t = 60)]
+        duration: u64,
+        /// Maximum API requests in flight
+        #[arg(long, default_value_t = 32)]
+        concurrency: usize,
+        /// Pod watches opened to measure event fan-out
+        #[arg(long, default_value_t = 4)]
+        watchers: usize,
+        /// Write the report here instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Apply pending storage schema migrations (also done at startup)
+    Migrate {
+        /// Path to the redb database file
+        #[arg(long, default_value = "./reddwarf.redb")]
+        data_dir: String,
+        /// Report what the pending migrations would change without applying
+        /// them
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// Check storage integrity and repair what can be re-derived: rebuild
+    /// the indices from the stored objects and HEAD from the commit DAG
+    Repair {
+        /// Path to the redb database file
+        #[arg(long, default_value = "./reddwarf.redb")]
+        data_dir: String,
+        /// Also delete values that are cut short or otherwise unparseable
+        #[arg(long, default_value_t = false)]
+        drop_corrupt: bool,
+    },
+    /// Write every stored object, and optionally the version history, to a
+    /// line-delimited export. Run it against a stopped server or a backup.
+    Export {
+        /// Path to the redb database file
+        #[arg(long, default_value = "./reddwarf.redb")]
+        data_dir: String,
+        /// Export format; "jsonl" is the only one
+        #[arg(long, default_value = "jsonl")]
+        format: String,
+        /// Also export the commits of the version history
+        #[arg(long, default_value_t = false)]
+        history: bool,
+        /// Write the export here instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Check an export and load it into a new database, then migrate the
+    /// database to the current schema
+    Import {
+        /// Path of the new redb database file
+        #[arg(long, default_value = "./reddwarf.redb")]
+        data_dir: String,
+        /// Export to load
+        #[arg(long)]
+        input: PathBuf,
+        /// Only read and check the export
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// Generate a new encryption key and make it the primary of a keyring
+    /// file, keeping the old keys readable. Running servers pick it up and
+    /// re-encrypt stored values in the background.
+    RotateEncryptionKey {
+        /// Keyring file passed to --encryption-key-file (created if missing)
+        #[arg(long)]
+        key_file: PathBuf,
+        /// Id of the new key (default: "key-<timestamp>")
+        #[arg(long)]
+        key_id: Option<String>,
+    },
+    /// Run the scheduler's filters and scores for the pods of a manifest
+    /// against a snapshot of cluster state without binding anything, and
+    /// print where each pod would go and why every node passed or failed
+    SimulateSchedule {
+        /// Manifest with the pods to place (Pods, Deployments, ReplicaSets),
+        /// and optionally Nodes and RuntimeClasses; "-" reads stdin
+        #[arg(short = 'f', long)]
+        file: PathBuf,
+        /// Also take the nodes, bound pods and RuntimeClasses of the cluster
+        /// at --api-url
+        #[arg(long, default_value_t = false)]
+        nodes_from_cluster: bool,
+        /// API server URL
+        #[arg(long, default_value = "http://127.0.0.1:6443")]
+        api_url: String,
+        /// Path to a PEM-encoded CA certificate to trust for the API server's
+        /// TLS certificate
+        #[arg(long)]
+        ca_cert: Option<PathBuf>,
+        /// Report format: "text" or "json"
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+    /// Report on a running cluster
+    Analyze {
+        #[command(subcommand)]
+        command: AnalyzeCommand,
+    },
+    /// Check the version skew between the API server and the node agents,
+    /// and upgrade the agents one node at a time
+    Upgrade {
+        #[command(subcommand)]
+        command: UpgradeCommand,
+    },
+}
+
+/// Arguments shared by `upgrade plan` and `upgrade apply`
+#[derive(clap::Args, Clone, Debug)]
+struct UpgradeArgs {
+    /// API server URL
+    #[arg(long, default_value = "http://127.0.0.1:6443")]
+    api_url: String,
+    /// Path to a PEM-encoded CA certificate to trust for the API server's
+    /// TLS certificate
+    #[arg(long)]
+    ca_cert: Option<PathBuf>,
+    /// Version to bring the agents to (default: the API server's)
+    #[arg(long)]
+    to: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum UpgradeCommand {
+    /// Show each node's version, whether its skew is supported and whether
+    /// it would be upgraded
+    Plan {
+        #[command(flatten)]
+        args: UpgradeArgs,
+    },
+    /// Cordon, drain and upgrade each outdated node in turn, then make it
+    /// schedulable again once its agent is Ready at the target version
+    Apply {
+        #[command(flatten)]
+        args: UpgradeArgs,
+        /// Shell command upgrading a node's agent; "{node}" is replaced by
+        /// the node name, e.g. "ssh {node} pkg update reddwarf"
+        #[arg(long)]
+        upgrade_command: String,
+        /// Seconds evicted pods may take to leave a node
+        #[arg(long, default_value_t = 300)]
+        drain_timeout: u64,
+        /// Seconds an upgraded agent may take to come back Ready
+        #[arg(long, default_value_t = 600)]
+        ready_timeout: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum AnalyzeCommand {
+    /// Pod startup latency percentiles for each stage
+    Startup {
+        /// API server URL
+        #[arg(long, default_value = "http://127.0.0.1:6443")]
+        api_url: String,
+        /// Only report on pods in this namespace
+        #[arg(long)]
+        namespace: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -111,14 +632,164 @@ async fn main() -> miette::Result<()> {
         )
         .init();
 
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(init::expand_arg_files(std::env::args())?);
 
     match cli.command {
+        Commands::Analyze {
+            command: AnalyzeCommand::Startup { api_url, namespace },
+        } => run_analyze_startup(&api_url, namespace.as_deref()).await,
+        Commands::SimulateSchedule {
+            file,
+            nodes_from_cluster,
+            api_url,
+            ca_cert,
+            format,
+        } => {
+            let json = match format.as_str() {
+                "text" => false,
+                "json" => true,
+                other => {
+                    return Err(miette::miette!(
+                        help = "Use --format text or --format json",
+                        "Unknown report format '{}'",
+                        other
+                    ))
+                }
+            };
+            let ca_pem = match &ca_cert {
+                Some(path) => Some(std::fs::read(path).map_err(|e| {
+                    miette::miette!("Failed to read CA certificate '{}': {}", path.display(), e)
+                })?),
+                None => None,
+            };
+            let config = simulate::SimulateConfig {
+                file,
+                nodes_from_cluster,
+                api_url,
+                ca_pem,
+                json,
+            };
+            simulate::run(&config).await
+        }
+        Commands::Bench {
+            api_url,
+            namespace,
+            pods,
+            churn,
+            duration,
+            concurrency,
+            watchers,
+            output,
+        } => {
+            let churn_per_sec = bench::parse_rate(&churn).map_err(|e| {
+                miette::miette!(
+                    help = "Use a value like '50/s' or '600/m' for --churn",
+                    "Invalid --churn '{}': {}",
+                    churn,
+                    e
+                )
+            })?;
+            let config = bench::BenchConfig {
+                api_url,
+                namespace,
+                pods,
+                churn_per_sec,
+                duration: std::time::Duration::from_secs(duration),
+                concurrency,
+                watchers,
+            };
+            run_bench(&config, output.as_deref()).await
+        }
+        Commands::Init {
+            node_name,
+            node_ip,
+            bind,
+            data_dir,
+            storage_pool,
+            pod_cidr,
+            etherstub_name,
+            config,
+            force,
+            ignore_preflight_errors,
+            install_service,
+            manifest,
+        } => {
+            let config = init::InitConfig {
+                node_name,
+                node_ip,
+                bind,
+                data_dir,
+                storage_pool,
+                pod_cidr,
+                etherstub_name,
+                config_path: config,
+                force,
+                ignore_preflight_errors,
+                install_service: install_service.then_some(manifest),
+            };
+            init::run(&config).await
+        }
+        Commands::Migrate { data_dir, dry_run } => run_migrate(&data_dir, dry_run),
+        Commands::Upgrade {
+            command: UpgradeCommand::Plan { args },
+        } => upgrade::plan(&upgrade_config(&args, None, 0, 0)?).await,
+        Commands::Upgrade {
+            command:
+                UpgradeCommand::Apply {
+                    args,
+                    upgrade_command,
+                    drain_timeout,
+                    ready_timeout,
+                },
+        } => {
+            let config =
+                upgrade_config(&args, Some(upgrade_command), drain_timeout, ready_timeout)?;
+            upgrade::apply(&config).await
+        }
+        Commands::Repair {
+            data_dir,
+            drop_corrupt,
+        } => run_repair(&data_dir, drop_corrupt),
+        Commands::Export {
+            data_dir,
+            format,
+            history,
+            output,
+        } => run_export(&data_dir, &format, history, output.as_deref()),
+        Commands::Import {
+            data_dir,
+            input,
+            dry_run,
+        } => run_import(&data_dir, &input, dry_run),
+        Commands::RotateEncryptionKey { key_file, key_id } => {
+            run_rotate_encryption_key(&key_file, key_id.as_deref())
+        }
         Commands::Serve {
             bind,
             data_dir,
+            max_grace_period,
+            request_timeout,
+            default_deny_egress,
+            durability,
+            replica_args,
+            limit_args,
+            encryption_args,
             tls_args,
-        } => run_serve(&bind, &data_dir, &tls_args).await,
+        } => {
+            run_serve(
+                &bind,
+                &data_dir,
+                max_grace_period,
+                std::time::Duration::from_secs(request_timeout),
+                request_limits(&limit_args)?,
+                default_deny_egress,
+                &durability,
+                &replica_args,
+                &encryption_args,
+                &tls_args,
+            )
+            .await
+        }
         Commands::Agent {
             node_name,
             bind,
@@ -129,11 +800,44 @@ async fn main() -> miette::Result<()> {
             volumes_dataset,
             zonepath_prefix,
             pod_cidr,
+            cluster_cidr,
+            node_cidr_mask_size,
+            reserve_pod_ips,
+            node_ip,
             etherstub_name,
+            auto_network_setup,
+            egress_interface,
+            host_port_interface,
+            service_rules_interface,
+            service_rules_file,
+            egress_nat_interface,
+            egress_nat_file,
+            network_policy_file,
+            cluster_dns_service,
+            cluster_domain,
+            allowed_sysctls,
+            allowed_zone_attrs,
             system_reserved_cpu,
             system_reserved_memory,
             max_pods,
+            finished_pod_ttl,
             supported_brands,
+            topology_region,
+            topology_zone,
+            chassis,
+            devices,
+            warm_pools,
+            debug_token,
+            node_api_bind,
+            mesh,
+            spiffe_trust_domain,
+            max_grace_period,
+            request_timeout,
+            default_deny_egress,
+            durability,
+            join_args,
+            limit_args,
+            encryption_args,
             tls_args,
         } => {
             let reserved_cpu_millicores =
@@ -155,11 +859,43 @@ async fn main() -> miette::Result<()> {
                     )
                 })?;
 
-            let supported_brands: Vec<String> = supported_brands
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
+            let split_list = |list: &str| -> Vec<String> {
+                list.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            };
+            let supported_brands = split_list(&supported_brands);
+            let devices = devices
+                .iter()
+                .map(|spec| {
+                    DevicePool::parse(spec).map_err(|e| {
+                        miette::miette!(
+                            help = "Use a value like 'disk=/dev/dsk/c1t1d0,/dev/dsk/c1t2d0'",
+                            "Invalid --device '{}': {}",
+                            spec,
+                            e
+                        )
+                    })
+                })
+                .collect::<miette::Result<Vec<_>>>()?;
+            let warm_pools = warm_pools
+                .iter()
+                .map(|spec| {
+                    WarmPoolSpec::parse(spec).map_err(|e| {
+                        miette::miette!(
+                            help = "Use a value like 'reddwarf=2' or 'lx=1:/images/alpine.tar.gz'",
+                            "Invalid --warm-pool '{}': {}",
+                            spec,
+                            e
+                        )
+                    })
+                })
+                .collect::<miette::Result<Vec<_>>>()?;
+            let allowed_tunables = TunablesAllowlist {
+                sysctls: split_list(&allowed_sysctls),
+                zone_attrs: split_list(&allowed_zone_attrs),
+            };
 
             run_agent(
                 &node_name,
@@ -171,11 +907,45 @@ async fn main() -> miette::Result<()> {
                 volumes_dataset.as_deref(),
                 zonepath_prefix.as_deref(),
                 &pod_cidr,
+                cluster_cidr.as_deref(),
+                node_cidr_mask_size,
+                reserve_pod_ips,
+                node_ip.as_deref(),
                 &etherstub_name,
+                auto_network_setup,
+                egress_interface.as_deref(),
+                host_port_interface.as_deref(),
+                service_rules_interface.as_deref(),
+                &service_rules_file,
+                egress_nat_interface.as_deref(),
+                &egress_nat_file,
+                &network_policy_file,
+                cluster_dns_service.as_deref(),
+                &cluster_domain,
+                allowed_tunables,
                 reserved_cpu_millicores,
                 reserved_memory_bytes,
                 max_pods,
+                std::time::Duration::from_secs(finished_pod_ttl),
                 &supported_brands,
+                NodeTopology {
+                    region: topology_region,
+                    zone: topology_zone,
+                    chassis,
+                },
+                &devices,
+                &warm_pools,
+                debug_token.as_deref(),
+                node_api_bind.as_deref(),
+                mesh,
+                spiffe_trust_domain.as_deref(),
+                max_grace_period,
+                std::time::Duration::from_secs(request_timeout),
+                request_limits(&limit_args)?,
+                default_deny_egress,
+                &durability,
+                &join_args,
+                &encryption_args,
                 &tls_args,
             )
             .await
@@ -195,6 +965,30 @@ async fn shutdown_signal() -> &'static str {
     }
 }
 
+/// API server request limits from CLI arguments
+fn request_limits(args: &LimitArgs) -> miette::Result<RequestLimits> {
+    let mut max_body_bytes_per_kind = std::collections::HashMap::new();
+    for limit in &args.max_body_bytes_for {
+        let (plural, bytes) = limit
+            .split_once('=')
+            .and_then(|(plural, bytes)| Some((plural, bytes.parse::<usize>().ok()?)))
+            .ok_or_else(|| {
+                miette::miette!(
+                    "Invalid --max-body-bytes-for '{}': expected <plural>=<bytes>",
+                    limit
+                )
+            })?;
+        max_body_bytes_per_kind.insert(plural.to_string(), bytes);
+    }
+    Ok(RequestLimits {
+        max_body_bytes: args.max_body_bytes,
+        max_body_bytes_per_kind,
+        body_read_timeout: std::time::Duration::from_secs(args.body_read_timeout),
+        header_read_timeout: std::time::Duration::from_secs(args.header_read_timeout),
+        max_connections: args.max_connections,
+    })
+}
+
 /// Derive a `TlsMode` from CLI arguments.
 fn tls_mode_from_args(args: &TlsArgs, data_dir: &str) -> miette::Result<TlsMode> {
     if !args.tls {
@@ -223,25 +1017,96 @@ fn tls_mode_from_args(args: &TlsArgs, data_dir: &str) -> miette::Result<TlsMode>
     }
 }
 
+/// Sign the certificate requests of joining nodes with the cluster CA when
+/// --bootstrap-token-file is set
+fn bootstrap_signer(
+    args: &TlsArgs,
+    material: Option<&TlsMaterial>,
+) -> miette::Result<Option<BootstrapSigner>> {
+    let Some(path) = args.bootstrap_token_file.as_deref() else {
+        return Ok(None);
+    };
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| miette::miette!("Failed to read bootstrap tokens from {}: {}", path, e))?;
+    let tokens: Vec<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if tokens.is_empty() {
+        return Err(miette::miette!("{} holds no bootstrap tokens", path));
+    }
+    let (Some(ca_pem), Some(ca_key_pem)) = (
+        material.and_then(|m| m.ca_pem.as_deref()),
+        material.and_then(|m| m.ca_key_pem.as_deref()),
+    ) else {
+        return Err(miette::miette!(
+            help = "Run with --tls without --tls-cert/--tls-key to auto-generate the CA",
+            "--bootstrap-token-file requires the cluster CA certificate and key"
+        ));
+    };
+    info!("Node bootstrap enabled with {} token(s)", tokens.len());
+    BootstrapSigner::new(ca_pem, ca_key_pem, tokens).map(Some)
+}
+
 /// Run only the API server
-async fn run_serve(bind: &str, data_dir: &str, tls_args: &TlsArgs) -> miette::Result<()> {
+#[allow(clippy::too_many_arguments)]
+async fn run_serve(
+    bind: &str,
+    data_dir: &str,
+    max_grace_period: Option<i64>,
+    request_timeout: std::time::Duration,
+    limits: RequestLimits,
+    default_deny_egress: bool,
+    durability: &str,
+    replica_args: &ReplicaArgs,
+    encryption_args: &EncryptionArgs,
+    tls_args: &TlsArgs,
+) -> miette::Result<()> {
     info!("Starting reddwarf API server");
 
-    let state = create_app_state(data_dir)?;
-
-    bootstrap_default_namespace(&state).await?;
-
+    let mut state = create_app_state(data_dir, max_grace_period, default_deny_egress, durability)?;
+    if let Some(encryptor) = envelope_encryptor(encryption_args)? {
+        state = state.with_encryption(encryptor);
+    }
+    if let Some(url) = replica_args.follow.as_deref() {
+        let ca_pem = match replica_args.leader_ca.as_deref() {
+            Some(path) => Some(std::fs::read(path).map_err(|e| {
+                miette::miette!("Failed to read leader CA certificate '{}': {}", path, e)
+            })?),
+            None => None,
+        };
+        info!("Running as a read replica of {}", url);
+        state = state.with_leader(Leader::new(url, ca_pem.as_deref()));
+    }
     let tls_mode = tls_mode_from_args(tls_args, data_dir)?;
+    if tls_args.bootstrap_token_file.is_some() {
+        let material = resolve_tls(&tls_mode)?;
+        if let Some(signer) = bootstrap_signer(tls_args, material.as_ref())? {
+            state = state.with_bootstrap(signer);
+        }
+    }
+    let state = Arc::new(state);
+
+    // The leader bootstraps what its replicas copy
+    if state.leader.is_none() {
+        bootstrap_default_namespace(&state).await?;
+        bootstrap_priority_classes(&state).await?;
+    }
 
     let config = ApiConfig {
         listen_addr: bind
             .parse()
             .map_err(|e| miette::miette!("Invalid bind address '{}': {}", bind, e))?,
         tls_mode,
+        request_timeout,
+        limits,
     };
 
     let token = CancellationToken::new();
-    let server = ApiServer::new(config, state);
+    let supervisor = Supervisor::new(state.components.clone(), token.clone());
+    let server = ApiServer::new(config, state.clone());
     let server_token = token.clone();
 
     let server_handle = tokio::spawn(async move {
@@ -249,12 +1114,33 @@ async fn run_serve(bind: &str, data_dir: &str, tls_args: &TlsArgs) -> miette::Re
             error!("API server error: {}", e);
         }
     });
+    let sweeper_handle = spawn_event_sweeper(state.clone(), &supervisor);
+    let rotator_handle = spawn_key_rotator(state.clone(), &supervisor);
+    let squasher_handle = spawn_history_squasher(state.clone(), &supervisor);
+    // Replicas copy the leader's usage records instead of recording their own
+    let background_handle = if state.leader.is_some() {
+        let follower = ReplicaFollower::new(state, ReplicaFollowerConfig::default());
+        supervisor.spawn("replica-follower", follower, |follower, token| async move {
+            follower.run(token).await.map_err(|e| format!("{:?}", e))
+        })
+    } else {
+        spawn_usage_recorder(state, &supervisor)
+    };
 
     let sig = shutdown_signal().await;
     info!("Received {}, shutting down gracefully...", sig);
     token.cancel();
 
-    let _ = tokio::time::timeout(std::time::Duration::from_secs(5), server_handle).await;
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let _ = tokio::join!(
+            server_handle,
+            sweeper_handle,
+            rotator_handle,
+            squasher_handle,
+            background_handle
+        );
+    })
+    .await;
     info!("Shutdown complete");
 
     Ok(())
@@ -272,18 +1158,49 @@ async fn run_agent(
     volumes_dataset: Option<&str>,
     zonepath_prefix: Option<&str>,
     pod_cidr: &str,
+    cluster_cidr: Option<&str>,
+    node_cidr_mask_size: u8,
+    reserve_pod_ips: bool,
+    node_ip: Option<&str>,
     etherstub_name: &str,
+    auto_network_setup: bool,
+    egress_interface: Option<&str>,
+    host_port_interface: Option<&str>,
+    service_rules_interface: Option<&str>,
+    service_rules_file: &std::path::Path,
+    egress_nat_interface: Option<&str>,
+    egress_nat_file: &std::path::Path,
+    network_policy_file: &std::path::Path,
+    cluster_dns_service: Option<&str>,
+    cluster_domain: &str,
+    allowed_tunables: TunablesAllowlist,
     system_reserved_cpu_millicores: i64,
     system_reserved_memory_bytes: i64,
     max_pods: u32,
+    finished_pod_ttl: std::time::Duration,
     supported_brands: &[String],
+    topology: NodeTopology,
+    devices: &[DevicePool],
+    warm_pools: &[WarmPoolSpec],
+    debug_token: Option<&str>,
+    node_api_bind: Option<&str>,
+    mesh: bool,
+    spiffe_trust_domain: Option<&str>,
+    max_grace_period: Option<i64>,
+    request_timeout: std::time::Duration,
+    limits: RequestLimits,
+    default_deny_egress: bool,
+    durability: &str,
+    join_args: &JoinArgs,
+    encryption_args: &EncryptionArgs,
     tls_args: &TlsArgs,
 ) -> miette::Result<()> {
     info!("Starting reddwarf agent for node '{}'", node_name);
 
-    let state = create_app_state(data_dir)?;
-
-    bootstrap_default_namespace(&state).await?;
+    let mut state = create_app_state(data_dir, max_grace_period, default_deny_egress, durability)?;
+    if let Some(encryptor) = envelope_encryptor(encryption_args)? {
+        state = state.with_encryption(encryptor);
+    }
 
     let listen_addr: std::net::SocketAddr = bind
         .parse()
@@ -307,25 +1224,157 @@ async fn run_agent(
         .await
         .map_err(|e| miette::miette!("Failed to initialize storage: {}", e))?;
 
-    // Build TLS mode
-    let tls_mode = tls_mode_from_args(tls_args, data_dir)?;
+    // Create runtime with injected storage engine
+    let runtime: Arc<dyn ZoneRuntime> = create_runtime(storage_engine.clone());
+
+    // Decisions of the pod controller, read back through the debug API
+    let decision_log = Arc::new(
+        DecisionLog::new(state.storage.clone(), node_name)
+            .map_err(|e| miette::miette!("Failed to open the decision log: {}", e))?,
+    );
+
+    // Build TLS mode; a generated certificate also names this node so the
+    // node proxy of other API servers can verify it
+    let mut tls_mode = tls_mode_from_args(tls_args, data_dir)?;
+    if let TlsMode::AutoGenerate { san_entries, .. } = &mut tls_mode {
+        san_entries.push(node_name.to_string());
+        san_entries.extend(node_ip.map(String::from));
+    }
     let tls_enabled = !matches!(tls_mode, TlsMode::Disabled);
 
+    // A joining node has its certificate issued by the cluster CA on the
+    // first start, and reuses it after
+    if let (
+        Some(url),
+        TlsMode::AutoGenerate {
+            data_dir: tls_dir,
+            san_entries,
+        },
+    ) = (join_args.join.as_deref(), &tls_mode)
+    {
+        if !tls_dir.join("server.pem").exists() {
+            let join_ca = match join_args.join_ca.as_deref() {
+                Some(path) => Some(std::fs::read(path).map_err(|e| {
+                    miette::miette!("Failed to read join CA certificate '{}': {}", path, e)
+                })?),
+                None => None,
+            };
+            let join_token = join_args.join_token.as_deref().unwrap_or_default();
+            request_node_certificate(
+                url,
+                join_token,
+                join_ca.as_deref(),
+                node_name,
+                san_entries,
+                tls_dir,
+            )
+            .await?;
+        }
+    }
+
+    let tls_material = resolve_tls(&tls_mode)?;
+    let ca_pem = tls_material.as_ref().and_then(|m| m.ca_pem.clone());
+    // The issued certificate is valid for client auth, so a joined node
+    // presents it to the API server and when proxying to other nodes
+    let client_identity = match (&join_args.join, &tls_material) {
+        (Some(_), Some(material)) => {
+            let identity = reqwest::Identity::from_pkcs8_pem(&material.cert_pem, &material.key_pem)
+                .map_err(|e| miette::miette!("Failed to load the node certificate: {}", e))?;
+            Some(identity)
+        }
+        _ => None,
+    };
+    let bootstrap = bootstrap_signer(tls_args, tls_material.as_ref())?;
+
+    let state = match bootstrap {
+        Some(signer) => state.with_bootstrap(signer),
+        None => state,
+    };
+
+    // Expose the runtime's view of zones via the debug API, open sessions
+    // with local containers, and proxy operator requests to node agents,
+    // when a token is set
+    let state = Arc::new(match debug_token {
+        Some(token) => {
+            info!("Debug API enabled at /debug/zones");
+            info!("Node proxy enabled at /api/v1/nodes/{{name}}/proxy");
+            info!("Interactive sessions enabled at pods/{{name}}/exec and pods/{{name}}/attach");
+            let mut node_proxy = NodeProxy::new(token);
+            if tls_enabled {
+                node_proxy = node_proxy.with_tls(ca_pem.as_deref(), client_identity.clone());
+            }
+            state
+                .with_zone_debug(ZoneDebug::new(
+                    Arc::new(RuntimeZoneDebug {
+                        runtime: runtime.clone(),
+                        decision_log: decision_log.clone(),
+                    }),
+                    token,
+                ))
+                .with_container_sessions(ContainerSessions::new(
+                    Arc::new(RuntimeContainerSessions {
+                        node_name: node_name.to_string(),
+                        runtime: runtime.clone(),
+                    }),
+                    token,
+                ))
+                .with_node_proxy(node_proxy)
+        }
+        None => state,
+    });
+
+    bootstrap_default_namespace(&state).await?;
+    bootstrap_priority_classes(&state).await?;
+
     // Determine the API URL for internal components
     let scheme = if tls_enabled { "https" } else { "http" };
     let api_url = format!("{scheme}://127.0.0.1:{}", listen_addr.port());
 
     let token = CancellationToken::new();
+    // Restart the components below when they panic or fail, reporting them
+    // as ComponentStatuses
+    let supervisor = Supervisor::new(state.components.clone(), token.clone());
 
     // 1. Build API server and resolve TLS material *before* spawning
     let api_config = ApiConfig {
         listen_addr,
         tls_mode,
+        request_timeout,
+        limits,
     };
     let api_server = ApiServer::new(api_config, state.clone());
 
-    let tls_material = api_server.resolve_tls_material()?;
-    let ca_pem = tls_material.as_ref().and_then(|m| m.ca_pem.clone());
+    // Issue the mesh certificate up front so a missing CA fails startup
+    let mesh_identity = if mesh {
+        let (Some(ca_pem), Some(ca_key_pem)) = (
+            tls_material.as_ref().and_then(|m| m.ca_pem.as_deref()),
+            tls_material.as_ref().and_then(|m| m.ca_key_pem.as_deref()),
+        ) else {
+            return Err(miette::miette!(
+                help = "Run with --tls (without --tls-cert/--tls-key) so the cluster CA key is available",
+                "--mesh requires the cluster CA certificate and key"
+            ));
+        };
+        Some(MeshIdentity::issue(ca_pem, ca_key_pem, node_name)?)
+    } else {
+        None
+    };
+
+    let svid_issuer = match spiffe_trust_domain {
+        Some(trust_domain) => {
+            let (Some(ca_pem), Some(ca_key_pem)) = (
+                tls_material.as_ref().and_then(|m| m.ca_pem.as_deref()),
+                tls_material.as_ref().and_then(|m| m.ca_key_pem.as_deref()),
+            ) else {
+                return Err(miette::miette!(
+                    help = "Run with --tls (without --tls-cert/--tls-key) so the cluster CA key is available",
+                    "--spiffe-trust-domain requires the cluster CA certificate and key"
+                ));
+            };
+            Some(Arc::new(SvidIssuer::new(ca_pem, ca_key_pem, trust_domain)?))
+        }
+        None => None,
+    };
 
     let api_token = token.clone();
     let api_handle = tokio::spawn(async move {
@@ -334,6 +1383,45 @@ async fn run_agent(
         }
     });
 
+    let usage_handle = spawn_usage_recorder(state.clone(), &supervisor);
+    let sweeper_handle = spawn_event_sweeper(state.clone(), &supervisor);
+    let rotator_handle = spawn_key_rotator(state.clone(), &supervisor);
+    let squasher_handle = spawn_history_squasher(state.clone(), &supervisor);
+
+    // Serve this node's pods and stats to monitoring agents holding a
+    // certificate from the cluster CA
+    let node_api_handle = match (node_api_bind, &tls_material) {
+        (Some(node_api_bind), Some(material)) => {
+            if material.ca_pem.is_none() {
+                return Err(miette::miette!(
+                    help = "Run with --tls (without --tls-cert/--tls-key) so the cluster CA is available",
+                    "--node-api-bind requires the cluster CA certificate"
+                ));
+            }
+            let listen_addr = node_api_bind.parse().map_err(|e| {
+                miette::miette!("Invalid node API address '{}': {}", node_api_bind, e)
+            })?;
+            let node_api = NodeApiServer::new(
+                NodeApiConfig {
+                    listen_addr,
+                    tls: material.clone(),
+                },
+                Arc::new(RuntimeNodeApi {
+                    node_name: node_name.to_string(),
+                    runtime: runtime.clone(),
+                    pod_cache: PodCache::new(state.storage.clone(), node_name),
+                }),
+            );
+            let node_api_token = token.clone();
+            Some(tokio::spawn(async move {
+                if let Err(e) = node_api.run(node_api_token).await {
+                    error!("Node-local API error: {}", e);
+                }
+            }))
+        }
+        _ => None,
+    };
+
     // Give the API server a moment to start listening
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
@@ -341,26 +1429,214 @@ async fn run_agent(
     let scheduler = Scheduler::new(
         state.storage.clone(),
         state.version_store.clone(),
-        state.event_tx.clone(),
-        SchedulerConfig::default(),
+        state.event_bus.clone(),
+        SchedulerConfig {
+            reserve_pod_ips,
+            ..Default::default()
+        },
+    )
+    .with_event_recorder(EventRecorder::new(
+        state.events.clone(),
+        "default-scheduler",
+    ));
+    let scheduler_handle =
+        supervisor.spawn("scheduler", scheduler, |scheduler, token| async move {
+            scheduler.run(token).await
+        });
+
+    let api_client = Arc::new(ApiClient::with_tls(
+        &api_url,
+        ca_pem.as_deref(),
+        client_identity,
+    ));
+    let stats_client = api_client.clone();
+    state
+        .metrics
+        .add_collector(move || stats_client.circuit_stats().render());
+    let agent_recorder = EventRecorder::new(
+        Arc::new(ApiEventSink::new(api_client.clone())),
+        "reddwarf-agent",
+    )
+    .with_host(node_name);
+
+    // 3. Spawn node IPAM controller, which hands each Node a slice of the
+    //    cluster CIDR as its spec.podCIDR
+    let node_ipam_handle = match cluster_cidr {
+        Some(cluster_cidr) => {
+            let allocator =
+                NodeCidrAllocator::new(state.storage.clone(), cluster_cidr, node_cidr_mask_size)
+                    .map_err(|e| {
+                        miette::miette!(
+                            "Failed to initialize node CIDR allocation from '{}': {}",
+                            cluster_cidr,
+                            e
+                        )
+                    })?;
+            let node_ipam = NodeIpamController::new(
+                api_client.clone(),
+                state.event_bus.clone(),
+                allocator,
+                NodeIpamControllerConfig::default(),
+            );
+            Some(supervisor.spawn(
+                "node-ipam-controller",
+                node_ipam,
+                |node_ipam, token| async move { node_ipam.run(token).await },
+            ))
+        }
+        None => None,
+    };
+
+    // Materialize the default-deny egress policy of locked-down namespaces
+    let egress_lockdown = EgressLockdownController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        EgressLockdownControllerConfig::default(),
+    );
+    let egress_lockdown_handle = supervisor.spawn(
+        "egress-lockdown-controller",
+        egress_lockdown,
+        |egress_lockdown, token| async move { egress_lockdown.run(token).await },
+    );
+
+    // Roll Deployments out through ReplicaSets, and keep their pods running
+    let deployments = DeploymentController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        DeploymentControllerConfig::default(),
+    );
+    let deployments_handle = supervisor.spawn(
+        "deployment-controller",
+        deployments,
+        |deployments, token| async move { deployments.run(token).await },
+    );
+    let replica_sets = ReplicaSetController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        ReplicaSetControllerConfig::default(),
+    );
+    let replica_sets_handle = supervisor.spawn(
+        "replicaset-controller",
+        replica_sets,
+        |replica_sets, token| async move { replica_sets.run(token).await },
+    );
+
+    // Run a pod of every DaemonSet on each Ready node it selects
+    let daemon_sets = DaemonSetController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        DaemonSetControllerConfig::default(),
+    );
+    let daemon_sets_handle = supervisor.spawn(
+        "daemonset-controller",
+        daemon_sets,
+        |daemon_sets, token| async move { daemon_sets.run(token).await },
+    );
+
+    // Scale Deployments and ReplicaSets with the usage of their pods. No
+    // pipeline samples pod usage yet, so autoscalers only keep their targets
+    // within bounds and report the missing metrics in their status
+    let autoscalers = HorizontalPodAutoscalerController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        Arc::new(MemoryPodMetrics::new()),
+        HorizontalPodAutoscalerControllerConfig::default(),
+    );
+    let autoscalers_handle = supervisor.spawn(
+        "horizontal-pod-autoscaler-controller",
+        autoscalers,
+        |autoscalers, token| async move { autoscalers.run(token).await },
+    );
+
+    // Report how many pods each PodDisruptionBudget lets be disrupted
+    let disruption_budgets = DisruptionBudgetController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        DisruptionBudgetControllerConfig::default(),
+    );
+    let disruption_budgets_handle = supervisor.spawn(
+        "disruption-budget-controller",
+        disruption_budgets,
+        |disruption_budgets, token| async move { disruption_budgets.run(token).await },
+    );
+
+    // Delete pods that finished longer than their TTL ago
+    let ttl_after_finished = TtlAfterFinishedController::new(
+        api_client.clone(),
+        TtlAfterFinishedControllerConfig {
+            ttl: finished_pod_ttl,
+            ..Default::default()
+        },
+    );
+    let ttl_after_finished_handle = supervisor.spawn(
+        "ttl-after-finished-controller",
+        ttl_after_finished,
+        |ttl_after_finished, token| async move { ttl_after_finished.run(token).await },
     );
-    let scheduler_token = token.clone();
-    let scheduler_handle = tokio::spawn(async move {
-        if let Err(e) = scheduler.run(scheduler_token).await {
-            error!("Scheduler error: {}", e);
+
+    // List the pods every Service selects in its Endpoints and EndpointSlices
+    let endpoints = EndpointsController::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        EndpointsControllerConfig::default(),
+    );
+    let endpoints_handle = supervisor.spawn(
+        "endpoints-controller",
+        endpoints,
+        |endpoints, token| async move { endpoints.run(token).await },
+    );
+
+    // 4. Spawn node agent
+    let mut node_agent_config = NodeAgentConfig::new(node_name.to_string(), api_url.clone());
+    node_agent_config.system_reserved_cpu_millicores = system_reserved_cpu_millicores;
+    node_agent_config.system_reserved_memory_bytes = system_reserved_memory_bytes;
+    node_agent_config.max_pods = max_pods;
+    node_agent_config.supported_brands = supported_brands.to_vec();
+    node_agent_config.node_ip = node_ip.map(String::from);
+    node_agent_config.agent_port = Some(listen_addr.port());
+    node_agent_config.devices = devices.to_vec();
+    node_agent_config.topology = topology.detect().await;
+    let node_agent = NodeAgent::new(api_client.clone(), node_agent_config)
+        .with_event_recorder(agent_recorder.clone());
+    let node_agent_handle =
+        supervisor.spawn("node-agent", node_agent, |node_agent, token| async move {
+            node_agent.run(token).await
+        });
+
+    // 5. Create IPAM for per-pod IP allocation, from the pod CIDR assigned to
+    //    this node's Node object when a cluster CIDR is configured
+    let pod_cidr = match cluster_cidr {
+        Some(_) => {
+            let node_cidr =
+                wait_for_pod_cidr(&api_client, node_name, std::time::Duration::from_secs(60))
+                    .await?;
+            info!("Node '{}' was assigned pod CIDR {}", node_name, node_cidr);
+            node_cidr
         }
-    });
+        None => pod_cidr.to_string(),
+    };
+    let pod_cidr = pod_cidr.as_str();
 
-    // 3. Create runtime with injected storage engine
-    let runtime: Arc<dyn reddwarf_runtime::ZoneRuntime> = create_runtime(storage_engine);
+    // Wire the host for pod connectivity before any pod is started
+    let host_network = if auto_network_setup {
+        let mut config = HostNetworkConfig::new(etherstub_name.to_string(), pod_cidr.to_string());
+        config.egress_interface = egress_interface.map(String::from);
+        let host_network =
+            HostNetwork::new(Arc::new(DladmHostLinks), Arc::new(IpnatRuleSet), config);
+        if let Err(e) = host_network.setup().await {
+            host_network.teardown().await;
+            return Err(miette::miette!("Failed to set up the pod network: {}", e));
+        }
+        Some(host_network)
+    } else {
+        None
+    };
 
-    // 4. Create IPAM for per-pod IP allocation
     let ipam = Ipam::new(state.storage.clone(), pod_cidr).map_err(|e| {
         miette::miette!("Failed to initialize IPAM with CIDR '{}': {}", pod_cidr, e)
     })?;
 
-    // 5. Spawn pod controller
-    let api_client = Arc::new(ApiClient::with_ca_cert(&api_url, ca_pem.as_deref()));
+    // 6. Spawn pod controller
     let controller_config = PodControllerConfig {
         node_name: node_name.to_string(),
         api_url: api_url.clone(),
@@ -369,45 +1645,187 @@ async fn run_agent(
         etherstub_name: etherstub_name.to_string(),
         pod_cidr: pod_cidr.to_string(),
         reconcile_interval: std::time::Duration::from_secs(30),
+        host_port_interface: host_port_interface.map(String::from),
+        cluster_dns_service: cluster_dns_service.map(String::from),
+        cluster_domain: cluster_domain.to_string(),
+        allowed_tunables,
     };
 
-    let controller = PodController::new(
-        runtime,
+    let mut controller = PodController::new(
+        runtime.clone(),
         api_client.clone(),
-        state.event_tx.clone(),
+        state.event_bus.clone(),
         controller_config,
         ipam,
+    )
+    .with_host_ports(HostPortTable::new(state.storage.clone(), node_name))
+    .with_devices(DeviceTable::new(
+        state.storage.clone(),
+        node_name,
+        devices.to_vec(),
+    ))
+    .with_metrics(state.metrics.clone())
+    .with_pod_cache(PodCache::new(state.storage.clone(), node_name))
+    .with_event_recorder(agent_recorder)
+    .with_decision_log(decision_log);
+    if let Some(issuer) = svid_issuer {
+        controller = controller.with_svid_issuer(issuer);
+    }
+
+    // Keep idle zones installed for the controller to claim
+    let warm_pool_handle = if warm_pools.is_empty() {
+        None
+    } else {
+        let pool = Arc::new(WarmPool::new(
+            runtime.clone(),
+            zonepath_prefix,
+            warm_pools.to_vec(),
+        ));
+        controller = controller.with_warm_pool(pool.clone());
+        Some(
+            supervisor.spawn("warm-pool", pool, |pool, token| async move {
+                pool.run(token).await
+            }),
+        )
+    };
+
+    let controller_handle = supervisor.spawn(
+        "pod-controller",
+        controller,
+        |controller, token| async move { controller.run(token).await },
     );
-    let controller_token = token.clone();
-    let controller_handle = tokio::spawn(async move {
-        if let Err(e) = controller.run(controller_token).await {
-            error!("Pod controller error: {}", e);
+
+    // 7. Spawn eviction manager
+    let eviction_manager = EvictionManager::new(
+        runtime,
+        api_client.clone(),
+        EvictionManagerConfig::new(node_name.to_string()),
+    );
+    let eviction_handle = supervisor.spawn(
+        "eviction-manager",
+        eviction_manager,
+        |eviction_manager, token| async move { eviction_manager.run(token).await },
+    );
+
+    // 8. Spawn node health checker
+    let health_checker = NodeHealthChecker::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        NodeHealthCheckerConfig::default(),
+    );
+    let health_handle = supervisor.spawn(
+        "node-health-checker",
+        health_checker,
+        |health_checker, token| async move { health_checker.run(token).await },
+    );
+
+    // 9. Spawn mesh proxy
+    let mesh_handle = match mesh_identity {
+        Some(identity) => {
+            let gateway = parse_cidr(pod_cidr)
+                .map_err(|e| miette::miette!("Invalid pod CIDR '{}': {}", pod_cidr, e))?
+                .gateway;
+            let mesh_proxy = MeshProxy::new(
+                api_client.clone(),
+                identity,
+                Arc::new(IpnatRedirect),
+                MeshProxyConfig::new(node_name.to_string(), gateway.into()),
+            );
+            Some(
+                supervisor.spawn("mesh-proxy", mesh_proxy, |mesh_proxy, token| async move {
+                    mesh_proxy.run(token).await
+                }),
+            )
         }
-    });
+        None => None,
+    };
 
-    // 6. Spawn node agent
-    let mut node_agent_config = NodeAgentConfig::new(node_name.to_string(), api_url);
-    node_agent_config.system_reserved_cpu_millicores = system_reserved_cpu_millicores;
-    node_agent_config.system_reserved_memory_bytes = system_reserved_memory_bytes;
-    node_agent_config.max_pods = max_pods;
-    node_agent_config.supported_brands = supported_brands.to_vec();
-    let node_agent = NodeAgent::new(api_client.clone(), node_agent_config);
-    let agent_token = token.clone();
-    let node_agent_handle = tokio::spawn(async move {
-        if let Err(e) = node_agent.run(agent_token).await {
-            error!("Node agent error: {}", e);
+    // 10. Spawn route distributor for cross-node pod traffic
+    let route_handle = if cluster_cidr.is_some() {
+        if node_ip.is_none() {
+            warn!(
+                "--cluster-cidr is set without --node-ip; other nodes cannot route to this node's pods"
+            );
         }
+        let distributor = RouteDistributor::new(
+            api_client.clone(),
+            Arc::new(HostRouteTable),
+            RouteDistributorConfig::new(node_name.to_string()),
+        );
+        Some(supervisor.spawn(
+            "route-distributor",
+            distributor,
+            |distributor, token| async move { distributor.run(token).await },
+        ))
+    } else {
+        None
+    };
+
+    // 11. Spawn Service rule exporter
+    let service_rules_handle = service_rules_interface.map(|interface| {
+        let exporter = ServiceRuleExporter::new(
+            api_client.clone(),
+            Arc::new(IpnatRuleSet),
+            ServiceRuleExporterConfig::new(
+                node_name.to_string(),
+                interface.to_string(),
+                service_rules_file.to_path_buf(),
+            ),
+        );
+        supervisor.spawn(
+            "service-rule-exporter",
+            exporter,
+            |exporter, token| async move { exporter.run(token).await },
+        )
     });
 
-    // 7. Spawn node health checker
-    let health_checker = NodeHealthChecker::new(api_client, NodeHealthCheckerConfig::default());
-    let health_token = token.clone();
-    let health_handle = tokio::spawn(async move {
-        if let Err(e) = health_checker.run(health_token).await {
-            error!("Node health checker error: {}", e);
-        }
+    // 12. Spawn egress NAT controller
+    let egress_nat_handle = egress_nat_interface.map(|interface| {
+        let controller = EgressNatController::new(
+            api_client.clone(),
+            Arc::new(IpnatRuleSet),
+            EgressNatControllerConfig::new(
+                node_name.to_string(),
+                interface.to_string(),
+                egress_nat_file.to_path_buf(),
+            ),
+        );
+        supervisor.spawn(
+            "egress-nat-controller",
+            controller,
+            |controller, token| async move { controller.run(token).await },
+        )
     });
 
+    // 13. Spawn network policy controller, filtering each pod's VNIC by the
+    //     NetworkPolicies selecting it
+    let network_policy = NetworkPolicyController::new(
+        api_client.clone(),
+        Arc::new(IpfRuleSet),
+        NetworkPolicyControllerConfig::new(
+            node_name.to_string(),
+            network_policy_file.to_path_buf(),
+        ),
+    );
+    let network_policy_handle = supervisor.spawn(
+        "network-policy-controller",
+        network_policy,
+        |network_policy, token| async move { network_policy.run(token).await },
+    );
+
+    // 14. Spawn volume binder
+    let volume_binder = VolumeBinder::new(
+        api_client.clone(),
+        state.event_bus.clone(),
+        storage_engine,
+        VolumeBinderConfig::new(node_name.to_string()),
+    );
+    let volume_binder_handle = supervisor.spawn(
+        "volume-binder",
+        volume_binder,
+        |volume_binder, token| async move { volume_binder.run(token).await },
+    );
+
     info!(
         "All components started. API server on {}, node name: {}, pod CIDR: {}",
         bind, node_name, pod_cidr
@@ -423,19 +1841,511 @@ async fn run_agent(
     let _ = tokio::time::timeout(shutdown_timeout, async {
         let _ = tokio::join!(
             api_handle,
+            async {
+                if let Some(handle) = node_api_handle {
+                    let _ = handle.await;
+                }
+            },
+            usage_handle,
+            sweeper_handle,
+            rotator_handle,
+            squasher_handle,
             scheduler_handle,
             controller_handle,
+            async {
+                if let Some(handle) = warm_pool_handle {
+                    let _ = handle.await;
+                }
+            },
             node_agent_handle,
+            async {
+                if let Some(handle) = node_ipam_handle {
+                    let _ = handle.await;
+                }
+            },
+            egress_lockdown_handle,
+            deployments_handle,
+            replica_sets_handle,
+            daemon_sets_handle,
+            autoscalers_handle,
+            disruption_budgets_handle,
+            ttl_after_finished_handle,
+            endpoints_handle,
+            eviction_handle,
             health_handle,
+            async {
+                if let Some(handle) = mesh_handle {
+                    let _ = handle.await;
+                }
+            },
+            async {
+                if let Some(handle) = route_handle {
+                    let _ = handle.await;
+                }
+            },
+            async {
+                if let Some(handle) = service_rules_handle {
+                    let _ = handle.await;
+                }
+            },
+            async {
+                if let Some(handle) = egress_nat_handle {
+                    let _ = handle.await;
+                }
+            },
+            network_policy_handle,
+            volume_binder_handle,
         );
     })
     .await;
 
+    if let Some(host_network) = host_network {
+        host_network.teardown().await;
+    }
+
     info!("Shutdown complete");
 
     Ok(())
 }
 
+/// Spawn the recorder of per-namespace usage for chargeback reports
+fn spawn_usage_recorder(
+    state: Arc<AppState>,
+    supervisor: &Supervisor,
+) -> tokio::task::JoinHandle<()> {
+    let recorder = UsageRecorder::new(state, UsageRecorderConfig::default());
+    supervisor.spawn("usage-recorder", recorder, |recorder, token| async move {
+        recorder.run(token).await.map_err(|e| format!("{:?}", e))
+    })
+}
+
+/// Spawn the sweeper removing events past their TTL
+fn spawn_event_sweeper(
+    state: Arc<AppState>,
+    supervisor: &Supervisor,
+) -> tokio::task::JoinHandle<()> {
+    let sweeper = EventSweeper::new(state, EventSweeperConfig::default());
+    supervisor.spawn("event-sweeper", sweeper, |sweeper, token| async move {
+        sweeper.run(token).await.map_err(|e| format!("{:?}", e))
+    })
+}
+
+/// Spawn the rotator re-encrypting stored values when the primary
+/// encryption key changes
+fn spawn_key_rotator(state: Arc<AppState>, supervisor: &Supervisor) -> tokio::task::JoinHandle<()> {
+    let rotator = KeyRotator::new(state, KeyRotatorConfig::default());
+    supervisor.spawn("key-rotator", rotator, |rotator, token| async move {
+        rotator.run(token).await.map_err(|e| format!("{:?}", e))
+    })
+}
+
+/// Spawn the squasher collapsing runs of single-resource updates in the
+/// commit history
+fn spawn_history_squasher(
+    state: Arc<AppState>,
+    supervisor: &Supervisor,
+) -> tokio::task::JoinHandle<()> {
+    let squasher = HistorySquasher::new(state, HistorySquasherConfig::default());
+    supervisor.spawn("history-squasher", squasher, |squasher, token| async move {
+        squasher.run(token).await.map_err(|e| format!("{:?}", e))
+    })
+}
+
+/// The encryptor of values at rest configured by the encryption arguments,
+/// if any
+fn envelope_encryptor(args: &EncryptionArgs) -> miette::Result<Option<Arc<EnvelopeEncryptor>>> {
+    let provider: Arc<dyn KmsProvider> = match (&args.encryption_key_file, &args.kms_command) {
+        (Some(path), _) => Arc::new(LocalKms::open(path).map_err(|e| {
+            miette::miette!(
+                help = "Create one with `reddwarf rotate-encryption-key --key-file <path>`",
+                "Failed to load encryption keys from {}: {}",
+                path.display(),
+                e
+            )
+        })?),
+        (None, Some(command)) => Arc::new(ExternalKms::new(command)),
+        (None, None) => return Ok(None),
+    };
+    let key_id = provider
+        .primary_key_id()
+        .map_err(|e| miette::miette!("Failed to read the primary encryption key: {}", e))?;
+    info!(
+        "Encryption at rest enabled ({} KMS provider, key {})",
+        provider.name(),
+        key_id
+    );
+    Ok(Some(Arc::new(EnvelopeEncryptor::new(provider))))
+}
+
+/// Make a new key the primary of the keyring at `key_file`
+fn run_rotate_encryption_key(
+    key_file: &std::path::Path,
+    key_id: Option<&str>,
+) -> miette::Result<()> {
+    let key_id = LocalKms::rotate(key_file, key_id).map_err(|e| {
+        miette::miette!("Failed to rotate the keyring {}: {}", key_file.display(), e)
+    })?;
+    println!(
+        "Key {} is now the primary of {}; running servers re-encrypt stored values within a minute",
+        key_id,
+        key_file.display()
+    );
+    Ok(())
+}
+
+/// Run a bench and write its report
+async fn run_bench(
+    config: &bench::BenchConfig,
+    output: Option<&std::path::Path>,
+) -> miette::Result<()> {
+    let report = bench::run(config).await?;
+    let report = serde_json::to_string_pretty(&report)
+        .map_err(|e| miette::miette!("Failed to serialize bench report: {}", e))?;
+    match output {
+        Some(path) => std::fs::write(path, report + "\n").map_err(|e| {
+            miette::miette!("Failed to write bench report to {}: {}", path.display(), e)
+        })?,
+        None => println!("{}", report),
+    }
+    Ok(())
+}
+
+/// Print startup latency percentiles of the cluster's pods
+async fn run_analyze_startup(api_url: &str, namespace: Option<&str>) -> miette::Result<()> {
+    let path = match namespace {
+        Some(ns) => format!("/api/v1/namespaces/{}/pods", ns),
+        None => "/api/v1/pods".to_string(),
+    };
+    let list = ApiClient::new(api_url)
+        .get_json(&path)
+        .await
+        .map_err(|e| miette::miette!("Failed to list pods from {}: {}", api_url, e))?;
+    let pods: Vec<Pod> = serde_json::from_value(list["items"].clone())
+        .map_err(|e| miette::miette!("Failed to parse pod list: {}", e))?;
+
+    let startups: Vec<PodStartup> = pods.iter().map(PodStartup::from_pod).collect();
+    let ready = startups.iter().filter(|s| s.ready.is_some()).count();
+    println!("{} pods, {} reached Ready", pods.len(), ready);
+
+    let summary = summarize_startup(&startups);
+    if summary.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!(
+        "{:<16} {:>6} {:>9} {:>9} {:>9} {:>9}",
+        "STAGE", "PODS", "P50(s)", "P90(s)", "P99(s)", "MAX(s)"
+    );
+    for stage in summary {
+        println!(
+            "{:<16} {:>6} {:>9.3} {:>9.3} {:>9.3} {:>9.3}",
+            stage.stage, stage.count, stage.p50, stage.p90, stage.p99, stage.max
+        );
+    }
+    Ok(())
+}
+
+fn upgrade_config(
+    args: &UpgradeArgs,
+    upgrade_command: Option<String>,
+    drain_timeout: u64,
+    ready_timeout: u64,
+) -> miette::Result<upgrade::UpgradeConfig> {
+    let ca_pem = match &args.ca_cert {
+        Some(path) => Some(std::fs::read(path).map_err(|e| {
+            miette::miette!("Failed to read CA certificate '{}': {}", path.display(), e)
+        })?),
+        None => None,
+    };
+    let target = match args.to.as_deref() {
+        Some(version) => Some(Version::parse(version).ok_or_else(|| {
+            miette::miette!(
+                help = "Use a version like '0.2.0'",
+                "Invalid --to '{}'",
+                version
+            )
+        })?),
+        None => None,
+    };
+    Ok(upgrade::UpgradeConfig {
+        api_url: args.api_url.clone(),
+        ca_pem,
+        target,
+        upgrade_command,
+        drain_timeout: std::time::Duration::from_secs(drain_timeout),
+        ready_timeout: std::time::Duration::from_secs(ready_timeout),
+    })
+}
+
+/// Apply or dry-run the pending storage migrations and print what they do
+fn run_migrate(data_dir: &str, dry_run: bool) -> miette::Result<()> {
+    let storage = open_storage(data_dir, DurabilityMode::default())?;
+    let history = open_history(data_dir, DurabilityMode::default())?;
+    let run = migrate_storage(data_dir, &storage, &history, dry_run)?;
+
+    if run.migrations.is_empty() {
+        println!("Schema is at version {}; nothing to do", run.from_version);
+        return Ok(());
+    }
+    let target = run.migrations.last().map(|m| m.version).unwrap_or(0);
+    println!(
+        "{} schema version {} -> {}",
+        if dry_run { "Would migrate" } else { "Migrated" },
+        run.from_version,
+        target
+    );
+    println!();
+    println!(
+        "{:<8} {:<32} {:>8} {:>8}",
+        "VERSION", "NAME", "PUTS", "DELETES"
+    );
+    for m in &run.migrations {
+        println!(
+            "{:<8} {:<32} {:>8} {:>8}",
+            m.version, m.name, m.puts, m.deletes
+        );
+    }
+    Ok(())
+}
+
+/// Check storage integrity, repair it and report what is left
+fn run_repair(data_dir: &str, drop_corrupt: bool) -> miette::Result<()> {
+    let storage = open_storage(data_dir, DurabilityMode::default())?;
+    let history = open_history(data_dir, DurabilityMode::default())?;
+    migrate_storage(data_dir, &storage, &history, false)?;
+    let version_store = open_version_store(&history)?;
+
+    let (issues, dag_issues) = check_integrity(&storage, &version_store)?;
+    if issues.is_empty() && dag_issues.is_empty() {
+        println!("No integrity issues found");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("found: {}", issue);
+    }
+    for issue in &dag_issues {
+        println!("found: {}", issue);
+    }
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let backup = format!("{}.pre-repair-{}.bak", data_dir, stamp);
+    storage
+        .backup_to(&backup)
+        .map_err(|e| miette::miette!("Failed to back up storage before repair: {}", e))?;
+    println!("Backed up storage to {}", backup);
+    let history_backup = format!("{}.pre-repair-{}.bak", history_path(data_dir), stamp);
+    version_store
+        .history()
+        .backup_to(&history_backup)
+        .map_err(|e| miette::miette!("Failed to back up history before repair: {}", e))?;
+    println!("Backed up history to {}", history_backup);
+
+    let repair_err = |e: &dyn std::fmt::Display| miette::miette!("Repair failed: {}", e);
+    if drop_corrupt {
+        for key in integrity::drop_corrupt_values(&storage).map_err(|e| repair_err(&e))? {
+            println!("dropped corrupt value at {}", key);
+        }
+    }
+    let entries = integrity::rebuild_indices(&storage).map_err(|e| repair_err(&e))?;
+    println!("Rebuilt indices ({} entries)", entries);
+    if let Some(head) = version_store.rederive_head().map_err(|e| repair_err(&e))? {
+        println!("HEAD is {}", head);
+    }
+
+    let (issues, dag_issues) = check_integrity(&storage, &version_store)?;
+    if issues.is_empty() && dag_issues.is_empty() {
+        println!("Storage is consistent");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("remaining: {}", issue);
+    }
+    for issue in &dag_issues {
+        println!("remaining: {}", issue);
+    }
+    Err(miette::miette!(
+        help = "Corrupt values are only removed with --drop-corrupt; restore the backup to undo",
+        "{} integrity issue(s) remain in '{}'",
+        issues.len() + dag_issues.len(),
+        data_dir
+    ))
+}
+
+/// Export the database at `data_dir`
+fn run_export(
+    data_dir: &str,
+    format: &str,
+    with_history: bool,
+    output: Option<&std::path::Path>,
+) -> miette::Result<()> {
+    if format != "jsonl" {
+        return Err(miette::miette!(
+            "Unknown export format '{}'; the only one is jsonl",
+            format
+        ));
+    }
+    let storage = open_storage(data_dir, DurabilityMode::default())?;
+    let version = Migrator::current_version(&storage)
+        .map_err(|e| miette::miette!("Failed to read the schema of '{}': {}", data_dir, e))?;
+    if version < Migrator::new(migrations()).latest_version() {
+        return Err(miette::miette!(
+            help = format!("Run `reddwarf migrate --data-dir {}` first", data_dir),
+            "Storage at '{}' has pending migrations",
+            data_dir
+        ));
+    }
+    let history = match with_history {
+        true => Some(open_history(data_dir, DurabilityMode::default())?),
+        false => None,
+    };
+
+    let mut out: Box<dyn std::io::Write> = match output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).map_err(|e| {
+                miette::miette!("Failed to create export file {}: {}", path.display(), e)
+            })?,
+        )),
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+    let summary = export::export(&storage, history.as_deref(), &mut out)
+        .map_err(|e| miette::miette!("Export of '{}' failed: {}", data_dir, e))?;
+    // Stdout carries the export itself
+    eprintln!(
+        "Exported {} objects, {} other entries and {} commits",
+        summary.objects, summary.entries, summary.commits
+    );
+    Ok(())
+}
+
+/// Check the export at `input` and load it into a new database at
+/// `data_dir`
+fn run_import(data_dir: &str, input: &std::path::Path, dry_run: bool) -> miette::Result<()> {
+    let file = std::fs::File::open(input)
+        .map_err(|e| miette::miette!("Failed to open export {}: {}", input.display(), e))?;
+    let dump = export::read_export(std::io::BufReader::new(file))
+        .map_err(|e| miette::miette!("Failed to read export {}: {}", input.display(), e))?;
+    let summary = dump.summary();
+    println!(
+        "Export of {} at schema version {}: {} objects, {} other entries and {} commits",
+        dump.header.exported_at,
+        dump.header.schema_version,
+        summary.objects,
+        summary.entries,
+        summary.commits
+    );
+
+    let issues = dump.check();
+    for issue in &issues {
+        println!("found: {}", issue);
+    }
+    if !issues.is_empty() {
+        return Err(miette::miette!(
+            help = "Export the source again; nothing was imported",
+            "{} unresolved reference(s) in {}",
+            issues.len(),
+            input.display()
+        ));
+    }
+    if dry_run {
+        println!("Export is consistent");
+        return Ok(());
+    }
+
+    let storage = open_storage(data_dir, DurabilityMode::default())?;
+    let history = open_history(data_dir, DurabilityMode::default())?;
+    let imported = export::import(&dump, &storage, Some(&history))
+        .map_err(|e| miette::miette!("Import into '{}' failed: {}", data_dir, e))?;
+    println!(
+        "Imported {} objects, {} other entries and {} commits into {}",
+        imported.objects, imported.entries, imported.commits, data_dir
+    );
+    migrate_storage(data_dir, &storage, &history, false)?;
+    Ok(())
+}
+
+/// Run the storage and commit DAG integrity checks
+fn check_integrity(
+    storage: &RedbBackend,
+    version_store: &VersionStore,
+) -> miette::Result<(Vec<IntegrityIssue>, Vec<DagIssue>)> {
+    let issues = integrity::check(storage)
+        .map_err(|e| miette::miette!("Failed to check storage integrity: {}", e))?;
+    let dag_issues = version_store
+        .check_integrity()
+        .map_err(|e| miette::miette!("Failed to check the commit DAG: {}", e))?;
+    Ok((issues, dag_issues))
+}
+
+/// Refuse to start on storage that breaks an invariant the API server
+/// relies on. A lagging HEAD or a dangling parent only warrants a warning.
+fn verify_integrity(
+    data_dir: &str,
+    storage: &RedbBackend,
+    version_store: &VersionStore,
+) -> miette::Result<()> {
+    let (issues, dag_issues) = check_integrity(storage, version_store)?;
+    let mut fatal = 0;
+    for issue in &issues {
+        error!("Integrity check: {}", issue);
+        fatal += 1;
+    }
+    for issue in &dag_issues {
+        match issue {
+            DagIssue::HeadBehind { .. } | DagIssue::MissingParent { .. } => {
+                warn!("Integrity check: {}", issue)
+            }
+            _ => {
+                error!("Integrity check: {}", issue);
+                fatal += 1;
+            }
+        }
+    }
+    if fatal > 0 {
+        return Err(miette::miette!(
+            help = format!("Run `reddwarf repair --data-dir {}` to recover", data_dir),
+            "Storage at '{}' failed its integrity check with {} issue(s)",
+            data_dir,
+            fatal
+        ));
+    }
+    Ok(())
+}
+
+/// Run the pending storage migrations, backing the database up next to
+/// `data_dir` first unless it holds no resources yet. Migrations may move
+/// entries to `history`.
+fn migrate_storage(
+    data_dir: &str,
+    storage: &Arc<RedbBackend>,
+    history: &Arc<RedbBackend>,
+    dry_run: bool,
+) -> miette::Result<MigrationRun> {
+    let backend = storage.clone();
+    let backup_base = data_dir.to_string();
+    Migrator::new(migrations())
+        .with_history(history.clone())
+        .with_dry_run(dry_run)
+        .with_backup_hook(move |plan| {
+            if backend.keys()?.is_empty() {
+                return Ok(());
+            }
+            let target = plan.last().map(|m| m.version).unwrap_or(0);
+            let stamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let dest = format!("{}.pre-v{}-{}.bak", backup_base, target, stamp);
+            backend.backup_to(&dest)?;
+            info!("Backed up storage to {} before migrating", dest);
+            Ok(())
+        })
+        .run(storage)
+        .map_err(|e| miette::miette!("Failed to migrate storage at '{}': {}", data_dir, e))
+}
+
 /// Bootstrap the "default" namespace if it doesn't already exist
 async fn bootstrap_default_namespace(state: &AppState) -> miette::Result<()> {
     use reddwarf_apiserver::handlers::common::create_resource;
@@ -458,19 +2368,86 @@ async fn bootstrap_default_namespace(state: &AppState) -> miette::Result<()> {
     Ok(())
 }
 
+/// Bootstrap the built-in system PriorityClasses if they don't already exist
+async fn bootstrap_priority_classes(state: &AppState) -> miette::Result<()> {
+    use reddwarf_apiserver::handlers::common::create_resource;
+
+    for class in reddwarf_core::resources::system_priority_classes() {
+        let name = class.metadata.name.clone().unwrap_or_default();
+        match create_resource(state, class).await {
+            Ok(_) => info!("Created PriorityClass {}", name),
+            Err(ApiError::AlreadyExists(_)) => {}
+            Err(e) => {
+                return Err(miette::miette!(
+                    "Failed to bootstrap PriorityClass {}: {:?}",
+                    name,
+                    e
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Create the shared application state
-fn create_app_state(data_dir: &str) -> miette::Result<Arc<AppState>> {
-    let storage = Arc::new(
+fn create_app_state(
+    data_dir: &str,
+    max_grace_period: Option<i64>,
+    default_deny_egress: bool,
+    durability: &str,
+) -> miette::Result<AppState> {
+    let durability = DurabilityMode::parse(durability).ok_or_else(|| {
+        miette::miette!(
+            help = "Use 'immediate', 'paranoid' or 'eventual'",
+            "Invalid --durability '{}'",
+            durability
+        )
+    })?;
+    let storage = open_storage(data_dir, durability)?;
+    let history = open_history(data_dir, durability)?;
+    migrate_storage(data_dir, &storage, &history, false)?;
+    let version_store = open_version_store(&history)?;
+    verify_integrity(data_dir, &storage, &version_store)?;
+
+    let mut state = AppState::new(storage, version_store);
+    if default_deny_egress {
+        state = state.with_default_deny_egress();
+    }
+    Ok(match max_grace_period {
+        Some(seconds) => state.with_max_grace_period(seconds),
+        None => state,
+    })
+}
+
+/// Open the redb database at `data_dir`
+fn open_storage(data_dir: &str, durability: DurabilityMode) -> miette::Result<Arc<RedbBackend>> {
+    Ok(Arc::new(
         RedbBackend::new(std::path::Path::new(data_dir))
-            .map_err(|e| miette::miette!("Failed to open storage at '{}': {}", data_dir, e))?,
-    );
+            .map_err(|e| miette::miette!("Failed to open storage at '{}': {}", data_dir, e))?
+            .with_durability(durability),
+    ))
+}
 
-    let version_store = Arc::new(
-        VersionStore::new(storage.clone())
-            .map_err(|e| miette::miette!("Failed to create version store: {}", e))?,
-    );
+/// Path of the history database kept next to the database at `data_dir`
+fn history_path(data_dir: &str) -> String {
+    format!("{}.history", data_dir)
+}
 
-    Ok(Arc::new(AppState::new(storage, version_store)))
+/// Open the history database kept next to the database at `data_dir`
+fn open_history(data_dir: &str, durability: DurabilityMode) -> miette::Result<Arc<RedbBackend>> {
+    let path = history_path(data_dir);
+    Ok(Arc::new(
+        RedbBackend::new(std::path::Path::new(&path))
+            .map_err(|e| miette::miette!("Failed to open history at '{}': {}", path, e))?
+            .with_durability(durability),
+    ))
+}
+
+/// Open the commit DAG kept in `history`
+fn open_version_store(history: &Arc<RedbBackend>) -> miette::Result<Arc<VersionStore>> {
+    Ok(Arc::new(VersionStore::new(history.clone()).map_err(
+        |e| miette::miette!("Failed to create version store: {}", e),
+    )?))
 }
 
 /// Create the appropriate storage engine for this platform
@@ -488,7 +2465,7 @@ fn create_storage_engine(config: StoragePoolConfig) -> Arc<dyn StorageEngine> {
 }
 
 /// Create the appropriate zone runtime for this platform
-fn create_runtime(storage: Arc<dyn StorageEngine>) -> Arc<dyn reddwarf_runtime::ZoneRuntime> {
+fn create_runtime(storage: Arc<dyn StorageEngine>) -> Arc<dyn ZoneRuntime> {
     #[cfg(target_os = "illumos")]
     {
         info!("Using IllumosRuntime (native zone support)");
@@ -500,3 +2477,218 @@ fn create_runtime(storage: Arc<dyn StorageEngine>) -> Arc<dyn reddwarf_runtime::
         Arc::new(MockRuntime::new(storage))
     }
 }
+
+/// Debug API backend that forwards to the local zone runtime and the pod
+/// controller's decision log
+struct RuntimeZoneDebug {
+    runtime: Arc<dyn ZoneRuntime>,
+    decision_log: Arc<DecisionLog>,
+}
+
+impl RuntimeZoneDebug {
+    fn map_err(err: RuntimeError) -> ApiError {
+        match err {
+            RuntimeError::ZoneNotFound { .. } => ApiError::NotFound(err.to_string()),
+            RuntimeError::InvalidStateTransition { .. } => ApiError::Conflict(err.to_string()),
+            _ => ApiError::Internal(err.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl ZoneDebugBackend for RuntimeZoneDebug {
+    async fn list_zones(&self) -> reddwarf_apiserver::Result<Vec<serde_json::Value>> {
+        let zones = self.runtime.list_zones().await.map_err(Self::map_err)?;
+        zones
+            .into_iter()
+            .map(|z| serde_json::to_value(z).map_err(|e| ApiError::Internal(e.to_string())))
+            .collect()
+    }
+
+    async fn get_zone(&self, zone_name: &str) -> reddwarf_apiserver::Result<serde_json::Value> {
+        let zone = self
+            .runtime
+            .get_zone_info(zone_name)
+            .await
+            .map_err(Self::map_err)?;
+        serde_json::to_value(zone).map_err(|e| ApiError::Internal(e.to_string()))
+    }
+
+    async fn halt_zone(&self, zone_name: &str) -> reddwarf_apiserver::Result<()> {
+        self.runtime
+            .halt_zone(zone_name)
+            .await
+            .map_err(Self::map_err)
+    }
+
+    async fn list_decisions(
+        &self,
+        namespace: Option<&str>,
+        pod: Option<&str>,
+        limit: usize,
+    ) -> reddwarf_apiserver::Result<Vec<serde_json::Value>> {
+        let decisions = self
+            .decision_log
+            .decisions(namespace, pod, limit)
+            .map_err(Self::map_err)?;
+        decisions
+            .into_iter()
+            .map(|d| serde_json::to_value(d).map_err(|e| ApiError::Internal(e.to_string())))
+            .collect()
+    }
+}
+
+/// Session backend opening sessions with the containers of pods bound to
+/// this node through the local zone runtime
+struct RuntimeContainerSessions {
+    node_name: String,
+    runtime: Arc<dyn ZoneRuntime>,
+}
+
+#[async_trait]
+impl ContainerSessionBackend for RuntimeContainerSessions {
+    async fn open(
+        &self,
+        pod: &Pod,
+        request: SessionRequest,
+    ) -> reddwarf_apiserver::Result<ProcessSession> {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+        let name = pod.metadata.name.as_deref().unwrap_or_default();
+        let node = pod.spec.as_ref().and_then(|s| s.node_name.as_deref());
+        if node != Some(self.node_name.as_str()) {
+            return Err(ApiError::BadRequest(format!(
+                "Pod {}/{} does not run on node {}; open the session through its own node",
+                namespace, name, self.node_name
+            )));
+        }
+
+        self.runtime
+            .open_session(&pod_zone_name(namespace, name), &request)
+            .await
+            .map_err(RuntimeZoneDebug::map_err)
+    }
+}
+
+/// Node-local API backend reading the pods cached by this node's controller
+/// and the zones of its runtime
+struct RuntimeNodeApi {
+    node_name: String,
+    runtime: Arc<dyn ZoneRuntime>,
+    pod_cache: PodCache,
+}
+
+#[async_trait]
+impl NodeApiBackend for RuntimeNodeApi {
+    async fn pods(&self) -> reddwarf_apiserver::Result<Vec<Pod>> {
+        self.pod_cache
+            .pods()
+            .map_err(|e| ApiError::Internal(e.to_string()))
+    }
+
+    async fn stats_summary(&self) -> reddwarf_apiserver::Result<StatsSummary> {
+        let pods = self.pods().await?;
+        let zones = self
+            .runtime
+            .list_zones()
+            .await
+            .map_err(RuntimeZoneDebug::map_err)?;
+        let resources = detect_system_resources().map_err(|e| ApiError::Internal(e.to_string()))?;
+        let memory_available_bytes = detect_available_memory().unwrap_or_default();
+
+        let pods = pods
+            .iter()
+            .map(|pod| {
+                let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+                let name = pod.metadata.name.clone().unwrap_or_default();
+                let zone_name = pod_zone_name(&namespace, &name);
+                let zone = zones.iter().find(|z| z.zone_name == zone_name);
+                let requests = pod
+                    .spec
+                    .as_ref()
+                    .map(ResourceQuantities::pod_requests)
+                    .unwrap_or_default();
+                PodStats {
+                    uid: pod.metadata.uid.clone(),
+                    phase: pod.status.as_ref().and_then(|s| s.phase.clone()),
+                    zone_name: zone.map(|z| z.zone_name.clone()),
+                    zone_state: zone.map(|z| z.state.to_string()),
+                    cpu_request_millicores: requests.cpu_millicores,
+                    memory_request_bytes: requests.memory_bytes,
+                    namespace,
+                    name,
+                }
+            })
+            .collect();
+
+        Ok(StatsSummary {
+            node: NodeStats {
+                node_name: self.node_name.clone(),
+                cpu_count: resources.cpu_count,
+                memory_total_bytes: resources.total_memory_bytes,
+                memory_available_bytes,
+                zone_count: zones.len(),
+            },
+            pods,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negative_max_grace_period_is_rejected() {
+        for command in [&["serve"][..], &["agent", "--node-name", "node1"]] {
+            let parse = |value: &str| {
+                let flag = format!("--max-grace-period={}", value);
+                let mut args = vec!["reddwarf"];
+                args.extend(command);
+                args.push(&flag);
+                Cli::try_parse_from(args)
+            };
+            let err = parse("-1").err().expect("a negative grace period");
+            assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+            assert!(parse("0").is_ok());
+            assert!(parse("30").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_history_moves_to_its_own_database() {
+        use reddwarf_versioning::CommitBuilder;
+
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("reddwarf.redb");
+        let data_dir = data_dir.to_str().unwrap();
+
+        // A database from before the split, with its history inline
+        let commit = {
+            let storage = open_storage(data_dir, DurabilityMode::default()).unwrap();
+            Migrator::new(migrations()[..2].to_vec())
+                .run(&storage)
+                .unwrap();
+            VersionStore::new(storage)
+                .unwrap()
+                .create_commit(CommitBuilder::new().message("Before".to_string()))
+                .unwrap()
+        };
+
+        run_migrate(data_dir, true).unwrap();
+        let state = create_app_state(data_dir, None, false, "immediate").unwrap();
+        assert_eq!(state.version_store.head_id(), Some(commit.id));
+        assert!(state
+            .storage
+            .keys_with_prefix(b"version:")
+            .unwrap()
+            .is_empty());
+        let backups = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().contains(".pre-v3-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+}
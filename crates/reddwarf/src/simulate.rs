@@ -0,0 +1,210 @@
+//! `reddwarf simulate-schedule`: run the scheduler's filters and scores for
+//! the pods of a manifest file against a snapshot of cluster state, and
+//! print where each pod would go and how every node fared, binding nothing
+
+use reddwarf_core::Pod;
+use reddwarf_runtime::ApiClient;
+use reddwarf_scheduler::{ClusterSnapshot, SimulationReport, Simulator};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Parameters of `reddwarf simulate-schedule`
+#[derive(Debug, Clone)]
+pub struct SimulateConfig {
+    /// Manifest with the pods to place, and optionally nodes; "-" reads
+    /// stdin
+    pub file: PathBuf,
+    /// Also take nodes, bound pods and RuntimeClasses from the API server
+    pub nodes_from_cluster: bool,
+    /// API server URL
+    pub api_url: String,
+    /// CA certificate to trust for the API server's TLS certificate
+    pub ca_pem: Option<Vec<u8>>,
+    /// Print the report as JSON instead of a table
+    pub json: bool,
+}
+
+/// Simulate scheduling the pods of the manifest and print the outcome
+pub async fn run(config: &SimulateConfig) -> miette::Result<()> {
+    let manifest = read_manifest(config)?;
+    let mut snapshot = manifest.snapshot;
+    if config.nodes_from_cluster {
+        let cluster = fetch_snapshot(config).await?;
+        snapshot.nodes.extend(cluster.nodes);
+        snapshot.pods.extend(cluster.pods);
+        snapshot.runtime_classes.extend(cluster.runtime_classes);
+    }
+    if snapshot.nodes.is_empty() {
+        return Err(miette::miette!(
+            help = "Pass --nodes-from-cluster, or add Node objects to the file",
+            "No nodes to simulate scheduling onto"
+        ));
+    }
+
+    let report = Simulator::new().run(&snapshot, &manifest.pods);
+    if config.json {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize report: {}", e))?;
+        println!("{}", json);
+    } else {
+        print_report(&report, snapshot.nodes.len());
+    }
+    Ok(())
+}
+
+/// Objects read from the manifest file
+#[derive(Default)]
+struct Manifest {
+    pods: Vec<Pod>,
+    snapshot: ClusterSnapshot,
+}
+
+fn read_manifest(config: &SimulateConfig) -> miette::Result<Manifest> {
+    let path = config.file.display();
+    let data = if config.file.as_os_str() == "-" {
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|e| miette::miette!("Failed to read stdin: {}", e))?
+    } else {
+        std::fs::read_to_string(&config.file)
+            .map_err(|e| miette::miette!("Failed to read '{}': {}", path, e))?
+    };
+
+    let mut manifest = Manifest::default();
+    for document in serde_yaml::Deserializer::from_str(&data) {
+        let object = Value::deserialize(document)
+            .map_err(|e| miette::miette!("Failed to parse '{}': {}", path, e))?;
+        if !object.is_null() {
+            add_object(&mut manifest, object)?;
+        }
+    }
+    if manifest.pods.is_empty() {
+        return Err(miette::miette!(
+            help = "Give Pod, Deployment or ReplicaSet objects, as separate YAML documents or in a List",
+            "'{}' has no pods to simulate",
+            path
+        ));
+    }
+    Ok(manifest)
+}
+
+fn parse<T: serde::de::DeserializeOwned>(object: Value, kind: &str) -> miette::Result<T> {
+    serde_json::from_value(object).map_err(|e| miette::miette!("Invalid {}: {}", kind, e))
+}
+
+/// Add a manifest object; Deployments and ReplicaSets add a pod per replica
+fn add_object(manifest: &mut Manifest, object: Value) -> miette::Result<()> {
+    let kind = object["kind"].as_str().unwrap_or_default().to_string();
+    match kind.as_str() {
+        "Pod" => manifest.pods.push(parse(object, &kind)?),
+        "Node" => manifest.snapshot.nodes.push(parse(object, &kind)?),
+        "RuntimeClass" => manifest
+            .snapshot
+            .runtime_classes
+            .push(parse(object, &kind)?),
+        "Deployment" | "ReplicaSet" => {
+            let name = object["metadata"]["name"].as_str().unwrap_or_default();
+            let namespace = object["metadata"]["namespace"].as_str();
+            let replicas = object["spec"]["replicas"].as_u64().unwrap_or(1);
+            let template = &object["spec"]["template"];
+            for i in 0..replicas {
+                let mut pod: Pod = parse(
+                    serde_json::json!({
+                        "apiVersion": "v1",
+                        "kind": "Pod",
+                        "metadata": template["metadata"],
+                        "spec": template["spec"],
+                    }),
+                    &kind,
+                )?;
+                pod.metadata.name = Some(format!("{}-{}", name, i));
+                pod.metadata.namespace = namespace.map(String::from);
+                manifest.pods.push(pod);
+            }
+        }
+        list if list.ends_with("List") => {
+            for item in object["items"].as_array().cloned().unwrap_or_default() {
+                add_object(manifest, item)?;
+            }
+        }
+        other => {
+            return Err(miette::miette!(
+                help =
+                    "The file may hold Pod, Deployment, ReplicaSet, Node and RuntimeClass objects",
+                "Cannot simulate objects of kind '{}'",
+                other
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Nodes, pods and RuntimeClasses of the cluster
+async fn fetch_snapshot(config: &SimulateConfig) -> miette::Result<ClusterSnapshot> {
+    let client = ApiClient::with_ca_cert(&config.api_url, config.ca_pem.as_deref());
+    Ok(ClusterSnapshot {
+        nodes: list(&client, "/api/v1/nodes", "node").await?,
+        pods: list(&client, "/api/v1/pods", "pod").await?,
+        runtime_classes: list(
+            &client,
+            "/apis/node.k8s.io/v1/runtimeclasses",
+            "runtime class",
+        )
+        .await?,
+    })
+}
+
+async fn list<T: serde::de::DeserializeOwned>(
+    client: &ApiClient,
+    path: &str,
+    what: &str,
+) -> miette::Result<Vec<T>> {
+    let list = client
+        .get_json(path)
+        .await
+        .map_err(|e| miette::miette!("Failed to list {}s: {}", what, e))?;
+    serde_json::from_value(list["items"].clone())
+        .map_err(|e| miette::miette!("Failed to parse {} list: {}", what, e))
+}
+
+fn print_report(report: &SimulationReport, nodes: usize) {
+    let mut per_node: BTreeMap<&str, usize> = BTreeMap::new();
+    for placement in &report.placements {
+        let pod = format!("{}/{}", placement.namespace, placement.name);
+        match (&placement.node_name, &placement.message) {
+            (Some(node), None) => {
+                *per_node.entry(node).or_default() += 1;
+                println!("{} -> {}", pod, node);
+            }
+            (Some(node), Some(message)) => println!("{} -> {} ({})", pod, node, message),
+            (None, message) => println!(
+                "{} -> unschedulable: {}",
+                pod,
+                message.as_deref().unwrap_or_default()
+            ),
+        }
+        for decision in &placement.nodes {
+            match (decision.score, &decision.rejected_by) {
+                (Some(score), _) => println!("  {:<24} score {}", decision.node_name, score),
+                (None, filter) => println!(
+                    "  {:<24} {}: {}",
+                    decision.node_name,
+                    filter.as_deref().unwrap_or_default(),
+                    decision.reason.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{}/{} pods would be scheduled onto {} nodes",
+        report.scheduled(),
+        report.placements.len(),
+        nodes
+    );
+    for (node, count) in per_node {
+        println!("  {:<24} {} new pods", node, count);
+    }
+}
@@ -0,0 +1,238 @@
+//! Supervision of the components the agent runs as tasks: a component whose
+//! task panics, fails or returns before shutdown is restarted with backoff,
+//! and its health and restarts are reported to the API server's
+//! [`ComponentRegistry`] for `/api/v1/componentstatuses`
+
+use reddwarf_apiserver::ComponentRegistry;
+use reddwarf_runtime::backoff::{BackoffDecision, ReconcileBackoff, ReconcileBackoffConfig};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+/// A component that ran this long before failing starts over from the
+/// shortest restart delay
+const STABLE_RUN: Duration = Duration::from_secs(300);
+
+/// Restarts components of the agent until shutdown
+#[derive(Clone)]
+pub struct Supervisor {
+    registry: ComponentRegistry,
+    token: CancellationToken,
+    backoff: Arc<ReconcileBackoff>,
+}
+
+impl Supervisor {
+    pub fn new(registry: ComponentRegistry, token: CancellationToken) -> Self {
+        Self {
+            registry,
+            token,
+            backoff: Arc::new(ReconcileBackoff::new(ReconcileBackoffConfig {
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(60),
+                max_jitter: Duration::from_millis(500),
+                // Components are restarted for as long as the agent runs
+                error_budget: u32::MAX,
+            })),
+        }
+    }
+
+    /// Run `component` as `name` until shutdown, calling `run` again after
+    /// each failure
+    pub fn spawn<C, F, Fut, E>(&self, name: &str, component: C, run: F) -> JoinHandle<()>
+    where
+        C: Send + Sync + 'static,
+        F: Fn(Arc<C>, CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: std::fmt::Display + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let name = name.to_string();
+        let component = Arc::new(component);
+        tokio::spawn(async move {
+            loop {
+                supervisor.registry.started(&name);
+                let started = Instant::now();
+                let task = tokio::spawn(run(component.clone(), supervisor.token.clone()));
+                let failure = match task.await {
+                    Ok(Ok(())) => "Exited before shutdown".to_string(),
+                    Ok(Err(e)) => e.to_string(),
+                    Err(e) if e.is_panic() => format!("Panicked: {}", panic_message(e)),
+                    Err(e) => e.to_string(),
+                };
+                if supervisor.token.is_cancelled() {
+                    return;
+                }
+
+                supervisor.registry.failed(&name, failure.clone());
+                if started.elapsed() >= STABLE_RUN {
+                    supervisor.backoff.forget(&name);
+                }
+                let delay = match supervisor.backoff.record_failure(&name) {
+                    BackoffDecision::Retry { delay, .. } => delay,
+                    BackoffDecision::Exhausted { .. } => Duration::from_secs(60),
+                };
+                error!(
+                    "Component {} failed: {}; restarting in {:?}",
+                    name, failure, delay
+                );
+                tokio::select! {
+                    _ = supervisor.token.cancelled() => return,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                supervisor.registry.restarting(&name);
+                info!("Restarting component {}", name);
+            }
+        })
+    }
+}
+
+/// The message a task panicked with
+fn panic_message(error: tokio::task::JoinError) -> String {
+    let panic = error.into_panic();
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// When each run of a component started
+    #[derive(Default)]
+    struct Runs {
+        starts: Mutex<Vec<Instant>>,
+    }
+
+    impl Runs {
+        /// Record a run starting; returns how many have
+        fn start(&self) -> usize {
+            let mut starts = self.starts.lock().unwrap();
+            starts.push(Instant::now());
+            starts.len()
+        }
+
+        /// Time between consecutive starts
+        fn gaps(&self) -> Vec<Duration> {
+            let starts = self.starts.lock().unwrap();
+            starts.windows(2).map(|w| w[1] - w[0]).collect()
+        }
+    }
+
+    fn supervisor() -> (Supervisor, ComponentRegistry, CancellationToken) {
+        let registry = ComponentRegistry::new();
+        let token = CancellationToken::new();
+        (
+            Supervisor::new(registry.clone(), token.clone()),
+            registry,
+            token,
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_panicked_component_is_marked_failed_and_restarted() {
+        let (supervisor, registry, token) = supervisor();
+        let runs = Arc::new(Runs::default());
+        let handle = supervisor.spawn("flaky", runs.clone(), |runs, token| async move {
+            if runs.start() == 1 {
+                panic!("boom");
+            }
+            token.cancelled().await;
+            Ok::<(), String>(())
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let health = registry.get("flaky").unwrap();
+        assert!(!health.healthy);
+        assert_eq!(health.last_error.as_deref(), Some("Panicked: boom"));
+        assert_eq!(health.restarts, 0);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let health = registry.get("flaky").unwrap();
+        assert!(health.healthy);
+        assert_eq!(health.restarts, 1);
+        assert_eq!(runs.gaps().len(), 1);
+
+        token.cancel();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_repeated_errors_grow_restart_delay() {
+        let (supervisor, registry, token) = supervisor();
+        let runs = Arc::new(Runs::default());
+        let handle = supervisor.spawn("failing", runs.clone(), |runs, _| async move {
+            runs.start();
+            Err("unavailable".to_string())
+        });
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        let gaps = runs.gaps();
+        assert!(gaps.len() >= 3, "restarted {} times", gaps.len());
+        assert!(gaps[0] >= Duration::from_secs(1));
+        assert!(
+            gaps.windows(2).all(|w| w[1] > w[0]),
+            "delays {:?} don't grow",
+            gaps
+        );
+        let health = registry.get("failing").unwrap();
+        assert_eq!(health.last_error.as_deref(), Some("unavailable"));
+        assert_eq!(health.restarts as usize, gaps.len());
+
+        token.cancel();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stable_run_resets_restart_delay() {
+        let (supervisor, _registry, token) = supervisor();
+        let runs = Arc::new(Runs::default());
+        let handle = supervisor.spawn("steady", runs.clone(), |runs, _| async move {
+            if runs.start() == 3 {
+                tokio::time::sleep(STABLE_RUN).await;
+            }
+            Err("lost connection".to_string())
+        });
+
+        tokio::time::sleep(STABLE_RUN + Duration::from_secs(10)).await;
+        let gaps = runs.gaps();
+        assert!(gaps.len() >= 3, "restarted {} times", gaps.len());
+        assert!(gaps[1] >= Duration::from_secs(2));
+        // After the long run the delay starts over from the shortest
+        assert!(gaps[2] - STABLE_RUN < Duration::from_secs(2), "{:?}", gaps);
+
+        token.cancel();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clean_exit_on_shutdown_is_not_restarted() {
+        let (supervisor, registry, token) = supervisor();
+        let runs = Arc::new(Runs::default());
+        let handle = supervisor.spawn("worker", runs.clone(), |runs, token| async move {
+            runs.start();
+            token.cancelled().await;
+            Ok::<(), String>(())
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        token.cancel();
+        handle.await.unwrap();
+        tokio::time::sleep(Duration::from_secs(120)).await;
+
+        assert_eq!(runs.starts.lock().unwrap().len(), 1);
+        let health = registry.get("worker").unwrap();
+        assert!(health.healthy);
+        assert_eq!(health.restarts, 0);
+        assert_eq!(health.last_error, None);
+    }
+}
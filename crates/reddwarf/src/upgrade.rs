@@ -0,0 +1,421 @@
+//! `reddwarf upgrade plan/apply`: check the version skew between the API
+//! server and every node agent, then upgrade the agents one at a time by
+//! cordoning the node, evicting its pods, running an operator-supplied
+//! upgrade command and waiting for the agent to come back at the target
+//! version before making the node schedulable again
+
+use reddwarf_core::version::{check_agent_skew, node_version, Version};
+use reddwarf_core::{Node, Pod};
+use reddwarf_runtime::command::exec_unchecked;
+use reddwarf_runtime::ApiClient;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How often drains and upgrades are polled for progress
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Placeholder for the node name in the upgrade command
+pub const NODE_PLACEHOLDER: &str = "{node}";
+
+/// Annotation marking a mirror pod, the API server's copy of a static pod
+/// a node runs from its local configuration
+const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+
+/// Parameters of `reddwarf upgrade`
+#[derive(Debug, Clone)]
+pub struct UpgradeConfig {
+    /// API server URL
+    pub api_url: String,
+    /// CA certificate to trust for the API server's TLS certificate
+    pub ca_pem: Option<Vec<u8>>,
+    /// Version to bring the agents to (the API server's when unset)
+    pub target: Option<Version>,
+    /// Shell command upgrading one node's agent, with `{node}` replaced by
+    /// the node name (required by `apply`)
+    pub upgrade_command: Option<String>,
+    /// How long evicted pods may take to leave a node
+    pub drain_timeout: Duration,
+    /// How long an upgraded agent may take to report the target version and
+    /// become Ready
+    pub ready_timeout: Duration,
+}
+
+/// What the plan does with one node
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    UpToDate,
+    Upgrade,
+    /// The node reports no reddwarf version and is left alone
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+struct PlannedNode {
+    name: String,
+    version: Option<Version>,
+    /// Why the node's current version is not supported, if it is not
+    skew: Option<String>,
+    ready: bool,
+    action: Action,
+}
+
+#[derive(Debug, Clone)]
+struct Plan {
+    server: Version,
+    target: Version,
+    nodes: Vec<PlannedNode>,
+}
+
+/// Print the skew of every node and which ones an upgrade would touch
+pub async fn plan(config: &UpgradeConfig) -> miette::Result<()> {
+    let client = client(config);
+    let plan = build_plan(&client, config.target).await?;
+    print_plan(&plan);
+    Ok(())
+}
+
+/// Upgrade the agents the plan marks, one node at a time; stops at the
+/// first node that fails, leaving it cordoned
+pub async fn apply(config: &UpgradeConfig) -> miette::Result<()> {
+    let Some(command) = config.upgrade_command.as_deref() else {
+        return Err(miette::miette!(
+            help = "Pass e.g. --upgrade-command 'ssh {node} pkg update reddwarf && ssh {node} svcadm restart reddwarf'",
+            "upgrade apply needs the command that upgrades a node's agent"
+        ));
+    };
+    let client = client(config);
+    let plan = build_plan(&client, config.target).await?;
+    print_plan(&plan);
+
+    let pending: Vec<&PlannedNode> = plan
+        .nodes
+        .iter()
+        .filter(|n| n.action == Action::Upgrade)
+        .collect();
+    if pending.is_empty() {
+        println!("All nodes are at {}", plan.target);
+        return Ok(());
+    }
+    for (i, node) in pending.iter().enumerate() {
+        println!(
+            "[{}/{}] Upgrading {} to {}",
+            i + 1,
+            pending.len(),
+            node.name,
+            plan.target
+        );
+        upgrade_node(&client, config, command, &node.name, plan.target).await?;
+    }
+    println!("Upgraded {} node(s) to {}", pending.len(), plan.target);
+    Ok(())
+}
+
+fn client(config: &UpgradeConfig) -> ApiClient {
+    ApiClient::with_ca_cert(&config.api_url, config.ca_pem.as_deref())
+}
+
+async fn build_plan(client: &ApiClient, target: Option<Version>) -> miette::Result<Plan> {
+    let server = server_version(client).await?;
+    let target = target.unwrap_or(server);
+    check_agent_skew(server, target).map_err(|reason| {
+        miette::miette!(
+            help = "Upgrade the control plane first, one minor release at a time",
+            "Cannot upgrade agents to {}: {}",
+            target,
+            reason
+        )
+    })?;
+
+    let list = client
+        .get_json("/api/v1/nodes")
+        .await
+        .map_err(|e| miette::miette!("Failed to list nodes: {}", e))?;
+    let mut nodes: Vec<Node> = serde_json::from_value(list["items"].clone())
+        .map_err(|e| miette::miette!("Failed to parse node list: {}", e))?;
+    nodes.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+
+    let nodes = nodes
+        .iter()
+        .map(|node| {
+            let version = node_version(node);
+            let action = match version {
+                None => Action::Unknown,
+                Some(v) if v == target => Action::UpToDate,
+                Some(_) => Action::Upgrade,
+            };
+            PlannedNode {
+                name: node.metadata.name.clone().unwrap_or_default(),
+                version,
+                skew: version.and_then(|v| check_agent_skew(server, v).err()),
+                ready: is_ready(node),
+                action,
+            }
+        })
+        .collect();
+    Ok(Plan {
+        server,
+        target,
+        nodes,
+    })
+}
+
+/// The reddwarf version in the API server's `/version`, e.g.
+/// `v1.31.0-reddwarf.0.1.0`
+async fn server_version(client: &ApiClient) -> miette::Result<Version> {
+    let info = client
+        .get_json("/version")
+        .await
+        .map_err(|e| miette::miette!("Failed to get the API server version: {}", e))?;
+    let git_version = info["gitVersion"].as_str().unwrap_or_default();
+    git_version
+        .split_once("-reddwarf.")
+        .and_then(|(_, version)| Version::parse(version))
+        .ok_or_else(|| {
+            miette::miette!(
+                "API server reports version '{}', which is not a reddwarf version",
+                git_version
+            )
+        })
+}
+
+fn print_plan(plan: &Plan) {
+    println!("API server: {}", plan.server);
+    println!("Target:     {}", plan.target);
+    println!();
+    println!(
+        "{:<24} {:<10} {:<8} {:<12} SKEW",
+        "NODE", "VERSION", "READY", "ACTION"
+    );
+    for node in &plan.nodes {
+        let action = match node.action {
+            Action::UpToDate => "up to date",
+            Action::Upgrade => "upgrade",
+            Action::Unknown => "skip",
+        };
+        println!(
+            "{:<24} {:<10} {:<8} {:<12} {}",
+            node.name,
+            node.version
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            if node.ready { "True" } else { "False" },
+            action,
+            node.skew.as_deref().unwrap_or("supported")
+        );
+    }
+}
+
+async fn upgrade_node(
+    client: &ApiClient,
+    config: &UpgradeConfig,
+    command: &str,
+    node: &str,
+    target: Version,
+) -> miette::Result<()> {
+    info!("Cordoning node {}", node);
+    client
+        .set_node_unschedulable(node, true)
+        .await
+        .map_err(|e| miette::miette!("Failed to cordon {}: {}", node, e))?;
+
+    drain(client, node, config.drain_timeout).await?;
+
+    let command = command.replace(NODE_PLACEHOLDER, node);
+    info!("Running upgrade command for {}: {}", node, command);
+    let out = exec_unchecked("sh", &["-c", &command])
+        .await
+        .map_err(|e| miette::miette!("Failed to run the upgrade command: {}", e))?;
+    if out.exit_code != 0 {
+        return Err(miette::miette!(
+            help = format!("{} stays cordoned; fix it and rerun upgrade apply", node),
+            "Upgrade command for {} exited with {}: {}",
+            node,
+            out.exit_code,
+            out.stderr.trim()
+        ));
+    }
+
+    wait_for_version(client, node, target, config.ready_timeout).await?;
+
+    info!("Uncordoning node {}", node);
+    client
+        .set_node_unschedulable(node, false)
+        .await
+        .map_err(|e| miette::miette!("Failed to uncordon {}: {}", node, e))?;
+    Ok(())
+}
+
+/// Evict every pod bound to `node` and wait for them to be gone; evictions
+/// refused for now (e.g. by a disruption budget) are retried until the
+/// timeout. Pods a drain leaves alone (see [`ignored_by_drain`]) do not
+/// hold it up.
+async fn drain(client: &ApiClient, node: &str, timeout: Duration) -> miette::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let pods = pods_on_node(client, node).await?;
+        if pods.is_empty() {
+            info!("Node {} is drained", node);
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(miette::miette!(
+                help = format!(
+                    "{} stays cordoned; rerun with a longer --drain-timeout",
+                    node
+                ),
+                "{} pod(s) still on {} after {:?}",
+                pods.len(),
+                node,
+                timeout
+            ));
+        }
+        for pod in pods
+            .iter()
+            .filter(|p| p.metadata.deletion_timestamp.is_none())
+        {
+            let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+            let name = pod.metadata.name.as_deref().unwrap_or_default();
+            if let Err(e) = client.evict_pod(namespace, name, None).await {
+                warn!(
+                    "Eviction of {}/{} refused, retrying: {}",
+                    namespace, name, e
+                );
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn pods_on_node(client: &ApiClient, node: &str) -> miette::Result<Vec<Pod>> {
+    let list = client
+        .get_json("/api/v1/pods")
+        .await
+        .map_err(|e| miette::miette!("Failed to list pods: {}", e))?;
+    let pods: Vec<Pod> = serde_json::from_value(list["items"].clone())
+        .map_err(|e| miette::miette!("Failed to parse pod list: {}", e))?;
+    Ok(pods
+        .into_iter()
+        .filter(|p| p.spec.as_ref().and_then(|s| s.node_name.as_deref()) == Some(node))
+        .filter(|p| !ignored_by_drain(p))
+        .collect())
+}
+
+/// Whether a drain leaves `pod` on its node, as `kubectl drain
+/// --ignore-daemonsets` does: the DaemonSet controller recreates its pods on
+/// cordoned nodes however often they are evicted, and mirror pods go away
+/// only with the static pods they mirror
+fn ignored_by_drain(pod: &Pod) -> bool {
+    let daemon = pod
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|owner| owner.controller == Some(true) && owner.kind == "DaemonSet");
+    let mirror = pod
+        .metadata
+        .annotations
+        .as_ref()
+        .is_some_and(|a| a.contains_key(MIRROR_POD_ANNOTATION));
+    daemon || mirror
+}
+
+/// Wait for the upgraded agent to report `target` and become Ready
+async fn wait_for_version(
+    client: &ApiClient,
+    node: &str,
+    target: Version,
+    timeout: Duration,
+) -> miette::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match client.get_node(node).await {
+            Ok(current) if node_version(&current) == Some(target) && is_ready(&current) => {
+                info!("Node {} is Ready at {}", node, target);
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to get node {}: {}", node, e),
+        }
+        if Instant::now() >= deadline {
+            return Err(miette::miette!(
+                help = format!("{} stays cordoned; check its agent's logs", node),
+                "{} did not report version {} and become Ready within {:?}",
+                node,
+                target,
+                timeout
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn is_ready(node: &Node) -> bool {
+    node.status
+        .as_ref()
+        .and_then(|s| s.conditions.as_ref())
+        .is_some_and(|conditions| {
+            conditions
+                .iter()
+                .any(|c| c.type_ == "Ready" && c.status == "True")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reddwarf_runtime::mock_api::MockApiServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_drain_leaves_daemon_set_and_mirror_pods() {
+        let server = MockApiServer::start().await;
+        let pod = |name: &str, metadata: serde_json::Value| {
+            let mut pod =
+                json!({"metadata": metadata, "spec": {"nodeName": "node1", "containers": []}});
+            pod["metadata"]["name"] = json!(name);
+            pod["metadata"]["namespace"] = json!("default");
+            pod
+        };
+        let daemon = pod(
+            "agent-x7k2p",
+            json!({"ownerReferences": [{
+                "apiVersion": "apps/v1", "kind": "DaemonSet", "name": "agent",
+                "uid": "1", "controller": true
+            }]}),
+        );
+        let mirror = pod(
+            "static-node1",
+            json!({"annotations": {MIRROR_POD_ANNOTATION: "hash"}}),
+        );
+        server.respond(
+            "GET",
+            "/api/v1/pods",
+            200,
+            json!({"items": [daemon, mirror]}),
+        );
+
+        let client = ApiClient::new(&server.url());
+        drain(&client, "node1", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(server.requests().iter().all(|r| r.method == "GET"));
+
+        // Any other pod is evicted and holds the drain up until it is gone
+        let web = pod("web", json!({}));
+        server.respond(
+            "GET",
+            "/api/v1/pods",
+            200,
+            json!({"items": [daemon, mirror, web]}),
+        );
+        let timeout = Duration::from_millis(100);
+        assert!(drain(&client, "node1", timeout).await.is_err());
+        let evicted: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|r| r.method == "POST")
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(evicted, ["/api/v1/namespaces/default/pods/web/eviction"]);
+    }
+}
@@ -16,6 +16,13 @@ getprop() {
 
 case "$1" in
 start)
+    # A config file from `reddwarf init` holds every argument
+    config_file=$(getprop config_file)
+    if [ -n "$config_file" ]; then
+        exec $REDDWARF_BIN agent "@$config_file" &
+        exit $SMF_EXIT_OK
+    fi
+
     node_name=$(getprop node_name)
     listen_addr=$(getprop listen_addr)
     data_dir=$(getprop data_dir)